@@ -0,0 +1,97 @@
+//! Change detection benchmarks.
+//!
+//! Re-encodes a 20-entry mass quote into a persistent template buffer after
+//! two entries moved, either rewriting every field or through the
+//! `set_*_if_changed` setters of `CodegenConfig::change_detection`, which
+//! compare each value with the buffer and only write the ones that differ.
+//! With the buffer in L1 a compare costs more than the store it saves, so
+//! `set_if_changed` is the slower of the two; it pays off when the dirty
+//! masks let the caller skip downstream work for unchanged entries, or when
+//! the buffer is read by another core and untouched cache lines stay clean.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+#[allow(dead_code, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/mass_quote.rs"));
+}
+
+use generated::MassQuoteEncoder;
+
+const ENTRIES: usize = 20;
+
+/// One quote entry: security ID, bid price and size, offer price and size.
+type Quote = (u64, i64, u32, i64, u32);
+
+/// Returns the book for update `n`; entries 3 and 11 alternate prices.
+fn book(n: u64) -> Vec<Quote> {
+    (0..ENTRIES as u64)
+        .map(|i| {
+            let moved = if i == 3 || i == 11 { n % 2 } else { 0 };
+            let bid = 10_000 + i as i64 * 25 + moved as i64;
+            (1000 + i, bid, 10, bid + 5, 10)
+        })
+        .collect()
+}
+
+fn rewrite_all(buffer: &mut [u8], book: &[Quote]) -> usize {
+    let mut encoder = MassQuoteEncoder::wrap(buffer, 0);
+    encoder.set_quote_id(1).set_account_id(7);
+    let mut entries = encoder.entries_count(ENTRIES as u16);
+    for &(security_id, bid_px, bid_size, offer_px, offer_size) in book {
+        let mut entry = entries.next_entry().expect("entry");
+        entry
+            .set_security_id(security_id)
+            .set_bid_px(bid_px)
+            .set_bid_size(bid_size)
+            .set_offer_px(offer_px)
+            .set_offer_size(offer_size)
+            .set_valid_until(u64::MAX);
+    }
+    encoder.encoded_length()
+}
+
+fn rewrite_changed(buffer: &mut [u8], book: &[Quote]) -> usize {
+    let mut encoder = MassQuoteEncoder::wrap(buffer, 0);
+    encoder.set_quote_id_if_changed(1);
+    encoder.set_account_id_if_changed(7);
+    let mut changed = 0;
+    let mut entries = encoder.entries_count(ENTRIES as u16);
+    for &(security_id, bid_px, bid_size, offer_px, offer_size) in book {
+        let mut entry = entries.next_entry().expect("entry");
+        entry.set_security_id_if_changed(security_id);
+        entry.set_bid_px_if_changed(bid_px);
+        entry.set_bid_size_if_changed(bid_size);
+        entry.set_offer_px_if_changed(offer_px);
+        entry.set_offer_size_if_changed(offer_size);
+        entry.set_valid_until_if_changed(u64::MAX);
+        changed += usize::from(entry.is_dirty());
+    }
+    changed
+}
+
+fn benchmark_mass_quote(c: &mut Criterion) {
+    let books = [book(0), book(1)];
+    let mut buffer = vec![0u8; 1024];
+    rewrite_all(&mut buffer, &books[0]);
+    let mut group = c.benchmark_group("mass_quote_20_entries_2_moved");
+
+    let mut n = 0;
+    group.bench_function("rewrite_all", |b| {
+        b.iter(|| {
+            n += 1;
+            rewrite_all(black_box(&mut buffer), black_box(&books[n % 2]))
+        })
+    });
+    group.bench_function("set_if_changed", |b| {
+        b.iter(|| {
+            n += 1;
+            rewrite_changed(black_box(&mut buffer), black_box(&books[n % 2]))
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_mass_quote);
+criterion_main!(benches);
@@ -0,0 +1,250 @@
+//! MPSC channel latency and fairness under multiple producers.
+//!
+//! The single-producer MPSC numbers in the README say nothing about how
+//! the channel degrades when several threads feed one consumer, which is
+//! what matters when sizing a thread model. This bench runs 1, 2, 4 and 8
+//! producers against one busy-polling consumer, each thread pinned to its
+//! own core when the machine has enough of them.
+//!
+//! Two scenarios per producer count:
+//!
+//! 1. **Paced**: every producer sends one timestamped message every
+//!    [`SEND_INTERVAL`]. The consumer records the send-to-receive latency
+//!    of each message, overall and per producer. Fairness is the ratio of
+//!    the worst producer's p99.9 to the best one's; 1.00 means every
+//!    producer sees the same tail.
+//! 2. **Saturated**: producers send as fast as the channel accepts and
+//!    the consumer drains; reports the aggregate throughput.
+//!
+//! Run with:
+//!
+//! ```sh
+//! cargo bench -p ironsbe-bench --bench channel_contention
+//! ```
+//!
+//! Like `transport_round_trip`, this is a plain binary (`harness = false`)
+//! printing a markdown table: criterion reports confidence intervals of
+//! the mean, not the tail percentiles this bench is about.
+
+use core_affinity::CoreId;
+use hdrhistogram::Histogram;
+use ironsbe_channel::mpsc::{MpscChannel, MpscReceiver};
+use std::hint::spin_loop;
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Producer counts to measure.
+const PRODUCERS: [usize; 4] = [1, 2, 4, 8];
+/// Channel capacity, large enough that paced producers never block.
+const CAPACITY: usize = 4096;
+/// Messages each producer sends before measurement starts.
+const WARMUP_PER_PRODUCER: usize = 10_000;
+/// Messages each producer sends in the paced scenario.
+const PACED_PER_PRODUCER: usize = 100_000;
+/// Gap between two sends of one producer in the paced scenario.
+const SEND_INTERVAL: Duration = Duration::from_micros(2);
+/// Messages each producer sends in the saturated scenario.
+const SATURATED_PER_PRODUCER: usize = 1_000_000;
+
+/// A paced message: sending producer and send timestamp.
+type Stamped = (usize, Instant);
+
+/// Result of the paced scenario for one producer count.
+struct PacedResult {
+    overall: Histogram<u64>,
+    per_producer: Vec<Histogram<u64>>,
+}
+
+impl PacedResult {
+    /// Worst producer p99.9 divided by the best one.
+    fn fairness(&self) -> f64 {
+        let tails = self
+            .per_producer
+            .iter()
+            .map(|h| h.value_at_quantile(0.999) as f64);
+        let (min, max) = tails.fold((f64::INFINITY, 0.0f64), |(min, max), t| {
+            (min.min(t), max.max(t))
+        });
+        if min > 0.0 { max / min } else { 1.0 }
+    }
+}
+
+/// Histogram tracking 1 ns .. 1 s with 3 significant figures.
+fn histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(1, 1_000_000_000, 3).expect("histogram bounds are valid")
+}
+
+/// Pins the current thread to `core`, if there is one.
+fn pin(core: Option<CoreId>) {
+    if let Some(core) = core {
+        core_affinity::set_for_current(core);
+    }
+}
+
+/// Returns the core for thread `index` (0 is the consumer), or `None` if
+/// the machine has fewer cores than threads.
+fn core_for(cores: &[CoreId], threads: usize, index: usize) -> Option<CoreId> {
+    (threads <= cores.len()).then(|| cores[index])
+}
+
+/// Spins until `deadline`.
+fn spin_until(deadline: Instant) {
+    while Instant::now() < deadline {
+        spin_loop();
+    }
+}
+
+/// Busy-polls `rx` for `count` messages, handing each to `on_message`.
+fn consume<T>(rx: &MpscReceiver<T>, count: usize, mut on_message: impl FnMut(T)) {
+    let mut received = 0;
+    while received < count {
+        match rx.try_recv() {
+            Some(item) => {
+                on_message(item);
+                received += 1;
+            }
+            None => spin_loop(),
+        }
+    }
+}
+
+/// Runs the paced scenario with `producers` producers.
+fn run_paced(producers: usize, cores: &[CoreId]) -> PacedResult {
+    let threads = producers + 1;
+    let (tx, rx) = MpscChannel::bounded::<Stamped>(CAPACITY);
+    let start = Arc::new(Barrier::new(threads));
+
+    let handles: Vec<_> = (0..producers)
+        .map(|id| {
+            let tx = tx.clone();
+            let start = Arc::clone(&start);
+            let core = core_for(cores, threads, id + 1);
+            thread::spawn(move || {
+                pin(core);
+                start.wait();
+                let mut next = Instant::now();
+                for _ in 0..WARMUP_PER_PRODUCER + PACED_PER_PRODUCER {
+                    spin_until(next);
+                    tx.send((id, Instant::now())).expect("consumer alive");
+                    next += SEND_INTERVAL;
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+
+    pin(core_for(cores, threads, 0));
+    start.wait();
+    let mut overall = histogram();
+    let mut per_producer = vec![histogram(); producers];
+    let mut seen = vec![0usize; producers];
+    consume(
+        &rx,
+        producers * (WARMUP_PER_PRODUCER + PACED_PER_PRODUCER),
+        |(id, sent)| {
+            let latency = (sent.elapsed().as_nanos() as u64).max(1);
+            seen[id] += 1;
+            if seen[id] > WARMUP_PER_PRODUCER {
+                overall.saturating_record(latency);
+                per_producer[id].saturating_record(latency);
+            }
+        },
+    );
+
+    for handle in handles {
+        handle.join().expect("producer panicked");
+    }
+    PacedResult {
+        overall,
+        per_producer,
+    }
+}
+
+/// Runs the saturated scenario with `producers` producers and returns
+/// the aggregate throughput in messages per second.
+fn run_saturated(producers: usize, cores: &[CoreId]) -> f64 {
+    let threads = producers + 1;
+    let (tx, rx) = MpscChannel::bounded::<u64>(CAPACITY);
+    let start = Arc::new(Barrier::new(threads));
+
+    let handles: Vec<_> = (0..producers)
+        .map(|id| {
+            let tx = tx.clone();
+            let start = Arc::clone(&start);
+            let core = core_for(cores, threads, id + 1);
+            thread::spawn(move || {
+                pin(core);
+                start.wait();
+                for i in 0..SATURATED_PER_PRODUCER as u64 {
+                    tx.send(i).expect("consumer alive");
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+
+    pin(core_for(cores, threads, 0));
+    start.wait();
+    let began = Instant::now();
+    let total = producers * SATURATED_PER_PRODUCER;
+    consume(&rx, total, |item| {
+        std::hint::black_box(item);
+    });
+    let elapsed = began.elapsed();
+
+    for handle in handles {
+        handle.join().expect("producer panicked");
+    }
+    total as f64 / elapsed.as_secs_f64()
+}
+
+/// Formats nanoseconds as `ns` below 10 µs and as `µs` above.
+fn format_ns(ns: u64) -> String {
+    if ns < 10_000 {
+        format!("{ns} ns")
+    } else {
+        format!("{:.1} µs", ns as f64 / 1_000.0)
+    }
+}
+
+fn main() {
+    let cores = core_affinity::get_core_ids().unwrap_or_default();
+    println!(
+        "Running channel_contention ({} cores; {} paced messages per producer every {:?}, {} saturated)",
+        cores.len(),
+        PACED_PER_PRODUCER,
+        SEND_INTERVAL,
+        SATURATED_PER_PRODUCER,
+    );
+    let unpinned: Vec<_> = PRODUCERS
+        .iter()
+        .filter(|&&producers| producers + 1 > cores.len())
+        .collect();
+    if !unpinned.is_empty() {
+        println!("(threads not pinned for {unpinned:?} producers: not enough cores)");
+    }
+    println!();
+
+    println!(
+        "| Producers |       p50 |       p99 |     p99.9 |       max | Fairness (p99.9) |      Saturated |"
+    );
+    println!(
+        "|-----------|-----------|-----------|-----------|-----------|------------------|----------------|"
+    );
+    for &producers in &PRODUCERS {
+        let paced = run_paced(producers, &cores);
+        let throughput = run_saturated(producers, &cores);
+        let h = &paced.overall;
+        println!(
+            "| {:>9} | {:>9} | {:>9} | {:>9} | {:>9} | {:>15.2}x | {:>8.1}M msg/s |",
+            producers,
+            format_ns(h.value_at_quantile(0.50)),
+            format_ns(h.value_at_quantile(0.99)),
+            format_ns(h.value_at_quantile(0.999)),
+            format_ns(h.max()),
+            paced.fairness(),
+            throughput / 1e6,
+        );
+    }
+}
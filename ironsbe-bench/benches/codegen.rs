@@ -0,0 +1,165 @@
+//! Generated decoder benchmarks.
+//!
+//! Decodes an `ExecutionReport` from `schemas/market_data.xml`, a wide
+//! message with every field kind, fields added in schema version 2, a
+//! repeating group and var data, through the code `ironsbe-codegen` emits
+//! today. `codegen_accessor` times each kind of accessor on a wrapped
+//! decoder next to `raw_u64`, a hand-written little-endian load: an
+//! accessor that inlines costs the same as the raw load. `codegen_decode`
+//! times wrapping and reading the whole message, at the current and at the
+//! previous acting version.
+//!
+//! Accessor changes such as added bounds checks or version guards show up
+//! here. Save a baseline before the change and compare after it:
+//!
+//! ```bash
+//! cargo bench -p ironsbe-bench --bench codegen -- --save-baseline codegen-base
+//! cargo bench -p ironsbe-bench --bench codegen -- --baseline codegen-base
+//! ```
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use ironsbe_core::header::MessageHeader;
+use std::hint::black_box;
+
+#[allow(dead_code, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/market_data.rs"));
+}
+
+use generated::{ExecFlags, ExecutionReportDecoder, ExecutionReportEncoder, OrdStatus, Side};
+
+const OFFSET: usize = MessageHeader::ENCODED_LENGTH;
+
+/// Encodes an `ExecutionReport` with three fills and a short text.
+fn encode_report() -> Vec<u8> {
+    let mut buffer = vec![0u8; 512];
+    let mut encoder = ExecutionReportEncoder::wrap(&mut buffer, 0);
+    let mut flags = ExecFlags::new();
+    flags.set_aggressor();
+    encoder
+        .set_order_id(1_000_001)
+        .set_cl_ord_id(77)
+        .set_exec_id(5_000_123)
+        .set_transact_time(1_700_000_000_000_000_000)
+        .set_security_id(4_242)
+        .set_symbol(b"ESZ4")
+        .set_account(b"ACCT-0001")
+        .set_order_qty(100)
+        .set_last_qty(30)
+        .set_cum_qty(60)
+        .set_leaves_qty(40)
+        .set_side(Side::Buy)
+        .set_ord_status(OrdStatus::PartiallyFilled)
+        .set_flags(flags)
+        .set_stop_px(i64::MIN)
+        .set_min_qty(10)
+        .set_display_qty(u32::MAX)
+        .set_expire_time(u64::MAX)
+        .set_seq_num(9_001)
+        .set_trade_date(20_000)
+        .set_cross_id(31)
+        .set_trade_link_id(32)
+        .set_owner_id(33);
+    encoder.set_price().set_mantissa(450_025).set_exponent(-2);
+    encoder.set_last_px().set_mantissa(450_000).set_exponent(-2);
+    let mut fills = encoder.fills_count(3);
+    for i in 0..3u64 {
+        let mut fill = fills.next_entry().expect("fill");
+        fill.set_fill_qty(10).set_fill_id(90 + i);
+        fill.set_fill_px()
+            .set_mantissa(450_000 + i as i64)
+            .set_exponent(-2);
+    }
+    encoder.set_text(b"partial fill");
+    buffer
+}
+
+/// Reads every field, fill and the text, folding them into a checksum.
+fn decode_all(buffer: &[u8], acting_version: u16) -> u64 {
+    let report = ExecutionReportDecoder::wrap(buffer, OFFSET, acting_version);
+    let mut sum = report.order_id()
+        ^ report.cl_ord_id()
+        ^ report.exec_id()
+        ^ report.transact_time()
+        ^ report.security_id() as u64
+        ^ u64::from(report.symbol()[0])
+        ^ u64::from(report.account()[0])
+        ^ report.price().mantissa() as u64
+        ^ report.price().exponent() as u64
+        ^ report.last_px().mantissa() as u64
+        ^ u64::from(report.order_qty())
+        ^ u64::from(report.last_qty())
+        ^ u64::from(report.cum_qty())
+        ^ u64::from(report.leaves_qty())
+        ^ u64::from(u8::from(report.side()))
+        ^ u64::from(u8::from(report.ord_status()))
+        ^ u64::from(report.flags().raw())
+        ^ report.stop_px_opt().map_or(0, |v| v as u64)
+        ^ u64::from(report.min_qty_opt().unwrap_or(0))
+        ^ u64::from(report.display_qty_opt().unwrap_or(0))
+        ^ report.expire_time_opt().unwrap_or(0)
+        ^ u64::from(report.seq_num())
+        ^ u64::from(report.trade_date())
+        ^ report.cross_id()
+        ^ u64::from(report.trade_link_id())
+        ^ u64::from(report.owner_id_opt().unwrap_or(0));
+    for fill in report.fills() {
+        sum ^= fill.fill_px().mantissa() as u64 ^ u64::from(fill.fill_qty()) ^ fill.fill_id();
+    }
+    sum ^ report.text().len() as u64
+}
+
+fn benchmark_accessors(c: &mut Criterion) {
+    let buffer = encode_report();
+    let report = ExecutionReportDecoder::wrap(&buffer, OFFSET, 2);
+    let report_v1 = ExecutionReportDecoder::wrap(&buffer, OFFSET, 1);
+    let mut group = c.benchmark_group("codegen_accessor");
+
+    group.bench_function("raw_u64", |b| {
+        b.iter(|| {
+            let buffer = black_box(&buffer);
+            u64::from_le_bytes(buffer[OFFSET..OFFSET + 8].try_into().unwrap())
+        })
+    });
+    group.bench_function("u64", |b| b.iter(|| black_box(&report).order_id()));
+    group.bench_function("i32", |b| b.iter(|| black_box(&report).security_id()));
+    group.bench_function("char_array", |b| b.iter(|| black_box(&report).symbol()));
+    group.bench_function("char_array_as_str", |b| {
+        b.iter(|| black_box(&report).symbol_as_str())
+    });
+    group.bench_function("composite", |b| {
+        b.iter(|| black_box(&report).price().mantissa())
+    });
+    group.bench_function("enum", |b| b.iter(|| black_box(&report).ord_status()));
+    group.bench_function("set_choice", |b| {
+        b.iter(|| black_box(&report).flags().is_aggressor())
+    });
+    group.bench_function("optional", |b| b.iter(|| black_box(&report).stop_px_opt()));
+    group.bench_function("since_version_current", |b| {
+        b.iter(|| black_box(&report).cross_id())
+    });
+    group.bench_function("since_version_older", |b| {
+        b.iter(|| black_box(&report_v1).cross_id())
+    });
+    group.bench_function("group_count", |b| {
+        b.iter(|| black_box(&report).fills().count())
+    });
+    group.bench_function("var_data", |b| b.iter(|| black_box(&report).text()));
+    group.finish();
+}
+
+fn benchmark_decode(c: &mut Criterion) {
+    let buffer = encode_report();
+    let mut group = c.benchmark_group("codegen_decode");
+
+    group.bench_function("execution_report", |b| {
+        b.iter(|| decode_all(black_box(&buffer), 2))
+    });
+    group.bench_function("execution_report_v1", |b| {
+        b.iter(|| decode_all(black_box(&buffer), 1))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_accessors, benchmark_decode);
+criterion_main!(benches);
@@ -4,6 +4,7 @@
 //! - Encode/Decode NewOrderSingle (simulated SBE message)
 //! - Encode/Decode MarketData with multiple entries
 //! - SPSC channel send/recv latency
+//! - MPMC channel send/recv latency and fan-out throughput
 //! - TCP round-trip latency (localhost)
 //!
 //! Run with: cargo bench -p ironsbe-bench --bench performance
@@ -259,6 +260,77 @@ fn benchmark_spsc_channel(c: &mut Criterion) {
         })
     });
 
+    // A burst sent and drained item by item, versus as one batch.
+    for burst in [16usize, 64, 256] {
+        let items: Vec<u64> = (0..burst as u64).collect();
+        group.throughput(Throughput::Elements(burst as u64));
+        group.bench_with_input(
+            BenchmarkId::new("burst_per_item", burst),
+            &items,
+            |b, items| {
+                let (mut tx, mut rx) = spsc::channel::<u64>(4096);
+                let mut out = Vec::with_capacity(items.len());
+                b.iter(|| {
+                    for &item in items {
+                        tx.send(item).unwrap();
+                    }
+                    out.clear();
+                    while let Some(item) = rx.recv() {
+                        out.push(item);
+                    }
+                    black_box(out.len())
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("burst_batch", burst),
+            &items,
+            |b, items| {
+                let (mut tx, mut rx) = spsc::channel::<u64>(4096);
+                let mut out = Vec::with_capacity(items.len());
+                b.iter(|| {
+                    tx.send_batch(black_box(items));
+                    out.clear();
+                    black_box(rx.recv_batch(&mut out, usize::MAX))
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn benchmark_byte_ring(c: &mut Criterion) {
+    use ironsbe_channel::ring;
+
+    let mut group = c.benchmark_group("byte_ring");
+    group.throughput(Throughput::Elements(1));
+
+    let mut encoded = AlignedBuffer::<256>::new();
+    encode_new_order_single(&mut encoded);
+    let len = MessageHeader::ENCODED_LENGTH + NEW_ORDER_SINGLE_BLOCK_LENGTH as usize;
+
+    // An encoded message passed as an owned Vec, as the SPSC channel needs.
+    group.bench_function("spsc_vec", |b| {
+        let (mut tx, mut rx) = spsc::channel::<Vec<u8>>(4096);
+        b.iter(|| {
+            tx.send(black_box(&encoded.as_slice()[..len]).to_vec())
+                .unwrap();
+            black_box(rx.recv().unwrap().len())
+        })
+    });
+
+    // The same message encoded into a claimed slot and read in place.
+    group.bench_function("claim_commit", |b| {
+        let (mut tx, mut rx) = ring::channel(64 * 1024);
+        b.iter(|| {
+            let mut claim = tx.claim(len).unwrap();
+            claim.copy_from_slice(black_box(&encoded.as_slice()[..len]));
+            claim.commit();
+            black_box(rx.try_read().unwrap().len())
+        })
+    });
+
     group.finish();
 }
 
@@ -277,6 +349,108 @@ fn benchmark_mpsc_channel(c: &mut Criterion) {
         })
     });
 
+    for burst in [16usize, 64, 256] {
+        let items: Vec<u64> = (0..burst as u64).collect();
+        group.throughput(Throughput::Elements(burst as u64));
+        group.bench_with_input(
+            BenchmarkId::new("burst_per_item", burst),
+            &items,
+            |b, items| {
+                let (tx, rx) = mpsc::channel::<u64>(4096);
+                let mut out = Vec::with_capacity(items.len());
+                b.iter(|| {
+                    for &item in items {
+                        tx.try_send(item).unwrap();
+                    }
+                    out.clear();
+                    while let Some(item) = rx.try_recv() {
+                        out.push(item);
+                    }
+                    black_box(out.len())
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("burst_batch", burst),
+            &items,
+            |b, items| {
+                let (tx, rx) = mpsc::channel::<u64>(4096);
+                let mut out = Vec::with_capacity(items.len());
+                b.iter(|| {
+                    tx.send_batch(black_box(items));
+                    out.clear();
+                    black_box(rx.recv_batch(&mut out, usize::MAX))
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn benchmark_mpmc_channel(c: &mut Criterion) {
+    use ironsbe_channel::mpmc;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::{Duration, Instant};
+
+    let mut group = c.benchmark_group("mpmc_channel");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("send_recv", |b| {
+        let (tx, rx) = mpmc::channel::<u64>(4096);
+
+        b.iter(|| {
+            tx.send(black_box(42)).unwrap();
+            black_box(rx.recv().unwrap())
+        })
+    });
+
+    // One feed thread fanning out to N busy-polling workers: time until
+    // every item of a batch has been taken by some worker.
+    const BATCH: u64 = 1024;
+    group.throughput(Throughput::Elements(BATCH));
+    for workers in [1, 2, 4] {
+        group.bench_with_input(BenchmarkId::new("fan_out", workers), &workers, |b, &n| {
+            let (tx, rx) = mpmc::channel::<u64>(4096);
+            let taken = Arc::new(AtomicU64::new(0));
+            let handles: Vec<_> = (0..n)
+                .map(|_| {
+                    let rx = rx.clone();
+                    let taken = Arc::clone(&taken);
+                    std::thread::spawn(move || {
+                        while let Some(item) = rx.recv() {
+                            black_box(item);
+                            taken.fetch_add(1, Ordering::Release);
+                        }
+                    })
+                })
+                .collect();
+            drop(rx);
+
+            b.iter_custom(|iters| {
+                let mut elapsed = Duration::ZERO;
+                for _ in 0..iters {
+                    let target = taken.load(Ordering::Acquire) + BATCH;
+                    let start = Instant::now();
+                    for i in 0..BATCH {
+                        tx.send(black_box(i)).unwrap();
+                    }
+                    while taken.load(Ordering::Acquire) < target {
+                        std::hint::spin_loop();
+                    }
+                    elapsed += start.elapsed();
+                }
+                elapsed
+            });
+
+            drop(tx);
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        });
+    }
+
     group.finish();
 }
 
@@ -341,7 +515,9 @@ criterion_group!(
     benchmark_market_data_encode,
     benchmark_market_data_decode,
     benchmark_spsc_channel,
+    benchmark_byte_ring,
     benchmark_mpsc_channel,
+    benchmark_mpmc_channel,
     benchmark_header_operations,
     benchmark_buffer_operations,
 );
@@ -0,0 +1,217 @@
+//! Optional-field presence benchmarks.
+//!
+//! Compares per-access null-sentinel checks against the memoized presence
+//! bitmap (`CodegenConfig::presence_bitmap`) on a message with 24 optional
+//! fields, half of them null. The bitmap pays for every check up front in
+//! `wrap`: a single pass over all fields is no faster than reading the
+//! sentinels directly (the reads are plain loads), and wrapping to query one
+//! field is much slower. It wins only when an already-wrapped decoder answers
+//! presence queries repeatedly (`presence_24_optionals_prewrapped`).
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use ironsbe_core::header::MessageHeader;
+use std::hint::black_box;
+
+#[allow(dead_code, clippy::all)]
+mod sentinel {
+    include!(concat!(env!("OUT_DIR"), "/optional_heavy_sentinel.rs"));
+}
+
+#[allow(dead_code, clippy::all)]
+mod bitmap {
+    include!(concat!(env!("OUT_DIR"), "/optional_heavy_bitmap.rs"));
+}
+
+/// Calls `$method_<field>` for every optional field of `$decoder`.
+macro_rules! for_each_optional {
+    ($decoder:expr, $f:expr, $($field:ident),+) => {
+        $($f($decoder.$field());)+
+    };
+}
+
+macro_rules! count_present {
+    ($decoder:expr) => {{
+        let decoder = $decoder;
+        let mut count = 0u32;
+        for_each_optional!(
+            decoder,
+            |present: bool| count += u32::from(present),
+            has_field1,
+            has_field2,
+            has_field3,
+            has_field4,
+            has_field5,
+            has_field6,
+            has_field7,
+            has_field8,
+            has_field9,
+            has_field10,
+            has_field11,
+            has_field12,
+            has_field13,
+            has_field14,
+            has_field15,
+            has_field16,
+            has_field17,
+            has_field18,
+            has_field19,
+            has_field20,
+            has_field21,
+            has_field22,
+            has_field23,
+            has_field24
+        );
+        count
+    }};
+}
+
+macro_rules! sum_present {
+    ($decoder:expr) => {{
+        let decoder = $decoder;
+        let mut sum = 0i64;
+        for_each_optional!(
+            decoder,
+            |value: Option<_>| sum = sum.wrapping_add(value.map_or(0, |v| v as i64)),
+            field1_opt,
+            field2_opt,
+            field3_opt,
+            field4_opt,
+            field5_opt,
+            field6_opt,
+            field7_opt,
+            field8_opt,
+            field9_opt,
+            field10_opt,
+            field11_opt,
+            field12_opt,
+            field13_opt,
+            field14_opt,
+            field15_opt,
+            field16_opt,
+            field17_opt,
+            field18_opt,
+            field19_opt,
+            field20_opt,
+            field21_opt,
+            field22_opt,
+            field23_opt,
+            field24_opt
+        );
+        sum
+    }};
+}
+
+/// Encodes an `Instrument` with every odd-numbered optional field null.
+fn encode_instrument() -> Vec<u8> {
+    let mut buffer = vec![0u8; 256];
+    let mut encoder = sentinel::InstrumentEncoder::wrap(&mut buffer, 0);
+    encoder
+        .set_security_id(42)
+        .set_field1(i64::MIN)
+        .set_field2(2)
+        .set_field3(i32::MIN)
+        .set_field4(4)
+        .set_field5(i64::MIN)
+        .set_field6(6)
+        .set_field7(i32::MIN)
+        .set_field8(8)
+        .set_field9(i64::MIN)
+        .set_field10(10)
+        .set_field11(i32::MIN)
+        .set_field12(12)
+        .set_field13(i64::MIN)
+        .set_field14(14)
+        .set_field15(i32::MIN)
+        .set_field16(16)
+        .set_field17(i64::MIN)
+        .set_field18(18)
+        .set_field19(i32::MIN)
+        .set_field20(20)
+        .set_field21(i64::MIN)
+        .set_field22(22)
+        .set_field23(i32::MIN)
+        .set_field24(24);
+    buffer
+}
+
+fn benchmark_presence_all_fields(c: &mut Criterion) {
+    let buffer = encode_instrument();
+    let offset = MessageHeader::ENCODED_LENGTH;
+    let mut group = c.benchmark_group("presence_24_optionals");
+
+    group.bench_function("sentinel_count", |b| {
+        b.iter(|| {
+            count_present!(sentinel::InstrumentDecoder::wrap(
+                black_box(&buffer),
+                offset,
+                1
+            ))
+        })
+    });
+    group.bench_function("bitmap_count", |b| {
+        b.iter(|| {
+            count_present!(bitmap::InstrumentDecoder::wrap(
+                black_box(&buffer),
+                offset,
+                1
+            ))
+        })
+    });
+    group.bench_function("sentinel_sum_opt", |b| {
+        b.iter(|| {
+            sum_present!(sentinel::InstrumentDecoder::wrap(
+                black_box(&buffer),
+                offset,
+                1
+            ))
+        })
+    });
+    group.bench_function("bitmap_sum_opt", |b| {
+        b.iter(|| {
+            sum_present!(bitmap::InstrumentDecoder::wrap(
+                black_box(&buffer),
+                offset,
+                1
+            ))
+        })
+    });
+    group.finish();
+}
+
+fn benchmark_presence_repeated_queries(c: &mut Criterion) {
+    let buffer = encode_instrument();
+    let offset = MessageHeader::ENCODED_LENGTH;
+    let sentinel = sentinel::InstrumentDecoder::wrap(&buffer, offset, 1);
+    let bitmap = bitmap::InstrumentDecoder::wrap(&buffer, offset, 1);
+    let mut group = c.benchmark_group("presence_24_optionals_prewrapped");
+
+    group.bench_function("sentinel_count", |b| {
+        b.iter(|| count_present!(black_box(&sentinel)))
+    });
+    group.bench_function("bitmap_count", |b| {
+        b.iter(|| count_present!(black_box(&bitmap)))
+    });
+    group.finish();
+}
+
+fn benchmark_presence_single_field(c: &mut Criterion) {
+    let buffer = encode_instrument();
+    let offset = MessageHeader::ENCODED_LENGTH;
+    let mut group = c.benchmark_group("presence_single_field");
+
+    group.bench_function("sentinel", |b| {
+        b.iter(|| sentinel::InstrumentDecoder::wrap(black_box(&buffer), offset, 1).has_field8())
+    });
+    group.bench_function("bitmap", |b| {
+        b.iter(|| bitmap::InstrumentDecoder::wrap(black_box(&buffer), offset, 1).has_field8())
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_presence_all_fields,
+    benchmark_presence_repeated_queries,
+    benchmark_presence_single_field,
+);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+//! Generates the codegen-backed benchmark schemas into `OUT_DIR`.
+
+use ironsbe_codegen::{CodegenConfig, generate_from_file_with_config};
+use std::path::{Path, PathBuf};
+
+fn main() {
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").expect("OUT_DIR not set"));
+
+    for (schema, file, config) in [
+        (
+            "schemas/optional_heavy.xml",
+            "optional_heavy_sentinel.rs",
+            CodegenConfig::new(),
+        ),
+        (
+            "schemas/optional_heavy.xml",
+            "optional_heavy_bitmap.rs",
+            CodegenConfig::new().presence_bitmap(1),
+        ),
+        (
+            "schemas/mass_quote.xml",
+            "mass_quote.rs",
+            CodegenConfig::new().change_detection(true),
+        ),
+        (
+            "schemas/market_data.xml",
+            "market_data.rs",
+            CodegenConfig::new(),
+        ),
+    ] {
+        let schema = Path::new(schema);
+        println!("cargo:rerun-if-changed={}", schema.display());
+        let code = generate_from_file_with_config(schema, &config).expect("codegen failed");
+        std::fs::write(out_dir.join(file), code).expect("failed to write generated code");
+    }
+}
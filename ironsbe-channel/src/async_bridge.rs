@@ -2,7 +2,24 @@
 //!
 //! This module provides utilities for bridging between synchronous
 //! and asynchronous code paths.
-
+//!
+//! With the `futures` feature (on by default), channel halves convert into
+//! `futures` adapters: [`SpscReceiver::into_stream`] and
+//! [`MpscReceiver::into_stream`] return a `Stream`, and
+//! [`MpscSender::into_sink`] a `Sink`. They register the task's waker and
+//! are woken by the other side whatever the channel's
+//! [`WaitStrategy`](crate::WaitStrategy), so an awaiting task costs no CPU.
+
+#[cfg(feature = "futures")]
+use crate::ChannelError;
+#[cfg(feature = "futures")]
+use crate::mpsc::{MpscReceiver, MpscSender};
+#[cfg(feature = "futures")]
+use crate::spsc::SpscReceiver;
+#[cfg(feature = "futures")]
+use crate::wait::Waiter;
+#[cfg(feature = "futures")]
+use crossbeam_channel::TrySendError;
 use parking_lot::Mutex;
 use std::future::Future;
 use std::pin::Pin;
@@ -205,6 +222,170 @@ impl<T> Drop for OneshotReceiver<T> {
     }
 }
 
+/// Async stream over an SPSC receiver, from [`SpscReceiver::into_stream`].
+#[cfg(feature = "futures")]
+pub struct SpscStream<T> {
+    receiver: SpscReceiver<T>,
+    waiter: Arc<Waiter>,
+}
+
+#[cfg(feature = "futures")]
+impl<T> SpscReceiver<T> {
+    /// Converts the receiver into a [`Stream`](futures::Stream) that ends
+    /// once the sender is gone and the channel is drained.
+    #[must_use]
+    pub fn into_stream(self) -> SpscStream<T> {
+        let waiter = Arc::clone(self.waiter());
+        SpscStream {
+            receiver: self,
+            waiter,
+        }
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<T> SpscStream<T> {
+    /// Returns the underlying receiver.
+    #[must_use]
+    pub fn into_inner(self) -> SpscReceiver<T> {
+        self.receiver
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<T> futures::Stream for SpscStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        let receiver = &mut this.receiver;
+        this.waiter.poll_item(cx, || receiver.poll_item())
+    }
+}
+
+/// Async stream over an MPSC receiver, from [`MpscReceiver::into_stream`].
+#[cfg(feature = "futures")]
+pub struct MpscStream<T> {
+    receiver: MpscReceiver<T>,
+}
+
+#[cfg(feature = "futures")]
+impl<T> MpscReceiver<T> {
+    /// Converts the receiver into a [`Stream`](futures::Stream) that ends
+    /// once every sender is gone and the channel is drained.
+    #[must_use]
+    pub fn into_stream(self) -> MpscStream<T> {
+        MpscStream { receiver: self }
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<T> MpscStream<T> {
+    /// Returns the underlying receiver.
+    #[must_use]
+    pub fn into_inner(self) -> MpscReceiver<T> {
+        self.receiver
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<T> futures::Stream for MpscStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let receiver = &self.receiver;
+        receiver.waiter().poll_item(cx, || receiver.poll_item())
+    }
+}
+
+/// Async sink over an MPSC sender, from [`MpscSender::into_sink`].
+///
+/// Holds at most one item that did not fit; `poll_ready` and `poll_flush`
+/// wait for the receiver to make room for it.
+#[cfg(feature = "futures")]
+pub struct MpscSink<T> {
+    sender: MpscSender<T>,
+    pending: Option<T>,
+}
+
+// The pending item is never pinned.
+#[cfg(feature = "futures")]
+impl<T> Unpin for MpscSink<T> {}
+
+#[cfg(feature = "futures")]
+impl<T> MpscSender<T> {
+    /// Converts the sender into a [`Sink`](futures::Sink).
+    ///
+    /// The sink fails with [`ChannelError::Disconnected`] once the receiver
+    /// is gone. Clone the sender first to keep sending from sync code.
+    #[must_use]
+    pub fn into_sink(self) -> MpscSink<T> {
+        MpscSink {
+            sender: self,
+            pending: None,
+        }
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<T> MpscSink<T> {
+    /// Returns the underlying sender, with the item still waiting for
+    /// room, if any.
+    #[must_use]
+    pub fn into_inner(self) -> (MpscSender<T>, Option<T>) {
+        (self.sender, self.pending)
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<T> futures::Sink<T> for MpscSink<T> {
+    type Error = ChannelError<T>;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        assert!(
+            this.pending.is_none(),
+            "start_send called before poll_ready completed"
+        );
+        match this.sender.try_send(item) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(item)) => {
+                this.pending = Some(item);
+                Ok(())
+            }
+            Err(TrySendError::Disconnected(item)) => Err(ChannelError::Disconnected(item)),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        let (sender, pending) = (&this.sender, &mut this.pending);
+        sender.waiter().poll_space(cx, || {
+            let Some(item) = pending.take() else {
+                return Poll::Ready(Ok(()));
+            };
+            match sender.try_send(item) {
+                Ok(()) => Poll::Ready(Ok(())),
+                Err(TrySendError::Full(item)) => {
+                    *pending = Some(item);
+                    Poll::Pending
+                }
+                Err(TrySendError::Disconnected(item)) => {
+                    Poll::Ready(Err(ChannelError::Disconnected(item)))
+                }
+            }
+        })
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -236,4 +417,141 @@ mod tests {
         drop(tx);
         assert!(rx.try_recv().is_none());
     }
+
+    #[cfg(feature = "futures")]
+    mod adapters {
+        use super::*;
+        use crate::{WaitStrategy, mpsc, spsc};
+        use futures::executor::block_on;
+        use futures::task::{ArcWake, waker};
+        use futures::{Sink, SinkExt, Stream, StreamExt};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Counts wakeups.
+        struct CountingWaker(AtomicUsize);
+
+        impl ArcWake for CountingWaker {
+            fn wake_by_ref(arc_self: &Arc<Self>) {
+                arc_self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        #[test]
+        fn test_stream_sleeps_until_send() {
+            let (mut tx, rx) = spsc::channel_with_wait::<u64>(4, WaitStrategy::Park).unwrap();
+            let mut stream = rx.into_stream();
+            let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+            let waker = waker(Arc::clone(&counter));
+            let mut cx = Context::from_waker(&waker);
+
+            assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+            // Parked, not re-scheduled.
+            assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+            tx.send(7).unwrap();
+            assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+            assert_eq!(
+                Pin::new(&mut stream).poll_next(&mut cx),
+                Poll::Ready(Some(7))
+            );
+
+            drop(tx);
+            assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(None));
+        }
+
+        #[test]
+        fn test_spinning_strategies_wake_streams_on_send() {
+            let strategies = [
+                WaitStrategy::BusySpin,
+                WaitStrategy::SpinThenYield { spins: 10 },
+            ];
+            for strategy in strategies {
+                let (mut tx, rx) = spsc::channel_with_wait::<u64>(4, strategy).unwrap();
+                let mut stream = rx.into_stream();
+                let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+                let waker = waker(Arc::clone(&counter));
+                let mut cx = Context::from_waker(&waker);
+
+                assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+                // Waiting for the sender, not re-scheduled.
+                assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+                tx.send(7).unwrap();
+                assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+                assert_eq!(
+                    Pin::new(&mut stream).poll_next(&mut cx),
+                    Poll::Ready(Some(7))
+                );
+
+                let (tx, rx) = mpsc::channel_with_wait::<u64>(4, strategy).unwrap();
+                let mut stream = rx.into_stream();
+                assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+                assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+                tx.try_send(8).unwrap();
+                assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+                drop(tx);
+                assert_eq!(
+                    Pin::new(&mut stream).poll_next(&mut cx),
+                    Poll::Ready(Some(8))
+                );
+            }
+        }
+
+        #[test]
+        fn test_spinning_strategy_wakes_full_sink_on_receive() {
+            let (tx, rx) = mpsc::channel_with_wait::<u64>(1, WaitStrategy::BusySpin).unwrap();
+            let mut sink = tx.into_sink();
+            let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+            let waker = waker(Arc::clone(&counter));
+            let mut cx = Context::from_waker(&waker);
+
+            Pin::new(&mut sink).start_send(1).unwrap();
+            Pin::new(&mut sink).start_send(2).unwrap();
+            assert!(Pin::new(&mut sink).poll_flush(&mut cx).is_pending());
+            assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+            assert_eq!(rx.try_recv(), Some(1));
+            assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+            assert!(matches!(
+                Pin::new(&mut sink).poll_flush(&mut cx),
+                Poll::Ready(Ok(()))
+            ));
+            assert_eq!(rx.try_recv(), Some(2));
+        }
+
+        #[test]
+        fn test_sink_to_stream_with_backpressure() {
+            for strategy in [WaitStrategy::Park, WaitStrategy::BusySpin] {
+                let (tx, rx) = mpsc::channel_with_wait::<u64>(2, strategy).unwrap();
+                let consumer =
+                    std::thread::spawn(move || block_on(rx.into_stream().collect::<Vec<_>>()));
+
+                let mut sink = tx.into_sink();
+                block_on(async {
+                    for i in 0..1000 {
+                        sink.send(i).await.unwrap();
+                    }
+                    sink.close().await.unwrap();
+                });
+                drop(sink);
+
+                assert_eq!(consumer.join().unwrap(), (0..1000).collect::<Vec<_>>());
+            }
+        }
+
+        #[test]
+        fn test_sink_fails_when_receiver_dropped() {
+            let (tx, rx) = mpsc::channel_with_wait::<u64>(1, WaitStrategy::Park).unwrap();
+            let mut sink = tx.into_sink();
+            block_on(sink.send(1)).unwrap();
+
+            let dropper = std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                drop(rx);
+            });
+            // Full until the receiver goes away, which wakes the sink.
+            let result = block_on(sink.send(2));
+            assert_eq!(result, Err(ChannelError::Disconnected(2)));
+            dropper.join().unwrap();
+        }
+    }
 }
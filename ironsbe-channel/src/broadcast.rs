@@ -2,16 +2,32 @@
 //!
 //! This module provides a broadcast channel where a single sender can
 //! send messages to multiple receivers.
+//!
+//! The channel buffers the last `capacity` messages. When it is full, the
+//! next send evicts the oldest message, and each receiver that has not read
+//! that message yet is handled according to its [`LagPolicy`]:
+//!
+//! - [`LagPolicy::DropOldest`] (the default): the receiver loses the message
+//!   and its next [`try_recv`](BroadcastReceiver::try_recv) reports the gap
+//!   in a [`LagReport`].
+//! - [`LagPolicy::Block`]: the send waits until the receiver has read it.
+//! - [`LagPolicy::Disconnect`]: the receiver is evicted and receives
+//!   nothing more, so one slow consumer cannot hold back the others.
+//!
+//! [`BroadcastSender::lag_stats`] reports every receiver's lag and drop count,
+//! to find slow consumers in production.
 
-use parking_lot::RwLock;
+use crate::ChannelError;
+use parking_lot::{Condvar, Mutex, RwLock};
 use std::collections::VecDeque;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering, fence};
+use std::task::{Context, Poll, Waker};
 
 /// Creates a new broadcast channel with the given capacity.
 ///
 /// # Arguments
-/// * `capacity` - Maximum number of messages to buffer
+/// * `capacity` - Maximum number of messages to buffer; rounded up to 1
 ///
 /// # Returns
 /// A sender that can create receivers.
@@ -20,6 +36,91 @@ pub fn channel<T: Clone + Send + Sync>(capacity: usize) -> BroadcastSender<T> {
     BroadcastSender::new(capacity)
 }
 
+/// Creates a new broadcast channel whose receivers default to `policy`.
+///
+/// # Arguments
+/// * `capacity` - Maximum number of messages to buffer; rounded up to 1
+/// * `policy` - What happens to receivers that fall behind
+#[must_use]
+pub fn channel_with_policy<T: Clone + Send + Sync>(
+    capacity: usize,
+    policy: LagPolicy,
+) -> BroadcastSender<T> {
+    BroadcastSender::with_policy(capacity, policy)
+}
+
+/// What happens to a receiver when the sender must evict a message it has
+/// not read yet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LagPolicy {
+    /// Evict the message anyway; the receiver skips ahead and is told how
+    /// many messages it missed.
+    #[default]
+    DropOldest,
+    /// Make the sender wait until the receiver has read the message.
+    ///
+    /// A receiver that stops reading stalls every other receiver.
+    Block,
+    /// Evict the receiver; it receives nothing more.
+    Disconnect,
+}
+
+/// Messages a [`LagPolicy::DropOldest`] receiver missed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LagReport {
+    /// Sequence number of the first missed message.
+    pub first_missed: u64,
+    /// Number of consecutive messages missed.
+    pub missed: u64,
+}
+
+/// Error returned by [`BroadcastReceiver::try_recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// No new messages.
+    Empty,
+    /// Messages were evicted before this receiver read them; the next call
+    /// returns the oldest message still buffered.
+    Lagged(LagReport),
+    /// The receiver fell behind under [`LagPolicy::Disconnect`] and was
+    /// evicted.
+    Evicted,
+    /// The sender was dropped and every message was received.
+    Closed,
+}
+
+impl std::fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "channel empty"),
+            Self::Lagged(report) => write!(
+                f,
+                "receiver lagged, missed {} messages from sequence {}",
+                report.missed, report.first_missed
+            ),
+            Self::Evicted => write!(f, "receiver evicted for lagging"),
+            Self::Closed => write!(f, "channel closed"),
+        }
+    }
+}
+
+impl std::error::Error for TryRecvError {}
+
+/// Lag counters of one receiver, from [`BroadcastSender::lag_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriberLag {
+    /// Receiver ID, as returned by [`BroadcastReceiver::id`].
+    pub id: u64,
+    /// The receiver's lag policy.
+    pub policy: LagPolicy,
+    /// Messages sent that the receiver has not read.
+    pub lag: u64,
+    /// Messages evicted before the receiver read them.
+    pub dropped: u64,
+    /// Whether the receiver was evicted.
+    pub evicted: bool,
+}
+
 /// Shared state for the broadcast channel.
 struct BroadcastState<T> {
     /// Message buffer.
@@ -30,11 +131,105 @@ struct BroadcastState<T> {
     capacity: usize,
     /// Whether the sender is closed.
     closed: bool,
+    /// Live receivers.
+    subscribers: Vec<Arc<Subscriber>>,
+    /// ID of the next receiver.
+    next_id: u64,
+}
+
+impl<T> BroadcastState<T> {
+    /// Sequence number of the oldest buffered message.
+    fn front_seq(&self) -> u64 {
+        self.buffer.front().map_or(self.sequence, |(seq, _)| *seq)
+    }
+
+    fn register(&mut self, policy: LagPolicy, next_seq: u64) -> Arc<Subscriber> {
+        let subscriber = Arc::new(Subscriber {
+            id: self.next_id,
+            policy,
+            next_seq: AtomicU64::new(next_seq),
+            dropped: AtomicU64::new(0),
+            evicted: AtomicBool::new(false),
+        });
+        self.next_id += 1;
+        self.subscribers.push(Arc::clone(&subscriber));
+        subscriber
+    }
+
+    /// Returns true if the buffer is full and a blocking receiver has not
+    /// read the oldest message.
+    fn blocked(&self) -> bool {
+        self.buffer.len() >= self.capacity && {
+            let front = self.front_seq();
+            self.subscribers.iter().any(|sub| {
+                sub.policy == LagPolicy::Block && sub.next_seq.load(Ordering::SeqCst) <= front
+            })
+        }
+    }
+}
+
+/// Per-receiver state the sender inspects when evicting.
+struct Subscriber {
+    id: u64,
+    policy: LagPolicy,
+    /// Next sequence number the receiver will read.
+    next_seq: AtomicU64,
+    /// Messages evicted before the receiver read them.
+    dropped: AtomicU64,
+    evicted: AtomicBool,
+}
+
+/// State shared by the sender and its receivers.
+struct Shared<T> {
+    state: RwLock<BroadcastState<T>>,
+    /// Default policy of new receivers.
+    policy: LagPolicy,
+    /// Senders waiting for a blocking receiver to catch up.
+    waiters: AtomicUsize,
+    progress: Mutex<()>,
+    advanced: Condvar,
+    /// Set by receivers that registered a task; the sender that clears it
+    /// wakes them.
+    armed: AtomicBool,
+    tasks: Mutex<Vec<Waker>>,
+}
+
+impl<T> Shared<T> {
+    /// Wakes senders waiting in [`BroadcastSender::send`], if any.
+    fn notify_progress(&self) {
+        if self.waiters.load(Ordering::SeqCst) > 0 {
+            let _guard = self.progress.lock();
+            self.advanced.notify_all();
+        }
+    }
+
+    /// Registers `waker` to be woken by the next send or the sender's drop.
+    fn register(&self, waker: &Waker) {
+        let mut tasks = self.tasks.lock();
+        if !tasks.iter().any(|task| task.will_wake(waker)) {
+            tasks.push(waker.clone());
+        }
+        drop(tasks);
+        self.armed.store(true, Ordering::Relaxed);
+        // Orders arming before the receiver's re-check, pairing with the
+        // fence in `wake_tasks`.
+        fence(Ordering::SeqCst);
+    }
+
+    /// Wakes the registered receiver tasks, if any.
+    fn wake_tasks(&self) {
+        fence(Ordering::SeqCst);
+        if self.armed.load(Ordering::Relaxed) && self.armed.swap(false, Ordering::Relaxed) {
+            for task in self.tasks.lock().drain(..) {
+                task.wake();
+            }
+        }
+    }
 }
 
 /// Sender half of a broadcast channel.
 pub struct BroadcastSender<T> {
-    state: Arc<RwLock<BroadcastState<T>>>,
+    shared: Arc<Shared<T>>,
     sequence: AtomicU64,
 }
 
@@ -42,64 +237,146 @@ impl<T: Clone + Send + Sync> BroadcastSender<T> {
     /// Creates a new broadcast sender.
     ///
     /// # Arguments
-    /// * `capacity` - Maximum number of messages to buffer
+    /// * `capacity` - Maximum number of messages to buffer; rounded up to 1
     #[must_use]
     pub fn new(capacity: usize) -> Self {
+        Self::with_policy(capacity, LagPolicy::default())
+    }
+
+    /// Creates a new broadcast sender whose receivers default to `policy`.
+    ///
+    /// # Arguments
+    /// * `capacity` - Maximum number of messages to buffer; rounded up to 1
+    /// * `policy` - What happens to receivers that fall behind
+    #[must_use]
+    pub fn with_policy(capacity: usize, policy: LagPolicy) -> Self {
+        // An empty buffer would evict every message before it is read.
+        let capacity = capacity.max(1);
         Self {
-            state: Arc::new(RwLock::new(BroadcastState {
-                buffer: VecDeque::with_capacity(capacity),
-                sequence: 0,
-                capacity,
-                closed: false,
-            })),
+            shared: Arc::new(Shared {
+                state: RwLock::new(BroadcastState {
+                    buffer: VecDeque::with_capacity(capacity),
+                    sequence: 0,
+                    capacity,
+                    closed: false,
+                    subscribers: Vec::new(),
+                    next_id: 0,
+                }),
+                policy,
+                waiters: AtomicUsize::new(0),
+                progress: Mutex::new(()),
+                advanced: Condvar::new(),
+                armed: AtomicBool::new(false),
+                tasks: Mutex::new(Vec::new()),
+            }),
             sequence: AtomicU64::new(0),
         }
     }
 
     /// Sends a message to all receivers.
     ///
+    /// Waits while a [`LagPolicy::Block`] receiver has not read the message
+    /// a full buffer would evict.
+    ///
     /// # Arguments
     /// * `item` - Item to broadcast
     ///
     /// # Returns
     /// The sequence number of the sent message.
     pub fn send(&self, item: T) -> u64 {
-        let mut state = self.state.write();
-        let seq = state.sequence;
-        state.sequence += 1;
+        let mut item = item;
+        loop {
+            match self.try_send(item) {
+                Ok(seq) => return seq,
+                Err(ChannelError::Full(back)) => {
+                    item = back;
+                    self.wait_for_progress();
+                }
+                Err(_) => unreachable!("try_send only fails when full"),
+            }
+        }
+    }
+
+    /// Sends a message to all receivers without waiting.
+    ///
+    /// # Errors
+    /// Returns [`ChannelError::Full`] with the item if a
+    /// [`LagPolicy::Block`] receiver has not read the message a full buffer
+    /// would evict.
+    pub fn try_send(&self, item: T) -> Result<u64, ChannelError<T>> {
+        let mut state = self.shared.state.write();
+        if state.blocked() {
+            return Err(ChannelError::Full(item));
+        }
 
         // Remove old messages if at capacity
         while state.buffer.len() >= state.capacity {
-            state.buffer.pop_front();
+            let Some((evicted, _)) = state.buffer.pop_front() else {
+                break;
+            };
+            for sub in &state.subscribers {
+                if sub.next_seq.load(Ordering::SeqCst) > evicted
+                    || sub.evicted.load(Ordering::Relaxed)
+                {
+                    continue;
+                }
+                match sub.policy {
+                    LagPolicy::Disconnect => sub.evicted.store(true, Ordering::Release),
+                    _ => {
+                        sub.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }
         }
 
+        let seq = state.sequence;
+        state.sequence += 1;
         state.buffer.push_back((seq, item));
         self.sequence.store(seq + 1, Ordering::Release);
-        seq
+        drop(state);
+        self.shared.wake_tasks();
+        Ok(seq)
+    }
+
+    /// Waits until a receiver reads or goes away.
+    fn wait_for_progress(&self) {
+        let mut guard = self.shared.progress.lock();
+        self.shared.waiters.fetch_add(1, Ordering::SeqCst);
+        if self.shared.state.read().blocked() {
+            self.shared.advanced.wait(&mut guard);
+        }
+        self.shared.waiters.fetch_sub(1, Ordering::SeqCst);
     }
 
     /// Creates a new receiver subscribed to this sender.
     ///
-    /// The receiver will start receiving messages from the next send.
+    /// The receiver will start receiving messages from the next send, and
+    /// uses the channel's default [`LagPolicy`].
     #[must_use]
     pub fn subscribe(&self) -> BroadcastReceiver<T> {
-        let current_seq = self.sequence.load(Ordering::Acquire);
+        self.subscribe_with_policy(self.shared.policy)
+    }
+
+    /// Creates a new receiver with its own [`LagPolicy`], starting from the
+    /// next send.
+    #[must_use]
+    pub fn subscribe_with_policy(&self, policy: LagPolicy) -> BroadcastReceiver<T> {
+        let mut state = self.shared.state.write();
+        let current_seq = state.sequence;
         BroadcastReceiver {
-            state: Arc::clone(&self.state),
-            next_seq: current_seq,
+            subscriber: state.register(policy, current_seq),
+            shared: Arc::clone(&self.shared),
         }
     }
 
     /// Creates a new receiver that will receive all buffered messages.
     #[must_use]
     pub fn subscribe_from_start(&self) -> BroadcastReceiver<T> {
-        let state = self.state.read();
-        let start_seq = state.buffer.front().map(|(s, _)| *s).unwrap_or(0);
-        drop(state);
-
+        let mut state = self.shared.state.write();
+        let start_seq = state.front_seq();
         BroadcastReceiver {
-            state: Arc::clone(&self.state),
-            next_seq: start_seq,
+            subscriber: state.register(self.shared.policy, start_seq),
+            shared: Arc::clone(&self.shared),
         }
     }
 
@@ -112,7 +389,7 @@ impl<T: Clone + Send + Sync> BroadcastSender<T> {
     /// Returns the number of buffered messages.
     #[must_use]
     pub fn len(&self) -> usize {
-        self.state.read().buffer.len()
+        self.shared.state.read().buffer.len()
     }
 
     /// Returns true if the buffer is empty.
@@ -120,86 +397,218 @@ impl<T: Clone + Send + Sync> BroadcastSender<T> {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Returns the number of live receivers, including evicted ones not yet
+    /// dropped.
+    #[must_use]
+    pub fn receiver_count(&self) -> usize {
+        self.shared.state.read().subscribers.len()
+    }
+
+    /// Returns the lag counters of every live receiver, slowest first.
+    #[must_use]
+    pub fn lag_stats(&self) -> Vec<SubscriberLag> {
+        let state = self.shared.state.read();
+        let mut stats: Vec<_> = state
+            .subscribers
+            .iter()
+            .map(|sub| SubscriberLag {
+                id: sub.id,
+                policy: sub.policy,
+                lag: state
+                    .sequence
+                    .saturating_sub(sub.next_seq.load(Ordering::Relaxed)),
+                dropped: sub.dropped.load(Ordering::Relaxed),
+                evicted: sub.evicted.load(Ordering::Relaxed),
+            })
+            .collect();
+        stats.sort_by(|a, b| b.lag.cmp(&a.lag).then(a.id.cmp(&b.id)));
+        stats
+    }
 }
 
 impl<T> Drop for BroadcastSender<T> {
     fn drop(&mut self) {
-        self.state.write().closed = true;
+        self.shared.state.write().closed = true;
+        self.shared.wake_tasks();
     }
 }
 
 /// Receiver half of a broadcast channel.
 pub struct BroadcastReceiver<T> {
-    state: Arc<RwLock<BroadcastState<T>>>,
-    next_seq: u64,
+    shared: Arc<Shared<T>>,
+    subscriber: Arc<Subscriber>,
 }
 
 impl<T: Clone> BroadcastReceiver<T> {
-    /// Receives the next message.
+    /// Receives the next message, skipping over messages evicted before
+    /// this receiver read them.
     ///
     /// # Returns
     /// `Some((sequence, item))` if available, `None` if no new messages.
     pub fn recv(&mut self) -> Option<(u64, T)> {
-        let state = self.state.read();
-
-        // Find the message with our expected sequence
-        for (seq, item) in &state.buffer {
-            if *seq == self.next_seq {
-                self.next_seq += 1;
-                return Some((*seq, item.clone()));
+        loop {
+            match self.try_recv() {
+                Ok(message) => return Some(message),
+                Err(TryRecvError::Lagged(_)) => {}
+                Err(_) => return None,
             }
         }
+    }
 
-        None
+    /// Receives the next message, reporting gaps and eviction.
+    ///
+    /// # Errors
+    /// Returns [`TryRecvError::Lagged`] once per gap, then the oldest
+    /// buffered message on the next call; [`TryRecvError::Evicted`] if this
+    /// receiver was evicted; [`TryRecvError::Closed`] if the sender is gone
+    /// and nothing is left; otherwise [`TryRecvError::Empty`].
+    pub fn try_recv(&mut self) -> Result<(u64, T), TryRecvError> {
+        if self.subscriber.evicted.load(Ordering::Acquire) {
+            return Err(TryRecvError::Evicted);
+        }
+        let state = self.shared.state.read();
+        let next_seq = self.subscriber.next_seq.load(Ordering::Relaxed);
+        let front = state.front_seq();
+        if next_seq < front {
+            drop(state);
+            self.advance(front);
+            return Err(TryRecvError::Lagged(LagReport {
+                first_missed: next_seq,
+                missed: front - next_seq,
+            }));
+        }
+        match state.buffer.get((next_seq - front) as usize) {
+            Some((seq, item)) => {
+                let message = (*seq, item.clone());
+                drop(state);
+                self.advance(next_seq + 1);
+                Ok(message)
+            }
+            None if state.closed => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
     }
 
-    /// Receives all available messages.
+    /// Receives all available messages, skipping over evicted ones.
     ///
     /// # Returns
     /// A vector of (sequence, item) pairs.
     pub fn recv_all(&mut self) -> Vec<(u64, T)> {
-        let state = self.state.read();
         let mut result = Vec::new();
+        loop {
+            match self.try_recv() {
+                Ok(message) => result.push(message),
+                Err(TryRecvError::Lagged(_)) => {}
+                Err(_) => return result,
+            }
+        }
+    }
 
-        for (seq, item) in &state.buffer {
-            if *seq >= self.next_seq {
-                result.push((*seq, item.clone()));
-                self.next_seq = *seq + 1;
+    /// Polls for the next message, skipping over evicted ones and
+    /// registering the task to be woken by the next send when there is
+    /// none.
+    ///
+    /// # Returns
+    /// `Ready(None)` once the receiver is evicted or the sender is gone and
+    /// every message was received.
+    pub(crate) fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<(u64, T)>> {
+        let mut registered = false;
+        loop {
+            match self.try_recv() {
+                Ok(message) => return Poll::Ready(Some(message)),
+                Err(TryRecvError::Lagged(_)) => {}
+                Err(TryRecvError::Evicted | TryRecvError::Closed) => return Poll::Ready(None),
+                Err(TryRecvError::Empty) if registered => return Poll::Pending,
+                Err(TryRecvError::Empty) => {
+                    self.shared.register(cx.waker());
+                    registered = true;
+                }
             }
         }
+    }
 
-        result
+    /// Publishes this receiver's position.
+    fn advance(&self, next_seq: u64) {
+        self.subscriber.next_seq.store(next_seq, Ordering::SeqCst);
+        self.shared.notify_progress();
     }
 
-    /// Checks if the sender is still connected.
+    /// Checks if the sender is still connected and this receiver was not
+    /// evicted.
     #[must_use]
     pub fn is_connected(&self) -> bool {
-        !self.state.read().closed
+        !self.is_evicted() && !self.shared.state.read().closed
+    }
+
+    /// Returns true if this receiver fell behind under
+    /// [`LagPolicy::Disconnect`] and was evicted.
+    #[must_use]
+    pub fn is_evicted(&self) -> bool {
+        self.subscriber.evicted.load(Ordering::Acquire)
     }
 
     /// Returns the number of messages this receiver has missed.
     #[must_use]
     pub fn lag(&self) -> u64 {
-        let state = self.state.read();
-        state.sequence.saturating_sub(self.next_seq)
+        let state = self.shared.state.read();
+        state.sequence.saturating_sub(self.next_sequence())
+    }
+
+    /// Returns the number of messages evicted before this receiver read
+    /// them.
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.subscriber.dropped.load(Ordering::Relaxed)
     }
 
     /// Returns the next expected sequence number.
     #[must_use]
     pub fn next_sequence(&self) -> u64 {
-        self.next_seq
+        self.subscriber.next_seq.load(Ordering::Relaxed)
+    }
+
+    /// Returns this receiver's ID, as reported by
+    /// [`BroadcastSender::lag_stats`].
+    #[must_use]
+    pub fn id(&self) -> u64 {
+        self.subscriber.id
+    }
+
+    /// Returns this receiver's lag policy.
+    #[must_use]
+    pub fn policy(&self) -> LagPolicy {
+        self.subscriber.policy
     }
 }
 
 impl<T: Clone> Clone for BroadcastReceiver<T> {
+    /// Subscribes a new receiver at the same position and with the same
+    /// policy.
     fn clone(&self) -> Self {
+        let mut state = self.shared.state.write();
+        let subscriber = state.register(self.subscriber.policy, self.next_sequence());
+        if self.is_evicted() {
+            subscriber.evicted.store(true, Ordering::Release);
+        }
         Self {
-            state: Arc::clone(&self.state),
-            next_seq: self.next_seq,
+            shared: Arc::clone(&self.shared),
+            subscriber,
         }
     }
 }
 
+impl<T> Drop for BroadcastReceiver<T> {
+    fn drop(&mut self) {
+        self.shared
+            .state
+            .write()
+            .subscribers
+            .retain(|sub| !Arc::ptr_eq(sub, &self.subscriber));
+        self.shared.notify_progress();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -311,4 +720,138 @@ mod tests {
         drop(tx);
         assert!(!rx.is_connected());
     }
+
+    #[test]
+    fn test_drop_oldest_reports_lag() {
+        let tx = channel::<u64>(2);
+        let mut rx = tx.subscribe();
+
+        for i in 0..5 {
+            tx.send(i);
+        }
+
+        assert_eq!(rx.dropped(), 3);
+        assert_eq!(
+            rx.try_recv(),
+            Err(TryRecvError::Lagged(LagReport {
+                first_missed: 0,
+                missed: 3
+            }))
+        );
+        assert_eq!(rx.try_recv(), Ok((3, 3)));
+        assert_eq!(rx.recv(), Some((4, 4)));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        drop(tx);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Closed));
+    }
+
+    #[test]
+    fn test_disconnect_evicts_slow_receiver() {
+        let tx = channel_with_policy::<u64>(2, LagPolicy::Disconnect);
+        let mut slow = tx.subscribe();
+        let mut fast = tx.subscribe_with_policy(LagPolicy::DropOldest);
+
+        tx.send(1);
+        tx.send(2);
+        assert_eq!(fast.recv_all(), vec![(0, 1), (1, 2)]);
+        tx.send(3);
+
+        assert!(slow.is_evicted());
+        assert!(!slow.is_connected());
+        assert_eq!(slow.try_recv(), Err(TryRecvError::Evicted));
+        assert_eq!(fast.recv(), Some((2, 3)));
+        assert_eq!(fast.dropped(), 0);
+    }
+
+    #[test]
+    fn test_block_waits_for_receiver() {
+        let tx = Arc::new(channel::<u64>(2));
+        let mut rx = tx.subscribe_with_policy(LagPolicy::Block);
+
+        tx.send(1);
+        tx.send(2);
+        assert_eq!(tx.try_send(3), Err(ChannelError::Full(3)));
+
+        let sender = {
+            let tx = Arc::clone(&tx);
+            std::thread::spawn(move || (3..10).fold(0, |_, i| tx.send(i)))
+        };
+        let mut received = Vec::new();
+        while received.len() < 9 {
+            if let Some((_, item)) = rx.recv() {
+                received.push(item);
+            }
+        }
+        assert_eq!(sender.join().unwrap(), 8);
+        assert_eq!(received, (1..10).collect::<Vec<_>>());
+        assert_eq!(rx.dropped(), 0);
+    }
+
+    #[test]
+    fn test_zero_capacity_holds_one_message() {
+        let tx = channel_with_policy::<u64>(0, LagPolicy::Block);
+        let mut rx = tx.subscribe();
+        tx.send(1);
+        assert_eq!(tx.try_send(2), Err(ChannelError::Full(2)));
+        assert_eq!(rx.recv(), Some((0, 1)));
+        tx.send(2);
+        assert_eq!(rx.recv(), Some((1, 2)));
+    }
+
+    #[test]
+    fn test_dropping_blocking_receiver_unblocks_sender() {
+        let tx = Arc::new(channel::<u64>(1));
+        let rx = tx.subscribe_with_policy(LagPolicy::Block);
+        tx.send(1);
+
+        let sender = {
+            let tx = Arc::clone(&tx);
+            std::thread::spawn(move || tx.send(2))
+        };
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        drop(rx);
+        assert_eq!(sender.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_lag_stats() {
+        let tx = channel::<u64>(4);
+        let mut rx1 = tx.subscribe();
+        let rx2 = tx.subscribe_with_policy(LagPolicy::Disconnect);
+        let rx3 = rx1.clone();
+
+        for i in 0..6 {
+            tx.send(i);
+        }
+        rx1.recv_all();
+
+        let stats = tx.lag_stats();
+        assert_eq!(tx.receiver_count(), 3);
+        assert_eq!(
+            stats,
+            vec![
+                SubscriberLag {
+                    id: rx2.id(),
+                    policy: LagPolicy::Disconnect,
+                    lag: 6,
+                    dropped: 0,
+                    evicted: true
+                },
+                SubscriberLag {
+                    id: rx3.id(),
+                    policy: LagPolicy::DropOldest,
+                    lag: 6,
+                    dropped: 2,
+                    evicted: false
+                },
+                SubscriberLag {
+                    id: rx1.id(),
+                    policy: LagPolicy::DropOldest,
+                    lag: 0,
+                    dropped: 2,
+                    evicted: false
+                },
+            ]
+        );
+    }
 }
@@ -0,0 +1,138 @@
+//! Time-based expiry for queued messages.
+//!
+//! Messages that sit in an outbound queue behind a stalled peer can be
+//! dangerously stale by the time they reach the wire. An [`ExpiryPolicy`]
+//! assigns a time-to-live per template ID; queues carry [`Stamped`] items
+//! so the consumer can drop expired messages instead of sending them.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Per-template time-to-live policy for queued messages.
+///
+/// The default policy never expires anything.
+#[derive(Debug, Clone, Default)]
+pub struct ExpiryPolicy {
+    default_ttl: Option<Duration>,
+    template_ttls: HashMap<u16, Duration>,
+}
+
+impl ExpiryPolicy {
+    /// Creates a policy that never expires messages.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the TTL applied to templates without an override.
+    #[must_use]
+    pub fn default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the TTL for a single template ID, overriding the default.
+    #[must_use]
+    pub fn template_ttl(mut self, template_id: u16, ttl: Duration) -> Self {
+        self.template_ttls.insert(template_id, ttl);
+        self
+    }
+
+    /// Returns true if any TTL is configured.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.default_ttl.is_some() || !self.template_ttls.is_empty()
+    }
+
+    /// Returns the TTL for a message.
+    ///
+    /// `template_id` is `None` for messages whose template is unknown
+    /// (e.g. too short to carry a header); those get the default TTL.
+    #[must_use]
+    pub fn ttl_for(&self, template_id: Option<u16>) -> Option<Duration> {
+        template_id
+            .and_then(|id| self.template_ttls.get(&id).copied())
+            .or(self.default_ttl)
+    }
+
+    /// Returns true if a message queued at `queued_at` has outlived its TTL
+    /// at `now`.
+    #[must_use]
+    pub fn is_expired(&self, template_id: Option<u16>, queued_at: Instant, now: Instant) -> bool {
+        self.ttl_for(template_id)
+            .is_some_and(|ttl| now.saturating_duration_since(queued_at) > ttl)
+    }
+}
+
+/// A queued item stamped with the time it was enqueued.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stamped<T> {
+    /// The queued item.
+    pub item: T,
+    /// When the item was enqueued.
+    pub queued_at: Instant,
+}
+
+impl<T> Stamped<T> {
+    /// Stamps `item` with the current time.
+    #[must_use]
+    pub fn now(item: T) -> Self {
+        Self {
+            item,
+            queued_at: Instant::now(),
+        }
+    }
+
+    /// Returns how long the item has been queued at `now`.
+    #[must_use]
+    pub fn age(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.queued_at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiry_policy_default_never_expires() {
+        let policy = ExpiryPolicy::new();
+        let queued_at = Instant::now();
+        let later = queued_at + Duration::from_secs(3600);
+
+        assert!(!policy.is_enabled());
+        assert_eq!(policy.ttl_for(Some(1)), None);
+        assert!(!policy.is_expired(Some(1), queued_at, later));
+    }
+
+    #[test]
+    fn test_expiry_policy_template_override() {
+        let policy = ExpiryPolicy::new()
+            .default_ttl(Duration::from_millis(100))
+            .template_ttl(7, Duration::from_millis(10));
+
+        assert!(policy.is_enabled());
+        assert_eq!(policy.ttl_for(Some(7)), Some(Duration::from_millis(10)));
+        assert_eq!(policy.ttl_for(Some(8)), Some(Duration::from_millis(100)));
+        assert_eq!(policy.ttl_for(None), Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_expiry_policy_is_expired() {
+        let policy = ExpiryPolicy::new().template_ttl(7, Duration::from_millis(10));
+        let queued_at = Instant::now();
+
+        assert!(!policy.is_expired(Some(7), queued_at, queued_at + Duration::from_millis(10)));
+        assert!(policy.is_expired(Some(7), queued_at, queued_at + Duration::from_millis(11)));
+        assert!(!policy.is_expired(Some(8), queued_at, queued_at + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_stamped_age() {
+        let stamped = Stamped::now(vec![1u8, 2, 3]);
+        let later = stamped.queued_at + Duration::from_millis(5);
+
+        assert_eq!(stamped.age(later), Duration::from_millis(5));
+        assert_eq!(stamped.age(stamped.queued_at), Duration::ZERO);
+    }
+}
@@ -0,0 +1,115 @@
+//! Iterator adapters over [`ChannelReceiver`]s.
+//!
+//! Both adapters are non-blocking: they only yield items that are already
+//! queued. They replace `while let Some(item) = rx.try_recv()` chains in
+//! single-threaded poll loops.
+
+use crate::ChannelReceiver;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
+/// Iterator that drains a receiver until it is empty.
+///
+/// Returned by [`ChannelReceiver::drain`]. Once the receiver is observed
+/// empty the iterator is exhausted, even if more items arrive later.
+pub struct Drain<'a, R: ?Sized, T> {
+    receiver: &'a R,
+    done: bool,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<'a, R: ChannelReceiver<T> + ?Sized, T> Drain<'a, R, T> {
+    pub(crate) fn new(receiver: &'a R) -> Self {
+        Self {
+            receiver,
+            done: false,
+            _item: PhantomData,
+        }
+    }
+}
+
+impl<R: ChannelReceiver<T> + ?Sized, T> Iterator for Drain<'_, R, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.done {
+            return None;
+        }
+        let item = self.receiver.try_recv();
+        self.done = item.is_none();
+        item
+    }
+}
+
+impl<R: ChannelReceiver<T> + ?Sized, T> FusedIterator for Drain<'_, R, T> {}
+
+/// Resumable iterator over the items currently available in a receiver.
+///
+/// Returned by [`ChannelReceiver::iter`]. Every call to `next` is a
+/// non-blocking receive, so unlike [`Drain`] the iterator can be kept
+/// across poll-loop passes and yields again once new items arrive.
+pub struct Iter<'a, R: ?Sized, T> {
+    receiver: &'a R,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<'a, R: ChannelReceiver<T> + ?Sized, T> Iter<'a, R, T> {
+    pub(crate) fn new(receiver: &'a R) -> Self {
+        Self {
+            receiver,
+            _item: PhantomData,
+        }
+    }
+}
+
+impl<R: ChannelReceiver<T> + ?Sized, T> Iterator for Iter<'_, R, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.receiver.try_recv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ChannelReceiver;
+    use crate::mpsc::channel;
+
+    #[test]
+    fn test_drain_yields_available_items() {
+        let (tx, rx) = channel::<u32>(8);
+        for i in 0..3 {
+            tx.send(i).unwrap();
+        }
+
+        let items: Vec<u32> = ChannelReceiver::drain(&rx).collect();
+        assert_eq!(items, vec![0, 1, 2]);
+        assert!(rx.is_empty());
+    }
+
+    #[test]
+    fn test_drain_is_fused() {
+        let (tx, rx) = channel::<u32>(8);
+        let mut drain = ChannelReceiver::drain(&rx);
+        assert_eq!(drain.next(), None);
+
+        tx.send(1).unwrap();
+        assert_eq!(drain.next(), None);
+        assert_eq!(rx.try_recv(), Some(1));
+    }
+
+    #[test]
+    fn test_iter_resumes_after_new_items() {
+        let (tx, rx) = channel::<u32>(8);
+        let mut iter = rx.iter();
+        assert_eq!(iter.next(), None);
+
+        tx.send(7).unwrap();
+        tx.send(8).unwrap();
+        assert_eq!(iter.next(), Some(7));
+        assert_eq!(iter.next(), Some(8));
+        assert_eq!(iter.next(), None);
+    }
+}
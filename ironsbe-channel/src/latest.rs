@@ -0,0 +1,320 @@
+//! Latest-value (conflating) cell built on a seqlock.
+//!
+//! Consumers of market data often need only the most recent book or top of
+//! book, not every update in between. A [`Cell`] holds a single value: the
+//! [`Writer`] overwrites it in place and readers copy out whatever is
+//! current, so a slow reader skips intermediate updates instead of building
+//! a backlog.
+//!
+//! The cell is a seqlock. The writer bumps a sequence number to odd before
+//! writing and back to even after; a reader copies the value between two
+//! loads of the sequence and keeps the copy only if both are the same even
+//! number, retrying otherwise. The writer never waits for readers, and
+//! readers never write shared memory, so any number of them can poll the
+//! cell without slowing the writer down.
+//!
+//! Every write advances the value's version by one. Readers get the version
+//! with each value, and [`Reader::read_since`] returns a value only if it
+//! is newer than the one they last saw.
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering, fence};
+
+/// A value and the number of writes that preceded it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Versioned<T> {
+    /// The value.
+    pub value: T,
+    /// Version of the value: 0 for the initial value, then one more for
+    /// every write.
+    pub version: u64,
+}
+
+/// Seqlock-protected latest value, shared by one [`Writer`] and any number
+/// of [`Reader`]s.
+pub struct Cell<T> {
+    /// Twice the version, plus one while a write is in progress.
+    seq: AtomicU64,
+    value: UnsafeCell<T>,
+    writer_dropped: AtomicBool,
+}
+
+// SAFETY: only the single `Writer` writes `value`, between the odd and even
+// stores of `seq`; readers copy it out with a volatile read and discard the
+// copy unless `seq` was the same even number before and after, so they never
+// use a value the writer was changing. `T: Copy` means a copy holds no
+// ownership, and `T: Send` lets values written on one thread be read on
+// another.
+unsafe impl<T: Copy + Send> Sync for Cell<T> {}
+// SAFETY: see above.
+unsafe impl<T: Copy + Send> Send for Cell<T> {}
+
+impl<T: Copy> Cell<T> {
+    /// Creates a cell holding `initial` at version 0.
+    ///
+    /// # Returns
+    /// A tuple of (writer, reader). Clone the reader for more readers.
+    #[allow(clippy::new_ret_no_self)]
+    #[must_use]
+    pub fn new(initial: T) -> (Writer<T>, Reader<T>) {
+        let cell = Arc::new(Self {
+            seq: AtomicU64::new(0),
+            value: UnsafeCell::new(initial),
+            writer_dropped: AtomicBool::new(false),
+        });
+        (
+            Writer {
+                cell: Arc::clone(&cell),
+            },
+            Reader { cell },
+        )
+    }
+
+    /// Makes one attempt to copy out the value.
+    fn try_read(&self) -> Option<Versioned<T>> {
+        let before = self.seq.load(Ordering::Acquire);
+        if before & 1 == 1 {
+            return None;
+        }
+        // SAFETY: the pointer is valid and aligned for `T`. The copy may be
+        // torn if the writer is active, so it stays `MaybeUninit` until the
+        // sequence check below proves it was not.
+        let value = unsafe { std::ptr::read_volatile(self.value.get().cast::<MaybeUninit<T>>()) };
+        fence(Ordering::Acquire);
+        if self.seq.load(Ordering::Relaxed) != before {
+            return None;
+        }
+        Some(Versioned {
+            // SAFETY: no write overlapped the copy, so it is a whole `T`.
+            value: unsafe { value.assume_init() },
+            version: before / 2,
+        })
+    }
+
+    /// Returns the version of the latest completed write.
+    fn version(&self) -> u64 {
+        self.seq.load(Ordering::Acquire) / 2
+    }
+}
+
+impl<T> std::fmt::Debug for Cell<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cell")
+            .field("seq", &self.seq.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+/// The writing side of a [`Cell`]. There is exactly one.
+#[derive(Debug)]
+pub struct Writer<T> {
+    cell: Arc<Cell<T>>,
+}
+
+impl<T: Copy> Writer<T> {
+    /// Replaces the value, never waiting for readers.
+    ///
+    /// # Returns
+    /// The version of the new value.
+    pub fn write(&mut self, value: T) -> u64 {
+        let seq = self.cell.seq.load(Ordering::Relaxed);
+        self.cell.seq.store(seq + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        // SAFETY: `&mut self` on the only writer excludes other writes, and
+        // readers discard copies taken while `seq` is odd.
+        unsafe { std::ptr::write_volatile(self.cell.value.get(), value) };
+        self.cell.seq.store(seq + 2, Ordering::Release);
+        seq / 2 + 1
+    }
+
+    /// Replaces the value with `f` applied to the current one.
+    ///
+    /// # Returns
+    /// The version of the new value.
+    pub fn update(&mut self, f: impl FnOnce(T) -> T) -> u64 {
+        // SAFETY: only the writer writes the value, and it is not writing.
+        let current = unsafe { *self.cell.value.get() };
+        self.write(f(current))
+    }
+
+    /// Returns the current value; the writer never sees a torn one.
+    #[must_use]
+    pub fn get(&self) -> T {
+        // SAFETY: only the writer writes the value, and it is not writing.
+        unsafe { *self.cell.value.get() }
+    }
+
+    /// Returns the version of the current value.
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.cell.version()
+    }
+
+    /// Returns a new reader of this cell.
+    #[must_use]
+    pub fn reader(&self) -> Reader<T> {
+        Reader {
+            cell: Arc::clone(&self.cell),
+        }
+    }
+}
+
+impl<T> Drop for Writer<T> {
+    fn drop(&mut self) {
+        self.cell.writer_dropped.store(true, Ordering::Release);
+    }
+}
+
+/// A reading side of a [`Cell`]. Clone it for more readers.
+#[derive(Debug)]
+pub struct Reader<T> {
+    cell: Arc<Cell<T>>,
+}
+
+impl<T> Clone for Reader<T> {
+    fn clone(&self) -> Self {
+        Self {
+            cell: Arc::clone(&self.cell),
+        }
+    }
+}
+
+impl<T: Copy> Reader<T> {
+    /// Returns the latest value and its version.
+    ///
+    /// Spins while a write is in progress, which lasts as long as copying
+    /// one `T`.
+    #[must_use]
+    pub fn read(&self) -> Versioned<T> {
+        loop {
+            if let Some(read) = self.cell.try_read() {
+                return read;
+            }
+            std::hint::spin_loop();
+        }
+    }
+
+    /// Makes a single attempt to read the latest value.
+    ///
+    /// # Returns
+    /// `None` if the writer was writing, in which case the copy could have
+    /// been torn and was discarded.
+    #[must_use]
+    pub fn try_read(&self) -> Option<Versioned<T>> {
+        self.cell.try_read()
+    }
+
+    /// Returns the latest value if it is newer than `version`.
+    ///
+    /// Pass the version of the last value seen to poll for changes: `None`
+    /// means nothing was written since.
+    #[must_use]
+    pub fn read_since(&self, version: u64) -> Option<Versioned<T>> {
+        if self.cell.version() <= version {
+            return None;
+        }
+        Some(self.read())
+    }
+
+    /// Returns the version of the latest completed write, without reading
+    /// the value.
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.cell.version()
+    }
+
+    /// Returns true if the writer was dropped; the value will not change
+    /// again.
+    #[must_use]
+    pub fn is_disconnected(&self) -> bool {
+        self.cell.writer_dropped.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_latest_value() {
+        let (mut writer, reader) = Cell::new(0u64);
+        assert_eq!(
+            reader.read(),
+            Versioned {
+                value: 0,
+                version: 0
+            }
+        );
+
+        assert_eq!(writer.write(10), 1);
+        assert_eq!(writer.write(20), 2);
+        assert_eq!(writer.update(|v| v + 1), 3);
+        assert_eq!(writer.get(), 21);
+
+        let other = reader.clone();
+        assert_eq!(reader.read().value, 21);
+        assert_eq!(other.try_read().map(|r| r.version), Some(3));
+        assert_eq!(writer.reader().version(), 3);
+    }
+
+    #[test]
+    fn test_read_since() {
+        let (mut writer, reader) = Cell::new((1u32, 2u32));
+        assert_eq!(reader.read_since(0), None);
+
+        writer.write((3, 4));
+        let seen = reader.read_since(0).unwrap();
+        assert_eq!(seen.value, (3, 4));
+        assert_eq!(reader.read_since(seen.version), None);
+
+        writer.write((5, 6));
+        assert_eq!(reader.read_since(seen.version).unwrap().value, (5, 6));
+    }
+
+    #[test]
+    fn test_disconnected() {
+        let (mut writer, reader) = Cell::new(1u8);
+        writer.write(2);
+        assert!(!reader.is_disconnected());
+        drop(writer);
+        assert!(reader.is_disconnected());
+        assert_eq!(reader.read().value, 2);
+    }
+
+    #[test]
+    fn test_concurrent_reads_are_never_torn() {
+        const WRITES: u64 = 200_000;
+        let (mut writer, reader) = Cell::new([0u64; 16]);
+
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let reader = reader.clone();
+                std::thread::spawn(move || {
+                    let mut last = 0;
+                    while !reader.is_disconnected() || reader.version() > last {
+                        let Some(read) = reader.read_since(last) else {
+                            std::hint::spin_loop();
+                            continue;
+                        };
+                        assert!(read.version > last);
+                        assert!(read.value.iter().all(|&v| v == read.value[0]));
+                        assert_eq!(read.value[0], read.version);
+                        last = read.version;
+                    }
+                    last
+                })
+            })
+            .collect();
+
+        for i in 1..=WRITES {
+            writer.write([i; 16]);
+        }
+        drop(writer);
+
+        for handle in readers {
+            assert_eq!(handle.join().unwrap(), WRITES);
+        }
+    }
+}
@@ -5,16 +5,42 @@
 //! This crate provides:
 //! - [`spsc`] - Ultra-low-latency single-producer single-consumer channels (~20ns)
 //! - [`mpsc`] - Multi-producer single-consumer channels (~100ns)
-//! - [`broadcast`] - One-to-many broadcast channels
+//! - [`mpmc`] - Lock-free multi-producer multi-consumer channels for worker fan-out
+//! - [`ring`] - Zero-copy SPSC byte ring with claim/commit slots
+//! - [`priority`] - Two-lane MPSC channels that drain control before data
+//! - [`broadcast`] - One-to-many broadcast channels with per-subscriber lag policies
+//! - [`latest`] - Conflating latest-value cell: one writer, lock-free readers
 //! - [`async_bridge`] - Async/sync bridging utilities
+//! - [`expiry`] - Time-to-live policies for queued messages
+//! - [`router`] - Template-based routing onto dedicated consumer channels
+//! - [`wait`] - Receiver wait strategies: busy-spin, yield, park or eventfd
+//! - [`select`](mod@select) - Waiting on several receivers at once, with
+//!   [`select!`]
 
 pub mod async_bridge;
 pub mod broadcast;
+pub mod expiry;
+pub mod iter;
+pub mod latest;
+pub mod mpmc;
 pub mod mpsc;
+pub mod priority;
+pub mod ring;
+pub mod router;
+pub mod select;
 pub mod spsc;
+pub mod wait;
 
+pub use expiry::{ExpiryPolicy, Stamped};
+pub use iter::{Drain, Iter};
+pub use mpmc::{MpmcChannel, MpmcReceiver, MpmcSender};
 pub use mpsc::{MpscChannel, MpscReceiver, MpscSender};
+pub use priority::{Lane, PriorityChannel, PriorityReceiver, PrioritySender};
+pub use ring::{Claim, ClaimError, Message, RingConsumer, RingProducer};
+pub use router::{RouteError, Router};
+pub use select::{Fairness, Select, SelectError, Selectable};
 pub use spsc::{SpscChannel, SpscReceiver, SpscSender};
+pub use wait::WaitStrategy;
 
 /// Error type for channel operations.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -64,6 +90,37 @@ pub trait ChannelReceiver<T>: Send {
 
     /// Blocking receive with timeout.
     fn recv_timeout(&self, timeout: std::time::Duration) -> Option<T>;
+
+    /// Blocking receive until `deadline`.
+    ///
+    /// A deadline already in the past degrades to a single non-blocking
+    /// attempt.
+    fn recv_deadline(&self, deadline: std::time::Instant) -> Option<T> {
+        match deadline.checked_duration_since(std::time::Instant::now()) {
+            Some(timeout) => self.recv_timeout(timeout),
+            None => self.try_recv(),
+        }
+    }
+
+    /// Returns an iterator draining the items currently available.
+    ///
+    /// The iterator ends the first time the receiver is empty.
+    fn drain(&self) -> Drain<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        Drain::new(self)
+    }
+
+    /// Returns a resumable, non-blocking iterator over available items.
+    ///
+    /// See [`Iter`] for how it differs from [`drain`](Self::drain).
+    fn iter(&self) -> Iter<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        Iter::new(self)
+    }
 }
 
 #[cfg(test)]
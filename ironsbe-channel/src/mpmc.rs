@@ -0,0 +1,601 @@
+//! MPMC (Multi-Producer Multi-Consumer) channel.
+//!
+//! This module provides a bounded, lock-free MPMC channel for fanning work
+//! out from one or more producers to a pool of workers: every item is
+//! delivered to exactly one receiver, whichever claims it first.
+//!
+//! The queue is an array of slots, each carrying a sequence number that
+//! tells producers and consumers whose turn the slot is (Dmitry Vyukov's
+//! bounded MPMC queue). An uncontended send or receive is one
+//! compare-and-swap on the shared position plus one store to the slot.
+//! Blocking operations spin briefly and then yield the thread.
+
+use crate::{ChannelError, ChannelReceiver, ChannelSender};
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Creates a new bounded MPMC channel pair.
+///
+/// # Arguments
+/// * `capacity` - Minimum number of items the channel can hold; rounded up
+///   to a power of two, and to at least 2
+///
+/// # Returns
+/// A tuple of (sender, receiver), both of which can be cloned.
+#[must_use]
+pub fn channel<T: Send>(capacity: usize) -> (MpmcSender<T>, MpmcReceiver<T>) {
+    MpmcChannel::bounded(capacity)
+}
+
+/// MPMC channel factory.
+pub struct MpmcChannel;
+
+impl MpmcChannel {
+    /// Creates a new bounded MPMC channel pair.
+    ///
+    /// # Arguments
+    /// * `capacity` - Minimum number of items the channel can hold; rounded
+    ///   up to a power of two, and to at least 2
+    #[must_use]
+    pub fn bounded<T: Send>(capacity: usize) -> (MpmcSender<T>, MpmcReceiver<T>) {
+        let shared = Arc::new(Shared::new(capacity));
+        (
+            MpmcSender {
+                shared: Arc::clone(&shared),
+            },
+            MpmcReceiver { shared },
+        )
+    }
+}
+
+/// Pads and aligns a value to its own cache line pair, so producers and
+/// consumers do not false-share their positions.
+#[repr(align(128))]
+struct CachePadded<T>(T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// One slot of the ring.
+struct Slot<T> {
+    /// Equals the enqueue position when the slot is free for that lap, and
+    /// the position plus one once it holds a value.
+    sequence: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// State shared by all senders and receivers.
+struct Shared<T> {
+    buffer: Box<[Slot<T>]>,
+    mask: usize,
+    /// Next position to write.
+    enqueue: CachePadded<AtomicUsize>,
+    /// Next position to read.
+    dequeue: CachePadded<AtomicUsize>,
+    senders: AtomicUsize,
+    receivers: AtomicUsize,
+}
+
+// SAFETY: a slot's value is only accessed by the thread that claimed its
+// position with a compare-and-swap, and the sequence numbers order those
+// accesses between threads.
+unsafe impl<T: Send> Send for Shared<T> {}
+// SAFETY: see above.
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(2).next_power_of_two();
+        let buffer = (0..capacity)
+            .map(|i| Slot {
+                sequence: AtomicUsize::new(i),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            })
+            .collect();
+        Self {
+            buffer,
+            mask: capacity - 1,
+            enqueue: CachePadded(AtomicUsize::new(0)),
+            dequeue: CachePadded(AtomicUsize::new(0)),
+            senders: AtomicUsize::new(1),
+            receivers: AtomicUsize::new(1),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn push(&self, item: T) -> Result<(), T> {
+        let mut pos = self.enqueue.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[pos & self.mask];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            let lag = sequence.wrapping_sub(pos) as isize;
+            if lag == 0 {
+                match self.enqueue.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // SAFETY: the CAS gave this thread the slot for this
+                        // lap; no reader touches it until the store below.
+                        unsafe { (*slot.value.get()).write(item) };
+                        slot.sequence.store(pos.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if lag < 0 {
+                // The slot still holds the previous lap's value: full.
+                return Err(item);
+            } else {
+                pos = self.enqueue.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn pop(&self) -> Option<T> {
+        let mut pos = self.dequeue.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[pos & self.mask];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            let lag = sequence.wrapping_sub(pos.wrapping_add(1)) as isize;
+            if lag == 0 {
+                match self.dequeue.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // SAFETY: the CAS gave this thread the slot, and the
+                        // Acquire load above saw the writer's Release store.
+                        let item = unsafe { (*slot.value.get()).assume_init_read() };
+                        slot.sequence
+                            .store(pos.wrapping_add(self.mask + 1), Ordering::Release);
+                        return Some(item);
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if lag < 0 {
+                // Not written yet: empty.
+                return None;
+            } else {
+                pos = self.dequeue.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        loop {
+            let dequeue = self.dequeue.load(Ordering::Acquire);
+            let enqueue = self.enqueue.load(Ordering::Acquire);
+            // Retry if a receiver moved while the enqueue position was read.
+            if self.dequeue.load(Ordering::Acquire) == dequeue {
+                return enqueue.wrapping_sub(dequeue).min(self.capacity());
+            }
+        }
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        while let Some(item) = self.pop() {
+            drop(item);
+        }
+    }
+}
+
+/// Spins, then yields, between attempts of a blocking operation.
+struct Backoff(u32);
+
+impl Backoff {
+    const SPIN_LIMIT: u32 = 6;
+
+    fn snooze(&mut self) {
+        if self.0 <= Self::SPIN_LIMIT {
+            for _ in 0..1 << self.0 {
+                std::hint::spin_loop();
+            }
+            self.0 += 1;
+        } else {
+            std::thread::yield_now();
+        }
+    }
+}
+
+/// Sender half of an MPMC channel.
+///
+/// This can be cloned to create multiple senders.
+pub struct MpmcSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for MpmcSender<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for MpmcSender<T> {
+    fn drop(&mut self) {
+        self.shared.senders.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl<T> MpmcSender<T> {
+    /// Non-blocking send attempt.
+    ///
+    /// # Arguments
+    /// * `item` - Item to send
+    ///
+    /// # Errors
+    /// Returns the item if the channel is full or every receiver is gone.
+    #[inline]
+    pub fn try_send(&self, item: T) -> Result<(), ChannelError<T>> {
+        if !self.is_connected() {
+            return Err(ChannelError::Disconnected(item));
+        }
+        self.shared.push(item).map_err(ChannelError::Full)
+    }
+
+    /// Blocking send, waiting for space.
+    ///
+    /// # Arguments
+    /// * `item` - Item to send
+    ///
+    /// # Errors
+    /// Returns the item if every receiver is gone.
+    pub fn send(&self, mut item: T) -> Result<(), T> {
+        let mut backoff = Backoff(0);
+        loop {
+            match self.try_send(item) {
+                Ok(()) => return Ok(()),
+                Err(ChannelError::Full(rejected)) => item = rejected,
+                Err(ChannelError::Disconnected(rejected)) => return Err(rejected),
+                Err(ChannelError::Empty | ChannelError::Timeout) => unreachable!(),
+            }
+            backoff.snooze();
+        }
+    }
+
+    /// Send with timeout.
+    ///
+    /// # Arguments
+    /// * `item` - Item to send
+    /// * `timeout` - Maximum time to wait for space
+    ///
+    /// # Errors
+    /// Returns [`ChannelError::Timeout`] if no space freed up in time, or
+    /// the item if every receiver is gone.
+    pub fn send_timeout(&self, mut item: T, timeout: Duration) -> Result<(), ChannelError<T>> {
+        let deadline = Instant::now() + timeout;
+        let mut backoff = Backoff(0);
+        loop {
+            match self.try_send(item) {
+                Err(ChannelError::Full(rejected)) => item = rejected,
+                result => return result,
+            }
+            if Instant::now() >= deadline {
+                return Err(ChannelError::Timeout);
+            }
+            backoff.snooze();
+        }
+    }
+
+    /// Returns true if at least one receiver is still connected.
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.shared.receivers.load(Ordering::Acquire) > 0
+    }
+
+    /// Returns the number of items currently in the channel.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Returns true if the channel is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if the channel is full.
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    /// Returns the capacity of the channel.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+}
+
+impl<T: Send> ChannelSender<T> for MpmcSender<T> {
+    #[inline]
+    fn try_send(&self, item: T) -> Result<(), ChannelError<T>> {
+        MpmcSender::try_send(self, item)
+    }
+
+    fn send_timeout(&self, item: T, timeout: Duration) -> Result<(), ChannelError<T>> {
+        MpmcSender::send_timeout(self, item, timeout)
+    }
+}
+
+/// Receiver half of an MPMC channel.
+///
+/// This can be cloned to create multiple receivers; each item goes to
+/// exactly one of them.
+pub struct MpmcReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for MpmcReceiver<T> {
+    fn clone(&self) -> Self {
+        self.shared.receivers.fetch_add(1, Ordering::Relaxed);
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for MpmcReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.receivers.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl<T> MpmcReceiver<T> {
+    /// Non-blocking receive.
+    ///
+    /// # Returns
+    /// `Some(item)` if available, `None` if channel is empty.
+    #[inline]
+    pub fn try_recv(&self) -> Option<T> {
+        self.shared.pop()
+    }
+
+    /// Blocking receive.
+    ///
+    /// # Returns
+    /// `Some(item)` if received, `None` once every sender is gone and the
+    /// channel is empty.
+    pub fn recv(&self) -> Option<T> {
+        let mut backoff = Backoff(0);
+        loop {
+            if let Some(item) = self.try_recv() {
+                return Some(item);
+            }
+            if self.is_disconnected() {
+                // A sender may have pushed just before dropping.
+                return self.try_recv();
+            }
+            backoff.snooze();
+        }
+    }
+
+    /// Receive with timeout.
+    ///
+    /// # Arguments
+    /// * `timeout` - Maximum time to wait
+    ///
+    /// # Returns
+    /// `Some(item)` if received within timeout, `None` otherwise.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<T> {
+        self.recv_deadline(Instant::now() + timeout)
+    }
+
+    /// Receive with a deadline.
+    ///
+    /// # Arguments
+    /// * `deadline` - Instant after which to give up
+    ///
+    /// # Returns
+    /// `Some(item)` if received before the deadline, `None` otherwise.
+    pub fn recv_deadline(&self, deadline: Instant) -> Option<T> {
+        let mut backoff = Backoff(0);
+        loop {
+            if let Some(item) = self.try_recv() {
+                return Some(item);
+            }
+            if self.is_disconnected() {
+                return self.try_recv();
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            backoff.snooze();
+        }
+    }
+
+    /// Drains all available items from the channel.
+    ///
+    /// # Returns
+    /// An iterator over all currently available items.
+    pub fn drain(&self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(|| self.try_recv())
+    }
+
+    /// Returns the number of items currently in the channel.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Returns true if the channel is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the capacity of the channel.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /// Returns true if all senders have been dropped.
+    ///
+    /// Items sent before that can still be received.
+    #[must_use]
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.senders.load(Ordering::Acquire) == 0
+    }
+}
+
+impl<T: Send> ChannelReceiver<T> for MpmcReceiver<T> {
+    #[inline]
+    fn try_recv(&self) -> Option<T> {
+        MpmcReceiver::try_recv(self)
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Option<T> {
+        MpmcReceiver::recv_timeout(self, timeout)
+    }
+
+    fn recv_deadline(&self, deadline: Instant) -> Option<T> {
+        MpmcReceiver::recv_deadline(self, deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+    use std::thread;
+
+    #[test]
+    fn test_basic_send_recv() {
+        let (tx, rx) = channel::<u64>(16);
+
+        assert!(tx.try_send(42).is_ok());
+        assert_eq!(rx.len(), 1);
+        assert_eq!(rx.try_recv(), Some(42));
+        assert_eq!(rx.try_recv(), None);
+    }
+
+    #[test]
+    fn test_capacity_rounds_up_and_fills() {
+        let (tx, rx) = channel::<u32>(3);
+        assert_eq!(tx.capacity(), 4);
+        assert_eq!(channel::<u32>(0).0.capacity(), 2);
+
+        // Several laps around the ring.
+        for lap in 0..3 {
+            for i in 0..4 {
+                tx.try_send(lap * 10 + i).unwrap();
+            }
+            assert!(tx.is_full());
+            assert_eq!(tx.try_send(99), Err(ChannelError::Full(99)));
+            let received: Vec<_> = rx.drain().collect();
+            assert_eq!(received, (0..4).map(|i| lap * 10 + i).collect::<Vec<_>>());
+        }
+        assert!(rx.is_empty());
+    }
+
+    #[test]
+    fn test_disconnection() {
+        let (tx, rx) = channel::<u32>(4);
+        let rx2 = rx.clone();
+        tx.send(1).unwrap();
+        drop(tx);
+        assert!(rx.is_disconnected());
+        assert_eq!(rx2.recv(), Some(1));
+        assert_eq!(rx.recv(), None);
+
+        let (tx, rx) = channel::<u32>(4);
+        drop(rx);
+        assert!(!tx.is_connected());
+        assert_eq!(tx.try_send(7), Err(ChannelError::Disconnected(7)));
+        assert_eq!(tx.send(8), Err(8));
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let (tx, rx) = channel::<u32>(2);
+        assert_eq!(rx.recv_timeout(Duration::from_millis(5)), None);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(
+            tx.send_timeout(3, Duration::from_millis(5)),
+            Err(ChannelError::Timeout)
+        );
+        assert_eq!(ChannelReceiver::recv_deadline(&rx, Instant::now()), Some(1));
+    }
+
+    #[test]
+    fn test_unreceived_items_are_dropped() {
+        let item = Arc::new(());
+        let (tx, rx) = channel::<Arc<()>>(8);
+        for _ in 0..5 {
+            tx.send(Arc::clone(&item)).unwrap();
+        }
+        drop(rx.try_recv());
+        drop((tx, rx));
+        assert_eq!(Arc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn test_fan_out_delivers_each_item_once() {
+        const PRODUCERS: u64 = 3;
+        const CONSUMERS: usize = 4;
+        const PER_PRODUCER: u64 = 20_000;
+
+        let (tx, rx) = channel::<u64>(64);
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let consumers: Vec<_> = (0..CONSUMERS)
+            .map(|_| {
+                let rx = rx.clone();
+                let received = Arc::clone(&received);
+                thread::spawn(move || {
+                    let mut local = Vec::new();
+                    while let Some(item) = rx.recv() {
+                        local.push(item);
+                    }
+                    received.lock().unwrap().extend(local);
+                })
+            })
+            .collect();
+        drop(rx);
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        tx.send(p * PER_PRODUCER + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+
+        for handle in producers.into_iter().chain(consumers) {
+            handle.join().unwrap();
+        }
+        let received = received.lock().unwrap();
+        assert_eq!(received.len() as u64, PRODUCERS * PER_PRODUCER);
+        let unique: HashSet<_> = received.iter().copied().collect();
+        assert_eq!(unique.len(), received.len());
+    }
+}
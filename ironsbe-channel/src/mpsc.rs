@@ -3,8 +3,12 @@
 //! This module provides a bounded MPSC channel with multiple sender support
 //! and ~50-100ns latency.
 
-use crossbeam_channel::{Receiver, Sender, TrySendError, bounded};
-use std::time::Duration;
+use crate::ChannelReceiver;
+use crate::wait::{self, Notifier, SpaceNotifier, WaitStrategy, Waiter};
+use crossbeam_channel::{Receiver, Sender, TryRecvError, TrySendError, bounded};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::{Duration, Instant};
 
 /// Creates a new bounded MPSC channel pair.
 ///
@@ -18,6 +22,23 @@ pub fn channel<T: Send>(capacity: usize) -> (MpscSender<T>, MpscReceiver<T>) {
     MpscChannel::bounded(capacity)
 }
 
+/// Creates a new bounded MPSC channel pair whose receiver waits with
+/// `strategy`.
+///
+/// # Arguments
+/// * `capacity` - Maximum number of items the channel can hold
+/// * `strategy` - How [`MpscReceiver::recv_wait`] waits for items
+///
+/// # Errors
+/// Returns an error if the wakeup descriptor of
+/// [`WaitStrategy::EventFd`] cannot be created.
+pub fn channel_with_wait<T: Send>(
+    capacity: usize,
+    strategy: WaitStrategy,
+) -> std::io::Result<(MpscSender<T>, MpscReceiver<T>)> {
+    MpscChannel::bounded_with_wait(capacity, strategy)
+}
+
 /// MPSC channel factory.
 pub struct MpscChannel;
 
@@ -28,10 +49,40 @@ impl MpscChannel {
     /// * `capacity` - Maximum number of items the channel can hold
     #[must_use]
     pub fn bounded<T: Send>(capacity: usize) -> (MpscSender<T>, MpscReceiver<T>) {
+        Self::with_waiter(capacity, Waiter::busy_spin())
+    }
+
+    /// Creates a new bounded MPSC channel pair whose receiver waits with
+    /// `strategy`.
+    ///
+    /// # Arguments
+    /// * `capacity` - Maximum number of items the channel can hold
+    /// * `strategy` - How [`MpscReceiver::recv_wait`] waits for items
+    ///
+    /// # Errors
+    /// Returns an error if the wakeup descriptor of
+    /// [`WaitStrategy::EventFd`] cannot be created.
+    pub fn bounded_with_wait<T: Send>(
+        capacity: usize,
+        strategy: WaitStrategy,
+    ) -> std::io::Result<(MpscSender<T>, MpscReceiver<T>)> {
+        Ok(Self::with_waiter(capacity, Waiter::new(strategy)?))
+    }
+
+    fn with_waiter<T: Send>(capacity: usize, waiter: Waiter) -> (MpscSender<T>, MpscReceiver<T>) {
         let (sender, receiver) = bounded(capacity);
+        let arm = waiter.needs_arm();
+        let waiter = Arc::new(waiter);
         (
-            MpscSender { inner: sender },
-            MpscReceiver { inner: receiver },
+            MpscSender {
+                inner: sender,
+                notifier: Notifier::new(Arc::clone(&waiter)),
+            },
+            MpscReceiver {
+                inner: receiver,
+                waiter: SpaceNotifier::new(waiter),
+                arm,
+            },
         )
     }
 }
@@ -39,9 +90,20 @@ impl MpscChannel {
 /// Sender half of an MPSC channel.
 ///
 /// This can be cloned to create multiple senders.
-#[derive(Clone)]
 pub struct MpscSender<T> {
     inner: Sender<T>,
+    /// Dropped after `inner`, so a receiver woken by the last sender's drop
+    /// sees the disconnection.
+    notifier: Notifier,
+}
+
+impl<T> Clone for MpscSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            notifier: self.notifier.clone(),
+        }
+    }
 }
 
 impl<T> MpscSender<T> {
@@ -54,7 +116,35 @@ impl<T> MpscSender<T> {
     /// Returns the item if the channel is full or disconnected.
     #[inline]
     pub fn try_send(&self, item: T) -> Result<(), TrySendError<T>> {
-        self.inner.try_send(item)
+        self.inner.try_send(item)?;
+        self.notifier.notify();
+        Ok(())
+    }
+
+    /// Sends as many leading items of `items` as fit without blocking.
+    ///
+    /// The underlying channel claims slots one at a time, so unlike
+    /// [`SpscSender::send_batch`](crate::spsc::SpscSender::send_batch) this
+    /// saves only the per-call overhead.
+    ///
+    /// # Arguments
+    /// * `items` - Items to send, in order
+    ///
+    /// # Returns
+    /// The number of items sent, which is less than `items.len()` if the
+    /// channel filled up or the receiver is gone.
+    pub fn send_batch(&self, items: &[T]) -> usize
+    where
+        T: Clone,
+    {
+        let sent = items
+            .iter()
+            .take_while(|item| self.inner.try_send((*item).clone()).is_ok())
+            .count();
+        if sent > 0 {
+            self.notifier.notify();
+        }
+        sent
     }
 
     /// Blocking send.
@@ -65,7 +155,9 @@ impl<T> MpscSender<T> {
     /// # Errors
     /// Returns the item if the channel is disconnected.
     pub fn send(&self, item: T) -> Result<(), T> {
-        self.inner.send(item).map_err(|e| e.0)
+        self.inner.send(item).map_err(|e| e.0)?;
+        self.notifier.notify();
+        Ok(())
     }
 
     /// Send with timeout.
@@ -77,10 +169,14 @@ impl<T> MpscSender<T> {
     /// # Errors
     /// Returns the item if the operation times out or channel is disconnected.
     pub fn send_timeout(&self, item: T, timeout: Duration) -> Result<(), T> {
-        self.inner.send_timeout(item, timeout).map_err(|e| match e {
-            crossbeam_channel::SendTimeoutError::Timeout(v) => v,
-            crossbeam_channel::SendTimeoutError::Disconnected(v) => v,
-        })
+        self.inner
+            .send_timeout(item, timeout)
+            .map_err(|e| match e {
+                crossbeam_channel::SendTimeoutError::Timeout(v) => v,
+                crossbeam_channel::SendTimeoutError::Disconnected(v) => v,
+            })?;
+        self.notifier.notify();
+        Ok(())
     }
 
     /// Returns true if the receiver is still connected.
@@ -112,21 +208,46 @@ impl<T> MpscSender<T> {
     pub fn capacity(&self) -> Option<usize> {
         self.inner.capacity()
     }
+
+    /// Returns the channel's wait state.
+    #[cfg(feature = "futures")]
+    pub(crate) fn waiter(&self) -> &Waiter {
+        self.notifier.waiter()
+    }
 }
 
 /// Receiver half of an MPSC channel.
 pub struct MpscReceiver<T> {
     inner: Receiver<T>,
+    /// Dropped after `inner`, so async senders woken by the drop see the
+    /// disconnection.
+    waiter: SpaceNotifier,
+    /// Whether an empty receive must arm the wakeup descriptor.
+    arm: bool,
 }
 
 impl<T> MpscReceiver<T> {
+    /// Wakes async senders waiting for space if `item` was taken.
+    #[inline(always)]
+    fn took<U>(&self, item: Option<U>) -> Option<U> {
+        if item.is_some() {
+            self.waiter.notify_space();
+        }
+        item
+    }
+
     /// Non-blocking receive.
     ///
     /// # Returns
     /// `Some(item)` if available, `None` if channel is empty.
     #[inline]
     pub fn try_recv(&self) -> Option<T> {
-        self.inner.try_recv().ok()
+        let item = match self.inner.try_recv() {
+            Ok(item) => Some(item),
+            Err(_) if self.arm && self.waiter.arm() => self.inner.try_recv().ok(),
+            Err(_) => None,
+        };
+        self.took(item)
     }
 
     /// Blocking receive.
@@ -134,7 +255,7 @@ impl<T> MpscReceiver<T> {
     /// # Returns
     /// `Some(item)` if received, `None` if channel is disconnected.
     pub fn recv(&self) -> Option<T> {
-        self.inner.recv().ok()
+        self.took(self.inner.recv().ok())
     }
 
     /// Receive with timeout.
@@ -145,7 +266,103 @@ impl<T> MpscReceiver<T> {
     /// # Returns
     /// `Some(item)` if received within timeout, `None` otherwise.
     pub fn recv_timeout(&self, timeout: Duration) -> Option<T> {
-        self.inner.recv_timeout(timeout).ok()
+        self.took(self.inner.recv_timeout(timeout).ok())
+    }
+
+    /// Receive with a deadline.
+    ///
+    /// # Arguments
+    /// * `deadline` - Instant after which to give up
+    ///
+    /// # Returns
+    /// `Some(item)` if received before the deadline, `None` otherwise.
+    pub fn recv_deadline(&self, deadline: Instant) -> Option<T> {
+        self.took(self.inner.recv_deadline(deadline).ok())
+    }
+
+    /// Blocking receive using the channel's [`WaitStrategy`].
+    ///
+    /// # Returns
+    /// `Some(item)` if received, `None` if all senders are gone and the
+    /// channel is empty.
+    pub fn recv_wait(&self) -> Option<T> {
+        self.waiter.wait(None, || self.poll_item())
+    }
+
+    /// Receive with timeout using the channel's [`WaitStrategy`].
+    ///
+    /// # Arguments
+    /// * `timeout` - Maximum time to wait
+    ///
+    /// # Returns
+    /// `Some(item)` if received within timeout, `None` otherwise.
+    pub fn recv_wait_timeout(&self, timeout: Duration) -> Option<T> {
+        self.waiter
+            .wait(wait::deadline(timeout), || self.poll_item())
+    }
+
+    /// Returns the channel's wait strategy.
+    #[must_use]
+    pub fn wait_strategy(&self) -> WaitStrategy {
+        self.waiter.strategy()
+    }
+
+    /// Returns the descriptor of a [`WaitStrategy::EventFd`] channel, which
+    /// is readable while items may be pending.
+    ///
+    /// Register it with an event loop and, once it is readable, receive
+    /// until [`try_recv`](Self::try_recv) or
+    /// [`recv_batch`](Self::recv_batch) finds the channel empty; that empty
+    /// receive re-arms the descriptor. Returns `None` for other strategies.
+    /// Only available on Unix, like the strategy.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn wait_fd(&self) -> Option<std::os::fd::BorrowedFd<'_>> {
+        self.waiter.fd()
+    }
+
+    /// Polls for an item, reporting disconnection once drained.
+    pub(crate) fn poll_item(&self) -> Poll<Option<T>> {
+        let poll = match self.inner.try_recv() {
+            Ok(item) => Poll::Ready(Some(item)),
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+            Err(TryRecvError::Empty) if self.arm && self.waiter.arm() => {
+                match self.inner.try_recv() {
+                    Ok(item) => Poll::Ready(Some(item)),
+                    Err(TryRecvError::Disconnected) => Poll::Ready(None),
+                    Err(TryRecvError::Empty) => Poll::Pending,
+                }
+            }
+            Err(TryRecvError::Empty) => Poll::Pending,
+        };
+        if let Poll::Ready(item) = poll {
+            return Poll::Ready(self.took(item));
+        }
+        Poll::Pending
+    }
+
+    /// Returns the channel's wait state.
+    pub(crate) fn waiter(&self) -> &Waiter {
+        &self.waiter
+    }
+
+    /// Receives up to `max` available items into `out` without blocking.
+    ///
+    /// # Arguments
+    /// * `out` - Vector the items are appended to
+    /// * `max` - Maximum number of items to receive
+    ///
+    /// # Returns
+    /// The number of items received, 0 if the channel is empty.
+    pub fn recv_batch(&self, out: &mut Vec<T>, max: usize) -> usize {
+        let before = out.len();
+        out.extend(self.inner.try_iter().take(max));
+        if out.len() == before && max > 0 && self.arm && self.waiter.arm() {
+            out.extend(self.inner.try_iter().take(max));
+        }
+        let received = out.len() - before;
+        self.took((received > 0).then_some(()));
+        received
     }
 
     /// Returns a reference to the underlying crossbeam receiver for select operations.
@@ -159,7 +376,9 @@ impl<T> MpscReceiver<T> {
     /// # Returns
     /// An iterator over all currently available items.
     pub fn drain(&self) -> impl Iterator<Item = T> + '_ {
-        std::iter::from_fn(|| self.inner.try_recv().ok())
+        self.inner.try_iter().inspect(|_| {
+            self.took(Some(()));
+        })
     }
 
     /// Returns the number of items currently in the channel.
@@ -181,6 +400,21 @@ impl<T> MpscReceiver<T> {
     }
 }
 
+impl<T: Send> ChannelReceiver<T> for MpscReceiver<T> {
+    #[inline]
+    fn try_recv(&self) -> Option<T> {
+        MpscReceiver::try_recv(self)
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Option<T> {
+        MpscReceiver::recv_timeout(self, timeout)
+    }
+
+    fn recv_deadline(&self, deadline: Instant) -> Option<T> {
+        MpscReceiver::recv_deadline(self, deadline)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,6 +487,29 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_recv_deadline() {
+        let (tx, rx) = channel::<u64>(16);
+
+        let result = rx.recv_deadline(Instant::now() + Duration::from_millis(10));
+        assert!(result.is_none());
+
+        tx.send(9).unwrap();
+        assert_eq!(rx.recv_deadline(Instant::now()), Some(9));
+    }
+
+    #[test]
+    fn test_channel_receiver_recv_deadline() {
+        fn recv_by_trait<R: ChannelReceiver<u64>>(rx: &R, deadline: Instant) -> Option<u64> {
+            rx.recv_deadline(deadline)
+        }
+
+        let (tx, rx) = channel::<u64>(16);
+        tx.send(3).unwrap();
+        assert_eq!(recv_by_trait(&rx, Instant::now()), Some(3));
+        assert_eq!(recv_by_trait(&rx, Instant::now()), None);
+    }
+
     #[test]
     fn test_drain() {
         let (tx, rx) = channel::<u64>(16);
@@ -266,6 +523,22 @@ mod tests {
         assert!(rx.is_empty());
     }
 
+    #[test]
+    fn test_send_recv_batch() {
+        let (tx, rx) = channel::<u64>(4);
+
+        assert_eq!(tx.send_batch(&[1, 2, 3, 4, 5]), 4);
+
+        let mut out = Vec::new();
+        assert_eq!(rx.recv_batch(&mut out, 3), 3);
+        assert_eq!(rx.recv_batch(&mut out, 3), 1);
+        assert_eq!(out, vec![1, 2, 3, 4]);
+        assert_eq!(rx.recv_batch(&mut out, 3), 0);
+
+        drop(rx);
+        assert_eq!(tx.send_batch(&[1]), 0);
+    }
+
     #[test]
     fn test_receiver_len() {
         let (tx, rx) = channel::<u64>(16);
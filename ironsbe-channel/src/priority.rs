@@ -0,0 +1,364 @@
+//! Two-lane MPSC channel with a priority control lane.
+//!
+//! Control messages (cancels, kill switches, disconnects) travel on their
+//! own bounded lane and are always drained before the data lane, so an
+//! urgent action never waits behind a backlog of bulk traffic. Each lane
+//! keeps FIFO order on its own; there is no ordering between lanes.
+
+use crate::ChannelReceiver;
+use crossbeam_channel::{Receiver, Select, Sender, TryRecvError, TrySendError, bounded};
+use std::time::{Duration, Instant};
+
+/// Creates a new two-lane MPSC channel pair.
+///
+/// # Arguments
+/// * `control_capacity` - Maximum number of items the control lane can hold
+/// * `data_capacity` - Maximum number of items the data lane can hold
+///
+/// # Returns
+/// A tuple of (sender, receiver).
+#[must_use]
+pub fn channel<T: Send>(
+    control_capacity: usize,
+    data_capacity: usize,
+) -> (PrioritySender<T>, PriorityReceiver<T>) {
+    PriorityChannel::bounded(control_capacity, data_capacity)
+}
+
+/// Lane an item is queued on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lane {
+    /// Urgent traffic, always drained first.
+    Control,
+    /// Bulk traffic, drained once the control lane is empty.
+    Data,
+}
+
+/// Two-lane MPSC channel factory.
+pub struct PriorityChannel;
+
+impl PriorityChannel {
+    /// Creates a new two-lane MPSC channel pair.
+    ///
+    /// # Arguments
+    /// * `control_capacity` - Maximum number of items the control lane can hold
+    /// * `data_capacity` - Maximum number of items the data lane can hold
+    #[must_use]
+    pub fn bounded<T: Send>(
+        control_capacity: usize,
+        data_capacity: usize,
+    ) -> (PrioritySender<T>, PriorityReceiver<T>) {
+        let (control_tx, control_rx) = bounded(control_capacity);
+        let (data_tx, data_rx) = bounded(data_capacity);
+        (
+            PrioritySender {
+                control: control_tx,
+                data: data_tx,
+            },
+            PriorityReceiver {
+                control: control_rx,
+                data: data_rx,
+            },
+        )
+    }
+}
+
+/// Sender half of a two-lane MPSC channel.
+///
+/// This can be cloned to create multiple senders.
+#[derive(Clone)]
+pub struct PrioritySender<T> {
+    control: Sender<T>,
+    data: Sender<T>,
+}
+
+impl<T> PrioritySender<T> {
+    fn lane(&self, lane: Lane) -> &Sender<T> {
+        match lane {
+            Lane::Control => &self.control,
+            Lane::Data => &self.data,
+        }
+    }
+
+    /// Non-blocking send attempt on the given lane.
+    ///
+    /// # Errors
+    /// Returns the item if the lane is full or the channel is disconnected.
+    #[inline]
+    pub fn try_send_on(&self, lane: Lane, item: T) -> Result<(), TrySendError<T>> {
+        self.lane(lane).try_send(item)
+    }
+
+    /// Non-blocking send attempt on the data lane.
+    ///
+    /// # Errors
+    /// Returns the item if the lane is full or the channel is disconnected.
+    #[inline]
+    pub fn try_send(&self, item: T) -> Result<(), TrySendError<T>> {
+        self.data.try_send(item)
+    }
+
+    /// Non-blocking send attempt on the control lane.
+    ///
+    /// # Errors
+    /// Returns the item if the lane is full or the channel is disconnected.
+    #[inline]
+    pub fn try_send_control(&self, item: T) -> Result<(), TrySendError<T>> {
+        self.control.try_send(item)
+    }
+
+    /// Blocking send on the given lane.
+    ///
+    /// # Errors
+    /// Returns the item if the channel is disconnected.
+    pub fn send_on(&self, lane: Lane, item: T) -> Result<(), T> {
+        self.lane(lane).send(item).map_err(|e| e.0)
+    }
+
+    /// Blocking send on the data lane.
+    ///
+    /// # Errors
+    /// Returns the item if the channel is disconnected.
+    pub fn send(&self, item: T) -> Result<(), T> {
+        self.send_on(Lane::Data, item)
+    }
+
+    /// Blocking send on the control lane.
+    ///
+    /// # Errors
+    /// Returns the item if the channel is disconnected.
+    pub fn send_control(&self, item: T) -> Result<(), T> {
+        self.send_on(Lane::Control, item)
+    }
+
+    /// Returns the number of items currently queued on `lane`.
+    #[must_use]
+    pub fn len_on(&self, lane: Lane) -> usize {
+        self.lane(lane).len()
+    }
+
+    /// Returns the total number of items currently queued on both lanes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.control.len() + self.data.len()
+    }
+
+    /// Returns true if both lanes are empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.control.is_empty() && self.data.is_empty()
+    }
+}
+
+/// Receiver half of a two-lane MPSC channel.
+///
+/// Every receive checks the control lane before the data lane.
+pub struct PriorityReceiver<T> {
+    control: Receiver<T>,
+    data: Receiver<T>,
+}
+
+impl<T> PriorityReceiver<T> {
+    /// Takes the next item, control lane first.
+    fn poll(&self) -> Result<T, TryRecvError> {
+        match self.control.try_recv() {
+            Ok(item) => Ok(item),
+            Err(control) => match self.data.try_recv() {
+                Ok(item) => Ok(item),
+                Err(TryRecvError::Disconnected) if control.is_disconnected() => {
+                    Err(TryRecvError::Disconnected)
+                }
+                Err(_) => Err(TryRecvError::Empty),
+            },
+        }
+    }
+
+    /// Non-blocking receive, control lane first.
+    ///
+    /// # Returns
+    /// `Some(item)` if available, `None` if both lanes are empty.
+    #[inline]
+    pub fn try_recv(&self) -> Option<T> {
+        self.poll().ok()
+    }
+
+    /// Blocking receive, control lane first.
+    ///
+    /// # Returns
+    /// `Some(item)` if received, `None` if the channel is disconnected.
+    pub fn recv(&self) -> Option<T> {
+        self.recv_until(None)
+    }
+
+    /// Receive with timeout, control lane first.
+    ///
+    /// # Arguments
+    /// * `timeout` - Maximum time to wait
+    ///
+    /// # Returns
+    /// `Some(item)` if received within timeout, `None` otherwise.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<T> {
+        self.recv_until(Instant::now().checked_add(timeout))
+    }
+
+    /// Receive with a deadline, control lane first.
+    ///
+    /// # Arguments
+    /// * `deadline` - Instant after which to give up
+    ///
+    /// # Returns
+    /// `Some(item)` if received before the deadline, `None` otherwise.
+    pub fn recv_deadline(&self, deadline: Instant) -> Option<T> {
+        self.recv_until(Some(deadline))
+    }
+
+    fn recv_until(&self, deadline: Option<Instant>) -> Option<T> {
+        loop {
+            match self.poll() {
+                Ok(item) => return Some(item),
+                Err(TryRecvError::Disconnected) => return None,
+                Err(TryRecvError::Empty) => {}
+            }
+            // Wake on whichever lane becomes ready, then re-poll so the
+            // control lane still wins if both became ready together.
+            let mut select = Select::new();
+            select.recv(&self.control);
+            select.recv(&self.data);
+            match deadline {
+                Some(deadline) => {
+                    if select.ready_deadline(deadline).is_err() {
+                        return self.try_recv();
+                    }
+                }
+                None => {
+                    select.ready();
+                }
+            }
+        }
+    }
+
+    /// Drains all available items, control lane first.
+    ///
+    /// # Returns
+    /// An iterator over all currently available items.
+    pub fn drain(&self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(|| self.try_recv())
+    }
+
+    /// Returns the number of items currently queued on `lane`.
+    #[must_use]
+    pub fn len_on(&self, lane: Lane) -> usize {
+        match lane {
+            Lane::Control => self.control.len(),
+            Lane::Data => self.data.len(),
+        }
+    }
+
+    /// Returns the total number of items currently queued on both lanes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.control.len() + self.data.len()
+    }
+
+    /// Returns true if both lanes are empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.control.is_empty() && self.data.is_empty()
+    }
+}
+
+impl<T: Send> ChannelReceiver<T> for PriorityReceiver<T> {
+    #[inline]
+    fn try_recv(&self) -> Option<T> {
+        PriorityReceiver::try_recv(self)
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Option<T> {
+        PriorityReceiver::recv_timeout(self, timeout)
+    }
+
+    fn recv_deadline(&self, deadline: Instant) -> Option<T> {
+        PriorityReceiver::recv_deadline(self, deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_control_drained_before_data() {
+        let (tx, rx) = channel::<u64>(4, 16);
+
+        for i in 0..5 {
+            tx.send(i).unwrap();
+        }
+        tx.send_control(100).unwrap();
+        tx.send_control(101).unwrap();
+
+        let items: Vec<_> = rx.drain().collect();
+        assert_eq!(items, vec![100, 101, 0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_control_bypasses_full_data_lane() {
+        let (tx, rx) = channel::<u64>(1, 2);
+
+        tx.try_send(1).unwrap();
+        tx.try_send(2).unwrap();
+        assert!(matches!(tx.try_send(3), Err(TrySendError::Full(3))));
+
+        tx.try_send_control(9).unwrap();
+        assert_eq!(rx.try_recv(), Some(9));
+        assert_eq!(rx.try_recv(), Some(1));
+    }
+
+    #[test]
+    fn test_send_on_lane() {
+        let (tx, rx) = channel::<u64>(4, 4);
+
+        tx.try_send_on(Lane::Data, 1).unwrap();
+        tx.send_on(Lane::Control, 2).unwrap();
+        assert_eq!(tx.len_on(Lane::Control), 1);
+        assert_eq!(rx.len_on(Lane::Data), 1);
+        assert_eq!(rx.len(), 2);
+
+        assert_eq!(rx.try_recv(), Some(2));
+        assert_eq!(rx.try_recv(), Some(1));
+        assert!(rx.is_empty());
+    }
+
+    #[test]
+    fn test_recv_timeout() {
+        let (_tx, rx) = channel::<u64>(4, 4);
+        assert_eq!(rx.recv_timeout(Duration::from_millis(10)), None);
+    }
+
+    #[test]
+    fn test_recv_wakes_on_either_lane() {
+        let (tx, rx) = channel::<u64>(4, 4);
+
+        let control = tx.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            control.send_control(7).unwrap();
+        });
+
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Some(7));
+        handle.join().unwrap();
+
+        tx.send(8).unwrap();
+        assert_eq!(rx.recv(), Some(8));
+    }
+
+    #[test]
+    fn test_recv_after_disconnect() {
+        let (tx, rx) = channel::<u64>(4, 4);
+        tx.send(1).unwrap();
+        drop(tx);
+
+        assert_eq!(rx.recv(), Some(1));
+        assert_eq!(rx.recv(), None);
+    }
+}
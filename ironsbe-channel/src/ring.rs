@@ -0,0 +1,676 @@
+//! Zero-copy SPSC byte ring with claim/commit slots.
+//!
+//! The other channels move owned values, so passing an encoded message
+//! costs a `Vec<u8>` or `Box` per message. This ring instead stores
+//! variable-length messages inline, Disruptor style: the producer
+//! [`claim`](RingProducer::claim)s a slot, encodes the SBE message straight
+//! into it and [`commit`](Claim::commit)s it; the consumer reads each
+//! message as a `&[u8]` view into the ring, and its space is released when
+//! the view is dropped. Nothing is allocated after construction.
+//!
+//! Each record is an 8-byte header holding the message length, followed by
+//! the message padded to 8 bytes, so every message starts 8-byte aligned.
+//! A record never wraps: if it does not fit before the end of the buffer,
+//! the rest of the buffer is skipped and the record starts at offset 0.
+//! Records are therefore limited to half the ring, so that the skipped
+//! bytes and the record always fit in an empty ring.
+//!
+//! [`channel_with_memory`] maps the storage with a [`MemoryConfig`], to
+//! bind it to the consumer's NUMA node or back it with huge pages.
+
+use ironsbe_core::memory::{MemoryConfig, MemoryRegion};
+use std::cell::UnsafeCell;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Bytes of the header in front of every message.
+const HEADER_LEN: usize = 8;
+
+/// Header length value marking the rest of the buffer as skipped.
+const PADDING: u32 = u32::MAX;
+
+/// Creates a new byte ring.
+///
+/// # Arguments
+/// * `capacity` - Minimum size of the ring in bytes; rounded up to a power
+///   of two, and to at least 64
+///
+/// # Returns
+/// A tuple of (producer, consumer).
+#[must_use]
+pub fn channel(capacity: usize) -> (RingProducer, RingConsumer) {
+    let capacity = Shared::ring_len(capacity);
+    let buffer = Storage::Heap((0..capacity / 8).map(|_| UnsafeCell::new(0)).collect());
+    split(Shared::new(buffer, capacity))
+}
+
+/// Creates a new byte ring whose storage is placed as `config` says.
+///
+/// The storage is a fresh anonymous mapping, so it can be bound to a NUMA
+/// node and backed by huge pages; see [`ironsbe_core::memory`].
+///
+/// # Arguments
+/// * `capacity` - Minimum size of the ring in bytes; rounded up to a power
+///   of two, and to at least 64
+/// * `config` - NUMA node, huge-page and prefault options
+///
+/// # Errors
+/// Returns an error if the storage cannot be mapped, or, in strict mode, if
+/// the placement cannot be applied.
+pub fn channel_with_memory(
+    capacity: usize,
+    config: &MemoryConfig,
+) -> io::Result<(RingProducer, RingConsumer)> {
+    let capacity = Shared::ring_len(capacity);
+    let region = MemoryRegion::allocate(capacity, config)?;
+    Ok(split(Shared::new(Storage::Mapped(region), capacity)))
+}
+
+/// Hands out the two halves of a ring.
+fn split(shared: Shared) -> (RingProducer, RingConsumer) {
+    let shared = Arc::new(shared);
+    (
+        RingProducer {
+            shared: Arc::clone(&shared),
+            tail: 0,
+            cached_head: 0,
+        },
+        RingConsumer {
+            shared,
+            head: 0,
+            cached_tail: 0,
+        },
+    )
+}
+
+/// Error returned by [`RingProducer::claim`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimError {
+    /// Not enough free space until the consumer catches up.
+    Full,
+    /// The message can never fit in the ring.
+    TooLarge {
+        /// Requested message length.
+        len: usize,
+        /// Largest message the ring accepts.
+        max: usize,
+    },
+    /// The consumer was dropped.
+    Disconnected,
+}
+
+impl std::fmt::Display for ClaimError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Full => write!(f, "ring full"),
+            Self::TooLarge { len, max } => {
+                write!(f, "message of {len} bytes exceeds ring maximum of {max}")
+            }
+            Self::Disconnected => write!(f, "ring disconnected"),
+        }
+    }
+}
+
+impl std::error::Error for ClaimError {}
+
+/// Pads and aligns a value to its own cache line pair, so the producer and
+/// consumer do not false-share their positions.
+#[repr(align(128))]
+struct CachePadded<T>(T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// Memory holding the ring's bytes.
+enum Storage {
+    /// Heap words, so that records are 8-byte aligned.
+    Heap(Box<[UnsafeCell<u64>]>),
+    /// Page-aligned mapping placed by a [`MemoryConfig`].
+    Mapped(MemoryRegion),
+}
+
+impl Storage {
+    /// Returns a pointer to the first byte.
+    fn base(&self) -> *mut u8 {
+        match self {
+            Self::Heap(words) => UnsafeCell::raw_get(words.as_ptr()).cast(),
+            Self::Mapped(region) => region.as_ptr(),
+        }
+    }
+}
+
+/// State shared by the producer and the consumer.
+struct Shared {
+    /// Ring storage, at least `capacity` bytes.
+    buffer: Storage,
+    /// Ring size in bytes.
+    capacity: usize,
+    /// Bytes ever released by the consumer.
+    head: CachePadded<AtomicUsize>,
+    /// Bytes ever committed by the producer.
+    tail: CachePadded<AtomicUsize>,
+    producer_dropped: AtomicBool,
+    consumer_dropped: AtomicBool,
+}
+
+// SAFETY: the producer only writes bytes between `tail` and `head +
+// capacity`, which the consumer does not read, and publishes them with a
+// release store of `tail`; the consumer only reads bytes between `head` and
+// `tail` and hands them back with a release store of `head`.
+unsafe impl Sync for Shared {}
+// SAFETY: see above; the buffer holds plain bytes.
+unsafe impl Send for Shared {}
+
+impl Shared {
+    /// Returns the ring size for a requested capacity.
+    fn ring_len(capacity: usize) -> usize {
+        capacity.max(64).next_power_of_two()
+    }
+
+    fn new(buffer: Storage, capacity: usize) -> Self {
+        Self {
+            buffer,
+            capacity,
+            head: CachePadded(AtomicUsize::new(0)),
+            tail: CachePadded(AtomicUsize::new(0)),
+            producer_dropped: AtomicBool::new(false),
+            consumer_dropped: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns a pointer to byte `offset` of the ring.
+    fn at(&self, offset: usize) -> *mut u8 {
+        debug_assert!(offset < self.capacity);
+        // SAFETY: `offset` is within the buffer.
+        unsafe { self.buffer.base().add(offset) }
+    }
+
+    /// Writes a record header at `offset`.
+    ///
+    /// # Safety
+    /// The caller must own the header bytes at `offset`.
+    unsafe fn write_header(&self, offset: usize, len: u32) {
+        // SAFETY: `offset` is 8-byte aligned and owned per the caller.
+        unsafe { self.at(offset).cast::<u32>().write(len) };
+    }
+
+    /// Reads the record header at `offset`.
+    ///
+    /// # Safety
+    /// The header at `offset` must have been published to the caller.
+    unsafe fn read_header(&self, offset: usize) -> u32 {
+        // SAFETY: `offset` is 8-byte aligned and published per the caller.
+        unsafe { self.at(offset).cast::<u32>().read() }
+    }
+
+    /// Returns the ring bytes of a record size for a message of `len` bytes.
+    fn record_len(len: usize) -> usize {
+        HEADER_LEN + len.next_multiple_of(8)
+    }
+
+    /// Returns the largest message the ring accepts.
+    ///
+    /// A record skips at most the bytes left before the end of the buffer,
+    /// and only when it is longer than them, so a record of up to half the
+    /// ring always fits once the consumer has caught up. A longer one
+    /// could need more than the whole ring wherever the cursors stand.
+    fn max_message_len(&self) -> usize {
+        self.capacity / 2 - HEADER_LEN
+    }
+}
+
+/// Producer half of a byte ring.
+pub struct RingProducer {
+    shared: Arc<Shared>,
+    /// Position of the next record, ahead of `shared.tail` while a claim
+    /// is open.
+    tail: usize,
+    /// Last value read from `shared.head`.
+    cached_head: usize,
+}
+
+impl RingProducer {
+    /// Claims a slot of `len` bytes to encode a message into.
+    ///
+    /// The slot holds stale bytes from earlier messages, so the encoder
+    /// must write every byte it will publish. Nothing is visible to the
+    /// consumer until [`Claim::commit`]; dropping the claim abandons it.
+    ///
+    /// # Errors
+    /// Returns [`ClaimError::Full`] if the consumer has not freed enough
+    /// space yet, [`ClaimError::TooLarge`] if `len` exceeds
+    /// [`max_message_len`](Self::max_message_len), and
+    /// [`ClaimError::Disconnected`] if the consumer is gone.
+    pub fn claim(&mut self, len: usize) -> Result<Claim<'_>, ClaimError> {
+        let shared = &*self.shared;
+        if shared.consumer_dropped.load(Ordering::Relaxed) {
+            return Err(ClaimError::Disconnected);
+        }
+        let max = shared.max_message_len();
+        if len > max {
+            return Err(ClaimError::TooLarge { len, max });
+        }
+
+        let record = Shared::record_len(len);
+        let offset = self.tail & (shared.capacity - 1);
+        let until_end = shared.capacity - offset;
+        let skip = if record > until_end { until_end } else { 0 };
+        let needed = skip + record;
+        if self.free() < needed {
+            self.cached_head = shared.head.load(Ordering::Acquire);
+            if self.free() < needed {
+                return Err(ClaimError::Full);
+            }
+        }
+
+        let start = if skip > 0 {
+            // SAFETY: the skipped bytes are free and owned by the producer.
+            unsafe { shared.write_header(offset, PADDING) };
+            0
+        } else {
+            offset
+        };
+        Ok(Claim {
+            producer: self,
+            start,
+            skip,
+            len,
+        })
+    }
+
+    /// Copies `message` into the ring as one record.
+    ///
+    /// # Errors
+    /// Same as [`claim`](Self::claim).
+    pub fn write(&mut self, message: &[u8]) -> Result<(), ClaimError> {
+        let mut claim = self.claim(message.len())?;
+        claim.copy_from_slice(message);
+        claim.commit();
+        Ok(())
+    }
+
+    /// Returns true if the consumer is still connected.
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        !self.shared.consumer_dropped.load(Ordering::Relaxed)
+    }
+
+    /// Returns the ring size in bytes.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    /// Returns the largest message the ring accepts, half the ring less
+    /// the record header.
+    #[must_use]
+    pub fn max_message_len(&self) -> usize {
+        self.shared.max_message_len()
+    }
+
+    /// Returns the free bytes according to the cached consumer position.
+    fn free(&self) -> usize {
+        self.shared.capacity - (self.tail - self.cached_head)
+    }
+}
+
+impl Drop for RingProducer {
+    fn drop(&mut self) {
+        self.shared.producer_dropped.store(true, Ordering::Release);
+    }
+}
+
+/// A claimed slot, dereferencing to the message bytes.
+pub struct Claim<'a> {
+    producer: &'a mut RingProducer,
+    /// Ring offset of the record header.
+    start: usize,
+    /// Bytes skipped at the end of the buffer before the record.
+    skip: usize,
+    len: usize,
+}
+
+impl Claim<'_> {
+    /// Publishes the message to the consumer.
+    pub fn commit(self) {
+        let len = self.len;
+        self.commit_len(len);
+    }
+
+    /// Publishes only the first `len` bytes, for messages whose final
+    /// length is only known after encoding into a larger claim.
+    ///
+    /// # Panics
+    /// Panics if `len` exceeds the claimed length.
+    pub fn commit_len(self, len: usize) {
+        assert!(
+            len <= self.len,
+            "commit of {len} bytes exceeds claim of {}",
+            self.len
+        );
+        let shared = &*self.producer.shared;
+        // SAFETY: the header belongs to this claim; `len` fits in `u32`
+        // since it is below the ring size.
+        unsafe { shared.write_header(self.start, len as u32) };
+        self.producer.tail += self.skip + Shared::record_len(len);
+        shared.tail.store(self.producer.tail, Ordering::Release);
+    }
+}
+
+impl Deref for Claim<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        let data = self.producer.shared.at(self.start + HEADER_LEN);
+        // SAFETY: the claimed bytes are owned by the producer until commit.
+        unsafe { std::slice::from_raw_parts(data, self.len) }
+    }
+}
+
+impl DerefMut for Claim<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        let data = self.producer.shared.at(self.start + HEADER_LEN);
+        // SAFETY: the claimed bytes are owned by the producer until commit.
+        unsafe { std::slice::from_raw_parts_mut(data, self.len) }
+    }
+}
+
+/// Consumer half of a byte ring.
+pub struct RingConsumer {
+    shared: Arc<Shared>,
+    /// Position of the next record to read.
+    head: usize,
+    /// Last value read from `shared.tail`.
+    cached_tail: usize,
+}
+
+impl RingConsumer {
+    /// Returns a view of the next message, if one is available.
+    ///
+    /// The message's space is released when the view is dropped.
+    #[inline]
+    pub fn try_read(&mut self) -> Option<Message<'_>> {
+        let (start, len) = self.next()?;
+        Some(Message {
+            consumer: self,
+            start,
+            len,
+        })
+    }
+
+    /// Passes up to `max` available messages to `f`, releasing their space
+    /// with a single head update at the end.
+    ///
+    /// # Returns
+    /// The number of messages read.
+    pub fn read_batch(&mut self, max: usize, mut f: impl FnMut(&[u8])) -> usize {
+        let mut count = 0;
+        while count < max {
+            let Some((start, len)) = self.next() else {
+                break;
+            };
+            let data = self.shared.at(start + HEADER_LEN);
+            // SAFETY: the record is published and not yet released.
+            f(unsafe { std::slice::from_raw_parts(data, len) });
+            self.head += Shared::record_len(len);
+            count += 1;
+        }
+        if count > 0 {
+            self.shared.head.store(self.head, Ordering::Release);
+        }
+        count
+    }
+
+    /// Returns true if no message is available.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.head == self.shared.tail.load(Ordering::Acquire)
+    }
+
+    /// Returns true if the producer was dropped and every message has been
+    /// read.
+    #[must_use]
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.producer_dropped.load(Ordering::Acquire) && self.is_empty()
+    }
+
+    /// Returns the ring size in bytes.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    /// Returns the ring offset and length of the next message, skipping
+    /// padding, without releasing it.
+    fn next(&mut self) -> Option<(usize, usize)> {
+        loop {
+            if self.head == self.cached_tail {
+                self.cached_tail = self.shared.tail.load(Ordering::Acquire);
+                if self.head == self.cached_tail {
+                    return None;
+                }
+            }
+            let offset = self.head & (self.shared.capacity - 1);
+            // SAFETY: the record at `head` is below `tail`, so published.
+            let len = unsafe { self.shared.read_header(offset) };
+            if len == PADDING {
+                self.head += self.shared.capacity - offset;
+                continue;
+            }
+            return Some((offset, len as usize));
+        }
+    }
+}
+
+impl Drop for RingConsumer {
+    fn drop(&mut self) {
+        self.shared.consumer_dropped.store(true, Ordering::Release);
+    }
+}
+
+/// A message read from the ring, dereferencing to its bytes.
+///
+/// Dropping it releases the message's space to the producer.
+pub struct Message<'a> {
+    consumer: &'a mut RingConsumer,
+    start: usize,
+    len: usize,
+}
+
+impl Deref for Message<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        let data = self.consumer.shared.at(self.start + HEADER_LEN);
+        // SAFETY: the record is published and not yet released.
+        unsafe { std::slice::from_raw_parts(data, self.len) }
+    }
+}
+
+impl Drop for Message<'_> {
+    fn drop(&mut self) {
+        let consumer = &mut *self.consumer;
+        consumer.head += Shared::record_len(self.len);
+        consumer.shared.head.store(consumer.head, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironsbe_core::memory::HugePages;
+
+    #[test]
+    fn test_claim_commit_read() {
+        let (mut tx, mut rx) = channel(256);
+        assert_eq!(tx.capacity(), 256);
+        assert!(rx.try_read().is_none());
+
+        let mut claim = tx.claim(5).unwrap();
+        claim.copy_from_slice(b"hello");
+        // Not visible before commit.
+        assert!(rx.is_empty());
+        claim.commit();
+
+        let message = rx.try_read().unwrap();
+        assert_eq!(&*message, b"hello");
+        assert_eq!(message.as_ptr() as usize % 8, 0);
+        drop(message);
+        assert!(rx.try_read().is_none());
+    }
+
+    #[test]
+    fn test_abandoned_claim_and_commit_len() {
+        let (mut tx, mut rx) = channel(256);
+        tx.claim(16).unwrap()[..3].copy_from_slice(b"bad");
+        assert!(rx.try_read().is_none());
+
+        let mut claim = tx.claim(64).unwrap();
+        claim[..4].copy_from_slice(b"good");
+        claim.commit_len(4);
+        assert_eq!(&*rx.try_read().unwrap(), b"good");
+    }
+
+    #[test]
+    fn test_full_and_too_large() {
+        let (mut tx, mut rx) = channel(64);
+        assert_eq!(
+            tx.claim(25).err(),
+            Some(ClaimError::TooLarge { len: 25, max: 24 })
+        );
+
+        // Two 32-byte records fill the 64-byte ring.
+        tx.write(&[1; 24]).unwrap();
+        tx.write(&[2; 24]).unwrap();
+        assert_eq!(tx.claim(1).err(), Some(ClaimError::Full));
+
+        assert_eq!(&*rx.try_read().unwrap(), &[1; 24]);
+        tx.write(&[3; 24]).unwrap();
+    }
+
+    #[test]
+    fn test_largest_message_fits_empty_ring_at_any_offset() {
+        let (mut tx, mut rx) = channel(64);
+        let max = tx.max_message_len();
+        // A 56-byte record after an 8-byte message would need the 48
+        // bytes up to the end plus 64 more, so it must be rejected rather
+        // than report Full forever.
+        tx.write(&[0; 8]).unwrap();
+        drop(rx.try_read().unwrap());
+        assert_eq!(
+            tx.claim(56).err(),
+            Some(ClaimError::TooLarge { len: 56, max })
+        );
+
+        for offset in 0..8 {
+            let (mut tx, mut rx) = channel(64);
+            // Each empty message moves the cursors on by one 8-byte header.
+            for _ in 0..offset {
+                tx.write(&[]).unwrap();
+                drop(rx.try_read().unwrap());
+            }
+            tx.write(&vec![1; max]).unwrap();
+            assert_eq!(&*rx.try_read().unwrap(), &vec![1; max][..]);
+        }
+    }
+
+    #[test]
+    fn test_records_wrap_without_splitting() {
+        let (mut tx, mut rx) = channel(128);
+        let mut seen = Vec::new();
+        for round in 0u8..50 {
+            let len = 1 + usize::from(round) % 30;
+            tx.write(&vec![round; len]).unwrap();
+            let message = rx.try_read().unwrap();
+            assert_eq!(message.len(), len);
+            assert!(message.iter().all(|&b| b == round));
+            seen.push(round);
+        }
+        assert_eq!(seen.len(), 50);
+    }
+
+    #[test]
+    fn test_ring_with_memory() {
+        let config = MemoryConfig::new()
+            .numa_node(0)
+            .huge_pages(HugePages::Explicit)
+            .prefault(true);
+        let (mut tx, mut rx) = channel_with_memory(100, &config).unwrap();
+        assert_eq!(tx.capacity(), 128);
+        for round in 0u8..20 {
+            tx.write(&[round; 40]).unwrap();
+            assert_eq!(&*rx.try_read().unwrap(), &[round; 40]);
+        }
+
+        let strict = MemoryConfig::new().numa_node(1000).strict(true);
+        assert!(channel_with_memory(100, &strict).is_err());
+    }
+
+    #[test]
+    fn test_read_batch() {
+        let (mut tx, mut rx) = channel(1024);
+        for i in 0u8..10 {
+            tx.write(&[i; 3]).unwrap();
+        }
+        let mut firsts = Vec::new();
+        assert_eq!(rx.read_batch(4, |m| firsts.push(m[0])), 4);
+        assert_eq!(rx.read_batch(usize::MAX, |m| firsts.push(m[0])), 6);
+        assert_eq!(firsts, (0..10).collect::<Vec<_>>());
+        assert_eq!(rx.read_batch(usize::MAX, |_| {}), 0);
+    }
+
+    #[test]
+    fn test_disconnect() {
+        let (mut tx, rx) = channel(64);
+        tx.write(b"x").unwrap();
+        drop(tx);
+        assert!(!rx.is_disconnected());
+
+        let (mut tx, rx) = channel(64);
+        drop(rx);
+        assert!(!tx.is_connected());
+        assert_eq!(tx.claim(1).err(), Some(ClaimError::Disconnected));
+    }
+
+    #[test]
+    fn test_threaded_transfer() {
+        let (mut tx, mut rx) = channel(4096);
+        let producer = std::thread::spawn(move || {
+            for i in 0u32..100_000 {
+                let len = 4 + (i % 60) as usize;
+                loop {
+                    match tx.claim(len) {
+                        Ok(mut claim) => {
+                            claim[..4].copy_from_slice(&i.to_le_bytes());
+                            claim[4..].fill(i as u8);
+                            claim.commit();
+                            break;
+                        }
+                        Err(ClaimError::Full) => std::hint::spin_loop(),
+                        Err(err) => panic!("{err}"),
+                    }
+                }
+            }
+        });
+
+        let mut expected = 0u32;
+        while expected < 100_000 {
+            rx.read_batch(64, |message| {
+                let i = u32::from_le_bytes(message[..4].try_into().unwrap());
+                assert_eq!(i, expected);
+                assert_eq!(message.len(), 4 + (i % 60) as usize);
+                assert!(message[4..].iter().all(|&b| b == i as u8));
+                expected += 1;
+            });
+        }
+        producer.join().unwrap();
+        assert!(rx.is_disconnected());
+    }
+}
@@ -0,0 +1,189 @@
+//! Template-based routing onto dedicated consumer channels.
+//!
+//! A [`Router`] maps SBE template IDs to MPSC senders, plus an optional
+//! default, so one inbound stream can be split by message type — market
+//! data, execution reports and admin traffic each land on their own
+//! consumer thread without per-message dispatch code.
+
+use crate::mpsc::MpscSender;
+use crossbeam_channel::TrySendError;
+use std::collections::HashMap;
+
+/// Error returned when a routed item could not be delivered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteError<T> {
+    /// No route matches the template and there is no default route.
+    Unrouted(T),
+    /// The destination channel is full.
+    Full(T),
+    /// The destination channel's receiver was dropped.
+    Disconnected(T),
+}
+
+impl<T> RouteError<T> {
+    /// Returns the undelivered item.
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Unrouted(item) | Self::Full(item) | Self::Disconnected(item) => item,
+        }
+    }
+}
+
+impl<T> std::fmt::Display for RouteError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unrouted(_) => write!(f, "no route for template"),
+            Self::Full(_) => write!(f, "route channel full"),
+            Self::Disconnected(_) => write!(f, "route channel disconnected"),
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::error::Error for RouteError<T> {}
+
+/// Routing table from template IDs to destination channels.
+///
+/// Delivery never blocks: a full destination is reported as
+/// [`RouteError::Full`] so a slow consumer cannot stall the I/O thread
+/// feeding the router.
+pub struct Router<T> {
+    routes: HashMap<u16, MpscSender<T>>,
+    default_route: Option<MpscSender<T>>,
+}
+
+impl<T> Router<T> {
+    /// Creates a router with no routes.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            routes: HashMap::new(),
+            default_route: None,
+        }
+    }
+
+    /// Routes a template ID to `sender`, replacing any previous route.
+    #[must_use]
+    pub fn route(mut self, template_id: u16, sender: MpscSender<T>) -> Self {
+        self.routes.insert(template_id, sender);
+        self
+    }
+
+    /// Routes every template ID in `template_ids` to `sender`.
+    #[must_use]
+    pub fn routes(
+        mut self,
+        template_ids: impl IntoIterator<Item = u16>,
+        sender: &MpscSender<T>,
+    ) -> Self {
+        for template_id in template_ids {
+            self.routes.insert(template_id, sender.clone());
+        }
+        self
+    }
+
+    /// Sets the destination for templates without a route.
+    ///
+    /// Items whose template is unknown (e.g. too short to carry a header)
+    /// also go here.
+    #[must_use]
+    pub fn default_route(mut self, sender: MpscSender<T>) -> Self {
+        self.default_route = Some(sender);
+        self
+    }
+
+    /// Returns the destination for `template_id`, if any.
+    #[must_use]
+    pub fn destination(&self, template_id: Option<u16>) -> Option<&MpscSender<T>> {
+        template_id
+            .and_then(|id| self.routes.get(&id))
+            .or(self.default_route.as_ref())
+    }
+
+    /// Delivers `item` to the destination for `template_id` without
+    /// blocking.
+    ///
+    /// # Errors
+    /// Returns the item if there is no matching route, or the destination
+    /// is full or disconnected.
+    pub fn dispatch(&self, template_id: Option<u16>, item: T) -> Result<(), RouteError<T>> {
+        let Some(sender) = self.destination(template_id) else {
+            return Err(RouteError::Unrouted(item));
+        };
+        sender.try_send(item).map_err(|e| match e {
+            TrySendError::Full(item) => RouteError::Full(item),
+            TrySendError::Disconnected(item) => RouteError::Disconnected(item),
+        })
+    }
+}
+
+impl<T> Default for Router<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for Router<T> {
+    fn clone(&self) -> Self {
+        Self {
+            routes: self.routes.clone(),
+            default_route: self.default_route.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mpsc::MpscChannel;
+
+    #[test]
+    fn test_router_splits_by_template() {
+        let (md_tx, md_rx) = MpscChannel::bounded(8);
+        let (exec_tx, exec_rx) = MpscChannel::bounded(8);
+        let router = Router::new().routes([1, 2], &md_tx).route(10, exec_tx);
+
+        router.dispatch(Some(1), "md1").unwrap();
+        router.dispatch(Some(10), "exec").unwrap();
+        router.dispatch(Some(2), "md2").unwrap();
+
+        assert_eq!(md_rx.drain().collect::<Vec<_>>(), vec!["md1", "md2"]);
+        assert_eq!(exec_rx.drain().collect::<Vec<_>>(), vec!["exec"]);
+    }
+
+    #[test]
+    fn test_router_default_route() {
+        let (md_tx, md_rx) = MpscChannel::bounded(8);
+        let (admin_tx, admin_rx) = MpscChannel::bounded(8);
+        let router = Router::new().route(1, md_tx).default_route(admin_tx);
+
+        router.dispatch(Some(99), "admin").unwrap();
+        router.dispatch(None, "short").unwrap();
+
+        assert!(md_rx.is_empty());
+        assert_eq!(admin_rx.drain().collect::<Vec<_>>(), vec!["admin", "short"]);
+    }
+
+    #[test]
+    fn test_router_unrouted() {
+        let (md_tx, _md_rx) = MpscChannel::bounded(8);
+        let router = Router::new().route(1, md_tx);
+
+        assert_eq!(router.dispatch(Some(2), 7), Err(RouteError::Unrouted(7)));
+        assert_eq!(router.dispatch(None, 8), Err(RouteError::Unrouted(8)));
+    }
+
+    #[test]
+    fn test_router_full_and_disconnected() {
+        let (tx, rx) = MpscChannel::bounded(1);
+        let router = Router::new().route(1, tx);
+
+        router.dispatch(Some(1), 1).unwrap();
+        assert_eq!(router.dispatch(Some(1), 2), Err(RouteError::Full(2)));
+
+        drop(rx);
+        let err = router.dispatch(Some(1), 3).unwrap_err();
+        assert_eq!(err.to_string(), "route channel disconnected");
+        assert_eq!(err.into_inner(), 3);
+    }
+}
@@ -0,0 +1,556 @@
+//! Waiting on several receivers at once.
+//!
+//! An event loop that merges market data, order events and control
+//! commands would otherwise have to poll every channel in turn. [`Select`]
+//! and the [`select!`](crate::select!) macro wait on any number of SPSC,
+//! MPSC and broadcast receivers and return the first message to arrive:
+//!
+//! ```ignore
+//! use ironsbe_channel::select;
+//!
+//! loop {
+//!     select! {
+//!         recv(control) -> command => handle_command(command),
+//!         recv(orders) -> event => handle_order(event),
+//!         recv(market_data) -> (seq, update) => book.apply(seq, update),
+//!         timeout(Duration::from_secs(1)) => send_heartbeat(),
+//!         disconnected => break,
+//!     }
+//! }
+//! ```
+//!
+//! The thread sleeps while nothing is ready and senders wake it, whatever
+//! the receivers' [`WaitStrategy`](crate::WaitStrategy).
+//!
+//! When several receivers are ready at once, [`Fairness::RoundRobin`] (the
+//! default) starts each select at the next receiver so none is starved,
+//! and [`Fairness::Biased`] always takes the first ready receiver in
+//! declaration order, e.g. to drain control commands before data.
+
+use crate::broadcast::BroadcastReceiver;
+use crate::mpsc::MpscReceiver;
+use crate::spsc::SpscReceiver;
+use std::cell::Cell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::Thread;
+use std::time::{Duration, Instant};
+
+/// Spin rounds before a select that keeps being woken while it polls
+/// yields the thread.
+const SPIN_ROUNDS: u32 = 64;
+
+/// A receiver that [`Select`] can wait on.
+pub trait Selectable {
+    /// Item received.
+    type Item;
+
+    /// Receives an item if one is ready, otherwise registers `cx`'s waker
+    /// to be woken when one arrives.
+    ///
+    /// # Returns
+    /// `Ready(None)` once the channel is disconnected and drained.
+    fn poll_select(&mut self, cx: &mut Context<'_>) -> Poll<Option<Self::Item>>;
+}
+
+impl<T> Selectable for SpscReceiver<T> {
+    type Item = T;
+
+    fn poll_select(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        if let Poll::Ready(item) = self.poll_item() {
+            return Poll::Ready(item);
+        }
+        let waiter = Arc::clone(self.waiter());
+        waiter.poll_item(cx, || self.poll_item())
+    }
+}
+
+impl<T> Selectable for MpscReceiver<T> {
+    type Item = T;
+
+    fn poll_select(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let receiver = &*self;
+        receiver.waiter().poll_item(cx, || receiver.poll_item())
+    }
+}
+
+impl<T: Clone> Selectable for BroadcastReceiver<T> {
+    /// Sequence number and item; messages evicted before they were read are
+    /// skipped.
+    type Item = (u64, T);
+
+    fn poll_select(&mut self, cx: &mut Context<'_>) -> Poll<Option<(u64, T)>> {
+        self.poll_recv(cx)
+    }
+}
+
+/// Order in which a select checks its receivers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Fairness {
+    /// Start at the receiver after the one last selected, so a busy
+    /// receiver cannot starve the others.
+    #[default]
+    RoundRobin,
+    /// Always check the receivers in the order they were added.
+    Biased,
+}
+
+/// Error returned when no receiver was selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectError {
+    /// Nothing was ready (from [`Select::try_select`]).
+    Empty,
+    /// The timeout elapsed.
+    Timeout,
+    /// Every receiver is disconnected and drained.
+    Disconnected,
+}
+
+impl std::fmt::Display for SelectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no receiver ready"),
+            Self::Timeout => write!(f, "operation timed out"),
+            Self::Disconnected => write!(f, "all channels disconnected"),
+        }
+    }
+}
+
+impl std::error::Error for SelectError {}
+
+/// Polls one receiver, storing an item in its slot and returning
+/// `Ready(true)`, or `Ready(false)` once it is disconnected.
+type PollArm<'a> = Box<dyn FnMut(&mut Context<'_>) -> Poll<bool> + 'a>;
+
+/// One receiver of a [`Select`].
+struct Arm<'a> {
+    poll: PollArm<'a>,
+    closed: bool,
+}
+
+/// Waits on several receivers and receives from the first one ready.
+///
+/// Each receiver is added with a slot; the select returns the index of the
+/// receiver it received from, in the order they were added, and leaves the
+/// item in that receiver's slot:
+///
+/// ```ignore
+/// let (mut command, mut update) = (None, None);
+/// match Select::new()
+///     .fairness(Fairness::Biased)
+///     .recv(&mut control, &mut command)
+///     .recv(&mut market_data, &mut update)
+///     .select()
+/// {
+///     Ok(0) => handle_command(command.take().unwrap()),
+///     Ok(_) => apply(update.take().unwrap()),
+///     Err(_) => return, // every channel disconnected
+/// }
+/// ```
+///
+/// The [`select!`](crate::select!) macro writes this for you.
+pub struct Select<'a> {
+    arms: Vec<Arm<'a>>,
+    fairness: Fairness,
+    next: usize,
+}
+
+impl Default for Select<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Select<'a> {
+    /// Creates an empty select with [`Fairness::RoundRobin`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            arms: Vec::new(),
+            fairness: Fairness::default(),
+            next: next_start(),
+        }
+    }
+
+    /// Sets the order in which receivers are checked.
+    #[must_use]
+    pub fn fairness(mut self, fairness: Fairness) -> Self {
+        self.fairness = fairness;
+        self
+    }
+
+    /// Adds a receiver; when it is selected, the item is stored in `slot`.
+    #[must_use]
+    pub fn recv<S: Selectable>(
+        mut self,
+        receiver: &'a mut S,
+        slot: &'a mut Option<S::Item>,
+    ) -> Self {
+        self.arms.push(Arm {
+            poll: Box::new(move |cx| match receiver.poll_select(cx) {
+                Poll::Ready(Some(item)) => {
+                    *slot = Some(item);
+                    Poll::Ready(true)
+                }
+                Poll::Ready(None) => Poll::Ready(false),
+                Poll::Pending => Poll::Pending,
+            }),
+            closed: false,
+        });
+        self
+    }
+
+    /// Receives from the first ready receiver without waiting.
+    ///
+    /// # Errors
+    /// Returns [`SelectError::Empty`] if none is ready, or
+    /// [`SelectError::Disconnected`] if all are disconnected.
+    pub fn try_select(&mut self) -> Result<usize, SelectError> {
+        match self.poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(result) => result,
+            Poll::Pending => Err(SelectError::Empty),
+        }
+    }
+
+    /// Waits until a receiver is ready and receives from it.
+    ///
+    /// # Errors
+    /// Returns [`SelectError::Disconnected`] if all receivers are
+    /// disconnected.
+    pub fn select(&mut self) -> Result<usize, SelectError> {
+        self.wait(None)
+    }
+
+    /// Waits at most `timeout` for a receiver to be ready.
+    ///
+    /// # Errors
+    /// Returns [`SelectError::Timeout`] if none was ready in time, or
+    /// [`SelectError::Disconnected`] if all are disconnected.
+    pub fn select_timeout(&mut self, timeout: Duration) -> Result<usize, SelectError> {
+        self.wait(Some(Instant::now() + timeout))
+    }
+
+    /// Waits until `deadline` for a receiver to be ready.
+    ///
+    /// # Errors
+    /// Returns [`SelectError::Timeout`] if none was ready in time, or
+    /// [`SelectError::Disconnected`] if all are disconnected.
+    pub fn select_deadline(&mut self, deadline: Instant) -> Result<usize, SelectError> {
+        self.wait(Some(deadline))
+    }
+
+    fn wait(&mut self, deadline: Option<Instant>) -> Result<usize, SelectError> {
+        let parker = PARKER.with(Arc::clone);
+        let waker = Waker::from(Arc::clone(&parker));
+        let mut cx = Context::from_waker(&waker);
+        let mut spins = 0u32;
+        loop {
+            parker.notified.store(false, Ordering::SeqCst);
+            if let Poll::Ready(result) = self.poll(&mut cx) {
+                return result;
+            }
+            if parker.notified.load(Ordering::SeqCst) {
+                // An item arrived while polling.
+                if spins < SPIN_ROUNDS {
+                    spins += 1;
+                    std::hint::spin_loop();
+                } else {
+                    std::thread::yield_now();
+                }
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return Err(SelectError::Timeout);
+                }
+                continue;
+            }
+            while !parker.notified.load(Ordering::SeqCst) {
+                match deadline {
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        if now >= deadline {
+                            return Err(SelectError::Timeout);
+                        }
+                        std::thread::park_timeout(deadline - now);
+                    }
+                    None => std::thread::park(),
+                }
+            }
+        }
+    }
+
+    /// Polls every open receiver once, in fairness order.
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Result<usize, SelectError>> {
+        let len = self.arms.len();
+        let start = match self.fairness {
+            Fairness::RoundRobin if len > 0 => self.next % len,
+            _ => 0,
+        };
+        let mut open = false;
+        for index in (start..len).chain(0..start) {
+            let arm = &mut self.arms[index];
+            if arm.closed {
+                continue;
+            }
+            match (arm.poll)(cx) {
+                Poll::Ready(true) => {
+                    self.next = index + 1;
+                    return Poll::Ready(Ok(index));
+                }
+                Poll::Ready(false) => arm.closed = true,
+                Poll::Pending => open = true,
+            }
+        }
+        if open {
+            Poll::Pending
+        } else {
+            Poll::Ready(Err(SelectError::Disconnected))
+        }
+    }
+}
+
+/// Wakes a thread blocked in [`Select`].
+struct Parker {
+    thread: Thread,
+    notified: AtomicBool,
+}
+
+impl Wake for Parker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.notified.store(true, Ordering::SeqCst);
+        self.thread.unpark();
+    }
+}
+
+thread_local! {
+    static PARKER: Arc<Parker> = Arc::new(Parker {
+        thread: std::thread::current(),
+        notified: AtomicBool::new(false),
+    });
+    /// Rotates the first receiver of successive selects on this thread.
+    static START: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Returns the receiver a new round-robin select starts at, so that
+/// successive `select!`s on a thread take turns.
+fn next_start() -> usize {
+    START.with(|start| {
+        let next = start.get();
+        start.set(next.wrapping_add(1));
+        next
+    })
+}
+
+/// Waits on several receivers and runs the arm of the first one ready.
+///
+/// ```ignore
+/// select! {
+///     recv(control) -> command => handle_command(command),
+///     recv(market_data) -> (seq, update) => book.apply(seq, update),
+///     timeout(Duration::from_millis(100)) => idle(),
+///     disconnected => return,
+/// }
+/// ```
+///
+/// Each `recv(receiver) -> pattern => body` arm names a receiver
+/// implementing [`Selectable`]; the pattern binds the received item. After
+/// the `recv` arms, an optional `timeout(duration)` arm runs if nothing
+/// arrived in time, and an optional `disconnected` arm runs once every
+/// receiver is disconnected; without it, `select!` panics in that case.
+/// Arms are separated by commas. Bodies run in the enclosing function, so
+/// they can `break`, `continue` or `return`.
+///
+/// Receivers are checked round-robin; start with `biased;` to check them in
+/// the order written instead.
+#[macro_export]
+macro_rules! select {
+    (@arms $sel:ident; recv($rx:expr) -> $bind:pat => $body:expr $(, $($rest:tt)*)?) => {{
+        let mut slot = ::core::option::Option::None;
+        let step = {
+            let $sel = $sel.recv(&mut $rx, &mut slot);
+            $crate::select!(@arms $sel; $($($rest)*)?)
+        };
+        #[allow(unreachable_code, clippy::diverging_sub_expression)]
+        let step = match slot {
+            ::core::option::Option::Some($bind) => $crate::select::__Step::Done($body),
+            ::core::option::Option::None => step,
+        };
+        step
+    }};
+    (@arms $sel:ident; timeout($timeout:expr) => $body:expr $(, $($rest:tt)*)?) => {{
+        let result = {
+            let mut sel = $sel;
+            sel.select_timeout($timeout)
+        };
+        #[allow(unreachable_code, clippy::diverging_sub_expression)]
+        let step = match result {
+            ::core::result::Result::Ok(_) => $crate::select::__Step::Selected,
+            ::core::result::Result::Err($crate::select::SelectError::Timeout) => {
+                $crate::select::__Step::Done($body)
+            }
+            ::core::result::Result::Err(_) => $crate::select!(@closed $($($rest)*)?),
+        };
+        step
+    }};
+    (@arms $sel:ident; $($rest:tt)*) => {{
+        let result = {
+            let mut sel = $sel;
+            sel.select()
+        };
+        #[allow(unreachable_code, clippy::diverging_sub_expression)]
+        let step = match result {
+            ::core::result::Result::Ok(_) => $crate::select::__Step::Selected,
+            ::core::result::Result::Err(_) => $crate::select!(@closed $($rest)*),
+        };
+        step
+    }};
+    (@closed disconnected => $closed:expr $(,)?) => {
+        $crate::select::__Step::Done($closed)
+    };
+    (@closed) => {
+        ::core::panic!("select!: all channels disconnected")
+    };
+    (@start $fairness:expr; $($arms:tt)*) => {{
+        let sel = $crate::select::Select::new().fairness($fairness);
+        match $crate::select!(@arms sel; $($arms)*) {
+            $crate::select::__Step::Done(value) => value,
+            $crate::select::__Step::Selected => {
+                ::core::unreachable!("select!: the selected receiver left no item")
+            }
+        }
+    }};
+    (biased; $($arms:tt)*) => {
+        $crate::select!(@start $crate::select::Fairness::Biased; $($arms)*)
+    };
+    ($($arms:tt)*) => {
+        $crate::select!(@start $crate::select::Fairness::RoundRobin; $($arms)*)
+    };
+}
+
+/// Outcome of one level of a [`select!`](crate::select!) expansion.
+#[doc(hidden)]
+pub enum __Step<T> {
+    /// An arm ran and produced this value.
+    Done(T),
+    /// A receiver was selected; an enclosing level holds its item.
+    Selected,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WaitStrategy;
+    use crate::broadcast;
+    use crate::mpsc::{self, MpscChannel};
+    use crate::spsc;
+
+    #[test]
+    fn test_select_receives_from_ready_receiver() {
+        let (mut tx, mut a) = spsc::channel_with_wait::<u32>(8, WaitStrategy::Park).unwrap();
+        let (b_tx, mut b) = MpscChannel::bounded_with_wait::<&str>(8, WaitStrategy::Park).unwrap();
+
+        b_tx.send("order").unwrap();
+        let (mut x, mut y) = (None, None);
+        let index = Select::new()
+            .recv(&mut a, &mut x)
+            .recv(&mut b, &mut y)
+            .select();
+        assert_eq!(index, Ok(1));
+        assert_eq!((x, y), (None, Some("order")));
+
+        tx.send(7).unwrap();
+        let (mut x, mut y) = (None, None);
+        let mut select = Select::new().recv(&mut a, &mut x).recv(&mut b, &mut y);
+        assert_eq!(select.try_select(), Ok(0));
+        assert_eq!(select.try_select(), Err(SelectError::Empty));
+        drop(select);
+        assert_eq!(x, Some(7));
+    }
+
+    #[test]
+    fn test_select_wakes_on_send() {
+        let (tx, mut rx) = mpsc::channel_with_wait::<u64>(8, WaitStrategy::Park).unwrap();
+        let bus = broadcast::channel::<u64>(8);
+        let mut sub = bus.subscribe();
+
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            bus.send(5);
+            std::thread::sleep(Duration::from_millis(20));
+            tx.send(9).unwrap();
+        });
+
+        let mut received = Vec::new();
+        while received.len() < 2 {
+            select! {
+                recv(sub) -> (seq, item) => received.push((seq, item)),
+                recv(rx) -> item => received.push((99, item)),
+            }
+        }
+        sender.join().unwrap();
+        assert_eq!(received, vec![(0, 5), (99, 9)]);
+    }
+
+    #[test]
+    fn test_select_timeout_and_disconnect() {
+        let (tx, mut rx) = mpsc::channel_with_wait::<u64>(8, WaitStrategy::Park).unwrap();
+        let start = Instant::now();
+        let timed_out = select! {
+            recv(rx) -> _item => false,
+            timeout(Duration::from_millis(30)) => true,
+        };
+        assert!(timed_out);
+        assert!(start.elapsed() >= Duration::from_millis(30));
+
+        tx.send(1).unwrap();
+        drop(tx);
+        let mut items = Vec::new();
+        loop {
+            select! {
+                recv(rx) -> item => items.push(item),
+                timeout(Duration::from_secs(5)) => panic!("timed out"),
+                disconnected => break,
+            }
+        }
+        assert_eq!(items, vec![1]);
+    }
+
+    #[test]
+    fn test_select_spinning_receiver() {
+        let (mut tx, mut rx) = spsc::channel::<u64>(8);
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            tx.send(3).unwrap();
+        });
+        let item = select! {
+            recv(rx) -> item => item,
+            timeout(Duration::from_secs(5)) => 0,
+        };
+        sender.join().unwrap();
+        assert_eq!(item, 3);
+    }
+
+    #[test]
+    fn test_fairness() {
+        let (a_tx, mut a) = mpsc::channel_with_wait::<u8>(64, WaitStrategy::Park).unwrap();
+        let (b_tx, mut b) = mpsc::channel_with_wait::<u8>(64, WaitStrategy::Park).unwrap();
+        for _ in 0..10 {
+            a_tx.send(0).unwrap();
+            b_tx.send(1).unwrap();
+        }
+
+        let mut order = Vec::new();
+        for _ in 0..4 {
+            order.push(select! { biased; recv(a) -> x => x, recv(b) -> x => x });
+        }
+        assert_eq!(order, vec![0, 0, 0, 0]);
+
+        let (mut x, mut y) = (None, None);
+        let mut select = Select::new().recv(&mut a, &mut x).recv(&mut b, &mut y);
+        let picks: Vec<_> = (0..4).map(|_| select.select().unwrap()).collect();
+        assert!(picks.windows(2).all(|w| w[0] != w[1]), "{picks:?}");
+    }
+}
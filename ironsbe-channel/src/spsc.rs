@@ -3,9 +3,12 @@
 //! This module provides a lock-free ring buffer based channel optimized
 //! for single-producer single-consumer scenarios with ~10-20ns latency.
 
+use crate::wait::{self, WaitStrategy, Waiter};
 use rtrb::{Consumer, Producer, RingBuffer};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::Poll;
+use std::time::{Duration, Instant};
 
 /// Creates a new SPSC channel pair with the given capacity.
 ///
@@ -19,6 +22,22 @@ pub fn channel<T>(capacity: usize) -> (SpscSender<T>, SpscReceiver<T>) {
     SpscChannel::new(capacity)
 }
 
+/// Creates a new SPSC channel pair whose receiver waits with `strategy`.
+///
+/// # Arguments
+/// * `capacity` - Maximum number of items the channel can hold
+/// * `strategy` - How [`SpscReceiver::recv_wait`] waits for items
+///
+/// # Errors
+/// Returns an error if the wakeup descriptor of
+/// [`WaitStrategy::EventFd`] cannot be created.
+pub fn channel_with_wait<T>(
+    capacity: usize,
+    strategy: WaitStrategy,
+) -> std::io::Result<(SpscSender<T>, SpscReceiver<T>)> {
+    SpscChannel::with_wait(capacity, strategy)
+}
+
 /// SPSC channel factory.
 pub struct SpscChannel;
 
@@ -30,15 +49,43 @@ impl SpscChannel {
     #[allow(clippy::new_ret_no_self)]
     #[must_use]
     pub fn new<T>(capacity: usize) -> (SpscSender<T>, SpscReceiver<T>) {
+        Self::with_waiter(capacity, Waiter::busy_spin())
+    }
+
+    /// Creates a new SPSC channel pair whose receiver waits with `strategy`.
+    ///
+    /// # Arguments
+    /// * `capacity` - Maximum number of items the channel can hold
+    /// * `strategy` - How [`SpscReceiver::recv_wait`] waits for items
+    ///
+    /// # Errors
+    /// Returns an error if the wakeup descriptor of
+    /// [`WaitStrategy::EventFd`] cannot be created.
+    pub fn with_wait<T>(
+        capacity: usize,
+        strategy: WaitStrategy,
+    ) -> std::io::Result<(SpscSender<T>, SpscReceiver<T>)> {
+        Ok(Self::with_waiter(capacity, Waiter::new(strategy)?))
+    }
+
+    fn with_waiter<T>(capacity: usize, waiter: Waiter) -> (SpscSender<T>, SpscReceiver<T>) {
         let (producer, consumer) = RingBuffer::new(capacity);
         let closed = Arc::new(AtomicBool::new(false));
+        let arm = waiter.needs_arm();
+        let waiter = Arc::new(waiter);
 
         (
             SpscSender {
                 producer,
                 closed: Arc::clone(&closed),
+                waiter: Arc::clone(&waiter),
+            },
+            SpscReceiver {
+                consumer,
+                closed,
+                waiter,
+                arm,
             },
-            SpscReceiver { consumer, closed },
         )
     }
 }
@@ -49,6 +96,7 @@ impl SpscChannel {
 pub struct SpscSender<T> {
     producer: Producer<T>,
     closed: Arc<AtomicBool>,
+    waiter: Arc<Waiter>,
 }
 
 impl<T> SpscSender<T> {
@@ -66,9 +114,13 @@ impl<T> SpscSender<T> {
         if self.closed.load(Ordering::Relaxed) {
             return Err(item);
         }
-        self.producer.push(item).map_err(|e| match e {
-            rtrb::PushError::Full(item) => item,
-        })
+        match self.producer.push(item) {
+            Ok(()) => {
+                self.waiter.notify();
+                Ok(())
+            }
+            Err(rtrb::PushError::Full(item)) => Err(item),
+        }
     }
 
     /// Tries to send an item, returning immediately.
@@ -83,6 +135,34 @@ impl<T> SpscSender<T> {
         self.send(item)
     }
 
+    /// Sends as many leading items of `items` as fit, publishing them with
+    /// a single tail update.
+    ///
+    /// # Arguments
+    /// * `items` - Items to send, in order
+    ///
+    /// # Returns
+    /// The number of items sent, which is less than `items.len()` if the
+    /// channel filled up and 0 if the receiver is gone.
+    #[inline]
+    pub fn send_batch(&mut self, items: &[T]) -> usize
+    where
+        T: Clone,
+    {
+        if self.closed.load(Ordering::Relaxed) {
+            return 0;
+        }
+        let n = items.len().min(self.producer.slots());
+        let sent = match self.producer.write_chunk_uninit(n) {
+            Ok(chunk) => chunk.fill_from_iter(items[..n].iter().cloned()),
+            Err(_) => 0,
+        };
+        if sent > 0 {
+            self.waiter.notify();
+        }
+        sent
+    }
+
     /// Checks if the receiver is still connected.
     #[inline(always)]
     #[must_use]
@@ -115,6 +195,7 @@ impl<T> SpscSender<T> {
 impl<T> Drop for SpscSender<T> {
     fn drop(&mut self) {
         self.closed.store(true, Ordering::Release);
+        self.waiter.notify();
     }
 }
 
@@ -124,6 +205,9 @@ impl<T> Drop for SpscSender<T> {
 pub struct SpscReceiver<T> {
     consumer: Consumer<T>,
     closed: Arc<AtomicBool>,
+    waiter: Arc<Waiter>,
+    /// Whether an empty receive must arm the wakeup descriptor.
+    arm: bool,
 }
 
 impl<T> SpscReceiver<T> {
@@ -135,7 +219,11 @@ impl<T> SpscReceiver<T> {
     /// `Some(item)` if available, `None` if channel is empty.
     #[inline(always)]
     pub fn recv(&mut self) -> Option<T> {
-        self.consumer.pop().ok()
+        match self.consumer.pop() {
+            Ok(item) => Some(item),
+            Err(_) if self.arm && self.waiter.arm() => self.consumer.pop().ok(),
+            Err(_) => None,
+        }
     }
 
     /// Tries to receive an item, returning immediately.
@@ -182,13 +270,127 @@ impl<T> SpscReceiver<T> {
         None
     }
 
+    /// Busy-poll receive until a deadline.
+    ///
+    /// Spins like [`recv_spin`](Self::recv_spin) but gives up once
+    /// `deadline` has passed.
+    ///
+    /// # Arguments
+    /// * `deadline` - Instant after which to give up
+    ///
+    /// # Returns
+    /// `Some(item)` if received before the deadline, `None` otherwise.
+    #[inline]
+    pub fn recv_deadline(&mut self, deadline: Instant) -> Option<T> {
+        loop {
+            if let Ok(item) = self.consumer.pop() {
+                return Some(item);
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            std::hint::spin_loop();
+        }
+    }
+
+    /// Blocking receive using the channel's [`WaitStrategy`].
+    ///
+    /// # Returns
+    /// `Some(item)` if received, `None` if the sender is gone and the
+    /// channel is empty.
+    pub fn recv_wait(&mut self) -> Option<T> {
+        let waiter = Arc::clone(&self.waiter);
+        waiter.wait(None, || self.poll_item())
+    }
+
+    /// Receive with timeout using the channel's [`WaitStrategy`].
+    ///
+    /// # Arguments
+    /// * `timeout` - Maximum time to wait
+    ///
+    /// # Returns
+    /// `Some(item)` if received within timeout, `None` otherwise.
+    pub fn recv_wait_timeout(&mut self, timeout: Duration) -> Option<T> {
+        let waiter = Arc::clone(&self.waiter);
+        waiter.wait(wait::deadline(timeout), || self.poll_item())
+    }
+
+    /// Returns the channel's wait strategy.
+    #[must_use]
+    pub fn wait_strategy(&self) -> WaitStrategy {
+        self.waiter.strategy()
+    }
+
+    /// Returns the descriptor of a [`WaitStrategy::EventFd`] channel, which
+    /// is readable while items may be pending.
+    ///
+    /// Register it with an event loop and, once it is readable, receive
+    /// until [`recv`](Self::recv), [`try_recv`](Self::try_recv) or
+    /// [`recv_batch`](Self::recv_batch) finds the channel empty; that empty
+    /// receive re-arms the descriptor. Returns `None` for other strategies.
+    /// Only available on Unix, like the strategy.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn wait_fd(&self) -> Option<std::os::fd::BorrowedFd<'_>> {
+        self.waiter.fd()
+    }
+
+    /// Polls for an item, reporting disconnection once drained.
+    pub(crate) fn poll_item(&mut self) -> Poll<Option<T>> {
+        if let Some(item) = self.recv() {
+            return Poll::Ready(Some(item));
+        }
+        if self.closed.load(Ordering::Acquire) {
+            // The sender may have pushed just before disconnecting.
+            return Poll::Ready(self.consumer.pop().ok());
+        }
+        Poll::Pending
+    }
+
+    /// Returns the channel's wait state.
+    pub(crate) fn waiter(&self) -> &Arc<Waiter> {
+        &self.waiter
+    }
+
+    /// Receives up to `max` available items into `out`, releasing their
+    /// slots with a single head update.
+    ///
+    /// # Arguments
+    /// * `out` - Vector the items are appended to
+    /// * `max` - Maximum number of items to receive
+    ///
+    /// # Returns
+    /// The number of items received, 0 if the channel is empty.
+    #[inline]
+    pub fn recv_batch(&mut self, out: &mut Vec<T>, max: usize) -> usize {
+        let mut n = max.min(self.consumer.slots());
+        if n == 0 && max > 0 && self.arm && self.waiter.arm() {
+            n = max.min(self.consumer.slots());
+        }
+        match self.consumer.read_chunk(n) {
+            Ok(chunk) => {
+                out.extend(chunk);
+                n
+            }
+            Err(_) => 0,
+        }
+    }
+
     /// Drains all available items from the channel.
     ///
+    /// Items are read as one batch, so the head is updated once when the
+    /// iterator is dropped; items sent meanwhile are left for the next
+    /// call.
+    ///
     /// # Returns
     /// An iterator over all currently available items.
     #[inline]
     pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
-        std::iter::from_fn(|| self.consumer.pop().ok())
+        let n = self.consumer.slots();
+        self.consumer
+            .read_chunk(n)
+            .into_iter()
+            .flat_map(IntoIterator::into_iter)
     }
 
     /// Checks if the sender is still connected.
@@ -264,6 +466,18 @@ mod tests {
         assert!(tx.send(100).is_ok());
     }
 
+    #[test]
+    fn test_recv_deadline() {
+        let (mut tx, mut rx) = channel::<u64>(16);
+
+        let deadline = Instant::now() + std::time::Duration::from_millis(5);
+        assert_eq!(rx.recv_deadline(deadline), None);
+        assert!(Instant::now() >= deadline);
+
+        tx.send(4).unwrap();
+        assert_eq!(rx.recv_deadline(Instant::now()), Some(4));
+    }
+
     #[test]
     fn test_drain() {
         let (mut tx, mut rx) = channel::<u64>(16);
@@ -277,6 +491,39 @@ mod tests {
         assert!(rx.is_empty());
     }
 
+    #[test]
+    fn test_send_recv_batch() {
+        let (mut tx, mut rx) = channel::<u64>(4);
+
+        assert_eq!(tx.send_batch(&[1, 2, 3, 4, 5, 6]), 4);
+        assert_eq!(tx.send_batch(&[7]), 0);
+
+        let mut out = vec![0];
+        assert_eq!(rx.recv_batch(&mut out, 3), 3);
+        assert_eq!(out, vec![0, 1, 2, 3]);
+
+        assert_eq!(tx.send_batch(&[5, 6]), 2);
+        out.clear();
+        assert_eq!(rx.recv_batch(&mut out, 16), 3);
+        assert_eq!(out, vec![4, 5, 6]);
+        assert_eq!(rx.recv_batch(&mut out, 16), 0);
+
+        drop(rx);
+        assert_eq!(tx.send_batch(&[1]), 0);
+    }
+
+    #[test]
+    fn test_drain_stops_at_snapshot() {
+        let (mut tx, mut rx) = channel::<u64>(16);
+        tx.send_batch(&[1, 2, 3]);
+
+        let mut drain = rx.drain();
+        assert_eq!(drain.next(), Some(1));
+        tx.send(4).unwrap();
+        assert_eq!(drain.collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(rx.recv(), Some(4));
+    }
+
     #[test]
     fn test_disconnect_detection() {
         let (tx, rx) = channel::<u64>(16);
@@ -0,0 +1,568 @@
+//! Receiver wait strategies.
+//!
+//! A [`WaitStrategy`] decides what a receiver does between attempts while
+//! its channel is empty: burn a core for the lowest wakeup latency, yield
+//! to other threads, sleep until a sender wakes it, or wait on a file
+//! descriptor that an event loop (epoll, mio, tokio's `AsyncFd`) can watch
+//! alongside sockets. It is chosen per channel at construction, e.g. with
+//! [`spsc::channel_with_wait`](crate::spsc::channel_with_wait), and used by
+//! the receivers' `recv_wait` methods.
+//!
+//! Senders only pay for a wakeup when the receiver has announced that it
+//! is about to sleep, or an async task is waiting; otherwise a send adds a
+//! fence and a load. Async receivers and senders are always woken, with
+//! any strategy, so an awaiting task never spins the executor.
+
+use parking_lot::{Condvar, Mutex};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering, fence};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// How a receiver waits for items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaitStrategy {
+    /// Spins with a pause hint between attempts. Lowest latency, but keeps
+    /// a core fully busy.
+    #[default]
+    BusySpin,
+    /// Spins for `spins` attempts, then yields the thread between attempts.
+    SpinThenYield {
+        /// Attempts to spin before yielding.
+        spins: u32,
+    },
+    /// Sleeps until a sender wakes the receiver.
+    Park,
+    /// Waits on a file descriptor that becomes readable when items arrive:
+    /// an eventfd on Linux, a pipe on other Unix systems. The descriptor
+    /// is available from the receiver's `wait_fd` for use in an event loop.
+    ///
+    /// Only available on Unix (`cfg(unix)`); elsewhere use [`Park`](Self::Park)
+    /// to sleep until items arrive.
+    #[cfg(unix)]
+    EventFd,
+}
+
+/// Per-channel wait state shared by its senders and receiver.
+pub(crate) struct Waiter {
+    strategy: WaitStrategy,
+    /// Set by a sleeping receiver; a sender that clears it must wake it.
+    armed: AtomicBool,
+    lock: Mutex<()>,
+    condvar: Condvar,
+    #[cfg(unix)]
+    fd: Option<WakeFd>,
+    /// Task of an async receiver waiting for items.
+    task: Mutex<Option<Waker>>,
+    /// Set by async senders waiting for space; the receiver that clears it
+    /// must wake them.
+    space_armed: AtomicBool,
+    space_tasks: Mutex<Vec<Waker>>,
+}
+
+impl Waiter {
+    /// Creates the wait state for `strategy`.
+    ///
+    /// # Errors
+    /// Returns an error if the wakeup descriptor of
+    /// [`WaitStrategy::EventFd`] cannot be created.
+    pub(crate) fn new(strategy: WaitStrategy) -> std::io::Result<Self> {
+        Ok(Self {
+            strategy,
+            armed: AtomicBool::new(false),
+            lock: Mutex::new(()),
+            condvar: Condvar::new(),
+            #[cfg(unix)]
+            fd: match strategy {
+                WaitStrategy::EventFd => Some(WakeFd::new()?),
+                _ => None,
+            },
+            task: Mutex::new(None),
+            space_armed: AtomicBool::new(false),
+            space_tasks: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Creates the wait state for [`WaitStrategy::BusySpin`].
+    pub(crate) fn busy_spin() -> Self {
+        Self::new(WaitStrategy::BusySpin).expect("busy spin needs no resources")
+    }
+
+    /// Returns the strategy.
+    pub(crate) fn strategy(&self) -> WaitStrategy {
+        self.strategy
+    }
+
+    /// Wakes the receiver if it is sleeping or an async task waits for
+    /// items. Senders call this after publishing an item and when they
+    /// disconnect.
+    #[inline]
+    pub(crate) fn notify(&self) {
+        // Orders the publish before the load, pairing with the fence in
+        // the receiver between arming and re-checking the channel.
+        fence(Ordering::SeqCst);
+        if !self.armed.load(Ordering::Relaxed) {
+            return;
+        }
+        self.wake();
+    }
+
+    #[cold]
+    fn wake(&self) {
+        if !self.armed.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        match self.strategy {
+            WaitStrategy::Park => {
+                let _guard = self.lock.lock();
+                self.condvar.notify_one();
+            }
+            #[cfg(unix)]
+            WaitStrategy::EventFd => {
+                if let Some(fd) = &self.fd {
+                    fd.signal();
+                }
+            }
+            _ => {}
+        }
+        if let Some(task) = self.task.lock().take() {
+            task.wake();
+        }
+    }
+
+    /// Wakes async senders waiting for space. Receivers call this after
+    /// taking items.
+    #[inline]
+    pub(crate) fn notify_space(&self) {
+        fence(Ordering::SeqCst);
+        if self.space_armed.load(Ordering::Relaxed)
+            && self.space_armed.swap(false, Ordering::Relaxed)
+        {
+            for task in self.space_tasks.lock().drain(..) {
+                task.wake();
+            }
+        }
+    }
+
+    /// Polls for an item from an async receiver, registering the task to
+    /// be woken by the next send when `poll` finds the channel empty,
+    /// whatever the strategy.
+    pub(crate) fn poll_item<T>(
+        &self,
+        cx: &mut Context<'_>,
+        mut poll: impl FnMut() -> Poll<Option<T>>,
+    ) -> Poll<Option<T>> {
+        if let Poll::Ready(item) = poll() {
+            return Poll::Ready(item);
+        }
+        register(&mut self.task.lock(), cx.waker());
+        self.armed.store(true, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        poll()
+    }
+
+    /// Polls an async sender's attempt to use space, registering the task
+    /// to be woken by the next receive while `poll` finds the channel full.
+    #[cfg(feature = "futures")]
+    pub(crate) fn poll_space<R>(
+        &self,
+        cx: &mut Context<'_>,
+        mut poll: impl FnMut() -> Poll<R>,
+    ) -> Poll<R> {
+        if let Poll::Ready(result) = poll() {
+            return Poll::Ready(result);
+        }
+        {
+            let mut tasks = self.space_tasks.lock();
+            if !tasks.iter().any(|task| task.will_wake(cx.waker())) {
+                tasks.push(cx.waker().clone());
+            }
+        }
+        self.space_armed.store(true, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        poll()
+    }
+
+    /// Arms the wakeup descriptor after the receiver found the channel
+    /// empty, so that the next send makes it readable.
+    ///
+    /// # Returns
+    /// True if it was newly armed, in which case the caller must check the
+    /// channel again before waiting.
+    #[cold]
+    pub(crate) fn arm(&self) -> bool {
+        #[cfg(unix)]
+        if let Some(fd) = &self.fd
+            && !self.armed.load(Ordering::Relaxed)
+        {
+            fd.drain();
+            self.armed.store(true, Ordering::Relaxed);
+            fence(Ordering::SeqCst);
+            return true;
+        }
+        false
+    }
+
+    /// Waits until `poll` yields an item or reports disconnection, or until
+    /// `deadline` passes.
+    ///
+    /// `poll` returns `Ready(Some(item))` for an item, `Ready(None)` once
+    /// the channel is empty and disconnected, and `Pending` while empty.
+    pub(crate) fn wait<T>(
+        &self,
+        deadline: Option<Instant>,
+        mut poll: impl FnMut() -> Poll<Option<T>>,
+    ) -> Option<T> {
+        let mut spins = 0u32;
+        loop {
+            if let Poll::Ready(item) = poll() {
+                return item;
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return None;
+            }
+            match self.strategy {
+                WaitStrategy::BusySpin => std::hint::spin_loop(),
+                WaitStrategy::SpinThenYield { spins: limit } => {
+                    if spins < limit {
+                        spins += 1;
+                        std::hint::spin_loop();
+                    } else {
+                        std::thread::yield_now();
+                    }
+                }
+                WaitStrategy::Park => {
+                    let mut guard = self.lock.lock();
+                    self.armed.store(true, Ordering::Relaxed);
+                    fence(Ordering::SeqCst);
+                    if let Poll::Ready(item) = poll() {
+                        self.armed.store(false, Ordering::Relaxed);
+                        return item;
+                    }
+                    match deadline {
+                        Some(deadline) => {
+                            self.condvar.wait_until(&mut guard, deadline);
+                        }
+                        None => self.condvar.wait(&mut guard),
+                    }
+                    self.armed.store(false, Ordering::Relaxed);
+                }
+                #[cfg(unix)]
+                WaitStrategy::EventFd => {
+                    if let Some(fd) = &self.fd {
+                        // The receiver arms on every empty poll, so a send
+                        // after the poll above makes the descriptor readable.
+                        fd.wait(deadline);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns true if empty receives must [`arm`](Self::arm) the wakeup
+    /// descriptor.
+    pub(crate) fn needs_arm(&self) -> bool {
+        #[cfg(unix)]
+        return self.fd.is_some();
+        #[cfg(not(unix))]
+        false
+    }
+
+    /// Returns the wakeup descriptor of [`WaitStrategy::EventFd`].
+    #[cfg(unix)]
+    pub(crate) fn fd(&self) -> Option<std::os::fd::BorrowedFd<'_>> {
+        use std::os::fd::AsFd;
+        self.fd.as_ref().map(|fd| fd.read.as_fd())
+    }
+}
+
+/// Stores `waker` in `slot` unless it already wakes the same task.
+fn register(slot: &mut Option<Waker>, waker: &Waker) {
+    if !slot
+        .as_ref()
+        .is_some_and(|current| current.will_wake(waker))
+    {
+        *slot = Some(waker.clone());
+    }
+}
+
+/// A sender's handle on its channel's [`Waiter`], which also wakes the
+/// receiver when dropped.
+///
+/// Declared after the channel handle in a sender, it drops last, so the
+/// receiver it wakes already sees the disconnection.
+#[derive(Clone)]
+pub(crate) struct Notifier {
+    waiter: Arc<Waiter>,
+}
+
+impl Notifier {
+    /// Creates a notifier for `waiter`.
+    pub(crate) fn new(waiter: Arc<Waiter>) -> Self {
+        Self { waiter }
+    }
+
+    /// Wakes the receiver if it is asleep or awaited.
+    #[inline(always)]
+    pub(crate) fn notify(&self) {
+        self.waiter.notify();
+    }
+
+    /// Returns the channel's wait state.
+    #[cfg(feature = "futures")]
+    pub(crate) fn waiter(&self) -> &Arc<Waiter> {
+        &self.waiter
+    }
+}
+
+impl Drop for Notifier {
+    fn drop(&mut self) {
+        self.notify();
+    }
+}
+
+/// A receiver's handle on its channel's [`Waiter`], which also wakes async
+/// senders waiting for space when dropped.
+///
+/// Declared after the channel handle in a receiver, it drops last, so the
+/// senders it wakes already see the disconnection.
+pub(crate) struct SpaceNotifier(Arc<Waiter>);
+
+impl SpaceNotifier {
+    /// Creates a space notifier for `waiter`.
+    pub(crate) fn new(waiter: Arc<Waiter>) -> Self {
+        Self(waiter)
+    }
+}
+
+impl std::ops::Deref for SpaceNotifier {
+    type Target = Arc<Waiter>;
+
+    fn deref(&self) -> &Arc<Waiter> {
+        &self.0
+    }
+}
+
+impl Drop for SpaceNotifier {
+    fn drop(&mut self) {
+        self.0.notify_space();
+    }
+}
+
+/// Converts a timeout into a deadline; `None`, waiting forever, if it
+/// overflows.
+pub(crate) fn deadline(timeout: Duration) -> Option<Instant> {
+    Instant::now().checked_add(timeout)
+}
+
+/// A non-blocking descriptor that becomes readable when signalled.
+#[cfg(unix)]
+struct WakeFd {
+    read: std::os::fd::OwnedFd,
+    /// Write end of the pipe; `None` for an eventfd, which is written
+    /// through `read`.
+    write: Option<std::os::fd::OwnedFd>,
+}
+
+#[cfg(unix)]
+impl WakeFd {
+    #[cfg(target_os = "linux")]
+    fn new() -> std::io::Result<Self> {
+        use std::os::fd::FromRawFd;
+
+        // SAFETY: plain syscall; the result is checked below.
+        let fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // SAFETY: `fd` is a fresh descriptor owned by nobody else.
+        let read = unsafe { std::os::fd::OwnedFd::from_raw_fd(fd) };
+        Ok(Self { read, write: None })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn new() -> std::io::Result<Self> {
+        use std::os::fd::{AsRawFd, OwnedFd};
+
+        let (read, write) = std::io::pipe()?;
+        let (read, write) = (OwnedFd::from(read), OwnedFd::from(write));
+        for fd in [&read, &write] {
+            let fd = fd.as_raw_fd();
+            // SAFETY: `fd` is a valid descriptor owned by this function.
+            let ok = unsafe {
+                let flags = libc::fcntl(fd, libc::F_GETFL);
+                flags >= 0 && libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) >= 0
+            };
+            if !ok {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(Self {
+            read,
+            write: Some(write),
+        })
+    }
+
+    /// Makes the descriptor readable.
+    fn signal(&self) {
+        use std::os::fd::AsRawFd;
+
+        let fd = self.write.as_ref().unwrap_or(&self.read).as_raw_fd();
+        let one = 1u64.to_ne_bytes();
+        // An eventfd takes an 8-byte counter; a pipe needs one byte. A full
+        // pipe or saturated counter is already readable, so errors are
+        // ignored.
+        let len = if self.write.is_some() { 1 } else { one.len() };
+        // SAFETY: `one` outlives the call and holds at least `len` bytes.
+        unsafe { libc::write(fd, one.as_ptr().cast(), len) };
+    }
+
+    /// Consumes pending signals, so the descriptor is no longer readable.
+    fn drain(&self) {
+        use std::os::fd::AsRawFd;
+
+        let mut buf = [0u8; 64];
+        // SAFETY: `buf` outlives the call; the descriptor is non-blocking.
+        while unsafe { libc::read(self.read.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) } > 0 {
+            if self.write.is_none() {
+                // One read resets an eventfd counter.
+                break;
+            }
+        }
+    }
+
+    /// Blocks until the descriptor is readable or `deadline` passes.
+    fn wait(&self, deadline: Option<Instant>) {
+        use std::os::fd::AsRawFd;
+
+        let timeout_ms = deadline.map_or(-1, |deadline| {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            // Rounds up so that a wait never returns before the deadline.
+            remaining
+                .as_nanos()
+                .div_ceil(1_000_000)
+                .min(i32::MAX as u128) as i32
+        });
+        let mut pollfd = libc::pollfd {
+            fd: self.read.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `pollfd` outlives the call. Interruptions and errors
+        // return early; the caller polls the channel again either way.
+        unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mpsc, spsc};
+    use std::time::Duration;
+
+    fn strategies() -> Vec<WaitStrategy> {
+        vec![
+            WaitStrategy::BusySpin,
+            WaitStrategy::SpinThenYield { spins: 16 },
+            WaitStrategy::Park,
+            #[cfg(unix)]
+            WaitStrategy::EventFd,
+        ]
+    }
+
+    #[test]
+    fn test_spsc_recv_wait() {
+        for strategy in strategies() {
+            let (mut tx, mut rx) = spsc::channel_with_wait::<u64>(16, strategy).unwrap();
+            assert_eq!(rx.wait_strategy(), strategy);
+            assert_eq!(rx.recv_wait_timeout(Duration::from_millis(5)), None);
+
+            let producer = std::thread::spawn(move || {
+                for i in 0..1000 {
+                    if i % 100 == 0 {
+                        std::thread::sleep(Duration::from_millis(1));
+                    }
+                    while tx.send(i).is_err() {
+                        std::thread::yield_now();
+                    }
+                }
+            });
+            for i in 0..1000 {
+                assert_eq!(rx.recv_wait(), Some(i), "{strategy:?}");
+            }
+            producer.join().unwrap();
+            // The dropped sender wakes the receiver and ends the wait.
+            assert_eq!(rx.recv_wait(), None, "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn test_mpsc_recv_wait() {
+        for strategy in strategies() {
+            let (tx, rx) = mpsc::channel_with_wait::<u64>(16, strategy).unwrap();
+            let producers: Vec<_> = (0..2)
+                .map(|p| {
+                    let tx = tx.clone();
+                    std::thread::spawn(move || {
+                        for i in 0..500 {
+                            tx.send(p * 1000 + i).unwrap();
+                        }
+                    })
+                })
+                .collect();
+            drop(tx);
+
+            let mut received = 0;
+            while rx.recv_wait().is_some() {
+                received += 1;
+            }
+            assert_eq!(received, 1000, "{strategy:?}");
+            for producer in producers {
+                producer.join().unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_park_wakes_on_disconnect() {
+        let (tx, mut rx) = spsc::channel_with_wait::<u64>(4, WaitStrategy::Park).unwrap();
+        let dropper = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            drop(tx);
+        });
+        assert_eq!(rx.recv_wait(), None);
+        dropper.join().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_event_fd_readiness() {
+        use std::os::fd::AsRawFd;
+
+        fn readable(fd: std::os::fd::BorrowedFd<'_>) -> bool {
+            let mut pollfd = libc::pollfd {
+                fd: fd.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: `pollfd` outlives the call.
+            unsafe { libc::poll(&mut pollfd, 1, 0) == 1 }
+        }
+
+        let (mut tx, mut rx) = spsc::channel_with_wait::<u64>(4, WaitStrategy::EventFd).unwrap();
+        // Arms the descriptor, as an event loop does before sleeping.
+        assert_eq!(rx.try_recv(), None);
+        assert!(!readable(rx.wait_fd().unwrap()));
+
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert!(readable(rx.wait_fd().unwrap()));
+        assert_eq!(rx.try_recv(), Some(1));
+        assert_eq!(rx.try_recv(), Some(2));
+        assert_eq!(rx.try_recv(), None);
+        assert!(!readable(rx.wait_fd().unwrap()));
+
+        let (_tx, rx) = spsc::channel::<u64>(4);
+        assert!(rx.wait_fd().is_none());
+    }
+}
@@ -0,0 +1,139 @@
+//! Blocking client on std threads (feature `tcp-std`).
+//!
+//! [`BlockingClient`] talks to an IronSBE server over plain `std::net`
+//! sockets, for applications that do not run an async runtime. It speaks
+//! the same framing as the Tokio client, so it works against any IronSBE
+//! server.
+
+use crate::error::ClientError;
+use ironsbe_transport::TransportError;
+use ironsbe_transport::tcp_std::BlockingTcpConnection;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Client whose sends and receives block the calling thread.
+pub struct BlockingClient {
+    conn: BlockingTcpConnection,
+}
+
+impl BlockingClient {
+    /// Connects to the server at `addr`.
+    ///
+    /// # Errors
+    /// Returns error if the connection fails or times out.
+    pub fn connect(addr: SocketAddr) -> Result<Self, ClientError> {
+        BlockingTcpConnection::connect(addr)
+            .map(Self::from_connection)
+            .map_err(client_error)
+    }
+
+    /// Connects to the server at `addr`, giving up after `timeout`.
+    ///
+    /// # Errors
+    /// Returns error if the connection fails or times out.
+    pub fn connect_timeout(addr: SocketAddr, timeout: Duration) -> Result<Self, ClientError> {
+        BlockingTcpConnection::connect_timeout(addr, timeout)
+            .map(Self::from_connection)
+            .map_err(client_error)
+    }
+
+    /// Wraps an established connection.
+    #[must_use]
+    pub fn from_connection(conn: BlockingTcpConnection) -> Self {
+        Self { conn }
+    }
+
+    /// Returns the server address.
+    #[must_use]
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.conn.peer_addr()
+    }
+
+    /// Sends an SBE message to the server.
+    ///
+    /// # Errors
+    /// Returns error if the message is too large or the write fails.
+    pub fn send(&mut self, message: &[u8]) -> Result<(), ClientError> {
+        self.conn.send(message).map_err(client_error)
+    }
+
+    /// Blocks until the server sends a message.
+    ///
+    /// # Errors
+    /// Returns [`ClientError::ConnectionClosed`] if the server closed the
+    /// connection, or another error if the read fails.
+    pub fn recv(&mut self) -> Result<Vec<u8>, ClientError> {
+        self.conn.recv().map_err(client_error)
+    }
+
+    /// Waits up to `timeout` for a message from the server.
+    ///
+    /// Returns `Ok(None)` if none arrived in time.
+    ///
+    /// # Errors
+    /// Same as [`recv`](Self::recv).
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, ClientError> {
+        self.conn.recv_timeout(timeout).map_err(client_error)
+    }
+
+    /// Closes the connection.
+    pub fn disconnect(self) {
+        let _ = self.conn.shutdown();
+    }
+}
+
+/// Maps transport errors onto the client's own variants where they exist.
+fn client_error(e: TransportError) -> ClientError {
+    match e {
+        TransportError::ConnectTimeout => ClientError::ConnectTimeout,
+        TransportError::ConnectionClosed => ClientError::ConnectionClosed,
+        other => ClientError::Transport(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironsbe_transport::tcp_std::BlockingTcpListener;
+    use std::thread;
+
+    #[test]
+    fn test_blocking_client_round_trip() {
+        let listener = BlockingTcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let mut conn = listener.accept().unwrap();
+            let request = conn.recv().unwrap();
+            conn.send(&request).unwrap();
+        });
+
+        let mut client = BlockingClient::connect(addr).unwrap();
+        assert_eq!(client.peer_addr(), addr);
+        client.send(b"ping").unwrap();
+        assert_eq!(client.recv().unwrap(), b"ping");
+        server.join().unwrap();
+
+        assert!(matches!(client.recv(), Err(ClientError::ConnectionClosed)));
+        assert!(
+            client
+                .recv_timeout(Duration::from_millis(10))
+                .is_err_and(|e| matches!(e, ClientError::ConnectionClosed))
+        );
+    }
+
+    #[test]
+    fn test_blocking_client_recv_timeout() {
+        let listener = BlockingTcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || listener.accept().unwrap());
+
+        let mut client = BlockingClient::connect(addr).unwrap();
+        let _conn = server.join().unwrap();
+        assert!(
+            client
+                .recv_timeout(Duration::from_millis(10))
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -3,12 +3,18 @@
 use crate::error::ClientError;
 use crate::reconnect::{ReconnectConfig, ReconnectState};
 use crate::session::ClientSession;
+use ironsbe_channel::expiry::{ExpiryPolicy, Stamped};
+use ironsbe_channel::priority::{PriorityChannel, PriorityReceiver, PrioritySender};
+use ironsbe_channel::router::Router;
 use ironsbe_channel::spsc;
-use ironsbe_transport::traits::Transport;
+use ironsbe_core::header::MessageHeader;
+use ironsbe_session::{Session as FixpSession, SessionConfig, SessionEvent, TerminationCode};
+use ironsbe_transport::traits::{Connection, Transport};
+use std::collections::VecDeque;
 use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::Notify;
 
 /// Builder for configuring and creating a client.
@@ -25,6 +31,8 @@ pub struct ClientBuilder<T: Transport = ironsbe_transport::DefaultTransport> {
     connect_timeout: Duration,
     reconnect_config: ReconnectConfig,
     channel_capacity: usize,
+    expiry_policy: ExpiryPolicy,
+    fixp: Option<SessionConfig>,
     _transport: PhantomData<T>,
 }
 
@@ -39,6 +47,8 @@ pub struct ClientBuilder<T: Transport> {
     connect_timeout: Duration,
     reconnect_config: ReconnectConfig,
     channel_capacity: usize,
+    expiry_policy: ExpiryPolicy,
+    fixp: Option<SessionConfig>,
     _transport: PhantomData<T>,
 }
 
@@ -52,6 +62,8 @@ impl<T: Transport> ClientBuilder<T> {
             connect_timeout: Duration::from_secs(5),
             reconnect_config: ReconnectConfig::default(),
             channel_capacity: 4096,
+            fixp: None,
+            expiry_policy: ExpiryPolicy::default(),
             _transport: PhantomData,
         }
     }
@@ -103,16 +115,47 @@ impl<T: Transport> ClientBuilder<T> {
     }
 
     /// Sets the channel capacity.
+    ///
+    /// Applies to the event channel and to each lane of the command
+    /// channel: urgent sends and disconnects have their own lane and are
+    /// handled before queued regular sends.
     #[must_use]
     pub fn channel_capacity(mut self, capacity: usize) -> Self {
         self.channel_capacity = capacity;
         self
     }
 
+    /// Sets the expiry policy for the send queue.
+    ///
+    /// Messages that wait in the send queue longer than the TTL for their
+    /// template ID (e.g. behind a stalled socket) are dropped instead of
+    /// sent and reported as [`ClientEvent::MessageExpired`]. By default
+    /// nothing expires.
+    #[must_use]
+    pub fn expiry_policy(mut self, policy: ExpiryPolicy) -> Self {
+        self.expiry_policy = policy;
+        self
+    }
+
+    /// Runs the FIXP session layer over the connection.
+    ///
+    /// The client negotiates a logical session once and establishes it on
+    /// every connection, holding back messages until it is established.
+    /// Sent messages are sequenced and kept for retransmission, received
+    /// ones are deduplicated, and gaps are recovered, including across
+    /// reconnects. Session events are reported as [`ClientEvent::Fixp`];
+    /// [`ClientHandle::finish_session`] ends the logical session.
+    #[must_use]
+    pub fn fixp(mut self, config: SessionConfig) -> Self {
+        self.fixp = Some(config);
+        self
+    }
+
     /// Builds the client and handle.
     #[must_use]
     pub fn build(self) -> (Client<T>, ClientHandle) {
-        let (cmd_tx, cmd_rx) = spsc::channel(self.channel_capacity);
+        let (cmd_tx, cmd_rx) =
+            PriorityChannel::bounded(self.channel_capacity, self.channel_capacity);
         let (event_tx, event_rx) = spsc::channel(self.channel_capacity);
 
         let cmd_notify = Arc::new(Notify::new());
@@ -126,6 +169,9 @@ impl<T: Transport> ClientBuilder<T> {
             ),
             connect_timeout: self.connect_timeout,
             reconnect_state: ReconnectState::new(self.reconnect_config),
+            expiry_policy: self.expiry_policy,
+            fixp: self.fixp.map(FixpSession::client),
+            held: VecDeque::new(),
             cmd_rx,
             event_tx,
             cmd_notify: Arc::clone(&cmd_notify),
@@ -189,6 +235,42 @@ impl ClientBuilder {
         self.connect_config = Some(cfg.connect_timeout(timeout));
         self
     }
+
+    /// Connects over TLS (Tokio TCP backend only).
+    ///
+    /// Convenience shortcut that sets
+    /// [`TcpClientConfig::tls`](ironsbe_transport::tcp::TcpClientConfig::tls).
+    /// Reconnects share the configuration and resume the TLS session.
+    #[cfg(feature = "tls")]
+    #[must_use]
+    pub fn tls(mut self, config: ironsbe_transport::tcp::TlsClientConfig) -> Self {
+        let cfg = self
+            .connect_config
+            .take()
+            .unwrap_or_else(|| ironsbe_transport::tcp::TcpClientConfig::new(self.server_addr));
+        self.connect_config = Some(cfg.tls(config));
+        self
+    }
+
+    /// Connects to CME iLink 3 order entry (Tokio TCP backend only).
+    ///
+    /// Convenience shortcut that switches the framing to CME's compact
+    /// SOFH and runs the FIXP session layer with
+    /// [`ILink3Codec::session_config`](ironsbe_session::ILink3Codec::session_config),
+    /// so Negotiate and Establish are signed with the codec's credentials.
+    /// Keep a clone of `codec` to read the fault tolerance indicator and
+    /// reject codes from its status.
+    #[cfg(feature = "ilink3")]
+    #[must_use]
+    pub fn ilink3(mut self, codec: &ironsbe_session::ILink3Codec) -> Self {
+        let cfg = self
+            .connect_config
+            .take()
+            .unwrap_or_else(|| ironsbe_transport::tcp::TcpClientConfig::new(self.server_addr));
+        self.connect_config =
+            Some(cfg.framing(ironsbe_transport::tcp::framing::Framing::compact_sofh()));
+        self.fixp(codec.session_config())
+    }
 }
 
 /// The main client instance.
@@ -200,7 +282,12 @@ pub struct Client<T: Transport = ironsbe_transport::DefaultTransport> {
     connect_config: Option<T::ConnectConfig>,
     connect_timeout: Duration,
     reconnect_state: ReconnectState,
-    cmd_rx: spsc::SpscReceiver<ClientCommand>,
+    expiry_policy: ExpiryPolicy,
+    /// FIXP session, kept across reconnects.
+    fixp: Option<FixpSession>,
+    /// Messages waiting for the FIXP session to be established.
+    held: VecDeque<Vec<u8>>,
+    cmd_rx: PriorityReceiver<ClientCommand>,
     event_tx: spsc::SpscSender<ClientEvent>,
     cmd_notify: Arc<Notify>,
     event_notify: Arc<Notify>,
@@ -216,7 +303,12 @@ pub struct Client<T: Transport> {
     connect_config: Option<T::ConnectConfig>,
     connect_timeout: Duration,
     reconnect_state: ReconnectState,
-    cmd_rx: spsc::SpscReceiver<ClientCommand>,
+    expiry_policy: ExpiryPolicy,
+    /// FIXP session, kept across reconnects.
+    fixp: Option<FixpSession>,
+    /// Messages waiting for the FIXP session to be established.
+    held: VecDeque<Vec<u8>>,
+    cmd_rx: PriorityReceiver<ClientCommand>,
     event_tx: spsc::SpscSender<ClientEvent>,
     cmd_notify: Arc<Notify>,
     event_notify: Arc<Notify>,
@@ -280,15 +372,44 @@ impl<T: Transport> Client<T> {
         tracing::info!("Connected to {}", self.server_addr);
 
         let mut session = ClientSession::new(conn);
+        if let Some(fixp) = &mut self.fixp {
+            fixp.start(Instant::now());
+            self.flush_fixp(&mut session).await?;
+        }
 
         loop {
             tokio::select! {
                 _ = self.cmd_notify.notified() => {
-                    // Drain all available commands after notification.
-                    while let Some(cmd) = self.cmd_rx.recv() {
+                    // Drain all available commands after notification, control
+                    // lane (urgent sends, disconnect) first.
+                    while let Some(cmd) = self.cmd_rx.try_recv() {
                         match cmd {
                             ClientCommand::Send(msg) => {
-                                session.send(&msg).await?;
+                                if is_expired(&self.expiry_policy, &msg) {
+                                    tracing::warn!(age = ?msg.age(Instant::now()), "dropping expired message");
+                                    let _ = self.event_tx.send(ClientEvent::MessageExpired(msg.item));
+                                    self.event_notify.notify_one();
+                                    continue;
+                                }
+                                match &mut self.fixp {
+                                    Some(_) => {
+                                        self.held.push_back(msg.item);
+                                        if self.flush_fixp(&mut session).await? {
+                                            return Ok(());
+                                        }
+                                    }
+                                    None => session.send(&msg.item).await?,
+                                }
+                            }
+                            ClientCommand::FinishSession => {
+                                if let Some(fixp) = &mut self.fixp {
+                                    if let Err(e) = fixp.finish(Instant::now()) {
+                                        tracing::warn!(error = %e, "cannot finish FIXP session");
+                                    }
+                                    if self.flush_fixp(&mut session).await? {
+                                        return Ok(());
+                                    }
+                                }
                             }
                             ClientCommand::Disconnect => {
                                 return Ok(());
@@ -300,6 +421,15 @@ impl<T: Transport> Client<T> {
                 result = session.recv() => {
                     match result {
                         Ok(Some(msg)) => {
+                            if let Some(fixp) = &mut self.fixp {
+                                let result = fixp.on_frame(&msg, Instant::now());
+                                if self.flush_fixp(&mut session).await? {
+                                    return Ok(());
+                                }
+                                if result?.is_none() {
+                                    continue;
+                                }
+                            }
                             let _ = self.event_tx.send(ClientEvent::Message(msg.to_vec()));
                             self.event_notify.notify_one();
                         }
@@ -311,14 +441,76 @@ impl<T: Transport> Client<T> {
                         }
                     }
                 }
+
+                _ = sleep_until(self.fixp.as_ref().and_then(FixpSession::next_timeout)) => {
+                    if let Some(fixp) = &mut self.fixp {
+                        fixp.on_timer(Instant::now());
+                        if self.flush_fixp(&mut session).await? {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends held messages once the FIXP session is established, then the
+    /// frames it queued, and reports its events.
+    ///
+    /// Returns true once the logical session finished.
+    ///
+    /// # Errors
+    /// Returns an error if sending fails or the session was rejected or
+    /// terminated for any other reason.
+    async fn flush_fixp<C: Connection>(
+        &mut self,
+        session: &mut ClientSession<C>,
+    ) -> Result<bool, ClientError> {
+        let Some(fixp) = &mut self.fixp else {
+            return Ok(false);
+        };
+        while fixp.is_established()
+            && let Some(msg) = self.held.pop_front()
+        {
+            if let Err(e) = fixp.send(&msg, Instant::now()) {
+                tracing::warn!(error = %e, "dropping message after FIXP finish");
+            }
+        }
+        while let Some(frame) = fixp.poll_transmit() {
+            session.send(&frame).await?;
+        }
+        let mut ended = Ok(false);
+        while let Some(event) = fixp.poll_event() {
+            match &event {
+                SessionEvent::Terminated {
+                    code: TerminationCode::Finished,
+                } => ended = Ok(true),
+                SessionEvent::Terminated { code } => {
+                    ended = Err(ClientError::SessionTerminated(*code));
+                }
+                SessionEvent::NegotiationRejected(_) | SessionEvent::EstablishmentRejected(_) => {
+                    ended = Err(ClientError::SessionRejected(event.clone()));
+                }
+                _ => {}
             }
+            let _ = self.event_tx.send(ClientEvent::Fixp(event));
+            self.event_notify.notify_one();
         }
+        ended
+    }
+}
+
+/// Sleeps until `deadline`, or forever if there is none.
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
     }
 }
 
 /// Handle for sending messages and receiving events.
 pub struct ClientHandle {
-    cmd_tx: spsc::SpscSender<ClientCommand>,
+    cmd_tx: PrioritySender<ClientCommand>,
     event_rx: spsc::SpscReceiver<ClientEvent>,
     cmd_notify: Arc<Notify>,
     event_notify: Arc<Notify>,
@@ -331,7 +523,7 @@ impl ClientHandle {
     /// the single-threaded `LocalClient` builder so both client flavours
     /// can hand back the same handle type.
     pub(crate) fn new(
-        cmd_tx: spsc::SpscSender<ClientCommand>,
+        cmd_tx: PrioritySender<ClientCommand>,
         event_rx: spsc::SpscReceiver<ClientEvent>,
         cmd_notify: Arc<Notify>,
         event_notify: Arc<Notify>,
@@ -351,15 +543,52 @@ impl ClientHandle {
     #[inline]
     pub fn send(&mut self, message: Vec<u8>) -> Result<(), ClientError> {
         self.cmd_tx
-            .send(ClientCommand::Send(message))
+            .try_send(ClientCommand::Send(Stamped::now(message)))
+            .map_err(|_| ClientError::Channel)?;
+        self.cmd_notify.notify_one();
+        Ok(())
+    }
+
+    /// Sends an urgent SBE message to the server (non-blocking).
+    ///
+    /// The message goes on the control lane and is written before any
+    /// messages queued with [`send`](Self::send), so cancels and kill
+    /// switches are not stuck behind bulk order flow.
+    ///
+    /// # Errors
+    /// Returns error if the control lane is full or disconnected.
+    #[inline]
+    pub fn send_urgent(&mut self, message: Vec<u8>) -> Result<(), ClientError> {
+        self.cmd_tx
+            .try_send_control(ClientCommand::Send(Stamped::now(message)))
+            .map_err(|_| ClientError::Channel)?;
+        self.cmd_notify.notify_one();
+        Ok(())
+    }
+
+    /// Ends the FIXP session once the messages queued before it are sent
+    /// (see [`ClientBuilder::fixp`]).
+    ///
+    /// The client announces it finished sending; when the server confirms
+    /// it received everything, the logical session is terminated and the
+    /// client stops.
+    ///
+    /// # Errors
+    /// Returns error if the channel is full or disconnected.
+    pub fn finish_session(&mut self) -> Result<(), ClientError> {
+        self.cmd_tx
+            .try_send(ClientCommand::FinishSession)
             .map_err(|_| ClientError::Channel)?;
         self.cmd_notify.notify_one();
         Ok(())
     }
 
     /// Disconnects from the server.
+    ///
+    /// Queued on the control lane, so it takes effect before pending
+    /// regular sends are written.
     pub fn disconnect(&mut self) {
-        let _ = self.cmd_tx.send(ClientCommand::Disconnect);
+        let _ = self.cmd_tx.try_send_control(ClientCommand::Disconnect);
         self.cmd_notify.notify_one();
     }
 
@@ -380,6 +609,28 @@ impl ClientHandle {
         self.event_rx.drain()
     }
 
+    /// Drains all available events, delivering messages through `router`.
+    ///
+    /// Each [`ClientEvent::Message`] goes to the channel routed for its
+    /// template ID. Every other event, and any message the router could not
+    /// deliver (unrouted, or its channel full or disconnected), is yielded
+    /// to the caller instead.
+    pub fn drain_routed<'a>(
+        &'a mut self,
+        router: &'a Router<Vec<u8>>,
+    ) -> impl Iterator<Item = ClientEvent> + 'a {
+        self.event_rx.drain().filter_map(move |event| match event {
+            ClientEvent::Message(msg) => {
+                let template_id = MessageHeader::try_wrap(&msg, 0).map(|h| h.template_id);
+                router
+                    .dispatch(template_id, msg)
+                    .err()
+                    .map(|e| ClientEvent::Message(e.into_inner()))
+            }
+            other => Some(other),
+        })
+    }
+
     /// Asynchronously waits for the next event.
     ///
     /// Returns `Some(event)` when an event is available, or keeps waiting.
@@ -410,8 +661,10 @@ impl ClientHandle {
 /// Commands that can be sent to the client.
 #[derive(Debug)]
 pub enum ClientCommand {
-    /// Send a message to the server.
-    Send(Vec<u8>),
+    /// Send a message to the server, stamped with its enqueue time.
+    Send(Stamped<Vec<u8>>),
+    /// Finish the FIXP session, after the messages queued before it.
+    FinishSession,
     /// Disconnect from the server.
     Disconnect,
 }
@@ -425,10 +678,23 @@ pub enum ClientEvent {
     Disconnected,
     /// Received a message from the server.
     Message(Vec<u8>),
+    /// A queued message outlived its TTL and was dropped instead of sent.
+    MessageExpired(Vec<u8>),
+    /// The FIXP session changed state (see [`ClientBuilder::fixp`]).
+    Fixp(SessionEvent),
     /// An error occurred.
     Error(String),
 }
 
+/// Returns true if a queued message outlived `policy` by now.
+pub(crate) fn is_expired(policy: &ExpiryPolicy, msg: &Stamped<Vec<u8>>) -> bool {
+    policy.is_expired(
+        MessageHeader::try_wrap(&msg.item, 0).map(|h| h.template_id),
+        msg.queued_at,
+        Instant::now(),
+    )
+}
+
 #[cfg(all(test, feature = "tcp-tokio"))]
 mod tests {
     use super::*;
@@ -478,6 +744,38 @@ mod tests {
         let _ = builder;
     }
 
+    #[test]
+    fn test_client_builder_expiry_policy() {
+        let addr: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+        let policy = ExpiryPolicy::new().template_ttl(1, Duration::from_millis(5));
+        let builder = DefaultClientBuilder::new(addr).expiry_policy(policy);
+        assert!(builder.expiry_policy.is_enabled());
+    }
+
+    #[test]
+    fn test_is_expired_uses_message_template() {
+        let policy = ExpiryPolicy::new().template_ttl(7, Duration::from_millis(5));
+        let mut message = vec![0u8; MessageHeader::ENCODED_LENGTH];
+        MessageHeader::new(0, 7, 1, 1).encode(&mut message, 0);
+        let stale = Instant::now() - Duration::from_secs(1);
+
+        let mut msg = Stamped {
+            item: message,
+            queued_at: stale,
+        };
+        assert!(is_expired(&policy, &msg));
+
+        msg.queued_at = Instant::now();
+        assert!(!is_expired(&policy, &msg));
+
+        // Without a header there is no template and no default TTL.
+        let short = Stamped {
+            item: vec![1, 2, 3],
+            queued_at: stale,
+        };
+        assert!(!is_expired(&policy, &short));
+    }
+
     #[test]
     fn test_client_builder_build() {
         let addr: SocketAddr = "127.0.0.1:9000".parse().unwrap();
@@ -486,7 +784,7 @@ mod tests {
 
     #[test]
     fn test_client_command_debug() {
-        let cmd = ClientCommand::Send(vec![1, 2, 3]);
+        let cmd = ClientCommand::Send(Stamped::now(vec![1, 2, 3]));
         let debug_str = format!("{:?}", cmd);
         assert!(debug_str.contains("Send"));
 
@@ -520,10 +818,77 @@ mod tests {
         handle.disconnect();
     }
 
+    #[test]
+    fn test_client_handle_send_urgent_bypasses_backlog() {
+        let addr: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+        let (client, mut handle) = DefaultClientBuilder::new(addr).build();
+
+        handle.send(vec![1]).unwrap();
+        handle.send(vec![2]).unwrap();
+        handle.send_urgent(vec![9]).unwrap();
+        handle.disconnect();
+
+        let order: Vec<_> = client
+            .cmd_rx
+            .drain()
+            .map(|cmd| match cmd {
+                ClientCommand::Send(msg) => msg.item[0],
+                ClientCommand::FinishSession | ClientCommand::Disconnect => 0,
+            })
+            .collect();
+        assert_eq!(order, vec![9, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_client_handle_finish_session_follows_queued_sends() {
+        let addr: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+        let (client, mut handle) = DefaultClientBuilder::new(addr)
+            .fixp(SessionConfig::default())
+            .build();
+        assert!(client.fixp.is_some());
+
+        handle.send(vec![1]).unwrap();
+        handle.finish_session().unwrap();
+        let commands: Vec<_> = client.cmd_rx.drain().collect();
+        assert!(matches!(
+            commands[..],
+            [ClientCommand::Send(_), ClientCommand::FinishSession]
+        ));
+    }
+
     #[test]
     fn test_client_handle_poll() {
         let addr: SocketAddr = "127.0.0.1:9000".parse().unwrap();
         let (_client, mut handle) = DefaultClientBuilder::new(addr).build();
         assert!(handle.poll().is_none());
     }
+
+    #[test]
+    fn test_client_handle_drain_routed() {
+        let addr: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+        let (mut client, mut handle) = DefaultClientBuilder::new(addr).build();
+        let (md_tx, md_rx) = ironsbe_channel::mpsc::MpscChannel::bounded(8);
+        let router = Router::new().route(1, md_tx);
+
+        let message = |template_id| {
+            let mut buf = vec![0u8; MessageHeader::ENCODED_LENGTH];
+            MessageHeader::new(0, template_id, 1, 1).encode(&mut buf, 0);
+            buf
+        };
+        client.event_tx.send(ClientEvent::Connected).unwrap();
+        client
+            .event_tx
+            .send(ClientEvent::Message(message(1)))
+            .unwrap();
+        client
+            .event_tx
+            .send(ClientEvent::Message(message(2)))
+            .unwrap();
+
+        let rest: Vec<_> = handle.drain_routed(&router).collect();
+        assert!(matches!(rest[0], ClientEvent::Connected));
+        assert!(matches!(&rest[1], ClientEvent::Message(msg) if *msg == message(2)));
+        assert_eq!(rest.len(), 2);
+        assert_eq!(md_rx.try_recv(), Some(message(1)));
+    }
 }
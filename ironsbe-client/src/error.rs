@@ -28,4 +28,28 @@ pub enum ClientError {
     /// Channel error.
     #[error("channel error")]
     Channel,
+
+    /// FIXP session protocol error.
+    #[error("session error: {0}")]
+    Session(#[from] ironsbe_session::SessionError),
+
+    /// The server rejected the FIXP session.
+    #[error("session rejected: {0:?}")]
+    SessionRejected(ironsbe_session::SessionEvent),
+
+    /// The FIXP session was terminated.
+    #[error("session terminated: {0:?}")]
+    SessionTerminated(ironsbe_session::TerminationCode),
+}
+
+/// Error type for quote management.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum QuoteError {
+    /// No order with this client order ID is in the book.
+    #[error("unknown order: {0}")]
+    UnknownOrder(u64),
+
+    /// The order is being canceled and cannot be requoted.
+    #[error("order {0} has a cancel pending")]
+    PendingCancel(u64),
 }
@@ -5,14 +5,32 @@
 //! This crate provides:
 //! - Client builder with configuration options
 //! - Automatic reconnection logic
+//! - An opt-in FIXP session layer that survives reconnects
+//! - CME iLink 3 order entry sessions (`ilink3` feature)
 //! - Async/sync bridging for message handling
+//! - A blocking client for applications without an async runtime
+//! - Quote management: tracking, requoting and reconciling own orders
+//!
+//! The async client needs the `tokio` feature, which every async backend
+//! enables. With `default-features = false, features = ["tcp-std"]` only
+//! [`BlockingClient`] is built and Tokio is not a dependency.
 
+#[cfg(feature = "tcp-std")]
+pub mod blocking;
+#[cfg(feature = "tokio")]
 pub mod builder;
 pub mod error;
+#[cfg(feature = "tokio")]
 pub mod local_builder;
+pub mod quotes;
 pub mod reconnect;
 pub mod session;
 
+#[cfg(feature = "tcp-std")]
+pub use blocking::BlockingClient;
+#[cfg(feature = "tokio")]
 pub use builder::{Client, ClientBuilder, ClientCommand, ClientEvent, ClientHandle};
-pub use error::ClientError;
+pub use error::{ClientError, QuoteError};
+#[cfg(feature = "tokio")]
 pub use local_builder::{LocalClient, LocalClientBuilder};
+pub use quotes::QuoteBook;
@@ -8,15 +8,17 @@
 //! [`LocalClient::run`] must be polled inside a single-threaded reactor
 //! that owns a Tokio `LocalSet` (typically `tokio_uring::start`).
 
-use crate::builder::{ClientCommand, ClientEvent, ClientHandle};
+use crate::builder::{ClientCommand, ClientEvent, ClientHandle, is_expired};
 use crate::error::ClientError;
 use crate::reconnect::{ReconnectConfig, ReconnectState};
+use ironsbe_channel::expiry::ExpiryPolicy;
+use ironsbe_channel::priority::{PriorityChannel, PriorityReceiver};
 use ironsbe_channel::spsc;
 use ironsbe_transport::traits::{LocalConnection, LocalTransport};
 use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::Notify;
 
 /// Builder for [`LocalClient`].
@@ -30,6 +32,7 @@ pub struct LocalClientBuilder<T: LocalTransport> {
     connect_timeout: Duration,
     reconnect_config: ReconnectConfig,
     channel_capacity: usize,
+    expiry_policy: ExpiryPolicy,
     _transport: PhantomData<T>,
 }
 
@@ -43,6 +46,7 @@ impl<T: LocalTransport> LocalClientBuilder<T> {
             connect_timeout: Duration::from_secs(5),
             reconnect_config: ReconnectConfig::default(),
             channel_capacity: 4096,
+            expiry_policy: ExpiryPolicy::default(),
             _transport: PhantomData,
         }
     }
@@ -82,17 +86,27 @@ impl<T: LocalTransport> LocalClientBuilder<T> {
         self
     }
 
-    /// Sets the cmd/event channel capacity.
+    /// Sets the cmd/event channel capacity (per lane for the command channel).
     #[must_use]
     pub fn channel_capacity(mut self, capacity: usize) -> Self {
         self.channel_capacity = capacity;
         self
     }
 
+    /// Sets the expiry policy for the send queue.
+    ///
+    /// See [`crate::ClientBuilder::expiry_policy`].
+    #[must_use]
+    pub fn expiry_policy(mut self, policy: ExpiryPolicy) -> Self {
+        self.expiry_policy = policy;
+        self
+    }
+
     /// Builds the client and its external handle.
     #[must_use]
     pub fn build(self) -> (LocalClient<T>, ClientHandle) {
-        let (cmd_tx, cmd_rx) = spsc::channel(self.channel_capacity);
+        let (cmd_tx, cmd_rx) =
+            PriorityChannel::bounded(self.channel_capacity, self.channel_capacity);
         let (event_tx, event_rx) = spsc::channel(self.channel_capacity);
         let cmd_notify = Arc::new(Notify::new());
         let event_notify = Arc::new(Notify::new());
@@ -105,6 +119,7 @@ impl<T: LocalTransport> LocalClientBuilder<T> {
             ),
             connect_timeout: self.connect_timeout,
             reconnect_state: ReconnectState::new(self.reconnect_config),
+            expiry_policy: self.expiry_policy,
             cmd_rx,
             event_tx,
             cmd_notify: Arc::clone(&cmd_notify),
@@ -126,7 +141,8 @@ pub struct LocalClient<T: LocalTransport> {
     connect_config: Option<T::ConnectConfig>,
     connect_timeout: Duration,
     reconnect_state: ReconnectState,
-    cmd_rx: spsc::SpscReceiver<ClientCommand>,
+    expiry_policy: ExpiryPolicy,
+    cmd_rx: PriorityReceiver<ClientCommand>,
     event_tx: spsc::SpscSender<ClientEvent>,
     cmd_notify: Arc<Notify>,
     event_notify: Arc<Notify>,
@@ -180,13 +196,21 @@ impl<T: LocalTransport> LocalClient<T> {
         loop {
             tokio::select! {
                 _ = self.cmd_notify.notified() => {
-                    while let Some(cmd) = self.cmd_rx.recv() {
+                    while let Some(cmd) = self.cmd_rx.try_recv() {
                         match cmd {
                             ClientCommand::Send(msg) => {
-                                conn.send(&msg)
+                                if is_expired(&self.expiry_policy, &msg) {
+                                    tracing::warn!(age = ?msg.age(Instant::now()), "dropping expired message");
+                                    let _ = self.event_tx.send(ClientEvent::MessageExpired(msg.item));
+                                    self.event_notify.notify_one();
+                                    continue;
+                                }
+                                conn.send(&msg.item)
                                     .await
                                     .map_err(|e| ClientError::Io(std::io::Error::other(e.to_string())))?;
                             }
+                            // LocalClient has no FIXP session layer.
+                            ClientCommand::FinishSession => {}
                             ClientCommand::Disconnect => return Ok(()),
                         }
                     }
@@ -0,0 +1,676 @@
+//! Own-order tracking for quote management.
+//!
+//! A market maker keeps quotes resting on both sides of many instruments
+//! and constantly moves them. [`QuoteBook`] tracks those orders from the
+//! client's side: it hands out client order IDs, coalesces requotes into
+//! one replace per order, builds mass-cancel requests, and keeps its state
+//! in line with the venue through execution reports and order status
+//! snapshots.
+//!
+//! The book is schema-agnostic. Callers translate the requests it returns
+//! into their venue's messages, and the venue's execution reports into
+//! [`ExecutionReport`]s.
+
+use crate::error::QuoteError;
+use std::collections::{BTreeMap, HashMap};
+
+/// Side of an own order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    /// Buy order, quoting the bid.
+    Buy,
+    /// Sell order, quoting the ask.
+    Sell,
+}
+
+/// Lifecycle state of an own order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderState {
+    /// Sent, not yet acknowledged by the venue.
+    PendingNew,
+    /// Resting at the venue.
+    Live,
+    /// A replace is in flight.
+    PendingReplace,
+    /// A cancel is in flight.
+    PendingCancel,
+}
+
+/// An order the client has resting, or in flight, at the venue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnOrder {
+    /// Current client order ID; changes with every accepted replace.
+    pub cl_ord_id: u64,
+    /// Venue order ID, once acknowledged.
+    pub order_id: Option<u64>,
+    /// Instrument the order is for.
+    pub instrument_id: u64,
+    /// Order side.
+    pub side: Side,
+    /// Fixed-point limit price.
+    pub price: i64,
+    /// Quantity still open.
+    pub leaves_qty: u64,
+    /// Quantity filled so far.
+    pub cum_qty: u64,
+    /// Lifecycle state.
+    pub state: OrderState,
+}
+
+impl OwnOrder {
+    /// Returns true once the venue has acknowledged the order.
+    #[must_use]
+    pub fn is_acknowledged(&self) -> bool {
+        self.state != OrderState::PendingNew
+    }
+}
+
+/// A new order to send.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NewOrderRequest {
+    /// Client order ID allocated for the order.
+    pub cl_ord_id: u64,
+    /// Instrument to quote.
+    pub instrument_id: u64,
+    /// Order side.
+    pub side: Side,
+    /// Fixed-point limit price.
+    pub price: i64,
+    /// Order quantity.
+    pub quantity: u64,
+}
+
+/// A cancel/replace to send.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplaceRequest {
+    /// Client order ID of the order being replaced.
+    pub orig_cl_ord_id: u64,
+    /// Client order ID allocated for the replacement.
+    pub cl_ord_id: u64,
+    /// Venue order ID, if acknowledged.
+    pub order_id: Option<u64>,
+    /// Instrument of the order.
+    pub instrument_id: u64,
+    /// Order side.
+    pub side: Side,
+    /// New fixed-point limit price.
+    pub price: i64,
+    /// New open quantity.
+    pub quantity: u64,
+}
+
+/// A cancel to send.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CancelRequest {
+    /// Client order ID of the order being canceled.
+    pub orig_cl_ord_id: u64,
+    /// Client order ID allocated for the cancel.
+    pub cl_ord_id: u64,
+    /// Venue order ID, if acknowledged.
+    pub order_id: Option<u64>,
+    /// Instrument of the order.
+    pub instrument_id: u64,
+    /// Order side.
+    pub side: Side,
+}
+
+/// What an execution report says happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecKind {
+    /// The order was accepted.
+    New,
+    /// The order traded `last_qty`.
+    Trade {
+        /// Quantity of this fill.
+        last_qty: u64,
+    },
+    /// A replace was accepted; `orig_cl_ord_id` names the replaced order.
+    Replaced,
+    /// The order was canceled or expired.
+    Canceled,
+    /// The new order was rejected.
+    Rejected,
+    /// A replace or cancel was rejected; the order keeps resting.
+    CancelRejected,
+}
+
+/// The fields of a venue execution report the book needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionReport {
+    /// Client order ID the report is for.
+    pub cl_ord_id: u64,
+    /// Client order ID of the replaced or canceled order, if any.
+    pub orig_cl_ord_id: Option<u64>,
+    /// Venue order ID.
+    pub order_id: Option<u64>,
+    /// What happened.
+    pub kind: ExecKind,
+    /// Order price after the report.
+    pub price: i64,
+    /// Quantity still open after the report.
+    pub leaves_qty: u64,
+    /// Quantity filled so far.
+    pub cum_qty: u64,
+}
+
+/// One open order as listed by the venue, e.g. in an order mass status
+/// response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VenueOrder {
+    /// Client order ID.
+    pub cl_ord_id: u64,
+    /// Venue order ID.
+    pub order_id: u64,
+    /// Fixed-point limit price.
+    pub price: i64,
+    /// Quantity still open.
+    pub leaves_qty: u64,
+}
+
+/// Outcome of [`QuoteBook::reconcile`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReconcileReport {
+    /// Orders the venue lists but the book does not know; they should be
+    /// canceled.
+    pub unknown: Vec<VenueOrder>,
+    /// Orders the book thought were working that the venue no longer
+    /// lists; they were removed from the book.
+    pub missing: Vec<OwnOrder>,
+    /// Client order IDs whose price or open quantity were corrected to the
+    /// venue's.
+    pub corrected: Vec<u64>,
+}
+
+impl ReconcileReport {
+    /// Returns true if the book already matched the venue.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.unknown.is_empty() && self.missing.is_empty() && self.corrected.is_empty()
+    }
+}
+
+/// The client's own orders, per instrument and side.
+///
+/// Orders enter through [`new_order`](Self::new_order) and leave when a
+/// report cancels, rejects or completely fills them. Requotes are staged
+/// with [`requote`](Self::requote) and sent in batches by
+/// [`flush_replaces`](Self::flush_replaces): however often a quote moves
+/// between two flushes, it costs one replace, and an order with a replace
+/// already in flight keeps its staged quote until the next flush.
+#[derive(Debug)]
+pub struct QuoteBook {
+    orders: HashMap<u64, OwnOrder>,
+    /// Replacement and cancel IDs in flight, mapped to the order's current ID.
+    in_flight: HashMap<u64, u64>,
+    /// Requotes awaiting the next flush, by current client order ID.
+    staged: BTreeMap<u64, (i64, u64)>,
+    next_cl_ord_id: u64,
+}
+
+impl QuoteBook {
+    /// Creates an empty book allocating client order IDs from
+    /// `first_cl_ord_id` upwards.
+    #[must_use]
+    pub fn new(first_cl_ord_id: u64) -> Self {
+        Self {
+            orders: HashMap::new(),
+            in_flight: HashMap::new(),
+            staged: BTreeMap::new(),
+            next_cl_ord_id: first_cl_ord_id,
+        }
+    }
+
+    fn allocate(&mut self) -> u64 {
+        let id = self.next_cl_ord_id;
+        self.next_cl_ord_id += 1;
+        id
+    }
+
+    /// Records a new order and returns the request to send.
+    pub fn new_order(
+        &mut self,
+        instrument_id: u64,
+        side: Side,
+        price: i64,
+        quantity: u64,
+    ) -> NewOrderRequest {
+        let cl_ord_id = self.allocate();
+        self.orders.insert(
+            cl_ord_id,
+            OwnOrder {
+                cl_ord_id,
+                order_id: None,
+                instrument_id,
+                side,
+                price,
+                leaves_qty: quantity,
+                cum_qty: 0,
+                state: OrderState::PendingNew,
+            },
+        );
+        NewOrderRequest {
+            cl_ord_id,
+            instrument_id,
+            side,
+            price,
+            quantity,
+        }
+    }
+
+    /// Stages a new price and open quantity for an order, replacing any
+    /// quote staged for it before.
+    ///
+    /// # Errors
+    /// Returns an error if the order is unknown or being canceled.
+    pub fn requote(&mut self, cl_ord_id: u64, price: i64, quantity: u64) -> Result<(), QuoteError> {
+        let order = self
+            .orders
+            .get(&cl_ord_id)
+            .ok_or(QuoteError::UnknownOrder(cl_ord_id))?;
+        if order.state == OrderState::PendingCancel {
+            return Err(QuoteError::PendingCancel(cl_ord_id));
+        }
+        self.staged.insert(cl_ord_id, (price, quantity));
+        Ok(())
+    }
+
+    /// Returns one replace for every live order whose staged quote differs
+    /// from its current one, and marks those orders pending.
+    ///
+    /// Quotes staged for orders with a new order or replace still in
+    /// flight stay staged.
+    pub fn flush_replaces(&mut self) -> Vec<ReplaceRequest> {
+        let staged = std::mem::take(&mut self.staged);
+        let mut replaces = Vec::new();
+        for (cl_ord_id, (price, quantity)) in staged {
+            let Some(order) = self.orders.get(&cl_ord_id) else {
+                continue;
+            };
+            match order.state {
+                OrderState::Live => {}
+                OrderState::PendingNew | OrderState::PendingReplace => {
+                    self.staged.insert(cl_ord_id, (price, quantity));
+                    continue;
+                }
+                OrderState::PendingCancel => continue,
+            }
+            if order.price == price && order.leaves_qty == quantity {
+                continue;
+            }
+            let new_id = self.allocate();
+            let order = self
+                .orders
+                .get_mut(&cl_ord_id)
+                .expect("order looked up above");
+            order.state = OrderState::PendingReplace;
+            self.in_flight.insert(new_id, cl_ord_id);
+            replaces.push(ReplaceRequest {
+                orig_cl_ord_id: cl_ord_id,
+                cl_ord_id: new_id,
+                order_id: order.order_id,
+                instrument_id: order.instrument_id,
+                side: order.side,
+                price,
+                quantity,
+            });
+        }
+        replaces
+    }
+
+    /// Returns cancels for every order matching `instrument_id` and `side`
+    /// (`None` matches all), and marks them pending cancel.
+    ///
+    /// Orders already being canceled are skipped, and quotes staged for
+    /// the canceled orders are dropped.
+    pub fn mass_cancel(
+        &mut self,
+        instrument_id: Option<u64>,
+        side: Option<Side>,
+    ) -> Vec<CancelRequest> {
+        let mut targets: Vec<u64> = self
+            .orders
+            .values()
+            .filter(|order| {
+                order.state != OrderState::PendingCancel
+                    && instrument_id.is_none_or(|id| id == order.instrument_id)
+                    && side.is_none_or(|side| side == order.side)
+            })
+            .map(|order| order.cl_ord_id)
+            .collect();
+        targets.sort_unstable();
+
+        targets
+            .into_iter()
+            .map(|cl_ord_id| {
+                let cancel_id = self.allocate();
+                self.staged.remove(&cl_ord_id);
+                self.in_flight.insert(cancel_id, cl_ord_id);
+                let order = self.orders.get_mut(&cl_ord_id).expect("collected above");
+                order.state = OrderState::PendingCancel;
+                CancelRequest {
+                    orig_cl_ord_id: cl_ord_id,
+                    cl_ord_id: cancel_id,
+                    order_id: order.order_id,
+                    instrument_id: order.instrument_id,
+                    side: order.side,
+                }
+            })
+            .collect()
+    }
+
+    /// Applies an execution report.
+    ///
+    /// The report is authoritative: price and quantities are taken from
+    /// it. Returns the order if the report took it off the book (filled,
+    /// canceled or rejected).
+    ///
+    /// # Errors
+    /// Returns an error if the report is for an order the book does not
+    /// know.
+    pub fn apply(&mut self, report: &ExecutionReport) -> Result<Option<OwnOrder>, QuoteError> {
+        let current = self.resolve(report)?;
+        let order = self.orders.get_mut(&current).expect("resolved above");
+        if report.order_id.is_some() {
+            order.order_id = report.order_id;
+        }
+
+        match report.kind {
+            ExecKind::New => order.state = OrderState::Live,
+            ExecKind::Trade { .. } => {
+                if order.state == OrderState::PendingNew {
+                    order.state = OrderState::Live;
+                }
+            }
+            ExecKind::Replaced => {
+                let mut order = self.orders.remove(&current).expect("resolved above");
+                self.in_flight.remove(&report.cl_ord_id);
+                order.cl_ord_id = report.cl_ord_id;
+                order.state = OrderState::Live;
+                if let Some(staged) = self.staged.remove(&current) {
+                    self.staged.insert(report.cl_ord_id, staged);
+                }
+                self.orders.insert(report.cl_ord_id, order);
+                return Ok(self.update_quantities(report.cl_ord_id, report));
+            }
+            ExecKind::CancelRejected => {
+                self.in_flight.remove(&report.cl_ord_id);
+                order.state = OrderState::Live;
+                return Ok(None);
+            }
+            ExecKind::Canceled | ExecKind::Rejected => {
+                return Ok(self.remove(current));
+            }
+        }
+        Ok(self.update_quantities(current, report))
+    }
+
+    /// Finds the current client order ID a report refers to.
+    fn resolve(&self, report: &ExecutionReport) -> Result<u64, QuoteError> {
+        [Some(report.cl_ord_id), report.orig_cl_ord_id]
+            .into_iter()
+            .flatten()
+            .find_map(|id| {
+                self.in_flight
+                    .get(&id)
+                    .copied()
+                    .or(self.orders.contains_key(&id).then_some(id))
+            })
+            .ok_or(QuoteError::UnknownOrder(report.cl_ord_id))
+    }
+
+    /// Copies price and quantities from `report`, removing the order once
+    /// nothing is left open.
+    fn update_quantities(&mut self, cl_ord_id: u64, report: &ExecutionReport) -> Option<OwnOrder> {
+        let order = self.orders.get_mut(&cl_ord_id)?;
+        order.price = report.price;
+        order.leaves_qty = report.leaves_qty;
+        order.cum_qty = report.cum_qty;
+        if report.leaves_qty > 0 {
+            return None;
+        }
+        self.remove(cl_ord_id)
+    }
+
+    /// Takes an order off the book with its staged quote and in-flight IDs.
+    fn remove(&mut self, cl_ord_id: u64) -> Option<OwnOrder> {
+        self.staged.remove(&cl_ord_id);
+        self.in_flight.retain(|_, current| *current != cl_ord_id);
+        self.orders.remove(&cl_ord_id)
+    }
+
+    /// Reconciles the book against the venue's list of open orders, e.g.
+    /// after a reconnect.
+    ///
+    /// The venue wins: orders it no longer lists are removed, and prices
+    /// and open quantities are corrected to its values. Orders still
+    /// pending new are kept, as the venue may not have seen them yet.
+    pub fn reconcile(&mut self, open: &[VenueOrder]) -> ReconcileReport {
+        let mut report = ReconcileReport::default();
+        let mut listed = Vec::with_capacity(open.len());
+
+        for venue in open {
+            let current = self.in_flight.get(&venue.cl_ord_id).copied();
+            let Some(order) = self.orders.get_mut(&current.unwrap_or(venue.cl_ord_id)) else {
+                report.unknown.push(*venue);
+                continue;
+            };
+            listed.push(order.cl_ord_id);
+            order.order_id = Some(venue.order_id);
+            if order.state == OrderState::PendingNew {
+                order.state = OrderState::Live;
+            }
+            if (order.price, order.leaves_qty) != (venue.price, venue.leaves_qty) {
+                order.price = venue.price;
+                order.leaves_qty = venue.leaves_qty;
+                report.corrected.push(order.cl_ord_id);
+            }
+        }
+
+        let mut missing: Vec<u64> = self
+            .orders
+            .values()
+            .filter(|order| order.is_acknowledged() && !listed.contains(&order.cl_ord_id))
+            .map(|order| order.cl_ord_id)
+            .collect();
+        missing.sort_unstable();
+        for cl_ord_id in missing {
+            report.missing.extend(self.remove(cl_ord_id));
+        }
+        report
+    }
+
+    /// Returns the order with the given current client order ID.
+    #[must_use]
+    pub fn get(&self, cl_ord_id: u64) -> Option<&OwnOrder> {
+        self.orders.get(&cl_ord_id)
+    }
+
+    /// Returns the orders on one side of an instrument, best price first.
+    #[must_use]
+    pub fn orders(&self, instrument_id: u64, side: Side) -> Vec<&OwnOrder> {
+        let mut orders: Vec<_> = self
+            .orders
+            .values()
+            .filter(|order| order.instrument_id == instrument_id && order.side == side)
+            .collect();
+        match side {
+            Side::Buy => orders.sort_by_key(|order| std::cmp::Reverse(order.price)),
+            Side::Sell => orders.sort_by_key(|order| order.price),
+        }
+        orders
+    }
+
+    /// Returns the total open quantity on one side of an instrument.
+    #[must_use]
+    pub fn exposure(&self, instrument_id: u64, side: Side) -> u64 {
+        self.orders
+            .values()
+            .filter(|order| order.instrument_id == instrument_id && order.side == side)
+            .map(|order| order.leaves_qty)
+            .sum()
+    }
+
+    /// Returns the number of orders in the book.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    /// Returns true if the book holds no orders.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(cl_ord_id: u64, kind: ExecKind, price: i64, leaves_qty: u64) -> ExecutionReport {
+        ExecutionReport {
+            cl_ord_id,
+            orig_cl_ord_id: None,
+            order_id: Some(900 + cl_ord_id),
+            kind,
+            price,
+            leaves_qty,
+            cum_qty: 0,
+        }
+    }
+
+    fn live_book() -> (QuoteBook, u64, u64) {
+        let mut book = QuoteBook::new(1);
+        let bid = book.new_order(7, Side::Buy, 100, 10).cl_ord_id;
+        let ask = book.new_order(7, Side::Sell, 102, 10).cl_ord_id;
+        book.apply(&report(bid, ExecKind::New, 100, 10)).unwrap();
+        book.apply(&report(ask, ExecKind::New, 102, 10)).unwrap();
+        (book, bid, ask)
+    }
+
+    #[test]
+    fn test_requotes_coalesce_into_one_replace() {
+        let (mut book, bid, ask) = live_book();
+        book.requote(bid, 99, 10).unwrap();
+        book.requote(bid, 98, 12).unwrap();
+        // Staging the current quote is a no-op.
+        book.requote(ask, 102, 10).unwrap();
+
+        let replaces = book.flush_replaces();
+        assert_eq!(replaces.len(), 1);
+        let replace = replaces[0];
+        assert_eq!(
+            (replace.orig_cl_ord_id, replace.price, replace.quantity),
+            (bid, 98, 12)
+        );
+        assert_eq!(book.get(bid).unwrap().state, OrderState::PendingReplace);
+
+        // A quote staged while the replace is in flight waits for its ack.
+        book.requote(bid, 97, 12).unwrap();
+        assert!(book.flush_replaces().is_empty());
+
+        let mut ack = report(replace.cl_ord_id, ExecKind::Replaced, 98, 12);
+        ack.orig_cl_ord_id = Some(bid);
+        assert_eq!(book.apply(&ack).unwrap(), None);
+        assert!(book.get(bid).is_none());
+        let order = book.get(replace.cl_ord_id).unwrap();
+        assert_eq!((order.price, order.state), (98, OrderState::Live));
+
+        let next = book.flush_replaces();
+        assert_eq!(next.len(), 1);
+        assert_eq!(
+            (next[0].orig_cl_ord_id, next[0].price),
+            (replace.cl_ord_id, 97)
+        );
+    }
+
+    #[test]
+    fn test_fills_and_mass_cancel() {
+        let (mut book, bid, ask) = live_book();
+        book.new_order(8, Side::Buy, 50, 5);
+
+        let mut fill = report(bid, ExecKind::Trade { last_qty: 4 }, 100, 6);
+        fill.cum_qty = 4;
+        assert_eq!(book.apply(&fill).unwrap(), None);
+        assert_eq!(book.exposure(7, Side::Buy), 6);
+
+        let cancels = book.mass_cancel(Some(7), None);
+        assert_eq!(
+            cancels.iter().map(|c| c.orig_cl_ord_id).collect::<Vec<_>>(),
+            vec![bid, ask]
+        );
+        assert!(book.mass_cancel(Some(7), None).is_empty());
+        assert!(matches!(book.requote(bid, 1, 1), Err(QuoteError::PendingCancel(id)) if id == bid));
+
+        // A fill racing the cancel completes the bid; the ask is canceled.
+        let mut last = report(
+            cancels[0].cl_ord_id,
+            ExecKind::Trade { last_qty: 6 },
+            100,
+            0,
+        );
+        last.orig_cl_ord_id = Some(bid);
+        last.cum_qty = 10;
+        let done = book.apply(&last).unwrap().expect("fully filled");
+        assert_eq!((done.cl_ord_id, done.cum_qty), (bid, 10));
+        let canceled = book
+            .apply(&report(cancels[1].cl_ord_id, ExecKind::Canceled, 102, 0))
+            .unwrap();
+        assert_eq!(canceled.map(|order| order.cl_ord_id), Some(ask));
+        assert_eq!(book.len(), 1);
+
+        assert!(matches!(
+            book.apply(&report(999, ExecKind::New, 1, 1)),
+            Err(QuoteError::UnknownOrder(999))
+        ));
+    }
+
+    #[test]
+    fn test_reconcile_against_venue() {
+        let (mut book, bid, ask) = live_book();
+        let pending = book.new_order(7, Side::Buy, 99, 3).cl_ord_id;
+
+        let report = book.reconcile(&[
+            VenueOrder {
+                cl_ord_id: bid,
+                order_id: 901,
+                price: 100,
+                leaves_qty: 4,
+            },
+            VenueOrder {
+                cl_ord_id: 500,
+                order_id: 950,
+                price: 90,
+                leaves_qty: 1,
+            },
+        ]);
+
+        assert!(!report.is_clean());
+        assert_eq!(report.corrected, vec![bid]);
+        assert_eq!(
+            report
+                .unknown
+                .iter()
+                .map(|o| o.cl_ord_id)
+                .collect::<Vec<_>>(),
+            vec![500]
+        );
+        assert_eq!(
+            report
+                .missing
+                .iter()
+                .map(|o| o.cl_ord_id)
+                .collect::<Vec<_>>(),
+            vec![ask]
+        );
+        assert!(book.get(pending).is_some(), "pending new orders are kept");
+        assert_eq!(
+            book.orders(7, Side::Buy)
+                .iter()
+                .map(|o| o.price)
+                .collect::<Vec<_>>(),
+            vec![100, 99]
+        );
+        assert_eq!(book.exposure(7, Side::Buy), 7);
+    }
+}
@@ -0,0 +1,72 @@
+//! Generates the test schemas into `OUT_DIR` with the generator in the tree.
+
+use ironsbe_codegen::{CodegenConfig, Generator, Language, generate_from_files};
+use std::path::{Path, PathBuf};
+
+fn main() {
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").expect("OUT_DIR not set"));
+    let schemas = Path::new("schemas");
+    println!("cargo:rerun-if-changed={}", schemas.display());
+
+    for (name, config) in [
+        (
+            "change_detection",
+            CodegenConfig::new().change_detection(true),
+        ),
+        ("cross_lang", CodegenConfig::new()),
+        (
+            "extended_header",
+            CodegenConfig::new().round_trip_tests(true),
+        ),
+        ("ffi", CodegenConfig::new().ffi_shims(true)),
+        ("nested_groups", CodegenConfig::new()),
+        (
+            "no_std",
+            CodegenConfig::new().no_std(true).derive_serde(true),
+        ),
+        ("presence_bitmap", CodegenConfig::new().presence_bitmap(3)),
+        ("round_trip", CodegenConfig::new().round_trip_tests(true)),
+        ("serde_owned", CodegenConfig::new().derive_serde(true)),
+        (
+            "unchecked_reads",
+            CodegenConfig::new().unchecked_reads(true),
+        ),
+    ] {
+        let xml = std::fs::read_to_string(schemas.join(format!("{name}.xml")))
+            .expect("failed to read schema");
+        let schema = ironsbe_schema::parse_schema(&xml).expect("invalid schema");
+        let ir = ironsbe_schema::SchemaIr::from_schema(&schema);
+        let generator = |language| Generator::with_config(&ir, config.clone()).target(language);
+        write(
+            &out_dir,
+            &format!("{name}.rs"),
+            &generator(Language::Rust).generate(),
+        );
+
+        match name {
+            "cross_lang" => {
+                write(
+                    &out_dir,
+                    "cross_lang.hpp",
+                    &generator(Language::Cpp).generate(),
+                );
+                write(
+                    &out_dir,
+                    "cross_lang.java",
+                    &generator(Language::Java).generate(),
+                );
+            }
+            "ffi" => write(&out_dir, "ffi.h", &generator(Language::Rust).ffi_header()),
+            _ => {}
+        }
+    }
+
+    let multi = schemas.join("multi_schema");
+    let code = generate_from_files(&[multi.join("common.xml"), multi.join("orders.xml")])
+        .expect("failed to merge schemas");
+    write(&out_dir, "multi_schema.rs", &code);
+}
+
+fn write(out_dir: &Path, file: &str, contents: &str) {
+    std::fs::write(out_dir.join(file), contents).expect("failed to write generated code");
+}
@@ -0,0 +1,6 @@
+//! # IronSBE Codegen Tests
+//!
+//! Compiles the output of `ironsbe-codegen` and exercises it. `build.rs`
+//! generates every schema in `schemas/` into `OUT_DIR`, and each test in
+//! `tests/` includes its file from there, so the generated code is always
+//! that of the generator in the tree.
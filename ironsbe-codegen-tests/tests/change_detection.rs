@@ -0,0 +1,100 @@
+//! Change-detecting setters from `CodegenConfig::change_detection`.
+//!
+//! `build.rs` generates `schemas/change_detection.xml` with change detection
+//! enabled.
+
+#[allow(dead_code, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/change_detection.rs"));
+}
+
+use generated::{MassQuoteDecoder, MassQuoteEncoder, QuoteFlags, Side};
+use ironsbe_core::header::MessageHeader;
+
+const OFFSET: usize = MessageHeader::ENCODED_LENGTH;
+
+const GENERATED: &str = include_str!(concat!(env!("OUT_DIR"), "/change_detection.rs"));
+
+#[test]
+fn test_generated_code() {
+    assert!(GENERATED.contains("pub fn set_quote_id_if_changed(&mut self, value: u64) -> bool {"));
+    assert!(GENERATED.contains("pub const fn dirty_mask(&self) -> [u64; 1] {"));
+    // The message and the `quotes` entries each track their own fields.
+    assert_eq!(GENERATED.matches("dirty: [u64; 1],").count(), 2);
+}
+
+/// Encodes the template once, as a quoting engine would at start-up.
+fn template(buf: &mut [u8]) {
+    let mut encoder = MassQuoteEncoder::wrap(buf, 0);
+    encoder
+        .set_quote_id(1)
+        .set_symbol(b"ESZ6")
+        .set_side(Side::Buy)
+        .set_flags(QuoteFlags::new())
+        .set_volatility(0.25)
+        .set_skew(0.0)
+        .set_seq_num(1);
+    encoder.set_ref_price().set_mantissa(5000).set_exponent(-2);
+    let mut quotes = encoder.quotes_count(1);
+    let mut quote = quotes.next_entry().expect("quotes[0]");
+    quote
+        .set_bid_px(4999)
+        .set_bid_size(10)
+        .set_offer_px(5001)
+        .set_offer_size(10);
+}
+
+#[test]
+fn test_unchanged_fields_are_not_dirty() {
+    let mut buf = vec![0u8; 128];
+    template(&mut buf);
+
+    let mut encoder = MassQuoteEncoder::wrap(&mut buf, 0);
+    assert!(!encoder.set_quote_id_if_changed(1));
+    assert!(!encoder.set_symbol_if_changed(b"ESZ6"));
+    assert!(!encoder.set_side_if_changed(Side::Buy));
+    assert!(!encoder.set_flags_if_changed(QuoteFlags::new()));
+    assert!(!encoder.set_volatility_if_changed(0.25));
+    assert!(!encoder.set_skew_if_changed(0.0));
+    assert!(!encoder.is_dirty());
+    assert_eq!(encoder.dirty_mask(), [0]);
+}
+
+#[test]
+fn test_changed_fields_are_written_and_marked() {
+    let mut buf = vec![0u8; 128];
+    template(&mut buf);
+
+    let mut encoder = MassQuoteEncoder::wrap(&mut buf, 0);
+    let mut flags = QuoteFlags::new();
+    flags.set_firm();
+    assert!(encoder.set_flags_if_changed(flags));
+    assert!(encoder.set_seq_num_if_changed(2));
+    // A shorter symbol must clear the old tail.
+    assert!(encoder.set_symbol_if_changed(b"ES"));
+    // Signed zero differs from zero on the wire.
+    assert!(encoder.set_skew_if_changed(-0.0));
+    // Bits follow field order, skipping the constant and the composite.
+    assert_eq!(encoder.dirty_mask(), [1 << 1 | 1 << 3 | 1 << 5 | 1 << 6]);
+    {
+        let mut quotes = encoder.quotes_count(1);
+        let mut quote = quotes.next_entry().expect("quotes[0]");
+        assert!(!quote.set_bid_px_if_changed(4999));
+        assert!(quote.set_offer_px_if_changed(5002));
+        assert_eq!(quote.dirty_mask(), [1 << 2]);
+    }
+
+    encoder.clear_dirty();
+    assert!(!encoder.is_dirty());
+
+    let decoder = MassQuoteDecoder::wrap(&buf, OFFSET, 1);
+    assert_eq!(decoder.quote_id(), 1);
+    assert_eq!(decoder.symbol_as_str(), "ES");
+    assert!(decoder.flags().is_firm());
+    assert_eq!(decoder.seq_num(), 2);
+    assert!(decoder.skew().is_sign_negative());
+    assert_eq!(decoder.ref_price().mantissa(), 5000);
+    let quote = decoder.quotes().next().expect("quotes[0]");
+    assert_eq!(quote.bid_px(), 4999);
+    assert_eq!(quote.offer_px(), 5002);
+}
@@ -0,0 +1,387 @@
+//! Wire compatibility of the C++ and Java backends with the Rust output.
+//!
+//! `build.rs` generates `schemas/cross_lang.xml` in each language, as
+//! `cross_lang.{rs,hpp,java}` in `OUT_DIR`. When `g++` or `javac` is
+//! installed, a driver in that language decodes a message encoded by the
+//! Rust output, prints every value, and encodes the same values again; the
+//! bytes must match the Rust encoding exactly.
+
+#[allow(dead_code, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/cross_lang.rs"));
+}
+
+use generated::{Flags, OrderEncoder, Side};
+use std::path::Path;
+use std::process::Command;
+
+/// What both drivers print for the message from [`encode_order`].
+const EXPECTED: &str = "\
+orderId=18364758544493064720
+symbol=ESZ4
+price=-12345e-2
+side=Sell
+flags=513 postOnly=1 reduceOnly=1
+quantity=4000000000
+hasStopPx=0
+ratio=0.50
+delta=-300
+tick=-7
+weight=-2.25
+venue=65000
+lotSize=250
+market=XCME book=Buy base=10
+fill fillPx=-1 fillQty=3000000000 legs=7,65535 note=first
+fill fillPx=42 fillQty=1 legs= note=
+tag=-1
+tag=2147483647
+text=hello world
+length=140
+";
+
+const CPP_DRIVER: &str = r#"
+#include "cross_lang.hpp"
+
+#include <cstdio>
+#include <fstream>
+#include <iterator>
+#include <vector>
+
+using namespace wire::compat;
+
+static void print(const char* name, std::string_view value) {
+    std::printf("%s=%.*s", name, static_cast<int>(value.size()), value.data());
+}
+
+int main(int, char** argv) {
+    std::ifstream in(argv[1], std::ios::binary);
+    std::vector<char> input((std::istreambuf_iterator<char>(in)), std::istreambuf_iterator<char>());
+
+    const MessageHeader header = MessageHeader::read(input.data(), 0);
+    if (header.templateId != OrderDecoder::TEMPLATE_ID || header.schemaId != SCHEMA_ID) {
+        return 1;
+    }
+    OrderDecoder order(input.data(), MessageHeader::ENCODED_LENGTH, header.version);
+    std::printf("orderId=%llu\n", static_cast<unsigned long long>(order.orderId()));
+    print("symbol", order.symbol());
+    std::printf("\nprice=%llde%d\n", static_cast<long long>(order.price().mantissa()), order.price().exponent());
+    std::printf("side=%s\n", order.side() == Side::Sell ? "Sell" : "Buy");
+    std::printf("flags=%u postOnly=%d reduceOnly=%d\n", order.flags().raw(), order.flags().postOnly(), order.flags().reduceOnly());
+    std::printf("quantity=%u\n", order.quantity());
+    std::printf("hasStopPx=%d\n", order.hasStopPx());
+    std::printf("ratio=%.2f\n", static_cast<double>(order.ratio()));
+    std::printf("delta=%d\n", order.delta());
+    std::printf("tick=%d\n", order.tick());
+    std::printf("weight=%.2f\n", order.weight());
+    std::printf("venue=%u\n", order.venue());
+    std::printf("lotSize=%u\n", order.lotSize());
+    print("market", OrderDecoder::market());
+    std::printf(" book=%s base=%u\n", order.book() == Side::Buy ? "Buy" : "Sell", Decimal::base());
+    for (auto fills = order.fills(); fills.hasNext();) {
+        auto fill = fills.next();
+        std::printf("fill fillPx=%lld fillQty=%u legs=", static_cast<long long>(fill.fillPx()), fill.fillQty());
+        const char* separator = "";
+        for (auto legs = fill.legs(); legs.hasNext();) {
+            std::printf("%s%u", separator, legs.next().legRatio());
+            separator = ",";
+        }
+        std::printf(" ");
+        print("note", fill.note());
+        std::printf("\n");
+    }
+    for (auto tags = order.tags(); tags.hasNext();) {
+        std::printf("tag=%d\n", tags.next().tag());
+    }
+    print("text", order.text());
+    std::printf("\nlength=%zu\n", order.encodedLength());
+
+    std::vector<char> output(512);
+    OrderEncoder encoder(output.data(), 0);
+    encoder.orderId(18364758544493064720ULL).symbol("ESZ4");
+    encoder.price().mantissa(-12345).exponent(-2);
+    encoder.side(Side::Sell)
+        .flags(Flags().postOnly(true).reduceOnly(true))
+        .quantity(4000000000U)
+        .stopPx(INT64_MIN)
+        .ratio(0.5f)
+        .delta(-300)
+        .tick(-7)
+        .weight(-2.25)
+        .venue(65000)
+        .lotSize(250);
+    auto fills = encoder.fillsCount(2);
+    auto fill = fills.next();
+    fill.fillPx(-1).fillQty(3000000000U);
+    auto legs = fill.legsCount(2);
+    legs.next().legRatio(7);
+    legs.next().legRatio(65535);
+    fill.note("first");
+    fill = fills.next();
+    fill.fillPx(42).fillQty(1);
+    fill.legsCount(0);
+    fill.note("");
+    auto tags = encoder.tagsCount(2);
+    tags.next().tag(-1);
+    tags.next().tag(2147483647);
+    encoder.text("hello world");
+
+    std::ofstream out(argv[2], std::ios::binary);
+    out.write(output.data(), static_cast<std::streamsize>(encoder.encodedLength()));
+    return 0;
+}
+"#;
+
+const JAVA_DRIVER: &str = r#"
+import java.nio.ByteBuffer;
+import java.nio.ByteOrder;
+import java.nio.file.Files;
+import java.nio.file.Paths;
+import java.util.Arrays;
+import java.util.Locale;
+import wire.compat.Compat;
+import wire.compat.Compat.*;
+
+public class Driver {
+    public static void main(String[] args) throws Exception {
+        ByteBuffer input = ByteBuffer.wrap(Files.readAllBytes(Paths.get(args[0]))).order(ByteOrder.LITTLE_ENDIAN);
+        MessageHeader header = new MessageHeader().wrap(input, 0);
+        if (header.templateId() != OrderDecoder.TEMPLATE_ID || header.schemaId() != Compat.SCHEMA_ID) {
+            System.exit(1);
+        }
+        OrderDecoder order = new OrderDecoder().wrap(input, MessageHeader.ENCODED_LENGTH, header.version());
+        StringBuilder out = new StringBuilder();
+        out.append("orderId=").append(Long.toUnsignedString(order.orderId())).append('\n');
+        out.append("symbol=").append(order.symbol()).append('\n');
+        out.append("price=").append(order.price().mantissa()).append('e').append(order.price().exponent()).append('\n');
+        out.append("side=").append(order.side() == Side.SELL ? "Sell" : "Buy").append('\n');
+        int flags = order.flags();
+        out.append("flags=").append(flags)
+            .append(" postOnly=").append(Flags.postOnly(flags) ? 1 : 0)
+            .append(" reduceOnly=").append(Flags.reduceOnly(flags) ? 1 : 0).append('\n');
+        out.append("quantity=").append(order.quantity()).append('\n');
+        out.append("hasStopPx=").append(order.hasStopPx() ? 1 : 0).append('\n');
+        out.append(String.format(Locale.ROOT, "ratio=%.2f%n", order.ratio()));
+        out.append("delta=").append(order.delta()).append('\n');
+        out.append("tick=").append(order.tick()).append('\n');
+        out.append(String.format(Locale.ROOT, "weight=%.2f%n", order.weight()));
+        out.append("venue=").append(order.venue()).append('\n');
+        out.append("lotSize=").append(order.lotSize()).append('\n');
+        out.append("market=").append(order.market())
+            .append(" book=").append(order.book() == Side.BUY ? "Buy" : "Sell")
+            .append(" base=").append(order.price().base()).append('\n');
+        OrderDecoder.FillsGroupDecoder fills = order.fills();
+        while (fills.hasNext()) {
+            OrderDecoder.FillsEntryDecoder fill = fills.next();
+            out.append("fill fillPx=").append(fill.fillPx()).append(" fillQty=").append(fill.fillQty()).append(" legs=");
+            OrderDecoder.LegsGroupDecoder legs = fill.legs();
+            String separator = "";
+            while (legs.hasNext()) {
+                out.append(separator).append(legs.next().legRatio());
+                separator = ",";
+            }
+            out.append(" note=").append(fill.note()).append('\n');
+        }
+        OrderDecoder.TagsGroupDecoder tags = order.tags();
+        while (tags.hasNext()) {
+            out.append("tag=").append(tags.next().tag()).append('\n');
+        }
+        out.append("text=").append(order.text()).append('\n');
+        out.append("length=").append(order.encodedLength()).append('\n');
+        System.out.print(out);
+
+        ByteBuffer output = ByteBuffer.allocate(512).order(ByteOrder.LITTLE_ENDIAN);
+        OrderEncoder encoder = new OrderEncoder().wrap(output, 0);
+        encoder.orderId(0xFEDC_BA98_7654_3210L).symbol("ESZ4");
+        encoder.price().mantissa(-12345).exponent((byte) -2);
+        encoder.side(Side.SELL)
+            .flags(Flags.set(Flags.set(0, Flags.POST_ONLY), Flags.REDUCE_ONLY))
+            .quantity(4_000_000_000L)
+            .stopPx(Long.MIN_VALUE)
+            .ratio(0.5f)
+            .delta((short) -300)
+            .tick((byte) -7)
+            .weight(-2.25)
+            .venue(65000)
+            .lotSize((short) 250);
+        OrderEncoder.FillsGroupEncoder fillsOut = encoder.fillsCount(2);
+        OrderEncoder.FillsEntryEncoder fill = fillsOut.next();
+        fill.fillPx(-1).fillQty(3_000_000_000L);
+        OrderEncoder.LegsGroupEncoder legs = fill.legsCount(2);
+        legs.next().legRatio(7);
+        legs.next().legRatio(65535);
+        fill.note("first");
+        fill = fillsOut.next();
+        fill.fillPx(42).fillQty(1);
+        fill.legsCount(0);
+        fill.note("");
+        OrderEncoder.TagsGroupEncoder tagsOut = encoder.tagsCount(2);
+        tagsOut.next().tag(-1);
+        tagsOut.next().tag(2147483647);
+        encoder.text("hello world");
+        Files.write(Paths.get(args[1]), Arrays.copyOf(output.array(), encoder.encodedLength()));
+    }
+}
+"#;
+
+/// Encodes the reference message with the Rust output.
+fn encode_order() -> Vec<u8> {
+    let mut buf = vec![0u8; 512];
+    let len = {
+        let mut encoder = OrderEncoder::wrap(&mut buf, 0);
+        encoder
+            .set_order_id(0xFEDC_BA98_7654_3210)
+            .set_symbol(b"ESZ4");
+        encoder.set_price().set_mantissa(-12345).set_exponent(-2);
+        let mut flags = Flags::new();
+        flags.set_post_only();
+        flags.set_reduce_only();
+        encoder
+            .set_side(Side::Sell)
+            .set_flags(flags)
+            .set_quantity(4_000_000_000)
+            .set_stop_px(i64::MIN)
+            .set_ratio(0.5)
+            .set_delta(-300)
+            .set_tick(-7)
+            .set_weight(-2.25)
+            .set_venue(65000)
+            .set_lot_size(250);
+        {
+            let mut fills = encoder.fills_count(2);
+            {
+                let mut fill = fills.next_entry().expect("fills[0]");
+                fill.set_fill_px(-1).set_fill_qty(3_000_000_000);
+                {
+                    let mut legs = fill.legs_count(2);
+                    legs.next_entry().expect("legs[0]").set_leg_ratio(7);
+                    legs.next_entry().expect("legs[1]").set_leg_ratio(65535);
+                }
+                fill.set_note(b"first");
+            }
+            let mut fill = fills.next_entry().expect("fills[1]");
+            fill.set_fill_px(42).set_fill_qty(1);
+            fill.legs_count(0);
+            fill.set_note(b"");
+        }
+        {
+            let mut tags = encoder.tags_count(2);
+            tags.next_entry().expect("tags[0]").set_tag(-1);
+            tags.next_entry().expect("tags[1]").set_tag(i32::MAX);
+        }
+        encoder.set_text(b"hello world");
+        encoder.encoded_length()
+    };
+    buf.truncate(len);
+    buf
+}
+
+/// Constants decode from the Rust output without occupying any bytes.
+#[test]
+fn test_rust_decodes_constants() {
+    let bytes = encode_order();
+    let order = generated::OrderDecoder::wrap(&bytes, 8, 1);
+    assert_eq!(order.market(), b"XCME");
+    assert_eq!(order.market_as_str(), "XCME");
+    assert_eq!(order.book(), Side::Buy);
+    assert_eq!(order.price().base(), 10);
+    assert_eq!(order.lot_size(), 250);
+}
+
+/// Returns true if `tool` can be run, logging a skip otherwise.
+fn available(tool: &str) -> bool {
+    let found = Command::new(tool).arg("-version").output().is_ok();
+    if !found {
+        eprintln!("skipping: {tool} not found");
+    }
+    found
+}
+
+/// Runs `command`, panicking with its output if it fails.
+fn run(command: &mut Command) -> String {
+    let output = command.output().expect("failed to spawn");
+    assert!(
+        output.status.success(),
+        "{command:?} failed:\n{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("non-UTF-8 output")
+}
+
+fn out_dir() -> &'static Path {
+    Path::new(env!("OUT_DIR"))
+}
+
+#[test]
+fn test_generated_code() {
+    let cpp = include_str!(concat!(env!("OUT_DIR"), "/cross_lang.hpp"));
+    assert!(cpp.contains("namespace wire::compat {"));
+    assert!(cpp.contains("class OrderDecoder {"));
+    let java = include_str!(concat!(env!("OUT_DIR"), "/cross_lang.java"));
+    assert!(java.starts_with("// Generated by IronSBE codegen - DO NOT EDIT\n"));
+    assert!(java.contains("package wire.compat;"));
+    assert!(java.contains("public static final class OrderDecoder {"));
+}
+
+#[test]
+fn test_cpp_decodes_and_reencodes_rust_bytes() {
+    if !available("g++") {
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.bin");
+    let output = dir.path().join("output.bin");
+    let driver = dir.path().join("driver.cpp");
+    let binary = dir.path().join("driver");
+    let rust_bytes = encode_order();
+    std::fs::write(&input, &rust_bytes).unwrap();
+    std::fs::write(&driver, CPP_DRIVER).unwrap();
+
+    run(Command::new("g++")
+        .args(["-std=c++17", "-Wall", "-Wextra", "-Werror", "-I"])
+        .arg(out_dir())
+        .arg(&driver)
+        .arg("-o")
+        .arg(&binary));
+    let printed = run(Command::new(&binary).arg(&input).arg(&output));
+
+    assert_eq!(printed, EXPECTED);
+    assert_eq!(std::fs::read(&output).unwrap(), rust_bytes);
+}
+
+#[test]
+fn test_java_decodes_and_reencodes_rust_bytes() {
+    if !available("javac") || !available("java") {
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.bin");
+    let output = dir.path().join("output.bin");
+    let package = dir.path().join("wire/compat");
+    let classes = dir.path().join("classes");
+    let rust_bytes = encode_order();
+    std::fs::write(&input, &rust_bytes).unwrap();
+    std::fs::create_dir_all(&package).unwrap();
+    std::fs::copy(
+        out_dir().join("cross_lang.java"),
+        package.join("Compat.java"),
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("Driver.java"), JAVA_DRIVER).unwrap();
+
+    run(Command::new("javac")
+        .arg("-Werror")
+        .arg("-d")
+        .arg(&classes)
+        .arg(package.join("Compat.java"))
+        .arg(dir.path().join("Driver.java")));
+    let printed = run(Command::new("java")
+        .arg("-cp")
+        .arg(&classes)
+        .arg("Driver")
+        .arg(&input)
+        .arg(&output));
+
+    assert_eq!(printed, EXPECTED);
+    assert_eq!(std::fs::read(&output).unwrap(), rust_bytes);
+}
@@ -0,0 +1,103 @@
+//! Schema-specific message headers.
+//!
+//! `build.rs` generates `schemas/extended_header.xml`, whose `messageHeader`
+//! carries CME-style `numGroups` and `numVarDataFields` members after the
+//! standard four, with round-trip tests enabled. The generated code defines
+//! its own 12-byte `MessageHeader` and decodes through it; its round-trip
+//! tests run as part of this test binary.
+
+#[allow(dead_code, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/extended_header.rs"));
+}
+
+use generated::{MessageHeader, TradeDecoder, TradeEncoder};
+use ironsbe_core::decoder::{DecodeError, SbeDecoder};
+use ironsbe_core::encoder::SbeEncoder;
+use ironsbe_core::header::SbeHeader;
+
+const GENERATED: &str = include_str!(concat!(env!("OUT_DIR"), "/extended_header.rs"));
+
+#[test]
+fn test_generated_code() {
+    assert!(GENERATED.contains("impl SbeHeader for MessageHeader {"));
+    assert!(GENERATED.contains("impl<'a> SbeDecoder<'a, MessageHeader> for TradeDecoder<'a> {"));
+    // The generated header replaces the core one rather than importing it.
+    assert!(!GENERATED.contains("use ironsbe_core::header::MessageHeader;"));
+}
+
+fn encode_trade(buf: &mut [u8]) -> usize {
+    let mut encoder = TradeEncoder::wrap(buf, 0);
+    encoder.set_trade_id(7).set_qty(100);
+    {
+        let mut fills = encoder.fills_count(1);
+        fills.next_entry().expect("fills[0]").set_fill_qty(40);
+    }
+    encoder.set_memo(b"cross");
+    encoder.encoded_length()
+}
+
+#[test]
+fn test_encoder_writes_extended_header() {
+    let mut buf = [0u8; 64];
+    encode_trade(&mut buf);
+
+    assert_eq!(MessageHeader::ENCODED_LENGTH, 12);
+    let header = MessageHeader::wrap(&buf[..], 0);
+    assert_eq!(header.block_length, 12);
+    assert_eq!(header.template_id, 1);
+    assert_eq!(header.schema_id, 9);
+    assert_eq!(header.version, 2);
+    assert_eq!(header.num_groups, 1);
+    assert_eq!(header.num_var_data_fields, 1);
+    assert_eq!(SbeHeader::template_id(&header), 1);
+}
+
+#[test]
+fn test_decode_through_extended_header() {
+    let mut buf = [0u8; 64];
+    let len = encode_trade(&mut buf);
+
+    let decoder = TradeDecoder::decode(&buf[..len]).expect("valid message");
+    assert_eq!(decoder.trade_id(), 7);
+    assert_eq!(decoder.qty(), 100);
+    let fills: Vec<_> = decoder.fills().map(|fill| fill.fill_qty()).collect();
+    assert_eq!(fills, vec![40]);
+    assert_eq!(decoder.memo(), b"cross");
+
+    assert!(matches!(
+        TradeDecoder::decode(&buf[..11]),
+        Err(DecodeError::BufferTooShort { .. })
+    ));
+}
+
+/// Frames any message of the schema through the encoder trait alone.
+fn frame<'a, E: SbeEncoder<'a, MessageHeader>>(
+    buf: &'a mut [u8],
+    out: &mut [u8],
+) -> ironsbe_core::Result<usize> {
+    let encoder = E::wrap_and_apply_header(buf, 0)?;
+    encoder.encode_into(out)
+}
+
+#[test]
+fn test_generic_encoder_entrypoints() {
+    // The check covers the 12-byte header plus the 12-byte block.
+    let err = frame::<TradeEncoder>(&mut [0u8; 23], &mut [0u8; 64]).unwrap_err();
+    assert!(matches!(
+        err,
+        ironsbe_core::Error::BufferTooShort {
+            required: 24,
+            available: 23
+        }
+    ));
+
+    let mut buf = [0u8; 64];
+    let len = encode_trade(&mut buf);
+    let mut out = [0u8; 64];
+    let encoder = TradeEncoder::wrap(&mut buf, 0);
+    assert_eq!(encoder.as_bytes().len(), len);
+    assert_eq!(encoder.encode_into(&mut out).unwrap(), len);
+    assert!(encoder.encode_into(&mut out[..len - 1]).is_err());
+    assert_eq!(TradeDecoder::decode(&out[..len]).unwrap().memo(), b"cross");
+}
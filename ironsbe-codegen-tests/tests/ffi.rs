@@ -0,0 +1,147 @@
+//! C FFI accessor shims from `CodegenConfig::ffi_shims`.
+//!
+//! `build.rs` generates `schemas/ffi.xml` with the shims enabled, along with
+//! its C header, which must match the checked-in `golden/ffi.h`.
+
+#[allow(dead_code, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/ffi.rs"));
+}
+
+use generated::ffi::{
+    ffi_orders_cancel_order_id, ffi_orders_new_order_display_qty, ffi_orders_new_order_flags,
+    ffi_orders_new_order_order_id, ffi_orders_new_order_quantity, ffi_orders_new_order_side,
+    ffi_orders_new_order_symbol,
+};
+use generated::{CancelEncoder, NewOrderEncoder, OrderFlags, Side};
+use ironsbe_core::header::MessageHeader;
+use std::path::Path;
+
+/// The C header is the contract with C callers, so any change to it has to
+/// be reviewed. Set `IRONSBE_UPDATE_FIXTURES=1` to rewrite it instead.
+#[test]
+fn test_header_matches_golden() {
+    let header = include_str!(concat!(env!("OUT_DIR"), "/ffi.h"));
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/ffi.h");
+    if std::env::var_os("IRONSBE_UPDATE_FIXTURES").is_some() {
+        std::fs::write(&path, header).expect("failed to update golden header");
+    }
+    assert!(
+        header == std::fs::read_to_string(&path).expect("failed to read golden header"),
+        "generated header differs from tests/golden/ffi.h; rerun with IRONSBE_UPDATE_FIXTURES=1"
+    );
+}
+
+#[test]
+fn test_generated_code() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/ffi.rs"));
+    assert!(generated.contains(
+        "#[unsafe(no_mangle)]\n    pub unsafe extern \"C\" fn ffi_orders_new_order_side("
+    ));
+}
+
+fn new_order() -> Vec<u8> {
+    let mut buf = vec![0u8; MessageHeader::ENCODED_LENGTH + 38];
+    let mut encoder = NewOrderEncoder::wrap(&mut buf, 0);
+    encoder
+        .set_order_id(42)
+        .set_symbol(b"ESZ6\0\0")
+        .set_side(Side::Sell)
+        .set_flags(OrderFlags::from_raw(0b10))
+        .set_quantity(2.5)
+        .set_display_qty(0.5);
+    buf
+}
+
+#[test]
+fn test_shims_read_fields() {
+    let buf = new_order();
+    let (ptr, len) = (buf.as_ptr(), buf.len());
+
+    let mut order_id = 0u64;
+    let mut symbol = [0u8; 6];
+    let mut side = 0u8;
+    let mut flags = 0u16;
+    let mut quantity = 0f64;
+    let mut display_qty = 0f32;
+    // SAFETY: every pointer is valid for the accessed size.
+    unsafe {
+        assert!(ffi_orders_new_order_order_id(ptr, len, &mut order_id));
+        assert!(ffi_orders_new_order_symbol(ptr, len, symbol.as_mut_ptr()));
+        assert!(ffi_orders_new_order_side(ptr, len, &mut side));
+        assert!(ffi_orders_new_order_flags(ptr, len, &mut flags));
+        assert!(ffi_orders_new_order_quantity(ptr, len, &mut quantity));
+        assert!(ffi_orders_new_order_display_qty(ptr, len, &mut display_qty));
+    }
+    assert_eq!(order_id, 42);
+    assert_eq!(&symbol, b"ESZ6\0\0");
+    assert_eq!(side, 2);
+    assert_eq!(flags, OrderFlags::from_raw(0b10).raw());
+    assert_eq!(quantity, 2.5);
+    assert_eq!(display_qty, 0.5);
+}
+
+#[test]
+fn test_shims_reject_invalid_input() {
+    let buf = new_order();
+    let mut order_id = 7u64;
+
+    // SAFETY: every non-null pointer is valid for the accessed size.
+    unsafe {
+        // Null pointers and truncated buffers.
+        assert!(!ffi_orders_new_order_order_id(
+            std::ptr::null(),
+            0,
+            &mut order_id
+        ));
+        assert!(!ffi_orders_new_order_order_id(
+            buf.as_ptr(),
+            buf.len(),
+            std::ptr::null_mut()
+        ));
+        assert!(!ffi_orders_new_order_order_id(
+            buf.as_ptr(),
+            buf.len() - 1,
+            &mut order_id
+        ));
+        // Another template.
+        assert!(!ffi_orders_cancel_order_id(
+            buf.as_ptr(),
+            buf.len(),
+            &mut order_id
+        ));
+    }
+    assert_eq!(order_id, 7);
+
+    // An older version whose root block ends before the field.
+    let mut old = new_order();
+    old[0..2].copy_from_slice(&30u16.to_le_bytes());
+    old.truncate(MessageHeader::ENCODED_LENGTH + 30);
+    let mut display_qty = 0f32;
+    // SAFETY: the pointers are valid for the accessed size.
+    unsafe {
+        assert!(ffi_orders_new_order_order_id(
+            old.as_ptr(),
+            old.len(),
+            &mut order_id
+        ));
+        assert!(!ffi_orders_new_order_display_qty(
+            old.as_ptr(),
+            old.len(),
+            &mut display_qty
+        ));
+    }
+    assert_eq!(order_id, 42);
+
+    let mut cancel = vec![0u8; MessageHeader::ENCODED_LENGTH + 8];
+    CancelEncoder::wrap(&mut cancel, 0).set_order_id(9);
+    // SAFETY: the pointers are valid for the accessed size.
+    unsafe {
+        assert!(ffi_orders_cancel_order_id(
+            cancel.as_ptr(),
+            cancel.len(),
+            &mut order_id
+        ))
+    };
+    assert_eq!(order_id, 9);
+}
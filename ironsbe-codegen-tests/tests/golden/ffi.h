@@ -0,0 +1,30 @@
+/* Generated by IronSBE codegen - DO NOT EDIT */
+/* Schema: ffi.orders v2 */
+
+#ifndef FFI_ORDERS_SBE_FFI_H
+#define FFI_ORDERS_SBE_FFI_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* NewOrder (template ID 1) */
+bool ffi_orders_new_order_order_id(const uint8_t *buffer, size_t len, uint64_t *out);
+bool ffi_orders_new_order_symbol(const uint8_t *buffer, size_t len, char out[6]);
+bool ffi_orders_new_order_side(const uint8_t *buffer, size_t len, uint8_t *out);
+bool ffi_orders_new_order_flags(const uint8_t *buffer, size_t len, uint16_t *out);
+bool ffi_orders_new_order_quantity(const uint8_t *buffer, size_t len, double *out);
+bool ffi_orders_new_order_display_qty(const uint8_t *buffer, size_t len, float *out);
+
+/* Cancel (template ID 2) */
+bool ffi_orders_cancel_order_id(const uint8_t *buffer, size_t len, uint64_t *out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* FFI_ORDERS_SBE_FFI_H */
@@ -0,0 +1,74 @@
+//! Code generation from a schema split across several XML files.
+//!
+//! `build.rs` generates `schemas/multi_schema/common.xml` merged with
+//! `orders.xml`.
+
+#[allow(dead_code, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/multi_schema.rs"));
+}
+
+use generated::prelude::*;
+use ironsbe_codegen::{CodegenError, generate_from_files};
+use ironsbe_schema::ParseError;
+use std::path::{Path, PathBuf};
+
+fn schema_files() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("schemas/multi_schema");
+    vec![dir.join("common.xml"), dir.join("orders.xml")]
+}
+
+#[test]
+fn test_generated_code() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/multi_schema.rs"));
+    // Both files end up in one module with a single prelude.
+    assert_eq!(generated.matches("pub mod prelude {").count(), 1);
+    assert!(generated.contains("pub struct NewOrderEncoder<'a> {"));
+    assert!(generated.contains("pub enum Side {"));
+}
+
+#[test]
+fn test_messages_use_types_from_common_file() {
+    let mut buf = [0u8; 64];
+    let mut encoder = NewOrderEncoder::wrap(&mut buf, 0);
+    encoder
+        .set_symbol(b"ESZ6    ")
+        .set_side(Side::Sell)
+        .set_quantity(12);
+    assert_eq!(encoder.encoded_length(), MessageHeader::ENCODED_LENGTH + 17);
+
+    let header = MessageHeader::wrap(buf.as_slice(), 0);
+    assert_eq!({ header.schema_id }, SCHEMA_ID);
+    let decoder = NewOrderDecoder::wrap(&buf, MessageHeader::ENCODED_LENGTH, 1);
+    assert_eq!(decoder.symbol(), b"ESZ6    ");
+    assert_eq!(decoder.side(), Side::Sell);
+    assert_eq!(decoder.quantity(), 12);
+
+    assert_eq!(CancelOrderEncoder::BLOCK_LENGTH, 8);
+}
+
+#[test]
+fn test_conflicting_files_are_rejected() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let conflicting = dir.path().join("conflicting.xml");
+    std::fs::write(
+        &conflicting,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="multi" id="21" version="1" byteOrder="littleEndian">
+    <types>
+        <type name="Symbol" primitiveType="char" length="6"/>
+    </types>
+</sbe:messageSchema>"#,
+    )
+    .expect("write schema");
+
+    let mut files = schema_files();
+    files.push(conflicting);
+    let err = generate_from_files(&files).unwrap_err();
+    assert!(matches!(
+        err,
+        CodegenError::Parse(ParseError::DuplicateDefinition { ref kind, ref name })
+            if kind == "type" && name == "Symbol"
+    ));
+}
@@ -0,0 +1,326 @@
+//! Positional decoding and encoding of nested groups and var data.
+//!
+//! `build.rs` generates `schemas/nested_groups.xml`, which is compiled here
+//! and exercised against hand-encoded buffers.
+
+#[allow(dead_code, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/nested_groups.rs"));
+}
+
+use generated::{MessageVisitor, OrderBatchDecoder, OrderBatchEncoder, dispatch};
+use ironsbe_core::decoder::DecodeError;
+use ironsbe_core::header::MessageHeader;
+
+/// Little-endian byte writer for hand-encoding messages.
+#[derive(Default)]
+struct Wire(Vec<u8>);
+
+impl Wire {
+    fn u16(mut self, value: u16) -> Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn u32(mut self, value: u32) -> Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn u64(mut self, value: u64) -> Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn data(self, value: &[u8]) -> Self {
+        let mut wire = self.u16(value.len() as u16);
+        wire.0.extend_from_slice(value);
+        wire
+    }
+}
+
+/// Builds an `OrderBatch` where every section has a different size.
+fn hand_encoded() -> Vec<u8> {
+    Wire::default()
+        // message header
+        .u16(8)
+        .u16(1)
+        .u16(7)
+        .u16(1)
+        // root block
+        .u64(99)
+        // orders: 2 entries
+        .u16(12)
+        .u16(2)
+        // orders[0] with 2 fills and a note
+        .u64(1001)
+        .u32(50)
+        .u16(4)
+        .u16(2)
+        .u32(20)
+        .u32(30)
+        .data(b"ab")
+        // orders[1] with no fills and an empty note
+        .u64(1002)
+        .u32(75)
+        .u16(4)
+        .u16(0)
+        .data(b"")
+        // legs: 3 entries
+        .u16(2)
+        .u16(3)
+        .u16(1)
+        .u16(2)
+        .u16(3)
+        // text
+        .data(b"hello")
+        .0
+}
+
+#[test]
+fn test_generated_code() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/nested_groups.rs"));
+    // Sections after the root block are located from the acting block length.
+    assert!(generated.contains("self.offset + self.block_length as usize"));
+    assert!(generated.contains("fn text_offset(&self) -> usize {"));
+}
+
+#[test]
+fn test_decode_hand_encoded_sections() {
+    let buf = hand_encoded();
+    let decoder = OrderBatchDecoder::wrap(&buf, MessageHeader::ENCODED_LENGTH, 1);
+
+    assert_eq!(decoder.batch_id(), 99);
+
+    let orders: Vec<_> = decoder.orders().collect();
+    assert_eq!(orders.len(), 2);
+
+    assert_eq!(orders[0].order_id(), 1001);
+    assert_eq!(orders[0].quantity(), 50);
+    let fills: Vec<u32> = orders[0].fills().map(|f| f.fill_qty()).collect();
+    assert_eq!(fills, vec![20, 30]);
+    assert_eq!(orders[0].note(), b"ab");
+
+    assert_eq!(orders[1].order_id(), 1002);
+    assert_eq!(orders[1].quantity(), 75);
+    assert!(orders[1].fills().is_empty());
+    assert_eq!(orders[1].note(), b"");
+
+    let ratios: Vec<u16> = decoder.legs().map(|l| l.ratio()).collect();
+    assert_eq!(ratios, vec![1, 2, 3]);
+    assert_eq!(decoder.text(), b"hello");
+}
+
+#[test]
+fn test_decode_sections_out_of_order() {
+    let buf = hand_encoded();
+    let decoder = OrderBatchDecoder::wrap(&buf, MessageHeader::ENCODED_LENGTH, 1);
+
+    // Later sections do not depend on earlier ones having been read.
+    assert_eq!(decoder.text(), b"hello");
+    assert_eq!(decoder.legs().count(), 3);
+    assert_eq!(decoder.orders().count(), 2);
+}
+
+#[test]
+fn test_decoder_encoded_length_spans_all_sections() {
+    use ironsbe_core::decoder::SbeDecoder;
+
+    let buf = hand_encoded();
+    let decoder = OrderBatchDecoder::wrap(&buf, MessageHeader::ENCODED_LENGTH, 1);
+    assert_eq!(decoder.encoded_length(), buf.len());
+}
+
+#[test]
+fn test_decode_longer_root_block_from_newer_schema() {
+    use ironsbe_core::decoder::SbeDecoder;
+
+    // A newer sender appends a 4-byte field to the root block, so every
+    // section starts 4 bytes later than `BLOCK_LENGTH` implies.
+    let mut buf = hand_encoded();
+    buf[0..2].copy_from_slice(&12u16.to_le_bytes());
+    buf.splice(16..16, 0xDEAD_BEEFu32.to_le_bytes());
+
+    let header = MessageHeader::wrap(&buf[..], 0);
+    let decoder = OrderBatchDecoder::wrap_with_block_length(
+        &buf,
+        MessageHeader::ENCODED_LENGTH,
+        header.block_length,
+        header.version,
+    );
+
+    assert_eq!(decoder.batch_id(), 99);
+    let quantities: Vec<u32> = decoder.orders().map(|o| o.quantity()).collect();
+    assert_eq!(quantities, vec![50, 75]);
+    let ratios: Vec<u16> = decoder.legs().map(|l| l.ratio()).collect();
+    assert_eq!(ratios, vec![1, 2, 3]);
+    assert_eq!(decoder.text(), b"hello");
+    assert_eq!(decoder.encoded_length(), buf.len());
+    // Without a block length, `wrap` reads it from the header before the body.
+    let decoder = OrderBatchDecoder::wrap(&buf, MessageHeader::ENCODED_LENGTH, header.version);
+    assert_eq!(decoder.text(), b"hello");
+
+    #[derive(Default)]
+    struct Texts(Vec<Vec<u8>>);
+
+    impl MessageVisitor for Texts {
+        fn visit_order_batch(&mut self, message: OrderBatchDecoder<'_>) {
+            self.0.push(message.text().to_vec());
+        }
+    }
+
+    let mut texts = Texts::default();
+    dispatch(&header, &buf, &mut texts).unwrap();
+    assert_eq!(texts.0, vec![b"hello".to_vec()]);
+}
+
+#[test]
+fn test_encode_matches_hand_encoded() {
+    let expected = hand_encoded();
+    let mut buf = vec![0u8; expected.len()];
+
+    let mut encoder = OrderBatchEncoder::wrap(&mut buf, 0);
+    encoder.set_batch_id(99);
+    {
+        let mut orders = encoder.orders_count(2);
+
+        let mut entry = orders.next_entry().expect("orders[0]");
+        entry.set_order_id(1001).set_quantity(50);
+        {
+            let mut fills = entry.fills_count(2);
+            fills.next_entry().expect("fills[0]").set_fill_qty(20);
+            fills.next_entry().expect("fills[1]").set_fill_qty(30);
+            assert!(fills.next_entry().is_none());
+        }
+        entry.set_note(b"ab");
+
+        let mut entry = orders.next_entry().expect("orders[1]");
+        entry.set_order_id(1002).set_quantity(75);
+        entry.fills_count(0);
+        entry.set_note(b"");
+
+        assert!(orders.next_entry().is_none());
+    }
+    {
+        let mut legs = encoder.legs_count(3);
+        for ratio in 1..=3 {
+            legs.next_entry().expect("legs entry").set_ratio(ratio);
+        }
+    }
+    encoder.set_text(b"hello");
+
+    assert_eq!(encoder.encoded_length(), expected.len());
+    assert_eq!(buf, expected);
+}
+
+#[test]
+fn test_entry_length_grows_with_appended_sections() {
+    let mut buf = vec![0u8; 128];
+    let mut encoder = OrderBatchEncoder::wrap(&mut buf, 0);
+    let mut orders = encoder.orders_count(2);
+
+    let mut entry = orders.next_entry().expect("orders[0]");
+    entry.set_order_id(1).set_quantity(5);
+    entry
+        .fills_count(1)
+        .next_entry()
+        .expect("fills[0]")
+        .set_fill_qty(3);
+    let written = entry.set_note(b"abc").encoded_length();
+    assert_eq!(written, 12 + 4 + 4 + 2 + 3);
+
+    let mut entry = orders.next_entry().expect("orders[1]");
+    entry.fills_count(0);
+    assert_eq!(
+        entry.set_order_id(2).set_note(b"z").encoded_length(),
+        12 + 4 + 2 + 1
+    );
+
+    let decoder = OrderBatchDecoder::wrap(&buf, MessageHeader::ENCODED_LENGTH, 1);
+    let notes: Vec<_> = decoder
+        .orders()
+        .map(|order| order.note().to_vec())
+        .collect();
+    assert_eq!(notes, vec![b"abc".to_vec(), b"z".to_vec()]);
+}
+
+#[test]
+fn test_encoded_size_matches_hand_encoded() {
+    use generated::order_batch::OrdersEntryEncoder;
+
+    let minimal = OrdersEntryEncoder::encoded_size(&[], &[]);
+    let extra = (OrdersEntryEncoder::encoded_size(&[2], &[2]) - minimal)
+        + (OrdersEntryEncoder::encoded_size(&[0], &[0]) - minimal);
+
+    assert_eq!(
+        OrderBatchEncoder::encoded_size(&[2, 3], &[5]) + extra,
+        hand_encoded().len()
+    );
+    assert_eq!(
+        OrderBatchEncoder::encoded_size(&[], &[]),
+        MessageHeader::ENCODED_LENGTH + 8 + 4 + 4 + 2
+    );
+}
+
+#[test]
+fn test_debug_prints_fields_groups_and_var_data() {
+    let buf = hand_encoded();
+    let decoder = OrderBatchDecoder::wrap(&buf, MessageHeader::ENCODED_LENGTH, 1);
+
+    assert_eq!(
+        format!("{:?}", decoder),
+        "OrderBatchDecoder { batch_id: 99, orders: [\
+         OrdersEntryDecoder { order_id: 1001, quantity: 50, fills: [\
+         FillsEntryDecoder { fill_qty: 20 }, FillsEntryDecoder { fill_qty: 30 }], note: \"ab\" }, \
+         OrdersEntryDecoder { order_id: 1002, quantity: 75, fills: [], note: \"\" }], \
+         legs: [LegsEntryDecoder { ratio: 1 }, LegsEntryDecoder { ratio: 2 }, \
+         LegsEntryDecoder { ratio: 3 }], text: \"hello\" }"
+    );
+}
+
+#[test]
+fn test_dispatch_routes_by_template_id() {
+    #[derive(Default)]
+    struct Recorder {
+        batches: Vec<u64>,
+        unknown: Vec<u16>,
+    }
+
+    impl MessageVisitor for Recorder {
+        fn visit_order_batch(&mut self, message: OrderBatchDecoder<'_>) {
+            self.batches.push(message.batch_id());
+        }
+
+        fn unknown_template(&mut self, header: &MessageHeader, _buffer: &[u8]) {
+            self.unknown.push(header.template_id);
+        }
+    }
+
+    let mut buf = hand_encoded();
+    let mut recorder = Recorder::default();
+    dispatch(&MessageHeader::wrap(&buf[..], 0), &buf, &mut recorder).unwrap();
+
+    buf[2] = 42;
+    dispatch(&MessageHeader::wrap(&buf[..], 0), &buf, &mut recorder).unwrap();
+    assert_eq!(recorder.batches, vec![99]);
+    assert_eq!(recorder.unknown, vec![42]);
+
+    let header = MessageHeader::wrap(&buf[..], 0);
+    assert!(matches!(
+        dispatch(&header, &buf[..12], &mut recorder),
+        Err(DecodeError::BufferTooShort {
+            required: 16,
+            available: 12
+        })
+    ));
+
+    buf[4] = 8;
+    assert!(matches!(
+        dispatch(&MessageHeader::wrap(&buf[..], 0), &buf, &mut recorder),
+        Err(DecodeError::SchemaMismatch {
+            expected: 7,
+            actual: 8
+        })
+    ));
+    assert_eq!(recorder.unknown, vec![42]);
+}
@@ -0,0 +1,54 @@
+//! `no_std`, alloc-free output emitted with `CodegenConfig::no_std`.
+//!
+//! `build.rs` generates `schemas/no_std.xml` with `no_std` and
+//! `derive_serde` enabled.
+
+#[allow(dead_code, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/no_std.rs"));
+}
+
+use generated::{Flags, QuoteDecoder, QuoteEncoder, Side};
+use ironsbe_core::header::MessageHeader;
+
+const GENERATED: &str = include_str!(concat!(env!("OUT_DIR"), "/no_std.rs"));
+
+#[test]
+fn test_output_avoids_std_and_alloc() {
+    for forbidden in ["std::", "String", "Vec<", "to_vec", "to_string", "to_owned"] {
+        assert!(
+            !GENERATED.contains(forbidden),
+            "no_std output contains `{forbidden}`"
+        );
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    let mut buf = [0u8; 128];
+    {
+        let mut encoder = QuoteEncoder::wrap(&mut buf, 0);
+        encoder
+            .set_quote_id(7)
+            .set_symbol(b"NQH7")
+            .set_side(Side::Buy)
+            .set_flags(Flags::from_raw(0b01));
+        encoder
+            .levels_count(1)
+            .next_entry()
+            .expect("levels[0]")
+            .set_size(5);
+        encoder.set_memo(b"ok");
+    }
+
+    let decoder = QuoteDecoder::wrap(&buf, MessageHeader::ENCODED_LENGTH, 1);
+    assert_eq!(decoder.quote_id(), 7);
+    assert_eq!(decoder.symbol_as_str(), "NQH7");
+    assert_eq!(decoder.side(), Side::Buy);
+    assert_eq!(decoder.levels().map(|entry| entry.size()).sum::<u32>(), 5);
+    assert_eq!(decoder.memo(), b"ok");
+
+    // Var data is shown as bytes since there is no lossy UTF-8 conversion
+    let debug = format!("{decoder:?}");
+    assert!(debug.contains("memo: [111, 107]"));
+}
@@ -0,0 +1,91 @@
+//! Presence accessors for optional fields and `CodegenConfig::presence_bitmap`.
+//!
+//! `build.rs` generates `schemas/presence_bitmap.xml` with a presence bitmap
+//! for messages with at least 3 optional fields: `Order` gets the bitmap, `Cancel` and the `fills`
+//! entries check the null sentinel on every call.
+
+#[allow(dead_code, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/presence_bitmap.rs"));
+}
+
+use generated::{CancelDecoder, CancelEncoder, OrderDecoder, OrderEncoder};
+use ironsbe_core::header::MessageHeader;
+
+const OFFSET: usize = MessageHeader::ENCODED_LENGTH;
+
+#[test]
+fn test_bitmap_all_null() {
+    let mut buf = vec![0u8; 128];
+    {
+        let mut encoder = OrderEncoder::wrap(&mut buf, 0);
+        encoder
+            .set_order_id(1)
+            .set_price(i64::MIN)
+            .set_qty(0)
+            .set_ratio(f64::NAN)
+            .set_flag(0)
+            .set_venue(u8::MAX);
+        encoder.fills_count(0);
+    }
+
+    let decoder = OrderDecoder::wrap(&buf, OFFSET, 1);
+    assert!(!decoder.has_price());
+    assert!(!decoder.has_qty());
+    assert!(!decoder.has_ratio());
+    assert!(!decoder.has_flag());
+    assert!(!decoder.has_venue());
+    assert_eq!(decoder.price_opt(), None);
+    assert_eq!(decoder.ratio_opt(), None);
+}
+
+#[test]
+fn test_bitmap_present_values() {
+    let mut buf = vec![0u8; 128];
+    {
+        let mut encoder = OrderEncoder::wrap(&mut buf, 0);
+        encoder
+            .set_order_id(1)
+            .set_price(-5)
+            .set_qty(i32::MIN)
+            .set_ratio(0.5)
+            .set_flag(b'Y')
+            .set_venue(3);
+        let mut fills = encoder.fills_count(2);
+        fills.next_entry().expect("fills[0]").set_fill_qty(7);
+        fills.next_entry().expect("fills[1]").set_fill_qty(u32::MAX);
+    }
+
+    let decoder = OrderDecoder::wrap(&buf, OFFSET, 1);
+    assert_eq!(decoder.price_opt(), Some(-5));
+    // The schema's nullValue="0" replaces i32::MIN as the sentinel.
+    assert_eq!(decoder.qty_opt(), Some(i32::MIN));
+    assert_eq!(decoder.ratio_opt(), Some(0.5));
+    assert_eq!(decoder.flag_opt(), Some(b'Y'));
+    assert_eq!(decoder.venue_opt(), Some(3));
+
+    let fills: Vec<_> = decoder.fills().map(|fill| fill.fill_qty_opt()).collect();
+    assert_eq!(fills, vec![Some(7), None]);
+}
+
+#[test]
+fn test_sentinel_check_below_threshold() {
+    let mut buf = vec![0u8; 64];
+    CancelEncoder::wrap(&mut buf, 0)
+        .set_order_id(1)
+        .set_reason(u64::MAX);
+    let decoder = CancelDecoder::wrap(&buf, OFFSET, 1);
+    assert!(!decoder.has_reason());
+    assert_eq!(decoder.reason_opt(), None);
+
+    CancelEncoder::wrap(&mut buf, 0).set_reason(4);
+    let decoder = CancelDecoder::wrap(&buf, OFFSET, 1);
+    assert_eq!(decoder.reason_opt(), Some(4));
+}
+
+#[test]
+fn test_bitmap_only_above_threshold() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/presence_bitmap.rs"));
+    assert_eq!(generated.matches("presence: [u64; 1],").count(), 1);
+    assert!(generated.contains("self.presence[0] & (1 << 4) != 0"));
+}
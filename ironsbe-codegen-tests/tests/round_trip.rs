@@ -0,0 +1,207 @@
+//! Round-trip tests and golden corpus from `CodegenConfig::round_trip_tests`.
+//!
+//! `build.rs` generates `schemas/round_trip.xml` with round-trip tests
+//! enabled; the generated `round_trip_tests` module runs as part of this test
+//! binary. `golden/round_trip/` holds the golden sample of
+//! every message, one `<Message>.bin` file each.
+
+#[allow(dead_code, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/round_trip.rs"));
+}
+
+use generated::{
+    BookDecoder, BookEncoder, CancelDecoder, Flags, Instrument, InstrumentEncoder, MessageVisitor,
+    OrderDecoder, QuoteDecoder, Side, Venue, dispatch,
+};
+use ironsbe_codegen::Generator;
+use ironsbe_core::header::MessageHeader;
+use std::path::{Path, PathBuf};
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/round_trip")
+}
+
+fn golden_corpus() -> Vec<ironsbe_codegen::GoldenMessage> {
+    let xml = include_str!("../schemas/round_trip.xml");
+    let schema = ironsbe_schema::parse_schema(xml).expect("invalid schema");
+    let ir = ironsbe_schema::SchemaIr::from_schema(&schema);
+    Generator::new(&ir).golden_corpus()
+}
+
+/// The checked-in corpus pins the wire layout: a generator change that moves
+/// any field shows up here even if the generated round-trip tests agree with
+/// it.
+///
+/// Set `IRONSBE_UPDATE_FIXTURES=1` to rewrite the corpus instead.
+#[test]
+fn test_golden_corpus_up_to_date() {
+    let dir = golden_dir();
+    let corpus = golden_corpus();
+
+    if std::env::var_os("IRONSBE_UPDATE_FIXTURES").is_some() {
+        std::fs::create_dir_all(&dir).expect("failed to create corpus dir");
+        for golden in &corpus {
+            std::fs::write(dir.join(format!("{}.bin", golden.name)), &golden.bytes)
+                .expect("failed to update corpus");
+        }
+    }
+
+    let mut files: Vec<String> = std::fs::read_dir(&dir)
+        .expect("missing golden corpus")
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    let mut expected: Vec<String> = corpus.iter().map(|g| format!("{}.bin", g.name)).collect();
+    expected.sort();
+    assert_eq!(
+        files, expected,
+        "stale or missing files in tests/golden/round_trip"
+    );
+
+    for golden in &corpus {
+        let bytes = std::fs::read(dir.join(format!("{}.bin", golden.name))).unwrap();
+        assert!(
+            bytes == golden.bytes,
+            "golden sample of {} changed; rerun with IRONSBE_UPDATE_FIXTURES=1",
+            golden.name
+        );
+    }
+}
+
+#[test]
+fn test_generated_code() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/round_trip.rs"));
+    assert!(generated.contains("#[cfg(test)]\nmod round_trip_tests {"));
+}
+
+#[derive(Default)]
+struct Recorder {
+    seen: Vec<u16>,
+}
+
+impl MessageVisitor for Recorder {
+    fn visit_order(&mut self, message: OrderDecoder<'_>) {
+        assert_eq!(message.symbol(), b"BCDEFG");
+        assert_eq!(message.text(), b"pqr");
+        self.seen.push(OrderDecoder::TEMPLATE_ID);
+    }
+
+    fn visit_cancel(&mut self, message: CancelDecoder<'_>) {
+        assert_eq!(message.order_id(), 0x1817_1615_1413_1211);
+        self.seen.push(CancelDecoder::TEMPLATE_ID);
+    }
+
+    fn visit_quote(&mut self, message: QuoteDecoder<'_>) {
+        let instrument = message.instrument();
+        assert_eq!(instrument.price().exponent(), 81);
+        assert_eq!(instrument.side(), Side::Sell);
+        self.seen.push(QuoteDecoder::TEMPLATE_ID);
+    }
+
+    fn unknown_template(&mut self, header: &MessageHeader, _buffer: &[u8]) {
+        self.seen.push(header.template_id);
+    }
+}
+
+#[test]
+fn test_golden_corpus_dispatches() {
+    let mut recorder = Recorder::default();
+    for golden in golden_corpus() {
+        let header = MessageHeader::wrap(&golden.bytes, 0);
+        assert_eq!({ header.template_id }, golden.template_id);
+        dispatch(&header, &golden.bytes, &mut recorder).expect("dispatch failed");
+    }
+    // Heartbeat has no visitor override and is skipped silently.
+    assert_eq!(recorder.seen, vec![1, 2, 4]);
+}
+
+#[test]
+fn test_composite_standalone_at_offset() {
+    let mut buf = [0u8; 32];
+    let offset = 5;
+    {
+        let mut encoder = InstrumentEncoder::try_wrap(&mut buf, offset).expect("fits");
+        encoder
+            .set_side(Side::Sell)
+            .set_flags(Flags::from_raw(0b1))
+            .set_venue(Venue::Xnys)
+            .set_lot(100);
+        encoder.set_price().set_mantissa(-5).set_exponent(3);
+    }
+
+    let instrument = Instrument::try_wrap(&buf, offset).expect("fits");
+    assert_eq!(instrument.price().mantissa(), -5);
+    assert_eq!(instrument.price().exponent(), 3);
+    assert_eq!(instrument.side(), Side::Sell);
+    assert!(instrument.flags().is_post_only());
+    assert_eq!(instrument.venue(), Venue::Xnys);
+    assert_eq!(instrument.lot(), 100);
+
+    let end = offset + Instrument::ENCODED_LENGTH;
+    assert!(Instrument::try_wrap(&buf[..end - 1], offset).is_none());
+    assert!(Instrument::try_wrap(&buf, usize::MAX).is_none());
+    assert!(InstrumentEncoder::try_wrap(&mut buf[..end - 1], offset).is_none());
+}
+
+#[test]
+fn test_nested_entries_track_their_end() {
+    let mut buf = vec![0xFFu8; 128];
+    let written = {
+        let mut encoder = BookEncoder::wrap(&mut buf, 0);
+        encoder.set_book_id(9);
+        let mut levels = encoder.levels_count(2);
+        {
+            let mut level = levels.next_entry().expect("levels[0]");
+            level.set_px(100);
+            assert_eq!(level.encoded_length(), 8);
+            {
+                let mut orders = level.orders_count(2);
+                let mut order = orders.next_entry().expect("orders[0]");
+                order.set_qty(1).set_owner(b"ann");
+                assert_eq!(order.encoded_length(), 4 + 2 + 3);
+                orders
+                    .next_entry()
+                    .expect("orders[1]")
+                    .set_qty(2)
+                    .set_owner(b"bo");
+                assert_eq!(orders.encoded_length(), 4 + 9 + 8);
+            }
+            assert_eq!(level.encoded_length(), 8 + 21);
+            assert_eq!(level.set_venue(b"X").encoded_length(), 8 + 21 + 3);
+        }
+        {
+            let mut level = levels.next_entry().expect("levels[1]");
+            level.set_px(101);
+            level.orders_count(0);
+            level.set_venue(b"");
+        }
+        assert!(levels.next_entry().is_none());
+        assert_eq!(levels.encoded_length(), 4 + 32 + 14);
+        encoder.encoded_length()
+    };
+    assert_eq!(written, MessageHeader::ENCODED_LENGTH + 8 + 4 + 32 + 14);
+
+    let decoder = BookDecoder::wrap(&buf, MessageHeader::ENCODED_LENGTH, 3);
+    let levels: Vec<_> = decoder
+        .levels()
+        .map(|level| {
+            let owners: Vec<_> = level
+                .orders()
+                .map(|o| (o.qty(), o.owner().to_vec()))
+                .collect();
+            (level.px(), owners, level.venue().to_vec())
+        })
+        .collect();
+    assert_eq!(
+        levels,
+        vec![
+            (
+                100,
+                vec![(1, b"ann".to_vec()), (2, b"bo".to_vec())],
+                b"X".to_vec()
+            ),
+            (101, vec![], b"".to_vec()),
+        ]
+    );
+}
@@ -0,0 +1,104 @@
+//! Owned `serde` mirrors emitted with `CodegenConfig::derive_serde`.
+//!
+//! `build.rs` generates `schemas/serde_owned.xml` with `derive_serde`
+//! enabled.
+
+#[allow(dead_code, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/serde_owned.rs"));
+}
+
+use generated::quote::LevelsEntryOwned;
+use generated::{Decimal, DecimalOwned, Flags, QuoteDecoder, QuoteEncoder, QuoteOwned, Side};
+use ironsbe_core::header::MessageHeader;
+
+const GENERATED: &str = include_str!(concat!(env!("OUT_DIR"), "/serde_owned.rs"));
+
+#[test]
+fn test_generated_code() {
+    assert!(GENERATED.contains("pub fn to_owned(&self) -> QuoteOwned {"));
+    assert!(
+        GENERATED
+            .contains("#[derive(serde::Serialize, serde::Deserialize)]\npub struct QuoteOwned {")
+    );
+}
+
+fn encode_quote(buf: &mut [u8]) {
+    let mut encoder = QuoteEncoder::wrap(buf, 0);
+    encoder
+        .set_quote_id(42)
+        .set_symbol(b"ESZ6")
+        .set_side(Side::Sell)
+        .set_flags(Flags::from_raw(0b10));
+    encoder.set_price().set_mantissa(123_450).set_exponent(-2);
+    {
+        let mut levels = encoder.levels_count(2);
+        levels.next_entry().expect("levels[0]").set_size(10);
+        levels.next_entry().expect("levels[1]").set_size(20);
+    }
+    encoder.set_memo(b"hi");
+}
+
+fn expected() -> QuoteOwned {
+    QuoteOwned {
+        quote_id: 42,
+        symbol: "ESZ6".to_string(),
+        price: DecimalOwned {
+            mantissa: 123_450,
+            exponent: -2,
+        },
+        side: Side::Sell,
+        flags: Flags::from_raw(0b10),
+        levels: vec![LevelsEntryOwned { size: 10 }, LevelsEntryOwned { size: 20 }],
+        memo: b"hi".to_vec(),
+    }
+}
+
+#[test]
+fn test_to_owned_copies_all_sections() {
+    let mut buf = vec![0u8; 128];
+    encode_quote(&mut buf);
+
+    let decoder = QuoteDecoder::wrap(&buf, MessageHeader::ENCODED_LENGTH, 1);
+    assert_eq!(decoder.to_owned(), expected());
+}
+
+#[test]
+fn test_owned_json_round_trip() {
+    let mut buf = vec![0u8; 128];
+    encode_quote(&mut buf);
+
+    let owned = QuoteDecoder::wrap(&buf, MessageHeader::ENCODED_LENGTH, 1).to_owned();
+    let json = serde_json::to_string(&owned).expect("serialize");
+    assert!(json.contains("\"symbol\":\"ESZ6\""));
+    assert!(json.contains("\"side\":\"Sell\""));
+
+    let back: QuoteOwned = serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(back, owned);
+}
+
+#[test]
+fn test_debug_prints_decoded_values() {
+    let mut buf = vec![0u8; 128];
+    encode_quote(&mut buf);
+    let decoder = QuoteDecoder::wrap(&buf, MessageHeader::ENCODED_LENGTH, 1);
+
+    let debug = format!("{:?}", decoder);
+    assert!(debug.starts_with("QuoteDecoder { quote_id: 42, symbol: \"ESZ6\""));
+    assert!(debug.contains("price: Decimal { mantissa: 123450, exponent: -2 }"));
+    assert!(debug.contains("side: Sell"));
+    assert!(debug.contains("memo: \"hi\""));
+}
+
+#[test]
+fn test_owned_composite_encodes_standalone() {
+    let price = DecimalOwned {
+        mantissa: -7,
+        exponent: 4,
+    };
+    let mut buf = vec![0u8; 16];
+    price.encode(&mut buf, 3);
+
+    let decoded = Decimal::try_wrap(&buf, 3).expect("fits");
+    assert_eq!(decoded.to_owned(), price);
+}
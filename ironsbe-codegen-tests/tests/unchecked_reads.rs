@@ -0,0 +1,121 @@
+//! Bounds-check-free field reads from `CodegenConfig::unchecked_reads`.
+//!
+//! `build.rs` generates `schemas/unchecked_reads.xml` with unchecked reads
+//! enabled.
+
+#[allow(dead_code, clippy::all)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/unchecked_reads.rs"));
+}
+
+use generated::{Flags, MessageVisitor, Side, TradeDecoder, TradeEncoder, dispatch};
+use ironsbe_core::decoder::{DecodeError, SbeDecoder};
+use ironsbe_core::header::MessageHeader;
+
+const OFFSET: usize = MessageHeader::ENCODED_LENGTH;
+
+const GENERATED: &str = include_str!(concat!(env!("OUT_DIR"), "/unchecked_reads.rs"));
+
+#[test]
+fn test_generated_code() {
+    assert!(GENERATED.contains("unsafe { self.buffer.get_u64_le_unchecked(self.offset + 0) }"));
+    assert!(GENERATED.contains(") -> Result<Self, DecodeError> {"));
+}
+
+#[test]
+fn test_unchecked_reads_decode_every_field() {
+    let mut buf = vec![0u8; 128];
+    let len = {
+        let mut flags = Flags::new();
+        flags.set_implied();
+        let mut encoder = TradeEncoder::wrap(&mut buf, 0);
+        encoder
+            .set_trade_id(u64::MAX - 1)
+            .set_price(-12_345)
+            .set_qty(300)
+            .set_side(Side::Sell)
+            .set_flags(flags)
+            .set_ratio(0.75)
+            .set_symbol(b"ESZ6\0\0\0\0");
+        let mut legs = encoder.legs_count(2);
+        legs.next_entry()
+            .expect("legs[0]")
+            .set_leg_id(1)
+            .set_leg_qty(-5);
+        legs.next_entry()
+            .expect("legs[1]")
+            .set_leg_id(2)
+            .set_leg_qty(5);
+        encoder.encoded_length()
+    };
+
+    let decoder = TradeDecoder::wrap(&buf[..len], OFFSET, 1).expect("block fits");
+    assert_eq!(decoder.trade_id(), u64::MAX - 1);
+    assert_eq!(decoder.price(), -12_345);
+    assert_eq!(decoder.qty(), 300);
+    assert_eq!(decoder.side(), Side::Sell);
+    assert!(decoder.flags().is_implied());
+    assert!(!decoder.flags().is_aggressor());
+    assert_eq!(decoder.ratio(), 0.75);
+    assert_eq!(decoder.symbol_as_str(), "ESZ6");
+    let legs: Vec<_> = decoder
+        .legs()
+        .map(|leg| (leg.leg_id(), leg.leg_qty()))
+        .collect();
+    assert_eq!(legs, vec![(1, -5), (2, 5)]);
+}
+
+#[test]
+fn test_wrap_rejects_truncated_block() {
+    let buf = vec![0u8; OFFSET + 39];
+    assert!(matches!(
+        TradeDecoder::wrap(&buf, OFFSET, 1),
+        Err(DecodeError::BufferTooShort {
+            required: 48,
+            available: 47
+        })
+    ));
+}
+
+#[test]
+fn test_wrap_checks_header_block_length() {
+    let buf = vec![0u8; OFFSET + 44];
+
+    // A newer sender's longer root block must be in the buffer.
+    assert!(TradeDecoder::wrap_with_block_length(&buf, OFFSET, 44, 1).is_ok());
+    assert!(matches!(
+        TradeDecoder::wrap_with_block_length(&buf, OFFSET, 48, 1),
+        Err(DecodeError::BufferTooShort {
+            required: 56,
+            available: 52
+        })
+    ));
+
+    // A shorter one does not cover the fields read without bounds checks.
+    assert!(matches!(
+        TradeDecoder::wrap_with_block_length(&buf[..OFFSET + 32], OFFSET, 32, 1),
+        Err(DecodeError::BufferTooShort {
+            required: 48,
+            available: 40
+        })
+    ));
+}
+
+#[test]
+fn test_decode_and_dispatch_report_short_block() {
+    struct Ignore;
+    impl MessageVisitor for Ignore {}
+
+    // The header announces a 32-byte root block, which is all the buffer
+    // holds, but the schema's block is 40 bytes.
+    let mut buf = vec![0u8; OFFSET + 32];
+    MessageHeader::new(32, TradeDecoder::TEMPLATE_ID, 11, 1).encode(&mut buf, 0);
+
+    let short = DecodeError::BufferTooShort {
+        required: 48,
+        available: 40,
+    };
+    assert_eq!(TradeDecoder::decode(&buf).err(), Some(short.clone()));
+    let header = MessageHeader::wrap(&buf[..], 0);
+    assert_eq!(dispatch(&header, &buf, &mut Ignore), Err(short));
+}
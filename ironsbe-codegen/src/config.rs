@@ -0,0 +1,409 @@
+//! Code generation options.
+
+use std::collections::HashMap;
+
+/// Options controlling what the generator emits.
+///
+/// The default configuration produces the same output as [`Generator::new`].
+///
+/// [`Generator::new`]: crate::Generator::new
+#[derive(Debug, Clone, Default)]
+pub struct CodegenConfig {
+    /// Emit owned mirror structs with `serde` derives.
+    ///
+    /// When enabled, every message, group entry and composite decoder gains a
+    /// `to_owned()` method returning an owned `*Owned` struct that derives
+    /// `serde::Serialize` and `serde::Deserialize`. Generated enums and sets
+    /// derive both traits as well. The crate including the generated code
+    /// must depend on `serde` with the `derive` feature.
+    ///
+    /// The owned structs need `alloc` and are skipped when
+    /// [`no_std`](Self::no_std) is set; enums and sets still derive `serde`.
+    pub derive_serde: bool,
+
+    /// Emit code for `#![no_std]` crates without `alloc`.
+    ///
+    /// The generated code then only uses `core` and `ironsbe-core` with
+    /// default features disabled: no `String`, `Vec` or `std` paths. Var
+    /// data fields are shown as bytes in `Debug` output, and owned `serde`
+    /// mirrors are not generated.
+    pub no_std: bool,
+
+    /// Glob patterns selecting the messages to generate.
+    ///
+    /// Each pattern is matched against the message name and its decimal
+    /// template ID; `*` matches any run of characters and `?` a single one.
+    /// An empty list selects every message.
+    pub include_messages: Vec<String>,
+
+    /// Glob patterns removing messages from the selection.
+    ///
+    /// Exclusions take precedence over [`include_messages`](Self::include_messages).
+    /// Types referenced only by removed messages are not generated.
+    pub exclude_messages: Vec<String>,
+
+    /// Identifier overrides, keyed by schema name.
+    ///
+    /// A rename applies to every message, group, field, var data field and
+    /// type with that schema name. Generated identifiers are derived from the
+    /// new name exactly as they would be from the original one.
+    pub renames: HashMap<String, String>,
+
+    /// Minimum number of optional fields for a presence bitmap.
+    ///
+    /// Optional scalar fields always get `has_*` and `*_opt` accessors that
+    /// compare against the null sentinel on every call. Message decoders with
+    /// at least this many such fields instead check them all once in `wrap`
+    /// and answer presence queries from a memoized bitmap. `None` disables
+    /// the bitmap.
+    ///
+    /// The bitmap makes `wrap` more expensive and only pays off when a
+    /// decoder is queried repeatedly; see the `presence` benchmark in
+    /// `ironsbe-bench`.
+    pub presence_bitmap: Option<usize>,
+
+    /// Emit change-detecting setters and a dirty mask on encoders.
+    ///
+    /// Every scalar, enum, set and array field of a message or group entry
+    /// encoder gains a `set_*_if_changed` setter that compares the value
+    /// with the bytes already in the buffer and only writes it if they
+    /// differ, recording the field in the encoder's dirty mask. Engines
+    /// that keep a persistent template buffer per instrument and re-encode
+    /// nearly identical messages, such as mass quotes, then only touch the
+    /// fields that moved and can tell from the mask whether anything did.
+    ///
+    /// Comparing costs more than blindly writing into a cache-resident
+    /// buffer; the gain is in the work the mask lets callers skip. See the
+    /// `change_detection` benchmark in `ironsbe-bench`.
+    pub change_detection: bool,
+
+    /// Emit a `#[cfg(test)]` round-trip test for every message.
+    ///
+    /// Each test encodes a deterministic sample of the message, compares
+    /// the bytes with its golden encoding (see
+    /// [`Generator::golden_corpus`](crate::Generator::golden_corpus)), then
+    /// decodes it and compares every field. Repeating groups are written
+    /// empty.
+    pub round_trip_tests: bool,
+
+    /// Emit `extern "C"` accessor shims in an `ffi` module.
+    ///
+    /// Every non-constant scalar, enum, set and array field of a message's
+    /// root block gets a `#[no_mangle]` function named
+    /// `<package>_<message>_<field>` that decodes it from a pointer and
+    /// length, so C, C++ or Python code linked against the crate reuses the
+    /// generated layout. [`Generator::ffi_header`](crate::Generator::ffi_header)
+    /// writes the matching C header. Include the generated code in only one
+    /// crate of a binary, or the symbols clash.
+    pub ffi_shims: bool,
+
+    /// Read fixed-block fields without per-field bounds checks.
+    ///
+    /// Message decoders then check once in `wrap` that the root block fits
+    /// in the buffer, and `wrap` returns `Result<Self, DecodeError>` to
+    /// report when it does not. Group entries are checked as the iterator
+    /// reaches them, panicking if one is cut short. Scalar, enum and set
+    /// fields are then read with the `unsafe`
+    /// `ReadBuffer::get_*_unchecked` methods. The crate including the
+    /// generated code must enable the `unchecked` feature of
+    /// `ironsbe-core`. Only worth it for frames validated upfront, e.g. by
+    /// length and checksum, on paths where nanoseconds per field matter.
+    pub unchecked_reads: bool,
+
+    /// Skip pretty-printing and return the generator's raw output.
+    ///
+    /// By default the generated code is formatted with `prettyplease`, so
+    /// checked-in output diffs cleanly across schema changes.
+    pub raw_output: bool,
+
+    /// Parse schemas in strict mode.
+    ///
+    /// Unknown elements and attributes, misplaced members and stray text are
+    /// then errors instead of being skipped; see
+    /// [`ironsbe_schema::strict`]. Enable it where the schema is authored so
+    /// typos fail the build.
+    pub strict_schema: bool,
+}
+
+impl CodegenConfig {
+    /// Creates a config with every option disabled.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables or disables owned `serde` mirror structs.
+    #[must_use]
+    pub fn derive_serde(mut self, enabled: bool) -> Self {
+        self.derive_serde = enabled;
+        self
+    }
+
+    /// Enables or disables `no_std`, alloc-free output.
+    #[must_use]
+    pub fn no_std(mut self, enabled: bool) -> Self {
+        self.no_std = enabled;
+        self
+    }
+
+    /// Returns true if owned mirror structs are generated.
+    #[must_use]
+    pub fn owned_types(&self) -> bool {
+        self.derive_serde && !self.no_std
+    }
+
+    /// Enables presence bitmaps for messages with at least
+    /// `min_optional_fields` optional fields.
+    #[must_use]
+    pub fn presence_bitmap(mut self, min_optional_fields: usize) -> Self {
+        self.presence_bitmap = Some(min_optional_fields);
+        self
+    }
+
+    /// Enables or disables change-detecting setters and dirty masks.
+    #[must_use]
+    pub fn change_detection(mut self, enabled: bool) -> Self {
+        self.change_detection = enabled;
+        self
+    }
+
+    /// Enables or disables generated round-trip tests.
+    #[must_use]
+    pub fn round_trip_tests(mut self, enabled: bool) -> Self {
+        self.round_trip_tests = enabled;
+        self
+    }
+
+    /// Enables or disables `extern "C"` accessor shims.
+    #[must_use]
+    pub fn ffi_shims(mut self, enabled: bool) -> Self {
+        self.ffi_shims = enabled;
+        self
+    }
+
+    /// Enables or disables bounds-check-free field reads.
+    #[must_use]
+    pub fn unchecked_reads(mut self, enabled: bool) -> Self {
+        self.unchecked_reads = enabled;
+        self
+    }
+
+    /// Enables or disables raw, unformatted output.
+    #[must_use]
+    pub fn raw_output(mut self, enabled: bool) -> Self {
+        self.raw_output = enabled;
+        self
+    }
+
+    /// Enables or disables strict schema parsing.
+    #[must_use]
+    pub fn strict_schema(mut self, enabled: bool) -> Self {
+        self.strict_schema = enabled;
+        self
+    }
+
+    /// Adds a glob pattern selecting messages to generate.
+    #[must_use]
+    pub fn include_message(mut self, pattern: impl Into<String>) -> Self {
+        self.include_messages.push(pattern.into());
+        self
+    }
+
+    /// Adds a glob pattern removing messages from the output.
+    #[must_use]
+    pub fn exclude_message(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude_messages.push(pattern.into());
+        self
+    }
+
+    /// Renames the schema identifier `from` to `to` in the generated code.
+    #[must_use]
+    pub fn rename(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.renames.insert(from.into(), to.into());
+        self
+    }
+
+    /// Returns true if any include or exclude pattern is configured.
+    #[must_use]
+    pub fn has_message_filters(&self) -> bool {
+        !self.include_messages.is_empty() || !self.exclude_messages.is_empty()
+    }
+
+    /// Returns true if the message `name` with `template_id` is selected.
+    #[must_use]
+    pub fn is_message_selected(&self, name: &str, template_id: u16) -> bool {
+        let id = template_id.to_string();
+        let matches = |pattern: &String| glob_match(pattern, name) || glob_match(pattern, &id);
+
+        (self.include_messages.is_empty() || self.include_messages.iter().any(matches))
+            && !self.exclude_messages.iter().any(matches)
+    }
+
+    /// Returns the configured name for the schema identifier `name`.
+    #[must_use]
+    pub fn renamed<'a>(&'a self, name: &'a str) -> &'a str {
+        self.renames.get(name).map_or(name, String::as_str)
+    }
+}
+
+/// Matches `text` against a glob `pattern` supporting `*` and `?`.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it was tried at.
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, star_t)) => {
+                    p = star + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codegen_config_default() {
+        let config = CodegenConfig::default();
+        assert!(!config.derive_serde);
+        assert!(!config.no_std);
+        assert!(config.presence_bitmap.is_none());
+        assert!(!config.change_detection);
+        assert!(!config.round_trip_tests);
+        assert!(!config.ffi_shims);
+        assert!(!config.unchecked_reads);
+        assert!(!config.raw_output);
+        assert!(!config.strict_schema);
+    }
+
+    #[test]
+    fn test_codegen_config_strict_schema() {
+        let xml = r#"<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe" package="t" id="1" version="0">
+            <types>
+                <composite name="messageHeader">
+                    <type name="blockLength" primitiveType="uint16"/>
+                    <type name="templateId" primitiveType="uint16"/>
+                    <type name="schemaId" primitiveType="uint16"/>
+                    <type name="version" primitiveType="uint16"/>
+                </composite>
+            </types>
+            <sbe:message name="Ping" id="1">
+                <field name="seq" id="1" type="uint64" ofset="0"/>
+            </sbe:message>
+        </sbe:messageSchema>"#;
+
+        crate::generate_from_xml_with_config(xml, &CodegenConfig::new()).unwrap();
+        let config = CodegenConfig::new().strict_schema(true);
+        let err = crate::generate_from_xml_with_config(xml, &config).unwrap_err();
+        assert!(
+            err.to_string().contains("unknown attribute 'ofset'"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_codegen_config_derive_serde() {
+        let config = CodegenConfig::new().derive_serde(true);
+        assert!(config.derive_serde);
+        assert!(config.owned_types());
+    }
+
+    #[test]
+    fn test_codegen_config_no_std_skips_owned_types() {
+        let config = CodegenConfig::new().derive_serde(true).no_std(true);
+        assert!(config.no_std);
+        assert!(!config.owned_types());
+    }
+
+    #[test]
+    fn test_codegen_config_presence_bitmap() {
+        let config = CodegenConfig::new().presence_bitmap(20);
+        assert_eq!(config.presence_bitmap, Some(20));
+    }
+
+    #[test]
+    fn test_codegen_config_change_detection() {
+        let config = CodegenConfig::new().change_detection(true);
+        assert!(config.change_detection);
+    }
+
+    #[test]
+    fn test_codegen_config_round_trip_tests() {
+        let config = CodegenConfig::new().round_trip_tests(true);
+        assert!(config.round_trip_tests);
+    }
+
+    #[test]
+    fn test_codegen_config_ffi_shims() {
+        let config = CodegenConfig::new().ffi_shims(true);
+        assert!(config.ffi_shims);
+    }
+
+    #[test]
+    fn test_codegen_config_unchecked_reads() {
+        let config = CodegenConfig::new().unchecked_reads(true);
+        assert!(config.unchecked_reads);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("NewOrder*", "NewOrderSingle"));
+        assert!(glob_match("*Order*", "CancelOrderRequest"));
+        assert!(glob_match("Order?", "Order1"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXXbYYc"));
+        assert!(!glob_match("Order?", "Order"));
+        assert!(!glob_match("NewOrder", "NewOrderSingle"));
+        assert!(!glob_match("a*b*c", "aXXbYY"));
+    }
+
+    #[test]
+    fn test_message_selection() {
+        let config = CodegenConfig::new();
+        assert!(!config.has_message_filters());
+        assert!(config.is_message_selected("Anything", 1));
+
+        let config = CodegenConfig::new()
+            .include_message("*Order*")
+            .include_message("9?")
+            .exclude_message("MassCancelOrder");
+        assert!(config.has_message_filters());
+        assert!(config.is_message_selected("NewOrder", 1));
+        assert!(config.is_message_selected("Heartbeat", 90));
+        assert!(!config.is_message_selected("Heartbeat", 100));
+        assert!(!config.is_message_selected("MassCancelOrder", 2));
+    }
+
+    #[test]
+    fn test_exclude_only_selection() {
+        let config = CodegenConfig::new().exclude_message("1*");
+        assert!(config.is_message_selected("Trade", 2));
+        assert!(!config.is_message_selected("Trade", 12));
+    }
+
+    #[test]
+    fn test_renamed() {
+        let config = CodegenConfig::new().rename("NewOrderSingle", "NewOrder");
+        assert_eq!(config.renamed("NewOrderSingle"), "NewOrder");
+        assert_eq!(config.renamed("Trade"), "Trade");
+    }
+}
@@ -0,0 +1,417 @@
+//! Cross-language conformance test vectors.
+//!
+//! A [`ConformanceSuite`] holds the canonical encoding of one sample per
+//! message, the same one the generated round-trip tests check. Written to a
+//! directory, each vector is a `<Message>.bin` file next to a
+//! `<Message>.json` description listing the offset, length, type and value
+//! of every field, and `vectors.json` indexes them.
+//!
+//! Teams using another SBE implementation (Java, C++, ...) decode each
+//! binary and check the values against its description, then encode the
+//! described values into a directory of their own.
+//! [`ConformanceSuite::verify_dir`] compares those encodings with the
+//! canonical bytes and names the field of the first byte that differs.
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use ironsbe_schema::ir::SchemaIr;
+use serde_json::{Value, json};
+
+use crate::rust::{GoldenMessage, RoundTripGenerator};
+
+/// File indexing the vectors of a suite.
+pub const INDEX_FILE: &str = "vectors.json";
+
+/// Canonical encodings of one sample per message of a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceSuite {
+    /// Schema package.
+    pub package: String,
+    /// Schema ID.
+    pub schema_id: u16,
+    /// Schema version.
+    pub schema_version: u16,
+    /// One vector per message, in schema order.
+    pub vectors: Vec<GoldenMessage>,
+}
+
+impl ConformanceSuite {
+    /// Builds the suite for every message of `ir`.
+    #[must_use]
+    pub fn new(ir: &SchemaIr) -> Self {
+        Self {
+            package: ir.package.clone(),
+            schema_id: ir.schema_id,
+            schema_version: ir.schema_version,
+            vectors: RoundTripGenerator::new(ir).golden_corpus(),
+        }
+    }
+
+    /// Returns the vector of message `name`.
+    #[must_use]
+    pub fn vector(&self, name: &str) -> Option<&GoldenMessage> {
+        self.vectors.iter().find(|vector| vector.name == name)
+    }
+
+    /// Returns the JSON description of `vector`.
+    #[must_use]
+    pub fn describe(&self, vector: &GoldenMessage) -> Value {
+        let fields: Vec<Value> = vector
+            .fields
+            .iter()
+            .map(|field| {
+                json!({
+                    "path": field.path,
+                    "offset": field.offset,
+                    "length": field.length,
+                    "type": field.type_name,
+                    "value": field.value,
+                })
+            })
+            .collect();
+        json!({
+            "message": vector.name,
+            "templateId": vector.template_id,
+            "schemaId": self.schema_id,
+            "version": self.schema_version,
+            "byteOrder": "littleEndian",
+            "length": vector.bytes.len(),
+            "hex": hex(&vector.bytes),
+            "fields": fields,
+        })
+    }
+
+    /// Returns the JSON index of the suite.
+    #[must_use]
+    pub fn index(&self) -> Value {
+        let vectors: Vec<Value> = self
+            .vectors
+            .iter()
+            .map(|vector| {
+                json!({
+                    "message": vector.name,
+                    "templateId": vector.template_id,
+                    "binary": format!("{}.bin", vector.name),
+                    "description": format!("{}.json", vector.name),
+                })
+            })
+            .collect();
+        json!({
+            "package": self.package,
+            "schemaId": self.schema_id,
+            "version": self.schema_version,
+            "vectors": vectors,
+        })
+    }
+
+    /// Writes the index and every vector with its description to `dir`.
+    ///
+    /// # Errors
+    /// Returns an I/O error if the directory or a file cannot be written.
+    pub fn write_to(&self, dir: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join(INDEX_FILE), pretty(&self.index()))?;
+        for vector in &self.vectors {
+            std::fs::write(dir.join(format!("{}.bin", vector.name)), &vector.bytes)?;
+            std::fs::write(
+                dir.join(format!("{}.json", vector.name)),
+                pretty(&self.describe(vector)),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Compares `bytes`, as encoded by another implementation, with the
+    /// canonical encoding of message `name`.
+    ///
+    /// # Returns
+    /// `None` if the suite has no vector for `name`.
+    #[must_use]
+    pub fn verify(&self, name: &str, bytes: &[u8]) -> Option<VectorOutcome> {
+        let vector = self.vector(name)?;
+        let expected = &vector.bytes;
+        let mismatch = expected.iter().zip(bytes).position(|(e, a)| e != a);
+        Some(match mismatch {
+            Some(offset) => VectorOutcome::ByteMismatch {
+                offset,
+                expected: expected[offset],
+                actual: bytes[offset],
+                field: vector
+                    .fields
+                    .iter()
+                    .find(|f| (f.offset..f.offset + f.length).contains(&offset))
+                    .map(|f| f.path.clone()),
+            },
+            None if bytes.len() != expected.len() => VectorOutcome::LengthMismatch {
+                expected: expected.len(),
+                actual: bytes.len(),
+            },
+            None => VectorOutcome::Match,
+        })
+    }
+
+    /// Verifies the `<Message>.bin` files another implementation wrote to
+    /// `dir` against every vector of the suite.
+    ///
+    /// A missing file is reported as [`VectorOutcome::Missing`].
+    ///
+    /// # Errors
+    /// Returns an I/O error if an existing file cannot be read.
+    pub fn verify_dir(&self, dir: &Path) -> io::Result<ConformanceReport> {
+        let mut results = Vec::with_capacity(self.vectors.len());
+        for vector in &self.vectors {
+            let outcome = match std::fs::read(dir.join(format!("{}.bin", vector.name))) {
+                Ok(bytes) => self
+                    .verify(&vector.name, &bytes)
+                    .unwrap_or(VectorOutcome::Missing),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => VectorOutcome::Missing,
+                Err(e) => return Err(e),
+            };
+            results.push(VectorResult {
+                message: vector.name.clone(),
+                outcome,
+            });
+        }
+        Ok(ConformanceReport { results })
+    }
+}
+
+/// Result of comparing one encoding with its canonical vector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VectorOutcome {
+    /// The encoding is byte-identical.
+    Match,
+    /// No encoding was provided.
+    Missing,
+    /// The encodings share a prefix but differ in length.
+    LengthMismatch {
+        /// Canonical length.
+        expected: usize,
+        /// Length of the provided encoding.
+        actual: usize,
+    },
+    /// The first differing byte.
+    ByteMismatch {
+        /// Offset in the message, header included.
+        offset: usize,
+        /// Canonical byte.
+        expected: u8,
+        /// Provided byte.
+        actual: u8,
+        /// Path of the field covering the byte, if any; padding has none.
+        field: Option<String>,
+    },
+}
+
+impl fmt::Display for VectorOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Match => write!(f, "ok"),
+            Self::Missing => write!(f, "missing"),
+            Self::LengthMismatch { expected, actual } => {
+                write!(f, "length is {actual}, expected {expected}")
+            }
+            Self::ByteMismatch {
+                offset,
+                expected,
+                actual,
+                field,
+            } => {
+                write!(f, "byte {offset}")?;
+                if let Some(field) = field {
+                    write!(f, " ({field})")?;
+                }
+                write!(f, " is {actual:#04x}, expected {expected:#04x}")
+            }
+        }
+    }
+}
+
+/// Outcome for one message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorResult {
+    /// Message name.
+    pub message: String,
+    /// Comparison outcome.
+    pub outcome: VectorOutcome,
+}
+
+/// Outcome of [`ConformanceSuite::verify_dir`], one line per message when
+/// displayed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceReport {
+    /// One result per vector, in suite order.
+    pub results: Vec<VectorResult>,
+}
+
+impl ConformanceReport {
+    /// Returns true if every vector matched.
+    #[must_use]
+    pub fn is_conformant(&self) -> bool {
+        self.results
+            .iter()
+            .all(|result| result.outcome == VectorOutcome::Match)
+    }
+
+    /// Returns the results that did not match.
+    pub fn failures(&self) -> impl Iterator<Item = &VectorResult> {
+        self.results
+            .iter()
+            .filter(|result| result.outcome != VectorOutcome::Match)
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            writeln!(f, "{}: {}", result.message, result.outcome)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats bytes as lowercase hex without separators.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Pretty-prints `value` with a trailing newline.
+fn pretty(value: &Value) -> String {
+    let mut text = serde_json::to_string_pretty(value).unwrap_or_default();
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironsbe_schema::parse_schema;
+
+    const SCHEMA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="3" version="2" byteOrder="littleEndian">
+    <types>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+            <validValue name="Sell">2</validValue>
+        </enum>
+        <composite name="varDataEncoding">
+            <type name="length" primitiveType="uint16"/>
+            <type name="varData" primitiveType="uint8" length="0"/>
+        </composite>
+    </types>
+    <sbe:message name="Quote" id="5" blockLength="12">
+        <field name="bid" id="1" type="int64" offset="0"/>
+        <field name="side" id="2" type="Side" offset="8"/>
+        <data name="note" id="6" type="varDataEncoding"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+    fn suite() -> ConformanceSuite {
+        ConformanceSuite::new(&SchemaIr::from_schema(
+            &parse_schema(SCHEMA).expect("Failed to parse"),
+        ))
+    }
+
+    #[test]
+    fn test_describe() {
+        let suite = suite();
+        let vector = suite.vector("Quote").unwrap();
+        let description = suite.describe(vector);
+
+        assert_eq!(description["templateId"], 5);
+        assert_eq!(description["length"], vector.bytes.len());
+        assert!(
+            description["hex"]
+                .as_str()
+                .unwrap()
+                .starts_with("0c00050003000200")
+        );
+        let fields = description["fields"].as_array().unwrap();
+        let paths: Vec<&str> = fields.iter().map(|f| f["path"].as_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "header.blockLength",
+                "header.templateId",
+                "header.schemaId",
+                "header.version",
+                "bid",
+                "side",
+                "note.length",
+                "note"
+            ]
+        );
+        assert_eq!(fields[4]["offset"], 8);
+        assert_eq!(fields[4]["type"], "int64");
+        assert_eq!(fields[5]["type"], "Side");
+        assert_eq!(fields[5]["value"], "Sell");
+        assert_eq!(fields[7]["offset"], 22);
+    }
+
+    #[test]
+    fn test_verify() {
+        let suite = suite();
+        let canonical = suite.vector("Quote").unwrap().bytes.clone();
+        assert_eq!(
+            suite.verify("Quote", &canonical),
+            Some(VectorOutcome::Match)
+        );
+        assert_eq!(suite.verify("Unknown", &canonical), None);
+
+        let mut wrong = canonical.clone();
+        wrong[9] ^= 0xff;
+        assert_eq!(
+            suite.verify("Quote", &wrong),
+            Some(VectorOutcome::ByteMismatch {
+                offset: 9,
+                expected: canonical[9],
+                actual: canonical[9] ^ 0xff,
+                field: Some("bid".to_string()),
+            })
+        );
+
+        // Offsets 17..20 are padding up to the declared block length.
+        wrong = canonical.clone();
+        wrong[18] = 1;
+        let outcome = suite.verify("Quote", &wrong).unwrap();
+        assert!(matches!(
+            outcome,
+            VectorOutcome::ByteMismatch { field: None, .. }
+        ));
+
+        let outcome = suite.verify("Quote", &canonical[..10]).unwrap();
+        assert_eq!(
+            outcome.to_string(),
+            format!("length is 10, expected {}", canonical.len())
+        );
+    }
+
+    #[test]
+    fn test_write_and_verify_dir() {
+        let suite = suite();
+        let dir = tempfile::tempdir().expect("tempdir");
+        suite.write_to(dir.path()).expect("write suite");
+
+        let index: Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join(INDEX_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(index["vectors"][0]["binary"], "Quote.bin");
+        let description: Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join("Quote.json")).unwrap())
+                .unwrap();
+        assert_eq!(description, suite.describe(&suite.vectors[0]));
+
+        let report = suite.verify_dir(dir.path()).unwrap();
+        assert!(report.is_conformant());
+        assert_eq!(report.to_string(), "Quote: ok\n");
+
+        let empty = tempfile::tempdir().expect("tempdir");
+        let report = suite.verify_dir(empty.path()).unwrap();
+        assert!(!report.is_conformant());
+        assert_eq!(report.failures().count(), 1);
+        assert_eq!(report.to_string(), "Quote: missing\n");
+    }
+}
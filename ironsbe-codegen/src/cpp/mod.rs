@@ -0,0 +1,1299 @@
+//! C++ code generation.
+//!
+//! Emits a single header-only file of flyweights over `char` buffers. The
+//! flyweights read and write the same wire layout as the Rust output, so
+//! messages can be exchanged freely between the two.
+
+use ironsbe_schema::ir::{
+    CompositeFieldInfo, EnumVariant, ResolvedField, ResolvedGroup, ResolvedMessage, ResolvedType,
+    ResolvedVarData, SchemaIr, SetVariant, TypeKind, to_pascal_case, to_snake_case,
+};
+use ironsbe_schema::types::{MemberKind, PrimitiveType};
+use std::collections::{HashMap, HashSet};
+
+use crate::lang::{
+    Constant, FieldKind, NullValue, Section, SourceWriter, constant, entry_block_length,
+    escape_keyword, escape_string, field_kind, member_kind, null_value, sections, to_camel_case,
+};
+
+/// C++ reserved words that cannot be used as identifiers.
+const KEYWORDS: &[&str] = &[
+    "alignas",
+    "alignof",
+    "and",
+    "and_eq",
+    "asm",
+    "auto",
+    "bitand",
+    "bitor",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "compl",
+    "const",
+    "constexpr",
+    "const_cast",
+    "continue",
+    "decltype",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "dynamic_cast",
+    "else",
+    "enum",
+    "explicit",
+    "export",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "friend",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "mutable",
+    "namespace",
+    "new",
+    "noexcept",
+    "not",
+    "not_eq",
+    "nullptr",
+    "operator",
+    "or",
+    "or_eq",
+    "private",
+    "protected",
+    "public",
+    "register",
+    "reinterpret_cast",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "static_assert",
+    "static_cast",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "typeid",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+    "xor",
+    "xor_eq",
+];
+
+/// Generator for header-only C++17 flyweights.
+pub struct CppGenerator<'a> {
+    ir: &'a SchemaIr,
+}
+
+impl<'a> CppGenerator<'a> {
+    /// Creates a new C++ generator.
+    #[must_use]
+    pub fn new(ir: &'a SchemaIr) -> Self {
+        Self { ir }
+    }
+
+    /// Generates the header for the schema, without the file header comment.
+    #[must_use]
+    pub fn generate(&self) -> String {
+        let mut w = SourceWriter::new("    ");
+        w.line("#pragma once");
+        w.blank();
+        for header in ["cmath", "cstddef", "cstdint", "cstring", "string_view"] {
+            w.line(format!("#include <{header}>"));
+        }
+        w.blank();
+        w.line("#if defined(__BYTE_ORDER__) && __BYTE_ORDER__ != __ORDER_LITTLE_ENDIAN__");
+        w.line("#error \"IronSBE C++ flyweights require a little-endian host\"");
+        w.line("#endif");
+        w.blank();
+
+        let namespace = namespace(&self.ir.package);
+        w.line(format!("namespace {namespace} {{"));
+        w.blank();
+        self.generate_constants(&mut w);
+        generate_detail(&mut w);
+        generate_message_header(&mut w);
+
+        let types = self.ir.sorted_types();
+        for resolved_type in &types {
+            match &resolved_type.kind {
+                TypeKind::Enum { encoding, variants } => {
+                    generate_enum(&mut w, &resolved_type.name, *encoding, variants);
+                }
+                TypeKind::Set { encoding, choices } => {
+                    generate_set(&mut w, &resolved_type.name, *encoding, choices);
+                }
+                _ => {}
+            }
+        }
+        for resolved_type in dependency_order(&types) {
+            if let TypeKind::Composite { fields } = &resolved_type.kind
+                && !resolved_type.name.eq_ignore_ascii_case("messageHeader")
+            {
+                generate_composite(
+                    &mut w,
+                    &resolved_type.name,
+                    fields,
+                    resolved_type.encoded_length,
+                );
+            }
+        }
+        for msg in &self.ir.messages {
+            self.generate_message(&mut w, msg);
+        }
+
+        w.line(format!("}}  // namespace {namespace}"));
+        w.finish()
+    }
+
+    /// Generates schema constants.
+    fn generate_constants(&self, w: &mut SourceWriter) {
+        w.line("/// Schema ID for this protocol.");
+        w.line(format!(
+            "constexpr std::uint16_t SCHEMA_ID = {};",
+            self.ir.schema_id
+        ));
+        w.line("/// Schema version for this protocol.");
+        w.line(format!(
+            "constexpr std::uint16_t SCHEMA_VERSION = {};",
+            self.ir.schema_version
+        ));
+        w.blank();
+    }
+
+    /// Generates the group flyweights, the decoder, and the encoder of a
+    /// message.
+    fn generate_message(&self, w: &mut SourceWriter, msg: &ResolvedMessage) {
+        if !msg.groups.is_empty() {
+            let module = identifier(&to_snake_case(&msg.name));
+            w.line(format!("/// Types for {} repeating groups.", msg.name));
+            w.line(format!("namespace {module} {{"));
+            w.blank();
+            for group in &msg.groups {
+                self.generate_group(w, group);
+            }
+            w.line(format!("}}  // namespace {module}"));
+            w.blank();
+        }
+        let path = if msg.groups.is_empty() {
+            String::new()
+        } else {
+            format!("{}::", identifier(&to_snake_case(&msg.name)))
+        };
+        let sections = sections(&msg.groups, &msg.var_data);
+
+        // Decoder
+        let decoder = msg.decoder_name();
+        w.line(format!("/// {} Decoder (zero-copy).", msg.name));
+        section_order_doc(w, &sections);
+        w.open(format!("class {decoder} {{"));
+        w.label("public:");
+        w.line("/// Template ID for this message.");
+        w.line(format!(
+            "static constexpr std::uint16_t TEMPLATE_ID = {};",
+            msg.template_id
+        ));
+        w.line("/// Block length of the fixed portion.");
+        w.line(format!(
+            "static constexpr std::uint16_t BLOCK_LENGTH = {};",
+            msg.block_length
+        ));
+        w.blank();
+        w.line("/// Wraps `buffer` at `offset`, the start of the root block after the header.");
+        w.line(format!(
+            "{decoder}(const char* buffer, std::size_t offset, std::uint16_t actingVersion) noexcept"
+        ));
+        w.line("    : buffer_(buffer), offset_(offset), actingVersion_(actingVersion) {}");
+        w.blank();
+        w.line("/// Returns the schema version the message was encoded with.");
+        w.line("std::uint16_t actingVersion() const noexcept { return actingVersion_; }");
+        w.blank();
+        w.line("/// Returns the encoded length of the message, including the header.");
+        w.open("std::size_t encodedLength() const noexcept {");
+        end_offset(w, &sections, "offset_ + BLOCK_LENGTH", &path);
+        w.line("return MessageHeader::ENCODED_LENGTH + end - offset_;");
+        w.close("}");
+        w.blank();
+        for field in &msg.fields {
+            self.field_getter(w, field, "offset_");
+        }
+        section_getters(w, &sections, &path);
+        w.label("private:");
+        section_offsets(w, &sections, "offset_ + BLOCK_LENGTH", &path);
+        w.line("const char* buffer_;");
+        w.line("std::size_t offset_;");
+        w.line("std::uint16_t actingVersion_;");
+        w.close("};");
+        w.blank();
+
+        // Encoder
+        let encoder = msg.encoder_name();
+        let block_end = "offset_ + MessageHeader::ENCODED_LENGTH + BLOCK_LENGTH";
+        w.line(format!("/// {} Encoder.", msg.name));
+        section_order_doc(w, &sections);
+        w.open(format!("class {encoder} {{"));
+        w.label("public:");
+        w.line("/// Template ID for this message.");
+        w.line(format!(
+            "static constexpr std::uint16_t TEMPLATE_ID = {};",
+            msg.template_id
+        ));
+        w.line("/// Block length of the fixed portion.");
+        w.line(format!(
+            "static constexpr std::uint16_t BLOCK_LENGTH = {};",
+            msg.block_length
+        ));
+        w.blank();
+        w.line("/// Wraps `buffer` at `offset`, writing the message header.");
+        w.open(format!(
+            "{encoder}(char* buffer, std::size_t offset) noexcept : buffer_(buffer), offset_(offset) {{"
+        ));
+        w.line("MessageHeader{BLOCK_LENGTH, TEMPLATE_ID, SCHEMA_ID, SCHEMA_VERSION}.write(buffer, offset);");
+        w.close("}");
+        w.blank();
+        w.line("/// Returns the encoded length of the message, including the header.");
+        w.line("///");
+        w.line("/// Only meaningful once every section has been written.");
+        w.open("std::size_t encodedLength() const noexcept {");
+        end_offset(w, &sections, block_end, &path);
+        w.line("return end - offset_;");
+        w.close("}");
+        w.blank();
+        for field in &msg.fields {
+            self.field_setter(
+                w,
+                field,
+                "offset_ + MessageHeader::ENCODED_LENGTH",
+                &encoder,
+            );
+        }
+        section_setters(w, &sections, &path, &encoder);
+        w.label("private:");
+        section_offsets(w, &sections, block_end, &path);
+        w.line("char* buffer_;");
+        w.line("std::size_t offset_;");
+        w.close("};");
+        w.blank();
+    }
+
+    /// Generates the flyweights of `group`, after those of its nested groups.
+    fn generate_group(&self, w: &mut SourceWriter, group: &ResolvedGroup) {
+        for nested in &group.nested_groups {
+            self.generate_group(w, nested);
+        }
+        let sections = sections(&group.nested_groups, &group.var_data);
+        let flat = sections.is_empty();
+        let group_decoder = group.decoder_name();
+        let entry_decoder = group.entry_decoder_name();
+        let group_encoder = group.encoder_name();
+        let entry_encoder = group.entry_encoder_name();
+        let dimension = &group.dimension;
+        let header_length = if dimension.is_standard() {
+            "detail::GROUP_HEADER_LENGTH".to_string()
+        } else {
+            dimension.encoded_length.to_string()
+        };
+        let block_length_type = cpp_type(dimension.block_length_type);
+        let count_type = cpp_type(dimension.num_in_group_type);
+        let block_length_at = header_offset(dimension.block_length_offset);
+        let count_at = header_offset(dimension.num_in_group_offset);
+        let load_block_length =
+            format!("detail::load<{block_length_type}>(buffer, {block_length_at})");
+
+        // Entry decoder
+        w.line(format!("/// {} Entry Decoder.", group.name));
+        section_order_doc(w, &sections);
+        w.open(format!("class {entry_decoder} {{"));
+        w.label("public:");
+        w.line(format!(
+            "{entry_decoder}(const char* buffer, std::size_t offset, std::uint16_t blockLength) noexcept"
+        ));
+        w.line("    : buffer_(buffer), offset_(offset), blockLength_(blockLength) {}");
+        w.blank();
+        w.line("/// Returns the encoded length of this entry, including nested sections.");
+        w.open("std::size_t encodedLength() const noexcept {");
+        end_offset(w, &sections, "offset_ + blockLength_", "");
+        w.line("return end - offset_;");
+        w.close("}");
+        w.blank();
+        for field in &group.fields {
+            self.field_getter(w, field, "offset_");
+        }
+        section_getters(w, &sections, "");
+        w.label("private:");
+        section_offsets(w, &sections, "offset_ + blockLength_", "");
+        w.line("const char* buffer_;");
+        w.line("std::size_t offset_;");
+        w.line("std::uint16_t blockLength_;");
+        w.close("};");
+        w.blank();
+
+        // Group decoder
+        w.line(format!("/// {} Group Decoder.", group.name));
+        w.open(format!("class {group_decoder} {{"));
+        w.label("public:");
+        w.line("/// Wraps `buffer` at the group header position.");
+        w.line(format!(
+            "{group_decoder}(const char* buffer, std::size_t offset) noexcept"
+        ));
+        w.line("    : buffer_(buffer),");
+        w.line("      start_(offset),");
+        if dimension.block_length_type == PrimitiveType::Uint16 {
+            w.line(format!("      blockLength_({load_block_length}),"));
+        } else {
+            w.line(format!(
+                "      blockLength_(static_cast<std::uint16_t>({load_block_length})),"
+            ));
+        }
+        w.line(format!(
+            "      count_(detail::load<{count_type}>(buffer, {count_at})),"
+        ));
+        w.line(format!("      offset_(offset + {header_length}) {{}}"));
+        w.blank();
+        w.line("/// Returns the number of entries in the group.");
+        w.line(format!(
+            "{count_type} count() const noexcept {{ return count_; }}"
+        ));
+        w.line("/// Returns true if the group is empty.");
+        w.line("bool empty() const noexcept { return count_ == 0; }");
+        w.line("/// Returns true if `next()` has entries left to return.");
+        w.line("bool hasNext() const noexcept { return index_ < count_; }");
+        w.blank();
+        w.line("/// Returns the next entry. Requires `hasNext()`.");
+        w.open(format!("{entry_decoder} next() noexcept {{"));
+        w.line(format!(
+            "{entry_decoder} entry(buffer_, offset_, blockLength_);"
+        ));
+        w.line("offset_ += entry.encodedLength();");
+        w.line("++index_;");
+        w.line("return entry;");
+        w.close("}");
+        w.blank();
+        w.line("/// Returns the total encoded length of this group (header + all entries).");
+        w.open("std::size_t encodedLength() const noexcept {");
+        if flat {
+            w.line(format!(
+                "return {header_length} + std::size_t{{blockLength_}} * count_;"
+            ));
+        } else {
+            w.line(format!("std::size_t end = start_ + {header_length};"));
+            w.open(format!("for ({count_type} i = 0; i < count_; ++i) {{"));
+            w.line(format!(
+                "end += {entry_decoder}(buffer_, end, blockLength_).encodedLength();"
+            ));
+            w.close("}");
+            w.line("return end - start_;");
+        }
+        w.close("}");
+        w.blank();
+        w.label("private:");
+        w.line("const char* buffer_;");
+        w.line("std::size_t start_;");
+        w.line("std::uint16_t blockLength_;");
+        w.line(format!("{count_type} count_;"));
+        w.line(format!("{count_type} index_ = 0;"));
+        w.line("std::size_t offset_;");
+        w.close("};");
+        w.blank();
+
+        // Entry encoder
+        w.line(format!("/// {} Entry Encoder.", group.name));
+        section_order_doc(w, &sections);
+        w.open(format!("class {entry_encoder} {{"));
+        w.label("public:");
+        w.line("/// Block length of each entry.");
+        w.line(format!(
+            "static constexpr std::uint16_t BLOCK_LENGTH = {};",
+            entry_block_length(group)
+        ));
+        w.blank();
+        w.line(format!(
+            "{entry_encoder}(char* buffer, std::size_t offset) noexcept : buffer_(buffer), offset_(offset) {{}}"
+        ));
+        w.blank();
+        for field in &group.fields {
+            self.field_setter(w, field, "offset_", &entry_encoder);
+        }
+        section_setters(w, &sections, "", &entry_encoder);
+        w.label("private:");
+        section_offsets(w, &sections, "offset_ + BLOCK_LENGTH", "");
+        w.line("char* buffer_;");
+        w.line("std::size_t offset_;");
+        w.close("};");
+        w.blank();
+
+        // Group encoder
+        w.line(format!("/// {} Group Encoder.", group.name));
+        w.open(format!("class {group_encoder} {{"));
+        w.label("public:");
+        w.line("/// Wraps `buffer` at the group header position, writing the header.");
+        w.line(format!(
+            "{group_encoder}(char* buffer, std::size_t offset, {count_type} count) noexcept"
+        ));
+        w.open(format!(
+            "    : buffer_(buffer), start_(offset), count_(count), offset_(offset + {header_length}) {{"
+        ));
+        w.line(format!(
+            "detail::store<{block_length_type}>(buffer, {block_length_at}, {entry_encoder}::BLOCK_LENGTH);"
+        ));
+        w.line(format!(
+            "detail::store<{count_type}>(buffer, {count_at}, count);"
+        ));
+        w.close("}");
+        w.blank();
+        w.line("/// Returns true if `next()` has entries left to write.");
+        w.line("bool hasNext() const noexcept { return index_ < count_; }");
+        w.blank();
+        if flat {
+            w.line("/// Returns the next entry encoder. Requires `hasNext()`.");
+            w.open(format!("{entry_encoder} next() noexcept {{"));
+            w.line("const std::size_t offset = offset_;");
+            w.line(format!("offset_ += {entry_encoder}::BLOCK_LENGTH;"));
+        } else {
+            w.line("/// Returns the next entry encoder. Requires `hasNext()`.");
+            w.line("///");
+            w.line("/// The previous entry, including its nested sections, must be complete.");
+            w.open(format!("{entry_encoder} next() noexcept {{"));
+            w.open("if (index_ > 0) {");
+            w.line(format!(
+                "offset_ += {entry_decoder}(buffer_, offset_, {entry_encoder}::BLOCK_LENGTH).encodedLength();"
+            ));
+            w.close("}");
+            w.line("const std::size_t offset = offset_;");
+        }
+        w.line("++index_;");
+        w.line(format!("return {entry_encoder}(buffer_, offset);"));
+        w.close("}");
+        w.blank();
+        w.line("/// Returns the total encoded length of this group (header + all entries).");
+        w.line("///");
+        w.line("/// Only meaningful once every entry has been written.");
+        w.open("std::size_t encodedLength() const noexcept {");
+        w.line(format!(
+            "return {group_decoder}(buffer_, start_).encodedLength();"
+        ));
+        w.close("}");
+        w.blank();
+        w.label("private:");
+        w.line("char* buffer_;");
+        w.line("std::size_t start_;");
+        w.line(format!("{count_type} count_;"));
+        w.line(format!("{count_type} index_ = 0;"));
+        w.line("std::size_t offset_;");
+        w.close("};");
+        w.blank();
+    }
+
+    /// Generates the getter of a fixed-block field read at `base`.
+    fn field_getter(&self, w: &mut SourceWriter, field: &ResolvedField, base: &str) {
+        let getter = identifier(&to_camel_case(&field.name));
+        if let Some(value) = &field.constant {
+            w.line(format!(
+                "/// Field: {} (id={}, constant).",
+                field.name, field.id
+            ));
+            let value = constant(value, field.primitive_type, field.is_array);
+            constant_getter(w, &getter, field.primitive_type, value);
+            w.blank();
+            return;
+        }
+        let at = format!("{base} + {}", field.offset);
+        w.line(format!(
+            "/// Field: {} (id={}, offset={}).",
+            field.name, field.id, field.offset
+        ));
+        match field_kind(self.ir, field) {
+            FieldKind::Primitive(prim) => {
+                let ty = cpp_type(prim);
+                w.line(format!(
+                    "{ty} {getter}() const noexcept {{ return detail::load<{ty}>(buffer_, {at}); }}"
+                ));
+                if let Some(null) = null_value(field) {
+                    let has = format!("has{}", to_pascal_case(&field.name));
+                    let present = match null {
+                        NullValue::NaN => format!("!std::isnan({getter}())"),
+                        NullValue::Float(value) => {
+                            format!("{getter}() != static_cast<{ty}>({value:?})")
+                        }
+                        NullValue::Int(value) => {
+                            format!("{getter}() != static_cast<{ty}>({})", int_literal(value))
+                        }
+                    };
+                    w.line(format!("/// Returns true if {} is not null.", field.name));
+                    w.line(format!(
+                        "bool {has}() const noexcept {{ return {present}; }}"
+                    ));
+                }
+            }
+            FieldKind::Array { element, length } => {
+                let length = if element == PrimitiveType::Char {
+                    format!("detail::trimmedLength(buffer_ + {at}, {length})")
+                } else {
+                    length.to_string()
+                };
+                w.line(format!(
+                    "std::string_view {getter}() const noexcept {{ return std::string_view(buffer_ + {at}, {length}); }}"
+                ));
+            }
+            FieldKind::Enum { name, encoding } => {
+                let ty = cpp_type(enum_encoding(encoding));
+                w.line(format!(
+                    "{name} {getter}() const noexcept {{ return {}(detail::load<{ty}>(buffer_, {at})); }}",
+                    from_raw_name(&name)
+                ));
+            }
+            FieldKind::Set { name, encoding } => {
+                let ty = cpp_type(enum_encoding(encoding));
+                w.line(format!(
+                    "{name} {getter}() const noexcept {{ return {name}(detail::load<{ty}>(buffer_, {at})); }}"
+                ));
+            }
+            FieldKind::Composite { name } => {
+                w.line(format!(
+                    "{name} {getter}() const noexcept {{ return {name}(buffer_, {at}); }}"
+                ));
+            }
+        }
+        w.blank();
+    }
+
+    /// Generates the setter of a fixed-block field written at `base`.
+    fn field_setter(&self, w: &mut SourceWriter, field: &ResolvedField, base: &str, owner: &str) {
+        if field.constant.is_some() {
+            return;
+        }
+        let setter = identifier(&to_camel_case(&field.name));
+        let at = format!("{base} + {}", field.offset);
+        w.line(format!(
+            "/// Set field: {} (id={}, offset={}).",
+            field.name, field.id, field.offset
+        ));
+        match field_kind(self.ir, field) {
+            FieldKind::Primitive(prim) => {
+                let ty = cpp_type(prim);
+                w.open(format!("{owner}& {setter}({ty} value) noexcept {{"));
+                w.line(format!("detail::store<{ty}>(buffer_, {at}, value);"));
+            }
+            FieldKind::Array { length, .. } => {
+                w.open(format!(
+                    "{owner}& {setter}(std::string_view value) noexcept {{"
+                ));
+                w.line(format!(
+                    "detail::copyPadded(buffer_ + {at}, {length}, value);"
+                ));
+            }
+            FieldKind::Enum { name, encoding } => {
+                let ty = cpp_type(enum_encoding(encoding));
+                w.open(format!("{owner}& {setter}({name} value) noexcept {{"));
+                w.line(format!(
+                    "detail::store<{ty}>(buffer_, {at}, static_cast<{ty}>(value));"
+                ));
+            }
+            FieldKind::Set { name, encoding } => {
+                let ty = cpp_type(enum_encoding(encoding));
+                w.open(format!("{owner}& {setter}({name} value) noexcept {{"));
+                w.line(format!("detail::store<{ty}>(buffer_, {at}, value.raw());"));
+            }
+            FieldKind::Composite { name } => {
+                w.line(format!(
+                    "{name}Encoder {setter}() noexcept {{ return {name}Encoder(buffer_, {at}); }}"
+                ));
+                w.blank();
+                return;
+            }
+        }
+        w.line("return *this;");
+        w.close("}");
+        w.blank();
+    }
+}
+
+/// Generates the shared load/store helpers.
+fn generate_detail(w: &mut SourceWriter) {
+    w.line("namespace detail {");
+    w.blank();
+    w.line("constexpr std::size_t GROUP_HEADER_LENGTH = 4;");
+    w.blank();
+    w.line("template <typename T>");
+    w.open("inline T load(const char* buffer, std::size_t offset) noexcept {");
+    w.line("T value;");
+    w.line("std::memcpy(&value, buffer + offset, sizeof(T));");
+    w.line("return value;");
+    w.close("}");
+    w.blank();
+    w.line("template <typename T>");
+    w.open("inline void store(char* buffer, std::size_t offset, T value) noexcept {");
+    w.line("std::memcpy(buffer + offset, &value, sizeof(T));");
+    w.close("}");
+    w.blank();
+    w.line("/// Returns the length of a char array up to its first NUL.");
+    w.open("inline std::size_t trimmedLength(const char* data, std::size_t length) noexcept {");
+    w.line("const void* nul = std::memchr(data, 0, length);");
+    w.line("return nul == nullptr ? length : static_cast<std::size_t>(static_cast<const char*>(nul) - data);");
+    w.close("}");
+    w.blank();
+    w.line("/// Copies `value` into a `length`-byte array, zero-padding the rest.");
+    w.open(
+        "inline void copyPadded(char* data, std::size_t length, std::string_view value) noexcept {",
+    );
+    w.line("const std::size_t n = value.size() < length ? value.size() : length;");
+    w.line("std::memcpy(data, value.data(), n);");
+    w.line("std::memset(data + n, 0, length - n);");
+    w.close("}");
+    w.blank();
+    w.line("/// Returns the var data field at `offset`, whose length prefix is an `L`.");
+    w.line("template <typename L = std::uint16_t>");
+    w.open("inline std::string_view varData(const char* buffer, std::size_t offset) noexcept {");
+    w.line("return std::string_view(buffer + offset + sizeof(L), load<L>(buffer, offset));");
+    w.close("}");
+    w.blank();
+    w.line("/// Returns the encoded length of the var data field at `offset`.");
+    w.line("template <typename L = std::uint16_t>");
+    w.open("inline std::size_t varDataLength(const char* buffer, std::size_t offset) noexcept {");
+    w.line("return sizeof(L) + load<L>(buffer, offset);");
+    w.close("}");
+    w.blank();
+    w.line(
+        "/// Writes a var data field at `offset`, truncated to the largest length an `L` holds.",
+    );
+    w.line("template <typename L = std::uint16_t>");
+    w.open("inline void putVarData(char* buffer, std::size_t offset, std::string_view value) noexcept {");
+    w.line("constexpr std::size_t max = static_cast<L>(~L{});");
+    w.line("const std::size_t n = value.size() < max ? value.size() : max;");
+    w.line("store<L>(buffer, offset, static_cast<L>(n));");
+    w.line("std::memcpy(buffer + offset + sizeof(L), value.data(), n);");
+    w.close("}");
+    w.blank();
+    w.line("}  // namespace detail");
+    w.blank();
+}
+
+/// Generates the message header struct.
+fn generate_message_header(w: &mut SourceWriter) {
+    w.line("/// SBE message header preceding every message.");
+    w.open("struct MessageHeader {");
+    w.line("/// Encoded length of the header in bytes.");
+    w.line("static constexpr std::size_t ENCODED_LENGTH = 8;");
+    w.blank();
+    w.line("std::uint16_t blockLength;");
+    w.line("std::uint16_t templateId;");
+    w.line("std::uint16_t schemaId;");
+    w.line("std::uint16_t version;");
+    w.blank();
+    w.line("/// Reads the header at `offset`.");
+    w.open("static MessageHeader read(const char* buffer, std::size_t offset) noexcept {");
+    w.line("return MessageHeader{");
+    w.line("    detail::load<std::uint16_t>(buffer, offset),");
+    w.line("    detail::load<std::uint16_t>(buffer, offset + 2),");
+    w.line("    detail::load<std::uint16_t>(buffer, offset + 4),");
+    w.line("    detail::load<std::uint16_t>(buffer, offset + 6),");
+    w.line("};");
+    w.close("}");
+    w.blank();
+    w.line("/// Writes the header at `offset`.");
+    w.open("void write(char* buffer, std::size_t offset) const noexcept {");
+    w.line("detail::store<std::uint16_t>(buffer, offset, blockLength);");
+    w.line("detail::store<std::uint16_t>(buffer, offset + 2, templateId);");
+    w.line("detail::store<std::uint16_t>(buffer, offset + 4, schemaId);");
+    w.line("detail::store<std::uint16_t>(buffer, offset + 6, version);");
+    w.close("}");
+    w.close("};");
+    w.blank();
+}
+
+/// Generates an enum class and its conversion from the raw value.
+fn generate_enum(
+    w: &mut SourceWriter,
+    name: &str,
+    encoding: PrimitiveType,
+    variants: &[EnumVariant],
+) {
+    let name = to_pascal_case(name);
+    let ty = cpp_type(enum_encoding(encoding));
+    w.line(format!("/// {name} enum."));
+    w.open(format!("enum class {name} : {ty} {{"));
+    for variant in variants {
+        w.line(format!(
+            "{} = {},",
+            variant_name(&variant.name),
+            variant.value
+        ));
+    }
+    w.close("};");
+    w.blank();
+
+    w.line(format!(
+        "/// Converts a raw value to {name}, mapping unknown values to the first variant."
+    ));
+    w.open(format!(
+        "constexpr {name} {}({ty} value) noexcept {{",
+        from_raw_name(&name)
+    ));
+    match variants.first() {
+        Some(first) => {
+            w.open("switch (value) {");
+            for variant in variants {
+                w.line(format!(
+                    "case {}: return {name}::{};",
+                    variant.value,
+                    variant_name(&variant.name)
+                ));
+            }
+            w.line(format!(
+                "default: return {name}::{};",
+                variant_name(&first.name)
+            ));
+            w.close("}");
+        }
+        None => w.line(format!("return static_cast<{name}>(value);")),
+    }
+    w.close("}");
+    w.blank();
+}
+
+/// Generates a bitfield set wrapper.
+fn generate_set(w: &mut SourceWriter, name: &str, encoding: PrimitiveType, choices: &[SetVariant]) {
+    let name = to_pascal_case(name);
+    let ty = cpp_type(enum_encoding(encoding));
+    w.line(format!("/// {name} bitfield set."));
+    w.open(format!("class {name} {{"));
+    w.label("public:");
+    w.line(format!("constexpr {name}() noexcept = default;"));
+    w.line("/// Creates from the raw value.");
+    w.line(format!(
+        "constexpr explicit {name}({ty} raw) noexcept : raw_(raw) {{}}"
+    ));
+    w.line("/// Returns the raw value.");
+    w.line(format!(
+        "constexpr {ty} raw() const noexcept {{ return raw_; }}"
+    ));
+    w.line("/// Returns true if `bit` is set.");
+    w.line(
+        "constexpr bool isSet(unsigned bit) const noexcept { return ((raw_ >> bit) & 1U) != 0; }",
+    );
+    w.line("/// Sets `bit`.");
+    w.open(format!("constexpr {name}& set(unsigned bit) noexcept {{"));
+    w.line(format!(
+        "raw_ = static_cast<{ty}>(raw_ | ({ty}{{1}} << bit));"
+    ));
+    w.line("return *this;");
+    w.close("}");
+    w.line("/// Clears `bit`.");
+    w.open(format!("constexpr {name}& clear(unsigned bit) noexcept {{"));
+    w.line(format!(
+        "raw_ = static_cast<{ty}>(raw_ & ~({ty}{{1}} << bit));"
+    ));
+    w.line("return *this;");
+    w.close("}");
+    for choice in choices {
+        let accessor = identifier(&to_camel_case(&choice.name));
+        let bit = choice.bit_position;
+        w.blank();
+        w.line(format!("/// Returns true if {} is set.", choice.name));
+        w.line(format!(
+            "constexpr bool {accessor}() const noexcept {{ return isSet({bit}); }}"
+        ));
+        w.line(format!("/// Sets or clears {}.", choice.name));
+        w.line(format!(
+            "constexpr {name}& {accessor}(bool value) noexcept {{ return value ? set({bit}) : clear({bit}); }}"
+        ));
+    }
+    w.label("private:");
+    w.line(format!("{ty} raw_ = 0;"));
+    w.close("};");
+    w.blank();
+}
+
+/// Generates the decoder and encoder flyweights of a composite.
+fn generate_composite(
+    w: &mut SourceWriter,
+    name: &str,
+    fields: &[CompositeFieldInfo],
+    encoded_length: usize,
+) {
+    let name = to_pascal_case(name);
+    for (class, encoder) in [(name.clone(), false), (format!("{name}Encoder"), true)] {
+        let (pointer, what) = if encoder {
+            ("char*", "Encoder")
+        } else {
+            ("const char*", "Decoder (zero-copy)")
+        };
+        w.line(format!("/// {name} {what}."));
+        w.open(format!("class {class} {{"));
+        w.label("public:");
+        w.line(format!("/// Encoded length of {name} in bytes."));
+        w.line(format!(
+            "static constexpr std::size_t ENCODED_LENGTH = {encoded_length};"
+        ));
+        w.blank();
+        w.line(format!(
+            "{class}({pointer} buffer, std::size_t offset) noexcept : buffer_(buffer), offset_(offset) {{}}"
+        ));
+        w.blank();
+        for field in fields {
+            let accessor = identifier(&to_camel_case(&field.name));
+            let at = format!("offset_ + {}", field.offset);
+            if let Some(value) = &field.constant {
+                if !encoder {
+                    w.line(format!("/// Gets the {} field.", field.name));
+                    let value = constant(value, field.primitive_type, false);
+                    constant_getter(w, &accessor, field.primitive_type, value);
+                }
+            } else if encoder {
+                w.line(format!("/// Sets the {} field.", field.name));
+                composite_member_setter(w, &class, &accessor, field, &at);
+            } else {
+                w.line(format!("/// Gets the {} field.", field.name));
+                composite_member_getter(w, &accessor, field, &at);
+            }
+        }
+        w.label("private:");
+        w.line(format!("{pointer} buffer_;"));
+        w.line("std::size_t offset_;");
+        w.close("};");
+        w.blank();
+    }
+}
+
+/// Returns `types` with every composite after the composites it nests,
+/// keeping name order otherwise, since C++ needs a class defined before a
+/// member function returns it by value.
+fn dependency_order<'a>(types: &[&'a ResolvedType]) -> Vec<&'a ResolvedType> {
+    fn visit<'a>(
+        ty: &'a ResolvedType,
+        by_name: &HashMap<&str, &'a ResolvedType>,
+        seen: &mut HashSet<&'a str>,
+        out: &mut Vec<&'a ResolvedType>,
+    ) {
+        if !seen.insert(ty.name.as_str()) {
+            return;
+        }
+        if let TypeKind::Composite { fields } = &ty.kind {
+            for field in fields.iter().filter(|f| f.kind == MemberKind::Composite) {
+                if let Some(nested) = by_name.get(field.type_name.as_str()) {
+                    visit(nested, by_name, seen, out);
+                }
+            }
+        }
+        out.push(ty);
+    }
+
+    let by_name: HashMap<&str, &ResolvedType> =
+        types.iter().map(|ty| (ty.name.as_str(), *ty)).collect();
+    let mut seen = HashSet::new();
+    let mut out = Vec::with_capacity(types.len());
+    for ty in types {
+        visit(ty, &by_name, &mut seen, &mut out);
+    }
+    out
+}
+
+/// Writes the getter of a composite member read at `at`.
+fn composite_member_getter(
+    w: &mut SourceWriter,
+    accessor: &str,
+    field: &CompositeFieldInfo,
+    at: &str,
+) {
+    match member_kind(field) {
+        FieldKind::Enum { name, encoding } => {
+            let ty = cpp_type(enum_encoding(encoding));
+            w.line(format!(
+                "{name} {accessor}() const noexcept {{ return {}(detail::load<{ty}>(buffer_, {at})); }}",
+                from_raw_name(&name)
+            ));
+        }
+        FieldKind::Set { name, encoding } => {
+            let ty = cpp_type(enum_encoding(encoding));
+            w.line(format!(
+                "{name} {accessor}() const noexcept {{ return {name}(detail::load<{ty}>(buffer_, {at})); }}"
+            ));
+        }
+        FieldKind::Composite { name } => {
+            w.line(format!(
+                "{name} {accessor}() const noexcept {{ return {name}(buffer_, {at}); }}"
+            ));
+        }
+        FieldKind::Primitive(prim) | FieldKind::Array { element: prim, .. } => {
+            let ty = cpp_type(prim);
+            w.line(format!(
+                "{ty} {accessor}() const noexcept {{ return detail::load<{ty}>(buffer_, {at}); }}"
+            ));
+        }
+    }
+}
+
+/// Writes the setter of a composite member written at `at`.
+fn composite_member_setter(
+    w: &mut SourceWriter,
+    class: &str,
+    accessor: &str,
+    field: &CompositeFieldInfo,
+    at: &str,
+) {
+    match member_kind(field) {
+        FieldKind::Enum { name, encoding } => {
+            let ty = cpp_type(enum_encoding(encoding));
+            w.open(format!("{class}& {accessor}({name} value) noexcept {{"));
+            w.line(format!(
+                "detail::store<{ty}>(buffer_, {at}, static_cast<{ty}>(value));"
+            ));
+        }
+        FieldKind::Set { name, encoding } => {
+            let ty = cpp_type(enum_encoding(encoding));
+            w.open(format!("{class}& {accessor}({name} value) noexcept {{"));
+            w.line(format!("detail::store<{ty}>(buffer_, {at}, value.raw());"));
+        }
+        FieldKind::Composite { name } => {
+            w.line(format!(
+                "{name}Encoder {accessor}() noexcept {{ return {name}Encoder(buffer_, {at}); }}"
+            ));
+            return;
+        }
+        FieldKind::Primitive(prim) | FieldKind::Array { element: prim, .. } => {
+            let ty = cpp_type(prim);
+            w.open(format!("{class}& {accessor}({ty} value) noexcept {{"));
+            w.line(format!("detail::store<{ty}>(buffer_, {at}, value);"));
+        }
+    }
+    w.line("return *this;");
+    w.close("}");
+}
+
+/// Writes a getter returning a schema constant read as `prim`.
+fn constant_getter(
+    w: &mut SourceWriter,
+    getter: &str,
+    prim: Option<PrimitiveType>,
+    value: Constant<'_>,
+) {
+    let ty = cpp_type(prim.unwrap_or(PrimitiveType::Uint64));
+    let (ty, expr) = match value {
+        Constant::Enum { name, variant } => {
+            let expr = format!("{name}::{}", variant_name(variant));
+            (name, expr)
+        }
+        Constant::Int(value) => (
+            ty.to_string(),
+            format!("static_cast<{ty}>({})", int_literal(value)),
+        ),
+        Constant::Float(value) => (ty.to_string(), format!("static_cast<{ty}>({value:?})")),
+        Constant::Text(text) => (
+            "std::string_view".to_string(),
+            format!("\"{}\"", escape_string(text)),
+        ),
+        Constant::Invalid(text) => {
+            w.line(format!(
+                "static_assert(false, \"invalid constant value '{}' for {getter}\");",
+                escape_string(text)
+            ));
+            return;
+        }
+    };
+    w.line(format!(
+        "static constexpr {ty} {getter}() noexcept {{ return {expr}; }}"
+    ));
+}
+
+/// Writes a doc paragraph listing the trailing sections in wire order.
+fn section_order_doc(w: &mut SourceWriter, sections: &[Section<'_>]) {
+    if sections.is_empty() {
+        return;
+    }
+    let names: Vec<&str> = sections.iter().map(Section::name).collect();
+    w.line("///");
+    w.line(format!(
+        "/// Sections follow the fixed block in this order: {}.",
+        names.join(", ")
+    ));
+}
+
+/// Returns an expression for the encoded length of `section` at `offset`.
+fn section_length(section: &Section<'_>, path: &str) -> String {
+    match section {
+        Section::Group(group) => format!(
+            "{path}{}(buffer_, offset).encodedLength()",
+            group.decoder_name()
+        ),
+        Section::VarData(data) => format!(
+            "detail::varDataLength{}(buffer_, offset)",
+            length_type_argument(data)
+        ),
+    }
+}
+
+/// Returns the name of the private helper locating `section`.
+fn offset_fn(section: &Section<'_>) -> String {
+    format!("{}Offset", to_camel_case(section.name()))
+}
+
+/// Generates the private helpers locating each trailing section.
+fn section_offsets(w: &mut SourceWriter, sections: &[Section<'_>], block_end: &str, path: &str) {
+    let mut previous: Option<&Section<'_>> = None;
+    for section in sections {
+        w.line(format!("/// Offset of the `{}` section.", section.name()));
+        w.open(format!(
+            "std::size_t {}() const noexcept {{",
+            offset_fn(section)
+        ));
+        match previous {
+            None => w.line(format!("return {block_end};")),
+            Some(prev) => {
+                w.line(format!("const std::size_t offset = {}();", offset_fn(prev)));
+                w.line(format!("return offset + {};", section_length(prev, path)));
+            }
+        }
+        w.close("}");
+        w.blank();
+        previous = Some(section);
+    }
+}
+
+/// Generates the statements leaving the offset after the last section in a
+/// local named `end`.
+fn end_offset(w: &mut SourceWriter, sections: &[Section<'_>], block_end: &str, path: &str) {
+    match sections.last() {
+        None => w.line(format!("const std::size_t end = {block_end};")),
+        Some(last) => {
+            w.line(format!("const std::size_t offset = {}();", offset_fn(last)));
+            w.line(format!(
+                "const std::size_t end = offset + {};",
+                section_length(last, path)
+            ));
+        }
+    }
+}
+
+/// Generates the group and var data getters of a decoder.
+fn section_getters(w: &mut SourceWriter, sections: &[Section<'_>], path: &str) {
+    for section in sections {
+        let getter = identifier(&to_camel_case(section.name()));
+        let offset_fn = offset_fn(section);
+        match section {
+            Section::Group(group) => {
+                let decoder = group.decoder_name();
+                w.line(format!("/// Access {} repeating group.", group.name));
+                w.line(format!(
+                    "{path}{decoder} {getter}() const noexcept {{ return {path}{decoder}(buffer_, {offset_fn}()); }}"
+                ));
+            }
+            Section::VarData(data) => {
+                w.line(format!("/// Var data: {} (id={}).", data.name, data.id));
+                w.line(format!(
+                    "std::string_view {getter}() const noexcept {{ return detail::varData{}(buffer_, {offset_fn}()); }}",
+                    length_type_argument(data)
+                ));
+            }
+        }
+        w.blank();
+    }
+}
+
+/// Generates the group and var data setters of an encoder.
+fn section_setters(w: &mut SourceWriter, sections: &[Section<'_>], path: &str, owner: &str) {
+    for section in sections {
+        let name = to_camel_case(section.name());
+        let offset_fn = offset_fn(section);
+        match section {
+            Section::Group(group) => {
+                let encoder = group.encoder_name();
+                w.line(format!(
+                    "/// Begin encoding the {} repeating group.",
+                    group.name
+                ));
+                w.line("///");
+                w.line("/// All preceding groups must already be written.");
+                w.open(format!(
+                    "{path}{encoder} {name}Count({} count) noexcept {{",
+                    cpp_type(group.dimension.num_in_group_type)
+                ));
+                w.line(format!(
+                    "return {path}{encoder}(buffer_, {offset_fn}(), count);"
+                ));
+                w.close("}");
+            }
+            Section::VarData(data) => {
+                write_var_data_setter(w, data, &identifier(&name), &offset_fn, owner);
+            }
+        }
+        w.blank();
+    }
+}
+
+/// Generates a var data setter writing the length header and the bytes.
+fn write_var_data_setter(
+    w: &mut SourceWriter,
+    data: &ResolvedVarData,
+    setter: &str,
+    offset_fn: &str,
+    owner: &str,
+) {
+    w.line(format!("/// Set var data: {} (id={}).", data.name, data.id));
+    w.line("///");
+    w.line("/// All preceding groups and var data must already be written.");
+    w.open(format!(
+        "{owner}& {setter}(std::string_view value) noexcept {{"
+    ));
+    w.line(format!(
+        "detail::putVarData{}(buffer_, {offset_fn}(), value);",
+        length_type_argument(data)
+    ));
+    w.line("return *this;");
+    w.close("}");
+}
+
+/// Returns the template argument selecting the length prefix of a var data
+/// field, empty for the default `std::uint16_t`.
+fn length_type_argument(data: &ResolvedVarData) -> String {
+    match data.length_type {
+        PrimitiveType::Uint16 => String::new(),
+        other => format!("<{}>", cpp_type(other)),
+    }
+}
+
+/// Returns `offset`, or `offset + at` for a nonzero `at`.
+fn header_offset(at: usize) -> String {
+    if at == 0 {
+        "offset".to_string()
+    } else {
+        format!("offset + {at}")
+    }
+}
+
+/// Returns the C++ namespace for a schema package.
+fn namespace(package: &str) -> String {
+    package
+        .split('.')
+        .map(|segment| {
+            let segment: String = segment
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect();
+            identifier(&segment)
+        })
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// Escapes `name` if it is a C++ keyword.
+fn identifier(name: &str) -> String {
+    escape_keyword(name.to_string(), KEYWORDS)
+}
+
+/// Returns the C++ name of an enum variant.
+fn variant_name(name: &str) -> String {
+    identifier(&to_pascal_case(name))
+}
+
+/// Returns the name of the function converting a raw value to `enum_name`.
+fn from_raw_name(enum_name: &str) -> String {
+    format!("{}FromRaw", to_camel_case(enum_name))
+}
+
+/// Returns the integer type backing an enum or set.
+///
+/// `char` encodings are stored as `uint8_t`, like the Rust output.
+fn enum_encoding(encoding: PrimitiveType) -> PrimitiveType {
+    match encoding {
+        PrimitiveType::Char => PrimitiveType::Uint8,
+        other => other,
+    }
+}
+
+/// Returns the C++ type of a primitive.
+fn cpp_type(prim: PrimitiveType) -> &'static str {
+    match prim {
+        PrimitiveType::Char => "char",
+        PrimitiveType::Int8 => "std::int8_t",
+        PrimitiveType::Uint8 => "std::uint8_t",
+        PrimitiveType::Int16 => "std::int16_t",
+        PrimitiveType::Uint16 => "std::uint16_t",
+        PrimitiveType::Int32 => "std::int32_t",
+        PrimitiveType::Uint32 => "std::uint32_t",
+        PrimitiveType::Int64 => "std::int64_t",
+        PrimitiveType::Uint64 => "std::uint64_t",
+        PrimitiveType::Float => "float",
+        PrimitiveType::Double => "double",
+    }
+}
+
+/// Returns a C++ integer literal for `value` that is valid for any width.
+fn int_literal(value: i128) -> String {
+    if value == i128::from(i64::MIN) {
+        "INT64_MIN".to_string()
+    } else if value > i128::from(i64::MAX) {
+        format!("{value}ULL")
+    } else if value > i128::from(i32::MAX) || value < i128::from(i32::MIN) {
+        format!("{value}LL")
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironsbe_schema::parse_schema;
+
+    #[test]
+    fn test_generate_cpp_flyweights() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test.feed" id="1" version="2" byteOrder="littleEndian">
+    <types>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+            <validValue name="Sell">2</validValue>
+        </enum>
+    </types>
+    <sbe:message name="Trade" id="3" blockLength="17">
+        <field name="new" id="1" type="uint64" offset="0"/>
+        <field name="px" id="2" type="int64" offset="8" presence="optional"/>
+        <field name="side" id="3" type="Side" offset="16"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+        let code = CppGenerator::new(&ir).generate();
+
+        assert!(code.contains("namespace test::feed {"));
+        assert!(code.contains("constexpr std::uint16_t SCHEMA_VERSION = 2;"));
+        assert!(code.contains("enum class Side : std::uint8_t {"));
+        assert!(code.contains("default: return Side::Buy;"));
+        assert!(code.contains("class TradeDecoder {"));
+        assert!(code.contains("std::uint64_t new_() const noexcept"));
+        assert!(code.contains(
+            "bool hasPx() const noexcept { return px() != static_cast<std::int64_t>(INT64_MIN); }"
+        ));
+        assert!(code.contains("TradeEncoder& side(Side value) noexcept {"));
+    }
+
+    #[test]
+    fn test_int_literal() {
+        assert_eq!(int_literal(255), "255");
+        assert_eq!(int_literal(4_294_967_295), "4294967295LL");
+        assert_eq!(int_literal(i128::from(u64::MAX)), "18446744073709551615ULL");
+        assert_eq!(int_literal(i128::from(i64::MIN)), "INT64_MIN");
+    }
+}
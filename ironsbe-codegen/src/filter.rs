@@ -0,0 +1,188 @@
+//! Message filtering and identifier renaming applied before generation.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use ironsbe_schema::ir::{
+    ConstantValue, ResolvedField, ResolvedGroup, ResolvedMessage, ResolvedVarData, SchemaIr,
+    TypeKind, to_pascal_case, to_snake_case,
+};
+
+use crate::config::CodegenConfig;
+
+/// Returns the IR the generators should see under `config`.
+///
+/// The input is borrowed unchanged when no filter or rename is configured.
+pub(crate) fn apply<'a>(ir: &'a SchemaIr, config: &CodegenConfig) -> Cow<'a, SchemaIr> {
+    if !config.has_message_filters() && config.renames.is_empty() {
+        return Cow::Borrowed(ir);
+    }
+
+    let mut ir = ir.clone();
+    if config.has_message_filters() {
+        select_messages(&mut ir, config);
+    }
+    if !config.renames.is_empty() {
+        rename(&mut ir, &config.renames);
+    }
+    Cow::Owned(ir)
+}
+
+/// Drops unselected messages and the types only they referenced.
+fn select_messages(ir: &mut SchemaIr, config: &CodegenConfig) {
+    let (kept, dropped): (Vec<_>, Vec<_>) = ir
+        .messages
+        .drain(..)
+        .partition(|msg| config.is_message_selected(&msg.name, msg.template_id));
+
+    let mut used = HashSet::new();
+    for msg in &kept {
+        collect_type_names(&msg.fields, &msg.groups, &mut used);
+    }
+    let mut orphaned = HashSet::new();
+    for msg in &dropped {
+        collect_type_names(&msg.fields, &msg.groups, &mut orphaned);
+    }
+
+    let mut retained: HashSet<String> = ir
+        .types
+        .keys()
+        .filter(|name| used.contains(name.as_str()) || !orphaned.contains(name.as_str()))
+        .cloned()
+        .collect();
+
+    // Kept composites keep the types of their members and the enums their
+    // constants reference, recursively.
+    let mut pending: Vec<String> = retained.iter().cloned().collect();
+    while let Some(name) = pending.pop() {
+        let Some(TypeKind::Composite { fields }) = ir.types.get(&name).map(|ty| &ty.kind) else {
+            continue;
+        };
+        for field in fields {
+            let referenced = match &field.constant {
+                Some(ConstantValue::Enum { type_name, .. }) => type_name,
+                _ => &field.type_name,
+            };
+            if retained.insert(referenced.clone()) {
+                pending.push(referenced.clone());
+            }
+        }
+    }
+
+    ir.types.retain(|name, _| retained.contains(name));
+    ir.messages = kept;
+}
+
+/// Collects the type names referenced by `fields` and `groups`.
+fn collect_type_names<'a>(
+    fields: &'a [ResolvedField],
+    groups: &'a [ResolvedGroup],
+    names: &mut HashSet<&'a str>,
+) {
+    names.extend(fields.iter().map(|f| f.type_name.as_str()));
+    for group in groups {
+        collect_type_names(&group.fields, &group.nested_groups, names);
+    }
+}
+
+/// Applies `renames` to every identifier in `ir`.
+fn rename(ir: &mut SchemaIr, renames: &HashMap<String, String>) {
+    let types = std::mem::take(&mut ir.types);
+    ir.types = types
+        .into_values()
+        .map(|mut ty| {
+            if let Some(new) = renames.get(&ty.name) {
+                if !matches!(ty.kind, TypeKind::Primitive(_)) {
+                    ty.rust_type = to_pascal_case(new);
+                }
+                ty.name.clone_from(new);
+            }
+            if let TypeKind::Composite { fields } = &mut ty.kind {
+                for field in fields {
+                    if let Some(new) = renames.get(&field.type_name) {
+                        field.type_name.clone_from(new);
+                    }
+                    rename_constant(&mut field.constant, renames);
+                }
+            }
+            (ty.name.clone(), ty)
+        })
+        .collect();
+
+    // Type name -> Rust type, so renamed field types pick up the new name.
+    let rust_types: HashMap<&str, &str> = ir
+        .types
+        .iter()
+        .map(|(name, ty)| (name.as_str(), ty.rust_type.as_str()))
+        .collect();
+
+    for msg in &mut ir.messages {
+        rename_message(msg, &rust_types, renames);
+    }
+}
+
+fn rename_message(
+    msg: &mut ResolvedMessage,
+    types: &HashMap<&str, &str>,
+    renames: &HashMap<String, String>,
+) {
+    if let Some(new) = renames.get(&msg.name) {
+        msg.name.clone_from(new);
+    }
+    rename_fields(&mut msg.fields, types, renames);
+    rename_groups(&mut msg.groups, types, renames);
+    rename_var_data(&mut msg.var_data, renames);
+}
+
+fn rename_groups(
+    groups: &mut [ResolvedGroup],
+    types: &HashMap<&str, &str>,
+    renames: &HashMap<String, String>,
+) {
+    for group in groups {
+        if let Some(new) = renames.get(&group.name) {
+            group.name.clone_from(new);
+        }
+        rename_fields(&mut group.fields, types, renames);
+        rename_groups(&mut group.nested_groups, types, renames);
+        rename_var_data(&mut group.var_data, renames);
+    }
+}
+
+fn rename_fields(
+    fields: &mut [ResolvedField],
+    types: &HashMap<&str, &str>,
+    renames: &HashMap<String, String>,
+) {
+    for field in fields {
+        if let Some(new) = renames.get(&field.name) {
+            field.name.clone_from(new);
+            field.getter_name = to_snake_case(new);
+            field.setter_name = format!("set_{}", field.getter_name);
+        }
+        if let Some(new) = renames.get(&field.type_name) {
+            field.type_name.clone_from(new);
+            if let Some(rust_type) = types.get(new.as_str()) {
+                field.rust_type = (*rust_type).to_string();
+            }
+        }
+        rename_constant(&mut field.constant, renames);
+    }
+}
+
+/// Points an enum constant at its type's new name.
+fn rename_constant(constant: &mut Option<ConstantValue>, renames: &HashMap<String, String>) {
+    if let Some(ConstantValue::Enum { type_name, .. }) = constant
+        && let Some(new) = renames.get(type_name.as_str())
+    {
+        type_name.clone_from(new);
+    }
+}
+
+fn rename_var_data(var_data: &mut [ResolvedVarData], renames: &HashMap<String, String>) {
+    for data in var_data {
+        if let Some(new) = renames.get(&data.name) {
+            data.name.clone_from(new);
+        }
+    }
+}
@@ -1,48 +1,200 @@
 //! Main code generator orchestration.
 
 use ironsbe_schema::ir::SchemaIr;
+use proc_macro2::TokenStream;
+use quote::quote;
 
-use crate::rust::{EnumGenerator, MessageGenerator, TypeGenerator};
+use crate::config::CodegenConfig;
+use crate::conformance::ConformanceSuite;
+use crate::cpp::CppGenerator;
+use crate::filter;
+use crate::java::JavaGenerator;
+use crate::rust::imports::{generate_imports, generate_prelude};
+use crate::rust::{
+    DispatchGenerator, EnumGenerator, FfiGenerator, FuzzGenerator, FuzzHarness, GoldenMessage,
+    MessageGenerator, RoundTripGenerator, TypeGenerator, format_tokens, lit,
+};
+
+/// Target language of the generated code.
+///
+/// All targets read and write the same wire layout, so a message encoded
+/// by one can be decoded by any other.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Language {
+    /// Rust encoders and decoders over `ironsbe-core` buffers.
+    #[default]
+    Rust,
+    /// Header-only C++17 flyweights over `char` buffers.
+    Cpp,
+    /// Java flyweights over little-endian `ByteBuffer`s, in one file named
+    /// after the last segment of the schema package.
+    Java,
+}
 
 /// Main code generator for SBE schemas.
 pub struct Generator<'a> {
     ir: &'a SchemaIr,
+    config: CodegenConfig,
+    language: Language,
 }
 
 impl<'a> Generator<'a> {
     /// Creates a new generator for the given schema IR.
     #[must_use]
     pub fn new(ir: &'a SchemaIr) -> Self {
-        Self { ir }
+        Self::with_config(ir, CodegenConfig::default())
+    }
+
+    /// Creates a new generator with custom options.
+    #[must_use]
+    pub fn with_config(ir: &'a SchemaIr, config: CodegenConfig) -> Self {
+        Self {
+            ir,
+            config,
+            language: Language::default(),
+        }
+    }
+
+    /// Sets the target language (default: Rust).
+    ///
+    /// Message filters and renames apply to every target; the remaining
+    /// options only affect Rust output.
+    #[must_use]
+    pub fn target(mut self, language: Language) -> Self {
+        self.language = language;
+        self
     }
 
-    /// Generates the complete Rust code for the schema.
+    /// Generates the complete code for the schema in the target language.
+    ///
+    /// Message filters and renames from the config are applied first.
     #[must_use]
     pub fn generate(&self) -> String {
-        let mut output = String::with_capacity(64 * 1024);
+        let output = match self.language {
+            Language::Rust => {
+                let tokens = self.generate_tokens();
+                if self.config.raw_output {
+                    tokens.to_string()
+                } else {
+                    format_tokens(tokens)
+                }
+            }
+            Language::Cpp => CppGenerator::new(&filter::apply(self.ir, &self.config)).generate(),
+            Language::Java => JavaGenerator::new(&filter::apply(self.ir, &self.config)).generate(),
+        };
 
-        // File header
-        self.generate_header(&mut output);
+        // File header, added last since tokens cannot carry plain comments
+        let mut code = String::with_capacity(output.len() + 128);
+        self.generate_header(&mut code);
+        code.push_str(&output);
+        code
+    }
+
+    /// Generates the Rust items for the schema as a token stream, without
+    /// the file header, regardless of the target language.
+    ///
+    /// Useful for emitting the generated code from a procedural macro.
+    #[must_use]
+    pub fn generate_tokens(&self) -> TokenStream {
+        let ir = filter::apply(self.ir, &self.config);
+        let mut items = TokenStream::new();
 
         // Constants
-        self.generate_constants(&mut output);
+        items.extend(self.generate_constants());
 
         // Types (enums, sets, composites)
-        let type_gen = TypeGenerator::new(self.ir);
-        output.push_str(&type_gen.generate());
+        let type_gen = TypeGenerator::with_config(&ir, self.config.clone());
+        items.extend(type_gen.generate_tokens());
 
         // Enums
-        let enum_gen = EnumGenerator::new(self.ir);
-        output.push_str(&enum_gen.generate());
+        let enum_gen = EnumGenerator::with_config(&ir, self.config.clone());
+        items.extend(enum_gen.generate_tokens());
 
         // Messages
-        let msg_gen = MessageGenerator::new(self.ir);
-        output.push_str(&msg_gen.generate());
+        let msg_gen = MessageGenerator::with_config(&ir, self.config.clone());
+        items.extend(msg_gen.generate_tokens());
+
+        // Visitor and dispatch
+        items.extend(DispatchGenerator::with_config(&ir, self.config.clone()).generate_tokens());
+
+        // C FFI accessor shims
+        if self.config.ffi_shims {
+            items.extend(FfiGenerator::with_config(&ir, self.config.clone()).generate_tokens());
+        }
+
+        // Imports of exactly the `ironsbe-core` items used above, and a
+        // prelude re-exporting the public ones
+        let mut output = generate_imports(&items);
+        output.extend(generate_prelude(&items));
+        output.extend(items);
+
+        // Round-trip tests
+        if self.config.round_trip_tests {
+            output.extend(
+                RoundTripGenerator::with_config(&ir, self.config.clone()).generate_tokens(),
+            );
+        }
 
         output
     }
 
-    /// Generates the file header with imports.
+    /// Returns the golden sample encoding of every generated message.
+    ///
+    /// These are the bytes the round-trip tests from
+    /// [`CodegenConfig::round_trip_tests`] expect the encoders to produce.
+    /// Checking them in as a binary corpus catches layout changes across
+    /// generator versions.
+    #[must_use]
+    pub fn golden_corpus(&self) -> Vec<GoldenMessage> {
+        RoundTripGenerator::new(&filter::apply(self.ir, &self.config)).golden_corpus()
+    }
+
+    /// Returns the cross-language conformance vectors of every generated
+    /// message.
+    ///
+    /// Write them with [`ConformanceSuite::write_to`] for another SBE
+    /// implementation to decode and re-encode, then check its encodings with
+    /// [`ConformanceSuite::verify_dir`].
+    #[must_use]
+    pub fn conformance_suite(&self) -> ConformanceSuite {
+        ConformanceSuite::new(&filter::apply(self.ir, &self.config))
+    }
+
+    /// Generates a `cargo-fuzz` crate exercising every generated decoder.
+    ///
+    /// See [`FuzzGenerator::generate`] for the arguments. The harness has a
+    /// `decode_any` target feeding arbitrary bytes through `dispatch` and a
+    /// `decode_<message>` target per message; write it next to the crate
+    /// with [`FuzzHarness::write_to`] and run it with `cargo fuzz run`.
+    #[must_use]
+    pub fn fuzz_harness(&self, crate_name: &str, module: &str) -> FuzzHarness {
+        FuzzGenerator::new(&filter::apply(self.ir, &self.config)).generate(crate_name, module)
+    }
+
+    /// Generates the C header declaring the accessor shims emitted with
+    /// [`CodegenConfig::ffi_shims`].
+    ///
+    /// Message filters and renames from the config apply, so the header
+    /// matches the Rust output.
+    #[must_use]
+    pub fn ffi_header(&self) -> String {
+        let mut code = String::from(
+            "/* Generated by IronSBE codegen - DO NOT EDIT */
+",
+        );
+        code.push_str(&format!(
+            "/* Schema: {} v{} */
+
+",
+            self.ir.package, self.ir.schema_version
+        ));
+        code.push_str(
+            &FfiGenerator::new(&filter::apply(self.ir, &self.config)).generate_c_header(),
+        );
+        code
+    }
+
+    /// Generates the file header comment.
     fn generate_header(&self, output: &mut String) {
         output.push_str("// Generated by IronSBE codegen - DO NOT EDIT\n");
         output.push_str(&format!(
@@ -50,28 +202,18 @@ impl<'a> Generator<'a> {
             self.ir.package, self.ir.schema_version
         ));
         output.push('\n');
-        output.push_str("use ironsbe_core::{\n");
-        output.push_str("    buffer::{ReadBuffer, WriteBuffer},\n");
-        output.push_str("    header::{MessageHeader, GroupHeader, VarDataHeader},\n");
-        output.push_str("    decoder::{SbeDecoder, DecodeError},\n");
-        output.push_str("    encoder::SbeEncoder,\n");
-        output.push_str("};\n");
-        output.push('\n');
     }
 
     /// Generates schema constants.
-    fn generate_constants(&self, output: &mut String) {
-        output.push_str(&format!(
-            "/// Schema ID for this protocol.\n\
-             pub const SCHEMA_ID: u16 = {};\n",
-            self.ir.schema_id
-        ));
-        output.push_str(&format!(
-            "/// Schema version for this protocol.\n\
-             pub const SCHEMA_VERSION: u16 = {};\n",
-            self.ir.schema_version
-        ));
-        output.push('\n');
+    fn generate_constants(&self) -> TokenStream {
+        let schema_id = lit(usize::from(self.ir.schema_id));
+        let schema_version = lit(usize::from(self.ir.schema_version));
+        quote! {
+            /// Schema ID for this protocol.
+            pub const SCHEMA_ID: u16 = #schema_id;
+            /// Schema version for this protocol.
+            pub const SCHEMA_VERSION: u16 = #schema_version;
+        }
     }
 }
 
@@ -103,4 +245,218 @@ mod tests {
         assert!(code.contains("TestMessageDecoder"));
         assert!(code.contains("TestMessageEncoder"));
     }
+
+    #[test]
+    fn test_generate_formats_output() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <sbe:message name="TestMessage" id="1" blockLength="8">
+        <field name="value" id="1" type="uint64" offset="0"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+
+        let code = Generator::new(&ir).generate();
+        assert!(code.starts_with("// Generated by IronSBE codegen - DO NOT EDIT\n"));
+        assert!(code.contains("        Self {\n            buffer,\n"));
+        assert_eq!(
+            Generator::new(&ir).generate(),
+            code,
+            "output not deterministic"
+        );
+
+        let raw = Generator::with_config(&ir, CodegenConfig::new().raw_output(true)).generate();
+        assert!(raw.contains("Self { buffer , offset , block_length , acting_version }"));
+        assert!(!raw.contains("        Self {\n"));
+    }
+
+    #[test]
+    fn test_generate_tokens_parse_as_file() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <sbe:message name="TestMessage" id="1" blockLength="8">
+        <field name="value" id="1" type="uint64" offset="0"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+
+        let tokens = Generator::new(&ir).generate_tokens();
+        let file: syn::File = syn::parse2(tokens).expect("tokens should form a file");
+        assert!(file.items.iter().any(|item| matches!(
+            item,
+            syn::Item::Struct(s) if s.ident == "TestMessageDecoder"
+        )));
+    }
+
+    #[test]
+    fn test_imports_and_prelude() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <sbe:message name="TestMessage" id="1" blockLength="8">
+        <field name="value" id="1" type="uint64" offset="0"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+
+        let code = Generator::new(&ir).generate();
+        assert!(code.contains("use ironsbe_core::header::MessageHeader;"));
+        assert!(code.contains("use ironsbe_core::buffer::ReadBuffer;"));
+        assert!(!code.contains("GroupHeader"), "no groups in the schema");
+        assert!(!code.contains("VarDataHeader"), "no var data in the schema");
+        assert!(code.contains("use ironsbe_core::encoder::SbeEncoder;"));
+        assert!(code.contains("pub mod prelude {"));
+        assert!(code.contains("TestMessageDecoder, TestMessageEncoder"));
+    }
+
+    #[test]
+    fn test_derive_serde_option() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+        </enum>
+    </types>
+    <sbe:message name="TestMessage" id="1" blockLength="1">
+        <field name="side" id="1" type="Side" offset="0"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+
+        let plain = Generator::new(&ir).generate();
+        assert!(!plain.contains("serde::"));
+        assert!(!plain.contains("TestMessageOwned"));
+
+        let config = CodegenConfig::new().derive_serde(true);
+        let code = Generator::with_config(&ir, config).generate();
+        assert!(code.contains("pub struct TestMessageOwned"));
+        assert!(code.contains("pub fn to_owned(&self) -> TestMessageOwned"));
+        assert!(code.contains("pub side: Side,"));
+        assert_eq!(
+            code.matches("#[derive(serde::Serialize, serde::Deserialize)]")
+                .count(),
+            2,
+            "expected derives on the enum and the owned message"
+        );
+    }
+
+    const FILTER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+        </enum>
+        <enum name="HaltReason" encodingType="uint8">
+            <validValue name="News">1</validValue>
+        </enum>
+    </types>
+    <sbe:message name="NewOrderSingle" id="1" blockLength="9">
+        <field name="clOrdId" id="1" type="uint64" offset="0"/>
+        <field name="side" id="2" type="Side" offset="8"/>
+    </sbe:message>
+    <sbe:message name="CancelOrder" id="2" blockLength="8">
+        <field name="clOrdId" id="1" type="uint64" offset="0"/>
+    </sbe:message>
+    <sbe:message name="TradingHalt" id="30" blockLength="1">
+        <field name="reason" id="1" type="HaltReason" offset="0"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+    #[test]
+    fn test_message_filters() {
+        let schema = parse_schema(FILTER_XML).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+
+        let config = CodegenConfig::new()
+            .include_message("*Order*")
+            .include_message("30")
+            .exclude_message("Cancel*");
+        let code = Generator::with_config(&ir, config).generate();
+        assert!(code.contains("pub struct NewOrderSingleDecoder"));
+        assert!(code.contains("pub struct TradingHaltDecoder"));
+        assert!(!code.contains("CancelOrder"));
+        assert!(code.contains("pub enum HaltReason"));
+
+        let config = CodegenConfig::new().exclude_message("TradingHalt");
+        let code = Generator::with_config(&ir, config).generate();
+        assert!(!code.contains("TradingHalt"));
+        assert!(!code.contains("HaltReason"), "orphaned type not pruned");
+        assert!(code.contains("pub enum Side"));
+    }
+
+    #[test]
+    fn test_renames() {
+        let schema = parse_schema(FILTER_XML).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+
+        let config = CodegenConfig::new()
+            .rename("NewOrderSingle", "NewOrder")
+            .rename("clOrdId", "clientOrderId")
+            .rename("Side", "OrderSide");
+        let code = Generator::with_config(&ir, config).generate();
+        assert!(code.contains("pub struct NewOrderDecoder"));
+        assert!(!code.contains("NewOrderSingle"));
+        assert!(code.contains("pub fn client_order_id(&self) -> u64"));
+        assert!(code.contains("pub fn set_client_order_id(&mut self, value: u64)"));
+        assert!(!code.contains("cl_ord_id"));
+        assert!(code.contains("pub enum OrderSide"));
+        assert!(code.contains("pub fn side(&self) -> OrderSide"));
+        assert!(!code.contains("enum Side"));
+    }
+
+    #[test]
+    fn test_composite_member_types_survive_filters_and_renames() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <composite name="Quote">
+            <ref name="price" type="Decimal"/>
+            <ref name="side" type="Side"/>
+        </composite>
+        <composite name="Decimal">
+            <type name="mantissa" primitiveType="int64"/>
+            <type name="exponent" primitiveType="int8"/>
+        </composite>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+        </enum>
+    </types>
+    <sbe:message name="Book" id="1" blockLength="10">
+        <field name="quote" id="1" type="Quote" offset="0"/>
+    </sbe:message>
+    <sbe:message name="Trade" id="2" blockLength="10">
+        <field name="price" id="1" type="Decimal" offset="0"/>
+        <field name="side" id="2" type="Side" offset="9"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+
+        let config = CodegenConfig::new()
+            .exclude_message("Trade")
+            .rename("Side", "OrderSide");
+        let code = Generator::with_config(&ir, config).generate();
+        assert!(code.contains("pub fn price(&self) -> Decimal<'a>"));
+        assert!(code.contains("pub fn set_price(&mut self) -> DecimalEncoder<'_>"));
+        assert!(code.contains("pub fn side(&self) -> OrderSide"));
+        assert!(
+            code.contains("pub struct Decimal<'a>"),
+            "nested type pruned"
+        );
+        assert!(code.contains("pub enum OrderSide"), "nested type pruned");
+    }
 }
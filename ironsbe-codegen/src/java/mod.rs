@@ -0,0 +1,1581 @@
+//! Java code generation.
+//!
+//! Emits a single source file holding one outer class with a nested
+//! flyweight per type and message. The flyweights wrap little-endian
+//! `ByteBuffer`s and read and write the same wire layout as the Rust
+//! output. Unsigned types are widened to the next larger Java type.
+
+use ironsbe_schema::ir::{
+    CompositeFieldInfo, EnumVariant, ResolvedField, ResolvedGroup, ResolvedMessage,
+    ResolvedVarData, SchemaIr, SetVariant, TypeKind, to_pascal_case, to_screaming_snake_case,
+};
+use ironsbe_schema::types::PrimitiveType;
+
+use crate::lang::{
+    Constant, FieldKind, NullValue, Section, SourceWriter, constant, entry_block_length,
+    escape_keyword, escape_string, field_kind, member_kind, null_value, sections, to_camel_case,
+};
+
+/// Java reserved words that cannot be used as identifiers.
+const KEYWORDS: &[&str] = &[
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "true",
+    "try",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// Generator for Java flyweights.
+pub struct JavaGenerator<'a> {
+    ir: &'a SchemaIr,
+}
+
+impl<'a> JavaGenerator<'a> {
+    /// Creates a new Java generator.
+    #[must_use]
+    pub fn new(ir: &'a SchemaIr) -> Self {
+        Self { ir }
+    }
+
+    /// Returns the Java package of the generated file.
+    #[must_use]
+    pub fn package(&self) -> String {
+        self.ir
+            .package
+            .split('.')
+            .map(|segment| {
+                let segment: String = segment
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                    .collect();
+                identifier(&segment.to_ascii_lowercase())
+            })
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    /// Returns the name of the outer class, which the generated file must
+    /// be named after.
+    #[must_use]
+    pub fn class_name(&self) -> String {
+        let last = self.ir.package.rsplit('.').next().unwrap_or_default();
+        let name: String = last
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        to_pascal_case(&name)
+    }
+
+    /// Generates the source file for the schema, without the file header
+    /// comment.
+    #[must_use]
+    pub fn generate(&self) -> String {
+        let mut w = SourceWriter::new("    ");
+        w.line(format!("package {};", self.package()));
+        w.blank();
+        w.line("import java.nio.ByteBuffer;");
+        w.line("import java.nio.ByteOrder;");
+        w.line("import java.nio.charset.StandardCharsets;");
+        w.blank();
+
+        let class = self.class_name();
+        w.line(format!(
+            "/** Flyweights for the {} schema. */",
+            self.ir.package
+        ));
+        w.open(format!("public final class {class} {{"));
+        w.line("/** Schema ID for this protocol. */");
+        w.line(format!(
+            "public static final int SCHEMA_ID = {};",
+            self.ir.schema_id
+        ));
+        w.line("/** Schema version for this protocol. */");
+        w.line(format!(
+            "public static final int SCHEMA_VERSION = {};",
+            self.ir.schema_version
+        ));
+        w.line("/** Encoded length of a group header in bytes. */");
+        w.line("public static final int GROUP_HEADER_LENGTH = 4;");
+        w.line("/** Encoded length of a var data length prefix in bytes. */");
+        w.line("public static final int VAR_DATA_HEADER_LENGTH = 2;");
+        w.blank();
+        w.line(format!("private {class}() {{}}"));
+        w.blank();
+        generate_helpers(&mut w);
+        generate_message_header(&mut w);
+
+        for resolved_type in self.ir.sorted_types() {
+            match &resolved_type.kind {
+                TypeKind::Enum { encoding, variants } => {
+                    generate_enum(&mut w, &resolved_type.name, *encoding, variants);
+                }
+                TypeKind::Set { encoding, choices } => {
+                    generate_set(&mut w, &resolved_type.name, *encoding, choices);
+                }
+                TypeKind::Composite { fields }
+                    if !resolved_type.name.eq_ignore_ascii_case("messageHeader") =>
+                {
+                    generate_composite(
+                        &mut w,
+                        &resolved_type.name,
+                        fields,
+                        resolved_type.encoded_length,
+                    );
+                }
+                _ => {}
+            }
+        }
+        for msg in &self.ir.messages {
+            self.generate_message(&mut w, msg);
+        }
+
+        w.close("}");
+        w.finish()
+    }
+
+    /// Generates the decoder and encoder of a message, with their group
+    /// flyweights nested inside.
+    fn generate_message(&self, w: &mut SourceWriter, msg: &ResolvedMessage) {
+        let sections = sections(&msg.groups, &msg.var_data);
+        let decoder = msg.decoder_name();
+        let encoder = msg.encoder_name();
+        let mut groups = Vec::new();
+        collect_groups(&msg.groups, &mut groups);
+
+        // Decoder
+        class_doc(w, &format!("{} Decoder (zero-copy).", msg.name), &sections);
+        w.open(format!("public static final class {decoder} {{"));
+        w.line("/** Template ID for this message. */");
+        w.line(format!(
+            "public static final int TEMPLATE_ID = {};",
+            msg.template_id
+        ));
+        w.line("/** Block length of the fixed portion. */");
+        w.line(format!(
+            "public static final int BLOCK_LENGTH = {};",
+            msg.block_length
+        ));
+        w.blank();
+        self.decoder_members(w, &msg.fields, &sections, &decoder);
+        w.line("private int actingVersion;");
+        w.blank();
+        w.line("/** Wraps `buffer` at `offset`, the start of the root block after the header. */");
+        w.open(format!(
+            "public {decoder} wrap(ByteBuffer buffer, int offset, int actingVersion) {{"
+        ));
+        w.line("this.buffer = littleEndian(buffer);");
+        w.line("this.offset = offset;");
+        w.line("this.actingVersion = actingVersion;");
+        w.line("return this;");
+        w.close("}");
+        w.blank();
+        w.line("/** Returns the schema version the message was encoded with. */");
+        w.open("public int actingVersion() {");
+        w.line("return actingVersion;");
+        w.close("}");
+        w.blank();
+        w.line("/** Returns the encoded length of the message, including the header. */");
+        w.open("public int encodedLength() {");
+        w.line("return MessageHeader.ENCODED_LENGTH + BLOCK_LENGTH + lengthAfter(buffer, offset + BLOCK_LENGTH);");
+        w.close("}");
+        w.blank();
+        lengths_after(w, &sections, &decoder, false);
+        self.decoder_accessors(w, &msg.fields, &sections, &decoder, "offset + BLOCK_LENGTH");
+        for group in &groups {
+            self.generate_group_decoders(w, group, &decoder);
+        }
+        w.close("}");
+        w.blank();
+
+        // Encoder
+        class_doc(w, &format!("{} Encoder.", msg.name), &sections);
+        w.open(format!("public static final class {encoder} {{"));
+        w.line("/** Template ID for this message. */");
+        w.line(format!(
+            "public static final int TEMPLATE_ID = {};",
+            msg.template_id
+        ));
+        w.line("/** Block length of the fixed portion. */");
+        w.line(format!(
+            "public static final int BLOCK_LENGTH = {};",
+            msg.block_length
+        ));
+        w.blank();
+        self.encoder_members(w, &msg.fields, &sections);
+        w.blank();
+        w.line("/** Wraps `buffer` at `offset`, writing the message header. */");
+        w.open(format!(
+            "public {encoder} wrap(ByteBuffer buffer, int offset) {{"
+        ));
+        w.line("this.buffer = littleEndian(buffer);");
+        w.line("this.offset = offset;");
+        w.line("buffer.putShort(offset, (short) BLOCK_LENGTH);");
+        w.line("buffer.putShort(offset + 2, (short) TEMPLATE_ID);");
+        w.line("buffer.putShort(offset + 4, (short) SCHEMA_ID);");
+        w.line("buffer.putShort(offset + 6, (short) SCHEMA_VERSION);");
+        w.line("return this;");
+        w.close("}");
+        w.blank();
+        w.line("/**");
+        w.line(" * Returns the encoded length of the message, including the header.");
+        w.line(" *");
+        w.line(" * <p>Only meaningful once every section has been written.");
+        w.line(" */");
+        w.open("public int encodedLength() {");
+        w.line(format!(
+            "return MessageHeader.ENCODED_LENGTH + BLOCK_LENGTH + {decoder}.lengthAfter(buffer, offset + MessageHeader.ENCODED_LENGTH + BLOCK_LENGTH);"
+        ));
+        w.close("}");
+        w.blank();
+        self.encoder_accessors(
+            w,
+            &msg.fields,
+            &sections,
+            &encoder,
+            &decoder,
+            "offset + MessageHeader.ENCODED_LENGTH",
+            "offset + MessageHeader.ENCODED_LENGTH + BLOCK_LENGTH",
+        );
+        for group in &groups {
+            self.generate_group_encoders(w, group, &decoder);
+        }
+        w.close("}");
+        w.blank();
+    }
+
+    /// Generates the entry and group decoders of `group`.
+    fn generate_group_decoders(&self, w: &mut SourceWriter, group: &ResolvedGroup, path: &str) {
+        let sections = sections(&group.nested_groups, &group.var_data);
+        let flat = sections.is_empty();
+        let group_decoder = group.decoder_name();
+        let entry_decoder = group.entry_decoder_name();
+        let header = HeaderLayout::of(group);
+
+        // Group decoder
+        w.line(format!("/** {} Group Decoder. */", group.name));
+        w.open(format!("public static final class {group_decoder} {{"));
+        w.line(format!(
+            "private final {entry_decoder} entry = new {entry_decoder}();"
+        ));
+        w.line("private ByteBuffer buffer;");
+        w.line("private int start;");
+        w.line("private int blockLength;");
+        w.line("private int count;");
+        w.line("private int index;");
+        w.line("private int offset;");
+        w.blank();
+        w.line("/** Wraps `buffer` at the group header position. */");
+        w.open(format!(
+            "public {group_decoder} wrap(ByteBuffer buffer, int offset) {{"
+        ));
+        w.line("this.buffer = buffer;");
+        w.line("this.start = offset;");
+        w.line(format!("this.blockLength = {};", header.block_length));
+        w.line(format!("this.count = {};", header.count));
+        w.line("this.index = 0;");
+        w.line(format!("this.offset = offset + {};", header.length));
+        w.line("return this;");
+        w.close("}");
+        w.blank();
+        w.line("/** Returns the number of entries in the group. */");
+        w.open("public int count() {");
+        w.line("return count;");
+        w.close("}");
+        w.blank();
+        w.line("/** Returns true if `next()` has entries left to return. */");
+        w.open("public boolean hasNext() {");
+        w.line("return index < count;");
+        w.close("}");
+        w.blank();
+        w.line("/** Returns the next entry, reusing one flyweight. Requires `hasNext()`. */");
+        w.open(format!("public {entry_decoder} next() {{"));
+        w.line("entry.wrap(buffer, offset, blockLength);");
+        w.line("offset += entry.encodedLength();");
+        w.line("index++;");
+        w.line("return entry;");
+        w.close("}");
+        w.blank();
+        w.line("/** Returns the total encoded length of this group (header + all entries). */");
+        w.open("public int encodedLength() {");
+        w.line("return lengthAt(buffer, start);");
+        w.close("}");
+        w.blank();
+        w.line("/** Returns the encoded length of the group whose header is at `offset`. */");
+        w.open("static int lengthAt(ByteBuffer buffer, int offset) {");
+        w.line(format!("int blockLength = {};", header.block_length));
+        w.line(format!("int count = {};", header.count));
+        if flat {
+            w.line(format!("return {} + blockLength * count;", header.length));
+        } else {
+            w.line(format!("int end = offset + {};", header.length));
+            w.open("for (int i = 0; i < count; i++) {");
+            w.line(format!(
+                "end += blockLength + {entry_decoder}.lengthAfter(buffer, end + blockLength);"
+            ));
+            w.close("}");
+            w.line("return end - offset;");
+        }
+        w.close("}");
+        w.close("}");
+        w.blank();
+
+        // Entry decoder
+        class_doc(w, &format!("{} Entry Decoder.", group.name), &sections);
+        w.open(format!("public static final class {entry_decoder} {{"));
+        self.decoder_members(w, &group.fields, &sections, path);
+        w.line("private int blockLength;");
+        w.blank();
+        w.open(format!(
+            "{entry_decoder} wrap(ByteBuffer buffer, int offset, int blockLength) {{"
+        ));
+        w.line("this.buffer = buffer;");
+        w.line("this.offset = offset;");
+        w.line("this.blockLength = blockLength;");
+        w.line("return this;");
+        w.close("}");
+        w.blank();
+        w.line("/** Returns the encoded length of this entry, including nested sections. */");
+        w.open("public int encodedLength() {");
+        w.line("return blockLength + lengthAfter(buffer, offset + blockLength);");
+        w.close("}");
+        w.blank();
+        lengths_after(w, &sections, path, true);
+        self.decoder_accessors(w, &group.fields, &sections, path, "offset + blockLength");
+        w.close("}");
+        w.blank();
+    }
+
+    /// Generates the entry and group encoders of `group`.
+    fn generate_group_encoders(&self, w: &mut SourceWriter, group: &ResolvedGroup, path: &str) {
+        let sections = sections(&group.nested_groups, &group.var_data);
+        let flat = sections.is_empty();
+        let group_encoder = group.encoder_name();
+        let entry_encoder = group.entry_encoder_name();
+        let entry_decoder = group.entry_decoder_name();
+        let header = HeaderLayout::of(group);
+        let dimension = &group.dimension;
+
+        // Group encoder
+        w.line(format!("/** {} Group Encoder. */", group.name));
+        w.open(format!("public static final class {group_encoder} {{"));
+        w.line("/** Block length of each entry. */");
+        w.line(format!(
+            "public static final int BLOCK_LENGTH = {};",
+            entry_block_length(group)
+        ));
+        w.blank();
+        w.line(format!(
+            "private final {entry_encoder} entry = new {entry_encoder}();"
+        ));
+        w.line("private ByteBuffer buffer;");
+        w.line("private int start;");
+        w.line("private int count;");
+        w.line("private int index;");
+        w.line("private int offset;");
+        w.blank();
+        w.line("/** Wraps `buffer` at the group header position, writing the header. */");
+        w.open(format!(
+            "public {group_encoder} wrap(ByteBuffer buffer, int offset, int count) {{"
+        ));
+        w.line(format!(
+            "{};",
+            write_stmt(
+                dimension.block_length_type,
+                &header_offset(dimension.block_length_offset),
+                "BLOCK_LENGTH"
+            )
+        ));
+        w.line(format!(
+            "{};",
+            write_stmt(
+                dimension.num_in_group_type,
+                &header_offset(dimension.num_in_group_offset),
+                "count"
+            )
+        ));
+        w.line("this.buffer = buffer;");
+        w.line("this.start = offset;");
+        w.line("this.count = count;");
+        w.line("this.index = 0;");
+        w.line(format!("this.offset = offset + {};", header.length));
+        w.line("return this;");
+        w.close("}");
+        w.blank();
+        w.line("/** Returns true if `next()` has entries left to write. */");
+        w.open("public boolean hasNext() {");
+        w.line("return index < count;");
+        w.close("}");
+        w.blank();
+        if flat {
+            w.line("/** Returns the next entry encoder, reusing one flyweight. Requires `hasNext()`. */");
+            w.open(format!("public {entry_encoder} next() {{"));
+            w.line("entry.wrap(buffer, offset);");
+            w.line("offset += BLOCK_LENGTH;");
+        } else {
+            w.line("/**");
+            w.line(
+                " * Returns the next entry encoder, reusing one flyweight. Requires `hasNext()`.",
+            );
+            w.line(" *");
+            w.line(" * <p>The previous entry, including its nested sections, must be complete.");
+            w.line(" */");
+            w.open(format!("public {entry_encoder} next() {{"));
+            w.open("if (index > 0) {");
+            w.line(format!(
+                "offset += BLOCK_LENGTH + {path}.{entry_decoder}.lengthAfter(buffer, offset + BLOCK_LENGTH);"
+            ));
+            w.close("}");
+            w.line("entry.wrap(buffer, offset);");
+        }
+        w.line("index++;");
+        w.line("return entry;");
+        w.close("}");
+        w.blank();
+        w.line("/**");
+        w.line(" * Returns the total encoded length of this group (header + all entries).");
+        w.line(" *");
+        w.line(" * <p>Only meaningful once every entry has been written.");
+        w.line(" */");
+        w.open("public int encodedLength() {");
+        w.line(format!(
+            "return {path}.{}.lengthAt(buffer, start);",
+            group.decoder_name()
+        ));
+        w.close("}");
+        w.close("}");
+        w.blank();
+
+        // Entry encoder
+        class_doc(w, &format!("{} Entry Encoder.", group.name), &sections);
+        w.open(format!("public static final class {entry_encoder} {{"));
+        self.encoder_members(w, &group.fields, &sections);
+        w.blank();
+        w.open(format!(
+            "{entry_encoder} wrap(ByteBuffer buffer, int offset) {{"
+        ));
+        w.line("this.buffer = buffer;");
+        w.line("this.offset = offset;");
+        w.line("return this;");
+        w.close("}");
+        w.blank();
+        self.encoder_accessors(
+            w,
+            &group.fields,
+            &sections,
+            &entry_encoder,
+            path,
+            "offset",
+            &format!("offset + {group_encoder}.BLOCK_LENGTH"),
+        );
+        w.close("}");
+        w.blank();
+    }
+
+    /// Generates the fields of a decoder: reusable flyweights for
+    /// composites and groups, then the buffer position.
+    fn decoder_members(
+        &self,
+        w: &mut SourceWriter,
+        fields: &[ResolvedField],
+        sections: &[Section<'_>],
+        path: &str,
+    ) {
+        for field in fields {
+            if let FieldKind::Composite { name } = field_kind(self.ir, field) {
+                let member = member_name(&field.name);
+                w.line(format!("private final {name} {member} = new {name}();"));
+            }
+        }
+        for section in sections {
+            if let Section::Group(group) = section {
+                let decoder = format!("{path}.{}", group.decoder_name());
+                let member = member_name(&group.name);
+                w.line(format!(
+                    "private final {decoder} {member} = new {decoder}();"
+                ));
+            }
+        }
+        w.line("private ByteBuffer buffer;");
+        w.line("private int offset;");
+    }
+
+    /// Generates the fields of an encoder: reusable flyweights for
+    /// composites and groups, then the buffer position.
+    fn encoder_members(
+        &self,
+        w: &mut SourceWriter,
+        fields: &[ResolvedField],
+        sections: &[Section<'_>],
+    ) {
+        for field in fields {
+            if let FieldKind::Composite { name } = field_kind(self.ir, field) {
+                let member = member_name(&field.name);
+                w.line(format!(
+                    "private final {name}Encoder {member} = new {name}Encoder();"
+                ));
+            }
+        }
+        for section in sections {
+            if let Section::Group(group) = section {
+                let encoder = group.encoder_name();
+                let member = member_name(&group.name);
+                w.line(format!(
+                    "private final {encoder} {member} = new {encoder}();"
+                ));
+            }
+        }
+        w.line("private ByteBuffer buffer;");
+        w.line("private int offset;");
+    }
+
+    /// Generates the field getters, group accessors, var data getters, and
+    /// section offset helpers of a decoder.
+    fn decoder_accessors(
+        &self,
+        w: &mut SourceWriter,
+        fields: &[ResolvedField],
+        sections: &[Section<'_>],
+        path: &str,
+        block_end: &str,
+    ) {
+        for field in fields {
+            self.field_getter(w, field);
+        }
+        for section in sections {
+            let getter = identifier(&to_camel_case(section.name()));
+            let offset_fn = offset_fn(section);
+            match section {
+                Section::Group(group) => {
+                    let decoder = format!("{path}.{}", group.decoder_name());
+                    w.line(format!(
+                        "/** Access {} repeating group, reusing one flyweight. */",
+                        group.name
+                    ));
+                    w.open(format!("public {decoder} {getter}() {{"));
+                    w.line(format!(
+                        "return {}.wrap(buffer, {offset_fn}());",
+                        member_name(&group.name)
+                    ));
+                    w.close("}");
+                }
+                Section::VarData(data) => {
+                    let pascal = to_pascal_case(&data.name);
+                    w.line(format!(
+                        "/** Returns the length in bytes of var data: {} (id={}). */",
+                        data.name, data.id
+                    ));
+                    w.open(format!(
+                        "public int {}Length() {{",
+                        to_camel_case(&data.name)
+                    ));
+                    w.line(format!(
+                        "return {};",
+                        int_read(data.length_type, &format!("{offset_fn}()"))
+                    ));
+                    w.close("}");
+                    w.blank();
+                    w.line(format!(
+                        "/** Copies var data {} into `dst`, returning the number of bytes copied. */",
+                        data.name
+                    ));
+                    w.open(format!(
+                        "public int get{pascal}(byte[] dst, int dstOffset) {{"
+                    ));
+                    w.line(format!("int position = {offset_fn}();"));
+                    w.line(format!(
+                        "int length = Math.min({}, dst.length - dstOffset);",
+                        int_read(data.length_type, "position")
+                    ));
+                    w.line(format!(
+                        "buffer.get(position + {}, dst, dstOffset, length);",
+                        var_data_header_length(data)
+                    ));
+                    w.line("return length;");
+                    w.close("}");
+                    w.blank();
+                    w.line(format!("/** Var data {} decoded as UTF-8. */", data.name));
+                    w.open(format!("public String {getter}() {{"));
+                    w.line(format!(
+                        "byte[] bytes = new byte[{}Length()];",
+                        to_camel_case(&data.name)
+                    ));
+                    w.line(format!("get{pascal}(bytes, 0);"));
+                    w.line("return new String(bytes, StandardCharsets.UTF_8);");
+                    w.close("}");
+                }
+            }
+            w.blank();
+        }
+        section_offsets(w, sections, block_end, path);
+    }
+
+    /// Generates the field setters, group starters, var data setters, and
+    /// section offset helpers of an encoder.
+    #[allow(clippy::too_many_arguments)]
+    fn encoder_accessors(
+        &self,
+        w: &mut SourceWriter,
+        fields: &[ResolvedField],
+        sections: &[Section<'_>],
+        owner: &str,
+        path: &str,
+        base: &str,
+        block_end: &str,
+    ) {
+        for field in fields {
+            self.field_setter(w, field, owner, base);
+        }
+        for section in sections {
+            let name = to_camel_case(section.name());
+            let offset_fn = offset_fn(section);
+            match section {
+                Section::Group(group) => {
+                    w.line("/**");
+                    w.line(format!(
+                        " * Begin encoding the {} repeating group, reusing one flyweight.",
+                        group.name
+                    ));
+                    w.line(" *");
+                    w.line(" * <p>All preceding groups must already be written.");
+                    w.line(" */");
+                    w.open(format!(
+                        "public {} {name}Count(int count) {{",
+                        group.encoder_name()
+                    ));
+                    w.line(format!(
+                        "return {}.wrap(buffer, {offset_fn}(), count);",
+                        member_name(&group.name)
+                    ));
+                    w.close("}");
+                }
+                Section::VarData(data) => {
+                    let setter = identifier(&name);
+                    w.line("/**");
+                    w.line(format!(" * Set var data: {} (id={}).", data.name, data.id));
+                    w.line(" *");
+                    w.line(" * <p>All preceding groups and var data must already be written.");
+                    let (max, max_text) = match data.length_type {
+                        PrimitiveType::Uint8 => ("0xFF", "255"),
+                        PrimitiveType::Uint32 => ("Integer.MAX_VALUE", "2147483647"),
+                        _ => ("0xFFFF", "65535"),
+                    };
+                    w.line(format!(
+                        " * Values longer than {max_text} bytes are truncated."
+                    ));
+                    w.line(" */");
+                    w.open(format!("public {owner} {setter}(byte[] value) {{"));
+                    w.line(format!("int position = {offset_fn}();"));
+                    w.line(format!("int length = Math.min(value.length, {max});"));
+                    w.line(format!(
+                        "{};",
+                        write_stmt(data.length_type, "position", "length")
+                    ));
+                    w.line(format!(
+                        "buffer.put(position + {}, value, 0, length);",
+                        var_data_header_length(data)
+                    ));
+                    w.line("return this;");
+                    w.close("}");
+                    w.blank();
+                    w.line(format!(
+                        "/** Set var data {} from a UTF-8 string. */",
+                        data.name
+                    ));
+                    w.open(format!("public {owner} {setter}(String value) {{"));
+                    w.line(format!(
+                        "return {setter}(value.getBytes(StandardCharsets.UTF_8));"
+                    ));
+                    w.close("}");
+                }
+            }
+            w.blank();
+        }
+        section_offsets(w, sections, block_end, path);
+    }
+
+    /// Generates the getter of a fixed-block field.
+    fn field_getter(&self, w: &mut SourceWriter, field: &ResolvedField) {
+        let getter = identifier(&to_camel_case(&field.name));
+        if let Some(value) = &field.constant {
+            w.line(format!(
+                "/** Field: {} (id={}, constant). */",
+                field.name, field.id
+            ));
+            let value = constant(value, field.primitive_type, field.is_array);
+            constant_getter(w, &getter, field.primitive_type, value);
+            w.blank();
+            return;
+        }
+        let at = format!("offset + {}", field.offset);
+        w.line(format!(
+            "/** Field: {} (id={}, offset={}). */",
+            field.name, field.id, field.offset
+        ));
+        match field_kind(self.ir, field) {
+            FieldKind::Primitive(prim) => {
+                w.open(format!("public {} {getter}() {{", java_type(prim)));
+                w.line(format!("return {};", read_expr(prim, &at)));
+                w.close("}");
+                if let Some(null) = null_value(field) {
+                    let present = match null {
+                        NullValue::NaN => {
+                            let boxed = if prim == PrimitiveType::Float {
+                                "Float"
+                            } else {
+                                "Double"
+                            };
+                            format!("!{boxed}.isNaN({getter}())")
+                        }
+                        NullValue::Float(value) => {
+                            let suffix = if prim == PrimitiveType::Float {
+                                "f"
+                            } else {
+                                ""
+                            };
+                            format!("{getter}() != {value:?}{suffix}")
+                        }
+                        NullValue::Int(value) => {
+                            format!("{getter}() != {}", int_literal(prim, value))
+                        }
+                    };
+                    w.blank();
+                    w.line(format!(
+                        "/** Returns true if {} is not null. */",
+                        field.name
+                    ));
+                    w.open(format!(
+                        "public boolean has{}() {{",
+                        to_pascal_case(&field.name)
+                    ));
+                    w.line(format!("return {present};"));
+                    w.close("}");
+                }
+            }
+            FieldKind::Array { element, length } => {
+                let pascal = to_pascal_case(&field.name);
+                w.open(format!(
+                    "public int get{pascal}(byte[] dst, int dstOffset) {{"
+                ));
+                w.line(format!(
+                    "int length = Math.min({length}, dst.length - dstOffset);"
+                ));
+                w.line(format!("buffer.get({at}, dst, dstOffset, length);"));
+                w.line("return length;");
+                w.close("}");
+                if element == PrimitiveType::Char {
+                    w.blank();
+                    w.line(format!(
+                        "/** Field {} as a string, trimmed at the first NUL. */",
+                        field.name
+                    ));
+                    w.open(format!("public String {getter}() {{"));
+                    w.line(format!("return trimmed(buffer, {at}, {length});"));
+                    w.close("}");
+                }
+            }
+            FieldKind::Enum { name, encoding } => {
+                let encoding = enum_encoding(encoding);
+                w.open(format!("public {name} {getter}() {{"));
+                w.line(format!("return {name}.get({});", read_expr(encoding, &at)));
+                w.close("}");
+            }
+            FieldKind::Set { encoding, .. } => {
+                let encoding = enum_encoding(encoding);
+                w.open(format!("public {} {getter}() {{", java_type(encoding)));
+                w.line(format!("return {};", read_expr(encoding, &at)));
+                w.close("}");
+            }
+            FieldKind::Composite { name } => {
+                w.open(format!("public {name} {getter}() {{"));
+                w.line(format!(
+                    "return {}.wrap(buffer, {at});",
+                    member_name(&field.name)
+                ));
+                w.close("}");
+            }
+        }
+        w.blank();
+    }
+
+    /// Generates the setter of a fixed-block field written at `base`.
+    fn field_setter(&self, w: &mut SourceWriter, field: &ResolvedField, owner: &str, base: &str) {
+        if field.constant.is_some() {
+            return;
+        }
+        let setter = identifier(&to_camel_case(&field.name));
+        let at = format!("{base} + {}", field.offset);
+        w.line(format!(
+            "/** Set field: {} (id={}, offset={}). */",
+            field.name, field.id, field.offset
+        ));
+        match field_kind(self.ir, field) {
+            FieldKind::Primitive(prim) => {
+                w.open(format!(
+                    "public {owner} {setter}({} value) {{",
+                    java_type(prim)
+                ));
+                w.line(format!("{};", write_stmt(prim, &at, "value")));
+            }
+            FieldKind::Array { element, length } => {
+                w.open(format!("public {owner} {setter}(byte[] value) {{"));
+                w.line(format!("putPadded(buffer, {at}, {length}, value);"));
+                w.line("return this;");
+                w.close("}");
+                if element == PrimitiveType::Char {
+                    w.blank();
+                    w.line(format!(
+                        "/** Set field {} from a UTF-8 string, zero-padded. */",
+                        field.name
+                    ));
+                    w.open(format!("public {owner} {setter}(String value) {{"));
+                    w.line(format!(
+                        "return {setter}(value.getBytes(StandardCharsets.UTF_8));"
+                    ));
+                    w.close("}");
+                }
+                w.blank();
+                return;
+            }
+            FieldKind::Enum { name, encoding } => {
+                w.open(format!("public {owner} {setter}({name} value) {{"));
+                w.line(format!(
+                    "{};",
+                    write_stmt(enum_encoding(encoding), &at, "value.value()")
+                ));
+            }
+            FieldKind::Set { encoding, .. } => {
+                let encoding = enum_encoding(encoding);
+                w.open(format!(
+                    "public {owner} {setter}({} value) {{",
+                    java_type(encoding)
+                ));
+                w.line(format!("{};", write_stmt(encoding, &at, "value")));
+            }
+            FieldKind::Composite { name } => {
+                w.open(format!("public {name}Encoder {setter}() {{"));
+                w.line(format!(
+                    "return {}.wrap(buffer, {at});",
+                    member_name(&field.name)
+                ));
+                w.close("}");
+                w.blank();
+                return;
+            }
+        }
+        w.line("return this;");
+        w.close("}");
+        w.blank();
+    }
+}
+
+/// Collects `groups` and all their nested groups, parents first.
+fn collect_groups<'g>(groups: &'g [ResolvedGroup], out: &mut Vec<&'g ResolvedGroup>) {
+    for group in groups {
+        out.push(group);
+        collect_groups(&group.nested_groups, out);
+    }
+}
+
+/// Generates the static helpers shared by all flyweights.
+fn generate_helpers(w: &mut SourceWriter) {
+    w.open("static ByteBuffer littleEndian(ByteBuffer buffer) {");
+    w.open("if (buffer.order() != ByteOrder.LITTLE_ENDIAN) {");
+    w.line("throw new IllegalArgumentException(\"SBE buffers must be little-endian\");");
+    w.close("}");
+    w.line("return buffer;");
+    w.close("}");
+    w.blank();
+    w.open("static String trimmed(ByteBuffer buffer, int offset, int length) {");
+    w.line("int end = 0;");
+    w.open("while (end < length && buffer.get(offset + end) != 0) {");
+    w.line("end++;");
+    w.close("}");
+    w.line("byte[] bytes = new byte[end];");
+    w.line("buffer.get(offset, bytes, 0, end);");
+    w.line("return new String(bytes, StandardCharsets.UTF_8);");
+    w.close("}");
+    w.blank();
+    w.open("static void putPadded(ByteBuffer buffer, int offset, int length, byte[] value) {");
+    w.line("int n = Math.min(value.length, length);");
+    w.line("buffer.put(offset, value, 0, n);");
+    w.open("for (int i = n; i < length; i++) {");
+    w.line("buffer.put(offset + i, (byte) 0);");
+    w.close("}");
+    w.close("}");
+    w.blank();
+    w.open("static int varDataLength(ByteBuffer buffer, int offset) {");
+    w.line("return VAR_DATA_HEADER_LENGTH + (buffer.getShort(offset) & 0xFFFF);");
+    w.close("}");
+    w.blank();
+}
+
+/// Generates the message header flyweight.
+fn generate_message_header(w: &mut SourceWriter) {
+    w.line("/** SBE message header preceding every message. */");
+    w.open("public static final class MessageHeader {");
+    w.line("/** Encoded length of the header in bytes. */");
+    w.line("public static final int ENCODED_LENGTH = 8;");
+    w.blank();
+    w.line("private ByteBuffer buffer;");
+    w.line("private int offset;");
+    w.blank();
+    w.line("/** Wraps `buffer` at the header position. */");
+    w.open("public MessageHeader wrap(ByteBuffer buffer, int offset) {");
+    w.line("this.buffer = littleEndian(buffer);");
+    w.line("this.offset = offset;");
+    w.line("return this;");
+    w.close("}");
+    for (index, name) in ["blockLength", "templateId", "schemaId", "version"]
+        .iter()
+        .enumerate()
+    {
+        w.blank();
+        w.open(format!("public int {name}() {{"));
+        w.line(format!(
+            "return buffer.getShort(offset + {}) & 0xFFFF;",
+            index * 2
+        ));
+        w.close("}");
+    }
+    w.close("}");
+    w.blank();
+}
+
+/// Generates a Java enum carrying the wire value.
+fn generate_enum(
+    w: &mut SourceWriter,
+    name: &str,
+    encoding: PrimitiveType,
+    variants: &[EnumVariant],
+) {
+    let name = to_pascal_case(name);
+    let encoding = enum_encoding(encoding);
+    let ty = java_type(encoding);
+    w.line(format!("/** {name} enum. */"));
+    w.open(format!("public enum {name} {{"));
+    for (index, variant) in variants.iter().enumerate() {
+        let end = if index + 1 == variants.len() {
+            ';'
+        } else {
+            ','
+        };
+        w.line(format!(
+            "{}({}){end}",
+            variant_name(&variant.name),
+            int_literal(encoding, i128::from(variant.value))
+        ));
+    }
+    if variants.is_empty() {
+        w.line(";");
+    }
+    w.blank();
+    w.line(format!("private final {ty} value;"));
+    w.blank();
+    w.open(format!("{name}({ty} value) {{"));
+    w.line("this.value = value;");
+    w.close("}");
+    w.blank();
+    w.line("/** Returns the wire value. */");
+    w.open(format!("public {ty} value() {{"));
+    w.line("return value;");
+    w.close("}");
+    if let Some(first) = variants.first() {
+        w.blank();
+        w.line(
+            "/** Returns the variant for `value`, mapping unknown values to the first variant. */",
+        );
+        w.open(format!("public static {name} get({ty} value) {{"));
+        for variant in variants {
+            let variant = variant_name(&variant.name);
+            w.open(format!("if (value == {variant}.value) {{"));
+            w.line(format!("return {variant};"));
+            w.close("}");
+        }
+        w.line(format!("return {};", variant_name(&first.name)));
+        w.close("}");
+    }
+    w.close("}");
+    w.blank();
+}
+
+/// Generates the bit constants and helpers of a bitfield set.
+fn generate_set(w: &mut SourceWriter, name: &str, encoding: PrimitiveType, choices: &[SetVariant]) {
+    let name = to_pascal_case(name);
+    let encoding = enum_encoding(encoding);
+    let ty = java_type(encoding);
+    let one = if ty == "long" { "1L" } else { "1" };
+    let cast = if matches!(ty, "byte" | "short") {
+        format!("({ty}) ")
+    } else {
+        String::new()
+    };
+    w.line(format!(
+        "/** {name} bitfield set, operating on the raw value. */"
+    ));
+    w.open(format!("public static final class {name} {{"));
+    for choice in choices {
+        w.line(format!("/** Bit position of {}. */", choice.name));
+        w.line(format!(
+            "public static final int {} = {};",
+            to_screaming_snake_case(&choice.name),
+            choice.bit_position
+        ));
+    }
+    w.blank();
+    w.line(format!("private {name}() {{}}"));
+    w.blank();
+    w.line("/** Returns true if `bit` is set in `raw`. */");
+    w.open(format!("public static boolean isSet({ty} raw, int bit) {{"));
+    w.line(format!("return (raw & {one} << bit) != 0;"));
+    w.close("}");
+    w.blank();
+    w.line("/** Returns `raw` with `bit` set. */");
+    w.open(format!("public static {ty} set({ty} raw, int bit) {{"));
+    w.line(format!("return {cast}(raw | {one} << bit);"));
+    w.close("}");
+    w.blank();
+    w.line("/** Returns `raw` with `bit` cleared. */");
+    w.open(format!("public static {ty} clear({ty} raw, int bit) {{"));
+    w.line(format!("return {cast}(raw & ~({one} << bit));"));
+    w.close("}");
+    for choice in choices {
+        w.blank();
+        w.line(format!(
+            "/** Returns true if {} is set in `raw`. */",
+            choice.name
+        ));
+        w.open(format!(
+            "public static boolean {}({ty} raw) {{",
+            identifier(&to_camel_case(&choice.name))
+        ));
+        w.line(format!(
+            "return isSet(raw, {});",
+            to_screaming_snake_case(&choice.name)
+        ));
+        w.close("}");
+    }
+    w.close("}");
+    w.blank();
+}
+
+/// Generates the decoder and encoder flyweights of a composite.
+fn generate_composite(
+    w: &mut SourceWriter,
+    name: &str,
+    fields: &[CompositeFieldInfo],
+    encoded_length: usize,
+) {
+    let name = to_pascal_case(name);
+    for (class, encoder) in [(name.clone(), false), (format!("{name}Encoder"), true)] {
+        let what = if encoder {
+            "Encoder"
+        } else {
+            "Decoder (zero-copy)"
+        };
+        w.line(format!("/** {name} {what}. */"));
+        w.open(format!("public static final class {class} {{"));
+        w.line(format!("/** Encoded length of {name} in bytes. */"));
+        w.line(format!(
+            "public static final int ENCODED_LENGTH = {encoded_length};"
+        ));
+        w.blank();
+        for field in fields {
+            if let FieldKind::Composite { name } = member_kind(field) {
+                let flyweight = if encoder {
+                    format!("{name}Encoder")
+                } else {
+                    name
+                };
+                let member = member_name(&field.name);
+                w.line(format!(
+                    "private final {flyweight} {member} = new {flyweight}();"
+                ));
+            }
+        }
+        w.line("private ByteBuffer buffer;");
+        w.line("private int offset;");
+        w.blank();
+        w.line("/** Wraps `buffer` at `offset`. */");
+        w.open(format!(
+            "public {class} wrap(ByteBuffer buffer, int offset) {{"
+        ));
+        w.line("this.buffer = littleEndian(buffer);");
+        w.line("this.offset = offset;");
+        w.line("return this;");
+        w.close("}");
+        for field in fields {
+            let accessor = identifier(&to_camel_case(&field.name));
+            let at = format!("offset + {}", field.offset);
+            if let Some(value) = &field.constant {
+                if !encoder {
+                    let prim = field.primitive_type;
+                    w.blank();
+                    w.line(format!("/** Gets the {} field. */", field.name));
+                    constant_getter(w, &accessor, prim, constant(value, prim, false));
+                }
+                continue;
+            }
+            w.blank();
+            if encoder {
+                w.line(format!("/** Sets the {} field. */", field.name));
+                composite_member_setter(w, &class, &accessor, field, &at);
+            } else {
+                w.line(format!("/** Gets the {} field. */", field.name));
+                composite_member_getter(w, &accessor, field, &at);
+            }
+            w.close("}");
+        }
+        w.close("}");
+        w.blank();
+    }
+}
+
+/// Opens the getter of a composite member read at `at`, leaving its body
+/// open.
+fn composite_member_getter(
+    w: &mut SourceWriter,
+    accessor: &str,
+    field: &CompositeFieldInfo,
+    at: &str,
+) {
+    match member_kind(field) {
+        FieldKind::Enum { name, encoding } => {
+            let encoding = enum_encoding(encoding);
+            w.open(format!("public {name} {accessor}() {{"));
+            w.line(format!("return {name}.get({});", read_expr(encoding, at)));
+        }
+        FieldKind::Composite { name } => {
+            w.open(format!("public {name} {accessor}() {{"));
+            w.line(format!(
+                "return {}.wrap(buffer, {at});",
+                member_name(&field.name)
+            ));
+        }
+        FieldKind::Set { encoding, .. } => {
+            let prim = enum_encoding(encoding);
+            w.open(format!("public {} {accessor}() {{", java_type(prim)));
+            w.line(format!("return {};", read_expr(prim, at)));
+        }
+        FieldKind::Primitive(prim) | FieldKind::Array { element: prim, .. } => {
+            w.open(format!("public {} {accessor}() {{", java_type(prim)));
+            w.line(format!("return {};", read_expr(prim, at)));
+        }
+    }
+}
+
+/// Opens the setter of a composite member written at `at`, leaving its
+/// body open.
+fn composite_member_setter(
+    w: &mut SourceWriter,
+    class: &str,
+    accessor: &str,
+    field: &CompositeFieldInfo,
+    at: &str,
+) {
+    match member_kind(field) {
+        FieldKind::Enum { name, encoding } => {
+            w.open(format!("public {class} {accessor}({name} value) {{"));
+            w.line(format!(
+                "{};",
+                write_stmt(enum_encoding(encoding), at, "value.value()")
+            ));
+            w.line("return this;");
+        }
+        FieldKind::Composite { name } => {
+            w.open(format!("public {name}Encoder {accessor}() {{"));
+            w.line(format!(
+                "return {}.wrap(buffer, {at});",
+                member_name(&field.name)
+            ));
+        }
+        FieldKind::Set { encoding, .. } => {
+            let prim = enum_encoding(encoding);
+            w.open(format!(
+                "public {class} {accessor}({} value) {{",
+                java_type(prim)
+            ));
+            w.line(format!("{};", write_stmt(prim, at, "value")));
+            w.line("return this;");
+        }
+        FieldKind::Primitive(prim) | FieldKind::Array { element: prim, .. } => {
+            w.open(format!(
+                "public {class} {accessor}({} value) {{",
+                java_type(prim)
+            ));
+            w.line(format!("{};", write_stmt(prim, at, "value")));
+            w.line("return this;");
+        }
+    }
+}
+
+/// Writes a getter returning a schema constant read as `prim`.
+fn constant_getter(
+    w: &mut SourceWriter,
+    getter: &str,
+    prim: Option<PrimitiveType>,
+    value: Constant<'_>,
+) {
+    let prim = prim.unwrap_or(PrimitiveType::Uint64);
+    let ty = java_type(prim);
+    let (ty, expr) = match value {
+        Constant::Enum { name, variant } => {
+            let expr = format!("{name}.{}", variant_name(variant));
+            (name, expr)
+        }
+        Constant::Int(value) => (ty.to_string(), int_literal(prim, value)),
+        Constant::Float(value) => {
+            let suffix = if prim == PrimitiveType::Float {
+                "f"
+            } else {
+                ""
+            };
+            (ty.to_string(), format!("{value:?}{suffix}"))
+        }
+        Constant::Text(text) => ("String".to_string(), format!("\"{}\"", escape_string(text))),
+        Constant::Invalid(text) => {
+            w.open(format!("public {ty} {getter}() {{"));
+            w.line(format!(
+                "throw new IllegalStateException(\"invalid constant value '{}'\");",
+                escape_string(text)
+            ));
+            w.close("}");
+            return;
+        }
+    };
+    w.open(format!("public {ty} {getter}() {{"));
+    w.line(format!("return {expr};"));
+    w.close("}");
+}
+
+/// Writes the javadoc of a class with trailing `sections`, listing them in
+/// wire order.
+fn class_doc(w: &mut SourceWriter, summary: &str, sections: &[Section<'_>]) {
+    if sections.is_empty() {
+        w.line(format!("/** {summary} */"));
+        return;
+    }
+    let names: Vec<&str> = sections.iter().map(Section::name).collect();
+    w.line("/**");
+    w.line(format!(" * {summary}"));
+    w.line(" *");
+    w.line(format!(
+        " * <p>Sections follow the fixed block in this order: {}.",
+        names.join(", ")
+    ));
+    w.line(" */");
+}
+
+/// Returns an expression for the encoded length of `section` at `position`.
+fn section_length(section: &Section<'_>, path: &str) -> String {
+    match section {
+        Section::Group(group) => {
+            format!("{path}.{}.lengthAt(buffer, position)", group.decoder_name())
+        }
+        Section::VarData(data) if data.length_type == PrimitiveType::Uint16 => {
+            "varDataLength(buffer, position)".to_string()
+        }
+        Section::VarData(data) => format!(
+            "{} + ({})",
+            data.header_length(),
+            int_read(data.length_type, "position")
+        ),
+    }
+}
+
+/// Generates the static helper measuring the trailing sections that start
+/// at `position`, used to skip over entries and messages without wrapping
+/// them.
+fn lengths_after(w: &mut SourceWriter, sections: &[Section<'_>], path: &str, entry: bool) {
+    let what = if entry { "an entry" } else { "the message" };
+    w.line(format!(
+        "/** Returns the encoded length of the sections of {what} that start at `position`. */"
+    ));
+    w.open("static int lengthAfter(ByteBuffer buffer, int position) {");
+    if sections.is_empty() {
+        w.line("return 0;");
+    } else {
+        w.line("int start = position;");
+        for section in sections {
+            w.line(format!("position += {};", section_length(section, path)));
+        }
+        w.line("return position - start;");
+    }
+    w.close("}");
+    w.blank();
+}
+
+/// Returns the name of the private helper locating `section`.
+fn offset_fn(section: &Section<'_>) -> String {
+    format!("{}Offset", to_camel_case(section.name()))
+}
+
+/// Generates the private helpers locating each trailing section.
+fn section_offsets(w: &mut SourceWriter, sections: &[Section<'_>], block_end: &str, path: &str) {
+    let mut previous: Option<&Section<'_>> = None;
+    for section in sections {
+        w.open(format!("private int {}() {{", offset_fn(section)));
+        match previous {
+            None => w.line(format!("return {block_end};")),
+            Some(prev) => {
+                w.line(format!("int position = {}();", offset_fn(prev)));
+                w.line(format!("return position + {};", section_length(prev, path)));
+            }
+        }
+        w.close("}");
+        w.blank();
+        previous = Some(section);
+    }
+}
+
+/// Returns the name of the private flyweight member backing an accessor.
+fn member_name(name: &str) -> String {
+    format!("{}Flyweight", to_camel_case(name))
+}
+
+/// Escapes `name` if it is a Java keyword.
+fn identifier(name: &str) -> String {
+    escape_keyword(name.to_string(), KEYWORDS)
+}
+
+/// Returns the Java name of an enum constant.
+fn variant_name(name: &str) -> String {
+    identifier(&to_screaming_snake_case(name))
+}
+
+/// Returns the integer type backing an enum or set.
+///
+/// `char` encodings are stored as `uint8`, like the Rust output.
+fn enum_encoding(encoding: PrimitiveType) -> PrimitiveType {
+    match encoding {
+        PrimitiveType::Char => PrimitiveType::Uint8,
+        other => other,
+    }
+}
+
+/// Returns the Java type holding values of a primitive.
+///
+/// Unsigned types widen to the next larger signed type, except `uint64`
+/// which is carried as the same bits in a `long`.
+fn java_type(prim: PrimitiveType) -> &'static str {
+    match prim {
+        PrimitiveType::Char | PrimitiveType::Int8 => "byte",
+        PrimitiveType::Uint8 | PrimitiveType::Int16 => "short",
+        PrimitiveType::Uint16 | PrimitiveType::Int32 => "int",
+        PrimitiveType::Uint32 | PrimitiveType::Int64 | PrimitiveType::Uint64 => "long",
+        PrimitiveType::Float => "float",
+        PrimitiveType::Double => "double",
+    }
+}
+
+/// Java expressions for the members of a group's dimension header at
+/// `offset`.
+struct HeaderLayout {
+    /// Encoded length of the header.
+    length: String,
+    /// Reads `blockLength` as an `int`.
+    block_length: String,
+    /// Reads `numInGroup` as an `int`.
+    count: String,
+}
+
+impl HeaderLayout {
+    /// Returns the header layout of `group`.
+    fn of(group: &ResolvedGroup) -> Self {
+        let dimension = &group.dimension;
+        Self {
+            length: if dimension.is_standard() {
+                "GROUP_HEADER_LENGTH".to_string()
+            } else {
+                dimension.encoded_length.to_string()
+            },
+            block_length: int_read(
+                dimension.block_length_type,
+                &header_offset(dimension.block_length_offset),
+            ),
+            count: int_read(
+                dimension.num_in_group_type,
+                &header_offset(dimension.num_in_group_offset),
+            ),
+        }
+    }
+}
+
+/// Returns `offset`, or `offset + at` for a nonzero `at`.
+fn header_offset(at: usize) -> String {
+    if at == 0 {
+        "offset".to_string()
+    } else {
+        format!("offset + {at}")
+    }
+}
+
+/// Returns the size of the length prefix of a var data field.
+fn var_data_header_length(data: &ResolvedVarData) -> String {
+    match data.length_type {
+        PrimitiveType::Uint16 => "VAR_DATA_HEADER_LENGTH".to_string(),
+        _ => data.header_length().to_string(),
+    }
+}
+
+/// Returns an expression reading a length or count primitive at `at` as an
+/// `int`.
+fn int_read(prim: PrimitiveType, at: &str) -> String {
+    match prim {
+        PrimitiveType::Uint8 => format!("buffer.get({at}) & 0xFF"),
+        PrimitiveType::Uint16 => format!("buffer.getShort({at}) & 0xFFFF"),
+        PrimitiveType::Uint32 | PrimitiveType::Int32 => format!("buffer.getInt({at})"),
+        other => format!("(int) ({})", read_expr(other, at)),
+    }
+}
+
+/// Returns an expression reading a primitive at `at`.
+fn read_expr(prim: PrimitiveType, at: &str) -> String {
+    match prim {
+        PrimitiveType::Char | PrimitiveType::Int8 => format!("buffer.get({at})"),
+        PrimitiveType::Uint8 => format!("(short) (buffer.get({at}) & 0xFF)"),
+        PrimitiveType::Int16 => format!("buffer.getShort({at})"),
+        PrimitiveType::Uint16 => format!("buffer.getShort({at}) & 0xFFFF"),
+        PrimitiveType::Int32 => format!("buffer.getInt({at})"),
+        PrimitiveType::Uint32 => format!("buffer.getInt({at}) & 0xFFFF_FFFFL"),
+        PrimitiveType::Int64 | PrimitiveType::Uint64 => format!("buffer.getLong({at})"),
+        PrimitiveType::Float => format!("buffer.getFloat({at})"),
+        PrimitiveType::Double => format!("buffer.getDouble({at})"),
+    }
+}
+
+/// Returns a statement writing `value` as a primitive at `at`.
+fn write_stmt(prim: PrimitiveType, at: &str, value: &str) -> String {
+    match prim {
+        PrimitiveType::Char | PrimitiveType::Int8 => format!("buffer.put({at}, {value})"),
+        PrimitiveType::Uint8 => format!("buffer.put({at}, (byte) {value})"),
+        PrimitiveType::Int16 => format!("buffer.putShort({at}, {value})"),
+        PrimitiveType::Uint16 => format!("buffer.putShort({at}, (short) {value})"),
+        PrimitiveType::Int32 => format!("buffer.putInt({at}, {value})"),
+        PrimitiveType::Uint32 => format!("buffer.putInt({at}, (int) {value})"),
+        PrimitiveType::Int64 | PrimitiveType::Uint64 => format!("buffer.putLong({at}, {value})"),
+        PrimitiveType::Float => format!("buffer.putFloat({at}, {value})"),
+        PrimitiveType::Double => format!("buffer.putDouble({at}, {value})"),
+    }
+}
+
+/// Returns a Java literal of the value type of `prim` for `value`.
+///
+/// `uint64` values above `Long.MAX_VALUE` keep their bits.
+fn int_literal(prim: PrimitiveType, value: i128) -> String {
+    match java_type(prim) {
+        "long" => {
+            let value = if prim == PrimitiveType::Uint64 {
+                value as u64 as i64
+            } else {
+                value as i64
+            };
+            format!("{value}L")
+        }
+        "byte" | "short" => format!("({}) {value}", java_type(prim)),
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironsbe_schema::parse_schema;
+
+    #[test]
+    fn test_generate_java_flyweights() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test.feed" id="1" version="2" byteOrder="littleEndian">
+    <types>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+            <validValue name="Sell">2</validValue>
+        </enum>
+    </types>
+    <sbe:message name="Trade" id="3" blockLength="21">
+        <field name="new" id="1" type="uint64" offset="0"/>
+        <field name="qty" id="2" type="uint32" offset="8" presence="optional"/>
+        <field name="px" id="3" type="int64" offset="12"/>
+        <field name="side" id="4" type="Side" offset="20"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+        let generator = JavaGenerator::new(&ir);
+        assert_eq!(generator.package(), "test.feed");
+        assert_eq!(generator.class_name(), "Feed");
+
+        let code = generator.generate();
+        assert!(code.starts_with("package test.feed;\n"));
+        assert!(code.contains("public final class Feed {"));
+        assert!(code.contains("BUY((short) 1),"));
+        assert!(code.contains("public static final class TradeDecoder {"));
+        assert!(code.contains("public long new_() {"));
+        assert!(code.contains("return buffer.getInt(offset + 8) & 0xFFFF_FFFFL;"));
+        assert!(code.contains("return qty() != 4294967295L;"));
+        assert!(code.contains("public TradeEncoder side(Side value) {"));
+    }
+
+    #[test]
+    fn test_int_literal() {
+        assert_eq!(int_literal(PrimitiveType::Uint8, 255), "(short) 255");
+        assert_eq!(int_literal(PrimitiveType::Uint16, 65_535), "65535");
+        assert_eq!(
+            int_literal(PrimitiveType::Uint64, i128::from(u64::MAX)),
+            "-1L"
+        );
+        assert_eq!(
+            int_literal(PrimitiveType::Int64, i128::from(i64::MIN)),
+            "-9223372036854775808L"
+        );
+    }
+}
@@ -0,0 +1,382 @@
+//! Helpers shared by the C++ and Java backends.
+//!
+//! The Rust backend builds token streams; the other backends write source
+//! text line by line with a [`SourceWriter`] and classify the schema with
+//! the helpers here so that all three agree on the wire layout.
+
+use ironsbe_schema::ir::{
+    CompositeFieldInfo, ConstantValue, ResolvedField, ResolvedGroup, ResolvedVarData, SchemaIr,
+    TypeKind, to_pascal_case,
+};
+use ironsbe_schema::types::{MemberKind, PrimitiveType};
+
+/// Indenting line writer for generated source text.
+pub(crate) struct SourceWriter {
+    out: String,
+    depth: usize,
+    unit: &'static str,
+}
+
+impl SourceWriter {
+    /// Creates an empty writer indenting with `unit` per level.
+    pub(crate) fn new(unit: &'static str) -> Self {
+        Self {
+            out: String::new(),
+            depth: 0,
+            unit,
+        }
+    }
+
+    /// Writes one line at the current indentation.
+    pub(crate) fn line(&mut self, text: impl AsRef<str>) {
+        let text = text.as_ref();
+        if !text.is_empty() {
+            for _ in 0..self.depth {
+                self.out.push_str(self.unit);
+            }
+            self.out.push_str(text);
+        }
+        self.out.push('\n');
+    }
+
+    /// Writes an empty line.
+    pub(crate) fn blank(&mut self) {
+        self.out.push('\n');
+    }
+
+    /// Writes a line opening a block and indents what follows.
+    pub(crate) fn open(&mut self, text: impl AsRef<str>) {
+        self.line(text);
+        self.depth += 1;
+    }
+
+    /// Dedents and writes a line closing a block, dropping a blank line
+    /// right before it.
+    pub(crate) fn close(&mut self, text: impl AsRef<str>) {
+        if self.out.ends_with("\n\n") {
+            self.out.pop();
+        }
+        self.depth = self.depth.saturating_sub(1);
+        self.line(text);
+    }
+
+    /// Writes a line one level out without changing the indentation, for
+    /// labels such as C++ access specifiers.
+    pub(crate) fn label(&mut self, text: impl AsRef<str>) {
+        self.depth = self.depth.saturating_sub(1);
+        self.line(text);
+        self.depth += 1;
+    }
+
+    /// Returns the written text.
+    pub(crate) fn finish(self) -> String {
+        self.out
+    }
+}
+
+/// Converts a schema name to camelCase.
+pub(crate) fn to_camel_case(name: &str) -> String {
+    let pascal = to_pascal_case(name);
+    let mut chars = pascal.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => pascal,
+    }
+}
+
+/// Appends `_` to `name` if it is one of `keywords`.
+pub(crate) fn escape_keyword(name: String, keywords: &[&str]) -> String {
+    if keywords.contains(&name.as_str()) {
+        name + "_"
+    } else {
+        name
+    }
+}
+
+/// How a field is laid out and accessed.
+pub(crate) enum FieldKind {
+    /// Scalar primitive.
+    Primitive(PrimitiveType),
+    /// Fixed-length array, accessed as bytes.
+    Array {
+        /// Element type.
+        element: PrimitiveType,
+        /// Length in bytes.
+        length: usize,
+    },
+    /// Enum encoded as `encoding`.
+    Enum {
+        /// Type name in PascalCase.
+        name: String,
+        /// Encoding primitive.
+        encoding: PrimitiveType,
+    },
+    /// Bitfield set encoded as `encoding`.
+    Set {
+        /// Type name in PascalCase.
+        name: String,
+        /// Encoding primitive.
+        encoding: PrimitiveType,
+    },
+    /// Composite type.
+    Composite {
+        /// Type name in PascalCase.
+        name: String,
+    },
+}
+
+/// Classifies `field`.
+///
+/// Fields of unknown type are read as `uint64`, like the Rust backend does.
+pub(crate) fn field_kind(ir: &SchemaIr, field: &ResolvedField) -> FieldKind {
+    if field.is_array {
+        return FieldKind::Array {
+            element: field.primitive_type.unwrap_or(PrimitiveType::Uint8),
+            length: field.encoded_length,
+        };
+    }
+    match ir.get_type(&field.type_name).map(|t| &t.kind) {
+        Some(TypeKind::Enum { encoding, .. }) => FieldKind::Enum {
+            name: to_pascal_case(&field.type_name),
+            encoding: *encoding,
+        },
+        Some(TypeKind::Set { encoding, .. }) => FieldKind::Set {
+            name: to_pascal_case(&field.type_name),
+            encoding: *encoding,
+        },
+        Some(TypeKind::Composite { .. }) => FieldKind::Composite {
+            name: to_pascal_case(&field.type_name),
+        },
+        _ => FieldKind::Primitive(field.primitive_type.unwrap_or(PrimitiveType::Uint64)),
+    }
+}
+
+/// Classifies a composite member like [`field_kind`] does a field.
+pub(crate) fn member_kind(field: &CompositeFieldInfo) -> FieldKind {
+    let name = to_pascal_case(&field.type_name);
+    match (field.kind, field.primitive_type) {
+        (MemberKind::Enum, Some(encoding)) => FieldKind::Enum { name, encoding },
+        (MemberKind::Set, Some(encoding)) => FieldKind::Set { name, encoding },
+        (_, Some(prim)) => FieldKind::Primitive(prim),
+        (_, None) => FieldKind::Composite { name },
+    }
+}
+
+/// Null sentinel of an optional scalar field.
+pub(crate) enum NullValue {
+    /// Integer or character code.
+    Int(i128),
+    /// Finite floating-point value.
+    Float(f64),
+    /// Any NaN.
+    NaN,
+}
+
+/// Returns the null sentinel of `field`, or `None` if it is not an optional
+/// scalar primitive.
+///
+/// Uses the schema's `nullValue` when it is a valid literal for the field's
+/// type, and the SBE default null otherwise.
+pub(crate) fn null_value(field: &ResolvedField) -> Option<NullValue> {
+    if !field.is_optional || field.is_array {
+        return None;
+    }
+    let prim = field.primitive_type?;
+
+    if let Some(null) = field.null_value.as_deref().map(str::trim) {
+        match prim {
+            PrimitiveType::Char => {
+                let mut chars = null.chars();
+                if let (Some(c), None) = (chars.next(), chars.next())
+                    && c.is_ascii()
+                {
+                    return Some(NullValue::Int(i128::from(c as u8)));
+                }
+            }
+            PrimitiveType::Float | PrimitiveType::Double => {
+                if let Ok(value) = null.parse::<f64>()
+                    && value.is_finite()
+                {
+                    return Some(NullValue::Float(value));
+                }
+            }
+            _ => {
+                if let Ok(value) = null.parse::<i128>() {
+                    return Some(NullValue::Int(value));
+                }
+            }
+        }
+    }
+
+    Some(match prim {
+        PrimitiveType::Char => NullValue::Int(0),
+        PrimitiveType::Float | PrimitiveType::Double => NullValue::NaN,
+        p if p.is_signed() => NullValue::Int(-(1i128 << (p.size() * 8 - 1))),
+        p => NullValue::Int((1i128 << (p.size() * 8)) - 1),
+    })
+}
+
+/// Value of a schema constant, classified for printing as a literal.
+pub(crate) enum Constant<'c> {
+    /// Enum variant.
+    Enum {
+        /// Enum type name in PascalCase.
+        name: String,
+        /// Schema name of the variant.
+        variant: &'c str,
+    },
+    /// Integer or character code.
+    Int(i128),
+    /// Finite floating-point value.
+    Float(f64),
+    /// Character array contents, printable ASCII only.
+    Text(&'c str),
+    /// Literal that is not valid for the constant's type.
+    Invalid(&'c str),
+}
+
+/// Classifies `constant`, read as `prim` or as a character array if
+/// `is_array`.
+pub(crate) fn constant(
+    constant: &ConstantValue,
+    prim: Option<PrimitiveType>,
+    is_array: bool,
+) -> Constant<'_> {
+    let value = match constant {
+        ConstantValue::Enum {
+            type_name, variant, ..
+        } => {
+            return Constant::Enum {
+                name: to_pascal_case(type_name),
+                variant,
+            };
+        }
+        ConstantValue::Literal(value) => value.as_str(),
+    };
+    let parsed = match prim {
+        _ if is_array => value
+            .bytes()
+            .all(|b| b.is_ascii_graphic() || b == b' ')
+            .then_some(Constant::Text(value)),
+        Some(PrimitiveType::Char) => match value.as_bytes() {
+            [byte] if byte.is_ascii() => Some(Constant::Int(i128::from(*byte))),
+            _ => None,
+        },
+        Some(p) if p.is_float() => value
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .map(Constant::Float),
+        Some(_) => value.parse::<i128>().ok().map(Constant::Int),
+        None => None,
+    };
+    parsed.unwrap_or(Constant::Invalid(value))
+}
+
+/// Escapes printable ASCII `text` for a double-quoted C++ or Java string
+/// literal.
+pub(crate) fn escape_string(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// A trailing section of a message or group entry, in wire order.
+#[derive(Clone, Copy)]
+pub(crate) enum Section<'s> {
+    /// Repeating group.
+    Group(&'s ResolvedGroup),
+    /// Variable-length data field.
+    VarData(&'s ResolvedVarData),
+}
+
+impl Section<'_> {
+    /// Returns the schema name of the section.
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Self::Group(group) => &group.name,
+            Self::VarData(data) => &data.name,
+        }
+    }
+}
+
+/// Collects the trailing sections in wire order: groups first, then var data.
+pub(crate) fn sections<'s>(
+    groups: &'s [ResolvedGroup],
+    var_data: &'s [ResolvedVarData],
+) -> Vec<Section<'s>> {
+    groups
+        .iter()
+        .map(Section::Group)
+        .chain(var_data.iter().map(Section::VarData))
+        .collect()
+}
+
+/// Returns the block length written for entries of `group`.
+///
+/// Uses the schema value if nonzero, and the end of the last field
+/// otherwise.
+pub(crate) fn entry_block_length(group: &ResolvedGroup) -> u16 {
+    if group.block_length > 0 {
+        return group.block_length;
+    }
+    group
+        .fields
+        .iter()
+        .map(|f| f.offset + f.encoded_length)
+        .max()
+        .unwrap_or(0) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_writer_indents_blocks() {
+        let mut w = SourceWriter::new("    ");
+        w.open("class A {");
+        w.line("int x;");
+        w.blank();
+        w.close("};");
+        w.blank();
+        w.line("}");
+        assert_eq!(w.finish(), "class A {\n    int x;\n};\n\n}\n");
+    }
+
+    #[test]
+    fn test_to_camel_case() {
+        assert_eq!(to_camel_case("quoteId"), "quoteId");
+        assert_eq!(to_camel_case("QuoteId"), "quoteId");
+        assert_eq!(to_camel_case("last_fill"), "lastFill");
+    }
+
+    #[test]
+    fn test_escape_keyword() {
+        assert_eq!(escape_keyword("class".into(), &["class"]), "class_");
+        assert_eq!(escape_keyword("price".into(), &["class"]), "price");
+    }
+
+    #[test]
+    fn test_constant_classification() {
+        let literal = |value: &str| ConstantValue::Literal(value.to_string());
+        assert!(matches!(
+            constant(&literal("A"), Some(PrimitiveType::Char), false),
+            Constant::Int(65)
+        ));
+        assert!(matches!(
+            constant(&literal("XCME"), Some(PrimitiveType::Char), true),
+            Constant::Text("XCME")
+        ));
+        assert!(matches!(
+            constant(&literal("-4"), Some(PrimitiveType::Int8), false),
+            Constant::Int(-4)
+        ));
+        assert!(matches!(
+            constant(&literal("0.5"), Some(PrimitiveType::Float), false),
+            Constant::Float(_)
+        ));
+        assert!(matches!(
+            constant(&literal("abc"), Some(PrimitiveType::Uint32), false),
+            Constant::Invalid("abc")
+        ));
+    }
+}
@@ -4,16 +4,35 @@
 //!
 //! This crate provides:
 //! - Rust code generation from SBE schemas
+//! - C++ and Java flyweights wire-compatible with the Rust output
 //! - Message encoder/decoder generation
+//! - A `MessageVisitor` trait and `dispatch` function routing by template ID
+//! - Optional round-trip tests and a golden sample corpus per message
+//! - Optional change-detecting setters with a dirty mask for template reuse
+//! - Optional `extern "C"` accessor shims and a C header for other languages
+//! - `cargo-fuzz` targets feeding arbitrary bytes into the decoders
+//! - Conformance test vectors and a verifier for other SBE implementations
 //! - Type and enum generation
+//! - Message filtering and identifier renaming
+//! - Schemas split across several XML files
+//! - `no_std`, alloc-free output for embedded targets
 //! - Build script integration
 
+pub mod config;
+pub mod conformance;
+pub mod cpp;
 pub mod error;
+mod filter;
 pub mod generator;
+pub mod java;
+mod lang;
 pub mod rust;
 
+pub use config::CodegenConfig;
+pub use conformance::{ConformanceReport, ConformanceSuite, VectorOutcome};
 pub use error::CodegenError;
-pub use generator::Generator;
+pub use generator::{Generator, Language};
+pub use rust::{FuzzHarness, FuzzTarget, GoldenField, GoldenMessage};
 
 /// Generates Rust code from an SBE XML schema string.
 ///
@@ -26,9 +45,30 @@ pub use generator::Generator;
 /// # Errors
 /// Returns `CodegenError` if parsing or generation fails.
 pub fn generate_from_xml(xml: &str) -> Result<String, CodegenError> {
+    generate_from_xml_with_config(xml, &CodegenConfig::default())
+}
+
+/// Generates Rust code from an SBE XML schema string with custom options.
+///
+/// # Arguments
+/// * `xml` - SBE XML schema content
+/// * `config` - Code generation options
+///
+/// # Returns
+/// Generated Rust code as a string.
+///
+/// # Errors
+/// Returns `CodegenError` if parsing or generation fails.
+pub fn generate_from_xml_with_config(
+    xml: &str,
+    config: &CodegenConfig,
+) -> Result<String, CodegenError> {
+    if config.strict_schema {
+        ironsbe_schema::check_strict(xml)?;
+    }
     let schema = ironsbe_schema::parse_schema(xml)?;
     let ir = ironsbe_schema::SchemaIr::from_schema(&schema);
-    let generator = Generator::new(&ir);
+    let generator = Generator::with_config(&ir, config.clone());
     Ok(generator.generate())
 }
 
@@ -43,6 +83,87 @@ pub fn generate_from_xml(xml: &str) -> Result<String, CodegenError> {
 /// # Errors
 /// Returns `CodegenError` if reading, parsing, or generation fails.
 pub fn generate_from_file(path: &std::path::Path) -> Result<String, CodegenError> {
+    generate_from_file_with_config(path, &CodegenConfig::default())
+}
+
+/// Generates Rust code from an SBE XML schema file with custom options.
+///
+/// `<xi:include>` elements are resolved relative to the file's directory.
+///
+/// # Arguments
+/// * `path` - Path to the SBE XML schema file
+/// * `config` - Code generation options
+///
+/// # Returns
+/// Generated Rust code as a string.
+///
+/// # Errors
+/// Returns `CodegenError` if reading, parsing, or generation fails.
+pub fn generate_from_file_with_config(
+    path: &std::path::Path,
+    config: &CodegenConfig,
+) -> Result<String, CodegenError> {
+    let xml = read_with_includes(path)?;
+    generate_from_xml_with_config(&xml, config)
+}
+
+/// Reads a schema file and expands its `<xi:include>` elements relative to
+/// the file's directory.
+fn read_with_includes(path: &std::path::Path) -> Result<String, CodegenError> {
     let xml = std::fs::read_to_string(path)?;
-    generate_from_xml(&xml)
+    let base = path.parent().unwrap_or(std::path::Path::new("."));
+    let expanded = ironsbe_schema::resolve_includes(&xml, ironsbe_schema::file_resolver(base))?;
+    Ok(expanded.into_owned())
+}
+
+/// Generates Rust code from an SBE schema split across several XML files.
+///
+/// The files are merged into one schema as described in
+/// [`ironsbe_schema::parse_schemas`], so the output is a single module even
+/// when common types and messages live in different files. Each file's
+/// `<xi:include>` elements are resolved relative to its directory.
+///
+/// # Arguments
+/// * `paths` - Paths to the SBE XML schema files
+///
+/// # Returns
+/// Generated Rust code as a string.
+///
+/// # Errors
+/// Returns `CodegenError` if reading or parsing fails, or if the files
+/// conflict.
+pub fn generate_from_files<P: AsRef<std::path::Path>>(paths: &[P]) -> Result<String, CodegenError> {
+    generate_from_files_with_config(paths, &CodegenConfig::default())
+}
+
+/// Generates Rust code from an SBE schema split across several XML files
+/// with custom options.
+///
+/// # Arguments
+/// * `paths` - Paths to the SBE XML schema files
+/// * `config` - Code generation options
+///
+/// # Returns
+/// Generated Rust code as a string.
+///
+/// # Errors
+/// Returns `CodegenError` if reading or parsing fails, or if the files
+/// conflict.
+pub fn generate_from_files_with_config<P: AsRef<std::path::Path>>(
+    paths: &[P],
+    config: &CodegenConfig,
+) -> Result<String, CodegenError> {
+    let xmls = paths
+        .iter()
+        .map(|path| read_with_includes(path.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let xmls: Vec<&str> = xmls.iter().map(String::as_str).collect();
+    if config.strict_schema {
+        xmls.iter()
+            .try_for_each(|xml| ironsbe_schema::check_strict(xml))?;
+    }
+    let schema = ironsbe_schema::parse_schemas(&xmls)?;
+    let ir = ironsbe_schema::SchemaIr::from_schema(&schema);
+    let generator = Generator::with_config(&ir, config.clone());
+    Ok(generator.generate())
 }
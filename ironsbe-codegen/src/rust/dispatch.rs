@@ -0,0 +1,179 @@
+//! Message visitor and dispatch code generation.
+//!
+//! Emits a `MessageVisitor` trait with one method per message and a
+//! `dispatch` function that routes a buffer to the right method by template
+//! ID, so handlers do not need to match on template IDs themselves.
+
+use ironsbe_schema::ir::{SchemaIr, to_snake_case};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::CodegenConfig;
+use crate::rust::{doc, format_tokens, ident};
+
+/// Generator for the message visitor trait and dispatch function.
+pub struct DispatchGenerator<'a> {
+    ir: &'a SchemaIr,
+    config: CodegenConfig,
+}
+
+impl<'a> DispatchGenerator<'a> {
+    /// Creates a new dispatch generator.
+    #[must_use]
+    pub fn new(ir: &'a SchemaIr) -> Self {
+        Self::with_config(ir, CodegenConfig::default())
+    }
+
+    /// Creates a new dispatch generator matching decoders generated with
+    /// `config`.
+    #[must_use]
+    pub fn with_config(ir: &'a SchemaIr, config: CodegenConfig) -> Self {
+        Self { ir, config }
+    }
+
+    /// Generates the visitor and dispatch function as formatted source.
+    #[must_use]
+    pub fn generate(&self) -> String {
+        format_tokens(self.generate_tokens())
+    }
+
+    /// Generates the visitor and dispatch function.
+    ///
+    /// Emits nothing for a schema without messages.
+    #[must_use]
+    pub fn generate_tokens(&self) -> TokenStream {
+        if self.ir.messages.is_empty() {
+            return TokenStream::new();
+        }
+
+        let methods = self.ir.messages.iter().map(|msg| {
+            let method_doc = doc(&format!(
+                "Called with a decoded {} message (template ID {}).",
+                msg.name, msg.template_id
+            ));
+            let method = format_ident!("visit_{}", to_snake_case(&msg.name));
+            let decoder = ident(&msg.decoder_name());
+            quote! {
+                #method_doc
+                fn #method(&mut self, message: #decoder<'_>) {
+                    let _ = message;
+                }
+            }
+        });
+
+        // Decoders with unchecked reads check the root block in `wrap`.
+        let checked_wrap = self.config.unchecked_reads.then(|| quote! { ? });
+        let arms = self.ir.messages.iter().map(|msg| {
+            let method = format_ident!("visit_{}", to_snake_case(&msg.name));
+            let decoder = ident(&msg.decoder_name());
+            quote! {
+                #decoder::TEMPLATE_ID => {
+                    let message = #decoder::wrap_with_block_length(
+                        buffer,
+                        MessageHeader::ENCODED_LENGTH,
+                        header.block_length,
+                        header.version,
+                    )#checked_wrap;
+                    visitor.#method(message);
+                }
+            }
+        });
+
+        quote! {
+            /// Receives the messages routed by [`dispatch`], one method per message.
+            ///
+            /// Every method does nothing by default, so implementors only
+            /// override the messages they handle.
+            pub trait MessageVisitor {
+                #(#methods)*
+                /// Called for a message whose template ID is not in this schema.
+                ///
+                /// `buffer` holds the full message, starting with `header`.
+                fn unknown_template(&mut self, header: &MessageHeader, buffer: &[u8]) {
+                    let _ = (header, buffer);
+                }
+            }
+
+            /// Decodes the message in `buffer` and passes it to the matching
+            /// `visitor` method.
+            ///
+            /// `buffer` holds the full message starting with its header, and
+            /// `header` is that header as read by [`MessageHeader::wrap`].
+            ///
+            /// # Errors
+            /// Returns [`DecodeError::SchemaMismatch`] if the message belongs to
+            /// another schema, and [`DecodeError::BufferTooShort`] if `buffer`
+            /// does not hold the header and the root block.
+            pub fn dispatch<V: MessageVisitor + ?Sized>(
+                header: &MessageHeader,
+                buffer: &[u8],
+                visitor: &mut V,
+            ) -> Result<(), DecodeError> {
+                if header.schema_id != SCHEMA_ID {
+                    return Err(DecodeError::SchemaMismatch {
+                        expected: SCHEMA_ID,
+                        actual: header.schema_id,
+                    });
+                }
+                let required = MessageHeader::ENCODED_LENGTH + header.block_length as usize;
+                if buffer.len() < required {
+                    return Err(DecodeError::BufferTooShort {
+                        required,
+                        available: buffer.len(),
+                    });
+                }
+                match header.template_id {
+                    #(#arms)*
+                    _ => visitor.unknown_template(header, buffer),
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironsbe_schema::parse_schema;
+
+    #[test]
+    fn test_generate_visitor_and_dispatch() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <sbe:message name="NewOrder" id="1" blockLength="8">
+        <field name="orderId" id="1" type="uint64" offset="0"/>
+    </sbe:message>
+    <sbe:message name="CancelOrder" id="2" blockLength="8">
+        <field name="orderId" id="1" type="uint64" offset="0"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+        let code = DispatchGenerator::new(&ir).generate();
+
+        assert!(code.contains("pub trait MessageVisitor"));
+        assert!(code.contains("fn visit_new_order(&mut self, message: NewOrderDecoder<'_>)"));
+        assert!(code.contains("fn visit_cancel_order(&mut self, message: CancelOrderDecoder<'_>)"));
+        assert!(code.contains("CancelOrderDecoder::TEMPLATE_ID => {"));
+        assert!(code.contains("visitor.visit_cancel_order(message);"));
+        assert!(code.contains("pub fn dispatch<V: MessageVisitor + ?Sized>("));
+    }
+
+    #[test]
+    fn test_generate_nothing_without_messages() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <type name="uint64" primitiveType="uint64"/>
+    </types>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+        assert!(DispatchGenerator::new(&ir).generate_tokens().is_empty());
+    }
+}
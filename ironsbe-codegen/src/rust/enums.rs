@@ -5,37 +5,53 @@ use ironsbe_schema::ir::{
     to_snake_case,
 };
 use ironsbe_schema::types::PrimitiveType;
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use crate::config::CodegenConfig;
+use crate::rust::{doc, format_tokens, ident, lit, serde_derive, signed_lit};
 
 /// Generator for enum and set definitions.
 pub struct EnumGenerator<'a> {
     ir: &'a SchemaIr,
+    config: CodegenConfig,
 }
 
 impl<'a> EnumGenerator<'a> {
     /// Creates a new enum generator.
     #[must_use]
     pub fn new(ir: &'a SchemaIr) -> Self {
-        Self { ir }
+        Self::with_config(ir, CodegenConfig::default())
     }
 
-    /// Generates all enum and set definitions.
+    /// Creates a new enum generator with custom options.
+    #[must_use]
+    pub fn with_config(ir: &'a SchemaIr, config: CodegenConfig) -> Self {
+        Self { ir, config }
+    }
+
+    /// Generates all enum and set definitions as formatted source.
     #[must_use]
     pub fn generate(&self) -> String {
-        let mut output = String::new();
+        format_tokens(self.generate_tokens())
+    }
 
-        for resolved_type in self.ir.types.values() {
-            match &resolved_type.kind {
+    /// Generates all enum and set definitions.
+    #[must_use]
+    pub fn generate_tokens(&self) -> TokenStream {
+        self.ir
+            .sorted_types()
+            .into_iter()
+            .map(|resolved_type| match &resolved_type.kind {
                 TypeKind::Enum { encoding, variants } => {
-                    output.push_str(&self.generate_enum(&resolved_type.name, *encoding, variants));
+                    self.generate_enum(&resolved_type.name, *encoding, variants)
                 }
                 TypeKind::Set { encoding, choices } => {
-                    output.push_str(&self.generate_set(&resolved_type.name, *encoding, choices));
+                    self.generate_set(&resolved_type.name, *encoding, choices)
                 }
-                _ => {}
-            }
-        }
-
-        output
+                _ => TokenStream::new(),
+            })
+            .collect()
     }
 
     /// Generates an enum definition.
@@ -44,152 +60,225 @@ impl<'a> EnumGenerator<'a> {
         name: &str,
         encoding: PrimitiveType,
         variants: &[EnumVariant],
-    ) -> String {
-        let mut output = String::new();
+    ) -> TokenStream {
         let rust_name = to_pascal_case(name);
-        let rust_type = encoding.rust_type();
-
-        output.push_str(&format!("/// {} enum.\n", rust_name));
-        output.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n");
-        output.push_str(&format!("#[repr({})]\n", rust_type));
-        output.push_str(&format!("pub enum {} {{\n", rust_name));
+        let name_ident = ident(&rust_name);
+        let rust_type = ident(encoding.rust_type());
+        let enum_doc = doc(&format!("{} enum.", rust_name));
+        let serde = self.config.derive_serde.then(serde_derive);
 
         // Generate enum variants from schema
-        for variant in variants {
+        let variant_defs = variants.iter().map(|variant| {
             let variant_name = to_pascal_case(&variant.name);
-            output.push_str(&format!("    /// {} variant.\n", variant_name));
-            output.push_str(&format!("    {} = {},\n", variant_name, variant.value));
-        }
-
-        output.push_str("}\n\n");
-
-        // Implement From<primitive> -> Enum (safe match, no transmute)
-        // Only generate if variants exist to avoid empty match
-        if !variants.is_empty() {
-            output.push_str(&format!("impl From<{}> for {} {{\n", rust_type, rust_name));
-            output.push_str(&format!("    fn from(value: {}) -> Self {{\n", rust_type));
-            output.push_str("        match value {\n");
-            for variant in variants {
-                let variant_name = to_pascal_case(&variant.name);
-                output.push_str(&format!(
-                    "            {} => Self::{},\n",
-                    variant.value, variant_name
-                ));
+            let variant_doc = doc(&format!("{} variant.", variant_name));
+            let variant_ident = ident(&variant_name);
+            let value = signed_lit(variant.value);
+            quote! {
+                #variant_doc
+                #variant_ident = #value,
             }
-            // Default to first variant for unknown values
-            let first_name = to_pascal_case(&variants[0].name);
-            output.push_str(&format!("            _ => Self::{},\n", first_name));
-            output.push_str("        }\n");
-            output.push_str("    }\n");
-            output.push_str("}\n\n");
-        }
-
-        // Implement From<Enum> -> primitive
-        if !variants.is_empty() {
-            output.push_str(&format!("impl From<{}> for {} {{\n", rust_name, rust_type));
-            output.push_str(&format!("    fn from(value: {}) -> Self {{\n", rust_name));
-            output.push_str("        value as Self\n");
-            output.push_str("    }\n");
-            output.push_str("}\n\n");
-        }
+        });
+
+        let mut output = quote! {
+            #enum_doc
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+            #serde
+            #[repr(#rust_type)]
+            pub enum #name_ident {
+                #(#variant_defs)*
+            }
+        };
 
+        let variants: Vec<_> = variants
+            .iter()
+            .map(|variant| (ident(&to_pascal_case(&variant.name)), variant.value))
+            .collect();
+        output.extend(enum_impls(&name_ident, encoding, &variants, false));
         output
     }
 
     /// Generates a set (bitfield) definition.
-    fn generate_set(&self, name: &str, encoding: PrimitiveType, choices: &[SetVariant]) -> String {
-        let mut output = String::new();
+    fn generate_set(
+        &self,
+        name: &str,
+        encoding: PrimitiveType,
+        choices: &[SetVariant],
+    ) -> TokenStream {
         let rust_name = to_pascal_case(name);
-        let rust_type = encoding.rust_type();
-
-        output.push_str(&format!("/// {} bitfield set.\n", rust_name));
-        output.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]\n");
-        output.push_str(&format!("pub struct {}({});\n\n", rust_name, rust_type));
-
-        output.push_str(&format!("impl {} {{\n", rust_name));
-
-        // Generate bit position constants for each choice
-        for choice in choices {
-            let const_name = to_screaming_snake_case(&choice.name);
-            output.push_str(&format!(
-                "    /// Bit position for {} choice.\n",
-                choice.name
-            ));
-            output.push_str(&format!(
-                "    pub const {}: u8 = {};\n",
-                const_name, choice.bit_position
-            ));
-        }
-        if !choices.is_empty() {
-            output.push('\n');
+        let name_ident = ident(&rust_name);
+        let rust_type = ident(encoding.rust_type());
+        let set_doc = doc(&format!("{} bitfield set.", rust_name));
+        let serde = self.config.derive_serde.then(serde_derive);
+        let methods = set_impls(&name_ident, encoding, choices);
+
+        quote! {
+            #set_doc
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+            #serde
+            pub struct #name_ident(#rust_type);
+            #methods
         }
+    }
+}
 
-        output.push_str(&format!("    /// Creates a new empty {}.\n", rust_name));
-        output.push_str("    #[must_use]\n");
-        output.push_str("    pub const fn new() -> Self {\n");
-        output.push_str("        Self(0)\n");
-        output.push_str("    }\n\n");
-
-        output.push_str(&format!("    /// Creates from raw {} value.\n", rust_type));
-        output.push_str("    #[must_use]\n");
-        output.push_str(&format!(
-            "    pub const fn from_raw(value: {}) -> Self {{\n",
-            rust_type
-        ));
-        output.push_str("        Self(value)\n");
-        output.push_str("    }\n\n");
-
-        output.push_str("    /// Returns the raw value.\n");
-        output.push_str("    #[must_use]\n");
-        output.push_str(&format!(
-            "    pub const fn raw(&self) -> {} {{\n",
-            rust_type
-        ));
-        output.push_str("        self.0\n");
-        output.push_str("    }\n\n");
-
-        output.push_str("    /// Checks if a bit is set.\n");
-        output.push_str("    #[must_use]\n");
-        output.push_str("    pub const fn is_set(&self, bit: u8) -> bool {\n");
-        output.push_str("        (self.0 >> bit) & 1 != 0\n");
-        output.push_str("    }\n\n");
-
-        output.push_str("    /// Sets a bit.\n");
-        output.push_str("    pub fn set(&mut self, bit: u8) {\n");
-        output.push_str("        self.0 |= 1 << bit;\n");
-        output.push_str("    }\n\n");
-
-        output.push_str("    /// Clears a bit.\n");
-        output.push_str("    pub fn clear(&mut self, bit: u8) {\n");
-        output.push_str("        self.0 &= !(1 << bit);\n");
-        output.push_str("    }\n");
-
-        // Generate named methods for each choice
-        for choice in choices {
-            let method_name = to_snake_case(&choice.name);
-            output.push_str(&format!("\n    /// Checks if {} is set.\n", choice.name));
-            output.push_str("    #[must_use]\n");
-            output.push_str(&format!(
-                "    pub const fn is_{}(&self) -> bool {{\n",
-                method_name
-            ));
-            output.push_str(&format!("        self.is_set({})\n", choice.bit_position));
-            output.push_str("    }\n");
-
-            output.push_str(&format!("\n    /// Sets {}.\n", choice.name));
-            output.push_str(&format!("    pub fn set_{}(&mut self) {{\n", method_name));
-            output.push_str(&format!("        self.set({});\n", choice.bit_position));
-            output.push_str("    }\n");
-
-            output.push_str(&format!("\n    /// Clears {}.\n", choice.name));
-            output.push_str(&format!("    pub fn clear_{}(&mut self) {{\n", method_name));
-            output.push_str(&format!("        self.clear({});\n", choice.bit_position));
-            output.push_str("    }\n");
+/// Conversions between an enum and its encoding primitive.
+///
+/// Emits `From<enum>` for the primitive and a conversion back. Generated
+/// enums get a lenient `From<primitive>`, which decoder accessors use, that
+/// maps unknown values to the first variant. With `checked`, as
+/// `#[derive(SbeEnum)]` asks for, it is `TryFrom<primitive>` instead,
+/// failing with `UnknownEnumValue` (named unqualified) on values no variant
+/// has. Emits nothing if `variants` is empty.
+#[must_use]
+pub fn enum_impls(
+    name: &Ident,
+    encoding: PrimitiveType,
+    variants: &[(Ident, i64)],
+    checked: bool,
+) -> TokenStream {
+    let Some((first_ident, _)) = variants.first() else {
+        return TokenStream::new();
+    };
+    let rust_type = ident(encoding.rust_type());
+    let from_primitive = if checked {
+        let arms = variants.iter().map(|(variant_ident, value)| {
+            let value = signed_lit(*value);
+            quote! { #value => Ok(Self::#variant_ident), }
+        });
+        quote! {
+            impl TryFrom<#rust_type> for #name {
+                type Error = UnknownEnumValue<#rust_type>;
+
+                #[inline]
+                fn try_from(value: #rust_type) -> Result<Self, Self::Error> {
+                    match value {
+                        #(#arms)*
+                        _ => Err(UnknownEnumValue(value)),
+                    }
+                }
+            }
         }
+    } else {
+        let arms = variants.iter().map(|(variant_ident, value)| {
+            let value = signed_lit(*value);
+            quote! { #value => Self::#variant_ident, }
+        });
+        // Safe match, no transmute; default to first variant for unknown values
+        quote! {
+            impl From<#rust_type> for #name {
+                #[inline]
+                fn from(value: #rust_type) -> Self {
+                    match value {
+                        #(#arms)*
+                        _ => Self::#first_ident,
+                    }
+                }
+            }
+        }
+    };
+
+    quote! {
+        #from_primitive
+        impl From<#name> for #rust_type {
+            #[inline]
+            fn from(value: #name) -> Self {
+                value as Self
+            }
+        }
+    }
+}
 
-        output.push_str("}\n\n");
-
-        output
+/// Inherent impl for a set newtype over its encoding primitive.
+///
+/// Emits a bit position constant and `is_*`/`set_*`/`clear_*` accessors per
+/// choice, plus raw conversions. Shared with `#[derive(SbeSet)]`.
+#[must_use]
+pub fn set_impls(name: &Ident, encoding: PrimitiveType, choices: &[SetVariant]) -> TokenStream {
+    let rust_name = name.to_string();
+    let rust_type = ident(encoding.rust_type());
+    let new_doc = doc(&format!("Creates a new empty {}.", rust_name));
+    let from_raw_doc = doc(&format!("Creates from raw {} value.", rust_type));
+
+    // Generate bit position constants for each choice
+    let constants = choices.iter().map(|choice| {
+        let const_doc = doc(&format!("Bit position for {} choice.", choice.name));
+        let const_name = ident(&to_screaming_snake_case(&choice.name));
+        let bit = lit(usize::from(choice.bit_position));
+        quote! {
+            #const_doc
+            pub const #const_name: u8 = #bit;
+        }
+    });
+
+    // Generate named methods for each choice
+    let methods = choices.iter().map(|choice| {
+        let method_name = to_snake_case(&choice.name);
+        let is_name = format_ident!("is_{}", method_name);
+        let set_name = format_ident!("set_{}", method_name);
+        let clear_name = format_ident!("clear_{}", method_name);
+        let is_doc = doc(&format!("Checks if {} is set.", choice.name));
+        let set_doc = doc(&format!("Sets {}.", choice.name));
+        let clear_doc = doc(&format!("Clears {}.", choice.name));
+        let bit = lit(usize::from(choice.bit_position));
+        quote! {
+            #is_doc
+            #[inline]
+            #[must_use]
+            pub const fn #is_name(&self) -> bool {
+                self.is_set(#bit)
+            }
+            #set_doc
+            #[inline]
+            pub fn #set_name(&mut self) {
+                self.set(#bit);
+            }
+            #clear_doc
+            #[inline]
+            pub fn #clear_name(&mut self) {
+                self.clear(#bit);
+            }
+        }
+    });
+
+    quote! {
+        impl #name {
+            #(#constants)*
+            #new_doc
+            #[inline]
+            #[must_use]
+            pub const fn new() -> Self {
+                Self(0)
+            }
+            #from_raw_doc
+            #[inline]
+            #[must_use]
+            pub const fn from_raw(value: #rust_type) -> Self {
+                Self(value)
+            }
+            /// Returns the raw value.
+            #[inline]
+            #[must_use]
+            pub const fn raw(&self) -> #rust_type {
+                self.0
+            }
+            /// Checks if a bit is set.
+            #[inline]
+            #[must_use]
+            pub const fn is_set(&self, bit: u8) -> bool {
+                (self.0 >> bit) & 1 != 0
+            }
+            /// Sets a bit.
+            #[inline]
+            pub fn set(&mut self, bit: u8) {
+                self.0 |= 1 << bit;
+            }
+            /// Clears a bit.
+            #[inline]
+            pub fn clear(&mut self, bit: u8) {
+                self.0 &= !(1 << bit);
+            }
+            #(#methods)*
+        }
     }
 }
 
@@ -0,0 +1,382 @@
+//! C FFI accessor shim generation.
+//!
+//! Emits an `ffi` module of `extern "C"` functions, one per root block
+//! field, that decode the field from a raw pointer and length through the
+//! generated decoder. C, C++ or Python (via `ctypes`) code linked against
+//! the crate then reads messages with the same layout logic as the Rust
+//! side. [`FfiGenerator::generate_c_header`] writes the matching C header.
+//!
+//! Constant and composite fields, groups and var data get no shim.
+
+use ironsbe_schema::ir::{ResolvedField, ResolvedMessage, SchemaIr, TypeKind, to_snake_case};
+use ironsbe_schema::types::PrimitiveType;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::CodegenConfig;
+use crate::lang::SourceWriter;
+use crate::rust::{doc, format_tokens, ident, lit};
+
+/// Generator for C FFI accessor shims.
+pub struct FfiGenerator<'a> {
+    ir: &'a SchemaIr,
+    config: CodegenConfig,
+}
+
+/// How a shim hands a field to the caller.
+enum ShimOutput {
+    /// Writes one value of the primitive type through `out`.
+    Value {
+        prim: PrimitiveType,
+        /// Expression converting the getter's result to `prim`.
+        convert: fn(TokenStream) -> TokenStream,
+    },
+    /// Copies the field's bytes to `out`.
+    Bytes { len: usize, is_char: bool },
+}
+
+/// One generated accessor.
+struct Shim<'a> {
+    message: &'a ResolvedMessage,
+    field: &'a ResolvedField,
+    symbol: String,
+    output: ShimOutput,
+}
+
+impl<'a> FfiGenerator<'a> {
+    /// Creates a new FFI shim generator.
+    #[must_use]
+    pub fn new(ir: &'a SchemaIr) -> Self {
+        Self::with_config(ir, CodegenConfig::default())
+    }
+
+    /// Creates a new FFI shim generator over decoders generated with
+    /// `config`.
+    #[must_use]
+    pub fn with_config(ir: &'a SchemaIr, config: CodegenConfig) -> Self {
+        Self { ir, config }
+    }
+
+    /// Generates the `ffi` module as formatted source.
+    #[must_use]
+    pub fn generate(&self) -> String {
+        format_tokens(self.generate_tokens())
+    }
+
+    /// Generates the `ffi` module.
+    ///
+    /// Emits nothing for a schema without messages.
+    #[must_use]
+    pub fn generate_tokens(&self) -> TokenStream {
+        if self.ir.messages.is_empty() {
+            return TokenStream::new();
+        }
+        let shims = self
+            .shims()
+            .into_iter()
+            .map(|shim| self.generate_shim(&shim));
+
+        quote! {
+            /// C-callable field accessors over encoded messages.
+            ///
+            /// Every function takes a full message, starting with its header,
+            /// as `buffer` and `len`, and writes one root block field to
+            /// `out`. It returns `false` and leaves `out` untouched if a
+            /// pointer is null, the message belongs to another template or
+            /// schema, or the buffer or the message's root block is too short
+            /// to hold the field.
+            pub mod ffi {
+                use super::*;
+
+                /// Returns the block length and acting version of the
+                /// `template_id` message in `buffer`, if its root block holds the
+                /// first `field_end` bytes.
+                fn acting(buffer: &[u8], template_id: u16, field_end: usize) -> Option<(u16, u16)> {
+                    let header = MessageHeader::try_wrap(buffer, 0)?;
+                    let block_length = header.block_length as usize;
+                    (header.schema_id == SCHEMA_ID
+                        && header.template_id == template_id
+                        && field_end <= block_length
+                        && buffer.len() >= MessageHeader::ENCODED_LENGTH + block_length)
+                        .then_some((header.block_length, header.version))
+                }
+
+                /// Borrows the `len` bytes at `buffer`, or returns `None` for a
+                /// null pointer.
+                ///
+                /// # Safety
+                /// A non-null `buffer` must point to `len` readable bytes that
+                /// outlive `'a`.
+                unsafe fn message<'a>(buffer: *const u8, len: usize) -> Option<&'a [u8]> {
+                    (!buffer.is_null()).then(|| unsafe { core::slice::from_raw_parts(buffer, len) })
+                }
+
+                #(#shims)*
+            }
+        }
+    }
+
+    /// Generates the C header declaring every shim.
+    #[must_use]
+    pub fn generate_c_header(&self) -> String {
+        let guard = format!(
+            "{}_SBE_FFI_H",
+            symbol_prefix(&self.ir.package).to_uppercase()
+        );
+        let mut w = SourceWriter::new("    ");
+        w.line(format!("#ifndef {guard}"));
+        w.line(format!("#define {guard}"));
+        w.blank();
+        w.line("#include <stdbool.h>");
+        w.line("#include <stddef.h>");
+        w.line("#include <stdint.h>");
+        w.blank();
+        w.line("#ifdef __cplusplus");
+        w.line("extern \"C\" {");
+        w.line("#endif");
+
+        let mut current = None;
+        for shim in self.shims() {
+            if current != Some(shim.message.template_id) {
+                current = Some(shim.message.template_id);
+                w.blank();
+                w.line(format!(
+                    "/* {} (template ID {}) */",
+                    shim.message.name, shim.message.template_id
+                ));
+            }
+            let out = match &shim.output {
+                ShimOutput::Value { prim, .. } => format!("{} *out", c_type(*prim)),
+                ShimOutput::Bytes { len, is_char } => {
+                    let ty = if *is_char { "char" } else { "uint8_t" };
+                    format!("{ty} out[{len}]")
+                }
+            };
+            w.line(format!(
+                "bool {}(const uint8_t *buffer, size_t len, {out});",
+                shim.symbol
+            ));
+        }
+
+        w.blank();
+        w.line("#ifdef __cplusplus");
+        w.line("}");
+        w.line("#endif");
+        w.blank();
+        w.line(format!("#endif /* {guard} */"));
+        w.finish()
+    }
+
+    /// Returns the accessor of every eligible root block field.
+    fn shims(&self) -> Vec<Shim<'a>> {
+        let prefix = symbol_prefix(&self.ir.package);
+        self.ir
+            .messages
+            .iter()
+            .flat_map(|message| {
+                let prefix = &prefix;
+                message.fields.iter().filter_map(move |field| {
+                    let output = self.output(field)?;
+                    Some(Shim {
+                        message,
+                        field,
+                        symbol: format!(
+                            "{prefix}_{}_{}",
+                            to_snake_case(&message.name),
+                            field.getter_name
+                        ),
+                        output,
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Returns how the shim for `field` outputs it, or `None` if the field
+    /// gets no shim.
+    fn output(&self, field: &ResolvedField) -> Option<ShimOutput> {
+        if field.constant.is_some() || field.encoded_length == 0 {
+            return None;
+        }
+        if field.is_array {
+            return Some(ShimOutput::Bytes {
+                len: field.encoded_length,
+                is_char: field.primitive_type == Some(PrimitiveType::Char),
+            });
+        }
+        match self.ir.get_type(&field.type_name).map(|t| &t.kind) {
+            Some(TypeKind::Enum { encoding, .. }) => Some(ShimOutput::Value {
+                prim: *encoding,
+                convert: |value| quote! { #value.into() },
+            }),
+            Some(TypeKind::Set { encoding, .. }) => Some(ShimOutput::Value {
+                prim: *encoding,
+                convert: |value| quote! { #value.raw() },
+            }),
+            Some(TypeKind::Composite { .. }) => None,
+            _ => Some(ShimOutput::Value {
+                prim: field.primitive_type?,
+                convert: |value| value,
+            }),
+        }
+    }
+
+    /// Generates one accessor function.
+    fn generate_shim(&self, shim: &Shim<'_>) -> TokenStream {
+        let Shim {
+            message,
+            field,
+            symbol,
+            output,
+        } = shim;
+        let symbol = ident(symbol);
+        let decoder = ident(&message.decoder_name());
+        let wrap = quote! {
+            #decoder::wrap_with_block_length(buffer, MessageHeader::ENCODED_LENGTH, block_length, version)
+        };
+        // Decoders with unchecked reads check the root block in `wrap`.
+        let wrap = if self.config.unchecked_reads {
+            quote! {
+                let Ok(decoder) = #wrap else {
+                    return false;
+                };
+            }
+        } else {
+            quote! { let decoder = #wrap; }
+        };
+        let getter = ident(&field.getter_name);
+        let field_end = lit(field.offset + field.encoded_length);
+
+        let (out_type, out_desc, write) = match output {
+            ShimOutput::Value { prim, convert } => {
+                let prim_type = ident(prim.rust_type());
+                let value = convert(quote! { decoder.#getter() });
+                (
+                    quote! { *mut #prim_type },
+                    format!("a writable `{}`", prim.rust_type()),
+                    quote! {
+                        let value: #prim_type = #value;
+                        unsafe { out.write_unaligned(value) };
+                    },
+                )
+            }
+            ShimOutput::Bytes { len, .. } => (
+                quote! { *mut u8 },
+                format!("{len} writable bytes"),
+                quote! {
+                    let bytes = decoder.#getter();
+                    unsafe { core::ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len()) };
+                },
+            ),
+        };
+        let shim_doc = doc(&format!(
+            "Reads `{}.{}` (id={}) into `out`.\n\n\
+             # Safety\n\
+             `buffer` must be null or point to `len` readable bytes, and `out` \
+             must be null or point to {out_desc}.",
+            message.name, field.name, field.id
+        ));
+
+        quote! {
+            #shim_doc
+            #[unsafe(no_mangle)]
+            pub unsafe extern "C" fn #symbol(buffer: *const u8, len: usize, out: #out_type) -> bool {
+                let Some(buffer) = (unsafe { message(buffer, len) }) else {
+                    return false;
+                };
+                let Some((block_length, version)) = acting(buffer, #decoder::TEMPLATE_ID, #field_end) else {
+                    return false;
+                };
+                if out.is_null() {
+                    return false;
+                }
+                #wrap
+                #write
+                true
+            }
+        }
+    }
+}
+
+/// Symbol prefix for a schema package: snake case, with anything that is
+/// not valid in a C identifier replaced by `_`.
+fn symbol_prefix(package: &str) -> String {
+    to_snake_case(package)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// C type of a primitive.
+fn c_type(prim: PrimitiveType) -> &'static str {
+    match prim {
+        PrimitiveType::Char => "char",
+        PrimitiveType::Int8 => "int8_t",
+        PrimitiveType::Int16 => "int16_t",
+        PrimitiveType::Int32 => "int32_t",
+        PrimitiveType::Int64 => "int64_t",
+        PrimitiveType::Uint8 => "uint8_t",
+        PrimitiveType::Uint16 => "uint16_t",
+        PrimitiveType::Uint32 => "uint32_t",
+        PrimitiveType::Uint64 => "uint64_t",
+        PrimitiveType::Float => "float",
+        PrimitiveType::Double => "double",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironsbe_schema::parse_schema;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="trading.orders" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <type name="Symbol" primitiveType="char" length="4"/>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+            <validValue name="Sell">2</validValue>
+        </enum>
+    </types>
+    <sbe:message name="NewOrder" id="1" blockLength="13">
+        <field name="orderId" id="1" type="uint64" offset="0"/>
+        <field name="symbol" id="2" type="Symbol" offset="8"/>
+        <field name="side" id="3" type="Side" offset="12"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+    fn ir() -> SchemaIr {
+        SchemaIr::from_schema(&parse_schema(XML).expect("Failed to parse"))
+    }
+
+    #[test]
+    fn test_generate_shims() {
+        let code = FfiGenerator::new(&ir()).generate();
+
+        assert!(code.contains("pub mod ffi"));
+        assert!(code.contains("#[unsafe(no_mangle)]"));
+        assert!(code.contains(
+            "pub unsafe extern \"C\" fn trading_orders_new_order_order_id(\n        buffer: *const u8,\n        len: usize,\n        out: *mut u64,\n    ) -> bool"
+        ));
+        assert!(code.contains("fn trading_orders_new_order_side("));
+        assert!(code.contains("acting(\n            buffer,\n            NewOrderDecoder::TEMPLATE_ID,\n            13,\n        )"));
+    }
+
+    #[test]
+    fn test_generate_c_header() {
+        let header = FfiGenerator::new(&ir()).generate_c_header();
+
+        assert!(header.starts_with("#ifndef TRADING_ORDERS_SBE_FFI_H"));
+        assert!(header.contains("/* NewOrder (template ID 1) */"));
+        assert!(header.contains(
+            "bool trading_orders_new_order_order_id(const uint8_t *buffer, size_t len, uint64_t *out);"
+        ));
+        assert!(header.contains(
+            "bool trading_orders_new_order_symbol(const uint8_t *buffer, size_t len, char out[4]);"
+        ));
+        assert!(header.contains(
+            "bool trading_orders_new_order_side(const uint8_t *buffer, size_t len, uint8_t *out);"
+        ));
+    }
+}
@@ -0,0 +1,340 @@
+//! `cargo-fuzz` harness generation.
+//!
+//! Emits a fuzz crate manifest plus one `libfuzzer-sys` target routing
+//! arbitrary bytes through the generated `dispatch` function and one target
+//! per message feeding them straight into its decoder. Every target reads
+//! each field, group entry and var data field of the decoded message through
+//! its `Debug` implementation, so any out-of-bounds read is reported as a
+//! crash.
+
+use std::io;
+use std::path::Path;
+
+use ironsbe_schema::ir::{ResolvedMessage, SchemaIr, to_snake_case};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::rust::{format_tokens, ident};
+
+/// Name of the target decoding any message of the schema.
+pub const DISPATCH_TARGET: &str = "decode_any";
+
+/// Source of a single fuzz target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzTarget {
+    /// Target name, as passed to `cargo fuzz run`.
+    pub name: String,
+    /// Contents of `fuzz_targets/<name>.rs`.
+    pub source: String,
+}
+
+/// A complete `cargo-fuzz` crate for the generated decoders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzHarness {
+    /// Contents of the fuzz crate's `Cargo.toml`.
+    pub manifest: String,
+    /// One dispatching target followed by one target per message.
+    pub targets: Vec<FuzzTarget>,
+}
+
+impl FuzzHarness {
+    /// Writes the harness to `dir`, usually the `fuzz` directory next to the
+    /// crate including the generated code.
+    ///
+    /// # Errors
+    /// Returns an I/O error if a directory or file cannot be written.
+    pub fn write_to(&self, dir: &Path) -> io::Result<()> {
+        let targets_dir = dir.join("fuzz_targets");
+        std::fs::create_dir_all(&targets_dir)?;
+        std::fs::write(dir.join("Cargo.toml"), &self.manifest)?;
+        for target in &self.targets {
+            std::fs::write(
+                targets_dir.join(format!("{}.rs", target.name)),
+                &target.source,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Generator for `cargo-fuzz` targets over the generated decoders.
+pub struct FuzzGenerator<'a> {
+    ir: &'a SchemaIr,
+}
+
+impl<'a> FuzzGenerator<'a> {
+    /// Creates a new fuzz harness generator.
+    #[must_use]
+    pub fn new(ir: &'a SchemaIr) -> Self {
+        Self { ir }
+    }
+
+    /// Generates the fuzz crate for the codecs in `module` of `crate_name`.
+    ///
+    /// # Arguments
+    /// * `crate_name` - Package name of the crate including the generated
+    ///   code, expected one directory above the fuzz crate
+    /// * `module` - Path of the generated module inside that crate, such as
+    ///   `"codecs::trading"`, or `""` for the crate root
+    ///
+    /// A schema without messages yields a harness without targets.
+    #[must_use]
+    pub fn generate(&self, crate_name: &str, module: &str) -> FuzzHarness {
+        let codec_path = codec_path(crate_name, module);
+        let mut targets = Vec::with_capacity(self.ir.messages.len() + 1);
+        if !self.ir.messages.is_empty() {
+            targets.push(FuzzTarget {
+                name: DISPATCH_TARGET.to_string(),
+                source: target_source(self.generate_dispatch_target(&codec_path)),
+            });
+        }
+        for msg in &self.ir.messages {
+            targets.push(FuzzTarget {
+                name: message_target_name(msg),
+                source: target_source(generate_message_target(msg, &codec_path)),
+            });
+        }
+        FuzzHarness {
+            manifest: generate_manifest(crate_name, &targets),
+            targets,
+        }
+    }
+
+    /// Generates the target decoding a full message, header included, of
+    /// any template through `dispatch`.
+    fn generate_dispatch_target(&self, codec_path: &TokenStream) -> TokenStream {
+        let methods = self.ir.messages.iter().map(|msg| {
+            let method = format_ident!("visit_{}", to_snake_case(&msg.name));
+            let decoder = ident(&msg.decoder_name());
+            quote! {
+                fn #method(&mut self, message: #decoder<'_>) {
+                    walk(message);
+                }
+            }
+        });
+        let walk = generate_walk();
+        // A schema-specific header is generated into the codec itself.
+        let header_import = self
+            .ir
+            .message_header
+            .is_standard()
+            .then(|| quote! { use ironsbe_core::header::MessageHeader; });
+        quote! {
+            #![no_main]
+
+            use ironsbe_core::decoder::SbeDecoder;
+            #header_import
+            use libfuzzer_sys::fuzz_target;
+            use std::io::Write;
+            use #codec_path::*;
+
+            struct Walker;
+
+            impl MessageVisitor for Walker {
+                #(#methods)*
+            }
+
+            #walk
+
+            fn fuzz(data: &[u8]) {
+                let Some(header) = MessageHeader::try_wrap(data, 0) else {
+                    return;
+                };
+                let _ = dispatch(&header, data, &mut Walker);
+            }
+        }
+    }
+}
+
+/// Formats a target and appends its `fuzz_target!` entry point.
+///
+/// The macro call is added as text because `prettyplease` leaves macro
+/// arguments unformatted.
+fn target_source(tokens: TokenStream) -> String {
+    let mut source = format_tokens(tokens);
+    source.push_str("fuzz_target!(|data: &[u8]| fuzz(data));\n");
+    source
+}
+
+/// Returns the fuzz target name of `msg`.
+fn message_target_name(msg: &ResolvedMessage) -> String {
+    format!("decode_{}", to_snake_case(&msg.name))
+}
+
+/// Generates the target decoding a root block, without header, of `msg`.
+fn generate_message_target(msg: &ResolvedMessage, codec_path: &TokenStream) -> TokenStream {
+    let decoder = ident(&msg.decoder_name());
+    let walk = generate_walk();
+    quote! {
+        #![no_main]
+
+        use ironsbe_core::decoder::SbeDecoder;
+        use libfuzzer_sys::fuzz_target;
+        use std::io::Write;
+        use #codec_path::*;
+
+        #walk
+
+        fn fuzz(data: &[u8]) {
+            if data.len() < #decoder::BLOCK_LENGTH as usize {
+                return;
+            }
+            if let Ok(message) = #decoder::try_wrap(data, 0, SCHEMA_VERSION) {
+                walk(message);
+            }
+        }
+    }
+}
+
+/// Generates the `walk` function reading every part of a decoded message.
+fn generate_walk() -> TokenStream {
+    quote! {
+        /// Reads every field, group entry and var data field of `message`.
+        fn walk<'a, D: SbeDecoder<'a> + core::fmt::Debug>(message: D) {
+            let _ = message.encoded_length();
+            let _ = write!(std::io::sink(), "{message:?}");
+        }
+    }
+}
+
+/// Returns the path of the generated module as a token stream.
+fn codec_path(crate_name: &str, module: &str) -> TokenStream {
+    let segments = std::iter::once(crate_name.replace('-', "_"))
+        .chain(
+            module
+                .split("::")
+                .filter(|segment| !segment.is_empty())
+                .map(str::to_string),
+        )
+        .map(|segment| ident(&segment));
+    quote! { #(#segments)::* }
+}
+
+/// Generates the fuzz crate manifest, in the layout of `cargo fuzz init`.
+fn generate_manifest(crate_name: &str, targets: &[FuzzTarget]) -> String {
+    let mut manifest = format!(
+        "[package]\n\
+         name = \"{crate_name}-fuzz\"\n\
+         version = \"0.0.0\"\n\
+         publish = false\n\
+         edition = \"2024\"\n\
+         \n\
+         [package.metadata]\n\
+         cargo-fuzz = true\n\
+         \n\
+         [dependencies]\n\
+         libfuzzer-sys = \"0.4\"\n\
+         ironsbe-core = \"{version}\"\n\
+         \n\
+         [dependencies.{crate_name}]\n\
+         path = \"..\"\n",
+        version = env!("CARGO_PKG_VERSION"),
+    );
+    for target in targets {
+        manifest.push_str(&format!(
+            "\n[[bin]]\n\
+             name = \"{name}\"\n\
+             path = \"fuzz_targets/{name}.rs\"\n\
+             test = false\n\
+             doc = false\n\
+             bench = false\n",
+            name = target.name,
+        ));
+    }
+    manifest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironsbe_schema::parse_schema;
+
+    const SCHEMA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <sbe:message name="NewOrder" id="1" blockLength="8">
+        <field name="orderId" id="1" type="uint64" offset="0"/>
+    </sbe:message>
+    <sbe:message name="CancelOrder" id="2" blockLength="8">
+        <field name="orderId" id="1" type="uint64" offset="0"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+    fn harness(xml: &str, crate_name: &str, module: &str) -> FuzzHarness {
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+        FuzzGenerator::new(&ir).generate(crate_name, module)
+    }
+
+    #[test]
+    fn test_generate_targets() {
+        let harness = harness(SCHEMA, "my-codecs", "trading");
+
+        let names: Vec<_> = harness.targets.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["decode_any", "decode_new_order", "decode_cancel_order"]
+        );
+
+        let any = &harness.targets[0].source;
+        assert!(any.starts_with("#![no_main]"));
+        assert!(any.contains("use my_codecs::trading::*;"));
+        assert!(any.contains("impl MessageVisitor for Walker"));
+        assert!(any.contains("fn visit_cancel_order(&mut self, message: CancelOrderDecoder<'_>)"));
+        assert!(any.contains("MessageHeader::try_wrap(data, 0)"));
+
+        let new_order = &harness.targets[1].source;
+        assert!(new_order.contains("if data.len() < NewOrderDecoder::BLOCK_LENGTH as usize"));
+        assert!(new_order.contains("if let Ok(message) = NewOrderDecoder::try_wrap("));
+        assert!(new_order.contains("walk(message);"));
+        assert!(new_order.ends_with("fuzz_target!(|data: &[u8]| fuzz(data));\n"));
+    }
+
+    #[test]
+    fn test_generate_manifest() {
+        let harness = harness(SCHEMA, "my-codecs", "");
+
+        assert!(harness.manifest.contains("name = \"my-codecs-fuzz\""));
+        assert!(harness.manifest.contains("cargo-fuzz = true"));
+        assert!(
+            harness
+                .manifest
+                .contains("[dependencies.my-codecs]\npath = \"..\"")
+        );
+        assert!(
+            harness.manifest.contains(
+                "name = \"decode_new_order\"\npath = \"fuzz_targets/decode_new_order.rs\""
+            )
+        );
+        assert!(harness.targets[0].source.contains("use my_codecs::*;"));
+    }
+
+    #[test]
+    fn test_generate_nothing_without_messages() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <type name="Price" primitiveType="int64"/>
+    </types>
+</sbe:messageSchema>"#;
+
+        let harness = harness(xml, "codecs", "");
+        assert!(harness.targets.is_empty());
+        assert!(!harness.manifest.contains("[[bin]]"));
+    }
+
+    #[test]
+    fn test_write_to() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let harness = harness(SCHEMA, "codecs", "");
+        harness.write_to(dir.path()).expect("write harness");
+
+        let manifest = std::fs::read_to_string(dir.path().join("Cargo.toml")).unwrap();
+        assert_eq!(manifest, harness.manifest);
+        for target in &harness.targets {
+            let path = dir.path().join(format!("fuzz_targets/{}.rs", target.name));
+            assert_eq!(std::fs::read_to_string(path).unwrap(), target.source);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! `use` declarations and the `prelude` module of a generated file.
+//!
+//! Generated items name `ironsbe-core` types unqualified. Instead of a fixed
+//! import list, which leaves unused imports behind for schemas without groups
+//! or var data, the items are scanned after generation and only what they
+//! reference is imported. The file then compiles warning-free in any module
+//! of any crate that depends on `ironsbe-core`.
+
+use std::collections::BTreeSet;
+
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use quote::quote;
+
+use crate::rust::tokens;
+
+/// `ironsbe-core` items generated code may name, with their module.
+const CORE_ITEMS: &[(&str, &str)] = &[
+    ("ReadBuffer", "buffer"),
+    ("WriteBuffer", "buffer"),
+    ("MessageHeader", "header"),
+    ("SbeHeader", "header"),
+    ("GroupHeader", "header"),
+    ("VarDataHeader", "header"),
+    ("SbeDecoder", "decoder"),
+    ("DecodeError", "decoder"),
+    ("SbeEncoder", "encoder"),
+];
+
+/// `ironsbe-core` items re-exported by the prelude next to the generated
+/// ones: the buffer traits needed to call accessors on byte slices, the
+/// decoder trait and its error, and the message header.
+const PRELUDE_CORE_ITEMS: &[&str] = &[
+    "ReadBuffer",
+    "WriteBuffer",
+    "MessageHeader",
+    "SbeDecoder",
+    "SbeEncoder",
+    "DecodeError",
+];
+
+/// Generates the `use` declarations needed by `items`.
+///
+/// An item counts as used when it is named, or, for the buffer traits, when
+/// one of their `get_*`/`put_*` methods is called. Items the generated code
+/// defines itself, such as a schema-specific `MessageHeader`, are not
+/// imported.
+pub(crate) fn generate_imports(items: &TokenStream) -> TokenStream {
+    let mut used = Usage::default();
+    used.scan(items.clone());
+    let defined = defined_names(items);
+
+    let mut imports = TokenStream::new();
+    for (name, module) in CORE_ITEMS {
+        if used.names(name) && !defined.iter().any(|d| d == name) {
+            let path = tokens(&format!("ironsbe_core::{module}::{name}"));
+            imports.extend(quote! { use #path; });
+        }
+    }
+    imports
+}
+
+/// Generates a `prelude` module re-exporting every public item at the top
+/// level of `items`, plus the `ironsbe-core` items callers need to use
+/// them, so a single `use generated::prelude::*;` brings the protocol into
+/// scope.
+///
+/// Modules holding group types are not re-exported; they stay reachable
+/// through their parent. The module allows `unused_imports`, since the
+/// re-exports are unused until a caller imports them.
+pub(crate) fn generate_prelude(items: &TokenStream) -> TokenStream {
+    let names = defined_names(items);
+    let core = CORE_ITEMS
+        .iter()
+        .filter(|(name, _)| PRELUDE_CORE_ITEMS.contains(name) && !names.iter().any(|n| n == name))
+        .map(|(name, module)| tokens(&format!("ironsbe_core::{module}::{name}")));
+
+    quote! {
+        /// Re-exports the generated protocol and the `ironsbe-core` items
+        /// needed to use it.
+        #[allow(unused_imports)]
+        pub mod prelude {
+            pub use super::{#(#names),*};
+            #(pub use #core;)*
+        }
+    }
+}
+
+/// Returns the names of the public, non-module items at the top level of
+/// `items`.
+fn defined_names(items: &TokenStream) -> Vec<proc_macro2::Ident> {
+    let file: syn::File =
+        syn::parse2(items.clone()).unwrap_or_else(|e| panic!("generated items do not parse: {e}"));
+    file.items.iter().filter_map(public_name).collect()
+}
+
+/// Returns the name of a public, non-module item.
+fn public_name(item: &syn::Item) -> Option<proc_macro2::Ident> {
+    let (vis, name) = match item {
+        syn::Item::Const(item) => (&item.vis, &item.ident),
+        syn::Item::Enum(item) => (&item.vis, &item.ident),
+        syn::Item::Fn(item) => (&item.vis, &item.sig.ident),
+        syn::Item::Static(item) => (&item.vis, &item.ident),
+        syn::Item::Struct(item) => (&item.vis, &item.ident),
+        syn::Item::Trait(item) => (&item.vis, &item.ident),
+        syn::Item::Type(item) => (&item.vis, &item.ident),
+        _ => return None,
+    };
+    matches!(vis, syn::Visibility::Public(_)).then(|| name.clone())
+}
+
+/// Identifiers and method calls found in generated items.
+#[derive(Default)]
+struct Usage {
+    idents: BTreeSet<String>,
+    methods: BTreeSet<String>,
+}
+
+impl Usage {
+    /// Records every identifier and `.method(..)` call in `stream`.
+    fn scan(&mut self, stream: TokenStream) {
+        let tokens: Vec<_> = stream.into_iter().collect();
+        for (i, token) in tokens.iter().enumerate() {
+            match token {
+                TokenTree::Group(group) => self.scan(group.stream()),
+                TokenTree::Ident(name) => {
+                    let name = name.to_string();
+                    let after_dot = matches!(i.checked_sub(1).map(|p| &tokens[p]), Some(TokenTree::Punct(p)) if p.as_char() == '.');
+                    let called = matches!(
+                        tokens.get(i + 1),
+                        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis
+                    );
+                    if after_dot && called {
+                        self.methods.insert(name.clone());
+                    }
+                    self.idents.insert(name);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns true if the items refer to the `ironsbe-core` item `name`.
+    fn names(&self, name: &str) -> bool {
+        if self.idents.contains(name) {
+            return true;
+        }
+        let prefix = match name {
+            "ReadBuffer" => "get_",
+            "WriteBuffer" => "put_",
+            _ => return false,
+        };
+        self.methods.iter().any(|m| m.starts_with(prefix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imports_only_used_items() {
+        let items = quote! {
+            pub struct FooDecoder<'a> { buffer: &'a [u8] }
+            impl<'a> FooDecoder<'a> {
+                pub fn value(&self) -> u32 { self.buffer.get_u32_le(MessageHeader::ENCODED_LENGTH) }
+            }
+        };
+        let imports = generate_imports(&items).to_string();
+        assert!(imports.contains("ironsbe_core :: buffer :: ReadBuffer"));
+        assert!(imports.contains("ironsbe_core :: header :: MessageHeader"));
+        assert!(!imports.contains("WriteBuffer"));
+        assert!(!imports.contains("GroupHeader"));
+        assert!(!imports.contains("SbeEncoder"));
+
+        assert!(generate_imports(&quote! { pub const SCHEMA_ID: u16 = 1; }).is_empty());
+    }
+
+    #[test]
+    fn test_prelude_reexports_public_items() {
+        let items = quote! {
+            pub const SCHEMA_ID: u16 = 1;
+            pub struct FooDecoder;
+            struct Private;
+            pub mod foo { pub struct BarGroupDecoder; }
+        };
+        let prelude = generate_prelude(&items).to_string();
+        assert!(prelude.contains("pub use super :: { SCHEMA_ID , FooDecoder }"));
+        assert!(prelude.contains("pub use ironsbe_core :: decoder :: SbeDecoder"));
+        assert!(!prelude.contains("Private"));
+        assert!(prelude.contains("pub use ironsbe_core :: encoder :: SbeEncoder"));
+        assert!(!prelude.contains("GroupHeader"));
+    }
+}
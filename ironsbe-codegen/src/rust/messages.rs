@@ -1,819 +1,2238 @@
 //! Message encoder/decoder code generation.
+//!
+//! # Section layout
+//!
+//! A message (or group entry) is laid out as its fixed block followed by its
+//! trailing sections in schema order: every repeating group, then every
+//! variable-length data field. Only the first section has a fixed position;
+//! each later one starts where the previous one ends, which depends on the
+//! group counts and data lengths actually encoded.
+//!
+//! Generated accessors therefore locate a section by walking the sections
+//! before it in the buffer. Decoders may read sections in any order. Encoders
+//! must write them in schema order, since an encoder finds its position by
+//! reading back the sections already written.
 
 use ironsbe_schema::ir::{
-    ResolvedField, ResolvedGroup, ResolvedMessage, SchemaIr, TypeKind, to_snake_case,
+    ConstantValue, GroupDimension, MessageHeaderLayout, ResolvedField, ResolvedGroup,
+    ResolvedMessage, ResolvedVarData, SchemaIr, TypeKind, to_snake_case,
 };
 use ironsbe_schema::types::PrimitiveType;
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use std::collections::BTreeMap;
+
+use crate::config::CodegenConfig;
+use crate::rust::{constant_expr, doc, format_tokens, ident, lit, serde_derive, tokens};
+
+/// A trailing section of a message or group entry, in wire order.
+#[derive(Clone, Copy)]
+enum Section<'s> {
+    /// Repeating group.
+    Group(&'s ResolvedGroup),
+    /// Variable-length data field.
+    VarData(&'s ResolvedVarData),
+}
+
+impl Section<'_> {
+    /// Returns the snake_case name of the section.
+    fn name(&self) -> String {
+        match self {
+            Self::Group(group) => to_snake_case(&group.name),
+            Self::VarData(data) => to_snake_case(&data.name),
+        }
+    }
+
+    /// Returns the name of the private helper locating this section.
+    fn offset_fn(&self) -> Ident {
+        format_ident!("{}_offset", self.name())
+    }
+
+    /// Returns an expression for the encoded size of the section at `offset`.
+    ///
+    /// `path` qualifies group decoder types and `buffer` is a `&[u8]`
+    /// expression for the buffer holding the section.
+    fn length_expr(&self, path: &TokenStream, buffer: &TokenStream) -> TokenStream {
+        match self {
+            Self::Group(group) => {
+                let decoder = ident(&group.decoder_name());
+                quote! { #path #decoder::wrap(#buffer, offset).encoded_length() }
+            }
+            Self::VarData(data) => {
+                let header = var_data_header(data);
+                quote! { #header::wrap(#buffer, offset).total_size() }
+            }
+        }
+    }
+}
+
+/// Collects the trailing sections in wire order: groups first, then var data.
+fn sections<'s>(groups: &'s [ResolvedGroup], var_data: &'s [ResolvedVarData]) -> Vec<Section<'s>> {
+    groups
+        .iter()
+        .map(Section::Group)
+        .chain(var_data.iter().map(Section::VarData))
+        .collect()
+}
+
+/// Returns the header type of a group: the core `GroupHeader` for the
+/// standard dimension, else the struct generated for its dimension type.
+fn group_header(group: &ResolvedGroup) -> TokenStream {
+    if group.dimension.is_standard() {
+        quote! { GroupHeader }
+    } else {
+        let header = ident(&group.dimension.header_name());
+        quote! { #header }
+    }
+}
+
+/// Returns the Rust type of a group's entry count.
+fn count_type(group: &ResolvedGroup) -> Ident {
+    ident(group.dimension.num_in_group_type.rust_type())
+}
+
+/// Returns the core header type matching the length encoding of a var data
+/// field.
+fn var_data_header(data: &ResolvedVarData) -> TokenStream {
+    match data.length_type {
+        PrimitiveType::Uint8 => quote! { ironsbe_core::header::VarDataHeader8 },
+        PrimitiveType::Uint32 => quote! { ironsbe_core::header::VarDataHeader32 },
+        _ => quote! { VarDataHeader },
+    }
+}
+
+/// Returns `offset`, or `offset + at` for a nonzero `at`.
+fn offset_plus(at: usize) -> TokenStream {
+    if at == 0 {
+        quote! { offset }
+    } else {
+        let at = lit(at);
+        quote! { offset + #at }
+    }
+}
+
+/// Generates the header struct of a non-standard group dimension.
+fn generate_dimension_header(dimension: &GroupDimension) -> TokenStream {
+    let header_doc = doc(&format!(
+        "Repeating group header `{}` ({} bytes).",
+        dimension.type_name, dimension.encoded_length
+    ));
+    let name = ident(&dimension.header_name());
+    let encoded_length = lit(dimension.encoded_length);
+    let block_length_type = ident(dimension.block_length_type.rust_type());
+    let num_in_group_type = ident(dimension.num_in_group_type.rust_type());
+    let block_length_at = offset_plus(dimension.block_length_offset);
+    let num_in_group_at = offset_plus(dimension.num_in_group_offset);
+    let read_block_length = ident(get_read_method(Some(dimension.block_length_type)));
+    let read_num_in_group = ident(get_read_method(Some(dimension.num_in_group_type)));
+    let write_block_length = ident(get_write_method(Some(dimension.block_length_type)));
+    let write_num_in_group = ident(get_write_method(Some(dimension.num_in_group_type)));
+
+    quote! {
+        #header_doc
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+        pub struct #name {
+            /// Length of each group entry in bytes.
+            pub block_length: #block_length_type,
+            /// Number of entries in the group.
+            pub num_in_group: #num_in_group_type,
+        }
+        impl #name {
+            /// Encoded length of the group header in bytes.
+            pub const ENCODED_LENGTH: usize = #encoded_length;
+            /// Creates a new group header with the specified values.
+            #[must_use]
+            pub const fn new(block_length: #block_length_type, num_in_group: #num_in_group_type) -> Self {
+                Self {
+                    block_length,
+                    num_in_group,
+                }
+            }
+            /// Decodes the group header at the given offset.
+            #[inline(always)]
+            #[must_use]
+            pub fn wrap<B: ReadBuffer + ?Sized>(buffer: &B, offset: usize) -> Self {
+                Self {
+                    block_length: buffer.#read_block_length(#block_length_at),
+                    num_in_group: buffer.#read_num_in_group(#num_in_group_at),
+                }
+            }
+            /// Encodes the group header at the given offset.
+            #[inline(always)]
+            pub fn encode<B: WriteBuffer + ?Sized>(&self, buffer: &mut B, offset: usize) {
+                buffer.#write_block_length(#block_length_at, self.block_length);
+                buffer.#write_num_in_group(#num_in_group_at, self.num_in_group);
+            }
+        }
+    }
+}
+
+/// Generates the `MessageHeader` struct of a schema whose header composite
+/// extends the standard layout, in place of `ironsbe_core`'s.
+fn generate_message_header(layout: &MessageHeaderLayout) -> TokenStream {
+    let header_doc = doc(&format!(
+        "Message header `{}` ({} bytes).",
+        layout.type_name, layout.encoded_length
+    ));
+    let encoded_length = lit(layout.encoded_length);
+    let names: Vec<_> = layout
+        .members
+        .iter()
+        .map(|m| ident(&to_snake_case(&m.name)))
+        .collect();
+    let fields = layout.members.iter().zip(&names).map(|(member, name)| {
+        let member_doc = doc(&format!("Header member `{}`.", member.name));
+        let rust_type = ident(member.primitive_type.rust_type());
+        quote! {
+            #member_doc
+            pub #name: #rust_type,
+        }
+    });
+    let reads = layout.members.iter().zip(&names).map(|(member, name)| {
+        let read = ident(get_read_method(Some(member.primitive_type)));
+        let at = offset_plus(member.offset);
+        quote! { #name: buffer.#read(#at), }
+    });
+    let writes = layout.members.iter().zip(&names).map(|(member, name)| {
+        let write = ident(get_write_method(Some(member.primitive_type)));
+        let at = offset_plus(member.offset);
+        quote! { buffer.#write(#at, self.#name); }
+    });
+
+    quote! {
+        #header_doc
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+        pub struct MessageHeader {
+            #(#fields)*
+        }
+        impl MessageHeader {
+            /// Encoded length of the message header in bytes.
+            pub const ENCODED_LENGTH: usize = #encoded_length;
+            /// Decodes the message header at the given offset.
+            #[inline(always)]
+            #[must_use]
+            pub fn wrap<B: ReadBuffer + ?Sized>(buffer: &B, offset: usize) -> Self {
+                Self {
+                    #(#reads)*
+                }
+            }
+            /// Decodes the message header at the given offset, if the buffer
+            /// is long enough to hold one.
+            #[inline]
+            #[must_use]
+            pub fn try_wrap(buffer: &[u8], offset: usize) -> Option<Self> {
+                <Self as SbeHeader>::try_wrap(buffer, offset)
+            }
+            /// Encodes the message header at the given offset.
+            #[inline(always)]
+            pub fn encode<B: WriteBuffer + ?Sized>(&self, buffer: &mut B, offset: usize) {
+                #(#writes)*
+            }
+        }
+        impl SbeHeader for MessageHeader {
+            const ENCODED_LENGTH: usize = #encoded_length;
+            fn wrap<B: ReadBuffer + ?Sized>(buffer: &B, offset: usize) -> Self {
+                Self::wrap(buffer, offset)
+            }
+            fn encode<B: WriteBuffer + ?Sized>(&self, buffer: &mut B, offset: usize) {
+                self.encode(buffer, offset);
+            }
+            fn block_length(&self) -> u16 {
+                self.block_length
+            }
+            fn template_id(&self) -> u16 {
+                self.template_id
+            }
+            fn schema_id(&self) -> u16 {
+                self.schema_id
+            }
+            fn version(&self) -> u16 {
+                self.version
+            }
+        }
+    }
+}
+
+/// Collects the non-standard dimensions of `groups` and their nested groups,
+/// keyed by header struct name.
+fn collect_dimensions<'g>(
+    groups: &'g [ResolvedGroup],
+    dimensions: &mut BTreeMap<String, &'g GroupDimension>,
+) {
+    for group in groups {
+        if !group.dimension.is_standard() {
+            dimensions
+                .entry(group.dimension.header_name())
+                .or_insert(&group.dimension);
+        }
+        collect_dimensions(&group.nested_groups, dimensions);
+    }
+}
+
+/// Returns true if entries of the group have a variable encoded length.
+fn has_variable_entries(group: &ResolvedGroup) -> bool {
+    !group.nested_groups.is_empty() || !group.var_data.is_empty()
+}
+
+/// Qualifies items of the message-scoped group module, e.g. `new_order::`.
+fn module_path(msg: &ResolvedMessage) -> TokenStream {
+    let module = ident(&to_snake_case(&msg.name));
+    quote! { #module:: }
+}
+
+/// Generates the private helpers locating each trailing section.
+///
+/// `block_end` is an expression for the offset right after the fixed block,
+/// where the first section starts.
+fn generate_section_offsets(
+    sections: &[Section<'_>],
+    block_end: &TokenStream,
+    path: &TokenStream,
+    buffer: &TokenStream,
+) -> TokenStream {
+    let mut previous: Option<&Section<'_>> = None;
+
+    sections
+        .iter()
+        .map(|section| {
+            let section_doc = doc(&format!("Offset of the `{}` section.", section.name()));
+            let offset_fn = section.offset_fn();
+            let body = match previous {
+                None => quote! { #block_end },
+                Some(prev) => {
+                    let prev_fn = prev.offset_fn();
+                    let length = prev.length_expr(path, buffer);
+                    quote! {
+                        let offset = self.#prev_fn();
+                        offset + #length
+                    }
+                }
+            };
+            previous = Some(section);
+            quote! {
+                #section_doc
+                #[inline]
+                fn #offset_fn(&self) -> usize {
+                    #body
+                }
+            }
+        })
+        .collect()
+}
+
+/// Generates the statements computing the offset right after the last section.
+///
+/// The generated statements leave the result in a local named `end`.
+fn generate_end_offset(
+    sections: &[Section<'_>],
+    block_end: &TokenStream,
+    path: &TokenStream,
+    buffer: &TokenStream,
+) -> TokenStream {
+    match sections.last() {
+        None => quote! { let end = #block_end; },
+        Some(last) => {
+            let last_fn = last.offset_fn();
+            let length = last.length_expr(path, buffer);
+            quote! {
+                let offset = self.#last_fn();
+                let end = offset + #length;
+            }
+        }
+    }
+}
+
+/// Returns a doc paragraph listing the trailing sections in wire order.
+fn section_order_doc(sections: &[Section<'_>]) -> TokenStream {
+    if sections.is_empty() {
+        return TokenStream::new();
+    }
+    let names: Vec<String> = sections.iter().map(Section::name).collect();
+    doc(&format!(
+        "\nSections follow the fixed block in this order: {}.",
+        names.join(", ")
+    ))
+}
+
+/// Generates the getter of a constant field, which reads nothing from the
+/// buffer.
+fn generate_constant_getter(field: &ResolvedField, constant: &ConstantValue) -> TokenStream {
+    let getter_doc = doc(&format!(
+        "Field: {} (id={}, constant).",
+        field.name, field.id
+    ));
+    let getter = ident(&field.getter_name);
+    let value = constant_expr(constant, field.primitive_type, field.is_array);
+
+    if field.is_array {
+        let str_doc = doc(&format!("Field {} as string.", field.name));
+        let as_str = format_ident!("{}_as_str", field.getter_name);
+        let text = match constant {
+            ConstantValue::Literal(text) => text.as_str(),
+            ConstantValue::Enum { variant, .. } => variant.as_str(),
+        };
+        return quote! {
+            #getter_doc
+            #[inline(always)]
+            #[must_use]
+            pub fn #getter(&self) -> &'a [u8] {
+                #value
+            }
+            #str_doc
+            #[inline]
+            #[must_use]
+            pub fn #as_str(&self) -> &'a str {
+                #text
+            }
+        };
+    }
+
+    let rust_type = tokens(&field.rust_type);
+    quote! {
+        #getter_doc
+        #[inline(always)]
+        #[must_use]
+        pub fn #getter(&self) -> #rust_type {
+            #value
+        }
+    }
+}
+
+/// Returns the block length written in a group header: the schema value if
+/// nonzero, else the end of the last field.
+pub(crate) fn effective_block_length(group: &ResolvedGroup) -> u16 {
+    if group.block_length > 0 {
+        group.block_length
+    } else {
+        group
+            .fields
+            .iter()
+            .map(|f| f.offset + f.encoded_length)
+            .max()
+            .unwrap_or(0) as u16
+    }
+}
+
+/// Returns a `usize` expression for element `index` of the slice `values`,
+/// or zero if the slice is shorter.
+fn slice_value(values: &Ident, index: usize) -> TokenStream {
+    let skipped = (0..index).map(|_| quote! { _ });
+    quote! {
+        match #values {
+            [#(#skipped,)* value, ..] => *value,
+            _ => 0,
+        }
+    }
+}
+
+/// Generates `encoded_size`, computing the size of a message or group entry
+/// from its group entry counts and var data lengths before it is encoded.
+///
+/// `fixed` is the size of everything before the trailing sections and
+/// `path` qualifies group encoder types.
+fn generate_encoded_size(
+    groups: &[ResolvedGroup],
+    var_data: &[ResolvedVarData],
+    fixed: &TokenStream,
+    path: &TokenStream,
+    summary: &str,
+) -> TokenStream {
+    let num_entries = format_ident!("num_entries");
+    let var_data_lens = format_ident!("var_data_lens");
+
+    let mut doc_text = format!(
+        "{summary}\n\n\
+         `num_entries` lists the entry count of each repeating group and\n\
+         `var_data_lens` the length of each var data field, both in schema\n\
+         order; missing values count as zero."
+    );
+    if groups.iter().any(has_variable_entries) {
+        doc_text.push_str(
+            "\n\nEntries are counted with their nested groups empty and their\n\
+             var data zero-length; add the extra size of each entry as given\n\
+             by its entry encoder's `encoded_size`.",
+        );
+    }
+    let size_doc = doc(&doc_text);
+
+    let group_sizes = groups.iter().enumerate().map(|(i, group)| {
+        let entries = slice_value(&num_entries, i);
+        let count = format_ident!("{}_count", to_snake_case(&group.name));
+        let header = group_header(group);
+        let entry_size = if has_variable_entries(group) {
+            let entry = ident(&group.entry_encoder_name());
+            quote! { #path #entry::encoded_size(&[], &[]) }
+        } else {
+            let encoder = ident(&group.encoder_name());
+            quote! { #path #encoder::BLOCK_LENGTH as usize }
+        };
+        quote! {
+            let #count = #entries;
+            size += #header::ENCODED_LENGTH + #count * #entry_size;
+        }
+    });
+    let var_data_sizes = var_data.iter().enumerate().map(|(i, data)| {
+        let value = slice_value(&var_data_lens, i);
+        let len = format_ident!("{}_len", to_snake_case(&data.name));
+        let header = var_data_header(data);
+        quote! {
+            let #len = #value;
+            size += #header::total_size_for(#len);
+        }
+    });
+    let unused_entries = groups.is_empty().then(|| quote! { let _ = #num_entries; });
+    let unused_lens = var_data
+        .is_empty()
+        .then(|| quote! { let _ = #var_data_lens; });
+
+    let body = if groups.is_empty() && var_data.is_empty() {
+        quote! {
+            let _ = (#num_entries, #var_data_lens);
+            #fixed
+        }
+    } else {
+        quote! {
+            #unused_entries
+            #unused_lens
+            let mut size = #fixed;
+            #(#group_sizes)*
+            #(#var_data_sizes)*
+            size
+        }
+    };
+
+    quote! {
+        #size_doc
+        #[must_use]
+        pub const fn encoded_size(#num_entries: &[usize], #var_data_lens: &[usize]) -> usize {
+            #body
+        }
+    }
+}
+
+/// Generates a var data getter returning the raw bytes.
+fn generate_var_data_getter(data: &ResolvedVarData) -> TokenStream {
+    let name = ident(&to_snake_case(&data.name));
+    let offset_fn = format_ident!("{}_offset", name);
+    let getter_doc = doc(&format!("Var data: {} (id={}).", data.name, data.id));
+    let header = var_data_header(data);
+
+    quote! {
+        #getter_doc
+        #[inline]
+        #[must_use]
+        pub fn #name(&self) -> &'a [u8] {
+            let offset = self.#offset_fn();
+            let header = #header::wrap(self.buffer, offset);
+            let start = offset + #header::ENCODED_LENGTH;
+            &self.buffer[start..start + header.length as usize]
+        }
+    }
+}
+
+/// Generates a var data setter writing the length header and the bytes.
+///
+/// Values longer than the length encoding can hold are truncated. With
+/// `track_end`, the setter also moves the encoder's running `end` past the
+/// value.
+fn generate_var_data_setter(data: &ResolvedVarData, track_end: bool) -> TokenStream {
+    let name = to_snake_case(&data.name);
+    let setter = format_ident!("set_{}", name);
+    let offset_fn = format_ident!("{}_offset", name);
+    let setter_doc = doc(&format!(
+        "Set var data: {} (id={}).\n\nAll preceding groups and var data must already be written.",
+        data.name, data.id
+    ));
+    let header = var_data_header(data);
+    let length_type = match data.length_type {
+        PrimitiveType::Uint8 => format_ident!("u8"),
+        PrimitiveType::Uint32 => format_ident!("u32"),
+        _ => format_ident!("u16"),
+    };
+    let end_update = track_end.then(|| quote! { *self.end = start + len; });
+
+    quote! {
+        #setter_doc
+        pub fn #setter(&mut self, value: &[u8]) -> &mut Self {
+            let offset = self.#offset_fn();
+            let len = value.len().min(#length_type::MAX as usize);
+            #header::new(len as #length_type).encode(self.buffer, offset);
+            let start = offset + #header::ENCODED_LENGTH;
+            self.buffer[start..start + len].copy_from_slice(&value[..len]);
+            #end_update
+            self
+        }
+    }
+}
+
+/// Generates the group decoder `Debug` impl, listing the remaining entries.
+fn generate_group_debug(decoder_name: &Ident) -> TokenStream {
+    quote! {
+        impl core::fmt::Debug for #decoder_name<'_> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.debug_list().entries(*self).finish()
+            }
+        }
+    }
+}
+
+/// Returns true if `field` is an optional scalar with a null sentinel.
+fn has_null_sentinel(field: &ResolvedField) -> bool {
+    field.is_optional && !field.is_array && field.primitive_type.is_some()
+}
+
+/// Returns an expression that is true if `value` is not the null sentinel of
+/// `field`.
+///
+/// Uses the schema's `nullValue` when it is a valid literal for the field's
+/// type, and the SBE default null otherwise.
+fn present_expr(field: &ResolvedField, value: &TokenStream) -> TokenStream {
+    let Some(prim) = field.primitive_type else {
+        return quote! { true };
+    };
+    let rust_type = prim.rust_type();
+
+    if let Some(null) = field.null_value.as_deref().map(str::trim) {
+        match prim {
+            PrimitiveType::Char => {
+                let mut chars = null.chars();
+                if let (Some(c), None) = (chars.next(), chars.next())
+                    && c.is_ascii()
+                {
+                    let null = lit(usize::from(c as u8));
+                    return quote! { #value != #null };
+                }
+            }
+            PrimitiveType::Float | PrimitiveType::Double => {
+                if null.parse::<f64>().is_ok_and(f64::is_finite) {
+                    let null = tokens(&format!("{}_{}", null, rust_type));
+                    return quote! { #value != #null };
+                }
+            }
+            _ => {
+                if null.parse::<i128>().is_ok() {
+                    let null = tokens(&format!("{}_{}", null, rust_type));
+                    return quote! { #value != #null };
+                }
+            }
+        }
+    }
+
+    let rust_type = ident(rust_type);
+    match prim {
+        PrimitiveType::Char => quote! { #value != 0 },
+        PrimitiveType::Float | PrimitiveType::Double => quote! { !#value.is_nan() },
+        p if p.is_signed() => quote! { #value != #rust_type::MIN },
+        _ => quote! { #value != #rust_type::MAX },
+    }
+}
+
+/// Generates the presence accessors of an optional scalar field.
+///
+/// With `bit`, presence is read from the decoder's memoized bitmap;
+/// otherwise every call compares the field against its null sentinel.
+fn generate_presence_accessors(field: &ResolvedField, bit: Option<usize>) -> TokenStream {
+    let getter = ident(&field.getter_name);
+    let has = format_ident!("has_{}", field.getter_name);
+    let opt = format_ident!("{}_opt", field.getter_name);
+    let rust_type = tokens(&field.rust_type);
+    let read_method = ident(get_read_method(field.primitive_type));
+    let offset = lit(field.offset);
+    let read = quote! { self.buffer.#read_method(self.offset + #offset) };
+    let has_doc = doc(&format!("Returns true if {} is not null.", field.name));
+    let opt_doc = doc(&format!("Field {}, or `None` if null.", field.name));
+
+    let (has_body, opt_body) = match bit {
+        Some(bit) => {
+            let word = lit(bit / 64);
+            let shift = lit(bit % 64);
+            (
+                quote! { self.presence[#word] & (1 << #shift) != 0 },
+                quote! { self.#has().then(|| self.#getter()) },
+            )
+        }
+        None => {
+            let present = present_expr(field, &quote! { value });
+            (
+                present_expr(field, &read),
+                quote! {
+                    let value = #read;
+                    (#present).then_some(value)
+                },
+            )
+        }
+    };
+
+    quote! {
+        #has_doc
+        #[inline(always)]
+        #[must_use]
+        pub fn #has(&self) -> bool {
+            #has_body
+        }
+        #opt_doc
+        #[inline(always)]
+        #[must_use]
+        pub fn #opt(&self) -> Option<#rust_type> {
+            #opt_body
+        }
+    }
+}
+
+/// Generates the statements copying `len` bytes of `value` into an array
+/// field at `offset`, zero-padding the rest.
+fn array_copy(offset: &TokenStream, len: usize) -> TokenStream {
+    let len = lit(len);
+    quote! {
+        let copy_len = value.len().min(#len);
+        self.buffer[self.offset + #offset..self.offset + #offset + copy_len]
+            .copy_from_slice(&value[..copy_len]);
+        if copy_len < #len {
+            self.buffer[self.offset + #offset + copy_len..self.offset + #offset + #len].fill(0);
+        }
+    }
+}
+
+/// Generates the statements returning `false` early if an array field at
+/// `offset` already holds `value` zero-padded to `len` bytes.
+fn array_unchanged_return(offset: &TokenStream, len: usize) -> TokenStream {
+    let len = lit(len);
+    quote! {
+        let current = &self.buffer[self.offset + #offset..self.offset + #offset + #len];
+        let copy_len = value.len().min(#len);
+        if current[..copy_len] == value[..copy_len] && current[copy_len..].iter().all(|&b| b == 0) {
+            return false;
+        }
+    }
+}
+
+/// Generates the dirty mask accessors of an encoder tracking `words`
+/// 64-bit words of fields.
+fn generate_dirty_accessors(words: usize) -> TokenStream {
+    let words = lit(words);
+    quote! {
+        /// Returns the dirty mask.
+        ///
+        /// Bit `n` is set once the `n`th change-tracked field has been
+        /// rewritten by its `set_*_if_changed` setter.
+        #[inline]
+        #[must_use]
+        pub const fn dirty_mask(&self) -> [u64; #words] {
+            self.dirty
+        }
+        /// Returns true if any field has been rewritten since the encoder
+        /// was wrapped or the mask last cleared.
+        #[inline]
+        #[must_use]
+        pub fn is_dirty(&self) -> bool {
+            self.dirty.iter().any(|&word| word != 0)
+        }
+        /// Clears the dirty mask.
+        #[inline]
+        pub fn clear_dirty(&mut self) {
+            self.dirty = [0; #words];
+        }
+    }
+}
 
 /// Generator for message encoders and decoders.
 pub struct MessageGenerator<'a> {
     ir: &'a SchemaIr,
+    config: CodegenConfig,
 }
 
 impl<'a> MessageGenerator<'a> {
     /// Creates a new message generator.
     #[must_use]
     pub fn new(ir: &'a SchemaIr) -> Self {
-        Self { ir }
+        Self::with_config(ir, CodegenConfig::default())
     }
 
-    /// Generates all message definitions.
+    /// Creates a new message generator with custom options.
+    #[must_use]
+    pub fn with_config(ir: &'a SchemaIr, config: CodegenConfig) -> Self {
+        Self { ir, config }
+    }
+
+    /// Generates all message definitions as formatted source.
     #[must_use]
     pub fn generate(&self) -> String {
-        let mut output = String::new();
+        format_tokens(self.generate_tokens())
+    }
+
+    /// Generates all message definitions.
+    #[must_use]
+    pub fn generate_tokens(&self) -> TokenStream {
+        let mut output = TokenStream::new();
+
+        if !self.ir.message_header.is_standard() {
+            output.extend(generate_message_header(&self.ir.message_header));
+        }
+
+        let mut dimensions = BTreeMap::new();
+        for msg in &self.ir.messages {
+            collect_dimensions(&msg.groups, &mut dimensions);
+        }
+        output.extend(dimensions.into_values().map(generate_dimension_header));
 
         for msg in &self.ir.messages {
-            output.push_str(&self.generate_decoder(msg));
-            output.push_str(&self.generate_encoder(msg));
+            output.extend(self.generate_decoder(msg));
+            output.extend(self.generate_encoder(msg));
 
             // Generate group decoders and encoders in a message-scoped module
             if !msg.groups.is_empty() {
-                let mod_name = to_snake_case(&msg.name);
-                output.push_str(&format!("/// Types for {} repeating groups.\n", msg.name));
-                output.push_str(&format!("pub mod {} {{\n", mod_name));
-                output.push_str("    use super::*;\n\n");
-                for group in &msg.groups {
-                    output.push_str(&self.generate_group_decoder(group));
-                    output.push_str(&self.generate_group_encoder(group));
-                }
-                output.push_str("}\n\n");
+                let mod_doc = doc(&format!("Types for {} repeating groups.", msg.name));
+                let mod_name = ident(&to_snake_case(&msg.name));
+                let groups = msg.groups.iter().map(|group| {
+                    let decoder = self.generate_group_decoder(group);
+                    let encoder = self.generate_group_encoder(group, false);
+                    quote! { #decoder #encoder }
+                });
+                output.extend(quote! {
+                    #mod_doc
+                    pub mod #mod_name {
+                        use super::*;
+                        #(#groups)*
+                    }
+                });
             }
         }
 
         output
     }
 
+    /// Returns the fields tracked by the presence bitmap of a message.
+    ///
+    /// Empty unless the bitmap is enabled and the message has at least the
+    /// configured number of optional scalar fields.
+    fn bitmap_fields<'f>(&self, fields: &'f [ResolvedField]) -> Vec<&'f ResolvedField> {
+        let Some(min) = self.config.presence_bitmap else {
+            return Vec::new();
+        };
+        let optional: Vec<_> = fields.iter().filter(|f| has_null_sentinel(f)).collect();
+        if optional.len() >= min.max(1) {
+            optional
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns the fields given `set_*_if_changed` setters.
+    ///
+    /// Empty unless change detection is enabled. Constants have no setter
+    /// and composites are written through their own encoder.
+    fn tracked_fields<'f>(&self, fields: &'f [ResolvedField]) -> Vec<&'f ResolvedField> {
+        if !self.config.change_detection {
+            return Vec::new();
+        }
+        fields
+            .iter()
+            .filter(|field| field.constant.is_none())
+            .filter(|field| {
+                !matches!(
+                    self.ir.get_type(&field.type_name).map(|t| &t.kind),
+                    Some(TypeKind::Composite { .. })
+                )
+            })
+            .collect()
+    }
+
     /// Generates a message decoder.
-    fn generate_decoder(&self, msg: &ResolvedMessage) -> String {
-        let mut output = String::new();
-        let decoder_name = msg.decoder_name();
+    fn generate_decoder(&self, msg: &ResolvedMessage) -> TokenStream {
+        let decoder_name = ident(&msg.decoder_name());
+        let sections = sections(&msg.groups, &msg.var_data);
+        let path = module_path(msg);
+        let buffer = quote! { self.buffer };
+        let block_end = quote! { self.offset + self.block_length as usize };
+        let bitmap = self.bitmap_fields(&msg.fields);
+        let template_id = lit(usize::from(msg.template_id));
+        let block_length = lit(usize::from(msg.block_length));
 
         // Struct definition
-        output.push_str(&format!("/// {} Decoder (zero-copy).\n", msg.name));
-        output.push_str("#[derive(Debug, Clone, Copy)]\n");
-        output.push_str(&format!("pub struct {}<'a> {{\n", decoder_name));
-        output.push_str("    buffer: &'a [u8],\n");
-        output.push_str("    offset: usize,\n");
-        output.push_str("    acting_version: u16,\n");
-        output.push_str("}\n\n");
-
-        // Implementation
-        output.push_str(&format!("impl<'a> {}<'a> {{\n", decoder_name));
-        output.push_str(&format!(
-            "    /// Template ID for this message.\n\
-             pub const TEMPLATE_ID: u16 = {};\n",
-            msg.template_id
-        ));
-        output.push_str(&format!(
-            "    /// Block length of the fixed portion.\n\
-             pub const BLOCK_LENGTH: u16 = {};\n\n",
-            msg.block_length
-        ));
+        let struct_doc = doc(&format!("{} Decoder (zero-copy).", msg.name));
+        let order_doc = section_order_doc(&sections);
+        let words = lit(bitmap.len().div_ceil(64));
+        let presence_field = (!bitmap.is_empty()).then(|| quote! { presence: [u64; #words], });
 
         // Constructor
-        output.push_str("    /// Wraps a buffer for zero-copy decoding.\n");
-        output.push_str("    ///\n");
-        output.push_str("    /// # Arguments\n");
-        output.push_str("    /// * `buffer` - Buffer containing the message\n");
-        output.push_str(
-            "    /// * `offset` - Offset to the start of the root block (after header)\n",
-        );
-        output.push_str("    /// * `acting_version` - Schema version for compatibility\n");
-        output.push_str("    #[inline]\n");
-        output.push_str("    #[must_use]\n");
-        output.push_str(
-            "    pub fn wrap(buffer: &'a [u8], offset: usize, acting_version: u16) -> Self {\n",
-        );
-        output.push_str("        Self { buffer, offset, acting_version }\n");
-        output.push_str("    }\n\n");
+        let wrap_doc = if bitmap.is_empty() {
+            TokenStream::new()
+        } else {
+            doc("Reads every optional field once to memoize its presence.\n")
+        };
+        let unchecked = self.config.unchecked_reads;
+        let errors_doc = unchecked.then(|| {
+            doc("# Errors\n\
+                 Returns [`DecodeError::BufferTooShort`] if the root block extends past\n\
+                 the end of `buffer`, at `block_length` or at [`Self::BLOCK_LENGTH`]\n\
+                 for the fields read without bounds checks, whichever is longer.\n")
+        });
+        // Unchecked field reads rely on this check, so `wrap` reports a short
+        // buffer instead of handing out a decoder.
+        let length_check = unchecked.then(|| {
+            quote! {
+                let required = offset
+                    .saturating_add(usize::from(block_length.max(Self::BLOCK_LENGTH)));
+                if buffer.len() < required {
+                    return Err(DecodeError::BufferTooShort {
+                        required,
+                        available: buffer.len(),
+                    });
+                }
+            }
+        });
+        let (wrap_output, wrap_must_use) = if unchecked {
+            (quote! { Result<Self, DecodeError> }, TokenStream::new())
+        } else {
+            (quote! { Self }, quote! { #[must_use] })
+        };
+        let (presence_init, decoder) = if bitmap.is_empty() {
+            (
+                TokenStream::new(),
+                quote! { Self { buffer, offset, block_length, acting_version } },
+            )
+        } else {
+            let marks = bitmap.iter().enumerate().map(|(bit, field)| {
+                let read_method = ident(get_read_method(field.primitive_type));
+                let offset = lit(field.offset);
+                let present =
+                    present_expr(field, &quote! { buffer.#read_method(offset + #offset) });
+                let word = lit(bit / 64);
+                let shift = lit(bit % 64);
+                quote! { presence[#word] |= u64::from(#present) << #shift; }
+            });
+            (
+                quote! {
+                    let mut presence = [0u64; #words];
+                    #(#marks)*
+                },
+                quote! { Self { buffer, offset, block_length, acting_version, presence } },
+            )
+        };
+        let wrap_body = if unchecked {
+            quote! { #length_check #presence_init Ok(#decoder) }
+        } else {
+            quote! { #presence_init #decoder }
+        };
+        let trait_wrap = if unchecked {
+            quote! {
+                #[inline]
+                fn wrap_with_block_length(
+                    buffer: &'a [u8],
+                    offset: usize,
+                    block_length: u16,
+                    acting_version: u16,
+                ) -> Self {
+                    match Self::wrap_with_block_length(buffer, offset, block_length, acting_version) {
+                        Ok(decoder) => decoder,
+                        Err(error) => panic!("{error}"),
+                    }
+                }
+                #[inline]
+                fn try_wrap_with_block_length(
+                    buffer: &'a [u8],
+                    offset: usize,
+                    block_length: u16,
+                    acting_version: u16,
+                ) -> Result<Self, DecodeError> {
+                    Self::wrap_with_block_length(buffer, offset, block_length, acting_version)
+                }
+            }
+        } else {
+            quote! {
+                #[inline]
+                fn wrap_with_block_length(
+                    buffer: &'a [u8],
+                    offset: usize,
+                    block_length: u16,
+                    acting_version: u16,
+                ) -> Self {
+                    Self::wrap_with_block_length(buffer, offset, block_length, acting_version)
+                }
+            }
+        };
 
         // Field getters
-        for field in &msg.fields {
-            output.push_str(&self.generate_field_getter(field));
-        }
+        let getters = msg.fields.iter().map(|field| {
+            let getter = self.generate_field_getter(field, usize::from(msg.block_length));
+            let presence = has_null_sentinel(field).then(|| {
+                let bit = bitmap.iter().position(|f| std::ptr::eq(*f, field));
+                generate_presence_accessors(field, bit)
+            });
+            quote! { #getter #presence }
+        });
+
+        // Group and var data accessors
+        let group_accessors = msg
+            .groups
+            .iter()
+            .map(|group| self.generate_group_accessor(group, &path));
+        let var_data_getters = msg.var_data.iter().map(generate_var_data_getter);
+        let section_offsets = generate_section_offsets(&sections, &block_end, &path, &buffer);
+
+        let to_owned = self.config.owned_types().then(|| {
+            self.generate_to_owned(&msg.owned_name(), &msg.fields, &msg.groups, &msg.var_data)
+        });
+        let owned_struct = self.config.owned_types().then(|| {
+            self.generate_owned_struct(
+                &msg.owned_name(),
+                &format!("Owned copy of a decoded {} message.", msg.name),
+                &msg.fields,
+                &msg.groups,
+                &msg.var_data,
+                &path,
+            )
+        });
+        let debug = self.generate_debug(&decoder_name, &msg.fields, &msg.groups, &msg.var_data);
+        // `SbeDecoder` defaults to the core header; a generated one is named.
+        let header_param =
+            (!self.ir.message_header.is_standard()).then(|| quote! { , MessageHeader });
+
+        let encoded_length = if sections.is_empty() {
+            quote! { MessageHeader::ENCODED_LENGTH + self.block_length as usize }
+        } else {
+            let end = generate_end_offset(&sections, &block_end, &path, &buffer);
+            quote! {
+                #end
+                MessageHeader::ENCODED_LENGTH + end - self.offset
+            }
+        };
 
-        // Group accessors
-        let mut group_offset = msg.block_length as usize;
-        for group in &msg.groups {
-            output.push_str(&self.generate_group_accessor(group, group_offset, &msg.name));
-            group_offset += 4; // Group header size
+        quote! {
+            #struct_doc
+            #order_doc
+            #[derive(Clone, Copy)]
+            pub struct #decoder_name<'a> {
+                buffer: &'a [u8],
+                offset: usize,
+                block_length: u16,
+                acting_version: u16,
+                #presence_field
+            }
+            impl<'a> #decoder_name<'a> {
+                /// Template ID for this message.
+                pub const TEMPLATE_ID: u16 = #template_id;
+                /// Block length of the fixed portion.
+                pub const BLOCK_LENGTH: u16 = #block_length;
+                /// Wraps a buffer for zero-copy decoding.
+                ///
+                /// The root block length is read from the message header that
+                /// ends at `offset`, or is [`Self::BLOCK_LENGTH`] if no header
+                /// fits before it.
+                ///
+                #errors_doc
+                /// # Arguments
+                /// * `buffer` - Buffer containing the message
+                /// * `offset` - Offset to the start of the root block (after header)
+                /// * `acting_version` - Schema version for compatibility
+                #[inline]
+                #wrap_must_use
+                pub fn wrap(buffer: &'a [u8], offset: usize, acting_version: u16) -> #wrap_output {
+                    let block_length = <MessageHeader as SbeHeader>::block_length_before(buffer, offset)
+                        .unwrap_or(Self::BLOCK_LENGTH);
+                    Self::wrap_with_block_length(buffer, offset, block_length, acting_version)
+                }
+                /// Wraps a buffer for zero-copy decoding with the root block
+                /// length from an already decoded message header.
+                ///
+                #wrap_doc
+                #errors_doc
+                /// # Arguments
+                /// * `buffer` - Buffer containing the message
+                /// * `offset` - Offset to the start of the root block (after header)
+                /// * `block_length` - Root block length from the message header,
+                ///   which locates the groups and var data
+                /// * `acting_version` - Schema version for compatibility
+                #[inline]
+                #wrap_must_use
+                pub fn wrap_with_block_length(
+                    buffer: &'a [u8],
+                    offset: usize,
+                    block_length: u16,
+                    acting_version: u16,
+                ) -> #wrap_output {
+                    #wrap_body
+                }
+                #(#getters)*
+                #(#group_accessors)*
+                #(#var_data_getters)*
+                #section_offsets
+                #to_owned
+            }
+            #owned_struct
+            #debug
+            impl<'a> SbeDecoder<'a #header_param> for #decoder_name<'a> {
+                const TEMPLATE_ID: u16 = #template_id;
+                const SCHEMA_ID: u16 = SCHEMA_ID;
+                const SCHEMA_VERSION: u16 = SCHEMA_VERSION;
+                const BLOCK_LENGTH: u16 = #block_length;
+                #trait_wrap
+                #[inline]
+                fn encoded_length(&self) -> usize {
+                    #encoded_length
+                }
+            }
         }
+    }
 
-        output.push_str("}\n\n");
-
-        // SbeDecoder trait implementation
-        output.push_str(&format!(
-            "impl<'a> SbeDecoder<'a> for {}<'a> {{\n",
-            decoder_name
-        ));
-        output.push_str(&format!(
-            "    const TEMPLATE_ID: u16 = {};\n",
-            msg.template_id
-        ));
-        output.push_str("    const SCHEMA_ID: u16 = SCHEMA_ID;\n");
-        output.push_str("    const SCHEMA_VERSION: u16 = SCHEMA_VERSION;\n");
-        output.push_str(&format!(
-            "    const BLOCK_LENGTH: u16 = {};\n\n",
-            msg.block_length
-        ));
-
-        output.push_str(
-            "    fn wrap(buffer: &'a [u8], offset: usize, acting_version: u16) -> Self {\n",
-        );
-        output.push_str("        Self::wrap(buffer, offset, acting_version)\n");
-        output.push_str("    }\n\n");
-
-        output.push_str("    fn encoded_length(&self) -> usize {\n");
-        output.push_str("        MessageHeader::ENCODED_LENGTH + Self::BLOCK_LENGTH as usize\n");
-        output.push_str("    }\n");
-        output.push_str("}\n\n");
+    /// Generates the upfront check that a group entry fits in the buffer,
+    /// at the `block_length` from the group header or at the schema's
+    /// `entry_block_length`, which unchecked field reads rely on.
+    ///
+    /// Group iterators cannot report an error per entry, so a truncated entry
+    /// panics, as a checked read past the end would. Empty unless unchecked
+    /// reads are enabled.
+    fn block_length_check(&self, entry_block_length: TokenStream) -> Option<TokenStream> {
+        self.config.unchecked_reads.then(|| {
+            quote! {
+                let required = usize::from(block_length).max(#entry_block_length);
+                assert!(
+                    offset <= buffer.len() && buffer.len() - offset >= required,
+                    "buffer too short for the block"
+                );
+            }
+        })
+    }
 
-        output
+    /// Returns the expression reading a field with `read_method` at
+    /// `offset` into the block.
+    ///
+    /// With unchecked reads enabled, fields inside the block, which `wrap`
+    /// has checked fits in the buffer, skip the bounds check.
+    fn read_expr(
+        &self,
+        read_method: &str,
+        field: &ResolvedField,
+        block_length: usize,
+    ) -> TokenStream {
+        let offset = lit(field.offset);
+        if self.config.unchecked_reads && field.offset + field.encoded_length <= block_length {
+            let read_method = format_ident!("{}_unchecked", read_method);
+            quote! { unsafe { self.buffer.#read_method(self.offset + #offset) } }
+        } else {
+            let read_method = ident(read_method);
+            quote! { self.buffer.#read_method(self.offset + #offset) }
+        }
     }
 
     /// Generates a field getter method.
-    fn generate_field_getter(&self, field: &ResolvedField) -> String {
-        let mut output = String::new();
-
-        output.push_str(&format!(
-            "    /// Field: {} (id={}, offset={}).\n",
+    fn generate_field_getter(&self, field: &ResolvedField, block_length: usize) -> TokenStream {
+        if let Some(constant) = &field.constant {
+            return generate_constant_getter(field, constant);
+        }
+        let getter_doc = doc(&format!(
+            "Field: {} (id={}, offset={}).",
             field.name, field.id, field.offset
         ));
-        output.push_str("    #[inline(always)]\n");
-        output.push_str("    #[must_use]\n");
+        let getter = ident(&field.getter_name);
+        let offset = lit(field.offset);
 
-        if field.is_array {
+        let accessor = if field.is_array {
             // Array field - return slice
             let elem_type = field.primitive_type.map(|p| p.rust_type()).unwrap_or("u8");
-            let len = field.array_length.unwrap_or(1);
 
             if elem_type == "u8" {
                 // Byte array - return &[u8]
-                output.push_str(&format!(
-                    "    pub fn {}(&self) -> &'a [u8] {{\n",
-                    field.getter_name
-                ));
-                output.push_str(&format!(
-                    "        &self.buffer[self.offset + {}..self.offset + {} + {}]\n",
-                    field.offset, field.offset, len
-                ));
-                output.push_str("    }\n\n");
+                let len = lit(field.array_length.unwrap_or(1));
+                let range = quote! { self.offset + #offset..self.offset + #offset + #len };
 
                 // Also generate a string accessor for char arrays
-                output.push_str(&format!(
-                    "    /// Field {} as string (trimmed).\n",
-                    field.name
-                ));
-                output.push_str("    #[inline]\n");
-                output.push_str("    #[must_use]\n");
-                output.push_str(&format!(
-                    "    pub fn {}_as_str(&self) -> &'a str {{\n",
-                    field.getter_name
-                ));
-                output.push_str(&format!(
-                    "        let bytes = &self.buffer[self.offset + {}..self.offset + {} + {}];\n",
-                    field.offset, field.offset, len
-                ));
-                output.push_str(
-                    "        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());\n",
-                );
-                output.push_str("        std::str::from_utf8(&bytes[..end]).unwrap_or(\"\")\n");
-                output.push_str("    }\n\n");
+                let str_doc = doc(&format!("Field {} as string (trimmed).", field.name));
+                let as_str = format_ident!("{}_as_str", field.getter_name);
+                quote! {
+                    pub fn #getter(&self) -> &'a [u8] {
+                        &self.buffer[#range]
+                    }
+                    #str_doc
+                    #[inline]
+                    #[must_use]
+                    pub fn #as_str(&self) -> &'a str {
+                        let bytes = &self.buffer[#range];
+                        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+                        core::str::from_utf8(&bytes[..end]).unwrap_or("")
+                    }
+                }
             } else {
                 // Other array types
-                output.push_str(&format!(
-                    "    pub fn {}(&self) -> &'a [u8] {{\n",
-                    field.getter_name
-                ));
-                output.push_str(&format!(
-                    "        &self.buffer[self.offset + {}..self.offset + {}]\n",
-                    field.offset,
-                    field.offset + field.encoded_length
-                ));
-                output.push_str("    }\n\n");
+                let end = lit(field.offset + field.encoded_length);
+                quote! {
+                    pub fn #getter(&self) -> &'a [u8] {
+                        &self.buffer[self.offset + #offset..self.offset + #end]
+                    }
+                }
             }
         } else {
             // Scalar field - check if it's an enum/set type
-            let rust_type = &field.rust_type;
+            let rust_type = tokens(&field.rust_type);
             let resolved_type = self.ir.get_type(&field.type_name);
 
             match resolved_type.map(|t| &t.kind) {
                 Some(TypeKind::Enum { encoding, .. }) => {
                     // Enum field - use encoding primitive and wrap with From
-                    let read_method = get_read_method(Some(*encoding));
-                    output.push_str(&format!(
-                        "    pub fn {}(&self) -> {} {{\n",
-                        field.getter_name, rust_type
-                    ));
-                    output.push_str(&format!(
-                        "        {}::from(self.buffer.{}(self.offset + {}))\n",
-                        rust_type, read_method, field.offset
-                    ));
-                    output.push_str("    }\n\n");
+                    let read =
+                        self.read_expr(get_read_method(Some(*encoding)), field, block_length);
+                    quote! {
+                        pub fn #getter(&self) -> #rust_type {
+                            #rust_type::from(#read)
+                        }
+                    }
                 }
                 Some(TypeKind::Set { encoding, .. }) => {
                     // Set field - use encoding primitive and wrap with from_raw
-                    let read_method = get_read_method(Some(*encoding));
-                    output.push_str(&format!(
-                        "    pub fn {}(&self) -> {} {{\n",
-                        field.getter_name, rust_type
-                    ));
-                    output.push_str(&format!(
-                        "        {}::from_raw(self.buffer.{}(self.offset + {}))\n",
-                        rust_type, read_method, field.offset
-                    ));
-                    output.push_str("    }\n\n");
+                    let read =
+                        self.read_expr(get_read_method(Some(*encoding)), field, block_length);
+                    quote! {
+                        pub fn #getter(&self) -> #rust_type {
+                            #rust_type::from_raw(#read)
+                        }
+                    }
                 }
                 Some(TypeKind::Composite { .. }) => {
                     // Composite field - return wrapper struct
-                    output.push_str(&format!(
-                        "    pub fn {}(&self) -> {}<'a> {{\n",
-                        field.getter_name, rust_type
-                    ));
-                    output.push_str(&format!(
-                        "        {}::wrap(self.buffer, self.offset + {})\n",
-                        rust_type, field.offset
-                    ));
-                    output.push_str("    }\n\n");
+                    quote! {
+                        pub fn #getter(&self) -> #rust_type<'a> {
+                            #rust_type::wrap(self.buffer, self.offset + #offset)
+                        }
+                    }
                 }
                 _ => {
                     // Primitive field
-                    let read_method = get_read_method(field.primitive_type);
-                    output.push_str(&format!(
-                        "    pub fn {}(&self) -> {} {{\n",
-                        field.getter_name, rust_type
-                    ));
-                    output.push_str(&format!(
-                        "        self.buffer.{}(self.offset + {})\n",
-                        read_method, field.offset
-                    ));
-                    output.push_str("    }\n\n");
+                    let read =
+                        self.read_expr(get_read_method(field.primitive_type), field, block_length);
+                    quote! {
+                        pub fn #getter(&self) -> #rust_type {
+                            #read
+                        }
+                    }
                 }
             }
+        };
+
+        quote! {
+            #getter_doc
+            #[inline(always)]
+            #[must_use]
+            #accessor
         }
+    }
 
-        output
+    /// Returns the owned field type and the decoder expression producing it.
+    fn owned_field(&self, field: &ResolvedField) -> (TokenStream, TokenStream) {
+        let getter = ident(&field.getter_name);
+
+        if field.is_array {
+            return match field.primitive_type {
+                Some(PrimitiveType::Char) => {
+                    let as_str = format_ident!("{}_as_str", field.getter_name);
+                    (quote! { String }, quote! { self.#as_str().to_string() })
+                }
+                _ => (quote! { Vec<u8> }, quote! { self.#getter().to_vec() }),
+            };
+        }
+
+        match self.ir.get_type(&field.type_name).map(|t| &t.kind) {
+            Some(TypeKind::Composite { .. }) => {
+                let owned = format_ident!("{}Owned", field.rust_type);
+                (quote! { #owned }, quote! { self.#getter().to_owned() })
+            }
+            _ => (tokens(&field.rust_type), quote! { self.#getter() }),
+        }
     }
 
-    /// Generates a group accessor method.
-    fn generate_group_accessor(
+    /// Generates the `to_owned()` method of a message or entry decoder.
+    fn generate_to_owned(
         &self,
-        group: &ResolvedGroup,
-        offset: usize,
-        msg_name: &str,
-    ) -> String {
-        let mut output = String::new();
-        let qualified = format!("{}::{}", to_snake_case(msg_name), group.decoder_name());
-
-        output.push_str(&format!("    /// Access {} repeating group.\n", group.name));
-        output.push_str("    #[inline]\n");
-        output.push_str("    #[must_use]\n");
-        output.push_str(&format!(
-            "    pub fn {}(&self) -> {}<'a> {{\n",
-            to_snake_case(&group.name),
-            qualified
+        owned_name: &str,
+        fields: &[ResolvedField],
+        groups: &[ResolvedGroup],
+        var_data: &[ResolvedVarData],
+    ) -> TokenStream {
+        let to_owned_doc = doc(&format!(
+            "Copies every field, group and var data into an owned [`{}`].",
+            owned_name
         ));
-        output.push_str(&format!(
-            "        {}::wrap(self.buffer, self.offset + {})\n",
-            qualified, offset
-        ));
-        output.push_str("    }\n\n");
+        let owned = ident(owned_name);
+        let field_copies = fields.iter().map(|field| {
+            let name = ident(&field.getter_name);
+            let (_, expr) = self.owned_field(field);
+            quote! { #name: #expr, }
+        });
+        let group_copies = groups.iter().map(|group| {
+            let name = ident(&to_snake_case(&group.name));
+            quote! { #name: self.#name().map(|entry| entry.to_owned()).collect(), }
+        });
+        let var_data_copies = var_data.iter().map(|data| {
+            let name = ident(&to_snake_case(&data.name));
+            quote! { #name: self.#name().to_vec(), }
+        });
+
+        quote! {
+            #to_owned_doc
+            #[must_use]
+            pub fn to_owned(&self) -> #owned {
+                #owned {
+                    #(#field_copies)*
+                    #(#group_copies)*
+                    #(#var_data_copies)*
+                }
+            }
+        }
+    }
 
-        output
+    /// Generates the owned, serde-enabled mirror of a message or entry.
+    fn generate_owned_struct(
+        &self,
+        owned_name: &str,
+        doc_text: &str,
+        fields: &[ResolvedField],
+        groups: &[ResolvedGroup],
+        var_data: &[ResolvedVarData],
+        path: &TokenStream,
+    ) -> TokenStream {
+        let struct_doc = doc(doc_text);
+        let serde = serde_derive();
+        let owned = ident(owned_name);
+        let owned_fields = fields.iter().map(|field| {
+            let field_doc = doc(&format!("Field: {} (id={}).", field.name, field.id));
+            let name = ident(&field.getter_name);
+            let (ty, _) = self.owned_field(field);
+            quote! {
+                #field_doc
+                pub #name: #ty,
+            }
+        });
+        let owned_groups = groups.iter().map(|group| {
+            let group_doc = doc(&format!("Group: {} (id={}).", group.name, group.id));
+            let name = ident(&to_snake_case(&group.name));
+            let entry = ident(&group.entry_owned_name());
+            quote! {
+                #group_doc
+                pub #name: Vec<#path #entry>,
+            }
+        });
+        let owned_var_data = var_data.iter().map(|data| {
+            let data_doc = doc(&format!("Var data: {} (id={}).", data.name, data.id));
+            let name = ident(&to_snake_case(&data.name));
+            quote! {
+                #data_doc
+                pub #name: Vec<u8>,
+            }
+        });
+
+        quote! {
+            #struct_doc
+            #[derive(Debug, Clone, PartialEq)]
+            #serde
+            pub struct #owned {
+                #(#owned_fields)*
+                #(#owned_groups)*
+                #(#owned_var_data)*
+            }
+        }
+    }
+
+    /// Generates the `Debug` impl of a message or entry decoder.
+    ///
+    /// Prints every field with its decoded value (enums by name, char arrays
+    /// as strings), every group as a list of entries and every var data
+    /// field as lossy UTF-8.
+    fn generate_debug(
+        &self,
+        decoder_name: &Ident,
+        fields: &[ResolvedField],
+        groups: &[ResolvedGroup],
+        var_data: &[ResolvedVarData],
+    ) -> TokenStream {
+        let type_name = decoder_name.to_string();
+        let field_entries = fields.iter().map(|field| {
+            let name = &field.getter_name;
+            let value = if field.is_array && field.primitive_type == Some(PrimitiveType::Char) {
+                format_ident!("{}_as_str", name)
+            } else {
+                ident(name)
+            };
+            quote! { .field(#name, &self.#value()) }
+        });
+        let group_entries = groups.iter().map(|group| {
+            let name = to_snake_case(&group.name);
+            let getter = ident(&name);
+            quote! { .field(#name, &self.#getter()) }
+        });
+        let var_data_entries = var_data.iter().map(|data| {
+            let name = to_snake_case(&data.name);
+            let getter = ident(&name);
+            if self.config.no_std {
+                quote! { .field(#name, &self.#getter()) }
+            } else {
+                quote! { .field(#name, &String::from_utf8_lossy(self.#getter())) }
+            }
+        });
+
+        quote! {
+            impl core::fmt::Debug for #decoder_name<'_> {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    f.debug_struct(#type_name)
+                        #(#field_entries)*
+                        #(#group_entries)*
+                        #(#var_data_entries)*
+                        .finish()
+                }
+            }
+        }
+    }
+
+    /// Generates a group accessor method.
+    ///
+    /// `path` qualifies the group decoder type (empty for nested groups,
+    /// which live in the same module as their parent entry).
+    fn generate_group_accessor(&self, group: &ResolvedGroup, path: &TokenStream) -> TokenStream {
+        let accessor_doc = doc(&format!("Access {} repeating group.", group.name));
+        let name = to_snake_case(&group.name);
+        let accessor = ident(&name);
+        let offset_fn = format_ident!("{}_offset", name);
+        let decoder = ident(&group.decoder_name());
+
+        quote! {
+            #accessor_doc
+            #[inline]
+            #[must_use]
+            pub fn #accessor(&self) -> #path #decoder<'a> {
+                #path #decoder::wrap(self.buffer, self.#offset_fn())
+            }
+        }
     }
 
     /// Generates a message encoder.
-    fn generate_encoder(&self, msg: &ResolvedMessage) -> String {
-        let mut output = String::new();
-        let encoder_name = msg.encoder_name();
+    fn generate_encoder(&self, msg: &ResolvedMessage) -> TokenStream {
+        let encoder_name = ident(&msg.encoder_name());
+        let sections = sections(&msg.groups, &msg.var_data);
+        let path = module_path(msg);
+        let buffer = quote! { &*self.buffer };
+        let block_end =
+            quote! { self.offset + MessageHeader::ENCODED_LENGTH + Self::BLOCK_LENGTH as usize };
+        let template_id = lit(usize::from(msg.template_id));
+        let block_length = lit(usize::from(msg.block_length));
 
         // Struct definition
-        output.push_str(&format!("/// {} Encoder.\n", msg.name));
-        output.push_str(&format!("pub struct {}<'a> {{\n", encoder_name));
-        output.push_str("    buffer: &'a mut [u8],\n");
-        output.push_str("    offset: usize,\n");
-        output.push_str("}\n\n");
-
-        // Implementation
-        output.push_str(&format!("impl<'a> {}<'a> {{\n", encoder_name));
-        output.push_str(&format!(
-            "    /// Template ID for this message.\n\
-             pub const TEMPLATE_ID: u16 = {};\n",
-            msg.template_id
-        ));
-        output.push_str(&format!(
-            "    /// Block length of the fixed portion.\n\
-             pub const BLOCK_LENGTH: u16 = {};\n\n",
-            msg.block_length
-        ));
-
-        // Constructor
-        output.push_str("    /// Wraps a buffer for encoding, writing the header.\n");
-        output.push_str("    #[inline]\n");
-        output.push_str("    pub fn wrap(buffer: &'a mut [u8], offset: usize) -> Self {\n");
-        output.push_str("        let mut encoder = Self { buffer, offset };\n");
-        output.push_str("        encoder.write_header();\n");
-        output.push_str("        encoder\n");
-        output.push_str("    }\n\n");
-
-        // Write header
-        output.push_str("    fn write_header(&mut self) {\n");
-        output.push_str("        let header = MessageHeader {\n");
-        output.push_str("            block_length: Self::BLOCK_LENGTH,\n");
-        output.push_str("            template_id: Self::TEMPLATE_ID,\n");
-        output.push_str("            schema_id: SCHEMA_ID,\n");
-        output.push_str("            version: SCHEMA_VERSION,\n");
-        output.push_str("        };\n");
-        output.push_str("        header.encode(self.buffer, self.offset);\n");
-        output.push_str("    }\n\n");
+        let struct_doc = doc(&format!("{} Encoder.", msg.name));
+        let order_doc = section_order_doc(&sections);
 
         // Encoded length
-        output.push_str("    /// Returns the encoded length of the message.\n");
-        output.push_str("    #[must_use]\n");
-        output.push_str("    pub const fn encoded_length(&self) -> usize {\n");
-        output.push_str("        MessageHeader::ENCODED_LENGTH + Self::BLOCK_LENGTH as usize\n");
-        output.push_str("    }\n\n");
+        let encoded_length = if sections.is_empty() {
+            quote! {
+                /// Returns the encoded length of the message.
+                #[must_use]
+                pub const fn encoded_length(&self) -> usize {
+                    MessageHeader::ENCODED_LENGTH + Self::BLOCK_LENGTH as usize
+                }
+            }
+        } else {
+            let end = generate_end_offset(&sections, &block_end, &path, &buffer);
+            quote! {
+                /// Returns the encoded length of the message.
+                ///
+                /// Only meaningful once every section has been written.
+                #[must_use]
+                pub fn encoded_length(&self) -> usize {
+                    #end
+                    end - self.offset
+                }
+            }
+        };
 
-        // Field setters
-        for field in &msg.fields {
-            output.push_str(&self.generate_field_setter(field));
-        }
+        let encoded_size = generate_encoded_size(
+            &msg.groups,
+            &msg.var_data,
+            &quote! { MessageHeader::ENCODED_LENGTH + Self::BLOCK_LENGTH as usize },
+            &path,
+            "Returns the encoded size, header included, of a message with the\n\
+             given group entry counts and var data lengths.",
+        );
 
-        // Group encoder accessors
-        let mut group_offset = msg.block_length as usize;
-        for group in &msg.groups {
-            output.push_str(&self.generate_group_encoder_accessor(group, group_offset, &msg.name));
-            group_offset += 4; // Group header size
+        // Field setters
+        let tracked = self.tracked_fields(&msg.fields);
+        let setters = msg
+            .fields
+            .iter()
+            .filter(|field| field.constant.is_none())
+            .map(|field| {
+                let bit = tracked.iter().position(|f| std::ptr::eq(*f, field));
+                self.generate_field_setter(field, bit)
+            });
+        let words = lit(tracked.len().div_ceil(64));
+        let dirty_field = (!tracked.is_empty()).then(|| quote! { dirty: [u64; #words], });
+        let dirty_init = (!tracked.is_empty()).then(|| quote! { dirty: [0; #words], });
+        let dirty_accessors =
+            (!tracked.is_empty()).then(|| generate_dirty_accessors(tracked.len().div_ceil(64)));
+
+        // Group encoder accessors and var data setters
+        let group_accessors = msg
+            .groups
+            .iter()
+            .map(|group| self.generate_group_encoder_accessor(group, &path, false));
+        let var_data_setters = msg
+            .var_data
+            .iter()
+            .map(|data| generate_var_data_setter(data, false));
+        let section_offsets = generate_section_offsets(&sections, &block_end, &path, &buffer);
+        let header_param =
+            (!self.ir.message_header.is_standard()).then(|| quote! { , MessageHeader });
+        let header_extensions = self
+            .ir
+            .message_header
+            .members
+            .iter()
+            .filter(|m| !MessageHeaderLayout::REQUIRED_MEMBERS.contains(&m.name.as_str()))
+            .map(|m| {
+                let name = ident(&to_snake_case(&m.name));
+                let value = lit(MessageHeaderLayout::member_value(&m.name, msg, self.ir) as usize);
+                quote! { #name: #value, }
+            });
+
+        quote! {
+            #struct_doc
+            #order_doc
+            pub struct #encoder_name<'a> {
+                buffer: &'a mut [u8],
+                offset: usize,
+                #dirty_field
+            }
+            impl<'a> #encoder_name<'a> {
+                /// Template ID for this message.
+                pub const TEMPLATE_ID: u16 = #template_id;
+                /// Block length of the fixed portion.
+                pub const BLOCK_LENGTH: u16 = #block_length;
+                /// Wraps a buffer for encoding, writing the header.
+                #[inline]
+                pub fn wrap(buffer: &'a mut [u8], offset: usize) -> Self {
+                    let mut encoder = Self { buffer, offset, #dirty_init };
+                    encoder.write_header();
+                    encoder
+                }
+                fn write_header(&mut self) {
+                    let header = MessageHeader {
+                        block_length: Self::BLOCK_LENGTH,
+                        template_id: Self::TEMPLATE_ID,
+                        schema_id: SCHEMA_ID,
+                        version: SCHEMA_VERSION,
+                        #(#header_extensions)*
+                    };
+                    header.encode(self.buffer, self.offset);
+                }
+                #encoded_length
+                #encoded_size
+                #dirty_accessors
+                #(#setters)*
+                #(#group_accessors)*
+                #(#var_data_setters)*
+                #section_offsets
+            }
+            impl<'a> SbeEncoder<'a #header_param> for #encoder_name<'a> {
+                const TEMPLATE_ID: u16 = #template_id;
+                const SCHEMA_ID: u16 = SCHEMA_ID;
+                const SCHEMA_VERSION: u16 = SCHEMA_VERSION;
+                const BLOCK_LENGTH: u16 = #block_length;
+                fn wrap(buffer: &'a mut [u8], offset: usize) -> Self {
+                    Self::wrap(buffer, offset)
+                }
+                fn encoded_length(&self) -> usize {
+                    self.encoded_length()
+                }
+                fn as_bytes(&self) -> &[u8] {
+                    &self.buffer[self.offset..self.offset + self.encoded_length()]
+                }
+            }
         }
-
-        output.push_str("}\n\n");
-
-        output
     }
 
     /// Generates a field setter method.
-    fn generate_field_setter(&self, field: &ResolvedField) -> String {
-        let mut output = String::new();
-        let field_offset = format!("MessageHeader::ENCODED_LENGTH + {}", field.offset);
+    fn generate_field_setter(&self, field: &ResolvedField, bit: Option<usize>) -> TokenStream {
+        let offset = lit(field.offset);
+        self.generate_setter(
+            field,
+            quote! { MessageHeader::ENCODED_LENGTH + #offset },
+            bit,
+        )
+    }
+
+    /// Generates a field setter for a group entry encoder.
+    ///
+    /// Unlike the message-level `generate_field_setter`, this uses the raw field
+    /// offset (relative to the entry start) without a `MessageHeader::ENCODED_LENGTH`
+    /// prefix.
+    fn generate_entry_field_setter(
+        &self,
+        field: &ResolvedField,
+        bit: Option<usize>,
+    ) -> TokenStream {
+        let offset = lit(field.offset);
+        self.generate_setter(field, quote! { #offset }, bit)
+    }
 
-        output.push_str(&format!(
-            "    /// Set field: {} (id={}, offset={}).\n",
+    /// Generates a setter writing `field` at `self.offset + field_offset`.
+    ///
+    /// With a dirty mask `bit`, also generates its `set_*_if_changed`
+    /// variant.
+    fn generate_setter(
+        &self,
+        field: &ResolvedField,
+        field_offset: TokenStream,
+        bit: Option<usize>,
+    ) -> TokenStream {
+        let setter_doc = doc(&format!(
+            "Set field: {} (id={}, offset={}).",
             field.name, field.id, field.offset
         ));
-        output.push_str("    #[inline(always)]\n");
+        let setter = ident(&field.setter_name);
 
-        if field.is_array {
+        let method = if field.is_array {
             // Array field - accept slice
-            let len = field.array_length.unwrap_or(field.encoded_length);
-
-            output.push_str(&format!(
-                "    pub fn {}(&mut self, value: &[u8]) -> &mut Self {{\n",
-                field.setter_name
-            ));
-            output.push_str(&format!(
-                "        let copy_len = value.len().min({});\n",
-                len
-            ));
-            output.push_str(&format!(
-                "        self.buffer[self.offset + {}..self.offset + {} + copy_len]\n",
-                field_offset, field_offset
-            ));
-            output.push_str("            .copy_from_slice(&value[..copy_len]);\n");
-            output.push_str(&format!("        if copy_len < {} {{\n", len));
-            output.push_str(&format!(
-                "            self.buffer[self.offset + {} + copy_len..self.offset + {} + {}].fill(0);\n",
-                field_offset, field_offset, len
-            ));
-            output.push_str("        }\n");
-            output.push_str("        self\n");
-            output.push_str("    }\n\n");
+            let copy = array_copy(
+                &field_offset,
+                field.array_length.unwrap_or(field.encoded_length),
+            );
+            quote! {
+                pub fn #setter(&mut self, value: &[u8]) -> &mut Self {
+                    #copy
+                    self
+                }
+            }
         } else {
             // Scalar field - check if it's an enum/set type
-            let rust_type = &field.rust_type;
+            let rust_type = tokens(&field.rust_type);
             let resolved_type = self.ir.get_type(&field.type_name);
 
             match resolved_type.map(|t| &t.kind) {
                 Some(TypeKind::Enum { encoding, .. }) => {
                     // Enum field - convert enum to primitive before writing
-                    let write_method = get_write_method(Some(*encoding));
-                    let prim_type = encoding.rust_type();
-                    output.push_str(&format!(
-                        "    pub fn {}(&mut self, value: {}) -> &mut Self {{\n",
-                        field.setter_name, rust_type
-                    ));
-                    output.push_str(&format!(
-                        "        self.buffer.{}(self.offset + {}, {}::from(value));\n",
-                        write_method, field_offset, prim_type
-                    ));
-                    output.push_str("        self\n");
-                    output.push_str("    }\n\n");
+                    let write_method = ident(get_write_method(Some(*encoding)));
+                    let prim_type = ident(encoding.rust_type());
+                    quote! {
+                        pub fn #setter(&mut self, value: #rust_type) -> &mut Self {
+                            self.buffer.#write_method(self.offset + #field_offset, #prim_type::from(value));
+                            self
+                        }
+                    }
                 }
                 Some(TypeKind::Set { encoding, .. }) => {
                     // Set field - use raw() to get the primitive value
-                    let write_method = get_write_method(Some(*encoding));
-                    output.push_str(&format!(
-                        "    pub fn {}(&mut self, value: {}) -> &mut Self {{\n",
-                        field.setter_name, rust_type
-                    ));
-                    output.push_str(&format!(
-                        "        self.buffer.{}(self.offset + {}, value.raw());\n",
-                        write_method, field_offset
-                    ));
-                    output.push_str("        self\n");
-                    output.push_str("    }\n\n");
+                    let write_method = ident(get_write_method(Some(*encoding)));
+                    quote! {
+                        pub fn #setter(&mut self, value: #rust_type) -> &mut Self {
+                            self.buffer.#write_method(self.offset + #field_offset, value.raw());
+                            self
+                        }
+                    }
                 }
                 Some(TypeKind::Composite { .. }) => {
                     // Composite field - return encoder for nested writes
-                    output.push_str(&format!(
-                        "    pub fn {}(&mut self) -> {}Encoder<'_> {{\n",
-                        field.setter_name, rust_type
-                    ));
-                    output.push_str(&format!(
-                        "        {}Encoder::wrap(self.buffer, self.offset + {})\n",
-                        rust_type, field_offset
-                    ));
-                    output.push_str("    }\n\n");
+                    let encoder = format_ident!("{}Encoder", field.rust_type);
+                    quote! {
+                        pub fn #setter(&mut self) -> #encoder<'_> {
+                            #encoder::wrap(self.buffer, self.offset + #field_offset)
+                        }
+                    }
                 }
                 _ => {
                     // Primitive field
-                    let write_method = get_write_method(field.primitive_type);
-                    output.push_str(&format!(
-                        "    pub fn {}(&mut self, value: {}) -> &mut Self {{\n",
-                        field.setter_name, rust_type
-                    ));
-                    output.push_str(&format!(
-                        "        self.buffer.{}(self.offset + {}, value);\n",
-                        write_method, field_offset
-                    ));
-                    output.push_str("        self\n");
-                    output.push_str("    }\n\n");
+                    let write_method = ident(get_write_method(field.primitive_type));
+                    quote! {
+                        pub fn #setter(&mut self, value: #rust_type) -> &mut Self {
+                            self.buffer.#write_method(self.offset + #field_offset, value);
+                            self
+                        }
+                    }
                 }
             }
-        }
+        };
 
-        output
+        let if_changed = bit.map(|bit| self.generate_if_changed_setter(field, &field_offset, bit));
+
+        quote! {
+            #setter_doc
+            #[inline(always)]
+            #method
+            #if_changed
+        }
     }
 
-    /// Generates a group decoder.
-    fn generate_group_decoder(&self, group: &ResolvedGroup) -> String {
-        let mut output = String::new();
-        let decoder_name = group.decoder_name();
-        let entry_name = group.entry_decoder_name();
-
-        // Group decoder struct
-        output.push_str(&format!("/// {} Group Decoder.\n", group.name));
-        output.push_str("#[derive(Debug, Clone, Copy)]\n");
-        output.push_str(&format!("pub struct {}<'a> {{\n", decoder_name));
-        output.push_str("    buffer: &'a [u8],\n");
-        output.push_str("    block_length: u16,\n");
-        output.push_str("    count: u16,\n");
-        output.push_str("    index: u16,\n");
-        output.push_str("    offset: usize,\n");
-        output.push_str("}\n\n");
-
-        // Group decoder implementation
-        output.push_str(&format!("impl<'a> {}<'a> {{\n", decoder_name));
-        output.push_str("    /// Wraps a buffer at the group header position.\n");
-        output.push_str("    #[must_use]\n");
-        output.push_str("    pub fn wrap(buffer: &'a [u8], offset: usize) -> Self {\n");
-        output.push_str("        let header = GroupHeader::wrap(buffer, offset);\n");
-        output.push_str("        Self {\n");
-        output.push_str("            buffer,\n");
-        output.push_str("            block_length: header.block_length,\n");
-        output.push_str("            count: header.num_in_group,\n");
-        output.push_str("            index: 0,\n");
-        output.push_str("            offset: offset + GroupHeader::ENCODED_LENGTH,\n");
-        output.push_str("        }\n");
-        output.push_str("    }\n\n");
-
-        output.push_str("    /// Returns the number of entries in the group.\n");
-        output.push_str("    #[must_use]\n");
-        output.push_str("    pub const fn count(&self) -> u16 {\n");
-        output.push_str("        self.count\n");
-        output.push_str("    }\n\n");
-
-        output.push_str("    /// Returns true if the group is empty.\n");
-        output.push_str("    #[must_use]\n");
-        output.push_str("    pub const fn is_empty(&self) -> bool {\n");
-        output.push_str("        self.count == 0\n");
-        output.push_str("    }\n");
-        output.push_str("}\n\n");
-
-        // Iterator implementation
-        output.push_str(&format!("impl<'a> Iterator for {}<'a> {{\n", decoder_name));
-        output.push_str(&format!("    type Item = {}<'a>;\n\n", entry_name));
-        output.push_str("    fn next(&mut self) -> Option<Self::Item> {\n");
-        output.push_str("        if self.index >= self.count {\n");
-        output.push_str("            return None;\n");
-        output.push_str("        }\n");
-        output.push_str(&format!(
-            "        let entry = {}::wrap(self.buffer, self.offset);\n",
-            entry_name
-        ));
-        output.push_str("        self.offset += self.block_length as usize;\n");
-        output.push_str("        self.index += 1;\n");
-        output.push_str("        Some(entry)\n");
-        output.push_str("    }\n\n");
-
-        output.push_str("    fn size_hint(&self) -> (usize, Option<usize>) {\n");
-        output.push_str("        let remaining = (self.count - self.index) as usize;\n");
-        output.push_str("        (remaining, Some(remaining))\n");
-        output.push_str("    }\n");
-        output.push_str("}\n\n");
-
-        output.push_str(&format!(
-            "impl<'a> ExactSizeIterator for {}<'a> {{}}\n\n",
-            decoder_name
+    /// Generates a setter that only writes `field` if its encoded value
+    /// differs from the buffer, marking `bit` of the dirty mask when it does.
+    fn generate_if_changed_setter(
+        &self,
+        field: &ResolvedField,
+        field_offset: &TokenStream,
+        bit: usize,
+    ) -> TokenStream {
+        let setter_doc = doc(&format!(
+            "Set field {} only if it differs from the buffer, marking bit {} of\n\
+             the dirty mask.\n\n\
+             Returns true if the field was written.",
+            field.name, bit
         ));
+        let setter = format_ident!("{}_if_changed", field.setter_name);
+        let word = lit(bit / 64);
+        let shift = lit(bit % 64);
+        let mark = quote! { self.dirty[#word] |= 1 << #shift; };
+
+        if field.is_array {
+            let len = field.array_length.unwrap_or(field.encoded_length);
+            let unchanged = array_unchanged_return(field_offset, len);
+            let copy = array_copy(field_offset, len);
+            return quote! {
+                #setter_doc
+                #[inline(always)]
+                pub fn #setter(&mut self, value: &[u8]) -> bool {
+                    #unchanged
+                    #copy
+                    #mark
+                    true
+                }
+            };
+        }
 
-        // Entry decoder
-        output.push_str(&self.generate_entry_decoder(group));
+        let rust_type = tokens(&field.rust_type);
+        let (encoding, raw) = match self.ir.get_type(&field.type_name).map(|t| &t.kind) {
+            Some(TypeKind::Enum { encoding, .. }) => {
+                let prim_type = ident(encoding.rust_type());
+                (Some(*encoding), quote! { #prim_type::from(value) })
+            }
+            Some(TypeKind::Set { encoding, .. }) => (Some(*encoding), quote! { value.raw() }),
+            _ => (field.primitive_type, quote! { value }),
+        };
+        let read_method = ident(get_read_method(encoding));
+        let write_method = ident(get_write_method(encoding));
+        // Floats compare by bit pattern so NaN payloads and signed zeros
+        // count as changes.
+        let unchanged = match encoding {
+            Some(PrimitiveType::Float | PrimitiveType::Double) => {
+                quote! { self.buffer.#read_method(offset).to_bits() == raw.to_bits() }
+            }
+            _ => quote! { self.buffer.#read_method(offset) == raw },
+        };
 
-        // Nested groups
-        for nested in &group.nested_groups {
-            output.push_str(&self.generate_group_decoder(nested));
+        quote! {
+            #setter_doc
+            #[inline(always)]
+            pub fn #setter(&mut self, value: #rust_type) -> bool {
+                let raw = #raw;
+                let offset = self.offset + #field_offset;
+                if #unchanged {
+                    return false;
+                }
+                self.buffer.#write_method(offset, raw);
+                #mark
+                true
+            }
         }
+    }
 
-        output
+    /// Generates a group decoder.
+    fn generate_group_decoder(&self, group: &ResolvedGroup) -> TokenStream {
+        let group_doc = doc(&format!("{} Group Decoder.", group.name));
+        let decoder_name = ident(&group.decoder_name());
+        let entry_name = ident(&group.entry_decoder_name());
+        let variable = has_variable_entries(group);
+        let start_field = variable.then(|| quote! { start: usize, });
+        let start_init = variable.then(|| quote! { start: offset, });
+        let header = group_header(group);
+        let count_type = count_type(group);
+        let block_length = match group.dimension.block_length_type {
+            PrimitiveType::Uint16 => quote! { header.block_length },
+            PrimitiveType::Uint8 => quote! { u16::from(header.block_length) },
+            _ => quote! { header.block_length as u16 },
+        };
+
+        let encoded_length = if variable {
+            quote! {
+                pub fn encoded_length(&self) -> usize {
+                    let first = self.start + #header::ENCODED_LENGTH;
+                    let mut end = first;
+                    for _ in 0..self.count {
+                        end += #entry_name::wrap(self.buffer, end, self.block_length).encoded_length();
+                    }
+                    #header::ENCODED_LENGTH + end - first
+                }
+            }
+        } else {
+            quote! {
+                pub const fn encoded_length(&self) -> usize {
+                    #header::ENCODED_LENGTH + self.block_length as usize * self.count as usize
+                }
+            }
+        };
+
+        let debug = generate_group_debug(&decoder_name);
+        let entry = self.generate_entry_decoder(group);
+        let nested = group
+            .nested_groups
+            .iter()
+            .map(|nested| self.generate_group_decoder(nested));
+
+        quote! {
+            #group_doc
+            #[derive(Clone, Copy)]
+            pub struct #decoder_name<'a> {
+                buffer: &'a [u8],
+                #start_field
+                block_length: u16,
+                count: #count_type,
+                index: #count_type,
+                offset: usize,
+            }
+            impl<'a> #decoder_name<'a> {
+                /// Wraps a buffer at the group header position.
+                #[inline]
+                #[must_use]
+                pub fn wrap(buffer: &'a [u8], offset: usize) -> Self {
+                    let header = #header::wrap(buffer, offset);
+                    Self {
+                        buffer,
+                        #start_init
+                        block_length: #block_length,
+                        count: header.num_in_group,
+                        index: 0,
+                        offset: offset + #header::ENCODED_LENGTH,
+                    }
+                }
+                /// Returns the number of entries in the group.
+                #[inline]
+                #[must_use]
+                pub const fn count(&self) -> #count_type {
+                    self.count
+                }
+                /// Returns true if the group is empty.
+                #[inline]
+                #[must_use]
+                pub const fn is_empty(&self) -> bool {
+                    self.count == 0
+                }
+                /// Returns the total encoded length of this group (header + all entries).
+                #[inline]
+                #[must_use]
+                #encoded_length
+            }
+            impl<'a> Iterator for #decoder_name<'a> {
+                type Item = #entry_name<'a>;
+                #[inline]
+                fn next(&mut self) -> Option<Self::Item> {
+                    if self.index >= self.count {
+                        return None;
+                    }
+                    let entry = #entry_name::wrap(self.buffer, self.offset, self.block_length);
+                    self.offset += entry.encoded_length();
+                    self.index += 1;
+                    Some(entry)
+                }
+                #[inline]
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    let remaining = (self.count - self.index) as usize;
+                    (remaining, Some(remaining))
+                }
+            }
+            impl<'a> ExactSizeIterator for #decoder_name<'a> {}
+            #debug
+            #entry
+            #(#nested)*
+        }
     }
 
     /// Generates a group entry decoder.
-    fn generate_entry_decoder(&self, group: &ResolvedGroup) -> String {
-        let mut output = String::new();
-        let entry_name = group.entry_decoder_name();
-
-        output.push_str(&format!("/// {} Entry Decoder.\n", group.name));
-        output.push_str("#[derive(Debug, Clone, Copy)]\n");
-        output.push_str(&format!("pub struct {}<'a> {{\n", entry_name));
-        output.push_str("    buffer: &'a [u8],\n");
-        output.push_str("    offset: usize,\n");
-        output.push_str("}\n\n");
-
-        output.push_str(&format!("impl<'a> {}<'a> {{\n", entry_name));
-        output.push_str("    fn wrap(buffer: &'a [u8], offset: usize) -> Self {\n");
-        output.push_str("        Self { buffer, offset }\n");
-        output.push_str("    }\n\n");
+    fn generate_entry_decoder(&self, group: &ResolvedGroup) -> TokenStream {
+        let entry_doc = doc(&format!("{} Entry Decoder.", group.name));
+        let entry_name = ident(&group.entry_decoder_name());
+        let sections = sections(&group.nested_groups, &group.var_data);
+        let order_doc = section_order_doc(&sections);
+        let path = TokenStream::new();
+        let buffer = quote! { self.buffer };
+        let block_end = quote! { self.offset + self.block_length as usize };
+
+        let encoded_length = if sections.is_empty() {
+            quote! {
+                pub const fn encoded_length(&self) -> usize {
+                    self.block_length as usize
+                }
+            }
+        } else {
+            let end = generate_end_offset(&sections, &block_end, &path, &buffer);
+            quote! {
+                pub fn encoded_length(&self) -> usize {
+                    #end
+                    end - self.offset
+                }
+            }
+        };
 
         // Field getters
-        for field in &group.fields {
-            output.push_str(&self.generate_field_getter(field));
+        let entry_block_length = effective_block_length(group);
+        let block_length = lit(usize::from(entry_block_length));
+        let length_check = self.block_length_check(quote! { #block_length });
+        let getters = group.fields.iter().map(|field| {
+            let getter = self.generate_field_getter(field, usize::from(entry_block_length));
+            let presence =
+                has_null_sentinel(field).then(|| generate_presence_accessors(field, None));
+            quote! { #getter #presence }
+        });
+
+        // Nested group and var data accessors
+        let group_accessors = group
+            .nested_groups
+            .iter()
+            .map(|nested| self.generate_group_accessor(nested, &path));
+        let var_data_getters = group.var_data.iter().map(generate_var_data_getter);
+        let section_offsets = generate_section_offsets(&sections, &block_end, &path, &buffer);
+
+        let to_owned = self.config.owned_types().then(|| {
+            self.generate_to_owned(
+                &group.entry_owned_name(),
+                &group.fields,
+                &group.nested_groups,
+                &group.var_data,
+            )
+        });
+        let debug = self.generate_debug(
+            &entry_name,
+            &group.fields,
+            &group.nested_groups,
+            &group.var_data,
+        );
+        let owned_struct = self.config.owned_types().then(|| {
+            self.generate_owned_struct(
+                &group.entry_owned_name(),
+                &format!("Owned copy of a decoded {} entry.", group.name),
+                &group.fields,
+                &group.nested_groups,
+                &group.var_data,
+                &path,
+            )
+        });
+
+        quote! {
+            #entry_doc
+            #order_doc
+            #[derive(Clone, Copy)]
+            pub struct #entry_name<'a> {
+                buffer: &'a [u8],
+                offset: usize,
+                block_length: u16,
+            }
+            impl<'a> #entry_name<'a> {
+                #[inline]
+                fn wrap(buffer: &'a [u8], offset: usize, block_length: u16) -> Self {
+                    #length_check
+                    Self {
+                        buffer,
+                        offset,
+                        block_length,
+                    }
+                }
+                /// Returns the encoded length of this entry, including nested sections.
+                #[inline]
+                #[must_use]
+                #encoded_length
+                #(#getters)*
+                #(#group_accessors)*
+                #(#var_data_getters)*
+                #section_offsets
+                #to_owned
+            }
+            #debug
+            #owned_struct
         }
-
-        output.push_str("}\n\n");
-
-        output
     }
 
     /// Generates a group encoder.
-    fn generate_group_encoder(&self, group: &ResolvedGroup) -> String {
-        let mut output = String::new();
-        let encoder_name = group.encoder_name();
-        let entry_name = group.entry_encoder_name();
-
-        // Compute effective block length: use XML value if nonzero, else derive from fields
-        let effective_block_length = if group.block_length > 0 {
-            group.block_length
-        } else {
-            group
-                .fields
-                .iter()
-                .map(|f| f.offset + f.encoded_length)
-                .max()
-                .unwrap_or(0) as u16
+    ///
+    /// A `nested` group with variable-length entries writes its running end
+    /// through to the enclosing entry, which shares it with its own group.
+    fn generate_group_encoder(&self, group: &ResolvedGroup, nested: bool) -> TokenStream {
+        let group_doc = doc(&format!("{} Group Encoder.", group.name));
+        let encoder_name = ident(&group.encoder_name());
+        let entry_name = ident(&group.entry_encoder_name());
+
+        let block_length = lit(usize::from(effective_block_length(group)));
+        let header = group_header(group);
+        let count_type = count_type(group);
+        let header_block_length = match group.dimension.block_length_type {
+            PrimitiveType::Uint16 => quote! { Self::BLOCK_LENGTH },
+            other => {
+                let rust_type = ident(other.rust_type());
+                quote! { Self::BLOCK_LENGTH as #rust_type }
+            }
         };
 
-        // Group encoder struct
-        output.push_str(&format!("/// {} Group Encoder.\n", group.name));
-        output.push_str(&format!("pub struct {}<'a> {{\n", encoder_name));
-        output.push_str("    buffer: &'a mut [u8],\n");
-        output.push_str("    count: u16,\n");
-        output.push_str("    index: u16,\n");
-        output.push_str("    offset: usize,\n");
-        output.push_str("}\n\n");
-
-        // Group encoder implementation
-        output.push_str(&format!("impl<'a> {}<'a> {{\n", encoder_name));
-        output.push_str(&format!(
-            "    /// Block length of each entry.\n\
-             pub const BLOCK_LENGTH: u16 = {};\n\n",
-            effective_block_length
-        ));
-
-        // wrap constructor
-        output
-            .push_str("    /// Wraps a buffer at the group header position, writing the header.\n");
-        output.push_str("    ///\n");
-        output.push_str("    /// # Arguments\n");
-        output.push_str("    /// * `buffer` - Mutable buffer to write to\n");
-        output.push_str("    /// * `offset` - Offset of the group header\n");
-        output.push_str("    /// * `count` - Number of entries to encode\n");
-        output.push_str(
-            "    pub fn wrap(buffer: &'a mut [u8], offset: usize, count: u16) -> Self {\n",
-        );
-        output.push_str("        let header = GroupHeader::new(Self::BLOCK_LENGTH, count);\n");
-        output.push_str("        header.encode(buffer, offset);\n");
-        output.push_str("        Self {\n");
-        output.push_str("            buffer,\n");
-        output.push_str("            count,\n");
-        output.push_str("            index: 0,\n");
-        output.push_str("            offset: offset + GroupHeader::ENCODED_LENGTH,\n");
-        output.push_str("        }\n");
-        output.push_str("    }\n\n");
+        let variable = has_variable_entries(group);
+        // `end` is where the next entry starts. Entries of a variable group
+        // move it as their nested sections are written; a nested group
+        // borrows the enclosing entry's `end` so that entry grows too.
+        let (end_field, end_param, end_set, end_init, end) = if !variable {
+            (
+                quote! { offset: usize, },
+                TokenStream::new(),
+                TokenStream::new(),
+                quote! { offset: offset + #header::ENCODED_LENGTH, },
+                TokenStream::new(),
+            )
+        } else if nested {
+            (
+                quote! { start: usize, end: &'a mut usize, },
+                quote! { , end: &'a mut usize },
+                quote! { *end = offset + #header::ENCODED_LENGTH; },
+                quote! { start: offset, end, },
+                quote! { *self.end },
+            )
+        } else {
+            (
+                quote! { start: usize, end: usize, },
+                TokenStream::new(),
+                TokenStream::new(),
+                quote! { start: offset, end: offset + #header::ENCODED_LENGTH, },
+                quote! { self.end },
+            )
+        };
+        let end_doc = (variable && nested)
+            .then(|| quote! { #[doc = " * `end` - Running end of the enclosing entry"] });
 
         // next_entry
-        output.push_str(
-            "    /// Returns the next entry encoder, or `None` if all entries are written.\n",
-        );
-        output.push_str(&format!(
-            "    pub fn next_entry(&mut self) -> Option<{}<'_>> {{\n",
-            entry_name
-        ));
-        output.push_str("        if self.index >= self.count {\n");
-        output.push_str("            return None;\n");
-        output.push_str("        }\n");
-        output.push_str("        let offset = self.offset;\n");
-        output.push_str("        self.offset += Self::BLOCK_LENGTH as usize;\n");
-        output.push_str("        self.index += 1;\n");
-        output.push_str(&format!(
-            "        Some({}::wrap(&mut *self.buffer, offset))\n",
-            entry_name
-        ));
-        output.push_str("    }\n\n");
+        let next_entry = if variable {
+            quote! {
+                /// Returns the next entry encoder, or `None` if all entries are written.
+                ///
+                /// The entry starts where the previous one, including its
+                /// nested sections, ends.
+                pub fn next_entry(&mut self) -> Option<#entry_name<'_>> {
+                    if self.index >= self.count {
+                        return None;
+                    }
+                    let offset = #end;
+                    self.index += 1;
+                    Some(#entry_name::wrap(&mut *self.buffer, offset, &mut #end))
+                }
+            }
+        } else {
+            quote! {
+                /// Returns the next entry encoder, or `None` if all entries are written.
+                pub fn next_entry(&mut self) -> Option<#entry_name<'_>> {
+                    if self.index >= self.count {
+                        return None;
+                    }
+                    let offset = self.offset;
+                    self.offset += Self::BLOCK_LENGTH as usize;
+                    self.index += 1;
+                    Some(#entry_name::wrap(&mut *self.buffer, offset))
+                }
+            }
+        };
 
         // encoded_length
-        output.push_str(
-            "    /// Returns the total encoded length of this group (header + all entries).\n",
-        );
-        output.push_str("    #[must_use]\n");
-        output.push_str("    pub const fn encoded_length(&self) -> usize {\n");
-        output.push_str("        GroupHeader::ENCODED_LENGTH + Self::BLOCK_LENGTH as usize * self.count as usize\n");
-        output.push_str("    }\n");
-        output.push_str("}\n\n");
-
-        // Entry encoder
-        output.push_str(&self.generate_entry_encoder(group));
-
-        // Nested group encoders
-        for nested in &group.nested_groups {
-            output.push_str(&self.generate_group_encoder(nested));
-        }
+        let encoded_length = if variable {
+            quote! {
+                /// Returns the total encoded length of this group (header + all entries).
+                ///
+                /// Covers the entries and nested sections written so far.
+                #[must_use]
+                pub fn encoded_length(&self) -> usize {
+                    #end - self.start
+                }
+            }
+        } else {
+            quote! {
+                /// Returns the total encoded length of this group (header + all entries).
+                #[must_use]
+                pub const fn encoded_length(&self) -> usize {
+                    #header::ENCODED_LENGTH + Self::BLOCK_LENGTH as usize * self.count as usize
+                }
+            }
+        };
 
-        output
+        let entry = self.generate_entry_encoder(group);
+        let nested = group
+            .nested_groups
+            .iter()
+            .map(|nested| self.generate_group_encoder(nested, true));
+
+        quote! {
+            #group_doc
+            pub struct #encoder_name<'a> {
+                buffer: &'a mut [u8],
+                #end_field
+                count: #count_type,
+                index: #count_type,
+            }
+            impl<'a> #encoder_name<'a> {
+                /// Block length of each entry.
+                pub const BLOCK_LENGTH: u16 = #block_length;
+                /// Wraps a buffer at the group header position, writing the header.
+                ///
+                /// # Arguments
+                /// * `buffer` - Mutable buffer to write to
+                /// * `offset` - Offset of the group header
+                /// * `count` - Number of entries to encode
+                #end_doc
+                pub fn wrap(buffer: &'a mut [u8], offset: usize, count: #count_type #end_param) -> Self {
+                    let header = #header::new(#header_block_length, count);
+                    header.encode(buffer, offset);
+                    #end_set
+                    Self {
+                        buffer,
+                        #end_init
+                        count,
+                        index: 0,
+                    }
+                }
+                #next_entry
+                #encoded_length
+            }
+            #entry
+            #(#nested)*
+        }
     }
 
     /// Generates a group entry encoder.
-    fn generate_entry_encoder(&self, group: &ResolvedGroup) -> String {
-        let mut output = String::new();
-        let entry_name = group.entry_encoder_name();
-
-        output.push_str(&format!("/// {} Entry Encoder.\n", group.name));
-        output.push_str(&format!("pub struct {}<'a> {{\n", entry_name));
-        output.push_str("    buffer: &'a mut [u8],\n");
-        output.push_str("    offset: usize,\n");
-        output.push_str("}\n\n");
-
-        output.push_str(&format!("impl<'a> {}<'a> {{\n", entry_name));
-        output.push_str("    pub fn wrap(buffer: &'a mut [u8], offset: usize) -> Self {\n");
-        output.push_str("        Self { buffer, offset }\n");
-        output.push_str("    }\n\n");
+    fn generate_entry_encoder(&self, group: &ResolvedGroup) -> TokenStream {
+        let entry_doc = doc(&format!("{} Entry Encoder.", group.name));
+        let entry_name = ident(&group.entry_encoder_name());
+        let sections = sections(&group.nested_groups, &group.var_data);
+        let order_doc = section_order_doc(&sections);
+        let path = TokenStream::new();
+        let group_encoder = ident(&group.encoder_name());
+        let block_end = quote! { self.offset + #group_encoder::BLOCK_LENGTH as usize };
+        let variable = has_variable_entries(group);
 
         // Field setters
-        for field in &group.fields {
-            output.push_str(&self.generate_entry_field_setter(field));
-        }
-
-        output.push_str("}\n\n");
-
-        output
-    }
-
-    /// Generates a field setter for a group entry encoder.
-    ///
-    /// Unlike the message-level `generate_field_setter`, this uses the raw field
-    /// offset (relative to the entry start) without a `MessageHeader::ENCODED_LENGTH`
-    /// prefix.
-    fn generate_entry_field_setter(&self, field: &ResolvedField) -> String {
-        let mut output = String::new();
-        let field_offset = field.offset;
-
-        output.push_str(&format!(
-            "    /// Set field: {} (id={}, offset={}).\n",
-            field.name, field.id, field.offset
-        ));
-        output.push_str("    #[inline(always)]\n");
-
-        if field.is_array {
-            let len = field.array_length.unwrap_or(field.encoded_length);
-
-            output.push_str(&format!(
-                "    pub fn {}(&mut self, value: &[u8]) -> &mut Self {{\n",
-                field.setter_name
-            ));
-            output.push_str(&format!(
-                "        let copy_len = value.len().min({});\n",
-                len
-            ));
-            output.push_str(&format!(
-                "        self.buffer[self.offset + {}..self.offset + {} + copy_len]\n",
-                field_offset, field_offset
-            ));
-            output.push_str("            .copy_from_slice(&value[..copy_len]);\n");
-            output.push_str(&format!("        if copy_len < {} {{\n", len));
-            output.push_str(&format!(
-                "            self.buffer[self.offset + {} + copy_len..self.offset + {} + {}].fill(0);\n",
-                field_offset, field_offset, len
-            ));
-            output.push_str("        }\n");
-            output.push_str("        self\n");
-            output.push_str("    }\n\n");
+        let tracked = self.tracked_fields(&group.fields);
+        let setters = group
+            .fields
+            .iter()
+            .filter(|field| field.constant.is_none())
+            .map(|field| {
+                let bit = tracked.iter().position(|f| std::ptr::eq(*f, field));
+                self.generate_entry_field_setter(field, bit)
+            });
+        let words = lit(tracked.len().div_ceil(64));
+        let dirty_field = (!tracked.is_empty()).then(|| quote! { dirty: [u64; #words], });
+        let dirty_init = (!tracked.is_empty()).then(|| quote! { dirty: [0; #words], });
+        let dirty_accessors =
+            (!tracked.is_empty()).then(|| generate_dirty_accessors(tracked.len().div_ceil(64)));
+
+        // Nested group encoder accessors and var data setters
+        let group_accessors = group
+            .nested_groups
+            .iter()
+            .map(|nested| self.generate_group_encoder_accessor(nested, &path, variable));
+        let var_data_setters = group
+            .var_data
+            .iter()
+            .map(|data| generate_var_data_setter(data, variable));
+        let section_offsets =
+            generate_section_offsets(&sections, &block_end, &path, &quote! { &*self.buffer });
+        // An entry's length grows as its nested groups and var data are
+        // appended; they move the running `end` it shares with its group.
+        let (end_field, end_param, end_set, end_init) = if variable {
+            (
+                quote! { end: &'a mut usize, },
+                quote! { , end: &'a mut usize },
+                quote! { *end = offset + #group_encoder::BLOCK_LENGTH as usize; },
+                quote! { end, },
+            )
         } else {
-            let rust_type = &field.rust_type;
-            let resolved_type = self.ir.get_type(&field.type_name);
-
-            match resolved_type.map(|t| &t.kind) {
-                Some(TypeKind::Enum { encoding, .. }) => {
-                    let write_method = get_write_method(Some(*encoding));
-                    let prim_type = encoding.rust_type();
-                    output.push_str(&format!(
-                        "    pub fn {}(&mut self, value: {}) -> &mut Self {{\n",
-                        field.setter_name, rust_type
-                    ));
-                    output.push_str(&format!(
-                        "        self.buffer.{}(self.offset + {}, {}::from(value));\n",
-                        write_method, field_offset, prim_type
-                    ));
-                    output.push_str("        self\n");
-                    output.push_str("    }\n\n");
-                }
-                Some(TypeKind::Set { encoding, .. }) => {
-                    let write_method = get_write_method(Some(*encoding));
-                    output.push_str(&format!(
-                        "    pub fn {}(&mut self, value: {}) -> &mut Self {{\n",
-                        field.setter_name, rust_type
-                    ));
-                    output.push_str(&format!(
-                        "        self.buffer.{}(self.offset + {}, value.raw());\n",
-                        write_method, field_offset
-                    ));
-                    output.push_str("        self\n");
-                    output.push_str("    }\n\n");
+            Default::default()
+        };
+        let encoded_length = if variable {
+            quote! {
+                /// Returns the encoded length of this entry, including its
+                /// nested groups and var data.
+                ///
+                /// Grows as sections are appended.
+                #[must_use]
+                pub fn encoded_length(&self) -> usize {
+                    *self.end - self.offset
                 }
-                Some(TypeKind::Composite { .. }) => {
-                    output.push_str(&format!(
-                        "    pub fn {}(&mut self) -> {}Encoder<'_> {{\n",
-                        field.setter_name, rust_type
-                    ));
-                    output.push_str(&format!(
-                        "        {}Encoder::wrap(self.buffer, self.offset + {})\n",
-                        rust_type, field_offset
-                    ));
-                    output.push_str("    }\n\n");
+            }
+        } else {
+            quote! {
+                /// Returns the encoded length of this entry.
+                #[must_use]
+                pub const fn encoded_length(&self) -> usize {
+                    #group_encoder::BLOCK_LENGTH as usize
                 }
-                _ => {
-                    let write_method = get_write_method(field.primitive_type);
-                    output.push_str(&format!(
-                        "    pub fn {}(&mut self, value: {}) -> &mut Self {{\n",
-                        field.setter_name, rust_type
-                    ));
-                    output.push_str(&format!(
-                        "        self.buffer.{}(self.offset + {}, value);\n",
-                        write_method, field_offset
-                    ));
-                    output.push_str("        self\n");
-                    output.push_str("    }\n\n");
+            }
+        };
+        let encoded_size = variable.then(|| {
+            generate_encoded_size(
+                &group.nested_groups,
+                &group.var_data,
+                &quote! { #group_encoder::BLOCK_LENGTH as usize },
+                &path,
+                "Returns the encoded size of one entry with the given nested\n\
+                 group entry counts and var data lengths.",
+            )
+        });
+
+        quote! {
+            #entry_doc
+            #order_doc
+            pub struct #entry_name<'a> {
+                buffer: &'a mut [u8],
+                offset: usize,
+                #end_field
+                #dirty_field
+            }
+            impl<'a> #entry_name<'a> {
+                pub fn wrap(buffer: &'a mut [u8], offset: usize #end_param) -> Self {
+                    #end_set
+                    Self { buffer, offset, #end_init #dirty_init }
                 }
+                #encoded_length
+                #encoded_size
+                #dirty_accessors
+                #(#setters)*
+                #(#group_accessors)*
+                #(#var_data_setters)*
+                #section_offsets
             }
         }
-
-        output
     }
 
-    /// Generates a group encoder accessor on the parent message encoder.
+    /// Generates a group encoder accessor on the parent encoder.
+    ///
+    /// `path` qualifies the group encoder type (empty for nested groups).
+    /// With `track_end`, the parent is a group entry whose running `end` the
+    /// group moves as it is written.
     fn generate_group_encoder_accessor(
         &self,
         group: &ResolvedGroup,
-        offset: usize,
-        msg_name: &str,
-    ) -> String {
-        let mut output = String::new();
-        let qualified = format!("{}::{}", to_snake_case(msg_name), group.encoder_name());
-
-        output.push_str(&format!(
-            "    /// Begin encoding the {} repeating group.\n",
+        path: &TokenStream,
+        track_end: bool,
+    ) -> TokenStream {
+        let accessor_doc = doc(&format!(
+            "Begin encoding the {} repeating group.\n\nAll preceding groups must already be written.",
             group.name
         ));
-        output.push_str(&format!(
-            "    pub fn {}_count(&mut self, count: u16) -> {}<'_> {{\n",
-            to_snake_case(&group.name),
-            qualified
-        ));
-        output.push_str(&format!(
-            "        {}::wrap(&mut *self.buffer, self.offset + MessageHeader::ENCODED_LENGTH + {}, count)\n",
-            qualified, offset
-        ));
-        output.push_str("    }\n\n");
+        let name = to_snake_case(&group.name);
+        let accessor = format_ident!("{}_count", name);
+        let offset_fn = format_ident!("{}_offset", name);
+        let encoder = ident(&group.encoder_name());
+        let count_type = count_type(group);
+        let body = if !track_end {
+            quote! { #path #encoder::wrap(&mut *self.buffer, offset, count) }
+        } else if has_variable_entries(group) {
+            quote! { #path #encoder::wrap(&mut *self.buffer, offset, count, &mut *self.end) }
+        } else {
+            quote! {
+                let group = #path #encoder::wrap(&mut *self.buffer, offset, count);
+                *self.end = offset + group.encoded_length();
+                group
+            }
+        };
 
-        output
+        quote! {
+            #accessor_doc
+            pub fn #accessor(&mut self, count: #count_type) -> #path #encoder<'_> {
+                let offset = self.#offset_fn();
+                #body
+            }
+        }
     }
 }
 
@@ -1002,6 +2421,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_encoded_size_emitted() {
+        let xml = schema_with_group_no_offsets();
+        let schema = parse_schema(&xml).expect("Failed to parse schema");
+        let ir = SchemaIr::from_schema(&schema);
+        let code = MessageGenerator::new(&ir).generate();
+
+        assert!(code.contains(
+            "pub const fn encoded_size(num_entries: &[usize], var_data_lens: &[usize]) -> usize"
+        ));
+        assert!(code.contains("let _ = var_data_lens;"));
+        assert!(code.contains("let orders_count = match num_entries {"));
+    }
+
     #[test]
     fn test_group_encoder_emitted() {
         let xml = schema_with_group_no_offsets();
@@ -1278,9 +2711,7 @@ mod tests {
 
         // encoded_length uses BLOCK_LENGTH * count
         assert!(
-            code.contains(
-                "GroupHeader::ENCODED_LENGTH + Self::BLOCK_LENGTH as usize * self.count as usize"
-            ),
+            code.contains("+ Self::BLOCK_LENGTH as usize * self.count as usize"),
             "encoded_length should use BLOCK_LENGTH * count"
         );
 
@@ -1,11 +1,140 @@
 //! Rust code generation modules.
+//!
+//! Every generator builds a [`TokenStream`] with `quote!`; `generate()`
+//! methods pretty-print it for callers that want source text.
 
+pub mod dispatch;
 pub mod enums;
+pub mod ffi;
+pub mod fuzz;
 pub mod groups;
+pub(crate) mod imports;
 pub mod messages;
+pub mod round_trip;
 pub mod types;
 
+pub use dispatch::DispatchGenerator;
 pub use enums::EnumGenerator;
+pub use ffi::FfiGenerator;
+pub use fuzz::{FuzzGenerator, FuzzHarness, FuzzTarget};
 pub use groups::GroupGenerator;
 pub use messages::MessageGenerator;
+pub use round_trip::{GoldenField, GoldenMessage, RoundTripGenerator};
 pub use types::TypeGenerator;
+
+use ironsbe_schema::ir::{ConstantValue, to_pascal_case};
+use ironsbe_schema::types::PrimitiveType;
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::{ToTokens, quote};
+
+/// Attribute deriving the `serde` traits on generated items.
+pub(crate) fn serde_derive() -> TokenStream {
+    quote! { #[derive(serde::Serialize, serde::Deserialize)] }
+}
+
+/// Doc attributes for `text`, one per line.
+///
+/// Each line is prefixed with a space so it prints as `/// line`; empty lines
+/// print as a bare `///`.
+pub(crate) fn doc(text: &str) -> TokenStream {
+    text.split('\n')
+        .map(|line| {
+            let line = if line.is_empty() {
+                String::new()
+            } else {
+                format!(" {line}")
+            };
+            quote! { #[doc = #line] }
+        })
+        .collect()
+}
+
+/// Identifier for a generated name.
+///
+/// # Panics
+/// Panics if `name` is not a valid Rust identifier.
+pub(crate) fn ident(name: &str) -> Ident {
+    Ident::new(name, Span::call_site())
+}
+
+/// Tokens for a Rust type or expression given as source text, such as a
+/// resolved field type (`[u8; 8]`) or a numeric literal with a suffix.
+///
+/// # Panics
+/// Panics if `source` does not tokenize.
+pub(crate) fn tokens(source: &str) -> TokenStream {
+    source
+        .parse()
+        .unwrap_or_else(|e| panic!("invalid generated tokens `{source}`: {e}"))
+}
+
+/// Unsuffixed integer literal, printed as `8` rather than `8usize`.
+pub(crate) fn lit(value: usize) -> Literal {
+    Literal::usize_unsuffixed(value)
+}
+
+/// Unsuffixed, possibly negative integer literal.
+pub(crate) fn signed_lit(value: i64) -> TokenStream {
+    let abs = Literal::u64_unsuffixed(value.unsigned_abs());
+    if value < 0 {
+        quote! { -#abs }
+    } else {
+        quote! { #abs }
+    }
+}
+
+/// Expression for a schema constant read as `prim`, or as a byte string if
+/// `is_array`.
+///
+/// Enum constants name the variant. A literal that is not valid for `prim`
+/// becomes a `compile_error!` so the schema problem surfaces at the include
+/// site.
+pub(crate) fn constant_expr(
+    constant: &ConstantValue,
+    prim: Option<PrimitiveType>,
+    is_array: bool,
+) -> TokenStream {
+    let value = match constant {
+        ConstantValue::Enum {
+            type_name, variant, ..
+        } => {
+            let ty = ident(&to_pascal_case(type_name));
+            let variant = ident(&to_pascal_case(variant));
+            return quote! { #ty::#variant };
+        }
+        ConstantValue::Literal(value) => value,
+    };
+    let literal = match prim {
+        _ if is_array => Some(Literal::byte_string(value.as_bytes()).into_token_stream()),
+        Some(PrimitiveType::Char) => match value.as_bytes() {
+            [byte] if byte.is_ascii() => Some(Literal::byte_character(*byte).into_token_stream()),
+            _ => None,
+        },
+        Some(p) if p.is_float() => value
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .map(|v| tokens(&format!("{v:?}{}", p.rust_type()))),
+        Some(p) => value
+            .parse::<i128>()
+            .ok()
+            .map(|v| tokens(&format!("{v}{}", p.rust_type()))),
+        None => None,
+    };
+    literal.unwrap_or_else(|| {
+        let message = format!("invalid constant value `{value}`");
+        quote! { compile_error!(#message) }
+    })
+}
+
+/// Pretty-prints generated items.
+///
+/// Falls back to the raw token text if the tokens do not form a valid file,
+/// so a codegen bug surfaces as a compile error at the include site rather
+/// than a panic here.
+pub(crate) fn format_tokens(tokens: TokenStream) -> String {
+    match syn::parse2::<syn::File>(tokens.clone()) {
+        Ok(file) => prettyplease::unparse(&file),
+        Err(_) => tokens.to_string(),
+    }
+}
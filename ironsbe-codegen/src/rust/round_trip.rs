@@ -0,0 +1,635 @@
+//! Round-trip test and golden sample generation.
+//!
+//! Every message gets a deterministic sample: each fixed field, composite
+//! member and var data field holds a distinct value, and every repeating
+//! group is written empty. The sample's wire bytes are computed here directly
+//! from the schema layout, independently of the generated encoders, so the
+//! generated tests catch any drift between the two.
+
+use ironsbe_schema::ir::{
+    CompositeFieldInfo, EnumVariant, MessageHeaderLayout, ResolvedField, ResolvedMessage, SchemaIr,
+    SetVariant, TypeKind, to_pascal_case, to_screaming_snake_case, to_snake_case,
+};
+use ironsbe_schema::types::{MemberKind, PrimitiveType};
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::{ToTokens, format_ident, quote};
+use serde_json::Value;
+
+use crate::CodegenConfig;
+use crate::rust::messages::effective_block_length;
+use crate::rust::{doc, format_tokens, ident, signed_lit, tokens};
+
+/// Sample encoding of one message, as checked by the generated round-trip
+/// test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoldenMessage {
+    /// Message name.
+    pub name: String,
+    /// Template ID.
+    pub template_id: u16,
+    /// Encoded message, starting with its header.
+    pub bytes: Vec<u8>,
+    /// Every value in `bytes`, in wire order.
+    pub fields: Vec<GoldenField>,
+}
+
+/// One value of a golden sample and where it sits on the wire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoldenField {
+    /// Dotted schema path, such as `header.templateId`, `price.mantissa` or
+    /// `legs.numInGroup`.
+    pub path: String,
+    /// Offset in the encoded message.
+    pub offset: usize,
+    /// Encoded length in bytes.
+    pub length: usize,
+    /// SBE type name: a primitive, or the enum or set type.
+    pub type_name: String,
+    /// Sampled value: a number, an enum variant name, or the text of a
+    /// character array or var data field.
+    pub value: Value,
+}
+
+/// Generator for `#[cfg(test)]` round-trip tests and golden samples.
+pub struct RoundTripGenerator<'a> {
+    ir: &'a SchemaIr,
+    config: CodegenConfig,
+}
+
+impl<'a> RoundTripGenerator<'a> {
+    /// Creates a new round-trip generator.
+    #[must_use]
+    pub fn new(ir: &'a SchemaIr) -> Self {
+        Self::with_config(ir, CodegenConfig::default())
+    }
+
+    /// Creates a new round-trip generator testing decoders generated with
+    /// `config`.
+    #[must_use]
+    pub fn with_config(ir: &'a SchemaIr, config: CodegenConfig) -> Self {
+        Self { ir, config }
+    }
+
+    /// Generates the round-trip test module as formatted source.
+    #[must_use]
+    pub fn generate(&self) -> String {
+        format_tokens(self.generate_tokens())
+    }
+
+    /// Generates a `#[cfg(test)]` module with one round-trip test per
+    /// message.
+    ///
+    /// Each test encodes the message sample into a buffer sized by the
+    /// encoder's `encoded_size`, compares the bytes with the golden encoding,
+    /// then decodes it and compares its length and every field. Emits
+    /// nothing for a schema without messages.
+    #[must_use]
+    pub fn generate_tokens(&self) -> TokenStream {
+        if self.ir.messages.is_empty() {
+            return TokenStream::new();
+        }
+        let tests = self.ir.messages.iter().map(|msg| self.generate_test(msg));
+        quote! {
+            #[cfg(test)]
+            mod round_trip_tests {
+                use super::*;
+                #(#tests)*
+            }
+        }
+    }
+
+    /// Returns the golden sample of every message.
+    #[must_use]
+    pub fn golden_corpus(&self) -> Vec<GoldenMessage> {
+        self.ir
+            .messages
+            .iter()
+            .map(|msg| {
+                let sample = self.sample(msg);
+                GoldenMessage {
+                    name: msg.name.clone(),
+                    template_id: msg.template_id,
+                    bytes: sample.bytes,
+                    fields: sample.fields,
+                }
+            })
+            .collect()
+    }
+
+    fn generate_test(&self, msg: &ResolvedMessage) -> TokenStream {
+        let sample = self.sample(msg);
+        let snake = to_snake_case(&msg.name);
+        let test_name = format_ident!("{}_round_trip", snake);
+        let golden = format_ident!("{}_GOLDEN", to_screaming_snake_case(&msg.name));
+        let golden_doc = doc(&format!("Golden encoding of the {} sample.", msg.name));
+        // Decoders with unchecked reads check the root block in `wrap`.
+        let checked_wrap = self
+            .config
+            .unchecked_reads
+            .then(|| quote! { .expect("root block fits") });
+        let encoder = ident(&msg.encoder_name());
+        let decoder = ident(&msg.decoder_name());
+        let len = Literal::usize_unsuffixed(sample.bytes.len());
+        let bytes = sample.bytes.iter().map(|b| Literal::u8_unsuffixed(*b));
+        // A message without fields or sections is never written to.
+        let encoder_mut = (!sample.writes.is_empty()).then(|| quote! { mut });
+        let var_data_lens = sample
+            .var_data_lens
+            .iter()
+            .map(|l| Literal::usize_unsuffixed(*l));
+        let Sample { writes, checks, .. } = sample;
+
+        quote! {
+            #golden_doc
+            const #golden: [u8; #len] = [#(#bytes),*];
+
+            #[test]
+            fn #test_name() {
+                let mut buffer = [0u8; #encoder::encoded_size(&[], &[#(#var_data_lens),*])];
+                let #encoder_mut encoder = #encoder::wrap(&mut buffer, 0);
+                #(#writes)*
+                assert_eq!(encoder.encoded_length(), #golden.len());
+                assert_eq!(buffer, #golden);
+
+                let decoder = #decoder::wrap(&buffer, MessageHeader::ENCODED_LENGTH, SCHEMA_VERSION)#checked_wrap;
+                assert_eq!(decoder.encoded_length(), #golden.len());
+                #(#checks)*
+            }
+        }
+    }
+
+    /// Builds the sample of `msg`: its wire bytes, the encoder statements
+    /// writing it and the decoder assertions reading it back.
+    fn sample(&self, msg: &ResolvedMessage) -> Sample {
+        let layout = &self.ir.message_header;
+        let mut sample = Sample {
+            bytes: vec![0; layout.encoded_length + usize::from(msg.block_length)],
+            writes: Vec::new(),
+            checks: Vec::new(),
+            var_data_lens: Vec::new(),
+            fields: Vec::new(),
+            seed: 0,
+        };
+        for member in &layout.members {
+            let value = MessageHeaderLayout::member_value(&member.name, msg, self.ir);
+            let size = member.primitive_type.size();
+            put_le(&mut sample.bytes[member.offset..], size, value);
+            sample.record(
+                &format!("header.{}", member.name),
+                member.offset,
+                size,
+                member.primitive_type.sbe_name(),
+                value.into(),
+            );
+        }
+
+        for field in msg.fields.iter().filter(|f| f.constant.is_none()) {
+            self.sample_field(&mut sample, field, layout.encoded_length + field.offset);
+        }
+
+        for group in &msg.groups {
+            let count = format_ident!("{}_count", to_snake_case(&group.name));
+            let accessor = ident(&to_snake_case(&group.name));
+            let dimension = &group.dimension;
+            let mut header = vec![0u8; dimension.encoded_length];
+            let block_length = effective_block_length(group);
+            let block_length_at = dimension.block_length_offset;
+            let block_length_size = dimension.block_length_type.size();
+            put_le(
+                &mut header[block_length_at..],
+                block_length_size,
+                block_length.into(),
+            );
+            let position = sample.bytes.len();
+            let block_length_path = format!("{}.blockLength", group.name);
+            sample.record(
+                &block_length_path,
+                position + block_length_at,
+                block_length_size,
+                dimension.block_length_type.sbe_name(),
+                block_length.into(),
+            );
+            let count_path = format!("{}.numInGroup", group.name);
+            sample.record(
+                &count_path,
+                position + dimension.num_in_group_offset,
+                dimension.num_in_group_type.size(),
+                dimension.num_in_group_type.sbe_name(),
+                0.into(),
+            );
+            sample.bytes.extend_from_slice(&header);
+            sample.writes.push(quote! { encoder.#count(0); });
+            sample
+                .checks
+                .push(quote! { assert!(decoder.#accessor().is_empty()); });
+        }
+
+        for data in &msg.var_data {
+            let name = to_snake_case(&data.name);
+            let getter = ident(&name);
+            let setter = format_ident!("set_{}", name);
+            let value: Vec<u8> = (0..3 + sample.seed % 5)
+                .map(|i| b'a' + ((sample.seed + i) % 26) as u8)
+                .collect();
+            sample.seed += 1;
+            sample.var_data_lens.push(value.len());
+            let header_length = data.header_length();
+            let mut header = vec![0u8; header_length];
+            put_le(&mut header, header_length, value.len() as u64);
+            let position = sample.bytes.len();
+            let length_path = format!("{}.length", data.name);
+            sample.record(
+                &length_path,
+                position,
+                header_length,
+                data.length_type.sbe_name(),
+                value.len().into(),
+            );
+            let text = String::from_utf8_lossy(&value).into_owned();
+            let data_position = position + header_length;
+            sample.record(&data.name, data_position, value.len(), "uint8", text.into());
+            sample.bytes.extend_from_slice(&header);
+            sample.bytes.extend_from_slice(&value);
+            let value = Literal::byte_string(&value);
+            sample.writes.push(quote! { encoder.#setter(#value); });
+            sample
+                .checks
+                .push(quote! { assert_eq!(decoder.#getter(), #value); });
+        }
+
+        sample
+    }
+
+    /// Adds `field`, located at `position` in the sample bytes.
+    fn sample_field(&self, sample: &mut Sample, field: &ResolvedField, position: usize) {
+        let getter = ident(&field.getter_name);
+        let setter = ident(&field.setter_name);
+
+        if field.is_array {
+            let value: Vec<u8> = (0..field.encoded_length)
+                .map(|i| b'A' + ((sample.seed + i) % 26) as u8)
+                .collect();
+            sample.seed += 1;
+            sample.bytes[position..position + value.len()].copy_from_slice(&value);
+            let prim = field.primitive_type.unwrap_or(PrimitiveType::Char);
+            let text = String::from_utf8_lossy(&value).into_owned();
+            sample.record(
+                &field.name,
+                position,
+                value.len(),
+                prim.sbe_name(),
+                text.into(),
+            );
+            let value = Literal::byte_string(&value);
+            sample.writes.push(quote! { encoder.#setter(#value); });
+            sample
+                .checks
+                .push(quote! { assert_eq!(decoder.#getter(), #value); });
+            return;
+        }
+
+        let rust_type = tokens(&field.rust_type);
+        match self.ir.get_type(&field.type_name).map(|t| &t.kind) {
+            Some(TypeKind::Enum { encoding, variants }) if !variants.is_empty() => {
+                let variant = sample.enum_variant(
+                    &field.name,
+                    &field.type_name,
+                    *encoding,
+                    variants,
+                    position,
+                );
+                sample
+                    .writes
+                    .push(quote! { encoder.#setter(#rust_type::#variant); });
+                sample
+                    .checks
+                    .push(quote! { assert_eq!(decoder.#getter(), #rust_type::#variant); });
+            }
+            Some(TypeKind::Set { encoding, choices }) => {
+                let raw =
+                    sample.set_raw(&field.name, &field.type_name, *encoding, choices, position);
+                let raw = Literal::u64_unsuffixed(raw);
+                sample
+                    .writes
+                    .push(quote! { encoder.#setter(#rust_type::from_raw(#raw)); });
+                sample
+                    .checks
+                    .push(quote! { assert_eq!(decoder.#getter().raw(), #raw); });
+            }
+            Some(TypeKind::Composite { fields }) => self.sample_composite(
+                sample,
+                fields,
+                &field.name,
+                position,
+                &quote! { encoder.#setter() },
+                &quote! { decoder.#getter() },
+            ),
+            _ => {
+                let prim = field.primitive_type.unwrap_or(PrimitiveType::Uint8);
+                let value = sample.primitive(&field.name, prim, position);
+                sample.writes.push(quote! { encoder.#setter(#value); });
+                sample
+                    .checks
+                    .push(quote! { assert_eq!(decoder.#getter(), #value); });
+            }
+        }
+    }
+
+    /// Adds the members of a composite located at `position`, written
+    /// through the encoder expression `encoder` and read through the decoder
+    /// expression `decoder`.
+    ///
+    /// Runs of scalar members are set in one chained statement; nested
+    /// composites are written through their own encoder.
+    fn sample_composite(
+        &self,
+        sample: &mut Sample,
+        fields: &[CompositeFieldInfo],
+        path: &str,
+        position: usize,
+        encoder: &TokenStream,
+        decoder: &TokenStream,
+    ) {
+        let mut sets = Vec::new();
+        let mut written = false;
+        for member in fields.iter().filter(|m| m.constant.is_none()) {
+            let name = to_snake_case(&member.name);
+            let member_getter = ident(&name);
+            let member_setter = format_ident!("set_{}", name);
+            let path = format!("{}.{}", path, member.name);
+            let at = position + member.offset;
+            let resolved = self.ir.get_type(&member.type_name);
+            let rust_type = resolved.map(|t| tokens(&t.rust_type));
+            let value = match (resolved.map(|t| &t.kind), member.kind) {
+                (Some(TypeKind::Composite { fields }), MemberKind::Composite) => {
+                    if !sets.is_empty() {
+                        sample.writes.push(quote! { #encoder #(#sets)*; });
+                        sets.clear();
+                    }
+                    self.sample_composite(
+                        sample,
+                        fields,
+                        &path,
+                        at,
+                        &quote! { #encoder.#member_setter() },
+                        &quote! { #decoder.#member_getter() },
+                    );
+                    written = true;
+                    continue;
+                }
+                (Some(TypeKind::Enum { encoding, variants }), MemberKind::Enum)
+                    if !variants.is_empty() =>
+                {
+                    let variant =
+                        sample.enum_variant(&path, &member.type_name, *encoding, variants, at);
+                    quote! { #rust_type::#variant }
+                }
+                (Some(TypeKind::Set { encoding, choices }), MemberKind::Set) => {
+                    let raw = sample.set_raw(&path, &member.type_name, *encoding, choices, at);
+                    let raw = Literal::u64_unsuffixed(raw);
+                    quote! { #rust_type::from_raw(#raw) }
+                }
+                _ => {
+                    let prim = member.primitive_type.unwrap_or(PrimitiveType::Uint8);
+                    sample.primitive(&path, prim, at)
+                }
+            };
+            sets.push(quote! { .#member_setter(#value) });
+            sample
+                .checks
+                .push(quote! { assert_eq!(#decoder.#member_getter(), #value); });
+        }
+        if !sets.is_empty() || !written {
+            sample.writes.push(quote! { #encoder #(#sets)*; });
+        }
+    }
+}
+
+/// Message sample under construction.
+struct Sample {
+    /// Wire bytes, starting with the message header.
+    bytes: Vec<u8>,
+    /// Encoder statements, in wire order.
+    writes: Vec<TokenStream>,
+    /// Decoder assertions.
+    checks: Vec<TokenStream>,
+    /// Length of each var data value, in schema order.
+    var_data_lens: Vec<usize>,
+    /// Layout and value of everything written to `bytes`.
+    fields: Vec<GoldenField>,
+    /// Index of the next sampled value, so each one differs.
+    seed: usize,
+}
+
+impl Sample {
+    /// Records the value at `path`, occupying `length` bytes at `offset`.
+    fn record(&mut self, path: &str, offset: usize, length: usize, type_name: &str, value: Value) {
+        self.fields.push(GoldenField {
+            path: path.to_string(),
+            offset,
+            length,
+            type_name: type_name.to_string(),
+            value,
+        });
+    }
+
+    /// Writes the next variant of an enum at `position`, records it under
+    /// `path` and returns the variant's identifier.
+    fn enum_variant(
+        &mut self,
+        path: &str,
+        type_name: &str,
+        encoding: PrimitiveType,
+        variants: &[EnumVariant],
+        position: usize,
+    ) -> Ident {
+        let variant = &variants[self.seed % variants.len()];
+        self.seed += 1;
+        put_le(
+            &mut self.bytes[position..],
+            encoding.size(),
+            variant.value as u64,
+        );
+        let name = Value::from(variant.name.as_str());
+        self.record(path, position, encoding.size(), type_name, name);
+        ident(&to_pascal_case(&variant.name))
+    }
+
+    /// Writes a set with every choice on at `position`, records it under
+    /// `path` and returns its raw value.
+    fn set_raw(
+        &mut self,
+        path: &str,
+        type_name: &str,
+        encoding: PrimitiveType,
+        choices: &[SetVariant],
+        position: usize,
+    ) -> u64 {
+        let raw = choices
+            .iter()
+            .fold(0u64, |raw, choice| raw | 1 << choice.bit_position);
+        put_le(&mut self.bytes[position..], encoding.size(), raw);
+        self.record(path, position, encoding.size(), type_name, raw.into());
+        raw
+    }
+
+    /// Writes the next sample value of type `prim` at `position`, records it
+    /// under `path` and returns its literal.
+    ///
+    /// Integers use a byte pattern that differs in every byte, so a value
+    /// written at the wrong offset or width shows up in the golden bytes;
+    /// signed values are negative once the pattern sets the sign bit.
+    fn primitive(&mut self, path: &str, prim: PrimitiveType, position: usize) -> TokenStream {
+        let seed = self.seed;
+        self.seed += 1;
+        let size = prim.size();
+        let bytes = &mut self.bytes[position..position + size];
+
+        let (literal, value) = match prim {
+            PrimitiveType::Float => {
+                let value = seed as f32 + 0.5;
+                bytes.copy_from_slice(&value.to_le_bytes());
+                (
+                    Literal::f32_unsuffixed(value).into_token_stream(),
+                    value.into(),
+                )
+            }
+            PrimitiveType::Double => {
+                let value = seed as f64 + 0.25;
+                bytes.copy_from_slice(&value.to_le_bytes());
+                (
+                    Literal::f64_unsuffixed(value).into_token_stream(),
+                    value.into(),
+                )
+            }
+            _ => {
+                for (i, byte) in bytes.iter_mut().enumerate() {
+                    *byte = (((seed * 0x20) + 0x11 + i) & 0xff) as u8;
+                }
+                let mut raw = [0u8; 8];
+                raw[..size].copy_from_slice(bytes);
+                let value = u64::from_le_bytes(raw);
+                if prim.is_signed() {
+                    let shift = 64 - 8 * size as u32;
+                    let value = ((value << shift) as i64) >> shift;
+                    (signed_lit(value), value.into())
+                } else {
+                    (
+                        Literal::u64_unsuffixed(value).into_token_stream(),
+                        value.into(),
+                    )
+                }
+            }
+        };
+        self.record(path, position, size, prim.sbe_name(), value);
+        literal
+    }
+}
+
+/// Writes the low `size` bytes of `value` little-endian at the start of
+/// `bytes`.
+fn put_le(bytes: &mut [u8], size: usize, value: u64) {
+    bytes[..size].copy_from_slice(&value.to_le_bytes()[..size]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironsbe_schema::parse_schema;
+
+    fn ir() -> SchemaIr {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="3" version="2" byteOrder="littleEndian">
+    <types>
+        <composite name="varDataEncoding">
+            <type name="length" primitiveType="uint16"/>
+            <type name="varData" primitiveType="uint8" length="0"/>
+        </composite>
+    </types>
+    <sbe:message name="Quote" id="5" blockLength="10">
+        <field name="bid" id="1" type="int64" offset="0"/>
+        <field name="size" id="2" type="uint16" offset="8"/>
+        <group name="legs" id="3" dimensionType="groupSizeEncoding" blockLength="4">
+            <field name="ratio" id="4" type="uint32" offset="0"/>
+        </group>
+        <data name="note" id="6" type="varDataEncoding"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+        SchemaIr::from_schema(&parse_schema(xml).expect("Failed to parse"))
+    }
+
+    #[test]
+    fn test_golden_corpus_layout() {
+        let ir = ir();
+        let corpus = RoundTripGenerator::new(&ir).golden_corpus();
+        assert_eq!(corpus.len(), 1);
+        let golden = &corpus[0];
+        assert_eq!(golden.name, "Quote");
+        assert_eq!(golden.template_id, 5);
+
+        let bytes = &golden.bytes;
+        // Header: blockLength 10, template 5, schema 3, version 2.
+        assert_eq!(&bytes[..8], &[10, 0, 5, 0, 3, 0, 2, 0]);
+        // bid: first sample, every byte distinct.
+        assert_eq!(
+            &bytes[8..16],
+            &[0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18]
+        );
+        // size: second sample.
+        assert_eq!(&bytes[16..18], &[0x31, 0x32]);
+        // Empty legs group: blockLength 4, count 0.
+        assert_eq!(&bytes[18..22], &[4, 0, 0, 0]);
+        // note: length-prefixed sample bytes.
+        assert_eq!(&bytes[22..], &[5, 0, b'c', b'd', b'e', b'f', b'g']);
+
+        let layout: Vec<_> = golden
+            .fields
+            .iter()
+            .map(|f| (f.path.as_str(), f.offset, f.length))
+            .collect();
+        assert_eq!(
+            &layout[4..],
+            &[
+                ("bid", 8, 8),
+                ("size", 16, 2),
+                ("legs.blockLength", 18, 2),
+                ("legs.numInGroup", 20, 2),
+                ("note.length", 22, 2),
+                ("note", 24, 5),
+            ]
+        );
+        assert_eq!(golden.fields[5].value, 0x3231);
+    }
+
+    #[test]
+    fn test_generate_round_trip_test() {
+        let ir = ir();
+        let code = RoundTripGenerator::new(&ir).generate();
+
+        assert!(code.contains("#[cfg(test)]\nmod round_trip_tests {"));
+        assert!(code.contains("const QUOTE_GOLDEN: [u8; 29] = ["));
+        assert!(code.contains("fn quote_round_trip() {"));
+        assert!(code.contains("encoder.legs_count(0);"));
+        assert!(code.contains("assert!(decoder.legs().is_empty());"));
+        assert!(code.contains("assert_eq!(decoder.note(), b\"cdefg\");"));
+    }
+
+    #[test]
+    fn test_generate_nothing_without_messages() {
+        let ir = SchemaIr::from_schema(
+            &parse_schema(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <type name="uint64" primitiveType="uint64"/>
+    </types>
+</sbe:messageSchema>"#,
+            )
+            .expect("Failed to parse"),
+        );
+        assert!(RoundTripGenerator::new(&ir).generate_tokens().is_empty());
+        assert!(RoundTripGenerator::new(&ir).golden_corpus().is_empty());
+    }
+}
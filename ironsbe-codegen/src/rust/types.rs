@@ -1,40 +1,62 @@
 //! Type code generation.
 
-use ironsbe_schema::ir::{CompositeFieldInfo, SchemaIr, TypeKind, to_pascal_case, to_snake_case};
-use ironsbe_schema::types::PrimitiveType;
+use ironsbe_schema::ir::{
+    CompositeFieldInfo, ConstantValue, SchemaIr, TypeKind, to_pascal_case, to_snake_case,
+};
+use ironsbe_schema::types::{MemberKind, PrimitiveType};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use crate::config::CodegenConfig;
+use crate::rust::{constant_expr, doc, format_tokens, ident, lit, serde_derive};
 
 /// Generator for type definitions.
 pub struct TypeGenerator<'a> {
     ir: &'a SchemaIr,
+    config: CodegenConfig,
 }
 
 impl<'a> TypeGenerator<'a> {
     /// Creates a new type generator.
     #[must_use]
     pub fn new(ir: &'a SchemaIr) -> Self {
-        Self { ir }
+        Self::with_config(ir, CodegenConfig::default())
     }
 
-    /// Generates all type definitions.
+    /// Creates a new type generator with custom options.
+    #[must_use]
+    pub fn with_config(ir: &'a SchemaIr, config: CodegenConfig) -> Self {
+        Self { ir, config }
+    }
+
+    /// Generates all type definitions as formatted source.
     #[must_use]
     pub fn generate(&self) -> String {
-        let mut output = String::new();
+        format_tokens(self.generate_tokens())
+    }
 
-        for resolved_type in self.ir.types.values() {
-            if let TypeKind::Composite { fields } = &resolved_type.kind {
-                // Skip messageHeader - it's provided by ironsbe_core::header::MessageHeader
-                if resolved_type.name.eq_ignore_ascii_case("messageHeader") {
-                    continue;
+    /// Generates all type definitions.
+    #[must_use]
+    pub fn generate_tokens(&self) -> TokenStream {
+        self.ir
+            .sorted_types()
+            .into_iter()
+            .filter_map(|resolved_type| match &resolved_type.kind {
+                // Skip the message header: it is ironsbe_core's MessageHeader,
+                // or the struct generated with the messages for an extended one
+                TypeKind::Composite { fields }
+                    if !resolved_type.name.eq_ignore_ascii_case("messageHeader")
+                        && resolved_type.name != self.ir.message_header.type_name =>
+                {
+                    Some(self.generate_composite(
+                        &resolved_type.name,
+                        fields,
+                        resolved_type.encoded_length,
+                    ))
                 }
-                output.push_str(&self.generate_composite(
-                    &resolved_type.name,
-                    fields,
-                    resolved_type.encoded_length,
-                ));
-            }
-        }
-
-        output
+                _ => None,
+            })
+            .collect()
     }
 
     /// Generates a composite type struct with zero-copy decoder and encoder.
@@ -43,105 +65,280 @@ impl<'a> TypeGenerator<'a> {
         name: &str,
         fields: &[CompositeFieldInfo],
         encoded_length: usize,
-    ) -> String {
-        let mut output = String::new();
+    ) -> TokenStream {
         let struct_name = to_pascal_case(name);
+        let decoder = ident(&struct_name);
+        let encoder = format_ident!("{}Encoder", struct_name);
+        let owned = format_ident!("{}Owned", struct_name);
+        let encoded_length = lit(encoded_length);
+        let decoder_doc = doc(&format!("{} Decoder (zero-copy).", struct_name));
+        let encoder_doc = doc(&format!("{} Encoder.", struct_name));
+        let length_doc = doc(&format!("Encoded length of {} in bytes.", struct_name));
 
-        // Generate decoder struct
-        output.push_str(&format!("/// {} Decoder (zero-copy).\n", struct_name));
-        output.push_str("#[derive(Debug, Clone, Copy)]\n");
-        output.push_str(&format!("pub struct {}<'a> {{\n", struct_name));
-        output.push_str("    buffer: &'a [u8],\n");
-        output.push_str("    offset: usize,\n");
-        output.push_str("}\n\n");
-
-        output.push_str(&format!("impl<'a> {}<'a> {{\n", struct_name));
-        output.push_str(&format!(
-            "    /// Encoded length of {} in bytes.\n",
-            struct_name
-        ));
-        output.push_str(&format!(
-            "    pub const ENCODED_LENGTH: usize = {};\n\n",
-            encoded_length
-        ));
+        // Field getters
+        let getters = fields.iter().map(|field| {
+            let getter_doc = doc(&format!("Gets the {} field.", field.name));
+            let field_name = ident(&to_snake_case(&field.name));
+            let rust_type = self.member_type(field);
+            if let Some(constant) = &field.constant {
+                let value = constant_expr(constant, field.primitive_type, false);
+                return quote! {
+                    #getter_doc
+                    #[inline(always)]
+                    #[must_use]
+                    pub fn #field_name(&self) -> #rust_type {
+                        #value
+                    }
+                };
+            }
+            let offset = lit(field.offset);
+            let Some(prim) = field.primitive_type else {
+                // Nested composite - return its decoder
+                return quote! {
+                    #getter_doc
+                    #[inline(always)]
+                    #[must_use]
+                    pub fn #field_name(&self) -> #rust_type<'a> {
+                        #rust_type::wrap(self.buffer, self.offset + #offset)
+                    }
+                };
+            };
+            let read = ident(get_read_method(prim));
+            let read = quote! { self.buffer.#read(self.offset + #offset) };
+            let value = match field.kind {
+                MemberKind::Enum => quote! { #rust_type::from(#read) },
+                MemberKind::Set => quote! { #rust_type::from_raw(#read) },
+                _ => read,
+            };
+            quote! {
+                #getter_doc
+                #[inline(always)]
+                #[must_use]
+                pub fn #field_name(&self) -> #rust_type {
+                    #value
+                }
+            }
+        });
 
-        // Constructor
-        output.push_str("    /// Wraps a buffer for zero-copy decoding.\n");
-        output.push_str("    #[inline]\n");
-        output.push_str("    #[must_use]\n");
-        output.push_str("    pub fn wrap(buffer: &'a [u8], offset: usize) -> Self {\n");
-        output.push_str("        Self { buffer, offset }\n");
-        output.push_str("    }\n\n");
+        let to_owned = self.config.owned_types().then(|| {
+            let to_owned_doc = doc(&format!(
+                "Copies every field into an owned [`{}Owned`].",
+                struct_name
+            ));
+            let copies = fields.iter().map(|field| {
+                let field_name = ident(&to_snake_case(&field.name));
+                match field.kind {
+                    MemberKind::Composite => quote! { #field_name: self.#field_name().to_owned(), },
+                    _ => quote! { #field_name: self.#field_name(), },
+                }
+            });
+            quote! {
+                #to_owned_doc
+                #[must_use]
+                pub fn to_owned(&self) -> #owned {
+                    #owned {
+                        #(#copies)*
+                    }
+                }
+            }
+        });
 
-        // Field getters
-        for field in fields {
+        let debug_fields = fields.iter().map(|field| {
             let field_name = to_snake_case(&field.name);
-            let rust_type = field.primitive_type.rust_type();
-            let read_method = get_read_method(field.primitive_type);
-
-            output.push_str(&format!("    /// Gets the {} field.\n", field.name));
-            output.push_str("    #[inline(always)]\n");
-            output.push_str("    #[must_use]\n");
-            output.push_str(&format!(
-                "    pub fn {}(&self) -> {} {{\n",
-                field_name, rust_type
-            ));
-            output.push_str(&format!(
-                "        self.buffer.{}(self.offset + {})\n",
-                read_method, field.offset
-            ));
-            output.push_str("    }\n\n");
+            let getter = ident(&field_name);
+            quote! { .field(#field_name, &self.#getter()) }
+        });
+
+        let owned_struct = self
+            .config
+            .owned_types()
+            .then(|| self.generate_composite_owned(&struct_name, fields));
+
+        // Field setters; constants are not encoded
+        let setters = fields.iter().filter(|f| f.constant.is_none()).map(|field| {
+            let setter_doc = doc(&format!("Sets the {} field.", field.name));
+            let setter = format_ident!("set_{}", to_snake_case(&field.name));
+            let rust_type = self.member_type(field);
+            let offset = lit(field.offset);
+            let Some(prim) = field.primitive_type else {
+                // Nested composite - return its encoder
+                let encoder = format_ident!("{}Encoder", rust_type);
+                return quote! {
+                    #setter_doc
+                    #[inline(always)]
+                    pub fn #setter(&mut self) -> #encoder<'_> {
+                        #encoder::wrap(self.buffer, self.offset + #offset)
+                    }
+                };
+            };
+            let write_method = ident(get_write_method(prim));
+            let value = match field.kind {
+                MemberKind::Enum => {
+                    let prim_type = ident(prim.rust_type());
+                    quote! { #prim_type::from(value) }
+                }
+                MemberKind::Set => quote! { value.raw() },
+                _ => quote! { value },
+            };
+            quote! {
+                #setter_doc
+                #[inline(always)]
+                pub fn #setter(&mut self, value: #rust_type) -> &mut Self {
+                    self.buffer.#write_method(self.offset + #offset, #value);
+                    self
+                }
+            }
+        });
+
+        quote! {
+            #decoder_doc
+            #[derive(Clone, Copy)]
+            pub struct #decoder<'a> {
+                buffer: &'a [u8],
+                offset: usize,
+            }
+            impl<'a> #decoder<'a> {
+                #length_doc
+                pub const ENCODED_LENGTH: usize = #encoded_length;
+                /// Wraps a buffer for zero-copy decoding.
+                #[inline]
+                #[must_use]
+                pub fn wrap(buffer: &'a [u8], offset: usize) -> Self {
+                    Self { buffer, offset }
+                }
+                /// Wraps a buffer, returning `None` if it is too short to hold
+                /// the composite at the given offset.
+                #[inline]
+                #[must_use]
+                pub fn try_wrap(buffer: &'a [u8], offset: usize) -> Option<Self> {
+                    (buffer.len().saturating_sub(offset) >= Self::ENCODED_LENGTH)
+                        .then(|| Self::wrap(buffer, offset))
+                }
+                #(#getters)*
+                #to_owned
+            }
+            impl core::fmt::Debug for #decoder<'_> {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    f.debug_struct(#struct_name)
+                        #(#debug_fields)*
+                        .finish()
+                }
+            }
+            #owned_struct
+            #encoder_doc
+            pub struct #encoder<'a> {
+                buffer: &'a mut [u8],
+                offset: usize,
+            }
+            impl<'a> #encoder<'a> {
+                #length_doc
+                pub const ENCODED_LENGTH: usize = #encoded_length;
+                /// Wraps a buffer for encoding.
+                #[inline]
+                pub fn wrap(buffer: &'a mut [u8], offset: usize) -> Self {
+                    Self { buffer, offset }
+                }
+                /// Wraps a buffer, returning `None` if it is too short to hold
+                /// the composite at the given offset.
+                #[inline]
+                #[must_use]
+                pub fn try_wrap(buffer: &'a mut [u8], offset: usize) -> Option<Self> {
+                    (buffer.len().saturating_sub(offset) >= Self::ENCODED_LENGTH)
+                        .then(|| Self::wrap(buffer, offset))
+                }
+                #(#setters)*
+            }
         }
+    }
 
-        output.push_str("}\n\n");
+    /// Returns the type of a composite member: the referenced enum for enum
+    /// constants, the enum, set or composite type of those members, and the
+    /// primitive type otherwise.
+    fn member_type(&self, field: &CompositeFieldInfo) -> Ident {
+        if let Some(ConstantValue::Enum { type_name, .. }) = &field.constant {
+            return ident(&to_pascal_case(type_name));
+        }
+        match (field.kind, field.primitive_type) {
+            (MemberKind::Primitive, Some(prim)) => ident(prim.rust_type()),
+            _ => ident(
+                &self
+                    .ir
+                    .get_type(&field.type_name)
+                    .map_or_else(|| to_pascal_case(&field.type_name), |t| t.rust_type.clone()),
+            ),
+        }
+    }
 
-        // Generate encoder struct
-        output.push_str(&format!("/// {} Encoder.\n", struct_name));
-        output.push_str(&format!("pub struct {}Encoder<'a> {{\n", struct_name));
-        output.push_str("    buffer: &'a mut [u8],\n");
-        output.push_str("    offset: usize,\n");
-        output.push_str("}\n\n");
+    /// Generates the owned, serde-enabled mirror of a composite.
+    fn generate_composite_owned(
+        &self,
+        struct_name: &str,
+        fields: &[CompositeFieldInfo],
+    ) -> TokenStream {
+        let owned_doc = doc(&format!("Owned copy of a decoded {}.", struct_name));
+        let owned = format_ident!("{}Owned", struct_name);
+        let serde = serde_derive();
+        let owned_fields = fields.iter().map(|field| {
+            let field_doc = doc(&format!("{} field.", field.name));
+            let field_name = ident(&to_snake_case(&field.name));
+            let rust_type = match field.kind {
+                MemberKind::Composite => format_ident!("{}Owned", self.member_type(field)),
+                _ => self.member_type(field),
+            };
+            quote! {
+                #field_doc
+                pub #field_name: #rust_type,
+            }
+        });
 
-        output.push_str(&format!("impl<'a> {}Encoder<'a> {{\n", struct_name));
-        output.push_str(&format!(
-            "    /// Encoded length of {} in bytes.\n",
+        // Constants are not encoded
+        let writes: Vec<_> = fields
+            .iter()
+            .filter(|f| f.constant.is_none())
+            .map(|field| {
+                let field_name = ident(&to_snake_case(&field.name));
+                let offset = lit(field.offset);
+                let Some(prim) = field.primitive_type else {
+                    return quote! { self.#field_name.encode(buffer, offset + #offset); };
+                };
+                let write_method = ident(get_write_method(prim));
+                let value = match field.kind {
+                    MemberKind::Enum => {
+                        let prim_type = ident(prim.rust_type());
+                        quote! { #prim_type::from(self.#field_name) }
+                    }
+                    MemberKind::Set => quote! { self.#field_name.raw() },
+                    _ => quote! { self.#field_name },
+                };
+                quote! { buffer.#write_method(offset + #offset, #value); }
+            })
+            .collect();
+        // An all-constant composite writes nothing
+        let (buffer, offset) = if writes.is_empty() {
+            (format_ident!("_buffer"), format_ident!("_offset"))
+        } else {
+            (format_ident!("buffer"), format_ident!("offset"))
+        };
+        let encode_doc = doc(&format!(
+            "Encodes the {} to the buffer at the given offset.",
             struct_name
         ));
-        output.push_str(&format!(
-            "    pub const ENCODED_LENGTH: usize = {};\n\n",
-            encoded_length
-        ));
-
-        // Constructor
-        output.push_str("    /// Wraps a buffer for encoding.\n");
-        output.push_str("    #[inline]\n");
-        output.push_str("    pub fn wrap(buffer: &'a mut [u8], offset: usize) -> Self {\n");
-        output.push_str("        Self { buffer, offset }\n");
-        output.push_str("    }\n\n");
 
-        // Field setters
-        for field in fields {
-            let field_name = to_snake_case(&field.name);
-            let rust_type = field.primitive_type.rust_type();
-            let write_method = get_write_method(field.primitive_type);
-
-            output.push_str(&format!("    /// Sets the {} field.\n", field.name));
-            output.push_str("    #[inline(always)]\n");
-            output.push_str(&format!(
-                "    pub fn set_{}(&mut self, value: {}) -> &mut Self {{\n",
-                field_name, rust_type
-            ));
-            output.push_str(&format!(
-                "        self.buffer.{}(self.offset + {}, value);\n",
-                write_method, field.offset
-            ));
-            output.push_str("        self\n");
-            output.push_str("    }\n\n");
+        quote! {
+            #owned_doc
+            #[derive(Debug, Clone, PartialEq)]
+            #serde
+            pub struct #owned {
+                #(#owned_fields)*
+            }
+            impl #owned {
+                #encode_doc
+                #[inline]
+                pub fn encode<B: WriteBuffer + ?Sized>(&self, #buffer: &mut B, #offset: usize) {
+                    #(#writes)*
+                }
+            }
         }
-
-        output.push_str("}\n\n");
-
-        output
     }
 }
 
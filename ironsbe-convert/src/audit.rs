@@ -0,0 +1,381 @@
+//! Sequence reconciliation between the two sides of a session.
+//!
+//! After an incident the question is usually which messages the peer
+//! actually got. Given the frames one side sent and the frames the other
+//! side received, in journal order, [`SequenceAudit`] reads the sequence
+//! number of every message from a schema field, and [`AuditReport`] lists
+//! the gaps, duplicates and reorderings seen on each side, the messages that
+//! were lost or arrived without being sent, and those whose bytes differ.
+//!
+//! Tap captures written by `ironsbe_core::TapWriter` are one source of
+//! journals: parse each line with `TapRecord::parse` and keep the outbound
+//! frames of the session on the sender and the inbound ones on the
+//! receiver.
+
+use ironsbe_schema::{DynamicMessage, SchemaIr};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Reads sequence numbers from a session's messages and reconciles journals.
+#[derive(Debug, Clone)]
+pub struct SequenceAudit<'a> {
+    ir: &'a SchemaIr,
+    field: String,
+}
+
+impl<'a> SequenceAudit<'a> {
+    /// Creates an audit reading sequence numbers from the root field
+    /// `field` (e.g. `"seqNum"`) of messages of `ir`.
+    #[must_use]
+    pub fn new(ir: &'a SchemaIr, field: impl Into<String>) -> Self {
+        Self {
+            ir,
+            field: field.into(),
+        }
+    }
+
+    /// Returns the sequence number of `frame`.
+    ///
+    /// Returns `None` if the frame does not decode, or its message has no
+    /// unsigned sequence field or holds its null value there.
+    #[must_use]
+    pub fn sequence(&self, frame: &[u8]) -> Option<u64> {
+        self.classify(frame).ok().flatten()
+    }
+
+    /// Reconciles the frames one side `sent` against the frames the other
+    /// side `received`, both in journal order.
+    pub fn reconcile<'f>(
+        &self,
+        sent: impl IntoIterator<Item = &'f [u8]>,
+        received: impl IntoIterator<Item = &'f [u8]>,
+    ) -> AuditReport {
+        let (sent, sent_frames) = self.side(sent);
+        let (received, received_frames) = self.side(received);
+
+        // Sent messages past the last received one may still have been in
+        // flight when the journals were taken.
+        let horizon = received.last.unwrap_or(0);
+        let lost = ranges(
+            sent_frames
+                .keys()
+                .copied()
+                .filter(|seq| *seq <= horizon && !received_frames.contains_key(seq)),
+        );
+        let unacknowledged = ranges(sent_frames.keys().copied().filter(|seq| *seq > horizon));
+        let unexpected = received_frames
+            .keys()
+            .copied()
+            .filter(|seq| !sent_frames.contains_key(seq))
+            .collect();
+        let mismatched = received_frames
+            .iter()
+            .filter(|(seq, bytes)| sent_frames.get(seq).is_some_and(|sent| sent != *bytes))
+            .map(|(seq, _)| *seq)
+            .collect();
+
+        AuditReport {
+            sent,
+            received,
+            lost,
+            unacknowledged,
+            unexpected,
+            mismatched,
+        }
+    }
+
+    /// Summarizes one journal and maps each sequence number to the first
+    /// frame that carried it.
+    fn side<'f>(
+        &self,
+        frames: impl IntoIterator<Item = &'f [u8]>,
+    ) -> (JournalSummary, BTreeMap<u64, &'f [u8]>) {
+        let mut summary = JournalSummary::default();
+        let mut by_seq = BTreeMap::new();
+        let mut duplicates = Vec::new();
+        for frame in frames {
+            summary.frames += 1;
+            let seq = match self.classify(frame) {
+                Ok(Some(seq)) => seq,
+                Ok(None) => {
+                    summary.unsequenced += 1;
+                    continue;
+                }
+                Err(()) => {
+                    summary.undecodable += 1;
+                    continue;
+                }
+            };
+            if by_seq.contains_key(&seq) {
+                duplicates.push(seq);
+                continue;
+            }
+            if summary.last.is_some_and(|last| seq < last) {
+                summary.out_of_order.push(seq);
+            }
+            summary.last = summary.last.max(Some(seq));
+            by_seq.insert(seq, frame);
+        }
+
+        summary.first = by_seq.keys().next().copied();
+        // From neighbouring keys, so that a far-off sequence number costs no
+        // more than any other.
+        summary.gaps = by_seq
+            .keys()
+            .zip(by_seq.keys().skip(1))
+            .filter(|&(prev, next)| *next > prev + 1)
+            .map(|(prev, next)| prev + 1..=next - 1)
+            .collect();
+        duplicates.sort_unstable();
+        duplicates.dedup();
+        summary.duplicates = duplicates;
+        (summary, by_seq)
+    }
+
+    /// Decodes the sequence number of a frame, or `Err` if the frame does
+    /// not decode.
+    fn classify(&self, frame: &[u8]) -> Result<Option<u64>, ()> {
+        let message = DynamicMessage::decode(self.ir, frame).map_err(|_| ())?;
+        Ok(message
+            .field(&self.field)
+            .filter(|value| !value.is_null())
+            .and_then(|value| value.as_u64()))
+    }
+}
+
+/// Sequencing of one side's journal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JournalSummary {
+    /// Frames in the journal.
+    pub frames: usize,
+    /// Frames of messages without a sequence number, such as heartbeats.
+    pub unsequenced: usize,
+    /// Frames that do not decode against the schema.
+    pub undecodable: usize,
+    /// Lowest sequence number.
+    pub first: Option<u64>,
+    /// Highest sequence number.
+    pub last: Option<u64>,
+    /// Sequence numbers missing between the first and the last.
+    pub gaps: Vec<RangeInclusive<u64>>,
+    /// Sequence numbers seen more than once.
+    pub duplicates: Vec<u64>,
+    /// Sequence numbers seen after a higher one, in journal order.
+    pub out_of_order: Vec<u64>,
+}
+
+/// Result of [`SequenceAudit::reconcile`].
+///
+/// Its [`Display`](fmt::Display) output is the reconciliation report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditReport {
+    /// The sender's journal.
+    pub sent: JournalSummary,
+    /// The receiver's journal.
+    pub received: JournalSummary,
+    /// Sent and never received, up to the last received sequence number.
+    pub lost: Vec<RangeInclusive<u64>>,
+    /// Sent after the last received sequence number: in flight when the
+    /// journals were taken, or lost at disconnect.
+    pub unacknowledged: Vec<RangeInclusive<u64>>,
+    /// Received but never sent.
+    pub unexpected: Vec<u64>,
+    /// Received with different bytes than were sent.
+    pub mismatched: Vec<u64>,
+}
+
+impl AuditReport {
+    /// Returns true if both journals are continuous and every sent message
+    /// arrived once, unchanged and in order.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        [&self.sent, &self.received].iter().all(|side| {
+            side.undecodable == 0
+                && side.gaps.is_empty()
+                && side.duplicates.is_empty()
+                && side.out_of_order.is_empty()
+        }) && self.lost.is_empty()
+            && self.unacknowledged.is_empty()
+            && self.unexpected.is_empty()
+            && self.mismatched.is_empty()
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, side) in [("sent", &self.sent), ("received", &self.received)] {
+            write!(f, "{name}: {} frames", side.frames)?;
+            if let (Some(first), Some(last)) = (side.first, side.last) {
+                write!(f, ", seq {first}..={last}")?;
+            }
+            writeln!(
+                f,
+                ", {} unsequenced, {} undecodable",
+                side.unsequenced, side.undecodable
+            )?;
+            writeln!(f, "  gaps: {}", List(&side.gaps))?;
+            writeln!(f, "  duplicates: {}", List(&side.duplicates))?;
+            writeln!(f, "  out of order: {}", List(&side.out_of_order))?;
+        }
+        writeln!(f, "lost: {}", List(&self.lost))?;
+        writeln!(f, "unacknowledged: {}", List(&self.unacknowledged))?;
+        writeln!(f, "unexpected: {}", List(&self.unexpected))?;
+        writeln!(f, "mismatched: {}", List(&self.mismatched))?;
+        write!(
+            f,
+            "result: {}",
+            if self.is_clean() {
+                "clean"
+            } else {
+                "DISCREPANCIES"
+            }
+        )
+    }
+}
+
+/// Comma-separated list, or `none`.
+struct List<'a, T>(&'a [T]);
+
+impl<T: fmt::Debug> fmt::Display for List<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("none");
+        }
+        for (i, item) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{item:?}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Collapses ascending sequence numbers into inclusive ranges.
+fn ranges(seqs: impl IntoIterator<Item = u64>) -> Vec<RangeInclusive<u64>> {
+    let mut out: Vec<RangeInclusive<u64>> = Vec::new();
+    for seq in seqs {
+        match out.last_mut() {
+            Some(range) if *range.end() + 1 == seq => *range = *range.start()..=seq,
+            _ => out.push(seq..=seq),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::from_json;
+    use ironsbe_schema::parse_schema;
+    use serde_json::json;
+
+    const SCHEMA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="audit" id="4" version="1" byteOrder="littleEndian">
+    <types/>
+    <sbe:message name="Exec" id="1" blockLength="12">
+        <field name="seqNum" id="1" type="uint64" offset="0"/>
+        <field name="qty" id="2" type="uint32" offset="8"/>
+    </sbe:message>
+    <sbe:message name="Heartbeat" id="2" blockLength="8">
+        <field name="sentAt" id="3" type="uint64" offset="0"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+    fn ir() -> SchemaIr {
+        SchemaIr::from_schema(&parse_schema(SCHEMA).unwrap())
+    }
+
+    fn exec(ir: &SchemaIr, seq: u64, qty: u32) -> Vec<u8> {
+        let mut buf = [0u8; 64];
+        let len = from_json(&json!({"Exec": {"seqNum": seq, "qty": qty}}), ir, &mut buf).unwrap();
+        buf[..len].to_vec()
+    }
+
+    fn heartbeat(ir: &SchemaIr) -> Vec<u8> {
+        let mut buf = [0u8; 64];
+        let len = from_json(&json!({"Heartbeat": {"sentAt": 5}}), ir, &mut buf).unwrap();
+        buf[..len].to_vec()
+    }
+
+    #[test]
+    fn test_clean_session() {
+        let ir = ir();
+        let frames: Vec<_> = (1..=5).map(|seq| exec(&ir, seq, 1)).collect();
+        let mut received = frames.clone();
+        received.insert(2, heartbeat(&ir));
+
+        let audit = SequenceAudit::new(&ir, "seqNum");
+        assert_eq!(audit.sequence(&frames[3]), Some(4));
+        assert_eq!(audit.sequence(&received[2]), None);
+
+        let report = audit.reconcile(
+            frames.iter().map(Vec::as_slice),
+            received.iter().map(Vec::as_slice),
+        );
+        assert!(report.is_clean(), "{report}");
+        assert_eq!(report.received.unsequenced, 1);
+        assert!(report.to_string().ends_with("result: clean"));
+    }
+
+    #[test]
+    fn test_reports_discrepancies() {
+        let ir = ir();
+        let sent: Vec<_> = [1, 2, 3, 4, 5, 6, 8, 9]
+            .into_iter()
+            .map(|seq| exec(&ir, seq, 1))
+            .collect();
+        let received = [
+            exec(&ir, 1, 1),
+            exec(&ir, 3, 1),
+            exec(&ir, 2, 1),
+            exec(&ir, 3, 1),
+            exec(&ir, 6, 2),
+            exec(&ir, 7, 1),
+            b"garbage".to_vec(),
+        ];
+
+        let report = SequenceAudit::new(&ir, "seqNum").reconcile(
+            sent.iter().map(Vec::as_slice),
+            received.iter().map(Vec::as_slice),
+        );
+        assert_eq!(report.sent.gaps, vec![7..=7]);
+        assert_eq!(report.received.gaps, vec![4..=5]);
+        assert_eq!(report.received.duplicates, vec![3]);
+        assert_eq!(report.received.out_of_order, vec![2]);
+        assert_eq!(report.received.undecodable, 1);
+        assert_eq!(report.lost, vec![4..=5]);
+        assert_eq!(report.unacknowledged, vec![8..=9]);
+        assert_eq!(report.unexpected, vec![7]);
+        assert_eq!(report.mismatched, vec![6]);
+        assert!(!report.is_clean());
+
+        let text = report.to_string();
+        assert!(text.contains("received: 7 frames, seq 1..=7, 0 unsequenced, 1 undecodable"));
+        assert!(text.contains("lost: 4..=5\n"));
+        assert!(text.ends_with("result: DISCREPANCIES"));
+    }
+
+    #[test]
+    fn test_far_apart_sequence_numbers() {
+        let ir = ir();
+        let far = u64::MAX - 1;
+        let frames = [exec(&ir, 1, 1), exec(&ir, 2, 1), exec(&ir, far, 1)];
+
+        let report = SequenceAudit::new(&ir, "seqNum").reconcile(
+            frames.iter().map(Vec::as_slice),
+            frames.iter().map(Vec::as_slice),
+        );
+        assert_eq!(report.received.gaps, vec![3..=far - 1]);
+        assert_eq!(report.sent.gaps, report.received.gaps);
+        assert!(report.lost.is_empty());
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert_eq!(ranges([1, 2, 3, 5, 7, 8]), vec![1..=3, 5..=5, 7..=8]);
+        assert!(ranges([]).is_empty());
+    }
+}
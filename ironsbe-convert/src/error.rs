@@ -0,0 +1,50 @@
+//! Error types for conversion operations.
+
+use ironsbe_schema::DynamicError;
+use thiserror::Error;
+
+/// Error type for conversion operations.
+#[derive(Debug, Error)]
+pub enum ConvertError {
+    /// The SBE message could not be decoded.
+    #[error("decode error: {0}")]
+    Decode(#[from] DynamicError),
+
+    /// The JSON document is not an object with a single message name key.
+    #[error("expected an object with a single key naming the message")]
+    InvalidDocument,
+
+    /// The schema has no message with this name.
+    #[error("unknown message '{0}'")]
+    UnknownMessage(String),
+
+    /// The JSON has a key the schema does not define.
+    #[error("unknown field '{0}'")]
+    UnknownField(String),
+
+    /// A required field is missing from the JSON.
+    #[error("missing required field '{0}'")]
+    MissingField(String),
+
+    /// A JSON value does not fit the field's type.
+    #[error("invalid value for '{field}': {reason}")]
+    InvalidValue {
+        /// Path of the field, e.g. `fills[1].qty`.
+        field: String,
+        /// What is wrong with the value.
+        reason: String,
+    },
+
+    /// The output buffer is too small for the message.
+    #[error("buffer too short: need {required} bytes, have {available}")]
+    BufferTooShort {
+        /// Bytes needed to reach the end of the section being written.
+        required: usize,
+        /// Bytes in the buffer.
+        available: usize,
+    },
+
+    /// The scenario script is malformed.
+    #[error("invalid scenario: {0}")]
+    InvalidScenario(String),
+}
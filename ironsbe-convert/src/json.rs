@@ -0,0 +1,769 @@
+//! JSON conversion.
+//!
+//! A message is an object with a single key, the message name, holding its
+//! fields, repeating groups and var data by name:
+//!
+//! ```json
+//! {"Order": {"orderId": 42, "symbol": "ES", "side": "Sell",
+//!            "flags": ["PostOnly"], "price": {"mantissa": 12345, "exponent": -2},
+//!            "stop": null, "fills": [{"qty": 5, "note": "a"}], "text": "hello"}}
+//! ```
+//!
+//! Values map as follows:
+//! - integers and floats are numbers, and non-finite floats `null`
+//! - a `char` is a one-character string, a `char` array a string without
+//!   its padding, a `uint8` array an array of numbers
+//! - enums are variant names, sets arrays of choice names; either is a
+//!   number when the value has no name in the schema
+//! - composites are objects and repeating groups arrays of objects
+//! - var data is a string when it is UTF-8, otherwise an array of numbers
+//! - optional fields holding their null value are `null`
+//!
+//! [`from_json`] accepts these forms, numbers for enums, sets and `char`s,
+//! and arrays of numbers for any byte field. Optional fields, groups and
+//! var data may be left out; constants are ignored.
+
+use crate::error::ConvertError;
+use ironsbe_schema::ir::{
+    MessageHeaderLayout, ResolvedField, ResolvedGroup, ResolvedVarData, TypeKind,
+};
+use ironsbe_schema::{DynamicBlock, DynamicMessage, PrimitiveType, SbeValue, SchemaIr};
+use serde_json::{Map, Number, Value};
+
+/// Converts the message at the start of `buffer` to JSON.
+///
+/// # Errors
+/// Returns [`ConvertError::Decode`] if the message does not decode against
+/// `ir`.
+pub fn to_json(buffer: &[u8], ir: &SchemaIr) -> Result<Value, ConvertError> {
+    let message = DynamicMessage::decode(ir, buffer)?;
+    let mut document = Map::new();
+    document.insert(message.name().to_string(), block_to_json(&message));
+    Ok(Value::Object(document))
+}
+
+/// Encodes the JSON message `json` into `buf`, header included.
+///
+/// Returns the encoded length.
+///
+/// # Errors
+/// Returns an error if `json` does not name a message of `ir`, has keys or
+/// values that do not fit its fields, lacks a required field, or if `buf`
+/// is too small.
+pub fn from_json(json: &Value, ir: &SchemaIr, buf: &mut [u8]) -> Result<usize, ConvertError> {
+    let (name, body) = match json.as_object() {
+        Some(document) if document.len() == 1 => document.iter().next().expect("one entry"),
+        _ => return Err(ConvertError::InvalidDocument),
+    };
+    let message = ir
+        .messages
+        .iter()
+        .find(|m| m.name == *name)
+        .ok_or_else(|| ConvertError::UnknownMessage(name.clone()))?;
+    let body = body.as_object().ok_or(ConvertError::InvalidDocument)?;
+
+    let mut encoder = Encoder { ir, buf, offset: 0 };
+    let layout = &ir.message_header;
+    let header = encoder.reserve(layout.encoded_length)?;
+    for member in &layout.members {
+        let value = MessageHeaderLayout::member_value(&member.name, message, ir);
+        let at = header + member.offset;
+        let out = &mut encoder.buf[at..at + member.primitive_type.size()];
+        put_uint(member.primitive_type, value, out);
+    }
+    encoder.block(
+        body,
+        usize::from(message.block_length),
+        &message.fields,
+        &message.groups,
+        &message.var_data,
+        "",
+    )?;
+    Ok(encoder.offset)
+}
+
+/// Converts the fields, groups and var data of a block to an object.
+fn block_to_json(block: &DynamicBlock<'_>) -> Value {
+    let mut object = Map::new();
+    for field in block.fields() {
+        object.insert(field.name.to_string(), value_to_json(&field.value));
+    }
+    for group in block.groups() {
+        let entries = group.iter().map(block_to_json).collect();
+        object.insert(group.name().to_string(), Value::Array(entries));
+    }
+    for (name, bytes) in block.var_data_fields() {
+        let value = match std::str::from_utf8(bytes) {
+            Ok(text) => Value::from(text),
+            Err(_) => byte_array(bytes),
+        };
+        object.insert(name.to_string(), value);
+    }
+    Value::Object(object)
+}
+
+/// Converts a decoded value to JSON.
+fn value_to_json(value: &SbeValue<'_>) -> Value {
+    match value {
+        SbeValue::Int(v) => Value::from(*v),
+        SbeValue::UInt(v) => Value::from(*v),
+        SbeValue::Float(v) => Number::from_f64(*v).map_or(Value::Null, Value::Number),
+        SbeValue::Char(c) => Value::from(char::from(*c).to_string()),
+        SbeValue::Chars(bytes) => value
+            .as_str()
+            .map_or_else(|| byte_array(bytes), Value::from),
+        SbeValue::Bytes(bytes) => byte_array(bytes),
+        SbeValue::Array(values) => Value::Array(values.iter().map(value_to_json).collect()),
+        SbeValue::Enum {
+            name: Some(name), ..
+        } => Value::from(*name),
+        SbeValue::Enum { name: None, value } => Value::from(*value),
+        // Choices are distinct bits, so a count mismatch means unnamed bits
+        // are set.
+        SbeValue::Set { raw, choices } if raw.count_ones() as usize == choices.len() => {
+            Value::from(choices.clone())
+        }
+        SbeValue::Set { raw, .. } => Value::from(*raw),
+        SbeValue::Composite(members) => Value::Object(
+            members
+                .iter()
+                .map(|(name, value)| (name.to_string(), value_to_json(value)))
+                .collect(),
+        ),
+        SbeValue::Null => Value::Null,
+    }
+}
+
+/// Converts bytes to an array of numbers.
+fn byte_array(bytes: &[u8]) -> Value {
+    Value::from(bytes.to_vec())
+}
+
+/// Writes a message into a buffer, section by section.
+struct Encoder<'a, 'b> {
+    ir: &'a SchemaIr,
+    buf: &'b mut [u8],
+    offset: usize,
+}
+
+impl Encoder<'_, '_> {
+    /// Zeroes the next `len` bytes and returns their offset.
+    fn reserve(&mut self, len: usize) -> Result<usize, ConvertError> {
+        let start = self.offset;
+        let end = start + len;
+        let available = self.buf.len();
+        let bytes = self
+            .buf
+            .get_mut(start..end)
+            .ok_or(ConvertError::BufferTooShort {
+                required: end,
+                available,
+            })?;
+        bytes.fill(0);
+        self.offset = end;
+        Ok(start)
+    }
+
+    /// Writes a block of `block_length` bytes and its trailing sections.
+    fn block(
+        &mut self,
+        object: &Map<String, Value>,
+        block_length: usize,
+        fields: &[ResolvedField],
+        groups: &[ResolvedGroup],
+        var_data: &[ResolvedVarData],
+        path: &str,
+    ) -> Result<(), ConvertError> {
+        if let Some(key) = object.keys().find(|key| {
+            !fields.iter().any(|f| f.name == **key)
+                && !groups.iter().any(|g| g.name == **key)
+                && !var_data.iter().any(|d| d.name == **key)
+        }) {
+            return Err(ConvertError::UnknownField(join(path, key)));
+        }
+
+        let start = self.reserve(block_length)?;
+        for field in fields.iter().filter(|f| f.constant.is_none()) {
+            let field_path = join(path, &field.name);
+            let range = start + field.offset..start + field.offset + field.encoded_length;
+            if range.end > start + block_length {
+                return Err(invalid(&field_path, "field lies past the block length"));
+            }
+            let out = &mut self.buf[range];
+            match object.get(&field.name) {
+                Some(Value::Null) | None if field.is_optional => {
+                    write_null(field, out);
+                }
+                None => return Err(ConvertError::MissingField(field_path)),
+                Some(value) => encode_value(
+                    self.ir,
+                    &field.type_name,
+                    field.primitive_type,
+                    value,
+                    out,
+                    &field_path,
+                )?,
+            }
+        }
+        for group in groups {
+            self.group(object.get(&group.name), group, &join(path, &group.name))?;
+        }
+        for data in var_data {
+            self.var_data(object.get(&data.name), data, &join(path, &data.name))?;
+        }
+        Ok(())
+    }
+
+    /// Writes a repeating group from an array of entry objects.
+    fn group(
+        &mut self,
+        value: Option<&Value>,
+        group: &ResolvedGroup,
+        path: &str,
+    ) -> Result<(), ConvertError> {
+        let entries = match value {
+            None | Some(Value::Null) => &[][..],
+            Some(Value::Array(entries)) => entries.as_slice(),
+            Some(_) => return Err(invalid(path, "expected an array of entries")),
+        };
+        let block_length = if group.block_length > 0 {
+            usize::from(group.block_length)
+        } else {
+            group
+                .fields
+                .iter()
+                .map(|f| f.offset + f.encoded_length)
+                .max()
+                .unwrap_or(0)
+        };
+
+        let dimension = &group.dimension;
+        let header = self.reserve(dimension.encoded_length)?;
+        for (prim, offset, value, what) in [
+            (
+                dimension.block_length_type,
+                dimension.block_length_offset,
+                block_length,
+                "block length",
+            ),
+            (
+                dimension.num_in_group_type,
+                dimension.num_in_group_offset,
+                entries.len(),
+                "entry count",
+            ),
+        ] {
+            let value = value as u64;
+            if value > unsigned_max(prim) {
+                return Err(invalid(path, &format!("{what} {value} overflows {prim:?}")));
+            }
+            let at = header + offset;
+            put_uint(prim, value, &mut self.buf[at..at + prim.size()]);
+        }
+
+        for (i, entry) in entries.iter().enumerate() {
+            let entry_path = format!("{path}[{i}]");
+            let object = entry
+                .as_object()
+                .ok_or_else(|| invalid(&entry_path, "expected an object"))?;
+            self.block(
+                object,
+                block_length,
+                &group.fields,
+                &group.nested_groups,
+                &group.var_data,
+                &entry_path,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes a var data field from a string or an array of numbers.
+    fn var_data(
+        &mut self,
+        value: Option<&Value>,
+        data: &ResolvedVarData,
+        path: &str,
+    ) -> Result<(), ConvertError> {
+        let bytes = match value {
+            None | Some(Value::Null) => Vec::new(),
+            Some(value) => json_bytes(value, path)?,
+        };
+        let len = bytes.len() as u64;
+        if len > unsigned_max(data.length_type) {
+            return Err(invalid(
+                path,
+                &format!("{len} bytes overflow the {:?} length", data.length_type),
+            ));
+        }
+        let prefix = self.reserve(data.header_length())?;
+        put_uint(
+            data.length_type,
+            len,
+            &mut self.buf[prefix..prefix + data.header_length()],
+        );
+        let start = self.reserve(bytes.len())?;
+        self.buf[start..start + bytes.len()].copy_from_slice(&bytes);
+        Ok(())
+    }
+}
+
+/// Encodes `value` as the type `type_name`, or as `prim` if the schema does
+/// not define it, into `out`.
+fn encode_value(
+    ir: &SchemaIr,
+    type_name: &str,
+    prim: Option<PrimitiveType>,
+    value: &Value,
+    out: &mut [u8],
+    path: &str,
+) -> Result<(), ConvertError> {
+    match ir.get_type(type_name).map(|t| &t.kind) {
+        Some(TypeKind::Enum { encoding, variants }) => {
+            let raw =
+                match value {
+                    Value::String(name) => variants
+                        .iter()
+                        .find(|v| v.name == *name)
+                        .map(|v| v.value)
+                        .ok_or_else(|| invalid(path, &format!("no variant named '{name}'")))?,
+                    _ => json_int(value, *encoding, path)?,
+                };
+            put_int(*encoding, raw, out);
+        }
+        Some(TypeKind::Set { encoding, choices }) => {
+            let raw = match value {
+                Value::Array(names) => names.iter().try_fold(0u64, |raw, name| {
+                    let choice = choices
+                        .iter()
+                        .find(|c| name.as_str() == Some(c.name.as_str()))
+                        .ok_or_else(|| invalid(path, &format!("no choice named {name}")))?;
+                    Ok::<_, ConvertError>(raw | 1 << choice.bit_position)
+                })?,
+                _ => json_uint(value, *encoding, path)?,
+            };
+            put_uint(*encoding, raw, out);
+        }
+        Some(TypeKind::Composite { fields }) => {
+            let object = value
+                .as_object()
+                .ok_or_else(|| invalid(path, "expected an object"))?;
+            if let Some(key) = object
+                .keys()
+                .find(|key| !fields.iter().any(|f| f.name == **key))
+            {
+                return Err(ConvertError::UnknownField(join(path, key)));
+            }
+            for member in fields.iter().filter(|f| f.constant.is_none()) {
+                let member_path = join(path, &member.name);
+                let value = object
+                    .get(&member.name)
+                    .ok_or_else(|| ConvertError::MissingField(member_path.clone()))?;
+                encode_value(
+                    ir,
+                    &member.type_name,
+                    member.primitive_type,
+                    value,
+                    &mut out[member.offset..member.offset + member.encoded_length],
+                    &member_path,
+                )?;
+            }
+        }
+        Some(TypeKind::Primitive(prim)) => encode_primitive(*prim, value, out, path)?,
+        None => match prim {
+            Some(prim) => encode_primitive(prim, value, out, path)?,
+            None => copy_bytes(value, out, path)?,
+        },
+    }
+    Ok(())
+}
+
+/// Encodes a primitive value or array of `prim` into `out`.
+fn encode_primitive(
+    prim: PrimitiveType,
+    value: &Value,
+    out: &mut [u8],
+    path: &str,
+) -> Result<(), ConvertError> {
+    if out.len() == prim.size() {
+        return encode_scalar(prim, value, out, path);
+    }
+    if matches!(prim, PrimitiveType::Char | PrimitiveType::Uint8) {
+        return copy_bytes(value, out, path);
+    }
+    let items = value
+        .as_array()
+        .ok_or_else(|| invalid(path, "expected an array"))?;
+    let capacity = out.len() / prim.size();
+    if items.len() > capacity {
+        return Err(invalid(
+            path,
+            &format!("{} items exceed the length of {capacity}", items.len()),
+        ));
+    }
+    for (i, (item, chunk)) in items
+        .iter()
+        .zip(out.chunks_exact_mut(prim.size()))
+        .enumerate()
+    {
+        encode_scalar(prim, item, chunk, &format!("{path}[{i}]"))?;
+    }
+    Ok(())
+}
+
+/// Encodes a single value of `prim` into `out`.
+fn encode_scalar(
+    prim: PrimitiveType,
+    value: &Value,
+    out: &mut [u8],
+    path: &str,
+) -> Result<(), ConvertError> {
+    match prim {
+        PrimitiveType::Char => {
+            let byte = match value {
+                Value::String(text) => {
+                    let mut chars = text.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => u8::try_from(c)
+                            .map_err(|_| invalid(path, &format!("'{c}' is not a single byte")))?,
+                        _ => return Err(invalid(path, "expected a single character")),
+                    }
+                }
+                _ => json_uint(value, prim, path)? as u8,
+            };
+            out[0] = byte;
+        }
+        PrimitiveType::Float | PrimitiveType::Double => {
+            let v = match value {
+                Value::Null => f64::NAN,
+                _ => value
+                    .as_f64()
+                    .ok_or_else(|| invalid(path, "expected a number"))?,
+            };
+            put_float(prim, v, out);
+        }
+        p if p.is_signed() => put_int(p, json_int(value, p, path)?, out),
+        p => put_uint(p, json_uint(value, p, path)?, out),
+    }
+    Ok(())
+}
+
+/// Copies a string or array of numbers into a byte field, zero-padded.
+fn copy_bytes(value: &Value, out: &mut [u8], path: &str) -> Result<(), ConvertError> {
+    let bytes = json_bytes(value, path)?;
+    if bytes.len() > out.len() {
+        return Err(invalid(
+            path,
+            &format!("{} bytes exceed the length of {}", bytes.len(), out.len()),
+        ));
+    }
+    out[..bytes.len()].copy_from_slice(&bytes);
+    out[bytes.len()..].fill(0);
+    Ok(())
+}
+
+/// Returns the bytes of a string or of an array of numbers.
+fn json_bytes(value: &Value, path: &str) -> Result<Vec<u8>, ConvertError> {
+    match value {
+        Value::String(text) => Ok(text.as_bytes().to_vec()),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                item.as_u64()
+                    .and_then(|b| u8::try_from(b).ok())
+                    .ok_or_else(|| invalid(path, "expected byte values 0-255"))
+            })
+            .collect(),
+        _ => Err(invalid(path, "expected a string or an array of bytes")),
+    }
+}
+
+/// Writes the null value of an optional primitive `field`.
+///
+/// Other fields are left zeroed.
+fn write_null(field: &ResolvedField, out: &mut [u8]) {
+    let Some(prim) = field.primitive_type else {
+        return;
+    };
+    if field.is_array {
+        return;
+    }
+    let custom = field.null_value.as_deref().map(str::trim);
+    match prim {
+        PrimitiveType::Char => {
+            out[0] = custom
+                .filter(|null| null.len() == 1)
+                .map_or(0, |null| null.as_bytes()[0]);
+        }
+        PrimitiveType::Float | PrimitiveType::Double => {
+            let null = custom
+                .and_then(|null| null.parse::<f64>().ok())
+                .filter(|null| null.is_finite())
+                .unwrap_or(f64::NAN);
+            put_float(prim, null, out);
+        }
+        p if p.is_signed() => {
+            let null = custom.and_then(|null| null.parse().ok());
+            put_int(p, null.unwrap_or_else(|| signed_min(p)), out);
+        }
+        p => {
+            let null = custom.and_then(|null| null.parse().ok());
+            put_uint(p, null.unwrap_or_else(|| unsigned_max(p)), out);
+        }
+    }
+}
+
+/// Reads a JSON integer that fits `prim`.
+fn json_int(value: &Value, prim: PrimitiveType, path: &str) -> Result<i64, ConvertError> {
+    if !prim.is_signed() {
+        let v = json_uint(value, prim, path)?;
+        return i64::try_from(v).map_err(|_| invalid(path, &format!("{v} overflows an enum")));
+    }
+    let v = value
+        .as_i64()
+        .ok_or_else(|| invalid(path, "expected an integer"))?;
+    let max = -(signed_min(prim) + 1);
+    if v < signed_min(prim) || v > max {
+        return Err(invalid(path, &format!("{v} overflows {prim:?}")));
+    }
+    Ok(v)
+}
+
+/// Reads a non-negative JSON integer that fits `prim`.
+fn json_uint(value: &Value, prim: PrimitiveType, path: &str) -> Result<u64, ConvertError> {
+    let v = value
+        .as_u64()
+        .ok_or_else(|| invalid(path, "expected a non-negative integer"))?;
+    if v > unsigned_max(prim) {
+        return Err(invalid(path, &format!("{v} overflows {prim:?}")));
+    }
+    Ok(v)
+}
+
+/// Writes the low `prim.size()` bytes of `v`, little-endian.
+fn put_int(prim: PrimitiveType, v: i64, out: &mut [u8]) {
+    out.copy_from_slice(&v.to_le_bytes()[..prim.size()]);
+}
+
+/// Writes the low `prim.size()` bytes of `v`, little-endian.
+fn put_uint(prim: PrimitiveType, v: u64, out: &mut [u8]) {
+    out.copy_from_slice(&v.to_le_bytes()[..prim.size()]);
+}
+
+/// Writes a `float` or `double`, little-endian.
+fn put_float(prim: PrimitiveType, v: f64, out: &mut [u8]) {
+    match prim {
+        PrimitiveType::Float => out.copy_from_slice(&(v as f32).to_le_bytes()),
+        _ => out.copy_from_slice(&v.to_le_bytes()),
+    }
+}
+
+/// Returns the minimum of a signed `prim`.
+fn signed_min(prim: PrimitiveType) -> i64 {
+    -1i64 << (prim.size() * 8 - 1)
+}
+
+/// Returns the maximum of an unsigned `prim`.
+fn unsigned_max(prim: PrimitiveType) -> u64 {
+    u64::MAX >> (64 - prim.size() * 8)
+}
+
+/// Joins a field name onto the path of its parent.
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+/// Builds an [`ConvertError::InvalidValue`].
+fn invalid(path: &str, reason: &str) -> ConvertError {
+    ConvertError::InvalidValue {
+        field: path.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironsbe_schema::parse_schema;
+    use serde_json::json;
+
+    const SCHEMA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="conv" id="7" version="1" byteOrder="littleEndian">
+    <types>
+        <type name="Symbol" primitiveType="char" length="4"/>
+        <type name="Venue" primitiveType="char" length="4" presence="constant">XCME</type>
+        <composite name="Decimal">
+            <type name="mantissa" primitiveType="int64"/>
+            <type name="exponent" primitiveType="int8"/>
+        </composite>
+        <composite name="varDataEncoding">
+            <type name="length" primitiveType="uint16"/>
+            <type name="varData" primitiveType="uint8" length="0"/>
+        </composite>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+            <validValue name="Sell">2</validValue>
+        </enum>
+        <set name="Flags" encodingType="uint8">
+            <choice name="PostOnly">0</choice>
+            <choice name="Hidden">2</choice>
+        </set>
+    </types>
+    <sbe:message name="Order" id="3" blockLength="28">
+        <field name="orderId" id="1" type="uint64" offset="0"/>
+        <field name="symbol" id="2" type="Symbol" offset="8"/>
+        <field name="venue" id="3" type="Venue"/>
+        <field name="side" id="4" type="Side" offset="12"/>
+        <field name="flags" id="5" type="Flags" offset="13"/>
+        <field name="price" id="6" type="Decimal" offset="14"/>
+        <field name="stop" id="7" type="int32" offset="23" presence="optional"/>
+        <field name="tick" id="8" type="int8" offset="27"/>
+        <group name="fills" id="10" dimensionType="groupSizeEncoding" blockLength="12">
+            <field name="qty" id="11" type="uint32" offset="0"/>
+            <field name="px" id="12" type="double" offset="4"/>
+            <data name="note" id="13" type="varDataEncoding"/>
+        </group>
+        <data name="text" id="20" type="varDataEncoding"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+    fn ir() -> SchemaIr {
+        SchemaIr::from_schema(&parse_schema(SCHEMA).expect("valid schema"))
+    }
+
+    fn order() -> Value {
+        json!({"Order": {
+            "orderId": 42,
+            "symbol": "ES",
+            "venue": "XCME",
+            "side": "Sell",
+            "flags": ["PostOnly", "Hidden"],
+            "price": {"mantissa": 12345, "exponent": -2},
+            "stop": null,
+            "tick": -3,
+            "fills": [
+                {"qty": 5, "px": 1.5, "note": "a"},
+                {"qty": 7, "px": 2.25, "note": ""}
+            ],
+            "text": "hello"
+        }})
+    }
+
+    fn encode(json: &Value) -> Result<Vec<u8>, ConvertError> {
+        let mut buf = [0u8; 256];
+        let len = from_json(json, &ir(), &mut buf)?;
+        Ok(buf[..len].to_vec())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let ir = ir();
+        let buf = encode(&order()).expect("encodes");
+        assert_eq!(buf.len(), 8 + 28 + 4 + 2 * (12 + 2) + 1 + 2 + 5);
+        assert_eq!(&buf[16..20], b"ES\0\0");
+        assert_eq!(&buf[31..35], &i32::MIN.to_le_bytes());
+
+        let decoded = DynamicMessage::decode(&ir, &buf).expect("decodes");
+        assert_eq!(decoded.field("side").and_then(|v| v.as_i64()), Some(2));
+        assert_eq!(to_json(&buf, &ir).expect("converts"), order());
+    }
+
+    #[test]
+    fn test_numeric_forms_and_missing_sections() {
+        let ir = ir();
+        let json = json!({"Order": {
+            "orderId": 1,
+            "symbol": [69, 83],
+            "side": 7,
+            "flags": 2,
+            "price": {"mantissa": 1, "exponent": 0},
+            "tick": 0
+        }});
+        let buf = encode(&json).expect("encodes");
+        let back = to_json(&buf, &ir).expect("converts");
+        let order = &back["Order"];
+        assert_eq!(order["symbol"], "ES");
+        assert_eq!(order["side"], 7);
+        assert_eq!(order["flags"], 2);
+        assert_eq!(order["stop"], Value::Null);
+        assert_eq!(order["fills"], json!([]));
+        assert_eq!(order["text"], "");
+    }
+
+    #[test]
+    fn test_rejects_invalid_documents() {
+        let mut json = order();
+        json["Order"]["fills"][1]["qty"] = json!(-1);
+        assert!(matches!(
+            encode(&json),
+            Err(ConvertError::InvalidValue { field, .. }) if field == "fills[1].qty"
+        ));
+
+        let mut json = order();
+        json["Order"]["tick"] = json!(128);
+        assert!(matches!(
+            encode(&json),
+            Err(ConvertError::InvalidValue { .. })
+        ));
+
+        let mut json = order();
+        json["Order"]["symbol"] = json!("TOOLONG");
+        assert!(matches!(
+            encode(&json),
+            Err(ConvertError::InvalidValue { .. })
+        ));
+
+        let mut json = order();
+        json["Order"]["side"] = json!("Short");
+        assert!(matches!(
+            encode(&json),
+            Err(ConvertError::InvalidValue { .. })
+        ));
+
+        let mut json = order();
+        json["Order"]["price"]["scale"] = json!(1);
+        assert!(matches!(
+            encode(&json),
+            Err(ConvertError::UnknownField(field)) if field == "price.scale"
+        ));
+
+        let mut json = order();
+        json["Order"]
+            .as_object_mut()
+            .expect("object")
+            .remove("orderId");
+        assert!(matches!(
+            encode(&json),
+            Err(ConvertError::MissingField(field)) if field == "orderId"
+        ));
+
+        assert!(matches!(
+            encode(&json!({"Quote": {}})),
+            Err(ConvertError::UnknownMessage(_))
+        ));
+        assert!(matches!(
+            encode(&json!([])),
+            Err(ConvertError::InvalidDocument)
+        ));
+
+        let mut small = [0u8; 20];
+        assert!(matches!(
+            from_json(&order(), &ir(), &mut small),
+            Err(ConvertError::BufferTooShort { required: 36, .. })
+        ));
+    }
+
+    #[test]
+    fn test_to_json_reports_decode_errors() {
+        let buf = encode(&order()).expect("encodes");
+        assert!(matches!(
+            to_json(&buf[..20], &ir()),
+            Err(ConvertError::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,28 @@
+//! # IronSBE Convert
+//!
+//! Conversion between SBE messages and other representations, driven by a
+//! [`SchemaIr`](ironsbe_schema::SchemaIr) loaded at runtime.
+//!
+//! This crate provides:
+//! - [`to_json`] rendering any message of a schema as JSON
+//! - [`from_json`] encoding JSON back into an SBE message
+//! - [`SequenceAudit`] reconciling the journals of both sides of a session
+//! - [`ScenarioRunner`] replaying scripted YAML scenarios for venue
+//!   certification (feature `scenario`)
+//!
+//! REST and admin gateways can accept and return JSON, and captures can be
+//! stored human-readable, without glue code per message.
+
+pub mod audit;
+pub mod error;
+pub mod json;
+#[cfg(feature = "scenario")]
+pub mod scenario;
+
+pub use audit::{AuditReport, JournalSummary, SequenceAudit};
+pub use error::ConvertError;
+pub use json::{from_json, to_json};
+#[cfg(feature = "scenario")]
+pub use scenario::{
+    Scenario, ScenarioPeer, ScenarioReport, ScenarioRunner, StepOutcome, StepReport,
+};
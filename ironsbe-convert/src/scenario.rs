@@ -0,0 +1,545 @@
+//! Scripted session scenarios for conformance testing.
+//!
+//! Venues certify a client or server by walking it through scripted
+//! message sequences. A [`Scenario`] is such a script, loaded from YAML:
+//! each step either sends a message or expects one, with messages written
+//! in the JSON form of [`crate::json`]:
+//!
+//! ```yaml
+//! name: New order is acknowledged
+//! timeout_ms: 2000
+//! ignore: [Heartbeat]
+//! steps:
+//!   - name: Send order
+//!     send:
+//!       NewOrder: {clOrdId: 7, symbol: ES, side: Buy, qty: 10}
+//!   - name: Order acknowledged
+//!     expect:
+//!       ExecutionReport: {clOrdId: 7, execType: New}
+//!     timeout_ms: 500
+//! ```
+//!
+//! An expected message matches when it has the expected name and every
+//! listed field has the listed value; fields left out are not checked.
+//! Composites and group entries are matched the same way, and groups must
+//! have as many entries as listed. A message with no fields (`Logout:`)
+//! matches any message of that name. Received messages named in `ignore`
+//! are skipped while waiting for an expected one.
+//!
+//! [`ScenarioRunner::run`] drives a [`ScenarioPeer`], the connection to the
+//! system under test, and returns a [`ScenarioReport`] with the outcome of
+//! every step. The runner is the counterparty: connect it to a server to
+//! certify the server, or accept the client's connection to certify the
+//! client. [`BlockingTcpConnection`] implements [`ScenarioPeer`].
+
+use crate::error::ConvertError;
+use crate::json::{from_json, to_json};
+use ironsbe_schema::SchemaIr;
+use ironsbe_transport::tcp_std::BlockingTcpConnection;
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+use std::time::Duration;
+
+/// A scripted sequence of sent and expected messages.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Name of the scenario, for the report.
+    pub name: String,
+    /// Default time to wait for an expected message, in milliseconds.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Names of messages skipped while waiting for an expected one.
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    /// Steps, in order.
+    pub steps: Vec<Step>,
+}
+
+/// One step of a [`Scenario`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    /// Name of the step, for the report.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// What the step does.
+    #[serde(flatten)]
+    pub action: Action,
+    /// Time to wait for an expected message, in milliseconds, overriding
+    /// the scenario's.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+/// What a [`Step`] does.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Encodes and sends the message.
+    Send(Value),
+    /// Waits for a message and checks it against the expected one.
+    Expect(Value),
+}
+
+impl Scenario {
+    /// Parses a scenario from YAML.
+    ///
+    /// # Errors
+    /// Returns [`ConvertError::InvalidScenario`] if the YAML is malformed
+    /// or does not describe a scenario.
+    pub fn from_yaml(yaml: &str) -> Result<Self, ConvertError> {
+        serde_norway::from_str(yaml).map_err(|e| ConvertError::InvalidScenario(e.to_string()))
+    }
+}
+
+/// Connection to the system under test.
+pub trait ScenarioPeer {
+    /// Sends one encoded message.
+    ///
+    /// # Errors
+    /// Returns an error if the message cannot be sent.
+    fn send(&mut self, message: &[u8]) -> std::io::Result<()>;
+
+    /// Receives one message, waiting at most `timeout`.
+    ///
+    /// # Errors
+    /// Returns an error if the connection fails.
+    fn recv_timeout(&mut self, timeout: Duration) -> std::io::Result<Option<Vec<u8>>>;
+}
+
+impl ScenarioPeer for BlockingTcpConnection {
+    fn send(&mut self, message: &[u8]) -> std::io::Result<()> {
+        BlockingTcpConnection::send(self, message).map_err(std::io::Error::other)
+    }
+
+    fn recv_timeout(&mut self, timeout: Duration) -> std::io::Result<Option<Vec<u8>>> {
+        BlockingTcpConnection::recv_timeout(self, timeout).map_err(std::io::Error::other)
+    }
+}
+
+/// Outcome of one step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// The message was sent, or the expected message received.
+    Passed,
+    /// The step failed, with the reason.
+    Failed(String),
+    /// An earlier step failed, so this one did not run.
+    Skipped,
+}
+
+/// Report of one step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepReport {
+    /// Position of the step, from 1.
+    pub number: usize,
+    /// Name of the step, or a description of its action.
+    pub name: String,
+    /// What happened.
+    pub outcome: StepOutcome,
+}
+
+/// Report of a scenario run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioReport {
+    /// Name of the scenario.
+    pub name: String,
+    /// Every step, in order.
+    pub steps: Vec<StepReport>,
+}
+
+impl ScenarioReport {
+    /// Returns true if every step passed.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.steps
+            .iter()
+            .all(|step| step.outcome == StepOutcome::Passed)
+    }
+
+    /// Returns the first failed step, if any.
+    #[must_use]
+    pub fn failure(&self) -> Option<&StepReport> {
+        self.steps
+            .iter()
+            .find(|step| matches!(step.outcome, StepOutcome::Failed(_)))
+    }
+}
+
+impl fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if self.passed() { "PASS" } else { "FAIL" };
+        writeln!(f, "{verdict} {}", self.name)?;
+        for step in &self.steps {
+            match &step.outcome {
+                StepOutcome::Passed => writeln!(f, "  [PASS] {}. {}", step.number, step.name)?,
+                StepOutcome::Failed(reason) => {
+                    writeln!(f, "  [FAIL] {}. {}: {reason}", step.number, step.name)?;
+                }
+                StepOutcome::Skipped => writeln!(f, "  [SKIP] {}. {}", step.number, step.name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs [`Scenario`]s against a [`ScenarioPeer`].
+#[derive(Debug, Clone)]
+pub struct ScenarioRunner<'a> {
+    ir: &'a SchemaIr,
+    timeout: Duration,
+    buffer_size: usize,
+}
+
+impl<'a> ScenarioRunner<'a> {
+    /// Creates a runner encoding and decoding with `ir`.
+    #[must_use]
+    pub fn new(ir: &'a SchemaIr) -> Self {
+        Self {
+            ir,
+            timeout: Duration::from_secs(5),
+            buffer_size: 64 * 1024,
+        }
+    }
+
+    /// Sets the time to wait for an expected message when neither the
+    /// scenario nor the step sets one (default 5 seconds).
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the largest message a send step may encode (default 64KB).
+    #[must_use]
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = size;
+        self
+    }
+
+    /// Runs every step of `scenario` in order against `peer`.
+    ///
+    /// Stops at the first failed step; the remaining steps are reported as
+    /// skipped.
+    pub fn run(&self, scenario: &Scenario, peer: &mut impl ScenarioPeer) -> ScenarioReport {
+        let mut buffer = vec![0u8; self.buffer_size];
+        let mut failed = false;
+        let steps = scenario
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let outcome = if failed {
+                    StepOutcome::Skipped
+                } else {
+                    match self.run_step(scenario, step, peer, &mut buffer) {
+                        Ok(()) => StepOutcome::Passed,
+                        Err(reason) => {
+                            failed = true;
+                            StepOutcome::Failed(reason)
+                        }
+                    }
+                };
+                StepReport {
+                    number: index + 1,
+                    name: step.name.clone().unwrap_or_else(|| describe(&step.action)),
+                    outcome,
+                }
+            })
+            .collect();
+        ScenarioReport {
+            name: scenario.name.clone(),
+            steps,
+        }
+    }
+
+    fn run_step(
+        &self,
+        scenario: &Scenario,
+        step: &Step,
+        peer: &mut impl ScenarioPeer,
+        buffer: &mut [u8],
+    ) -> Result<(), String> {
+        match &step.action {
+            Action::Send(message) => {
+                let len = from_json(message, self.ir, buffer).map_err(|e| e.to_string())?;
+                peer.send(&buffer[..len])
+                    .map_err(|e| format!("send failed: {e}"))
+            }
+            Action::Expect(expected) => {
+                let timeout = step
+                    .timeout_ms
+                    .or(scenario.timeout_ms)
+                    .map_or(self.timeout, Duration::from_millis);
+                let deadline = std::time::Instant::now() + timeout;
+                loop {
+                    let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+                    let received = peer
+                        .recv_timeout(remaining)
+                        .map_err(|e| format!("receive failed: {e}"))?
+                        .ok_or_else(|| format!("no message within {}ms", timeout.as_millis()))?;
+                    let actual = to_json(&received, self.ir)
+                        .map_err(|e| format!("received undecodable message: {e}"))?;
+                    if message_name(&actual)
+                        .is_some_and(|name| scenario.ignore.iter().any(|i| i == name))
+                    {
+                        continue;
+                    }
+                    return matches(expected, &actual);
+                }
+            }
+        }
+    }
+}
+
+/// Describes an unnamed step by its action.
+fn describe(action: &Action) -> String {
+    match action {
+        Action::Send(message) => format!("send {}", message_name(message).unwrap_or("?")),
+        Action::Expect(message) => format!("expect {}", message_name(message).unwrap_or("?")),
+    }
+}
+
+/// Returns the message name of a JSON message.
+fn message_name(message: &Value) -> Option<&str> {
+    message.as_object()?.keys().next().map(String::as_str)
+}
+
+/// Checks a received message against the expected one.
+fn matches(expected: &Value, actual: &Value) -> Result<(), String> {
+    let (Some(expected_name), Some(actual_name)) = (message_name(expected), message_name(actual))
+    else {
+        return Err("expected message must be an object naming the message".to_string());
+    };
+    if expected_name != actual_name {
+        return Err(format!("expected {expected_name}, received {actual_name}"));
+    }
+    match &expected[expected_name] {
+        // No fields listed: any message of that name.
+        Value::Null => Ok(()),
+        body => match_value(body, &actual[actual_name], expected_name),
+    }
+}
+
+/// Checks that `actual` holds everything in `expected`, reporting the path
+/// of the first difference.
+fn match_value(expected: &Value, actual: &Value, path: &str) -> Result<(), String> {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            expected.iter().try_for_each(|(key, value)| {
+                let path = format!("{path}.{key}");
+                match actual.get(key) {
+                    Some(actual) => match_value(value, actual, &path),
+                    None => Err(format!("{path}: missing")),
+                }
+            })
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            if expected.len() != actual.len() {
+                return Err(format!(
+                    "{path}: expected {} entries, received {}",
+                    expected.len(),
+                    actual.len()
+                ));
+            }
+            expected
+                .iter()
+                .zip(actual)
+                .enumerate()
+                .try_for_each(|(i, (expected, actual))| {
+                    match_value(expected, actual, &format!("{path}[{i}]"))
+                })
+        }
+        (Value::Number(e), Value::Number(a)) if e == a || e.as_f64() == a.as_f64() => Ok(()),
+        _ if expected == actual => Ok(()),
+        _ => Err(format!("{path}: expected {expected}, received {actual}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironsbe_schema::parse_schema;
+    use serde_json::json;
+    use std::collections::VecDeque;
+
+    const SCHEMA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="cert" id="9" version="1" byteOrder="littleEndian">
+    <types>
+        <type name="Symbol" primitiveType="char" length="4"/>
+        <enum name="ExecType" encodingType="uint8">
+            <validValue name="New">0</validValue>
+            <validValue name="Rejected">8</validValue>
+        </enum>
+    </types>
+    <sbe:message name="NewOrder" id="1" blockLength="16">
+        <field name="clOrdId" id="1" type="uint64" offset="0"/>
+        <field name="symbol" id="2" type="Symbol" offset="8"/>
+        <field name="qty" id="3" type="uint32" offset="12"/>
+    </sbe:message>
+    <sbe:message name="ExecutionReport" id="2" blockLength="9">
+        <field name="clOrdId" id="1" type="uint64" offset="0"/>
+        <field name="execType" id="2" type="ExecType" offset="8"/>
+    </sbe:message>
+    <sbe:message name="Heartbeat" id="3" blockLength="8">
+        <field name="sentAt" id="1" type="uint64" offset="0"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+    const SCENARIO: &str = r#"
+name: New order is acknowledged
+timeout_ms: 100
+ignore: [Heartbeat]
+steps:
+  - name: Send order
+    send:
+      NewOrder: {clOrdId: 7, symbol: ES, qty: 10}
+  - expect:
+      ExecutionReport: {clOrdId: 7, execType: New}
+  - name: Nothing else
+    expect:
+      ExecutionReport:
+"#;
+
+    fn ir() -> SchemaIr {
+        SchemaIr::from_schema(&parse_schema(SCHEMA).expect("valid schema"))
+    }
+
+    /// Records sent messages and replays scripted responses.
+    struct ScriptedPeer {
+        sent: Vec<Value>,
+        responses: VecDeque<Vec<u8>>,
+    }
+
+    impl ScriptedPeer {
+        fn new(ir: &SchemaIr, responses: &[Value]) -> Self {
+            let responses = responses
+                .iter()
+                .map(|message| {
+                    let mut buf = vec![0u8; 256];
+                    let len = from_json(message, ir, &mut buf).expect("encodes");
+                    buf.truncate(len);
+                    buf
+                })
+                .collect();
+            Self {
+                sent: Vec::new(),
+                responses,
+            }
+        }
+    }
+
+    impl ScenarioPeer for ScriptedPeer {
+        fn send(&mut self, message: &[u8]) -> std::io::Result<()> {
+            self.sent.push(to_json(message, &ir()).expect("decodes"));
+            Ok(())
+        }
+
+        fn recv_timeout(&mut self, _timeout: Duration) -> std::io::Result<Option<Vec<u8>>> {
+            Ok(self.responses.pop_front())
+        }
+    }
+
+    #[test]
+    fn test_scenario_passes() {
+        let ir = ir();
+        let scenario = Scenario::from_yaml(SCENARIO).unwrap();
+        let mut peer = ScriptedPeer::new(
+            &ir,
+            &[
+                json!({"Heartbeat": {"sentAt": 1}}),
+                json!({"ExecutionReport": {"clOrdId": 7, "execType": "New"}}),
+                json!({"ExecutionReport": {"clOrdId": 8, "execType": "Rejected"}}),
+            ],
+        );
+
+        let report = ScenarioRunner::new(&ir).run(&scenario, &mut peer);
+        assert!(report.passed(), "{report}");
+        assert_eq!(report.steps[1].name, "expect ExecutionReport");
+        assert_eq!(
+            peer.sent,
+            vec![json!({"NewOrder": {"clOrdId": 7, "symbol": "ES", "qty": 10}})]
+        );
+    }
+
+    #[test]
+    fn test_scenario_reports_mismatch_and_skips() {
+        let ir = ir();
+        let scenario = Scenario::from_yaml(SCENARIO).unwrap();
+        let mut peer = ScriptedPeer::new(
+            &ir,
+            &[json!({"ExecutionReport": {"clOrdId": 7, "execType": "Rejected"}})],
+        );
+
+        let report = ScenarioRunner::new(&ir).run(&scenario, &mut peer);
+        assert!(!report.passed());
+        assert_eq!(report.steps[0].outcome, StepOutcome::Passed);
+        assert_eq!(
+            report.failure().unwrap().outcome,
+            StepOutcome::Failed(
+                "ExecutionReport.execType: expected \"New\", received \"Rejected\"".to_string()
+            )
+        );
+        assert_eq!(report.steps[2].outcome, StepOutcome::Skipped);
+        let text = report.to_string();
+        assert!(text.starts_with("FAIL New order is acknowledged\n"));
+        assert!(text.contains("  [SKIP] 3. Nothing else\n"));
+    }
+
+    #[test]
+    fn test_scenario_timeout_and_bad_send() {
+        let ir = ir();
+        let scenario = Scenario::from_yaml(SCENARIO).unwrap();
+        let report = ScenarioRunner::new(&ir).run(&scenario, &mut ScriptedPeer::new(&ir, &[]));
+        assert_eq!(
+            report.steps[1].outcome,
+            StepOutcome::Failed("no message within 100ms".to_string())
+        );
+
+        let scenario = Scenario::from_yaml(
+            "name: bad\nsteps:\n  - send:\n      NewOrder: {clOrdId: 1, bogus: 2}\n",
+        )
+        .unwrap();
+        let report = ScenarioRunner::new(&ir).run(&scenario, &mut ScriptedPeer::new(&ir, &[]));
+        assert!(matches!(&report.steps[0].outcome, StepOutcome::Failed(r) if r.contains("bogus")));
+    }
+
+    #[test]
+    fn test_scenario_over_tcp() {
+        use ironsbe_transport::tcp_std::BlockingTcpListener;
+
+        let ir = ir();
+        let listener = BlockingTcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let venue = std::thread::spawn(move || {
+            let ir = self::ir();
+            let mut conn = listener.accept().unwrap();
+            let order = conn.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+            let order = to_json(&order, &ir).unwrap();
+            let report = json!({"ExecutionReport": {
+                "clOrdId": order["NewOrder"]["clOrdId"], "execType": "New"}});
+            for message in [report.clone(), report] {
+                let mut buf = [0u8; 64];
+                let len = from_json(&message, &ir, &mut buf).unwrap();
+                conn.send(&buf[..len]).unwrap();
+            }
+        });
+
+        let scenario = Scenario::from_yaml(SCENARIO).unwrap();
+        let mut conn = BlockingTcpConnection::connect(addr).unwrap();
+        let report = ScenarioRunner::new(&ir).run(&scenario, &mut conn);
+        venue.join().unwrap();
+        assert!(report.passed(), "{report}");
+    }
+
+    #[test]
+    fn test_invalid_scenario() {
+        assert!(matches!(
+            Scenario::from_yaml("name: x\nsteps:\n  - wait: 5\n"),
+            Err(ConvertError::InvalidScenario(_))
+        ));
+    }
+}
@@ -3,10 +3,23 @@
 //! This module provides:
 //! - [`ReadBuffer`] trait for read-only buffer access
 //! - [`WriteBuffer`] trait for read-write buffer access
+//! - [`CheckedReadBuffer`] for reading untrusted bytes without panicking
 //! - [`AlignedBuffer`] for cache-line aligned buffers
 //! - [`BufferPool`] for reusable buffer allocation
-
+//! - [`PooledBuffer`] for handing a pooled buffer downstream by value
+
+#[cfg(feature = "std")]
+use crate::canary;
+use crate::decoder::DecodeError;
+#[cfg(feature = "std")]
+use crate::memory::MemoryConfig;
+#[cfg(feature = "std")]
+use crate::pool::{PoolCounters, PoolStats};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use crossbeam_queue::ArrayQueue;
+#[cfg(feature = "std")]
 use std::sync::Arc;
 
 /// Trait for read-only buffer access with optimized primitive reads.
@@ -141,7 +154,323 @@ pub trait ReadBuffer {
     fn get_str(&self, offset: usize, len: usize) -> &str {
         let bytes = self.get_bytes(offset, len);
         let end = bytes.iter().position(|&b| b == 0).unwrap_or(len);
-        std::str::from_utf8(&bytes[..end]).unwrap_or("")
+        core::str::from_utf8(&bytes[..end]).unwrap_or("")
+    }
+
+    /// Reads a u8 at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the buffer.
+    #[inline(always)]
+    fn try_get_u8(&self, offset: usize) -> Result<u8, DecodeError> {
+        Ok(u8::from_le_bytes(checked_array(self.as_slice(), offset)?))
+    }
+
+    /// Reads an i8 at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the buffer.
+    #[inline(always)]
+    fn try_get_i8(&self, offset: usize) -> Result<i8, DecodeError> {
+        Ok(i8::from_le_bytes(checked_array(self.as_slice(), offset)?))
+    }
+
+    /// Reads a u16 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the buffer.
+    #[inline(always)]
+    fn try_get_u16_le(&self, offset: usize) -> Result<u16, DecodeError> {
+        Ok(u16::from_le_bytes(checked_array(self.as_slice(), offset)?))
+    }
+
+    /// Reads an i16 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the buffer.
+    #[inline(always)]
+    fn try_get_i16_le(&self, offset: usize) -> Result<i16, DecodeError> {
+        Ok(i16::from_le_bytes(checked_array(self.as_slice(), offset)?))
+    }
+
+    /// Reads a u32 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the buffer.
+    #[inline(always)]
+    fn try_get_u32_le(&self, offset: usize) -> Result<u32, DecodeError> {
+        Ok(u32::from_le_bytes(checked_array(self.as_slice(), offset)?))
+    }
+
+    /// Reads an i32 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the buffer.
+    #[inline(always)]
+    fn try_get_i32_le(&self, offset: usize) -> Result<i32, DecodeError> {
+        Ok(i32::from_le_bytes(checked_array(self.as_slice(), offset)?))
+    }
+
+    /// Reads a u64 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the buffer.
+    #[inline(always)]
+    fn try_get_u64_le(&self, offset: usize) -> Result<u64, DecodeError> {
+        Ok(u64::from_le_bytes(checked_array(self.as_slice(), offset)?))
+    }
+
+    /// Reads an i64 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the buffer.
+    #[inline(always)]
+    fn try_get_i64_le(&self, offset: usize) -> Result<i64, DecodeError> {
+        Ok(i64::from_le_bytes(checked_array(self.as_slice(), offset)?))
+    }
+
+    /// Reads an f32 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the buffer.
+    #[inline(always)]
+    fn try_get_f32_le(&self, offset: usize) -> Result<f32, DecodeError> {
+        Ok(f32::from_bits(self.try_get_u32_le(offset)?))
+    }
+
+    /// Reads an f64 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the buffer.
+    #[inline(always)]
+    fn try_get_f64_le(&self, offset: usize) -> Result<f64, DecodeError> {
+        Ok(f64::from_bits(self.try_get_u64_le(offset)?))
+    }
+
+    /// Returns a slice of bytes at the given offset and length, checking
+    /// bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to start from
+    /// * `len` - Number of bytes to read
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the range extends past
+    /// the end of the buffer.
+    #[inline(always)]
+    fn try_get_bytes(&self, offset: usize, len: usize) -> Result<&[u8], DecodeError> {
+        checked_range(self.as_slice(), offset, len)
+    }
+
+    /// Reads a fixed-length character array as a string slice, checking
+    /// bounds and encoding.
+    /// Trims null bytes from the end.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to start from
+    /// * `len` - Maximum length of the string
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the field extends past
+    /// the end of the buffer, or [`DecodeError::InvalidUtf8`] with the
+    /// offset of the first invalid byte.
+    #[inline]
+    fn try_get_str(&self, offset: usize, len: usize) -> Result<&str, DecodeError> {
+        let bytes = self.try_get_bytes(offset, len)?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(len);
+        core::str::from_utf8(&bytes[..end]).map_err(|e| DecodeError::InvalidUtf8 {
+            offset: offset + e.valid_up_to(),
+        })
+    }
+
+    /// Reads a u8 at the given offset without checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Safety
+    /// `offset` plus the value's size must not exceed `self.as_slice().len()`,
+    /// the slice the read goes through, whatever [`len`](Self::len) reports.
+    #[cfg(feature = "unchecked")]
+    #[inline(always)]
+    unsafe fn get_u8_unchecked(&self, offset: usize) -> u8 {
+        // SAFETY: the caller guarantees the value is within `as_slice()`.
+        u8::from_le_bytes(unsafe { unchecked_array(self.as_slice(), offset) })
+    }
+
+    /// Reads an i8 at the given offset without checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Safety
+    /// `offset` plus the value's size must not exceed `self.as_slice().len()`,
+    /// the slice the read goes through, whatever [`len`](Self::len) reports.
+    #[cfg(feature = "unchecked")]
+    #[inline(always)]
+    unsafe fn get_i8_unchecked(&self, offset: usize) -> i8 {
+        // SAFETY: the caller guarantees the value is within `as_slice()`.
+        i8::from_le_bytes(unsafe { unchecked_array(self.as_slice(), offset) })
+    }
+
+    /// Reads a u16 in little-endian at the given offset without checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Safety
+    /// `offset` plus the value's size must not exceed `self.as_slice().len()`,
+    /// the slice the read goes through, whatever [`len`](Self::len) reports.
+    #[cfg(feature = "unchecked")]
+    #[inline(always)]
+    unsafe fn get_u16_le_unchecked(&self, offset: usize) -> u16 {
+        // SAFETY: the caller guarantees the value is within `as_slice()`.
+        u16::from_le_bytes(unsafe { unchecked_array(self.as_slice(), offset) })
+    }
+
+    /// Reads an i16 in little-endian at the given offset without checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Safety
+    /// `offset` plus the value's size must not exceed `self.as_slice().len()`,
+    /// the slice the read goes through, whatever [`len`](Self::len) reports.
+    #[cfg(feature = "unchecked")]
+    #[inline(always)]
+    unsafe fn get_i16_le_unchecked(&self, offset: usize) -> i16 {
+        // SAFETY: the caller guarantees the value is within `as_slice()`.
+        i16::from_le_bytes(unsafe { unchecked_array(self.as_slice(), offset) })
+    }
+
+    /// Reads a u32 in little-endian at the given offset without checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Safety
+    /// `offset` plus the value's size must not exceed `self.as_slice().len()`,
+    /// the slice the read goes through, whatever [`len`](Self::len) reports.
+    #[cfg(feature = "unchecked")]
+    #[inline(always)]
+    unsafe fn get_u32_le_unchecked(&self, offset: usize) -> u32 {
+        // SAFETY: the caller guarantees the value is within `as_slice()`.
+        u32::from_le_bytes(unsafe { unchecked_array(self.as_slice(), offset) })
+    }
+
+    /// Reads an i32 in little-endian at the given offset without checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Safety
+    /// `offset` plus the value's size must not exceed `self.as_slice().len()`,
+    /// the slice the read goes through, whatever [`len`](Self::len) reports.
+    #[cfg(feature = "unchecked")]
+    #[inline(always)]
+    unsafe fn get_i32_le_unchecked(&self, offset: usize) -> i32 {
+        // SAFETY: the caller guarantees the value is within `as_slice()`.
+        i32::from_le_bytes(unsafe { unchecked_array(self.as_slice(), offset) })
+    }
+
+    /// Reads a u64 in little-endian at the given offset without checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Safety
+    /// `offset` plus the value's size must not exceed `self.as_slice().len()`,
+    /// the slice the read goes through, whatever [`len`](Self::len) reports.
+    #[cfg(feature = "unchecked")]
+    #[inline(always)]
+    unsafe fn get_u64_le_unchecked(&self, offset: usize) -> u64 {
+        // SAFETY: the caller guarantees the value is within `as_slice()`.
+        u64::from_le_bytes(unsafe { unchecked_array(self.as_slice(), offset) })
+    }
+
+    /// Reads an i64 in little-endian at the given offset without checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Safety
+    /// `offset` plus the value's size must not exceed `self.as_slice().len()`,
+    /// the slice the read goes through, whatever [`len`](Self::len) reports.
+    #[cfg(feature = "unchecked")]
+    #[inline(always)]
+    unsafe fn get_i64_le_unchecked(&self, offset: usize) -> i64 {
+        // SAFETY: the caller guarantees the value is within `as_slice()`.
+        i64::from_le_bytes(unsafe { unchecked_array(self.as_slice(), offset) })
+    }
+
+    /// Reads an f32 in little-endian at the given offset without checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Safety
+    /// `offset` plus the value's size must not exceed `self.as_slice().len()`,
+    /// the slice the read goes through, whatever [`len`](Self::len) reports.
+    #[cfg(feature = "unchecked")]
+    #[inline(always)]
+    unsafe fn get_f32_le_unchecked(&self, offset: usize) -> f32 {
+        // SAFETY: forwarded from the caller.
+        f32::from_bits(unsafe { self.get_u32_le_unchecked(offset) })
+    }
+
+    /// Reads an f64 in little-endian at the given offset without checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to read from
+    ///
+    /// # Safety
+    /// `offset` plus the value's size must not exceed `self.as_slice().len()`,
+    /// the slice the read goes through, whatever [`len`](Self::len) reports.
+    #[cfg(feature = "unchecked")]
+    #[inline(always)]
+    unsafe fn get_f64_le_unchecked(&self, offset: usize) -> f64 {
+        // SAFETY: forwarded from the caller.
+        f64::from_bits(unsafe { self.get_u64_le_unchecked(offset) })
     }
 }
 
@@ -293,47 +622,488 @@ pub trait WriteBuffer: ReadBuffer {
     fn zero(&mut self, offset: usize, len: usize) {
         self.as_mut_slice()[offset..offset + len].fill(0);
     }
-}
 
-/// Implement ReadBuffer for byte slices.
-impl ReadBuffer for [u8] {
+    /// Writes a u8 at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to write to
+    /// * `value` - Value to write
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value would extend
+    /// past the end of the buffer. Nothing is written in that case.
+    #[inline(always)]
+    fn try_put_u8(&mut self, offset: usize, value: u8) -> Result<(), DecodeError> {
+        self.try_put_bytes(offset, &value.to_le_bytes())
+    }
+
+    /// Writes an i8 at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to write to
+    /// * `value` - Value to write
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value would extend
+    /// past the end of the buffer. Nothing is written in that case.
+    #[inline(always)]
+    fn try_put_i8(&mut self, offset: usize, value: i8) -> Result<(), DecodeError> {
+        self.try_put_bytes(offset, &value.to_le_bytes())
+    }
+
+    /// Writes a u16 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to write to
+    /// * `value` - Value to write
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value would extend
+    /// past the end of the buffer. Nothing is written in that case.
+    #[inline(always)]
+    fn try_put_u16_le(&mut self, offset: usize, value: u16) -> Result<(), DecodeError> {
+        self.try_put_bytes(offset, &value.to_le_bytes())
+    }
+
+    /// Writes an i16 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to write to
+    /// * `value` - Value to write
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value would extend
+    /// past the end of the buffer. Nothing is written in that case.
+    #[inline(always)]
+    fn try_put_i16_le(&mut self, offset: usize, value: i16) -> Result<(), DecodeError> {
+        self.try_put_bytes(offset, &value.to_le_bytes())
+    }
+
+    /// Writes a u32 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to write to
+    /// * `value` - Value to write
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value would extend
+    /// past the end of the buffer. Nothing is written in that case.
+    #[inline(always)]
+    fn try_put_u32_le(&mut self, offset: usize, value: u32) -> Result<(), DecodeError> {
+        self.try_put_bytes(offset, &value.to_le_bytes())
+    }
+
+    /// Writes an i32 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to write to
+    /// * `value` - Value to write
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value would extend
+    /// past the end of the buffer. Nothing is written in that case.
+    #[inline(always)]
+    fn try_put_i32_le(&mut self, offset: usize, value: i32) -> Result<(), DecodeError> {
+        self.try_put_bytes(offset, &value.to_le_bytes())
+    }
+
+    /// Writes a u64 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to write to
+    /// * `value` - Value to write
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value would extend
+    /// past the end of the buffer. Nothing is written in that case.
+    #[inline(always)]
+    fn try_put_u64_le(&mut self, offset: usize, value: u64) -> Result<(), DecodeError> {
+        self.try_put_bytes(offset, &value.to_le_bytes())
+    }
+
+    /// Writes an i64 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to write to
+    /// * `value` - Value to write
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value would extend
+    /// past the end of the buffer. Nothing is written in that case.
+    #[inline(always)]
+    fn try_put_i64_le(&mut self, offset: usize, value: i64) -> Result<(), DecodeError> {
+        self.try_put_bytes(offset, &value.to_le_bytes())
+    }
+
+    /// Writes an f32 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to write to
+    /// * `value` - Value to write
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value would extend
+    /// past the end of the buffer. Nothing is written in that case.
+    #[inline(always)]
+    fn try_put_f32_le(&mut self, offset: usize, value: f32) -> Result<(), DecodeError> {
+        self.try_put_u32_le(offset, value.to_bits())
+    }
+
+    /// Writes an f64 in little-endian at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to write to
+    /// * `value` - Value to write
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value would extend
+    /// past the end of the buffer. Nothing is written in that case.
+    #[inline(always)]
+    fn try_put_f64_le(&mut self, offset: usize, value: f64) -> Result<(), DecodeError> {
+        self.try_put_u64_le(offset, value.to_bits())
+    }
+
+    /// Writes a byte slice at the given offset, checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to write to
+    /// * `src` - Source bytes to copy
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the bytes would extend
+    /// past the end of the buffer. Nothing is written in that case.
+    #[inline(always)]
+    fn try_put_bytes(&mut self, offset: usize, src: &[u8]) -> Result<(), DecodeError> {
+        checked_range_mut(self.as_mut_slice(), offset, src.len())?.copy_from_slice(src);
+        Ok(())
+    }
+
+    /// Writes a string to a fixed-length field, padding with nulls and
+    /// checking bounds.
+    ///
+    /// # Arguments
+    /// * `offset` - Byte offset to write to
+    /// * `value` - String value to write
+    /// * `max_len` - Maximum field length in bytes
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the field would extend
+    /// past the end of the buffer. Nothing is written in that case.
+    #[inline]
+    fn try_put_str(
+        &mut self,
+        offset: usize,
+        value: &str,
+        max_len: usize,
+    ) -> Result<(), DecodeError> {
+        let field = checked_range_mut(self.as_mut_slice(), offset, max_len)?;
+        let bytes = value.as_bytes();
+        let copy_len = bytes.len().min(max_len);
+        field[..copy_len].copy_from_slice(&bytes[..copy_len]);
+        field[copy_len..].fill(0);
+        Ok(())
+    }
+}
+
+/// Returns the `len` bytes at `offset`, or the error a checked access
+/// reports.
+#[inline(always)]
+fn checked_range(buf: &[u8], offset: usize, len: usize) -> Result<&[u8], DecodeError> {
+    offset
+        .checked_add(len)
+        .and_then(|end| buf.get(offset..end))
+        .ok_or_else(|| too_short(buf.len(), offset, len))
+}
+
+/// Mutable counterpart of [`checked_range`].
+#[inline(always)]
+fn checked_range_mut(buf: &mut [u8], offset: usize, len: usize) -> Result<&mut [u8], DecodeError> {
+    let available = buf.len();
+    offset
+        .checked_add(len)
+        .and_then(|end| buf.get_mut(offset..end))
+        .ok_or_else(|| too_short(available, offset, len))
+}
+
+/// Copies the `N` bytes at `offset` out of `buf`, checking bounds.
+#[inline(always)]
+fn checked_array<const N: usize>(buf: &[u8], offset: usize) -> Result<[u8; N], DecodeError> {
+    let mut array = [0u8; N];
+    array.copy_from_slice(checked_range(buf, offset, N)?);
+    Ok(array)
+}
+
+/// Reads `N` bytes at `offset` with an unaligned pointer read.
+///
+/// # Safety
+/// `offset + N` must not exceed `buf.len()`.
+#[cfg(feature = "unchecked")]
+#[inline(always)]
+unsafe fn unchecked_array<const N: usize>(buf: &[u8], offset: usize) -> [u8; N] {
+    debug_assert!(offset <= buf.len() && buf.len() - offset >= N);
+    // SAFETY: the caller guarantees `offset..offset + N` is within `buf`.
+    unsafe { core::ptr::read_unaligned(buf.as_ptr().add(offset).cast::<[u8; N]>()) }
+}
+
+#[cold]
+fn too_short(available: usize, offset: usize, len: usize) -> DecodeError {
+    DecodeError::BufferTooShort {
+        required: offset.saturating_add(len),
+        available,
+    }
+}
+
+/// Implement ReadBuffer for byte slices.
+impl ReadBuffer for [u8] {
+    #[inline(always)]
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+}
+
+/// Implement WriteBuffer for byte slices.
+impl WriteBuffer for [u8] {
+    #[inline(always)]
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+/// Implement ReadBuffer for `Vec<u8>`.
+#[cfg(feature = "alloc")]
+impl ReadBuffer for Vec<u8> {
+    #[inline(always)]
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+}
+
+/// Implement WriteBuffer for `Vec<u8>`.
+#[cfg(feature = "alloc")]
+impl WriteBuffer for Vec<u8> {
+    #[inline(always)]
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+/// Implement ReadBuffer for `bytes::Bytes`.
+#[cfg(feature = "bytes")]
+impl ReadBuffer for bytes::Bytes {
+    #[inline(always)]
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        bytes::Bytes::len(self)
+    }
+}
+
+/// Implement ReadBuffer for `bytes::BytesMut`.
+#[cfg(feature = "bytes")]
+impl ReadBuffer for bytes::BytesMut {
+    #[inline(always)]
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        bytes::BytesMut::len(self)
+    }
+}
+
+/// Implement WriteBuffer for `bytes::BytesMut`.
+#[cfg(feature = "bytes")]
+impl WriteBuffer for bytes::BytesMut {
+    #[inline(always)]
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+/// Read-only view of untrusted bytes whose accessors check bounds.
+///
+/// Each read returns [`DecodeError::BufferTooShort`] instead of panicking
+/// when it would run past the end, so transports can decode frames off the
+/// wire without `catch_unwind`. Slices and sub-views borrowed from it live
+/// as long as the underlying bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckedReadBuffer<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> CheckedReadBuffer<'a> {
+    /// Wraps `bytes`.
+    #[must_use]
+    pub const fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// Returns the wrapped bytes.
+    #[must_use]
+    pub const fn as_slice(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the length of the view in bytes.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns true if the view is empty.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Returns the `len` bytes at `offset` as a narrower view, e.g. one
+    /// frame of a datagram.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the range extends past
+    /// the end of the view.
+    pub fn view(&self, offset: usize, len: usize) -> Result<Self, DecodeError> {
+        checked_range(self.bytes, offset, len).map(Self::new)
+    }
+
+    /// Reads a u8 at the given offset.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the view.
+    #[inline(always)]
+    pub fn get_u8(&self, offset: usize) -> Result<u8, DecodeError> {
+        self.bytes.try_get_u8(offset)
+    }
+
+    /// Reads an i8 at the given offset.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the view.
+    #[inline(always)]
+    pub fn get_i8(&self, offset: usize) -> Result<i8, DecodeError> {
+        self.bytes.try_get_i8(offset)
+    }
+
+    /// Reads a u16 in little-endian at the given offset.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the view.
+    #[inline(always)]
+    pub fn get_u16_le(&self, offset: usize) -> Result<u16, DecodeError> {
+        self.bytes.try_get_u16_le(offset)
+    }
+
+    /// Reads an i16 in little-endian at the given offset.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the view.
+    #[inline(always)]
+    pub fn get_i16_le(&self, offset: usize) -> Result<i16, DecodeError> {
+        self.bytes.try_get_i16_le(offset)
+    }
+
+    /// Reads a u32 in little-endian at the given offset.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the view.
     #[inline(always)]
-    fn as_slice(&self) -> &[u8] {
-        self
+    pub fn get_u32_le(&self, offset: usize) -> Result<u32, DecodeError> {
+        self.bytes.try_get_u32_le(offset)
     }
 
+    /// Reads an i32 in little-endian at the given offset.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the view.
     #[inline(always)]
-    fn len(&self) -> usize {
-        <[u8]>::len(self)
+    pub fn get_i32_le(&self, offset: usize) -> Result<i32, DecodeError> {
+        self.bytes.try_get_i32_le(offset)
     }
-}
 
-/// Implement WriteBuffer for byte slices.
-impl WriteBuffer for [u8] {
+    /// Reads a u64 in little-endian at the given offset.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the view.
     #[inline(always)]
-    fn as_mut_slice(&mut self) -> &mut [u8] {
-        self
+    pub fn get_u64_le(&self, offset: usize) -> Result<u64, DecodeError> {
+        self.bytes.try_get_u64_le(offset)
     }
-}
 
-/// Implement ReadBuffer for `Vec<u8>`.
-impl ReadBuffer for Vec<u8> {
+    /// Reads an i64 in little-endian at the given offset.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the view.
     #[inline(always)]
-    fn as_slice(&self) -> &[u8] {
-        self
+    pub fn get_i64_le(&self, offset: usize) -> Result<i64, DecodeError> {
+        self.bytes.try_get_i64_le(offset)
     }
 
+    /// Reads an f32 in little-endian at the given offset.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the view.
     #[inline(always)]
-    fn len(&self) -> usize {
-        Vec::len(self)
+    pub fn get_f32_le(&self, offset: usize) -> Result<f32, DecodeError> {
+        self.bytes.try_get_f32_le(offset)
     }
-}
 
-/// Implement WriteBuffer for `Vec<u8>`.
-impl WriteBuffer for Vec<u8> {
+    /// Reads an f64 in little-endian at the given offset.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the value extends past
+    /// the end of the view.
     #[inline(always)]
-    fn as_mut_slice(&mut self) -> &mut [u8] {
-        self
+    pub fn get_f64_le(&self, offset: usize) -> Result<f64, DecodeError> {
+        self.bytes.try_get_f64_le(offset)
+    }
+
+    /// Returns the `len` bytes at `offset`.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the range extends past
+    /// the end of the view.
+    #[inline(always)]
+    pub fn get_bytes(&self, offset: usize, len: usize) -> Result<&'a [u8], DecodeError> {
+        checked_range(self.bytes, offset, len)
+    }
+
+    /// Reads a fixed-length character array as a string slice.
+    /// Trims null bytes from the end.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the field extends past
+    /// the end of the view, or [`DecodeError::InvalidUtf8`] with the offset
+    /// of the first invalid byte.
+    #[inline]
+    pub fn get_str(&self, offset: usize, len: usize) -> Result<&'a str, DecodeError> {
+        let bytes = self.get_bytes(offset, len)?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(len);
+        core::str::from_utf8(&bytes[..end]).map_err(|e| DecodeError::InvalidUtf8 {
+            offset: offset + e.valid_up_to(),
+        })
     }
 }
 
@@ -407,14 +1177,201 @@ impl<const N: usize> AsMut<[u8]> for AlignedBuffer<N> {
     }
 }
 
-impl<const N: usize> std::fmt::Debug for AlignedBuffer<N> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<const N: usize> core::fmt::Debug for AlignedBuffer<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("AlignedBuffer")
             .field("capacity", &N)
             .finish()
     }
 }
 
+/// Size of a cache line in bytes, the alignment of [`AlignedBuffer`] and
+/// [`AlignedVec`].
+pub const CACHE_LINE_SIZE: usize = 64;
+
+/// One cache line of [`AlignedVec`] storage.
+#[cfg(feature = "alloc")]
+#[repr(C, align(64))]
+#[derive(Clone, Copy)]
+struct CacheLine([u8; CACHE_LINE_SIZE]);
+
+#[cfg(feature = "alloc")]
+impl CacheLine {
+    const ZERO: Self = Self([0u8; CACHE_LINE_SIZE]);
+}
+
+/// Heap-allocated, growable, cache-line aligned buffer.
+///
+/// The heap counterpart of [`AlignedBuffer`] for buffers too large for the
+/// stack, such as multi-megabyte market data snapshots. The first byte is
+/// always 64-byte aligned, and bytes added by [`resize`](Self::resize) or
+/// [`extend_from_slice`](Self::extend_from_slice) start out zeroed.
+///
+/// Requires the `alloc` feature.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Default)]
+pub struct AlignedVec {
+    /// Backing storage; every byte past `len` is kept zeroed.
+    lines: Vec<CacheLine>,
+    len: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl AlignedVec {
+    /// Creates an empty buffer without allocating.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            lines: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Creates an empty buffer with room for at least `capacity` bytes.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            lines: Vec::with_capacity(capacity.div_ceil(CACHE_LINE_SIZE)),
+            len: 0,
+        }
+    }
+
+    /// Creates a buffer of `len` zeroed bytes.
+    #[must_use]
+    pub fn zeroed(len: usize) -> Self {
+        let mut buffer = Self::new();
+        buffer.resize(len);
+        buffer
+    }
+
+    /// Returns the number of bytes the buffer holds without reallocating.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.lines.capacity() * CACHE_LINE_SIZE
+    }
+
+    /// Reserves room for at least `additional` more bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = (self.len + additional).div_ceil(CACHE_LINE_SIZE);
+        self.lines.reserve(needed.saturating_sub(self.lines.len()));
+    }
+
+    /// Grows or shrinks the buffer to `new_len` bytes, zero-filling any new
+    /// bytes.
+    pub fn resize(&mut self, new_len: usize) {
+        if new_len < self.len {
+            let len = self.len;
+            self.all_bytes_mut()[new_len..len].fill(0);
+        }
+        self.lines
+            .resize(new_len.div_ceil(CACHE_LINE_SIZE), CacheLine::ZERO);
+        self.len = new_len;
+    }
+
+    /// Appends `bytes` to the end of the buffer.
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        let start = self.len;
+        self.resize(start + bytes.len());
+        self.all_bytes_mut()[start..start + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// Empties the buffer, keeping its allocation.
+    pub fn clear(&mut self) {
+        self.resize(0);
+    }
+
+    /// Returns every byte of the backing storage, including the zeroed
+    /// tail past `len`.
+    fn all_bytes(&self) -> &[u8] {
+        // SAFETY: `CacheLine` is a `repr(C)` wrapper around a byte array, so
+        // the lines are `lines.len() * CACHE_LINE_SIZE` contiguous,
+        // initialized bytes, and `u8` has no alignment requirement.
+        unsafe {
+            core::slice::from_raw_parts(
+                self.lines.as_ptr().cast::<u8>(),
+                self.lines.len() * CACHE_LINE_SIZE,
+            )
+        }
+    }
+
+    /// Mutable counterpart of [`all_bytes`](Self::all_bytes).
+    fn all_bytes_mut(&mut self) -> &mut [u8] {
+        // SAFETY: as in `all_bytes`; the slice borrows `lines` mutably, so
+        // it is the only reference to the storage.
+        unsafe {
+            core::slice::from_raw_parts_mut(
+                self.lines.as_mut_ptr().cast::<u8>(),
+                self.lines.len() * CACHE_LINE_SIZE,
+            )
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ReadBuffer for AlignedVec {
+    #[inline(always)]
+    fn as_slice(&self) -> &[u8] {
+        &self.all_bytes()[..self.len]
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl WriteBuffer for AlignedVec {
+    #[inline(always)]
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        let len = self.len;
+        &mut self.all_bytes_mut()[..len]
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<[u8]> for AlignedVec {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsMut<[u8]> for AlignedVec {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl PartialEq for AlignedVec {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Eq for AlignedVec {}
+
+#[cfg(feature = "alloc")]
+impl From<&[u8]> for AlignedVec {
+    fn from(bytes: &[u8]) -> Self {
+        let mut buffer = Self::with_capacity(bytes.len());
+        buffer.extend_from_slice(bytes);
+        buffer
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Debug for AlignedVec {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AlignedVec")
+            .field("len", &self.len)
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
 /// Default buffer size for the pool (64KB).
 pub const DEFAULT_BUFFER_SIZE: usize = 65536;
 
@@ -422,35 +1379,141 @@ pub const DEFAULT_BUFFER_SIZE: usize = 65536;
 ///
 /// The pool uses a lock-free queue for thread-safe buffer acquisition
 /// and release with minimal contention.
-pub struct BufferPool {
-    buffers: Arc<ArrayQueue<Box<AlignedBuffer<DEFAULT_BUFFER_SIZE>>>>,
+///
+/// `N` is the size class of the pooled buffers. [`BufferPool::new`] builds
+/// the default 64KB class; other classes are built with
+/// [`with_capacity`](Self::with_capacity), e.g.
+/// `BufferPool::<{ 4 * 1024 * 1024 }>::with_capacity(8)` for snapshot-sized
+/// buffers. Buffers are allocated directly on the heap, so large classes
+/// never touch the stack.
+///
+/// In debug builds the last [`canary::CANARY_LEN`] bytes of every pooled
+/// buffer are a canary region; callers must stay within
+/// [`USABLE_LEN`](Self::USABLE_LEN) bytes, and [`release`](Self::release)
+/// panics if the canary was overwritten.
+///
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+pub struct BufferPool<const N: usize = DEFAULT_BUFFER_SIZE> {
+    buffers: Arc<ArrayQueue<Box<AlignedBuffer<N>>>>,
     capacity: usize,
+    counters: Arc<PoolCounters>,
 }
 
+#[cfg(feature = "std")]
 impl BufferPool {
-    /// Creates a new buffer pool with the specified capacity.
+    /// Creates a new pool of [`DEFAULT_BUFFER_SIZE`] buffers.
     ///
     /// # Arguments
     /// * `capacity` - Maximum number of buffers in the pool
     #[must_use]
     pub fn new(capacity: usize) -> Self {
+        Self::with_capacity(capacity)
+    }
+
+    /// Returns the number of bytes callers may write in a pooled buffer.
+    ///
+    /// This is the full buffer size in release builds, and excludes the
+    /// trailing canary region in debug builds.
+    #[inline]
+    #[must_use]
+    pub const fn usable_len() -> usize {
+        Self::USABLE_LEN
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> BufferPool<N> {
+    /// Size of every pooled buffer in bytes.
+    pub const BUFFER_SIZE: usize = N;
+
+    /// Number of bytes callers may write in a pooled buffer: the full
+    /// buffer in release builds, without the trailing canary region in
+    /// debug builds.
+    pub const USABLE_LEN: usize = if canary::ENABLED {
+        N - canary::CANARY_LEN
+    } else {
+        N
+    };
+
+    /// Creates a new pool of `N`-byte buffers.
+    ///
+    /// # Arguments
+    /// * `capacity` - Maximum number of buffers in the pool
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
         let buffers = ArrayQueue::new(capacity);
         for _ in 0..capacity {
-            let _ = buffers.push(Box::new(AlignedBuffer::zeroed()));
+            let mut buffer = Self::allocate();
+            Self::fill_canary(&mut buffer);
+            let _ = buffers.push(buffer);
         }
         Self {
             buffers: Arc::new(buffers),
             capacity,
+            counters: Arc::default(),
         }
     }
 
+    /// Creates a new pool of `N`-byte buffers placed as `config` says.
+    ///
+    /// The buffers are allocated with the calling thread's memory policy
+    /// bound to the configured NUMA node, then the rest of the
+    /// configuration is applied as [`memory::apply`](crate::memory::apply)
+    /// describes. Pooled buffers are heap allocations, so they cannot come
+    /// from the huge-page pool, and transparent huge pages only help
+    /// buffers of a huge page or more.
+    ///
+    /// # Arguments
+    /// * `capacity` - Maximum number of buffers in the pool
+    /// * `config` - NUMA node, huge-page and prefault options
+    ///
+    /// # Errors
+    /// In strict mode, returns an error if the placement cannot be applied.
+    pub fn with_memory(capacity: usize, config: &MemoryConfig) -> std::io::Result<Self> {
+        let buffers = ArrayQueue::new(capacity);
+        crate::memory::on_node(config, || {
+            for _ in 0..capacity {
+                let mut buffer = Self::allocate();
+                crate::memory::apply(buffer.as_mut_slice(), config)?;
+                Self::fill_canary(&mut buffer);
+                let _ = buffers.push(buffer);
+            }
+            Ok::<_, std::io::Error>(())
+        })??;
+        Ok(Self {
+            buffers: Arc::new(buffers),
+            capacity,
+            counters: Arc::default(),
+        })
+    }
+
     /// Acquires a buffer from the pool.
     ///
     /// Returns `None` if the pool is empty.
     #[inline]
     #[must_use]
-    pub fn acquire(&self) -> Option<Box<AlignedBuffer<DEFAULT_BUFFER_SIZE>>> {
-        self.buffers.pop()
+    pub fn acquire(&self) -> Option<Box<AlignedBuffer<N>>> {
+        let buffer = self.buffers.pop();
+        if buffer.is_some() {
+            self.counters.acquired();
+        } else {
+            self.counters.missed();
+        }
+        buffer
+    }
+
+    /// Acquires a buffer that returns to the pool when dropped.
+    ///
+    /// Returns `None` if the pool is empty.
+    #[inline]
+    #[must_use]
+    pub fn acquire_owned(&self) -> Option<PooledBuffer<N>> {
+        Some(PooledBuffer {
+            buffer: Some(self.acquire()?),
+            len: 0,
+            pool: self.clone(),
+        })
     }
 
     /// Releases a buffer back to the pool.
@@ -459,12 +1522,42 @@ impl BufferPool {
     ///
     /// # Arguments
     /// * `buffer` - Buffer to release
+    ///
+    /// # Panics
+    /// In debug builds, panics if anything was written past
+    /// [`USABLE_LEN`](Self::USABLE_LEN).
     #[inline]
-    pub fn release(&self, mut buffer: Box<AlignedBuffer<DEFAULT_BUFFER_SIZE>>) {
+    pub fn release(&self, mut buffer: Box<AlignedBuffer<N>>) {
+        if canary::ENABLED {
+            let usable = Self::USABLE_LEN;
+            canary::check(&buffer.as_slice()[usable..], usable, "pooled buffer");
+        }
         buffer.as_mut_slice().fill(0);
+        Self::fill_canary(&mut buffer);
+        self.counters.released();
         let _ = self.buffers.push(buffer);
     }
 
+    /// Allocates a zeroed buffer straight on the heap.
+    fn allocate() -> Box<AlignedBuffer<N>> {
+        const {
+            assert!(
+                N > canary::CANARY_LEN,
+                "pooled buffers must be larger than the canary region"
+            );
+        }
+        // SAFETY: `AlignedBuffer<N>` is a byte array, for which all zeroes
+        // is a valid value.
+        unsafe { Box::<AlignedBuffer<N>>::new_zeroed().assume_init() }
+    }
+
+    /// Fills the trailing canary region of a buffer in debug builds.
+    fn fill_canary(buffer: &mut AlignedBuffer<N>) {
+        if canary::ENABLED {
+            canary::fill(&mut buffer.as_mut_slice()[Self::USABLE_LEN..]);
+        }
+    }
+
     /// Returns the capacity of the pool.
     #[must_use]
     pub fn capacity(&self) -> usize {
@@ -476,26 +1569,137 @@ impl BufferPool {
     pub fn available(&self) -> usize {
         self.buffers.len()
     }
+
+    /// Returns the pool's acquire, miss and high-water counters, shared by
+    /// every clone.
+    #[must_use]
+    pub fn stats(&self) -> PoolStats {
+        self.counters.snapshot()
+    }
 }
 
-impl Clone for BufferPool {
+#[cfg(feature = "std")]
+impl<const N: usize> Clone for BufferPool<N> {
     fn clone(&self) -> Self {
         Self {
             buffers: Arc::clone(&self.buffers),
             capacity: self.capacity,
+            counters: Arc::clone(&self.counters),
         }
     }
 }
 
-impl std::fmt::Debug for BufferPool {
+#[cfg(feature = "std")]
+impl<const N: usize> std::fmt::Debug for BufferPool<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("BufferPool")
+            .field("buffer_size", &N)
             .field("capacity", &self.capacity)
             .field("available", &self.buffers.len())
             .finish()
     }
 }
 
+/// Buffer acquired from a [`BufferPool`] with
+/// [`acquire_owned`](BufferPool::acquire_owned), released back to it when
+/// dropped.
+///
+/// Dereferences to its first [`len`](Self::len) bytes. Fill it through
+/// [`writable`](Self::writable) and then [`set_len`](Self::set_len), e.g.
+/// with the number of bytes a socket read returned, and move it
+/// downstream; the buffer is recycled wherever the last owner drops it.
+#[cfg(feature = "std")]
+pub struct PooledBuffer<const N: usize = DEFAULT_BUFFER_SIZE> {
+    /// Taken out in `drop` to hand back to the pool.
+    buffer: Option<Box<AlignedBuffer<N>>>,
+    len: usize,
+    pool: BufferPool<N>,
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> PooledBuffer<N> {
+    /// Returns the number of valid bytes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no byte is valid.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the whole writable region, [`BufferPool::USABLE_LEN`] bytes
+    /// long, regardless of the current length.
+    pub fn writable(&mut self) -> &mut [u8] {
+        &mut self.aligned_mut().as_mut_slice()[..BufferPool::<N>::USABLE_LEN]
+    }
+
+    /// Sets the number of valid bytes.
+    ///
+    /// # Panics
+    /// Panics if `len` exceeds [`BufferPool::USABLE_LEN`].
+    pub fn set_len(&mut self, len: usize) {
+        assert!(
+            len <= BufferPool::<N>::USABLE_LEN,
+            "length {len} exceeds pooled buffer of {} bytes",
+            BufferPool::<N>::USABLE_LEN
+        );
+        self.len = len;
+    }
+
+    fn aligned(&self) -> &AlignedBuffer<N> {
+        self.buffer.as_ref().expect("buffer present until drop")
+    }
+
+    fn aligned_mut(&mut self) -> &mut AlignedBuffer<N> {
+        self.buffer.as_mut().expect("buffer present until drop")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> std::ops::Deref for PooledBuffer<N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.aligned().as_slice()[..self.len]
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> std::ops::DerefMut for PooledBuffer<N> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        let len = self.len;
+        &mut self.aligned_mut().as_mut_slice()[..len]
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> AsRef<[u8]> for PooledBuffer<N> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> Drop for PooledBuffer<N> {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            self.pool.release(buffer);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> std::fmt::Debug for PooledBuffer<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PooledBuffer")
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -558,6 +1762,86 @@ mod tests {
         assert_eq!(buf.get_bytes(0, data.len()), data);
     }
 
+    #[test]
+    fn test_checked_access_reports_short_buffers() {
+        let mut buf = [0u8; 8];
+
+        assert_eq!(buf.try_put_u32_le(4, 0xDEAD_BEEF), Ok(()));
+        assert_eq!(buf.try_get_u32_le(4), Ok(0xDEAD_BEEF));
+        assert!(buf.try_put_i64_le(usize::MAX, 7).is_err());
+        assert_eq!(
+            buf.try_put_u64_le(1, 7),
+            Err(DecodeError::BufferTooShort {
+                required: 9,
+                available: 8,
+            })
+        );
+        assert_eq!(buf.get_u8(1), 0, "failed write left the buffer untouched");
+        assert_eq!(
+            buf.try_get_f64_le(4),
+            Err(DecodeError::BufferTooShort {
+                required: 12,
+                available: 8,
+            })
+        );
+        assert!(buf.try_get_bytes(usize::MAX, 2).is_err());
+
+        assert_eq!(buf.try_put_str(0, "AB", 4), Ok(()));
+        assert_eq!(buf.try_get_str(0, 4), Ok("AB"));
+        assert!(buf.try_put_str(6, "AB", 4).is_err());
+        buf[0] = 0xFF;
+        assert_eq!(
+            buf.try_get_str(0, 4),
+            Err(DecodeError::InvalidUtf8 { offset: 0 })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "unchecked")]
+    fn test_unchecked_reads_match_checked_reads() {
+        let mut buf = AlignedBuffer::<64>::new();
+        buf.put_u8(0, 0xAB);
+        buf.put_i16_le(1, -2);
+        buf.put_u32_le(3, 0xDEAD_BEEF);
+        buf.put_i64_le(7, i64::MIN + 1);
+        buf.put_f64_le(15, -1.25);
+        let slice = buf.as_slice();
+
+        // SAFETY: every read is within the 64-byte buffer.
+        unsafe {
+            assert_eq!(slice.get_u8_unchecked(0), slice.get_u8(0));
+            assert_eq!(slice.get_i8_unchecked(0), slice.get_i8(0));
+            assert_eq!(slice.get_i16_le_unchecked(1), -2);
+            assert_eq!(slice.get_u16_le_unchecked(1), slice.get_u16_le(1));
+            assert_eq!(slice.get_u32_le_unchecked(3), 0xDEAD_BEEF);
+            assert_eq!(slice.get_i32_le_unchecked(3), slice.get_i32_le(3));
+            assert_eq!(slice.get_i64_le_unchecked(7), i64::MIN + 1);
+            assert_eq!(slice.get_u64_le_unchecked(7), slice.get_u64_le(7));
+            assert_eq!(slice.get_f64_le_unchecked(15), -1.25);
+            assert_eq!(slice.get_f32_le_unchecked(60), slice.get_f32_le(60));
+        }
+    }
+
+    #[test]
+    fn test_checked_read_buffer() {
+        let mut data = [0u8; 12];
+        data.put_u16_le(0, 10);
+        data.put_str(2, "ES", 4);
+        data.put_i32_le(6, -5);
+
+        let frame = CheckedReadBuffer::new(&data);
+        assert_eq!(frame.get_u16_le(0), Ok(10));
+        assert_eq!(frame.get_str(2, 4), Ok("ES"));
+        assert_eq!(frame.get_i32_le(6), Ok(-5));
+        assert!(frame.get_u32_le(10).is_err());
+
+        let body = frame.view(2, 8).expect("in bounds");
+        assert_eq!(body.len(), 8);
+        assert_eq!(body.get_i32_le(4), Ok(-5));
+        assert!(body.get_u8(8).is_err());
+        assert!(frame.view(8, 8).is_err());
+    }
+
     #[test]
     fn test_read_write_str() {
         let mut buf: AlignedBuffer<64> = AlignedBuffer::new();
@@ -570,6 +1854,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_buffer_pool() {
         let pool = BufferPool::new(4);
         assert_eq!(pool.capacity(), 4);
@@ -589,12 +1874,30 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_buffer_pool_empty() {
         let pool = BufferPool::new(1);
         let _buf = pool.acquire().expect("Should acquire buffer");
         assert!(pool.acquire().is_none(), "Pool should be empty");
     }
 
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_buffer_pool_stats() {
+        let pool = BufferPool::new(2);
+        let a = pool.acquire().expect("Should acquire buffer");
+        let b = pool.clone().acquire_owned().expect("Should acquire buffer");
+        assert!(pool.acquire().is_none());
+        pool.release(a);
+        drop(b);
+
+        let stats = pool.stats();
+        assert_eq!(stats.acquires, 2);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.in_use, 0);
+        assert_eq!(stats.high_water, 2);
+    }
+
     #[test]
     fn test_slice_read_buffer() {
         let data: &[u8] = &[0x12, 0x34, 0x56, 0x78];
@@ -604,6 +1907,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "alloc")]
     fn test_vec_write_buffer() {
         let mut data = vec![0u8; 16];
         data.put_u32_le(0, 0xDEADBEEF);
@@ -636,6 +1940,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_buffer_pool_clone() {
         let pool1 = BufferPool::new(2);
         let pool2 = pool1.clone();
@@ -651,6 +1956,51 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
+    fn test_buffer_pool_usable_len() {
+        let pool = BufferPool::new(1);
+        let mut buf = pool.acquire().expect("Should acquire buffer");
+        let usable = BufferPool::usable_len();
+        assert!(usable <= DEFAULT_BUFFER_SIZE);
+
+        buf.as_mut_slice()[..usable].fill(0xFF);
+        pool.release(buf);
+
+        let buf = pool.acquire().expect("Should acquire buffer");
+        assert!(buf.as_slice()[..usable].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "canary overwritten in pooled buffer")]
+    fn test_buffer_pool_detects_overrun() {
+        let pool = BufferPool::new(1);
+        let mut buf = pool.acquire().expect("Should acquire buffer");
+        buf.put_u32_le(BufferPool::usable_len() - 2, 0xDEAD_BEEF);
+        pool.release(buf);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_pooled_buffer_returns_on_drop() {
+        let pool = BufferPool::new(1);
+        let mut buf = pool.acquire_owned().expect("Should acquire buffer");
+        assert!(buf.is_empty());
+        assert_eq!(buf.writable().len(), BufferPool::usable_len());
+
+        buf.writable()[..3].copy_from_slice(b"abc");
+        buf.set_len(3);
+        assert_eq!(&*buf, b"abc");
+        assert!(pool.acquire_owned().is_none());
+
+        let moved = std::thread::spawn(move || buf.to_vec()).join().unwrap();
+        assert_eq!(moved, b"abc");
+        assert_eq!(pool.available(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
     fn test_buffer_pool_debug() {
         let pool = BufferPool::new(4);
         let debug_str = format!("{:?}", pool);
@@ -674,6 +2024,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "alloc")]
     fn test_vec_write_buffer_all_types() {
         let mut data = vec![0u8; 64];
 
@@ -711,6 +2062,83 @@ mod tests {
         assert_eq!(data.get_str(52, 8), "hello");
     }
 
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_aligned_vec_grows_aligned() {
+        let mut buf = AlignedVec::new();
+        assert!(buf.is_empty());
+
+        buf.resize(100);
+        buf.put_u64_le(92, 0x0102_0304_0506_0708);
+        assert_eq!(buf.as_slice().as_ptr() as usize % CACHE_LINE_SIZE, 0);
+
+        buf.resize(4 * 1024 * 1024);
+        assert_eq!(buf.len(), 4 * 1024 * 1024);
+        assert!(buf.capacity() >= buf.len());
+        assert_eq!(buf.as_slice().as_ptr() as usize % CACHE_LINE_SIZE, 0);
+        assert_eq!(buf.get_u64_le(92), 0x0102_0304_0506_0708);
+        assert!(buf.as_slice()[100..].iter().all(|&b| b == 0));
+
+        buf.extend_from_slice(b"tail");
+        assert_eq!(buf.get_bytes(4 * 1024 * 1024, 4), b"tail");
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_aligned_vec_shrink_zeroes_dropped_bytes() {
+        let mut buf = AlignedVec::from(&[0xFFu8; 40][..]);
+        buf.resize(10);
+        buf.resize(40);
+        assert_eq!(buf.get_bytes(0, 10), &[0xFF; 10]);
+        assert!(buf.as_slice()[10..].iter().all(|&b| b == 0));
+
+        buf.clear();
+        assert!(buf.is_empty());
+        assert_eq!(buf, AlignedVec::new());
+        assert!(format!("{buf:?}").contains("AlignedVec"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_buffer_pool_size_class() {
+        const SIZE: usize = 4 * 1024 * 1024;
+        let pool = BufferPool::<SIZE>::with_capacity(2);
+        assert_eq!(BufferPool::<SIZE>::BUFFER_SIZE, SIZE);
+
+        let mut buf = pool.acquire_owned().expect("Should acquire buffer");
+        assert_eq!(buf.writable().len(), BufferPool::<SIZE>::USABLE_LEN);
+        buf.writable()[SIZE / 2] = 7;
+        buf.set_len(SIZE / 2 + 1);
+        assert_eq!(buf[SIZE / 2], 7);
+        drop(buf);
+
+        assert_eq!(pool.available(), 2);
+        assert!(format!("{pool:?}").contains("buffer_size"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_buffer_pool_with_memory() {
+        use crate::memory::HugePages;
+
+        const SIZE: usize = 2 * 1024 * 1024;
+        let config = MemoryConfig::new()
+            .numa_node(0)
+            .huge_pages(HugePages::Transparent)
+            .prefault(true);
+        let pool = BufferPool::<SIZE>::with_memory(2, &config).unwrap();
+        assert_eq!(pool.available(), 2);
+
+        let mut buf = pool.acquire_owned().expect("Should acquire buffer");
+        assert!(buf.writable().iter().all(|&b| b == 0));
+        buf.writable()[SIZE / 2] = 1;
+        drop(buf);
+        assert_eq!(pool.available(), 2);
+
+        let strict = MemoryConfig::new().numa_node(1000).strict(true);
+        assert!(BufferPool::<SIZE>::with_memory(1, &strict).is_err());
+    }
+
     #[test]
     fn test_aligned_buffer_default() {
         let buf: AlignedBuffer<32> = AlignedBuffer::default();
@@ -0,0 +1,89 @@
+//! Debug-build canary regions for catching out-of-bounds writes.
+//!
+//! Pooled buffers and shared ring slots keep a known byte pattern in the
+//! memory next to the region callers may write. An encoder with a wrong
+//! offset that writes past its message clobbers the pattern, and the owner
+//! checks it when the memory is handed back (pool release, ring commit)
+//! instead of letting the corruption surface as a decode failure in some
+//! unrelated message later on.
+//!
+//! Checks only run when [`ENABLED`] is true, which is the case in builds
+//! with `debug_assertions`. Release builds skip both filling and checking.
+
+/// True if canary regions are maintained and checked in this build.
+pub const ENABLED: bool = cfg!(debug_assertions);
+
+/// Length of a canary region in bytes.
+pub const CANARY_LEN: usize = 64;
+
+/// Byte pattern stored in canary regions.
+pub const CANARY_BYTE: u8 = 0xA5;
+
+/// Fills `region` with the canary pattern.
+#[inline]
+pub fn fill(region: &mut [u8]) {
+    region.fill(CANARY_BYTE);
+}
+
+/// Returns the offset of the first byte in `region` that no longer holds
+/// the canary pattern, or `None` if the region is intact.
+#[inline]
+#[must_use]
+pub fn find_corruption(region: &[u8]) -> Option<usize> {
+    region.iter().position(|&b| b != CANARY_BYTE)
+}
+
+/// Panics if `region` no longer holds the canary pattern.
+///
+/// # Arguments
+/// * `region` - Canary region to check
+/// * `base` - Offset of `region` within its owner, used in the message
+/// * `owner` - Description of the memory the region guards
+///
+/// # Panics
+/// Panics with the absolute offset of the first corrupted byte.
+#[inline]
+#[track_caller]
+pub fn check(region: &[u8], base: usize, owner: &str) {
+    if let Some(pos) = find_corruption(region) {
+        panic!(
+            "canary overwritten in {owner} at offset {}: found {:#04x}, expected {CANARY_BYTE:#04x}; \
+             something wrote past the end of its message",
+            base + pos,
+            region[pos],
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_and_find() {
+        let mut region = [0u8; CANARY_LEN];
+        assert_eq!(find_corruption(&region), Some(0));
+
+        fill(&mut region);
+        assert_eq!(find_corruption(&region), None);
+
+        region[10] = 0;
+        assert_eq!(find_corruption(&region), Some(10));
+    }
+
+    #[test]
+    fn test_check_intact() {
+        let mut region = [0u8; 8];
+        fill(&mut region);
+        check(&region, 0, "test region");
+    }
+
+    #[test]
+    #[should_panic(expected = "canary overwritten in test region at offset 103")]
+    fn test_check_reports_absolute_offset() {
+        let mut region = [0u8; 8];
+        fill(&mut region);
+        region[3] = 1;
+        check(&region, 100, "test region");
+    }
+}
@@ -0,0 +1,246 @@
+//! CRC32C frame checksums.
+//!
+//! Links that need integrity checking beyond TCP's 16-bit checksum (or
+//! that have none, like shared memory) append a 4-byte CRC32C (Castagnoli)
+//! trailer to each message and verify it on receipt. The checksum uses the
+//! SSE4.2 `crc32` instruction on x86_64 and the CRC extension on aarch64
+//! when the CPU has them, and a table-driven fallback otherwise.
+//!
+//! The trailer is the little-endian CRC32C of the message bytes before it.
+
+use crate::decoder::DecodeError;
+
+/// Length of the checksum trailer in bytes.
+pub const TRAILER_LENGTH: usize = 4;
+
+/// Reflected CRC32C polynomial.
+const POLYNOMIAL: u32 = 0x82F6_3B78;
+
+/// Slicing-by-8 lookup tables.
+static TABLES: [[u32; 256]; 8] = build_tables();
+
+const fn build_tables() -> [[u32; 256]; 8] {
+    let mut tables = [[0u32; 256]; 8];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut crc = byte as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        tables[0][byte] = crc;
+        byte += 1;
+    }
+    let mut slice = 1;
+    while slice < 8 {
+        let mut byte = 0;
+        while byte < 256 {
+            let previous = tables[slice - 1][byte];
+            tables[slice][byte] = (previous >> 8) ^ tables[0][(previous & 0xFF) as usize];
+            byte += 1;
+        }
+        slice += 1;
+    }
+    tables
+}
+
+/// Returns the CRC32C of `data`.
+#[must_use]
+pub fn crc32c(data: &[u8]) -> u32 {
+    crc32c_update(0, data)
+}
+
+/// Extends `crc`, the CRC32C of some preceding bytes, over `data`.
+///
+/// `crc32c_update(crc32c(a), b)` equals the CRC32C of `a` followed by `b`.
+#[must_use]
+pub fn crc32c_update(crc: u32, data: &[u8]) -> u32 {
+    !update_raw(!crc, data)
+}
+
+/// Runs the CRC register over `data` on the fastest available path.
+fn update_raw(state: u32, data: &[u8]) -> u32 {
+    #[cfg(target_arch = "x86_64")]
+    if has_hardware_crc() {
+        // SAFETY: the CPU supports SSE4.2.
+        return unsafe { update_sse42(state, data) };
+    }
+    #[cfg(target_arch = "aarch64")]
+    if has_hardware_crc() {
+        // SAFETY: the CPU supports the CRC extension.
+        return unsafe { update_arm_crc(state, data) };
+    }
+    update_table(state, data)
+}
+
+/// Returns true if the CRC32C instructions are available.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn has_hardware_crc() -> bool {
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    return std::arch::is_x86_feature_detected!("sse4.2");
+    #[cfg(all(feature = "std", target_arch = "aarch64"))]
+    return std::arch::is_aarch64_feature_detected!("crc");
+    #[cfg(all(not(feature = "std"), target_arch = "x86_64"))]
+    return cfg!(target_feature = "sse4.2");
+    #[cfg(all(not(feature = "std"), target_arch = "aarch64"))]
+    return cfg!(target_feature = "crc");
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.2")]
+unsafe fn update_sse42(state: u32, data: &[u8]) -> u32 {
+    use core::arch::x86_64::{_mm_crc32_u8, _mm_crc32_u64};
+
+    let mut state = u64::from(state);
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let word = u64::from_le_bytes(chunk.try_into().expect("8-byte chunk"));
+        state = _mm_crc32_u64(state, word);
+    }
+    let mut state = state as u32;
+    for &byte in chunks.remainder() {
+        state = _mm_crc32_u8(state, byte);
+    }
+    state
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "crc")]
+unsafe fn update_arm_crc(state: u32, data: &[u8]) -> u32 {
+    use core::arch::aarch64::{__crc32cb, __crc32cd};
+
+    let mut state = state;
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let word = u64::from_le_bytes(chunk.try_into().expect("8-byte chunk"));
+        state = __crc32cd(state, word);
+    }
+    for &byte in chunks.remainder() {
+        state = __crc32cb(state, byte);
+    }
+    state
+}
+
+/// Table-driven CRC32C, eight bytes per step.
+fn update_table(mut state: u32, data: &[u8]) -> u32 {
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let low = state ^ u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        state = TABLES[7][(low & 0xFF) as usize]
+            ^ TABLES[6][((low >> 8) & 0xFF) as usize]
+            ^ TABLES[5][((low >> 16) & 0xFF) as usize]
+            ^ TABLES[4][(low >> 24) as usize]
+            ^ TABLES[3][chunk[4] as usize]
+            ^ TABLES[2][chunk[5] as usize]
+            ^ TABLES[1][chunk[6] as usize]
+            ^ TABLES[0][chunk[7] as usize];
+    }
+    for &byte in chunks.remainder() {
+        state = (state >> 8) ^ TABLES[0][((state ^ u32::from(byte)) & 0xFF) as usize];
+    }
+    state
+}
+
+/// Returns the trailer to append after `message`.
+#[must_use]
+pub fn trailer(message: &[u8]) -> [u8; TRAILER_LENGTH] {
+    crc32c(message).to_le_bytes()
+}
+
+/// Returns the trailer to append after a message made of `parts`, such as
+/// the slices of a vectored send.
+#[must_use]
+pub fn trailer_parts<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> [u8; TRAILER_LENGTH] {
+    parts.into_iter().fold(0, crc32c_update).to_le_bytes()
+}
+
+/// Checks the trailer of `frame` and returns the message before it.
+///
+/// # Errors
+/// Returns [`DecodeError::BufferTooShort`] if `frame` is shorter than the
+/// trailer, or [`DecodeError::ChecksumMismatch`] if the trailer does not
+/// match the message.
+pub fn verify(frame: &[u8]) -> Result<&[u8], DecodeError> {
+    let Some(split) = frame.len().checked_sub(TRAILER_LENGTH) else {
+        return Err(DecodeError::BufferTooShort {
+            required: TRAILER_LENGTH,
+            available: frame.len(),
+        });
+    };
+    let (message, tail) = frame.split_at(split);
+    let tail = [tail[0], tail[1], tail[2], tail[3]];
+    verify_trailer(message, tail)?;
+    Ok(message)
+}
+
+/// Checks `message` against a trailer received separately from it.
+///
+/// # Errors
+/// Returns [`DecodeError::ChecksumMismatch`] if `trailer` does not match
+/// the message.
+pub fn verify_trailer(message: &[u8], trailer: [u8; TRAILER_LENGTH]) -> Result<(), DecodeError> {
+    let expected = u32::from_le_bytes(trailer);
+    let actual = crc32c(message);
+    if expected != actual {
+        return Err(DecodeError::ChecksumMismatch { expected, actual });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_vectors() {
+        assert_eq!(crc32c(b""), 0);
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        assert_eq!(crc32c(&[0u8; 32]), 0x8A91_36AA);
+        assert_eq!(crc32c(&[0xFFu8; 32]), 0x62A8_AB43);
+    }
+
+    #[test]
+    fn test_hardware_matches_table() {
+        let data: Vec<u8> = (0..1_000u32).map(|i| (i * 31 % 251) as u8).collect();
+        for len in [0, 1, 7, 8, 9, 63, 64, 65, 1_000] {
+            let slice = &data[..len];
+            assert_eq!(
+                update_raw(!0, slice),
+                update_table(!0, slice),
+                "length {len}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_update_continues_checksum() {
+        let (a, b) = b"hello, checksummed world".split_at(7);
+        assert_eq!(crc32c_update(crc32c(a), b), crc32c(&[a, b].concat()));
+        assert_eq!(trailer_parts([a, b]), trailer(&[a, b].concat()));
+    }
+
+    #[test]
+    fn test_trailer_round_trip() {
+        let mut frame = b"sbe message".to_vec();
+        frame.extend_from_slice(&trailer(&frame));
+        assert_eq!(verify(&frame), Ok(&b"sbe message"[..]));
+
+        frame[0] ^= 1;
+        assert!(matches!(
+            verify(&frame),
+            Err(DecodeError::ChecksumMismatch { .. })
+        ));
+        assert_eq!(
+            verify(&[1, 2]),
+            Err(DecodeError::BufferTooShort {
+                required: 4,
+                available: 2
+            })
+        );
+    }
+}
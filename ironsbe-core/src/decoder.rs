@@ -2,7 +2,7 @@
 //!
 //! This module provides the [`SbeDecoder`] trait for zero-copy message decoding.
 
-use crate::header::MessageHeader;
+use crate::header::{MessageHeader, SbeHeader};
 
 /// Error type for decoding operations.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -47,10 +47,17 @@ pub enum DecodeError {
         /// Minimum supported version.
         min_supported: u16,
     },
+    /// Frame checksum does not match its contents.
+    ChecksumMismatch {
+        /// Checksum carried in the frame trailer.
+        expected: u32,
+        /// Checksum computed over the received bytes.
+        actual: u32,
+    },
 }
 
-impl std::fmt::Display for DecodeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::BufferTooShort {
                 required,
@@ -92,11 +99,33 @@ impl std::fmt::Display for DecodeError {
                     version, min_supported
                 )
             }
+            Self::ChecksumMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "checksum mismatch: expected {:#010x}, actual {:#010x}",
+                    expected, actual
+                )
+            }
         }
     }
 }
 
-impl std::error::Error for DecodeError {}
+impl core::error::Error for DecodeError {}
+
+/// A raw value that matches no variant of an enum.
+///
+/// The error of the `TryFrom<primitive>` conversion `#[derive(SbeEnum)]`
+/// generates; it carries the value as read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownEnumValue<T>(pub T);
+
+impl<T: core::fmt::Display> core::fmt::Display for UnknownEnumValue<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "unknown enum value {}", self.0)
+    }
+}
+
+impl<T: core::fmt::Debug + core::fmt::Display> core::error::Error for UnknownEnumValue<T> {}
 
 /// Trait for zero-copy SBE message decoders.
 ///
@@ -105,6 +134,8 @@ impl std::error::Error for DecodeError {}
 ///
 /// # Type Parameters
 /// * `'a` - Lifetime of the underlying buffer
+/// * `H` - Message header layout of the schema, [`MessageHeader`] unless the
+///   schema's `headerType` composite extends it
 ///
 /// # Example
 /// ```ignore
@@ -113,7 +144,7 @@ impl std::error::Error for DecodeError {}
 /// let symbol = decoder.symbol();
 /// let quantity = decoder.quantity();
 /// ```
-pub trait SbeDecoder<'a>: Sized {
+pub trait SbeDecoder<'a, H: SbeHeader = MessageHeader>: Sized {
     /// Schema template ID for this message type.
     const TEMPLATE_ID: u16;
 
@@ -128,14 +159,86 @@ pub trait SbeDecoder<'a>: Sized {
 
     /// Wraps a buffer to decode a message (zero-copy).
     ///
+    /// The root block length is read from the header that ends at `offset`;
+    /// if no header fits before it, [`BLOCK_LENGTH`](Self::BLOCK_LENGTH) is
+    /// assumed. Use [`wrap_with_block_length`](Self::wrap_with_block_length)
+    /// when the header has already been decoded or is not in `buffer`.
+    ///
+    /// # Arguments
+    /// * `buffer` - Byte buffer containing the message
+    /// * `offset` - Byte offset where the message starts (after header)
+    /// * `acting_version` - Version to use for decoding (for compatibility)
+    ///
+    /// # Returns
+    /// A decoder instance wrapping the buffer.
+    ///
+    /// # Panics
+    /// Panics where [`wrap_with_block_length`](Self::wrap_with_block_length)
+    /// does.
+    fn wrap(buffer: &'a [u8], offset: usize, acting_version: u16) -> Self {
+        let block_length = H::block_length_before(buffer, offset).unwrap_or(Self::BLOCK_LENGTH);
+        Self::wrap_with_block_length(buffer, offset, block_length, acting_version)
+    }
+
+    /// Wraps a buffer to decode a message with a known root block length.
+    ///
     /// # Arguments
     /// * `buffer` - Byte buffer containing the message
     /// * `offset` - Byte offset where the message starts (after header)
+    /// * `acting_block_length` - Block length from the message header, which
+    ///   may differ from [`BLOCK_LENGTH`](Self::BLOCK_LENGTH) for other
+    ///   schema versions; groups and var data start after it
     /// * `acting_version` - Version to use for decoding (for compatibility)
     ///
     /// # Returns
     /// A decoder instance wrapping the buffer.
-    fn wrap(buffer: &'a [u8], offset: usize, acting_version: u16) -> Self;
+    ///
+    /// # Panics
+    /// Decoders that read fields without bounds checks panic if the root
+    /// block does not fit in `buffer`;
+    /// [`try_wrap_with_block_length`](Self::try_wrap_with_block_length)
+    /// reports that as an error instead.
+    fn wrap_with_block_length(
+        buffer: &'a [u8],
+        offset: usize,
+        acting_block_length: u16,
+        acting_version: u16,
+    ) -> Self;
+
+    /// Wraps a buffer like [`wrap`](Self::wrap), returning an error where
+    /// `wrap` would panic.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the decoder requires more of
+    /// the buffer than is available.
+    fn try_wrap(buffer: &'a [u8], offset: usize, acting_version: u16) -> Result<Self, DecodeError> {
+        let block_length = H::block_length_before(buffer, offset).unwrap_or(Self::BLOCK_LENGTH);
+        Self::try_wrap_with_block_length(buffer, offset, block_length, acting_version)
+    }
+
+    /// Wraps a buffer like
+    /// [`wrap_with_block_length`](Self::wrap_with_block_length), returning an
+    /// error where it would panic.
+    ///
+    /// The default forwards to `wrap_with_block_length`, which cannot fail
+    /// for decoders that bounds-check every read.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] if the decoder requires more of
+    /// the buffer than is available.
+    fn try_wrap_with_block_length(
+        buffer: &'a [u8],
+        offset: usize,
+        acting_block_length: u16,
+        acting_version: u16,
+    ) -> Result<Self, DecodeError> {
+        Ok(Self::wrap_with_block_length(
+            buffer,
+            offset,
+            acting_block_length,
+            acting_version,
+        ))
+    }
 
     /// Returns the encoded length of the message in bytes.
     ///
@@ -149,17 +252,17 @@ pub trait SbeDecoder<'a>: Sized {
     ///
     /// # Errors
     /// Returns an error if the template ID or schema ID doesn't match.
-    fn validate_header(header: &MessageHeader) -> Result<(), DecodeError> {
-        if header.template_id != Self::TEMPLATE_ID {
+    fn validate_header(header: &H) -> Result<(), DecodeError> {
+        if header.template_id() != Self::TEMPLATE_ID {
             return Err(DecodeError::TemplateMismatch {
                 expected: Self::TEMPLATE_ID,
-                actual: header.template_id,
+                actual: header.template_id(),
             });
         }
-        if header.schema_id != Self::SCHEMA_ID {
+        if header.schema_id() != Self::SCHEMA_ID {
             return Err(DecodeError::SchemaMismatch {
                 expected: Self::SCHEMA_ID,
-                actual: header.schema_id,
+                actual: header.schema_id(),
             });
         }
         Ok(())
@@ -173,17 +276,13 @@ pub trait SbeDecoder<'a>: Sized {
     /// # Errors
     /// Returns an error if the header is invalid or buffer is too short.
     fn decode(buffer: &'a [u8]) -> Result<Self, DecodeError> {
-        if buffer.len() < MessageHeader::ENCODED_LENGTH {
-            return Err(DecodeError::BufferTooShort {
-                required: MessageHeader::ENCODED_LENGTH,
-                available: buffer.len(),
-            });
-        }
-
-        let header = MessageHeader::wrap(buffer, 0);
+        let header = H::try_wrap(buffer, 0).ok_or(DecodeError::BufferTooShort {
+            required: H::ENCODED_LENGTH,
+            available: buffer.len(),
+        })?;
         Self::validate_header(&header)?;
 
-        let required_len = MessageHeader::ENCODED_LENGTH + header.block_length as usize;
+        let required_len = H::ENCODED_LENGTH + usize::from(header.block_length());
         if buffer.len() < required_len {
             return Err(DecodeError::BufferTooShort {
                 required: required_len,
@@ -191,11 +290,12 @@ pub trait SbeDecoder<'a>: Sized {
             });
         }
 
-        Ok(Self::wrap(
+        Self::try_wrap_with_block_length(
             buffer,
-            MessageHeader::ENCODED_LENGTH,
-            header.version,
-        ))
+            H::ENCODED_LENGTH,
+            header.block_length(),
+            header.version(),
+        )
     }
 }
 
@@ -218,7 +318,7 @@ pub trait MessageDispatch {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::buffer::{AlignedBuffer, ReadBuffer};
+    use crate::buffer::{AlignedBuffer, ReadBuffer, WriteBuffer};
 
     #[test]
     fn test_decode_error_display_buffer_too_short() {
@@ -325,6 +425,7 @@ mod tests {
     struct TestDecoder<'a> {
         buffer: &'a [u8],
         offset: usize,
+        block_length: u16,
         #[allow(dead_code)]
         acting_version: u16,
     }
@@ -335,16 +436,22 @@ mod tests {
         const SCHEMA_VERSION: u16 = 1;
         const BLOCK_LENGTH: u16 = 16;
 
-        fn wrap(buffer: &'a [u8], offset: usize, acting_version: u16) -> Self {
+        fn wrap_with_block_length(
+            buffer: &'a [u8],
+            offset: usize,
+            block_length: u16,
+            acting_version: u16,
+        ) -> Self {
             Self {
                 buffer,
                 offset,
+                block_length,
                 acting_version,
             }
         }
 
         fn encoded_length(&self) -> usize {
-            MessageHeader::ENCODED_LENGTH + Self::BLOCK_LENGTH as usize
+            MessageHeader::ENCODED_LENGTH + self.block_length as usize
         }
     }
 
@@ -397,6 +504,17 @@ mod tests {
         assert_eq!(decoder.encoded_length(), 24); // 8 header + 16 block
     }
 
+    #[test]
+    fn test_decode_uses_header_block_length() {
+        let mut buffer = AlignedBuffer::<32>::new();
+        // A newer schema version with a 20-byte block.
+        let header = MessageHeader::new(20, 1, 100, 2);
+        header.encode(&mut buffer, 0);
+
+        let decoder = TestDecoder::decode(buffer.as_slice()).unwrap();
+        assert_eq!(decoder.encoded_length(), 28);
+    }
+
     #[test]
     fn test_decoder_wrap() {
         let buffer = [0u8; 32];
@@ -404,4 +522,103 @@ mod tests {
         assert_eq!(decoder.offset, 8);
         assert_eq!(decoder.buffer.len(), 32);
     }
+
+    #[test]
+    fn test_decoder_wrap_reads_header_block_length() {
+        let mut buffer = AlignedBuffer::<32>::new();
+        MessageHeader::new(20, 1, 100, 2).encode(&mut buffer, 0);
+
+        let decoder = TestDecoder::wrap(buffer.as_slice(), MessageHeader::ENCODED_LENGTH, 2);
+        assert_eq!(decoder.block_length, 20);
+        // No header fits before offset 4, so the schema's block length is used.
+        let decoder = TestDecoder::wrap(buffer.as_slice(), 4, 2);
+        assert_eq!(decoder.block_length, 16);
+        let decoder = TestDecoder::wrap_with_block_length(buffer.as_slice(), 8, 12, 2);
+        assert_eq!(decoder.encoded_length(), 20);
+    }
+
+    /// Header with CME's `numGroups` and `numVarDataFields` extension.
+    #[derive(Clone, Copy)]
+    struct ExtendedHeader {
+        base: MessageHeader,
+        num_groups: u16,
+    }
+
+    impl SbeHeader for ExtendedHeader {
+        const ENCODED_LENGTH: usize = 12;
+
+        fn wrap<B: ReadBuffer + ?Sized>(buffer: &B, offset: usize) -> Self {
+            Self {
+                base: MessageHeader::wrap(buffer, offset),
+                num_groups: buffer.get_u16_le(offset + 8),
+            }
+        }
+
+        fn encode<B: WriteBuffer + ?Sized>(&self, buffer: &mut B, offset: usize) {
+            self.base.encode(buffer, offset);
+            buffer.put_u16_le(offset + 8, self.num_groups);
+            buffer.put_u16_le(offset + 10, 0);
+        }
+
+        fn block_length(&self) -> u16 {
+            self.base.block_length
+        }
+
+        fn template_id(&self) -> u16 {
+            self.base.template_id
+        }
+
+        fn schema_id(&self) -> u16 {
+            self.base.schema_id
+        }
+
+        fn version(&self) -> u16 {
+            self.base.version
+        }
+    }
+
+    struct ExtendedDecoder {
+        offset: usize,
+    }
+
+    impl<'a> SbeDecoder<'a, ExtendedHeader> for ExtendedDecoder {
+        const TEMPLATE_ID: u16 = 1;
+        const SCHEMA_ID: u16 = 100;
+        const SCHEMA_VERSION: u16 = 1;
+        const BLOCK_LENGTH: u16 = 4;
+
+        fn wrap_with_block_length(
+            _buffer: &'a [u8],
+            offset: usize,
+            _block_length: u16,
+            _acting_version: u16,
+        ) -> Self {
+            Self { offset }
+        }
+
+        fn encoded_length(&self) -> usize {
+            self.offset + Self::BLOCK_LENGTH as usize
+        }
+    }
+
+    #[test]
+    fn test_decode_with_extended_header() {
+        let mut buffer = AlignedBuffer::<16>::new();
+        ExtendedHeader {
+            base: MessageHeader::new(4, 1, 100, 1),
+            num_groups: 2,
+        }
+        .encode(&mut buffer, 0);
+
+        let decoder = ExtendedDecoder::decode(buffer.as_slice()).expect("decodes");
+        assert_eq!(decoder.offset, 12, "root block follows the 12-byte header");
+        assert!(matches!(
+            ExtendedDecoder::decode(&buffer.as_slice()[..14]),
+            Err(DecodeError::BufferTooShort { required: 16, .. })
+        ));
+        assert_eq!(
+            ExtendedHeader::try_wrap(&[0; 11], 0).map(|h| h.num_groups),
+            None
+        );
+    }
 }
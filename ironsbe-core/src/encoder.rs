@@ -2,26 +2,33 @@
 //!
 //! This module provides the [`SbeEncoder`] trait for message encoding.
 
-use crate::header::MessageHeader;
+use crate::error::{Error, Result};
+use crate::header::{MessageHeader, SbeHeader};
 
 /// Trait for SBE message encoders.
 ///
 /// Implementations wrap a mutable byte buffer and provide field setters
-/// that write directly to the buffer.
+/// that write directly to the buffer. Generic code, such as a responder or
+/// a journal, can frame and copy out any message through this trait.
+///
+/// # Type Parameters
+/// * `'a` - Lifetime of the underlying buffer
+/// * `H` - Message header layout of the schema, [`MessageHeader`] unless the
+///   schema's `headerType` composite extends it
 ///
 /// # Example
 /// ```ignore
 /// // Generated encoder usage
 /// let mut buffer = [0u8; 256];
-/// let mut encoder = NewOrderSingleEncoder::wrap(&mut buffer, 0);
+/// let mut encoder = NewOrderSingleEncoder::wrap_and_apply_header(&mut buffer, 0)?;
 /// encoder
 ///     .set_symbol(b"AAPL    ")
 ///     .set_quantity(100)
 ///     .set_price_mantissa(15050)
 ///     .set_price_exponent(-2);
-/// let len = encoder.encoded_length();
+/// let len = encoder.encode_into(&mut frame)?;
 /// ```
-pub trait SbeEncoder: Sized {
+pub trait SbeEncoder<'a, H: SbeHeader = MessageHeader>: Sized {
     /// Schema template ID for this message type.
     const TEMPLATE_ID: u16;
 
@@ -44,13 +51,54 @@ pub trait SbeEncoder: Sized {
     ///
     /// # Returns
     /// An encoder instance wrapping the buffer.
-    fn wrap(buffer: &mut [u8], offset: usize) -> Self;
+    ///
+    /// # Panics
+    /// May panic if the buffer cannot hold the header and the fixed block;
+    /// use [`wrap_and_apply_header`](Self::wrap_and_apply_header) to check
+    /// first.
+    fn wrap(buffer: &'a mut [u8], offset: usize) -> Self;
 
     /// Returns the final encoded length after all writes.
     ///
     /// This includes the header and all written portions.
     fn encoded_length(&self) -> usize;
 
+    /// Returns the encoded message, header included.
+    fn as_bytes(&self) -> &[u8];
+
+    /// Wraps a mutable buffer and writes the message header, checking
+    /// first that the header and the fixed block fit.
+    ///
+    /// # Errors
+    /// Returns [`Error::BufferTooShort`] if they do not.
+    fn wrap_and_apply_header(buffer: &'a mut [u8], offset: usize) -> Result<Self> {
+        let required = offset + H::ENCODED_LENGTH + usize::from(Self::BLOCK_LENGTH);
+        if buffer.len() < required {
+            return Err(Error::BufferTooShort {
+                required,
+                available: buffer.len(),
+            });
+        }
+        Ok(Self::wrap(buffer, offset))
+    }
+
+    /// Copies the encoded message, header included, to the start of `out`
+    /// and returns its length.
+    ///
+    /// # Errors
+    /// Returns [`Error::BufferTooShort`] if `out` cannot hold the message.
+    fn encode_into(&self, out: &mut [u8]) -> Result<usize> {
+        let bytes = self.as_bytes();
+        if out.len() < bytes.len() {
+            return Err(Error::BufferTooShort {
+                required: bytes.len(),
+                available: out.len(),
+            });
+        }
+        out[..bytes.len()].copy_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
     /// Creates the message header for this encoder.
     #[must_use]
     fn create_header() -> MessageHeader {
@@ -314,19 +362,22 @@ mod tests {
     }
 
     /// Test encoder implementation for testing purposes.
-    struct TestEncoder {
+    struct TestEncoder<'a> {
+        buffer: &'a mut [u8],
         offset: usize,
         len: usize,
     }
 
-    impl SbeEncoder for TestEncoder {
+    impl<'a> SbeEncoder<'a> for TestEncoder<'a> {
         const TEMPLATE_ID: u16 = 1;
         const SCHEMA_ID: u16 = 100;
         const SCHEMA_VERSION: u16 = 1;
         const BLOCK_LENGTH: u16 = 16;
 
-        fn wrap(_buffer: &mut [u8], offset: usize) -> TestEncoder {
+        fn wrap(buffer: &'a mut [u8], offset: usize) -> Self {
+            Self::create_header().encode(buffer, offset);
             TestEncoder {
+                buffer,
                 offset,
                 len: MessageHeader::ENCODED_LENGTH + Self::BLOCK_LENGTH as usize,
             }
@@ -335,6 +386,10 @@ mod tests {
         fn encoded_length(&self) -> usize {
             self.len
         }
+
+        fn as_bytes(&self) -> &[u8] {
+            &self.buffer[self.offset..self.offset + self.len]
+        }
     }
 
     #[test]
@@ -360,4 +415,27 @@ mod tests {
         assert_eq!(encoder.offset, 0);
         assert_eq!(encoder.encoded_length(), 24); // 8 header + 16 block
     }
+
+    #[test]
+    fn test_sbe_encoder_checked_entrypoints() {
+        let mut buf = [0u8; 64];
+        assert!(matches!(
+            TestEncoder::wrap_and_apply_header(&mut buf[..30], 8),
+            Err(Error::BufferTooShort {
+                required: 32,
+                available: 30
+            })
+        ));
+
+        let encoder = TestEncoder::wrap_and_apply_header(&mut buf, 8).unwrap();
+        encoder.buffer[16] = 0xAB;
+        let mut out = [0u8; 24];
+        assert_eq!(encoder.encode_into(&mut out).unwrap(), 24);
+        assert_eq!(
+            MessageHeader::wrap(&out[..], 0),
+            TestEncoder::create_header()
+        );
+        assert_eq!(out[8], 0xAB);
+        assert!(encoder.encode_into(&mut [0u8; 23]).is_err());
+    }
 }
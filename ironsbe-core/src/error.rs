@@ -1,5 +1,7 @@
 //! Error types for IronSBE core operations.
 
+#[cfg(feature = "alloc")]
+use alloc::string::String;
 use thiserror::Error;
 
 /// Core error type for IronSBE operations.
@@ -58,6 +60,7 @@ pub enum Error {
     },
 
     /// Group iteration error.
+    #[cfg(feature = "alloc")]
     #[error("group iteration error: {message}")]
     GroupError {
         /// Error message.
@@ -77,4 +80,4 @@ pub enum Error {
 }
 
 /// Result type alias for IronSBE core operations.
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
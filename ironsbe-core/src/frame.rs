@@ -0,0 +1,184 @@
+//! Owned message frames for handing messages across threads.
+//!
+//! Decoders borrow the buffer they wrap, so they cannot be sent to another
+//! thread on their own. An [`OwnedFrame`] keeps the storage a message was
+//! received into (a `bytes::Bytes` from the transport, a pooled buffer, a
+//! `Vec<u8>`) together with the position of the message inside it. It
+//! moves through channels without copying, and the consumer decodes it in
+//! place with [`OwnedFrame::decode`].
+
+use crate::buffer::ReadBuffer;
+use crate::decoder::{DecodeError, SbeDecoder};
+use crate::header::MessageHeader;
+use core::ops::Deref;
+
+/// A message at `offset..offset + len` of an owned buffer.
+///
+/// Dereferences to the message bytes, header included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedFrame<B> {
+    buffer: B,
+    offset: usize,
+    len: usize,
+}
+
+impl<B: AsRef<[u8]>> OwnedFrame<B> {
+    /// Creates a frame for the `len` bytes at `offset` in `buffer`.
+    ///
+    /// Returns `None` if they run past the end of the buffer.
+    #[must_use]
+    pub fn new(buffer: B, offset: usize, len: usize) -> Option<Self> {
+        let end = offset.checked_add(len)?;
+        (end <= buffer.as_ref().len()).then_some(Self {
+            buffer,
+            offset,
+            len,
+        })
+    }
+
+    /// Creates a frame spanning the whole buffer.
+    #[must_use]
+    pub fn whole(buffer: B) -> Self {
+        let len = buffer.as_ref().len();
+        Self {
+            buffer,
+            offset: 0,
+            len,
+        }
+    }
+
+    /// Returns the offset of the message in the buffer.
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the length of the message.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the frame holds no bytes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the message header, or `None` if the frame is shorter than
+    /// one.
+    #[must_use]
+    pub fn header(&self) -> Option<MessageHeader> {
+        (self.len >= MessageHeader::ENCODED_LENGTH).then(|| MessageHeader::wrap(&**self, 0))
+    }
+
+    /// Returns the template ID from the message header.
+    #[must_use]
+    pub fn template_id(&self) -> Option<u16> {
+        self.header().map(|header| header.template_id)
+    }
+
+    /// Decodes the message in place, validating its header.
+    ///
+    /// # Errors
+    /// Returns an error if the header does not match `D` or the frame is
+    /// too short.
+    pub fn decode<'a, D: SbeDecoder<'a>>(&'a self) -> Result<D, DecodeError> {
+        D::decode(self)
+    }
+
+    /// Returns the underlying buffer.
+    #[must_use]
+    pub fn get_ref(&self) -> &B {
+        &self.buffer
+    }
+
+    /// Returns the underlying buffer, dropping the message bounds.
+    #[must_use]
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+}
+
+impl<B: AsRef<[u8]>> Deref for OwnedFrame<B> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buffer.as_ref()[self.offset..self.offset + self.len]
+    }
+}
+
+impl<B: AsRef<[u8]>> AsRef<[u8]> for OwnedFrame<B> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<B: AsRef<[u8]>> ReadBuffer for OwnedFrame<B> {
+    #[inline(always)]
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl From<bytes::Bytes> for OwnedFrame<bytes::Bytes> {
+    fn from(bytes: bytes::Bytes) -> Self {
+        Self::whole(bytes)
+    }
+}
+
+/// Freezes the buffer a transport read a frame into, without copying.
+#[cfg(feature = "bytes")]
+impl From<bytes::BytesMut> for OwnedFrame<bytes::Bytes> {
+    fn from(bytes: bytes::BytesMut) -> Self {
+        Self::whole(bytes.freeze())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(template_id: u16) -> [u8; 16] {
+        let mut buf = [0u8; 16];
+        MessageHeader::new(8, template_id, 1, 1).encode(&mut buf[..], 0);
+        buf[8..].copy_from_slice(&42u64.to_le_bytes());
+        buf
+    }
+
+    #[test]
+    fn test_frame_bounds_and_header() {
+        let mut buffer = [0xFFu8; 20];
+        buffer[4..].copy_from_slice(&message(3));
+
+        let frame = OwnedFrame::new(buffer, 4, 16).expect("in bounds");
+        assert_eq!((frame.offset(), frame.len()), (4, 16));
+        assert_eq!(frame.template_id(), Some(3));
+        assert_eq!(frame.get_u64_le(8), 42);
+        assert_eq!(&*frame, &message(3));
+
+        assert!(OwnedFrame::new(buffer, 4, 17).is_none());
+        assert!(OwnedFrame::new(buffer, usize::MAX, 2).is_none());
+        assert_eq!(OwnedFrame::whole([0u8; 4]).header(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "bytes")]
+    fn test_frame_from_bytes_mut_shares_storage() {
+        let read = bytes::BytesMut::from(&message(5)[..]);
+        let pointer = read.as_ptr();
+
+        let frame = OwnedFrame::from(read);
+        assert_eq!(frame.as_ptr(), pointer, "freezing does not copy");
+        let sent = std::thread::spawn(move || frame.get_u64_le(8))
+            .join()
+            .unwrap();
+        assert_eq!(sent, 42);
+    }
+}
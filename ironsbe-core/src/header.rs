@@ -4,9 +4,60 @@
 //! - [`MessageHeader`] - 8-byte message header
 //! - [`GroupHeader`] - 4-byte repeating group header
 //! - [`VarDataHeader`] - Variable-length data header
+//!
+//! Schemas whose `headerType` composite differs from the standard layout
+//! (e.g. CME's `numGroups` and `numVarDataFields` extension) get a header
+//! struct generated from it; both implement [`SbeHeader`].
 
 use crate::buffer::{ReadBuffer, WriteBuffer};
 
+/// Message header layout that frames the messages of a schema.
+///
+/// Implemented by the standard [`MessageHeader`] and by the header structs
+/// generated for schemas with an extended `headerType` composite, so
+/// decoding can validate whichever layout the schema uses.
+pub trait SbeHeader: Copy {
+    /// Encoded length of the header in bytes.
+    const ENCODED_LENGTH: usize;
+
+    /// Decodes the header at the given offset.
+    ///
+    /// # Panics
+    /// Panics if the buffer is too short.
+    fn wrap<B: ReadBuffer + ?Sized>(buffer: &B, offset: usize) -> Self;
+
+    /// Encodes the header to the buffer at the given offset.
+    fn encode<B: WriteBuffer + ?Sized>(&self, buffer: &mut B, offset: usize);
+
+    /// Returns the length of the root block in bytes.
+    fn block_length(&self) -> u16;
+
+    /// Returns the message template identifier.
+    fn template_id(&self) -> u16;
+
+    /// Returns the schema identifier.
+    fn schema_id(&self) -> u16;
+
+    /// Returns the schema version number.
+    fn version(&self) -> u16;
+
+    /// Decodes the header at the given offset, if the buffer is long enough
+    /// to hold one.
+    #[inline]
+    fn try_wrap(buffer: &[u8], offset: usize) -> Option<Self> {
+        (buffer.len().saturating_sub(offset) >= Self::ENCODED_LENGTH)
+            .then(|| Self::wrap(buffer, offset))
+    }
+
+    /// Returns the root block length from the header that ends at `offset`,
+    /// where a message body starts, if one fits before it.
+    #[inline]
+    fn block_length_before(buffer: &[u8], offset: usize) -> Option<u16> {
+        let start = offset.checked_sub(Self::ENCODED_LENGTH)?;
+        Self::try_wrap(buffer, start).map(|header| header.block_length())
+    }
+}
+
 /// Standard SBE message header (8 bytes).
 ///
 /// The message header precedes every SBE message and contains:
@@ -75,6 +126,19 @@ impl MessageHeader {
         }
     }
 
+    /// Decodes the message header at the given offset, if the buffer is
+    /// long enough to hold one.
+    ///
+    /// # Arguments
+    /// * `buffer` - Buffer to read from
+    /// * `offset` - Byte offset to start reading
+    #[inline]
+    #[must_use]
+    pub fn try_wrap(buffer: &[u8], offset: usize) -> Option<Self> {
+        (buffer.len().saturating_sub(offset) >= Self::ENCODED_LENGTH)
+            .then(|| Self::wrap(buffer, offset))
+    }
+
     /// Encodes the message header to the buffer at the given offset.
     ///
     /// # Arguments
@@ -95,6 +159,40 @@ impl MessageHeader {
     }
 }
 
+impl SbeHeader for MessageHeader {
+    const ENCODED_LENGTH: usize = Self::ENCODED_LENGTH;
+
+    #[inline(always)]
+    fn wrap<B: ReadBuffer + ?Sized>(buffer: &B, offset: usize) -> Self {
+        Self::wrap(buffer, offset)
+    }
+
+    #[inline(always)]
+    fn encode<B: WriteBuffer + ?Sized>(&self, buffer: &mut B, offset: usize) {
+        self.encode(buffer, offset);
+    }
+
+    #[inline(always)]
+    fn block_length(&self) -> u16 {
+        self.block_length
+    }
+
+    #[inline(always)]
+    fn template_id(&self) -> u16 {
+        self.template_id
+    }
+
+    #[inline(always)]
+    fn schema_id(&self) -> u16 {
+        self.schema_id
+    }
+
+    #[inline(always)]
+    fn version(&self) -> u16 {
+        self.version
+    }
+}
+
 /// Repeating group header (4 bytes).
 ///
 /// The group header precedes each repeating group and contains:
@@ -236,6 +334,19 @@ impl VarDataHeader {
         Self::ENCODED_LENGTH + self.length as usize
     }
 
+    /// Returns the total size (header + data) of a field holding `len`
+    /// bytes, with `len` truncated to `u16::MAX` as the header can't encode
+    /// more.
+    #[must_use]
+    pub const fn total_size_for(len: usize) -> usize {
+        let len = if len > u16::MAX as usize {
+            u16::MAX as usize
+        } else {
+            len
+        };
+        Self::ENCODED_LENGTH + len
+    }
+
     /// Returns true if the data is empty.
     #[must_use]
     pub const fn is_empty(&self) -> bool {
@@ -245,7 +356,7 @@ impl VarDataHeader {
 
 /// Variable-length data header with u8 length (1 byte).
 #[repr(C, packed)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct VarDataHeader8 {
     /// Length of the variable data in bytes.
     pub length: u8,
@@ -275,11 +386,36 @@ impl VarDataHeader8 {
     pub fn encode<B: WriteBuffer + ?Sized>(&self, buffer: &mut B, offset: usize) {
         buffer.put_u8(offset, self.length);
     }
+
+    /// Returns the total size (header + data).
+    #[must_use]
+    pub const fn total_size(&self) -> usize {
+        Self::ENCODED_LENGTH + self.length as usize
+    }
+
+    /// Returns the total size (header + data) of a field holding `len`
+    /// bytes, with `len` truncated to `u8::MAX` as the header can't encode
+    /// more.
+    #[must_use]
+    pub const fn total_size_for(len: usize) -> usize {
+        let len = if len > u8::MAX as usize {
+            u8::MAX as usize
+        } else {
+            len
+        };
+        Self::ENCODED_LENGTH + len
+    }
+
+    /// Returns true if the data is empty.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.length == 0
+    }
 }
 
 /// Variable-length data header with u32 length (4 bytes).
 #[repr(C, packed)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct VarDataHeader32 {
     /// Length of the variable data in bytes.
     pub length: u32,
@@ -309,6 +445,31 @@ impl VarDataHeader32 {
     pub fn encode<B: WriteBuffer + ?Sized>(&self, buffer: &mut B, offset: usize) {
         buffer.put_u32_le(offset, self.length);
     }
+
+    /// Returns the total size (header + data).
+    #[must_use]
+    pub const fn total_size(&self) -> usize {
+        Self::ENCODED_LENGTH + self.length as usize
+    }
+
+    /// Returns the total size (header + data) of a field holding `len`
+    /// bytes, with `len` truncated to `u32::MAX` as the header can't encode
+    /// more.
+    #[must_use]
+    pub const fn total_size_for(len: usize) -> usize {
+        let len = if len > u32::MAX as usize {
+            u32::MAX as usize
+        } else {
+            len
+        };
+        Self::ENCODED_LENGTH + len
+    }
+
+    /// Returns true if the data is empty.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.length == 0
+    }
 }
 
 #[cfg(test)]
@@ -331,6 +492,18 @@ mod tests {
         assert_eq!({ decoded.version }, 1);
     }
 
+    #[test]
+    fn test_message_header_try_wrap() {
+        let mut buf = [0u8; 12];
+        MessageHeader::new(4, 7, 100, 1).encode(&mut buf[..], 2);
+
+        let decoded = MessageHeader::try_wrap(&buf, 2).expect("header fits");
+        assert_eq!({ decoded.template_id }, 7);
+        assert!(MessageHeader::try_wrap(&buf, 5).is_none());
+        assert!(MessageHeader::try_wrap(&buf[..7], 0).is_none());
+        assert!(MessageHeader::try_wrap(&buf, 20).is_none());
+    }
+
     #[test]
     fn test_message_header_size() {
         assert_eq!(MessageHeader::ENCODED_LENGTH, 8);
@@ -375,6 +548,16 @@ mod tests {
         assert_eq!(decoded.total_size(), 258);
     }
 
+    #[test]
+    fn test_var_data_header_total_size_for() {
+        assert_eq!(VarDataHeader::total_size_for(0), 2);
+        assert_eq!(VarDataHeader::total_size_for(256), 258);
+        assert_eq!(
+            VarDataHeader::total_size_for(100_000),
+            2 + u16::MAX as usize
+        );
+    }
+
     #[test]
     fn test_var_data_header8() {
         let mut buf: AlignedBuffer<16> = AlignedBuffer::new();
@@ -385,6 +568,8 @@ mod tests {
 
         assert_eq!(header, decoded);
         assert_eq!(VarDataHeader8::ENCODED_LENGTH, 1);
+        assert_eq!(decoded.total_size(), 101);
+        assert_eq!(VarDataHeader8::total_size_for(1_000), 1 + u8::MAX as usize);
     }
 
     #[test]
@@ -397,6 +582,8 @@ mod tests {
 
         assert_eq!(header, decoded);
         assert_eq!(VarDataHeader32::ENCODED_LENGTH, 4);
+        assert_eq!(decoded.total_size(), 1_000_004);
+        assert!(VarDataHeader32::default().is_empty());
     }
 
     #[test]
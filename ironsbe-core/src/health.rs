@@ -0,0 +1,462 @@
+//! Self-health reporting for long-running gateways.
+//!
+//! A [`HealthMonitor`] aggregates three kinds of liveness signal into one
+//! [`HealthSnapshot`]:
+//! - poll loops, which bump a [`Heartbeat`] every iteration and are
+//!   unhealthy once it stops moving
+//! - channels, whose consumer bumps a heartbeat per message and which are
+//!   unhealthy when messages are queued but the consumer stopped taking them
+//! - feeds, which bump a heartbeat per packet and are degraded when nothing
+//!   arrived for too long
+//!
+//! Heartbeats are a relaxed atomic increment, cheap enough for the hot path.
+//! All timing happens when a snapshot is taken. Admin endpoints and metrics
+//! exporters publish snapshots, and a [`Watchdog`] checks them periodically
+//! so a supervisor can restart a gateway that stopped making progress.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Progress counter bumped by a monitored component.
+///
+/// Cloning shares the counter.
+#[derive(Debug, Clone, Default)]
+pub struct Heartbeat(Arc<AtomicU64>);
+
+impl Heartbeat {
+    /// Records one unit of progress.
+    #[inline]
+    pub fn beat(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of beats so far.
+    #[inline]
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Health of a component or of the whole process, from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HealthStatus {
+    /// Making progress.
+    Healthy,
+    /// Working but not receiving input, e.g. a quiet or dead feed.
+    Degraded,
+    /// Stopped making progress; restarting is warranted.
+    Unhealthy,
+}
+
+impl fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Healthy => "healthy",
+            Self::Degraded => "degraded",
+            Self::Unhealthy => "unhealthy",
+        })
+    }
+}
+
+/// Kind of monitored component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComponentKind {
+    /// A poll loop.
+    Loop,
+    /// A channel and its consumer.
+    Channel,
+    /// An inbound market data or order feed.
+    Feed,
+}
+
+impl fmt::Display for ComponentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Loop => "loop",
+            Self::Channel => "channel",
+            Self::Feed => "feed",
+        })
+    }
+}
+
+/// Health of one component at the time of a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentHealth {
+    /// Name given at registration.
+    pub name: String,
+    /// Kind of component.
+    pub kind: ComponentKind,
+    /// Status derived from the idle time.
+    pub status: HealthStatus,
+    /// Heartbeats so far.
+    pub beats: u64,
+    /// Time since the heartbeat last moved, or for a channel since it was
+    /// last seen empty or its consumer last made progress.
+    pub idle: Duration,
+    /// Queued messages, for channels.
+    pub depth: Option<usize>,
+}
+
+/// Health of every registered component at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthSnapshot {
+    status: HealthStatus,
+    components: Vec<ComponentHealth>,
+}
+
+impl HealthSnapshot {
+    /// Returns the worst status of any component, or healthy if none are
+    /// registered.
+    #[must_use]
+    pub fn status(&self) -> HealthStatus {
+        self.status
+    }
+
+    /// Returns true if every component is healthy.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.status == HealthStatus::Healthy
+    }
+
+    /// Returns the components in registration order.
+    #[must_use]
+    pub fn components(&self) -> &[ComponentHealth] {
+        &self.components
+    }
+
+    /// Returns the component registered as `name`.
+    #[must_use]
+    pub fn component(&self, name: &str) -> Option<&ComponentHealth> {
+        self.components.iter().find(|c| c.name == name)
+    }
+}
+
+impl fmt::Display for HealthSnapshot {
+    /// Writes the overall status, then one line per component.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.status)?;
+        for c in &self.components {
+            write!(
+                f,
+                "\n{} {}: {} (beats {}, idle {:?}",
+                c.kind, c.name, c.status, c.beats, c.idle
+            )?;
+            if let Some(depth) = c.depth {
+                write!(f, ", depth {depth}")?;
+            }
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the queue depth of a monitored channel.
+type DepthProbe = Box<dyn Fn() -> usize + Send + Sync>;
+
+struct Component {
+    name: String,
+    kind: ComponentKind,
+    limit: Duration,
+    heartbeat: Heartbeat,
+    depth: Option<DepthProbe>,
+    last_count: u64,
+    last_progress: Instant,
+}
+
+/// Registry of monitored components.
+///
+/// Share it behind an [`Arc`]; registration and snapshots take `&self`.
+#[derive(Default)]
+pub struct HealthMonitor {
+    components: Mutex<Vec<Component>>,
+}
+
+impl HealthMonitor {
+    /// Creates a monitor with no components.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a poll loop that is unhealthy after `max_silence` without
+    /// a beat.
+    ///
+    /// Loops that block waiting for input must wake and beat at least that
+    /// often even when idle.
+    pub fn register_loop(&self, name: impl Into<String>, max_silence: Duration) -> Heartbeat {
+        self.register(name.into(), ComponentKind::Loop, max_silence, None)
+    }
+
+    /// Registers a channel whose consumer beats once per message taken.
+    ///
+    /// The channel is unhealthy when `depth` has been non-zero for
+    /// `stall_after` without the consumer beating.
+    pub fn register_channel(
+        &self,
+        name: impl Into<String>,
+        stall_after: Duration,
+        depth: impl Fn() -> usize + Send + Sync + 'static,
+    ) -> Heartbeat {
+        self.register(
+            name.into(),
+            ComponentKind::Channel,
+            stall_after,
+            Some(Box::new(depth)),
+        )
+    }
+
+    /// Registers a feed that beats once per packet and is degraded after
+    /// `max_staleness` without one.
+    pub fn register_feed(&self, name: impl Into<String>, max_staleness: Duration) -> Heartbeat {
+        self.register(name.into(), ComponentKind::Feed, max_staleness, None)
+    }
+
+    fn register(
+        &self,
+        name: String,
+        kind: ComponentKind,
+        limit: Duration,
+        depth: Option<DepthProbe>,
+    ) -> Heartbeat {
+        let heartbeat = Heartbeat::default();
+        self.lock().push(Component {
+            name,
+            kind,
+            limit,
+            heartbeat: heartbeat.clone(),
+            depth,
+            last_count: 0,
+            last_progress: Instant::now(),
+        });
+        heartbeat
+    }
+
+    /// Removes the component registered as `name`.
+    ///
+    /// Returns true if it was registered.
+    pub fn unregister(&self, name: &str) -> bool {
+        let mut components = self.lock();
+        let before = components.len();
+        components.retain(|c| c.name != name);
+        components.len() != before
+    }
+
+    /// Returns the health of every component now.
+    #[must_use]
+    pub fn snapshot(&self) -> HealthSnapshot {
+        self.snapshot_at(Instant::now())
+    }
+
+    /// Returns the health of every component as of `now`.
+    ///
+    /// Progress is measured between snapshots, so `now` must not go
+    /// backwards across calls.
+    #[must_use]
+    pub fn snapshot_at(&self, now: Instant) -> HealthSnapshot {
+        let mut components = self.lock();
+        let components: Vec<_> = components
+            .iter_mut()
+            .map(|c| {
+                let beats = c.heartbeat.count();
+                let depth = c.depth.as_ref().map(|probe| probe());
+                if beats != c.last_count || depth == Some(0) {
+                    c.last_count = beats;
+                    c.last_progress = now;
+                }
+                let idle = now.saturating_duration_since(c.last_progress);
+                let status = match c.kind {
+                    _ if idle <= c.limit => HealthStatus::Healthy,
+                    ComponentKind::Feed => HealthStatus::Degraded,
+                    ComponentKind::Loop | ComponentKind::Channel => HealthStatus::Unhealthy,
+                };
+                ComponentHealth {
+                    name: c.name.clone(),
+                    kind: c.kind,
+                    status,
+                    beats,
+                    idle,
+                    depth,
+                }
+            })
+            .collect();
+        HealthSnapshot {
+            status: components
+                .iter()
+                .map(|c| c.status)
+                .max()
+                .unwrap_or(HealthStatus::Healthy),
+            components,
+        }
+    }
+
+    /// Starts a thread that takes a snapshot every `interval` and calls
+    /// `on_unhealthy` with each one whose status is unhealthy.
+    ///
+    /// The thread stops when the returned [`Watchdog`] is stopped or
+    /// dropped.
+    ///
+    /// # Panics
+    /// Panics if the thread cannot be spawned.
+    pub fn spawn_watchdog(
+        self: &Arc<Self>,
+        interval: Duration,
+        mut on_unhealthy: impl FnMut(&HealthSnapshot) + Send + 'static,
+    ) -> Watchdog {
+        let monitor = Arc::clone(self);
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        let thread = std::thread::Builder::new()
+            .name("ironsbe-watchdog".into())
+            .spawn(move || {
+                loop {
+                    std::thread::park_timeout(interval);
+                    if stopped.load(Ordering::Acquire) {
+                        break;
+                    }
+                    let snapshot = monitor.snapshot();
+                    if snapshot.status() == HealthStatus::Unhealthy {
+                        on_unhealthy(&snapshot);
+                    }
+                }
+            })
+            .expect("failed to spawn watchdog thread");
+        Watchdog {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Component>> {
+        self.components
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for HealthMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self.lock().iter().map(|c| c.name.clone()).collect();
+        f.debug_struct("HealthMonitor")
+            .field("components", &names)
+            .finish()
+    }
+}
+
+/// Handle to a watchdog thread started by
+/// [`HealthMonitor::spawn_watchdog`].
+#[derive(Debug)]
+pub struct Watchdog {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    /// Stops the thread and waits for it to exit.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.stop.store(true, Ordering::Release);
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::mpsc;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_loop_unhealthy_when_heartbeat_stops() {
+        let monitor = HealthMonitor::new();
+        let beat = monitor.register_loop("io", SECOND);
+        let start = Instant::now();
+
+        beat.beat();
+        assert!(monitor.snapshot_at(start).is_healthy());
+
+        let snapshot = monitor.snapshot_at(start + 2 * SECOND);
+        assert_eq!(snapshot.status(), HealthStatus::Unhealthy);
+        let io = snapshot.component("io").expect("registered");
+        assert_eq!((io.beats, io.idle), (1, 2 * SECOND));
+
+        beat.beat();
+        assert!(monitor.snapshot_at(start + 3 * SECOND).is_healthy());
+    }
+
+    #[test]
+    fn test_channel_stalls_only_with_queued_messages() {
+        let monitor = HealthMonitor::new();
+        let depth = Arc::new(AtomicUsize::new(0));
+        let probe = Arc::clone(&depth);
+        let consumer =
+            monitor.register_channel("orders", SECOND, move || probe.load(Ordering::Relaxed));
+        let start = Instant::now();
+
+        assert!(monitor.snapshot_at(start).is_healthy());
+        assert!(monitor.snapshot_at(start + 5 * SECOND).is_healthy());
+
+        depth.store(3, Ordering::Relaxed);
+        assert!(monitor.snapshot_at(start + 6 * SECOND).is_healthy());
+        let snapshot = monitor.snapshot_at(start + 8 * SECOND);
+        assert_eq!(snapshot.status(), HealthStatus::Unhealthy);
+        assert_eq!(snapshot.components()[0].depth, Some(3));
+
+        consumer.beat();
+        assert!(monitor.snapshot_at(start + 9 * SECOND).is_healthy());
+    }
+
+    #[test]
+    fn test_stale_feed_degrades_and_worst_status_wins() {
+        let monitor = HealthMonitor::new();
+        let _feed = monitor.register_feed("mdA", SECOND);
+        let io = monitor.register_loop("io", 10 * SECOND);
+        let start = Instant::now();
+
+        let snapshot = monitor.snapshot_at(start + 2 * SECOND);
+        assert_eq!(snapshot.status(), HealthStatus::Degraded);
+        assert_eq!(
+            snapshot.to_string().lines().next(),
+            Some("degraded"),
+            "{snapshot}"
+        );
+
+        let snapshot = monitor.snapshot_at(start + 20 * SECOND);
+        assert_eq!(snapshot.status(), HealthStatus::Unhealthy);
+
+        io.beat();
+        assert!(monitor.unregister("mdA"));
+        assert!(monitor.snapshot_at(start + 21 * SECOND).is_healthy());
+    }
+
+    #[test]
+    fn test_watchdog_reports_unhealthy_snapshots() {
+        let monitor = Arc::new(HealthMonitor::new());
+        let _beat = monitor.register_loop("io", Duration::ZERO);
+        let (tx, rx) = mpsc::channel();
+        let watchdog = monitor.spawn_watchdog(Duration::from_millis(5), move |snapshot| {
+            let _ = tx.send(snapshot.status());
+        });
+
+        let status = rx.recv_timeout(5 * SECOND).expect("watchdog fired");
+        assert_eq!(status, HealthStatus::Unhealthy);
+        watchdog.stop();
+    }
+}
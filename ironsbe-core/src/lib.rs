@@ -8,16 +8,85 @@
 //! - Decoder and Encoder traits for SBE messages
 //! - Error types for encoding/decoding operations
 //! - Aligned buffer implementations for optimal performance
+//! - Owned frames for sending received messages across threads
+//! - Size-classed buffer pools with usage counters
+//! - NUMA binding and huge-page backing for long-lived buffers
+//! - CPU affinity for accept, session IO and consumer threads
+//! - Debug-build canary regions for catching out-of-bounds writes
+//! - Health monitoring of poll loops, channels and feeds
+//! - A runtime-attachable message tap for inspecting live traffic
+//! - Scatter-gather message assembly for vectored socket writes
+//! - RFC 3339 and `SystemTime` conversions for timestamps
+//! - Hardware-accelerated CRC32C frame checksums
+//!
+//! ## Features
+//!
+//! - `std` (default): [`BufferPool`], [`HealthMonitor`], [`MessageTap`],
+//!   [`IoSliceEncoder`], [`MemoryConfig`], [`CoreConfig`], wall-clock timestamps and floating-point decimal
+//!   conversions. Implies `alloc`.
+//! - `alloc`: buffer trait impls for `Vec<u8>`, the heap-backed
+//!   [`AlignedVec`] and [`Error::GroupError`].
+//! - `bytes`: buffer trait impls for `bytes::Bytes` and `bytes::BytesMut`, and
+//!   [`OwnedFrame`] conversions from them. Implies `alloc`.
+//! - `chrono`: conversions between [`types::Timestamp`] and
+//!   `chrono::DateTime`. Implies `std`.
+//! - `unchecked`: `unsafe` [`ReadBuffer`] reads without bounds checks, for
+//!   decoders generated with `CodegenConfig::unchecked_reads`.
+//!
+//! With default features disabled the crate is `#![no_std]` and
+//! allocation-free, which is what code generated with
+//! `CodegenConfig::no_std` builds against.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
 pub mod buffer;
+pub mod canary;
+pub mod checksum;
 pub mod decoder;
 pub mod encoder;
 pub mod error;
+pub mod frame;
 pub mod header;
+#[cfg(feature = "std")]
+pub mod health;
+#[cfg(feature = "std")]
+pub mod memory;
+#[cfg(feature = "std")]
+pub mod pool;
+#[cfg(feature = "std")]
+pub mod runtime;
+#[cfg(feature = "std")]
+pub mod tap;
+pub mod time;
 pub mod types;
+#[cfg(feature = "std")]
+pub mod vectored;
 
-pub use buffer::{AlignedBuffer, BufferPool, ReadBuffer, WriteBuffer};
-pub use decoder::{DecodeError, SbeDecoder};
+#[cfg(feature = "alloc")]
+pub use buffer::AlignedVec;
+pub use buffer::{AlignedBuffer, CheckedReadBuffer, ReadBuffer, WriteBuffer};
+#[cfg(feature = "std")]
+pub use buffer::{BufferPool, PooledBuffer};
+pub use decoder::{DecodeError, SbeDecoder, UnknownEnumValue};
 pub use encoder::SbeEncoder;
 pub use error::{Error, Result};
-pub use header::{GroupHeader, MessageHeader, VarDataHeader};
+pub use frame::OwnedFrame;
+pub use header::{GroupHeader, MessageHeader, SbeHeader, VarDataHeader};
+#[cfg(feature = "std")]
+pub use health::{
+    ComponentHealth, ComponentKind, HealthMonitor, HealthSnapshot, HealthStatus, Heartbeat,
+    Watchdog,
+};
+#[cfg(feature = "std")]
+pub use memory::{HugePages, MemoryConfig, MemoryRegion};
+#[cfg(feature = "std")]
+pub use pool::{FallbackPolicy, PoolStats, TieredBuffer, TieredBufferPool, TieredPoolConfig};
+#[cfg(feature = "std")]
+pub use runtime::CoreConfig;
+#[cfg(feature = "std")]
+pub use tap::{MessageTap, TapDirection, TapFilter, TapRecord, TapSink, TapWriter};
+#[cfg(feature = "std")]
+pub use vectored::IoSliceEncoder;
@@ -0,0 +1,610 @@
+//! NUMA placement and huge-page backing for long-lived buffers.
+//!
+//! Rings, buffer pools and shared memory are allocated once and then
+//! touched on every message, so where their pages live matters. On a
+//! multi-socket host, memory on the wrong node adds a cross-socket hop to
+//! every access. With regular 4 KiB pages, a large ring also thrashes the
+//! TLB. A [`MemoryConfig`] says which NUMA node to bind memory to and
+//! whether to back it with huge pages:
+//! - [`MemoryRegion::allocate`] maps fresh anonymous memory with the
+//!   configuration applied; the byte ring uses it for its storage
+//! - [`apply`] applies it to memory that already exists, such as a pooled
+//!   buffer or a file mapping
+//! - [`on_node`] runs an allocation with the calling thread's memory policy
+//!   bound to the node, for memory the global allocator hands out
+//!
+//! NUMA binding and huge pages are Linux features. Elsewhere, and on Linux
+//! hosts without NUMA support or reserved huge pages, the options are
+//! skipped unless the configuration is [`strict`](MemoryConfig::strict),
+//! in which case the call fails instead.
+
+use std::io;
+use std::ptr::NonNull;
+
+/// How memory is backed by huge pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HugePages {
+    /// Regular pages.
+    #[default]
+    Off,
+    /// Transparent huge pages, requested with `madvise(MADV_HUGEPAGE)`. The
+    /// kernel may still use regular pages, e.g. when memory is fragmented.
+    Transparent,
+    /// Pages from the reserved huge-page pool, mapped with `MAP_HUGETLB`.
+    /// The pool is sized with `vm.nr_hugepages` and only fresh anonymous
+    /// mappings can use it; [`apply`] treats this as
+    /// [`Transparent`](Self::Transparent).
+    Explicit,
+}
+
+/// Placement of long-lived memory.
+///
+/// The default places memory the way the allocator would: regular pages,
+/// no NUMA binding and no prefaulting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryConfig {
+    numa_node: Option<u32>,
+    huge_pages: HugePages,
+    prefault: bool,
+    strict: bool,
+}
+
+impl MemoryConfig {
+    /// Creates the default configuration.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds the memory to NUMA node `node`.
+    #[must_use]
+    pub fn numa_node(mut self, node: u32) -> Self {
+        self.numa_node = Some(node);
+        self
+    }
+
+    /// Sets how the memory is backed by huge pages.
+    #[must_use]
+    pub fn huge_pages(mut self, huge_pages: HugePages) -> Self {
+        self.huge_pages = huge_pages;
+        self
+    }
+
+    /// Sets whether every page is touched up front, so the first messages
+    /// do not pay for page faults.
+    #[must_use]
+    pub fn prefault(mut self, prefault: bool) -> Self {
+        self.prefault = prefault;
+        self
+    }
+
+    /// Sets whether options the platform cannot honour are errors. When
+    /// false, the default, they are skipped.
+    #[must_use]
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Returns the NUMA node the memory is bound to, if any.
+    #[must_use]
+    pub fn node(&self) -> Option<u32> {
+        self.numa_node
+    }
+
+    /// Returns how the memory is backed by huge pages.
+    #[must_use]
+    pub fn pages(&self) -> HugePages {
+        self.huge_pages
+    }
+
+    /// Turns the result of an optional placement step into an error only
+    /// in strict mode.
+    fn check(&self, result: io::Result<()>) -> io::Result<()> {
+        match result {
+            Err(e) if self.strict => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Zeroed, page-aligned anonymous memory mapped with a [`MemoryConfig`].
+///
+/// The mapping is released when the region is dropped.
+pub struct MemoryRegion {
+    ptr: NonNull<u8>,
+    len: usize,
+    /// Bytes actually mapped: `len` rounded up to the page size.
+    mapped: usize,
+}
+
+// SAFETY: the region owns its memory like a `Box<[u8]>`; shared access only
+// reads it, and writes need `&mut self` or go through `as_ptr` under the
+// caller's own synchronisation.
+unsafe impl Send for MemoryRegion {}
+// SAFETY: see above.
+unsafe impl Sync for MemoryRegion {}
+
+impl MemoryRegion {
+    /// Maps at least `len` zeroed bytes placed as `config` says.
+    ///
+    /// With [`HugePages::Explicit`], the mapping falls back to
+    /// [`HugePages::Transparent`] if the huge-page pool is empty, unless
+    /// the configuration is strict.
+    ///
+    /// # Errors
+    /// Returns an error if the memory cannot be mapped, or, in strict mode,
+    /// if the NUMA binding or huge pages cannot be applied.
+    pub fn allocate(len: usize, config: &MemoryConfig) -> io::Result<Self> {
+        let len = len.max(1);
+        let (ptr, mapped, huge) = sys::map(len, config)?;
+        let region = Self { ptr, len, mapped };
+        // SAFETY: the mapping is `mapped` bytes long and owned by `region`.
+        let all = unsafe { std::slice::from_raw_parts_mut(ptr.as_ptr(), mapped) };
+        let config = if huge {
+            config.huge_pages(HugePages::Off)
+        } else {
+            *config
+        };
+        apply(all, &config)?;
+        Ok(region)
+    }
+
+    /// Returns the length in bytes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the region is empty, which it never is.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a pointer to the first byte, aligned to the page size.
+    ///
+    /// Writing through it while a slice of the region is borrowed is
+    /// undefined behaviour.
+    #[must_use]
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+
+    /// Returns the region as a slice.
+    #[must_use]
+    pub fn as_slice(&self) -> &[u8] {
+        // SAFETY: the region owns `len` initialised bytes at `ptr`.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// Returns the region as a mutable slice.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: as above, and `&mut self` makes the access exclusive.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for MemoryRegion {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` and `mapped` describe a mapping made by `sys::map`
+        // that nothing borrows any more.
+        unsafe { sys::unmap(self.ptr, self.mapped) };
+    }
+}
+
+impl std::fmt::Debug for MemoryRegion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryRegion")
+            .field("ptr", &self.ptr)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// Applies `config` to memory that is already allocated.
+///
+/// NUMA binding and huge pages only cover the whole pages inside `memory`,
+/// and pages already faulted in are migrated to the node. Memory smaller
+/// than a page is only prefaulted; allocate it inside [`on_node`] to place
+/// it. [`HugePages::Explicit`] is treated as [`HugePages::Transparent`],
+/// or rejected in strict mode.
+///
+/// # Errors
+/// In strict mode, returns an error if an option cannot be applied.
+pub fn apply(memory: &mut [u8], config: &MemoryConfig) -> io::Result<()> {
+    if config.huge_pages == HugePages::Explicit && config.strict {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "huge-page pool memory can only be mapped fresh",
+        ));
+    }
+    let page = sys::page_size();
+    let start = memory.as_mut_ptr() as usize;
+    let first = start.next_multiple_of(page) - start;
+    let whole = memory.len().saturating_sub(first) / page * page;
+    if whole > 0 {
+        let pages = &mut memory[first..first + whole];
+        if config.huge_pages != HugePages::Off {
+            config.check(sys::advise_huge(pages))?;
+        }
+        if let Some(node) = config.numa_node {
+            config.check(sys::bind(pages, node))?;
+        }
+    }
+    if config.prefault {
+        touch(memory, page);
+    }
+    Ok(())
+}
+
+/// Runs `f` with the calling thread's memory policy bound to the node in
+/// `config`, so the pages the global allocator takes from the kernel while
+/// `f` runs come from that node. The previous policy is restored after.
+///
+/// Without a node in `config`, just runs `f`.
+///
+/// # Errors
+/// In strict mode, returns an error if the policy cannot be changed;
+/// `f` does not run then.
+pub fn on_node<R>(config: &MemoryConfig, f: impl FnOnce() -> R) -> io::Result<R> {
+    let Some(node) = config.numa_node else {
+        return Ok(f());
+    };
+    let previous = match sys::bind_thread(node) {
+        Ok(previous) => Some(previous),
+        Err(e) if config.strict => return Err(e),
+        Err(_) => None,
+    };
+    let result = f();
+    if let Some(previous) = previous {
+        sys::restore_thread(previous);
+    }
+    Ok(result)
+}
+
+/// Writes one byte of every page so the kernel faults them all in now.
+fn touch(memory: &mut [u8], page: usize) {
+    for chunk in memory.chunks_mut(page) {
+        let byte = chunk.as_mut_ptr();
+        // SAFETY: `byte` points at the first byte of a non-empty chunk we
+        // borrow mutably. Volatile keeps the write of the unchanged value.
+        unsafe { byte.write_volatile(byte.read_volatile()) };
+    }
+}
+
+/// Returns the error for an option the platform does not have.
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn unsupported(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{what} is not supported on this platform"),
+    )
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use super::{HugePages, MemoryConfig};
+    use std::io;
+    use std::ptr::NonNull;
+
+    /// Binds pages strictly to the nodes in the mask.
+    const MPOL_BIND: libc::c_int = 2;
+    /// Migrates pages already faulted in to the bound node.
+    const MPOL_MF_MOVE: libc::c_uint = 1 << 1;
+    /// Nodes a mask can name.
+    const MAX_NODES: usize = 1024;
+    /// Size of a huge page from the default pool.
+    const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+    type NodeMask = [libc::c_ulong; MAX_NODES / libc::c_ulong::BITS as usize];
+
+    pub(super) fn page_size() -> usize {
+        // SAFETY: `sysconf` has no preconditions.
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        usize::try_from(size).unwrap_or(4096)
+    }
+
+    /// Maps `len` anonymous bytes, from the huge-page pool if `config`
+    /// asks for it.
+    ///
+    /// # Returns
+    /// The mapping, its length, and whether it uses the huge-page pool.
+    pub(super) fn map(len: usize, config: &MemoryConfig) -> io::Result<(NonNull<u8>, usize, bool)> {
+        if config.huge_pages == HugePages::Explicit {
+            let mapped = len.next_multiple_of(HUGE_PAGE_SIZE);
+            match mmap(mapped, libc::MAP_HUGETLB) {
+                Ok(ptr) => return Ok((ptr, mapped, true)),
+                Err(e) if config.strict => return Err(e),
+                Err(_) => {}
+            }
+        }
+        let mapped = len.next_multiple_of(page_size());
+        Ok((mmap(mapped, 0)?, mapped, false))
+    }
+
+    fn mmap(len: usize, flags: libc::c_int) -> io::Result<NonNull<u8>> {
+        // SAFETY: an anonymous private mapping at an address of the
+        // kernel's choosing does not alias any existing memory.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        NonNull::new(ptr.cast()).ok_or_else(|| io::Error::other("mmap returned null"))
+    }
+
+    /// # Safety
+    /// `ptr` and `len` must describe a mapping returned by [`map`].
+    pub(super) unsafe fn unmap(ptr: NonNull<u8>, len: usize) {
+        // SAFETY: per the caller.
+        unsafe { libc::munmap(ptr.as_ptr().cast(), len) };
+    }
+
+    pub(super) fn advise_huge(pages: &mut [u8]) -> io::Result<()> {
+        // SAFETY: `pages` is page-aligned memory we borrow mutably.
+        let rc =
+            unsafe { libc::madvise(pages.as_mut_ptr().cast(), pages.len(), libc::MADV_HUGEPAGE) };
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn node_mask(node: u32) -> io::Result<NodeMask> {
+        let node = node as usize;
+        if node >= MAX_NODES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("NUMA node {node} is out of range"),
+            ));
+        }
+        let bits = libc::c_ulong::BITS as usize;
+        let mut mask = NodeMask::default();
+        mask[node / bits] |= 1 << (node % bits);
+        Ok(mask)
+    }
+
+    pub(super) fn bind(pages: &mut [u8], node: u32) -> io::Result<()> {
+        let mask = node_mask(node)?;
+        // SAFETY: `pages` is page-aligned memory we borrow mutably, and
+        // `mask` holds `MAX_NODES` bits. The kernel reads one bit fewer
+        // than `maxnode`, hence the `+ 1`.
+        let rc = unsafe {
+            libc::syscall(
+                libc::SYS_mbind,
+                pages.as_mut_ptr(),
+                pages.len(),
+                MPOL_BIND,
+                mask.as_ptr(),
+                MAX_NODES + 1,
+                MPOL_MF_MOVE,
+            )
+        };
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// A thread memory policy saved by [`bind_thread`].
+    pub(super) struct Policy {
+        mode: libc::c_int,
+        mask: NodeMask,
+    }
+
+    pub(super) fn bind_thread(node: u32) -> io::Result<Policy> {
+        let mask = node_mask(node)?;
+        let mut previous = Policy {
+            mode: 0,
+            mask: NodeMask::default(),
+        };
+        // SAFETY: both out-pointers are valid for the sizes given.
+        let rc = unsafe {
+            libc::syscall(
+                libc::SYS_get_mempolicy,
+                &mut previous.mode,
+                previous.mask.as_mut_ptr(),
+                MAX_NODES + 1,
+                std::ptr::null_mut::<libc::c_void>(),
+                0,
+            )
+        };
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        set_thread(MPOL_BIND, &mask)?;
+        Ok(previous)
+    }
+
+    pub(super) fn restore_thread(policy: Policy) {
+        let _ = set_thread(policy.mode, &policy.mask);
+    }
+
+    fn set_thread(mode: libc::c_int, mask: &NodeMask) -> io::Result<()> {
+        // SAFETY: `mask` holds `MAX_NODES` bits.
+        let rc =
+            unsafe { libc::syscall(libc::SYS_set_mempolicy, mode, mask.as_ptr(), MAX_NODES + 1) };
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+mod sys {
+    use super::{HugePages, MemoryConfig, unsupported};
+    use std::io;
+    use std::ptr::NonNull;
+
+    pub(super) fn page_size() -> usize {
+        // SAFETY: `sysconf` has no preconditions.
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        usize::try_from(size).unwrap_or(4096)
+    }
+
+    pub(super) fn map(len: usize, config: &MemoryConfig) -> io::Result<(NonNull<u8>, usize, bool)> {
+        if config.huge_pages == HugePages::Explicit && config.strict {
+            return Err(unsupported("huge-page pool memory"));
+        }
+        let len = len.next_multiple_of(page_size());
+        // SAFETY: an anonymous private mapping at an address of the
+        // kernel's choosing does not alias any existing memory.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANON,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        let ptr = NonNull::new(ptr.cast()).ok_or_else(|| io::Error::other("mmap returned null"))?;
+        Ok((ptr, len, false))
+    }
+
+    /// # Safety
+    /// `ptr` and `len` must describe a mapping returned by [`map`].
+    pub(super) unsafe fn unmap(ptr: NonNull<u8>, len: usize) {
+        // SAFETY: per the caller.
+        unsafe { libc::munmap(ptr.as_ptr().cast(), len) };
+    }
+
+    pub(super) fn advise_huge(_pages: &mut [u8]) -> io::Result<()> {
+        Err(unsupported("transparent huge pages"))
+    }
+
+    pub(super) fn bind(_pages: &mut [u8], _node: u32) -> io::Result<()> {
+        Err(unsupported("NUMA binding"))
+    }
+
+    pub(super) struct Policy;
+
+    pub(super) fn bind_thread(_node: u32) -> io::Result<Policy> {
+        Err(unsupported("NUMA binding"))
+    }
+
+    pub(super) fn restore_thread(_policy: Policy) {}
+}
+
+#[cfg(not(unix))]
+mod sys {
+    use super::{HugePages, MemoryConfig, unsupported};
+    use std::alloc::Layout;
+    use std::io;
+    use std::ptr::NonNull;
+
+    pub(super) fn page_size() -> usize {
+        4096
+    }
+
+    pub(super) fn map(len: usize, config: &MemoryConfig) -> io::Result<(NonNull<u8>, usize, bool)> {
+        if config.huge_pages == HugePages::Explicit && config.strict {
+            return Err(unsupported("huge-page pool memory"));
+        }
+        let len = len.next_multiple_of(page_size());
+        let layout = Layout::from_size_align(len, page_size()).map_err(io::Error::other)?;
+        // SAFETY: `layout` has a non-zero size.
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+        let ptr = NonNull::new(ptr).ok_or_else(|| io::Error::from(io::ErrorKind::OutOfMemory))?;
+        Ok((ptr, len, false))
+    }
+
+    /// # Safety
+    /// `ptr` and `len` must describe memory returned by [`map`].
+    pub(super) unsafe fn unmap(ptr: NonNull<u8>, len: usize) {
+        // SAFETY: per the caller, `map` allocated it with this layout.
+        unsafe {
+            std::alloc::dealloc(
+                ptr.as_ptr(),
+                Layout::from_size_align_unchecked(len, page_size()),
+            )
+        };
+    }
+
+    pub(super) fn advise_huge(_pages: &mut [u8]) -> io::Result<()> {
+        Err(unsupported("transparent huge pages"))
+    }
+
+    pub(super) fn bind(_pages: &mut [u8], _node: u32) -> io::Result<()> {
+        Err(unsupported("NUMA binding"))
+    }
+
+    pub(super) struct Policy;
+
+    pub(super) fn bind_thread(_node: u32) -> io::Result<Policy> {
+        Err(unsupported("NUMA binding"))
+    }
+
+    pub(super) fn restore_thread(_policy: Policy) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_region_is_zeroed_and_aligned() {
+        let mut region = MemoryRegion::allocate(10_000, &MemoryConfig::default()).unwrap();
+        assert_eq!(region.len(), 10_000);
+        assert!(!region.is_empty());
+        assert_eq!(region.as_ptr() as usize % sys::page_size(), 0);
+        assert!(region.as_slice().iter().all(|&b| b == 0));
+
+        region.as_mut_slice()[9_999] = 7;
+        assert_eq!(region.as_slice()[9_999], 7);
+    }
+
+    #[test]
+    fn test_best_effort_placement_never_fails() {
+        for huge_pages in [HugePages::Off, HugePages::Transparent, HugePages::Explicit] {
+            let config = MemoryConfig::new()
+                .numa_node(0)
+                .huge_pages(huge_pages)
+                .prefault(true);
+            let mut region = MemoryRegion::allocate(64 * 1024, &config).unwrap();
+            region.as_mut_slice().fill(1);
+
+            let mut heap = vec![0u8; 64 * 1024];
+            apply(&mut heap, &config).unwrap();
+            assert_eq!(on_node(&config, || 5).unwrap(), 5);
+        }
+    }
+
+    #[test]
+    fn test_strict_rejects_what_cannot_be_applied() {
+        let config = MemoryConfig::new().numa_node(1000).strict(true);
+        assert!(MemoryRegion::allocate(4096, &config).is_err());
+        assert!(on_node(&config, || unreachable!()).is_err());
+
+        let config = MemoryConfig::new()
+            .huge_pages(HugePages::Explicit)
+            .strict(true);
+        let mut heap = vec![0u8; 8192];
+        let err = apply(&mut heap, &config).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn test_config_accessors() {
+        let config = MemoryConfig::new()
+            .numa_node(1)
+            .huge_pages(HugePages::Transparent);
+        assert_eq!(config.node(), Some(1));
+        assert_eq!(config.pages(), HugePages::Transparent);
+        assert_eq!(MemoryConfig::default().node(), None);
+    }
+}
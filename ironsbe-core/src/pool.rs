@@ -0,0 +1,524 @@
+//! Tiered buffer pool with size classes and usage counters.
+//!
+//! [`BufferPool`](crate::buffer::BufferPool) hands out buffers of one
+//! compile-time size. Gateways that handle both small order messages and
+//! multi-megabyte snapshots would either waste memory on large buffers for
+//! every message or run out of room. A [`TieredBufferPool`] keeps a queue
+//! per size class and serves each request from the smallest class that
+//! fits, spilling to larger classes when that one is empty.
+//!
+//! Every pool counts acquires, misses and the high-water mark of buffers
+//! in use, exposed as [`PoolStats`], so capacities can be sized from
+//! production telemetry instead of guessed.
+
+use crate::buffer::{AlignedVec, ReadBuffer, WriteBuffer};
+use crate::canary;
+use crossbeam_queue::ArrayQueue;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Size classes suggested for market data gateways: 2KB, 16KB, 64KB and
+/// 1MB.
+pub const DEFAULT_SIZE_CLASSES: [usize; 4] = [2 * 1024, 16 * 1024, 64 * 1024, 1024 * 1024];
+
+/// Usage counters of a pool or of one size class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PoolStats {
+    /// Acquire calls that returned a buffer.
+    pub acquires: u64,
+    /// Acquire calls that found no pooled buffer, whether they then failed
+    /// or were served by a larger class or a fallback allocation.
+    pub misses: u64,
+    /// Buffers allocated because the pool was exhausted.
+    pub fallbacks: u64,
+    /// Buffers currently handed out.
+    pub in_use: u64,
+    /// Most buffers ever handed out at once.
+    pub high_water: u64,
+}
+
+/// Lock-free counters behind [`PoolStats`].
+#[derive(Debug, Default)]
+pub(crate) struct PoolCounters {
+    acquires: AtomicU64,
+    misses: AtomicU64,
+    fallbacks: AtomicU64,
+    in_use: AtomicU64,
+    high_water: AtomicU64,
+}
+
+impl PoolCounters {
+    /// Records a buffer handed out.
+    #[inline]
+    pub(crate) fn acquired(&self) {
+        self.acquires.fetch_add(1, Ordering::Relaxed);
+        let in_use = self.in_use.fetch_add(1, Ordering::Relaxed) + 1;
+        self.high_water.fetch_max(in_use, Ordering::Relaxed);
+    }
+
+    /// Records an acquire that found no pooled buffer.
+    #[inline]
+    pub(crate) fn missed(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a buffer allocated because the pool was exhausted.
+    #[inline]
+    pub(crate) fn fell_back(&self) {
+        self.fallbacks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a buffer given back.
+    #[inline]
+    pub(crate) fn released(&self) {
+        // Saturate: callers may release buffers they did not acquire.
+        let _ = self
+            .in_use
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+    }
+
+    /// Returns a snapshot of the counters.
+    pub(crate) fn snapshot(&self) -> PoolStats {
+        PoolStats {
+            acquires: self.acquires.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            fallbacks: self.fallbacks.load(Ordering::Relaxed),
+            in_use: self.in_use.load(Ordering::Relaxed),
+            high_water: self.high_water.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// What a [`TieredBufferPool`] does when no class can serve a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FallbackPolicy {
+    /// Return `None`.
+    #[default]
+    Fail,
+    /// Allocate a buffer of the smallest fitting class. It joins the pool
+    /// on release if the class has room, and is freed otherwise.
+    Allocate,
+}
+
+/// Configuration of a [`TieredBufferPool`].
+#[derive(Debug, Clone, Default)]
+pub struct TieredPoolConfig {
+    tiers: Vec<(usize, usize)>,
+    fallback: FallbackPolicy,
+}
+
+impl TieredPoolConfig {
+    /// Creates a configuration without size classes.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a size class of `capacity` buffers of `buffer_size` bytes.
+    #[must_use]
+    pub fn tier(mut self, buffer_size: usize, capacity: usize) -> Self {
+        self.tiers.push((buffer_size, capacity));
+        self
+    }
+
+    /// Adds every [`DEFAULT_SIZE_CLASSES`] size with the same capacity.
+    #[must_use]
+    pub fn default_tiers(self, capacity: usize) -> Self {
+        DEFAULT_SIZE_CLASSES
+            .into_iter()
+            .fold(self, |config, size| config.tier(size, capacity))
+    }
+
+    /// Sets what happens when every fitting class is empty.
+    #[must_use]
+    pub fn fallback(mut self, policy: FallbackPolicy) -> Self {
+        self.fallback = policy;
+        self
+    }
+}
+
+/// One size class.
+struct Tier {
+    buffer_size: usize,
+    capacity: usize,
+    buffers: ArrayQueue<AlignedVec>,
+    counters: PoolCounters,
+}
+
+impl Tier {
+    /// Returns the number of bytes callers may write in a buffer of this
+    /// class: all of it in release builds, without the trailing canary
+    /// region in debug builds.
+    fn usable_len(&self) -> usize {
+        if canary::ENABLED {
+            self.buffer_size - canary::CANARY_LEN
+        } else {
+            self.buffer_size
+        }
+    }
+
+    /// Allocates a zeroed buffer of this class.
+    fn allocate(&self) -> AlignedVec {
+        let mut buffer = AlignedVec::zeroed(self.buffer_size);
+        self.fill_canary(&mut buffer);
+        buffer
+    }
+
+    fn fill_canary(&self, buffer: &mut AlignedVec) {
+        if canary::ENABLED {
+            let usable = self.usable_len();
+            canary::fill(&mut buffer.as_mut_slice()[usable..]);
+        }
+    }
+
+    /// Zeroes a buffer and queues it again, or frees it if the class is
+    /// full.
+    fn release(&self, mut buffer: AlignedVec) {
+        if canary::ENABLED {
+            let usable = self.usable_len();
+            canary::check(&buffer.as_slice()[usable..], usable, "tiered pool buffer");
+        }
+        buffer.as_mut_slice().fill(0);
+        self.fill_canary(&mut buffer);
+        self.counters.released();
+        let _ = self.buffers.push(buffer);
+    }
+}
+
+/// Pool of aligned heap buffers in several size classes.
+///
+/// Cloning shares the pool. Buffers come back to their class when the
+/// [`TieredBuffer`] is dropped.
+///
+/// As with [`BufferPool`](crate::buffer::BufferPool), debug builds keep a
+/// canary region at the end of every buffer and check it on release.
+///
+/// # Example
+/// ```
+/// use ironsbe_core::pool::{FallbackPolicy, TieredBufferPool, TieredPoolConfig};
+///
+/// let pool = TieredBufferPool::new(
+///     TieredPoolConfig::new()
+///         .tier(2048, 64)
+///         .tier(1024 * 1024, 2)
+///         .fallback(FallbackPolicy::Allocate),
+/// );
+/// let snapshot = pool.acquire(300_000).expect("1MB class");
+/// assert_eq!(snapshot.buffer_size(), 1024 * 1024);
+/// ```
+#[derive(Clone)]
+pub struct TieredBufferPool {
+    tiers: Arc<[Tier]>,
+    fallback: FallbackPolicy,
+}
+
+impl TieredBufferPool {
+    /// Creates a pool and allocates every buffer of every class.
+    ///
+    /// # Panics
+    /// Panics if no class is configured, if two classes have the same size,
+    /// or if a class is not larger than the debug canary region.
+    #[must_use]
+    pub fn new(config: TieredPoolConfig) -> Self {
+        let mut sizes = config.tiers;
+        sizes.sort_unstable_by_key(|&(size, _)| size);
+        assert!(
+            !sizes.is_empty(),
+            "tiered pool needs at least one size class"
+        );
+        assert!(
+            sizes.windows(2).all(|w| w[0].0 != w[1].0),
+            "tiered pool size classes must be distinct"
+        );
+        assert!(
+            sizes[0].0 > canary::CANARY_LEN,
+            "pooled buffers must be larger than the canary region"
+        );
+
+        let tiers = sizes
+            .into_iter()
+            .map(|(buffer_size, capacity)| {
+                let tier = Tier {
+                    buffer_size,
+                    capacity,
+                    buffers: ArrayQueue::new(capacity.max(1)),
+                    counters: PoolCounters::default(),
+                };
+                for _ in 0..capacity {
+                    let _ = tier.buffers.push(tier.allocate());
+                }
+                tier
+            })
+            .collect();
+        Self {
+            tiers,
+            fallback: config.fallback,
+        }
+    }
+
+    /// Acquires a buffer with at least `len` usable bytes.
+    ///
+    /// Serves the request from the smallest class that fits, or the next
+    /// larger class with a free buffer. When all of them are empty, the
+    /// [`FallbackPolicy`] decides. Returns `None` if no class is large
+    /// enough or the pool is exhausted and does not fall back.
+    #[must_use]
+    pub fn acquire(&self, len: usize) -> Option<TieredBuffer> {
+        let first = self.tiers.iter().position(|t| t.usable_len() >= len)?;
+        for (index, tier) in self.tiers.iter().enumerate().skip(first) {
+            if let Some(buffer) = tier.buffers.pop() {
+                tier.counters.acquired();
+                return Some(self.hand_out(index, buffer));
+            }
+            tier.counters.missed();
+        }
+        match self.fallback {
+            FallbackPolicy::Fail => None,
+            FallbackPolicy::Allocate => {
+                let tier = &self.tiers[first];
+                tier.counters.fell_back();
+                tier.counters.acquired();
+                Some(self.hand_out(first, tier.allocate()))
+            }
+        }
+    }
+
+    fn hand_out(&self, tier: usize, buffer: AlignedVec) -> TieredBuffer {
+        TieredBuffer {
+            buffer: Some(buffer),
+            len: 0,
+            tier,
+            pool: self.clone(),
+        }
+    }
+
+    /// Returns the counters of every class, smallest first, with the class
+    /// size.
+    #[must_use]
+    pub fn tier_stats(&self) -> Vec<(usize, PoolStats)> {
+        self.tiers
+            .iter()
+            .map(|t| (t.buffer_size, t.counters.snapshot()))
+            .collect()
+    }
+
+    /// Returns the counters summed over every class.
+    ///
+    /// The high-water mark is the sum of the per-class marks, an upper
+    /// bound on the buffers ever in use at once.
+    #[must_use]
+    pub fn stats(&self) -> PoolStats {
+        self.tiers
+            .iter()
+            .map(|t| t.counters.snapshot())
+            .fold(PoolStats::default(), |total, s| PoolStats {
+                acquires: total.acquires + s.acquires,
+                misses: total.misses + s.misses,
+                fallbacks: total.fallbacks + s.fallbacks,
+                in_use: total.in_use + s.in_use,
+                high_water: total.high_water + s.high_water,
+            })
+    }
+
+    /// Returns the number of free buffers in the class of `buffer_size`
+    /// bytes, or `None` if there is no such class.
+    #[must_use]
+    pub fn available(&self, buffer_size: usize) -> Option<usize> {
+        self.tiers
+            .iter()
+            .find(|t| t.buffer_size == buffer_size)
+            .map(|t| t.buffers.len())
+    }
+}
+
+impl std::fmt::Debug for TieredBufferPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut list = f.debug_list();
+        for tier in self.tiers.iter() {
+            list.entry(&format_args!(
+                "{}B: {}/{} available",
+                tier.buffer_size,
+                tier.buffers.len(),
+                tier.capacity
+            ));
+        }
+        list.finish()
+    }
+}
+
+/// Buffer acquired from a [`TieredBufferPool`], returned to its class when
+/// dropped.
+///
+/// Like [`PooledBuffer`](crate::buffer::PooledBuffer), it dereferences to
+/// its first [`len`](Self::len) bytes; fill it through
+/// [`writable`](Self::writable) and then [`set_len`](Self::set_len).
+pub struct TieredBuffer {
+    /// Taken out in `drop` to hand back to the pool.
+    buffer: Option<AlignedVec>,
+    len: usize,
+    tier: usize,
+    pool: TieredBufferPool,
+}
+
+impl TieredBuffer {
+    /// Returns the number of valid bytes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no byte is valid.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the size of the buffer's class.
+    #[must_use]
+    pub fn buffer_size(&self) -> usize {
+        self.tier().buffer_size
+    }
+
+    /// Returns the whole writable region, regardless of the current
+    /// length.
+    pub fn writable(&mut self) -> &mut [u8] {
+        let usable = self.tier().usable_len();
+        &mut self.aligned_mut().as_mut_slice()[..usable]
+    }
+
+    /// Sets the number of valid bytes.
+    ///
+    /// # Panics
+    /// Panics if `len` exceeds the writable region.
+    pub fn set_len(&mut self, len: usize) {
+        let usable = self.tier().usable_len();
+        assert!(
+            len <= usable,
+            "length {len} exceeds pooled buffer of {usable} bytes"
+        );
+        self.len = len;
+    }
+
+    fn tier(&self) -> &Tier {
+        &self.pool.tiers[self.tier]
+    }
+
+    fn aligned(&self) -> &AlignedVec {
+        self.buffer.as_ref().expect("buffer present until drop")
+    }
+
+    fn aligned_mut(&mut self) -> &mut AlignedVec {
+        self.buffer.as_mut().expect("buffer present until drop")
+    }
+}
+
+impl std::ops::Deref for TieredBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.aligned().as_slice()[..self.len]
+    }
+}
+
+impl std::ops::DerefMut for TieredBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        let len = self.len;
+        &mut self.aligned_mut().as_mut_slice()[..len]
+    }
+}
+
+impl AsRef<[u8]> for TieredBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Drop for TieredBuffer {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            self.tier().release(buffer);
+        }
+    }
+}
+
+impl std::fmt::Debug for TieredBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TieredBuffer")
+            .field("buffer_size", &self.buffer_size())
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(fallback: FallbackPolicy) -> TieredBufferPool {
+        TieredBufferPool::new(
+            TieredPoolConfig::new()
+                .tier(16 * 1024, 1)
+                .tier(2048, 2)
+                .fallback(fallback),
+        )
+    }
+
+    #[test]
+    fn test_picks_smallest_fitting_class_then_spills() {
+        let pool = pool(FallbackPolicy::Fail);
+        let a = pool.acquire(100).expect("2KB class");
+        let b = pool.acquire(100).expect("2KB class");
+        assert_eq!((a.buffer_size(), b.buffer_size()), (2048, 2048));
+
+        let c = pool.acquire(100).expect("spills to 16KB");
+        assert_eq!(c.buffer_size(), 16 * 1024);
+        assert!(pool.acquire(100).is_none());
+        assert!(pool.acquire(1024 * 1024).is_none(), "no class fits");
+
+        let stats = pool.tier_stats();
+        assert_eq!(stats[0].0, 2048);
+        assert_eq!(stats[0].1.acquires, 2);
+        assert_eq!(stats[0].1.misses, 2);
+        assert_eq!(stats[1].1.misses, 1);
+        assert_eq!(pool.stats().in_use, 3);
+
+        drop((a, b, c));
+        let stats = pool.stats();
+        assert_eq!(stats.in_use, 0);
+        assert_eq!(stats.high_water, 3);
+        assert_eq!(pool.available(2048), Some(2));
+        assert_eq!(pool.available(4096), None);
+    }
+
+    #[test]
+    fn test_fallback_allocates_and_frees_extra_buffers() {
+        let pool = pool(FallbackPolicy::Allocate);
+        let held: Vec<_> = (0..4).map(|_| pool.acquire(10).expect("buffer")).collect();
+        assert_eq!(held[3].buffer_size(), 2048);
+        assert_eq!(pool.stats().fallbacks, 1);
+        assert_eq!(pool.tier_stats()[0].1.high_water, 3);
+
+        drop(held);
+        assert_eq!(pool.available(2048), Some(2));
+        assert_eq!(pool.available(16 * 1024), Some(1));
+    }
+
+    #[test]
+    fn test_buffer_is_zeroed_on_reuse() {
+        let pool = pool(FallbackPolicy::Fail);
+        let mut buf = pool.acquire(16 * 1024 - canary::CANARY_LEN).expect("16KB");
+        buf.writable()[..4].copy_from_slice(b"abcd");
+        buf.set_len(4);
+        assert_eq!(&*buf, b"abcd");
+        drop(buf);
+
+        let mut buf = pool.acquire(5000).expect("16KB");
+        assert!(buf.writable().iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "distinct")]
+    fn test_rejects_duplicate_classes() {
+        let _ = TieredBufferPool::new(TieredPoolConfig::new().tier(2048, 1).tier(2048, 1));
+    }
+}
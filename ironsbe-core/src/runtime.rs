@@ -0,0 +1,374 @@
+//! CPU affinity for the threads on the message hot path.
+//!
+//! A thread that migrates between cores loses its warm caches, and one
+//! that shares a core with unrelated work waits behind it. A
+//! [`CoreConfig`] says which cores the hot-path threads run on:
+//! - the accept thread of a server
+//! - the per-session IO threads, assigned round-robin
+//! - the channel consumer threads, such as market-data shard workers
+//!
+//! With [`isolate`](CoreConfig::isolate) set, the remaining threads, e.g.
+//! the workers of a tokio runtime, are kept off those cores; see
+//! [`CoreConfig::runtime_cores`].
+//!
+//! Pinning is a Linux feature. Elsewhere, and when a core is not available
+//! to the process, pinning is skipped unless the configuration is
+//! [`strict`](CoreConfig::strict), in which case the call fails instead.
+
+use std::io;
+
+/// Cores for the hot-path threads.
+///
+/// The default pins nothing.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CoreConfig {
+    accept: Option<usize>,
+    sessions: Vec<usize>,
+    consumers: Vec<usize>,
+    isolate: bool,
+    strict: bool,
+}
+
+impl CoreConfig {
+    /// Creates the default configuration.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pins the accept thread to `core`.
+    #[must_use]
+    pub fn accept_core(mut self, core: usize) -> Self {
+        self.accept = Some(core);
+        self
+    }
+
+    /// Pins the per-session IO threads to `cores`, round-robin in the
+    /// order sessions start.
+    #[must_use]
+    pub fn session_cores(mut self, cores: impl IntoIterator<Item = usize>) -> Self {
+        self.sessions = cores.into_iter().collect();
+        self
+    }
+
+    /// Pins channel consumer thread `i` to the `i`-th of `cores`,
+    /// wrapping around when there are more consumers than cores.
+    #[must_use]
+    pub fn consumer_cores(mut self, cores: impl IntoIterator<Item = usize>) -> Self {
+        self.consumers = cores.into_iter().collect();
+        self
+    }
+
+    /// Sets whether threads outside the hot path are kept off the hot-path
+    /// cores.
+    #[must_use]
+    pub fn isolate(mut self, isolate: bool) -> Self {
+        self.isolate = isolate;
+        self
+    }
+
+    /// Sets whether cores that cannot be pinned are errors. When false,
+    /// the default, pinning is skipped.
+    #[must_use]
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Returns the core of the accept thread, if any.
+    #[must_use]
+    pub fn accept(&self) -> Option<usize> {
+        self.accept
+    }
+
+    /// Returns the core of the `index`-th session IO thread, if any.
+    #[must_use]
+    pub fn session(&self, index: usize) -> Option<usize> {
+        round_robin(&self.sessions, index)
+    }
+
+    /// Returns the core of the `index`-th consumer thread, if any.
+    #[must_use]
+    pub fn consumer(&self, index: usize) -> Option<usize> {
+        round_robin(&self.consumers, index)
+    }
+
+    /// Returns the consumer cores in order.
+    #[must_use]
+    pub fn consumers(&self) -> &[usize] {
+        &self.consumers
+    }
+
+    /// Returns true if threads outside the hot path avoid its cores.
+    #[must_use]
+    pub fn is_isolated(&self) -> bool {
+        self.isolate
+    }
+
+    /// Returns true if cores that cannot be pinned are errors.
+    #[must_use]
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Returns every hot-path core, sorted and without duplicates.
+    #[must_use]
+    pub fn hot_cores(&self) -> Vec<usize> {
+        let mut cores: Vec<usize> = self
+            .accept
+            .iter()
+            .chain(&self.sessions)
+            .chain(&self.consumers)
+            .copied()
+            .collect();
+        cores.sort_unstable();
+        cores.dedup();
+        cores
+    }
+
+    /// Returns the cores threads outside the hot path may run on: the
+    /// cores available to the calling thread minus the hot-path cores.
+    /// Returns `None` when the configuration does not isolate the hot
+    /// path, meaning those threads are left alone.
+    ///
+    /// # Errors
+    /// Returns an error if the available cores cannot be read, or if the
+    /// hot path takes all of them.
+    pub fn runtime_cores(&self) -> io::Result<Option<Vec<usize>>> {
+        if !self.isolate {
+            return Ok(None);
+        }
+        let hot = self.hot_cores();
+        let cores: Vec<usize> = available_cores()?
+            .into_iter()
+            .filter(|core| hot.binary_search(core).is_err())
+            .collect();
+        if cores.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the hot path leaves no cores for other threads",
+            ));
+        }
+        Ok(Some(cores))
+    }
+
+    /// Pins the calling thread to `core`; does nothing for `None`.
+    ///
+    /// # Errors
+    /// In strict mode, returns an error if the thread cannot be pinned.
+    pub fn pin(&self, core: Option<usize>) -> io::Result<()> {
+        match core {
+            Some(core) => self.check(pin_current_thread(&[core])),
+            None => Ok(()),
+        }
+    }
+
+    /// Pins the calling thread as the accept thread.
+    ///
+    /// # Errors
+    /// In strict mode, returns an error if the thread cannot be pinned.
+    pub fn pin_accept(&self) -> io::Result<()> {
+        self.pin(self.accept)
+    }
+
+    /// Pins the calling thread as the `index`-th session IO thread.
+    ///
+    /// # Errors
+    /// In strict mode, returns an error if the thread cannot be pinned.
+    pub fn pin_session(&self, index: usize) -> io::Result<()> {
+        self.pin(self.session(index))
+    }
+
+    /// Pins the calling thread as the `index`-th consumer thread.
+    ///
+    /// # Errors
+    /// In strict mode, returns an error if the thread cannot be pinned.
+    pub fn pin_consumer(&self, index: usize) -> io::Result<()> {
+        self.pin(self.consumer(index))
+    }
+
+    /// Confines the calling thread to [`runtime_cores`](Self::runtime_cores);
+    /// does nothing without isolation. Meant for the thread-start hook of
+    /// an async runtime.
+    ///
+    /// # Errors
+    /// Returns an error if the runtime cores cannot be computed, or, in
+    /// strict mode, if the thread cannot be confined to them.
+    pub fn pin_runtime_thread(&self) -> io::Result<()> {
+        match self.runtime_cores()? {
+            Some(cores) => self.check(pin_current_thread(&cores)),
+            None => Ok(()),
+        }
+    }
+
+    /// Turns the result of a pinning step into an error only in strict
+    /// mode.
+    fn check(&self, result: io::Result<()>) -> io::Result<()> {
+        match result {
+            Err(e) if self.strict => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn round_robin(cores: &[usize], index: usize) -> Option<usize> {
+    if cores.is_empty() {
+        None
+    } else {
+        Some(cores[index % cores.len()])
+    }
+}
+
+/// Restricts the calling thread to `cores`.
+///
+/// # Errors
+/// Returns an error if `cores` is empty, names a core the process may not
+/// use, or the platform cannot pin threads.
+pub fn pin_current_thread(cores: &[usize]) -> io::Result<()> {
+    if cores.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no cores to pin the thread to",
+        ));
+    }
+    sys::set_affinity(cores)
+}
+
+/// Returns the cores the calling thread may run on, in ascending order.
+///
+/// # Errors
+/// Returns an error if the affinity mask cannot be read.
+pub fn available_cores() -> io::Result<Vec<usize>> {
+    sys::affinity()
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::io;
+
+    pub(super) fn set_affinity(cores: &[usize]) -> io::Result<()> {
+        // SAFETY: an all-zero `cpu_set_t` is an empty set.
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        let max = 8 * std::mem::size_of::<libc::cpu_set_t>();
+        for &core in cores {
+            if core >= max {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("core {core} is out of range"),
+                ));
+            }
+            // SAFETY: `core` is inside the set, checked above.
+            unsafe { libc::CPU_SET(core, &mut set) };
+        }
+        // SAFETY: `set` is a valid `cpu_set_t` of the size passed; pid 0 is
+        // the calling thread.
+        let rc = unsafe { libc::sched_setaffinity(0, std::mem::size_of_val(&set), &set) };
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub(super) fn affinity() -> io::Result<Vec<usize>> {
+        // SAFETY: an all-zero `cpu_set_t` is an empty set.
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        // SAFETY: the kernel writes at most `size_of_val(&set)` bytes into
+        // `set`; pid 0 is the calling thread.
+        let rc = unsafe { libc::sched_getaffinity(0, std::mem::size_of_val(&set), &mut set) };
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let max = 8 * std::mem::size_of::<libc::cpu_set_t>();
+        // SAFETY: every index is inside the set.
+        Ok((0..max)
+            .filter(|&core| unsafe { libc::CPU_ISSET(core, &set) })
+            .collect())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::io;
+
+    pub(super) fn set_affinity(_cores: &[usize]) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "thread affinity is not supported on this platform",
+        ))
+    }
+
+    pub(super) fn affinity() -> io::Result<Vec<usize>> {
+        let count = std::thread::available_parallelism()?.get();
+        Ok((0..count).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_pins_nothing() {
+        let config = CoreConfig::new();
+        assert_eq!(config.accept(), None);
+        assert_eq!(config.session(0), None);
+        assert_eq!(config.consumer(3), None);
+        assert!(config.hot_cores().is_empty());
+        assert_eq!(config.runtime_cores().unwrap(), None);
+        config.pin_accept().unwrap();
+        config.pin_runtime_thread().unwrap();
+    }
+
+    #[test]
+    fn test_round_robin_assignment() {
+        let config = CoreConfig::new()
+            .accept_core(1)
+            .session_cores([2, 3])
+            .consumer_cores([4, 2]);
+        assert_eq!(config.accept(), Some(1));
+        assert_eq!(config.session(0), Some(2));
+        assert_eq!(config.session(1), Some(3));
+        assert_eq!(config.session(2), Some(2));
+        assert_eq!(config.consumer(1), Some(2));
+        assert_eq!(config.consumers(), &[4, 2]);
+        assert_eq!(config.hot_cores(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_runtime_cores_exclude_hot_path() {
+        let available = available_cores().unwrap();
+        assert!(!available.is_empty());
+        let hot = available[0];
+        let config = CoreConfig::new().accept_core(hot).isolate(true);
+        if available.len() == 1 {
+            assert!(config.runtime_cores().is_err());
+        } else {
+            let cores = config.runtime_cores().unwrap().unwrap();
+            assert!(!cores.contains(&hot));
+            assert_eq!(cores.len(), available.len() - 1);
+        }
+    }
+
+    #[test]
+    fn test_unavailable_core_is_skipped_unless_strict() {
+        let config = CoreConfig::new().accept_core(usize::MAX);
+        std::thread::spawn(move || {
+            config.pin_accept().unwrap();
+            assert!(config.strict(true).pin_accept().is_err());
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_pin_current_thread() {
+        let core = available_cores().unwrap()[0];
+        std::thread::spawn(move || {
+            pin_current_thread(&[core]).unwrap();
+            assert_eq!(available_cores().unwrap(), vec![core]);
+        })
+        .join()
+        .unwrap();
+    }
+}
@@ -0,0 +1,471 @@
+//! Runtime-attachable message tap for live debugging.
+//!
+//! A [`MessageTap`] is placed wherever frames pass through a gateway
+//! (server sessions, client connections, feed handlers) and is told about
+//! every frame with [`MessageTap::observe`]. While nothing is attached that
+//! costs one relaxed atomic load. An admin endpoint or console command
+//! [`attach`](MessageTap::attach)es a [`TapSink`] with a [`TapFilter`] at
+//! runtime, and the matching frames are copied to it with a timestamp, so
+//! operators can inspect live traffic without restarting the process or
+//! running an external capture.
+//!
+//! Sinks are provided for `std::sync::mpsc` channels and, through
+//! [`TapWriter`], for anything implementing [`Write`].
+
+use crate::header::MessageHeader;
+use crate::types::Timestamp;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{Sender, SyncSender, TrySendError};
+use std::sync::{Mutex, PoisonError};
+
+/// Direction of a tapped frame relative to the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TapDirection {
+    /// Received from a peer.
+    Inbound,
+    /// Sent to a peer.
+    Outbound,
+}
+
+impl fmt::Display for TapDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Inbound => "in",
+            Self::Outbound => "out",
+        })
+    }
+}
+
+/// Selects the frames a tap copies.
+///
+/// The default copies every frame in both directions.
+#[derive(Debug, Clone)]
+pub struct TapFilter {
+    sample_every: u64,
+    templates: Option<Vec<u16>>,
+    direction: Option<TapDirection>,
+}
+
+impl Default for TapFilter {
+    fn default() -> Self {
+        Self {
+            sample_every: 1,
+            templates: None,
+            direction: None,
+        }
+    }
+}
+
+impl TapFilter {
+    /// Creates a filter matching every frame.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copies only one in every `n` matching frames, starting with the
+    /// first. Zero is treated as one.
+    #[must_use]
+    pub fn sample_every(mut self, n: u64) -> Self {
+        self.sample_every = n.max(1);
+        self
+    }
+
+    /// Copies only frames whose header carries one of `templates`.
+    #[must_use]
+    pub fn templates(mut self, templates: impl IntoIterator<Item = u16>) -> Self {
+        self.templates = Some(templates.into_iter().collect());
+        self
+    }
+
+    /// Copies only frames travelling in `direction`.
+    #[must_use]
+    pub fn direction(mut self, direction: TapDirection) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Returns true if a frame passes the template and direction criteria,
+    /// before sampling.
+    #[must_use]
+    pub fn matches(&self, direction: TapDirection, template_id: Option<u16>) -> bool {
+        self.direction.is_none_or(|d| d == direction)
+            && self
+                .templates
+                .as_ref()
+                .is_none_or(|t| template_id.is_some_and(|id| t.contains(&id)))
+    }
+}
+
+/// Copy of one tapped frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TapRecord {
+    /// When the frame was observed.
+    pub timestamp: Timestamp,
+    /// Direction of the frame.
+    pub direction: TapDirection,
+    /// Caller-chosen stream identifier, such as a session or feed ID.
+    pub stream: u64,
+    /// Template ID from the frame's message header, if it has one.
+    pub template_id: Option<u16>,
+    /// The frame bytes.
+    pub bytes: Vec<u8>,
+}
+
+impl TapRecord {
+    /// Parses a line written by [`TapWriter`], so captures can be read
+    /// back for replay or auditing.
+    ///
+    /// Returns `None` if the line is malformed or its length does not
+    /// match its bytes.
+    #[must_use]
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.split_ascii_whitespace();
+        let timestamp = Timestamp::new(parts.next()?.parse().ok()?);
+        let direction = match parts.next()? {
+            "in" => TapDirection::Inbound,
+            "out" => TapDirection::Outbound,
+            _ => return None,
+        };
+        let stream = parts.next()?.strip_prefix("stream=")?.parse().ok()?;
+        let template_id = match parts.next()?.strip_prefix("template=")? {
+            "-" => None,
+            id => Some(id.parse().ok()?),
+        };
+        let len: usize = parts.next()?.strip_prefix("len=")?.parse().ok()?;
+        let hex = parts.next().unwrap_or("");
+        if parts.next().is_some() || hex.len() != len * 2 {
+            return None;
+        }
+        let bytes = hex
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+            .collect::<Option<_>>()?;
+        Some(Self {
+            timestamp,
+            direction,
+            stream,
+            template_id,
+            bytes,
+        })
+    }
+}
+
+impl fmt::Display for TapRecord {
+    /// Writes one line: timestamp, direction, stream, template, length and
+    /// the bytes in hex.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} stream={} template=",
+            self.timestamp.as_nanos(),
+            self.direction,
+            self.stream
+        )?;
+        match self.template_id {
+            Some(id) => write!(f, "{id}")?,
+            None => f.write_str("-")?,
+        }
+        write!(f, " len={} ", self.bytes.len())?;
+        for byte in &self.bytes {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Destination of tapped frames.
+///
+/// Sinks run on the thread that observed the frame, so they should hand
+/// records off rather than block. Returning an error detaches the sink.
+pub trait TapSink: Send {
+    /// Receives one tapped frame.
+    ///
+    /// # Errors
+    /// Returns an error if the sink can no longer accept records.
+    fn record(&mut self, record: TapRecord) -> io::Result<()>;
+}
+
+impl TapSink for Sender<TapRecord> {
+    fn record(&mut self, record: TapRecord) -> io::Result<()> {
+        self.send(record)
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+}
+
+/// Drops records while the channel is full instead of waiting.
+impl TapSink for SyncSender<TapRecord> {
+    fn record(&mut self, record: TapRecord) -> io::Result<()> {
+        match self.try_send(record) {
+            Ok(()) | Err(TrySendError::Full(_)) => Ok(()),
+            Err(TrySendError::Disconnected(_)) => Err(io::Error::from(io::ErrorKind::BrokenPipe)),
+        }
+    }
+}
+
+/// Sink writing each record as a [`Display`](fmt::Display) line.
+#[derive(Debug)]
+pub struct TapWriter<W> {
+    writer: W,
+}
+
+impl<W: Write + Send> TapWriter<W> {
+    /// Creates a sink writing to `writer`.
+    ///
+    /// Each record is written with its own `write` calls, so wrap files in
+    /// a [`BufWriter`](std::io::BufWriter) to batch them.
+    #[must_use]
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Returns the underlying writer.
+    #[must_use]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Send> TapSink for TapWriter<W> {
+    fn record(&mut self, record: TapRecord) -> io::Result<()> {
+        writeln!(self.writer, "{record}")
+    }
+}
+
+struct Attached {
+    filter: TapFilter,
+    sink: Box<dyn TapSink>,
+    /// Frames that passed the filter, used for sampling.
+    matched: u64,
+}
+
+/// Tap point for the frames of one gateway.
+///
+/// Share it behind an [`Arc`](std::sync::Arc) between the code that sees
+/// frames and the admin code that attaches and detaches sinks; every method
+/// takes `&self`.
+#[derive(Default)]
+pub struct MessageTap {
+    active: AtomicBool,
+    attached: Mutex<Option<Attached>>,
+    captured: AtomicU64,
+}
+
+impl MessageTap {
+    /// Creates a tap with nothing attached.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts copying frames matching `filter` to `sink`, replacing any
+    /// sink already attached.
+    pub fn attach(&self, filter: TapFilter, sink: impl TapSink + 'static) {
+        let mut attached = self.lock();
+        *attached = Some(Attached {
+            filter,
+            sink: Box::new(sink),
+            matched: 0,
+        });
+        self.active.store(true, Ordering::Release);
+    }
+
+    /// Stops copying frames. Returns false if nothing was attached.
+    pub fn detach(&self) -> bool {
+        let mut attached = self.lock();
+        self.active.store(false, Ordering::Release);
+        attached.take().is_some()
+    }
+
+    /// Returns true if a sink is attached.
+    #[must_use]
+    pub fn is_attached(&self) -> bool {
+        self.active.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of frames copied to sinks so far.
+    #[must_use]
+    pub fn captured(&self) -> u64 {
+        self.captured.load(Ordering::Relaxed)
+    }
+
+    /// Reports a frame passing through the tap point.
+    ///
+    /// Copies it to the attached sink if it passes the filter and the
+    /// sample. A sink that returns an error is detached.
+    ///
+    /// # Arguments
+    /// * `direction` - Whether the frame was received or sent
+    /// * `stream` - Identifies the session, connection or feed
+    /// * `frame` - The frame, starting with its message header
+    #[inline]
+    pub fn observe(&self, direction: TapDirection, stream: u64, frame: &[u8]) {
+        if self.active.load(Ordering::Relaxed) {
+            self.copy(direction, stream, frame);
+        }
+    }
+
+    #[cold]
+    fn copy(&self, direction: TapDirection, stream: u64, frame: &[u8]) {
+        let mut guard = self.lock();
+        let Some(attached) = guard.as_mut() else {
+            return;
+        };
+        let template_id = MessageHeader::try_wrap(frame, 0).map(|h| h.template_id);
+        if !attached.filter.matches(direction, template_id) {
+            return;
+        }
+        attached.matched += 1;
+        if (attached.matched - 1) % attached.filter.sample_every != 0 {
+            return;
+        }
+        let record = TapRecord {
+            timestamp: Timestamp::now(),
+            direction,
+            stream,
+            template_id,
+            bytes: frame.to_vec(),
+        };
+        if attached.sink.record(record).is_ok() {
+            self.captured.fetch_add(1, Ordering::Relaxed);
+        } else {
+            *guard = None;
+            self.active.store(false, Ordering::Release);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Attached>> {
+        self.attached.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for MessageTap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageTap")
+            .field("attached", &self.is_attached())
+            .field("captured", &self.captured())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn frame(template_id: u16) -> Vec<u8> {
+        let mut buf = vec![0u8; MessageHeader::ENCODED_LENGTH + 2];
+        MessageHeader::new(2, template_id, 1, 1).encode(&mut buf[..], 0);
+        buf
+    }
+
+    #[test]
+    fn test_detached_tap_copies_nothing() {
+        let tap = MessageTap::new();
+        tap.observe(TapDirection::Inbound, 1, &frame(7));
+        assert!(!tap.is_attached());
+        assert_eq!(tap.captured(), 0);
+    }
+
+    #[test]
+    fn test_filter_and_sampling() {
+        let tap = MessageTap::new();
+        let (tx, rx) = mpsc::channel();
+        tap.attach(
+            TapFilter::new()
+                .templates([7])
+                .direction(TapDirection::Inbound)
+                .sample_every(2),
+            tx,
+        );
+
+        for _ in 0..5 {
+            tap.observe(TapDirection::Inbound, 3, &frame(7));
+            tap.observe(TapDirection::Inbound, 3, &frame(8));
+            tap.observe(TapDirection::Outbound, 3, &frame(7));
+        }
+        tap.observe(TapDirection::Inbound, 3, b"short");
+
+        let records: Vec<_> = rx.try_iter().collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(tap.captured(), 3);
+        assert!(records.iter().all(|r| r.template_id == Some(7)
+            && r.direction == TapDirection::Inbound
+            && r.stream == 3
+            && r.bytes == frame(7)));
+
+        assert!(tap.detach());
+        assert!(!tap.detach());
+        tap.observe(TapDirection::Inbound, 3, &frame(7));
+        assert_eq!(tap.captured(), 3);
+    }
+
+    #[test]
+    fn test_disconnected_sink_detaches() {
+        let tap = MessageTap::new();
+        let (tx, rx) = mpsc::sync_channel(1);
+        tap.attach(TapFilter::new(), tx);
+
+        tap.observe(TapDirection::Outbound, 1, &frame(1));
+        tap.observe(TapDirection::Outbound, 1, &frame(2));
+        assert!(
+            tap.is_attached(),
+            "a full channel drops instead of detaching"
+        );
+        assert_eq!(rx.try_iter().count(), 1);
+
+        drop(rx);
+        tap.observe(TapDirection::Outbound, 1, &frame(3));
+        assert!(!tap.is_attached());
+    }
+
+    #[test]
+    fn test_writer_sink_lines() {
+        let record = TapRecord {
+            timestamp: Timestamp::new(5),
+            direction: TapDirection::Outbound,
+            stream: 9,
+            template_id: None,
+            bytes: b"ab".to_vec(),
+        };
+        let mut writer = TapWriter::new(Vec::new());
+        writer.record(record.clone()).unwrap();
+        writer
+            .record(TapRecord {
+                template_id: Some(16),
+                direction: TapDirection::Inbound,
+                ..record
+            })
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "5 out stream=9 template=- len=2 6162\n5 in stream=9 template=16 len=2 6162\n"
+        );
+    }
+
+    #[test]
+    fn test_record_parse_round_trip() {
+        let record = TapRecord {
+            timestamp: Timestamp::new(42),
+            direction: TapDirection::Inbound,
+            stream: 3,
+            template_id: Some(7),
+            bytes: frame(7),
+        };
+        assert_eq!(TapRecord::parse(&record.to_string()), Some(record));
+
+        let empty = TapRecord::parse("1 out stream=0 template=- len=0").unwrap();
+        assert!(empty.bytes.is_empty());
+
+        assert_eq!(TapRecord::parse("1 out stream=0 template=- len=2 61"), None);
+        assert_eq!(
+            TapRecord::parse("1 sideways stream=0 template=- len=0"),
+            None
+        );
+        assert_eq!(TapRecord::parse("1 out stream=0 template=- len=1 zz"), None);
+    }
+}
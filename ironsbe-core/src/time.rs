@@ -0,0 +1,431 @@
+//! Conversions between [`Timestamp`] and calendar time.
+//!
+//! SBE carries time as an integer count of units since the Unix epoch,
+//! usually in a `UTCTimestamp` composite of a `uint64` `time` and a
+//! constant `unit`. This module converts those counts to and from
+//! [`Timestamp`] nanoseconds, formats and parses nanosecond RFC 3339 UTC
+//! strings, and, with `std`, converts to and from `SystemTime`. The
+//! `chrono` feature adds conversions to and from `chrono::DateTime`.
+//!
+//! Formatting and parsing need no allocation and work without `std`.
+
+use crate::types::Timestamp;
+use core::fmt;
+use core::str::FromStr;
+use thiserror::Error;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+const SECS_PER_DAY: i64 = 86_400;
+
+/// Error converting calendar time to a [`Timestamp`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum TimestampError {
+    /// The string is not an RFC 3339 date and time.
+    #[error("invalid RFC 3339 timestamp")]
+    InvalidFormat,
+    /// The time is before the Unix epoch or beyond the range of `u64`
+    /// nanoseconds.
+    #[error("timestamp out of range")]
+    OutOfRange,
+}
+
+/// Resolution of an SBE `UTCTimestamp`, as in the `TimeUnit` enum of the
+/// FIX SBE standard schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum TimeUnit {
+    /// Seconds.
+    Second = 0,
+    /// Milliseconds.
+    Millisecond = 3,
+    /// Microseconds.
+    Microsecond = 6,
+    /// Nanoseconds.
+    Nanosecond = 9,
+}
+
+impl TimeUnit {
+    /// Returns the unit for its encoded value, the power of ten below a
+    /// second.
+    #[must_use]
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Second),
+            3 => Some(Self::Millisecond),
+            6 => Some(Self::Microsecond),
+            9 => Some(Self::Nanosecond),
+            _ => None,
+        }
+    }
+
+    /// Returns the encoded value.
+    #[must_use]
+    pub const fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the number of nanoseconds in one unit.
+    #[must_use]
+    pub const fn nanos(self) -> u64 {
+        match self {
+            Self::Second => 1_000_000_000,
+            Self::Millisecond => 1_000_000,
+            Self::Microsecond => 1_000,
+            Self::Nanosecond => 1,
+        }
+    }
+}
+
+impl Timestamp {
+    /// Creates a timestamp from a count of `unit`s since the epoch, as
+    /// found in a `UTCTimestamp` composite.
+    ///
+    /// # Errors
+    /// Returns [`TimestampError::OutOfRange`] if the result does not fit
+    /// in `u64` nanoseconds.
+    pub const fn from_units(time: u64, unit: TimeUnit) -> Result<Self, TimestampError> {
+        match time.checked_mul(unit.nanos()) {
+            Some(nanos) if nanos != u64::MAX => Ok(Self(nanos)),
+            _ => Err(TimestampError::OutOfRange),
+        }
+    }
+
+    /// Returns the whole number of `unit`s since the epoch, truncating
+    /// finer digits.
+    #[must_use]
+    pub const fn to_units(&self, unit: TimeUnit) -> u64 {
+        self.0 / unit.nanos()
+    }
+
+    /// Parses an RFC 3339 date and time such as
+    /// `2024-03-01T14:30:00.123456789Z`.
+    ///
+    /// Up to nine fractional digits and numeric offsets (`+02:00`) are
+    /// accepted; `t` or a space may separate date and time.
+    ///
+    /// # Errors
+    /// Returns [`TimestampError::InvalidFormat`] for malformed input and
+    /// [`TimestampError::OutOfRange`] for times before the epoch or past
+    /// the `u64` range.
+    pub fn parse_rfc3339(s: &str) -> Result<Self, TimestampError> {
+        let bytes = s.as_bytes();
+        if bytes.len() < 20
+            || bytes[4] != b'-'
+            || bytes[7] != b'-'
+            || !matches!(bytes[10], b'T' | b't' | b' ')
+            || bytes[13] != b':'
+            || bytes[16] != b':'
+        {
+            return Err(TimestampError::InvalidFormat);
+        }
+        let year = digits(&bytes[0..4])?;
+        let month = digits(&bytes[5..7])?;
+        let day = digits(&bytes[8..10])?;
+        let hour = digits(&bytes[11..13])?;
+        let minute = digits(&bytes[14..16])?;
+        let second = digits(&bytes[17..19])?;
+        if !(1..=12).contains(&month)
+            || day == 0
+            || day > days_in_month(year, month)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return Err(TimestampError::InvalidFormat);
+        }
+
+        let mut rest = &bytes[19..];
+        let mut fraction = 0;
+        if let [b'.', tail @ ..] = rest {
+            let len = tail.iter().take_while(|b| b.is_ascii_digit()).count();
+            if len == 0 || len > 9 {
+                return Err(TimestampError::InvalidFormat);
+            }
+            fraction = digits(&tail[..len])? * 10u32.pow(9 - len as u32);
+            rest = &tail[len..];
+        }
+        let offset = match rest {
+            [b'Z' | b'z'] => 0,
+            [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+                let hours = digits(&[*h1, *h2])?;
+                let minutes = digits(&[*m1, *m2])?;
+                if hours > 23 || minutes > 59 {
+                    return Err(TimestampError::InvalidFormat);
+                }
+                let offset = i64::from(hours * 3_600 + minutes * 60);
+                if *sign == b'-' { -offset } else { offset }
+            }
+            _ => return Err(TimestampError::InvalidFormat),
+        };
+
+        let secs = days_from_civil(year, month, day) * SECS_PER_DAY
+            + i64::from(hour * 3_600 + minute * 60 + second)
+            - offset;
+        let secs = u64::try_from(secs).map_err(|_| TimestampError::OutOfRange)?;
+        match secs
+            .checked_mul(NANOS_PER_SEC)
+            .and_then(|nanos| nanos.checked_add(u64::from(fraction)))
+        {
+            Some(nanos) if nanos != u64::MAX => Ok(Self(nanos)),
+            _ => Err(TimestampError::OutOfRange),
+        }
+    }
+}
+
+/// Formats as RFC 3339 UTC with nanoseconds, e.g.
+/// `2024-03-01T14:30:00.123456789Z`; the null timestamp prints `NULL`.
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_null() {
+            return f.write_str("NULL");
+        }
+        let secs = self.0 / NANOS_PER_SEC;
+        let nanos = self.0 % NANOS_PER_SEC;
+        let (year, month, day) = civil_from_days((secs / SECS_PER_DAY as u64) as i64);
+        let time = secs % SECS_PER_DAY as u64;
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{nanos:09}Z",
+            time / 3_600,
+            time / 60 % 60,
+            time % 60
+        )
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = TimestampError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_rfc3339(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Timestamp> for std::time::SystemTime {
+    fn from(ts: Timestamp) -> Self {
+        std::time::UNIX_EPOCH + core::time::Duration::from_nanos(ts.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<std::time::SystemTime> for Timestamp {
+    type Error = TimestampError;
+
+    fn try_from(time: std::time::SystemTime) -> Result<Self, Self::Error> {
+        let since_epoch = time
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|_| TimestampError::OutOfRange)?;
+        match u64::try_from(since_epoch.as_nanos()) {
+            Ok(nanos) if nanos != u64::MAX => Ok(Self(nanos)),
+            _ => Err(TimestampError::OutOfRange),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<Timestamp> for chrono::DateTime<chrono::Utc> {
+    fn from(ts: Timestamp) -> Self {
+        // `u64` nanoseconds end in 2554, well inside chrono's range.
+        chrono::DateTime::from_timestamp(
+            (ts.0 / NANOS_PER_SEC) as i64,
+            (ts.0 % NANOS_PER_SEC) as u32,
+        )
+        .expect("u64 nanoseconds are within chrono's range")
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> TryFrom<chrono::DateTime<Tz>> for Timestamp {
+    type Error = TimestampError;
+
+    fn try_from(time: chrono::DateTime<Tz>) -> Result<Self, Self::Error> {
+        let secs = u64::try_from(time.timestamp()).map_err(|_| TimestampError::OutOfRange)?;
+        match secs
+            .checked_mul(NANOS_PER_SEC)
+            .and_then(|nanos| nanos.checked_add(u64::from(time.timestamp_subsec_nanos())))
+        {
+            Some(nanos) if nanos != u64::MAX => Ok(Self(nanos)),
+            _ => Err(TimestampError::OutOfRange),
+        }
+    }
+}
+
+/// Parses ASCII digits.
+fn digits(bytes: &[u8]) -> Result<u32, TimestampError> {
+    bytes.iter().try_fold(0u32, |value, &b| {
+        if b.is_ascii_digit() {
+            Ok(value * 10 + u32::from(b - b'0'))
+        } else {
+            Err(TimestampError::InvalidFormat)
+        }
+    })
+}
+
+const fn is_leap_year(year: u32) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+const fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days from 1970-01-01 to a proleptic Gregorian date.
+fn days_from_civil(year: u32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian date of a day count from 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_units() {
+        assert_eq!(TimeUnit::from_u8(6), Some(TimeUnit::Microsecond));
+        assert_eq!(TimeUnit::from_u8(2), None);
+        assert_eq!(TimeUnit::Millisecond.as_u8(), 3);
+
+        let ts = Timestamp::from_units(1_700_000_000_123, TimeUnit::Millisecond).unwrap();
+        assert_eq!(ts.as_nanos(), 1_700_000_000_123_000_000);
+        assert_eq!(ts.to_units(TimeUnit::Second), 1_700_000_000);
+        assert_eq!(ts.to_units(TimeUnit::Microsecond), 1_700_000_000_123_000);
+        assert_eq!(
+            Timestamp::from_units(u64::MAX / 10, TimeUnit::Second),
+            Err(TimestampError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_rfc3339_round_trip() {
+        let ts = Timestamp::new(1_709_303_400_123_456_789);
+        let text = ts.to_string();
+        assert_eq!(text, "2024-03-01T14:30:00.123456789Z");
+        assert_eq!(text.parse::<Timestamp>(), Ok(ts));
+
+        assert_eq!(
+            Timestamp::new(0).to_string(),
+            "1970-01-01T00:00:00.000000000Z"
+        );
+        assert_eq!(Timestamp::NULL.to_string(), "NULL");
+        // Last representable instant, and leap days on either side of 2000.
+        let last = Timestamp::new(u64::MAX - 1);
+        assert_eq!(last.to_string().parse::<Timestamp>(), Ok(last));
+        for text in [
+            "2000-02-29T23:59:59.999999999Z",
+            "2096-02-29T00:00:00.000000001Z",
+        ] {
+            assert_eq!(text.parse::<Timestamp>().unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_rfc3339_parse_variants() {
+        let ts = Timestamp::parse_rfc3339("2024-03-01T14:30:00Z").unwrap();
+        assert_eq!(ts.as_nanos(), 1_709_303_400 * NANOS_PER_SEC);
+        assert_eq!(
+            Timestamp::parse_rfc3339("2024-03-01 16:30:00.5+02:00"),
+            Ok(Timestamp::new(ts.as_nanos() + 500_000_000))
+        );
+        assert_eq!(
+            Timestamp::parse_rfc3339("2024-03-01t09:00:00.000001-05:30"),
+            Ok(Timestamp::new(ts.as_nanos() + 1_000))
+        );
+
+        for bad in [
+            "2024-03-01T14:30:00",
+            "2024-02-30T00:00:00Z",
+            "2023-02-29T00:00:00Z",
+            "2024-03-01T24:00:00Z",
+            "2024-03-01T14:30:00.Z",
+            "2024-03-01T14:30:00.1234567890Z",
+            "2024-03-01T14:30:00+0200",
+            "2024-3-01T14:30:00Z",
+        ] {
+            assert_eq!(
+                Timestamp::parse_rfc3339(bad),
+                Err(TimestampError::InvalidFormat),
+                "{bad}"
+            );
+        }
+        assert_eq!(
+            Timestamp::parse_rfc3339("1969-12-31T23:59:59Z"),
+            Err(TimestampError::OutOfRange)
+        );
+        assert_eq!(
+            Timestamp::parse_rfc3339("2600-01-01T00:00:00Z"),
+            Err(TimestampError::OutOfRange)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_system_time_conversions() {
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        let ts = Timestamp::new(1_700_000_000_000_000_007);
+        let time = SystemTime::from(ts);
+        assert_eq!(
+            time.duration_since(UNIX_EPOCH).unwrap(),
+            Duration::from_nanos(1_700_000_000_000_000_007)
+        );
+        assert_eq!(Timestamp::try_from(time), Ok(ts));
+        assert_eq!(
+            Timestamp::try_from(UNIX_EPOCH - Duration::from_secs(1)),
+            Err(TimestampError::OutOfRange)
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_conversions() {
+        use chrono::{DateTime, FixedOffset, Utc};
+
+        let ts = Timestamp::new(1_709_303_400_123_456_789);
+        let utc = DateTime::<Utc>::from(ts);
+        assert_eq!(
+            utc.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true),
+            ts.to_string()
+        );
+        assert_eq!(Timestamp::try_from(utc), Ok(ts));
+
+        let local: DateTime<FixedOffset> =
+            DateTime::parse_from_rfc3339("2024-03-01T16:30:00.123456789+02:00").unwrap();
+        assert_eq!(Timestamp::try_from(local), Ok(ts));
+        let before_epoch = DateTime::parse_from_rfc3339("1960-01-01T00:00:00Z").unwrap();
+        assert_eq!(
+            Timestamp::try_from(before_epoch),
+            Err(TimestampError::OutOfRange)
+        );
+    }
+}
@@ -186,6 +186,7 @@ impl Decimal {
     /// # Arguments
     /// * `value` - The floating point value
     /// * `exponent` - The desired exponent (negative for decimal places)
+    #[cfg(feature = "std")]
     #[must_use]
     pub fn from_f64(value: f64, exponent: i8) -> Self {
         let multiplier = 10f64.powi(-exponent as i32);
@@ -194,6 +195,7 @@ impl Decimal {
     }
 
     /// Converts the decimal to a floating point value.
+    #[cfg(feature = "std")]
     #[must_use]
     pub fn to_f64(&self) -> f64 {
         self.mantissa as f64 * 10f64.powi(self.exponent as i32)
@@ -215,6 +217,7 @@ impl Decimal {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::fmt::Display for Decimal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.is_null() {
@@ -225,6 +228,352 @@ impl std::fmt::Display for Decimal {
     }
 }
 
+/// Direction in which [`Decimal::round_to_tick`] rounds values between ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rounding {
+    /// Toward negative infinity; a passive bid rounds down.
+    Down,
+    /// Toward positive infinity; a passive offer rounds up.
+    Up,
+    /// To the nearest tick, with halves away from zero.
+    Nearest,
+}
+
+/// Returns `mantissa * 10^power` as an `i128`, or `None` on overflow.
+fn scale_up(mantissa: i64, power: u32) -> Option<i128> {
+    if mantissa == 0 {
+        return Some(0);
+    }
+    10i128.checked_pow(power)?.checked_mul(i128::from(mantissa))
+}
+
+/// Returns how many powers of ten separate two exponents.
+fn exponent_gap(a: i8, b: i8) -> u32 {
+    (i16::from(a) - i16::from(b)).unsigned_abs().into()
+}
+
+/// Returns both mantissas at the smaller of the two exponents.
+fn align(a: Decimal, b: Decimal) -> Option<(i128, i128, i8)> {
+    let exponent = a.exponent.min(b.exponent);
+    let a_scaled = scale_up(a.mantissa, exponent_gap(a.exponent, exponent))?;
+    let b_scaled = scale_up(b.mantissa, exponent_gap(b.exponent, exponent))?;
+    Some((a_scaled, b_scaled, exponent))
+}
+
+/// Narrows a mantissa back to `i64`, rejecting the null sentinel.
+fn narrow(mantissa: i128, exponent: i8) -> Option<Decimal> {
+    let mantissa = i64::try_from(mantissa).ok()?;
+    (mantissa != i64::MIN).then_some(Decimal::new(mantissa, exponent))
+}
+
+impl Decimal {
+    /// Returns the same value with `exponent`, or `None` if that would
+    /// drop non-zero digits or overflow the mantissa.
+    #[must_use]
+    pub fn rescale(&self, exponent: i8) -> Option<Self> {
+        if self.is_null() {
+            return None;
+        }
+        let power = exponent_gap(self.exponent, exponent);
+        if exponent <= self.exponent {
+            return narrow(scale_up(self.mantissa, power)?, exponent);
+        }
+        let divisor = 10i128.checked_pow(power);
+        match divisor {
+            Some(divisor) if i128::from(self.mantissa) % divisor == 0 => {
+                narrow(i128::from(self.mantissa) / divisor, exponent)
+            }
+            // A divisor beyond `i128` only divides zero.
+            None if self.mantissa == 0 => Some(Self::new(0, exponent)),
+            _ => None,
+        }
+    }
+
+    /// Adds two decimals exactly, at the smaller exponent.
+    ///
+    /// Returns `None` if either is null or the sum overflows.
+    #[must_use]
+    pub fn checked_add(&self, other: Self) -> Option<Self> {
+        if self.is_null() || other.is_null() {
+            return None;
+        }
+        let (a, b, exponent) = align(*self, other)?;
+        narrow(a.checked_add(b)?, exponent)
+    }
+
+    /// Subtracts `other` exactly, at the smaller exponent.
+    ///
+    /// Returns `None` if either is null or the difference overflows.
+    #[must_use]
+    pub fn checked_sub(&self, other: Self) -> Option<Self> {
+        if self.is_null() || other.is_null() {
+            return None;
+        }
+        let (a, b, exponent) = align(*self, other)?;
+        narrow(a.checked_sub(b)?, exponent)
+    }
+
+    /// Multiplies two decimals exactly; the exponents add.
+    ///
+    /// Returns `None` if either is null or the product overflows.
+    #[must_use]
+    pub fn checked_mul(&self, other: Self) -> Option<Self> {
+        if self.is_null() || other.is_null() {
+            return None;
+        }
+        let exponent = self.exponent.checked_add(other.exponent)?;
+        narrow(
+            i128::from(self.mantissa) * i128::from(other.mantissa),
+            exponent,
+        )
+    }
+
+    /// Rounds to a multiple of `tick`, at the smaller of the two exponents.
+    ///
+    /// Returns `None` if either is null, `tick` is not positive or the
+    /// result overflows.
+    #[must_use]
+    pub fn round_to_tick(&self, tick: Self, rounding: Rounding) -> Option<Self> {
+        if self.is_null() || tick.is_null() || tick.mantissa <= 0 {
+            return None;
+        }
+        let (value, tick, exponent) = align(*self, tick)?;
+        let ticks = match rounding {
+            Rounding::Down => value.div_euclid(tick),
+            Rounding::Up => -(-value).div_euclid(tick),
+            Rounding::Nearest => {
+                let ticks = (2 * value.abs() + tick) / (2 * tick);
+                if value < 0 { -ticks } else { ticks }
+            }
+        };
+        narrow(ticks.checked_mul(tick)?, exponent)
+    }
+
+    /// Compares the values of two decimals, whatever their exponents.
+    ///
+    /// Null sorts below every value.
+    #[must_use]
+    pub fn cmp_value(&self, other: &Self) -> core::cmp::Ordering {
+        use core::cmp::Ordering;
+        match (self.is_null(), other.is_null()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            (false, false) => {}
+        }
+        if let Some((a, b, _)) = align(*self, *other) {
+            return a.cmp(&b);
+        }
+        // Scaling overflowed, so the side with the larger exponent has the
+        // larger magnitude and its sign decides.
+        if self.exponent > other.exponent {
+            self.mantissa.cmp(&0)
+        } else {
+            0.cmp(&other.mantissa)
+        }
+    }
+
+    /// Returns the value with trailing zeros moved into the exponent, so
+    /// equal values have equal parts.
+    fn normalized(&self) -> (i64, i16) {
+        if self.mantissa == 0 {
+            return (0, 0);
+        }
+        let (mut mantissa, mut exponent) = (self.mantissa, i16::from(self.exponent));
+        while mantissa % 10 == 0 {
+            mantissa /= 10;
+            exponent += 1;
+        }
+        (mantissa, exponent)
+    }
+
+    /// Writes the exact value in positional notation.
+    fn fmt_exact(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.mantissa < 0 {
+            f.write_str("-")?;
+        }
+        let digits = u128::from(self.mantissa.unsigned_abs());
+        if self.exponent >= 0 {
+            write!(f, "{digits}")?;
+            for _ in 0..self.exponent {
+                f.write_str("0")?;
+            }
+            return Ok(());
+        }
+        let places = usize::from(self.exponent.unsigned_abs());
+        let (whole, fraction) = match 10u128.checked_pow(places as u32) {
+            Some(divisor) => (digits / divisor, digits % divisor),
+            None => (0, digits),
+        };
+        write!(f, "{whole}.{fraction:0places$}")
+    }
+}
+
+/// Defines a fixed-point newtype over [`Decimal`] that compares by value.
+macro_rules! fixed_point {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy)]
+        pub struct $name(Decimal);
+
+        impl $name {
+            /// Creates a value of `mantissa * 10^exponent`.
+            #[must_use]
+            pub const fn new(mantissa: i64, exponent: i8) -> Self {
+                Self(Decimal::new(mantissa, exponent))
+            }
+
+            /// Wraps a decimal, or returns `None` if it is null.
+            #[must_use]
+            pub const fn from_decimal(decimal: Decimal) -> Option<Self> {
+                if decimal.is_null() {
+                    None
+                } else {
+                    Some(Self(decimal))
+                }
+            }
+
+            /// Returns the mantissa.
+            #[must_use]
+            pub const fn mantissa(&self) -> i64 {
+                self.0.mantissa
+            }
+
+            /// Returns the exponent.
+            #[must_use]
+            pub const fn exponent(&self) -> i8 {
+                self.0.exponent
+            }
+
+            /// Returns the underlying decimal.
+            #[must_use]
+            pub const fn to_decimal(&self) -> Decimal {
+                self.0
+            }
+
+            /// Returns true if the value is zero.
+            #[must_use]
+            pub const fn is_zero(&self) -> bool {
+                self.0.mantissa == 0
+            }
+
+            /// Returns the same value with `exponent`; see
+            /// [`Decimal::rescale`].
+            #[must_use]
+            pub fn rescale(&self, exponent: i8) -> Option<Self> {
+                self.0.rescale(exponent).map(Self)
+            }
+
+            /// Adds exactly, or returns `None` on overflow.
+            #[must_use]
+            pub fn checked_add(self, other: Self) -> Option<Self> {
+                self.0.checked_add(other.0).map(Self)
+            }
+
+            /// Subtracts exactly, or returns `None` on overflow.
+            #[must_use]
+            pub fn checked_sub(self, other: Self) -> Option<Self> {
+                self.0.checked_sub(other.0).map(Self)
+            }
+
+            /// Multiplies by an integer factor, or returns `None` on
+            /// overflow.
+            #[must_use]
+            pub fn checked_mul(self, factor: i64) -> Option<Self> {
+                self.0.checked_mul(Decimal::new(factor, 0)).map(Self)
+            }
+
+            /// Rounds to a multiple of `tick`; see [`Decimal::round_to_tick`].
+            #[must_use]
+            pub fn round_to_tick(self, tick: Self, rounding: Rounding) -> Option<Self> {
+                self.0.round_to_tick(tick.0, rounding).map(Self)
+            }
+
+            /// Returns true if the value is a whole multiple of `tick`.
+            #[must_use]
+            pub fn is_on_tick(self, tick: Self) -> bool {
+                self.round_to_tick(tick, Rounding::Down) == Some(self)
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.0.cmp_value(&other.0).is_eq()
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                self.0.cmp_value(&other.0)
+            }
+        }
+
+        impl core::hash::Hash for $name {
+            fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+                self.0.normalized().hash(state);
+            }
+        }
+
+        impl From<$name> for Decimal {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl core::fmt::Display for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                self.0.fmt_exact(f)
+            }
+        }
+    };
+}
+
+fixed_point! {
+    /// A price as an exact fixed-point decimal.
+    ///
+    /// Prices compare, hash and print by value, so `1.50` and `1.5` are
+    /// equal. Arithmetic is exact and checked; rounding happens only in
+    /// [`round_to_tick`](Self::round_to_tick), in the direction asked for.
+    ///
+    /// # Example
+    /// ```
+    /// use ironsbe_core::types::{Price, Qty, Rounding};
+    ///
+    /// let tick = Price::new(25, -2); // 0.25
+    /// let bid = Price::new(100_137, -3).round_to_tick(tick, Rounding::Down).unwrap();
+    /// assert_eq!(bid, Price::new(100, 0));
+    /// assert_eq!(bid.to_string(), "100.000");
+    ///
+    /// let notional = bid.checked_notional(Qty::new(3, 0)).unwrap();
+    /// assert_eq!(notional.mantissa, 300_000);
+    /// ```
+    Price
+}
+
+fixed_point! {
+    /// A quantity as an exact fixed-point decimal.
+    ///
+    /// Behaves like [`Price`]; round to the lot size with
+    /// [`round_to_tick`](Self::round_to_tick).
+    Qty
+}
+
+impl Price {
+    /// Returns `price * qty`, or `None` on overflow.
+    #[must_use]
+    pub fn checked_notional(self, qty: Qty) -> Option<Decimal> {
+        self.0.checked_mul(qty.0)
+    }
+}
+
 /// Timestamp type representing nanoseconds since Unix epoch.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct Timestamp(pub u64);
@@ -246,6 +595,7 @@ impl Timestamp {
     }
 
     /// Creates a timestamp from the current time.
+    #[cfg(feature = "std")]
     #[must_use]
     pub fn now() -> Self {
         use std::time::{SystemTime, UNIX_EPOCH};
@@ -290,7 +640,7 @@ impl Timestamp {
     /// # Arguments
     /// * `duration` - Duration to convert
     #[must_use]
-    pub fn from_duration(duration: std::time::Duration) -> Self {
+    pub fn from_duration(duration: core::time::Duration) -> Self {
         Self(duration.as_nanos() as u64)
     }
 }
@@ -321,10 +671,13 @@ impl ByteOrder {
     /// Parses byte order from a string.
     #[must_use]
     pub fn parse(s: &str) -> Option<Self> {
-        match s.to_lowercase().as_str() {
-            "littleendian" | "little-endian" | "le" => Some(Self::LittleEndian),
-            "bigendian" | "big-endian" | "be" => Some(Self::BigEndian),
-            _ => None,
+        let is = |names: &[&str]| names.iter().any(|n| s.eq_ignore_ascii_case(n));
+        if is(&["littleendian", "little-endian", "le"]) {
+            Some(Self::LittleEndian)
+        } else if is(&["bigendian", "big-endian", "be"]) {
+            Some(Self::BigEndian)
+        } else {
+            None
         }
     }
 
@@ -358,12 +711,13 @@ impl Presence {
     /// Parses presence from a string.
     #[must_use]
     pub fn parse(s: &str) -> Option<Self> {
-        match s.to_lowercase().as_str() {
-            "required" => Some(Self::Required),
-            "optional" => Some(Self::Optional),
-            "constant" => Some(Self::Constant),
-            _ => None,
-        }
+        [
+            ("required", Self::Required),
+            ("optional", Self::Optional),
+            ("constant", Self::Constant),
+        ]
+        .into_iter()
+        .find_map(|(name, presence)| s.eq_ignore_ascii_case(name).then_some(presence))
     }
 
     /// Returns true if this is a required field.
@@ -553,6 +907,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_decimal_conversion() {
         let dec = Decimal::new(15050, -2);
         assert!((dec.to_f64() - 150.50).abs() < 0.001);
@@ -572,6 +927,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_decimal_display() {
         let dec = Decimal::new(15050, -2);
         let s = format!("{}", dec);
@@ -593,6 +949,168 @@ mod tests {
         assert_eq!(Decimal::ENCODED_LENGTH, 9);
     }
 
+    #[test]
+    fn test_decimal_checked_arithmetic() {
+        let a = Decimal::new(150, -2);
+        let b = Decimal::new(25, -1);
+        assert_eq!(a.checked_add(b), Some(Decimal::new(400, -2)));
+        assert_eq!(a.checked_sub(b), Some(Decimal::new(-100, -2)));
+        assert_eq!(a.checked_mul(b), Some(Decimal::new(3750, -3)));
+        assert_eq!(
+            Decimal::new(i64::MAX, 0).checked_add(Decimal::new(1, 0)),
+            None
+        );
+        assert_eq!(Decimal::new(1, 127).checked_mul(Decimal::new(1, 1)), None);
+        assert_eq!(Decimal::null().checked_add(a), None);
+        // Sums whose alignment would overflow are rejected, not truncated.
+        assert_eq!(
+            Decimal::new(1, 100).checked_add(Decimal::new(1, -100)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_decimal_rescale() {
+        assert_eq!(
+            Decimal::new(15, -1).rescale(-3),
+            Some(Decimal::new(1500, -3))
+        );
+        assert_eq!(
+            Decimal::new(1500, -3).rescale(-1),
+            Some(Decimal::new(15, -1))
+        );
+        assert_eq!(Decimal::new(1501, -3).rescale(-1), None);
+        assert_eq!(Decimal::new(1, 0).rescale(-19), None);
+        assert_eq!(
+            Decimal::new(0, -128).rescale(127),
+            Some(Decimal::new(0, 127))
+        );
+    }
+
+    #[test]
+    fn test_decimal_round_to_tick() {
+        let tick = Decimal::new(5, -2);
+        let value = Decimal::new(1_012, -3); // 1.012
+        assert_eq!(
+            value.round_to_tick(tick, Rounding::Down),
+            Some(Decimal::new(1_000, -3))
+        );
+        assert_eq!(
+            value.round_to_tick(tick, Rounding::Up),
+            Some(Decimal::new(1_050, -3))
+        );
+        assert_eq!(
+            value.round_to_tick(tick, Rounding::Nearest),
+            Some(Decimal::new(1_000, -3))
+        );
+
+        let negative = Decimal::new(-1_025, -3); // exactly half a tick
+        assert_eq!(
+            negative.round_to_tick(tick, Rounding::Down),
+            Some(Decimal::new(-1_050, -3))
+        );
+        assert_eq!(
+            negative.round_to_tick(tick, Rounding::Up),
+            Some(Decimal::new(-1_000, -3))
+        );
+        assert_eq!(
+            negative.round_to_tick(tick, Rounding::Nearest),
+            Some(Decimal::new(-1_050, -3))
+        );
+        assert_eq!(
+            value.round_to_tick(Decimal::new(0, 0), Rounding::Down),
+            None
+        );
+        assert_eq!(
+            value.round_to_tick(Decimal::new(-5, -2), Rounding::Down),
+            None
+        );
+    }
+
+    #[test]
+    fn test_decimal_cmp_value() {
+        use core::cmp::Ordering;
+        assert_eq!(
+            Decimal::new(15, -1).cmp_value(&Decimal::new(150, -2)),
+            Ordering::Equal
+        );
+        assert_eq!(
+            Decimal::new(2, 0).cmp_value(&Decimal::new(199, -2)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            Decimal::new(1, 100).cmp_value(&Decimal::new(i64::MAX, 0)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            Decimal::new(-1, 100).cmp_value(&Decimal::new(i64::MIN + 1, 0)),
+            Ordering::Less
+        );
+        assert_eq!(
+            Decimal::new(0, 100).cmp_value(&Decimal::new(-1, -100)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            Decimal::null().cmp_value(&Decimal::new(i64::MIN + 1, 0)),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn test_price_and_qty() {
+        use std::collections::HashSet;
+
+        let price = Price::new(10_050, -2);
+        assert_eq!(price, Price::new(1_005, -1));
+        assert!(price < Price::new(101, 0));
+        assert_eq!(
+            [Price::new(1_005, -1), Price::new(10_050, -2)]
+                .into_iter()
+                .collect::<HashSet<_>>()
+                .len(),
+            1
+        );
+        assert_eq!(price.mantissa(), 10_050);
+        assert_eq!(price.exponent(), -2);
+        assert_eq!(Decimal::from(price), Decimal::new(10_050, -2));
+        assert_eq!(Price::from_decimal(Decimal::null()), None);
+
+        let tick = Price::new(1, -1);
+        assert!(price.is_on_tick(tick));
+        assert!(!Price::new(10_055, -2).is_on_tick(tick));
+        assert_eq!(price.checked_mul(3), Some(Price::new(30_150, -2)));
+        assert_eq!(
+            price.checked_sub(Price::new(1, 2)),
+            Some(Price::new(50, -2))
+        );
+
+        let qty = Qty::new(2_500, -3);
+        assert_eq!(
+            qty.round_to_tick(Qty::new(1, 0), Rounding::Down),
+            Some(Qty::new(2, 0))
+        );
+        assert_eq!(
+            price.checked_notional(qty),
+            Some(Decimal::new(25_125_000, -5))
+        );
+        assert!(Qty::new(0, 3).is_zero());
+    }
+
+    #[test]
+    fn test_price_display_is_exact() {
+        assert_eq!(Price::new(10_050, -2).to_string(), "100.50");
+        assert_eq!(Price::new(-5, -3).to_string(), "-0.005");
+        assert_eq!(Price::new(12, 2).to_string(), "1200");
+        assert_eq!(
+            Qty::new(7, -40).to_string(),
+            format!("0.{}7", "0".repeat(39))
+        );
+        assert_eq!(
+            Price::new(i64::MIN + 1, -1).to_string(),
+            "-922337203685477580.7"
+        );
+    }
+
     #[test]
     fn test_timestamp_new() {
         let ts = Timestamp::new(1_000_000_000);
@@ -612,6 +1130,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_timestamp_now() {
         let ts = Timestamp::now();
         assert!(!ts.is_null());
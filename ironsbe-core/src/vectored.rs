@@ -0,0 +1,269 @@
+//! Scatter-gather message assembly.
+//!
+//! Encoding a message with large var data fields normally means copying
+//! every payload into one contiguous buffer before the socket copies it
+//! again. An [`IoSliceEncoder`] instead keeps the message as a list of
+//! parts: the encoded header and fixed block, small bytes copied into an
+//! internal scratch area (such as var data length headers), and borrowed
+//! payload slices. The parts go to the kernel in one `write_vectored`
+//! call.
+
+use crate::header::VarDataHeader;
+use std::io::{self, IoSlice, Write};
+use std::ops::Range;
+
+/// One part of a scattered message.
+#[derive(Debug, Clone)]
+enum Part<'a> {
+    /// Bytes borrowed from the caller.
+    Borrowed(&'a [u8]),
+    /// Bytes in the encoder's scratch area.
+    Scratch(Range<usize>),
+}
+
+/// A message assembled from borrowed slices without copying them.
+///
+/// # Example
+/// ```
+/// use ironsbe_core::vectored::IoSliceEncoder;
+///
+/// let head = [0u8; 16]; // header and fixed block, encoded in place
+/// let text = b"a long free-text field";
+///
+/// let mut message = IoSliceEncoder::new();
+/// message.append(&head).append_var_data(text);
+///
+/// let mut wire = Vec::new();
+/// message.write_to(&mut wire).unwrap();
+/// assert_eq!(wire.len(), message.len());
+/// assert_eq!(&wire[18..], text);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IoSliceEncoder<'a> {
+    parts: Vec<Part<'a>>,
+    scratch: Vec<u8>,
+    len: usize,
+}
+
+impl<'a> IoSliceEncoder<'a> {
+    /// Creates an empty message.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `bytes` without copying them.
+    pub fn append(&mut self, bytes: &'a [u8]) -> &mut Self {
+        if !bytes.is_empty() {
+            self.parts.push(Part::Borrowed(bytes));
+            self.len += bytes.len();
+        }
+        self
+    }
+
+    /// Appends a copy of `bytes`, for small parts such as length fields
+    /// that do not outlive the call.
+    pub fn append_copy(&mut self, bytes: &[u8]) -> &mut Self {
+        if bytes.is_empty() {
+            return self;
+        }
+        let start = self.scratch.len();
+        self.scratch.extend_from_slice(bytes);
+        let end = self.scratch.len();
+        // Consecutive copies share one part.
+        match self.parts.last_mut() {
+            Some(Part::Scratch(range)) if range.end == start => range.end = end,
+            _ => self.parts.push(Part::Scratch(start..end)),
+        }
+        self.len += bytes.len();
+        self
+    }
+
+    /// Appends a var data field with the standard `uint16` length header,
+    /// copying the header and borrowing `value`.
+    ///
+    /// # Panics
+    /// Panics if `value` is longer than `u16::MAX` bytes.
+    pub fn append_var_data(&mut self, value: &'a [u8]) -> &mut Self {
+        let len = u16::try_from(value.len()).expect("var data longer than u16::MAX");
+        let mut header = [0u8; VarDataHeader::ENCODED_LENGTH];
+        VarDataHeader::new(len).encode(&mut header[..], 0);
+        self.append_copy(&header).append(value)
+    }
+
+    /// Returns the total length of the message.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the message is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of slices the message is made of.
+    #[must_use]
+    pub fn slice_count(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// Returns the message as slices for `write_vectored`.
+    #[must_use]
+    pub fn io_slices(&self) -> Vec<IoSlice<'_>> {
+        self.parts
+            .iter()
+            .map(|part| IoSlice::new(self.part_bytes(part)))
+            .collect()
+    }
+
+    fn part_bytes<'s>(&'s self, part: &'s Part<'a>) -> &'s [u8] {
+        match part {
+            Part::Borrowed(bytes) => bytes,
+            Part::Scratch(range) => &self.scratch[range.clone()],
+        }
+    }
+
+    /// Writes the whole message to `writer` with vectored writes.
+    ///
+    /// # Errors
+    /// Returns the first error from `writer`.
+    pub fn write_to<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        write_all_vectored(writer, &mut self.io_slices())
+    }
+
+    /// Copies the message into one contiguous buffer, for sinks that
+    /// cannot take slices.
+    #[must_use]
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.len);
+        for part in &self.parts {
+            bytes.extend_from_slice(self.part_bytes(part));
+        }
+        bytes
+    }
+
+    /// Empties the message, keeping the scratch allocation.
+    pub fn clear(&mut self) {
+        self.parts.clear();
+        self.scratch.clear();
+        self.len = 0;
+    }
+}
+
+/// Writes every byte of `slices` to `writer`, retrying short vectored
+/// writes.
+///
+/// `slices` is consumed as it is written.
+///
+/// # Errors
+/// Returns the first error from `writer`, or [`io::ErrorKind::WriteZero`]
+/// if it stops accepting bytes.
+pub fn write_all_vectored<W: Write + ?Sized>(
+    writer: &mut W,
+    mut slices: &mut [IoSlice<'_>],
+) -> io::Result<()> {
+    IoSlice::advance_slices(&mut slices, 0);
+    while !slices.is_empty() {
+        match writer.write_vectored(slices) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole message",
+                ));
+            }
+            Ok(written) => IoSlice::advance_slices(&mut slices, written),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::MessageHeader;
+
+    /// Accepts at most `limit` bytes per call, from the first slice only.
+    struct Trickle {
+        written: Vec<u8>,
+        limit: usize,
+        calls: usize,
+    }
+
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.calls += 1;
+            let n = buf.len().min(self.limit);
+            self.written.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_parts_match_contiguous_encoding() {
+        let mut head = [0u8; MessageHeader::ENCODED_LENGTH + 4];
+        MessageHeader::new(4, 9, 1, 1).encode(&mut head[..], 0);
+        head[8..].copy_from_slice(&7u32.to_le_bytes());
+        let first = vec![b'x'; 300];
+
+        let mut message = IoSliceEncoder::new();
+        message
+            .append(&head)
+            .append_var_data(&first)
+            .append_var_data(b"")
+            .append_copy(&[1, 2])
+            .append(b"");
+        // The empty var data's header merges into the copy that follows.
+        assert_eq!(message.slice_count(), 4);
+        assert_eq!(message.len(), 12 + 2 + 300 + 2 + 2);
+
+        let mut expected = head.to_vec();
+        expected.extend_from_slice(&300u16.to_le_bytes());
+        expected.extend_from_slice(&first);
+        expected.extend_from_slice(&[0, 0, 1, 2]);
+        assert_eq!(message.to_vec(), expected);
+
+        let mut vectored = Vec::new();
+        message.write_to(&mut vectored).unwrap();
+        assert_eq!(vectored, expected);
+    }
+
+    #[test]
+    fn test_short_writes_are_resumed() {
+        let mut message = IoSliceEncoder::new();
+        message.append(b"abcde").append_copy(b"fg").append(b"hij");
+
+        let mut sink = Trickle {
+            written: Vec::new(),
+            limit: 3,
+            calls: 0,
+        };
+        message.write_to(&mut sink).unwrap();
+        assert_eq!(sink.written, b"abcdefghij");
+        assert_eq!(sink.calls, 4);
+
+        message.clear();
+        assert!(message.is_empty());
+        assert_eq!(message.slice_count(), 0);
+    }
+
+    #[test]
+    fn test_write_zero_is_an_error() {
+        let mut sink = Trickle {
+            written: Vec::new(),
+            limit: 0,
+            calls: 0,
+        };
+        let mut message = IoSliceEncoder::new();
+        message.append(b"abc");
+        let err = message.write_to(&mut sink).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+    }
+}
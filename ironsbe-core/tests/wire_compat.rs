@@ -0,0 +1,100 @@
+//! Binary compatibility snapshots of the core wire types.
+//!
+//! Every layout here is shared with other processes, other SBE
+//! implementations or files on disk, so it must not change between
+//! releases. The expected bytes are written out by hand: a refactor that
+//! moves a field fails here even when encoding and decoding still agree
+//! with each other.
+
+use ironsbe_core::header::{
+    GroupHeader, MessageHeader, VarDataHeader, VarDataHeader8, VarDataHeader32,
+};
+use std::mem::{align_of, size_of};
+
+const _: () = {
+    assert!(size_of::<MessageHeader>() == MessageHeader::ENCODED_LENGTH);
+    assert!(size_of::<MessageHeader>() == 8);
+    assert!(align_of::<MessageHeader>() == 1);
+    assert!(size_of::<GroupHeader>() == GroupHeader::ENCODED_LENGTH);
+    assert!(size_of::<GroupHeader>() == 4);
+    assert!(align_of::<GroupHeader>() == 1);
+    assert!(size_of::<VarDataHeader>() == VarDataHeader::ENCODED_LENGTH);
+    assert!(size_of::<VarDataHeader>() == 2);
+    assert!(align_of::<VarDataHeader>() == 1);
+    assert!(size_of::<VarDataHeader8>() == VarDataHeader8::ENCODED_LENGTH);
+    assert!(size_of::<VarDataHeader8>() == 1);
+    assert!(size_of::<VarDataHeader32>() == VarDataHeader32::ENCODED_LENGTH);
+    assert!(size_of::<VarDataHeader32>() == 4);
+    assert!(align_of::<VarDataHeader32>() == 1);
+};
+
+/// Formats bytes as lowercase hex.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Encodes with `encode` into a buffer of `len` bytes and returns its hex.
+fn encoded(len: usize, encode: impl FnOnce(&mut [u8])) -> String {
+    let mut buf = vec![0u8; len];
+    encode(&mut buf);
+    hex(&buf)
+}
+
+#[test]
+fn test_message_header_layout() {
+    let header = MessageHeader::new(0x0102, 0x0304, 0x0506, 0x0708);
+    let bytes = encoded(8, |buf| header.encode(buf, 0));
+    // blockLength, templateId, schemaId, version; each u16 little-endian.
+    assert_eq!(bytes, "0201040306050807");
+    assert_eq!(
+        MessageHeader::wrap(&[2u8, 1, 4, 3, 6, 5, 8, 7][..], 0),
+        header
+    );
+}
+
+#[test]
+fn test_group_header_layout() {
+    let header = GroupHeader::new(0x0a0b, 0x0c0d);
+    // blockLength, numInGroup; each u16 little-endian.
+    assert_eq!(encoded(4, |buf| header.encode(buf, 0)), "0b0a0d0c");
+}
+
+#[test]
+fn test_var_data_header_layouts() {
+    assert_eq!(
+        encoded(2, |buf| VarDataHeader::new(0x0102).encode(buf, 0)),
+        "0201"
+    );
+    assert_eq!(
+        encoded(1, |buf| VarDataHeader8::new(0x7f).encode(buf, 0)),
+        "7f"
+    );
+    assert_eq!(
+        encoded(4, |buf| VarDataHeader32::new(0x0102_0304).encode(buf, 0)),
+        "04030201"
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_tap_record_line_format() {
+    use ironsbe_core::tap::{TapDirection, TapRecord};
+    use ironsbe_core::types::Timestamp;
+
+    let record = TapRecord {
+        timestamp: Timestamp::new(1_700_000_000_000_000_123),
+        direction: TapDirection::Outbound,
+        stream: 7,
+        template_id: Some(3),
+        bytes: vec![0x08, 0x00, 0x03, 0x00, 0xab],
+    };
+    let line = "1700000000000000123 out stream=7 template=3 len=5 08000300ab";
+    assert_eq!(record.to_string(), line);
+    assert_eq!(TapRecord::parse(line), Some(record));
+
+    let headerless = "42 in stream=0 template=- len=0 ";
+    assert_eq!(
+        TapRecord::parse(headerless).map(|record| record.to_string()),
+        Some(headerless.to_string())
+    );
+}
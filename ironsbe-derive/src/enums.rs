@@ -0,0 +1,262 @@
+//! `#[derive(SbeEnum)]` and `#[derive(SbeSet)]` expansion.
+//!
+//! Both derives emit their impls through the same helpers `ironsbe-codegen`
+//! uses for schema enums and sets, so a hand-written type converts and
+//! exposes accessors like a generated one. Derived enums only get the
+//! checked `TryFrom` conversion from raw values, not the lenient `From`
+//! generated decoders use.
+
+use ironsbe_codegen::rust::enums::{enum_impls, set_impls};
+use ironsbe_schema::ir::SetVariant;
+use ironsbe_schema::types::PrimitiveType;
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Expr, Fields, Lit, LitInt, UnOp};
+
+use crate::message::primitive_from_rust;
+
+/// Expands `#[derive(SbeEnum)]`.
+pub(crate) fn expand_enum(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    reject_generics(input, "SbeEnum")?;
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(
+            name.span(),
+            "SbeEnum can only be derived for enums",
+        ));
+    };
+    if data.variants.is_empty() {
+        return Err(syn::Error::new(
+            name.span(),
+            "SbeEnum requires at least one variant",
+        ));
+    }
+    let encoding = enum_repr(input)?;
+
+    // Resolve discriminants the way rustc does: explicit or previous + 1
+    let mut variants: Vec<(Ident, i64)> = Vec::with_capacity(data.variants.len());
+    let mut next = 0i64;
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new(
+                variant.span(),
+                "SbeEnum variants cannot have fields",
+            ));
+        }
+        let value = match &variant.discriminant {
+            Some((_, expr)) => discriminant_value(expr)?,
+            None => next,
+        };
+        next = value.wrapping_add(1);
+        variants.push((variant.ident.clone(), value));
+    }
+
+    let impls = enum_impls(name, encoding, &variants, true);
+    Ok(quote! {
+        const _: () = {
+            use ::ironsbe_core::decoder::UnknownEnumValue;
+            #impls
+        };
+    })
+}
+
+/// Expands `#[derive(SbeSet)]`.
+pub(crate) fn expand_set(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    reject_generics(input, "SbeSet")?;
+    let field = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0],
+            _ => {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "SbeSet requires a tuple struct with a single field, e.g. `struct Flags(u8);`",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                name.span(),
+                "SbeSet can only be derived for structs",
+            ));
+        }
+    };
+
+    let encoding = match &field.ty {
+        syn::Type::Path(path) => path.path.get_ident().map(ToString::to_string),
+        _ => None,
+    }
+    .filter(|ty| matches!(ty.as_str(), "u8" | "u16" | "u32" | "u64"))
+    .and_then(|ty| primitive_from_rust(&ty))
+    .ok_or_else(|| {
+        syn::Error::new(
+            field.ty.span(),
+            "SbeSet field must be `u8`, `u16`, `u32` or `u64`",
+        )
+    })?;
+
+    let choices = parse_choices(input, encoding)?;
+    Ok(set_impls(name, encoding, &choices))
+}
+
+/// Rejects generic types, which codegen never produces.
+fn reject_generics(input: &DeriveInput, derive: &str) -> syn::Result<()> {
+    if input.generics.params.is_empty() {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            input.generics.span(),
+            format!("{derive} cannot be derived for generic types"),
+        ))
+    }
+}
+
+/// Returns the encoding given by the enum's `#[repr(...)]`.
+fn enum_repr(input: &DeriveInput) -> syn::Result<PrimitiveType> {
+    let mut encoding = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if let Some(ident) = meta.path.get_ident() {
+                encoding = encoding.or_else(|| primitive_from_rust(&ident.to_string()));
+            }
+            Ok(())
+        })?;
+    }
+    encoding.ok_or_else(|| {
+        syn::Error::new(
+            input.ident.span(),
+            "SbeEnum requires an integer `#[repr(...)]`, e.g. `#[repr(u8)]`",
+        )
+    })
+}
+
+/// Evaluates an explicit discriminant: an integer or byte literal,
+/// optionally negated.
+fn discriminant_value(expr: &Expr) -> syn::Result<i64> {
+    match expr {
+        Expr::Lit(syn::ExprLit {
+            lit: Lit::Int(int), ..
+        }) => int.base10_parse(),
+        Expr::Lit(syn::ExprLit {
+            lit: Lit::Byte(byte),
+            ..
+        }) => Ok(i64::from(byte.value())),
+        Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => {
+            discriminant_value(&unary.expr).map(|value| -value)
+        }
+        Expr::Group(group) => discriminant_value(&group.expr),
+        Expr::Paren(paren) => discriminant_value(&paren.expr),
+        _ => Err(syn::Error::new(
+            expr.span(),
+            "SbeEnum discriminants must be integer or byte literals",
+        )),
+    }
+}
+
+/// Parses `#[sbe(choices(Name = bit, ...))]`.
+fn parse_choices(input: &DeriveInput, encoding: PrimitiveType) -> syn::Result<Vec<SetVariant>> {
+    let bits = encoding.size() * 8;
+    let mut choices: Vec<SetVariant> = Vec::new();
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("sbe")) {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("choices") {
+                return Err(meta.error("unknown set attribute; expected `choices`"));
+            }
+            meta.parse_nested_meta(|choice| {
+                let name = choice
+                    .path
+                    .get_ident()
+                    .ok_or_else(|| choice.error("expected a choice name"))?
+                    .to_string();
+                let bit_lit: LitInt = choice.value()?.parse()?;
+                let bit_position: u8 = bit_lit.base10_parse()?;
+                if usize::from(bit_position) >= bits {
+                    return Err(syn::Error::new(
+                        bit_lit.span(),
+                        format!("bit {bit_position} is out of range for a {bits}-bit set"),
+                    ));
+                }
+                if let Some(other) = choices.iter().find(|c| c.bit_position == bit_position) {
+                    return Err(syn::Error::new(
+                        bit_lit.span(),
+                        format!("bit {bit_position} is already used by `{}`", other.name),
+                    ));
+                }
+                choices.push(SetVariant { name, bit_position });
+                Ok(())
+            })
+        })?;
+    }
+    Ok(choices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_enum_requires_repr() {
+        let input: DeriveInput = parse_quote! {
+            enum Side { Buy = 1, Sell = 2 }
+        };
+        let err = expand_enum(&input).expect_err("expected an error");
+        assert!(err.to_string().contains("repr"), "{err}");
+    }
+
+    #[test]
+    fn test_enum_rejects_data_variants() {
+        let input: DeriveInput = parse_quote! {
+            #[repr(u8)]
+            enum Side { Buy(u8) }
+        };
+        let err = expand_enum(&input).expect_err("expected an error");
+        assert!(err.to_string().contains("cannot have fields"), "{err}");
+    }
+
+    #[test]
+    fn test_enum_implicit_discriminants() {
+        let input: DeriveInput = parse_quote! {
+            #[repr(i8)]
+            enum Level { Low = -1, Mid, High = 5, Max }
+        };
+        let tokens = expand_enum(&input).expect("expand").to_string();
+        assert!(tokens.contains("- 1 => Ok (Self :: Low)"), "{tokens}");
+        assert!(tokens.contains("0 => Ok (Self :: Mid)"), "{tokens}");
+        assert!(tokens.contains("6 => Ok (Self :: Max)"), "{tokens}");
+        assert!(!tokens.contains("impl From < i8 >"), "{tokens}");
+    }
+
+    #[test]
+    fn test_set_requires_unsigned_newtype() {
+        let input: DeriveInput = parse_quote! {
+            struct Flags(i8);
+        };
+        let err = expand_set(&input).expect_err("expected an error");
+        assert!(err.to_string().contains("must be `u8`"), "{err}");
+    }
+
+    #[test]
+    fn test_set_bit_out_of_range() {
+        let input: DeriveInput = parse_quote! {
+            #[sbe(choices(Active = 8))]
+            struct Flags(u8);
+        };
+        let err = expand_set(&input).expect_err("expected an error");
+        assert!(err.to_string().contains("out of range"), "{err}");
+    }
+
+    #[test]
+    fn test_set_duplicate_bit() {
+        let input: DeriveInput = parse_quote! {
+            #[sbe(choices(Active = 0, Visible = 0))]
+            struct Flags(u8);
+        };
+        let err = expand_set(&input).expect_err("expected an error");
+        assert!(
+            err.to_string().contains("already used by `Active`"),
+            "{err}"
+        );
+    }
+}
@@ -5,40 +5,116 @@
 //! This crate provides derive macros for automatically implementing
 //! SBE encoder/decoder traits.
 
+mod enums;
+mod message;
+
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{DeriveInput, parse_macro_input};
 
-/// Derives the SbeMessage trait for a struct.
+/// Derives an SBE decoder and encoder for a struct.
+///
+/// Generates `{Name}Decoder` and `{Name}Encoder` with the same accessors
+/// `ironsbe-codegen` emits for a schema message, an `SbeDecoder` impl for the
+/// decoder, and `encode`/`decode` helpers on the struct itself. The expansion
+/// refers to `ironsbe_core`, which must be a dependency of the calling crate.
+///
+/// Fields must be primitive integers, floats or `[u8; N]`.
+///
+/// # Message attributes
+/// * `template_id` - Template ID (required)
+/// * `block_length` - Fixed block length; defaults to the end of the last field
+/// * `schema_id` - Schema ID (default 0)
+/// * `schema_version` - Schema version (default 0)
+///
+/// # Field attributes
+/// * `offset` - Byte offset in the block; defaults to the end of the previous field
+/// * `type` - SBE primitive type name, e.g. `"char"` for a text array
+/// * `length` - Array length, checked against the Rust array type
+/// * `optional` - Generates `has_*` and `*_opt` accessors using the SBE null value
 ///
 /// # Example
 /// ```ignore
 /// #[derive(SbeMessage)]
 /// #[sbe(template_id = 1, block_length = 56)]
 /// struct NewOrderSingle {
-///     #[sbe(offset = 0, type = "ClOrdId")]
+///     #[sbe(offset = 0, type = "char")]
 ///     cl_ord_id: [u8; 20],
-///     #[sbe(offset = 20, type = "Symbol")]
+///     #[sbe(offset = 20, type = "char", length = 8)]
 ///     symbol: [u8; 8],
+///     #[sbe(offset = 28)]
+///     quantity: u64,
 /// }
+///
+/// let order = NewOrderSingle { cl_ord_id: *b"ORD-0000000000000001", symbol: *b"AAPL\0\0\0\0", quantity: 100 };
+/// let len = order.encode(&mut buffer, 0);
+/// let decoder = NewOrderSingleDecoder::decode(&buffer[..len])?;
+/// assert_eq!(decoder.symbol_as_str(), "AAPL");
 /// ```
 #[proc_macro_derive(SbeMessage, attributes(sbe))]
 pub fn derive_sbe_message(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
-    let name = &input.ident;
-
-    // For now, generate a simple implementation
-    // Full implementation would parse attributes and generate field accessors
-    let expanded = quote! {
-        impl #name {
-            /// Returns the template ID for this message.
-            pub const fn template_id() -> u16 {
-                0 // Would be parsed from attribute
-            }
-        }
-    };
+    message::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-    TokenStream::from(expanded)
+/// Derives conversions between a fieldless enum and its encoding primitive.
+///
+/// Generates `TryFrom<primitive>` and `From<enum>` impls through the helper
+/// `ironsbe-codegen` uses for a schema `<enum>`. Values no variant has are
+/// rejected with `ironsbe_core::decoder::UnknownEnumValue`, which carries
+/// the raw value, so the crate must depend on `ironsbe-core`. There is no
+/// `From<primitive>`. The enum must have an integer `#[repr(...)]`, which
+/// sets the encoding.
+///
+/// # Example
+/// ```ignore
+/// #[derive(SbeEnum, Debug, Clone, Copy, PartialEq, Eq)]
+/// #[repr(u8)]
+/// enum Side {
+///     Buy = b'1',
+///     Sell = b'2',
+/// }
+///
+/// assert_eq!(Side::try_from(b'2'), Ok(Side::Sell));
+/// assert_eq!(Side::try_from(b'9'), Err(UnknownEnumValue(b'9')));
+/// assert_eq!(u8::from(Side::Buy), b'1');
+/// ```
+#[proc_macro_derive(SbeEnum)]
+pub fn derive_sbe_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    enums::expand_enum(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives bit accessors for a set (bitfield) newtype.
+///
+/// Generates the same inherent impl that `ironsbe-codegen` emits for a schema
+/// `<set>`: `new`, `from_raw`, `raw`, `is_set`/`set`/`clear`, and per choice
+/// a bit position constant plus `is_*`, `set_*` and `clear_*` methods. The
+/// struct must wrap a single `u8`, `u16`, `u32` or `u64`.
+///
+/// # Set attributes
+/// * `choices(Name = bit, ...)` - Choice names and bit positions
+///
+/// # Example
+/// ```ignore
+/// #[derive(SbeSet, Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// #[sbe(choices(Active = 0, Visible = 1))]
+/// struct Flags(u8);
+///
+/// let mut flags = Flags::new();
+/// flags.set_visible();
+/// assert_eq!(flags.raw(), 1 << Flags::VISIBLE);
+/// ```
+#[proc_macro_derive(SbeSet, attributes(sbe))]
+pub fn derive_sbe_set(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    enums::expand_set(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
 /// Derives field accessors for SBE fields.
@@ -0,0 +1,505 @@
+//! `#[derive(SbeMessage)]` expansion.
+//!
+//! The struct and its `#[sbe(...)]` attributes are lowered to a single-message
+//! [`SchemaIr`], and the decoder and encoder are emitted by the same
+//! [`MessageGenerator`] that `ironsbe-codegen` uses for XML schemas, so
+//! derived and generated messages have identical accessors.
+
+use std::collections::HashMap;
+
+use ironsbe_codegen::rust::MessageGenerator;
+use ironsbe_schema::ir::{
+    MessageHeaderLayout, ResolvedField, ResolvedMessage, SchemaIr, to_snake_case,
+};
+use ironsbe_schema::types::PrimitiveType;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Expr, Fields, Lit, LitInt, LitStr, Type};
+
+/// Message-level `#[sbe(...)]` options.
+#[derive(Default)]
+struct MessageAttrs {
+    template_id: Option<u16>,
+    block_length: Option<u16>,
+    schema_id: u16,
+    schema_version: u16,
+}
+
+/// Field-level `#[sbe(...)]` options.
+#[derive(Default)]
+struct FieldAttrs {
+    offset: Option<usize>,
+    sbe_type: Option<LitStr>,
+    length: Option<LitInt>,
+    optional: bool,
+}
+
+/// Wire layout of a field, inferred from its Rust type and attributes.
+struct FieldLayout {
+    primitive: PrimitiveType,
+    array_length: Option<usize>,
+}
+
+impl FieldLayout {
+    fn encoded_length(&self) -> usize {
+        self.primitive.size() * self.array_length.unwrap_or(1)
+    }
+}
+
+/// Expands `#[derive(SbeMessage)]`.
+pub(crate) fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let vis = &input.vis;
+
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "SbeMessage cannot be derived for generic structs",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            Fields::Unit => &syn::punctuated::Punctuated::new(),
+            Fields::Unnamed(_) => {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "SbeMessage requires named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                name.span(),
+                "SbeMessage can only be derived for structs",
+            ));
+        }
+    };
+
+    let attrs = parse_message_attrs(input)?;
+    let template_id = attrs.template_id.ok_or_else(|| {
+        syn::Error::new(
+            name.span(),
+            "missing `#[sbe(template_id = ...)]` on SbeMessage",
+        )
+    })?;
+
+    // Lay out the fixed block, filling in omitted offsets
+    let mut resolved = Vec::with_capacity(fields.len());
+    let mut end = 0;
+    for (index, field) in fields.iter().enumerate() {
+        let ident = field.ident.as_ref().expect("named field");
+        let field_attrs = parse_field_attrs(field)?;
+        let layout = field_layout(&field.ty, &field_attrs)?;
+
+        if field_attrs.optional && layout.array_length.is_some() {
+            return Err(syn::Error::new(
+                ident.span(),
+                "`optional` is only supported on scalar fields",
+            ));
+        }
+        let offset = field_attrs.offset.unwrap_or(end);
+        if offset < end {
+            return Err(syn::Error::new(
+                ident.span(),
+                format!(
+                    "field `{ident}` at offset {offset} overlaps the previous field, which ends at {end}"
+                ),
+            ));
+        }
+        end = offset + layout.encoded_length();
+
+        let getter_name = ident.to_string();
+        resolved.push(ResolvedField {
+            name: getter_name.clone(),
+            id: u16::try_from(index + 1).unwrap_or(u16::MAX),
+            type_name: layout.primitive.sbe_name().to_string(),
+            offset,
+            encoded_length: layout.encoded_length(),
+            rust_type: match layout.array_length {
+                Some(len) => format!("[{}; {}]", layout.primitive.rust_type(), len),
+                None => layout.primitive.rust_type().to_string(),
+            },
+            setter_name: format!("set_{getter_name}"),
+            getter_name,
+            is_optional: field_attrs.optional,
+            is_array: layout.array_length.is_some(),
+            array_length: layout.array_length,
+            primitive_type: Some(layout.primitive),
+            null_value: None,
+            constant: None,
+        });
+    }
+
+    let block_length = match attrs.block_length {
+        Some(block_length) if usize::from(block_length) < end => {
+            return Err(syn::Error::new(
+                name.span(),
+                format!(
+                    "block_length {block_length} is shorter than the fields, which end at {end}"
+                ),
+            ));
+        }
+        Some(block_length) => block_length,
+        None => u16::try_from(end)
+            .map_err(|_| syn::Error::new(name.span(), "fields exceed the maximum block length"))?,
+    };
+
+    let message = ResolvedMessage {
+        name: name.to_string(),
+        template_id,
+        block_length,
+        fields: resolved,
+        groups: Vec::new(),
+        var_data: Vec::new(),
+    };
+    let decoder = format_ident!("{}", message.decoder_name());
+    let encoder = format_ident!("{}", message.encoder_name());
+    let ir = SchemaIr {
+        package: String::new(),
+        schema_id: attrs.schema_id,
+        schema_version: attrs.schema_version,
+        types: HashMap::new(),
+        messages: vec![message],
+        message_header: MessageHeaderLayout::standard("messageHeader"),
+    };
+    let generated = MessageGenerator::new(&ir).generate_tokens();
+    let encode_fields = ir.messages[0].fields.iter().map(|field| {
+        let getter = format_ident!("{}", field.getter_name);
+        let setter = format_ident!("{}", field.setter_name);
+        if field.is_array {
+            quote! { encoder.#setter(&self.#getter); }
+        } else {
+            quote! { encoder.#setter(self.#getter); }
+        }
+    });
+    let decode_fields = ir.messages[0].fields.iter().map(|field| {
+        let getter = format_ident!("{}", field.getter_name);
+        if field.is_array {
+            quote! { #getter: decoder.#getter().try_into().expect("array field length"), }
+        } else {
+            quote! { #getter: decoder.#getter(), }
+        }
+    });
+
+    let module = format_ident!("__sbe_{}", to_snake_case(&name.to_string()));
+    let schema_id = attrs.schema_id;
+    let schema_version = attrs.schema_version;
+
+    Ok(quote! {
+        impl #name {
+            /// Template ID for this message.
+            pub const TEMPLATE_ID: u16 = #template_id;
+            /// Block length of the fixed portion.
+            pub const BLOCK_LENGTH: u16 = #block_length;
+            /// Schema ID for this message.
+            pub const SCHEMA_ID: u16 = #schema_id;
+            /// Schema version for this message.
+            pub const SCHEMA_VERSION: u16 = #schema_version;
+
+            /// Returns the template ID for this message.
+            pub const fn template_id() -> u16 {
+                Self::TEMPLATE_ID
+            }
+
+            /// Encodes the message, including its header, at `offset`.
+            ///
+            /// Returns the number of bytes written.
+            ///
+            /// # Panics
+            /// Panics if `buffer` is too short for the message.
+            pub fn encode(&self, buffer: &mut [u8], offset: usize) -> usize {
+                let mut encoder = #encoder::wrap(buffer, offset);
+                #(#encode_fields)*
+                encoder.encoded_length()
+            }
+
+            /// Decodes a message from a buffer starting with its header.
+            ///
+            /// # Errors
+            /// Returns an error if the header does not match this message or
+            /// the buffer is too short.
+            pub fn decode(buffer: &[u8]) -> ::core::result::Result<Self, ::ironsbe_core::decoder::DecodeError> {
+                let decoder = <#decoder<'_> as ::ironsbe_core::decoder::SbeDecoder<'_>>::decode(buffer)?;
+                ::core::result::Result::Ok(Self {
+                    #(#decode_fields)*
+                })
+            }
+        }
+
+        #[doc(hidden)]
+        #[allow(dead_code, unused_imports, clippy::all)]
+        mod #module {
+            use ::ironsbe_core::{
+                buffer::{ReadBuffer, WriteBuffer},
+                decoder::SbeDecoder,
+                encoder::SbeEncoder,
+                header::{MessageHeader, SbeHeader},
+            };
+
+            const SCHEMA_ID: u16 = #schema_id;
+            const SCHEMA_VERSION: u16 = #schema_version;
+
+            #generated
+        }
+
+        #vis use #module::{#decoder, #encoder};
+    })
+}
+
+/// Parses the `#[sbe(...)]` attributes on the struct.
+fn parse_message_attrs(input: &DeriveInput) -> syn::Result<MessageAttrs> {
+    let mut attrs = MessageAttrs::default();
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("sbe")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("template_id") {
+                attrs.template_id = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+            } else if meta.path.is_ident("block_length") {
+                attrs.block_length = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+            } else if meta.path.is_ident("schema_id") {
+                attrs.schema_id = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+            } else if meta.path.is_ident("schema_version") {
+                attrs.schema_version = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+            } else {
+                return Err(meta.error(
+                    "unknown message attribute; expected `template_id`, `block_length`, `schema_id` or `schema_version`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+/// Parses the `#[sbe(...)]` attributes on a field.
+fn parse_field_attrs(field: &syn::Field) -> syn::Result<FieldAttrs> {
+    let mut attrs = FieldAttrs::default();
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("sbe")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("offset") {
+                attrs.offset = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+            } else if meta.path.is_ident("type") {
+                attrs.sbe_type = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("length") {
+                attrs.length = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("optional") {
+                attrs.optional = true;
+            } else {
+                return Err(meta.error(
+                    "unknown field attribute; expected `offset`, `type`, `length` or `optional`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+/// Infers the wire layout of a field from its Rust type, checked against
+/// the `type` and `length` attributes when present.
+fn field_layout(ty: &Type, attrs: &FieldAttrs) -> syn::Result<FieldLayout> {
+    let unsupported = || {
+        syn::Error::new(
+            ty.span(),
+            "unsupported SbeMessage field type; expected a primitive integer, float or `[u8; N]`",
+        )
+    };
+
+    let (rust_type, array_length) = match ty {
+        Type::Array(array) => {
+            let elem = primitive_ident(&array.elem).ok_or_else(unsupported)?;
+            if elem != "u8" {
+                return Err(unsupported());
+            }
+            let Expr::Lit(syn::ExprLit {
+                lit: Lit::Int(len), ..
+            }) = &array.len
+            else {
+                return Err(syn::Error::new(
+                    array.len.span(),
+                    "array length must be an integer literal",
+                ));
+            };
+            (elem, Some(len.base10_parse::<usize>()?))
+        }
+        _ => (primitive_ident(ty).ok_or_else(unsupported)?, None),
+    };
+
+    if let Some(length) = &attrs.length {
+        let length_value = length.base10_parse::<usize>()?;
+        match array_length {
+            Some(n) if n == length_value => {}
+            Some(n) => {
+                return Err(syn::Error::new(
+                    length.span(),
+                    format!("`length = {length_value}` does not match the array length {n}"),
+                ));
+            }
+            None => {
+                return Err(syn::Error::new(
+                    length.span(),
+                    "`length` is only valid on `[u8; N]` fields",
+                ));
+            }
+        }
+    }
+
+    let primitive = match &attrs.sbe_type {
+        Some(sbe_type) => {
+            let primitive = PrimitiveType::from_sbe_name(&sbe_type.value()).ok_or_else(|| {
+                syn::Error::new(
+                    sbe_type.span(),
+                    format!("unknown SBE primitive type `{}`", sbe_type.value()),
+                )
+            })?;
+            if primitive.rust_type() != rust_type {
+                return Err(syn::Error::new(
+                    sbe_type.span(),
+                    format!(
+                        "SBE type `{}` is encoded as `{}`, not `{}`",
+                        sbe_type.value(),
+                        primitive.rust_type(),
+                        rust_type
+                    ),
+                ));
+            }
+            primitive
+        }
+        None => primitive_from_rust(&rust_type).ok_or_else(unsupported)?,
+    };
+
+    Ok(FieldLayout {
+        primitive,
+        array_length,
+    })
+}
+
+/// Returns the name of a primitive numeric type, e.g. `u32`.
+fn primitive_ident(ty: &Type) -> Option<String> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let ident = path.path.get_ident()?.to_string();
+    primitive_from_rust(&ident).map(|_| ident)
+}
+
+/// Maps a Rust primitive to the SBE primitive with the same encoding.
+pub(crate) fn primitive_from_rust(rust_type: &str) -> Option<PrimitiveType> {
+    match rust_type {
+        "u8" => Some(PrimitiveType::Uint8),
+        "i8" => Some(PrimitiveType::Int8),
+        "u16" => Some(PrimitiveType::Uint16),
+        "i16" => Some(PrimitiveType::Int16),
+        "u32" => Some(PrimitiveType::Uint32),
+        "i32" => Some(PrimitiveType::Int32),
+        "u64" => Some(PrimitiveType::Uint64),
+        "i64" => Some(PrimitiveType::Int64),
+        "f32" => Some(PrimitiveType::Float),
+        "f64" => Some(PrimitiveType::Double),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn expand_err(input: DeriveInput) -> String {
+        expand(&input).expect_err("expected an error").to_string()
+    }
+
+    #[test]
+    fn test_missing_template_id() {
+        let err = expand_err(parse_quote! {
+            struct Ping {
+                id: u64,
+            }
+        });
+        assert!(err.contains("template_id"), "{err}");
+    }
+
+    #[test]
+    fn test_overlapping_fields() {
+        let err = expand_err(parse_quote! {
+            #[sbe(template_id = 1)]
+            struct Ping {
+                id: u64,
+                #[sbe(offset = 4)]
+                seq: u32,
+            }
+        });
+        assert!(err.contains("overlaps"), "{err}");
+    }
+
+    #[test]
+    fn test_block_length_too_short() {
+        let err = expand_err(parse_quote! {
+            #[sbe(template_id = 1, block_length = 4)]
+            struct Ping {
+                id: u64,
+            }
+        });
+        assert!(err.contains("block_length 4"), "{err}");
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let err = expand_err(parse_quote! {
+            #[sbe(template_id = 1)]
+            struct Ping {
+                #[sbe(type = "int32")]
+                id: u64,
+            }
+        });
+        assert!(err.contains("not `u64`"), "{err}");
+    }
+
+    #[test]
+    fn test_length_mismatch() {
+        let err = expand_err(parse_quote! {
+            #[sbe(template_id = 1)]
+            struct Ping {
+                #[sbe(length = 4)]
+                symbol: [u8; 8],
+            }
+        });
+        assert!(err.contains("does not match"), "{err}");
+    }
+
+    #[test]
+    fn test_unsupported_field_type() {
+        let err = expand_err(parse_quote! {
+            #[sbe(template_id = 1)]
+            struct Ping {
+                name: String,
+            }
+        });
+        assert!(err.contains("unsupported"), "{err}");
+    }
+
+    #[test]
+    fn test_unknown_attribute() {
+        let err = expand_err(parse_quote! {
+            #[sbe(template_id = 1, templat = 2)]
+            struct Ping {
+                id: u64,
+            }
+        });
+        assert!(err.contains("unknown message attribute"), "{err}");
+    }
+
+    #[test]
+    fn test_char_array_field() {
+        let input: DeriveInput = parse_quote! {
+            #[sbe(template_id = 1)]
+            struct Quote {
+                #[sbe(type = "char")]
+                symbol: [u8; 8],
+            }
+        };
+        let tokens = expand(&input).expect("expand").to_string();
+        assert!(tokens.contains("symbol_as_str"));
+    }
+}
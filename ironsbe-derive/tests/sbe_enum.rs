@@ -0,0 +1,60 @@
+//! Conversions and accessors from `#[derive(SbeEnum)]` and `#[derive(SbeSet)]`.
+
+use ironsbe_core::decoder::UnknownEnumValue;
+use ironsbe_derive::{SbeEnum, SbeSet};
+
+#[derive(SbeEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum Side {
+    Buy = b'1',
+    Sell = b'2',
+}
+
+#[derive(SbeEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i16)]
+enum Direction {
+    Down = -1,
+    Flat,
+    Up,
+}
+
+#[derive(SbeSet, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[sbe(choices(Active = 0, Visible = 1, lastFill = 15))]
+struct Flags(u16);
+
+#[test]
+fn test_enum_conversions() {
+    assert_eq!(Side::try_from(b'2'), Ok(Side::Sell));
+    assert_eq!(u8::from(Side::Buy), b'1');
+    assert_eq!(Direction::try_from(1i16), Ok(Direction::Up));
+    assert_eq!(Direction::try_from(-1i16), Ok(Direction::Down));
+    assert_eq!(i16::from(Direction::Flat), 0);
+}
+
+#[test]
+fn test_enum_rejects_unknown_values() {
+    assert_eq!(Side::try_from(b'9'), Err(UnknownEnumValue(b'9')));
+    assert_eq!(Side::try_from(0u8), Err(UnknownEnumValue(0)));
+    assert_eq!(Direction::try_from(42i16), Err(UnknownEnumValue(42)));
+    assert_eq!(
+        Side::try_from(b'9').unwrap_err().to_string(),
+        "unknown enum value 57"
+    );
+}
+
+#[test]
+fn test_set_accessors() {
+    let mut flags = Flags::new();
+    assert_eq!(flags, Flags::default());
+
+    flags.set_visible();
+    flags.set_last_fill();
+    assert!(flags.is_visible());
+    assert!(flags.is_last_fill());
+    assert!(!flags.is_active());
+    assert_eq!(flags.raw(), (1 << Flags::VISIBLE) | (1 << Flags::LAST_FILL));
+
+    flags.clear_visible();
+    assert_eq!(flags, Flags::from_raw(1 << 15));
+    assert!(flags.is_set(Flags::LAST_FILL));
+}
@@ -0,0 +1,112 @@
+//! Round trips through `#[derive(SbeMessage)]` encoders and decoders.
+
+use ironsbe_core::decoder::{DecodeError, SbeDecoder};
+use ironsbe_core::header::MessageHeader;
+use ironsbe_derive::SbeMessage;
+
+#[derive(SbeMessage, Debug, Clone, PartialEq)]
+#[sbe(template_id = 7, schema_id = 42, schema_version = 3)]
+pub struct NewOrder {
+    order_id: u64,
+    #[sbe(type = "char", length = 8)]
+    symbol: [u8; 8],
+    price: i64,
+    quantity: u32,
+    #[sbe(offset = 32, optional)]
+    max_floor: u32,
+}
+
+#[derive(SbeMessage, Debug, PartialEq)]
+#[sbe(template_id = 8, block_length = 16)]
+struct Heartbeat {
+    #[sbe(offset = 4)]
+    seq: u32,
+}
+
+fn order() -> NewOrder {
+    NewOrder {
+        order_id: 1001,
+        symbol: *b"AAPL\0\0\0\0",
+        price: -15_050,
+        quantity: 100,
+        max_floor: u32::MAX,
+    }
+}
+
+#[test]
+fn test_constants() {
+    assert_eq!(NewOrder::template_id(), 7);
+    assert_eq!(NewOrder::BLOCK_LENGTH, 36);
+    assert_eq!(<NewOrderDecoder<'_> as SbeDecoder<'_>>::SCHEMA_ID, 42);
+    assert_eq!(<NewOrderDecoder<'_> as SbeDecoder<'_>>::SCHEMA_VERSION, 3);
+    assert_eq!(Heartbeat::BLOCK_LENGTH, 16);
+}
+
+#[test]
+fn test_encode_writes_header() {
+    let mut buffer = [0u8; 64];
+    let len = order().encode(&mut buffer, 0);
+    assert_eq!(len, MessageHeader::ENCODED_LENGTH + 36);
+
+    let header = MessageHeader::wrap(&buffer[..], 0);
+    assert_eq!({ header.template_id }, 7);
+    assert_eq!({ header.schema_id }, 42);
+    assert_eq!({ header.version }, 3);
+    assert_eq!({ header.block_length }, 36);
+}
+
+#[test]
+fn test_decoder_accessors() {
+    let mut buffer = [0u8; 64];
+    let len = order().encode(&mut buffer, 0);
+
+    let decoder = NewOrderDecoder::decode(&buffer[..len]).expect("decode");
+    assert_eq!(decoder.order_id(), 1001);
+    assert_eq!(decoder.symbol_as_str(), "AAPL");
+    assert_eq!(decoder.price(), -15_050);
+    assert_eq!(decoder.quantity(), 100);
+    assert!(!decoder.has_max_floor());
+    assert_eq!(decoder.max_floor_opt(), None);
+    assert_eq!(decoder.encoded_length(), len);
+}
+
+#[test]
+fn test_encoder_setters() {
+    let mut buffer = [0u8; 64];
+    let mut encoder = NewOrderEncoder::wrap(&mut buffer, 0);
+    encoder
+        .set_order_id(5)
+        .set_symbol(b"MSFT")
+        .set_max_floor(10);
+
+    let decoder = NewOrderDecoder::decode(&buffer).expect("decode");
+    assert_eq!(decoder.order_id(), 5);
+    assert_eq!(decoder.symbol(), b"MSFT\0\0\0\0");
+    assert_eq!(decoder.max_floor_opt(), Some(10));
+}
+
+#[test]
+fn test_struct_round_trip() {
+    let mut buffer = [0u8; 64];
+    let len = order().encode(&mut buffer, 0);
+    assert_eq!(NewOrder::decode(&buffer[..len]), Ok(order()));
+
+    let heartbeat = Heartbeat { seq: 9 };
+    let len = heartbeat.encode(&mut buffer, 0);
+    assert_eq!(len, MessageHeader::ENCODED_LENGTH + 16);
+    assert_eq!(Heartbeat::decode(&buffer[..len]), Ok(heartbeat));
+}
+
+#[test]
+fn test_decode_rejects_other_template() {
+    let mut buffer = [0u8; 64];
+    let len = Heartbeat { seq: 1 }.encode(&mut buffer, 0);
+
+    assert_eq!(
+        NewOrder::decode(&buffer[..len]),
+        Err(DecodeError::TemplateMismatch {
+            expected: 7,
+            actual: 8,
+        })
+    );
+}
@@ -0,0 +1,37 @@
+//! Error types for journal operations.
+
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Error type for journal operations.
+#[derive(Debug, Error)]
+pub enum JournalError {
+    /// IO error.
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A frame does not fit in an empty segment.
+    #[error("frame of {len} bytes exceeds the segment limit of {max} bytes")]
+    FrameTooLarge {
+        /// Length of the frame in bytes.
+        len: usize,
+        /// Largest frame a segment holds, in bytes.
+        max: usize,
+    },
+
+    /// Another writer has the journal directory open.
+    #[error("journal {} is locked by another writer", dir.display())]
+    Locked {
+        /// Path of the journal directory.
+        dir: PathBuf,
+    },
+
+    /// A segment holds something other than a valid run of records.
+    #[error("corrupt journal segment {}: invalid data at offset {offset}", segment.display())]
+    Corrupt {
+        /// Path of the segment file.
+        segment: PathBuf,
+        /// Offset of the invalid header or record in the file.
+        offset: usize,
+    },
+}
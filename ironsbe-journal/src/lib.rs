@@ -0,0 +1,37 @@
+//! # IronSBE Journal
+//!
+//! Persistent append-only journal of SBE frames.
+//!
+//! A gateway appends every frame it sends or receives to a [`Journal`],
+//! and a [`JournalReader`] reads them back later for recovery, audit or
+//! deterministic backtesting. This crate provides:
+//! - Memory-mapped segment files, rotated once full, with a checksum on
+//!   every record and recovery from writes cut short by a crash
+//! - Lookup by sequence number and by timestamp through a sparse index
+//! - Replay of a range at its original pace, accelerated, or unthrottled
+//!
+//! # Example
+//! ```no_run
+//! use ironsbe_core::TapDirection;
+//! use ironsbe_journal::{Journal, JournalConfig, JournalReader, ReplaySpeed};
+//!
+//! let mut journal = Journal::open("journal", JournalConfig::default())?;
+//! let sequence = journal.append(TapDirection::Outbound, b"frame")?;
+//!
+//! let reader = JournalReader::open("journal")?;
+//! for record in reader.replay(sequence.., ReplaySpeed::Accelerated(10.0)) {
+//!     println!("{} {:?} {} bytes", record.sequence, record.direction, record.frame.len());
+//! }
+//! # Ok::<(), ironsbe_journal::JournalError>(())
+//! ```
+
+pub mod error;
+pub mod reader;
+pub mod replay;
+mod segment;
+pub mod writer;
+
+pub use error::JournalError;
+pub use reader::{JournalReader, JournalRecord, Records};
+pub use replay::{Replay, ReplaySpeed};
+pub use writer::{Journal, JournalConfig};
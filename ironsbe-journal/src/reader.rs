@@ -0,0 +1,411 @@
+//! Reading a journal by sequence number or timestamp.
+
+use crate::error::JournalError;
+use crate::replay::{Replay, ReplaySpeed};
+use crate::segment::{self, HEADER_SIZE, RawRecord, Slot};
+use ironsbe_core::TapDirection;
+use ironsbe_core::types::Timestamp;
+use memmap2::{Mmap, MmapOptions};
+use std::fs::File;
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+
+/// Records between two entries of a segment's index.
+const INDEX_INTERVAL: u64 = 64;
+
+/// A frame read from a journal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JournalRecord<'a> {
+    /// Sequence number of the record in the journal.
+    pub sequence: u64,
+    /// Time the frame was journaled, or the timestamp it was appended with.
+    pub timestamp: Timestamp,
+    /// Whether the frame was received or sent.
+    pub direction: TapDirection,
+    /// The frame bytes.
+    pub frame: &'a [u8],
+}
+
+impl<'a> From<RawRecord<'a>> for JournalRecord<'a> {
+    fn from(record: RawRecord<'a>) -> Self {
+        Self {
+            sequence: record.sequence,
+            timestamp: Timestamp::new(record.timestamp),
+            direction: record.direction,
+            frame: record.frame,
+        }
+    }
+}
+
+/// Position of every [`INDEX_INTERVAL`]th record of a segment.
+#[derive(Debug, Clone, Copy)]
+struct IndexEntry {
+    sequence: u64,
+    timestamp: u64,
+    offset: usize,
+}
+
+/// A mapped segment holding at least one record.
+struct Segment {
+    mmap: Mmap,
+    /// Sequence number past the last record.
+    end: u64,
+    last_timestamp: u64,
+    index: Vec<IndexEntry>,
+}
+
+impl Segment {
+    fn first(&self) -> u64 {
+        self.index[0].sequence
+    }
+
+    /// Returns the offset of the record with sequence `sequence`, which the
+    /// segment must hold.
+    fn offset_of(&self, sequence: u64) -> usize {
+        let entry = self.index[self.index.partition_point(|e| e.sequence <= sequence) - 1];
+        self.skip(entry.offset, sequence - entry.sequence)
+    }
+
+    /// Returns the sequence number of the first record stamped `timestamp`
+    /// or later, which the segment must hold.
+    fn sequence_at(&self, timestamp: u64) -> u64 {
+        let entry = self.index[self
+            .index
+            .partition_point(|e| e.timestamp < timestamp)
+            .saturating_sub(1)];
+        let (mut sequence, mut offset) = (entry.sequence, entry.offset);
+        while let Slot::Record(record) = segment::read_record(&self.mmap, offset) {
+            if record.timestamp >= timestamp {
+                break;
+            }
+            sequence += 1;
+            offset += record.size;
+        }
+        sequence
+    }
+
+    fn skip(&self, mut offset: usize, records: u64) -> usize {
+        for _ in 0..records {
+            match segment::read_record(&self.mmap, offset) {
+                Slot::Record(record) => offset += record.size,
+                Slot::End | Slot::Invalid => unreachable!("segment was scanned on open"),
+            }
+        }
+        offset
+    }
+}
+
+/// Read-only view of a journal directory.
+///
+/// Opening a reader maps every segment and checks every record, building
+/// an index by sequence number and timestamp as it goes. Records appended
+/// afterwards are picked up by [`refresh`](Self::refresh). A reader may
+/// be open while a writer appends to the same directory.
+pub struct JournalReader {
+    dir: PathBuf,
+    segments: Vec<Segment>,
+}
+
+impl JournalReader {
+    /// Opens the journal in `dir`.
+    ///
+    /// The journal may start at any sequence number, so segments may be
+    /// pruned from the front. A record that fails its checksum at the end
+    /// of the last segment is taken for a write in progress and ends the
+    /// journal.
+    ///
+    /// # Errors
+    /// Returns an error if a segment cannot be mapped, or
+    /// [`JournalError::Corrupt`] if one has an invalid header or record, or
+    /// does not continue where the previous one ended.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, JournalError> {
+        let dir = dir.as_ref().to_path_buf();
+        let listed = segment::list(&dir)?;
+        let mut segments: Vec<Segment> = Vec::with_capacity(listed.len());
+        let last = listed.len().saturating_sub(1);
+        for (i, (first, path)) in listed.into_iter().enumerate() {
+            let corrupt = |offset| JournalError::Corrupt {
+                segment: path.clone(),
+                offset,
+            };
+            if segments.last().is_some_and(|prev| prev.end != first) {
+                return Err(corrupt(0));
+            }
+            // SAFETY: the writer never truncates a segment once created,
+            // and record headers it may be storing are read atomically.
+            let mmap = unsafe { MmapOptions::new().map(&File::open(&path)?)? };
+            if !segment::check_header(&mmap, &path, first)? {
+                if i == last {
+                    break;
+                }
+                return Err(corrupt(0));
+            }
+
+            let (mut sequence, mut offset, mut last_timestamp) = (first, HEADER_SIZE, 0);
+            let mut index = Vec::new();
+            loop {
+                match segment::read_record(&mmap, offset) {
+                    Slot::Record(record) if record.sequence == sequence => {
+                        if (sequence - first) % INDEX_INTERVAL == 0 {
+                            index.push(IndexEntry {
+                                sequence,
+                                timestamp: record.timestamp,
+                                offset,
+                            });
+                        }
+                        sequence += 1;
+                        offset += record.size;
+                        last_timestamp = record.timestamp;
+                    }
+                    Slot::End => break,
+                    _ if i == last => break,
+                    _ => return Err(corrupt(offset)),
+                }
+            }
+            if !index.is_empty() {
+                segments.push(Segment {
+                    mmap,
+                    end: sequence,
+                    last_timestamp,
+                    index,
+                });
+            }
+        }
+        Ok(Self { dir, segments })
+    }
+
+    /// Reopens the journal to pick up records appended since it was opened.
+    ///
+    /// # Errors
+    /// Returns the errors of [`open`](Self::open).
+    pub fn refresh(&mut self) -> Result<(), JournalError> {
+        *self = Self::open(&self.dir)?;
+        Ok(())
+    }
+
+    /// Returns the sequence number of the first record.
+    #[must_use]
+    pub fn first_sequence(&self) -> Option<u64> {
+        self.segments.first().map(Segment::first)
+    }
+
+    /// Returns the sequence number of the last record.
+    #[must_use]
+    pub fn last_sequence(&self) -> Option<u64> {
+        self.segments.last().map(|segment| segment.end - 1)
+    }
+
+    /// Returns true if the journal holds no records.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the record with sequence number `sequence`.
+    #[must_use]
+    pub fn get(&self, sequence: u64) -> Option<JournalRecord<'_>> {
+        self.range(sequence..=sequence).next()
+    }
+
+    /// Returns the sequence number of the first record stamped `timestamp`
+    /// or later.
+    #[must_use]
+    pub fn seek(&self, timestamp: Timestamp) -> Option<u64> {
+        let timestamp = timestamp.as_nanos();
+        let i = self
+            .segments
+            .partition_point(|segment| segment.last_timestamp < timestamp);
+        self.segments
+            .get(i)
+            .map(|segment| segment.sequence_at(timestamp))
+    }
+
+    /// Iterates over the records whose sequence numbers are in `sequences`.
+    #[must_use]
+    pub fn range(&self, sequences: impl RangeBounds<u64>) -> Records<'_> {
+        let start = match sequences.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match sequences.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => u64::MAX,
+        };
+        let start = start.max(self.first_sequence().unwrap_or(0));
+        let segment = self.segments.partition_point(|s| s.end <= start);
+        let offset = self
+            .segments
+            .get(segment)
+            .map_or(HEADER_SIZE, |s| s.offset_of(start));
+        Records {
+            segments: &self.segments,
+            segment,
+            offset,
+            sequence: start,
+            end,
+        }
+    }
+
+    /// Iterates over the records stamped from `from` up to, but excluding,
+    /// `to`.
+    #[must_use]
+    pub fn range_by_time(&self, from: Timestamp, to: Timestamp) -> Records<'_> {
+        let end_of = |timestamp| self.seek(timestamp).unwrap_or(u64::MAX);
+        self.range(end_of(from)..end_of(to))
+    }
+
+    /// Replays the records whose sequence numbers are in `sequences`,
+    /// paced by their timestamps at `speed`.
+    #[must_use]
+    pub fn replay(&self, sequences: impl RangeBounds<u64>, speed: ReplaySpeed) -> Replay<'_> {
+        Replay::new(self.range(sequences), speed)
+    }
+}
+
+/// Iterator over a range of journal records, in sequence order.
+pub struct Records<'a> {
+    segments: &'a [Segment],
+    segment: usize,
+    offset: usize,
+    sequence: u64,
+    end: u64,
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = JournalRecord<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.sequence >= self.end {
+            return None;
+        }
+        let mut segment = self.segments.get(self.segment)?;
+        if self.sequence >= segment.end {
+            self.segment += 1;
+            self.offset = HEADER_SIZE;
+            segment = self.segments.get(self.segment)?;
+        }
+        let Slot::Record(record) = segment::read_record(&segment.mmap, self.offset) else {
+            unreachable!("segment was scanned on open");
+        };
+        self.offset += record.size;
+        self.sequence += 1;
+        Some(record.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writer::{Journal, JournalConfig};
+    use tempfile::tempdir;
+
+    /// Writes `count` records 10 ns apart, `per_segment` to a segment.
+    fn journal(dir: &Path, count: u64, per_segment: usize) -> Journal {
+        let config = JournalConfig {
+            segment_size: HEADER_SIZE + per_segment * 40,
+            first_sequence: 100,
+        };
+        let mut journal = Journal::open(dir, config).unwrap();
+        for i in 0..count {
+            let direction = if i % 2 == 0 {
+                TapDirection::Inbound
+            } else {
+                TapDirection::Outbound
+            };
+            journal
+                .append_at(direction, Timestamp::new(1_000 + i * 10), &i.to_le_bytes())
+                .unwrap();
+        }
+        journal
+    }
+
+    fn sequences(records: Records<'_>) -> Vec<u64> {
+        records.map(|record| record.sequence).collect()
+    }
+
+    #[test]
+    fn test_empty_journal() {
+        let dir = tempdir().unwrap();
+        let reader = JournalReader::open(dir.path()).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(reader.first_sequence(), None);
+        assert_eq!(reader.range(..).count(), 0);
+        assert_eq!(reader.seek(Timestamp::new(0)), None);
+    }
+
+    #[test]
+    fn test_get_across_segments() {
+        let dir = tempdir().unwrap();
+        journal(dir.path(), 300, 100);
+        let reader = JournalReader::open(dir.path()).unwrap();
+        assert_eq!(reader.first_sequence(), Some(100));
+        assert_eq!(reader.last_sequence(), Some(399));
+        for i in [0u64, 3, 4, 63, 64, 65, 299] {
+            let record = reader.get(100 + i).unwrap();
+            assert_eq!(record.sequence, 100 + i);
+            assert_eq!(record.timestamp, Timestamp::new(1_000 + i * 10));
+            assert_eq!(record.frame, i.to_le_bytes());
+        }
+        assert_eq!(reader.get(99), None);
+        assert_eq!(reader.get(400), None);
+    }
+
+    #[test]
+    fn test_range_bounds() {
+        let dir = tempdir().unwrap();
+        journal(dir.path(), 10, 4);
+        let reader = JournalReader::open(dir.path()).unwrap();
+        assert_eq!(sequences(reader.range(103..106)), [103, 104, 105]);
+        assert_eq!(sequences(reader.range(107..)), [107, 108, 109]);
+        assert_eq!(sequences(reader.range(..=101)), [100, 101]);
+        assert_eq!(reader.range(..).count(), 10);
+    }
+
+    #[test]
+    fn test_seek_by_timestamp() {
+        let dir = tempdir().unwrap();
+        journal(dir.path(), 200, 100);
+        let reader = JournalReader::open(dir.path()).unwrap();
+        assert_eq!(reader.seek(Timestamp::new(0)), Some(100));
+        assert_eq!(reader.seek(Timestamp::new(1_000)), Some(100));
+        assert_eq!(reader.seek(Timestamp::new(1_001)), Some(101));
+        assert_eq!(reader.seek(Timestamp::new(2_990)), Some(299));
+        assert_eq!(reader.seek(Timestamp::new(2_991)), None);
+        assert_eq!(
+            sequences(reader.range_by_time(Timestamp::new(1_035), Timestamp::new(1_070))),
+            [104, 105, 106]
+        );
+    }
+
+    #[test]
+    fn test_refresh_sees_new_records() {
+        let dir = tempdir().unwrap();
+        let mut writer = journal(dir.path(), 3, 4);
+        let mut reader = JournalReader::open(dir.path()).unwrap();
+        writer.append(TapDirection::Inbound, b"later").unwrap();
+        assert_eq!(reader.last_sequence(), Some(102));
+        reader.refresh().unwrap();
+        assert_eq!(reader.get(103).unwrap().frame, b"later");
+    }
+
+    #[test]
+    fn test_pruned_and_corrupt_segments() {
+        let dir = tempdir().unwrap();
+        journal(dir.path(), 12, 4);
+        std::fs::remove_file(segment::path(dir.path(), 100)).unwrap();
+        let reader = JournalReader::open(dir.path()).unwrap();
+        assert_eq!(reader.first_sequence(), Some(104));
+
+        // A bad record before the last segment is corruption.
+        let path = segment::path(dir.path(), 104);
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[HEADER_SIZE + 40 + 32] ^= 1;
+        std::fs::write(&path, bytes).unwrap();
+        assert!(matches!(
+            JournalReader::open(dir.path()),
+            Err(JournalError::Corrupt { offset: 104, .. })
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+//! Paced replay of journal records.
+
+use crate::reader::{JournalRecord, Records};
+use std::time::{Duration, Instant};
+
+/// How fast a [`Replay`] hands out records.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReplaySpeed {
+    /// Records are spaced as they were journaled.
+    #[default]
+    Original,
+    /// Records are spaced as they were journaled, divided by the factor:
+    /// `Accelerated(10.0)` replays ten times faster. Factors that are not
+    /// positive replay as fast as possible.
+    Accelerated(f64),
+    /// Records are handed out as fast as they are asked for.
+    Unthrottled,
+}
+
+/// Iterator over journal records that waits before each one until it is
+/// due.
+///
+/// The first record is due at once, and every later one once the time
+/// between its timestamp and the first record's, scaled by the
+/// [`ReplaySpeed`], has passed. A consumer slower than the journal falls
+/// behind rather than skipping records, so a replay always delivers the
+/// same records in the same order.
+pub struct Replay<'a> {
+    records: Records<'a>,
+    speed: ReplaySpeed,
+    start: Option<(Instant, u64)>,
+}
+
+impl<'a> Replay<'a> {
+    pub(crate) fn new(records: Records<'a>, speed: ReplaySpeed) -> Self {
+        Self {
+            records,
+            speed,
+            start: None,
+        }
+    }
+
+    /// Returns how long after the first record a record `elapsed`
+    /// nanoseconds later in the journal is due, or `None` if it is due at
+    /// once.
+    fn delay(&self, elapsed: u64) -> Option<Duration> {
+        let nanos = match self.speed {
+            ReplaySpeed::Original => elapsed as f64,
+            ReplaySpeed::Accelerated(factor) if factor > 0.0 => elapsed as f64 / factor,
+            ReplaySpeed::Accelerated(_) | ReplaySpeed::Unthrottled => return None,
+        };
+        Some(Duration::from_nanos(nanos as u64))
+    }
+}
+
+impl<'a> Iterator for Replay<'a> {
+    type Item = JournalRecord<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.records.next()?;
+        let timestamp = record.timestamp.as_nanos();
+        match self.start {
+            None => self.start = Some((Instant::now(), timestamp)),
+            Some((started, first)) => {
+                if let Some(delay) = self.delay(timestamp.saturating_sub(first)) {
+                    let due = started + delay;
+                    let now = Instant::now();
+                    if due > now {
+                        std::thread::sleep(due - now);
+                    }
+                }
+            }
+        }
+        Some(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::JournalReader;
+    use crate::writer::{Journal, JournalConfig};
+    use ironsbe_core::TapDirection;
+    use ironsbe_core::types::Timestamp;
+    use tempfile::tempdir;
+
+    /// Journals five records 50 ms apart.
+    fn journal(dir: &std::path::Path) -> JournalReader {
+        let mut journal = Journal::open(dir, JournalConfig::default()).unwrap();
+        for i in 0..5u64 {
+            let timestamp = Timestamp::new(i * 50_000_000);
+            journal
+                .append_at(TapDirection::Inbound, timestamp, &[i as u8])
+                .unwrap();
+        }
+        JournalReader::open(dir).unwrap()
+    }
+
+    #[test]
+    fn test_original_speed_keeps_spacing() {
+        let dir = tempdir().unwrap();
+        let reader = journal(dir.path());
+        let started = Instant::now();
+        let frames: Vec<_> = reader
+            .replay(.., ReplaySpeed::Original)
+            .map(|record| record.frame[0])
+            .collect();
+        assert_eq!(frames, [0, 1, 2, 3, 4]);
+        assert!(started.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_accelerated_speed_divides_spacing() {
+        let dir = tempdir().unwrap();
+        let reader = journal(dir.path());
+        let started = Instant::now();
+        assert_eq!(
+            reader.replay(1..5, ReplaySpeed::Accelerated(4.0)).count(),
+            4
+        );
+        let accelerated = started.elapsed();
+        assert!(accelerated >= Duration::from_millis(37));
+
+        // Compared with the same span at the original speed rather than a
+        // wall-clock bound, and with slack for a loaded machine.
+        let started = Instant::now();
+        assert_eq!(reader.replay(1..5, ReplaySpeed::Original).count(), 4);
+        let original = started.elapsed();
+        assert!(accelerated < original);
+    }
+
+    #[test]
+    fn test_unthrottled_does_not_wait() {
+        let dir = tempdir().unwrap();
+        let reader = journal(dir.path());
+        let started = Instant::now();
+        assert_eq!(reader.replay(.., ReplaySpeed::Unthrottled).count(), 5);
+        assert_eq!(reader.replay(.., ReplaySpeed::Accelerated(0.0)).count(), 5);
+        assert!(started.elapsed() < Duration::from_millis(100));
+    }
+}
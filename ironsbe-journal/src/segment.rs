@@ -0,0 +1,249 @@
+//! On-disk segment format.
+//!
+//! A journal is a directory of segment files, each named after the
+//! sequence number of its first record (`00000000000000000001.journal`)
+//! and preallocated to the segment size, next to the `writer.lock` file
+//! the writer holds an exclusive lock on. All integers are little-endian.
+//!
+//! A segment starts with a 64-byte header:
+//!
+//! | Offset | Size | Field                    |
+//! |--------|------|--------------------------|
+//! | 0      | 8    | magic `IRSBEJNL`         |
+//! | 8      | 4    | format version           |
+//! | 16     | 8    | sequence of first record |
+//!
+//! Records follow it back to back, each padded to a multiple of 8 bytes:
+//!
+//! | Offset | Size | Field                                        |
+//! |--------|------|----------------------------------------------|
+//! | 0      | 4    | record size, header and padding included     |
+//! | 4      | 4    | CRC32C of bytes 8 to the end of the frame    |
+//! | 8      | 4    | frame length                                 |
+//! | 12     | 1    | direction: 0 inbound, 1 outbound             |
+//! | 16     | 8    | sequence number                              |
+//! | 24     | 8    | timestamp, nanoseconds since the Unix epoch  |
+//! | 32     | n    | frame                                        |
+//!
+//! The size is stored last, with release ordering, so a reader sees a
+//! record either complete or not at all. The zeroes past the last record
+//! read as a size of 0, which ends the segment.
+
+use crate::error::JournalError;
+use ironsbe_core::TapDirection;
+use ironsbe_core::checksum;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Identifies a segment file.
+const MAGIC: [u8; 8] = *b"IRSBEJNL";
+
+/// Version of the segment format.
+const VERSION: u32 = 1;
+
+/// Extension of segment file names.
+const EXTENSION: &str = "journal";
+
+/// Name of the file the writer locks.
+pub(crate) const LOCK_FILE: &str = "writer.lock";
+
+/// Size of the segment header in bytes.
+pub(crate) const HEADER_SIZE: usize = 64;
+
+/// Size of a record header in bytes.
+pub(crate) const RECORD_HEADER_SIZE: usize = 32;
+
+/// Returns the size of the record holding a frame of `len` bytes.
+pub(crate) const fn record_size(len: usize) -> usize {
+    (RECORD_HEADER_SIZE + len + 7) & !7
+}
+
+/// Returns the path of the segment starting at `first` in `dir`.
+pub(crate) fn path(dir: &Path, first: u64) -> PathBuf {
+    dir.join(format!("{first:020}.{EXTENSION}"))
+}
+
+/// Lists the segments in `dir` as their first sequence and path, in order.
+pub(crate) fn list(dir: &Path) -> io::Result<Vec<(u64, PathBuf)>> {
+    let mut segments = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != EXTENSION) {
+            continue;
+        }
+        let first = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse().ok());
+        if let Some(first) = first {
+            segments.push((first, path));
+        }
+    }
+    segments.sort_unstable_by_key(|(first, _)| *first);
+    Ok(segments)
+}
+
+/// Writes the header of a segment starting at `first`.
+pub(crate) fn write_header(data: &mut [u8], first: u64) {
+    data[..HEADER_SIZE].fill(0);
+    data[..8].copy_from_slice(&MAGIC);
+    data[8..12].copy_from_slice(&VERSION.to_le_bytes());
+    data[16..24].copy_from_slice(&first.to_le_bytes());
+}
+
+/// Checks the header of the segment at `path`, expected to start at
+/// `first`.
+///
+/// # Returns
+/// `false` if the header is all zeroes, as left by a writer that stopped
+/// while creating the segment.
+pub(crate) fn check_header(data: &[u8], path: &Path, first: u64) -> Result<bool, JournalError> {
+    let corrupt = || JournalError::Corrupt {
+        segment: path.to_path_buf(),
+        offset: 0,
+    };
+    let header = data.get(..HEADER_SIZE).ok_or_else(corrupt)?;
+    if header.iter().all(|&b| b == 0) {
+        return Ok(false);
+    }
+    if header[..8] != MAGIC
+        || u32_at(header, 8) != VERSION
+        || u64::from_le_bytes(header[16..24].try_into().unwrap()) != first
+    {
+        return Err(corrupt());
+    }
+    Ok(true)
+}
+
+/// A record read from a segment.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RawRecord<'a> {
+    pub(crate) sequence: u64,
+    pub(crate) timestamp: u64,
+    pub(crate) direction: TapDirection,
+    pub(crate) frame: &'a [u8],
+    pub(crate) size: usize,
+}
+
+/// What a segment holds at an offset.
+pub(crate) enum Slot<'a> {
+    /// A complete record.
+    Record(RawRecord<'a>),
+    /// No record: the segment ends here.
+    End,
+    /// Bytes that are not a valid record.
+    Invalid,
+}
+
+/// Writes a record at `offset`, which must be 8-aligned and leave room for
+/// it.
+pub(crate) fn write_record(
+    data: &mut [u8],
+    offset: usize,
+    sequence: u64,
+    timestamp: u64,
+    direction: TapDirection,
+    frame: &[u8],
+) {
+    let size = record_size(frame.len());
+    let record = &mut data[offset..offset + size];
+    record[8..12].copy_from_slice(&(frame.len() as u32).to_le_bytes());
+    record[12] = match direction {
+        TapDirection::Inbound => 0,
+        TapDirection::Outbound => 1,
+    };
+    record[13..16].fill(0);
+    record[16..24].copy_from_slice(&sequence.to_le_bytes());
+    record[24..32].copy_from_slice(&timestamp.to_le_bytes());
+    record[RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + frame.len()].copy_from_slice(frame);
+    record[RECORD_HEADER_SIZE + frame.len()..].fill(0);
+    let crc = checksum::crc32c(&record[8..RECORD_HEADER_SIZE + frame.len()]);
+    record[4..8].copy_from_slice(&crc.to_le_bytes());
+    let word = data[offset..offset + 4].as_mut_ptr().cast::<u32>();
+    assert!(word.is_aligned());
+    // SAFETY: the word is in bounds, aligned, and borrowed mutably.
+    unsafe { AtomicU32::from_ptr(word) }.store(size as u32, Ordering::Release);
+}
+
+/// Reads the record at `offset`, which must be 8-aligned.
+pub(crate) fn read_record(data: &[u8], offset: usize) -> Slot<'_> {
+    if offset + RECORD_HEADER_SIZE > data.len() {
+        return Slot::End;
+    }
+    let word = data[offset..offset + 4].as_ptr().cast::<AtomicU32>();
+    assert!(word.is_aligned());
+    // SAFETY: the word is in bounds and aligned. A writer in another
+    // process may be storing it, so it is read atomically.
+    let size = unsafe { &*word }.load(Ordering::Acquire) as usize;
+    if size == 0 {
+        return Slot::End;
+    }
+    let len = u32_at(data, offset + 8) as usize;
+    if size != record_size(len) || offset + size > data.len() {
+        return Slot::Invalid;
+    }
+    let record = &data[offset..offset + size];
+    let crc = checksum::crc32c(&record[8..RECORD_HEADER_SIZE + len]);
+    let direction = match record[12] {
+        0 => TapDirection::Inbound,
+        1 => TapDirection::Outbound,
+        _ => return Slot::Invalid,
+    };
+    if crc != u32_at(record, 4) {
+        return Slot::Invalid;
+    }
+    Slot::Record(RawRecord {
+        sequence: u64::from_le_bytes(record[16..24].try_into().unwrap()),
+        timestamp: u64::from_le_bytes(record[24..32].try_into().unwrap()),
+        direction,
+        frame: &record[RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + len],
+        size,
+    })
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_round_trip() {
+        let mut data = vec![0u8; 256];
+        write_record(&mut data, 64, 7, 1_000, TapDirection::Outbound, b"hello");
+        let Slot::Record(record) = read_record(&data, 64) else {
+            panic!("record not found");
+        };
+        assert_eq!(record.sequence, 7);
+        assert_eq!(record.timestamp, 1_000);
+        assert_eq!(record.direction, TapDirection::Outbound);
+        assert_eq!(record.frame, b"hello");
+        assert_eq!(record.size, 40);
+        assert!(matches!(read_record(&data, 64 + record.size), Slot::End));
+    }
+
+    #[test]
+    fn test_corrupt_record_is_invalid() {
+        let mut data = vec![0u8; 256];
+        write_record(&mut data, 0, 1, 1, TapDirection::Inbound, b"frame");
+        data[RECORD_HEADER_SIZE] ^= 1;
+        assert!(matches!(read_record(&data, 0), Slot::Invalid));
+    }
+
+    #[test]
+    fn test_header_check() {
+        let path = Path::new("test.journal");
+        let mut data = vec![0u8; HEADER_SIZE];
+        assert!(!check_header(&data, path, 5).unwrap());
+        write_header(&mut data, 5);
+        assert!(check_header(&data, path, 5).unwrap());
+        assert!(matches!(
+            check_header(&data, path, 6),
+            Err(JournalError::Corrupt { offset: 0, .. })
+        ));
+    }
+}
@@ -0,0 +1,410 @@
+//! Appending frames to a journal.
+
+use crate::error::JournalError;
+use crate::segment::{self, HEADER_SIZE, RECORD_HEADER_SIZE, Slot};
+use ironsbe_core::TapDirection;
+use ironsbe_core::types::Timestamp;
+use memmap2::{MmapMut, MmapOptions};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Configuration of a journal writer.
+#[derive(Debug, Clone)]
+pub struct JournalConfig {
+    /// Size of each segment file in bytes. Files are preallocated to this
+    /// size, and a frame must fit in an empty one.
+    pub segment_size: usize,
+    /// Sequence number of the first record of a new journal.
+    pub first_sequence: u64,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            segment_size: 64 * 1024 * 1024,
+            first_sequence: 1,
+        }
+    }
+}
+
+/// Writer appending frames to a journal directory.
+///
+/// Records are numbered consecutively and stamped with a timestamp that
+/// never goes backwards. When the current segment is full, the writer
+/// moves on to a new one named after the next sequence number. Only one
+/// writer may have a directory open at a time, which an exclusive lock on
+/// a file in the directory enforces across processes.
+pub struct Journal {
+    dir: PathBuf,
+    config: JournalConfig,
+    /// Held for the writer's lifetime; the lock is released on drop.
+    _lock: File,
+    mmap: MmapMut,
+    offset: usize,
+    next_sequence: u64,
+    last_timestamp: u64,
+}
+
+impl Journal {
+    /// Opens the journal in `dir`, creating the directory if needed, and
+    /// positions the writer after the last complete record.
+    ///
+    /// A record the previous writer left incomplete is erased.
+    ///
+    /// # Errors
+    /// Returns [`JournalError::Locked`] if another writer has the
+    /// directory open, an error if the directory or a segment cannot be
+    /// created or mapped, or [`JournalError::Corrupt`] if the last segment
+    /// has an invalid header.
+    pub fn open(dir: impl AsRef<Path>, config: JournalConfig) -> Result<Self, JournalError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let lock = lock(&dir)?;
+
+        let Some((first, path)) = segment::list(&dir)?.pop() else {
+            let first = config.first_sequence;
+            let mmap = create_segment(&dir, first, config.segment_size)?;
+            return Ok(Self {
+                dir,
+                config,
+                _lock: lock,
+                mmap,
+                offset: HEADER_SIZE,
+                next_sequence: first,
+                last_timestamp: 0,
+            });
+        };
+
+        let mut mmap = map_segment(&path, HEADER_SIZE)?;
+        if !segment::check_header(&mmap, &path, first)? {
+            segment::write_header(&mut mmap, first);
+        }
+        let mut journal = Self {
+            dir,
+            config,
+            _lock: lock,
+            mmap,
+            offset: HEADER_SIZE,
+            next_sequence: first,
+            last_timestamp: 0,
+        };
+        journal.recover();
+        Ok(journal)
+    }
+
+    /// Moves past the complete records of the current segment and erases
+    /// whatever an interrupted write left after them.
+    fn recover(&mut self) {
+        while let Slot::Record(record) = segment::read_record(&self.mmap, self.offset) {
+            if record.sequence != self.next_sequence {
+                break;
+            }
+            self.offset += record.size;
+            self.next_sequence += 1;
+            self.last_timestamp = record.timestamp;
+        }
+        // Past the last byte written the segment is still zero, so only
+        // the bytes up to it need erasing; zeroing the whole tail would
+        // dirty every page of the file on each open.
+        let tail = &mut self.mmap[self.offset..];
+        if let Some(last) = tail.iter().rposition(|&b| b != 0) {
+            tail[..=last].fill(0);
+        }
+    }
+
+    /// Appends a frame stamped with the current time.
+    ///
+    /// # Returns
+    /// The sequence number of the record.
+    ///
+    /// # Errors
+    /// Returns [`JournalError::FrameTooLarge`] if the frame does not fit
+    /// in a segment, or an error if a new segment cannot be created.
+    pub fn append(&mut self, direction: TapDirection, frame: &[u8]) -> Result<u64, JournalError> {
+        self.append_at(direction, Timestamp::now(), frame)
+    }
+
+    /// Appends a frame stamped with `timestamp`, such as its receive
+    /// timestamp.
+    ///
+    /// A timestamp earlier than the previous record's is raised to it, so
+    /// records stay ordered in time.
+    ///
+    /// # Returns
+    /// The sequence number of the record.
+    ///
+    /// # Errors
+    /// Returns [`JournalError::FrameTooLarge`] if the frame does not fit
+    /// in a segment, or an error if a new segment cannot be created.
+    pub fn append_at(
+        &mut self,
+        direction: TapDirection,
+        timestamp: Timestamp,
+        frame: &[u8],
+    ) -> Result<u64, JournalError> {
+        let size = segment::record_size(frame.len());
+        if self.offset + size > self.mmap.len() {
+            let max = self.config.segment_size.saturating_sub(HEADER_SIZE);
+            if size > max {
+                return Err(JournalError::FrameTooLarge {
+                    len: frame.len(),
+                    max: max.saturating_sub(RECORD_HEADER_SIZE) & !7,
+                });
+            }
+            self.rotate()?;
+        }
+
+        let sequence = self.next_sequence;
+        let timestamp = timestamp.as_nanos().max(self.last_timestamp);
+        segment::write_record(
+            &mut self.mmap,
+            self.offset,
+            sequence,
+            timestamp,
+            direction,
+            frame,
+        );
+        self.offset += size;
+        self.next_sequence += 1;
+        self.last_timestamp = timestamp;
+        Ok(sequence)
+    }
+
+    /// Starts a new segment at the next sequence number.
+    fn rotate(&mut self) -> Result<(), JournalError> {
+        self.mmap.flush_async()?;
+        self.mmap = create_segment(&self.dir, self.next_sequence, self.config.segment_size)?;
+        self.offset = HEADER_SIZE;
+        Ok(())
+    }
+
+    /// Writes the current segment to disk, returning once it is durable.
+    ///
+    /// Records are visible to readers as soon as they are appended; this
+    /// only matters if the machine, not just the process, may go down.
+    ///
+    /// # Errors
+    /// Returns an error if the segment cannot be written.
+    pub fn flush(&self) -> Result<(), JournalError> {
+        Ok(self.mmap.flush()?)
+    }
+
+    /// Returns the sequence number the next record will get.
+    #[must_use]
+    pub fn next_sequence(&self) -> u64 {
+        self.next_sequence
+    }
+
+    /// Returns the journal directory.
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+/// Takes the writer lock of the journal in `dir`.
+fn lock(dir: &Path) -> Result<File, JournalError> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(dir.join(segment::LOCK_FILE))?;
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(TryLockError::WouldBlock) => Err(JournalError::Locked {
+            dir: dir.to_path_buf(),
+        }),
+        Err(TryLockError::Error(err)) => Err(err.into()),
+    }
+}
+
+/// Creates the segment starting at `first`, replacing any file left at
+/// its path, whose records would otherwise be taken for new ones.
+fn create_segment(dir: &Path, first: u64, size: usize) -> Result<MmapMut, JournalError> {
+    let path = segment::path(dir, first);
+    // Unlinked rather than truncated, so a reader mapping the old file
+    // keeps valid pages.
+    match fs::remove_file(&path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    file.set_len(size.max(HEADER_SIZE) as u64)?;
+    // SAFETY: the file was just created and the caller holds the writer
+    // lock, so no other writer maps or resizes it; readers only map it
+    // read-only, and the journal never truncates a segment.
+    let mut mmap = unsafe { MmapOptions::new().map_mut(&file)? };
+    segment::write_header(&mut mmap, first);
+    Ok(mmap)
+}
+
+/// Maps the segment at `path` for writing.
+fn map_segment(path: &Path, min_len: usize) -> Result<MmapMut, JournalError> {
+    let file = OpenOptions::new().read(true).write(true).open(path)?;
+    if (file.metadata()?.len() as usize) < min_len {
+        return Err(JournalError::Corrupt {
+            segment: path.to_path_buf(),
+            offset: 0,
+        });
+    }
+    // SAFETY: as in `create_segment`, the writer lock rules out another
+    // writer mapping or truncating the file while it is mapped.
+    Ok(unsafe { MmapOptions::new().map_mut(&file)? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn small() -> JournalConfig {
+        JournalConfig {
+            segment_size: 256,
+            ..JournalConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_append_numbers_records() {
+        let dir = tempdir().unwrap();
+        let mut journal = Journal::open(dir.path(), JournalConfig::default()).unwrap();
+        assert_eq!(journal.append(TapDirection::Inbound, b"a").unwrap(), 1);
+        assert_eq!(journal.append(TapDirection::Outbound, b"b").unwrap(), 2);
+        assert_eq!(journal.next_sequence(), 3);
+    }
+
+    #[test]
+    fn test_rotates_full_segments() {
+        let dir = tempdir().unwrap();
+        let mut journal = Journal::open(dir.path(), small()).unwrap();
+        // 64-byte header, then three 64-byte records per segment.
+        for _ in 0..7 {
+            journal.append(TapDirection::Inbound, &[0; 32]).unwrap();
+        }
+        let segments = segment::list(dir.path()).unwrap();
+        let firsts: Vec<_> = segments.iter().map(|(first, _)| *first).collect();
+        assert_eq!(firsts, [1, 4, 7]);
+    }
+
+    #[test]
+    fn test_rejects_frame_larger_than_segment() {
+        let dir = tempdir().unwrap();
+        let mut journal = Journal::open(dir.path(), small()).unwrap();
+        assert!(matches!(
+            journal.append(TapDirection::Inbound, &[0; 200]),
+            Err(JournalError::FrameTooLarge { len: 200, max: 160 })
+        ));
+        assert!(journal.append(TapDirection::Inbound, &[0; 160]).is_ok());
+    }
+
+    #[test]
+    fn test_reopen_continues_and_erases_torn_record() {
+        let dir = tempdir().unwrap();
+        {
+            let mut journal = Journal::open(dir.path(), small()).unwrap();
+            journal.append(TapDirection::Inbound, b"one").unwrap();
+            journal.append(TapDirection::Inbound, b"two").unwrap();
+        }
+
+        // Corrupt the second record as a crash mid-write would.
+        let path = segment::path(dir.path(), 1);
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[HEADER_SIZE + 40 + RECORD_HEADER_SIZE] ^= 0xFF;
+        fs::write(&path, bytes).unwrap();
+
+        let mut journal = Journal::open(dir.path(), small()).unwrap();
+        assert_eq!(journal.next_sequence(), 2);
+        assert_eq!(journal.append(TapDirection::Inbound, b"again").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_second_writer_is_locked_out() {
+        let dir = tempdir().unwrap();
+        let journal = Journal::open(dir.path(), small()).unwrap();
+        assert!(matches!(
+            Journal::open(dir.path(), small()),
+            Err(JournalError::Locked { .. })
+        ));
+        drop(journal);
+        assert!(Journal::open(dir.path(), small()).is_ok());
+    }
+
+    #[test]
+    fn test_new_segment_replaces_stale_file() {
+        // A segment from another journal, holding records 4 to 6.
+        let stale = tempdir().unwrap();
+        {
+            let config = JournalConfig {
+                first_sequence: 4,
+                ..small()
+            };
+            let mut journal = Journal::open(stale.path(), config).unwrap();
+            for _ in 0..3 {
+                journal.append(TapDirection::Inbound, b"stale").unwrap();
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        let mut journal = Journal::open(dir.path(), small()).unwrap();
+        for _ in 0..3 {
+            journal.append(TapDirection::Inbound, &[0; 32]).unwrap();
+        }
+        fs::copy(segment::path(stale.path(), 4), segment::path(dir.path(), 4)).unwrap();
+        // Rotates onto the stale file.
+        assert_eq!(journal.append(TapDirection::Inbound, b"fresh").unwrap(), 4);
+        drop(journal);
+
+        let journal = Journal::open(dir.path(), small()).unwrap();
+        assert_eq!(journal.next_sequence(), 5);
+        assert!(matches!(
+            segment::read_record(&journal.mmap, HEADER_SIZE + 40),
+            Slot::End
+        ));
+    }
+
+    #[test]
+    fn test_recover_erases_only_written_tail() {
+        let dir = tempdir().unwrap();
+        {
+            let mut journal = Journal::open(dir.path(), small()).unwrap();
+            journal.append(TapDirection::Inbound, b"one").unwrap();
+        }
+        // Leave a torn record with no size, as a crash before its last
+        // store would.
+        let path = segment::path(dir.path(), 1);
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[HEADER_SIZE + 40 + 8..HEADER_SIZE + 40 + 100].fill(0xAB);
+        fs::write(&path, bytes).unwrap();
+
+        let mut journal = Journal::open(dir.path(), small()).unwrap();
+        assert_eq!(journal.next_sequence(), 2);
+        assert!(journal.mmap[HEADER_SIZE + 40..].iter().all(|&b| b == 0));
+        journal.append(TapDirection::Inbound, b"two").unwrap();
+        assert!(matches!(
+            segment::read_record(&journal.mmap, HEADER_SIZE + 80),
+            Slot::End
+        ));
+    }
+
+    #[test]
+    fn test_timestamps_never_go_backwards() {
+        let dir = tempdir().unwrap();
+        let mut journal = Journal::open(dir.path(), JournalConfig::default()).unwrap();
+        journal
+            .append_at(TapDirection::Inbound, Timestamp::new(100), b"a")
+            .unwrap();
+        journal
+            .append_at(TapDirection::Inbound, Timestamp::new(50), b"b")
+            .unwrap();
+        let Slot::Record(record) = segment::read_record(&journal.mmap, HEADER_SIZE + 40) else {
+            panic!("record not found");
+        };
+        assert_eq!(record.timestamp, 100);
+    }
+}
@@ -22,37 +22,108 @@ pub enum Side {
     Ask,
 }
 
+/// Default number of price levels kept in a [`BookSide`]'s near array.
+pub const DEFAULT_NEAR_DEPTH: usize = 32;
+
 /// One side of the order book.
+///
+/// The best levels live in a small sorted array so that updates near the
+/// touch are a binary search and a short memmove. Deeper levels spill into
+/// a `BTreeMap`, which keeps depth unbounded. Levels migrate between the
+/// two as the touch moves: the array is always filled first, and every
+/// level in it is better than every level in the tail.
 #[derive(Debug)]
 pub struct BookSide {
-    levels: BTreeMap<i64, PriceLevel>,
+    /// Best levels, sorted by ascending price.
+    near: Vec<PriceLevel>,
+    /// Levels worse than every level in `near`.
+    far: BTreeMap<i64, PriceLevel>,
+    near_depth: usize,
     is_bid: bool,
 }
 
 impl BookSide {
-    /// Creates a new book side.
+    /// Creates a new book side keeping [`DEFAULT_NEAR_DEPTH`] levels in
+    /// the near array.
     #[must_use]
     pub fn new(is_bid: bool) -> Self {
+        Self::with_near_depth(is_bid, DEFAULT_NEAR_DEPTH)
+    }
+
+    /// Creates a new book side keeping `near_depth` levels (at least one)
+    /// in the near array.
+    #[must_use]
+    pub fn with_near_depth(is_bid: bool, near_depth: usize) -> Self {
+        let near_depth = near_depth.max(1);
         Self {
-            levels: BTreeMap::new(),
+            near: Vec::with_capacity(near_depth + 1),
+            far: BTreeMap::new(),
+            near_depth,
             is_bid,
         }
     }
 
+    /// Returns the number of levels kept in the near array.
+    #[must_use]
+    pub fn near_depth(&self) -> usize {
+        self.near_depth
+    }
+
     /// Applies an update to the book side.
     #[inline]
     pub fn update(&mut self, price: i64, quantity: u64, order_count: u32) {
-        if quantity == 0 {
-            self.levels.remove(&price);
+        let level = PriceLevel {
+            price,
+            quantity,
+            order_count,
+        };
+        match self.near.binary_search_by_key(&price, |l| l.price) {
+            Ok(pos) if quantity == 0 => {
+                self.near.remove(pos);
+                self.promote();
+            }
+            Ok(pos) => self.near[pos] = level,
+            Err(_) if quantity == 0 => {
+                self.far.remove(&price);
+            }
+            Err(pos) => {
+                // Past the worst near level: belongs in the tail once the
+                // array is full.
+                let beyond_near = if self.is_bid {
+                    pos == 0
+                } else {
+                    pos == self.near.len()
+                };
+                if beyond_near && self.near.len() == self.near_depth {
+                    self.far.insert(price, level);
+                } else {
+                    self.near.insert(pos, level);
+                    if self.near.len() > self.near_depth {
+                        self.demote();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moves the worst near level into the tail.
+    fn demote(&mut self) {
+        let level = if self.is_bid {
+            self.near.remove(0)
         } else {
-            self.levels.insert(
-                price,
-                PriceLevel {
-                    price,
-                    quantity,
-                    order_count,
-                },
-            );
+            self.near.pop().expect("near array overflowed")
+        };
+        self.far.insert(level.price, level);
+    }
+
+    /// Refills the near array with the best tail level, if any.
+    fn promote(&mut self) {
+        if self.is_bid {
+            if let Some((_, level)) = self.far.pop_last() {
+                self.near.insert(0, level);
+            }
+        } else if let Some((_, level)) = self.far.pop_first() {
+            self.near.push(level);
         }
     }
 
@@ -61,9 +132,9 @@ impl BookSide {
     #[must_use]
     pub fn top(&self) -> Option<&PriceLevel> {
         if self.is_bid {
-            self.levels.values().next_back()
+            self.near.last()
         } else {
-            self.levels.values().next()
+            self.near.first()
         }
     }
 
@@ -71,38 +142,58 @@ impl BookSide {
     #[must_use]
     pub fn best_n(&self, n: usize) -> Vec<&PriceLevel> {
         if self.is_bid {
-            self.levels.values().rev().take(n).collect()
+            self.near
+                .iter()
+                .rev()
+                .chain(self.far.values().rev())
+                .take(n)
+                .collect()
         } else {
-            self.levels.values().take(n).collect()
+            self.near.iter().chain(self.far.values()).take(n).collect()
         }
     }
 
     /// Returns the level at a specific price.
     #[must_use]
     pub fn get(&self, price: i64) -> Option<&PriceLevel> {
-        self.levels.get(&price)
+        match self.near.binary_search_by_key(&price, |l| l.price) {
+            Ok(pos) => Some(&self.near[pos]),
+            Err(_) => self.far.get(&price),
+        }
     }
 
     /// Clears all levels.
     pub fn clear(&mut self) {
-        self.levels.clear();
+        self.near.clear();
+        self.far.clear();
     }
 
     /// Returns the number of price levels.
     #[must_use]
     pub fn len(&self) -> usize {
-        self.levels.len()
+        self.near.len() + self.far.len()
     }
 
     /// Returns true if there are no levels.
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        self.levels.is_empty()
+        self.near.is_empty()
     }
 
     /// Iterates over all levels in price order.
     pub fn iter(&self) -> impl Iterator<Item = &PriceLevel> {
-        self.levels.values()
+        // The tail sits below the near array on the bid side and above it
+        // on the ask side.
+        let (below, above) = if self.is_bid {
+            (Some(self.far.values()), None)
+        } else {
+            (None, Some(self.far.values()))
+        };
+        below
+            .into_iter()
+            .flatten()
+            .chain(self.near.iter())
+            .chain(above.into_iter().flatten())
     }
 }
 
@@ -431,4 +522,88 @@ mod tests {
         assert_eq!(side.top().unwrap().quantity, 75);
         assert_eq!(side.top().unwrap().order_count, 3);
     }
+
+    /// Reference model: a plain map applying the same updates.
+    fn reference_update(map: &mut BTreeMap<i64, PriceLevel>, price: i64, quantity: u64) {
+        if quantity == 0 {
+            map.remove(&price);
+        } else {
+            map.insert(
+                price,
+                PriceLevel {
+                    price,
+                    quantity,
+                    order_count: 1,
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_book_side_migrates_between_near_and_tail() {
+        let mut side = BookSide::with_near_depth(false, 2);
+        for price in [103, 101, 104, 102] {
+            side.update(price, 10, 1);
+        }
+        assert_eq!(side.len(), 4);
+        assert_eq!(side.top().unwrap().price, 101);
+        assert_eq!(side.get(104).unwrap().price, 104);
+
+        // Removing the touch promotes the best tail level.
+        side.update(101, 0, 0);
+        side.update(102, 0, 0);
+        assert_eq!(side.top().unwrap().price, 103);
+        assert_eq!(
+            side.best_n(5).iter().map(|l| l.price).collect::<Vec<_>>(),
+            vec![103, 104]
+        );
+
+        // A new better level demotes the worst near level.
+        side.update(100, 5, 1);
+        assert_eq!(
+            side.iter().map(|l| l.price).collect::<Vec<_>>(),
+            vec![100, 103, 104]
+        );
+    }
+
+    #[test]
+    fn test_book_side_matches_reference_model() {
+        for is_bid in [true, false] {
+            let mut side = BookSide::with_near_depth(is_bid, 4);
+            let mut reference = BTreeMap::new();
+            let mut state = 0x2545_f491_4f6c_dd1du64;
+            for _ in 0..5_000 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let price = 1_000 + (state % 40) as i64;
+                let quantity = (state >> 32) % 4;
+                side.update(price, quantity, 1);
+                reference_update(&mut reference, price, quantity);
+
+                assert_eq!(side.len(), reference.len());
+                assert!(side.iter().eq(reference.values()));
+                let best = if is_bid {
+                    reference.values().next_back()
+                } else {
+                    reference.values().next()
+                };
+                assert_eq!(side.top(), best);
+            }
+        }
+    }
+
+    #[test]
+    fn test_book_side_bid_best_n_spans_tail() {
+        let mut side = BookSide::with_near_depth(true, 2);
+        for price in 100..106 {
+            side.update(price, 1, 1);
+        }
+        assert_eq!(side.near_depth(), 2);
+        assert_eq!(
+            side.best_n(4).iter().map(|l| l.price).collect::<Vec<_>>(),
+            vec![105, 104, 103, 102]
+        );
+        assert_eq!(side.get(100).unwrap().price, 100);
+    }
 }
@@ -1,8 +1,10 @@
 //! Market data handler with recovery support.
 
 use crate::book::{BookSnapshot, BookUpdate, OrderBook};
+use crate::status::{SecurityStatus, TradingStatus, TradingStatusTracker};
 use ironsbe_channel::spsc::SpscSender;
 use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// State of an instrument's market data.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -28,6 +30,23 @@ pub enum MarketDataEvent {
     StateChanged(u64, InstrumentState),
     /// Gap detected in sequence.
     GapDetected(u64, u64, u64),
+    /// Trading status changed.
+    TradingStatusChanged(u64, TradingStatus),
+    /// Every update of one inbound packet/transaction has been applied.
+    ///
+    /// Carries the instruments whose books changed, in the order they were
+    /// first touched. Consumers can defer reacting until this arrives to
+    /// only ever observe consistent books.
+    EventComplete(Vec<u64>),
+    /// Follows [`EventComplete`](Self::EventComplete) for packets passed to
+    /// [`MarketDataHandler::on_timestamped_packet`].
+    EventLatency {
+        /// When the packet was received, in nanoseconds since the Unix
+        /// epoch, e.g. a kernel or NIC receive timestamp.
+        received_at: u64,
+        /// Time from `received_at` until every update was applied.
+        latency: Duration,
+    },
 }
 
 /// Market data handler following CME MDP 3.0 patterns.
@@ -37,6 +56,9 @@ pub struct MarketDataHandler {
     expected_seq: HashMap<u64, u64>,
     update_tx: SpscSender<MarketDataEvent>,
     pending_incrementals: HashMap<u64, Vec<BookUpdate>>,
+    trading_status: TradingStatusTracker,
+    /// Instruments whose books changed since the last [`end_event`](Self::end_event).
+    event_instruments: Vec<u64>,
 }
 
 impl MarketDataHandler {
@@ -49,6 +71,8 @@ impl MarketDataHandler {
             expected_seq: HashMap::new(),
             update_tx,
             pending_incrementals: HashMap::new(),
+            trading_status: TradingStatusTracker::new(),
+            event_instruments: Vec::new(),
         }
     }
 
@@ -67,6 +91,8 @@ impl MarketDataHandler {
         self.states.remove(&instrument_id);
         self.expected_seq.remove(&instrument_id);
         self.pending_incrementals.remove(&instrument_id);
+        self.trading_status.remove(instrument_id);
+        self.event_instruments.retain(|&id| id != instrument_id);
     }
 
     /// Processes an incremental update from the feed.
@@ -149,6 +175,87 @@ impl MarketDataHandler {
         Ok(())
     }
 
+    /// Processes every incremental update of one inbound packet, then
+    /// signals the end of the event with [`end_event`](Self::end_event).
+    ///
+    /// # Errors
+    /// Returns error if processing fails. The event is then left open and
+    /// the instruments touched so far are reported by the next
+    /// [`end_event`](Self::end_event).
+    pub fn on_packet<I>(&mut self, updates: I) -> Result<(), HandlerError>
+    where
+        I: IntoIterator<Item = BookUpdate>,
+    {
+        for update in updates {
+            self.on_incremental(update)?;
+        }
+        self.end_event();
+        Ok(())
+    }
+
+    /// Processes every incremental update of one inbound packet received
+    /// at `received_at`, in nanoseconds since the Unix epoch, like
+    /// [`on_packet`](Self::on_packet).
+    ///
+    /// With a kernel or NIC receive timestamp this attributes latency to
+    /// the wire: [`MarketDataEvent::EventLatency`] follows the
+    /// `EventComplete`, with the time from the packet reaching the host
+    /// to its updates being applied.
+    ///
+    /// # Errors
+    /// As for [`on_packet`](Self::on_packet).
+    pub fn on_timestamped_packet<I>(
+        &mut self,
+        updates: I,
+        received_at: u64,
+    ) -> Result<(), HandlerError>
+    where
+        I: IntoIterator<Item = BookUpdate>,
+    {
+        for update in updates {
+            self.on_incremental(update)?;
+        }
+        if self.complete_event() {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            let latency = now.saturating_sub(Duration::from_nanos(received_at));
+            let _ = self.update_tx.send(MarketDataEvent::EventLatency {
+                received_at,
+                latency,
+            });
+        }
+        Ok(())
+    }
+
+    /// Marks the end of the current event (e.g. the feed's end-of-event
+    /// flag), emitting [`MarketDataEvent::EventComplete`] with the
+    /// instruments whose books changed since the previous call.
+    ///
+    /// Nothing is emitted if no book changed, e.g. when every update was
+    /// queued for recovery.
+    pub fn end_event(&mut self) {
+        self.complete_event();
+    }
+
+    /// Emits `EventComplete` if any book changed, returning true if so.
+    fn complete_event(&mut self) -> bool {
+        if self.event_instruments.is_empty() {
+            return false;
+        }
+        let instruments = std::mem::take(&mut self.event_instruments);
+        let _ = self
+            .update_tx
+            .send(MarketDataEvent::EventComplete(instruments));
+        true
+    }
+
+    fn touch(&mut self, instrument_id: u64) {
+        if !self.event_instruments.contains(&instrument_id) {
+            self.event_instruments.push(instrument_id);
+        }
+    }
+
     /// Processes a snapshot from the recovery feed.
     ///
     /// # Errors
@@ -181,11 +288,27 @@ impl MarketDataHandler {
             let _ = self
                 .update_tx
                 .send(MarketDataEvent::BookUpdated(instrument_id));
+            self.touch(instrument_id);
         }
 
         Ok(())
     }
 
+    /// Processes a security status message from the feed.
+    ///
+    /// Statuses for instruments that are not subscribed are ignored.
+    pub fn on_security_status(&mut self, status: SecurityStatus) {
+        if !self.books.contains_key(&status.instrument_id) {
+            return;
+        }
+        if let Some(transition) = self.trading_status.on_security_status(&status) {
+            let _ = self.update_tx.send(MarketDataEvent::TradingStatusChanged(
+                transition.instrument_id,
+                transition.to,
+            ));
+        }
+    }
+
     fn apply_update(&mut self, update: BookUpdate) -> Result<(), HandlerError> {
         let instrument_id = update.instrument_id;
         let seq = update.seq_num;
@@ -212,6 +335,7 @@ impl MarketDataHandler {
                     .update_tx
                     .send(MarketDataEvent::TopOfBookChanged(instrument_id));
             }
+            self.touch(instrument_id);
         }
 
         Ok(())
@@ -229,6 +353,18 @@ impl MarketDataHandler {
         self.states.get(&instrument_id).copied()
     }
 
+    /// Gets the trading status for an instrument.
+    #[must_use]
+    pub fn get_trading_status(&self, instrument_id: u64) -> Option<TradingStatus> {
+        self.trading_status.status(instrument_id)
+    }
+
+    /// Returns true if the instrument is open for continuous trading.
+    #[must_use]
+    pub fn is_quotable(&self, instrument_id: u64) -> bool {
+        self.trading_status.is_quotable(instrument_id)
+    }
+
     /// Returns all subscribed instrument IDs.
     #[must_use]
     pub fn subscribed_instruments(&self) -> Vec<u64> {
@@ -307,6 +443,14 @@ mod tests {
         let event = MarketDataEvent::GapDetected(1, 10, 15);
         let debug_str = format!("{:?}", event);
         assert!(debug_str.contains("GapDetected"));
+
+        let event = MarketDataEvent::TradingStatusChanged(1, TradingStatus::Halted);
+        let debug_str = format!("{:?}", event);
+        assert!(debug_str.contains("Halted"));
+
+        let event = MarketDataEvent::EventComplete(vec![1, 2]);
+        let debug_str = format!("{:?}", event);
+        assert!(debug_str.contains("EventComplete"));
     }
 
     #[test]
@@ -406,4 +550,137 @@ mod tests {
         assert!(instruments.contains(&200));
         assert!(instruments.contains(&300));
     }
+
+    fn update(instrument_id: u64, seq_num: u64, price: i64) -> BookUpdate {
+        BookUpdate {
+            instrument_id,
+            seq_num,
+            side: Side::Bid,
+            price,
+            quantity: 10,
+            order_count: 1,
+        }
+    }
+
+    fn activate(handler: &mut MarketDataHandler, instrument_id: u64) {
+        handler.subscribe(instrument_id);
+        handler
+            .on_snapshot(BookSnapshot {
+                instrument_id,
+                seq_num: 0,
+                bids: Vec::new(),
+                asks: Vec::new(),
+            })
+            .unwrap();
+        handler.end_event();
+    }
+
+    #[test]
+    fn test_handler_timestamped_packet_reports_latency() {
+        let (tx, mut rx) = SpscChannel::new(64);
+        let mut handler = MarketDataHandler::new(tx);
+        activate(&mut handler, 100);
+        let _: Vec<_> = rx.drain().collect();
+
+        let received_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64
+            - 2_000_000;
+        handler
+            .on_timestamped_packet([update(100, 1, 10)], received_at)
+            .unwrap();
+        let events: Vec<_> = rx.drain().collect();
+        let n = events.len();
+        assert!(matches!(&events[n - 2], MarketDataEvent::EventComplete(ids) if ids == &vec![100]));
+        match events[n - 1] {
+            MarketDataEvent::EventLatency {
+                received_at: at,
+                latency,
+            } => {
+                assert_eq!(at, received_at);
+                assert!(latency >= Duration::from_millis(2));
+            }
+            ref other => panic!("unexpected event {other:?}"),
+        }
+
+        // Packets that change no book report no latency either.
+        handler
+            .on_timestamped_packet([update(100, 1, 10)], received_at)
+            .unwrap();
+        assert_eq!(rx.drain().count(), 0);
+    }
+
+    #[test]
+    fn test_handler_on_packet_emits_event_complete_last() {
+        let (tx, mut rx) = SpscChannel::new(64);
+        let mut handler = MarketDataHandler::new(tx);
+        activate(&mut handler, 100);
+        activate(&mut handler, 200);
+        let _: Vec<_> = rx.drain().collect();
+
+        handler
+            .on_packet([update(200, 1, 50), update(100, 1, 10), update(200, 2, 51)])
+            .unwrap();
+
+        let events: Vec<_> = rx.drain().collect();
+        assert!(matches!(
+            events.last(),
+            Some(MarketDataEvent::EventComplete(ids)) if ids == &vec![200, 100]
+        ));
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| matches!(e, MarketDataEvent::EventComplete(_)))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_handler_end_event_without_changes_is_silent() {
+        let (tx, mut rx) = SpscChannel::new(16);
+        let mut handler = MarketDataHandler::new(tx);
+        handler.subscribe(100);
+
+        // Still initializing: the update is queued, no book changes.
+        handler.on_packet([update(100, 1, 10)]).unwrap();
+        assert!(rx.recv().is_none());
+    }
+
+    #[test]
+    fn test_handler_on_security_status() {
+        let (tx, mut rx) = SpscChannel::new(16);
+        let mut handler = MarketDataHandler::new(tx);
+
+        handler.subscribe(100);
+        assert!(!handler.is_quotable(100));
+
+        handler.on_security_status(SecurityStatus {
+            instrument_id: 100,
+            seq_num: 1,
+            status: TradingStatus::Open,
+        });
+        assert_eq!(handler.get_trading_status(100), Some(TradingStatus::Open));
+        assert!(handler.is_quotable(100));
+        assert!(matches!(
+            rx.recv(),
+            Some(MarketDataEvent::TradingStatusChanged(
+                100,
+                TradingStatus::Open
+            ))
+        ));
+
+        // Unsubscribed instruments are ignored
+        handler.on_security_status(SecurityStatus {
+            instrument_id: 200,
+            seq_num: 1,
+            status: TradingStatus::Open,
+        });
+        assert_eq!(handler.get_trading_status(200), None);
+        assert!(rx.recv().is_none());
+
+        handler.unsubscribe(100);
+        assert_eq!(handler.get_trading_status(100), None);
+    }
 }
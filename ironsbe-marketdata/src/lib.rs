@@ -6,13 +6,26 @@
 //! - Order book management with bid/ask sides
 //! - Snapshot and incremental update handling
 //! - Gap detection and recovery
+//! - End-of-event signaling once a packet has been fully applied, with
+//!   wire-to-book latency for timestamped packets
 //! - A/B feed arbitration
+//! - Per-instrument trading status tracking
+//! - Symbol-partitioned book building across pinned worker threads
+//! - Adaptive per-instrument snapshot scheduling for publishers
 
 pub mod arbitration;
 pub mod book;
 pub mod handler;
 pub mod instruments;
 pub mod recovery;
+pub mod sharded;
+pub mod snapshot_schedule;
+pub mod status;
 
-pub use book::{BookSide, BookSnapshot, BookUpdate, OrderBook, PriceLevel, Side};
+pub use book::{
+    BookSide, BookSnapshot, BookUpdate, DEFAULT_NEAR_DEPTH, OrderBook, PriceLevel, Side,
+};
 pub use handler::{InstrumentState, MarketDataEvent, MarketDataHandler};
+pub use sharded::{ShardConfig, ShardedMarketDataHandler};
+pub use snapshot_schedule::{SnapshotScheduleConfig, SnapshotScheduler};
+pub use status::{SecurityStatus, StatusTransition, TradingStatus, TradingStatusTracker};
@@ -0,0 +1,580 @@
+//! Symbol-partitioned parallel book building.
+//!
+//! A single [`MarketDataHandler`] builds every book on one thread, which
+//! caps a wide universe at what one core can apply. A
+//! [`ShardedMarketDataHandler`] partitions instruments across worker
+//! threads instead. The receive thread only demultiplexes: each update is
+//! forwarded over an SPSC queue to the worker that owns its instrument, and
+//! that worker applies it to its own `MarketDataHandler`. An instrument
+//! always maps to the same worker and each queue is FIFO, so updates of one
+//! instrument are applied in arrival order; updates of instruments on
+//! different workers are applied concurrently.
+//!
+//! Events are delivered on the worker threads, to a sink that also gets
+//! read access to the shard's handler and therefore its books.
+
+use crate::book::{BookSnapshot, BookUpdate};
+use crate::handler::{HandlerError, MarketDataEvent, MarketDataHandler};
+use crate::status::SecurityStatus;
+use ironsbe_channel::spsc::{self, SpscReceiver, SpscSender};
+use ironsbe_core::runtime::CoreConfig;
+use std::collections::HashMap;
+use std::thread::{self, JoinHandle};
+
+/// Spins on an empty queue before a worker yields its core.
+const SPIN_COUNT: usize = 1_024;
+
+/// Configuration for a [`ShardedMarketDataHandler`].
+#[derive(Debug, Clone)]
+pub struct ShardConfig {
+    /// Number of worker threads.
+    pub shards: usize,
+    /// Capacity of each worker's inbound queue, in commands.
+    pub queue_capacity: usize,
+    /// Capacity of each worker's event queue, drained after every command.
+    pub event_capacity: usize,
+    /// Core ids to pin the workers to, in shard order. Workers beyond the
+    /// end of the list are not pinned.
+    pub cores: Vec<usize>,
+}
+
+impl Default for ShardConfig {
+    fn default() -> Self {
+        Self {
+            shards: 4,
+            queue_capacity: 64 * 1024,
+            event_capacity: 64 * 1024,
+            cores: Vec::new(),
+        }
+    }
+}
+
+impl ShardConfig {
+    /// Creates a config with `shards` unpinned workers.
+    #[must_use]
+    pub fn new(shards: usize) -> Self {
+        Self {
+            shards,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the capacity of each worker's inbound queue.
+    #[must_use]
+    pub fn queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity;
+        self
+    }
+
+    /// Sets the capacity of each worker's event queue.
+    #[must_use]
+    pub fn event_capacity(mut self, capacity: usize) -> Self {
+        self.event_capacity = capacity;
+        self
+    }
+
+    /// Pins worker `i` to the `i`-th core id of `cores`.
+    #[must_use]
+    pub fn cores(mut self, cores: impl IntoIterator<Item = usize>) -> Self {
+        self.cores = cores.into_iter().collect();
+        self
+    }
+
+    /// Pins the workers to the consumer cores of `config`, one core per
+    /// worker in shard order.
+    #[must_use]
+    pub fn core_config(self, config: &CoreConfig) -> Self {
+        self.cores(config.consumers().iter().copied())
+    }
+}
+
+/// Work forwarded to a shard.
+enum ShardCommand {
+    Subscribe(u64),
+    Unsubscribe(u64),
+    Incremental(BookUpdate),
+    Snapshot(BookSnapshot),
+    SecurityStatus(SecurityStatus),
+    MarkStale(u64),
+    EndEvent,
+    Shutdown,
+}
+
+/// Market data handler that builds books on several pinned worker threads.
+///
+/// Instruments are assigned to shards by hashing their id, or explicitly
+/// with [`subscribe_on`](Self::subscribe_on). The methods mirror
+/// [`MarketDataHandler`] and are called from the receive thread; they only
+/// enqueue work, spinning while the target queue is full.
+///
+/// [`end_event`](Self::end_event) is forwarded to every shard that received
+/// updates in the event, so each emits its own
+/// [`MarketDataEvent::EventComplete`] covering the instruments it owns.
+///
+/// # Example
+/// ```
+/// use ironsbe_marketdata::sharded::{ShardConfig, ShardedMarketDataHandler};
+/// use ironsbe_marketdata::{BookSnapshot, MarketDataEvent, MarketDataHandler};
+///
+/// let config = ShardConfig::new(2).cores([2, 3]);
+/// let mut handler = ShardedMarketDataHandler::spawn(config, |_shard| {
+///     |handler: &MarketDataHandler, event: MarketDataEvent| {
+///         if let MarketDataEvent::BookUpdated(id) = event {
+///             let _top = handler.get_book(id).and_then(|book| book.bids.top());
+///         }
+///     }
+/// });
+/// handler.subscribe_on(42, 1).unwrap();
+/// handler
+///     .on_snapshot(BookSnapshot {
+///         instrument_id: 42,
+///         seq_num: 1,
+///         bids: Vec::new(),
+///         asks: Vec::new(),
+///     })
+///     .unwrap();
+/// let shards = handler.shutdown();
+/// assert!(shards[1].get_book(42).is_some());
+/// ```
+pub struct ShardedMarketDataHandler {
+    senders: Vec<SpscSender<ShardCommand>>,
+    workers: Vec<JoinHandle<MarketDataHandler>>,
+    /// Shard of every subscribed instrument.
+    routes: HashMap<u64, usize>,
+    /// Shards that received updates since the last `end_event`.
+    dirty: Vec<bool>,
+}
+
+impl ShardedMarketDataHandler {
+    /// Starts the workers.
+    ///
+    /// `make_sink` is called once per shard, on the calling thread, and
+    /// returns the function that receives that shard's events on its worker
+    /// thread together with the shard's handler.
+    ///
+    /// # Panics
+    /// Panics if `config.shards` is zero or a worker thread cannot be
+    /// spawned.
+    pub fn spawn<F, S>(config: ShardConfig, mut make_sink: F) -> Self
+    where
+        F: FnMut(usize) -> S,
+        S: FnMut(&MarketDataHandler, MarketDataEvent) + Send + 'static,
+    {
+        assert!(config.shards > 0, "at least one shard is required");
+        let core_ids = if config.cores.is_empty() {
+            Vec::new()
+        } else {
+            core_affinity::get_core_ids().unwrap_or_default()
+        };
+
+        let mut senders = Vec::with_capacity(config.shards);
+        let mut workers = Vec::with_capacity(config.shards);
+        for shard in 0..config.shards {
+            let (tx, rx) = spsc::channel(config.queue_capacity);
+            let core = config
+                .cores
+                .get(shard)
+                .and_then(|&id| core_ids.iter().copied().find(|core| core.id == id));
+            let sink = make_sink(shard);
+            let event_capacity = config.event_capacity;
+            let worker = thread::Builder::new()
+                .name(format!("ironsbe-md-shard-{shard}"))
+                .spawn(move || {
+                    if let Some(core) = core
+                        && !core_affinity::set_for_current(core)
+                    {
+                        tracing::warn!(shard, core = core.id, "failed to pin shard worker");
+                    }
+                    run_shard(shard, rx, event_capacity, sink)
+                })
+                .expect("failed to spawn shard worker");
+            senders.push(tx);
+            workers.push(worker);
+        }
+
+        Self {
+            senders,
+            workers,
+            routes: HashMap::new(),
+            dirty: vec![false; config.shards],
+        }
+    }
+
+    /// Returns the number of shards.
+    #[must_use]
+    pub fn shard_count(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// Returns the shard `instrument_id` is routed to.
+    #[must_use]
+    pub fn shard_of(&self, instrument_id: u64) -> usize {
+        self.routes
+            .get(&instrument_id)
+            .copied()
+            .unwrap_or_else(|| Self::hash_shard(instrument_id, self.shard_count()))
+    }
+
+    /// Returns the default shard of `instrument_id` among `shards`.
+    #[must_use]
+    pub fn hash_shard(instrument_id: u64, shards: usize) -> usize {
+        // Fibonacci hashing spreads sequential ids evenly.
+        let hash = instrument_id.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32;
+        (hash % shards as u64) as usize
+    }
+
+    /// Subscribes to an instrument on its default shard.
+    ///
+    /// # Errors
+    /// Returns an error if the shard's worker has stopped.
+    pub fn subscribe(&mut self, instrument_id: u64) -> Result<(), HandlerError> {
+        let shard = self.shard_of(instrument_id);
+        self.subscribe_on(instrument_id, shard)
+    }
+
+    /// Subscribes to an instrument on `shard`, e.g. to keep related
+    /// instruments together or to isolate a busy one.
+    ///
+    /// # Errors
+    /// Returns an error if the shard's worker has stopped.
+    ///
+    /// # Panics
+    /// Panics if `shard` is out of range.
+    pub fn subscribe_on(&mut self, instrument_id: u64, shard: usize) -> Result<(), HandlerError> {
+        assert!(shard < self.shard_count(), "shard {shard} out of range");
+        if let Some(previous) = self.routes.insert(instrument_id, shard)
+            && previous != shard
+        {
+            self.send(previous, ShardCommand::Unsubscribe(instrument_id))?;
+        }
+        self.send(shard, ShardCommand::Subscribe(instrument_id))
+    }
+
+    /// Unsubscribes from an instrument.
+    ///
+    /// # Errors
+    /// Returns an error if the shard's worker has stopped.
+    pub fn unsubscribe(&mut self, instrument_id: u64) -> Result<(), HandlerError> {
+        let shard = self.shard_of(instrument_id);
+        self.routes.remove(&instrument_id);
+        self.send(shard, ShardCommand::Unsubscribe(instrument_id))
+    }
+
+    /// Forwards an incremental update to the instrument's shard.
+    ///
+    /// # Errors
+    /// Returns an error if the shard's worker has stopped.
+    pub fn on_incremental(&mut self, update: BookUpdate) -> Result<(), HandlerError> {
+        let shard = self.shard_of(update.instrument_id);
+        self.dirty[shard] = true;
+        self.send(shard, ShardCommand::Incremental(update))
+    }
+
+    /// Forwards every incremental update of one inbound packet, then ends
+    /// the event with [`end_event`](Self::end_event).
+    ///
+    /// # Errors
+    /// Returns an error if a shard's worker has stopped.
+    pub fn on_packet<I>(&mut self, updates: I) -> Result<(), HandlerError>
+    where
+        I: IntoIterator<Item = BookUpdate>,
+    {
+        for update in updates {
+            self.on_incremental(update)?;
+        }
+        self.end_event()
+    }
+
+    /// Forwards a snapshot to the instrument's shard.
+    ///
+    /// # Errors
+    /// Returns an error if the shard's worker has stopped.
+    pub fn on_snapshot(&mut self, snapshot: BookSnapshot) -> Result<(), HandlerError> {
+        let shard = self.shard_of(snapshot.instrument_id);
+        self.dirty[shard] = true;
+        self.send(shard, ShardCommand::Snapshot(snapshot))
+    }
+
+    /// Forwards a security status message to the instrument's shard.
+    ///
+    /// # Errors
+    /// Returns an error if the shard's worker has stopped.
+    pub fn on_security_status(&mut self, status: SecurityStatus) -> Result<(), HandlerError> {
+        let shard = self.shard_of(status.instrument_id);
+        self.send(shard, ShardCommand::SecurityStatus(status))
+    }
+
+    /// Marks an instrument as stale.
+    ///
+    /// # Errors
+    /// Returns an error if the shard's worker has stopped.
+    pub fn mark_stale(&mut self, instrument_id: u64) -> Result<(), HandlerError> {
+        let shard = self.shard_of(instrument_id);
+        self.send(shard, ShardCommand::MarkStale(instrument_id))
+    }
+
+    /// Marks the end of the current event on every shard that received
+    /// updates since the previous call.
+    ///
+    /// # Errors
+    /// Returns an error if a shard's worker has stopped.
+    pub fn end_event(&mut self) -> Result<(), HandlerError> {
+        for shard in 0..self.shard_count() {
+            if std::mem::take(&mut self.dirty[shard]) {
+                self.send(shard, ShardCommand::EndEvent)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns all subscribed instrument IDs.
+    #[must_use]
+    pub fn subscribed_instruments(&self) -> Vec<u64> {
+        self.routes.keys().copied().collect()
+    }
+
+    /// Stops the workers once they have applied everything queued, and
+    /// returns their handlers in shard order.
+    ///
+    /// # Panics
+    /// Panics if a worker panicked.
+    pub fn shutdown(mut self) -> Vec<MarketDataHandler> {
+        self.stop()
+            .into_iter()
+            .map(|handler| handler.expect("shard worker panicked"))
+            .collect()
+    }
+
+    fn stop(&mut self) -> Vec<thread::Result<MarketDataHandler>> {
+        for shard in 0..self.senders.len() {
+            // A stopped worker needs no shutdown request.
+            let _ = self.send(shard, ShardCommand::Shutdown);
+        }
+        self.workers.drain(..).map(JoinHandle::join).collect()
+    }
+
+    /// Enqueues `command`, spinning while the shard's queue is full.
+    fn send(&mut self, shard: usize, mut command: ShardCommand) -> Result<(), HandlerError> {
+        let sender = &mut self.senders[shard];
+        loop {
+            match sender.send(command) {
+                Ok(()) => return Ok(()),
+                Err(_) if !sender.is_connected() => {
+                    return Err(HandlerError {
+                        message: format!("shard {shard} worker stopped"),
+                    });
+                }
+                Err(rejected) => {
+                    command = rejected;
+                    std::hint::spin_loop();
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ShardedMarketDataHandler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Worker loop: applies commands in order and hands events to `sink`.
+fn run_shard<S>(
+    shard: usize,
+    mut commands: SpscReceiver<ShardCommand>,
+    event_capacity: usize,
+    mut sink: S,
+) -> MarketDataHandler
+where
+    S: FnMut(&MarketDataHandler, MarketDataEvent),
+{
+    let (event_tx, mut events) = spsc::channel(event_capacity);
+    let mut handler = MarketDataHandler::new(event_tx);
+    loop {
+        let Some(command) = commands.recv_spin_limited(SPIN_COUNT) else {
+            if !commands.is_connected() {
+                break;
+            }
+            thread::yield_now();
+            continue;
+        };
+        let result = match command {
+            ShardCommand::Subscribe(id) => {
+                handler.subscribe(id);
+                Ok(())
+            }
+            ShardCommand::Unsubscribe(id) => {
+                handler.unsubscribe(id);
+                Ok(())
+            }
+            ShardCommand::Incremental(update) => handler.on_incremental(update),
+            ShardCommand::Snapshot(snapshot) => handler.on_snapshot(snapshot),
+            ShardCommand::SecurityStatus(status) => {
+                handler.on_security_status(status);
+                Ok(())
+            }
+            ShardCommand::MarkStale(id) => {
+                handler.mark_stale(id);
+                Ok(())
+            }
+            ShardCommand::EndEvent => {
+                handler.end_event();
+                Ok(())
+            }
+            ShardCommand::Shutdown => break,
+        };
+        if let Err(err) = result {
+            tracing::warn!(shard, %err, "shard failed to apply market data");
+        }
+        for event in events.drain() {
+            sink(&handler, event);
+        }
+    }
+    handler
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::Side;
+    use std::sync::{Arc, Mutex};
+
+    fn update(instrument_id: u64, seq_num: u64, price: i64) -> BookUpdate {
+        BookUpdate {
+            instrument_id,
+            seq_num,
+            side: Side::Bid,
+            price,
+            quantity: 10,
+            order_count: 1,
+        }
+    }
+
+    fn snapshot(instrument_id: u64) -> BookSnapshot {
+        BookSnapshot {
+            instrument_id,
+            seq_num: 0,
+            bids: Vec::new(),
+            asks: Vec::new(),
+        }
+    }
+
+    /// Records every event with its shard and the top bid at the time.
+    type Log = Arc<Mutex<Vec<(usize, MarketDataEvent, Option<i64>)>>>;
+
+    fn spawn_logged(config: ShardConfig) -> (ShardedMarketDataHandler, Log) {
+        let log = Log::default();
+        let handler = ShardedMarketDataHandler::spawn(config, |shard| {
+            let log = Arc::clone(&log);
+            move |handler: &MarketDataHandler, event: MarketDataEvent| {
+                let top = match &event {
+                    MarketDataEvent::BookUpdated(id) => handler
+                        .get_book(*id)
+                        .and_then(|book| book.bids.top())
+                        .map(|level| level.price),
+                    _ => None,
+                };
+                log.lock().unwrap().push((shard, event, top));
+            }
+        });
+        (handler, log)
+    }
+
+    #[test]
+    fn test_updates_keep_per_instrument_order() {
+        let (mut handler, log) = spawn_logged(ShardConfig::new(3).queue_capacity(8));
+        let instruments: Vec<u64> = (1..=12).collect();
+        for &id in &instruments {
+            handler.subscribe(id).unwrap();
+            handler.on_snapshot(snapshot(id)).unwrap();
+        }
+        handler.end_event().unwrap();
+
+        // Each update raises the bid, so the top bid seen after every
+        // update must be strictly increasing per instrument.
+        for seq in 1..=50 {
+            let packet: Vec<_> = instruments
+                .iter()
+                .map(|&id| update(id, seq, seq as i64 * 100 + id as i64))
+                .collect();
+            handler.on_packet(packet).unwrap();
+        }
+        let shards = handler.shutdown();
+        assert_eq!(shards.len(), 3);
+
+        let log = log.lock().unwrap();
+        let mut used = [false; 3];
+        for &id in &instruments {
+            let tops: Vec<i64> = log
+                .iter()
+                .filter_map(|(shard, event, top)| match event {
+                    MarketDataEvent::BookUpdated(event_id) if *event_id == id => {
+                        used[*shard] = true;
+                        *top
+                    }
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(tops.len(), 50, "instrument {id}");
+            assert!(tops.windows(2).all(|pair| pair[0] < pair[1]));
+
+            let shard = ShardedMarketDataHandler::hash_shard(id, 3);
+            let book = shards[shard].get_book(id).unwrap();
+            assert_eq!(book.bids.top().unwrap().price, 5_000 + id as i64);
+        }
+        assert_eq!(used, [true; 3]);
+    }
+
+    #[test]
+    fn test_end_event_is_forwarded_to_touched_shards() {
+        let (mut handler, log) = spawn_logged(ShardConfig::new(2));
+        handler.subscribe_on(1, 0).unwrap();
+        handler.subscribe_on(2, 1).unwrap();
+        handler.subscribe_on(3, 1).unwrap();
+        assert_eq!(handler.shard_of(3), 1);
+        for id in 1..=3 {
+            handler.on_snapshot(snapshot(id)).unwrap();
+        }
+        handler.end_event().unwrap();
+        handler
+            .on_packet([update(3, 1, 10), update(2, 1, 20)])
+            .unwrap();
+        handler.on_packet([update(1, 1, 30)]).unwrap();
+        handler.shutdown();
+
+        let completes: Vec<(usize, Vec<u64>)> = log
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(shard, event, _)| match event {
+                MarketDataEvent::EventComplete(ids) => Some((*shard, ids.clone())),
+                _ => None,
+            })
+            .collect();
+        let shard_0: Vec<_> = completes.iter().filter(|(s, _)| *s == 0).collect();
+        let shard_1: Vec<_> = completes.iter().filter(|(s, _)| *s == 1).collect();
+        assert_eq!(shard_0.len(), 2);
+        assert_eq!(shard_0[1].1, vec![1]);
+        assert_eq!(shard_1.len(), 2);
+        assert_eq!(shard_1[1].1, vec![3, 2]);
+    }
+
+    #[test]
+    fn test_resubscribe_moves_instrument() {
+        let (mut handler, _log) = spawn_logged(ShardConfig::new(2).cores([usize::MAX]));
+        handler.subscribe_on(7, 0).unwrap();
+        handler.subscribe_on(7, 1).unwrap();
+        assert_eq!(handler.subscribed_instruments(), vec![7]);
+        let shards = handler.shutdown();
+        assert!(shards[0].get_book(7).is_none());
+        assert!(shards[1].get_book(7).is_some());
+    }
+
+    #[test]
+    fn test_core_config_pins_consumers() {
+        let cores = CoreConfig::new().accept_core(0).consumer_cores([2, 3]);
+        let config = ShardConfig::new(2).core_config(&cores);
+        assert_eq!(config.cores, vec![2, 3]);
+    }
+}
@@ -0,0 +1,464 @@
+//! Adaptive snapshot scheduling for market data publishers.
+//!
+//! A publisher interleaves periodic per-instrument snapshots with the
+//! incremental feed so that late joiners can recover without a request.
+//! A fixed interval is wrong both ways: busy instruments pile up thousands
+//! of incrementals a joiner must replay, while quiet ones are republished
+//! unchanged. [`SnapshotScheduler`] tracks each instrument's update rate
+//! and aims for a snapshot every
+//! [`updates_per_snapshot`](SnapshotScheduleConfig::updates_per_snapshot)
+//! updates, within [`min_interval`](SnapshotScheduleConfig::min_interval)
+//! and [`max_interval`](SnapshotScheduleConfig::max_interval).
+//!
+//! Instruments that have not changed since their last snapshot are skipped
+//! unless a subscriber joined since. While subscribers are joining, every
+//! instrument with something to send drops to the minimum interval so new
+//! joiners recover quickly.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Tuning of a [`SnapshotScheduler`].
+#[derive(Debug, Clone)]
+pub struct SnapshotScheduleConfig {
+    /// Shortest time between two snapshots of an instrument.
+    pub min_interval: Duration,
+    /// Longest time an instrument that changed waits for its snapshot.
+    pub max_interval: Duration,
+    /// Number of incremental updates a joiner should at most have to replay.
+    pub updates_per_snapshot: u32,
+    /// Time constant of the decaying average behind the update rate.
+    pub rate_window: Duration,
+    /// How long after a subscriber joins snapshots stay at the minimum
+    /// interval.
+    pub join_window: Duration,
+}
+
+impl Default for SnapshotScheduleConfig {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_millis(100),
+            max_interval: Duration::from_secs(30),
+            updates_per_snapshot: 1_000,
+            rate_window: Duration::from_secs(1),
+            join_window: Duration::from_secs(5),
+        }
+    }
+}
+
+impl SnapshotScheduleConfig {
+    /// Creates the default configuration.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the shortest time between two snapshots of an instrument.
+    #[must_use]
+    pub fn min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+
+    /// Sets the longest time a changed instrument waits for its snapshot.
+    #[must_use]
+    pub fn max_interval(mut self, interval: Duration) -> Self {
+        self.max_interval = interval;
+        self
+    }
+
+    /// Sets the target number of updates between snapshots.
+    ///
+    /// Values below 1 are treated as 1.
+    #[must_use]
+    pub fn updates_per_snapshot(mut self, updates: u32) -> Self {
+        self.updates_per_snapshot = updates.max(1);
+        self
+    }
+
+    /// Sets the time constant of the update rate average.
+    #[must_use]
+    pub fn rate_window(mut self, window: Duration) -> Self {
+        self.rate_window = window;
+        self
+    }
+
+    /// Sets how long snapshots stay fast after a subscriber joins.
+    #[must_use]
+    pub fn join_window(mut self, window: Duration) -> Self {
+        self.join_window = window;
+        self
+    }
+}
+
+/// Schedule state of one instrument.
+#[derive(Debug, Clone)]
+struct InstrumentSchedule {
+    /// Exponentially decayed update count, as of `rate_at`.
+    decayed_updates: f64,
+    rate_at: Instant,
+    updates_since_snapshot: u64,
+    last_snapshot: Option<Instant>,
+    last_join: Option<Instant>,
+}
+
+impl InstrumentSchedule {
+    fn new(now: Instant) -> Self {
+        Self {
+            decayed_updates: 0.0,
+            rate_at: now,
+            updates_since_snapshot: 0,
+            last_snapshot: None,
+            last_join: None,
+        }
+    }
+
+    /// Decays the update count to `now`.
+    fn decay(&mut self, now: Instant, window: Duration) {
+        let elapsed = now.saturating_duration_since(self.rate_at);
+        if window.is_zero() {
+            self.decayed_updates = 0.0;
+        } else {
+            self.decayed_updates *= (-elapsed.as_secs_f64() / window.as_secs_f64()).exp();
+        }
+        self.rate_at = self.rate_at.max(now);
+    }
+}
+
+/// Decides when each instrument's next snapshot is due.
+///
+/// The scheduler performs no I/O: feed it updates and joins, poll
+/// [`due`](Self::due) from the publishing loop, publish a snapshot of each
+/// returned instrument and confirm it with
+/// [`snapshot_sent`](Self::snapshot_sent).
+///
+/// # Example
+/// ```
+/// use ironsbe_marketdata::{SnapshotScheduleConfig, SnapshotScheduler};
+/// use std::time::{Duration, Instant};
+///
+/// let mut scheduler = SnapshotScheduler::new(SnapshotScheduleConfig::new());
+/// let start = Instant::now();
+/// scheduler.on_update(7, start);
+///
+/// // The first snapshot of a changed instrument is due at once.
+/// assert_eq!(scheduler.due(start), vec![7]);
+/// scheduler.snapshot_sent(7, start);
+/// assert!(scheduler.due(start + Duration::from_secs(60)).is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct SnapshotScheduler {
+    config: SnapshotScheduleConfig,
+    instruments: HashMap<u64, InstrumentSchedule>,
+    last_join: Option<Instant>,
+}
+
+impl SnapshotScheduler {
+    /// Creates a scheduler with no instruments.
+    #[must_use]
+    pub fn new(config: SnapshotScheduleConfig) -> Self {
+        Self {
+            config,
+            instruments: HashMap::new(),
+            last_join: None,
+        }
+    }
+
+    /// Returns the configuration.
+    #[must_use]
+    pub fn config(&self) -> &SnapshotScheduleConfig {
+        &self.config
+    }
+
+    /// Records an incremental update of `instrument_id`, adding the
+    /// instrument on first sight.
+    pub fn on_update(&mut self, instrument_id: u64, now: Instant) {
+        let window = self.config.rate_window;
+        let schedule = self
+            .instruments
+            .entry(instrument_id)
+            .or_insert_with(|| InstrumentSchedule::new(now));
+        schedule.decay(now, window);
+        schedule.decayed_updates += 1.0;
+        schedule.updates_since_snapshot += 1;
+    }
+
+    /// Records a subscriber joining the whole feed.
+    ///
+    /// Every instrument becomes due for one snapshot, even if unchanged,
+    /// and changed instruments use the minimum interval for the
+    /// [`join_window`](SnapshotScheduleConfig::join_window).
+    pub fn on_subscriber_join(&mut self, now: Instant) {
+        self.last_join = Some(now);
+    }
+
+    /// Records a subscriber joining a single instrument, such as a
+    /// per-instrument channel.
+    pub fn on_instrument_join(&mut self, instrument_id: u64, now: Instant) {
+        self.instruments
+            .entry(instrument_id)
+            .or_insert_with(|| InstrumentSchedule::new(now))
+            .last_join = Some(now);
+    }
+
+    /// Records that a snapshot of `instrument_id` was published.
+    pub fn snapshot_sent(&mut self, instrument_id: u64, now: Instant) {
+        let schedule = self
+            .instruments
+            .entry(instrument_id)
+            .or_insert_with(|| InstrumentSchedule::new(now));
+        schedule.updates_since_snapshot = 0;
+        schedule.last_snapshot = Some(now);
+    }
+
+    /// Stops scheduling `instrument_id`.
+    ///
+    /// # Returns
+    /// True if the instrument was tracked.
+    pub fn remove(&mut self, instrument_id: u64) -> bool {
+        self.instruments.remove(&instrument_id).is_some()
+    }
+
+    /// Returns the update rate of `instrument_id` in updates per second,
+    /// averaged over the [`rate_window`](SnapshotScheduleConfig::rate_window).
+    #[must_use]
+    pub fn update_rate(&self, instrument_id: u64, now: Instant) -> Option<f64> {
+        let mut schedule = self.instruments.get(&instrument_id)?.clone();
+        schedule.decay(now, self.config.rate_window);
+        Some(self.rate(&schedule))
+    }
+
+    /// Returns the current snapshot interval of `instrument_id`.
+    #[must_use]
+    pub fn interval(&self, instrument_id: u64, now: Instant) -> Option<Duration> {
+        let mut schedule = self.instruments.get(&instrument_id)?.clone();
+        schedule.decay(now, self.config.rate_window);
+        Some(self.interval_of(&schedule, now))
+    }
+
+    /// Returns the instruments whose snapshot is due at `now`, in
+    /// ascending ID order.
+    ///
+    /// An instrument is due if it changed or a subscriber joined since its
+    /// last snapshot, and its interval has elapsed. The result stays the
+    /// same until [`snapshot_sent`](Self::snapshot_sent) is called.
+    #[must_use]
+    pub fn due(&mut self, now: Instant) -> Vec<u64> {
+        let window = self.config.rate_window;
+        for schedule in self.instruments.values_mut() {
+            schedule.decay(now, window);
+        }
+        let mut due: Vec<u64> = self
+            .instruments
+            .iter()
+            .filter(|(_, schedule)| self.is_due(schedule, now))
+            .map(|(&id, _)| id)
+            .collect();
+        due.sort_unstable();
+        due
+    }
+
+    /// Returns when the next snapshot falls due, assuming no further
+    /// updates or joins, or `None` if nothing needs publishing.
+    ///
+    /// Lets a publishing loop sleep until then instead of polling.
+    #[must_use]
+    pub fn next_due(&self, now: Instant) -> Option<Instant> {
+        self.instruments
+            .values()
+            .filter(|schedule| self.has_news(schedule))
+            .map(|schedule| {
+                let mut schedule = schedule.clone();
+                schedule.decay(now, self.config.rate_window);
+                match schedule.last_snapshot {
+                    Some(last) => (last + self.interval_of(&schedule, now)).max(now),
+                    None => now,
+                }
+            })
+            .min()
+    }
+
+    /// Returns the number of tracked instruments.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    /// Returns true if no instruments are tracked.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+
+    /// Returns the decayed update rate of `schedule` in updates per second.
+    fn rate(&self, schedule: &InstrumentSchedule) -> f64 {
+        let window = self.config.rate_window.as_secs_f64();
+        if window > 0.0 {
+            schedule.decayed_updates / window
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the latest join affecting `schedule`.
+    fn last_join(&self, schedule: &InstrumentSchedule) -> Option<Instant> {
+        schedule.last_join.max(self.last_join)
+    }
+
+    /// Returns true if `schedule` changed or gained a joiner since its last
+    /// snapshot.
+    fn has_news(&self, schedule: &InstrumentSchedule) -> bool {
+        let joined = match (self.last_join(schedule), schedule.last_snapshot) {
+            (Some(join), Some(snapshot)) => join > snapshot,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        schedule.updates_since_snapshot > 0 || joined
+    }
+
+    /// Returns the interval for `schedule`, whose count is decayed to `now`.
+    fn interval_of(&self, schedule: &InstrumentSchedule, now: Instant) -> Duration {
+        let config = &self.config;
+        let joining = self
+            .last_join(schedule)
+            .is_some_and(|join| now.saturating_duration_since(join) < config.join_window);
+        let rate = self.rate(schedule);
+        let adapted = if joining {
+            config.min_interval
+        } else if rate > 0.0 {
+            Duration::try_from_secs_f64(f64::from(config.updates_per_snapshot) / rate)
+                .unwrap_or(config.max_interval)
+        } else {
+            config.max_interval
+        };
+        adapted.clamp(
+            config.min_interval,
+            config.max_interval.max(config.min_interval),
+        )
+    }
+
+    /// Returns true if `schedule`, decayed to `now`, needs a snapshot.
+    fn is_due(&self, schedule: &InstrumentSchedule, now: Instant) -> bool {
+        if !self.has_news(schedule) {
+            return false;
+        }
+        match schedule.last_snapshot {
+            None => true,
+            Some(last) => now.saturating_duration_since(last) >= self.interval_of(schedule, now),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SnapshotScheduleConfig {
+        SnapshotScheduleConfig::new()
+            .min_interval(Duration::from_millis(100))
+            .max_interval(Duration::from_secs(10))
+            .updates_per_snapshot(100)
+            .rate_window(Duration::from_secs(1))
+            .join_window(Duration::from_secs(2))
+    }
+
+    #[test]
+    fn test_busy_instrument_snapshots_more_often() {
+        let mut scheduler = SnapshotScheduler::new(config());
+        let start = Instant::now();
+        for id in [1, 2] {
+            scheduler.on_update(id, start);
+            scheduler.snapshot_sent(id, start);
+        }
+
+        // Instrument 1 ticks 1,000 times a second, instrument 2 once.
+        for ms in 0..1_000u64 {
+            scheduler.on_update(1, start + Duration::from_millis(ms));
+        }
+        scheduler.on_update(2, start + Duration::from_millis(500));
+
+        let now = start + Duration::from_secs(1);
+        let busy = scheduler.interval(1, now).unwrap();
+        let quiet = scheduler.interval(2, now).unwrap();
+        assert!(busy < Duration::from_millis(300), "{busy:?}");
+        assert_eq!(quiet, Duration::from_secs(10));
+        assert!(scheduler.update_rate(1, now).unwrap() > 500.0);
+        assert_eq!(scheduler.due(now), vec![1]);
+        assert_eq!(scheduler.next_due(now), Some(now));
+
+        scheduler.snapshot_sent(1, now);
+        assert_eq!(
+            scheduler.next_due(now),
+            Some(start + Duration::from_secs(10))
+        );
+        assert_eq!(scheduler.due(start + Duration::from_secs(10)), vec![2]);
+    }
+
+    #[test]
+    fn test_unchanged_instrument_is_not_republished() {
+        let mut scheduler = SnapshotScheduler::new(config());
+        let start = Instant::now();
+        scheduler.on_update(1, start);
+        assert_eq!(scheduler.due(start), vec![1]);
+        scheduler.snapshot_sent(1, start);
+
+        assert!(scheduler.due(start + Duration::from_secs(60)).is_empty());
+        assert_eq!(scheduler.next_due(start), None);
+    }
+
+    #[test]
+    fn test_join_forces_fast_snapshot() {
+        let mut scheduler = SnapshotScheduler::new(config());
+        let start = Instant::now();
+        for id in [1, 2] {
+            scheduler.on_update(id, start);
+            scheduler.snapshot_sent(id, start);
+        }
+
+        // A feed-wide join makes even unchanged instruments due once.
+        let join = start + Duration::from_secs(1);
+        scheduler.on_subscriber_join(join);
+        assert_eq!(scheduler.due(join), vec![1, 2]);
+        for id in [1, 2] {
+            scheduler.snapshot_sent(id, join);
+        }
+
+        // Another joiner right after waits out the minimum interval.
+        scheduler.on_subscriber_join(join + Duration::from_millis(50));
+        assert!(scheduler.due(join + Duration::from_millis(60)).is_empty());
+        let now = join + Duration::from_millis(100);
+        assert_eq!(scheduler.due(now), vec![1, 2]);
+        for id in [1, 2] {
+            scheduler.snapshot_sent(id, now);
+        }
+        assert!(scheduler.due(now + Duration::from_secs(5)).is_empty());
+
+        // During the join window a changed instrument uses the minimum
+        // interval; afterwards it falls back to its rate.
+        scheduler.on_update(1, now);
+        assert_eq!(
+            scheduler.interval(1, now + Duration::from_millis(10)),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            scheduler.interval(1, join + Duration::from_secs(3)),
+            Some(Duration::from_secs(10))
+        );
+
+        // A per-instrument join only affects that instrument.
+        let later = start + Duration::from_secs(20);
+        scheduler.on_instrument_join(2, later);
+        assert_eq!(
+            scheduler.due(later + Duration::from_millis(100)),
+            vec![1, 2]
+        );
+        scheduler.snapshot_sent(1, later);
+        scheduler.snapshot_sent(2, later);
+        scheduler.on_instrument_join(2, later + Duration::from_secs(1));
+        assert_eq!(scheduler.due(later + Duration::from_secs(2)), vec![2]);
+
+        assert!(scheduler.remove(2));
+        assert!(!scheduler.remove(2));
+        assert_eq!(scheduler.len(), 1);
+    }
+}
@@ -0,0 +1,302 @@
+//! Instrument trading status tracking.
+//!
+//! Exchanges publish security-status messages whenever an instrument moves
+//! between trading phases. [`TradingStatusTracker`] folds those messages into
+//! a per-instrument [`TradingStatus`] so strategies can check, next to the
+//! book, whether quoting is currently allowed.
+
+use std::collections::HashMap;
+
+/// Trading phase of an instrument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TradingStatus {
+    /// Orders may be entered but do not match yet.
+    PreOpen,
+    /// Continuous trading.
+    Open,
+    /// Trading halted by the exchange.
+    Halted,
+    /// Auction or crossing period; orders match at a single price.
+    Auction,
+    /// Trading session closed.
+    Closed,
+}
+
+impl TradingStatus {
+    /// Maps a FIX `SecurityTradingStatus` (tag 326) code to a status.
+    ///
+    /// Returns `None` for codes that do not imply a phase, such as
+    /// "no change" (103) or "unknown" (20).
+    #[must_use]
+    pub fn from_security_trading_status(code: u8) -> Option<Self> {
+        match code {
+            21 | 15 => Some(Self::PreOpen),
+            17 => Some(Self::Open),
+            2 => Some(Self::Halted),
+            24 | 25 => Some(Self::Auction),
+            4 | 18 | 26 => Some(Self::Closed),
+            _ => None,
+        }
+    }
+
+    /// Returns true if resting quotes can trade in this phase.
+    #[must_use]
+    pub const fn is_quotable(self) -> bool {
+        matches!(self, Self::Open)
+    }
+
+    /// Returns true if orders may be entered in this phase.
+    #[must_use]
+    pub const fn accepts_orders(self) -> bool {
+        matches!(self, Self::PreOpen | Self::Open | Self::Auction)
+    }
+
+    /// Returns true if the move from `self` to `next` follows the normal
+    /// session lifecycle.
+    ///
+    /// Any phase may be halted or closed, a halt may resume into any open
+    /// phase, and a closed instrument only reopens through pre-open.
+    #[must_use]
+    pub const fn can_transition_to(self, next: Self) -> bool {
+        matches!(
+            (self, next),
+            (_, Self::Halted | Self::Closed)
+                | (Self::PreOpen, Self::Open | Self::Auction)
+                | (Self::Auction, Self::Open)
+                | (Self::Open, Self::Auction)
+                | (Self::Halted, Self::PreOpen | Self::Open | Self::Auction)
+                | (Self::Closed, Self::PreOpen)
+        )
+    }
+}
+
+/// Security status message from the feed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecurityStatus {
+    /// Instrument identifier.
+    pub instrument_id: u64,
+    /// Sequence number.
+    pub seq_num: u64,
+    /// New trading status.
+    pub status: TradingStatus,
+}
+
+/// A change in an instrument's trading status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusTransition {
+    /// Instrument identifier.
+    pub instrument_id: u64,
+    /// Previous status, or `None` for the first status seen.
+    pub from: Option<TradingStatus>,
+    /// New status.
+    pub to: TradingStatus,
+}
+
+impl StatusTransition {
+    /// Returns true if the transition follows the normal session lifecycle.
+    ///
+    /// The first status seen for an instrument is always expected.
+    #[must_use]
+    pub fn is_expected(&self) -> bool {
+        self.from.is_none_or(|from| from.can_transition_to(self.to))
+    }
+}
+
+/// Tracks the trading status of each instrument.
+///
+/// The feed is authoritative: every status is applied, including moves that
+/// skip lifecycle steps. Use [`StatusTransition::is_expected`] to flag them.
+#[derive(Debug, Default)]
+pub struct TradingStatusTracker {
+    /// Current status and the sequence number that set it, per instrument.
+    statuses: HashMap<u64, (TradingStatus, u64)>,
+}
+
+impl TradingStatusTracker {
+    /// Creates an empty tracker.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a security status message.
+    ///
+    /// # Returns
+    /// The transition if the status changed, or `None` if it is unchanged
+    /// or the message is older than the last status applied.
+    pub fn on_security_status(&mut self, msg: &SecurityStatus) -> Option<StatusTransition> {
+        let from = match self.statuses.get(&msg.instrument_id) {
+            Some(&(_, seq)) if msg.seq_num < seq => return None,
+            Some(&(status, _)) => Some(status),
+            None => None,
+        };
+
+        self.statuses
+            .insert(msg.instrument_id, (msg.status, msg.seq_num));
+        if from == Some(msg.status) {
+            return None;
+        }
+        Some(StatusTransition {
+            instrument_id: msg.instrument_id,
+            from,
+            to: msg.status,
+        })
+    }
+
+    /// Returns the current status of an instrument.
+    #[must_use]
+    pub fn status(&self, instrument_id: u64) -> Option<TradingStatus> {
+        self.statuses.get(&instrument_id).map(|&(status, _)| status)
+    }
+
+    /// Returns true if the instrument is known to be open for trading.
+    ///
+    /// Instruments without a status are not quotable.
+    #[must_use]
+    pub fn is_quotable(&self, instrument_id: u64) -> bool {
+        self.status(instrument_id)
+            .is_some_and(TradingStatus::is_quotable)
+    }
+
+    /// Returns the IDs of all instruments currently in `status`.
+    #[must_use]
+    pub fn instruments_in(&self, status: TradingStatus) -> Vec<u64> {
+        self.statuses
+            .iter()
+            .filter(|(_, (s, _))| *s == status)
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// Forgets the status of an instrument.
+    pub fn remove(&mut self, instrument_id: u64) -> Option<TradingStatus> {
+        self.statuses
+            .remove(&instrument_id)
+            .map(|(status, _)| status)
+    }
+
+    /// Returns the number of tracked instruments.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.statuses.len()
+    }
+
+    /// Returns true if no instrument is tracked.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.statuses.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(instrument_id: u64, seq_num: u64, status: TradingStatus) -> SecurityStatus {
+        SecurityStatus {
+            instrument_id,
+            seq_num,
+            status,
+        }
+    }
+
+    #[test]
+    fn test_from_security_trading_status() {
+        assert_eq!(
+            TradingStatus::from_security_trading_status(17),
+            Some(TradingStatus::Open)
+        );
+        assert_eq!(
+            TradingStatus::from_security_trading_status(2),
+            Some(TradingStatus::Halted)
+        );
+        assert_eq!(
+            TradingStatus::from_security_trading_status(21),
+            Some(TradingStatus::PreOpen)
+        );
+        assert_eq!(TradingStatus::from_security_trading_status(103), None);
+    }
+
+    #[test]
+    fn test_lifecycle_transitions() {
+        use TradingStatus::*;
+
+        assert!(PreOpen.can_transition_to(Auction));
+        assert!(Auction.can_transition_to(Open));
+        assert!(Open.can_transition_to(Halted));
+        assert!(Halted.can_transition_to(Open));
+        assert!(Open.can_transition_to(Closed));
+        assert!(Closed.can_transition_to(PreOpen));
+
+        assert!(!Closed.can_transition_to(Open));
+        assert!(!Open.can_transition_to(PreOpen));
+    }
+
+    #[test]
+    fn test_tracker_emits_transitions() {
+        let mut tracker = TradingStatusTracker::new();
+
+        let first = tracker
+            .on_security_status(&status(1, 1, TradingStatus::PreOpen))
+            .unwrap();
+        assert_eq!(first.from, None);
+        assert!(first.is_expected());
+        assert!(!tracker.is_quotable(1));
+
+        let open = tracker
+            .on_security_status(&status(1, 2, TradingStatus::Open))
+            .unwrap();
+        assert_eq!(open.from, Some(TradingStatus::PreOpen));
+        assert_eq!(open.to, TradingStatus::Open);
+        assert!(tracker.is_quotable(1));
+
+        // Repeated status is not a transition
+        assert!(
+            tracker
+                .on_security_status(&status(1, 3, TradingStatus::Open))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_tracker_ignores_stale_status() {
+        let mut tracker = TradingStatusTracker::new();
+        tracker.on_security_status(&status(1, 10, TradingStatus::Halted));
+
+        assert!(
+            tracker
+                .on_security_status(&status(1, 9, TradingStatus::Open))
+                .is_none()
+        );
+        assert_eq!(tracker.status(1), Some(TradingStatus::Halted));
+    }
+
+    #[test]
+    fn test_tracker_applies_unexpected_transition() {
+        let mut tracker = TradingStatusTracker::new();
+        tracker.on_security_status(&status(1, 1, TradingStatus::Closed));
+
+        let transition = tracker
+            .on_security_status(&status(1, 2, TradingStatus::Open))
+            .unwrap();
+        assert!(!transition.is_expected());
+        assert_eq!(tracker.status(1), Some(TradingStatus::Open));
+    }
+
+    #[test]
+    fn test_tracker_queries() {
+        let mut tracker = TradingStatusTracker::new();
+        tracker.on_security_status(&status(1, 1, TradingStatus::Halted));
+        tracker.on_security_status(&status(2, 1, TradingStatus::Open));
+        tracker.on_security_status(&status(3, 1, TradingStatus::Halted));
+
+        let mut halted = tracker.instruments_in(TradingStatus::Halted);
+        halted.sort_unstable();
+        assert_eq!(halted, vec![1, 3]);
+        assert_eq!(tracker.len(), 3);
+        assert!(!tracker.is_quotable(4));
+
+        assert_eq!(tracker.remove(1), Some(TradingStatus::Halted));
+        assert_eq!(tracker.status(1), None);
+    }
+}
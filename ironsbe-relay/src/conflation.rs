@@ -0,0 +1,180 @@
+//! Per-subscriber conflation.
+//!
+//! A [`Conflator`] assigns each feed message an optional key. Messages that
+//! share a key supersede each other (e.g. top-of-book for one instrument),
+//! so when a subscriber falls behind, its [`ConflatingQueue`] keeps only the
+//! newest pending message per key. Unkeyed messages (trades, status
+//! changes) are never dropped; a subscriber that cannot keep up with them
+//! overflows its queue and is disconnected.
+
+use bytes::Bytes;
+use ironsbe_core::header::MessageHeader;
+use std::collections::{BTreeMap, HashMap};
+
+/// Assigns conflation keys to feed messages.
+pub trait Conflator: Send + Sync + 'static {
+    /// Returns the conflation key of `message`, or `None` if the message
+    /// must be delivered even when a newer message follows it.
+    ///
+    /// # Arguments
+    /// * `header` - Decoded message header
+    /// * `message` - Full message (including header)
+    fn key(&self, header: &MessageHeader, message: &[u8]) -> Option<u64>;
+}
+
+impl<F> Conflator for F
+where
+    F: Fn(&MessageHeader, &[u8]) -> Option<u64> + Send + Sync + 'static,
+{
+    fn key(&self, header: &MessageHeader, message: &[u8]) -> Option<u64> {
+        self(header, message)
+    }
+}
+
+/// Conflator that never conflates.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoConflation;
+
+impl Conflator for NoConflation {
+    fn key(&self, _header: &MessageHeader, _message: &[u8]) -> Option<u64> {
+        None
+    }
+}
+
+/// The queue held more than its maximum depth of undeliverable messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFull;
+
+/// Outbound queue of one subscriber, ordered by sequence number.
+#[derive(Debug)]
+pub struct ConflatingQueue {
+    /// Pending frames by sequence number, with their conflation key.
+    pending: BTreeMap<u64, (Option<u64>, Bytes)>,
+    /// Sequence number of the pending frame for each key.
+    by_key: HashMap<u64, u64>,
+    max_depth: usize,
+    conflated: u64,
+}
+
+impl ConflatingQueue {
+    /// Creates an empty queue holding at most `max_depth` frames.
+    ///
+    /// Values below 1 are treated as 1.
+    #[must_use]
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            pending: BTreeMap::new(),
+            by_key: HashMap::new(),
+            max_depth: max_depth.max(1),
+            conflated: 0,
+        }
+    }
+
+    /// Queues a frame, dropping any pending frame with the same key.
+    ///
+    /// # Errors
+    /// Returns [`QueueFull`] if the queue is at its maximum depth after
+    /// conflation; the frame is not queued.
+    pub fn push(&mut self, seq: u64, key: Option<u64>, frame: Bytes) -> Result<(), QueueFull> {
+        if let Some(key) = key
+            && let Some(old_seq) = self.by_key.remove(&key)
+        {
+            self.pending.remove(&old_seq);
+            self.conflated += 1;
+        }
+        if self.pending.len() >= self.max_depth {
+            return Err(QueueFull);
+        }
+        if let Some(key) = key {
+            self.by_key.insert(key, seq);
+        }
+        self.pending.insert(seq, (key, frame));
+        Ok(())
+    }
+
+    /// Removes the oldest pending frame.
+    pub fn pop(&mut self) -> Option<(u64, Bytes)> {
+        let (seq, (key, frame)) = self.pending.pop_first()?;
+        if let Some(key) = key {
+            self.by_key.remove(&key);
+        }
+        Some((seq, frame))
+    }
+
+    /// Returns the number of pending frames.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns true if no frame is pending.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Returns the number of frames dropped because a newer frame with the
+    /// same key was queued.
+    #[must_use]
+    pub fn conflated(&self) -> u64 {
+        self.conflated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(byte: u8) -> Bytes {
+        Bytes::from(vec![byte])
+    }
+
+    #[test]
+    fn test_queue_keeps_sequence_order() {
+        let mut queue = ConflatingQueue::new(8);
+        queue.push(1, None, frame(1)).unwrap();
+        queue.push(2, Some(10), frame(2)).unwrap();
+        queue.push(3, None, frame(3)).unwrap();
+
+        let seqs: Vec<_> = std::iter::from_fn(|| queue.pop()).map(|(s, _)| s).collect();
+        assert_eq!(seqs, vec![1, 2, 3]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_queue_conflates_same_key() {
+        let mut queue = ConflatingQueue::new(8);
+        queue.push(1, Some(10), frame(1)).unwrap();
+        queue.push(2, Some(20), frame(2)).unwrap();
+        queue.push(3, Some(10), frame(3)).unwrap();
+
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.conflated(), 1);
+        assert_eq!(queue.pop(), Some((2, frame(2))));
+        assert_eq!(queue.pop(), Some((3, frame(3))));
+
+        // Key 10 is no longer pending, so this does not conflate
+        queue.push(4, Some(10), frame(4)).unwrap();
+        assert_eq!(queue.conflated(), 1);
+    }
+
+    #[test]
+    fn test_queue_full() {
+        let mut queue = ConflatingQueue::new(2);
+        queue.push(1, None, frame(1)).unwrap();
+        queue.push(2, Some(10), frame(2)).unwrap();
+        assert_eq!(queue.push(3, None, frame(3)), Err(QueueFull));
+
+        // Conflation frees the slot it replaces
+        queue.push(4, Some(10), frame(4)).unwrap();
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_closure_conflator() {
+        let conflator = |header: &MessageHeader, _: &[u8]| Some(u64::from(header.template_id));
+        let header = MessageHeader::new(0, 5, 1, 1);
+        assert_eq!(conflator.key(&header, &[]), Some(5));
+        assert_eq!(NoConflation.key(&header, &[]), None);
+    }
+}
@@ -0,0 +1,16 @@
+//! Error types for relay operations.
+
+use ironsbe_client::ClientError;
+use thiserror::Error;
+
+/// Error type for relay operations.
+#[derive(Debug, Error)]
+pub enum RelayError {
+    /// IO error.
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The upstream feed connection failed for good.
+    #[error("upstream error: {0}")]
+    Upstream(#[from] ClientError),
+}
@@ -0,0 +1,29 @@
+//! # IronSBE Relay
+//!
+//! WAN fan-out relay for SBE feeds.
+//!
+//! A relay subscribes to a local feed as an IronSBE client and republishes
+//! every message to remote subscribers, typically in other regions. This
+//! crate provides:
+//! - Relay builder and handle built from the client and server pieces
+//! - Relay link framing with per-message sequence numbers
+//! - Per-subscriber conflation so a slow link only affects itself
+//! - Gap recovery from a bounded replay window, falling back to a
+//!   conflated snapshot when the gap is too old
+//! - Subscriber-side sequence tracking for remote consumers
+//!
+//! Both sides run over TCP, through the `tcp-tokio` transport.
+
+pub mod conflation;
+pub mod error;
+pub mod protocol;
+pub mod relay;
+pub mod replay;
+pub mod subscriber;
+
+pub use conflation::{ConflatingQueue, Conflator, NoConflation, QueueFull};
+pub use error::RelayError;
+pub use protocol::RelayFrame;
+pub use relay::{Relay, RelayBuilder, RelayEvent, RelayHandle};
+pub use replay::{Replay, ReplayWindow};
+pub use subscriber::{RelaySubscriber, SubscriberEvent};
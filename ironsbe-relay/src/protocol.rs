@@ -0,0 +1,167 @@
+//! Relay link framing.
+//!
+//! Everything on a relay link is an SBE message under
+//! [`RELAY_SCHEMA_ID`]:
+//!
+//! - `Data` wraps one feed message with the relay sequence number it was
+//!   published under. The block holds the sequence; the wrapped message,
+//!   header included, follows it unchanged.
+//! - `Resume` is the first message a subscriber sends. It carries the last
+//!   sequence number the subscriber applied, or 0 for a fresh start.
+//! - `Reset` tells a subscriber that the relay could not replay everything
+//!   after its last sequence, so it must drop its state before applying
+//!   the snapshot that follows. Snapshot frames keep their original
+//!   sequence numbers, which may be lower than the subscriber's last one.
+
+use bytes::{BufMut, Bytes, BytesMut};
+use ironsbe_core::header::MessageHeader;
+
+/// Schema ID of relay link messages.
+pub const RELAY_SCHEMA_ID: u16 = 0xFE01;
+
+/// Schema version of relay link messages.
+pub const RELAY_SCHEMA_VERSION: u16 = 1;
+
+/// Template ID of the data frame.
+pub const DATA_TEMPLATE_ID: u16 = 1;
+
+/// Template ID of the resume request.
+pub const RESUME_TEMPLATE_ID: u16 = 2;
+
+/// Template ID of the reset notice.
+pub const RESET_TEMPLATE_ID: u16 = 3;
+
+/// Block length of every relay link message (one `u64`).
+const BLOCK_LENGTH: u16 = 8;
+
+/// Length of a relay frame before any wrapped message.
+pub const FRAME_OVERHEAD: usize = MessageHeader::ENCODED_LENGTH + BLOCK_LENGTH as usize;
+
+/// Decoded relay link message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayFrame<'a> {
+    /// Feed message published under `seq`.
+    Data {
+        /// Relay sequence number.
+        seq: u64,
+        /// Wrapped feed message, including its SBE header.
+        message: &'a [u8],
+    },
+    /// Subscriber resume request.
+    Resume {
+        /// Last sequence the subscriber applied (0 = none).
+        last_seq: u64,
+    },
+    /// Replay could not cover the requested range.
+    Reset {
+        /// Sequence the relay will assign to its next live message.
+        next_seq: u64,
+    },
+}
+
+impl<'a> RelayFrame<'a> {
+    /// Decodes a relay link message.
+    ///
+    /// Returns `None` for messages outside the relay schema, unknown
+    /// templates, or truncated frames.
+    #[must_use]
+    pub fn decode(buffer: &'a [u8]) -> Option<Self> {
+        let header = MessageHeader::try_wrap(buffer, 0)?;
+        if header.schema_id != RELAY_SCHEMA_ID || buffer.len() < FRAME_OVERHEAD {
+            return None;
+        }
+        let value = u64::from_le_bytes(
+            buffer[MessageHeader::ENCODED_LENGTH..FRAME_OVERHEAD]
+                .try_into()
+                .ok()?,
+        );
+        match header.template_id {
+            DATA_TEMPLATE_ID => Some(Self::Data {
+                seq: value,
+                message: &buffer[FRAME_OVERHEAD..],
+            }),
+            RESUME_TEMPLATE_ID => Some(Self::Resume { last_seq: value }),
+            RESET_TEMPLATE_ID => Some(Self::Reset { next_seq: value }),
+            _ => None,
+        }
+    }
+}
+
+/// Encodes a data frame wrapping `message`.
+#[must_use]
+pub fn encode_data(seq: u64, message: &[u8]) -> Bytes {
+    let mut buf = BytesMut::with_capacity(FRAME_OVERHEAD + message.len());
+    put_frame(&mut buf, DATA_TEMPLATE_ID, seq);
+    buf.put_slice(message);
+    buf.freeze()
+}
+
+/// Encodes a resume request.
+#[must_use]
+pub fn encode_resume(last_seq: u64) -> Bytes {
+    let mut buf = BytesMut::with_capacity(FRAME_OVERHEAD);
+    put_frame(&mut buf, RESUME_TEMPLATE_ID, last_seq);
+    buf.freeze()
+}
+
+/// Encodes a reset notice.
+#[must_use]
+pub fn encode_reset(next_seq: u64) -> Bytes {
+    let mut buf = BytesMut::with_capacity(FRAME_OVERHEAD);
+    put_frame(&mut buf, RESET_TEMPLATE_ID, next_seq);
+    buf.freeze()
+}
+
+/// Writes the relay header and block.
+fn put_frame(buf: &mut BytesMut, template_id: u16, value: u64) {
+    let mut header = [0u8; MessageHeader::ENCODED_LENGTH];
+    MessageHeader::new(
+        BLOCK_LENGTH,
+        template_id,
+        RELAY_SCHEMA_ID,
+        RELAY_SCHEMA_VERSION,
+    )
+    .encode(&mut header[..], 0);
+    buf.put_slice(&header);
+    buf.put_u64_le(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_round_trip() {
+        let frame = encode_data(42, b"payload");
+        assert_eq!(frame.len(), FRAME_OVERHEAD + 7);
+        assert_eq!(
+            RelayFrame::decode(&frame),
+            Some(RelayFrame::Data {
+                seq: 42,
+                message: b"payload",
+            })
+        );
+    }
+
+    #[test]
+    fn test_control_round_trip() {
+        assert_eq!(
+            RelayFrame::decode(&encode_resume(7)),
+            Some(RelayFrame::Resume { last_seq: 7 })
+        );
+        assert_eq!(
+            RelayFrame::decode(&encode_reset(9)),
+            Some(RelayFrame::Reset { next_seq: 9 })
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_foreign_messages() {
+        let mut foreign = [0u8; 16];
+        MessageHeader::new(8, RESUME_TEMPLATE_ID, 1, 1).encode(&mut foreign[..], 0);
+        assert_eq!(RelayFrame::decode(&foreign), None);
+
+        let resume = encode_resume(1);
+        assert_eq!(RelayFrame::decode(&resume[..FRAME_OVERHEAD - 1]), None);
+    }
+}
@@ -0,0 +1,588 @@
+//! Relay builder and main relay implementation.
+
+use crate::conflation::{ConflatingQueue, Conflator, NoConflation};
+use crate::error::RelayError;
+use crate::protocol::{RelayFrame, encode_data, encode_reset};
+use crate::replay::ReplayWindow;
+use bytes::Bytes;
+use ironsbe_channel::mpsc::{MpscChannel, MpscReceiver, MpscSender};
+use ironsbe_client::{ClientBuilder, ClientEvent, ClientHandle};
+use ironsbe_core::header::MessageHeader;
+use ironsbe_server::SessionManager;
+use ironsbe_transport::traits::{Connection, Listener, Transport};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+
+/// Builder for configuring and creating a relay.
+///
+/// `U` is the transport used to subscribe to the local feed and `D` the
+/// transport remote subscribers connect over. Both default to
+/// [`ironsbe_transport::DefaultTransport`], the Tokio TCP transport.
+#[cfg(feature = "tcp-tokio")]
+pub struct RelayBuilder<
+    U: Transport = ironsbe_transport::DefaultTransport,
+    D: Transport = ironsbe_transport::DefaultTransport,
+> {
+    upstream_addr: SocketAddr,
+    connect_config: Option<U::ConnectConfig>,
+    bind_addr: SocketAddr,
+    bind_config: Option<D::BindConfig>,
+    conflator: Arc<dyn Conflator>,
+    max_subscribers: usize,
+    max_queue_depth: usize,
+    replay_capacity: usize,
+    resume_timeout: Duration,
+    reconnect_delay: Duration,
+    max_reconnect_attempts: usize,
+    channel_capacity: usize,
+    _transport: PhantomData<(U, D)>,
+}
+
+/// Builder for configuring and creating a relay.
+///
+/// With the `tcp-tokio` feature disabled, both transports must be specified
+/// explicitly.
+#[cfg(not(feature = "tcp-tokio"))]
+pub struct RelayBuilder<U: Transport, D: Transport> {
+    upstream_addr: SocketAddr,
+    connect_config: Option<U::ConnectConfig>,
+    bind_addr: SocketAddr,
+    bind_config: Option<D::BindConfig>,
+    conflator: Arc<dyn Conflator>,
+    max_subscribers: usize,
+    max_queue_depth: usize,
+    replay_capacity: usize,
+    resume_timeout: Duration,
+    reconnect_delay: Duration,
+    max_reconnect_attempts: usize,
+    channel_capacity: usize,
+    _transport: PhantomData<(U, D)>,
+}
+
+impl<U: Transport, D: Transport> RelayBuilder<U, D> {
+    /// Creates a relay builder subscribing to the feed at `upstream_addr`.
+    #[must_use]
+    pub fn new(upstream_addr: SocketAddr) -> Self {
+        Self {
+            upstream_addr,
+            connect_config: None,
+            bind_addr: "0.0.0.0:9100".parse().unwrap(),
+            bind_config: None,
+            conflator: Arc::new(NoConflation),
+            max_subscribers: 100,
+            max_queue_depth: 65_536,
+            replay_capacity: 65_536,
+            resume_timeout: Duration::from_secs(5),
+            reconnect_delay: Duration::from_millis(100),
+            max_reconnect_attempts: 0,
+            channel_capacity: 4096,
+            _transport: PhantomData,
+        }
+    }
+
+    /// Supplies a backend-specific configuration for the feed connection.
+    #[must_use]
+    pub fn connect_config(mut self, config: U::ConnectConfig) -> Self {
+        self.connect_config = Some(config);
+        self
+    }
+
+    /// Sets the address remote subscribers connect to.
+    ///
+    /// Clears any [`bind_config`](Self::bind_config) previously supplied.
+    #[must_use]
+    pub fn bind(mut self, addr: SocketAddr) -> Self {
+        self.bind_addr = addr;
+        self.bind_config = None;
+        self
+    }
+
+    /// Supplies a backend-specific bind configuration for subscribers.
+    #[must_use]
+    pub fn bind_config(mut self, config: D::BindConfig) -> Self {
+        self.bind_config = Some(config);
+        self
+    }
+
+    /// Sets the conflator deciding which pending messages a lagging
+    /// subscriber may skip. By default nothing is conflated.
+    #[must_use]
+    pub fn conflator(mut self, conflator: impl Conflator) -> Self {
+        self.conflator = Arc::new(conflator);
+        self
+    }
+
+    /// Sets the maximum number of connected subscribers.
+    #[must_use]
+    pub fn max_subscribers(mut self, max: usize) -> Self {
+        self.max_subscribers = max;
+        self
+    }
+
+    /// Sets how many messages may wait for one subscriber after
+    /// conflation before it is disconnected as too slow.
+    #[must_use]
+    pub fn max_queue_depth(mut self, depth: usize) -> Self {
+        self.max_queue_depth = depth;
+        self
+    }
+
+    /// Sets how many published messages are retained for gap recovery.
+    #[must_use]
+    pub fn replay_capacity(mut self, capacity: usize) -> Self {
+        self.replay_capacity = capacity;
+        self
+    }
+
+    /// Sets how long a new subscriber has to send its resume request.
+    #[must_use]
+    pub fn resume_timeout(mut self, timeout: Duration) -> Self {
+        self.resume_timeout = timeout;
+        self
+    }
+
+    /// Sets the initial delay before reconnecting to the feed.
+    #[must_use]
+    pub fn reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    /// Sets the maximum feed reconnection attempts (0 = unlimited).
+    #[must_use]
+    pub fn max_reconnect_attempts(mut self, max: usize) -> Self {
+        self.max_reconnect_attempts = max;
+        self
+    }
+
+    /// Sets the event channel capacity.
+    #[must_use]
+    pub fn channel_capacity(mut self, capacity: usize) -> Self {
+        self.channel_capacity = capacity;
+        self
+    }
+
+    /// Builds the relay and handle.
+    #[must_use]
+    pub fn build(self) -> (Relay<U, D>, RelayHandle) {
+        let (event_tx, event_rx) = MpscChannel::bounded(self.channel_capacity);
+        let shutdown_token = CancellationToken::new();
+
+        let mut upstream = ClientBuilder::<U>::new(self.upstream_addr)
+            .reconnect(true)
+            .reconnect_delay(self.reconnect_delay)
+            .max_reconnect_attempts(self.max_reconnect_attempts)
+            .channel_capacity(self.channel_capacity);
+        if let Some(config) = self.connect_config {
+            upstream = upstream.connect_config(config);
+        }
+
+        let relay = Relay {
+            upstream: Some(upstream),
+            bind_config: Some(
+                self.bind_config
+                    .unwrap_or_else(|| D::BindConfig::from(self.bind_addr)),
+            ),
+            fan_out: Arc::new(FanOut {
+                state: Mutex::new(FanOutState {
+                    next_seq: 1,
+                    window: ReplayWindow::new(self.replay_capacity),
+                    subscribers: HashMap::new(),
+                }),
+                conflator: self.conflator,
+                max_queue_depth: self.max_queue_depth,
+                event_tx: event_tx.clone(),
+            }),
+            sessions: Arc::new(SessionManager::new()),
+            max_subscribers: self.max_subscribers,
+            resume_timeout: self.resume_timeout,
+            event_tx,
+            shutdown_token: shutdown_token.clone(),
+            _transport: PhantomData,
+        };
+
+        let handle = RelayHandle {
+            event_rx,
+            shutdown_token,
+        };
+
+        (relay, handle)
+    }
+}
+
+/// Relay node republishing a local SBE feed to remote subscribers.
+///
+/// Every feed message is assigned a relay sequence number and wrapped in a
+/// [`RelayFrame::Data`](crate::RelayFrame::Data). Each subscriber has its
+/// own [`ConflatingQueue`], so a slow WAN link only ever delays or
+/// conflates its own traffic. Subscribers resume after a disconnect by
+/// sending their last sequence number; the relay replays what they missed
+/// from its [`ReplayWindow`], or a snapshot if the gap is too old.
+#[cfg(feature = "tcp-tokio")]
+pub struct Relay<
+    U: Transport = ironsbe_transport::DefaultTransport,
+    D: Transport = ironsbe_transport::DefaultTransport,
+> {
+    upstream: Option<ClientBuilder<U>>,
+    bind_config: Option<D::BindConfig>,
+    fan_out: Arc<FanOut>,
+    sessions: Arc<SessionManager>,
+    max_subscribers: usize,
+    resume_timeout: Duration,
+    event_tx: MpscSender<RelayEvent>,
+    shutdown_token: CancellationToken,
+    _transport: PhantomData<D>,
+}
+
+/// Relay node republishing a local SBE feed to remote subscribers.
+///
+/// See the `tcp-tokio` variant for details.
+#[cfg(not(feature = "tcp-tokio"))]
+pub struct Relay<U: Transport, D: Transport> {
+    upstream: Option<ClientBuilder<U>>,
+    bind_config: Option<D::BindConfig>,
+    fan_out: Arc<FanOut>,
+    sessions: Arc<SessionManager>,
+    max_subscribers: usize,
+    resume_timeout: Duration,
+    event_tx: MpscSender<RelayEvent>,
+    shutdown_token: CancellationToken,
+    _transport: PhantomData<D>,
+}
+
+impl<U, D> Relay<U, D>
+where
+    U: Transport,
+    D: Transport,
+    D::Connection: Send + 'static,
+{
+    /// Runs the relay until shutdown or until the feed connection fails
+    /// for good.
+    ///
+    /// # Errors
+    /// Returns `RelayError` if the subscriber listener cannot be bound or
+    /// the feed client gives up reconnecting.
+    pub async fn run(&mut self) -> Result<(), RelayError> {
+        let bind_config = self
+            .bind_config
+            .take()
+            .expect("Relay::run called more than once");
+        let mut listener = D::bind_with(bind_config)
+            .await
+            .map_err(std::io::Error::other)?;
+        if let Ok(addr) = listener.local_addr() {
+            tracing::info!("Relay listening on {}", addr);
+            let _ = self.event_tx.try_send(RelayEvent::Listening(addr));
+        }
+
+        let (mut client, client_handle) = self
+            .upstream
+            .take()
+            .expect("Relay::run called more than once")
+            .build();
+        let mut upstream = tokio::spawn(async move { client.run().await });
+        let pump = tokio::spawn(pump_feed(
+            client_handle,
+            Arc::clone(&self.fan_out),
+            self.shutdown_token.clone(),
+        ));
+
+        let result = loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok(conn) => self.handle_connection(conn),
+                    Err(e) => tracing::error!("Accept error: {}", e),
+                },
+
+                finished = &mut upstream => {
+                    break match finished {
+                        Ok(Ok(())) => Ok(()),
+                        Ok(Err(e)) => Err(RelayError::Upstream(e)),
+                        Err(e) => Err(RelayError::Io(std::io::Error::other(e))),
+                    };
+                }
+
+                _ = self.shutdown_token.cancelled() => break Ok(()),
+            }
+        };
+
+        // Tear down subscriber sessions and the feed pump
+        self.shutdown_token.cancel();
+        let _ = pump.await;
+        upstream.abort();
+        result
+    }
+
+    fn handle_connection(&self, conn: D::Connection) {
+        let addr = conn
+            .peer_addr()
+            .unwrap_or_else(|_| "0.0.0.0:0".parse().unwrap());
+        if self.sessions.count() >= self.max_subscribers {
+            tracing::warn!("Max subscribers reached, rejecting {}", addr);
+            return;
+        }
+
+        let session_id = self.sessions.create_session(addr);
+        let _ = self
+            .event_tx
+            .try_send(RelayEvent::SubscriberConnected(session_id, addr));
+
+        let fan_out = Arc::clone(&self.fan_out);
+        let sessions = Arc::clone(&self.sessions);
+        let token = self.shutdown_token.child_token();
+        let resume_timeout = self.resume_timeout;
+        let span = tracing::info_span!("relay_subscriber", session_id, %addr);
+        tokio::spawn(
+            async move {
+                if let Err(e) =
+                    serve_subscriber(session_id, conn, &fan_out, token, resume_timeout).await
+                {
+                    tracing::warn!(error = %e, "subscriber error");
+                }
+                fan_out.unsubscribe(session_id);
+                sessions.close_session(session_id);
+                let _ = fan_out
+                    .event_tx
+                    .try_send(RelayEvent::SubscriberClosed(session_id));
+            }
+            .instrument(span),
+        );
+    }
+}
+
+/// Handle for controlling the relay from outside.
+pub struct RelayHandle {
+    event_rx: MpscReceiver<RelayEvent>,
+    shutdown_token: CancellationToken,
+}
+
+impl RelayHandle {
+    /// Requests relay shutdown, disconnecting every subscriber.
+    pub fn shutdown(&self) {
+        self.shutdown_token.cancel();
+    }
+
+    /// Polls for relay events.
+    pub fn poll_events(&self) -> impl Iterator<Item = RelayEvent> + '_ {
+        std::iter::from_fn(|| self.event_rx.try_recv())
+    }
+}
+
+/// Events emitted by the relay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayEvent {
+    /// The subscriber listener is bound to this address.
+    Listening(SocketAddr),
+    /// Connected to the local feed.
+    UpstreamConnected,
+    /// Lost the local feed connection; the relay keeps reconnecting.
+    UpstreamDisconnected,
+    /// A remote subscriber connected.
+    SubscriberConnected(u64, SocketAddr),
+    /// A subscriber resumed and is now receiving live traffic.
+    SubscriberResumed {
+        /// Session ID.
+        session_id: u64,
+        /// Last sequence the subscriber reported.
+        last_seq: u64,
+        /// Number of frames replayed.
+        replayed: usize,
+        /// True if the gap was too old and a snapshot was sent instead.
+        reset: bool,
+    },
+    /// A subscriber fell further behind than its queue allows and was
+    /// disconnected.
+    SubscriberLagged(u64),
+    /// A subscriber disconnected.
+    SubscriberClosed(u64),
+}
+
+/// Sequencing, history and per-subscriber queues shared by all tasks.
+struct FanOut {
+    state: Mutex<FanOutState>,
+    conflator: Arc<dyn Conflator>,
+    max_queue_depth: usize,
+    event_tx: MpscSender<RelayEvent>,
+}
+
+struct FanOutState {
+    next_seq: u64,
+    window: ReplayWindow,
+    subscribers: HashMap<u64, SubscriberSlot>,
+}
+
+/// Live subscriber registered with the fan-out.
+struct SubscriberSlot {
+    queue: ConflatingQueue,
+    notify: Arc<Notify>,
+    token: CancellationToken,
+}
+
+impl FanOut {
+    /// Sequences a feed message and queues it for every subscriber.
+    fn publish(&self, message: &[u8]) {
+        let Some(header) = MessageHeader::try_wrap(message, 0) else {
+            tracing::warn!(len = message.len(), "dropping feed message without header");
+            return;
+        };
+        let key = self.conflator.key(&header, message);
+
+        let mut state = self.state.lock();
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        let frame = encode_data(seq, message);
+        state.window.push(seq, key, frame.clone());
+
+        state.subscribers.retain(|&session_id, slot| {
+            if slot.queue.push(seq, key, frame.clone()).is_err() {
+                tracing::warn!(session_id, "subscriber lagged, disconnecting");
+                slot.token.cancel();
+                let _ = self
+                    .event_tx
+                    .try_send(RelayEvent::SubscriberLagged(session_id));
+                return false;
+            }
+            slot.notify.notify_one();
+            true
+        });
+    }
+
+    /// Registers a subscriber resuming after `last_seq`.
+    ///
+    /// Returns the frames to send before anything from the queue, and the
+    /// notifier signalled when the queue has frames.
+    fn subscribe(
+        &self,
+        session_id: u64,
+        last_seq: u64,
+        token: CancellationToken,
+    ) -> (Vec<Bytes>, Arc<Notify>) {
+        let mut state = self.state.lock();
+        let next_seq = state.next_seq;
+        let replay = state.window.replay(last_seq, next_seq);
+        let notify = Arc::new(Notify::new());
+        state.subscribers.insert(
+            session_id,
+            SubscriberSlot {
+                queue: ConflatingQueue::new(self.max_queue_depth),
+                notify: Arc::clone(&notify),
+                token,
+            },
+        );
+        drop(state);
+
+        let _ = self.event_tx.try_send(RelayEvent::SubscriberResumed {
+            session_id,
+            last_seq,
+            replayed: replay.frames.len(),
+            reset: replay.reset,
+        });
+        let frames = replay
+            .reset
+            .then(|| encode_reset(next_seq))
+            .into_iter()
+            .chain(replay.frames.into_iter().map(|(_, frame)| frame))
+            .collect();
+        (frames, notify)
+    }
+
+    /// Takes the next queued frame for a subscriber.
+    fn pop(&self, session_id: u64) -> Option<Bytes> {
+        let mut state = self.state.lock();
+        let (_, frame) = state.subscribers.get_mut(&session_id)?.queue.pop()?;
+        Some(frame)
+    }
+
+    fn unsubscribe(&self, session_id: u64) {
+        self.state.lock().subscribers.remove(&session_id);
+    }
+}
+
+/// Forwards feed client events into the fan-out until shutdown.
+async fn pump_feed(mut handle: ClientHandle, fan_out: Arc<FanOut>, token: CancellationToken) {
+    loop {
+        tokio::select! {
+            event = handle.wait_event() => match event {
+                Some(ClientEvent::Message(message)) => fan_out.publish(&message),
+                Some(ClientEvent::Connected) => {
+                    let _ = fan_out.event_tx.try_send(RelayEvent::UpstreamConnected);
+                }
+                Some(ClientEvent::Disconnected) => {
+                    let _ = fan_out.event_tx.try_send(RelayEvent::UpstreamDisconnected);
+                }
+                Some(_) => {}
+                None => return,
+            },
+            _ = token.cancelled() => {
+                handle.disconnect();
+                return;
+            }
+        }
+    }
+}
+
+/// Serves one subscriber: waits for its resume request, sends the replay,
+/// then streams its queue until it disconnects, lags or the relay stops.
+async fn serve_subscriber<C: Connection>(
+    session_id: u64,
+    mut conn: C,
+    fan_out: &FanOut,
+    token: CancellationToken,
+    resume_timeout: Duration,
+) -> Result<(), C::Error> {
+    let last_seq = tokio::select! {
+        resume = tokio::time::timeout(resume_timeout, wait_resume(&mut conn)) => match resume {
+            Ok(Ok(Some(last_seq))) => last_seq,
+            Ok(Ok(None)) => return Ok(()),
+            Ok(Err(e)) => return Err(e),
+            Err(_) => {
+                tracing::warn!("no resume request, closing");
+                return Ok(());
+            }
+        },
+        _ = token.cancelled() => return Ok(()),
+    };
+
+    let (replay, notify) = fan_out.subscribe(session_id, last_seq, token.clone());
+    for frame in replay {
+        conn.send_owned(frame).await?;
+    }
+
+    loop {
+        tokio::select! {
+            _ = notify.notified() => {
+                while let Some(frame) = fan_out.pop(session_id) {
+                    tokio::select! {
+                        sent = conn.send_owned(frame) => sent?,
+                        _ = token.cancelled() => return Ok(()),
+                    }
+                }
+            }
+            received = conn.recv() => match received? {
+                Some(_) => tracing::debug!("ignoring subscriber message after resume"),
+                None => return Ok(()),
+            },
+            _ = token.cancelled() => return Ok(()),
+        }
+    }
+}
+
+/// Reads until the subscriber's resume request.
+///
+/// Returns `None` if the connection closes first.
+async fn wait_resume<C: Connection>(conn: &mut C) -> Result<Option<u64>, C::Error> {
+    while let Some(message) = conn.recv().await? {
+        match RelayFrame::decode(&message) {
+            Some(RelayFrame::Resume { last_seq }) => return Ok(Some(last_seq)),
+            _ => tracing::debug!("ignoring message before resume"),
+        }
+    }
+    Ok(None)
+}
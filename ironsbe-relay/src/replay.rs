@@ -0,0 +1,193 @@
+//! Retained history for subscriber gap recovery.
+//!
+//! The relay keeps the last N published frames plus the newest frame for
+//! every conflation key. A reconnecting subscriber that was away for less
+//! than the window gets exactly what it missed (conflated); one that was
+//! away longer, or is new, gets a snapshot built from the latest frame per
+//! key plus the unkeyed frames still in the window.
+
+use bytes::Bytes;
+use std::collections::{HashMap, VecDeque};
+
+/// Frames to send a resuming subscriber before live traffic.
+#[derive(Debug, Default)]
+pub struct Replay {
+    /// True if the replay does not continue from the subscriber's last
+    /// sequence, so it must discard its state first.
+    pub reset: bool,
+    /// Frames in sequence order.
+    pub frames: Vec<(u64, Bytes)>,
+}
+
+/// Bounded history of published frames.
+#[derive(Debug)]
+pub struct ReplayWindow {
+    capacity: usize,
+    /// Most recent frames, oldest first.
+    entries: VecDeque<(u64, Option<u64>, Bytes)>,
+    /// Newest frame per conflation key, regardless of the window.
+    latest: HashMap<u64, (u64, Bytes)>,
+}
+
+impl ReplayWindow {
+    /// Creates an empty window retaining up to `capacity` frames.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            latest: HashMap::new(),
+        }
+    }
+
+    /// Records a published frame.
+    pub fn push(&mut self, seq: u64, key: Option<u64>, frame: Bytes) {
+        if let Some(key) = key {
+            self.latest.insert(key, (seq, frame.clone()));
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((seq, key, frame));
+    }
+
+    /// Returns the oldest sequence number still in the window.
+    #[must_use]
+    pub fn first_seq(&self) -> Option<u64> {
+        self.entries.front().map(|(seq, _, _)| *seq)
+    }
+
+    /// Returns the number of frames in the window.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the window holds no frames.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Builds the replay for a subscriber whose last applied sequence is
+    /// `last_seq` (0 for a new subscriber), when `next_seq` is the sequence
+    /// the next published frame will get.
+    #[must_use]
+    pub fn replay(&self, last_seq: u64, next_seq: u64) -> Replay {
+        if last_seq != 0 && last_seq + 1 == next_seq {
+            return Replay::default();
+        }
+        // The window covers the gap if it still holds the frame right after
+        // `last_seq`; a subscriber ahead of the relay is never covered
+        let covered =
+            last_seq < next_seq && self.first_seq().is_some_and(|first| first <= last_seq + 1);
+
+        if covered {
+            // Only the newest frame per key is worth sending
+            let frames = self
+                .entries
+                .iter()
+                .filter(|(seq, key, _)| *seq > last_seq && self.is_latest(*seq, *key))
+                .map(|(seq, _, frame)| (*seq, frame.clone()))
+                .collect();
+            return Replay {
+                reset: false,
+                frames,
+            };
+        }
+
+        let mut frames: Vec<_> = self
+            .latest
+            .values()
+            .cloned()
+            .chain(
+                self.entries
+                    .iter()
+                    .filter(|(_, key, _)| key.is_none())
+                    .map(|(seq, _, frame)| (*seq, frame.clone())),
+            )
+            .collect();
+        frames.sort_unstable_by_key(|(seq, _)| *seq);
+        Replay {
+            reset: last_seq != 0,
+            frames,
+        }
+    }
+
+    /// Returns true if `seq` is the newest frame for `key`.
+    fn is_latest(&self, seq: u64, key: Option<u64>) -> bool {
+        key.is_none_or(|key| self.latest.get(&key).is_some_and(|(s, _)| *s == seq))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(frames: &[(u64, Option<u64>)], capacity: usize) -> ReplayWindow {
+        let mut window = ReplayWindow::new(capacity);
+        for &(seq, key) in frames {
+            window.push(seq, key, Bytes::from(seq.to_le_bytes().to_vec()));
+        }
+        window
+    }
+
+    fn seqs(replay: &Replay) -> Vec<u64> {
+        replay.frames.iter().map(|(seq, _)| *seq).collect()
+    }
+
+    #[test]
+    fn test_replay_up_to_date() {
+        let window = window(&[(1, None), (2, None)], 8);
+        let replay = window.replay(2, 3);
+        assert!(!replay.reset);
+        assert!(replay.frames.is_empty());
+    }
+
+    #[test]
+    fn test_replay_within_window_is_conflated() {
+        let window = window(&[(1, Some(7)), (2, None), (3, Some(7)), (4, Some(8))], 8);
+        let replay = window.replay(1, 5);
+        assert!(!replay.reset);
+        assert_eq!(seqs(&replay), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_replay_beyond_window_resets() {
+        let window = window(
+            &[(1, Some(7)), (2, None), (3, Some(8)), (4, None), (5, None)],
+            2,
+        );
+        assert_eq!(window.first_seq(), Some(4));
+
+        let replay = window.replay(1, 6);
+        assert!(replay.reset);
+        // Latest per key plus the unkeyed frames still retained
+        assert_eq!(seqs(&replay), vec![1, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_replay_new_subscriber() {
+        let full = window(&[(1, Some(7)), (2, Some(7)), (3, None)], 8);
+        let replay = full.replay(0, 4);
+        assert!(!replay.reset);
+        assert_eq!(seqs(&replay), vec![2, 3]);
+
+        let trimmed = window(&[(1, Some(7)), (2, None), (3, None)], 1);
+        let replay = trimmed.replay(0, 4);
+        assert!(!replay.reset);
+        assert_eq!(seqs(&replay), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_replay_subscriber_ahead_of_relay() {
+        // The relay restarted and its sequence went back
+        let window = window(&[(1, Some(7))], 8);
+        let replay = window.replay(10, 2);
+        assert!(replay.reset);
+        assert_eq!(seqs(&replay), vec![1]);
+    }
+}
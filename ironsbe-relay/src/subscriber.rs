@@ -0,0 +1,115 @@
+//! Remote side of a relay link.
+
+use crate::protocol::{RelayFrame, encode_resume};
+use bytes::Bytes;
+
+/// What a relay frame means to the subscriber.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriberEvent<'a> {
+    /// Feed message to apply.
+    Message {
+        /// Relay sequence number of the message.
+        seq: u64,
+        /// Feed message, including its SBE header.
+        message: &'a [u8],
+    },
+    /// The relay could not replay everything that was missed; discard all
+    /// state derived from earlier messages before applying what follows.
+    Reset,
+}
+
+/// Tracks a subscriber's position in the relay stream.
+///
+/// Keep one per remote consumer across reconnects: send
+/// [`resume_request`](Self::resume_request) as the first message on every
+/// new connection, then pass each received message to
+/// [`on_frame`](Self::on_frame).
+#[derive(Debug, Default, Clone)]
+pub struct RelaySubscriber {
+    last_seq: u64,
+}
+
+impl RelaySubscriber {
+    /// Creates a subscriber that has not applied any message.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the last sequence number applied (0 = none).
+    #[must_use]
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    /// Encodes the resume request for a new connection.
+    #[must_use]
+    pub fn resume_request(&self) -> Bytes {
+        encode_resume(self.last_seq)
+    }
+
+    /// Interprets a message received from the relay.
+    ///
+    /// Returns `None` for frames that are not relay frames and for data the
+    /// subscriber has already applied.
+    pub fn on_frame<'a>(&mut self, frame: &'a [u8]) -> Option<SubscriberEvent<'a>> {
+        match RelayFrame::decode(frame)? {
+            RelayFrame::Data { seq, message } if seq > self.last_seq => {
+                self.last_seq = seq;
+                Some(SubscriberEvent::Message { seq, message })
+            }
+            RelayFrame::Reset { .. } => {
+                // Snapshot frames may be older than anything seen so far
+                self.last_seq = 0;
+                Some(SubscriberEvent::Reset)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{encode_data, encode_reset};
+
+    #[test]
+    fn test_subscriber_tracks_sequence() {
+        let mut subscriber = RelaySubscriber::new();
+        assert_eq!(
+            RelayFrame::decode(&subscriber.resume_request()),
+            Some(RelayFrame::Resume { last_seq: 0 })
+        );
+
+        let frame = encode_data(3, b"msg");
+        assert_eq!(
+            subscriber.on_frame(&frame),
+            Some(SubscriberEvent::Message {
+                seq: 3,
+                message: b"msg",
+            })
+        );
+        assert_eq!(subscriber.last_seq(), 3);
+
+        // Duplicates from an overlapping replay are skipped
+        assert_eq!(subscriber.on_frame(&frame), None);
+        assert_eq!(
+            RelayFrame::decode(&subscriber.resume_request()),
+            Some(RelayFrame::Resume { last_seq: 3 })
+        );
+    }
+
+    #[test]
+    fn test_subscriber_reset_accepts_snapshot() {
+        let mut subscriber = RelaySubscriber::new();
+        subscriber.on_frame(&encode_data(10, b"old"));
+
+        assert_eq!(
+            subscriber.on_frame(&encode_reset(50)),
+            Some(SubscriberEvent::Reset)
+        );
+        // Snapshot frames are older than the subscriber's last sequence
+        assert!(subscriber.on_frame(&encode_data(4, b"snap")).is_some());
+        assert_eq!(subscriber.last_seq(), 4);
+    }
+}
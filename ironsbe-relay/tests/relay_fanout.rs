@@ -0,0 +1,236 @@
+//! End-to-end fan-out through a relay.
+//!
+//! A plain IronSBE server plays the local feed; the relay subscribes to it
+//! and remote subscribers connect to the relay over TCP, dropping and
+//! resuming their connection to exercise gap recovery.
+
+#![cfg(feature = "tcp-tokio")]
+
+use ironsbe_core::header::MessageHeader;
+use ironsbe_relay::{RelayBuilder, RelayEvent, RelayHandle, RelaySubscriber, SubscriberEvent};
+use ironsbe_server::{MessageHandler, Responder, ServerBuilder, ServerEvent, ServerHandle};
+use ironsbe_transport::DefaultTransport;
+use ironsbe_transport::traits::Transport;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The feed never expects inbound messages.
+struct NoopHandler;
+
+impl MessageHandler for NoopHandler {
+    fn on_message(
+        &self,
+        _session_id: u64,
+        _header: &MessageHeader,
+        _buffer: &[u8],
+        _responder: &dyn Responder,
+    ) {
+    }
+}
+
+/// Builds a one-byte feed message for `template_id`.
+fn feed_message(template_id: u16, value: u8) -> Vec<u8> {
+    let mut message = vec![0u8; MessageHeader::ENCODED_LENGTH + 1];
+    MessageHeader::new(1, template_id, 1, 1).encode(&mut message[..], 0);
+    message[MessageHeader::ENCODED_LENGTH] = value;
+    message
+}
+
+/// Polls relay events until `matches` accepts one, returning it.
+async fn wait_relay_event(
+    handle: &RelayHandle,
+    mut matches: impl FnMut(&RelayEvent) -> bool,
+) -> RelayEvent {
+    let deadline = Instant::now() + TIMEOUT;
+    while Instant::now() < deadline {
+        if let Some(event) = handle.poll_events().find(&mut matches) {
+            return event;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    panic!("relay event not observed within {TIMEOUT:?}");
+}
+
+/// Starts the feed server and returns its handle and address.
+async fn start_feed() -> (ServerHandle, SocketAddr) {
+    let (mut server, handle) = ServerBuilder::<NoopHandler>::new()
+        .bind("127.0.0.1:0".parse().unwrap())
+        .handler(NoopHandler)
+        .build();
+    tokio::spawn(async move {
+        let _ = server.run().await;
+    });
+
+    let deadline = Instant::now() + TIMEOUT;
+    while Instant::now() < deadline {
+        let listening = handle.poll_events().find_map(|event| match event {
+            ServerEvent::Listening(addr) => Some(addr),
+            _ => None,
+        });
+        if let Some(addr) = listening {
+            return (handle, addr);
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    panic!("feed did not start listening");
+}
+
+/// Starts a relay on the feed and waits until it is connected.
+async fn start_relay(feed: &ServerHandle, builder: RelayBuilder) -> (RelayHandle, SocketAddr) {
+    let (mut relay, handle) = builder.bind("127.0.0.1:0".parse().unwrap()).build();
+    tokio::spawn(async move {
+        let _ = relay.run().await;
+    });
+
+    let RelayEvent::Listening(addr) =
+        wait_relay_event(&handle, |e| matches!(e, RelayEvent::Listening(_))).await
+    else {
+        unreachable!()
+    };
+    wait_relay_event(&handle, |e| *e == RelayEvent::UpstreamConnected).await;
+
+    // Broadcasts only reach sessions the feed has registered
+    let deadline = Instant::now() + TIMEOUT;
+    while !feed
+        .poll_events()
+        .any(|e| matches!(e, ServerEvent::SessionCreated(..)))
+    {
+        assert!(Instant::now() < deadline, "feed never saw the relay");
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    (handle, addr)
+}
+
+/// Connects a subscriber to the relay and waits until it is live.
+async fn subscribe(
+    relay: &RelayHandle,
+    addr: SocketAddr,
+    subscriber: &RelaySubscriber,
+) -> <DefaultTransport as Transport>::Connection {
+    let mut conn = DefaultTransport::connect(addr).await.unwrap();
+    conn.send(&subscriber.resume_request()).await.unwrap();
+    wait_relay_event(relay, |e| matches!(e, RelayEvent::SubscriberResumed { .. })).await;
+    conn
+}
+
+/// Receives relay frames until `count` messages have been applied.
+///
+/// Returns the payload bytes applied, with `None` marking a reset.
+async fn receive(
+    conn: &mut <DefaultTransport as Transport>::Connection,
+    subscriber: &mut RelaySubscriber,
+    count: usize,
+) -> Vec<Option<u8>> {
+    let mut applied = Vec::new();
+    let mut messages = 0;
+    while messages < count {
+        let frame = tokio::time::timeout(TIMEOUT, conn.recv())
+            .await
+            .expect("timed out waiting for relay frame")
+            .unwrap()
+            .expect("relay closed the connection");
+        match subscriber.on_frame(&frame) {
+            Some(SubscriberEvent::Message { message, .. }) => {
+                applied.push(Some(message[MessageHeader::ENCODED_LENGTH]));
+                messages += 1;
+            }
+            Some(SubscriberEvent::Reset) => applied.push(None),
+            None => {}
+        }
+    }
+    applied
+}
+
+/// Receives relay frames until the subscriber has applied `seq`.
+async fn catch_up(
+    conn: &mut <DefaultTransport as Transport>::Connection,
+    subscriber: &mut RelaySubscriber,
+    seq: u64,
+) {
+    while subscriber.last_seq() < seq {
+        receive(conn, subscriber, 1).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_relay_fans_out_and_resumes() {
+    let (feed, feed_addr) = start_feed().await;
+    let (relay, relay_addr) = start_relay(&feed, RelayBuilder::new(feed_addr)).await;
+
+    let mut first = RelaySubscriber::new();
+    let mut second = RelaySubscriber::new();
+    let mut first_conn = subscribe(&relay, relay_addr, &first).await;
+    let mut second_conn = subscribe(&relay, relay_addr, &second).await;
+
+    for value in 1..=3 {
+        feed.broadcast(feed_message(1, value));
+    }
+    assert_eq!(
+        receive(&mut first_conn, &mut first, 3).await,
+        vec![Some(1), Some(2), Some(3)]
+    );
+    assert_eq!(
+        receive(&mut second_conn, &mut second, 3).await,
+        vec![Some(1), Some(2), Some(3)]
+    );
+
+    // Drop the first subscriber and publish while it is away
+    drop(first_conn);
+    wait_relay_event(&relay, |e| matches!(e, RelayEvent::SubscriberClosed(_))).await;
+    for value in 4..=5 {
+        feed.broadcast(feed_message(1, value));
+    }
+    assert_eq!(
+        receive(&mut second_conn, &mut second, 2).await,
+        vec![Some(4), Some(5)]
+    );
+
+    let mut first_conn = subscribe(&relay, relay_addr, &first).await;
+    assert_eq!(
+        receive(&mut first_conn, &mut first, 2).await,
+        vec![Some(4), Some(5)]
+    );
+    assert_eq!(first.last_seq(), 5);
+
+    relay.shutdown();
+    feed.shutdown();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_relay_resets_subscriber_beyond_window() {
+    let (feed, feed_addr) = start_feed().await;
+    let builder = RelayBuilder::new(feed_addr)
+        .replay_capacity(2)
+        .conflator(|header: &MessageHeader, _: &[u8]| Some(u64::from(header.template_id)));
+    let (relay, relay_addr) = start_relay(&feed, builder).await;
+
+    let mut subscriber = RelaySubscriber::new();
+    let mut conn = subscribe(&relay, relay_addr, &subscriber).await;
+    // Stays connected to tell when the relay has published everything
+    let mut witness = RelaySubscriber::new();
+    let mut witness_conn = subscribe(&relay, relay_addr, &witness).await;
+
+    feed.broadcast(feed_message(1, 1));
+    assert_eq!(receive(&mut conn, &mut subscriber, 1).await, vec![Some(1)]);
+    drop(conn);
+    wait_relay_event(&relay, |e| matches!(e, RelayEvent::SubscriberClosed(_))).await;
+
+    // Four updates across two keys overflow the two-frame window
+    for (template_id, value) in [(1, 2), (2, 3), (1, 4), (2, 5)] {
+        feed.broadcast(feed_message(template_id, value));
+    }
+    catch_up(&mut witness_conn, &mut witness, 5).await;
+
+    // The subscriber gets a reset followed by the latest frame per key
+    let mut conn = subscribe(&relay, relay_addr, &subscriber).await;
+    assert_eq!(
+        receive(&mut conn, &mut subscriber, 2).await,
+        vec![None, Some(4), Some(5)]
+    );
+    assert_eq!(subscriber.last_seq(), 5);
+
+    relay.shutdown();
+    feed.shutdown();
+}
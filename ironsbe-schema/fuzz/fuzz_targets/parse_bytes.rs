@@ -0,0 +1,15 @@
+//! Feeds arbitrary bytes to the schema parser.
+//!
+//! Mostly exercises the XML layer; `parse_schema` reaches the SBE layout
+//! code far more often.
+
+#![no_main]
+
+use ironsbe_schema::parse_schema;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(xml) = std::str::from_utf8(data) {
+        let _ = parse_schema(xml);
+    }
+});
@@ -0,0 +1,127 @@
+//! Structured fuzzing of the schema parser.
+//!
+//! Starts from a valid schema and applies a sequence of edits: deleting or
+//! duplicating spans, inserting SBE fragments and extreme numbers, and
+//! nesting groups or composites deeply. Inputs stay close enough to a real
+//! schema to get past the XML layer, and every schema that parses is also
+//! validated and lowered to the IR. Nothing may panic, overflow the stack or
+//! hang.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use ironsbe_schema::validation::validate_schema;
+use ironsbe_schema::{SchemaIr, parse_schema};
+use libfuzzer_sys::fuzz_target;
+
+const SEEDS: &[&str] = &[
+    include_str!("../../../ironsbe-codegen-tests/schemas/round_trip.xml"),
+    include_str!("../../../ironsbe-codegen-tests/schemas/nested_groups.xml"),
+    include_str!("../../../ironsbe-codegen-tests/schemas/presence_bitmap.xml"),
+    include_str!("../../../ironsbe-codegen-tests/schemas/cross_lang.xml"),
+    include_str!("../../../ironsbe-bench/schemas/mass_quote.xml"),
+];
+
+const FRAGMENTS: &[&str] = &[
+    r#"<group name="g" id="90">"#,
+    "</group>",
+    r#"<composite name="c">"#,
+    "</composite>",
+    r#"<ref name="r" type="c"/>"#,
+    r#"<enum name="e" encodingType="char">"#,
+    "</enum>",
+    r#"<set name="s" encodingType="uint8">"#,
+    "</set>",
+    r#"<validValue name="v"/>"#,
+    r#"<choice name="b">63</choice>"#,
+    r#"<type name="t" primitiveType="uint64" length="0"/>"#,
+    r#"<field name="f" id="91" type="c"/>"#,
+    r#"<data name="d" id="92" type="t"/>"#,
+    r#" offset="65535""#,
+    r#" length="65535""#,
+    r#" blockLength="0""#,
+    r#" presence="constant""#,
+    r#" presence="optional""#,
+    r#" dimensionType="c""#,
+    r#" sinceVersion="65535""#,
+    r#" nullValue="x""#,
+    "18446744073709551616",
+    "4294967295",
+    "-1",
+    "&amp;",
+    "\"",
+    "<",
+    ">",
+    "/>",
+];
+
+#[derive(Debug, Arbitrary)]
+enum Element {
+    Group,
+    Composite,
+}
+
+#[derive(Debug, Arbitrary)]
+enum Edit {
+    Delete { at: u16, len: u8 },
+    Duplicate { from: u16, len: u8, to: u16 },
+    Insert { at: u16, fragment: u8 },
+    Text { at: u16, text: String },
+    Nest { at: u16, element: Element, depth: u16 },
+}
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    seed: u8,
+    edits: Vec<Edit>,
+}
+
+/// Returns the char boundary at or before `at`, wrapped into `xml`.
+fn position(xml: &str, at: impl Into<usize>) -> usize {
+    let mut at = at.into() % (xml.len() + 1);
+    while !xml.is_char_boundary(at) {
+        at -= 1;
+    }
+    at
+}
+
+fn apply(xml: &mut String, edit: &Edit) {
+    match edit {
+        Edit::Delete { at, len } => {
+            let start = position(xml, *at);
+            let end = position(xml, start + usize::from(*len)).max(start);
+            xml.replace_range(start..end, "");
+        }
+        Edit::Duplicate { from, len, to } => {
+            let start = position(xml, *from);
+            let end = position(xml, start + usize::from(*len)).max(start);
+            let span = xml[start..end].to_string();
+            xml.insert_str(position(xml, *to), &span);
+        }
+        Edit::Insert { at, fragment } => {
+            let fragment = FRAGMENTS[usize::from(*fragment) % FRAGMENTS.len()];
+            xml.insert_str(position(xml, *at), fragment);
+        }
+        Edit::Text { at, text } => xml.insert_str(position(xml, *at), text),
+        Edit::Nest { at, element, depth } => {
+            let (open, close) = match element {
+                Element::Group => (r#"<group name="n" id="93">"#, "</group>"),
+                Element::Composite => (r#"<composite name="n">"#, "</composite>"),
+            };
+            let depth = usize::from(*depth);
+            let nested = format!("{}{}", open.repeat(depth), close.repeat(depth));
+            xml.insert_str(position(xml, *at), &nested);
+        }
+    }
+}
+
+fuzz_target!(|input: Input| {
+    let mut xml = SEEDS[usize::from(input.seed) % SEEDS.len()].to_string();
+    for edit in input.edits.iter().take(16) {
+        apply(&mut xml, edit);
+    }
+    if let Ok(schema) = parse_schema(&xml) {
+        let _ = validate_schema(&schema);
+        let _ = SchemaIr::from_schema(&schema);
+    }
+});
@@ -0,0 +1,881 @@
+//! Schema-driven decoding without generated code.
+//!
+//! [`DynamicMessage`] walks an encoded message using a [`SchemaIr`] loaded
+//! at runtime, for tools that must handle schemas unknown at compile time:
+//! dumpers, bridges, monitors. Fields are read by name as [`SbeValue`]s,
+//! repeating groups and var data are reached through the
+//! [`DynamicBlock`] holding them.
+//!
+//! Decoding checks the whole message against the buffer up front, so the
+//! accessors cannot fail afterwards. Like the generated code, values are
+//! read little-endian.
+
+use crate::error::DynamicError;
+use crate::ir::{
+    ConstantValue, ResolvedField, ResolvedGroup, ResolvedMessage, ResolvedVarData, SchemaIr,
+    TypeKind,
+};
+use crate::types::PrimitiveType;
+use ironsbe_core::header::MessageHeader;
+use std::fmt;
+use std::ops::Deref;
+
+/// Value of a field read through a [`DynamicMessage`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SbeValue<'a> {
+    /// Signed integer of any width.
+    Int(i64),
+    /// Unsigned integer of any width.
+    UInt(u64),
+    /// `float` or `double`.
+    Float(f64),
+    /// Single `char`.
+    Char(u8),
+    /// `char` array, padding included.
+    Chars(&'a [u8]),
+    /// `uint8` array, or bytes of a type the schema does not define.
+    Bytes(&'a [u8]),
+    /// Array of any other primitive.
+    Array(Vec<SbeValue<'a>>),
+    /// Enum value, with the name of its variant if the schema defines one.
+    Enum {
+        /// Variant name, `None` for a value the schema does not list.
+        name: Option<&'a str>,
+        /// Encoded value.
+        value: i64,
+    },
+    /// Set value, with the names of the choices it holds.
+    Set {
+        /// Encoded bits.
+        raw: u64,
+        /// Names of the set choices, in schema order.
+        choices: Vec<&'a str>,
+    },
+    /// Composite value, as its members in schema order.
+    Composite(Vec<(&'a str, SbeValue<'a>)>),
+    /// Optional field holding its null value.
+    Null,
+}
+
+impl SbeValue<'_> {
+    /// Returns true for [`SbeValue::Null`].
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    /// Returns the value as an `i64` if it is an integer, character or enum
+    /// that fits.
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Self::Int(v) => Some(v),
+            Self::UInt(v) => i64::try_from(v).ok(),
+            Self::Char(c) => Some(i64::from(c)),
+            Self::Enum { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a `u64` if it is a non-negative integer,
+    /// character or set.
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Self::Int(v) => u64::try_from(v).ok(),
+            Self::UInt(v) => Some(v),
+            Self::Char(c) => Some(u64::from(c)),
+            Self::Set { raw, .. } => Some(raw),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an `f64` if it is numeric.
+    #[must_use]
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::Int(v) => Some(v as f64),
+            Self::UInt(v) => Some(v as f64),
+            Self::Float(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes of a `char` or `uint8` array.
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Chars(bytes) | Self::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Returns a `char` array as a string, without its trailing padding.
+    ///
+    /// Returns `None` for other values or if the bytes are not UTF-8.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        let Self::Chars(bytes) = self else {
+            return None;
+        };
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        std::str::from_utf8(&bytes[..end]).ok()
+    }
+}
+
+impl fmt::Display for SbeValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(v) => write!(f, "{v}"),
+            Self::UInt(v) => write!(f, "{v}"),
+            Self::Float(v) => write!(f, "{v}"),
+            Self::Char(c) => write!(f, "'{}'", char::from(*c).escape_default()),
+            Self::Chars(bytes) => match self.as_str() {
+                Some(s) => write!(f, "{s:?}"),
+                None => write!(f, "{bytes:02x?}"),
+            },
+            Self::Bytes(bytes) => write!(f, "{bytes:02x?}"),
+            Self::Array(values) => {
+                f.write_str("[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_str("]")
+            }
+            Self::Enum {
+                name: Some(name), ..
+            } => f.write_str(name),
+            Self::Enum { name: None, value } => write!(f, "{value}"),
+            Self::Set { choices, .. } => write!(f, "{{{}}}", choices.join(", ")),
+            Self::Composite(members) => {
+                f.write_str("{")?;
+                for (i, (name, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{name}: {value}")?;
+                }
+                f.write_str("}")
+            }
+            Self::Null => f.write_str("null"),
+        }
+    }
+}
+
+/// A field and its value, as yielded by [`DynamicBlock::fields`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicField<'a> {
+    /// Field name.
+    pub name: &'a str,
+    /// Field ID.
+    pub id: u16,
+    /// Decoded value.
+    pub value: SbeValue<'a>,
+}
+
+/// A message decoded against a [`SchemaIr`].
+///
+/// Dereferences to the [`DynamicBlock`] of its root fields, groups and var
+/// data.
+#[derive(Debug, Clone)]
+pub struct DynamicMessage<'a> {
+    message: &'a ResolvedMessage,
+    header: MessageHeader,
+    body: DynamicBlock<'a>,
+    encoded_length: usize,
+}
+
+impl<'a> DynamicMessage<'a> {
+    /// Decodes the message starting with its header at the start of
+    /// `buffer`.
+    ///
+    /// The root block length is taken from the header, so messages from
+    /// older or newer schema versions decode as long as their blocks only
+    /// grew: fields past the end of a shorter block are left out. The
+    /// header is read with the schema's layout, extensions included.
+    ///
+    /// # Errors
+    /// Returns an error if the header names another schema or an unknown
+    /// template, or if the buffer ends before the message does.
+    pub fn decode(ir: &'a SchemaIr, buffer: &'a [u8]) -> Result<Self, DynamicError> {
+        let mut cursor = Cursor { buffer, offset: 0 };
+        let layout = &ir.message_header;
+        let bytes = cursor.take(layout.encoded_length)?;
+        let member =
+            |name| read_uint(PrimitiveType::Uint16, &bytes[layout.offset_of(name)..]) as u16;
+        let header = MessageHeader::new(
+            member("blockLength"),
+            member("templateId"),
+            member("schemaId"),
+            member("version"),
+        );
+        let (schema_id, template_id) = (header.schema_id, header.template_id);
+        if schema_id != ir.schema_id {
+            return Err(DynamicError::SchemaMismatch {
+                expected: ir.schema_id,
+                actual: schema_id,
+            });
+        }
+        let message = ir
+            .messages
+            .iter()
+            .find(|m| m.template_id == template_id)
+            .ok_or(DynamicError::UnknownTemplate(template_id))?;
+
+        let body = DynamicBlock::decode(
+            ir,
+            &mut cursor,
+            usize::from(header.block_length),
+            &message.fields,
+            &message.groups,
+            &message.var_data,
+        )?;
+        Ok(Self {
+            message,
+            header,
+            body,
+            encoded_length: cursor.offset,
+        })
+    }
+
+    /// Returns the message name.
+    #[must_use]
+    pub fn name(&self) -> &'a str {
+        &self.message.name
+    }
+
+    /// Returns the template ID.
+    #[must_use]
+    pub fn template_id(&self) -> u16 {
+        self.message.template_id
+    }
+
+    /// Returns the standard members of the message header.
+    #[must_use]
+    pub fn header(&self) -> MessageHeader {
+        self.header
+    }
+
+    /// Returns the encoded length of the message, header included.
+    #[must_use]
+    pub fn encoded_length(&self) -> usize {
+        self.encoded_length
+    }
+}
+
+impl<'a> Deref for DynamicMessage<'a> {
+    type Target = DynamicBlock<'a>;
+
+    fn deref(&self) -> &DynamicBlock<'a> {
+        &self.body
+    }
+}
+
+/// Fields, groups and var data of a message or of a group entry.
+#[derive(Debug, Clone)]
+pub struct DynamicBlock<'a> {
+    ir: &'a SchemaIr,
+    fields: &'a [ResolvedField],
+    block: &'a [u8],
+    groups: Vec<DynamicGroup<'a>>,
+    var_data: Vec<(&'a ResolvedVarData, &'a [u8])>,
+}
+
+impl<'a> DynamicBlock<'a> {
+    /// Decodes a block of `block_length` bytes and its trailing sections.
+    fn decode(
+        ir: &'a SchemaIr,
+        cursor: &mut Cursor<'a>,
+        block_length: usize,
+        fields: &'a [ResolvedField],
+        groups: &'a [ResolvedGroup],
+        var_data: &'a [ResolvedVarData],
+    ) -> Result<Self, DynamicError> {
+        let block = cursor.take(block_length)?;
+        let groups = groups
+            .iter()
+            .map(|group| DynamicGroup::decode(ir, cursor, group))
+            .collect::<Result<_, _>>()?;
+        let var_data = var_data
+            .iter()
+            .map(|data| {
+                let prefix = cursor.take(data.header_length())?;
+                let len = read_uint(data.length_type, prefix);
+                let len = usize::try_from(len).unwrap_or(usize::MAX);
+                Ok((data, cursor.take(len)?))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            ir,
+            fields,
+            block,
+            groups,
+            var_data,
+        })
+    }
+
+    /// Returns the fields present in the block, in schema order.
+    ///
+    /// Constants are included with their schema value.
+    pub fn fields(&self) -> impl Iterator<Item = DynamicField<'a>> + '_ {
+        self.fields.iter().filter_map(|field| {
+            Some(DynamicField {
+                name: &field.name,
+                id: field.id,
+                value: self.value(field)?,
+            })
+        })
+    }
+
+    /// Returns the value of the field `name`.
+    ///
+    /// Returns `None` if there is no such field, or if it lies past the
+    /// end of a block encoded by an older schema version.
+    #[must_use]
+    pub fn field(&self, name: &str) -> Option<SbeValue<'a>> {
+        self.value(self.fields.iter().find(|f| f.name == name)?)
+    }
+
+    /// Returns the repeating groups, in schema order.
+    #[must_use]
+    pub fn groups(&self) -> &[DynamicGroup<'a>] {
+        &self.groups
+    }
+
+    /// Returns the repeating group `name`.
+    #[must_use]
+    pub fn group(&self, name: &str) -> Option<&DynamicGroup<'a>> {
+        self.groups.iter().find(|g| g.name() == name)
+    }
+
+    /// Returns the var data fields and their bytes, in schema order.
+    pub fn var_data_fields(&self) -> impl Iterator<Item = (&'a str, &'a [u8])> + '_ {
+        self.var_data
+            .iter()
+            .map(|(data, bytes)| (data.name.as_str(), *bytes))
+    }
+
+    /// Returns the bytes of the var data field `name`.
+    #[must_use]
+    pub fn var_data(&self, name: &str) -> Option<&'a [u8]> {
+        self.var_data
+            .iter()
+            .find(|(data, _)| data.name == name)
+            .map(|(_, bytes)| *bytes)
+    }
+
+    /// Decodes `field`, or returns `None` if it lies past the block.
+    fn value(&self, field: &'a ResolvedField) -> Option<SbeValue<'a>> {
+        if let Some(constant) = &field.constant {
+            return Some(constant_value(constant, field.primitive_type));
+        }
+        let bytes = self
+            .block
+            .get(field.offset..field.offset + field.encoded_length)?;
+        let value = decode_value(self.ir, &field.type_name, field.primitive_type, bytes);
+        let is_null = field.is_optional && !field.is_array && is_null(field, &value);
+        Some(if is_null { SbeValue::Null } else { value })
+    }
+}
+
+/// A repeating group decoded against a [`SchemaIr`].
+#[derive(Debug, Clone)]
+pub struct DynamicGroup<'a> {
+    group: &'a ResolvedGroup,
+    entries: Vec<DynamicBlock<'a>>,
+}
+
+impl<'a> DynamicGroup<'a> {
+    /// Decodes the group starting with its dimension header.
+    fn decode(
+        ir: &'a SchemaIr,
+        cursor: &mut Cursor<'a>,
+        group: &'a ResolvedGroup,
+    ) -> Result<Self, DynamicError> {
+        let dimension = &group.dimension;
+        let header = cursor.take(dimension.encoded_length)?;
+        let member = |prim: PrimitiveType, offset: usize| {
+            let value = read_uint(prim, &header[offset..offset + prim.size()]);
+            usize::try_from(value).unwrap_or(usize::MAX)
+        };
+        let block_length = member(dimension.block_length_type, dimension.block_length_offset);
+        let count = member(dimension.num_in_group_type, dimension.num_in_group_offset);
+
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            entries.push(DynamicBlock::decode(
+                ir,
+                cursor,
+                block_length,
+                &group.fields,
+                &group.nested_groups,
+                &group.var_data,
+            )?);
+        }
+        Ok(Self { group, entries })
+    }
+
+    /// Returns the group name.
+    #[must_use]
+    pub fn name(&self) -> &'a str {
+        &self.group.name
+    }
+
+    /// Returns the group ID.
+    #[must_use]
+    pub fn id(&self) -> u16 {
+        self.group.id
+    }
+
+    /// Returns the number of entries.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the group has no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entries, in encoded order.
+    #[must_use]
+    pub fn entries(&self) -> &[DynamicBlock<'a>] {
+        &self.entries
+    }
+
+    /// Returns an iterator over the entries.
+    pub fn iter(&self) -> std::slice::Iter<'_, DynamicBlock<'a>> {
+        self.entries.iter()
+    }
+}
+
+impl<'g, 'a> IntoIterator for &'g DynamicGroup<'a> {
+    type Item = &'g DynamicBlock<'a>;
+    type IntoIter = std::slice::Iter<'g, DynamicBlock<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Bounds-checked reader over the message buffer.
+struct Cursor<'a> {
+    buffer: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Returns the next `len` bytes and advances past them.
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DynamicError> {
+        let end = self.offset.saturating_add(len);
+        let bytes = self
+            .buffer
+            .get(self.offset..end)
+            .ok_or(DynamicError::BufferTooShort {
+                required: end,
+                available: self.buffer.len(),
+            })?;
+        self.offset = end;
+        Ok(bytes)
+    }
+}
+
+/// Decodes `bytes` as the type `type_name`, or as `prim` if the schema
+/// does not define it.
+fn decode_value<'a>(
+    ir: &'a SchemaIr,
+    type_name: &str,
+    prim: Option<PrimitiveType>,
+    bytes: &'a [u8],
+) -> SbeValue<'a> {
+    match ir.get_type(type_name).map(|t| &t.kind) {
+        Some(TypeKind::Enum { encoding, variants }) => {
+            let value = read_int(*encoding, bytes);
+            let name = variants
+                .iter()
+                .find(|v| v.value == value)
+                .map(|v| v.name.as_str());
+            SbeValue::Enum { name, value }
+        }
+        Some(TypeKind::Set { encoding, choices }) => {
+            let raw = read_uint(*encoding, bytes);
+            let choices = choices
+                .iter()
+                .filter(|c| c.bit_position < 64 && raw & (1 << c.bit_position) != 0)
+                .map(|c| c.name.as_str())
+                .collect();
+            SbeValue::Set { raw, choices }
+        }
+        Some(TypeKind::Composite { fields }) => SbeValue::Composite(
+            fields
+                .iter()
+                .map(|member| {
+                    let value = match &member.constant {
+                        Some(constant) => constant_value(constant, member.primitive_type),
+                        None => decode_value(
+                            ir,
+                            &member.type_name,
+                            member.primitive_type,
+                            &bytes[member.offset..member.offset + member.encoded_length],
+                        ),
+                    };
+                    (member.name.as_str(), value)
+                })
+                .collect(),
+        ),
+        Some(TypeKind::Primitive(prim)) => decode_primitive(*prim, bytes),
+        None => match prim {
+            Some(prim) => decode_primitive(prim, bytes),
+            None => SbeValue::Bytes(bytes),
+        },
+    }
+}
+
+/// Decodes a primitive value or array of `prim`.
+fn decode_primitive(prim: PrimitiveType, bytes: &[u8]) -> SbeValue<'_> {
+    if bytes.len() == prim.size() {
+        return decode_scalar(prim, bytes);
+    }
+    match prim {
+        PrimitiveType::Char => SbeValue::Chars(bytes),
+        PrimitiveType::Uint8 => SbeValue::Bytes(bytes),
+        _ => SbeValue::Array(
+            bytes
+                .chunks_exact(prim.size())
+                .map(|chunk| decode_scalar(prim, chunk))
+                .collect(),
+        ),
+    }
+}
+
+/// Decodes a single value of `prim`.
+fn decode_scalar(prim: PrimitiveType, bytes: &[u8]) -> SbeValue<'_> {
+    match prim {
+        PrimitiveType::Char => SbeValue::Char(bytes[0]),
+        PrimitiveType::Float => SbeValue::Float(f64::from(f32::from_le_bytes(
+            bytes.try_into().expect("float is 4 bytes"),
+        ))),
+        PrimitiveType::Double => SbeValue::Float(f64::from_le_bytes(
+            bytes.try_into().expect("double is 8 bytes"),
+        )),
+        p if p.is_signed() => SbeValue::Int(read_int(p, bytes)),
+        p => SbeValue::UInt(read_uint(p, bytes)),
+    }
+}
+
+/// Reads an integer of `prim`, sign-extending signed encodings.
+fn read_int(prim: PrimitiveType, bytes: &[u8]) -> i64 {
+    match prim {
+        PrimitiveType::Int8 => i64::from(bytes[0] as i8),
+        PrimitiveType::Int16 => i64::from(i16::from_le_bytes([bytes[0], bytes[1]])),
+        PrimitiveType::Int32 => i64::from(i32::from_le_bytes(
+            bytes[..4].try_into().expect("int32 is 4 bytes"),
+        )),
+        PrimitiveType::Int64 => {
+            i64::from_le_bytes(bytes[..8].try_into().expect("int64 is 8 bytes"))
+        }
+        p => read_uint(p, bytes) as i64,
+    }
+}
+
+/// Reads an unsigned integer of `prim`, zero-extended to 64 bits.
+fn read_uint(prim: PrimitiveType, bytes: &[u8]) -> u64 {
+    let mut word = [0u8; 8];
+    let len = prim.size().min(8);
+    word[..len].copy_from_slice(&bytes[..len]);
+    u64::from_le_bytes(word)
+}
+
+/// Returns the value of a constant of `prim`.
+fn constant_value(constant: &ConstantValue, prim: Option<PrimitiveType>) -> SbeValue<'_> {
+    match constant {
+        ConstantValue::Enum { variant, value, .. } => SbeValue::Enum {
+            name: Some(variant),
+            value: *value,
+        },
+        ConstantValue::Literal(literal) => {
+            let text = literal.trim();
+            let parsed = match prim {
+                Some(PrimitiveType::Char) if text.len() == 1 => {
+                    Some(SbeValue::Char(text.as_bytes()[0]))
+                }
+                Some(PrimitiveType::Float | PrimitiveType::Double) => {
+                    text.parse().ok().map(SbeValue::Float)
+                }
+                Some(p) if p.is_signed() => text.parse().ok().map(SbeValue::Int),
+                Some(p) if p.is_unsigned() => text.parse().ok().map(SbeValue::UInt),
+                _ => None,
+            };
+            parsed.unwrap_or(SbeValue::Chars(literal.as_bytes()))
+        }
+    }
+}
+
+/// Returns true if `value` is the null value of the optional `field`.
+///
+/// Uses the schema's `nullValue` when it parses, and otherwise the SBE
+/// default: zero for `char`, NaN for floats, the minimum for signed and
+/// the maximum for unsigned integers.
+fn is_null(field: &ResolvedField, value: &SbeValue<'_>) -> bool {
+    let Some(prim) = field.primitive_type else {
+        return false;
+    };
+    let custom = field.null_value.as_deref().map(str::trim);
+    match *value {
+        SbeValue::Char(c) => match custom {
+            Some(null) if null.len() == 1 => c == null.as_bytes()[0],
+            _ => c == 0,
+        },
+        SbeValue::Float(v) => match custom.and_then(|null| null.parse::<f64>().ok()) {
+            Some(null) if null.is_finite() => v == null,
+            _ => v.is_nan(),
+        },
+        SbeValue::Int(v) => {
+            v == custom
+                .and_then(|null| null.parse().ok())
+                .unwrap_or_else(|| signed_min(prim))
+        }
+        SbeValue::UInt(v) => {
+            v == custom
+                .and_then(|null| null.parse().ok())
+                .unwrap_or_else(|| unsigned_max(prim))
+        }
+        _ => false,
+    }
+}
+
+/// Returns the minimum of a signed `prim`.
+fn signed_min(prim: PrimitiveType) -> i64 {
+    -1i64 << (prim.size() * 8 - 1)
+}
+
+/// Returns the maximum of an unsigned `prim`.
+fn unsigned_max(prim: PrimitiveType) -> u64 {
+    u64::MAX >> (64 - prim.size() * 8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_schema;
+
+    const SCHEMA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="dyn" id="7" version="1" byteOrder="littleEndian">
+    <types>
+        <type name="Symbol" primitiveType="char" length="4"/>
+        <type name="Venue" primitiveType="char" length="4" presence="constant">XCME</type>
+        <composite name="Decimal">
+            <type name="mantissa" primitiveType="int64"/>
+            <type name="exponent" primitiveType="int8"/>
+        </composite>
+        <composite name="varDataEncoding">
+            <type name="length" primitiveType="uint16"/>
+            <type name="varData" primitiveType="uint8" length="0"/>
+        </composite>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+            <validValue name="Sell">2</validValue>
+        </enum>
+        <set name="Flags" encodingType="uint8">
+            <choice name="PostOnly">0</choice>
+            <choice name="Hidden">2</choice>
+        </set>
+    </types>
+    <sbe:message name="Order" id="3" blockLength="28">
+        <field name="orderId" id="1" type="uint64" offset="0"/>
+        <field name="symbol" id="2" type="Symbol" offset="8"/>
+        <field name="venue" id="3" type="Venue"/>
+        <field name="side" id="4" type="Side" offset="12"/>
+        <field name="flags" id="5" type="Flags" offset="13"/>
+        <field name="price" id="6" type="Decimal" offset="14"/>
+        <field name="stop" id="7" type="int32" offset="23" presence="optional"/>
+        <field name="tick" id="8" type="int8" offset="27"/>
+        <group name="fills" id="10" dimensionType="groupSizeEncoding" blockLength="12">
+            <field name="qty" id="11" type="uint32" offset="0"/>
+            <field name="px" id="12" type="double" offset="4"/>
+            <data name="note" id="13" type="varDataEncoding"/>
+        </group>
+        <data name="text" id="20" type="varDataEncoding"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+    fn ir() -> SchemaIr {
+        SchemaIr::from_schema(&parse_schema(SCHEMA).expect("valid schema"))
+    }
+
+    /// Encodes an `Order` with two fills and returns the buffer.
+    fn encode() -> Vec<u8> {
+        let mut buf = Vec::new();
+        for v in [28u16, 3, 7, 1] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.extend_from_slice(&42u64.to_le_bytes());
+        buf.extend_from_slice(b"ES\0\0");
+        buf.push(2);
+        buf.push(0b101);
+        buf.extend_from_slice(&12_345i64.to_le_bytes());
+        buf.push(-2i8 as u8);
+        buf.extend_from_slice(&i32::MIN.to_le_bytes());
+        buf.push(-3i8 as u8);
+        // fills: blockLength 12, two entries
+        buf.extend_from_slice(&12u16.to_le_bytes());
+        buf.extend_from_slice(&2u16.to_le_bytes());
+        for (qty, px, note) in [(5u32, 1.5f64, &b"a"[..]), (7, 2.25, b"")] {
+            buf.extend_from_slice(&qty.to_le_bytes());
+            buf.extend_from_slice(&px.to_le_bytes());
+            buf.extend_from_slice(&(note.len() as u16).to_le_bytes());
+            buf.extend_from_slice(note);
+        }
+        buf.extend_from_slice(&5u16.to_le_bytes());
+        buf.extend_from_slice(b"hello");
+        buf
+    }
+
+    #[test]
+    fn test_decode_fields_by_name() {
+        let ir = ir();
+        let buf = encode();
+        let msg = DynamicMessage::decode(&ir, &buf).expect("decodes");
+
+        assert_eq!(msg.name(), "Order");
+        assert_eq!(msg.template_id(), 3);
+        assert_eq!(msg.encoded_length(), buf.len());
+        assert_eq!(msg.field("orderId"), Some(SbeValue::UInt(42)));
+        assert_eq!(msg.field("symbol").unwrap().as_str(), Some("ES"));
+        assert_eq!(msg.field("venue").unwrap().as_str(), Some("XCME"));
+        assert_eq!(
+            msg.field("side"),
+            Some(SbeValue::Enum {
+                name: Some("Sell"),
+                value: 2
+            })
+        );
+        assert_eq!(
+            msg.field("flags"),
+            Some(SbeValue::Set {
+                raw: 0b101,
+                choices: vec!["PostOnly", "Hidden"]
+            })
+        );
+        assert_eq!(
+            msg.field("price"),
+            Some(SbeValue::Composite(vec![
+                ("mantissa", SbeValue::Int(12_345)),
+                ("exponent", SbeValue::Int(-2)),
+            ]))
+        );
+        assert_eq!(msg.field("stop"), Some(SbeValue::Null));
+        assert_eq!(msg.field("tick"), Some(SbeValue::Int(-3)));
+        assert_eq!(msg.field("missing"), None);
+        assert_eq!(msg.var_data("text"), Some(&b"hello"[..]));
+
+        let names: Vec<_> = msg.fields().map(|f| f.name).collect();
+        assert_eq!(
+            names,
+            [
+                "orderId", "symbol", "venue", "side", "flags", "price", "stop", "tick"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_groups() {
+        let ir = ir();
+        let buf = encode();
+        let msg = DynamicMessage::decode(&ir, &buf).expect("decodes");
+
+        let fills = msg.group("fills").expect("fills group");
+        assert_eq!(fills.len(), 2);
+        let entries: Vec<_> = fills
+            .iter()
+            .map(|fill| {
+                (
+                    fill.field("qty").and_then(|v| v.as_u64()),
+                    fill.field("px").and_then(|v| v.as_f64()),
+                    fill.var_data("note"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (Some(5), Some(1.5), Some(&b"a"[..])),
+                (Some(7), Some(2.25), Some(&b""[..])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shorter_block_omits_new_fields() {
+        let ir = ir();
+        let mut buf = encode();
+        // An older version without `tick` encoded a 27-byte block.
+        buf[0] = 27;
+        buf.remove(8 + 27);
+        let msg = DynamicMessage::decode(&ir, &buf).expect("decodes");
+        assert_eq!(msg.field("tick"), None);
+        assert_eq!(msg.field("stop"), Some(SbeValue::Null));
+        assert_eq!(msg.var_data("text"), Some(&b"hello"[..]));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let ir = ir();
+        let buf = encode();
+
+        assert_eq!(
+            DynamicMessage::decode(&ir, &buf[..buf.len() - 1]).unwrap_err(),
+            DynamicError::BufferTooShort {
+                required: buf.len(),
+                available: buf.len() - 1
+            }
+        );
+
+        let mut unknown = buf.clone();
+        unknown[2] = 99;
+        assert_eq!(
+            DynamicMessage::decode(&ir, &unknown).unwrap_err(),
+            DynamicError::UnknownTemplate(99)
+        );
+
+        let mut other_schema = buf;
+        other_schema[4] = 8;
+        assert_eq!(
+            DynamicMessage::decode(&ir, &other_schema).unwrap_err(),
+            DynamicError::SchemaMismatch {
+                expected: 7,
+                actual: 8
+            }
+        );
+    }
+
+    #[test]
+    fn test_value_display() {
+        let ir = ir();
+        let buf = encode();
+        let msg = DynamicMessage::decode(&ir, &buf).expect("decodes");
+        let rendered: Vec<_> = msg
+            .fields()
+            .map(|f| format!("{}={}", f.name, f.value))
+            .collect();
+        assert_eq!(
+            rendered,
+            [
+                "orderId=42",
+                "symbol=\"ES\"",
+                "venue=\"XCME\"",
+                "side=Sell",
+                "flags={PostOnly, Hidden}",
+                "price={mantissa: 12345, exponent: -2}",
+                "stop=null",
+                "tick=-3",
+            ]
+        );
+    }
+}
@@ -38,6 +38,15 @@ pub enum ParseError {
         context: String,
     },
 
+    /// Attribute not defined for the element, rejected in strict mode.
+    #[error("unknown attribute '{attribute}' on element '{element}'")]
+    UnknownAttribute {
+        /// Element name.
+        element: String,
+        /// Attribute name.
+        attribute: String,
+    },
+
     /// Unknown type reference.
     #[error("unknown type '{type_name}' referenced in field '{field}'")]
     UnknownType {
@@ -56,6 +65,28 @@ pub enum ParseError {
         name: String,
     },
 
+    /// Schema documents being merged disagree on a `messageSchema` attribute.
+    #[error(
+        "cannot merge schemas: '{attribute}' is '{expected}' in the first document but '{actual}' in another"
+    )]
+    MergeMismatch {
+        /// Attribute name.
+        attribute: String,
+        /// Value in the first document.
+        expected: String,
+        /// Conflicting value.
+        actual: String,
+    },
+
+    /// An `<xi:include>` could not be resolved.
+    #[error("cannot include '{href}': {reason}")]
+    Include {
+        /// Referenced document.
+        href: String,
+        /// Why it could not be included.
+        reason: String,
+    },
+
     /// Invalid schema structure.
     #[error("invalid schema structure: {message}")]
     InvalidStructure {
@@ -70,6 +101,90 @@ pub enum ParseError {
     /// UTF-8 decoding error.
     #[error("UTF-8 error: {0}")]
     Utf8(#[from] std::str::Utf8Error),
+
+    /// Error annotated with where in the document it occurred.
+    #[error("{}{source}", located_prefix(.location.as_ref(), .path))]
+    Located {
+        /// Position in the document, when known.
+        location: Option<Location>,
+        /// Path of the enclosing elements, e.g.
+        /// `message[NewOrderSingle]/field[price]`; empty at the top level.
+        path: String,
+        /// The underlying error.
+        source: Box<ParseError>,
+    },
+}
+
+/// A 1-based line and column in a schema document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// Line number.
+    pub line: usize,
+    /// Column number, in characters.
+    pub column: usize,
+}
+
+impl Location {
+    /// Returns the location of byte `offset` in `text`.
+    ///
+    /// Offsets past the end of `text` point just after its last character.
+    #[must_use]
+    pub fn from_offset(text: &str, offset: usize) -> Self {
+        let mut end = offset.min(text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let before = &text[..end];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Formats the context of a [`ParseError::Located`] ahead of its source.
+fn located_prefix(location: Option<&Location>, path: &str) -> String {
+    match (location, path.is_empty()) {
+        (Some(location), false) => format!("{location} ({path}): "),
+        (Some(location), true) => format!("{location}: "),
+        (None, false) => format!("{path}: "),
+        (None, true) => String::new(),
+    }
+}
+
+/// Error type for decoding a message with [`DynamicMessage`].
+///
+/// [`DynamicMessage`]: crate::dynamic::DynamicMessage
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DynamicError {
+    /// The buffer ends before the message does.
+    #[error("buffer too short: need {required} bytes, have {available}")]
+    BufferTooShort {
+        /// Bytes needed to reach the end of the section being read.
+        required: usize,
+        /// Bytes in the buffer.
+        available: usize,
+    },
+
+    /// The header names a different schema.
+    #[error("schema ID mismatch: expected {expected}, got {actual}")]
+    SchemaMismatch {
+        /// ID of the loaded schema.
+        expected: u16,
+        /// ID in the message header.
+        actual: u16,
+    },
+
+    /// The schema has no message with the header's template ID.
+    #[error("unknown template ID {0}")]
+    UnknownTemplate(u16),
 }
 
 /// Error type for schema validation.
@@ -171,6 +286,14 @@ impl ParseError {
         }
     }
 
+    /// Creates an unknown attribute error.
+    pub fn unknown_attr(element: impl Into<String>, attribute: impl Into<String>) -> Self {
+        Self::UnknownAttribute {
+            element: element.into(),
+            attribute: attribute.into(),
+        }
+    }
+
     /// Creates a duplicate definition error.
     pub fn duplicate(kind: impl Into<String>, name: impl Into<String>) -> Self {
         Self::DuplicateDefinition {
@@ -178,6 +301,98 @@ impl ParseError {
             name: name.into(),
         }
     }
+
+    /// Creates an include error.
+    pub fn include(href: impl Into<String>, reason: impl std::fmt::Display) -> Self {
+        Self::Include {
+            href: href.into(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Returns the underlying error, without location context.
+    #[must_use]
+    pub fn root(&self) -> &ParseError {
+        match self {
+            Self::Located { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// Returns where in the document the error occurred, if known.
+    #[must_use]
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            Self::Located { location, .. } => *location,
+            _ => None,
+        }
+    }
+
+    /// Returns the path of the elements enclosing the error, if known.
+    #[must_use]
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::Located { path, .. } if !path.is_empty() => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Prefixes the error's element path with `segment`.
+    pub(crate) fn within(self, segment: &str) -> Self {
+        match self {
+            Self::Located {
+                location,
+                path,
+                source,
+            } => Self::Located {
+                location,
+                path: if path.is_empty() {
+                    segment.to_string()
+                } else {
+                    format!("{segment}/{path}")
+                },
+                source,
+            },
+            other => Self::Located {
+                location: None,
+                path: segment.to_string(),
+                source: Box::new(other),
+            },
+        }
+    }
+
+    /// Records the position `reader` reached in `xml` as where the error
+    /// occurred, unless already known.
+    ///
+    /// XML syntax errors use the position of the offending markup; other
+    /// errors use the end of the element being parsed.
+    pub(crate) fn at_reader_position(self, xml: &str, reader: &quick_xml::Reader<&[u8]>) -> Self {
+        let offset = match self.root() {
+            Self::Xml(_) => reader.error_position(),
+            _ => reader.buffer_position(),
+        };
+        self.at(Location::from_offset(xml, offset as usize))
+    }
+
+    /// Records where the error occurred, unless already known.
+    pub(crate) fn at(self, at: Location) -> Self {
+        match self {
+            Self::Located {
+                location,
+                path,
+                source,
+            } => Self::Located {
+                location: location.or(Some(at)),
+                path,
+                source,
+            },
+            other => Self::Located {
+                location: Some(at),
+                path: String::new(),
+                source: Box::new(other),
+            },
+        }
+    }
 }
 
 #[cfg(test)]
@@ -212,6 +427,15 @@ mod tests {
         assert!(msg.contains("unknown element"));
     }
 
+    #[test]
+    fn test_parse_error_unknown_attr() {
+        let err = ParseError::unknown_attr("message", "blocklength");
+        assert_eq!(
+            err.to_string(),
+            "unknown attribute 'blocklength' on element 'message'"
+        );
+    }
+
     #[test]
     fn test_parse_error_duplicate() {
         let err = ParseError::duplicate("type", "MyType");
@@ -232,6 +456,28 @@ mod tests {
         assert!(msg.contains("myField"));
     }
 
+    #[test]
+    fn test_parse_error_merge_mismatch() {
+        let err = ParseError::MergeMismatch {
+            attribute: "version".to_string(),
+            expected: "1".to_string(),
+            actual: "2".to_string(),
+        };
+        let msg = err.to_string();
+        assert!(msg.contains("cannot merge"));
+        assert!(msg.contains("'version' is '1'"));
+        assert!(msg.contains("'2'"));
+    }
+
+    #[test]
+    fn test_parse_error_include() {
+        let err = ParseError::include("common.xml", "file not found");
+        assert_eq!(
+            err.to_string(),
+            "cannot include 'common.xml': file not found"
+        );
+    }
+
     #[test]
     fn test_parse_error_invalid_structure() {
         let err = ParseError::InvalidStructure {
@@ -328,4 +574,38 @@ mod tests {
         let debug_str = format!("{:?}", err);
         assert!(debug_str.contains("MissingAttribute"));
     }
+
+    #[test]
+    fn test_location_from_offset() {
+        let text = "<a>\n  <b/>\n</a>";
+        assert_eq!(
+            Location::from_offset(text, 0),
+            Location { line: 1, column: 1 }
+        );
+        assert_eq!(
+            Location::from_offset(text, 6),
+            Location { line: 2, column: 3 }
+        );
+        assert_eq!(
+            Location::from_offset(text, 1_000),
+            Location { line: 3, column: 5 }
+        );
+    }
+
+    #[test]
+    fn test_parse_error_located() {
+        let err = ParseError::invalid_attr("field", "offset", "abc")
+            .within("field[price]")
+            .within("message[NewOrderSingle]")
+            .at(Location { line: 7, column: 9 });
+
+        assert_eq!(err.path(), Some("message[NewOrderSingle]/field[price]"));
+        assert_eq!(err.location(), Some(Location { line: 7, column: 9 }));
+        assert!(matches!(err.root(), ParseError::InvalidAttribute { .. }));
+        assert_eq!(
+            err.to_string(),
+            "line 7, column 9 (message[NewOrderSingle]/field[price]): \
+             invalid value 'abc' for attribute 'offset' on element 'field'"
+        );
+    }
 }
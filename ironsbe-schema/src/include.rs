@@ -0,0 +1,262 @@
+//! XInclude resolution.
+//!
+//! Real-world schemas (CME templates among them) keep shared types in
+//! separate files pulled in with `<xi:include href="..."/>`. The includes
+//! are expanded textually before parsing: each include element is replaced
+//! by the root element of the referenced document, recursively.
+
+use std::borrow::Cow;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+
+use crate::error::ParseError;
+use crate::parser::attr_value;
+
+/// Maximum nesting depth of includes, guarding against include cycles.
+pub const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Replaces every `<xi:include>` element of `xml` with the document
+/// `resolver` returns for its `href`.
+///
+/// The XML declaration and anything else before the included document's
+/// root element are dropped. Includes inside included documents are
+/// resolved the same way, up to [`MAX_INCLUDE_DEPTH`] levels deep. Only
+/// whole-document XML inclusion is supported: `parse="text"` and
+/// `xpointer` are rejected, and `<xi:fallback>` children are ignored.
+///
+/// # Arguments
+/// * `xml` - XML schema content
+/// * `resolver` - Returns the content of the document at an `href`
+///
+/// # Returns
+/// `xml` itself if it has no includes, otherwise the expanded document.
+///
+/// # Errors
+/// Returns [`ParseError::Include`] if the resolver fails, an include is
+/// malformed or unsupported, or includes nest too deeply, and
+/// [`ParseError::Xml`] if a document is malformed. Errors are wrapped in
+/// [`ParseError::Located`] with the position in the document that raised
+/// them, and errors from included documents name the include in their
+/// path.
+pub fn resolve_includes<'a, F>(xml: &'a str, mut resolver: F) -> Result<Cow<'a, str>, ParseError>
+where
+    F: FnMut(&str) -> io::Result<String>,
+{
+    expand(xml, &mut resolver, 0)
+}
+
+/// Returns a resolver reading `href`s relative to the directory `base`.
+pub fn file_resolver(base: impl Into<PathBuf>) -> impl FnMut(&str) -> io::Result<String> {
+    let base = base.into();
+    move |href| std::fs::read_to_string(base.join(Path::new(href)))
+}
+
+/// Expands the includes of `xml`, nested `depth` levels deep.
+fn expand<'a, F>(xml: &'a str, resolver: &mut F, depth: usize) -> Result<Cow<'a, str>, ParseError>
+where
+    F: FnMut(&str) -> io::Result<String>,
+{
+    let mut reader = Reader::from_str(xml);
+    expand_from(&mut reader, xml, resolver, depth)
+        .map_err(|err| err.at_reader_position(xml, &reader))
+}
+
+/// Expands the includes `reader` finds in `xml`.
+fn expand_from<'a, F>(
+    reader: &mut Reader<&[u8]>,
+    xml: &'a str,
+    resolver: &mut F,
+    depth: usize,
+) -> Result<Cow<'a, str>, ParseError>
+where
+    F: FnMut(&str) -> io::Result<String>,
+{
+    let mut output = String::new();
+    // End of the input already copied to `output`.
+    let mut copied = 0;
+
+    loop {
+        let start = reader.buffer_position() as usize;
+        let event = reader.read_event().map_err(ParseError::Xml)?;
+        let (include, has_children) = match &event {
+            Event::Empty(e) if is_include(e) => (e, false),
+            Event::Start(e) if is_include(e) => (e, true),
+            Event::Eof => break,
+            _ => continue,
+        };
+        let href = include_href(include)?;
+        if has_children {
+            reader
+                .read_to_end(include.name())
+                .map_err(ParseError::Xml)?;
+        }
+        if depth == MAX_INCLUDE_DEPTH {
+            return Err(ParseError::include(
+                &href,
+                format!("includes nest deeper than {MAX_INCLUDE_DEPTH} levels"),
+            ));
+        }
+        let end = reader.buffer_position() as usize;
+        let document = resolver(&href).map_err(|e| ParseError::include(&href, e))?;
+        let root = root_element(&document)?;
+        output.push_str(&xml[copied..start]);
+        let expanded = expand(root, resolver, depth + 1)
+            .map_err(|err| err.within(&format!("include[{href}]")))?;
+        output.push_str(&expanded);
+        copied = end;
+    }
+
+    if copied == 0 {
+        return Ok(Cow::Borrowed(xml));
+    }
+    output.push_str(&xml[copied..]);
+    Ok(Cow::Owned(output))
+}
+
+/// Returns true if `e` is an XInclude `include` element.
+fn is_include(e: &BytesStart<'_>) -> bool {
+    e.name().as_ref() == b"xi:include"
+}
+
+/// Reads the `href` of an include element, rejecting unsupported forms.
+fn include_href(e: &BytesStart<'_>) -> Result<String, ParseError> {
+    let mut href = None;
+    let mut unsupported = None;
+
+    for attr in e.attributes().flatten() {
+        let key = std::str::from_utf8(attr.key.as_ref())?;
+        let value = &*attr_value(&attr)?;
+
+        match (key, value) {
+            ("href", _) => href = Some(value.to_string()),
+            ("parse", "xml") => {}
+            ("parse" | "xpointer", _) => unsupported = Some(format!("{key}=\"{value}\"")),
+            _ => {}
+        }
+    }
+
+    let href = href.ok_or_else(|| ParseError::missing_attr("xi:include", "href"))?;
+    match unsupported {
+        Some(attribute) => Err(ParseError::include(
+            &href,
+            format!("{attribute} is not supported"),
+        )),
+        None => Ok(href),
+    }
+}
+
+/// Returns `document` from its root element on, dropping the XML
+/// declaration, comments and doctype before it.
+fn root_element(document: &str) -> Result<&str, ParseError> {
+    let mut reader = Reader::from_str(document);
+    loop {
+        let start = reader.buffer_position() as usize;
+        match reader.read_event() {
+            Ok(Event::Start(_) | Event::Empty(_)) => return Ok(document[start..].trim_end()),
+            Ok(Event::Eof) => return Ok(""),
+            Err(e) => return Err(ParseError::Xml(e)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn resolver(files: &[(&str, &str)]) -> impl FnMut(&str) -> io::Result<String> {
+        let files: HashMap<String, String> = files
+            .iter()
+            .map(|(name, content)| (name.to_string(), content.to_string()))
+            .collect();
+        move |href| {
+            files
+                .get(href)
+                .cloned()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not found"))
+        }
+    }
+
+    #[test]
+    fn test_without_includes_borrows_input() {
+        let xml = "<a><b/></a>";
+        let resolved = resolve_includes(xml, resolver(&[])).unwrap();
+        assert!(matches!(resolved, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_expands_nested_includes() {
+        let xml =
+            r#"<a xmlns:xi="http://www.w3.org/2001/XInclude"><xi:include href="b.xml"/><c/></a>"#;
+        let files = [
+            (
+                "b.xml",
+                "<?xml version=\"1.0\"?>\n<!-- shared -->\n<b><xi:include href=\"d.xml\"/></b>\n",
+            ),
+            ("d.xml", "<d/>"),
+        ];
+        let resolved = resolve_includes(xml, resolver(&files)).unwrap();
+        assert_eq!(
+            resolved,
+            r#"<a xmlns:xi="http://www.w3.org/2001/XInclude"><b><d/></b><c/></a>"#
+        );
+    }
+
+    #[test]
+    fn test_ignores_fallback() {
+        let xml = r#"<a><xi:include href="b.xml"><xi:fallback><x/></xi:fallback></xi:include></a>"#;
+        let resolved = resolve_includes(xml, resolver(&[("b.xml", "<b/>")])).unwrap();
+        assert_eq!(resolved, "<a><b/></a>");
+    }
+
+    #[test]
+    fn test_rejects_unresolvable_and_unsupported_includes() {
+        let err =
+            resolve_includes(r#"<a><xi:include href="x.xml"/></a>"#, resolver(&[])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 30: cannot include 'x.xml': not found"
+        );
+
+        let err = resolve_includes(
+            r#"<a><xi:include href="x.txt" parse="text"/></a>"#,
+            resolver(&[("x.txt", "text")]),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("parse=\"text\" is not supported"));
+
+        let err = resolve_includes("<a><xi:include/></a>", resolver(&[])).unwrap_err();
+        assert!(matches!(err.root(), ParseError::MissingAttribute { .. }));
+    }
+
+    #[test]
+    fn test_locates_errors_in_included_documents() {
+        let files = [("bad.xml", "<b>\n  <c></d>\n</b>")];
+        let err = resolve_includes(r#"<a><xi:include href="bad.xml"/></a>"#, resolver(&files))
+            .unwrap_err();
+        assert!(matches!(err.root(), ParseError::Xml(_)));
+        assert_eq!(err.path(), Some("include[bad.xml]"));
+        assert_eq!(err.location().map(|l| l.line), Some(2));
+    }
+
+    #[test]
+    fn test_rejects_include_cycle() {
+        let files = [("loop.xml", r#"<b><xi:include href="loop.xml"/></b>"#)];
+        let err = resolve_includes(r#"<a><xi:include href="loop.xml"/></a>"#, resolver(&files))
+            .unwrap_err();
+        assert!(err.to_string().contains("nest deeper than 16 levels"));
+    }
+
+    #[test]
+    fn test_file_resolver() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("types.xml"), "<types/>").unwrap();
+        let mut resolve = file_resolver(dir.path());
+        assert_eq!(resolve("types.xml").unwrap(), "<types/>");
+        assert!(resolve("missing.xml").is_err());
+    }
+}
@@ -3,7 +3,7 @@
 //! This module provides a flattened, resolved representation of the schema
 //! that is easier to use for code generation.
 
-use crate::types::{PrimitiveType, Schema, TypeDef};
+use crate::types::{MemberKind, PrimitiveType, Schema, TypeDef};
 use std::collections::HashMap;
 
 /// Intermediate representation of a schema for code generation.
@@ -19,6 +19,8 @@ pub struct SchemaIr {
     pub types: HashMap<String, ResolvedType>,
     /// Messages with resolved field types.
     pub messages: Vec<ResolvedMessage>,
+    /// Layout of the message header composite named by `headerType`.
+    pub message_header: MessageHeaderLayout,
 }
 
 impl SchemaIr {
@@ -31,6 +33,7 @@ impl SchemaIr {
             schema_version: schema.version,
             types: HashMap::new(),
             messages: Vec::new(),
+            message_header: MessageHeaderLayout::standard(&schema.header_type),
         };
 
         // Resolve types
@@ -39,6 +42,36 @@ impl SchemaIr {
             ir.types.insert(resolved.name.clone(), resolved);
         }
 
+        // Composite constants may reference enums declared after them
+        for type_def in &schema.types {
+            if let TypeDef::Composite(c) = type_def {
+                let constants: Vec<_> = c
+                    .fields
+                    .iter()
+                    .map(|f| {
+                        if f.is_constant() {
+                            resolve_constant(
+                                f.value_ref.as_deref(),
+                                f.constant_value.as_deref(),
+                                &ir.types,
+                            )
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                if let Some(TypeKind::Composite { fields }) =
+                    ir.types.get_mut(&c.name).map(|t| &mut t.kind)
+                {
+                    for (info, constant) in fields.iter_mut().zip(constants) {
+                        info.constant = constant;
+                    }
+                }
+            }
+        }
+
+        ir.message_header = MessageHeaderLayout::resolve(&schema.header_type, &ir.types);
+
         // Resolve messages
         for msg in &schema.messages {
             ir.messages
@@ -53,6 +86,17 @@ impl SchemaIr {
     pub fn get_type(&self, name: &str) -> Option<&ResolvedType> {
         self.types.get(name)
     }
+
+    /// Returns all resolved types sorted by name.
+    ///
+    /// Generators iterate types through this so their output does not
+    /// depend on `HashMap` iteration order.
+    #[must_use]
+    pub fn sorted_types(&self) -> Vec<&ResolvedType> {
+        let mut types: Vec<&ResolvedType> = self.types.values().collect();
+        types.sort_by(|a, b| a.name.cmp(&b.name));
+        types
+    }
 }
 
 /// Resolved type information.
@@ -70,6 +114,10 @@ pub struct ResolvedType {
     pub is_array: bool,
     /// Array length (if array).
     pub array_length: Option<usize>,
+    /// Custom null value from the schema (primitive types only).
+    pub null_value: Option<String>,
+    /// Constant value from the schema (primitive types only).
+    pub constant_value: Option<String>,
 }
 
 impl ResolvedType {
@@ -92,21 +140,26 @@ impl ResolvedType {
                 },
                 is_array: p.is_array(),
                 array_length: p.length,
+                null_value: p.null_value.clone(),
+                constant_value: p.constant_value.clone(),
             },
             TypeDef::Composite(c) => {
                 let mut offset = 0usize;
                 let fields = c
                     .fields
                     .iter()
-                    .filter_map(|f| {
+                    .map(|f| {
                         let field_offset = offset;
                         offset += f.encoded_length;
-                        f.primitive_type.map(|prim| CompositeFieldInfo {
+                        CompositeFieldInfo {
                             name: f.name.clone(),
-                            primitive_type: prim,
+                            kind: f.kind,
+                            type_name: f.type_name.clone(),
+                            primitive_type: f.primitive_type,
                             offset: field_offset,
                             encoded_length: f.encoded_length,
-                        })
+                            constant: None,
+                        }
                     })
                     .collect();
                 Self {
@@ -116,6 +169,8 @@ impl ResolvedType {
                     rust_type: to_pascal_case(&c.name),
                     is_array: false,
                     array_length: None,
+                    null_value: None,
+                    constant_value: None,
                 }
             }
             TypeDef::Enum(e) => {
@@ -145,6 +200,8 @@ impl ResolvedType {
                     rust_type: to_pascal_case(&e.name),
                     is_array: false,
                     array_length: None,
+                    null_value: None,
+                    constant_value: None,
                 }
             }
             TypeDef::Set(s) => {
@@ -166,6 +223,8 @@ impl ResolvedType {
                     rust_type: to_pascal_case(&s.name),
                     is_array: false,
                     array_length: None,
+                    null_value: None,
+                    constant_value: None,
                 }
             }
         }
@@ -181,6 +240,8 @@ impl ResolvedType {
             rust_type: prim.rust_type().to_string(),
             is_array: false,
             array_length: None,
+            null_value: None,
+            constant_value: None,
         }
     }
 }
@@ -208,12 +269,64 @@ pub struct SetVariant {
 pub struct CompositeFieldInfo {
     /// Field name.
     pub name: String,
-    /// Primitive type of this field.
-    pub primitive_type: PrimitiveType,
+    /// What the field encodes.
+    pub kind: MemberKind,
+    /// Name of the enum, set or composite type (the primitive type name for
+    /// primitive fields).
+    pub type_name: String,
+    /// Primitive type of this field, or the encoding type of an enum or
+    /// set; `None` for a nested composite.
+    pub primitive_type: Option<PrimitiveType>,
     /// Offset within the composite.
     pub offset: usize,
-    /// Encoded length in bytes.
+    /// Encoded length in bytes (zero for constants).
     pub encoded_length: usize,
+    /// Resolved constant value, if the member is a constant.
+    pub constant: Option<ConstantValue>,
+}
+
+/// Resolved value of a `presence="constant"` field or composite member.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstantValue {
+    /// Literal value from the schema, e.g. the text of a constant `<type>`.
+    Literal(String),
+    /// Enum variant referenced by `valueRef`.
+    Enum {
+        /// Enum type name.
+        type_name: String,
+        /// Variant name.
+        variant: String,
+        /// Discriminant value of the variant.
+        value: i64,
+    },
+}
+
+/// Resolves a constant from a `valueRef` of the form `Enum.Variant`, or
+/// else from a literal value.
+///
+/// Returns `None` if the value reference does not name a known enum
+/// variant, or if there is neither a reference nor a literal.
+#[must_use]
+pub fn resolve_constant(
+    value_ref: Option<&str>,
+    literal: Option<&str>,
+    types: &HashMap<String, ResolvedType>,
+) -> Option<ConstantValue> {
+    let Some(value_ref) = value_ref else {
+        return literal.map(|value| ConstantValue::Literal(value.to_string()));
+    };
+    let (type_name, variant) = value_ref.split_once('.')?;
+    let TypeKind::Enum { variants, .. } = &types.get(type_name)?.kind else {
+        return None;
+    };
+    variants
+        .iter()
+        .find(|v| v.name == variant)
+        .map(|v| ConstantValue::Enum {
+            type_name: type_name.to_string(),
+            variant: v.name.clone(),
+            value: v.value,
+        })
 }
 
 /// Type kind enumeration.
@@ -281,11 +394,7 @@ impl ResolvedMessage {
         let var_data = msg
             .data_fields
             .iter()
-            .map(|d| ResolvedVarData {
-                name: d.name.clone(),
-                id: d.id,
-                type_name: d.type_name.clone(),
-            })
+            .map(|d| ResolvedVarData::from_data_field_def(d, types))
             .collect();
 
         Self {
@@ -309,6 +418,12 @@ impl ResolvedMessage {
     pub fn encoder_name(&self) -> String {
         format!("{}Encoder", self.name)
     }
+
+    /// Returns the owned mirror struct name.
+    #[must_use]
+    pub fn owned_name(&self) -> String {
+        format!("{}Owned", self.name)
+    }
 }
 
 /// Resolved field information.
@@ -338,6 +453,10 @@ pub struct ResolvedField {
     pub array_length: Option<usize>,
     /// Primitive type (if applicable).
     pub primitive_type: Option<PrimitiveType>,
+    /// Custom null value of the field's type, if the schema defines one.
+    pub null_value: Option<String>,
+    /// Resolved constant value, if the field is a constant.
+    pub constant: Option<ConstantValue>,
 }
 
 impl ResolvedField {
@@ -367,6 +486,21 @@ impl ResolvedField {
                 (field.encoded_length, "u64".to_string(), false, None, None)
             };
 
+        let type_constant = resolved_type
+            .as_ref()
+            .and_then(|rt| rt.constant_value.as_deref());
+        let constant = if field.is_constant() || type_constant.is_some() {
+            resolve_constant(field.value_ref.as_deref(), type_constant, types)
+        } else {
+            None
+        };
+        // Constants are not encoded
+        let encoded_length = if field.is_constant() || constant.is_some() {
+            0
+        } else {
+            encoded_length
+        };
+
         Self {
             name: field.name.clone(),
             id: field.id,
@@ -380,6 +514,8 @@ impl ResolvedField {
             is_array,
             array_length,
             primitive_type,
+            null_value: resolved_type.and_then(|rt| rt.null_value),
+            constant,
         }
     }
 }
@@ -393,6 +529,8 @@ pub struct ResolvedGroup {
     pub id: u16,
     /// Block length per entry.
     pub block_length: u16,
+    /// Layout of the group's dimension header.
+    pub dimension: GroupDimension,
     /// Resolved fields.
     pub fields: Vec<ResolvedField>,
     /// Nested groups.
@@ -423,17 +561,14 @@ impl ResolvedGroup {
         let var_data = group
             .data_fields
             .iter()
-            .map(|d| ResolvedVarData {
-                name: d.name.clone(),
-                id: d.id,
-                type_name: d.type_name.clone(),
-            })
+            .map(|d| ResolvedVarData::from_data_field_def(d, types))
             .collect();
 
         Self {
             name: group.name.clone(),
             id: group.id,
             block_length: group.block_length,
+            dimension: GroupDimension::resolve(&group.dimension_type, types),
             fields,
             nested_groups,
             var_data,
@@ -463,6 +598,226 @@ impl ResolvedGroup {
     pub fn entry_encoder_name(&self) -> String {
         format!("{}EntryEncoder", to_pascal_case(&self.name))
     }
+
+    /// Returns the owned entry mirror struct name.
+    #[must_use]
+    pub fn entry_owned_name(&self) -> String {
+        format!("{}EntryOwned", to_pascal_case(&self.name))
+    }
+}
+
+/// Resolved layout of a repeating group's dimension header.
+///
+/// Resolved from the composite named by the group's `dimensionType`. A
+/// schema that does not define the composite, or whose composite lacks a
+/// `blockLength` or `numInGroup` member, gets the standard 4-byte
+/// `groupSizeEncoding` layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupDimension {
+    /// Name of the dimension composite.
+    pub type_name: String,
+    /// Encoding of the `blockLength` member.
+    pub block_length_type: PrimitiveType,
+    /// Offset of the `blockLength` member.
+    pub block_length_offset: usize,
+    /// Encoding of the `numInGroup` member.
+    pub num_in_group_type: PrimitiveType,
+    /// Offset of the `numInGroup` member.
+    pub num_in_group_offset: usize,
+    /// Encoded length of the header, including any other members.
+    pub encoded_length: usize,
+}
+
+impl GroupDimension {
+    /// Resolves the dimension header named `type_name`.
+    #[must_use]
+    pub fn resolve(type_name: &str, types: &HashMap<String, ResolvedType>) -> Self {
+        let fields = match types.get(type_name).map(|t| &t.kind) {
+            Some(TypeKind::Composite { fields }) => fields.as_slice(),
+            _ => &[],
+        };
+        let member = |name: &str| {
+            fields
+                .iter()
+                .find(|f| f.name == name && f.encoded_length > 0)
+                .and_then(|f| f.primitive_type.map(|p| (p, f.offset)))
+        };
+        match (member("blockLength"), member("numInGroup")) {
+            (Some((block_length_type, block_length_offset)), Some((num_type, num_offset))) => {
+                Self {
+                    type_name: type_name.to_string(),
+                    block_length_type,
+                    block_length_offset,
+                    num_in_group_type: num_type,
+                    num_in_group_offset: num_offset,
+                    encoded_length: fields.iter().map(|f| f.encoded_length).sum(),
+                }
+            }
+            _ => Self::standard(type_name),
+        }
+    }
+
+    /// Returns the standard layout: `blockLength` and `numInGroup` as
+    /// `uint16`.
+    #[must_use]
+    pub fn standard(type_name: &str) -> Self {
+        Self {
+            type_name: type_name.to_string(),
+            block_length_type: PrimitiveType::Uint16,
+            block_length_offset: 0,
+            num_in_group_type: PrimitiveType::Uint16,
+            num_in_group_offset: 2,
+            encoded_length: 4,
+        }
+    }
+
+    /// Returns true if the layout matches the standard 4-byte header.
+    #[must_use]
+    pub fn is_standard(&self) -> bool {
+        self.block_length_type == PrimitiveType::Uint16
+            && self.block_length_offset == 0
+            && self.num_in_group_type == PrimitiveType::Uint16
+            && self.num_in_group_offset == 2
+            && self.encoded_length == 4
+    }
+
+    /// Returns the name of the header struct generated for this layout.
+    #[must_use]
+    pub fn header_name(&self) -> String {
+        format!("{}Header", to_pascal_case(&self.type_name))
+    }
+}
+
+/// Scalar member of a message header composite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderMember {
+    /// Member name, e.g. `numGroups`.
+    pub name: String,
+    /// Encoding of the member.
+    pub primitive_type: PrimitiveType,
+    /// Offset within the header.
+    pub offset: usize,
+}
+
+/// Layout of a schema's message header.
+///
+/// The standard header is `blockLength`, `templateId`, `schemaId` and
+/// `version`, all `uint16`. Some venues extend it, e.g. CME appends
+/// `numGroups` and `numVarDataFields`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageHeaderLayout {
+    /// Name of the header composite.
+    pub type_name: String,
+    /// Non-constant members in wire order.
+    pub members: Vec<HeaderMember>,
+    /// Encoded length of the header.
+    pub encoded_length: usize,
+}
+
+impl MessageHeaderLayout {
+    /// Members every header must have, as `uint16`.
+    pub const REQUIRED_MEMBERS: [&'static str; 4] =
+        ["blockLength", "templateId", "schemaId", "version"];
+
+    /// Resolves the header composite named `type_name`.
+    ///
+    /// Falls back to the standard layout if there is no such composite, if
+    /// it lacks a `uint16` member of [`REQUIRED_MEMBERS`](Self::REQUIRED_MEMBERS),
+    /// or if a member is not a scalar primitive.
+    #[must_use]
+    pub fn resolve(type_name: &str, types: &HashMap<String, ResolvedType>) -> Self {
+        let Some(TypeKind::Composite { fields }) = types.get(type_name).map(|t| &t.kind) else {
+            return Self::standard(type_name);
+        };
+        let members: Option<Vec<_>> = fields
+            .iter()
+            .filter(|f| f.encoded_length > 0)
+            .map(|f| {
+                let primitive_type = f
+                    .primitive_type
+                    .filter(|p| f.kind == MemberKind::Primitive && p.size() == f.encoded_length)?;
+                Some(HeaderMember {
+                    name: f.name.clone(),
+                    primitive_type,
+                    offset: f.offset,
+                })
+            })
+            .collect();
+        let layout = members.map(|members| Self {
+            type_name: type_name.to_string(),
+            encoded_length: fields.iter().map(|f| f.encoded_length).sum(),
+            members,
+        });
+        match layout {
+            Some(layout)
+                if Self::REQUIRED_MEMBERS.iter().all(|name| {
+                    layout
+                        .member(name)
+                        .is_some_and(|m| m.primitive_type == PrimitiveType::Uint16)
+                }) =>
+            {
+                layout
+            }
+            _ => Self::standard(type_name),
+        }
+    }
+
+    /// Returns the standard 8-byte layout.
+    #[must_use]
+    pub fn standard(type_name: &str) -> Self {
+        Self {
+            type_name: type_name.to_string(),
+            members: Self::REQUIRED_MEMBERS
+                .iter()
+                .enumerate()
+                .map(|(i, name)| HeaderMember {
+                    name: (*name).to_string(),
+                    primitive_type: PrimitiveType::Uint16,
+                    offset: 2 * i,
+                })
+                .collect(),
+            encoded_length: 8,
+        }
+    }
+
+    /// Returns true if the layout matches the standard 8-byte header.
+    #[must_use]
+    pub fn is_standard(&self) -> bool {
+        *self == Self::standard(&self.type_name)
+    }
+
+    /// Returns the member named `name`.
+    #[must_use]
+    pub fn member(&self, name: &str) -> Option<&HeaderMember> {
+        self.members.iter().find(|m| m.name == name)
+    }
+
+    /// Returns the offset of the required member `name`.
+    ///
+    /// # Panics
+    /// Panics if `name` is not one of [`REQUIRED_MEMBERS`](Self::REQUIRED_MEMBERS).
+    #[must_use]
+    pub fn offset_of(&self, name: &str) -> usize {
+        self.member(name)
+            .unwrap_or_else(|| panic!("message header has no {name} member"))
+            .offset
+    }
+
+    /// Returns the value an encoder writes to member `name` of the header
+    /// of `message`: the message's group count for `numGroups`, its var
+    /// data count for `numVarDataFields`, and zero for any other extension.
+    #[must_use]
+    pub fn member_value(name: &str, message: &ResolvedMessage, ir: &SchemaIr) -> u64 {
+        match name {
+            "blockLength" => message.block_length.into(),
+            "templateId" => message.template_id.into(),
+            "schemaId" => ir.schema_id.into(),
+            "version" => ir.schema_version.into(),
+            "numGroups" => message.groups.len() as u64,
+            "numVarDataFields" => message.var_data.len() as u64,
+            _ => 0,
+        }
+    }
 }
 
 /// Resolved variable data field.
@@ -474,6 +829,40 @@ pub struct ResolvedVarData {
     pub id: u16,
     /// Type name.
     pub type_name: String,
+    /// Encoding of the length prefix.
+    pub length_type: PrimitiveType,
+}
+
+impl ResolvedVarData {
+    /// Creates a resolved var data field from a data field definition.
+    ///
+    /// The length encoding is the `length` member of the composite named by
+    /// the field's type, or `uint16` if the schema does not define it.
+    #[must_use]
+    pub fn from_data_field_def(
+        data: &crate::messages::DataFieldDef,
+        types: &HashMap<String, ResolvedType>,
+    ) -> Self {
+        let length_type = match types.get(&data.type_name).map(|t| &t.kind) {
+            Some(TypeKind::Composite { fields }) => fields
+                .iter()
+                .find(|f| f.name == "length")
+                .and_then(|f| f.primitive_type),
+            _ => None,
+        };
+        Self {
+            name: data.name.clone(),
+            id: data.id,
+            type_name: data.type_name.clone(),
+            length_type: length_type.unwrap_or(PrimitiveType::Uint16),
+        }
+    }
+
+    /// Returns the size of the length prefix in bytes.
+    #[must_use]
+    pub fn header_length(&self) -> usize {
+        self.length_type.size()
+    }
 }
 
 /// Converts a string to snake_case.
@@ -650,6 +1039,48 @@ mod tests {
         assert!(!ir.messages.is_empty());
     }
 
+    #[test]
+    fn test_sorted_types_ordered_by_name() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <type name="zeta" primitiveType="uint8"/>
+        <type name="alpha" primitiveType="uint16"/>
+        <type name="mid" primitiveType="uint32"/>
+    </types>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+        let names: Vec<&str> = ir.sorted_types().iter().map(|t| t.name.as_str()).collect();
+
+        assert_eq!(names, vec!["alpha", "mid", "zeta"]);
+    }
+
+    #[test]
+    fn test_field_null_value_from_type() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <type name="Qty" primitiveType="int32" nullValue="0"/>
+    </types>
+    <sbe:message name="Order" id="1" blockLength="12">
+        <field name="qty" id="1" type="Qty" offset="0" presence="optional"/>
+        <field name="price" id="2" type="int64" offset="4" presence="optional"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+        let fields = &ir.messages[0].fields;
+
+        assert_eq!(fields[0].null_value.as_deref(), Some("0"));
+        assert_eq!(fields[1].null_value, None);
+        assert!(fields.iter().all(|f| f.is_optional));
+    }
+
     #[test]
     fn test_resolved_type_from_primitive() {
         let resolved = ResolvedType::from_primitive(PrimitiveType::Uint64);
@@ -735,4 +1166,168 @@ mod tests {
 
         assert!(ir.types.contains_key("Decimal"));
     }
+
+    #[test]
+    fn test_schema_ir_composite_member_kinds() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <composite name="Quote">
+            <ref name="price" type="Decimal"/>
+            <enum name="Side" encodingType="char">
+                <validValue name="Buy">B</validValue>
+            </enum>
+        </composite>
+        <composite name="Decimal">
+            <type name="mantissa" primitiveType="int64"/>
+            <type name="exponent" primitiveType="int8"/>
+        </composite>
+    </types>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+
+        let Some(TypeKind::Composite { fields }) = ir.get_type("Quote").map(|t| &t.kind) else {
+            panic!("Quote is not a composite");
+        };
+        let members: Vec<_> = fields
+            .iter()
+            .map(|f| {
+                (
+                    f.name.as_str(),
+                    f.kind,
+                    f.type_name.as_str(),
+                    f.primitive_type,
+                    f.offset,
+                )
+            })
+            .collect();
+        assert_eq!(
+            members,
+            vec![
+                ("price", MemberKind::Composite, "Decimal", None, 0),
+                (
+                    "Side",
+                    MemberKind::Enum,
+                    "Side",
+                    Some(PrimitiveType::Char),
+                    9
+                ),
+            ]
+        );
+        assert_eq!(ir.get_type("Quote").unwrap().encoded_length, 10);
+        assert!(ir.get_type("Side").is_some());
+    }
+
+    #[test]
+    fn test_constants_resolved() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <composite name="Quote">
+            <type name="price" primitiveType="int64"/>
+            <type name="exponent" primitiveType="int8" presence="constant">-4</type>
+            <type name="side" primitiveType="uint8" presence="constant" valueRef="Side.Sell"/>
+        </composite>
+        <type name="Venue" primitiveType="char" presence="constant">X</type>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+            <validValue name="Sell">2</validValue>
+        </enum>
+    </types>
+    <sbe:message name="Test" id="1" blockLength="8">
+        <field name="side" id="1" type="Side" presence="constant" valueRef="Side.Buy"/>
+        <field name="venue" id="2" type="Venue"/>
+        <field name="quote" id="3" type="Quote"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+
+        let Some(TypeKind::Composite { fields }) = ir.get_type("Quote").map(|t| &t.kind) else {
+            panic!("Quote is not a composite");
+        };
+        assert_eq!(fields[0].constant, None);
+        assert_eq!(
+            fields[1].constant,
+            Some(ConstantValue::Literal("-4".to_string()))
+        );
+        assert_eq!(
+            fields[2].constant,
+            Some(ConstantValue::Enum {
+                type_name: "Side".to_string(),
+                variant: "Sell".to_string(),
+                value: 2,
+            })
+        );
+
+        let msg = &ir.messages[0];
+        assert_eq!(
+            msg.fields[0].constant,
+            Some(ConstantValue::Enum {
+                type_name: "Side".to_string(),
+                variant: "Buy".to_string(),
+                value: 1,
+            })
+        );
+        assert_eq!(msg.fields[0].encoded_length, 0);
+        assert_eq!(
+            msg.fields[1].constant,
+            Some(ConstantValue::Literal("X".to_string()))
+        );
+        assert_eq!(msg.fields[1].encoded_length, 0);
+        assert_eq!(msg.fields[2].constant, None);
+        assert_eq!(msg.fields[2].offset, 0);
+        assert_eq!(msg.fields[2].encoded_length, 8);
+    }
+
+    #[test]
+    fn test_group_dimension_and_var_data_length_resolved() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <composite name="groupSize32">
+            <type name="blockLength" primitiveType="uint16"/>
+            <type name="numInGroup" primitiveType="uint32"/>
+        </composite>
+        <composite name="varString8">
+            <type name="length" primitiveType="uint8"/>
+            <type name="varData" primitiveType="uint8" length="0"/>
+        </composite>
+    </types>
+    <sbe:message name="Test" id="1">
+        <group name="wide" id="1" dimensionType="groupSize32">
+            <field name="qty" id="2" type="uint32"/>
+        </group>
+        <group name="plain" id="3">
+            <field name="qty" id="4" type="uint32"/>
+        </group>
+        <data name="note" id="5" type="varString8"/>
+        <data name="text" id="6" type="varDataEncoding"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        let ir = SchemaIr::from_schema(&schema);
+        let msg = &ir.messages[0];
+
+        let wide = &msg.groups[0].dimension;
+        assert_eq!(wide.block_length_type, PrimitiveType::Uint16);
+        assert_eq!(wide.num_in_group_type, PrimitiveType::Uint32);
+        assert_eq!(wide.num_in_group_offset, 2);
+        assert_eq!(wide.encoded_length, 6);
+        assert!(!wide.is_standard());
+        assert_eq!(wide.header_name(), "GroupSize32Header");
+        assert!(msg.groups[1].dimension.is_standard());
+
+        assert_eq!(msg.var_data[0].length_type, PrimitiveType::Uint8);
+        assert_eq!(msg.var_data[0].header_length(), 1);
+        assert_eq!(msg.var_data[1].length_type, PrimitiveType::Uint16);
+        assert_eq!(msg.var_data[1].header_length(), 2);
+    }
 }
@@ -4,22 +4,40 @@
 //!
 //! This crate provides:
 //! - XML schema parsing from FIX SBE specifications
+//! - Import of FIX Orchestra repositories
+//! - Merging schemas split across several XML documents
+//! - `<xi:include>` resolution and `<ref>` expansion in composites
 //! - Type definitions for schema elements
-//! - Schema validation
+//! - Schema validation, and a strict parse mode for schema authors
 //! - Intermediate representation for code generation
+//! - Schema-driven decoding of messages without generated code
+//! - Writing schemas back to SBE XML
 
+pub mod dynamic;
 pub mod error;
+pub mod include;
 pub mod ir;
 pub mod messages;
+pub mod orchestra;
 pub mod parser;
+pub mod strict;
 pub mod types;
 pub mod validation;
+pub mod writer;
 
-pub use error::{ParseError, SchemaError};
+pub use dynamic::{DynamicBlock, DynamicField, DynamicGroup, DynamicMessage, SbeValue};
+pub use error::{DynamicError, Location, ParseError, SchemaError};
+pub use include::{file_resolver, resolve_includes};
 pub use ir::SchemaIr;
 pub use messages::{DataFieldDef, FieldDef, GroupDef, MessageDef};
-pub use parser::parse_schema;
+pub use orchestra::{OrchestraOptions, import_orchestra, import_orchestra_file, orchestra_to_sbe};
+pub use parser::{
+    parse_schema, parse_schema_file, parse_schema_file_strict, parse_schema_strict,
+    parse_schema_with_resolver, parse_schemas,
+};
+pub use strict::check_strict;
 pub use types::{
-    ByteOrder, CompositeDef, CompositeField, EnumDef, EnumValue, Presence, PrimitiveDef,
-    PrimitiveType, Schema, SetChoice, SetDef, TypeDef,
+    ByteOrder, CompositeDef, CompositeField, EnumDef, EnumValue, MemberKind, Presence,
+    PrimitiveDef, PrimitiveType, Schema, SetChoice, SetDef, TypeDef,
 };
+pub use writer::{write_schema, write_schema_file};
@@ -0,0 +1,1147 @@
+//! FIX Orchestra import.
+//!
+//! Many shops maintain their FIX dialect as an Orchestra repository rather
+//! than as SBE XML. [`import_orchestra`] converts a repository into a
+//! [`Schema`], so code can be generated straight from it:
+//!
+//! - Every message becomes an SBE message whose template ID is the
+//!   Orchestra message `id` and whose `semanticType` is its `MsgType`.
+//!   Only messages of the base scenario are imported.
+//! - Components are flattened into the messages and groups using them.
+//!   `StandardHeader` and `StandardTrailer` are skipped: SBE carries its own
+//!   message header. A field reached twice is kept once.
+//! - Groups become repeating groups, with the tag of their `NumInGroup`
+//!   field as ID.
+//! - Code sets of `char` or small `int` values become enums. Others fall
+//!   back to the encoding of their datatype.
+//! - Datatypes map to SBE encodings as listed below, unless the repository
+//!   gives an SBE mapping (`<mappedDatatype standard="SBE" base="int64"/>`,
+//!   with `parameter="length=N"` for arrays).
+//! - `data` fields become var data; the `Length` fields naming them in
+//!   their `lengthId` are dropped, since var data carries its own length.
+//!
+//! | FIX datatype | SBE encoding |
+//! |---|---|
+//! | `int` | `int32` |
+//! | `Length`, `NumInGroup` | `uint16` |
+//! | `SeqNum`, `TagNum` | `uint32` |
+//! | `DayOfMonth` | `uint8` |
+//! | `float`, `Qty`, `Price`, `PriceOffset`, `Amt`, `Percentage` | `int64` mantissa, `int8` exponent |
+//! | `char`, `Boolean` | `char` |
+//! | `Country`, `Currency`, `Exchange`, `Language`, `MonthYear` | `char[2]`, `char[3]`, `char[4]`, `char[2]`, `char[8]` |
+//! | `UTCTimestamp`, `TZTimestamp`, `UTCTimeOnly`, `TZTimeOnly`, `LocalMktTime` | `uint64` nanoseconds |
+//! | `UTCDateOnly`, `LocalMktDate` | `uint16` days since the epoch |
+//! | `data`, `XMLData` | var data |
+//! | other string types | `char[`[`string_length`](OrchestraOptions::string_length)`]` |
+//!
+//! A constant field of an enum type becomes a constant referring to the
+//! matching value; other constants are imported as required fields.
+//!
+//! Repositories in the older FIX Repository format have to be converted
+//! to Orchestra first, e.g. with the FIX Trading Community's Orchestra
+//! tools.
+
+use crate::error::ParseError;
+use crate::parser::{attr_value, parse_schema};
+use crate::types::{ByteOrder, Presence, PrimitiveType, Schema};
+use crate::writer::{Attrs, SBE_NAMESPACE, XmlWriter};
+use quick_xml::Reader;
+use quick_xml::escape::unescape;
+use quick_xml::events::{BytesStart, Event};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Default [`OrchestraOptions::string_length`].
+pub const DEFAULT_STRING_LENGTH: usize = 20;
+
+/// Components holding the FIX session header and trailer, not imported.
+const SESSION_COMPONENTS: [&str; 2] = ["StandardHeader", "StandardTrailer"];
+
+/// Deepest nesting of components and groups followed, guarding against
+/// components that include themselves.
+const MAX_DEPTH: usize = 32;
+
+/// Settings of an Orchestra import.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrchestraOptions {
+    /// Schema package; derived from the repository name when unset.
+    pub package: Option<String>,
+    /// Schema ID.
+    pub schema_id: u16,
+    /// Schema version.
+    pub version: u16,
+    /// Byte order of the schema.
+    pub byte_order: ByteOrder,
+    /// Length of the fixed `char` arrays that string datatypes map to.
+    pub string_length: usize,
+    /// Names of the messages to import; every message when unset.
+    pub messages: Option<Vec<String>>,
+}
+
+impl OrchestraOptions {
+    /// Creates options importing every message into schema 1, version 0.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the schema package.
+    #[must_use]
+    pub fn package(mut self, package: impl Into<String>) -> Self {
+        self.package = Some(package.into());
+        self
+    }
+
+    /// Sets the schema ID.
+    #[must_use]
+    pub fn schema_id(mut self, id: u16) -> Self {
+        self.schema_id = id;
+        self
+    }
+
+    /// Sets the schema version.
+    #[must_use]
+    pub fn version(mut self, version: u16) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets the byte order of the schema.
+    #[must_use]
+    pub fn byte_order(mut self, byte_order: ByteOrder) -> Self {
+        self.byte_order = byte_order;
+        self
+    }
+
+    /// Sets the length of the `char` arrays string datatypes map to.
+    #[must_use]
+    pub fn string_length(mut self, length: usize) -> Self {
+        self.string_length = length;
+        self
+    }
+
+    /// Imports only the named messages, with the types they use.
+    #[must_use]
+    pub fn messages<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.messages = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+}
+
+impl Default for OrchestraOptions {
+    fn default() -> Self {
+        Self {
+            package: None,
+            schema_id: 1,
+            version: 0,
+            byte_order: ByteOrder::LittleEndian,
+            string_length: DEFAULT_STRING_LENGTH,
+            messages: None,
+        }
+    }
+}
+
+/// Converts an Orchestra repository into a [`Schema`].
+///
+/// # Errors
+/// Returns `ParseError` if the XML is malformed, a reference or datatype
+/// cannot be resolved, or an ID does not fit SBE.
+pub fn import_orchestra(xml: &str, options: &OrchestraOptions) -> Result<Schema, ParseError> {
+    parse_schema(&orchestra_to_sbe(xml, options)?)
+}
+
+/// Converts the Orchestra repository file at `path` into a [`Schema`].
+///
+/// # Errors
+/// Returns `ParseError` if the file cannot be read, or for the reasons
+/// listed on [`import_orchestra`].
+pub fn import_orchestra_file(
+    path: &Path,
+    options: &OrchestraOptions,
+) -> Result<Schema, ParseError> {
+    import_orchestra(&std::fs::read_to_string(path)?, options)
+}
+
+/// Converts an Orchestra repository into an SBE XML schema document, e.g.
+/// to check it in and edit it from then on.
+///
+/// # Errors
+/// Same as [`import_orchestra`], except that the result is not parsed.
+pub fn orchestra_to_sbe(xml: &str, options: &OrchestraOptions) -> Result<String, ParseError> {
+    let root = read_tree(xml)?;
+    let repository = Repository::new(&root);
+    let mut importer = Importer {
+        repository: &repository,
+        options,
+        types: Vec::new(),
+        type_names: HashSet::new(),
+    };
+
+    let mut messages = Vec::new();
+    for message in repository.messages() {
+        let name = message.required("name")?;
+        if options
+            .messages
+            .as_ref()
+            .is_some_and(|names| !names.iter().any(|n| n == name))
+        {
+            continue;
+        }
+        let segment = format!("message[{name}]");
+        messages.push(importer.message(message).map_err(|e| e.within(&segment))?);
+    }
+
+    Ok(importer.write(&root, &messages))
+}
+
+/// An XML element with its attributes and children.
+struct Element {
+    /// Local name, without namespace prefix.
+    name: String,
+    /// Attributes by local name, unescaped.
+    attrs: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn from_start(e: &BytesStart<'_>) -> Result<Self, ParseError> {
+        let mut attrs = Vec::new();
+        for attr in e.attributes() {
+            let attr = attr.map_err(quick_xml::Error::from)?;
+            let key = std::str::from_utf8(attr.key.local_name().into_inner())?.to_string();
+            attrs.push((key, attr_value(&attr)?.into_owned()));
+        }
+        Ok(Self {
+            name: std::str::from_utf8(e.local_name().into_inner())?.to_string(),
+            attrs,
+            children: Vec::new(),
+            text: String::new(),
+        })
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn required(&self, name: &str) -> Result<&str, ParseError> {
+        self.attr(name)
+            .ok_or_else(|| ParseError::missing_attr(&self.name, name))
+    }
+
+    /// Parses the attribute `name` as an SBE ID.
+    fn id(&self, name: &str) -> Result<u16, ParseError> {
+        let value = self.required(name)?;
+        value
+            .parse()
+            .map_err(|_| ParseError::invalid_attr(&self.name, name, value))
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    fn child<'a>(&'a self, name: &'a str) -> Option<&'a Element> {
+        self.children(name).next()
+    }
+
+    /// Returns the scenario, `base` when unset.
+    fn scenario(&self) -> &str {
+        self.attr("scenario").unwrap_or("base")
+    }
+
+    /// Returns the first documentation text, with whitespace collapsed.
+    fn documentation(&self) -> Option<String> {
+        let text = self
+            .child("annotation")?
+            .child("documentation")?
+            .text
+            .as_str();
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        (!text.is_empty()).then_some(text)
+    }
+}
+
+/// Reads `xml` into a tree of [`Element`]s.
+fn read_tree(xml: &str) -> Result<Element, ParseError> {
+    let mut reader = Reader::from_str(xml);
+    let mut open: Vec<Element> = Vec::new();
+    loop {
+        let closed = match reader.read_event()? {
+            Event::Start(e) => {
+                open.push(Element::from_start(&e)?);
+                continue;
+            }
+            Event::Empty(e) => Element::from_start(&e)?,
+            Event::End(_) => open.pop().expect("the reader checks nesting"),
+            Event::Text(t) => {
+                if let Some(element) = open.last_mut() {
+                    element
+                        .text
+                        .push_str(&t.decode().map_err(quick_xml::Error::from)?);
+                }
+                continue;
+            }
+            Event::GeneralRef(r) => {
+                if let Some(element) = open.last_mut() {
+                    let name = r.decode().map_err(quick_xml::Error::from)?;
+                    let reference = format!("&{name};");
+                    let text = unescape(&reference).map_err(quick_xml::Error::from)?;
+                    element.text.push_str(&text);
+                }
+                continue;
+            }
+            Event::Eof => {
+                return Err(ParseError::InvalidStructure {
+                    message: "no repository element found".to_string(),
+                });
+            }
+            _ => continue,
+        };
+        match open.last_mut() {
+            Some(parent) => parent.children.push(closed),
+            None => return Ok(closed),
+        }
+    }
+}
+
+/// An Orchestra element's ID and scenario.
+type Key<'a> = (&'a str, &'a str);
+
+/// Index of the definitions in a repository.
+struct Repository<'a> {
+    root: &'a Element,
+    datatypes: HashMap<&'a str, &'a Element>,
+    code_sets: HashMap<Key<'a>, &'a Element>,
+    fields: HashMap<Key<'a>, &'a Element>,
+    components: HashMap<Key<'a>, &'a Element>,
+    groups: HashMap<Key<'a>, &'a Element>,
+    /// IDs of the fields holding the length of a `data` field.
+    length_fields: HashSet<&'a str>,
+}
+
+impl<'a> Repository<'a> {
+    fn new(root: &'a Element) -> Self {
+        let by_key = |section, item, key: &'static str| {
+            section_items(root, section, item)
+                .filter_map(|e| Some(((e.attr(key)?, e.scenario()), e)))
+                .collect::<HashMap<_, _>>()
+        };
+        Self {
+            root,
+            datatypes: section_items(root, "datatypes", "datatype")
+                .filter_map(|e| Some((e.attr("name")?, e)))
+                .collect(),
+            code_sets: by_key("codeSets", "codeSet", "name"),
+            fields: by_key("fields", "field", "id"),
+            components: by_key("components", "component", "id"),
+            groups: by_key("groups", "group", "id"),
+            length_fields: section_items(root, "fields", "field")
+                .filter_map(|e| e.attr("lengthId"))
+                .collect(),
+        }
+    }
+
+    fn messages(&self) -> impl Iterator<Item = &'a Element> {
+        section_items(self.root, "messages", "message").filter(|m| m.scenario() == "base")
+    }
+
+    /// Looks up `id` in `scenario`, falling back to the base scenario.
+    fn lookup(
+        map: &HashMap<Key<'a>, &'a Element>,
+        kind: &str,
+        id: &str,
+        scenario: &str,
+    ) -> Result<&'a Element, ParseError> {
+        map.get(&(id, scenario))
+            .or_else(|| map.get(&(id, "base")))
+            .copied()
+            .ok_or_else(|| ParseError::InvalidStructure {
+                message: format!("{kind} '{id}' is not defined"),
+            })
+    }
+}
+
+/// Returns the `item` elements of every `section` under `root`.
+fn section_items<'a>(
+    root: &'a Element,
+    section: &'a str,
+    item: &'a str,
+) -> impl Iterator<Item = &'a Element> {
+    root.children(section).flat_map(move |s| s.children(item))
+}
+
+/// SBE encoding of a FIX datatype.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    /// A primitive, or an array of `length` primitives.
+    Primitive(PrimitiveType, Option<usize>),
+    /// A mantissa and exponent composite.
+    Decimal,
+    /// Variable-length data.
+    VarData,
+}
+
+/// Returns the default encoding of the FIX datatype `name`.
+fn builtin_encoding(name: &str, string_length: usize) -> Option<Encoding> {
+    use PrimitiveType::{Char, Int32, Uint8, Uint16, Uint32, Uint64};
+    let encoding = match name {
+        "int" => Encoding::Primitive(Int32, None),
+        "Length" | "NumInGroup" => Encoding::Primitive(Uint16, None),
+        "SeqNum" | "TagNum" => Encoding::Primitive(Uint32, None),
+        "DayOfMonth" => Encoding::Primitive(Uint8, None),
+        "float" | "Qty" | "Price" | "PriceOffset" | "Amt" | "Percentage" => Encoding::Decimal,
+        "char" | "Boolean" => Encoding::Primitive(Char, None),
+        "Country" | "Language" => Encoding::Primitive(Char, Some(2)),
+        "Currency" => Encoding::Primitive(Char, Some(3)),
+        "Exchange" => Encoding::Primitive(Char, Some(4)),
+        "MonthYear" => Encoding::Primitive(Char, Some(8)),
+        "UTCTimestamp" | "TZTimestamp" | "UTCTimeOnly" | "TZTimeOnly" | "LocalMktTime" => {
+            Encoding::Primitive(Uint64, None)
+        }
+        "UTCDateOnly" | "LocalMktDate" => Encoding::Primitive(Uint16, None),
+        "data" | "XMLData" => Encoding::VarData,
+        "String"
+        | "MultipleCharValue"
+        | "MultipleStringValue"
+        | "XID"
+        | "XIDREF"
+        | "Tenor"
+        | "Reserved100Plus"
+        | "Reserved1000Plus"
+        | "Reserved4000Plus" => Encoding::Primitive(Char, Some(string_length)),
+        _ => return None,
+    };
+    Some(encoding)
+}
+
+/// Returns the SBE type name of a FIX datatype, capitalized where it would
+/// clash with an SBE primitive (`char`, `float`).
+fn type_name(fix_name: &str) -> String {
+    if PrimitiveType::from_sbe_name(fix_name).is_none() {
+        return fix_name.to_string();
+    }
+    let mut chars = fix_name.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// A type declared in the generated schema.
+enum TypeDecl {
+    Primitive {
+        name: String,
+        primitive: PrimitiveType,
+        length: Option<usize>,
+    },
+    Decimal {
+        name: String,
+    },
+    Enum {
+        name: String,
+        encoding: PrimitiveType,
+        description: Option<String>,
+        /// Name, value and description of every code.
+        values: Vec<(String, String, Option<String>)>,
+    },
+}
+
+/// A message or group body, in SBE order.
+#[derive(Default)]
+struct Block {
+    fields: Vec<FieldOut>,
+    groups: Vec<GroupOut>,
+    data: Vec<DataOut>,
+    /// IDs of the fields, groups and data already in the block.
+    seen: HashSet<u16>,
+}
+
+struct FieldOut {
+    name: String,
+    id: u16,
+    type_name: String,
+    presence: Presence,
+    value_ref: Option<String>,
+    semantic_type: String,
+    description: Option<String>,
+}
+
+struct GroupOut {
+    name: String,
+    id: u16,
+    description: Option<String>,
+    block: Block,
+}
+
+struct DataOut {
+    name: String,
+    id: u16,
+    description: Option<String>,
+}
+
+struct MessageOut {
+    name: String,
+    id: u16,
+    msg_type: Option<String>,
+    description: Option<String>,
+    block: Block,
+}
+
+/// Converts repository definitions, declaring the types they use.
+struct Importer<'r, 'a> {
+    repository: &'r Repository<'a>,
+    options: &'r OrchestraOptions,
+    types: Vec<TypeDecl>,
+    type_names: HashSet<String>,
+}
+
+impl Importer<'_, '_> {
+    fn message(&mut self, message: &Element) -> Result<MessageOut, ParseError> {
+        let mut block = Block::default();
+        if let Some(structure) = message.child("structure") {
+            self.members(structure, message.scenario(), false, &mut block, 0)?;
+        }
+        Ok(MessageOut {
+            name: message.required("name")?.to_string(),
+            id: message.id("id")?,
+            msg_type: message.attr("msgType").map(str::to_string),
+            description: message.documentation(),
+            block,
+        })
+    }
+
+    /// Adds the members of a message structure, component or group to
+    /// `block`. Members of an optional component become optional.
+    fn members(
+        &mut self,
+        parent: &Element,
+        scenario: &str,
+        optional: bool,
+        block: &mut Block,
+        depth: usize,
+    ) -> Result<(), ParseError> {
+        if depth > MAX_DEPTH {
+            return Err(ParseError::InvalidStructure {
+                message: format!("components nested deeper than {MAX_DEPTH} levels"),
+            });
+        }
+        for member in &parent.children {
+            let id = || member.required("id");
+            let scenario = member.attr("scenario").unwrap_or(scenario);
+            let member_optional =
+                optional || member.attr("presence").is_none_or(|p| p == "optional");
+            match member.name.as_str() {
+                "fieldRef" => {
+                    let field =
+                        Repository::lookup(&self.repository.fields, "field", id()?, scenario)?;
+                    self.field(field, member, optional, block)?;
+                }
+                "componentRef" => {
+                    let component = Repository::lookup(
+                        &self.repository.components,
+                        "component",
+                        id()?,
+                        scenario,
+                    )?;
+                    if component
+                        .attr("name")
+                        .is_some_and(|n| SESSION_COMPONENTS.contains(&n))
+                    {
+                        continue;
+                    }
+                    self.members(component, scenario, member_optional, block, depth + 1)?;
+                }
+                "groupRef" => {
+                    let group =
+                        Repository::lookup(&self.repository.groups, "group", id()?, scenario)?;
+                    let id = match group.child("numInGroup") {
+                        Some(num_in_group) => num_in_group.id("id")?,
+                        None => group.id("id")?,
+                    };
+                    if !block.seen.insert(id) {
+                        continue;
+                    }
+                    let mut nested = Block::default();
+                    self.members(group, scenario, false, &mut nested, depth + 1)?;
+                    block.groups.push(GroupOut {
+                        name: group.required("name")?.to_string(),
+                        id,
+                        description: group.documentation(),
+                        block: nested,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the field referenced by `field_ref` to `block`.
+    fn field(
+        &mut self,
+        field: &Element,
+        field_ref: &Element,
+        optional: bool,
+        block: &mut Block,
+    ) -> Result<(), ParseError> {
+        let id_attr = field.required("id")?;
+        if self.repository.length_fields.contains(id_attr) {
+            return Ok(());
+        }
+        let id = field.id("id")?;
+        if !block.seen.insert(id) {
+            return Ok(());
+        }
+        let name = field.required("name")?;
+        let fix_type = field.required("type")?;
+        let description = field_ref.documentation().or_else(|| field.documentation());
+
+        let code_set = self
+            .repository
+            .code_sets
+            .get(&(fix_type, field.scenario()))
+            .or_else(|| self.repository.code_sets.get(&(fix_type, "base")))
+            .copied();
+        let datatype = match code_set {
+            Some(code_set) => code_set.required("type")?,
+            None => fix_type,
+        };
+        let encoding = self.encoding(datatype, name, 0)?;
+        if encoding == Encoding::VarData {
+            block.data.push(DataOut {
+                name: name.to_string(),
+                id,
+                description,
+            });
+            return Ok(());
+        }
+
+        let enum_name = match code_set {
+            Some(code_set) => self.declare_enum(code_set, encoding)?,
+            None => None,
+        };
+        let type_name = match &enum_name {
+            Some(enum_name) => enum_name.clone(),
+            None => self.declare(datatype, encoding),
+        };
+
+        let mut presence = match field_ref.attr("presence") {
+            Some("required") if !optional => Presence::Required,
+            _ => Presence::Optional,
+        };
+        let mut value_ref = None;
+        if field_ref.attr("presence") == Some("constant") {
+            presence = Presence::Required;
+            if let (Some(enum_name), Some(code_set), Some(value)) =
+                (&enum_name, code_set, field_ref.attr("value"))
+                && let Some(code) = code_set
+                    .children("code")
+                    .find(|c| c.attr("value") == Some(value))
+            {
+                presence = Presence::Constant;
+                value_ref = Some(format!("{enum_name}.{}", code.required("name")?));
+            }
+        }
+
+        block.fields.push(FieldOut {
+            name: name.to_string(),
+            id,
+            type_name,
+            presence,
+            value_ref,
+            semantic_type: datatype.to_string(),
+            description,
+        });
+        Ok(())
+    }
+
+    /// Resolves the encoding of the datatype `name` used by `field`.
+    fn encoding(&self, name: &str, field: &str, depth: usize) -> Result<Encoding, ParseError> {
+        let unknown = || ParseError::UnknownType {
+            type_name: name.to_string(),
+            field: field.to_string(),
+        };
+        let datatype = self.repository.datatypes.get(name);
+        if let Some(mapped) = datatype.and_then(|d| {
+            d.children("mappedDatatype")
+                .find(|m| m.attr("standard") == Some("SBE"))
+        }) {
+            let base = mapped.required("base")?;
+            let primitive = PrimitiveType::from_sbe_name(base)
+                .ok_or_else(|| ParseError::invalid_attr("mappedDatatype", "base", base))?;
+            let length = match mapped
+                .attr("parameter")
+                .and_then(|p| p.strip_prefix("length="))
+            {
+                Some(length) => Some(length.parse().map_err(|_| {
+                    ParseError::invalid_attr("mappedDatatype", "parameter", length)
+                })?),
+                None => None,
+            };
+            return Ok(Encoding::Primitive(primitive, length));
+        }
+        if let Some(encoding) = builtin_encoding(name, self.options.string_length) {
+            return Ok(encoding);
+        }
+        match datatype.and_then(|d| d.attr("baseType")) {
+            Some(base) if depth < MAX_DEPTH => self.encoding(base, field, depth + 1),
+            _ => Err(unknown()),
+        }
+    }
+
+    /// Declares the type for `datatype` once and returns its name.
+    fn declare(&mut self, datatype: &str, encoding: Encoding) -> String {
+        let name = type_name(datatype);
+        if self.type_names.insert(name.clone()) {
+            self.types.push(match encoding {
+                Encoding::Primitive(primitive, length) => TypeDecl::Primitive {
+                    name: name.clone(),
+                    primitive,
+                    length,
+                },
+                Encoding::Decimal => TypeDecl::Decimal { name: name.clone() },
+                Encoding::VarData => unreachable!("var data is not a field type"),
+            });
+        }
+        name
+    }
+
+    /// Declares `code_set` as an enum once and returns its name, or `None`
+    /// if its values do not fit an SBE enum.
+    fn declare_enum(
+        &mut self,
+        code_set: &Element,
+        encoding: Encoding,
+    ) -> Result<Option<String>, ParseError> {
+        let codes: Vec<_> = code_set.children("code").collect();
+        let values = codes
+            .iter()
+            .map(|c| c.required("value"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let encoding = match encoding {
+            Encoding::Primitive(PrimitiveType::Char, None | Some(1))
+                if values.iter().all(|v| v.chars().count() == 1) =>
+            {
+                PrimitiveType::Char
+            }
+            // 255 is the null value of a uint8 enum.
+            Encoding::Primitive(p, None)
+                if p != PrimitiveType::Char
+                    && values
+                        .iter()
+                        .all(|v| v.parse::<u8>().is_ok_and(|v| v < 255)) =>
+            {
+                PrimitiveType::Uint8
+            }
+            _ => return Ok(None),
+        };
+
+        let name = code_set.required("name")?.to_string();
+        if self.type_names.insert(name.clone()) {
+            self.types.push(TypeDecl::Enum {
+                name: name.clone(),
+                encoding,
+                description: code_set.documentation(),
+                values: codes
+                    .iter()
+                    .zip(values)
+                    .map(|(code, value)| {
+                        Ok((
+                            code.required("name")?.to_string(),
+                            value.to_string(),
+                            code.documentation(),
+                        ))
+                    })
+                    .collect::<Result<_, ParseError>>()?,
+            });
+        }
+        Ok(Some(name))
+    }
+
+    /// Writes the SBE schema document.
+    fn write(&self, root: &Element, messages: &[MessageOut]) -> String {
+        let options = self.options;
+        let package = options.package.clone().unwrap_or_else(|| {
+            root.attr("name")
+                .unwrap_or("fix")
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() {
+                        c.to_ascii_lowercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect()
+        });
+        let mut writer = XmlWriter::document();
+        writer.open(
+            "sbe:messageSchema",
+            Attrs::new()
+                .attr("xmlns:sbe", SBE_NAMESPACE)
+                .attr("package", package)
+                .attr("id", options.schema_id)
+                .attr("version", options.version)
+                .opt("semanticVersion", root.attr("version"))
+                .opt("description", root.documentation())
+                .attr(
+                    "byteOrder",
+                    match options.byte_order {
+                        ByteOrder::LittleEndian => "littleEndian",
+                        ByteOrder::BigEndian => "bigEndian",
+                    },
+                ),
+        );
+
+        writer.open("types", Attrs::new());
+        let composite = |writer: &mut XmlWriter, name: &str, members: &[(&str, &str, &str)]| {
+            writer.open("composite", Attrs::new().attr("name", name));
+            for (member, primitive, length) in members {
+                let mut attrs = Attrs::new()
+                    .attr("name", member)
+                    .attr("primitiveType", primitive);
+                if !length.is_empty() {
+                    attrs = attrs.attr("length", length);
+                }
+                writer.empty("type", attrs);
+            }
+            writer.close("composite");
+        };
+        composite(
+            &mut writer,
+            "messageHeader",
+            &[
+                ("blockLength", "uint16", ""),
+                ("templateId", "uint16", ""),
+                ("schemaId", "uint16", ""),
+                ("version", "uint16", ""),
+            ],
+        );
+        composite(
+            &mut writer,
+            "groupSizeEncoding",
+            &[("blockLength", "uint16", ""), ("numInGroup", "uint16", "")],
+        );
+        composite(
+            &mut writer,
+            "varDataEncoding",
+            &[("length", "uint16", ""), ("varData", "uint8", "0")],
+        );
+        for decl in &self.types {
+            match decl {
+                TypeDecl::Primitive {
+                    name,
+                    primitive,
+                    length,
+                } => writer.empty(
+                    "type",
+                    Attrs::new()
+                        .attr("name", name)
+                        .attr("primitiveType", primitive.sbe_name())
+                        .opt("length", *length),
+                ),
+                TypeDecl::Decimal { name } => composite(
+                    &mut writer,
+                    name,
+                    &[("mantissa", "int64", ""), ("exponent", "int8", "")],
+                ),
+                TypeDecl::Enum {
+                    name,
+                    encoding,
+                    description,
+                    values,
+                } => {
+                    writer.open(
+                        "enum",
+                        Attrs::new()
+                            .attr("name", name)
+                            .attr("encodingType", encoding.sbe_name())
+                            .opt("description", description.as_ref()),
+                    );
+                    for (name, value, description) in values {
+                        writer.text_element(
+                            "validValue",
+                            Attrs::new()
+                                .attr("name", name)
+                                .opt("description", description.as_ref()),
+                            value,
+                        );
+                    }
+                    writer.close("enum");
+                }
+            }
+        }
+        writer.close("types");
+
+        for message in messages {
+            writer.open(
+                "sbe:message",
+                Attrs::new()
+                    .attr("name", &message.name)
+                    .attr("id", message.id)
+                    .opt("semanticType", message.msg_type.as_ref())
+                    .opt("description", message.description.as_ref()),
+            );
+            write_block(&mut writer, &message.block);
+            writer.close("sbe:message");
+        }
+
+        writer.close("sbe:messageSchema");
+        writer.finish()
+    }
+}
+
+/// Writes the fields, groups and var data of a message or group.
+fn write_block(writer: &mut XmlWriter, block: &Block) {
+    for field in &block.fields {
+        writer.empty(
+            "field",
+            Attrs::new()
+                .attr("name", &field.name)
+                .attr("id", field.id)
+                .attr("type", &field.type_name)
+                .presence(field.presence)
+                .opt("valueRef", field.value_ref.as_ref())
+                .attr("semanticType", &field.semantic_type)
+                .opt("description", field.description.as_ref()),
+        );
+    }
+    for group in &block.groups {
+        writer.open(
+            "group",
+            Attrs::new()
+                .attr("name", &group.name)
+                .attr("id", group.id)
+                .opt("description", group.description.as_ref()),
+        );
+        write_block(writer, &group.block);
+        writer.close("group");
+    }
+    for data in &block.data {
+        writer.empty(
+            "data",
+            Attrs::new()
+                .attr("name", &data.name)
+                .attr("id", data.id)
+                .attr("type", "varDataEncoding")
+                .opt("description", data.description.as_ref()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::MessageDef;
+    use crate::types::TypeDef;
+    use crate::validation::validate_schema;
+
+    const REPOSITORY: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<fixr:repository xmlns:fixr="http://fixprotocol.io/2020/orchestra/repository"
+                 name="FIX.Latest" version="FIX.Latest_EP269">
+    <fixr:datatypes>
+        <fixr:datatype name="int"/>
+        <fixr:datatype name="Length" baseType="int"/>
+        <fixr:datatype name="NumInGroup" baseType="int"/>
+        <fixr:datatype name="String"/>
+        <fixr:datatype name="char"/>
+        <fixr:datatype name="Price" baseType="float"/>
+        <fixr:datatype name="Qty" baseType="float"/>
+        <fixr:datatype name="UTCTimestamp" baseType="String"/>
+        <fixr:datatype name="data" baseType="String"/>
+        <fixr:datatype name="SymbolString" baseType="String">
+            <fixr:mappedDatatype standard="SBE" base="char" parameter="length=8"/>
+        </fixr:datatype>
+    </fixr:datatypes>
+    <fixr:codeSets>
+        <fixr:codeSet name="SideCodeSet" id="54" type="char">
+            <fixr:code name="Buy" id="54001" value="1">
+                <fixr:annotation><fixr:documentation>Buy &amp; hold</fixr:documentation></fixr:annotation>
+            </fixr:code>
+            <fixr:code name="Sell" id="54002" value="2"/>
+        </fixr:codeSet>
+        <fixr:codeSet name="PartyRoleCodeSet" id="452" type="int">
+            <fixr:code name="ExecutingFirm" id="452001" value="1"/>
+            <fixr:code name="ClearingFirm" id="452004" value="4"/>
+        </fixr:codeSet>
+        <fixr:codeSet name="ExecTypeCodeSet" id="150" type="String">
+            <fixr:code name="New" id="150001" value="0"/>
+            <fixr:code name="Trade" id="150002" value="F2"/>
+        </fixr:codeSet>
+    </fixr:codeSets>
+    <fixr:fields>
+        <fixr:field id="8" name="BeginString" type="String"/>
+        <fixr:field id="35" name="MsgType" type="String"/>
+        <fixr:field id="11" name="ClOrdID" type="String"/>
+        <fixr:field id="54" name="Side" type="SideCodeSet"/>
+        <fixr:field id="55" name="Symbol" type="SymbolString"/>
+        <fixr:field id="38" name="OrderQty" type="Qty"/>
+        <fixr:field id="44" name="Price" type="Price"/>
+        <fixr:field id="60" name="TransactTime" type="UTCTimestamp"/>
+        <fixr:field id="448" name="PartyID" type="String"/>
+        <fixr:field id="452" name="PartyRole" type="PartyRoleCodeSet"/>
+        <fixr:field id="453" name="NoPartyIDs" type="NumInGroup"/>
+        <fixr:field id="95" name="RawDataLength" type="Length"/>
+        <fixr:field id="96" name="RawData" type="data" lengthId="95"/>
+        <fixr:field id="150" name="ExecType" type="ExecTypeCodeSet"/>
+    </fixr:fields>
+    <fixr:components>
+        <fixr:component name="StandardHeader" id="1024">
+            <fixr:fieldRef id="8" presence="required"/>
+            <fixr:fieldRef id="35" presence="required"/>
+        </fixr:component>
+        <fixr:component name="Instrument" id="1003">
+            <fixr:fieldRef id="55" presence="required"/>
+        </fixr:component>
+    </fixr:components>
+    <fixr:groups>
+        <fixr:group name="Parties" id="1012">
+            <fixr:numInGroup id="453"/>
+            <fixr:fieldRef id="448" presence="required"/>
+            <fixr:fieldRef id="452"/>
+        </fixr:group>
+    </fixr:groups>
+    <fixr:messages>
+        <fixr:message name="NewOrderSingle" id="14" msgType="D">
+            <fixr:structure>
+                <fixr:componentRef id="1024" presence="required"/>
+                <fixr:fieldRef id="11" presence="required"/>
+                <fixr:groupRef id="1012"/>
+                <fixr:componentRef id="1003" presence="required"/>
+                <fixr:fieldRef id="54" presence="required"/>
+                <fixr:fieldRef id="60" presence="required"/>
+                <fixr:fieldRef id="38" presence="required"/>
+                <fixr:fieldRef id="44"/>
+                <fixr:fieldRef id="95"/>
+                <fixr:fieldRef id="96"/>
+            </fixr:structure>
+            <fixr:annotation><fixr:documentation>
+                Submits a new order.
+            </fixr:documentation></fixr:annotation>
+        </fixr:message>
+        <fixr:message name="ExecutionReport" id="8" msgType="8">
+            <fixr:structure>
+                <fixr:fieldRef id="150" presence="required"/>
+                <fixr:fieldRef id="54" presence="constant" value="2"/>
+                <fixr:componentRef id="1003"/>
+            </fixr:structure>
+        </fixr:message>
+        <fixr:message name="NewOrderSingle" id="14" msgType="D" scenario="Crypto">
+            <fixr:structure/>
+        </fixr:message>
+    </fixr:messages>
+</fixr:repository>"#;
+
+    fn message<'a>(schema: &'a Schema, name: &str) -> &'a MessageDef {
+        schema
+            .messages
+            .iter()
+            .find(|m| m.name == name)
+            .unwrap_or_else(|| panic!("no message {name}"))
+    }
+
+    #[test]
+    fn test_import_orchestra_messages() {
+        let schema = import_orchestra(REPOSITORY, &OrchestraOptions::new()).expect("import");
+        validate_schema(&schema).expect("imported schema is invalid");
+        assert_eq!(schema.package, "fix_latest");
+        assert_eq!(schema.semantic_version, "FIX.Latest_EP269");
+        assert_eq!(schema.messages.len(), 2, "only base scenarios");
+
+        let order = message(&schema, "NewOrderSingle");
+        assert_eq!(order.id, 14);
+        assert_eq!(order.semantic_type.as_deref(), Some("D"));
+        assert_eq!(order.description.as_deref(), Some("Submits a new order."));
+        let fields: Vec<_> = order.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "ClOrdID",
+                "Symbol",
+                "Side",
+                "TransactTime",
+                "OrderQty",
+                "Price"
+            ]
+        );
+        assert_eq!(order.fields[0].encoded_length, DEFAULT_STRING_LENGTH);
+        assert_eq!(order.fields[1].encoded_length, 8);
+        assert_eq!(order.fields[5].presence, Presence::Optional);
+        assert_eq!(order.fields[4].presence, Presence::Required);
+
+        let parties = &order.groups[0];
+        assert_eq!((parties.name.as_str(), parties.id), ("Parties", 453));
+        assert_eq!(parties.fields.len(), 2);
+        assert_eq!(order.data_fields[0].name, "RawData");
+
+        let report = message(&schema, "ExecutionReport");
+        let side = &report.fields[1];
+        assert_eq!(side.presence, Presence::Constant);
+        assert_eq!(side.value_ref.as_deref(), Some("SideCodeSet.Sell"));
+        assert_eq!(report.fields[2].presence, Presence::Optional);
+    }
+
+    #[test]
+    fn test_import_orchestra_types() {
+        let schema = import_orchestra(REPOSITORY, &OrchestraOptions::new()).expect("import");
+        let find = |name: &str| schema.types.iter().find(|t| t.name() == name);
+
+        let Some(TypeDef::Enum(side)) = find("SideCodeSet") else {
+            panic!("SideCodeSet is not an enum");
+        };
+        assert_eq!(side.encoding_type, PrimitiveType::Char);
+        assert_eq!(
+            side.valid_values[0].description.as_deref(),
+            Some("Buy & hold")
+        );
+        let Some(TypeDef::Enum(role)) = find("PartyRoleCodeSet") else {
+            panic!("PartyRoleCodeSet is not an enum");
+        };
+        assert_eq!(role.encoding_type, PrimitiveType::Uint8);
+        // Multi-character codes do not fit an enum.
+        assert!(find("ExecTypeCodeSet").is_none());
+        assert!(matches!(find("Price"), Some(TypeDef::Composite(_))));
+        assert!(
+            matches!(find("UTCTimestamp"), Some(TypeDef::Primitive(p)) if p.primitive_type == PrimitiveType::Uint64)
+        );
+        // Session header fields are not imported.
+        assert!(
+            schema
+                .messages
+                .iter()
+                .all(|m| m.fields.iter().all(|f| f.name != "MsgType"))
+        );
+    }
+
+    #[test]
+    fn test_import_orchestra_options_and_errors() {
+        let options = OrchestraOptions::new()
+            .package("orders")
+            .schema_id(42)
+            .string_length(12)
+            .messages(["ExecutionReport"]);
+        let xml = orchestra_to_sbe(REPOSITORY, &options).expect("import");
+        let schema = parse_schema(&xml).expect("parse");
+        assert_eq!((schema.package.as_str(), schema.id), ("orders", 42));
+        assert_eq!(schema.messages.len(), 1);
+        assert_eq!(schema.messages[0].fields[0].encoded_length, 12);
+        assert!(
+            !xml.contains("PartyRoleCodeSet"),
+            "unused types are left out"
+        );
+
+        let broken = REPOSITORY.replace(r#"type="Qty""#, r#"type="Mystery""#);
+        let err = import_orchestra(&broken, &OrchestraOptions::new()).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("message[NewOrderSingle]"), "{message}");
+        assert!(message.contains("Mystery"), "{message}");
+    }
+}
@@ -2,15 +2,35 @@
 //!
 //! This module provides functionality to parse FIX SBE XML schema files
 //! into the internal schema representation.
+//!
+//! Errors raised while reading a document are wrapped in
+//! [`ParseError::Located`], giving the line and column and the path of the
+//! enclosing elements, e.g. `message[NewOrderSingle]/field[price]`. In a
+//! schema split with includes, positions refer to the expanded document,
+//! except for errors raised while expanding an include.
 
 use crate::error::ParseError;
+use crate::include::{file_resolver, resolve_includes};
 use crate::messages::{DataFieldDef, FieldDef, GroupDef, MessageDef};
+use crate::strict::check_strict;
 use crate::types::{
-    ByteOrder, CompositeDef, CompositeField, EnumDef, EnumValue, Presence, PrimitiveDef,
-    PrimitiveType, Schema, SetChoice, SetDef, TypeDef,
+    ByteOrder, CompositeDef, CompositeField, EnumDef, EnumValue, MemberKind, Presence,
+    PrimitiveDef, PrimitiveType, Schema, SetChoice, SetDef, TypeDef,
 };
 use quick_xml::Reader;
+use quick_xml::escape::unescape;
+use quick_xml::events::attributes::Attribute;
 use quick_xml::events::{BytesStart, Event};
+use std::borrow::Cow;
+use std::io;
+use std::path::Path;
+
+/// Deepest nesting of repeating groups, or of composites declared inline in
+/// composites, that the parser accepts.
+///
+/// Both are parsed recursively, so unbounded nesting in a crafted schema
+/// would exhaust the stack.
+pub const MAX_NESTING: usize = 32;
 
 /// Parses an SBE XML schema from a string.
 ///
@@ -20,12 +40,226 @@ use quick_xml::events::{BytesStart, Event};
 /// # Returns
 /// Parsed schema or parse error.
 ///
+/// `<xi:include>` elements are rejected, as there is nothing to resolve
+/// them against; use [`parse_schema_with_resolver`] or
+/// [`parse_schema_file`] for schemas split with XInclude.
+///
 /// # Errors
 /// Returns `ParseError` if the XML is malformed or contains invalid schema elements.
 pub fn parse_schema(xml: &str) -> Result<Schema, ParseError> {
+    parse_schema_with_resolver(xml, no_resolver)
+}
+
+/// Parses an SBE XML schema, expanding `<xi:include>` elements with the
+/// documents `resolver` returns for their `href`.
+///
+/// The resolver lets includes come from memory, an archive or the file
+/// system alike; see [`resolve_includes`] for the supported forms.
+///
+/// # Arguments
+/// * `xml` - XML schema content
+/// * `resolver` - Returns the content of the document at an `href`
+///
+/// # Returns
+/// Parsed schema or parse error.
+///
+/// # Errors
+/// Returns `ParseError` if an include cannot be resolved, or if the
+/// expanded XML is malformed or contains invalid schema elements.
+pub fn parse_schema_with_resolver<F>(xml: &str, resolver: F) -> Result<Schema, ParseError>
+where
+    F: FnMut(&str) -> io::Result<String>,
+{
+    parse_document(&resolve_includes(xml, resolver)?, Pass::Full)
+}
+
+/// Parses an SBE XML schema file, resolving `<xi:include>` elements
+/// relative to the file's directory.
+///
+/// # Errors
+/// Returns `ParseError` if a file cannot be read, or if the expanded XML
+/// is malformed or contains invalid schema elements.
+pub fn parse_schema_file(path: &Path) -> Result<Schema, ParseError> {
+    let xml = std::fs::read_to_string(path)?;
+    let base = path.parent().unwrap_or(Path::new("."));
+    parse_schema_with_resolver(&xml, file_resolver(base))
+}
+
+/// Parses an SBE XML schema from a string, rejecting anything the SBE
+/// schema format does not define.
+///
+/// [`parse_schema`] is permissive and skips unknown elements and
+/// attributes, which suits third-party venue files. This variant first runs
+/// [`check_strict`], so typos and misplaced declarations fail instead of
+/// being ignored; use it where schemas are authored, e.g. in CI.
+///
+/// # Errors
+/// Returns `ParseError` for anything [`parse_schema`] rejects, and for the
+/// violations listed in [`crate::strict`].
+pub fn parse_schema_strict(xml: &str) -> Result<Schema, ParseError> {
+    check_strict(xml)?;
+    parse_schema(xml)
+}
+
+/// Parses an SBE XML schema file like [`parse_schema_file`], rejecting
+/// anything the SBE schema format does not define.
+///
+/// Includes are expanded before the [`check_strict`] pass, so errors are
+/// located in the expanded document.
+///
+/// # Errors
+/// Returns `ParseError` for anything [`parse_schema_file`] rejects, and
+/// for the violations listed in [`crate::strict`].
+pub fn parse_schema_file_strict(path: &Path) -> Result<Schema, ParseError> {
+    let xml = std::fs::read_to_string(path)?;
+    let base = path.parent().unwrap_or(Path::new("."));
+    let xml = resolve_includes(&xml, file_resolver(base))?;
+    check_strict(&xml)?;
+    parse_document(&xml, Pass::Full)
+}
+
+/// Resolver for parsing without include support.
+fn no_resolver(_href: &str) -> io::Result<String> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "no include resolver; use parse_schema_with_resolver",
+    ))
+}
+
+/// Parses a schema split across several SBE XML documents into one schema.
+///
+/// Exchanges often publish common types and per-segment messages in
+/// separate files. All documents must declare the same schema id, version,
+/// byte order and header type; the package and description are taken from
+/// the first one. Types are collected from every document before any
+/// message is parsed, so a message may use a type declared in any file.
+///
+/// A type may be declared in several documents as long as every
+/// declaration is identical. Like [`parse_schema`], this rejects
+/// `<xi:include>`; expand includes first with [`resolve_includes`].
+///
+/// # Arguments
+/// * `xmls` - XML schema documents
+///
+/// # Returns
+/// The merged schema or a parse error.
+///
+/// # Errors
+/// Returns `ParseError` if a document is malformed, if the documents
+/// disagree on a `messageSchema` attribute, if a type is declared twice
+/// with different definitions, or if two messages share a name or
+/// template ID.
+pub fn parse_schemas(xmls: &[&str]) -> Result<Schema, ParseError> {
+    let mut merged: Option<Schema> = None;
+
+    for xml in xmls {
+        let document = parse_document(&resolve_includes(xml, no_resolver)?, Pass::Types)?;
+        let Some(schema) = merged.as_mut() else {
+            merged = Some(document);
+            continue;
+        };
+        check_same_attributes(schema, &document)?;
+        for type_def in document.types {
+            match schema.get_type(type_def.name()) {
+                Some(existing) if *existing == type_def => {}
+                Some(_) => return Err(ParseError::duplicate("type", type_def.name())),
+                None => schema.add_type(type_def),
+            }
+        }
+    }
+
+    let mut schema = merged.ok_or_else(|| ParseError::InvalidStructure {
+        message: "No schema documents to merge".to_string(),
+    })?;
+    resolve_refs(&mut schema, true)?;
+
+    for xml in xmls {
+        let document = parse_document(xml, Pass::Messages(&schema))?;
+        for msg in document.messages {
+            if let Some(existing) = schema
+                .messages
+                .iter()
+                .find(|m| m.name == msg.name || m.id == msg.id)
+            {
+                return Err(if existing.name == msg.name {
+                    ParseError::duplicate("message", &msg.name)
+                } else {
+                    ParseError::duplicate("template ID", msg.id.to_string())
+                });
+            }
+            schema.messages.push(msg);
+        }
+    }
+
+    Ok(schema)
+}
+
+/// Checks that `document` declares the same `messageSchema` attributes as
+/// the first document, `schema`.
+fn check_same_attributes(schema: &Schema, document: &Schema) -> Result<(), ParseError> {
+    let pairs = [
+        ("id", schema.id.to_string(), document.id.to_string()),
+        (
+            "version",
+            schema.version.to_string(),
+            document.version.to_string(),
+        ),
+        (
+            "byteOrder",
+            format!("{:?}", schema.byte_order),
+            format!("{:?}", document.byte_order),
+        ),
+        (
+            "headerType",
+            schema.header_type.clone(),
+            document.header_type.clone(),
+        ),
+    ];
+    for (attribute, expected, actual) in pairs {
+        if expected != actual {
+            return Err(ParseError::MergeMismatch {
+                attribute: attribute.to_string(),
+                expected,
+                actual,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Parts of a schema document read by [`parse_document`].
+#[derive(Clone, Copy)]
+enum Pass<'s> {
+    /// Types and messages, resolving field types against the document.
+    Full,
+    /// Types only.
+    Types,
+    /// Messages only, resolving field types against the given schema.
+    Messages(&'s Schema),
+}
+
+/// Parses the parts of `xml` selected by `pass`.
+///
+/// Errors raised while reading the document carry the line and column the
+/// reader had reached, and the path of the enclosing elements.
+fn parse_document(xml: &str, pass: Pass<'_>) -> Result<Schema, ParseError> {
     let mut reader = Reader::from_str(xml);
     reader.config_mut().trim_text(true);
 
+    let schema =
+        read_document(&mut reader, pass).map_err(|err| err.at_reader_position(xml, &reader))?;
+
+    let mut schema = schema.ok_or_else(|| ParseError::InvalidStructure {
+        message: "No messageSchema element found".to_string(),
+    })?;
+    if matches!(pass, Pass::Full) {
+        resolve_refs(&mut schema, true)?;
+    }
+    Ok(schema)
+}
+
+/// Reads the elements of a document selected by `pass`.
+fn read_document(reader: &mut Reader<&[u8]>, pass: Pass<'_>) -> Result<Option<Schema>, ParseError> {
     let mut schema: Option<Schema> = None;
     let mut buf = Vec::new();
 
@@ -34,17 +268,25 @@ pub fn parse_schema(xml: &str) -> Result<Schema, ParseError> {
             Ok(Event::Start(ref e)) => {
                 let name_bytes = e.name().as_ref().to_vec();
                 let name = std::str::from_utf8(&name_bytes)?;
-                match name {
-                    "messageSchema" | "sbe:messageSchema" => {
-                        schema = Some(parse_message_schema(e)?);
-                    }
-                    "types" if schema.is_some() => {
-                        parse_types(&mut reader, schema.as_mut().unwrap())?;
+                match (name, schema.as_mut()) {
+                    ("messageSchema" | "sbe:messageSchema", _) => {
+                        schema = Some(scoped(e, parse_message_schema(e))?);
                     }
-                    "message" | "sbe:message" if schema.is_some() => {
-                        let msg = parse_message(&mut reader, e, schema.as_ref().unwrap())?;
-                        schema.as_mut().unwrap().messages.push(msg);
+                    ("types", Some(schema)) if !matches!(pass, Pass::Messages(_)) => {
+                        parse_types(reader, schema)?;
+                        resolve_refs(schema, false)?;
                     }
+                    ("message" | "sbe:message", Some(schema)) => match pass {
+                        Pass::Full => {
+                            let msg = scoped(e, parse_message(reader, e, schema))?;
+                            schema.messages.push(msg);
+                        }
+                        Pass::Messages(context) => {
+                            let msg = scoped(e, parse_message(reader, e, context))?;
+                            schema.messages.push(msg);
+                        }
+                        Pass::Types => skip_to_end(reader, name)?,
+                    },
                     _ => {}
                 }
             }
@@ -55,9 +297,28 @@ pub fn parse_schema(xml: &str) -> Result<Schema, ParseError> {
         buf.clear();
     }
 
-    schema.ok_or_else(|| ParseError::InvalidStructure {
-        message: "No messageSchema element found".to_string(),
-    })
+    Ok(schema)
+}
+
+/// Returns the path segment naming an element, e.g. `field[price]`.
+pub(crate) fn segment(e: &BytesStart<'_>) -> String {
+    let tag = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+    match e.try_get_attribute("name") {
+        Ok(Some(name)) => format!("{tag}[{}]", String::from_utf8_lossy(&name.value)),
+        _ => tag,
+    }
+}
+
+/// Adds the element `e` to the path of an error raised while parsing it.
+fn scoped<T>(e: &BytesStart<'_>, result: Result<T, ParseError>) -> Result<T, ParseError> {
+    result.map_err(|err| err.within(&segment(e)))
+}
+
+/// Returns the value of `attr` with entity and character references
+/// (`&amp;`, `&#60;`, ...) replaced.
+pub(crate) fn attr_value<'a>(attr: &'a Attribute<'_>) -> Result<Cow<'a, str>, ParseError> {
+    let raw = std::str::from_utf8(&attr.value)?;
+    unescape(raw).map_err(|e| ParseError::Xml(e.into()))
 }
 
 /// Parses the messageSchema element attributes.
@@ -72,7 +333,7 @@ fn parse_message_schema(e: &BytesStart<'_>) -> Result<Schema, ParseError> {
 
     for attr in e.attributes().flatten() {
         let key = std::str::from_utf8(attr.key.as_ref())?;
-        let value = std::str::from_utf8(&attr.value)?;
+        let value = &*attr_value(&attr)?;
 
         match key {
             "package" => package = value.to_string(),
@@ -119,22 +380,26 @@ fn parse_types(reader: &mut Reader<&[u8]>, schema: &mut Schema) -> Result<(), Pa
                 let name = std::str::from_utf8(&name_bytes)?;
                 match name {
                     "type" => {
-                        let type_def = parse_primitive_type(reader, e)?;
+                        let type_def = scoped(e, parse_primitive_type(reader, e))?;
                         schema.add_type(TypeDef::Primitive(type_def));
                         depth -= 1; // parse_primitive_type consumes the end tag
                     }
                     "composite" => {
-                        let composite = parse_composite(reader, e)?;
+                        let mut nested = Vec::new();
+                        let composite = scoped(e, parse_composite(reader, e, &mut nested, 1))?;
+                        for type_def in nested {
+                            schema.add_type(type_def);
+                        }
                         schema.add_type(TypeDef::Composite(composite));
                         depth -= 1;
                     }
                     "enum" => {
-                        let enum_def = parse_enum(reader, e)?;
+                        let enum_def = scoped(e, parse_enum(reader, e))?;
                         schema.add_type(TypeDef::Enum(enum_def));
                         depth -= 1;
                     }
                     "set" => {
-                        let set_def = parse_set(reader, e)?;
+                        let set_def = scoped(e, parse_set(reader, e))?;
                         schema.add_type(TypeDef::Set(set_def));
                         depth -= 1;
                     }
@@ -145,7 +410,7 @@ fn parse_types(reader: &mut Reader<&[u8]>, schema: &mut Schema) -> Result<(), Pa
                 let name_bytes = e.name().as_ref().to_vec();
                 let name = std::str::from_utf8(&name_bytes)?;
                 if name == "type" {
-                    let type_def = parse_primitive_type_empty(e)?;
+                    let type_def = scoped(e, parse_primitive_type_empty(e))?;
                     schema.add_type(TypeDef::Primitive(type_def));
                 }
             }
@@ -171,15 +436,22 @@ fn parse_primitive_type(
     e: &BytesStart<'_>,
 ) -> Result<PrimitiveDef, ParseError> {
     let mut type_def = parse_primitive_type_empty(e)?;
+    type_def.constant_value = read_constant_text(reader)?;
+    Ok(type_def)
+}
+
+/// Reads until the end tag of a `<type>` element, returning its trimmed
+/// text content (the constant value), if any.
+fn read_constant_text(reader: &mut Reader<&[u8]>) -> Result<Option<String>, ParseError> {
     let mut buf = Vec::new();
+    let mut constant_value = None;
 
-    // Read until end tag, capturing any constant value
     loop {
         match reader.read_event_into(&mut buf) {
             Ok(Event::Text(ref t)) => {
                 let text = std::str::from_utf8(t.as_ref())?.trim();
                 if !text.is_empty() {
-                    type_def.constant_value = Some(text.to_string());
+                    constant_value = Some(text.to_string());
                 }
             }
             Ok(Event::End(_)) => break,
@@ -190,7 +462,7 @@ fn parse_primitive_type(
         buf.clear();
     }
 
-    Ok(type_def)
+    Ok(constant_value)
 }
 
 /// Parses a primitive type definition (empty element).
@@ -207,7 +479,7 @@ fn parse_primitive_type_empty(e: &BytesStart<'_>) -> Result<PrimitiveDef, ParseE
 
     for attr in e.attributes().flatten() {
         let key = std::str::from_utf8(attr.key.as_ref())?;
-        let value = std::str::from_utf8(&attr.value)?;
+        let value = &*attr_value(&attr)?;
 
         match key {
             "name" => name = value.to_string(),
@@ -217,13 +489,7 @@ fn parse_primitive_type_empty(e: &BytesStart<'_>) -> Result<PrimitiveDef, ParseE
                         .ok_or_else(|| ParseError::invalid_attr("type", "primitiveType", value))?,
                 )
             }
-            "length" => {
-                length = Some(
-                    value
-                        .parse()
-                        .map_err(|_| ParseError::invalid_attr("type", "length", value))?,
-                )
-            }
+            "length" => length = Some(parse_extent("type", "length", value)?),
             "nullValue" => null_value = Some(value.to_string()),
             "minValue" => min_value = Some(value.to_string()),
             "maxValue" => max_value = Some(value.to_string()),
@@ -249,18 +515,24 @@ fn parse_primitive_type_empty(e: &BytesStart<'_>) -> Result<PrimitiveDef, ParseE
     Ok(type_def)
 }
 
-/// Parses a composite type definition.
+/// Parses a composite type definition nested `depth` levels deep.
+///
+/// Enums, sets and composites declared inline are pushed to `nested` as
+/// types of their own, and the composite refers to them by name.
 fn parse_composite(
     reader: &mut Reader<&[u8]>,
     e: &BytesStart<'_>,
+    nested: &mut Vec<TypeDef>,
+    depth: usize,
 ) -> Result<CompositeDef, ParseError> {
+    check_nesting("composites", depth)?;
     let mut name = String::new();
     let mut description = None;
     let mut semantic_type = None;
 
     for attr in e.attributes().flatten() {
         let key = std::str::from_utf8(attr.key.as_ref())?;
-        let value = std::str::from_utf8(&attr.value)?;
+        let value = &*attr_value(&attr)?;
 
         match key {
             "name" => name = value.to_string(),
@@ -275,17 +547,61 @@ fn parse_composite(
     composite.semantic_type = semantic_type;
 
     let mut buf = Vec::new();
-    let mut current_offset = 0;
+    // Unknown after a `<ref>` until the referenced type is resolved.
+    let mut current_offset = Some(0);
 
     loop {
         match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+            Ok(Event::Start(ref e)) => {
                 let name_bytes = e.name().as_ref().to_vec();
                 let tag_name = std::str::from_utf8(&name_bytes)?;
-                if tag_name == "type" {
-                    let field = parse_composite_field(e, current_offset)?;
-                    current_offset += field.encoded_length;
-                    composite.add_field(field);
+                match tag_name {
+                    "type" => {
+                        let mut field = scoped(e, parse_composite_field(e, current_offset))?;
+                        let text = read_constant_text(reader)?;
+                        if field.is_constant() {
+                            field.constant_value = text;
+                        }
+                        current_offset = next_offset(&field);
+                        composite.add_field(field);
+                    }
+                    "ref" => {
+                        composite.add_field(scoped(e, parse_composite_ref(e))?);
+                        current_offset = None;
+                        skip_to_end(reader, tag_name)?;
+                    }
+                    "enum" | "set" | "composite" => {
+                        let type_def = scoped(
+                            e,
+                            match tag_name {
+                                "enum" => parse_enum(reader, e).map(TypeDef::Enum),
+                                "set" => parse_set(reader, e).map(TypeDef::Set),
+                                _ => parse_composite(reader, e, nested, depth + 1)
+                                    .map(TypeDef::Composite),
+                            },
+                        )?;
+                        composite
+                            .add_field(scoped(e, nested_member(e, tag_name, type_def.name()))?);
+                        nested.push(type_def);
+                        current_offset = None;
+                    }
+                    _ => skip_to_end(reader, tag_name)?,
+                }
+            }
+            Ok(Event::Empty(ref e)) => {
+                let name_bytes = e.name().as_ref().to_vec();
+                let tag_name = std::str::from_utf8(&name_bytes)?;
+                match tag_name {
+                    "type" => {
+                        let field = scoped(e, parse_composite_field(e, current_offset))?;
+                        current_offset = next_offset(&field);
+                        composite.add_field(field);
+                    }
+                    "ref" => {
+                        composite.add_field(scoped(e, parse_composite_ref(e))?);
+                        current_offset = None;
+                    }
+                    _ => {}
                 }
             }
             Ok(Event::End(_)) => break,
@@ -299,21 +615,27 @@ fn parse_composite(
     Ok(composite)
 }
 
+/// Returns the offset following `field`, if known.
+fn next_offset(field: &CompositeField) -> Option<usize> {
+    field.offset.map(|offset| offset + field.encoded_length)
+}
+
 /// Parses a field within a composite type.
 fn parse_composite_field(
     e: &BytesStart<'_>,
-    default_offset: usize,
+    default_offset: Option<usize>,
 ) -> Result<CompositeField, ParseError> {
     let mut name = String::new();
     let mut primitive_type: Option<PrimitiveType> = None;
     let mut offset = None;
     let mut semantic_type = None;
     let mut description = None;
-    let constant_value = None;
+    let mut presence = Presence::Required;
+    let mut value_ref = None;
 
     for attr in e.attributes().flatten() {
         let key = std::str::from_utf8(attr.key.as_ref())?;
-        let value = std::str::from_utf8(&attr.value)?;
+        let value = &*attr_value(&attr)?;
 
         match key {
             "name" => name = value.to_string(),
@@ -323,34 +645,228 @@ fn parse_composite_field(
                         .ok_or_else(|| ParseError::invalid_attr("type", "primitiveType", value))?,
                 )
             }
-            "offset" => {
-                offset = Some(
-                    value
-                        .parse()
-                        .map_err(|_| ParseError::invalid_attr("type", "offset", value))?,
-                )
-            }
+            "offset" => offset = Some(parse_extent("type", "offset", value)?),
             "semanticType" => semantic_type = Some(value.to_string()),
             "description" => description = Some(value.to_string()),
-            "presence" if value == "constant" => {}
+            "presence" => {
+                presence = Presence::parse(value)
+                    .ok_or_else(|| ParseError::invalid_attr("type", "presence", value))?
+            }
+            "valueRef" => value_ref = Some(value.to_string()),
             _ => {}
         }
     }
 
     let prim = primitive_type.ok_or_else(|| ParseError::missing_attr("type", "primitiveType"))?;
     let type_name = prim.sbe_name().to_string();
-    let encoded_length = prim.size();
+    // Constant members are not encoded.
+    let encoded_length = if presence == Presence::Constant {
+        0
+    } else {
+        prim.size()
+    };
 
     let mut field = CompositeField::new(name, type_name, encoded_length);
     field.primitive_type = Some(prim);
-    field.offset = offset.or(Some(default_offset));
+    field.offset = offset.or(default_offset);
     field.semantic_type = semantic_type;
     field.description = description;
-    field.constant_value = constant_value;
+    field.presence = presence;
+    field.value_ref = value_ref;
 
     Ok(field)
 }
 
+/// Parses a `<ref>` within a composite type.
+///
+/// The referenced type may be declared later, so the member is a
+/// [`MemberKind::Ref`] placeholder until [`resolve_refs`] resolves it.
+fn parse_composite_ref(e: &BytesStart<'_>) -> Result<CompositeField, ParseError> {
+    let mut name = String::new();
+    let mut type_name = None;
+    let mut offset = None;
+    let mut semantic_type = None;
+    let mut description = None;
+
+    for attr in e.attributes().flatten() {
+        let key = std::str::from_utf8(attr.key.as_ref())?;
+        let value = &*attr_value(&attr)?;
+
+        match key {
+            "name" => name = value.to_string(),
+            "type" => type_name = Some(value.to_string()),
+            "offset" => offset = Some(parse_extent("ref", "offset", value)?),
+            "semanticType" => semantic_type = Some(value.to_string()),
+            "description" => description = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    let type_name = type_name.ok_or_else(|| ParseError::missing_attr("ref", "type"))?;
+    let mut field = CompositeField::new(name, type_name, 0);
+    field.kind = MemberKind::Ref;
+    field.offset = offset;
+    field.semantic_type = semantic_type;
+    field.description = description;
+    Ok(field)
+}
+
+/// Creates the member for an enum, set or composite declared inline in a
+/// composite, referring to it like a `<ref>` would.
+fn nested_member(
+    e: &BytesStart<'_>,
+    element: &str,
+    type_name: &str,
+) -> Result<CompositeField, ParseError> {
+    let mut field = CompositeField::new(type_name.to_string(), type_name.to_string(), 0);
+    field.kind = MemberKind::Ref;
+
+    for attr in e.attributes().flatten() {
+        if attr.key.as_ref() == b"offset" {
+            field.offset = Some(parse_extent(element, "offset", &attr_value(&attr)?)?);
+        }
+    }
+
+    Ok(field)
+}
+
+/// Returns true if `field` is a `<ref>` not resolved yet.
+fn is_unresolved_ref(field: &CompositeField) -> bool {
+    field.kind == MemberKind::Ref
+}
+
+/// Resolves every `<ref>` in the composites of `schema`.
+///
+/// A ref to a primitive becomes a primitive member, and a ref to an enum,
+/// set or composite a member of that kind, sized after the referenced type.
+/// Refs nest: a referenced composite is resolved first, so its length is
+/// known.
+///
+/// With `strict` unset, refs to types not declared yet are left for a later
+/// call; otherwise they are an error.
+fn resolve_refs(schema: &mut Schema, strict: bool) -> Result<(), ParseError> {
+    loop {
+        let mut pending = None;
+        let mut progress = false;
+
+        for index in 0..schema.types.len() {
+            let TypeDef::Composite(composite) = &schema.types[index] else {
+                continue;
+            };
+            if !composite.fields.iter().any(is_unresolved_ref) {
+                continue;
+            }
+            match expand_refs(composite, schema) {
+                // Each ref can multiply the size of the composite it names,
+                // so chains of them must be capped as they resolve.
+                Some(expanded) if expanded.encoded_length() > usize::from(u16::MAX) => {
+                    return Err(ParseError::InvalidStructure {
+                        message: format!(
+                            "composite '{}' is {} bytes, more than a block can hold",
+                            expanded.name,
+                            expanded.encoded_length()
+                        ),
+                    });
+                }
+                Some(expanded) => {
+                    schema.types[index] = TypeDef::Composite(expanded);
+                    progress = true;
+                }
+                None => pending = pending.or(Some(index)),
+            }
+        }
+
+        let Some(index) = pending else {
+            return Ok(());
+        };
+        if progress {
+            continue;
+        }
+        if !strict {
+            return Ok(());
+        }
+        let TypeDef::Composite(composite) = &schema.types[index] else {
+            unreachable!("only composites hold refs");
+        };
+        let field = composite
+            .fields
+            .iter()
+            .find(|f| is_unresolved_ref(f) && !schema.has_type(&f.type_name));
+        return Err(match field {
+            Some(field) => ParseError::UnknownType {
+                type_name: field.type_name.clone(),
+                field: format!("{}.{}", composite.name, field.name),
+            },
+            None => ParseError::InvalidStructure {
+                message: format!("circular <ref> through composite '{}'", composite.name),
+            },
+        });
+    }
+}
+
+/// Resolves the refs of `composite`, or returns `None` if a referenced type
+/// is missing or still has refs itself.
+fn expand_refs(composite: &CompositeDef, schema: &Schema) -> Option<CompositeDef> {
+    let mut expanded = CompositeDef::new(composite.name.clone());
+    expanded.description = composite.description.clone();
+    expanded.semantic_type = composite.semantic_type.clone();
+    let mut offset = 0;
+
+    let mut push = |mut field: CompositeField, expanded: &mut CompositeDef| {
+        let start = *field.offset.get_or_insert(offset);
+        offset = start + field.encoded_length;
+        expanded.add_field(field);
+    };
+
+    for field in &composite.fields {
+        if !is_unresolved_ref(field) {
+            push(field.clone(), &mut expanded);
+            continue;
+        }
+        let member = |kind: MemberKind, primitive: Option<PrimitiveType>, encoded_length| {
+            let mut member = field.clone();
+            member.kind = kind;
+            member.primitive_type = primitive;
+            member.encoded_length = encoded_length;
+            member
+        };
+        let member = match schema.get_type(&field.type_name)? {
+            TypeDef::Primitive(p) => {
+                let mut member = member(
+                    MemberKind::Primitive,
+                    Some(p.primitive_type),
+                    p.encoded_length(),
+                );
+                if p.constant_value.is_some() {
+                    member.presence = Presence::Constant;
+                    member.constant_value = p.constant_value.clone();
+                    member.encoded_length = 0;
+                }
+                member
+            }
+            TypeDef::Enum(e) => member(
+                MemberKind::Enum,
+                Some(e.encoding_type),
+                e.encoding_type.size(),
+            ),
+            TypeDef::Set(s) => member(
+                MemberKind::Set,
+                Some(s.encoding_type),
+                s.encoding_type.size(),
+            ),
+            TypeDef::Composite(inner) => {
+                if inner.fields.iter().any(is_unresolved_ref) {
+                    return None;
+                }
+                member(MemberKind::Composite, None, inner.encoded_length())
+            }
+        };
+        push(member, &mut expanded);
+    }
+
+    Some(expanded)
+}
+
 /// Parses an enum type definition.
 fn parse_enum(reader: &mut Reader<&[u8]>, e: &BytesStart<'_>) -> Result<EnumDef, ParseError> {
     let mut name = String::new();
@@ -360,7 +876,7 @@ fn parse_enum(reader: &mut Reader<&[u8]>, e: &BytesStart<'_>) -> Result<EnumDef,
 
     for attr in e.attributes().flatten() {
         let key = std::str::from_utf8(attr.key.as_ref())?;
-        let value = std::str::from_utf8(&attr.value)?;
+        let value = &*attr_value(&attr)?;
 
         match key {
             "name" => name = value.to_string(),
@@ -387,14 +903,19 @@ fn parse_enum(reader: &mut Reader<&[u8]>, e: &BytesStart<'_>) -> Result<EnumDef,
 
     loop {
         match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+            Ok(Event::Start(ref e)) => {
                 let name_bytes = e.name().as_ref().to_vec();
                 let tag_name = std::str::from_utf8(&name_bytes)?;
                 if tag_name == "validValue" {
-                    let value = parse_enum_value(reader, e)?;
+                    let value = scoped(e, parse_enum_value(reader, e))?;
                     enum_def.add_value(value);
                 }
             }
+            // Reading the value of a self-closing element would consume the
+            // end tag of the enum.
+            Ok(Event::Empty(ref e)) if e.name().as_ref() == b"validValue" => {
+                return scoped(e, Err(ParseError::invalid_attr("validValue", "value", "")));
+            }
             Ok(Event::End(_)) => break,
             Ok(Event::Eof) => break,
             Err(e) => return Err(ParseError::Xml(e)),
@@ -418,7 +939,7 @@ fn parse_enum_value(
 
     for attr in e.attributes().flatten() {
         let key = std::str::from_utf8(attr.key.as_ref())?;
-        let value = std::str::from_utf8(&attr.value)?;
+        let value = &*attr_value(&attr)?;
 
         match key {
             "name" => name = value.to_string(),
@@ -462,7 +983,7 @@ fn parse_set(reader: &mut Reader<&[u8]>, e: &BytesStart<'_>) -> Result<SetDef, P
 
     for attr in e.attributes().flatten() {
         let key = std::str::from_utf8(attr.key.as_ref())?;
-        let value = std::str::from_utf8(&attr.value)?;
+        let value = &*attr_value(&attr)?;
 
         match key {
             "name" => name = value.to_string(),
@@ -487,14 +1008,17 @@ fn parse_set(reader: &mut Reader<&[u8]>, e: &BytesStart<'_>) -> Result<SetDef, P
 
     loop {
         match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+            Ok(Event::Start(ref e)) => {
                 let name_bytes = e.name().as_ref().to_vec();
                 let tag_name = std::str::from_utf8(&name_bytes)?;
                 if tag_name == "choice" {
-                    let choice = parse_set_choice(reader, e)?;
+                    let choice = scoped(e, parse_set_choice(reader, e))?;
                     set_def.add_choice(choice);
                 }
             }
+            Ok(Event::Empty(ref e)) if e.name().as_ref() == b"choice" => {
+                return scoped(e, Err(ParseError::invalid_attr("choice", "value", "")));
+            }
             Ok(Event::End(_)) => break,
             Ok(Event::Eof) => break,
             Err(e) => return Err(ParseError::Xml(e)),
@@ -518,7 +1042,7 @@ fn parse_set_choice(
 
     for attr in e.attributes().flatten() {
         let key = std::str::from_utf8(attr.key.as_ref())?;
-        let value = std::str::from_utf8(&attr.value)?;
+        let value = &*attr_value(&attr)?;
 
         match key {
             "name" => name = value.to_string(),
@@ -539,7 +1063,9 @@ fn parse_set_choice(
                 let text = std::str::from_utf8(t.as_ref())?.trim();
                 bit_position = text
                     .parse()
-                    .map_err(|_| ParseError::invalid_attr("choice", "value", text))?;
+                    .ok()
+                    .filter(|&bit| bit < 64)
+                    .ok_or_else(|| ParseError::invalid_attr("choice", "value", text))?;
             }
             Ok(Event::End(_)) => break,
             Ok(Event::Eof) => break,
@@ -565,7 +1091,7 @@ fn parse_message(
 ) -> Result<MessageDef, ParseError> {
     let mut name = String::new();
     let mut id: u16 = 0;
-    let mut block_length: u16 = 0;
+    let mut block_length = None;
     let mut semantic_type = None;
     let mut description = None;
     let mut since_version = None;
@@ -573,7 +1099,7 @@ fn parse_message(
 
     for attr in e.attributes().flatten() {
         let key = std::str::from_utf8(attr.key.as_ref())?;
-        let value = std::str::from_utf8(&attr.value)?;
+        let value = &*attr_value(&attr)?;
 
         match key {
             "name" => name = value.to_string(),
@@ -583,9 +1109,11 @@ fn parse_message(
                     .map_err(|_| ParseError::invalid_attr("message", "id", value))?
             }
             "blockLength" => {
-                block_length = value
-                    .parse()
-                    .map_err(|_| ParseError::invalid_attr("message", "blockLength", value))?
+                block_length = Some(
+                    value
+                        .parse()
+                        .map_err(|_| ParseError::invalid_attr("message", "blockLength", value))?,
+                )
             }
             "semanticType" => semantic_type = Some(value.to_string()),
             "description" => description = Some(value.to_string()),
@@ -595,31 +1123,35 @@ fn parse_message(
         }
     }
 
-    let mut msg = MessageDef::new(name, id, block_length);
+    let mut msg = MessageDef::new(name, id, 0);
     msg.semantic_type = semantic_type;
     msg.description = description;
     msg.since_version = since_version;
     msg.deprecated = deprecated;
 
     let mut buf = Vec::new();
+    let mut fields_end = 0;
 
     loop {
-        match reader.read_event_into(&mut buf) {
+        let event = reader.read_event_into(&mut buf);
+        let is_start = matches!(event, Ok(Event::Start(_)));
+        match event {
             Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
                 let name_bytes = e.name().as_ref().to_vec();
                 let tag_name = std::str::from_utf8(&name_bytes)?;
 
                 match tag_name {
                     "field" => {
-                        let field = parse_field(e, schema)?;
+                        let field = scoped(e, parse_field(e, schema, fields_end))?;
+                        fields_end = field_end(&field, fields_end);
                         msg.add_field(field);
                     }
                     "group" => {
-                        let group = parse_group(reader, e, schema)?;
+                        let group = scoped(e, parse_group(reader, e, schema, is_start, 1))?;
                         msg.add_group(group);
                     }
                     "data" => {
-                        let data = parse_data_field(e)?;
+                        let data = scoped(e, parse_data_field(e))?;
                         msg.add_data_field(data);
                     }
                     _ => {}
@@ -633,17 +1165,24 @@ fn parse_message(
         buf.clear();
     }
 
-    auto_compute_field_offsets(&mut msg.fields);
+    msg.block_length = resolve_block_length(block_length, fields_end)?;
 
     Ok(msg)
 }
 
 /// Parses a field definition.
-fn parse_field(e: &BytesStart<'_>, schema: &Schema) -> Result<FieldDef, ParseError> {
+///
+/// A field without an `offset` is placed at `next_offset`, the end of the
+/// previous field; an explicit offset may leave padding but not overlap.
+fn parse_field(
+    e: &BytesStart<'_>,
+    schema: &Schema,
+    next_offset: usize,
+) -> Result<FieldDef, ParseError> {
     let mut name = String::new();
     let mut id: u16 = 0;
     let mut type_name = String::new();
-    let mut offset: usize = 0;
+    let mut offset = None;
     let mut presence = Presence::Required;
     let mut semantic_type = None;
     let mut description = None;
@@ -653,7 +1192,7 @@ fn parse_field(e: &BytesStart<'_>, schema: &Schema) -> Result<FieldDef, ParseErr
 
     for attr in e.attributes().flatten() {
         let key = std::str::from_utf8(attr.key.as_ref())?;
-        let value = std::str::from_utf8(&attr.value)?;
+        let value = &*attr_value(&attr)?;
 
         match key {
             "name" => name = value.to_string(),
@@ -663,11 +1202,7 @@ fn parse_field(e: &BytesStart<'_>, schema: &Schema) -> Result<FieldDef, ParseErr
                     .map_err(|_| ParseError::invalid_attr("field", "id", value))?
             }
             "type" => type_name = value.to_string(),
-            "offset" => {
-                offset = value
-                    .parse()
-                    .map_err(|_| ParseError::invalid_attr("field", "offset", value))?
-            }
+            "offset" => offset = Some(parse_extent("field", "offset", value)?),
             "presence" => {
                 presence = Presence::parse(value)
                     .ok_or_else(|| ParseError::invalid_attr("field", "presence", value))?
@@ -681,7 +1216,7 @@ fn parse_field(e: &BytesStart<'_>, schema: &Schema) -> Result<FieldDef, ParseErr
         }
     }
 
-    let mut field = FieldDef::new(name, id, type_name.clone(), offset);
+    let mut field = FieldDef::new(name, id, type_name.clone(), offset.unwrap_or(next_offset));
     field.presence = presence;
     field.semantic_type = semantic_type;
     field.description = description;
@@ -689,23 +1224,45 @@ fn parse_field(e: &BytesStart<'_>, schema: &Schema) -> Result<FieldDef, ParseErr
     field.deprecated = deprecated;
     field.value_ref = value_ref;
 
-    // Resolve encoded length from type
-    if let Some(type_def) = schema.get_type(&type_name) {
-        field.encoded_length = type_def.encoded_length();
+    // Resolve encoded length from type; constants are not encoded
+    match schema.get_type(&type_name) {
+        Some(TypeDef::Primitive(p)) if p.is_constant() => {}
+        Some(_) if field.is_constant() => {}
+        Some(type_def) => field.encoded_length = type_def.encoded_length(),
+        None if field.is_constant() => {}
+        None => {
+            if let Some(prim) = PrimitiveType::from_sbe_name(&type_name) {
+                field.encoded_length = prim.size();
+            }
+        }
+    }
+
+    if field.encoded_length > 0 && field.offset < next_offset {
+        return Err(ParseError::InvalidStructure {
+            message: format!(
+                "field '{}' at offset {} overlaps the previous field, which ends at {}",
+                field.name, field.offset, next_offset
+            ),
+        });
     }
 
     Ok(field)
 }
 
-/// Parses a group definition.
+/// Parses a group definition nested `depth` levels deep.
+///
+/// A self-closing `<group/>` has no body to read and no members.
 fn parse_group(
     reader: &mut Reader<&[u8]>,
     e: &BytesStart<'_>,
     schema: &Schema,
+    has_body: bool,
+    depth: usize,
 ) -> Result<GroupDef, ParseError> {
+    check_nesting("groups", depth)?;
     let mut name = String::new();
     let mut id: u16 = 0;
-    let mut block_length: u16 = 0;
+    let mut block_length = None;
     let mut dimension_type = "groupSizeEncoding".to_string();
     let mut description = None;
     let mut since_version = None;
@@ -713,7 +1270,7 @@ fn parse_group(
 
     for attr in e.attributes().flatten() {
         let key = std::str::from_utf8(attr.key.as_ref())?;
-        let value = std::str::from_utf8(&attr.value)?;
+        let value = &*attr_value(&attr)?;
 
         match key {
             "name" => name = value.to_string(),
@@ -723,9 +1280,11 @@ fn parse_group(
                     .map_err(|_| ParseError::invalid_attr("group", "id", value))?
             }
             "blockLength" => {
-                block_length = value
-                    .parse()
-                    .map_err(|_| ParseError::invalid_attr("group", "blockLength", value))?
+                block_length = Some(
+                    value
+                        .parse()
+                        .map_err(|_| ParseError::invalid_attr("group", "blockLength", value))?,
+                )
             }
             "dimensionType" => dimension_type = value.to_string(),
             "description" => description = Some(value.to_string()),
@@ -735,30 +1294,40 @@ fn parse_group(
         }
     }
 
-    let mut group = GroupDef::new(name, id, block_length);
+    let mut group = GroupDef::new(name, id, 0);
     group.dimension_type = dimension_type;
     group.description = description;
     group.since_version = since_version;
     group.deprecated = deprecated;
 
+    if !has_body {
+        group.block_length = resolve_block_length(block_length, 0)?;
+        return Ok(group);
+    }
+
     let mut buf = Vec::new();
+    let mut fields_end = 0;
 
     loop {
-        match reader.read_event_into(&mut buf) {
+        let event = reader.read_event_into(&mut buf);
+        let is_start = matches!(event, Ok(Event::Start(_)));
+        match event {
             Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
                 let name_bytes = e.name().as_ref().to_vec();
                 let tag_name = std::str::from_utf8(&name_bytes)?;
                 match tag_name {
                     "field" => {
-                        let field = parse_field(e, schema)?;
+                        let field = scoped(e, parse_field(e, schema, fields_end))?;
+                        fields_end = field_end(&field, fields_end);
                         group.add_field(field);
                     }
                     "group" => {
-                        let nested = parse_group(reader, e, schema)?;
+                        let nested =
+                            scoped(e, parse_group(reader, e, schema, is_start, depth + 1))?;
                         group.add_nested_group(nested);
                     }
                     "data" => {
-                        let data = parse_data_field(e)?;
+                        let data = scoped(e, parse_data_field(e))?;
                         group.add_data_field(data);
                     }
                     _ => {}
@@ -772,24 +1341,38 @@ fn parse_group(
         buf.clear();
     }
 
-    auto_compute_field_offsets(&mut group.fields);
+    group.block_length = resolve_block_length(block_length, fields_end)?;
 
     Ok(group)
 }
 
-/// Auto-computes field offsets for fields that did not specify an explicit offset.
+/// Returns the end of the fixed block after `field`, given the end
+/// `fields_end` of the fields before it.
+fn field_end(field: &FieldDef, fields_end: usize) -> usize {
+    if field.encoded_length == 0 {
+        fields_end
+    } else {
+        field.offset + field.encoded_length
+    }
+}
+
+/// Returns the block length of fields ending at `fields_end`.
 ///
-/// In SBE, the `offset` attribute on `<field>` elements is optional. When absent
-/// the parser defaults the offset to 0, which is only correct for the first field.
-/// This function walks the field list and, for any non-first field whose offset is
-/// still 0, assigns it the byte position immediately after the previous field.
-fn auto_compute_field_offsets(fields: &mut [FieldDef]) {
-    let mut running_offset = 0usize;
-    for field in fields.iter_mut() {
-        if running_offset > 0 && field.offset == 0 {
-            field.offset = running_offset;
-        }
-        running_offset = field.offset + field.encoded_length;
+/// Without a declared `blockLength`, or with `blockLength="0"`, the block
+/// ends with its last field. A declared block length may add trailing
+/// padding but must hold every field.
+fn resolve_block_length(declared: Option<u16>, fields_end: usize) -> Result<u16, ParseError> {
+    let computed = u16::try_from(fields_end).map_err(|_| ParseError::InvalidStructure {
+        message: format!("fields end at offset {fields_end}, beyond the largest blockLength"),
+    })?;
+    match declared {
+        Some(0) | None => Ok(computed),
+        Some(declared) if declared < computed => Err(ParseError::InvalidStructure {
+            message: format!(
+                "blockLength {declared} is smaller than the {computed} bytes its fields occupy"
+            ),
+        }),
+        Some(declared) => Ok(declared),
     }
 }
 
@@ -804,7 +1387,7 @@ fn parse_data_field(e: &BytesStart<'_>) -> Result<DataFieldDef, ParseError> {
 
     for attr in e.attributes().flatten() {
         let key = std::str::from_utf8(attr.key.as_ref())?;
-        let value = std::str::from_utf8(&attr.value)?;
+        let value = &*attr_value(&attr)?;
 
         match key {
             "name" => name = value.to_string(),
@@ -829,8 +1412,28 @@ fn parse_data_field(e: &BytesStart<'_>) -> Result<DataFieldDef, ParseError> {
     Ok(data)
 }
 
+/// Parses an `offset` or `length` attribute.
+///
+/// Neither can exceed the largest block length, 65535 bytes, which also
+/// keeps layout arithmetic on them from overflowing.
+fn parse_extent(element: &str, attribute: &str, value: &str) -> Result<usize, ParseError> {
+    value
+        .parse::<u16>()
+        .map(usize::from)
+        .map_err(|_| ParseError::invalid_attr(element, attribute, value))
+}
+
+/// Fails if `depth` levels of nested `what` exceed [`MAX_NESTING`].
+fn check_nesting(what: &str, depth: usize) -> Result<(), ParseError> {
+    if depth > MAX_NESTING {
+        return Err(ParseError::InvalidStructure {
+            message: format!("{what} nested deeper than {MAX_NESTING} levels"),
+        });
+    }
+    Ok(())
+}
+
 /// Skips to the end of the current element.
-#[allow(dead_code)]
 fn skip_to_end(reader: &mut Reader<&[u8]>, _tag_name: &str) -> Result<(), ParseError> {
     let mut buf = Vec::new();
     let mut depth = 1;
@@ -969,4 +1572,628 @@ mod tests {
         assert_eq!(group.fields[1].offset, 8);
         assert_eq!(group.fields[2].offset, 16);
     }
+
+    #[test]
+    fn test_message_layout_computed_without_offsets() {
+        let xml = document(
+            HEADER,
+            r#"<sbe:message name="Order" id="1">
+        <field name="orderId" id="1" type="uint64"/>
+        <field name="qty" id="2" type="uint32"/>
+        <field name="side" id="3" type="uint8"/>
+        <group name="fills" id="10" dimensionType="groupSizeEncoding">
+            <field name="px" id="11" type="int64"/>
+            <field name="size" id="12" type="uint16"/>
+        </group>
+    </sbe:message>"#,
+        );
+
+        let schema = parse_schema(&xml).expect("Failed to parse schema");
+        let msg = &schema.messages[0];
+        let offsets: Vec<_> = msg.fields.iter().map(|f| f.offset).collect();
+        assert_eq!(offsets, vec![0, 8, 12]);
+        assert_eq!(msg.block_length, 13);
+        assert_eq!(msg.groups[0].fields[1].offset, 8);
+        assert_eq!(msg.groups[0].block_length, 10);
+    }
+
+    #[test]
+    fn test_message_layout_honors_explicit_offsets_and_padding() {
+        let xml = document(
+            HEADER,
+            r#"<sbe:message name="Order" id="1" blockLength="32">
+        <field name="qty" id="1" type="uint32"/>
+        <field name="orderId" id="2" type="uint64" offset="8"/>
+        <field name="side" id="3" type="uint8"/>
+    </sbe:message>"#,
+        );
+
+        let schema = parse_schema(&xml).expect("Failed to parse schema");
+        let msg = &schema.messages[0];
+        let offsets: Vec<_> = msg.fields.iter().map(|f| f.offset).collect();
+        assert_eq!(offsets, vec![0, 8, 16]);
+        assert_eq!(msg.block_length, 32);
+    }
+
+    #[test]
+    fn test_message_layout_rejects_overlap_and_short_block() {
+        let overlap = document(
+            HEADER,
+            r#"<sbe:message name="Order" id="1">
+        <field name="orderId" id="1" type="uint64"/>
+        <field name="qty" id="2" type="uint32" offset="4"/>
+    </sbe:message>"#,
+        );
+        let err = parse_schema(&overlap).unwrap_err();
+        assert_eq!(err.path(), Some("message[Order]/field[qty]"));
+        assert!(err.to_string().contains("overlaps the previous field"));
+
+        let short = document(
+            HEADER,
+            r#"<sbe:message name="Order" id="1" blockLength="4">
+        <field name="orderId" id="1" type="uint64"/>
+    </sbe:message>"#,
+        );
+        let err = parse_schema(&short).unwrap_err();
+        assert_eq!(err.path(), Some("message[Order]"));
+        assert!(
+            err.to_string()
+                .contains("blockLength 4 is smaller than the 8 bytes")
+        );
+    }
+
+    #[test]
+    fn test_parse_composite_constant_members() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <composite name="Decimal">
+            <type name="mantissa" primitiveType="int64"/>
+            <type name="exponent" primitiveType="int8" presence="constant">-4</type>
+            <type name="side" primitiveType="uint8" presence="constant" valueRef="Side.Buy"/>
+            <type name="scale" primitiveType="uint8"/>
+        </composite>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+        </enum>
+    </types>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse schema");
+        let Some(TypeDef::Composite(decimal)) = schema.get_type("Decimal") else {
+            panic!("Decimal is not a composite");
+        };
+        let names: Vec<&str> = decimal.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["mantissa", "exponent", "side", "scale"]);
+
+        let exponent = &decimal.fields[1];
+        assert!(exponent.is_constant());
+        assert_eq!(exponent.constant_value.as_deref(), Some("-4"));
+        assert_eq!(exponent.encoded_length, 0);
+
+        let side = &decimal.fields[2];
+        assert_eq!(side.value_ref.as_deref(), Some("Side.Buy"));
+        assert_eq!(side.encoded_length, 0);
+
+        assert_eq!(decimal.fields[3].offset, Some(8));
+        assert_eq!(decimal.encoded_length(), 9);
+        assert!(schema.has_type("Side"));
+    }
+
+    #[test]
+    fn test_parse_constant_field_takes_no_space() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+        </enum>
+    </types>
+    <sbe:message name="Test" id="1" blockLength="8">
+        <group name="entries" id="100" dimensionType="groupSizeEncoding" blockLength="8">
+            <field name="side" id="1" type="Side" presence="constant" valueRef="Side.Buy"/>
+            <field name="qty" id="2" type="uint64"/>
+        </group>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse schema");
+        let group = &schema.messages[0].groups[0];
+        assert!(group.fields[0].is_constant());
+        assert_eq!(group.fields[0].encoded_length, 0);
+        assert_eq!(group.fields[0].value_ref.as_deref(), Some("Side.Buy"));
+        assert_eq!(group.fields[1].offset, 0);
+    }
+
+    fn document(attributes: &str, body: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe" {attributes}>
+{body}
+</sbe:messageSchema>"#
+        )
+    }
+
+    const COMMON_TYPES: &str = r#"<types>
+        <type name="Symbol" primitiveType="char" length="8"/>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+            <validValue name="Sell">2</validValue>
+        </enum>
+    </types>"#;
+
+    const HEADER: &str = r#"package="common" id="9" version="2" byteOrder="littleEndian""#;
+
+    #[test]
+    fn test_parse_schemas_resolves_types_across_documents() {
+        let common = document(HEADER, COMMON_TYPES);
+        let orders = document(
+            r#"package="orders" id="9" version="2""#,
+            r#"<sbe:message name="NewOrder" id="1">
+        <field name="symbol" id="1" type="Symbol"/>
+        <field name="side" id="2" type="Side"/>
+    </sbe:message>"#,
+        );
+        let quotes = document(
+            HEADER,
+            r#"<sbe:message name="Quote" id="2">
+        <field name="symbol" id="1" type="Symbol"/>
+    </sbe:message>"#,
+        );
+
+        // Messages may come before the file declaring their types.
+        let schema =
+            parse_schemas(&[orders.as_str(), &quotes, &common]).expect("Failed to merge schemas");
+
+        assert_eq!(schema.package, "orders");
+        assert_eq!((schema.id, schema.version), (9, 2));
+        assert!(schema.has_type("Symbol"));
+        assert!(schema.has_type("Side"));
+
+        let names: Vec<&str> = schema.messages.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["NewOrder", "Quote"]);
+        let side = &schema.messages[0].fields[1];
+        assert_eq!(side.encoded_length, 1);
+        assert_eq!(side.offset, 8);
+    }
+
+    #[test]
+    fn test_parse_schemas_accepts_identical_duplicate_types() {
+        let first = document(HEADER, COMMON_TYPES);
+        let second = document(HEADER, COMMON_TYPES);
+
+        let schema = parse_schemas(&[first.as_str(), &second]).expect("Failed to merge schemas");
+        assert_eq!(schema.types.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_schemas_rejects_conflicting_type() {
+        let first = document(HEADER, COMMON_TYPES);
+        let second = document(
+            HEADER,
+            r#"<types><type name="Symbol" primitiveType="char" length="6"/></types>"#,
+        );
+
+        let err = parse_schemas(&[first.as_str(), &second]).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::DuplicateDefinition { ref kind, ref name } if kind == "type" && name == "Symbol"
+        ));
+    }
+
+    #[test]
+    fn test_parse_schemas_rejects_mismatched_version() {
+        let first = document(HEADER, COMMON_TYPES);
+        let second = document(r#"package="common" id="9" version="3""#, "");
+
+        let err = parse_schemas(&[first.as_str(), &second]).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::MergeMismatch { ref attribute, ref expected, ref actual }
+                if attribute == "version" && expected == "2" && actual == "3"
+        ));
+    }
+
+    #[test]
+    fn test_parse_schemas_rejects_duplicate_template_id() {
+        let first = document(
+            HEADER,
+            r#"<sbe:message name="NewOrder" id="1" blockLength="0"></sbe:message>"#,
+        );
+        let second = document(
+            HEADER,
+            r#"<sbe:message name="Cancel" id="1" blockLength="0"></sbe:message>"#,
+        );
+
+        let err = parse_schemas(&[first.as_str(), &second]).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::DuplicateDefinition { ref kind, ref name } if kind == "template ID" && name == "1"
+        ));
+    }
+
+    #[test]
+    fn test_parse_schemas_requires_a_document() {
+        assert!(matches!(
+            parse_schemas(&[]),
+            Err(ParseError::InvalidStructure { .. })
+        ));
+    }
+
+    const REF_TYPES: &str = r#"<types>
+        <composite name="Instrument">
+            <ref name="price" type="Decimal"/>
+            <type name="venue" primitiveType="uint16"/>
+            <ref name="side" type="Side"/>
+            <ref name="symbol" type="Symbol"/>
+        </composite>
+        <composite name="Decimal">
+            <type name="mantissa" primitiveType="int64"/>
+            <type name="exponent" primitiveType="int8"/>
+        </composite>
+        <type name="Symbol" primitiveType="char" length="8"/>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+            <validValue name="Sell">2</validValue>
+        </enum>
+    </types>"#;
+
+    #[test]
+    fn test_parse_composite_refs_declared_later() {
+        let xml = document(
+            HEADER,
+            &format!(
+                r#"{REF_TYPES}
+    <sbe:message name="Quote" id="1">
+        <field name="instrument" id="1" type="Instrument"/>
+        <field name="qty" id="2" type="uint32"/>
+    </sbe:message>"#
+            ),
+        );
+        let schema = parse_schema(&xml).expect("Failed to parse schema");
+
+        let Some(TypeDef::Composite(instrument)) = schema.get_type("Instrument") else {
+            panic!("Instrument is not a composite");
+        };
+        let layout: Vec<_> = instrument
+            .fields
+            .iter()
+            .map(|f| {
+                (
+                    f.name.as_str(),
+                    f.kind,
+                    f.type_name.as_str(),
+                    f.primitive_type,
+                    f.offset,
+                    f.encoded_length,
+                )
+            })
+            .collect();
+        assert_eq!(
+            layout,
+            vec![
+                ("price", MemberKind::Composite, "Decimal", None, Some(0), 9),
+                (
+                    "venue",
+                    MemberKind::Primitive,
+                    "uint16",
+                    Some(PrimitiveType::Uint16),
+                    Some(9),
+                    2
+                ),
+                (
+                    "side",
+                    MemberKind::Enum,
+                    "Side",
+                    Some(PrimitiveType::Uint8),
+                    Some(11),
+                    1
+                ),
+                (
+                    "symbol",
+                    MemberKind::Primitive,
+                    "Symbol",
+                    Some(PrimitiveType::Char),
+                    Some(12),
+                    8
+                ),
+            ]
+        );
+        assert_eq!(instrument.encoded_length(), 20);
+
+        // The message sees the expanded length.
+        assert_eq!(schema.messages[0].fields[1].offset, 20);
+    }
+
+    #[test]
+    fn test_parse_composite_inline_members() {
+        let xml = document(
+            HEADER,
+            r#"<types>
+        <composite name="messageHeader">
+            <type name="blockLength" primitiveType="uint16"/>
+            <type name="templateId" primitiveType="uint16"/>
+            <type name="schemaId" primitiveType="uint16"/>
+            <type name="version" primitiveType="uint16"/>
+            <enum name="Channel" encodingType="uint8">
+                <validValue name="A">1</validValue>
+                <validValue name="B">2</validValue>
+            </enum>
+            <set name="HeaderFlags" encodingType="uint8">
+                <choice name="Replay">0</choice>
+            </set>
+            <composite name="sendingTime">
+                <type name="seconds" primitiveType="uint32"/>
+                <type name="nanos" primitiveType="uint32"/>
+            </composite>
+        </composite>
+    </types>"#,
+        );
+        let schema = parse_schema(&xml).expect("Failed to parse schema");
+
+        assert!(matches!(schema.get_type("Channel"), Some(TypeDef::Enum(_))));
+        assert!(matches!(
+            schema.get_type("HeaderFlags"),
+            Some(TypeDef::Set(_))
+        ));
+        assert!(matches!(
+            schema.get_type("sendingTime"),
+            Some(TypeDef::Composite(_))
+        ));
+        let Some(TypeDef::Composite(header)) = schema.get_type("messageHeader") else {
+            panic!("messageHeader is not a composite");
+        };
+        let members: Vec<_> = header.fields[4..]
+            .iter()
+            .map(|f| (f.name.as_str(), f.kind, f.offset, f.encoded_length))
+            .collect();
+        assert_eq!(
+            members,
+            vec![
+                ("Channel", MemberKind::Enum, Some(8), 1),
+                ("HeaderFlags", MemberKind::Set, Some(9), 1),
+                ("sendingTime", MemberKind::Composite, Some(10), 8),
+            ]
+        );
+        assert_eq!(header.encoded_length(), 18);
+    }
+
+    #[test]
+    fn test_parse_composite_ref_to_unknown_type() {
+        let xml = document(
+            HEADER,
+            r#"<types>
+        <composite name="Broken"><ref name="price" type="Missing"/></composite>
+    </types>"#,
+        );
+        let err = parse_schema(&xml).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UnknownType { ref type_name, ref field }
+                if type_name == "Missing" && field == "Broken.price"
+        ));
+    }
+
+    #[test]
+    fn test_parse_composite_ref_cycle() {
+        let xml = document(
+            HEADER,
+            r#"<types>
+        <composite name="A"><ref name="b" type="B"/></composite>
+        <composite name="B"><ref name="a" type="A"/></composite>
+    </types>"#,
+        );
+        let err = parse_schema(&xml).unwrap_err();
+        assert!(err.to_string().contains("circular <ref>"));
+    }
+
+    #[test]
+    fn test_parse_schemas_resolves_refs_across_documents() {
+        let first = document(
+            HEADER,
+            r#"<types>
+        <composite name="Leg"><ref name="side" type="Side"/></composite>
+    </types>"#,
+        );
+        let second = document(HEADER, COMMON_TYPES);
+
+        let schema = parse_schemas(&[first.as_str(), &second]).expect("Failed to merge");
+        assert_eq!(schema.get_type("Leg").unwrap().encoded_length(), 1);
+    }
+
+    #[test]
+    fn test_parse_schema_with_resolver_expands_includes() {
+        let xml = document(
+            &format!(r#"{HEADER} xmlns:xi="http://www.w3.org/2001/XInclude""#),
+            r#"<xi:include href="common.xml"/>
+    <sbe:message name="NewOrder" id="1">
+        <field name="symbol" id="1" type="Symbol"/>
+        <field name="side" id="2" type="Side"/>
+    </sbe:message>"#,
+        );
+        let common = format!("<?xml version=\"1.0\"?>\n{COMMON_TYPES}\n");
+
+        let schema = parse_schema_with_resolver(&xml, |href| {
+            assert_eq!(href, "common.xml");
+            Ok(common.clone())
+        })
+        .expect("Failed to parse schema");
+        assert!(schema.has_type("Side"));
+        assert_eq!(schema.messages[0].fields[1].offset, 8);
+
+        let err = parse_schema(&xml).unwrap_err();
+        assert!(matches!(err.root(), ParseError::Include { href, .. } if href == "common.xml"));
+    }
+
+    #[test]
+    fn test_parse_schema_file_resolves_relative_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("common.xml"), COMMON_TYPES).unwrap();
+        let xml = document(HEADER, r#"<xi:include href="common.xml"/>"#);
+        let path = dir.path().join("schema.xml");
+        std::fs::write(&path, xml).unwrap();
+
+        let schema = parse_schema_file(&path).expect("Failed to parse schema");
+        assert!(schema.has_type("Symbol"));
+    }
+
+    #[test]
+    fn test_parse_error_reports_location_and_path() {
+        let xml = document(
+            HEADER,
+            r#"<types>
+        <type name="Symbol" primitiveType="char" length="8"/>
+    </types>
+    <sbe:message name="NewOrderSingle" id="1" blockLength="8">
+        <field name="symbol" id="1" type="Symbol" offset="0"/>
+        <field name="price" id="2" type="Symbol" offset="abc"/>
+    </sbe:message>"#,
+        );
+
+        let err = parse_schema(&xml).unwrap_err();
+        assert_eq!(err.path(), Some("message[NewOrderSingle]/field[price]"));
+        assert_eq!(err.location().map(|l| l.line), Some(8));
+        assert!(matches!(
+            err.root(),
+            ParseError::InvalidAttribute { attribute, value, .. }
+                if attribute == "offset" && value == "abc"
+        ));
+        assert!(err.to_string().starts_with("line 8, column "));
+    }
+
+    #[test]
+    fn test_parse_error_path_through_composite_members() {
+        let xml = document(
+            HEADER,
+            r#"<types>
+        <composite name="messageHeader">
+            <type name="blockLength" primitiveType="uint16"/>
+            <enum name="Venue" encodingType="bogus">
+                <validValue name="A">1</validValue>
+            </enum>
+        </composite>
+    </types>"#,
+        );
+
+        let err = parse_schema(&xml).unwrap_err();
+        assert_eq!(err.path(), Some("composite[messageHeader]/enum[Venue]"));
+        assert_eq!(err.location().map(|l| l.line), Some(6));
+    }
+
+    #[test]
+    fn test_parse_error_locates_malformed_xml() {
+        let xml = document(
+            HEADER,
+            "<types>\n    <type name=\"A\" primitiveType=\"char\">\n</types>",
+        );
+
+        let err = parse_schema(&xml).unwrap_err();
+        assert!(matches!(err.root(), ParseError::Xml(_)));
+        assert_eq!(err.location().map(|l| l.line), Some(5));
+    }
+
+    fn nested(open: &str, close: &str, depth: usize) -> String {
+        format!("{}{}", open.repeat(depth), close.repeat(depth))
+    }
+
+    #[test]
+    fn test_parse_limits_nesting_depth() {
+        let group = r#"<group name="g" id="2">"#;
+        let message = |depth| {
+            document(
+                HEADER,
+                &format!(
+                    r#"<sbe:message name="M" id="1">{}</sbe:message>"#,
+                    nested(group, "</group>", depth)
+                ),
+            )
+        };
+        assert!(parse_schema(&message(MAX_NESTING)).is_ok());
+        let err = parse_schema(&message(MAX_NESTING + 1)).unwrap_err();
+        assert!(err.to_string().contains("groups nested deeper"), "{err}");
+        // Far past the limit, where unbounded recursion would overflow the
+        // stack.
+        assert!(parse_schema(&message(100_000)).is_err());
+
+        let composite = r#"<composite name="c">"#;
+        let types = document(
+            HEADER,
+            &format!(
+                "<types>{}</types>",
+                nested(composite, "</composite>", 100_000)
+            ),
+        );
+        let err = parse_schema(&types).unwrap_err();
+        assert!(
+            err.to_string().contains("composites nested deeper"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_extents_beyond_a_block() {
+        for body in [
+            r#"<types><type name="T" primitiveType="int64" length="18446744073709551615"/></types>"#,
+            r#"<sbe:message name="M" id="1"><field name="a" id="1" type="uint64" offset="18446744073709551615"/></sbe:message>"#,
+            r#"<types><composite name="C"><type name="a" primitiveType="uint8" offset="65536"/></composite></types>"#,
+        ] {
+            let err = parse_schema(&document(HEADER, body)).unwrap_err();
+            assert!(
+                matches!(err.root(), ParseError::InvalidAttribute { .. }),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_caps_composite_ref_chains() {
+        // Each composite holds two of the previous one, doubling its size.
+        let mut types = String::from(
+            r#"<composite name="c0"><type name="v" primitiveType="uint64"/></composite>"#,
+        );
+        for i in 1..=64 {
+            let prev = i - 1;
+            types.push_str(&format!(
+                r#"<composite name="c{i}"><ref name="a" type="c{prev}"/><ref name="b" type="c{prev}"/></composite>"#
+            ));
+        }
+        let xml = document(HEADER, &format!("<types>{types}</types>"));
+
+        let err = parse_schema(&xml).unwrap_err();
+        assert!(
+            err.to_string().contains("more than a block can hold"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_parse_self_closing_members() {
+        let xml = document(
+            HEADER,
+            r#"<sbe:message name="M" id="1">
+        <group name="empty" id="2"/>
+        <group name="fills" id="3"><field name="qty" id="4" type="uint32"/></group>
+    </sbe:message>"#,
+        );
+        let schema = parse_schema(&xml).expect("parses");
+        let groups = &schema.messages[0].groups;
+        assert_eq!(groups.len(), 2);
+        assert!(groups[0].fields.is_empty());
+        assert_eq!(groups[1].fields.len(), 1);
+
+        for body in [
+            r#"<enum name="E" encodingType="uint8"><validValue name="A"/></enum>"#,
+            r#"<set name="S" encodingType="uint8"><choice name="A"/></set>"#,
+            r#"<set name="S" encodingType="uint64"><choice name="A">64</choice></set>"#,
+        ] {
+            let xml = document(HEADER, &format!("<types>{body}</types>"));
+            let err = parse_schema(&xml).unwrap_err();
+            assert!(
+                matches!(err.root(), ParseError::InvalidAttribute { .. }),
+                "{err}"
+            );
+        }
+    }
 }
@@ -0,0 +1,387 @@
+//! Strict schema checks.
+//!
+//! The parser is permissive: elements and attributes it does not recognize
+//! are skipped, so files from venues that extend the schema format still
+//! load. Schema authors want the opposite in CI, where a typo such as
+//! `blocklength` or `<feild>` should fail the build instead of silently
+//! dropping a declaration. [`check_strict`] walks a document and rejects:
+//! - elements that are not part of the SBE schema format, or that appear
+//!   under the wrong parent
+//! - attributes the SBE specification does not define for an element, and
+//!   attributes given twice
+//! - fields declared after a repeating group or var data field, and groups
+//!   declared after a var data field
+//! - text outside constant `type` values, `validValue` and `choice`
+//!
+//! Namespace declarations and attributes in other namespaces, such as
+//! `xmlns:sbe` or `xsi:schemaLocation`, are always accepted.
+
+use crate::error::ParseError;
+use crate::parser::{attr_value, segment};
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+
+/// Attributes common to every element that can evolve with the schema.
+const VERSIONING: &[&str] = &["description", "sinceVersion", "deprecated"];
+
+/// Kind of element, with the children and attributes it allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Element {
+    Schema,
+    Types,
+    Type,
+    Composite,
+    Ref,
+    Enum,
+    ValidValue,
+    Set,
+    Choice,
+    Message,
+    Group,
+    Field,
+    Data,
+}
+
+impl Element {
+    /// Returns the element for the tag `name`.
+    fn from_tag(name: &str) -> Option<Self> {
+        Some(match name {
+            "messageSchema" | "sbe:messageSchema" => Self::Schema,
+            "types" => Self::Types,
+            "type" => Self::Type,
+            "composite" => Self::Composite,
+            "ref" => Self::Ref,
+            "enum" => Self::Enum,
+            "validValue" => Self::ValidValue,
+            "set" => Self::Set,
+            "choice" => Self::Choice,
+            "message" | "sbe:message" => Self::Message,
+            "group" => Self::Group,
+            "field" => Self::Field,
+            "data" => Self::Data,
+            _ => return None,
+        })
+    }
+
+    /// Returns true if `child` may appear directly inside this element.
+    fn allows_child(self, child: Self) -> bool {
+        use Element::*;
+        match self {
+            Schema => matches!(child, Types | Message),
+            Types => matches!(child, Type | Composite | Enum | Set),
+            Composite => matches!(child, Type | Composite | Enum | Set | Ref),
+            Enum => child == ValidValue,
+            Set => child == Choice,
+            Message | Group => matches!(child, Field | Group | Data),
+            Type | Ref | ValidValue | Choice | Field | Data => false,
+        }
+    }
+
+    /// Returns true if the element may hold text.
+    fn allows_text(self) -> bool {
+        matches!(self, Self::Type | Self::ValidValue | Self::Choice)
+    }
+
+    /// Returns the attributes defined for the element, besides
+    /// [`VERSIONING`].
+    fn attributes(self) -> &'static [&'static str] {
+        match self {
+            Self::Schema => &[
+                "package",
+                "id",
+                "version",
+                "semanticVersion",
+                "byteOrder",
+                "headerType",
+            ],
+            Self::Types => &[],
+            Self::Type => &[
+                "name",
+                "primitiveType",
+                "length",
+                "presence",
+                "nullValue",
+                "minValue",
+                "maxValue",
+                "characterEncoding",
+                "semanticType",
+                "offset",
+                "valueRef",
+            ],
+            Self::Composite => &["name", "semanticType", "offset"],
+            Self::Ref => &["name", "type", "offset", "semanticType"],
+            Self::Enum => &[
+                "name",
+                "encodingType",
+                "nullValue",
+                "semanticType",
+                "offset",
+            ],
+            Self::Set => &["name", "encodingType", "semanticType", "offset"],
+            Self::ValidValue | Self::Choice => &["name"],
+            Self::Message => &["name", "id", "blockLength", "semanticType"],
+            Self::Group => &["name", "id", "blockLength", "dimensionType", "semanticType"],
+            Self::Field => &[
+                "name",
+                "id",
+                "type",
+                "offset",
+                "presence",
+                "valueRef",
+                "semanticType",
+                "epoch",
+                "timeUnit",
+            ],
+            Self::Data => &["name", "id", "type", "semanticType"],
+        }
+    }
+}
+
+/// An element being read, with what its members have declared so far.
+struct Open {
+    element: Element,
+    segment: String,
+    seen_group: bool,
+    seen_data: bool,
+}
+
+/// Checks `xml` against the strict rules described in the
+/// [module documentation](self).
+///
+/// `<xi:include>` elements must be expanded first, with
+/// [`resolve_includes`](crate::include::resolve_includes).
+///
+/// # Errors
+/// Returns the first violation, located like other parse errors.
+pub fn check_strict(xml: &str) -> Result<(), ParseError> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut stack: Vec<Open> = Vec::new();
+    let mut buf = Vec::new();
+
+    loop {
+        let event = reader.read_event_into(&mut buf);
+        let result = match &event {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => open(&mut stack, e).map(|element| {
+                if matches!(event, Ok(Event::Start(_))) {
+                    stack.push(Open {
+                        element,
+                        segment: segment(e),
+                        seen_group: false,
+                        seen_data: false,
+                    });
+                }
+            }),
+            Ok(Event::End(_)) => {
+                stack.pop();
+                Ok(())
+            }
+            Ok(Event::Text(t)) => check_text(stack.last(), &String::from_utf8_lossy(t)),
+            Ok(Event::CData(t)) => check_text(stack.last(), &String::from_utf8_lossy(t)),
+            Ok(Event::GeneralRef(r)) => {
+                check_text(stack.last(), &format!("&{};", String::from_utf8_lossy(r)))
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => Err(ParseError::Xml(e.clone())),
+            _ => Ok(()),
+        };
+        if let Err(err) = result {
+            let err = stack
+                .iter()
+                .rev()
+                .fold(err, |err, open| err.within(&open.segment));
+            return Err(err.at_reader_position(xml, &reader));
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+/// Checks a start tag against its parent, the innermost element on
+/// `stack`, and returns its kind.
+fn open(stack: &mut [Open], e: &BytesStart<'_>) -> Result<Element, ParseError> {
+    let tag = std::str::from_utf8(e.name().as_ref())?.to_string();
+    let parent = stack.last_mut();
+    let element = match (Element::from_tag(&tag), parent.as_deref()) {
+        (Some(element), Some(p)) if p.element.allows_child(element) => element,
+        (Some(Element::Schema), None) => Element::Schema,
+        (_, p) => {
+            let context = p.map_or("document", |p| p.segment.as_str());
+            return Err(ParseError::unknown_element(&tag, context));
+        }
+    };
+
+    if let Some(parent) = parent {
+        check_member_order(parent, element, &segment(e))?;
+        parent.seen_group |= element == Element::Group;
+        parent.seen_data |= element == Element::Data;
+    }
+
+    let defined = element.attributes();
+    for attr in e.attributes() {
+        let attr = attr.map_err(|err| ParseError::Xml(err.into()))?;
+        let key = std::str::from_utf8(attr.key.as_ref())?;
+        if key == "xmlns" || key.contains(':') {
+            continue;
+        }
+        if !defined.contains(&key) && !VERSIONING.contains(&key) {
+            return Err(ParseError::unknown_attr(&tag, key).within(&segment(e)));
+        }
+        attr_value(&attr)?;
+    }
+    Ok(element)
+}
+
+/// Rejects message or group members out of the order SBE lays them out
+/// in: fixed fields, then groups, then var data.
+fn check_member_order(parent: &Open, element: Element, segment: &str) -> Result<(), ParseError> {
+    let misplaced = match element {
+        Element::Field => parent.seen_group || parent.seen_data,
+        Element::Group => parent.seen_data,
+        _ => false,
+    };
+    if misplaced {
+        let after = if parent.seen_data {
+            "var data"
+        } else {
+            "a group"
+        };
+        return Err(ParseError::InvalidStructure {
+            message: format!("{segment} is declared after {after}"),
+        });
+    }
+    Ok(())
+}
+
+/// Rejects text inside an element that only holds other elements.
+fn check_text(parent: Option<&Open>, text: &str) -> Result<(), ParseError> {
+    match parent {
+        Some(open) if open.element.allows_text() => Ok(()),
+        _ if text.trim().is_empty() => Ok(()),
+        Some(open) => Err(ParseError::InvalidStructure {
+            message: format!("unexpected text '{}' in {}", text.trim(), open.segment),
+        }),
+        None => Err(ParseError::InvalidStructure {
+            message: format!("unexpected text '{}' outside messageSchema", text.trim()),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_schema, parse_schema_strict};
+
+    /// Wraps `body` in a schema with the standard message header.
+    fn schema(body: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+                   xsi:schemaLocation="sbe.xsd" package="test" id="1" version="1">
+    <types>
+        <composite name="messageHeader" description="Header">
+            <type name="blockLength" primitiveType="uint16"/>
+            <type name="templateId" primitiveType="uint16"/>
+            <type name="schemaId" primitiveType="uint16"/>
+            <type name="version" primitiveType="uint16"/>
+        </composite>
+        <composite name="groupSizeEncoding">
+            <type name="blockLength" primitiveType="uint16"/>
+            <type name="numInGroup" primitiveType="uint16"/>
+        </composite>
+        <type name="Venue" primitiveType="char" length="4" presence="constant">XCME</type>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy" sinceVersion="1">1</validValue>
+        </enum>
+    </types>
+    {body}
+</sbe:messageSchema>"#
+        )
+    }
+
+    #[test]
+    fn test_accepts_well_formed_schema() {
+        let xml = schema(
+            r#"<sbe:message name="Order" id="1" semanticType="D">
+        <field name="id" id="1" type="uint64" deprecated="2"/>
+        <group name="legs" id="2" dimensionType="groupSizeEncoding">
+            <field name="side" id="3" type="Side"/>
+        </group>
+    </sbe:message>"#,
+        );
+        check_strict(&xml).unwrap();
+        assert_eq!(parse_schema_strict(&xml).unwrap().messages.len(), 1);
+    }
+
+    #[test]
+    fn test_rejects_unknown_attribute() {
+        let xml = schema(
+            r#"<sbe:message name="Order" id="1" blocklength="8">
+        <field name="id" id="1" type="uint64"/>
+    </sbe:message>"#,
+        );
+        parse_schema(&xml).expect("permissive mode ignores it");
+
+        let err = parse_schema_strict(&xml).unwrap_err();
+        assert!(matches!(
+            err.root(),
+            ParseError::UnknownAttribute { element, attribute }
+                if element == "sbe:message" && attribute == "blocklength"
+        ));
+        assert_eq!(err.path(), Some("messageSchema/message[Order]"));
+        assert_eq!(err.location().map(|l| l.line), Some(21));
+    }
+
+    #[test]
+    fn test_rejects_unknown_or_misplaced_element() {
+        for (body, element) in [
+            (
+                r#"<sbe:message name="Order" id="1"><feild name="id" id="1" type="uint64"/></sbe:message>"#,
+                "feild",
+            ),
+            (
+                r#"<sbe:message name="Order" id="1"><validValue name="x">1</validValue></sbe:message>"#,
+                "validValue",
+            ),
+        ] {
+            let err = check_strict(&schema(body)).unwrap_err();
+            assert!(
+                matches!(err.root(), ParseError::UnknownElement { element: e, context }
+                    if e == element && context == "message[Order]"),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_members_out_of_order() {
+        let cases = [
+            r#"<group name="g" id="1"/><field name="id" id="2" type="uint64"/>"#,
+            r#"<data name="d" id="1" type="uint8"/><group name="g" id="2"/>"#,
+        ];
+        for members in cases {
+            let xml = schema(&format!(
+                r#"<sbe:message name="Order" id="1">{members}</sbe:message>"#
+            ));
+            let err = check_strict(&xml).unwrap_err();
+            assert!(
+                matches!(err.root(), ParseError::InvalidStructure { .. }),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_stray_text_and_duplicate_attributes() {
+        let err = check_strict(&schema(
+            r#"<sbe:message name="Order" id="1">oops</sbe:message>"#,
+        ))
+        .unwrap_err();
+        assert!(err.to_string().contains("unexpected text 'oops'"), "{err}");
+
+        let err =
+            check_strict(&schema(r#"<sbe:message name="Order" id="1" id="2"/>"#)).unwrap_err();
+        assert!(matches!(err.root(), ParseError::Xml(_)), "{err}");
+    }
+}
@@ -100,7 +100,7 @@ impl ByteOrder {
 }
 
 /// Type definition variants.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TypeDef {
     /// Primitive type definition.
     Primitive(PrimitiveDef),
@@ -161,7 +161,7 @@ impl TypeDef {
 }
 
 /// Primitive type definition.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PrimitiveDef {
     /// Type name.
     pub name: String,
@@ -215,6 +215,12 @@ impl PrimitiveDef {
     pub fn is_array(&self) -> bool {
         self.length.is_some() && self.length != Some(1)
     }
+
+    /// Returns true if the type carries a constant value.
+    #[must_use]
+    pub fn is_constant(&self) -> bool {
+        self.constant_value.is_some()
+    }
 }
 
 /// SBE primitive types.
@@ -334,7 +340,7 @@ impl PrimitiveType {
 }
 
 /// Composite type definition.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CompositeDef {
     /// Type name.
     pub name: String,
@@ -371,13 +377,15 @@ impl CompositeDef {
 }
 
 /// Field within a composite type.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CompositeField {
     /// Field name.
     pub name: String,
     /// Type name (primitive or another type).
     pub type_name: String,
-    /// Primitive type (if directly a primitive).
+    /// What the member encodes.
+    pub kind: MemberKind,
+    /// Primitive type, or the encoding type of an enum or set member.
     pub primitive_type: Option<PrimitiveType>,
     /// Offset within the composite (optional, calculated if not specified).
     pub offset: Option<usize>,
@@ -387,8 +395,12 @@ pub struct CompositeField {
     pub semantic_type: Option<String>,
     /// Description.
     pub description: Option<String>,
-    /// Constant value.
+    /// Field presence.
+    pub presence: Presence,
+    /// Literal constant value (if presence is constant).
     pub constant_value: Option<String>,
+    /// Enum value reference such as `Side.Buy` (if presence is constant).
+    pub value_ref: Option<String>,
 }
 
 impl CompositeField {
@@ -398,18 +410,44 @@ impl CompositeField {
         Self {
             name,
             type_name,
+            kind: MemberKind::Primitive,
             primitive_type: None,
             offset: None,
             encoded_length,
             semantic_type: None,
             description: None,
+            presence: Presence::Required,
             constant_value: None,
+            value_ref: None,
         }
     }
+
+    /// Returns true if the field has a constant value.
+    ///
+    /// Constant fields take no space on the wire.
+    #[must_use]
+    pub fn is_constant(&self) -> bool {
+        self.presence == Presence::Constant
+    }
+}
+
+/// Kind of type a composite member encodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberKind {
+    /// Primitive value, declared with `<type>` or a `<ref>` to a primitive.
+    Primitive,
+    /// Enum named by the member's type name.
+    Enum,
+    /// Set named by the member's type name.
+    Set,
+    /// Nested composite named by the member's type name.
+    Composite,
+    /// `<ref>` whose type is not resolved yet; never left in a parsed schema.
+    Ref,
 }
 
 /// Enum type definition.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct EnumDef {
     /// Type name.
     pub name: String,
@@ -449,7 +487,7 @@ impl EnumDef {
 }
 
 /// Enum valid value.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct EnumValue {
     /// Value name.
     pub name: String,
@@ -490,7 +528,7 @@ impl EnumValue {
 }
 
 /// Set (bitfield) type definition.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SetDef {
     /// Type name.
     pub name: String,
@@ -527,7 +565,7 @@ impl SetDef {
 }
 
 /// Set choice (bit position).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SetChoice {
     /// Choice name.
     pub name: String,
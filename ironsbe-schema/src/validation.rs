@@ -43,12 +43,22 @@ fn validate_types(schema: &Schema) -> Result<(), SchemaError> {
 
 /// Validates a composite type definition.
 fn validate_composite(
-    _schema: &Schema,
+    schema: &Schema,
     composite: &crate::types::CompositeDef,
 ) -> Result<(), SchemaError> {
     let mut expected_offset = 0;
 
     for field in &composite.fields {
+        if field.is_constant() {
+            match &field.value_ref {
+                Some(value_ref) => validate_value_ref(schema, value_ref)?,
+                None if field.constant_value.is_none() => {
+                    return Err(missing_constant(&field.name));
+                }
+                None => {}
+            }
+        }
+
         if let Some(offset) = field.offset {
             if offset < expected_offset {
                 return Err(SchemaError::InvalidOffset {
@@ -65,6 +75,49 @@ fn validate_composite(
     Ok(())
 }
 
+/// Validates a `valueRef` of the form `Enum.Variant`.
+fn validate_value_ref(schema: &Schema, value_ref: &str) -> Result<(), SchemaError> {
+    let Some((enum_name, variant)) = value_ref.split_once('.') else {
+        return Err(SchemaError::Validation {
+            message: format!("valueRef '{value_ref}' is not of the form 'Enum.Value'"),
+        });
+    };
+    let Some(crate::types::TypeDef::Enum(enum_def)) = schema.get_type(enum_name) else {
+        return Err(SchemaError::TypeNotFound {
+            name: enum_name.to_string(),
+        });
+    };
+    if !enum_def.valid_values.iter().any(|v| v.name == variant) {
+        return Err(SchemaError::InvalidEnumValue {
+            enum_name: enum_name.to_string(),
+            value: variant.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Validates that a constant message or group field has a value.
+fn validate_constant_field(
+    schema: &Schema,
+    field: &crate::messages::FieldDef,
+) -> Result<(), SchemaError> {
+    if let Some(value_ref) = &field.value_ref {
+        return validate_value_ref(schema, value_ref);
+    }
+    match schema.get_type(&field.type_name) {
+        Some(crate::types::TypeDef::Primitive(p)) if p.is_constant() => Ok(()),
+        _ if field.is_constant() => Err(missing_constant(&field.name)),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the error for a constant without a value.
+fn missing_constant(field: &str) -> SchemaError {
+    SchemaError::Validation {
+        message: format!("Constant field '{field}' has neither a value nor a valueRef"),
+    }
+}
+
 /// Validates an enum type definition.
 fn validate_enum(enum_def: &crate::types::EnumDef) -> Result<(), SchemaError> {
     use std::collections::HashSet;
@@ -170,6 +223,7 @@ fn validate_message_fields(
                 });
             }
         }
+        validate_constant_field(schema, field)?;
 
         // Check offset ordering
         if field.offset < max_offset && field.encoded_length > 0 {
@@ -196,6 +250,10 @@ fn validate_message_fields(
         validate_group_fields(schema, group)?;
     }
 
+    for data in &msg.data_fields {
+        validate_var_data(schema, data)?;
+    }
+
     Ok(())
 }
 
@@ -212,13 +270,82 @@ fn validate_group_fields(
                 name: field.type_name.clone(),
             });
         }
+        validate_constant_field(schema, field)?;
     }
+    validate_dimension(schema, group)?;
 
     // Validate nested groups
     for nested in &group.nested_groups {
         validate_group_fields(schema, nested)?;
     }
 
+    for data in &group.data_fields {
+        validate_var_data(schema, data)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the primitive type of the composite member `name`, if present.
+fn member_type(
+    composite: &crate::types::CompositeDef,
+    name: &str,
+) -> Option<crate::types::PrimitiveType> {
+    composite
+        .fields
+        .iter()
+        .find(|f| f.name == name && !f.is_constant())
+        .and_then(|f| f.primitive_type)
+}
+
+/// Returns true if `prim` can encode a group size or var data length.
+fn is_length_type(prim: Option<crate::types::PrimitiveType>) -> bool {
+    use crate::types::PrimitiveType;
+    matches!(
+        prim,
+        Some(PrimitiveType::Uint8 | PrimitiveType::Uint16 | PrimitiveType::Uint32)
+    )
+}
+
+/// Validates a group's dimension composite, if the schema declares one.
+fn validate_dimension(
+    schema: &Schema,
+    group: &crate::messages::GroupDef,
+) -> Result<(), SchemaError> {
+    let Some(crate::types::TypeDef::Composite(composite)) = schema.get_type(&group.dimension_type)
+    else {
+        return Ok(());
+    };
+    for member in ["blockLength", "numInGroup"] {
+        if !is_length_type(member_type(composite, member)) {
+            return Err(SchemaError::Validation {
+                message: format!(
+                    "dimension type '{}' of group '{}' needs a uint8, uint16 or uint32 '{member}' member",
+                    composite.name, group.name
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Validates a var data field's encoding composite, if the schema
+/// declares one.
+fn validate_var_data(
+    schema: &Schema,
+    data: &crate::messages::DataFieldDef,
+) -> Result<(), SchemaError> {
+    let Some(crate::types::TypeDef::Composite(composite)) = schema.get_type(&data.type_name) else {
+        return Ok(());
+    };
+    if !is_length_type(member_type(composite, "length")) {
+        return Err(SchemaError::Validation {
+            message: format!(
+                "var data type '{}' of field '{}' needs a uint8, uint16 or uint32 'length' member",
+                composite.name, data.name
+            ),
+        });
+    }
     Ok(())
 }
 
@@ -382,4 +509,102 @@ mod tests {
         let result = validate_schema(&schema);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_validate_constant_value_refs() {
+        let schema_with = |value_ref: &str| {
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy">1</validValue>
+        </enum>
+    </types>
+    <sbe:message name="Test" id="1" blockLength="0">
+        <field name="side" id="1" type="Side" presence="constant" valueRef="{value_ref}"/>
+    </sbe:message>
+</sbe:messageSchema>"#
+            )
+        };
+
+        let schema = parse_schema(&schema_with("Side.Buy")).expect("Failed to parse");
+        assert!(validate_schema(&schema).is_ok());
+
+        let schema = parse_schema(&schema_with("Side.Sell")).expect("Failed to parse");
+        assert!(matches!(
+            validate_schema(&schema),
+            Err(SchemaError::InvalidEnumValue { .. })
+        ));
+
+        let schema = parse_schema(&schema_with("Venue.Xnas")).expect("Failed to parse");
+        assert!(matches!(
+            validate_schema(&schema),
+            Err(SchemaError::TypeNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_composite_constant_without_value() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <composite name="Decimal">
+            <type name="mantissa" primitiveType="int64"/>
+            <type name="exponent" primitiveType="int8" presence="constant"/>
+        </composite>
+    </types>
+</sbe:messageSchema>"#;
+
+        let schema = parse_schema(xml).expect("Failed to parse");
+        assert!(matches!(
+            validate_schema(&schema),
+            Err(SchemaError::Validation { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_dimension_and_var_data_encodings() {
+        let schema_with = |num_in_group: &str, length: &str| {
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="test" id="1" version="1" byteOrder="littleEndian">
+    <types>
+        <composite name="groupSize8">
+            <type name="blockLength" primitiveType="uint8"/>
+            <type name="numInGroup" primitiveType="{num_in_group}"/>
+        </composite>
+        <composite name="varString8">
+            <type name="length" primitiveType="{length}"/>
+            <type name="varData" primitiveType="uint8" length="0"/>
+        </composite>
+    </types>
+    <sbe:message name="Test" id="1">
+        <group name="entries" id="2" dimensionType="groupSize8">
+            <field name="qty" id="3" type="uint32"/>
+        </group>
+        <data name="note" id="4" type="varString8"/>
+    </sbe:message>
+</sbe:messageSchema>"#
+            )
+        };
+
+        let schema = parse_schema(&schema_with("uint8", "uint8")).expect("Failed to parse");
+        assert!(validate_schema(&schema).is_ok());
+
+        let schema = parse_schema(&schema_with("int16", "uint8")).expect("Failed to parse");
+        assert!(matches!(
+            validate_schema(&schema),
+            Err(SchemaError::Validation { .. })
+        ));
+
+        let schema = parse_schema(&schema_with("uint16", "uint64")).expect("Failed to parse");
+        assert!(matches!(
+            validate_schema(&schema),
+            Err(SchemaError::Validation { .. })
+        ));
+    }
 }
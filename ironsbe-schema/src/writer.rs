@@ -0,0 +1,445 @@
+//! SBE XML schema writer.
+//!
+//! Serializes a [`Schema`] back to XML in a canonical form, so tools that
+//! build or rewrite schemas can persist them and diff the result:
+//!
+//! - Every field and composite member carries its resolved offset, and
+//!   every message and group its block length.
+//! - Types declared inline in a composite are written as top-level types
+//!   and referenced with `<ref>`, as the parser stores them.
+//! - Attributes are written in a fixed order and optional attributes with
+//!   their default value are left out.
+//!
+//! Parsing the output yields an equal schema, and writing that schema
+//! again yields identical XML. A [`SchemaIr`](crate::ir::SchemaIr) drops
+//! descriptions and declaration order, so persist the [`Schema`] it was
+//! built from instead.
+
+use crate::messages::{DataFieldDef, FieldDef, GroupDef, MessageDef};
+use crate::types::{
+    ByteOrder, CompositeDef, CompositeField, EnumDef, MemberKind, Presence, PrimitiveDef, Schema,
+    SetDef, TypeDef,
+};
+use quick_xml::escape::escape;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+/// Namespace of the `sbe:` prefix.
+pub(crate) const SBE_NAMESPACE: &str = "http://fixprotocol.io/2016/sbe";
+
+/// Writes `schema` as an SBE XML document.
+#[must_use]
+pub fn write_schema(schema: &Schema) -> String {
+    let mut writer = XmlWriter::document();
+
+    let mut attrs = Attrs::new()
+        .attr("xmlns:sbe", SBE_NAMESPACE)
+        .attr("package", &schema.package)
+        .attr("id", schema.id)
+        .attr("version", schema.version);
+    if !schema.semantic_version.is_empty() {
+        attrs = attrs.attr("semanticVersion", &schema.semantic_version);
+    }
+    attrs = attrs.opt("description", schema.description.as_ref()).attr(
+        "byteOrder",
+        match schema.byte_order {
+            ByteOrder::LittleEndian => "littleEndian",
+            ByteOrder::BigEndian => "bigEndian",
+        },
+    );
+    if schema.header_type != "messageHeader" {
+        attrs = attrs.attr("headerType", &schema.header_type);
+    }
+    writer.open("sbe:messageSchema", attrs);
+
+    if !schema.types.is_empty() {
+        writer.open("types", Attrs::new());
+        for type_def in &schema.types {
+            match type_def {
+                TypeDef::Primitive(p) => write_primitive(&mut writer, p),
+                TypeDef::Composite(c) => write_composite(&mut writer, c),
+                TypeDef::Enum(e) => write_enum(&mut writer, e),
+                TypeDef::Set(s) => write_set(&mut writer, s),
+            }
+        }
+        writer.close("types");
+    }
+
+    for message in &schema.messages {
+        write_message(&mut writer, message);
+    }
+
+    writer.close("sbe:messageSchema");
+    writer.finish()
+}
+
+/// Writes `schema` to the file at `path`.
+///
+/// # Errors
+/// Returns the I/O error if the file cannot be written.
+pub fn write_schema_file(schema: &Schema, path: &Path) -> io::Result<()> {
+    std::fs::write(path, write_schema(schema))
+}
+
+fn write_primitive(writer: &mut XmlWriter, p: &PrimitiveDef) {
+    let mut attrs = Attrs::new()
+        .attr("name", &p.name)
+        .attr("primitiveType", p.primitive_type.sbe_name())
+        .opt("length", p.length)
+        .opt("nullValue", p.null_value.as_ref())
+        .opt("minValue", p.min_value.as_ref())
+        .opt("maxValue", p.max_value.as_ref())
+        .opt("characterEncoding", p.character_encoding.as_ref())
+        .opt("semanticType", p.semantic_type.as_ref())
+        .opt("description", p.description.as_ref());
+    match &p.constant_value {
+        Some(value) => {
+            attrs = attrs.attr("presence", "constant");
+            writer.text_element("type", attrs, value);
+        }
+        None => writer.empty("type", attrs),
+    }
+}
+
+fn write_composite(writer: &mut XmlWriter, c: &CompositeDef) {
+    let attrs = Attrs::new()
+        .attr("name", &c.name)
+        .opt("description", c.description.as_ref())
+        .opt("semanticType", c.semantic_type.as_ref());
+    writer.open("composite", attrs);
+    for member in &c.fields {
+        write_member(writer, member);
+    }
+    writer.close("composite");
+}
+
+/// Writes a composite member as `<type>` if it is declared by primitive
+/// type, or as `<ref>` if it names another type.
+fn write_member(writer: &mut XmlWriter, member: &CompositeField) {
+    let primitive = member
+        .primitive_type
+        .filter(|p| member.kind == MemberKind::Primitive && member.type_name == p.sbe_name());
+    let Some(primitive) = primitive else {
+        let attrs = Attrs::new()
+            .attr("name", &member.name)
+            .attr("type", &member.type_name)
+            .opt("offset", member.offset)
+            .opt("semanticType", member.semantic_type.as_ref())
+            .opt("description", member.description.as_ref());
+        writer.empty("ref", attrs);
+        return;
+    };
+
+    let attrs = Attrs::new()
+        .attr("name", &member.name)
+        .attr("primitiveType", primitive.sbe_name())
+        .opt("offset", member.offset)
+        .presence(member.presence)
+        .opt("valueRef", member.value_ref.as_ref())
+        .opt("semanticType", member.semantic_type.as_ref())
+        .opt("description", member.description.as_ref());
+    match &member.constant_value {
+        Some(value) => writer.text_element("type", attrs, value),
+        None => writer.empty("type", attrs),
+    }
+}
+
+fn write_enum(writer: &mut XmlWriter, e: &EnumDef) {
+    let attrs = Attrs::new()
+        .attr("name", &e.name)
+        .attr("encodingType", e.encoding_type.sbe_name())
+        .opt("nullValue", e.null_value.as_ref())
+        .opt("description", e.description.as_ref());
+    writer.open("enum", attrs);
+    for value in &e.valid_values {
+        let attrs = Attrs::new()
+            .attr("name", &value.name)
+            .opt("description", value.description.as_ref())
+            .opt("sinceVersion", value.since_version)
+            .opt("deprecated", value.deprecated);
+        writer.text_element("validValue", attrs, &value.value);
+    }
+    writer.close("enum");
+}
+
+fn write_set(writer: &mut XmlWriter, s: &SetDef) {
+    let attrs = Attrs::new()
+        .attr("name", &s.name)
+        .attr("encodingType", s.encoding_type.sbe_name())
+        .opt("description", s.description.as_ref());
+    writer.open("set", attrs);
+    for choice in &s.choices {
+        let attrs = Attrs::new()
+            .attr("name", &choice.name)
+            .opt("description", choice.description.as_ref())
+            .opt("sinceVersion", choice.since_version)
+            .opt("deprecated", choice.deprecated);
+        writer.text_element("choice", attrs, &choice.bit_position.to_string());
+    }
+    writer.close("set");
+}
+
+fn write_message(writer: &mut XmlWriter, message: &MessageDef) {
+    let attrs = Attrs::new()
+        .attr("name", &message.name)
+        .attr("id", message.id)
+        .attr("blockLength", message.block_length)
+        .opt("semanticType", message.semantic_type.as_ref())
+        .opt("description", message.description.as_ref())
+        .opt("sinceVersion", message.since_version)
+        .opt("deprecated", message.deprecated);
+    writer.open("sbe:message", attrs);
+    write_block(
+        writer,
+        &message.fields,
+        &message.groups,
+        &message.data_fields,
+    );
+    writer.close("sbe:message");
+}
+
+fn write_group(writer: &mut XmlWriter, group: &GroupDef) {
+    let mut attrs = Attrs::new()
+        .attr("name", &group.name)
+        .attr("id", group.id)
+        .attr("blockLength", group.block_length);
+    if group.dimension_type != "groupSizeEncoding" {
+        attrs = attrs.attr("dimensionType", &group.dimension_type);
+    }
+    attrs = attrs
+        .opt("description", group.description.as_ref())
+        .opt("sinceVersion", group.since_version)
+        .opt("deprecated", group.deprecated);
+    writer.open("group", attrs);
+    write_block(
+        writer,
+        &group.fields,
+        &group.nested_groups,
+        &group.data_fields,
+    );
+    writer.close("group");
+}
+
+/// Writes the fields, groups and var data of a message or group, in the
+/// order SBE requires.
+fn write_block(
+    writer: &mut XmlWriter,
+    fields: &[FieldDef],
+    groups: &[GroupDef],
+    data_fields: &[DataFieldDef],
+) {
+    for field in fields {
+        let attrs = Attrs::new()
+            .attr("name", &field.name)
+            .attr("id", field.id)
+            .attr("type", &field.type_name)
+            .attr("offset", field.offset)
+            .presence(field.presence)
+            .opt("valueRef", field.value_ref.as_ref())
+            .opt("semanticType", field.semantic_type.as_ref())
+            .opt("description", field.description.as_ref())
+            .opt("sinceVersion", field.since_version)
+            .opt("deprecated", field.deprecated);
+        writer.empty("field", attrs);
+    }
+    for group in groups {
+        write_group(writer, group);
+    }
+    for data in data_fields {
+        let attrs = Attrs::new()
+            .attr("name", &data.name)
+            .attr("id", data.id)
+            .attr("type", &data.type_name)
+            .opt("description", data.description.as_ref())
+            .opt("sinceVersion", data.since_version)
+            .opt("deprecated", data.deprecated);
+        writer.empty("data", attrs);
+    }
+}
+
+/// Attributes of one element, escaped and in insertion order.
+pub(crate) struct Attrs(String);
+
+impl Attrs {
+    pub(crate) fn new() -> Self {
+        Self(String::new())
+    }
+
+    pub(crate) fn attr(mut self, name: &str, value: impl ToString) -> Self {
+        let _ = write!(self.0, " {name}=\"{}\"", escape(value.to_string().as_str()));
+        self
+    }
+
+    pub(crate) fn opt(self, name: &str, value: Option<impl ToString>) -> Self {
+        match value {
+            Some(value) => self.attr(name, value),
+            None => self,
+        }
+    }
+
+    /// Adds `presence` unless it is the default, `required`.
+    pub(crate) fn presence(self, presence: Presence) -> Self {
+        match presence {
+            Presence::Required => self,
+            Presence::Optional => self.attr("presence", "optional"),
+            Presence::Constant => self.attr("presence", "constant"),
+        }
+    }
+}
+
+/// Indenting XML output.
+pub(crate) struct XmlWriter {
+    out: String,
+    depth: usize,
+}
+
+impl XmlWriter {
+    /// Starts a document with the XML declaration.
+    pub(crate) fn document() -> Self {
+        Self {
+            out: "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".to_string(),
+            depth: 0,
+        }
+    }
+
+    /// Returns the written document.
+    pub(crate) fn finish(self) -> String {
+        self.out
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.out.push_str("    ");
+        }
+    }
+
+    pub(crate) fn open(&mut self, tag: &str, attrs: Attrs) {
+        self.indent();
+        let _ = writeln!(self.out, "<{tag}{}>", attrs.0);
+        self.depth += 1;
+    }
+
+    pub(crate) fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.indent();
+        let _ = writeln!(self.out, "</{tag}>");
+    }
+
+    pub(crate) fn empty(&mut self, tag: &str, attrs: Attrs) {
+        self.indent();
+        let _ = writeln!(self.out, "<{tag}{}/>", attrs.0);
+    }
+
+    pub(crate) fn text_element(&mut self, tag: &str, attrs: Attrs, text: &str) {
+        self.indent();
+        let _ = writeln!(self.out, "<{tag}{}>{}</{tag}>", attrs.0, escape(text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_schema;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sbe:messageSchema xmlns:sbe="http://fixprotocol.io/2016/sbe"
+                   package="trading" id="7" version="2" semanticVersion="5.2"
+                   description="Orders &amp; fills" byteOrder="bigEndian">
+    <types>
+        <composite name="messageHeader">
+            <type name="blockLength" primitiveType="uint16"/>
+            <type name="templateId" primitiveType="uint16"/>
+            <type name="schemaId" primitiveType="uint16"/>
+            <type name="version" primitiveType="uint16"/>
+        </composite>
+        <type name="Symbol" primitiveType="char" length="8" characterEncoding="ASCII"/>
+        <type name="Venue" primitiveType="char" presence="constant">X</type>
+        <enum name="Side" encodingType="uint8">
+            <validValue name="Buy" description="Bid side">1</validValue>
+            <validValue name="Sell" sinceVersion="2">2</validValue>
+        </enum>
+        <set name="Flags" encodingType="uint8">
+            <choice name="Urgent">0</choice>
+            <choice name="Hidden">3</choice>
+        </set>
+        <composite name="Quote" description="Price &lt;and&gt; side">
+            <type name="mantissa" primitiveType="int64"/>
+            <type name="exponent" primitiveType="int8" presence="constant">-4</type>
+            <ref name="side" type="Side"/>
+            <enum name="Kind" encodingType="uint8">
+                <validValue name="Firm">0</validValue>
+            </enum>
+        </composite>
+    </types>
+    <sbe:message name="NewOrder" id="1" description="New order">
+        <field name="symbol" id="1" type="Symbol"/>
+        <field name="venue" id="2" type="Venue"/>
+        <field name="side" id="3" type="Side" presence="constant" valueRef="Side.Buy"/>
+        <field name="flags" id="4" type="Flags"/>
+        <field name="quote" id="5" type="Quote" sinceVersion="2"/>
+        <field name="qty" id="6" type="uint32" presence="optional"/>
+        <group name="legs" id="10" dimensionType="groupSize32">
+            <field name="ratio" id="11" type="uint16"/>
+            <group name="fills" id="12">
+                <field name="px" id="13" type="int64"/>
+            </group>
+            <data name="note" id="14" type="varDataEncoding"/>
+        </group>
+        <data name="text" id="20" type="varDataEncoding" deprecated="2"/>
+    </sbe:message>
+</sbe:messageSchema>"#;
+
+    #[test]
+    fn test_write_round_trips() {
+        let schema = parse_schema(XML).expect("Failed to parse");
+        let xml = write_schema(&schema);
+        let reparsed = parse_schema(&xml).expect("written schema does not parse");
+
+        assert_eq!(reparsed.package, schema.package);
+        assert_eq!(reparsed.semantic_version, "5.2");
+        assert_eq!(reparsed.description.as_deref(), Some("Orders & fills"));
+        assert_eq!(reparsed.byte_order, ByteOrder::BigEndian);
+        assert_eq!(reparsed.types, schema.types);
+        assert_eq!(
+            format!("{:?}", reparsed.messages),
+            format!("{:?}", schema.messages)
+        );
+        assert_eq!(write_schema(&reparsed), xml, "output not canonical");
+    }
+
+    #[test]
+    fn test_write_canonical_form() {
+        let schema = parse_schema(XML).expect("Failed to parse");
+        let xml = write_schema(&schema);
+
+        // Resolved offsets and block lengths are written out.
+        assert!(xml.contains(r#"<sbe:message name="NewOrder" id="1" blockLength="23""#));
+        assert!(xml.contains(r#"<field name="quote" id="5" type="Quote" offset="9""#));
+        assert!(xml.contains(
+            r#"<group name="legs" id="10" blockLength="2" dimensionType="groupSize32">"#
+        ));
+        // Inline types become top-level types referenced by name.
+        assert!(xml.contains(r#"<enum name="Kind" encodingType="uint8">"#));
+        assert!(xml.contains(r#"<ref name="Kind" type="Kind" offset="9"/>"#));
+        assert!(xml.contains(
+            r#"<type name="exponent" primitiveType="int8" offset="8" presence="constant">-4</type>"#
+        ));
+        assert!(
+            xml.contains(r#"<type name="Venue" primitiveType="char" presence="constant">X</type>"#)
+        );
+        assert!(xml.contains(r#"description="Price &lt;and&gt; side""#));
+        assert!(!xml.contains("headerType"));
+    }
+
+    #[test]
+    fn test_write_schema_file() {
+        let schema = parse_schema(XML).expect("Failed to parse");
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("trading.xml");
+
+        write_schema_file(&schema, &path).expect("write failed");
+        let reparsed = crate::parser::parse_schema_file(&path).expect("Failed to parse");
+        assert_eq!(reparsed.types, schema.types);
+    }
+}
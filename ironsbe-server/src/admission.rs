@@ -0,0 +1,83 @@
+//! Admission of connections beyond `max_connections`.
+//!
+//! By default a server at its connection limit closes new connections
+//! straight away. An [`AdmissionPolicy`] lets them wait in a bounded queue
+//! for a slot instead, and lets the server tell rejected clients why before
+//! closing, so they can back off rather than reconnect in a tight loop.
+//! Queued and rejected connections are reported as
+//! [`ServerEvent`](crate::ServerEvent)s so operators can watch capacity
+//! pressure.
+
+use std::fmt;
+use std::time::Duration;
+
+/// Default time a connection may wait in the admission queue.
+pub const DEFAULT_QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How a server treats connections arriving while it is at
+/// `max_connections`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdmissionPolicy {
+    /// Connections that may wait for a free slot; zero rejects at once.
+    pub queue_capacity: usize,
+    /// How long a connection may wait before it is rejected.
+    pub queue_timeout: Duration,
+    /// Message sent to a rejected connection before it is closed.
+    pub busy_message: Option<Vec<u8>>,
+}
+
+impl AdmissionPolicy {
+    /// Creates a policy that rejects at once without a message.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lets up to `capacity` connections wait up to `timeout` for a slot.
+    ///
+    /// Waiting connections are admitted in arrival order as sessions end.
+    #[must_use]
+    pub fn queue(mut self, capacity: usize, timeout: Duration) -> Self {
+        self.queue_capacity = capacity;
+        self.queue_timeout = timeout;
+        self
+    }
+
+    /// Sends `message` to rejected connections before closing them.
+    ///
+    /// Typically an SBE message of the application's protocol saying the
+    /// server is busy and when to retry. It is sent as one frame.
+    #[must_use]
+    pub fn busy_message(mut self, message: Vec<u8>) -> Self {
+        self.busy_message = Some(message);
+        self
+    }
+}
+
+impl Default for AdmissionPolicy {
+    fn default() -> Self {
+        Self {
+            queue_capacity: 0,
+            queue_timeout: DEFAULT_QUEUE_TIMEOUT,
+            busy_message: None,
+        }
+    }
+}
+
+/// Why a connection was turned away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    /// The server was at `max_connections` and the queue was full.
+    Full,
+    /// The connection waited in the queue longer than its timeout.
+    Timeout,
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full => f.write_str("server full"),
+            Self::Timeout => f.write_str("admission timed out"),
+        }
+    }
+}
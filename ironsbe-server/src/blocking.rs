@@ -0,0 +1,405 @@
+//! Blocking server on std threads (feature `tcp-std`).
+//!
+//! [`BlockingServer`] accepts connections with `std::net` and runs each
+//! session on its own thread, calling the same [`MessageHandler`] as the
+//! async servers. It is meant for applications that do not want an async
+//! runtime and serve a modest number of connections.
+//!
+//! With a [`CoreConfig`], the accept loop and the session threads are
+//! pinned to dedicated cores.
+
+use crate::error::ServerError;
+use crate::handler::{MessageHandler, Responder, SendError};
+use crate::session::SessionManager;
+use ironsbe_core::header::MessageHeader;
+use ironsbe_core::runtime::CoreConfig;
+use ironsbe_transport::TransportError;
+use ironsbe_transport::tcp_std::{BlockingTcpConnection, BlockingTcpListener};
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+/// Write halves of the live sessions, keyed by session ID.
+type SessionWriters = RwLock<HashMap<u64, Arc<Mutex<BlockingTcpConnection>>>>;
+
+/// State shared by the accept loop, session threads and handles.
+struct Shared {
+    sessions: SessionManager,
+    writers: SessionWriters,
+    shutdown: AtomicBool,
+    local_addr: SocketAddr,
+}
+
+impl Shared {
+    fn send_to(&self, session_id: u64, message: &[u8]) -> Result<(), SendError> {
+        let writer = self
+            .writers
+            .read()
+            .get(&session_id)
+            .cloned()
+            .ok_or_else(|| SendError {
+                message: format!("unknown session {session_id}"),
+            })?;
+        writer.lock().send(message).map_err(|e| SendError {
+            message: e.to_string(),
+        })
+    }
+}
+
+/// Thread-per-connection SBE server without an async runtime.
+pub struct BlockingServer<H> {
+    listener: BlockingTcpListener,
+    handler: Arc<H>,
+    shared: Arc<Shared>,
+    cores: Arc<CoreConfig>,
+}
+
+impl<H: MessageHandler + 'static> BlockingServer<H> {
+    /// Binds to `addr` and serves connections with `handler`.
+    ///
+    /// # Errors
+    /// Returns error if binding fails.
+    pub fn bind(addr: SocketAddr, handler: H) -> Result<Self, ServerError> {
+        let listener = BlockingTcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        Ok(Self {
+            listener,
+            handler: Arc::new(handler),
+            shared: Arc::new(Shared {
+                sessions: SessionManager::new(),
+                writers: RwLock::new(HashMap::new()),
+                shutdown: AtomicBool::new(false),
+                local_addr,
+            }),
+            cores: Arc::new(CoreConfig::default()),
+        })
+    }
+
+    /// Sets the maximum frame size of accepted connections.
+    #[must_use]
+    pub fn max_frame_size(mut self, size: usize) -> Self {
+        self.listener = self.listener.max_frame_size(size);
+        self
+    }
+
+    /// Pins the accept loop and the session threads as `cores` says.
+    /// [`run`](Self::run) pins the thread it is called on as the accept
+    /// thread.
+    #[must_use]
+    pub fn cores(mut self, cores: CoreConfig) -> Self {
+        self.cores = Arc::new(cores);
+        self
+    }
+
+    /// Returns the address the server is bound to.
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.shared.local_addr
+    }
+
+    /// Returns a handle for controlling the server from other threads.
+    #[must_use]
+    pub fn handle(&self) -> BlockingServerHandle {
+        BlockingServerHandle {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// Accepts connections until [`BlockingServerHandle::shutdown`] is
+    /// called, then waits for every session thread to finish.
+    ///
+    /// # Errors
+    /// Returns error if accepting or spawning a session thread fails, or,
+    /// with a strict [`CoreConfig`], if the accept thread cannot be pinned.
+    pub fn run(&self) -> Result<(), ServerError> {
+        self.cores.pin_accept()?;
+        tracing::info!("Blocking server listening on {}", self.shared.local_addr);
+        let mut sessions = Vec::new();
+        let mut spawned = 0;
+        let result = loop {
+            let conn = match self.listener.accept() {
+                Ok(conn) => conn,
+                Err(_) if self.shared.shutdown.load(Ordering::Acquire) => break Ok(()),
+                Err(e) => break Err(ServerError::Io(e)),
+            };
+            if self.shared.shutdown.load(Ordering::Acquire) {
+                break Ok(());
+            }
+            let handler = Arc::clone(&self.handler);
+            let shared = Arc::clone(&self.shared);
+            let cores = Arc::clone(&self.cores);
+            let index = spawned;
+            spawned += 1;
+            let session = thread::Builder::new()
+                .name(format!("ironsbe-session-{index}"))
+                .spawn(move || {
+                    if let Err(e) = cores.pin_session(index) {
+                        tracing::warn!("failed to pin session thread {index}: {e}");
+                        return;
+                    }
+                    run_session(conn, &*handler, &shared);
+                });
+            match session {
+                Ok(session) => sessions.push(session),
+                Err(e) => break Err(ServerError::Io(e)),
+            }
+            sessions.retain(|session| !session.is_finished());
+        };
+        for session in sessions {
+            let _ = session.join();
+        }
+        result
+    }
+}
+
+/// Reads frames from one connection until it closes or the server stops.
+fn run_session<H: MessageHandler>(mut conn: BlockingTcpConnection, handler: &H, shared: &Shared) {
+    let peer_addr = conn.peer_addr();
+    let writer = match conn.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            tracing::warn!("failed to clone connection from {peer_addr}: {e}");
+            return;
+        }
+    };
+    let session_id = shared.sessions.create_session(peer_addr);
+    shared
+        .writers
+        .write()
+        .insert(session_id, Arc::new(Mutex::new(writer)));
+    // Shutdown may have swept the writers before this one was registered.
+    if shared.shutdown.load(Ordering::Acquire) {
+        let _ = conn.shutdown();
+    }
+    tracing::info!("Session {session_id} connected from {peer_addr}");
+    handler.on_session_start(session_id);
+
+    let responder = BlockingResponder { session_id, shared };
+    loop {
+        match conn.recv() {
+            Ok(frame) => {
+                shared.sessions.touch_session(session_id);
+                if frame.len() < MessageHeader::ENCODED_LENGTH {
+                    handler.on_error(session_id, "Message too short for header");
+                    continue;
+                }
+                let header = MessageHeader::wrap(&frame, 0);
+                handler.on_message(session_id, &header, &frame, &responder);
+            }
+            Err(TransportError::ConnectionClosed) => break,
+            Err(e) => {
+                if !shared.shutdown.load(Ordering::Acquire) {
+                    handler.on_error(session_id, &e.to_string());
+                }
+                break;
+            }
+        }
+    }
+
+    shared.writers.write().remove(&session_id);
+    shared.sessions.close_session(session_id);
+    handler.on_session_end(session_id);
+    tracing::info!("Session {session_id} disconnected");
+}
+
+/// Responder writing straight to the session sockets.
+struct BlockingResponder<'a> {
+    session_id: u64,
+    shared: &'a Shared,
+}
+
+impl Responder for BlockingResponder<'_> {
+    fn send(&self, message: &[u8]) -> Result<(), SendError> {
+        self.shared.send_to(self.session_id, message)
+    }
+
+    fn send_to(&self, session_id: u64, message: &[u8]) -> Result<(), SendError> {
+        self.shared.send_to(session_id, message)
+    }
+}
+
+/// Handle for controlling a [`BlockingServer`] from other threads.
+#[derive(Clone)]
+pub struct BlockingServerHandle {
+    shared: Arc<Shared>,
+}
+
+impl BlockingServerHandle {
+    /// Stops the accept loop and closes every session.
+    pub fn shutdown(&self) {
+        if self.shared.shutdown.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        for writer in self.shared.writers.read().values() {
+            let _ = writer.lock().shutdown();
+        }
+        // Wake the accept loop with a throwaway connection.
+        let mut wake = self.shared.local_addr;
+        if wake.ip().is_unspecified() {
+            wake.set_ip(if wake.is_ipv4() {
+                Ipv4Addr::LOCALHOST.into()
+            } else {
+                Ipv6Addr::LOCALHOST.into()
+            });
+        }
+        let _ = TcpStream::connect(wake);
+    }
+
+    /// Sends a message to a single session by ID.
+    ///
+    /// # Errors
+    /// Returns error if the session is unknown or the write fails.
+    pub fn send_to(&self, session_id: u64, message: &[u8]) -> Result<(), SendError> {
+        self.shared.send_to(session_id, message)
+    }
+
+    /// Returns the IDs of the connected sessions.
+    #[must_use]
+    pub fn session_ids(&self) -> Vec<u64> {
+        self.shared.sessions.session_ids()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    /// Echoes every message and reports session lifecycle events.
+    struct EchoHandler {
+        events: Mutex<mpsc::Sender<String>>,
+    }
+
+    impl MessageHandler for EchoHandler {
+        fn on_message(
+            &self,
+            _session_id: u64,
+            _header: &MessageHeader,
+            buffer: &[u8],
+            responder: &dyn Responder,
+        ) {
+            responder.send(buffer).unwrap();
+        }
+
+        fn on_session_start(&self, session_id: u64) {
+            let _ = self.events.lock().send(format!("start {session_id}"));
+        }
+
+        fn on_session_end(&self, session_id: u64) {
+            let _ = self.events.lock().send(format!("end {session_id}"));
+        }
+
+        fn on_error(&self, session_id: u64, error: &str) {
+            let _ = self
+                .events
+                .lock()
+                .send(format!("error {session_id}: {error}"));
+        }
+    }
+
+    fn message(template_id: u16) -> Vec<u8> {
+        let mut buffer = vec![0u8; MessageHeader::ENCODED_LENGTH + 4];
+        MessageHeader::new(4, template_id, 1, 1).encode(&mut buffer, 0);
+        buffer
+    }
+
+    #[test]
+    fn test_blocking_server_echo_and_shutdown() {
+        let (events_tx, events) = mpsc::channel();
+        let server = BlockingServer::bind(
+            "127.0.0.1:0".parse().unwrap(),
+            EchoHandler {
+                events: Mutex::new(events_tx),
+            },
+        )
+        .unwrap();
+        let addr = server.local_addr();
+        let handle = server.handle();
+        let running = thread::spawn(move || server.run());
+
+        let mut client = BlockingTcpConnection::connect(addr).unwrap();
+        let event = events.recv_timeout(Duration::from_secs(5)).unwrap();
+        let session_id: u64 = event.strip_prefix("start ").unwrap().parse().unwrap();
+
+        client.send(&message(3)).unwrap();
+        assert_eq!(client.recv().unwrap(), message(3));
+        handle.send_to(session_id, &message(9)).unwrap();
+        assert_eq!(client.recv().unwrap(), message(9));
+
+        client.send(b"ab").unwrap();
+        assert_eq!(
+            events.recv_timeout(Duration::from_secs(5)).unwrap(),
+            format!("error {session_id}: Message too short for header")
+        );
+        assert_eq!(handle.session_ids(), vec![session_id]);
+
+        handle.shutdown();
+        running.join().unwrap().unwrap();
+        assert_eq!(
+            events.recv_timeout(Duration::from_secs(5)).unwrap(),
+            format!("end {session_id}")
+        );
+        assert!(matches!(
+            client.recv(),
+            Err(TransportError::ConnectionClosed)
+        ));
+        assert!(handle.send_to(session_id, &message(1)).is_err());
+    }
+
+    /// Reports the cores its session threads may run on.
+    #[cfg(target_os = "linux")]
+    struct AffinityHandler {
+        cores: Mutex<mpsc::Sender<Vec<usize>>>,
+    }
+
+    #[cfg(target_os = "linux")]
+    impl MessageHandler for AffinityHandler {
+        fn on_message(
+            &self,
+            _session_id: u64,
+            _header: &MessageHeader,
+            _buffer: &[u8],
+            _responder: &dyn Responder,
+        ) {
+        }
+
+        fn on_session_start(&self, _session_id: u64) {
+            let cores = ironsbe_core::runtime::available_cores().unwrap();
+            let _ = self.cores.lock().send(cores);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_blocking_server_pins_session_threads() {
+        let core = *ironsbe_core::runtime::available_cores()
+            .unwrap()
+            .last()
+            .unwrap();
+        let (cores_tx, cores) = mpsc::channel();
+        let server = BlockingServer::bind(
+            "127.0.0.1:0".parse().unwrap(),
+            AffinityHandler {
+                cores: Mutex::new(cores_tx),
+            },
+        )
+        .unwrap()
+        .cores(CoreConfig::new().session_cores([core]).strict(true));
+        let addr = server.local_addr();
+        let handle = server.handle();
+        let running = thread::spawn(move || server.run());
+
+        let _client = BlockingTcpConnection::connect(addr).unwrap();
+        assert_eq!(
+            cores.recv_timeout(Duration::from_secs(5)).unwrap(),
+            vec![core]
+        );
+
+        handle.shutdown();
+        running.join().unwrap().unwrap();
+    }
+}
@@ -1,17 +1,27 @@
 //! Server builder and main server implementation.
 
+use crate::admission::{AdmissionPolicy, RejectReason};
 use crate::error::ServerError;
 use crate::handler::{MessageHandler, Responder, SendError};
+use crate::outbound::{self, AsyncSessionResponder, OutboundReceiver, OutboundSender};
 use crate::session::SessionManager;
+use ironsbe_channel::expiry::{ExpiryPolicy, Stamped};
 use ironsbe_channel::mpsc::{MpscChannel, MpscReceiver, MpscSender};
+use ironsbe_channel::priority::{PriorityChannel, PriorityReceiver, PrioritySender};
+use ironsbe_core::frame::OwnedFrame;
 use ironsbe_core::header::MessageHeader;
+use ironsbe_core::tap::{MessageTap, TapDirection};
+use ironsbe_session::{FlowRegistry, Session as FixpSession, SessionEvent, SessionServerConfig};
 use ironsbe_transport::traits::{Connection, Listener, Transport};
+#[cfg(feature = "ws")]
+use ironsbe_transport::ws::{WsConnection, WsListener, WsServerConfig};
 use parking_lot::RwLock;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::{Notify, mpsc as tokio_mpsc};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
 use tokio_util::sync::CancellationToken;
 use tracing::Instrument;
 
@@ -20,7 +30,17 @@ use tracing::Instrument;
 /// [`Server::handle_command`] on `CloseSession` / `Shutdown`, and
 /// cloned into every [`SessionResponder`] so `send_to` can resolve
 /// the target against the live session table.  See #40, #41.
-type SessionSenderMap = Arc<RwLock<HashMap<u64, tokio_mpsc::UnboundedSender<Vec<u8>>>>>;
+///
+/// Queued messages are stamped with their enqueue time so the session
+/// writer can drop those that outlived the server's [`ExpiryPolicy`], and
+/// counted so [`AsyncSessionResponder`] sends can wait for capacity.
+pub(crate) type SessionSenderMap = Arc<RwLock<HashMap<u64, OutboundSender>>>;
+
+/// Default [`ServerBuilder::outbound_capacity`].
+pub const DEFAULT_OUTBOUND_CAPACITY: usize = 1024;
+
+/// How long sending the busy message to a rejected connection may take.
+const BUSY_SEND_TIMEOUT: Duration = Duration::from_secs(1);
 
 /// Builder for configuring and creating a server.
 ///
@@ -35,7 +55,15 @@ pub struct ServerBuilder<H, T: Transport = ironsbe_transport::DefaultTransport>
     bind_config: Option<T::BindConfig>,
     handler: Option<H>,
     max_connections: usize,
+    admission: AdmissionPolicy,
     channel_capacity: usize,
+    expiry_policy: ExpiryPolicy,
+    outbound_capacity: usize,
+    session_manager: Option<Arc<SessionManager>>,
+    tap: Option<Arc<MessageTap>>,
+    fixp: Option<Arc<FlowRegistry>>,
+    #[cfg(feature = "ws")]
+    ws_config: Option<WsServerConfig>,
     _transport: PhantomData<T>,
 }
 
@@ -49,7 +77,15 @@ pub struct ServerBuilder<H, T: Transport> {
     bind_config: Option<T::BindConfig>,
     handler: Option<H>,
     max_connections: usize,
+    admission: AdmissionPolicy,
     channel_capacity: usize,
+    expiry_policy: ExpiryPolicy,
+    outbound_capacity: usize,
+    session_manager: Option<Arc<SessionManager>>,
+    tap: Option<Arc<MessageTap>>,
+    fixp: Option<Arc<FlowRegistry>>,
+    #[cfg(feature = "ws")]
+    ws_config: Option<WsServerConfig>,
     _transport: PhantomData<T>,
 }
 
@@ -62,7 +98,15 @@ impl<H: MessageHandler, T: Transport> ServerBuilder<H, T> {
             bind_config: None,
             handler: None,
             max_connections: 1000,
+            admission: AdmissionPolicy::default(),
             channel_capacity: 4096,
+            expiry_policy: ExpiryPolicy::default(),
+            outbound_capacity: DEFAULT_OUTBOUND_CAPACITY,
+            session_manager: None,
+            tap: None,
+            fixp: None,
+            #[cfg(feature = "ws")]
+            ws_config: None,
             _transport: PhantomData,
         }
     }
@@ -104,13 +148,112 @@ impl<H: MessageHandler, T: Transport> ServerBuilder<H, T> {
         self
     }
 
+    /// Sets how connections beyond [`max_connections`](Self::max_connections)
+    /// are handled.
+    ///
+    /// By default they are closed at once. See [`AdmissionPolicy`] to let
+    /// them wait for a slot or to tell them the server is busy.
+    #[must_use]
+    pub fn admission_policy(mut self, policy: AdmissionPolicy) -> Self {
+        self.admission = policy;
+        self
+    }
+
     /// Sets the channel capacity.
+    ///
+    /// Applies to the event channel and to each lane of the command
+    /// channel: control commands (shutdown, session close) have their own
+    /// lane and are handled before queued broadcasts and pushes.
     #[must_use]
     pub fn channel_capacity(mut self, capacity: usize) -> Self {
         self.channel_capacity = capacity;
         self
     }
 
+    /// Sets the expiry policy for outbound session queues.
+    ///
+    /// Messages that wait in a session's outbound queue longer than the TTL
+    /// for their template ID are dropped instead of sent and reported as
+    /// [`ServerEvent::MessageExpired`]. By default nothing expires.
+    #[must_use]
+    pub fn expiry_policy(mut self, policy: ExpiryPolicy) -> Self {
+        self.expiry_policy = policy;
+        self
+    }
+
+    /// Sets how many messages a session may have queued before
+    /// [`AsyncSessionResponder`] sends to it wait.
+    ///
+    /// Synchronous [`Responder`] sends, broadcasts and
+    /// [`ServerHandle::send_to`] never wait and may queue past it. A
+    /// capacity of zero is treated as one.
+    #[must_use]
+    pub fn outbound_capacity(mut self, capacity: usize) -> Self {
+        self.outbound_capacity = capacity;
+        self
+    }
+
+    /// Sets the session manager tracking the server's sessions.
+    ///
+    /// Share one built with [`SessionManager::with_store`] with the handler
+    /// to persist sequence numbers and subscriptions and resume sessions
+    /// across restarts. The state is saved again on shutdown. By default
+    /// the server uses a fresh manager without a store.
+    #[must_use]
+    pub fn session_manager(mut self, sessions: Arc<SessionManager>) -> Self {
+        self.session_manager = Some(sessions);
+        self
+    }
+
+    /// Sets the tap that session frames are reported to.
+    ///
+    /// Every frame a session receives or sends is passed to
+    /// [`MessageTap::observe`] with the session ID as the stream, so an
+    /// admin endpoint holding the same tap can attach a sink at runtime to
+    /// inspect live traffic. By default no tap is installed.
+    #[must_use]
+    pub fn tap(mut self, tap: Arc<MessageTap>) -> Self {
+        self.tap = Some(tap);
+        self
+    }
+
+    /// Runs the FIXP session layer on every connection.
+    ///
+    /// Clients must negotiate and establish a session before exchanging
+    /// application messages. The handler only sees application messages;
+    /// its responses are sequenced, kept for retransmission and dropped
+    /// while the session is not established. Logical sessions outlive
+    /// connections, so a client can reconnect and recover what it missed.
+    /// Session events are reported as [`ServerEvent::Fixp`].
+    #[must_use]
+    pub fn fixp(mut self, config: SessionServerConfig) -> Self {
+        self.fixp = Some(Arc::new(FlowRegistry::new(config)));
+        self
+    }
+
+    /// Also accepts WebSocket connections on `addr`.
+    ///
+    /// WebSocket clients carry one SBE message per binary WebSocket
+    /// message and join the same sessions, handler and connection limit as
+    /// clients of the main transport.
+    #[cfg(feature = "ws")]
+    #[must_use]
+    pub fn listen_ws(mut self, addr: SocketAddr) -> Self {
+        self.ws_config = Some(WsServerConfig::new(addr));
+        self
+    }
+
+    /// Also accepts WebSocket connections, with the given settings.
+    ///
+    /// Use this instead of [`listen_ws`](Self::listen_ws) to override the
+    /// frame limits or handshake timeout.
+    #[cfg(feature = "ws")]
+    #[must_use]
+    pub fn ws_config(mut self, config: WsServerConfig) -> Self {
+        self.ws_config = Some(config);
+        self
+    }
+
     /// Builds the server and handle.
     ///
     /// # Panics
@@ -118,7 +261,8 @@ impl<H: MessageHandler, T: Transport> ServerBuilder<H, T> {
     #[must_use]
     pub fn build(self) -> (Server<H, T>, ServerHandle) {
         let handler = self.handler.expect("Handler required");
-        let (cmd_tx, cmd_rx) = MpscChannel::bounded(self.channel_capacity);
+        let (cmd_tx, cmd_rx) =
+            PriorityChannel::bounded(self.channel_capacity, self.channel_capacity);
         let (event_tx, event_rx) = MpscChannel::bounded(self.channel_capacity);
 
         let cmd_notify = Arc::new(Notify::new());
@@ -131,14 +275,22 @@ impl<H: MessageHandler, T: Transport> ServerBuilder<H, T> {
             ),
             handler: Arc::new(handler),
             max_connections: self.max_connections,
+            admission: self.admission,
+            waiting: VecDeque::new(),
+            expiry_policy: Arc::new(self.expiry_policy),
+            outbound_capacity: self.outbound_capacity,
             cmd_tx: cmd_tx.clone(),
             cmd_rx,
             event_tx,
-            sessions: SessionManager::new(),
+            sessions: self.session_manager.unwrap_or_default(),
             cmd_notify: Arc::clone(&cmd_notify),
             shutdown_token: CancellationToken::new(),
             session_tokens: HashMap::new(),
             session_senders: Arc::new(RwLock::new(HashMap::new())),
+            tap: self.tap,
+            fixp: self.fixp,
+            #[cfg(feature = "ws")]
+            ws_config: self.ws_config,
             _transport: PhantomData,
         };
 
@@ -185,6 +337,21 @@ impl<H: MessageHandler> ServerBuilder<H> {
         self.bind_config = Some(cfg.max_frame_size(size));
         self
     }
+
+    /// Accepts only TLS connections (Tokio TCP backend only).
+    ///
+    /// Convenience shortcut that sets
+    /// [`TcpServerConfig::tls`](ironsbe_transport::tcp::TcpServerConfig::tls).
+    #[cfg(feature = "tls")]
+    #[must_use]
+    pub fn tls(mut self, config: ironsbe_transport::tcp::TlsServerConfig) -> Self {
+        let cfg = self
+            .bind_config
+            .take()
+            .unwrap_or_else(|| ironsbe_transport::tcp::TcpServerConfig::new(self.bind_addr));
+        self.bind_config = Some(cfg.tls(config));
+        self
+    }
 }
 
 /// The main server instance.
@@ -197,15 +364,22 @@ pub struct Server<H, T: Transport = ironsbe_transport::DefaultTransport> {
     bind_config: Option<T::BindConfig>,
     handler: Arc<H>,
     max_connections: usize,
+    admission: AdmissionPolicy,
+    /// Connections waiting for a slot, oldest first.
+    waiting: VecDeque<Waiting<Inbound<T::Connection>>>,
+    /// Shared with every session writer to drop stale outbound messages.
+    expiry_policy: Arc<ExpiryPolicy>,
+    /// Queue depth at which [`AsyncSessionResponder`] sends wait.
+    outbound_capacity: usize,
     /// Cloned and handed to per-session tasks so they can fire
     /// `ServerCommand::CloseSession` when the session ends, freeing the
     /// `SessionManager` slot back in the run loop.  Without this the
     /// slot leaks and `max_connections` eventually rejects every new
     /// connection.
-    cmd_tx: MpscSender<ServerCommand>,
-    cmd_rx: MpscReceiver<ServerCommand>,
+    cmd_tx: PrioritySender<ServerCommand>,
+    cmd_rx: PriorityReceiver<ServerCommand>,
     event_tx: MpscSender<ServerEvent>,
-    sessions: SessionManager,
+    sessions: Arc<SessionManager>,
     cmd_notify: Arc<Notify>,
     /// Parent cancellation token. `cancel()` fans out to every live
     /// child token in `session_tokens`, so `ServerCommand::Shutdown`
@@ -221,6 +395,13 @@ pub struct Server<H, T: Transport = ironsbe_transport::DefaultTransport> {
     /// `target` against the live table and `ServerCommand::Broadcast`
     /// can iterate.  See #40, #41.
     session_senders: SessionSenderMap,
+    /// Reported every inbound and outbound session frame, if set.
+    tap: Option<Arc<MessageTap>>,
+    /// Logical FIXP sessions, if the session layer is enabled.
+    fixp: Option<Arc<FlowRegistry>>,
+    /// Settings of the additional WebSocket listener, taken in `run`.
+    #[cfg(feature = "ws")]
+    ws_config: Option<WsServerConfig>,
     _transport: PhantomData<T>,
 }
 
@@ -234,11 +415,18 @@ pub struct Server<H, T: Transport> {
     bind_config: Option<T::BindConfig>,
     handler: Arc<H>,
     max_connections: usize,
+    admission: AdmissionPolicy,
+    /// See the field with the same name on the `tcp-tokio` variant.
+    waiting: VecDeque<Waiting<Inbound<T::Connection>>>,
+    /// See the field with the same name on the `tcp-tokio` variant.
+    expiry_policy: Arc<ExpiryPolicy>,
     /// See the field with the same name on the `tcp-tokio` variant.
-    cmd_tx: MpscSender<ServerCommand>,
-    cmd_rx: MpscReceiver<ServerCommand>,
+    outbound_capacity: usize,
+    /// See the field with the same name on the `tcp-tokio` variant.
+    cmd_tx: PrioritySender<ServerCommand>,
+    cmd_rx: PriorityReceiver<ServerCommand>,
     event_tx: MpscSender<ServerEvent>,
-    sessions: SessionManager,
+    sessions: Arc<SessionManager>,
     cmd_notify: Arc<Notify>,
     /// See the field with the same name on the `tcp-tokio` variant.
     shutdown_token: CancellationToken,
@@ -246,15 +434,106 @@ pub struct Server<H, T: Transport> {
     session_tokens: HashMap<u64, CancellationToken>,
     /// See the field with the same name on the `tcp-tokio` variant.
     session_senders: SessionSenderMap,
+    /// See the field with the same name on the `tcp-tokio` variant.
+    tap: Option<Arc<MessageTap>>,
+    /// See the field with the same name on the `tcp-tokio` variant.
+    fixp: Option<Arc<FlowRegistry>>,
+    /// See the field with the same name on the `tcp-tokio` variant.
+    #[cfg(feature = "ws")]
+    ws_config: Option<WsServerConfig>,
     _transport: PhantomData<T>,
 }
 
+/// The server's listeners: the transport's and, with the `ws` feature, an
+/// optional WebSocket listener.
+struct Listeners<L> {
+    native: L,
+    #[cfg(feature = "ws")]
+    ws: Option<WsListener>,
+}
+
+impl<L: Listener> Listeners<L> {
+    /// Accepts the next connection on any listener.
+    async fn accept(&mut self) -> std::io::Result<Inbound<L::Connection>> {
+        #[cfg(feature = "ws")]
+        if let Some(ws) = &mut self.ws {
+            return tokio::select! {
+                result = self.native.accept() => {
+                    result.map(Inbound::Native).map_err(std::io::Error::other)
+                }
+                result = ws.accept() => result.map(|conn| Inbound::Ws(Box::new(conn))),
+            };
+        }
+        self.native
+            .accept()
+            .await
+            .map(Inbound::Native)
+            .map_err(std::io::Error::other)
+    }
+}
+
+/// A connection accepted on one of the server's [`Listeners`].
+enum Inbound<C> {
+    /// From the transport's listener.
+    Native(C),
+    /// From the WebSocket listener.
+    #[cfg(feature = "ws")]
+    Ws(Box<WsConnection>),
+}
+
+impl<C: Connection> Connection for Inbound<C> {
+    type Error = std::io::Error;
+
+    async fn recv(&mut self) -> Result<Option<bytes::BytesMut>, Self::Error> {
+        match self {
+            Self::Native(conn) => conn.recv().await.map_err(std::io::Error::other),
+            #[cfg(feature = "ws")]
+            Self::Ws(conn) => conn.recv().await,
+        }
+    }
+
+    async fn send(&mut self, msg: &[u8]) -> Result<(), Self::Error> {
+        match self {
+            Self::Native(conn) => conn.send(msg).await.map_err(std::io::Error::other),
+            #[cfg(feature = "ws")]
+            Self::Ws(conn) => conn.send(msg).await,
+        }
+    }
+
+    fn peer_addr(&self) -> std::io::Result<SocketAddr> {
+        match self {
+            Self::Native(conn) => conn.peer_addr(),
+            #[cfg(feature = "ws")]
+            Self::Ws(conn) => Ok(conn.peer_addr()),
+        }
+    }
+}
+
+/// A connection waiting in the admission queue.
+struct Waiting<C> {
+    conn: C,
+    addr: SocketAddr,
+    queued_at: Instant,
+}
+
 impl<H, T> Server<H, T>
 where
     H: MessageHandler + Send + Sync + 'static,
     T: Transport,
     T::Connection: Send + 'static,
 {
+    /// Returns the session manager tracking this server's sessions.
+    #[must_use]
+    pub fn sessions(&self) -> &Arc<SessionManager> {
+        &self.sessions
+    }
+
+    /// Returns the logical FIXP sessions, if the session layer is enabled.
+    #[must_use]
+    pub fn flow_registry(&self) -> Option<&Arc<FlowRegistry>> {
+        self.fixp.as_ref()
+    }
+
     /// Runs the server, accepting connections and processing messages.
     ///
     /// Uses the selected [`Transport`] backend to bind and accept connections.
@@ -266,7 +545,7 @@ where
             .bind_config
             .take()
             .unwrap_or_else(|| T::BindConfig::from(self.bind_addr));
-        let mut listener = T::bind_with(bind_config)
+        let listener = T::bind_with(bind_config)
             .await
             .map_err(|e| ServerError::Io(std::io::Error::other(e)))?;
         let effective_addr = listener.local_addr().unwrap_or(self.bind_addr);
@@ -276,10 +555,15 @@ where
         let _ = self
             .event_tx
             .try_send(ServerEvent::Listening(effective_addr));
+        let mut listeners = Listeners {
+            native: listener,
+            #[cfg(feature = "ws")]
+            ws: self.bind_ws().await?,
+        };
 
         loop {
             tokio::select! {
-                result = listener.accept() => {
+                result = listeners.accept() => {
                     match result {
                         Ok(conn) => {
                             let addr = conn.peer_addr().unwrap_or_else(
@@ -299,17 +583,89 @@ where
                             return Ok(());
                         }
                     }
+                    self.admit_waiting();
+                }
+
+                _ = sleep_until(self.next_queue_expiry()) => {
+                    self.admit_waiting();
                 }
             }
         }
     }
 
-    async fn handle_connection(&mut self, conn: T::Connection, addr: SocketAddr) {
-        if self.sessions.count() >= self.max_connections {
-            tracing::warn!("Max connections reached, rejecting {}", addr);
-            return;
+    /// Binds the WebSocket listener, if one was configured.
+    #[cfg(feature = "ws")]
+    async fn bind_ws(&mut self) -> Result<Option<WsListener>, ServerError> {
+        let Some(config) = self.ws_config.take() else {
+            return Ok(None);
+        };
+        let listener = WsListener::bind(config).await?;
+        let addr = listener.local_addr()?;
+        tracing::info!("Server listening for WebSocket clients on {}", addr);
+        let _ = self.event_tx.try_send(ServerEvent::ListeningWs(addr));
+        Ok(Some(listener))
+    }
+
+    async fn handle_connection(&mut self, conn: Inbound<T::Connection>, addr: SocketAddr) {
+        // Earlier arrivals still waiting go first.
+        if self.sessions.count() < self.max_connections && self.waiting.is_empty() {
+            self.admit(conn, addr);
+        } else if self.waiting.len() < self.admission.queue_capacity {
+            tracing::info!("Max connections reached, queueing {}", addr);
+            self.waiting.push_back(Waiting {
+                conn,
+                addr,
+                queued_at: Instant::now(),
+            });
+            let _ = self
+                .event_tx
+                .try_send(ServerEvent::ConnectionQueued(addr, self.waiting.len()));
+        } else {
+            self.reject(conn, addr, RejectReason::Full);
+        }
+    }
+
+    /// Returns when the oldest waiting connection times out, if any.
+    fn next_queue_expiry(&self) -> Option<Instant> {
+        self.waiting
+            .front()
+            .map(|w| w.queued_at + self.admission.queue_timeout)
+    }
+
+    /// Rejects waiting connections that timed out and admits the others
+    /// while slots are free.
+    fn admit_waiting(&mut self) {
+        let now = Instant::now();
+        while let Some(waiting) = self.waiting.front() {
+            if now.saturating_duration_since(waiting.queued_at) >= self.admission.queue_timeout {
+                let waiting = self.waiting.pop_front().expect("front exists");
+                self.reject(waiting.conn, waiting.addr, RejectReason::Timeout);
+            } else if self.sessions.count() < self.max_connections {
+                let waiting = self.waiting.pop_front().expect("front exists");
+                self.admit(waiting.conn, waiting.addr);
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Closes `conn`, sending it the busy message first if one is set.
+    fn reject(&self, conn: Inbound<T::Connection>, addr: SocketAddr, reason: RejectReason) {
+        tracing::warn!("Rejecting {}: {}", addr, reason);
+        let _ = self
+            .event_tx
+            .try_send(ServerEvent::ConnectionRejected(addr, reason));
+        if let Some(message) = self.admission.busy_message.clone() {
+            // Sent off the run loop so a slow peer cannot stall accepts.
+            let mut conn = conn;
+            tokio::spawn(async move {
+                let _ = tokio::time::timeout(BUSY_SEND_TIMEOUT, conn.send(&message)).await;
+            });
         }
+    }
 
+    /// Starts a session on `conn`.
+    fn admit(&mut self, conn: Inbound<T::Connection>, addr: SocketAddr) {
         let session_id = self.sessions.create_session(addr);
         let handler = Arc::clone(&self.handler);
         let event_tx = self.event_tx.clone();
@@ -332,11 +688,18 @@ where
         // `send_to` can find it) and also moved into the spawned
         // task's `SessionResponder`, which uses it as its fast-path
         // `send()` local sender.  See #40, #41.
-        let (out_tx, out_rx) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
+        let (out_tx, out_rx) = outbound::channel();
         self.session_senders
             .write()
             .insert(session_id, out_tx.clone());
         let senders = Arc::clone(&self.session_senders);
+        let expiry_policy = Arc::clone(&self.expiry_policy);
+        let outbound_capacity = self.outbound_capacity;
+        let tap = self.tap.clone();
+        let fixp = self
+            .fixp
+            .as_ref()
+            .map(|r| FixpSession::server(Arc::clone(r)));
 
         handler.on_session_start(session_id);
         let _ = event_tx.try_send(ServerEvent::SessionCreated(session_id, addr));
@@ -362,6 +725,11 @@ where
                     out_tx,
                     out_rx,
                     senders,
+                    outbound_capacity,
+                    expiry_policy.as_ref(),
+                    tap.as_deref(),
+                    fixp,
+                    &event_tx,
                 )
                 .await
                 {
@@ -371,7 +739,7 @@ where
                 tracing::info!("disconnected");
                 handler.on_session_end(session_id);
                 let _ = event_tx.try_send(ServerEvent::SessionClosed(session_id));
-                let _ = cmd_tx.try_send(ServerCommand::CloseSession(session_id));
+                let _ = cmd_tx.try_send_control(ServerCommand::CloseSession(session_id));
                 cmd_notify.notify_one();
             }
             .instrument(span),
@@ -389,6 +757,10 @@ where
                 self.shutdown_token.cancel();
                 self.session_tokens.clear();
                 self.session_senders.write().clear();
+                self.waiting.clear();
+                if let Err(e) = self.sessions.persist() {
+                    tracing::warn!(error = %e, "failed to persist session state on shutdown");
+                }
                 true
             }
             ServerCommand::CloseSession(session_id) => {
@@ -410,9 +782,10 @@ where
                 // exited but hasn't yet fired its own CloseSession
                 // cleanup back to the run loop) is opportunistically
                 // dropped from the registry via `retain`.  See #40.
+                let stamped = Stamped::now(message);
                 self.session_senders
                     .write()
-                    .retain(|_, sender| sender.send(message.clone()).is_ok());
+                    .retain(|_, sender| sender.send(stamped.clone()).is_ok());
                 false
             }
             ServerCommand::SendTo(session_id, message) => {
@@ -422,7 +795,7 @@ where
                 // the registry, mirroring `Broadcast`'s opportunistic cleanup.
                 let mut senders = self.session_senders.write();
                 if let Some(sender) = senders.get(&session_id)
-                    && sender.send(message).is_err()
+                    && sender.send(Stamped::now(message)).is_err()
                 {
                     senders.remove(&session_id);
                 }
@@ -432,9 +805,17 @@ where
     }
 }
 
+/// Sleeps until `deadline`, or forever if there is none.
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
 /// Handle for controlling the server from outside.
 pub struct ServerHandle {
-    cmd_tx: MpscSender<ServerCommand>,
+    cmd_tx: PrioritySender<ServerCommand>,
     event_rx: MpscReceiver<ServerEvent>,
     cmd_notify: Arc<Notify>,
 }
@@ -446,7 +827,7 @@ impl ServerHandle {
     /// the single-threaded `LocalServer` builder so both server flavours
     /// can hand back the same handle type.
     pub(crate) fn new(
-        cmd_tx: MpscSender<ServerCommand>,
+        cmd_tx: PrioritySender<ServerCommand>,
         event_rx: MpscReceiver<ServerEvent>,
         cmd_notify: Arc<Notify>,
     ) -> Self {
@@ -458,16 +839,21 @@ impl ServerHandle {
     }
 
     /// Requests server shutdown.
+    ///
+    /// Queued on the control lane, so it is handled before any pending
+    /// broadcasts or pushes.
     pub fn shutdown(&self) {
-        let _ = self.cmd_tx.try_send(ServerCommand::Shutdown);
+        let _ = self.cmd_tx.try_send_control(ServerCommand::Shutdown);
         self.cmd_notify.notify_one();
     }
 
     /// Closes a specific session.
+    ///
+    /// Queued on the control lane, like [`Self::shutdown`].
     pub fn close_session(&self, session_id: u64) {
         let _ = self
             .cmd_tx
-            .try_send(ServerCommand::CloseSession(session_id));
+            .try_send_control(ServerCommand::CloseSession(session_id));
         self.cmd_notify.notify_one();
     }
 
@@ -482,8 +868,8 @@ impl ServerHandle {
     /// Unlike [`Self::broadcast`], only the session identified by `session_id`
     /// receives the bytes. A non-existent or already-closed session is a benign
     /// no-op (the stale entry is reaped on the next attempt). Non-blocking: the
-    /// command is queued on the control channel and the run loop performs the
-    /// actual send.
+    /// command is queued on the data lane of the command channel and the run
+    /// loop performs the actual send.
     pub fn send_to(&self, session_id: u64, message: Vec<u8>) {
         let _ = self
             .cmd_tx
@@ -521,10 +907,24 @@ pub enum ServerEvent {
     /// connections.  Carries the *effective* local address (useful when
     /// the caller bound to port 0).
     Listening(SocketAddr),
+    /// The server has bound its WebSocket listener (feature `ws`).
+    /// Carries the effective local address.
+    ListeningWs(SocketAddr),
     /// A new session was created.
     SessionCreated(u64, SocketAddr),
     /// A session was closed.
     SessionClosed(u64),
+    /// A connection arrived at `max_connections` and waits for a slot.
+    /// Carries the peer address and the number of waiting connections.
+    ConnectionQueued(SocketAddr, usize),
+    /// A connection was closed without starting a session.
+    ConnectionRejected(SocketAddr, RejectReason),
+    /// An outbound message for a session outlived its TTL and was dropped
+    /// instead of sent.  Carries the session ID and the message bytes.
+    MessageExpired(u64, Vec<u8>),
+    /// The FIXP session on a connection changed state (see
+    /// [`ServerBuilder::fixp`]).  Carries the session ID and the event.
+    Fixp(u64, SessionEvent),
     /// An error occurred.
     Error(String),
 }
@@ -540,29 +940,44 @@ pub enum ServerEvent {
 ///   [`Server`], used by [`Responder::send_to`] to resolve the
 ///   target session against the live registry.  See #40, #41.
 struct SessionResponder {
-    tx: tokio_mpsc::UnboundedSender<Vec<u8>>,
+    tx: OutboundSender,
     senders: SessionSenderMap,
     session_id: u64,
+    /// Capacity handed to [`AsyncSessionResponder`]s of this session.
+    outbound_capacity: usize,
 }
 
 impl Responder for SessionResponder {
     fn send(&self, message: &[u8]) -> Result<(), SendError> {
-        self.tx.send(message.to_vec()).map_err(|_| SendError {
-            message: format!("session {} channel closed", self.session_id),
-        })
+        self.tx
+            .send(Stamped::now(message.to_vec()))
+            .map_err(|_| SendError {
+                message: format!("session {} channel closed", self.session_id),
+            })
     }
 
     fn send_to(&self, session_id: u64, message: &[u8]) -> Result<(), SendError> {
         let senders = self.senders.read();
         match senders.get(&session_id) {
-            Some(sender) => sender.send(message.to_vec()).map_err(|_| SendError {
-                message: format!("session {session_id} channel closed"),
-            }),
+            Some(sender) => sender
+                .send(Stamped::now(message.to_vec()))
+                .map_err(|_| SendError {
+                    message: format!("session {session_id} channel closed"),
+                }),
             None => Err(SendError {
                 message: format!("unknown session {session_id}"),
             }),
         }
     }
+
+    fn async_responder(&self) -> Option<AsyncSessionResponder> {
+        Some(AsyncSessionResponder::new(
+            self.tx.clone(),
+            Arc::clone(&self.senders),
+            self.session_id,
+            self.outbound_capacity,
+        ))
+    }
 }
 
 /// Handles a single client session over a transport [`Connection`].
@@ -580,14 +995,29 @@ impl Responder for SessionResponder {
 /// spawn.  `senders` is a clone of that shared map, handed into the
 /// [`SessionResponder`] so cross-session `send_to` and
 /// `ServerCommand::Broadcast` can find live sessions.  See #40, #41.
+///
+/// Outbound messages that outlived `expiry_policy` by the time they are
+/// dequeued are dropped and reported on `event_tx` as
+/// [`ServerEvent::MessageExpired`].
+///
+/// With `fixp` set, every frame goes through the FIXP session first: only
+/// application messages reach the handler, outbound messages are
+/// sequenced, and the session's own messages and heartbeats are sent
+/// alongside. The connection ends when the session terminates.
+#[allow(clippy::too_many_arguments)]
 async fn handle_session<H, C>(
     session_id: u64,
     mut conn: C,
     handler: &H,
     session_token: CancellationToken,
-    out_tx: tokio_mpsc::UnboundedSender<Vec<u8>>,
-    mut out_rx: tokio_mpsc::UnboundedReceiver<Vec<u8>>,
+    out_tx: OutboundSender,
+    mut out_rx: OutboundReceiver,
     senders: SessionSenderMap,
+    outbound_capacity: usize,
+    expiry_policy: &ExpiryPolicy,
+    tap: Option<&MessageTap>,
+    mut fixp: Option<FixpSession>,
+    event_tx: &MpscSender<ServerEvent>,
 ) -> Result<(), std::io::Error>
 where
     H: MessageHandler,
@@ -597,7 +1027,11 @@ where
         tx: out_tx,
         senders,
         session_id,
+        outbound_capacity,
     };
+    if let Some(session) = &mut fixp {
+        session.start(Instant::now());
+    }
 
     loop {
         tokio::select! {
@@ -605,10 +1039,26 @@ where
             result = conn.recv() => {
                 match result {
                     Ok(Some(data)) => {
+                        if let Some(tap) = tap {
+                            tap.observe(TapDirection::Inbound, session_id, data.as_ref());
+                        }
+                        if let Some(session) = &mut fixp {
+                            let result = session.on_frame(&data, Instant::now());
+                            flush_fixp(&mut conn, session, session_id, tap, event_tx).await?;
+                            match result {
+                                Ok(Some(_)) => {}
+                                Ok(None) if session.is_terminated() => return Ok(()),
+                                Ok(None) => continue,
+                                Err(e) => {
+                                    handler.on_error(session_id, &e.to_string());
+                                    return Err(std::io::Error::other(e));
+                                }
+                            }
+                        }
                         // Decode header and dispatch to handler
                         if data.len() >= MessageHeader::ENCODED_LENGTH {
                             let header = MessageHeader::wrap(data.as_ref(), 0);
-                            handler.on_message(session_id, &header, data.as_ref(), &responder);
+                            handler.on_frame(session_id, &header, OwnedFrame::from(data), &responder);
                         } else {
                             handler.on_error(session_id, "Message too short for header");
                         }
@@ -631,11 +1081,36 @@ where
             // once we enter this arm we are committed until the inner
             // `await` resolves.
             Some(msg) = out_rx.recv() => {
+                if expiry_policy.is_expired(
+                    MessageHeader::try_wrap(&msg.item, 0).map(|h| h.template_id),
+                    msg.queued_at,
+                    Instant::now(),
+                ) {
+                    tracing::warn!(age = ?msg.age(Instant::now()), "dropping expired message");
+                    let _ = event_tx.try_send(ServerEvent::MessageExpired(session_id, msg.item));
+                    continue;
+                }
+                if let Some(session) = &mut fixp
+                    && let Err(e) = session.send(&msg.item, Instant::now())
+                {
+                    tracing::warn!(error = %e, "dropping outbound message");
+                    continue;
+                }
+                let send = async {
+                    if let Some(session) = &mut fixp {
+                        return flush_fixp(&mut conn, session, session_id, tap, event_tx).await;
+                    }
+                    conn.send(&msg.item).await.map_err(std::io::Error::other)?;
+                    if let Some(tap) = tap {
+                        tap.observe(TapDirection::Outbound, session_id, &msg.item);
+                    }
+                    Ok(())
+                };
                 tokio::select! {
-                    send_result = conn.send(&msg) => {
+                    send_result = send => {
                         if let Err(e) = send_result {
                             tracing::error!(error = %e, "write error");
-                            return Err(std::io::Error::other(e));
+                            return Err(e);
                         }
                     }
                     _ = session_token.cancelled() => {
@@ -645,6 +1120,17 @@ where
                 }
             }
 
+            // FIXP heartbeats and keepalive lapse.
+            _ = sleep_until(fixp.as_ref().and_then(FixpSession::next_timeout)) => {
+                if let Some(session) = &mut fixp {
+                    session.on_timer(Instant::now());
+                    flush_fixp(&mut conn, session, session_id, tap, event_tx).await?;
+                    if session.is_terminated() {
+                        return Ok(());
+                    }
+                }
+            }
+
             // Cooperative cancellation from the run loop. Cleanup
             // (on_session_end + ServerEvent::SessionClosed) runs in
             // the spawned task closure once we return.
@@ -656,6 +1142,27 @@ where
     }
 }
 
+/// Sends the frames a FIXP session queued and reports its events.
+async fn flush_fixp<C: Connection>(
+    conn: &mut C,
+    session: &mut FixpSession,
+    session_id: u64,
+    tap: Option<&MessageTap>,
+    event_tx: &MpscSender<ServerEvent>,
+) -> Result<(), std::io::Error> {
+    while let Some(frame) = session.poll_transmit() {
+        conn.send(&frame).await.map_err(std::io::Error::other)?;
+        if let Some(tap) = tap {
+            tap.observe(TapDirection::Outbound, session_id, &frame);
+        }
+    }
+    while let Some(event) = session.poll_event() {
+        tracing::debug!(?event, "fixp session event");
+        let _ = event_tx.try_send(ServerEvent::Fixp(session_id, event));
+    }
+    Ok(())
+}
+
 #[cfg(all(test, feature = "tcp-tokio"))]
 mod tests {
     use super::*;
@@ -700,6 +1207,13 @@ mod tests {
         let _ = builder;
     }
 
+    #[test]
+    fn test_server_builder_expiry_policy() {
+        let policy = ExpiryPolicy::new().default_ttl(std::time::Duration::from_millis(5));
+        let builder = DefaultBuilder::<TestHandler>::new().expiry_policy(policy);
+        assert!(builder.expiry_policy.is_enabled());
+    }
+
     #[test]
     fn test_server_builder_max_connections() {
         let builder = DefaultBuilder::<TestHandler>::new().max_connections(500);
@@ -777,6 +1291,26 @@ mod tests {
         handle.broadcast(vec![1, 2, 3]);
     }
 
+    /// Shutdown and session close travel on the control lane, so the run
+    /// loop sees them ahead of a broadcast/push backlog.
+    #[test]
+    fn test_control_commands_bypass_data_backlog() {
+        let (server, handle) = DefaultBuilder::<TestHandler>::new()
+            .handler(TestHandler)
+            .build();
+
+        handle.broadcast(vec![1]);
+        handle.send_to(1, vec![2]);
+        handle.close_session(7);
+        handle.shutdown();
+
+        let order: Vec<_> = server.cmd_rx.drain().collect();
+        assert!(matches!(order[0], ServerCommand::CloseSession(7)));
+        assert!(matches!(order[1], ServerCommand::Shutdown));
+        assert!(matches!(order[2], ServerCommand::Broadcast(_)));
+        assert!(matches!(order[3], ServerCommand::SendTo(1, _)));
+    }
+
     /// `Server` is built with a fresh, uncancelled parent token and an
     /// empty session-token registry.  See #42.
     #[test]
@@ -829,6 +1363,29 @@ mod tests {
         );
     }
 
+    /// A shared, persistent session manager must be saved on Shutdown so
+    /// a restarted server can offer resumption of every session.
+    #[tokio::test]
+    async fn test_shutdown_persists_shared_session_manager() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.state");
+        let sessions =
+            Arc::new(SessionManager::with_store(crate::FileSessionStore::new(&path)).unwrap());
+        let (mut server, _handle) = DefaultBuilder::<TestHandler>::new()
+            .handler(TestHandler)
+            .session_manager(Arc::clone(&sessions))
+            .build();
+        assert!(Arc::ptr_eq(server.sessions(), &sessions));
+
+        let id = sessions.create_session("127.0.0.1:1234".parse().unwrap());
+        sessions.next_outbound_seq(id);
+        assert!(server.handle_command(ServerCommand::Shutdown).await);
+
+        let restarted = SessionManager::with_store(crate::FileSessionStore::new(&path)).unwrap();
+        let state = restarted.resumable_state(id).unwrap();
+        assert_eq!(state.next_outbound_seq, 2);
+    }
+
     /// `CloseSession(id)` must cancel exactly one child token and
     /// leave its siblings live.  This is the contract that
     /// `ServerHandle::close_session` exposes — without it the targeted
@@ -907,8 +1464,8 @@ mod tests {
             .handler(TestHandler)
             .build();
 
-        let (tx1, mut rx1) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
-        let (tx2, mut rx2) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
+        let (tx1, mut rx1) = outbound::channel();
+        let (tx2, mut rx2) = outbound::channel();
         {
             let mut senders = server.session_senders.write();
             senders.insert(1, tx1);
@@ -922,11 +1479,11 @@ mod tests {
 
         assert!(!exited);
         match rx1.try_recv() {
-            Ok(bytes) => assert_eq!(bytes, payload),
+            Ok(msg) => assert_eq!(msg.item, payload),
             other => panic!("session 1 did not receive broadcast: {other:?}"),
         }
         match rx2.try_recv() {
-            Ok(bytes) => assert_eq!(bytes, payload),
+            Ok(msg) => assert_eq!(msg.item, payload),
             other => panic!("session 2 did not receive broadcast: {other:?}"),
         }
         // Both entries must still be live — their channels are
@@ -942,8 +1499,8 @@ mod tests {
             .handler(TestHandler)
             .build();
 
-        let (tx1, mut rx1) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
-        let (tx2, mut rx2) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
+        let (tx1, mut rx1) = outbound::channel();
+        let (tx2, mut rx2) = outbound::channel();
         {
             let mut senders = server.session_senders.write();
             senders.insert(1, tx1);
@@ -957,7 +1514,7 @@ mod tests {
 
         assert!(!exited);
         match rx2.try_recv() {
-            Ok(bytes) => assert_eq!(bytes, payload),
+            Ok(msg) => assert_eq!(msg.item, payload),
             other => panic!("target session 2 did not receive the unicast: {other:?}"),
         }
         assert!(
@@ -976,7 +1533,7 @@ mod tests {
             .handler(TestHandler)
             .build();
 
-        let (tx1, _rx1) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
+        let (tx1, _rx1) = outbound::channel();
         server.session_senders.write().insert(1, tx1);
 
         let exited = server
@@ -996,8 +1553,8 @@ mod tests {
             .handler(TestHandler)
             .build();
 
-        let (tx_live, mut rx_live) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
-        let (tx_dead, rx_dead) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
+        let (tx_live, mut rx_live) = outbound::channel();
+        let (tx_dead, rx_dead) = outbound::channel();
         drop(rx_dead); // simulate a gone-away session
         {
             let mut senders = server.session_senders.write();
@@ -1012,7 +1569,7 @@ mod tests {
         // The live entry must have received the message and must
         // still be in the registry; the dead entry must be gone.
         match rx_live.try_recv() {
-            Ok(bytes) => assert_eq!(bytes, b"ping"),
+            Ok(msg) => assert_eq!(msg.item, b"ping"),
             other => panic!("live session did not receive broadcast: {other:?}"),
         }
         let senders = server.session_senders.read();
@@ -1030,8 +1587,8 @@ mod tests {
             .handler(TestHandler)
             .build();
 
-        let (tx1, _rx1) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
-        let (tx2, _rx2) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
+        let (tx1, _rx1) = outbound::channel();
+        let (tx2, _rx2) = outbound::channel();
         {
             let mut senders = server.session_senders.write();
             senders.insert(1, tx1);
@@ -1051,11 +1608,12 @@ mod tests {
     #[test]
     fn test_session_responder_send_to_unknown_session_returns_err() {
         let senders: SessionSenderMap = Arc::new(RwLock::new(HashMap::new()));
-        let (tx, _rx) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
+        let (tx, _rx) = outbound::channel();
         let responder = SessionResponder {
             tx,
             senders,
             session_id: 1,
+            outbound_capacity: DEFAULT_OUTBOUND_CAPACITY,
         };
 
         let result = responder.send_to(99, b"payload");
@@ -1073,8 +1631,8 @@ mod tests {
     #[test]
     fn test_session_responder_send_to_routes_to_target() {
         let senders: SessionSenderMap = Arc::new(RwLock::new(HashMap::new()));
-        let (tx_self, mut rx_self) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
-        let (tx_other, mut rx_other) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
+        let (tx_self, mut rx_self) = outbound::channel();
+        let (tx_other, mut rx_other) = outbound::channel();
         senders.write().insert(1, tx_self.clone());
         senders.write().insert(2, tx_other);
 
@@ -1082,13 +1640,14 @@ mod tests {
             tx: tx_self,
             senders,
             session_id: 1,
+            outbound_capacity: DEFAULT_OUTBOUND_CAPACITY,
         };
 
         let result = responder.send_to(2, b"cross-routed");
         assert!(result.is_ok(), "send_to should succeed for a live target");
 
         match rx_other.try_recv() {
-            Ok(bytes) => assert_eq!(bytes, b"cross-routed"),
+            Ok(msg) => assert_eq!(msg.item, b"cross-routed"),
             other => panic!("target session did not receive payload: {other:?}"),
         }
         // The responder's own channel must NOT have received the
@@ -1105,8 +1664,8 @@ mod tests {
     #[test]
     fn test_session_responder_send_to_closed_channel_returns_err() {
         let senders: SessionSenderMap = Arc::new(RwLock::new(HashMap::new()));
-        let (tx_self, _rx_self) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
-        let (tx_dead, rx_dead) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
+        let (tx_self, _rx_self) = outbound::channel();
+        let (tx_dead, rx_dead) = outbound::channel();
         drop(rx_dead);
         senders.write().insert(1, tx_self.clone());
         senders.write().insert(2, tx_dead);
@@ -1115,6 +1674,7 @@ mod tests {
             tx: tx_self,
             senders,
             session_id: 1,
+            outbound_capacity: DEFAULT_OUTBOUND_CAPACITY,
         };
 
         let result = responder.send_to(2, b"lost");
@@ -1126,4 +1686,114 @@ mod tests {
             Ok(()) => panic!("send_to on closed channel must fail"),
         }
     }
+
+    /// Connection whose reads never complete and whose writes are
+    /// recorded, so only the outbound path of `handle_session` runs.
+    struct RecordingConnection {
+        sent: Arc<parking_lot::Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl Connection for RecordingConnection {
+        type Error = std::io::Error;
+
+        async fn recv(&mut self) -> Result<Option<bytes::BytesMut>, Self::Error> {
+            std::future::pending().await
+        }
+
+        async fn send(&mut self, msg: &[u8]) -> Result<(), Self::Error> {
+            self.sent.lock().push(msg.to_vec());
+            Ok(())
+        }
+
+        fn peer_addr(&self) -> std::io::Result<SocketAddr> {
+            Ok("127.0.0.1:0".parse().unwrap())
+        }
+    }
+
+    fn message_with_template(template_id: u16) -> Vec<u8> {
+        let mut buf = vec![0u8; MessageHeader::ENCODED_LENGTH];
+        MessageHeader::new(0, template_id, 1, 1).encode(&mut buf, 0);
+        buf
+    }
+
+    /// Outbound messages that outlived their template's TTL are dropped
+    /// and surfaced as `MessageExpired`; fresh ones are still sent.
+    #[tokio::test]
+    async fn test_handle_session_drops_expired_messages() {
+        use ironsbe_core::tap::TapFilter;
+
+        let sent = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let conn = RecordingConnection {
+            sent: Arc::clone(&sent),
+        };
+        let policy = ExpiryPolicy::new().template_ttl(7, std::time::Duration::from_millis(10));
+        let (event_tx, event_rx) = MpscChannel::bounded(16);
+        let (out_tx, out_rx) = outbound::channel();
+        let token = CancellationToken::new();
+
+        let stale = message_with_template(7);
+        let stale_at = Instant::now() - std::time::Duration::from_secs(1);
+        out_tx
+            .send(Stamped {
+                item: stale.clone(),
+                queued_at: stale_at,
+            })
+            .unwrap();
+        // Same age, but template 8 has no TTL.
+        out_tx
+            .send(Stamped {
+                item: message_with_template(8),
+                queued_at: stale_at,
+            })
+            .unwrap();
+        out_tx.send(Stamped::now(message_with_template(7))).unwrap();
+
+        let tap = MessageTap::new();
+        let (tap_tx, tap_rx) = std::sync::mpsc::channel();
+        tap.attach(TapFilter::new(), tap_tx);
+
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            cancel.cancel();
+        });
+        handle_session(
+            1,
+            conn,
+            &TestHandler,
+            token,
+            out_tx,
+            out_rx,
+            Arc::new(RwLock::new(HashMap::new())),
+            DEFAULT_OUTBOUND_CAPACITY,
+            &policy,
+            Some(&tap),
+            None,
+            &event_tx,
+        )
+        .await
+        .unwrap();
+
+        let sent = sent.lock();
+        assert_eq!(
+            *sent,
+            vec![message_with_template(8), message_with_template(7)]
+        );
+        // The tap sees what was actually sent, not the expired message.
+        let tapped: Vec<_> = tap_rx
+            .try_iter()
+            .map(|r| (r.direction, r.stream, r.template_id))
+            .collect();
+        assert_eq!(
+            tapped,
+            vec![
+                (TapDirection::Outbound, 1, Some(8)),
+                (TapDirection::Outbound, 1, Some(7))
+            ]
+        );
+        match event_rx.try_recv() {
+            Some(ServerEvent::MessageExpired(1, bytes)) => assert_eq!(bytes, stale),
+            other => panic!("expected MessageExpired, got {other:?}"),
+        }
+    }
 }
@@ -0,0 +1,355 @@
+//! Inbound deduplication by business key.
+//!
+//! Retransmission and failover paths can deliver the same business message
+//! twice, sometimes on different sessions: a client that reconnects after
+//! a timeout resends orders the server already processed. A
+//! [`DedupHandler`] sits in front of the real handler, extracts a key from
+//! each message (e.g. `clOrdId` plus a sequence number) and drops messages
+//! whose key was already seen within a bounded window.
+
+use crate::handler::{MessageHandler, Responder};
+use bytes::Bytes;
+use ironsbe_core::frame::OwnedFrame;
+use ironsbe_core::header::MessageHeader;
+use parking_lot::Mutex;
+use std::collections::{HashSet, VecDeque};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Default time a key is remembered.
+pub const DEFAULT_DEDUP_WINDOW: Duration = Duration::from_secs(60);
+
+/// Default maximum number of remembered keys.
+pub const DEFAULT_DEDUP_CAPACITY: usize = 100_000;
+
+/// Bounds of the deduplication window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DedupConfig {
+    /// How long a key is remembered after it was first seen.
+    pub window: Duration,
+    /// Maximum number of remembered keys; the oldest are forgotten first.
+    pub capacity: usize,
+}
+
+impl DedupConfig {
+    /// Creates a config with the default window and capacity.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how long a key is remembered.
+    #[must_use]
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the maximum number of remembered keys.
+    #[must_use]
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_DEDUP_WINDOW,
+            capacity: DEFAULT_DEDUP_CAPACITY,
+        }
+    }
+}
+
+/// Set of recently seen keys, bounded in time and size.
+///
+/// A key stays in the set for [`DedupConfig::window`] after it was first
+/// seen; seeing it again does not extend its lifetime. When the set is
+/// full, the oldest key is forgotten to make room.
+#[derive(Debug)]
+pub struct DedupFilter<K> {
+    config: DedupConfig,
+    seen: HashSet<K>,
+    /// Keys in the order they were first seen.
+    order: VecDeque<(K, Instant)>,
+}
+
+impl<K: Hash + Eq + Clone> DedupFilter<K> {
+    /// Creates an empty filter.
+    #[must_use]
+    pub fn new(config: DedupConfig) -> Self {
+        Self {
+            config,
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Records `key` now; returns false if it is a duplicate.
+    pub fn insert(&mut self, key: K) -> bool {
+        self.insert_at(key, Instant::now())
+    }
+
+    /// Records `key` as seen at `now`; returns false if it was already
+    /// seen within the window.
+    pub fn insert_at(&mut self, key: K, now: Instant) -> bool {
+        self.expire(now);
+        if self.seen.contains(&key) {
+            return false;
+        }
+        if self.config.capacity == 0 {
+            return true;
+        }
+        while self.order.len() >= self.config.capacity {
+            self.forget_oldest();
+        }
+        self.seen.insert(key.clone());
+        self.order.push_back((key, now));
+        true
+    }
+
+    /// Returns true if `key` is remembered.
+    #[must_use]
+    pub fn contains(&self, key: &K) -> bool {
+        self.seen.contains(key)
+    }
+
+    /// Returns the number of remembered keys.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Returns true if no key is remembered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Forgets every key.
+    pub fn clear(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+    }
+
+    /// Forgets keys first seen more than a window before `now`.
+    fn expire(&mut self, now: Instant) {
+        while let Some((_, first_seen)) = self.order.front() {
+            if now.saturating_duration_since(*first_seen) < self.config.window {
+                break;
+            }
+            self.forget_oldest();
+        }
+    }
+
+    fn forget_oldest(&mut self) {
+        if let Some((key, _)) = self.order.pop_front() {
+            self.seen.remove(&key);
+        }
+    }
+}
+
+/// Handler that drops duplicate messages before they reach `inner`.
+///
+/// `extract` returns the business key of a message, or `None` for messages
+/// that are never deduplicated (heartbeats, requests without an ID). Keys
+/// are shared across sessions so a message resent on a new connection
+/// after failover is caught too; include the session ID in the key to
+/// deduplicate per session instead.
+///
+/// Duplicates are logged at debug level and counted in
+/// [`duplicates`](Self::duplicates).
+pub struct DedupHandler<H, F, K> {
+    inner: H,
+    extract: F,
+    filter: Mutex<DedupFilter<K>>,
+    duplicates: AtomicU64,
+}
+
+impl<H, F, K> DedupHandler<H, F, K>
+where
+    H: MessageHandler,
+    F: Fn(u64, &MessageHeader, &[u8]) -> Option<K> + Send + Sync,
+    K: Hash + Eq + Clone + Send,
+{
+    /// Wraps `inner`, deduplicating with the default window.
+    #[must_use]
+    pub fn new(inner: H, extract: F) -> Self {
+        Self::with_config(inner, extract, DedupConfig::default())
+    }
+
+    /// Wraps `inner`, deduplicating within the bounds of `config`.
+    #[must_use]
+    pub fn with_config(inner: H, extract: F, config: DedupConfig) -> Self {
+        Self {
+            inner,
+            extract,
+            filter: Mutex::new(DedupFilter::new(config)),
+            duplicates: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the wrapped handler.
+    #[must_use]
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+
+    /// Returns the number of duplicates dropped so far.
+    #[must_use]
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates.load(Ordering::Relaxed)
+    }
+
+    /// Forgets every remembered key, e.g. after a trading-day rollover.
+    pub fn reset(&self) {
+        self.filter.lock().clear();
+    }
+
+    /// Records the message's key and returns true if it was already seen.
+    fn is_duplicate(&self, session_id: u64, header: &MessageHeader, buffer: &[u8]) -> bool {
+        let Some(key) = (self.extract)(session_id, header, buffer) else {
+            return false;
+        };
+        if self.filter.lock().insert(key) {
+            return false;
+        }
+        self.duplicates.fetch_add(1, Ordering::Relaxed);
+        let template_id = header.template_id;
+        tracing::debug!(session_id, template_id, "dropping duplicate message");
+        true
+    }
+}
+
+impl<H, F, K> MessageHandler for DedupHandler<H, F, K>
+where
+    H: MessageHandler,
+    F: Fn(u64, &MessageHeader, &[u8]) -> Option<K> + Send + Sync,
+    K: Hash + Eq + Clone + Send,
+{
+    fn on_message(
+        &self,
+        session_id: u64,
+        header: &MessageHeader,
+        buffer: &[u8],
+        responder: &dyn Responder,
+    ) {
+        if !self.is_duplicate(session_id, header, buffer) {
+            self.inner.on_message(session_id, header, buffer, responder);
+        }
+    }
+
+    fn on_frame(
+        &self,
+        session_id: u64,
+        header: &MessageHeader,
+        frame: OwnedFrame<Bytes>,
+        responder: &dyn Responder,
+    ) {
+        if !self.is_duplicate(session_id, header, &frame) {
+            self.inner.on_frame(session_id, header, frame, responder);
+        }
+    }
+
+    fn on_session_start(&self, session_id: u64) {
+        self.inner.on_session_start(session_id);
+    }
+
+    fn on_session_end(&self, session_id: u64) {
+        self.inner.on_session_end(session_id);
+    }
+
+    fn on_error(&self, session_id: u64, error: &str) {
+        self.inner.on_error(session_id, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::SendError;
+    use std::sync::Arc;
+
+    struct NullResponder;
+
+    impl Responder for NullResponder {
+        fn send(&self, _message: &[u8]) -> Result<(), SendError> {
+            Ok(())
+        }
+
+        fn send_to(&self, _session_id: u64, _message: &[u8]) -> Result<(), SendError> {
+            Ok(())
+        }
+    }
+
+    /// Records the key byte of every message it receives.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<u8>>>);
+
+    impl MessageHandler for Recorder {
+        fn on_message(
+            &self,
+            _session_id: u64,
+            _header: &MessageHeader,
+            buffer: &[u8],
+            _responder: &dyn Responder,
+        ) {
+            self.0.lock().push(buffer[MessageHeader::ENCODED_LENGTH]);
+        }
+    }
+
+    fn message(template_id: u16, key: u8) -> (MessageHeader, Vec<u8>) {
+        let header = MessageHeader::new(1, template_id, 1, 1);
+        let mut buffer = vec![0u8; MessageHeader::ENCODED_LENGTH + 1];
+        header.encode(&mut buffer, 0);
+        buffer[MessageHeader::ENCODED_LENGTH] = key;
+        (header, buffer)
+    }
+
+    #[test]
+    fn test_filter_window_and_capacity() {
+        let config = DedupConfig::new()
+            .window(Duration::from_secs(10))
+            .capacity(2);
+        let mut filter = DedupFilter::new(config);
+        let t0 = Instant::now();
+
+        assert!(filter.insert_at(1, t0));
+        assert!(!filter.insert_at(1, t0 + Duration::from_secs(5)));
+        // Seeing a key again does not extend its window.
+        assert!(filter.insert_at(1, t0 + Duration::from_secs(10)));
+
+        // The oldest key makes room for a new one.
+        assert!(filter.insert_at(2, t0 + Duration::from_secs(11)));
+        assert!(filter.insert_at(3, t0 + Duration::from_secs(12)));
+        assert_eq!(filter.len(), 2);
+        assert!(!filter.contains(&1));
+        assert!(!filter.insert_at(3, t0 + Duration::from_secs(13)));
+    }
+
+    #[test]
+    fn test_handler_drops_duplicates_across_sessions() {
+        let recorder = Recorder::default();
+        // Template 0 is a heartbeat without a business key.
+        let handler = DedupHandler::new(recorder.clone(), |_, header: &MessageHeader, buffer| {
+            (header.template_id != 0).then(|| buffer[MessageHeader::ENCODED_LENGTH])
+        });
+
+        for (session_id, template_id, key) in
+            [(1, 1, 7), (2, 1, 7), (1, 1, 8), (1, 0, 9), (2, 0, 9)]
+        {
+            let (header, buffer) = message(template_id, key);
+            handler.on_message(session_id, &header, &buffer, &NullResponder);
+        }
+
+        assert_eq!(*recorder.0.lock(), vec![7, 8, 9, 9]);
+        assert_eq!(handler.duplicates(), 1);
+
+        handler.reset();
+        let (header, buffer) = message(1, 7);
+        handler.on_message(3, &header, &buffer, &NullResponder);
+        assert_eq!(recorder.0.lock().last(), Some(&7));
+    }
+}
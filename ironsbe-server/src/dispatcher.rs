@@ -1,14 +1,27 @@
 //! Message dispatcher for routing messages to handlers.
 
-use crate::handler::{MessageHandler, Responder, TypedHandler};
+use crate::handler::{
+    Delivery, MessageHandler, MessageStorage, OwnedHandler, OwnedMessage, Responder, TypedHandler,
+};
+use crate::snapshot::{SnapshotHandler, SnapshotProvider};
+use bytes::Bytes;
+use ironsbe_core::frame::OwnedFrame;
 use ironsbe_core::header::MessageHeader;
+use ironsbe_core::pool::TieredBufferPool;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+/// A registered handler and how it receives messages.
+enum Route {
+    Borrowed(Arc<dyn TypedHandler>),
+    Owned(Arc<dyn OwnedHandler>),
+}
+
 /// Dispatcher that routes messages to specific handlers based on template ID.
 pub struct MessageDispatcher {
-    handlers: HashMap<u16, Arc<dyn TypedHandler>>,
+    handlers: HashMap<u16, Route>,
     default_handler: Option<Arc<dyn MessageHandler>>,
+    pool: Option<TieredBufferPool>,
 }
 
 impl MessageDispatcher {
@@ -18,12 +31,76 @@ impl MessageDispatcher {
         Self {
             handlers: HashMap::new(),
             default_handler: None,
+            pool: None,
         }
     }
 
     /// Registers a handler for a specific template ID.
+    ///
+    /// The handler borrows each message for the duration of the callback
+    /// ([`Delivery::Borrowed`]).
     pub fn register<H: TypedHandler + 'static>(&mut self, template_id: u16, handler: H) {
-        self.handlers.insert(template_id, Arc::new(handler));
+        self.handlers
+            .insert(template_id, Route::Borrowed(Arc::new(handler)));
+    }
+
+    /// Registers a handler that receives messages it may keep
+    /// ([`Delivery::Owned`]).
+    ///
+    /// Replaces any handler registered for `template_id`, so a template
+    /// can be switched between delivery modes at runtime.
+    pub fn register_owned<H: OwnedHandler + 'static>(&mut self, template_id: u16, handler: H) {
+        self.handlers
+            .insert(template_id, Route::Owned(Arc::new(handler)));
+    }
+
+    /// Sets the pool that messages are copied into for owned delivery when
+    /// the transport's buffer cannot be shared.
+    ///
+    /// Without a pool, or when the pool has no fitting buffer, the message
+    /// is copied into a fresh allocation.
+    pub fn set_buffer_pool(&mut self, pool: TieredBufferPool) {
+        self.pool = Some(pool);
+    }
+
+    /// Returns how the handler for `template_id` receives messages, or
+    /// `None` if none is registered.
+    #[must_use]
+    pub fn delivery(&self, template_id: u16) -> Option<Delivery> {
+        self.handlers.get(&template_id).map(|route| match route {
+            Route::Borrowed(_) => Delivery::Borrowed,
+            Route::Owned(_) => Delivery::Owned,
+        })
+    }
+
+    /// Copies `buffer` into an owned message, pooled when possible.
+    fn to_owned_message(&self, buffer: &[u8]) -> OwnedMessage {
+        let storage = match self
+            .pool
+            .as_ref()
+            .and_then(|pool| pool.acquire(buffer.len()))
+        {
+            Some(mut pooled) => {
+                pooled.writable()[..buffer.len()].copy_from_slice(buffer);
+                pooled.set_len(buffer.len());
+                MessageStorage::Pooled(pooled)
+            }
+            None => MessageStorage::Shared(Bytes::copy_from_slice(buffer)),
+        };
+        OwnedFrame::whole(storage)
+    }
+
+    /// Registers a snapshot provider for a snapshot-request template ID.
+    ///
+    /// Uses the default chunking of [`SnapshotHandler::new`]; register a
+    /// configured [`SnapshotHandler`] through [`register`](Self::register)
+    /// to change it.
+    pub fn register_snapshot<P: SnapshotProvider + 'static>(
+        &mut self,
+        template_id: u16,
+        provider: P,
+    ) {
+        self.register(template_id, SnapshotHandler::new(provider));
     }
 
     /// Sets the default handler for unregistered template IDs.
@@ -53,8 +130,13 @@ impl MessageHandler for MessageDispatcher {
         responder: &dyn Responder,
     ) {
         let template_id = { header.template_id };
-        if let Some(handler) = self.handlers.get(&template_id) {
-            handler.handle(session_id, buffer, responder);
+        if let Some(route) = self.handlers.get(&template_id) {
+            match route {
+                Route::Borrowed(handler) => handler.handle(session_id, buffer, responder),
+                Route::Owned(handler) => {
+                    handler.handle_owned(session_id, self.to_owned_message(buffer), responder);
+                }
+            }
         } else if let Some(default) = &self.default_handler {
             default.on_message(session_id, header, buffer, responder);
         } else {
@@ -66,6 +148,31 @@ impl MessageHandler for MessageDispatcher {
         }
     }
 
+    fn on_frame(
+        &self,
+        session_id: u64,
+        header: &MessageHeader,
+        frame: OwnedFrame<Bytes>,
+        responder: &dyn Responder,
+    ) {
+        // Owned handlers and the default handler keep the frame; typed
+        // handlers take a slice.
+        match (
+            self.handlers.get(&{ header.template_id }),
+            &self.default_handler,
+        ) {
+            (Some(Route::Owned(handler)), _) => {
+                let (offset, len) = (frame.offset(), frame.len());
+                let storage = MessageStorage::Shared(frame.into_inner());
+                let message = OwnedFrame::new(storage, offset, len)
+                    .expect("frame bounds fit the buffer they came from");
+                handler.handle_owned(session_id, message, responder);
+            }
+            (None, Some(default)) => default.on_frame(session_id, header, frame, responder),
+            _ => self.on_message(session_id, header, &frame, responder),
+        }
+    }
+
     fn on_session_start(&self, session_id: u64) {
         if let Some(default) = &self.default_handler {
             default.on_session_start(session_id);
@@ -145,6 +252,102 @@ mod tests {
         assert!(called.load(Ordering::SeqCst));
     }
 
+    #[test]
+    fn test_dispatcher_register_snapshot() {
+        use crate::snapshot::SnapshotChunk;
+        use parking_lot::Mutex;
+
+        struct CountProvider;
+
+        impl SnapshotProvider for CountProvider {
+            type Snapshot = usize;
+
+            fn snapshot(&self, _session_id: u64, _request: &[u8]) -> Option<usize> {
+                Some(250)
+            }
+
+            fn entry_count(&self, snapshot: &usize) -> usize {
+                *snapshot
+            }
+
+            fn encode_chunk(&self, _: &usize, chunk: &SnapshotChunk, buffer: &mut [u8]) -> usize {
+                buffer[0] = chunk.entries.len() as u8;
+                1
+            }
+        }
+
+        struct RecordingResponder(Mutex<Vec<u8>>);
+
+        impl Responder for RecordingResponder {
+            fn send(&self, message: &[u8]) -> Result<(), SendError> {
+                self.0.lock().push(message[0]);
+                Ok(())
+            }
+
+            fn send_to(&self, _session_id: u64, message: &[u8]) -> Result<(), SendError> {
+                self.send(message)
+            }
+        }
+
+        let mut dispatcher = MessageDispatcher::new();
+        dispatcher.register_snapshot(7, CountProvider);
+        assert!(dispatcher.has_handler(7));
+
+        let header = MessageHeader::new(0, 7, 100, 1);
+        let responder = RecordingResponder(Mutex::new(Vec::new()));
+        dispatcher.on_message(1, &header, &[0u8; 8], &responder);
+
+        assert_eq!(*responder.0.lock(), vec![100, 100, 50]);
+    }
+
+    #[test]
+    fn test_dispatcher_owned_delivery() {
+        use crate::handler::FnOwnedHandler;
+        use ironsbe_core::pool::TieredPoolConfig;
+        use parking_lot::Mutex;
+
+        let kept: Arc<Mutex<Vec<OwnedMessage>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&kept);
+        let mut dispatcher = MessageDispatcher::new();
+        dispatcher.register_owned(
+            3,
+            FnOwnedHandler::new(move |_session_id, message, _responder| {
+                sink.lock().push(message);
+            }),
+        );
+        dispatcher.register(4, FnHandler::new(|_, _, _| {}));
+        assert_eq!(dispatcher.delivery(3), Some(Delivery::Owned));
+        assert_eq!(dispatcher.delivery(4), Some(Delivery::Borrowed));
+        assert_eq!(dispatcher.delivery(5), None);
+
+        let pool = TieredBufferPool::new(TieredPoolConfig::new().tier(1024, 1));
+        dispatcher.set_buffer_pool(pool.clone());
+        let header = MessageHeader::new(0, 3, 100, 1);
+        let mut message = vec![0u8; MessageHeader::ENCODED_LENGTH];
+        header.encode(&mut message, 0);
+        message.extend_from_slice(b"retained");
+
+        // Copied into the pool from a borrowed slice; the pool's one buffer
+        // is then in use, so the next copy falls back to an allocation.
+        dispatcher.on_message(1, &header, &message, &MockResponder);
+        dispatcher.on_message(1, &header, &message, &MockResponder);
+        // Shared without copying from an async server's frame.
+        let frame = Bytes::from(message.clone());
+        let frame_ptr = frame.as_ptr();
+        dispatcher.on_frame(1, &header, OwnedFrame::from(frame), &MockResponder);
+
+        let kept = std::mem::take(&mut *kept.lock());
+        assert_eq!(kept.len(), 3);
+        assert!(kept.iter().all(|m| &**m == message.as_slice()));
+        assert!(matches!(kept[0].get_ref(), MessageStorage::Pooled(_)));
+        assert!(matches!(kept[1].get_ref(), MessageStorage::Shared(_)));
+        assert_eq!(kept[2].as_ptr(), frame_ptr);
+        assert_eq!(kept[0].template_id(), Some(3));
+        assert_eq!(pool.stats().in_use, 1);
+        drop(kept);
+        assert_eq!(pool.stats().in_use, 0);
+    }
+
     #[test]
     fn test_dispatcher_on_message_no_handler() {
         let dispatcher = MessageDispatcher::new();
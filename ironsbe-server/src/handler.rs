@@ -1,6 +1,11 @@
 //! Message handler traits.
 
+#[cfg(feature = "tokio")]
+use crate::outbound::AsyncSessionResponder;
+use bytes::Bytes;
+use ironsbe_core::frame::OwnedFrame;
 use ironsbe_core::header::MessageHeader;
+use ironsbe_core::pool::TieredBuffer;
 
 /// Trait for handling incoming SBE messages.
 pub trait MessageHandler: Send + Sync {
@@ -19,6 +24,26 @@ pub trait MessageHandler: Send + Sync {
         responder: &dyn Responder,
     );
 
+    /// Called by the async servers with the frame the transport read, so a
+    /// handler can keep it or send it to another thread without copying.
+    ///
+    /// The default forwards to [`on_message`](Self::on_message).
+    ///
+    /// # Arguments
+    /// * `session_id` - ID of the session that sent the message
+    /// * `header` - Decoded message header
+    /// * `frame` - Full message (including header)
+    /// * `responder` - Interface for sending responses
+    fn on_frame(
+        &self,
+        session_id: u64,
+        header: &MessageHeader,
+        frame: OwnedFrame<Bytes>,
+        responder: &dyn Responder,
+    ) {
+        self.on_message(session_id, header, &frame, responder);
+    }
+
     /// Called when a new session is established.
     ///
     /// # Arguments
@@ -59,6 +84,45 @@ pub trait Responder: Send + Sync {
     /// # Errors
     /// Returns error if send fails.
     fn send_to(&self, session_id: u64, message: &[u8]) -> Result<(), SendError>;
+
+    /// Returns an owned responder for the current session whose sends wait
+    /// for outbound queue capacity.
+    ///
+    /// Meant for handlers that hand work to async tasks: move the returned
+    /// responder into the task and `await` its sends for natural
+    /// backpressure. Returns `None` if the server does not support it.
+    #[cfg(feature = "tokio")]
+    fn async_responder(&self) -> Option<AsyncSessionResponder> {
+        None
+    }
+}
+
+/// Responder whose sends wait for queue capacity instead of failing or
+/// queueing without bound.
+pub trait AsyncResponder: Send + Sync {
+    /// Sends a message to the current session once its queue has room.
+    ///
+    /// # Arguments
+    /// * `message` - Message bytes to send
+    ///
+    /// # Errors
+    /// Returns error if the session is gone, including while waiting.
+    fn send(&self, message: &[u8]) -> impl Future<Output = Result<(), SendError>> + Send;
+
+    /// Sends a message to a specific session once its queue has room.
+    ///
+    /// # Arguments
+    /// * `session_id` - Target session ID
+    /// * `message` - Message bytes to send
+    ///
+    /// # Errors
+    /// Returns error if the session is unknown or gone, including while
+    /// waiting.
+    fn send_to(
+        &self,
+        session_id: u64,
+        message: &[u8],
+    ) -> impl Future<Output = Result<(), SendError>> + Send;
 }
 
 /// Error type for send operations.
@@ -111,6 +175,83 @@ where
     }
 }
 
+/// Storage behind an [`OwnedMessage`].
+#[derive(Debug)]
+pub enum MessageStorage {
+    /// The buffer the transport read the message into, shared without
+    /// copying.
+    Shared(Bytes),
+    /// A pooled buffer the message was copied into; it returns to its pool
+    /// when dropped.
+    Pooled(TieredBuffer),
+}
+
+impl AsRef<[u8]> for MessageStorage {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::Shared(bytes) => bytes,
+            Self::Pooled(buffer) => buffer,
+        }
+    }
+}
+
+/// A message, header included, that its handler may keep past the
+/// callback.
+pub type OwnedMessage = OwnedFrame<MessageStorage>;
+
+/// How a [`MessageDispatcher`](crate::MessageDispatcher) hands a template's
+/// messages to its handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Delivery {
+    /// A `&[u8]` valid only during the callback; no copy is made.
+    #[default]
+    Borrowed,
+    /// An [`OwnedMessage`] the handler may retain. Frames from the async
+    /// servers are shared without copying; other messages are copied into
+    /// a pooled buffer.
+    Owned,
+}
+
+/// Handler for a specific message type that keeps messages.
+///
+/// Registered with
+/// [`MessageDispatcher::register_owned`](crate::MessageDispatcher::register_owned)
+/// for [`Delivery::Owned`], instead of copying the borrowed slice a
+/// [`TypedHandler`] gets.
+pub trait OwnedHandler: Send + Sync {
+    /// Handles a message of the specific type.
+    ///
+    /// # Arguments
+    /// * `session_id` - ID of the session
+    /// * `message` - Full message (including header), free to retain
+    /// * `responder` - Interface for sending responses
+    fn handle_owned(&self, session_id: u64, message: OwnedMessage, responder: &dyn Responder);
+}
+
+/// Wrapper to convert a closure into an [`OwnedHandler`].
+pub struct FnOwnedHandler<F> {
+    handler: F,
+}
+
+impl<F> FnOwnedHandler<F>
+where
+    F: Fn(u64, OwnedMessage, &dyn Responder) + Send + Sync,
+{
+    /// Creates a new function handler.
+    pub fn new(handler: F) -> Self {
+        Self { handler }
+    }
+}
+
+impl<F> OwnedHandler for FnOwnedHandler<F>
+where
+    F: Fn(u64, OwnedMessage, &dyn Responder) + Send + Sync,
+{
+    fn handle_owned(&self, session_id: u64, message: OwnedMessage, responder: &dyn Responder) {
+        (self.handler)(session_id, message, responder);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4,20 +4,61 @@
 //!
 //! This crate provides:
 //! - Server builder with configuration options
+//! - Queueing or rejecting connections beyond the connection limit
 //! - Session management for connected clients
-//! - Message handler traits and dispatcher
-//! - Connection acceptor
+//! - Session state persistence for resumption across restarts
+//! - An opt-in FIXP session layer: negotiation, establishment, sequenced
+//!   flows with retransmission, heartbeats and termination
+//! - Message handler traits and dispatcher, with borrowed or owned delivery
+//!   chosen per template
+//! - Template-based routing onto dedicated consumer channels
+//! - Deduplication of inbound messages by business key
+//! - Async responders with backpressure on outbound queues
+//! - On-demand snapshot serving
+//! - Connection acceptor, with an optional WebSocket listener alongside the
+//!   main transport (feature `ws`)
+//! - A blocking, thread-per-connection server for applications without an
+//!   async runtime
+//!
+//! The async servers need the `tokio` feature, which every async backend
+//! enables. With `default-features = false, features = ["tcp-std"]` only
+//! [`BlockingServer`] is built and Tokio is not a dependency.
 
+pub mod admission;
+#[cfg(feature = "tcp-std")]
+pub mod blocking;
+#[cfg(feature = "tokio")]
 pub mod builder;
+pub mod dedup;
 pub mod dispatcher;
 pub mod error;
 pub mod handler;
+#[cfg(feature = "tokio")]
 pub mod local_builder;
+#[cfg(feature = "tokio")]
+pub mod outbound;
+pub mod router;
 pub mod session;
+pub mod session_store;
+pub mod snapshot;
 
+pub use admission::{AdmissionPolicy, RejectReason};
+#[cfg(feature = "tcp-std")]
+pub use blocking::{BlockingServer, BlockingServerHandle};
+#[cfg(feature = "tokio")]
 pub use builder::{Server, ServerBuilder, ServerCommand, ServerEvent, ServerHandle};
+pub use dedup::{DedupConfig, DedupFilter, DedupHandler};
 pub use dispatcher::MessageDispatcher;
 pub use error::ServerError;
-pub use handler::{MessageHandler, Responder, TypedHandler};
+pub use handler::{
+    AsyncResponder, Delivery, FnOwnedHandler, MessageHandler, MessageStorage, OwnedHandler,
+    OwnedMessage, Responder, TypedHandler,
+};
+#[cfg(feature = "tokio")]
 pub use local_builder::{LocalServer, LocalServerBuilder};
-pub use session::SessionManager;
+#[cfg(feature = "tokio")]
+pub use outbound::AsyncSessionResponder;
+pub use router::{RoutedMessage, RoutingHandler};
+pub use session::{Session, SessionManager};
+pub use session_store::{FileSessionStore, SessionSnapshot, SessionState, SessionStore};
+pub use snapshot::{SnapshotChunk, SnapshotHandler, SnapshotProvider};
@@ -15,13 +15,17 @@
 use crate::error::ServerError;
 use crate::handler::{MessageHandler, Responder, SendError};
 use crate::session::SessionManager;
-use ironsbe_channel::mpsc::{MpscChannel, MpscReceiver, MpscSender};
+use ironsbe_channel::expiry::{ExpiryPolicy, Stamped};
+use ironsbe_channel::mpsc::{MpscChannel, MpscSender};
+use ironsbe_channel::priority::{PriorityChannel, PriorityReceiver, PrioritySender};
+use ironsbe_core::frame::OwnedFrame;
 use ironsbe_core::header::MessageHeader;
 use ironsbe_transport::traits::{LocalConnection, LocalListener, LocalTransport};
 use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::{Notify, mpsc as tokio_mpsc};
 use tracing::Instrument;
 
@@ -38,6 +42,8 @@ pub struct LocalServerBuilder<H, T: LocalTransport> {
     handler: Option<H>,
     max_connections: usize,
     channel_capacity: usize,
+    expiry_policy: ExpiryPolicy,
+    session_manager: Option<Arc<SessionManager>>,
     _transport: PhantomData<T>,
 }
 
@@ -53,6 +59,8 @@ impl<H: MessageHandler, T: LocalTransport> LocalServerBuilder<H, T> {
             handler: None,
             max_connections: 1000,
             channel_capacity: 4096,
+            expiry_policy: ExpiryPolicy::default(),
+            session_manager: None,
             _transport: PhantomData,
         }
     }
@@ -88,13 +96,31 @@ impl<H: MessageHandler, T: LocalTransport> LocalServerBuilder<H, T> {
         self
     }
 
-    /// Sets the cmd/event channel capacity.
+    /// Sets the cmd/event channel capacity (per lane for the command channel).
     #[must_use]
     pub fn channel_capacity(mut self, capacity: usize) -> Self {
         self.channel_capacity = capacity;
         self
     }
 
+    /// Sets the expiry policy for outbound session queues.
+    ///
+    /// See [`crate::ServerBuilder::expiry_policy`].
+    #[must_use]
+    pub fn expiry_policy(mut self, policy: ExpiryPolicy) -> Self {
+        self.expiry_policy = policy;
+        self
+    }
+
+    /// Sets the session manager tracking the server's sessions.
+    ///
+    /// See [`crate::ServerBuilder::session_manager`].
+    #[must_use]
+    pub fn session_manager(mut self, sessions: Arc<SessionManager>) -> Self {
+        self.session_manager = Some(sessions);
+        self
+    }
+
     /// Builds the server and its external handle.
     ///
     /// # Panics
@@ -102,7 +128,8 @@ impl<H: MessageHandler, T: LocalTransport> LocalServerBuilder<H, T> {
     #[must_use]
     pub fn build(self) -> (LocalServer<H, T>, ServerHandle) {
         let handler = self.handler.expect("Handler required");
-        let (cmd_tx, cmd_rx) = MpscChannel::bounded(self.channel_capacity);
+        let (cmd_tx, cmd_rx) =
+            PriorityChannel::bounded(self.channel_capacity, self.channel_capacity);
         let (event_tx, event_rx) = MpscChannel::bounded(self.channel_capacity);
         let cmd_notify = Arc::new(Notify::new());
 
@@ -114,10 +141,11 @@ impl<H: MessageHandler, T: LocalTransport> LocalServerBuilder<H, T> {
             ),
             handler: Rc::new(handler),
             max_connections: self.max_connections,
+            expiry_policy: Rc::new(self.expiry_policy),
             cmd_tx: cmd_tx.clone(),
             cmd_rx,
             event_tx,
-            sessions: SessionManager::new(),
+            sessions: self.session_manager.unwrap_or_default(),
             cmd_notify: Arc::clone(&cmd_notify),
             _transport: PhantomData,
         };
@@ -145,13 +173,15 @@ pub struct LocalServer<H, T: LocalTransport> {
     bind_config: Option<T::BindConfig>,
     handler: Rc<H>,
     max_connections: usize,
+    /// Shared with every session writer to drop stale outbound messages.
+    expiry_policy: Rc<ExpiryPolicy>,
     /// Cloned and handed to per-session tasks so they can fire
     /// `ServerCommand::CloseSession` when the session ends, freeing the
     /// `SessionManager` slot back in the run loop.
-    cmd_tx: MpscSender<ServerCommand>,
-    cmd_rx: MpscReceiver<ServerCommand>,
+    cmd_tx: PrioritySender<ServerCommand>,
+    cmd_rx: PriorityReceiver<ServerCommand>,
     event_tx: MpscSender<ServerEvent>,
-    sessions: SessionManager,
+    sessions: Arc<SessionManager>,
     cmd_notify: Arc<Notify>,
     _transport: PhantomData<T>,
 }
@@ -162,6 +192,12 @@ where
     T: LocalTransport,
     T::Connection: 'static,
 {
+    /// Returns the session manager tracking this server's sessions.
+    #[must_use]
+    pub fn sessions(&self) -> &Arc<SessionManager> {
+        &self.sessions
+    }
+
     /// Runs the server, accepting connections and processing messages.
     ///
     /// # Errors
@@ -230,6 +266,7 @@ where
         // eventually rejects every new connection.
         let cmd_tx = self.cmd_tx.clone();
         let cmd_notify = Arc::clone(&self.cmd_notify);
+        let expiry_policy = Rc::clone(&self.expiry_policy);
 
         handler.on_session_start(session_id);
         let _ = event_tx.try_send(ServerEvent::SessionCreated(session_id, addr));
@@ -245,13 +282,21 @@ where
         tokio::task::spawn_local(
             async move {
                 tracing::info!("connected");
-                if let Err(e) = handle_local_session(session_id, conn, handler.as_ref()).await {
+                if let Err(e) = handle_local_session(
+                    session_id,
+                    conn,
+                    handler.as_ref(),
+                    expiry_policy.as_ref(),
+                    &event_tx,
+                )
+                .await
+                {
                     tracing::error!(error = %e, "session error");
                 }
                 tracing::info!("disconnected");
                 handler.on_session_end(session_id);
                 let _ = event_tx.try_send(ServerEvent::SessionClosed(session_id));
-                let _ = cmd_tx.try_send(ServerCommand::CloseSession(session_id));
+                let _ = cmd_tx.try_send_control(ServerCommand::CloseSession(session_id));
                 cmd_notify.notify_one();
             }
             .instrument(span),
@@ -262,6 +307,9 @@ where
         match cmd {
             ServerCommand::Shutdown => {
                 tracing::info!("Local server shutdown requested");
+                if let Err(e) = self.sessions.persist() {
+                    tracing::warn!(error = %e, "failed to persist session state on shutdown");
+                }
                 true
             }
             ServerCommand::CloseSession(session_id) => {
@@ -281,14 +329,16 @@ where
 /// connection writer over an unbounded local channel.  Mirrors the
 /// equivalent type in [`crate::builder`].
 struct LocalSessionResponder {
-    tx: tokio_mpsc::UnboundedSender<Vec<u8>>,
+    tx: tokio_mpsc::UnboundedSender<Stamped<Vec<u8>>>,
 }
 
 impl Responder for LocalSessionResponder {
     fn send(&self, message: &[u8]) -> Result<(), SendError> {
-        self.tx.send(message.to_vec()).map_err(|_| SendError {
-            message: "channel closed".to_string(),
-        })
+        self.tx
+            .send(Stamped::now(message.to_vec()))
+            .map_err(|_| SendError {
+                message: "channel closed".to_string(),
+            })
     }
 
     fn send_to(&self, _session_id: u64, message: &[u8]) -> Result<(), SendError> {
@@ -301,17 +351,20 @@ impl Responder for LocalSessionResponder {
 /// handler back over the same connection.
 ///
 /// Mirrors the [`Connection`](ironsbe_transport::traits::Connection)
-/// version in [`crate::builder`].
+/// version in [`crate::builder`], including dropping responses that
+/// outlived `expiry_policy`.
 async fn handle_local_session<H, C>(
     session_id: u64,
     mut conn: C,
     handler: &H,
+    expiry_policy: &ExpiryPolicy,
+    event_tx: &MpscSender<ServerEvent>,
 ) -> Result<(), std::io::Error>
 where
     H: MessageHandler,
     C: LocalConnection,
 {
-    let (tx, mut rx) = tokio_mpsc::unbounded_channel::<Vec<u8>>();
+    let (tx, mut rx) = tokio_mpsc::unbounded_channel::<Stamped<Vec<u8>>>();
     let responder = LocalSessionResponder { tx };
 
     loop {
@@ -321,7 +374,7 @@ where
                     Ok(Some(data)) => {
                         if data.len() >= MessageHeader::ENCODED_LENGTH {
                             let header = MessageHeader::wrap(data.as_ref(), 0);
-                            handler.on_message(session_id, &header, data.as_ref(), &responder);
+                            handler.on_frame(session_id, &header, OwnedFrame::from(data), &responder);
                         } else {
                             handler.on_error(session_id, "Message too short for header");
                         }
@@ -337,7 +390,16 @@ where
             }
 
             Some(msg) = rx.recv() => {
-                if let Err(e) = conn.send(&msg).await {
+                if expiry_policy.is_expired(
+                    MessageHeader::try_wrap(&msg.item, 0).map(|h| h.template_id),
+                    msg.queued_at,
+                    Instant::now(),
+                ) {
+                    tracing::warn!(age = ?msg.age(Instant::now()), "dropping expired message");
+                    let _ = event_tx.try_send(ServerEvent::MessageExpired(session_id, msg.item));
+                    continue;
+                }
+                if let Err(e) = conn.send(&msg.item).await {
                     tracing::error!(error = %e, "write error");
                     return Err(std::io::Error::other(e.to_string()));
                 }
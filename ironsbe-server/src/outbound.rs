@@ -0,0 +1,280 @@
+//! Per-session outbound queues.
+//!
+//! Every session writer drains an unbounded channel. [`OutboundSender`]
+//! wraps its sending half with a depth gauge: synchronous sends keep
+//! queueing without limit, as they always have, while
+//! [`AsyncSessionResponder`] waits for the depth to drop below the server's
+//! outbound capacity, giving handlers that already run in async context
+//! natural backpressure instead of an ever-growing queue.
+
+use crate::builder::SessionSenderMap;
+use crate::handler::{AsyncResponder, SendError};
+use ironsbe_channel::expiry::Stamped;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::{Notify, mpsc as tokio_mpsc};
+
+/// Message queued for a session writer, stamped with its enqueue time.
+pub(crate) type OutboundItem = Stamped<Vec<u8>>;
+
+/// Queue depth shared by both halves of a session's outbound channel.
+#[derive(Debug, Default)]
+struct Depth {
+    /// Messages sent but not dequeued by the writer yet.
+    queued: AtomicUsize,
+    /// Woken whenever the writer dequeues a message.
+    drained: Notify,
+}
+
+/// Sending half of a session's outbound queue.
+#[derive(Debug, Clone)]
+pub(crate) struct OutboundSender {
+    tx: tokio_mpsc::UnboundedSender<OutboundItem>,
+    depth: Arc<Depth>,
+}
+
+/// Receiving half of a session's outbound queue, owned by its writer.
+#[derive(Debug)]
+pub(crate) struct OutboundReceiver {
+    rx: tokio_mpsc::UnboundedReceiver<OutboundItem>,
+    depth: Arc<Depth>,
+}
+
+/// Creates a session's outbound queue.
+pub(crate) fn channel() -> (OutboundSender, OutboundReceiver) {
+    let (tx, rx) = tokio_mpsc::unbounded_channel();
+    let depth = Arc::new(Depth::default());
+    (
+        OutboundSender {
+            tx,
+            depth: Arc::clone(&depth),
+        },
+        OutboundReceiver { rx, depth },
+    )
+}
+
+impl OutboundSender {
+    /// Queues `item` regardless of the queue depth.
+    ///
+    /// # Errors
+    /// Returns the item back if the writer is gone.
+    pub(crate) fn send(&self, item: OutboundItem) -> Result<(), OutboundItem> {
+        self.depth.queued.fetch_add(1, Ordering::AcqRel);
+        self.tx.send(item).map_err(|e| {
+            self.depth.queued.fetch_sub(1, Ordering::AcqRel);
+            e.0
+        })
+    }
+
+    /// Queues `item` once fewer than `capacity` messages are queued,
+    /// waiting for the writer to drain the queue as needed.
+    ///
+    /// # Errors
+    /// Returns the item back if the writer is gone, including while
+    /// waiting.
+    pub(crate) async fn send_when_ready(
+        &self,
+        item: OutboundItem,
+        capacity: usize,
+    ) -> Result<(), OutboundItem> {
+        let capacity = capacity.max(1);
+        loop {
+            // Register for the wake-up before checking the depth, so a
+            // dequeue between the check and the await is not missed.
+            let drained = self.depth.drained.notified();
+            tokio::pin!(drained);
+            drained.as_mut().enable();
+
+            let reserved = self
+                .depth
+                .queued
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                    (queued < capacity).then_some(queued + 1)
+                })
+                .is_ok();
+            if reserved {
+                return self.tx.send(item).map_err(|e| {
+                    self.depth.queued.fetch_sub(1, Ordering::AcqRel);
+                    e.0
+                });
+            }
+
+            tokio::select! {
+                _ = &mut drained => {}
+                _ = self.tx.closed() => return Err(item),
+            }
+        }
+    }
+
+    /// Returns the number of queued messages.
+    pub(crate) fn queued(&self) -> usize {
+        self.depth.queued.load(Ordering::Acquire)
+    }
+}
+
+impl OutboundReceiver {
+    /// Dequeues the next message, waking senders waiting for capacity.
+    pub(crate) async fn recv(&mut self) -> Option<OutboundItem> {
+        let item = self.rx.recv().await?;
+        self.depth.queued.fetch_sub(1, Ordering::AcqRel);
+        self.depth.drained.notify_waiters();
+        Some(item)
+    }
+
+    /// Dequeues the next message without waiting.
+    #[cfg(test)]
+    pub(crate) fn try_recv(&mut self) -> Result<OutboundItem, tokio_mpsc::error::TryRecvError> {
+        let item = self.rx.try_recv()?;
+        self.depth.queued.fetch_sub(1, Ordering::AcqRel);
+        self.depth.drained.notify_waiters();
+        Ok(item)
+    }
+}
+
+/// Owned responder whose sends wait for outbound queue capacity.
+///
+/// Obtained from [`Responder::async_responder`](crate::Responder::async_responder)
+/// inside [`MessageHandler::on_message`](crate::MessageHandler::on_message)
+/// and moved into the task that produces the responses. A send waits while
+/// the target session already has the server's
+/// [`outbound_capacity`](crate::ServerBuilder::outbound_capacity) messages
+/// queued, and fails once that session is gone.
+#[derive(Debug, Clone)]
+pub struct AsyncSessionResponder {
+    tx: OutboundSender,
+    senders: SessionSenderMap,
+    session_id: u64,
+    capacity: usize,
+}
+
+impl AsyncSessionResponder {
+    /// Creates a responder for `session_id`, sending through `tx`.
+    pub(crate) fn new(
+        tx: OutboundSender,
+        senders: SessionSenderMap,
+        session_id: u64,
+        capacity: usize,
+    ) -> Self {
+        Self {
+            tx,
+            senders,
+            session_id,
+            capacity,
+        }
+    }
+
+    /// Returns the session this responder sends to by default.
+    #[must_use]
+    pub fn session_id(&self) -> u64 {
+        self.session_id
+    }
+
+    /// Returns the number of messages queued for the session.
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.tx.queued()
+    }
+}
+
+impl AsyncResponder for AsyncSessionResponder {
+    async fn send(&self, message: &[u8]) -> Result<(), SendError> {
+        self.tx
+            .send_when_ready(Stamped::now(message.to_vec()), self.capacity)
+            .await
+            .map_err(|_| SendError {
+                message: format!("session {} channel closed", self.session_id),
+            })
+    }
+
+    async fn send_to(&self, session_id: u64, message: &[u8]) -> Result<(), SendError> {
+        // Clone the sender out so the registry lock is not held while
+        // waiting for capacity.
+        let sender = self.senders.read().get(&session_id).cloned();
+        let Some(sender) = sender else {
+            return Err(SendError {
+                message: format!("unknown session {session_id}"),
+            });
+        };
+        sender
+            .send_when_ready(Stamped::now(message.to_vec()), self.capacity)
+            .await
+            .map_err(|_| SendError {
+                message: format!("session {session_id} channel closed"),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::RwLock;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_depth_tracks_send_and_recv() {
+        let (tx, mut rx) = channel();
+        tx.send(Stamped::now(vec![1])).unwrap();
+        tx.send(Stamped::now(vec![2])).unwrap();
+        assert_eq!(tx.queued(), 2);
+
+        assert_eq!(rx.recv().await.unwrap().item, vec![1]);
+        assert_eq!(tx.queued(), 1);
+
+        drop(rx);
+        assert!(tx.send(Stamped::now(vec![3])).is_err());
+        assert_eq!(tx.queued(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_send_when_ready_waits_for_capacity() {
+        let (tx, mut rx) = channel();
+        tx.send_when_ready(Stamped::now(vec![1]), 1).await.unwrap();
+
+        let waiting = tokio::spawn({
+            let tx = tx.clone();
+            async move { tx.send_when_ready(Stamped::now(vec![2]), 1).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(
+            !waiting.is_finished(),
+            "send must wait while the queue is full"
+        );
+        assert_eq!(tx.queued(), 1);
+
+        assert_eq!(rx.recv().await.unwrap().item, vec![1]);
+        waiting.await.unwrap().unwrap();
+        assert_eq!(rx.recv().await.unwrap().item, vec![2]);
+    }
+
+    #[tokio::test]
+    async fn test_send_when_ready_fails_when_session_closes() {
+        let (tx, rx) = channel();
+        tx.send(Stamped::now(vec![1])).unwrap();
+
+        let waiting =
+            tokio::spawn(async move { tx.send_when_ready(Stamped::now(vec![2]), 1).await });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        drop(rx);
+
+        let returned = waiting.await.unwrap().unwrap_err();
+        assert_eq!(returned.item, vec![2]);
+    }
+
+    #[tokio::test]
+    async fn test_async_responder_send_to() {
+        let (tx_self, _rx_self) = channel();
+        let (tx_other, mut rx_other) = channel();
+        let senders: SessionSenderMap = Arc::new(RwLock::new(HashMap::new()));
+        senders.write().insert(2, tx_other);
+
+        let responder = AsyncSessionResponder::new(tx_self, Arc::clone(&senders), 1, 4);
+        responder.send_to(2, b"hello").await.unwrap();
+        assert_eq!(rx_other.recv().await.unwrap().item, b"hello");
+
+        let err = responder.send_to(99, b"x").await.unwrap_err();
+        assert!(err.message.contains("unknown session 99"));
+        responder.send(b"self").await.unwrap();
+        assert_eq!(responder.queued(), 1);
+    }
+}
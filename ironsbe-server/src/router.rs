@@ -0,0 +1,159 @@
+//! Message handler that routes inbound messages onto consumer channels.
+
+use crate::handler::{MessageHandler, Responder};
+use bytes::Bytes;
+use ironsbe_channel::router::{RouteError, Router};
+use ironsbe_core::frame::OwnedFrame;
+use ironsbe_core::header::MessageHeader;
+
+/// An inbound message handed to a routed consumer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutedMessage {
+    /// ID of the session that sent the message.
+    pub session_id: u64,
+    /// Template ID from the message header.
+    pub template_id: u16,
+    /// Full message (including header), sharing the buffer the transport
+    /// read it into.
+    pub payload: OwnedFrame<Bytes>,
+}
+
+/// Handler that splits inbound messages across channels by template ID.
+///
+/// Each message is wrapped in a [`RoutedMessage`] and delivered with
+/// [`Router::dispatch`], so consumers run on their own threads and never
+/// block the session. Frames from the async servers are handed over
+/// without copying; messages passed to
+/// [`on_message`](MessageHandler::on_message) directly are copied. Messages that cannot be delivered are logged and
+/// dropped.
+pub struct RoutingHandler {
+    router: Router<RoutedMessage>,
+}
+
+impl RoutingHandler {
+    /// Creates a handler delivering through `router`.
+    #[must_use]
+    pub fn new(router: Router<RoutedMessage>) -> Self {
+        Self { router }
+    }
+
+    /// Returns the routing table.
+    #[must_use]
+    pub fn router(&self) -> &Router<RoutedMessage> {
+        &self.router
+    }
+}
+
+impl MessageHandler for RoutingHandler {
+    fn on_message(
+        &self,
+        session_id: u64,
+        header: &MessageHeader,
+        buffer: &[u8],
+        responder: &dyn Responder,
+    ) {
+        let frame = OwnedFrame::from(Bytes::copy_from_slice(buffer));
+        self.on_frame(session_id, header, frame, responder);
+    }
+
+    fn on_frame(
+        &self,
+        session_id: u64,
+        header: &MessageHeader,
+        frame: OwnedFrame<Bytes>,
+        _responder: &dyn Responder,
+    ) {
+        let template_id = header.template_id;
+        let message = RoutedMessage {
+            session_id,
+            template_id,
+            payload: frame,
+        };
+        if let Err(e) = self.router.dispatch(Some(template_id), message) {
+            match e {
+                RouteError::Unrouted(_) => {
+                    tracing::debug!(session_id, template_id, "dropping unrouted message");
+                }
+                _ => {
+                    tracing::warn!(session_id, template_id, "dropping routed message: {e}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::SendError;
+    use ironsbe_channel::mpsc::MpscChannel;
+
+    struct NullResponder;
+
+    impl Responder for NullResponder {
+        fn send(&self, _message: &[u8]) -> Result<(), SendError> {
+            Ok(())
+        }
+
+        fn send_to(&self, _session_id: u64, _message: &[u8]) -> Result<(), SendError> {
+            Ok(())
+        }
+    }
+
+    fn message(template_id: u16) -> (MessageHeader, Vec<u8>) {
+        let header = MessageHeader::new(0, template_id, 1, 1);
+        let mut buffer = vec![0u8; MessageHeader::ENCODED_LENGTH];
+        header.encode(&mut buffer, 0);
+        (header, buffer)
+    }
+
+    #[test]
+    fn test_routing_handler_splits_sessions_and_templates() {
+        let (md_tx, md_rx) = MpscChannel::bounded(8);
+        let (admin_tx, admin_rx) = MpscChannel::bounded(8);
+        let handler = RoutingHandler::new(Router::new().route(1, md_tx).default_route(admin_tx));
+
+        let (md_header, md_buffer) = message(1);
+        let (admin_header, admin_buffer) = message(5);
+        handler.on_message(7, &md_header, &md_buffer, &NullResponder);
+        handler.on_message(8, &admin_header, &admin_buffer, &NullResponder);
+
+        assert_eq!(
+            md_rx.try_recv(),
+            Some(RoutedMessage {
+                session_id: 7,
+                template_id: 1,
+                payload: OwnedFrame::from(Bytes::from(md_buffer)),
+            })
+        );
+        let admin = admin_rx.try_recv().expect("default route");
+        assert_eq!((admin.session_id, admin.template_id), (8, 5));
+        assert!(md_rx.is_empty());
+    }
+
+    #[test]
+    fn test_routing_handler_moves_frames_without_copying() {
+        let (tx, rx) = MpscChannel::bounded(8);
+        let handler = RoutingHandler::new(Router::new().route(1, tx));
+
+        let (header, buffer) = message(1);
+        let frame = OwnedFrame::from(bytes::BytesMut::from(&buffer[..]));
+        let pointer = frame.as_ptr();
+        handler.on_frame(4, &header, frame, &NullResponder);
+
+        let routed = rx.try_recv().expect("routed");
+        assert_eq!(routed.payload.as_ptr(), pointer);
+        assert_eq!(&*routed.payload, &buffer[..]);
+    }
+
+    #[test]
+    fn test_routing_handler_drops_unrouted() {
+        let (md_tx, md_rx) = MpscChannel::bounded(8);
+        let handler = RoutingHandler::new(Router::new().route(1, md_tx));
+
+        let (header, buffer) = message(2);
+        handler.on_message(1, &header, &buffer, &NullResponder);
+
+        assert!(md_rx.is_empty());
+    }
+}
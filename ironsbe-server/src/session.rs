@@ -1,7 +1,17 @@
 //! Session management.
+//!
+//! Besides tracking live connections, the [`SessionManager`] keeps the
+//! per-session state a session protocol needs to resume a session:
+//! inbound and outbound sequence numbers and the set of subscribed streams.
+//! With a [`SessionStore`] attached, that state outlives both the
+//! connection and the process: closed sessions stay resumable, and a
+//! restarted server loads them back so clients can resume instead of
+//! logging on and resubscribing from scratch.
 
+use crate::session_store::{SessionSnapshot, SessionState, SessionStore};
 use parking_lot::RwLock;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::io;
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU64, Ordering};
 
@@ -16,12 +26,42 @@ pub struct Session {
     pub created_at: u64,
     /// Last activity timestamp.
     pub last_activity: u64,
+    /// Sequence number expected on the next inbound message.
+    pub next_inbound_seq: u64,
+    /// Sequence number of the next outbound message.
+    pub next_outbound_seq: u64,
+    /// Subscribed stream IDs (instruments, channels, ...).
+    pub subscriptions: BTreeSet<u64>,
+}
+
+impl Session {
+    /// Returns the resumable part of this session.
+    #[must_use]
+    pub fn state(&self) -> SessionState {
+        SessionState {
+            id: self.id,
+            next_inbound_seq: self.next_inbound_seq,
+            next_outbound_seq: self.next_outbound_seq,
+            subscriptions: self.subscriptions.clone(),
+        }
+    }
+}
+
+/// Returns the current time in nanos since the epoch.
+fn now_nanos() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
 }
 
 /// Manages active sessions.
 pub struct SessionManager {
     sessions: RwLock<HashMap<u64, Session>>,
+    /// Closed sessions a client may still resume; only kept with a store.
+    resumable: RwLock<HashMap<u64, SessionState>>,
     next_id: AtomicU64,
+    store: Option<Box<dyn SessionStore>>,
 }
 
 impl SessionManager {
@@ -30,32 +70,122 @@ impl SessionManager {
     pub fn new() -> Self {
         Self {
             sessions: RwLock::new(HashMap::new()),
+            resumable: RwLock::new(HashMap::new()),
             next_id: AtomicU64::new(1),
+            store: None,
         }
     }
 
+    /// Creates a session manager persisting its state to `store`.
+    ///
+    /// Every session found in the store becomes resumable, and new session
+    /// IDs continue after the highest ID ever handed out.
+    ///
+    /// The state is saved whenever a session is created, closed or resumed,
+    /// when a subscription changes, and on [`persist`](Self::persist).
+    /// Sequence numbers advance on every message and are only saved on
+    /// those occasions, so a resumed session may lag the last messages
+    /// before a crash; the session protocol's resend logic covers the gap.
+    ///
+    /// # Errors
+    /// Returns an I/O error if the stored state cannot be loaded.
+    pub fn with_store(store: impl SessionStore + 'static) -> io::Result<Self> {
+        let snapshot = store.load()?.unwrap_or_default();
+        let next_id = snapshot
+            .sessions
+            .iter()
+            .map(|state| state.id + 1)
+            .chain([snapshot.next_id, 1])
+            .max()
+            .unwrap_or(1);
+        let resumable = snapshot
+            .sessions
+            .into_iter()
+            .map(|state| (state.id, state))
+            .collect();
+
+        Ok(Self {
+            sessions: RwLock::new(HashMap::new()),
+            resumable: RwLock::new(resumable),
+            next_id: AtomicU64::new(next_id),
+            store: Some(Box::new(store)),
+        })
+    }
+
     /// Creates a new session and returns its ID.
     pub fn create_session(&self, peer_addr: SocketAddr) -> u64 {
         let id = self.next_id.fetch_add(1, Ordering::Relaxed);
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos() as u64;
+        let now = now_nanos();
 
         let session = Session {
             id,
             peer_addr,
             created_at: now,
             last_activity: now,
+            next_inbound_seq: 1,
+            next_outbound_seq: 1,
+            subscriptions: BTreeSet::new(),
         };
 
         self.sessions.write().insert(id, session);
+        self.save();
         id
     }
 
     /// Closes a session.
+    ///
+    /// With a store attached the session stays resumable until it is
+    /// resumed or [discarded](Self::discard_resumable).
     pub fn close_session(&self, session_id: u64) -> Option<Session> {
-        self.sessions.write().remove(&session_id)
+        let session = self.sessions.write().remove(&session_id)?;
+        if self.store.is_some() {
+            self.resumable.write().insert(session_id, session.state());
+            self.save();
+        }
+        Some(session)
+    }
+
+    /// Resumes the closed session `previous_id` on the live session
+    /// `session_id`, typically after the client presented `previous_id` in
+    /// a resume request.
+    ///
+    /// The live session takes over the sequence numbers and subscriptions
+    /// of the resumed one, which stops being resumable.
+    ///
+    /// # Returns
+    /// The resumed state, or `None` if `previous_id` is not resumable or
+    /// `session_id` is not live.
+    pub fn resume_session(&self, session_id: u64, previous_id: u64) -> Option<SessionState> {
+        let state = {
+            let mut sessions = self.sessions.write();
+            let session = sessions.get_mut(&session_id)?;
+            let state = self.resumable.write().remove(&previous_id)?;
+            session.next_inbound_seq = state.next_inbound_seq;
+            session.next_outbound_seq = state.next_outbound_seq;
+            session.subscriptions = state.subscriptions.clone();
+            state
+        };
+        self.save();
+        Some(state)
+    }
+
+    /// Drops a resumable session, e.g. once its client logged on afresh.
+    pub fn discard_resumable(&self, session_id: u64) -> Option<SessionState> {
+        let state = self.resumable.write().remove(&session_id)?;
+        self.save();
+        Some(state)
+    }
+
+    /// Returns the IDs of all resumable sessions.
+    #[must_use]
+    pub fn resumable_ids(&self) -> Vec<u64> {
+        self.resumable.read().keys().copied().collect()
+    }
+
+    /// Returns the state of a resumable session.
+    #[must_use]
+    pub fn resumable_state(&self, session_id: u64) -> Option<SessionState> {
+        self.resumable.read().get(&session_id).cloned()
     }
 
     /// Gets a session by ID.
@@ -66,16 +196,114 @@ impl SessionManager {
 
     /// Updates the last activity timestamp for a session.
     pub fn touch_session(&self, session_id: u64) {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos() as u64;
+        let now = now_nanos();
 
         if let Some(session) = self.sessions.write().get_mut(&session_id) {
             session.last_activity = now;
         }
     }
 
+    /// Records an inbound message with sequence number `seq`, so the next
+    /// one expected is `seq + 1`.
+    ///
+    /// Sequence numbers never move backwards; a duplicate is ignored.
+    pub fn record_inbound_seq(&self, session_id: u64, seq: u64) {
+        if let Some(session) = self.sessions.write().get_mut(&session_id) {
+            session.next_inbound_seq = session.next_inbound_seq.max(seq + 1);
+        }
+    }
+
+    /// Allocates the sequence number of the next outbound message.
+    ///
+    /// # Returns
+    /// The sequence number, or `None` if the session is not live.
+    pub fn next_outbound_seq(&self, session_id: u64) -> Option<u64> {
+        let mut sessions = self.sessions.write();
+        let session = sessions.get_mut(&session_id)?;
+        let seq = session.next_outbound_seq;
+        session.next_outbound_seq += 1;
+        Some(seq)
+    }
+
+    /// Subscribes a session to `stream_id`.
+    ///
+    /// # Returns
+    /// True if the subscription is new.
+    pub fn subscribe(&self, session_id: u64, stream_id: u64) -> bool {
+        let added = self
+            .sessions
+            .write()
+            .get_mut(&session_id)
+            .is_some_and(|session| session.subscriptions.insert(stream_id));
+        if added {
+            self.save();
+        }
+        added
+    }
+
+    /// Unsubscribes a session from `stream_id`.
+    ///
+    /// # Returns
+    /// True if the session was subscribed.
+    pub fn unsubscribe(&self, session_id: u64, stream_id: u64) -> bool {
+        let removed = self
+            .sessions
+            .write()
+            .get_mut(&session_id)
+            .is_some_and(|session| session.subscriptions.remove(&stream_id));
+        if removed {
+            self.save();
+        }
+        removed
+    }
+
+    /// Returns the stream IDs a session is subscribed to.
+    #[must_use]
+    pub fn subscriptions(&self, session_id: u64) -> Vec<u64> {
+        self.sessions
+            .read()
+            .get(&session_id)
+            .map(|session| session.subscriptions.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Captures the state of every live and resumable session.
+    #[must_use]
+    pub fn snapshot(&self) -> SessionSnapshot {
+        let mut sessions: Vec<SessionState> = self
+            .sessions
+            .read()
+            .values()
+            .map(Session::state)
+            .chain(self.resumable.read().values().cloned())
+            .collect();
+        sessions.sort_by_key(|state| state.id);
+        SessionSnapshot {
+            next_id: self.next_id.load(Ordering::Relaxed),
+            sessions,
+        }
+    }
+
+    /// Saves the current state to the store, if any.
+    ///
+    /// Call it at checkpoints to bound how far saved sequence numbers lag.
+    ///
+    /// # Errors
+    /// Returns an I/O error if the store fails to save.
+    pub fn persist(&self) -> io::Result<()> {
+        match &self.store {
+            Some(store) => store.save(&self.snapshot()),
+            None => Ok(()),
+        }
+    }
+
+    /// Saves the current state after a change, logging failures.
+    fn save(&self) {
+        if let Err(e) = self.persist() {
+            tracing::warn!(error = %e, "failed to persist session state");
+        }
+    }
+
     /// Returns the number of active sessions.
     #[must_use]
     pub fn count(&self) -> usize {
@@ -108,6 +336,7 @@ impl Default for SessionManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::session_store::FileSessionStore;
 
     #[test]
     fn test_create_session() {
@@ -145,4 +374,90 @@ mod tests {
         assert_eq!(session.id, id);
         assert_eq!(session.peer_addr, addr);
     }
+
+    #[test]
+    fn test_sequence_numbers_and_subscriptions() {
+        let manager = SessionManager::new();
+        let id = manager.create_session("127.0.0.1:8080".parse().unwrap());
+
+        assert_eq!(manager.next_outbound_seq(id), Some(1));
+        assert_eq!(manager.next_outbound_seq(id), Some(2));
+        manager.record_inbound_seq(id, 5);
+        manager.record_inbound_seq(id, 3);
+        assert_eq!(manager.get_session(id).unwrap().next_inbound_seq, 6);
+
+        assert!(manager.subscribe(id, 42));
+        assert!(!manager.subscribe(id, 42));
+        assert!(manager.subscribe(id, 7));
+        assert!(manager.unsubscribe(id, 42));
+        assert_eq!(manager.subscriptions(id), vec![7]);
+
+        assert_eq!(manager.next_outbound_seq(99), None);
+        assert!(!manager.subscribe(99, 1));
+    }
+
+    #[test]
+    fn test_closed_sessions_not_resumable_without_store() {
+        let manager = SessionManager::new();
+        let id = manager.create_session("127.0.0.1:8080".parse().unwrap());
+        manager.close_session(id);
+
+        assert!(manager.resumable_ids().is_empty());
+        assert!(manager.persist().is_ok());
+    }
+
+    #[test]
+    fn test_resume_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.state");
+        let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+
+        let (resumed_id, other_id) = {
+            let manager = SessionManager::with_store(FileSessionStore::new(&path)).unwrap();
+            let id = manager.create_session(addr);
+            let other = manager.create_session(addr);
+            manager.subscribe(id, 11);
+            manager.subscribe(id, 12);
+            manager.record_inbound_seq(id, 9);
+            manager.next_outbound_seq(id);
+            manager.close_session(other);
+            manager.persist().unwrap();
+            (id, other)
+        };
+
+        // Restart: every stored session is resumable, none is live.
+        let manager = SessionManager::with_store(FileSessionStore::new(&path)).unwrap();
+        assert_eq!(manager.count(), 0);
+        let mut resumable = manager.resumable_ids();
+        resumable.sort_unstable();
+        assert_eq!(resumable, vec![resumed_id, other_id]);
+
+        let id = manager.create_session(addr);
+        assert!(id > other_id, "session IDs must not repeat across restarts");
+
+        let state = manager.resume_session(id, resumed_id).unwrap();
+        assert_eq!(state.next_inbound_seq, 10);
+        assert_eq!(state.next_outbound_seq, 2);
+        assert_eq!(manager.subscriptions(id), vec![11, 12]);
+        assert_eq!(manager.next_outbound_seq(id), Some(2));
+        assert_eq!(manager.resume_session(id, resumed_id), None);
+        assert_eq!(manager.resumable_ids(), vec![other_id]);
+
+        assert!(manager.discard_resumable(other_id).is_some());
+        let stored = FileSessionStore::new(&path).load().unwrap().unwrap();
+        let ids: Vec<u64> = stored.sessions.iter().map(|state| state.id).collect();
+        assert_eq!(ids, vec![id]);
+    }
+
+    #[test]
+    fn test_resume_requires_live_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager =
+            SessionManager::with_store(FileSessionStore::new(dir.path().join("s"))).unwrap();
+        let id = manager.create_session("127.0.0.1:8080".parse().unwrap());
+        manager.close_session(id);
+
+        assert_eq!(manager.resume_session(99, id), None);
+        assert_eq!(manager.resumable_ids(), vec![id]);
+    }
 }
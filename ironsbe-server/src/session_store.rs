@@ -0,0 +1,226 @@
+//! Session state persistence.
+//!
+//! A [`SessionStore`] saves the resumable part of every session (ID,
+//! sequence numbers, subscriptions) so a restarted server can offer session
+//! resumption instead of forcing every client through a full logon and
+//! resubscribe. [`FileSessionStore`] keeps the state in a small text file,
+//! replaced atomically on every save.
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Resumable state of one session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionState {
+    /// Session ID.
+    pub id: u64,
+    /// Sequence number expected on the next inbound message.
+    pub next_inbound_seq: u64,
+    /// Sequence number of the next outbound message.
+    pub next_outbound_seq: u64,
+    /// Subscribed stream IDs (instruments, channels, ...).
+    pub subscriptions: BTreeSet<u64>,
+}
+
+/// Everything a [`SessionManager`](crate::SessionManager) needs to resume
+/// after a restart.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionSnapshot {
+    /// ID handed to the next new session, so IDs never repeat across
+    /// restarts.
+    pub next_id: u64,
+    /// State of every live or resumable session, ordered by ID.
+    pub sessions: Vec<SessionState>,
+}
+
+/// Durable storage for session state.
+pub trait SessionStore: Send + Sync {
+    /// Loads the last saved snapshot.
+    ///
+    /// # Returns
+    /// `None` if nothing was saved yet.
+    ///
+    /// # Errors
+    /// Returns an I/O error if the stored state cannot be read or decoded.
+    fn load(&self) -> io::Result<Option<SessionSnapshot>>;
+
+    /// Replaces the stored snapshot.
+    ///
+    /// # Errors
+    /// Returns an I/O error if the snapshot cannot be written.
+    fn save(&self, snapshot: &SessionSnapshot) -> io::Result<()>;
+}
+
+/// First line of a session state file.
+const FILE_HEADER: &str = "ironsbe-sessions v1";
+
+/// Session store backed by a text file.
+///
+/// Saves write a sibling temporary file and rename it over the target, so a
+/// crash mid-save leaves the previous snapshot intact.
+#[derive(Debug, Clone)]
+pub struct FileSessionStore {
+    path: PathBuf,
+}
+
+impl FileSessionStore {
+    /// Creates a store that keeps its state in `path`.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the path of the state file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl SessionStore for FileSessionStore {
+    fn load(&self) -> io::Result<Option<SessionSnapshot>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(text) => decode(&text).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&self, snapshot: &SessionSnapshot) -> io::Result<()> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, encode(snapshot))?;
+        std::fs::rename(&tmp, &self.path)
+    }
+}
+
+/// Encodes a snapshot as one `session` line per session:
+/// `session <id> <next inbound> <next outbound> <subscriptions>`, with the
+/// subscriptions comma-separated or `-` if there are none.
+fn encode(snapshot: &SessionSnapshot) -> String {
+    let mut text = format!("{FILE_HEADER}\nnext_id {}\n", snapshot.next_id);
+    for session in &snapshot.sessions {
+        let subscriptions = if session.subscriptions.is_empty() {
+            "-".to_string()
+        } else {
+            session
+                .subscriptions
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let _ = writeln!(
+            text,
+            "session {} {} {} {}",
+            session.id, session.next_inbound_seq, session.next_outbound_seq, subscriptions
+        );
+    }
+    text
+}
+
+/// Decodes the output of [`encode`].
+fn decode(text: &str) -> io::Result<SessionSnapshot> {
+    let invalid = |line: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid session state line: {line:?}"),
+        )
+    };
+    let number = |value: &str, line: &str| value.parse::<u64>().map_err(|_| invalid(line));
+
+    let mut lines = text.lines();
+    match lines.next() {
+        Some(FILE_HEADER) => {}
+        other => return Err(invalid(other.unwrap_or_default())),
+    }
+
+    let mut snapshot = SessionSnapshot::default();
+    for line in lines.filter(|line| !line.trim().is_empty()) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["next_id", id] => snapshot.next_id = number(id, line)?,
+            ["session", id, inbound, outbound, subscriptions] => {
+                let subscriptions = if *subscriptions == "-" {
+                    BTreeSet::new()
+                } else {
+                    subscriptions
+                        .split(',')
+                        .map(|id| number(id, line))
+                        .collect::<io::Result<_>>()?
+                };
+                snapshot.sessions.push(SessionState {
+                    id: number(id, line)?,
+                    next_inbound_seq: number(inbound, line)?,
+                    next_outbound_seq: number(outbound, line)?,
+                    subscriptions,
+                });
+            }
+            _ => return Err(invalid(line)),
+        }
+    }
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> SessionSnapshot {
+        SessionSnapshot {
+            next_id: 8,
+            sessions: vec![
+                SessionState {
+                    id: 3,
+                    next_inbound_seq: 12,
+                    next_outbound_seq: 40,
+                    subscriptions: [7, 11].into_iter().collect(),
+                },
+                SessionState {
+                    id: 5,
+                    next_inbound_seq: 1,
+                    next_outbound_seq: 1,
+                    subscriptions: BTreeSet::new(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let text = encode(&snapshot());
+        assert!(text.starts_with("ironsbe-sessions v1\nnext_id 8\n"));
+        assert!(text.contains("session 3 12 40 7,11\n"));
+        assert!(text.contains("session 5 1 1 -\n"));
+        assert_eq!(decode(&text).unwrap(), snapshot());
+    }
+
+    #[test]
+    fn test_decode_rejects_garbage() {
+        assert!(decode("").is_err());
+        assert!(decode("ironsbe-sessions v1\nsession 1 2\n").is_err());
+        assert!(decode("ironsbe-sessions v1\nnext_id x\n").is_err());
+        let err = decode("something else\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_file_store_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileSessionStore::new(dir.path().join("sessions.state"));
+
+        assert_eq!(store.load().unwrap(), None);
+        store.save(&snapshot()).unwrap();
+        assert_eq!(store.load().unwrap(), Some(snapshot()));
+
+        let updated = SessionSnapshot {
+            next_id: 9,
+            sessions: Vec::new(),
+        };
+        store.save(&updated).unwrap();
+        assert_eq!(store.load().unwrap(), Some(updated));
+        assert!(!dir.path().join("sessions.state.tmp").exists());
+    }
+}
@@ -0,0 +1,321 @@
+//! On-demand snapshot serving.
+//!
+//! A [`SnapshotProvider`] turns a snapshot-request message into a consistent
+//! copy of some state (typically an order book) and encodes it as response
+//! messages. [`SnapshotHandler`] drives a provider from the
+//! [`MessageDispatcher`](crate::MessageDispatcher), splitting deep snapshots
+//! into chunks so no single response exceeds the frame size.
+
+use crate::handler::{Responder, TypedHandler};
+use std::ops::Range;
+
+/// Default maximum number of entries encoded in one response message.
+pub const DEFAULT_MAX_ENTRIES_PER_CHUNK: usize = 100;
+
+/// Default size of the buffer each response message is encoded into.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// Position of one response message within a chunked snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotChunk {
+    /// Zero-based index of this chunk.
+    pub index: usize,
+    /// Total number of chunks in the response.
+    pub total: usize,
+    /// Snapshot entries carried by this chunk.
+    pub entries: Range<usize>,
+}
+
+impl SnapshotChunk {
+    /// Returns true if this is the final chunk of the response.
+    #[must_use]
+    pub fn is_last(&self) -> bool {
+        self.index + 1 == self.total
+    }
+}
+
+/// Source of snapshots served in response to snapshot-request messages.
+pub trait SnapshotProvider: Send + Sync {
+    /// Consistent copy of the state captured for one request.
+    type Snapshot;
+
+    /// Captures the snapshot requested by `request`.
+    ///
+    /// Returns `None` if the request cannot be served (unknown instrument,
+    /// malformed request, ...).
+    ///
+    /// # Arguments
+    /// * `session_id` - ID of the requesting session
+    /// * `request` - Full request message (including header)
+    fn snapshot(&self, session_id: u64, request: &[u8]) -> Option<Self::Snapshot>;
+
+    /// Returns the number of entries (e.g. book levels) in the snapshot.
+    fn entry_count(&self, snapshot: &Self::Snapshot) -> usize;
+
+    /// Encodes one chunk of the snapshot into `buffer`.
+    ///
+    /// # Returns
+    /// The encoded message length in bytes.
+    fn encode_chunk(
+        &self,
+        snapshot: &Self::Snapshot,
+        chunk: &SnapshotChunk,
+        buffer: &mut [u8],
+    ) -> usize;
+
+    /// Encodes a reply for a request that [`snapshot`](Self::snapshot)
+    /// rejected.
+    ///
+    /// # Returns
+    /// The encoded message length in bytes, or 0 to send nothing (default).
+    fn encode_unavailable(&self, _session_id: u64, _request: &[u8], _buffer: &mut [u8]) -> usize {
+        0
+    }
+}
+
+/// Typed handler that answers snapshot requests from a [`SnapshotProvider`].
+pub struct SnapshotHandler<P> {
+    provider: P,
+    max_entries_per_chunk: usize,
+    max_message_size: usize,
+}
+
+impl<P: SnapshotProvider> SnapshotHandler<P> {
+    /// Creates a handler with default chunking.
+    #[must_use]
+    pub fn new(provider: P) -> Self {
+        Self {
+            provider,
+            max_entries_per_chunk: DEFAULT_MAX_ENTRIES_PER_CHUNK,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+        }
+    }
+
+    /// Sets the maximum number of entries per response message.
+    ///
+    /// Values below 1 are treated as 1.
+    #[must_use]
+    pub fn max_entries_per_chunk(mut self, max: usize) -> Self {
+        self.max_entries_per_chunk = max.max(1);
+        self
+    }
+
+    /// Sets the size of the buffer each response message is encoded into.
+    #[must_use]
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.max_message_size = size;
+        self
+    }
+
+    /// Returns the wrapped provider.
+    #[must_use]
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Splits `entries` into chunk descriptors.
+    ///
+    /// An empty snapshot still yields one (empty) chunk so the requester
+    /// always receives a reply.
+    fn chunks(&self, entries: usize) -> impl Iterator<Item = SnapshotChunk> + use<P> {
+        let per_chunk = self.max_entries_per_chunk;
+        let total = entries.div_ceil(per_chunk).max(1);
+        (0..total).map(move |index| {
+            let start = index * per_chunk;
+            SnapshotChunk {
+                index,
+                total,
+                entries: start..(start + per_chunk).min(entries),
+            }
+        })
+    }
+
+    /// Sends `buffer[..len]`, returning false if the send failed.
+    fn send(&self, session_id: u64, buffer: &[u8], len: usize, responder: &dyn Responder) -> bool {
+        if len > buffer.len() {
+            tracing::warn!(
+                "Snapshot message of {} bytes exceeds buffer of {} bytes for session={}",
+                len,
+                buffer.len(),
+                session_id
+            );
+            return false;
+        }
+        if let Err(e) = responder.send(&buffer[..len]) {
+            tracing::warn!("Failed to send snapshot to session={}: {}", session_id, e);
+            return false;
+        }
+        true
+    }
+}
+
+impl<P: SnapshotProvider> TypedHandler for SnapshotHandler<P> {
+    fn handle(&self, session_id: u64, buffer: &[u8], responder: &dyn Responder) {
+        let mut out = vec![0u8; self.max_message_size];
+
+        let Some(snapshot) = self.provider.snapshot(session_id, buffer) else {
+            let len = self
+                .provider
+                .encode_unavailable(session_id, buffer, &mut out);
+            if len > 0 {
+                self.send(session_id, &out, len, responder);
+            }
+            return;
+        };
+
+        let entries = self.provider.entry_count(&snapshot);
+        for chunk in self.chunks(entries) {
+            out.fill(0);
+            let len = self.provider.encode_chunk(&snapshot, &chunk, &mut out);
+            if !self.send(session_id, &out, len, responder) {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::SendError;
+    use parking_lot::Mutex;
+
+    /// Provider over a fixed list of levels; requests carry the depth to
+    /// serve in their first byte, with 0 meaning "unknown instrument".
+    struct BookProvider {
+        levels: Vec<u8>,
+    }
+
+    impl SnapshotProvider for BookProvider {
+        type Snapshot = Vec<u8>;
+
+        fn snapshot(&self, _session_id: u64, request: &[u8]) -> Option<Vec<u8>> {
+            let depth = *request.first()? as usize;
+            (depth > 0).then(|| self.levels.iter().copied().take(depth).collect())
+        }
+
+        fn entry_count(&self, snapshot: &Vec<u8>) -> usize {
+            snapshot.len()
+        }
+
+        fn encode_chunk(
+            &self,
+            snapshot: &Vec<u8>,
+            chunk: &SnapshotChunk,
+            buffer: &mut [u8],
+        ) -> usize {
+            buffer[0] = chunk.index as u8;
+            buffer[1] = u8::from(chunk.is_last());
+            let levels = &snapshot[chunk.entries.clone()];
+            buffer[2..2 + levels.len()].copy_from_slice(levels);
+            2 + levels.len()
+        }
+
+        fn encode_unavailable(
+            &self,
+            _session_id: u64,
+            _request: &[u8],
+            buffer: &mut [u8],
+        ) -> usize {
+            buffer[0] = 0xFF;
+            1
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingResponder {
+        sent: Mutex<Vec<Vec<u8>>>,
+        fail: bool,
+    }
+
+    impl Responder for RecordingResponder {
+        fn send(&self, message: &[u8]) -> Result<(), SendError> {
+            if self.fail {
+                return Err(SendError {
+                    message: "closed".to_string(),
+                });
+            }
+            self.sent.lock().push(message.to_vec());
+            Ok(())
+        }
+
+        fn send_to(&self, _session_id: u64, message: &[u8]) -> Result<(), SendError> {
+            self.send(message)
+        }
+    }
+
+    fn handler() -> SnapshotHandler<BookProvider> {
+        SnapshotHandler::new(BookProvider {
+            levels: (1..=10).collect(),
+        })
+        .max_entries_per_chunk(4)
+        .max_message_size(64)
+    }
+
+    #[test]
+    fn test_snapshot_chunked_response() {
+        let responder = RecordingResponder::default();
+        handler().handle(1, &[10], &responder);
+
+        let sent = responder.sent.lock();
+        assert_eq!(
+            *sent,
+            vec![
+                vec![0, 0, 1, 2, 3, 4],
+                vec![1, 0, 5, 6, 7, 8],
+                vec![2, 1, 9, 10],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_snapshot_single_chunk() {
+        let responder = RecordingResponder::default();
+        handler().handle(1, &[3], &responder);
+
+        assert_eq!(*responder.sent.lock(), vec![vec![0, 1, 1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_snapshot_empty_book_sends_one_chunk() {
+        let responder = RecordingResponder::default();
+        let handler = SnapshotHandler::new(BookProvider { levels: Vec::new() });
+        handler.handle(1, &[5], &responder);
+
+        assert_eq!(*responder.sent.lock(), vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn test_snapshot_unavailable_reply() {
+        let responder = RecordingResponder::default();
+        handler().handle(1, &[0], &responder);
+
+        assert_eq!(*responder.sent.lock(), vec![vec![0xFF]]);
+    }
+
+    #[test]
+    fn test_snapshot_stops_on_send_error() {
+        let responder = RecordingResponder {
+            fail: true,
+            ..Default::default()
+        };
+        handler().handle(1, &[10], &responder);
+
+        assert!(responder.sent.lock().is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_chunk_is_last() {
+        let chunks: Vec<_> = handler().chunks(8).collect();
+        assert_eq!(chunks.len(), 2);
+        assert!(!chunks[0].is_last());
+        assert!(chunks[1].is_last());
+        assert_eq!(chunks[1].entries, 4..8);
+    }
+
+    #[test]
+    fn test_snapshot_max_entries_per_chunk_clamped() {
+        let handler = handler().max_entries_per_chunk(0);
+        assert_eq!(handler.chunks(3).count(), 3);
+    }
+}
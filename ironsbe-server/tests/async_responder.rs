@@ -0,0 +1,97 @@
+//! End-to-end test for `Responder::async_responder`.
+//!
+//! A handler hands every request to a spawned task that streams many
+//! replies through an `AsyncSessionResponder` with a tiny outbound
+//! capacity. Every send waits for the session writer to drain the queue,
+//! and the client must still receive every reply, in order.
+
+#![cfg(feature = "tcp-tokio")]
+
+use ironsbe_core::header::MessageHeader;
+use ironsbe_server::{
+    AsyncResponder, MessageHandler, Responder, ServerBuilder, ServerEvent, ServerHandle,
+};
+use ironsbe_transport::tcp::TcpServerConfig;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const REPLIES: u16 = 200;
+
+/// Replies to every request with [`REPLIES`] messages from a spawned task.
+struct StreamingHandler;
+
+impl MessageHandler for StreamingHandler {
+    fn on_message(
+        &self,
+        _session_id: u64,
+        _header: &MessageHeader,
+        _buffer: &[u8],
+        responder: &dyn Responder,
+    ) {
+        let responder = responder
+            .async_responder()
+            .expect("Server supports async responders");
+        tokio::spawn(async move {
+            for template_id in 0..REPLIES {
+                let mut reply = vec![0u8; MessageHeader::ENCODED_LENGTH];
+                MessageHeader::new(0, template_id, 1, 1).encode(&mut reply, 0);
+                responder.send(&reply).await.expect("session alive");
+                assert!(responder.queued() <= 2);
+            }
+        });
+    }
+}
+
+async fn wait_for_listening(handle: &Arc<ServerHandle>, deadline: Instant) -> Option<SocketAddr> {
+    while Instant::now() < deadline {
+        for event in handle.poll_events() {
+            if let ServerEvent::Listening(addr) = event {
+                return Some(addr);
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    None
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_async_responder_streams_with_backpressure() {
+    let bind_addr: SocketAddr = "127.0.0.1:0".parse().expect("addr");
+    let (mut server, handle) = ServerBuilder::<StreamingHandler>::new()
+        .bind_config(TcpServerConfig::new(bind_addr))
+        .handler(StreamingHandler)
+        .outbound_capacity(2)
+        .build();
+    let handle = Arc::new(handle);
+    let server_task = tokio::spawn(async move {
+        let _ = server.run().await;
+    });
+
+    let addr = wait_for_listening(&handle, Instant::now() + Duration::from_secs(2))
+        .await
+        .expect("server did not emit Listening within 2 s");
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    let mut request = vec![0u8; 4 + MessageHeader::ENCODED_LENGTH];
+    request[..4].copy_from_slice(&(MessageHeader::ENCODED_LENGTH as u32).to_le_bytes());
+    MessageHeader::new(0, 1, 1, 1).encode(&mut request, 4);
+    stream.write_all(&request).await.expect("write request");
+
+    let read_all = async {
+        for expected in 0..REPLIES {
+            let mut frame = [0u8; 4 + MessageHeader::ENCODED_LENGTH];
+            stream.read_exact(&mut frame).await.expect("read reply");
+            let header = MessageHeader::wrap(&frame[4..], 0);
+            assert_eq!({ header.template_id }, expected);
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(10), read_all)
+        .await
+        .expect("replies did not arrive within 10 s");
+
+    handle.shutdown();
+    let _ = tokio::time::timeout(Duration::from_secs(2), server_task).await;
+}
@@ -0,0 +1,131 @@
+//! Connections beyond `max_connections` wait in the admission queue,
+//! are admitted as sessions end, and are told the server is busy when the
+//! queue is full or their wait times out.
+
+#![cfg(feature = "tcp-tokio")]
+
+use ironsbe_core::header::MessageHeader;
+use ironsbe_server::{
+    AdmissionPolicy, MessageHandler, RejectReason, Responder, ServerBuilder, ServerEvent,
+    ServerHandle,
+};
+use ironsbe_transport::tcp::TcpServerConfig;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+
+struct NoopHandler;
+
+impl MessageHandler for NoopHandler {
+    fn on_message(
+        &self,
+        _session_id: u64,
+        _header: &MessageHeader,
+        _buffer: &[u8],
+        _responder: &dyn Responder,
+    ) {
+    }
+}
+
+const BUSY: &[u8] = b"busy, retry in 1s";
+const QUEUE_TIMEOUT: Duration = Duration::from_millis(300);
+const WAIT: Duration = Duration::from_secs(5);
+
+/// Polls events until `pick` returns a value, or panics after [`WAIT`].
+async fn next_event<T>(handle: &ServerHandle, mut pick: impl FnMut(ServerEvent) -> Option<T>) -> T {
+    let deadline = Instant::now() + WAIT;
+    while Instant::now() < deadline {
+        if let Some(value) = handle.poll_events().find_map(&mut pick) {
+            return value;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    panic!("expected event not seen within {WAIT:?}");
+}
+
+/// Reads one length-prefixed frame and then expects the connection to
+/// be closed.
+async fn read_busy_then_eof(stream: &mut TcpStream) {
+    let mut received = Vec::new();
+    tokio::time::timeout(WAIT, stream.read_to_end(&mut received))
+        .await
+        .expect("connection not closed")
+        .expect("read");
+    let mut expected = (BUSY.len() as u32).to_le_bytes().to_vec();
+    expected.extend_from_slice(BUSY);
+    assert_eq!(received, expected);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_server_queues_and_rejects_beyond_max_connections() {
+    let bind_addr: SocketAddr = "127.0.0.1:0".parse().expect("addr");
+    let (mut server, handle) = ServerBuilder::<NoopHandler>::new()
+        .bind_config(TcpServerConfig::new(bind_addr))
+        .handler(NoopHandler)
+        .max_connections(1)
+        .admission_policy(
+            AdmissionPolicy::new()
+                .queue(1, QUEUE_TIMEOUT)
+                .busy_message(BUSY.to_vec()),
+        )
+        .build();
+    let server_task = tokio::spawn(async move {
+        let _ = server.run().await;
+    });
+    let addr = next_event(&handle, |e| match e {
+        ServerEvent::Listening(addr) => Some(addr),
+        _ => None,
+    })
+    .await;
+
+    // The first client takes the only slot.
+    let first = TcpStream::connect(addr).await.expect("connect");
+    let first_id = next_event(&handle, |e| match e {
+        ServerEvent::SessionCreated(id, _) => Some(id),
+        _ => None,
+    })
+    .await;
+
+    // The second waits, the third finds the queue full.
+    let _second = TcpStream::connect(addr).await.expect("connect");
+    let queued = next_event(&handle, |e| match e {
+        ServerEvent::ConnectionQueued(_, waiting) => Some(waiting),
+        _ => None,
+    })
+    .await;
+    assert_eq!(queued, 1);
+
+    let mut third = TcpStream::connect(addr).await.expect("connect");
+    let reason = next_event(&handle, |e| match e {
+        ServerEvent::ConnectionRejected(_, reason) => Some(reason),
+        _ => None,
+    })
+    .await;
+    assert_eq!(reason, RejectReason::Full);
+    read_busy_then_eof(&mut third).await;
+
+    // Ending the first session admits the waiting one.
+    drop(first);
+    let second_id = next_event(&handle, |e| match e {
+        ServerEvent::SessionCreated(id, _) => Some(id),
+        _ => None,
+    })
+    .await;
+    assert_ne!(second_id, first_id);
+
+    // Nothing frees the slot now, so the next one times out.
+    let mut fourth = TcpStream::connect(addr).await.expect("connect");
+    let queued_at = Instant::now();
+    let reason = next_event(&handle, |e| match e {
+        ServerEvent::ConnectionRejected(_, reason) => Some(reason),
+        _ => None,
+    })
+    .await;
+    assert_eq!(reason, RejectReason::Timeout);
+    assert!(queued_at.elapsed() >= QUEUE_TIMEOUT - Duration::from_millis(50));
+    read_busy_then_eof(&mut fourth).await;
+
+    handle.shutdown();
+    let _ = tokio::time::timeout(Duration::from_secs(2), server_task).await;
+}
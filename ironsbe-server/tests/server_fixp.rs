@@ -0,0 +1,203 @@
+//! FIXP session mode: clients negotiate and establish a session before
+//! exchanging messages, and the session survives reconnects.
+
+#![cfg(feature = "tcp-tokio")]
+
+use ironsbe_client::{ClientBuilder, ClientEvent, ClientHandle};
+use ironsbe_core::header::MessageHeader;
+use ironsbe_server::{MessageHandler, Responder, ServerBuilder, ServerEvent, ServerHandle};
+use ironsbe_session::{
+    NegotiationRejectCode, SessionConfig, SessionEvent, SessionServerConfig, TerminationCode,
+};
+use ironsbe_transport::tcp::{TcpClient, TcpClientConfig, TcpServerConfig};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// Echoes every message back to its sender.
+struct EchoHandler;
+
+impl MessageHandler for EchoHandler {
+    fn on_message(
+        &self,
+        _session_id: u64,
+        _header: &MessageHeader,
+        buffer: &[u8],
+        responder: &dyn Responder,
+    ) {
+        let _ = responder.send(buffer);
+    }
+}
+
+const WAIT: Duration = Duration::from_secs(5);
+
+/// Polls server events until `pick` returns a value, or panics after
+/// [`WAIT`].
+async fn next_event<T>(handle: &ServerHandle, mut pick: impl FnMut(ServerEvent) -> Option<T>) -> T {
+    let deadline = Instant::now() + WAIT;
+    while Instant::now() < deadline {
+        if let Some(value) = handle.poll_events().find_map(&mut pick) {
+            return value;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    panic!("expected event not seen within {WAIT:?}");
+}
+
+/// Waits for client events until `pick` returns a value, collecting the
+/// FIXP events seen on the way.
+async fn next_client_event<T>(
+    handle: &mut ClientHandle,
+    seen: &mut Vec<SessionEvent>,
+    mut pick: impl FnMut(&ClientEvent) -> Option<T>,
+) -> T {
+    tokio::time::timeout(WAIT, async {
+        loop {
+            let event = handle.wait_event().await.expect("client alive");
+            if let ClientEvent::Fixp(event) = &event {
+                seen.push(event.clone());
+            }
+            if let Some(value) = pick(&event) {
+                return value;
+            }
+        }
+    })
+    .await
+    .expect("expected client event")
+}
+
+fn message(template_id: u16) -> Vec<u8> {
+    let mut message = vec![0u8; MessageHeader::ENCODED_LENGTH + 4];
+    message[2..4].copy_from_slice(&template_id.to_le_bytes());
+    message
+}
+
+fn echoed(template_id: u16) -> impl FnMut(&ClientEvent) -> Option<()> {
+    move |e| matches!(e, ClientEvent::Message(m) if *m == message(template_id)).then_some(())
+}
+
+fn established(e: &ClientEvent) -> Option<()> {
+    matches!(e, ClientEvent::Fixp(SessionEvent::Established { .. })).then_some(())
+}
+
+async fn start_server(config: SessionServerConfig) -> (ServerHandle, SocketAddr) {
+    let (mut server, handle) = ServerBuilder::<EchoHandler>::new()
+        .bind_config(TcpServerConfig::new("127.0.0.1:0".parse().unwrap()))
+        .fixp(config)
+        .handler(EchoHandler)
+        .build();
+    tokio::spawn(async move { server.run().await });
+    let addr = next_event(&handle, |e| match e {
+        ServerEvent::Listening(addr) => Some(addr),
+        _ => None,
+    })
+    .await;
+    (handle, addr)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_fixp_session_survives_reconnect_and_finishes() {
+    let config = SessionServerConfig::default().authenticator(|_, creds| creds == b"token");
+    let (server, addr) = start_server(config).await;
+
+    let (mut client, mut handle) = ClientBuilder::with_default_transport(addr)
+        .fixp(
+            SessionConfig::default()
+                .credentials("token")
+                .keepalive_interval(Duration::from_millis(100)),
+        )
+        .reconnect_delay(Duration::from_millis(20))
+        .build();
+    let client_task = tokio::spawn(async move { client.run().await });
+    let mut seen = Vec::new();
+
+    // Sent before the session is established, held until it is.
+    handle.send(message(1)).unwrap();
+    next_client_event(&mut handle, &mut seen, echoed(1)).await;
+    assert!(matches!(
+        seen[..],
+        [
+            SessionEvent::Negotiated { .. },
+            SessionEvent::Established { .. }
+        ]
+    ));
+
+    // Heartbeats keep an idle session alive past the keepalive interval.
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    handle.send(message(2)).unwrap();
+    next_client_event(&mut handle, &mut seen, echoed(2)).await;
+
+    // The server drops the connection; the client re-establishes the same
+    // logical session without negotiating again.
+    let session_id = next_event(&server, |e| match e {
+        ServerEvent::SessionCreated(id, _) => Some(id),
+        _ => None,
+    })
+    .await;
+    server.close_session(session_id);
+    next_client_event(&mut handle, &mut seen, |e| {
+        matches!(e, ClientEvent::Disconnected).then_some(())
+    })
+    .await;
+    seen.clear();
+    next_client_event(&mut handle, &mut seen, established).await;
+    assert_eq!(seen.len(), 1);
+    handle.send(message(3)).unwrap();
+    next_client_event(&mut handle, &mut seen, echoed(3)).await;
+
+    handle.finish_session().unwrap();
+    let result = tokio::time::timeout(WAIT, client_task)
+        .await
+        .expect("client stopped");
+    assert!(result.unwrap().is_ok());
+    next_event(&server, |e| match e {
+        ServerEvent::Fixp(
+            _,
+            SessionEvent::Terminated {
+                code: TerminationCode::Finished,
+            },
+        ) => Some(()),
+        _ => None,
+    })
+    .await;
+    server.shutdown();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_fixp_rejects_bad_credentials() {
+    let config = SessionServerConfig::default().authenticator(|_, creds| creds == b"token");
+    let (server, addr) = start_server(config).await;
+
+    let (mut client, mut handle) = ClientBuilder::with_default_transport(addr)
+        .fixp(SessionConfig::default().credentials("wrong"))
+        .reconnect(false)
+        .build();
+    let client_task = tokio::spawn(async move { client.run().await });
+
+    let mut seen = Vec::new();
+    let code = next_client_event(&mut handle, &mut seen, |e| match e {
+        ClientEvent::Fixp(SessionEvent::NegotiationRejected(code)) => Some(*code),
+        _ => None,
+    })
+    .await;
+    assert_eq!(code, NegotiationRejectCode::Credentials);
+    let result = tokio::time::timeout(WAIT, client_task)
+        .await
+        .expect("client stopped");
+    assert!(result.unwrap().is_err());
+    server.shutdown();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_fixp_closes_connections_that_skip_establishment() {
+    let (server, addr) = start_server(SessionServerConfig::default()).await;
+
+    let mut raw = TcpClient::connect(TcpClientConfig::new(addr))
+        .await
+        .expect("connect");
+    raw.send(&message(1)).await.unwrap();
+    let reply = tokio::time::timeout(WAIT, raw.recv())
+        .await
+        .expect("connection closed");
+    assert!(!matches!(reply, Ok(Some(_))));
+    server.shutdown();
+}
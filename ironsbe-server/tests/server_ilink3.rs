@@ -0,0 +1,132 @@
+//! CME iLink 3 sessions: a client connects to a local gateway speaking the
+//! iLink 3 session schema over the compact SOFH.
+
+#![cfg(feature = "tcp-tokio")]
+
+use ironsbe_client::{ClientBuilder, ClientEvent, ClientHandle};
+use ironsbe_core::header::MessageHeader;
+use ironsbe_server::{MessageHandler, Responder, ServerBuilder, ServerEvent, ServerHandle};
+use ironsbe_session::ilink3::ILINK3_SCHEMA_ID;
+use ironsbe_session::{
+    ILink3Codec, ILink3Credentials, SessionEvent, SessionServerConfig, TerminationCode,
+};
+use ironsbe_transport::tcp::TcpServerConfig;
+use ironsbe_transport::tcp::framing::Framing;
+use std::time::{Duration, Instant};
+
+/// Echoes every message back to its sender.
+struct EchoHandler;
+
+impl MessageHandler for EchoHandler {
+    fn on_message(
+        &self,
+        _session_id: u64,
+        _header: &MessageHeader,
+        buffer: &[u8],
+        responder: &dyn Responder,
+    ) {
+        let _ = responder.send(buffer);
+    }
+}
+
+const WAIT: Duration = Duration::from_secs(5);
+
+async fn next_event<T>(handle: &ServerHandle, mut pick: impl FnMut(ServerEvent) -> Option<T>) -> T {
+    let deadline = Instant::now() + WAIT;
+    while Instant::now() < deadline {
+        if let Some(value) = handle.poll_events().find_map(&mut pick) {
+            return value;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    panic!("expected event not seen within {WAIT:?}");
+}
+
+async fn next_client_event<T>(
+    handle: &mut ClientHandle,
+    mut pick: impl FnMut(&ClientEvent) -> Option<T>,
+) -> T {
+    tokio::time::timeout(WAIT, async {
+        loop {
+            let event = handle.wait_event().await.expect("client alive");
+            if let Some(value) = pick(&event) {
+                return value;
+            }
+        }
+    })
+    .await
+    .expect("expected client event")
+}
+
+fn codec() -> ILink3Codec {
+    ILink3Codec::new(ILink3Credentials::new(
+        "ACCESSKEYID123456789",
+        "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8",
+        "ABC",
+        "FIRM1",
+    ))
+    .unwrap()
+}
+
+/// An application message in the iLink 3 schema.
+fn new_order() -> Vec<u8> {
+    let mut message = vec![0u8; MessageHeader::ENCODED_LENGTH + 4];
+    MessageHeader::new(4, 514, ILINK3_SCHEMA_ID, 8).encode(&mut message[..], 0);
+    message
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_ilink3_client_establishes_and_logs_out() {
+    let (mut server, server_handle) = ServerBuilder::<EchoHandler>::new()
+        .bind_config(
+            TcpServerConfig::new("127.0.0.1:0".parse().unwrap()).framing(Framing::compact_sofh()),
+        )
+        .fixp(SessionServerConfig::default().codec(codec()))
+        .handler(EchoHandler)
+        .build();
+    tokio::spawn(async move { server.run().await });
+    let addr = next_event(&server_handle, |e| match e {
+        ServerEvent::Listening(addr) => Some(addr),
+        _ => None,
+    })
+    .await;
+
+    let codec = codec();
+    let (mut client, mut handle) = ClientBuilder::with_default_transport(addr)
+        .ilink3(&codec)
+        .reconnect(false)
+        .build();
+    let client_task = tokio::spawn(async move { client.run().await });
+
+    next_client_event(&mut handle, |e| {
+        matches!(e, ClientEvent::Fixp(SessionEvent::Established { .. })).then_some(())
+    })
+    .await;
+    let uuid = codec.status().uuid;
+    assert_ne!(uuid, 0);
+
+    handle.send(new_order()).unwrap();
+    next_client_event(&mut handle, |e| {
+        matches!(e, ClientEvent::Message(m) if *m == new_order()).then_some(())
+    })
+    .await;
+
+    // Finishing is a Terminate, answered by the gateway.
+    handle.finish_session().unwrap();
+    let result = tokio::time::timeout(WAIT, client_task)
+        .await
+        .expect("client stopped");
+    assert!(result.unwrap().is_ok());
+    next_event(&server_handle, |e| match e {
+        ServerEvent::Fixp(
+            _,
+            SessionEvent::Terminated {
+                code: TerminationCode::Finished,
+            },
+        ) => Some(()),
+        _ => None,
+    })
+    .await;
+    assert!(codec.status().last_reject.is_none());
+    server_handle.shutdown();
+}
@@ -0,0 +1,105 @@
+//! WebSocket clients share the server's sessions and handler with clients
+//! of the main TCP transport.
+
+#![cfg(all(feature = "tcp-tokio", feature = "ws"))]
+
+use ironsbe_core::header::MessageHeader;
+use ironsbe_server::{MessageHandler, Responder, ServerBuilder, ServerEvent, ServerHandle};
+use ironsbe_transport::tcp::{TcpClient, TcpClientConfig, TcpServerConfig};
+use ironsbe_transport::ws::{WsClientConfig, WsConnection};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// Echoes every message back to its sender.
+struct EchoHandler;
+
+impl MessageHandler for EchoHandler {
+    fn on_message(
+        &self,
+        _session_id: u64,
+        _header: &MessageHeader,
+        buffer: &[u8],
+        responder: &dyn Responder,
+    ) {
+        let _ = responder.send(buffer);
+    }
+}
+
+const WAIT: Duration = Duration::from_secs(5);
+
+/// Polls events until `pick` returns a value, or panics after [`WAIT`].
+async fn next_event<T>(handle: &ServerHandle, mut pick: impl FnMut(ServerEvent) -> Option<T>) -> T {
+    let deadline = Instant::now() + WAIT;
+    while Instant::now() < deadline {
+        if let Some(value) = handle.poll_events().find_map(&mut pick) {
+            return value;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    panic!("expected event not seen within {WAIT:?}");
+}
+
+fn message(template_id: u16) -> Vec<u8> {
+    let mut message = vec![0u8; MessageHeader::ENCODED_LENGTH + 4];
+    message[2..4].copy_from_slice(&template_id.to_le_bytes());
+    message
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_server_serves_tcp_and_ws_clients() {
+    let any: SocketAddr = "127.0.0.1:0".parse().expect("addr");
+    let (mut server, handle) = ServerBuilder::<EchoHandler>::new()
+        .bind_config(TcpServerConfig::new(any))
+        .listen_ws(any)
+        .handler(EchoHandler)
+        .build();
+    let server_task = tokio::spawn(async move { server.run().await });
+
+    let tcp_addr = next_event(&handle, |e| match e {
+        ServerEvent::Listening(addr) => Some(addr),
+        _ => None,
+    })
+    .await;
+    let ws_addr = next_event(&handle, |e| match e {
+        ServerEvent::ListeningWs(addr) => Some(addr),
+        _ => None,
+    })
+    .await;
+    assert_ne!(tcp_addr, ws_addr);
+
+    let mut tcp = TcpClient::connect(TcpClientConfig::new(tcp_addr))
+        .await
+        .expect("tcp connect");
+    let mut ws = WsConnection::connect(WsClientConfig::new(ws_addr).path("/feed"))
+        .await
+        .expect("ws connect");
+    for _ in 0..2 {
+        next_event(&handle, |e| match e {
+            ServerEvent::SessionCreated(id, _) => Some(id),
+            _ => None,
+        })
+        .await;
+    }
+
+    tcp.send(&message(1)).await.unwrap();
+    assert_eq!(tcp.recv().await.unwrap().unwrap()[..], message(1)[..]);
+    ws.send(&message(2)).await.unwrap();
+    assert_eq!(ws.recv().await.unwrap().unwrap()[..], message(2)[..]);
+
+    handle.broadcast(message(3));
+    assert_eq!(tcp.recv().await.unwrap().unwrap()[..], message(3)[..]);
+    assert_eq!(ws.recv().await.unwrap().unwrap()[..], message(3)[..]);
+
+    ws.close().await.unwrap();
+    next_event(&handle, |e| match e {
+        ServerEvent::SessionClosed(id) => Some(id),
+        _ => None,
+    })
+    .await;
+
+    handle.shutdown();
+    let result = tokio::time::timeout(Duration::from_secs(2), server_task)
+        .await
+        .expect("server stopped");
+    assert!(result.unwrap().is_ok());
+}
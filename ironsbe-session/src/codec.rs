@@ -0,0 +1,514 @@
+//! Wire encoding of session messages.
+//!
+//! [`SessionCodec`] maps [`SessionMessage`]s to frames and tells session
+//! messages apart from application messages on the same connection.
+//! [`FixpCodec`] is IronSBE's SBE encoding of the FIXP messages; venues
+//! with their own session schema supply another codec.
+
+use crate::error::SessionError;
+use crate::messages::{
+    Establish, EstablishmentAck, EstablishmentReject, EstablishmentRejectCode, FinishedReceiving,
+    FinishedSending, FlowType, Negotiate, NegotiationReject, NegotiationRejectCode,
+    NegotiationResponse, NotApplied, Retransmission, RetransmitReject, RetransmitRejectCode,
+    RetransmitRequest, SessionId, SessionMessage, Terminate, TerminationCode,
+};
+use ironsbe_core::header::MessageHeader;
+
+/// Schema ID of [`FixpCodec`] session messages.
+pub const FIXP_SCHEMA_ID: u16 = 0xFE02;
+
+/// Schema version of [`FixpCodec`] session messages.
+pub const FIXP_SCHEMA_VERSION: u16 = 1;
+
+/// Template IDs of [`FixpCodec`] session messages.
+pub mod template {
+    /// `Negotiate`.
+    pub const NEGOTIATE: u16 = 1;
+    /// `NegotiationResponse`.
+    pub const NEGOTIATION_RESPONSE: u16 = 2;
+    /// `NegotiationReject`.
+    pub const NEGOTIATION_REJECT: u16 = 3;
+    /// `Establish`.
+    pub const ESTABLISH: u16 = 4;
+    /// `EstablishmentAck`.
+    pub const ESTABLISHMENT_ACK: u16 = 5;
+    /// `EstablishmentReject`.
+    pub const ESTABLISHMENT_REJECT: u16 = 6;
+    /// `Sequence`.
+    pub const SEQUENCE: u16 = 7;
+    /// `RetransmitRequest`.
+    pub const RETRANSMIT_REQUEST: u16 = 8;
+    /// `Retransmission`.
+    pub const RETRANSMISSION: u16 = 9;
+    /// `RetransmitReject`.
+    pub const RETRANSMIT_REJECT: u16 = 10;
+    /// `Terminate`.
+    pub const TERMINATE: u16 = 11;
+    /// `FinishedSending`.
+    pub const FINISHED_SENDING: u16 = 12;
+    /// `FinishedReceiving`.
+    pub const FINISHED_RECEIVING: u16 = 13;
+    /// `NotApplied`.
+    pub const NOT_APPLIED: u16 = 14;
+}
+
+/// Encodes and recognises session messages.
+pub trait SessionCodec: Send + Sync + 'static {
+    /// Encodes `message` as one frame.
+    fn encode(&self, message: &SessionMessage) -> Vec<u8>;
+
+    /// Decodes a received frame.
+    ///
+    /// Returns `Ok(None)` if the frame is an application message.
+    ///
+    /// # Errors
+    /// Returns [`SessionError::Decode`] if the frame belongs to the session
+    /// schema but cannot be decoded.
+    fn decode(&self, frame: &[u8]) -> Result<Option<SessionMessage>, SessionError>;
+
+    /// Returns the most messages one RetransmitRequest may ask for; longer
+    /// gaps are requested in several parts.
+    fn max_retransmit_count(&self) -> u32 {
+        u32::MAX
+    }
+}
+
+/// SBE encoding of the FIXP session messages.
+///
+/// Every message has a standard 8-byte header under the codec's schema ID,
+/// which is how session messages are told apart from application messages.
+/// Blocks hold fixed fields in declaration order, little-endian, with
+/// session ids as 16 UUID bytes; credentials follow as variable-length data
+/// with a `u16` length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixpCodec {
+    schema_id: u16,
+}
+
+impl FixpCodec {
+    /// Creates a codec for session messages under `schema_id`.
+    #[must_use]
+    pub const fn new(schema_id: u16) -> Self {
+        Self { schema_id }
+    }
+
+    /// Returns the schema ID of session messages.
+    #[must_use]
+    pub const fn schema_id(&self) -> u16 {
+        self.schema_id
+    }
+}
+
+impl Default for FixpCodec {
+    fn default() -> Self {
+        Self::new(FIXP_SCHEMA_ID)
+    }
+}
+
+impl SessionCodec for FixpCodec {
+    fn encode(&self, message: &SessionMessage) -> Vec<u8> {
+        let mut w = Writer::default();
+        let template_id = match message {
+            SessionMessage::Negotiate(m) => {
+                w.id(m.session_id).u64(m.timestamp).u8(m.client_flow as u8);
+                template::NEGOTIATE
+            }
+            SessionMessage::NegotiationResponse(m) => {
+                w.id(m.session_id)
+                    .u64(m.request_timestamp)
+                    .u8(m.server_flow as u8);
+                template::NEGOTIATION_RESPONSE
+            }
+            SessionMessage::NegotiationReject(m) => {
+                w.id(m.session_id).u64(m.request_timestamp).u8(m.code as u8);
+                template::NEGOTIATION_REJECT
+            }
+            SessionMessage::Establish(m) => {
+                w.id(m.session_id)
+                    .u64(m.timestamp)
+                    .u32(m.keepalive_interval)
+                    .u64(m.next_seq_no);
+                template::ESTABLISH
+            }
+            SessionMessage::EstablishmentAck(m) => {
+                w.id(m.session_id)
+                    .u64(m.request_timestamp)
+                    .u32(m.keepalive_interval)
+                    .u64(m.next_seq_no);
+                template::ESTABLISHMENT_ACK
+            }
+            SessionMessage::EstablishmentReject(m) => {
+                w.id(m.session_id).u64(m.request_timestamp).u8(m.code as u8);
+                template::ESTABLISHMENT_REJECT
+            }
+            SessionMessage::Sequence { next_seq_no } => {
+                w.u64(*next_seq_no);
+                template::SEQUENCE
+            }
+            SessionMessage::RetransmitRequest(m) => {
+                w.id(m.session_id)
+                    .u64(m.timestamp)
+                    .u64(m.from_seq_no)
+                    .u32(m.count);
+                template::RETRANSMIT_REQUEST
+            }
+            SessionMessage::Retransmission(m) => {
+                w.id(m.session_id)
+                    .u64(m.request_timestamp)
+                    .u64(m.next_seq_no)
+                    .u32(m.count);
+                template::RETRANSMISSION
+            }
+            SessionMessage::RetransmitReject(m) => {
+                w.id(m.session_id).u64(m.request_timestamp).u8(m.code as u8);
+                template::RETRANSMIT_REJECT
+            }
+            SessionMessage::NotApplied(m) => {
+                w.u64(m.from_seq_no).u32(m.count);
+                template::NOT_APPLIED
+            }
+            SessionMessage::Terminate(m) => {
+                w.id(m.session_id).u8(m.code as u8);
+                template::TERMINATE
+            }
+            SessionMessage::FinishedSending(m) => {
+                w.id(m.session_id).u64(m.last_seq_no);
+                template::FINISHED_SENDING
+            }
+            SessionMessage::FinishedReceiving(m) => {
+                w.id(m.session_id);
+                template::FINISHED_RECEIVING
+            }
+        };
+        let block_length = w.0.len() as u16;
+        match message {
+            SessionMessage::Negotiate(Negotiate { credentials, .. })
+            | SessionMessage::Establish(Establish { credentials, .. }) => {
+                w.var_data(credentials);
+            }
+            _ => {}
+        }
+
+        let mut frame = vec![0u8; MessageHeader::ENCODED_LENGTH];
+        MessageHeader::new(
+            block_length,
+            template_id,
+            self.schema_id,
+            FIXP_SCHEMA_VERSION,
+        )
+        .encode(&mut frame[..], 0);
+        frame.extend_from_slice(&w.0);
+        frame
+    }
+
+    fn decode(&self, frame: &[u8]) -> Result<Option<SessionMessage>, SessionError> {
+        let Some(header) = MessageHeader::try_wrap(frame, 0) else {
+            return Ok(None);
+        };
+        if header.schema_id != self.schema_id {
+            return Ok(None);
+        }
+        let template_id = header.template_id;
+        let body = &frame[MessageHeader::ENCODED_LENGTH..];
+        let block_length = usize::from(header.block_length);
+        if body.len() < block_length {
+            return Err(SessionError::decode(template_id, "truncated block"));
+        }
+        let mut r = Reader::new(&body[..block_length], template_id);
+        // Variable-length data follows the block, whatever its length.
+        let mut var = Reader::new(&body[block_length..], template_id);
+        let message = match template_id {
+            template::NEGOTIATE => SessionMessage::Negotiate(Negotiate {
+                session_id: r.id()?,
+                timestamp: r.u64()?,
+                client_flow: r.flow()?,
+                credentials: var.var_data()?,
+            }),
+            template::NEGOTIATION_RESPONSE => {
+                SessionMessage::NegotiationResponse(NegotiationResponse {
+                    session_id: r.id()?,
+                    request_timestamp: r.u64()?,
+                    server_flow: r.flow()?,
+                })
+            }
+            template::NEGOTIATION_REJECT => SessionMessage::NegotiationReject(NegotiationReject {
+                session_id: r.id()?,
+                request_timestamp: r.u64()?,
+                code: NegotiationRejectCode::from_u8(r.u8()?),
+            }),
+            template::ESTABLISH => SessionMessage::Establish(Establish {
+                session_id: r.id()?,
+                timestamp: r.u64()?,
+                keepalive_interval: r.u32()?,
+                next_seq_no: r.u64()?,
+                credentials: var.var_data()?,
+            }),
+            template::ESTABLISHMENT_ACK => SessionMessage::EstablishmentAck(EstablishmentAck {
+                session_id: r.id()?,
+                request_timestamp: r.u64()?,
+                keepalive_interval: r.u32()?,
+                next_seq_no: r.u64()?,
+            }),
+            template::ESTABLISHMENT_REJECT => {
+                SessionMessage::EstablishmentReject(EstablishmentReject {
+                    session_id: r.id()?,
+                    request_timestamp: r.u64()?,
+                    code: EstablishmentRejectCode::from_u8(r.u8()?),
+                })
+            }
+            template::SEQUENCE => SessionMessage::Sequence {
+                next_seq_no: r.u64()?,
+            },
+            template::RETRANSMIT_REQUEST => SessionMessage::RetransmitRequest(RetransmitRequest {
+                session_id: r.id()?,
+                timestamp: r.u64()?,
+                from_seq_no: r.u64()?,
+                count: r.u32()?,
+            }),
+            template::RETRANSMISSION => SessionMessage::Retransmission(Retransmission {
+                session_id: r.id()?,
+                request_timestamp: r.u64()?,
+                next_seq_no: r.u64()?,
+                count: r.u32()?,
+            }),
+            template::RETRANSMIT_REJECT => SessionMessage::RetransmitReject(RetransmitReject {
+                session_id: r.id()?,
+                request_timestamp: r.u64()?,
+                code: RetransmitRejectCode::from_u8(r.u8()?),
+            }),
+            template::NOT_APPLIED => SessionMessage::NotApplied(NotApplied {
+                from_seq_no: r.u64()?,
+                count: r.u32()?,
+            }),
+            template::TERMINATE => SessionMessage::Terminate(Terminate {
+                session_id: r.id()?,
+                code: TerminationCode::from_u8(r.u8()?),
+            }),
+            template::FINISHED_SENDING => SessionMessage::FinishedSending(FinishedSending {
+                session_id: r.id()?,
+                last_seq_no: r.u64()?,
+            }),
+            template::FINISHED_RECEIVING => SessionMessage::FinishedReceiving(FinishedReceiving {
+                session_id: r.id()?,
+            }),
+            _ => return Err(SessionError::decode(template_id, "unknown template")),
+        };
+        Ok(Some(message))
+    }
+}
+
+/// Appends little-endian fields to a block.
+#[derive(Default)]
+pub(crate) struct Writer(pub(crate) Vec<u8>);
+
+impl Writer {
+    pub(crate) fn u8(&mut self, value: u8) -> &mut Self {
+        self.0.push(value);
+        self
+    }
+
+    #[cfg_attr(not(feature = "ilink3"), allow(dead_code))]
+    pub(crate) fn u16(&mut self, value: u16) -> &mut Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) -> &mut Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) -> &mut Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    /// Appends `data` as a fixed-length field of `len` bytes, truncated or
+    /// padded with zeros.
+    #[cfg_attr(not(feature = "ilink3"), allow(dead_code))]
+    pub(crate) fn fixed(&mut self, data: &[u8], len: usize) -> &mut Self {
+        let used = data.len().min(len);
+        self.0.extend_from_slice(&data[..used]);
+        self.0.resize(self.0.len() + len - used, 0);
+        self
+    }
+
+    fn id(&mut self, id: SessionId) -> &mut Self {
+        self.0.extend_from_slice(&id.to_bytes());
+        self
+    }
+
+    pub(crate) fn var_data(&mut self, data: &[u8]) -> &mut Self {
+        let len = data.len().min(usize::from(u16::MAX));
+        self.0.extend_from_slice(&(len as u16).to_le_bytes());
+        self.0.extend_from_slice(&data[..len]);
+        self
+    }
+}
+
+/// Reads little-endian fields from a block, failing on truncation.
+pub(crate) struct Reader<'a> {
+    buf: &'a [u8],
+    template_id: u16,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) const fn new(buf: &'a [u8], template_id: u16) -> Self {
+        Self { buf, template_id }
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], SessionError> {
+        if self.buf.len() < len {
+            return Err(SessionError::decode(self.template_id, "truncated field"));
+        }
+        let (head, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, SessionError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, SessionError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, SessionError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, SessionError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn id(&mut self) -> Result<SessionId, SessionError> {
+        Ok(SessionId::from_bytes(self.take(16)?.try_into().unwrap()))
+    }
+
+    fn flow(&mut self) -> Result<FlowType, SessionError> {
+        let value = self.u8()?;
+        FlowType::from_u8(value).ok_or_else(|| SessionError::decode(self.template_id, "flow type"))
+    }
+
+    pub(crate) fn var_data(&mut self) -> Result<Vec<u8>, SessionError> {
+        let len = usize::from(self.u16()?);
+        Ok(self.take(len)?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(message: SessionMessage) {
+        let codec = FixpCodec::default();
+        let frame = codec.encode(&message);
+        assert_eq!(codec.decode(&frame).unwrap(), Some(message));
+    }
+
+    #[test]
+    fn test_every_message_round_trips() {
+        let session_id = SessionId::generate();
+        round_trip(SessionMessage::Negotiate(Negotiate {
+            session_id,
+            timestamp: 1,
+            client_flow: FlowType::Recoverable,
+            credentials: b"user:secret".to_vec(),
+        }));
+        round_trip(SessionMessage::NegotiationResponse(NegotiationResponse {
+            session_id,
+            request_timestamp: 1,
+            server_flow: FlowType::Idempotent,
+        }));
+        round_trip(SessionMessage::NegotiationReject(NegotiationReject {
+            session_id,
+            request_timestamp: 1,
+            code: NegotiationRejectCode::DuplicateId,
+        }));
+        round_trip(SessionMessage::Establish(Establish {
+            session_id,
+            timestamp: 2,
+            keepalive_interval: 1000,
+            next_seq_no: 7,
+            credentials: Vec::new(),
+        }));
+        round_trip(SessionMessage::EstablishmentAck(EstablishmentAck {
+            session_id,
+            request_timestamp: 2,
+            keepalive_interval: 1000,
+            next_seq_no: 9,
+        }));
+        round_trip(SessionMessage::EstablishmentReject(EstablishmentReject {
+            session_id,
+            request_timestamp: 2,
+            code: EstablishmentRejectCode::Unnegotiated,
+        }));
+        round_trip(SessionMessage::Sequence { next_seq_no: 42 });
+        round_trip(SessionMessage::RetransmitRequest(RetransmitRequest {
+            session_id,
+            timestamp: 3,
+            from_seq_no: 5,
+            count: 2,
+        }));
+        round_trip(SessionMessage::Retransmission(Retransmission {
+            session_id,
+            request_timestamp: 3,
+            next_seq_no: 5,
+            count: 2,
+        }));
+        round_trip(SessionMessage::RetransmitReject(RetransmitReject {
+            session_id,
+            request_timestamp: 3,
+            code: RetransmitRejectCode::OutOfRange,
+        }));
+        round_trip(SessionMessage::NotApplied(NotApplied {
+            from_seq_no: 4,
+            count: 3,
+        }));
+        round_trip(SessionMessage::Terminate(Terminate {
+            session_id,
+            code: TerminationCode::KeepaliveLapsed,
+        }));
+        round_trip(SessionMessage::FinishedSending(FinishedSending {
+            session_id,
+            last_seq_no: 8,
+        }));
+        round_trip(SessionMessage::FinishedReceiving(FinishedReceiving {
+            session_id,
+        }));
+    }
+
+    #[test]
+    fn test_application_messages_pass_through() {
+        let codec = FixpCodec::default();
+        let mut application = [0u8; 16];
+        MessageHeader::new(8, template::NEGOTIATE, 1, 1).encode(&mut application[..], 0);
+        assert_eq!(codec.decode(&application).unwrap(), None);
+        assert_eq!(codec.decode(b"short").unwrap(), None);
+    }
+
+    #[test]
+    fn test_truncated_session_messages_fail() {
+        let codec = FixpCodec::default();
+        let frame = codec.encode(&SessionMessage::Sequence { next_seq_no: 1 });
+        assert!(codec.decode(&frame[..frame.len() - 1]).is_err());
+
+        let frame = codec.encode(&SessionMessage::Negotiate(Negotiate {
+            session_id: SessionId(1),
+            timestamp: 1,
+            client_flow: FlowType::Recoverable,
+            credentials: b"abc".to_vec(),
+        }));
+        assert!(codec.decode(&frame[..frame.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_schema_id_is_configurable() {
+        let codec = FixpCodec::new(77);
+        let frame = codec.encode(&SessionMessage::Sequence { next_seq_no: 1 });
+        let header = MessageHeader::wrap(&frame[..], 0);
+        assert_eq!({ header.schema_id }, 77);
+        assert_eq!(FixpCodec::default().decode(&frame).unwrap(), None);
+    }
+}
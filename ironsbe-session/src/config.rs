@@ -0,0 +1,185 @@
+//! Client and server session configuration.
+
+use crate::codec::{FixpCodec, SessionCodec};
+use crate::messages::{FlowType, SessionId};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default keepalive interval requested by clients.
+pub const DEFAULT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Default longest keepalive interval a server accepts.
+pub const DEFAULT_MAX_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default number of sent application messages kept for retransmission.
+pub const DEFAULT_RETRANSMIT_CAPACITY: usize = 10_000;
+
+/// Checks the credentials of a Negotiate or Establish.
+pub type Authenticator = Arc<dyn Fn(SessionId, &[u8]) -> bool + Send + Sync>;
+
+/// Configuration of the client side of a session.
+#[derive(Clone)]
+pub struct SessionConfig {
+    /// Logical session id, kept across reconnects.
+    pub session_id: SessionId,
+    /// Credentials sent with Negotiate and Establish.
+    pub credentials: Vec<u8>,
+    /// Requested keepalive interval.
+    pub keepalive_interval: Duration,
+    /// Flow type of client-to-server messages.
+    pub flow_type: FlowType,
+    /// Number of sent application messages kept for retransmission.
+    pub retransmit_capacity: usize,
+    /// Wire encoding of session messages.
+    pub codec: Arc<dyn SessionCodec>,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            session_id: SessionId::generate(),
+            credentials: Vec::new(),
+            keepalive_interval: DEFAULT_KEEPALIVE_INTERVAL,
+            flow_type: FlowType::Recoverable,
+            retransmit_capacity: DEFAULT_RETRANSMIT_CAPACITY,
+            codec: Arc::new(FixpCodec::default()),
+        }
+    }
+}
+
+impl fmt::Debug for SessionConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionConfig")
+            .field("session_id", &self.session_id)
+            .field("keepalive_interval", &self.keepalive_interval)
+            .field("flow_type", &self.flow_type)
+            .field("retransmit_capacity", &self.retransmit_capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SessionConfig {
+    /// Creates a configuration with a freshly generated session id.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the logical session id.
+    #[must_use]
+    pub fn session_id(mut self, id: SessionId) -> Self {
+        self.session_id = id;
+        self
+    }
+
+    /// Sets the credentials sent with Negotiate and Establish.
+    #[must_use]
+    pub fn credentials(mut self, credentials: impl Into<Vec<u8>>) -> Self {
+        self.credentials = credentials.into();
+        self
+    }
+
+    /// Sets the requested keepalive interval.
+    #[must_use]
+    pub fn keepalive_interval(mut self, interval: Duration) -> Self {
+        self.keepalive_interval = interval;
+        self
+    }
+
+    /// Sets the flow type of client-to-server messages.
+    #[must_use]
+    pub fn flow_type(mut self, flow_type: FlowType) -> Self {
+        self.flow_type = flow_type;
+        self
+    }
+
+    /// Sets the number of sent messages kept for retransmission.
+    #[must_use]
+    pub fn retransmit_capacity(mut self, capacity: usize) -> Self {
+        self.retransmit_capacity = capacity;
+        self
+    }
+
+    /// Sets the wire encoding of session messages.
+    #[must_use]
+    pub fn codec(mut self, codec: impl SessionCodec) -> Self {
+        self.codec = Arc::new(codec);
+        self
+    }
+}
+
+/// Configuration of the server side of sessions.
+#[derive(Clone)]
+pub struct SessionServerConfig {
+    /// Credentials check; every client is accepted if unset.
+    pub authenticator: Option<Authenticator>,
+    /// Longest keepalive interval accepted from clients.
+    pub max_keepalive_interval: Duration,
+    /// Number of sent application messages kept per session for
+    /// retransmission.
+    pub retransmit_capacity: usize,
+    /// Wire encoding of session messages.
+    pub codec: Arc<dyn SessionCodec>,
+}
+
+impl Default for SessionServerConfig {
+    fn default() -> Self {
+        Self {
+            authenticator: None,
+            max_keepalive_interval: DEFAULT_MAX_KEEPALIVE_INTERVAL,
+            retransmit_capacity: DEFAULT_RETRANSMIT_CAPACITY,
+            codec: Arc::new(FixpCodec::default()),
+        }
+    }
+}
+
+impl fmt::Debug for SessionServerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionServerConfig")
+            .field("authenticator", &self.authenticator.is_some())
+            .field("max_keepalive_interval", &self.max_keepalive_interval)
+            .field("retransmit_capacity", &self.retransmit_capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SessionServerConfig {
+    /// Creates a configuration accepting every client.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the credentials check.
+    #[must_use]
+    pub fn authenticator<F>(mut self, authenticator: F) -> Self
+    where
+        F: Fn(SessionId, &[u8]) -> bool + Send + Sync + 'static,
+    {
+        self.authenticator = Some(Arc::new(authenticator));
+        self
+    }
+
+    /// Sets the longest keepalive interval accepted from clients.
+    #[must_use]
+    pub fn max_keepalive_interval(mut self, interval: Duration) -> Self {
+        self.max_keepalive_interval = interval;
+        self
+    }
+
+    /// Sets the number of sent messages kept per session for
+    /// retransmission.
+    #[must_use]
+    pub fn retransmit_capacity(mut self, capacity: usize) -> Self {
+        self.retransmit_capacity = capacity;
+        self
+    }
+
+    /// Sets the wire encoding of session messages.
+    #[must_use]
+    pub fn codec(mut self, codec: impl SessionCodec) -> Self {
+        self.codec = Arc::new(codec);
+        self
+    }
+}
@@ -0,0 +1,34 @@
+//! Error types for session operations.
+
+use thiserror::Error;
+
+/// Error type for session operations.
+#[derive(Debug, Error)]
+pub enum SessionError {
+    /// A session message could not be decoded.
+    #[error("cannot decode session message {template_id}: {reason}")]
+    Decode {
+        /// Template ID of the message.
+        template_id: u16,
+        /// What was wrong with it.
+        reason: &'static str,
+    },
+
+    /// An application message was sent or received outside an established
+    /// session.
+    #[error("session is not established")]
+    NotEstablished,
+
+    /// Session credentials are malformed.
+    #[error("invalid credentials: {0}")]
+    Credentials(&'static str),
+}
+
+impl SessionError {
+    pub(crate) const fn decode(template_id: u16, reason: &'static str) -> Self {
+        Self::Decode {
+            template_id,
+            reason,
+        }
+    }
+}
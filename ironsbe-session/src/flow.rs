@@ -0,0 +1,303 @@
+//! Sequenced flows and the server's registry of logical sessions.
+
+use crate::config::SessionServerConfig;
+use crate::messages::{EstablishmentRejectCode, FlowType, NegotiationRejectCode, SessionId};
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Sequencing state of a logical session, kept across reconnects.
+#[derive(Debug)]
+pub(crate) struct Flow {
+    /// Sequence number of the next application message sent.
+    pub(crate) next_outbound: u64,
+    /// One past the highest sequence number received.
+    pub(crate) next_inbound: u64,
+    /// Flow type of the peer's messages.
+    pub(crate) peer_flow: FlowType,
+    /// Inbound ranges not received yet.
+    pub(crate) missing: Vec<Range<u64>>,
+    /// Sent application messages, ending at `next_outbound - 1`.
+    sent: VecDeque<Vec<u8>>,
+    capacity: usize,
+}
+
+impl Flow {
+    pub(crate) fn new(peer_flow: FlowType, capacity: usize) -> Self {
+        Self {
+            next_outbound: 1,
+            next_inbound: 1,
+            peer_flow,
+            missing: Vec::new(),
+            sent: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Assigns the next sequence number to `frame` and keeps a copy for
+    /// retransmission.
+    pub(crate) fn record(&mut self, frame: &[u8]) -> u64 {
+        let seq = self.next_outbound;
+        self.next_outbound += 1;
+        if self.capacity > 0 {
+            if self.sent.len() == self.capacity {
+                self.sent.pop_front();
+            }
+            self.sent.push_back(frame.to_vec());
+        }
+        seq
+    }
+
+    /// Returns the sent messages `from..from + count`, if all are still
+    /// kept.
+    pub(crate) fn sent_range(&self, from: u64, count: u32) -> Option<Vec<Vec<u8>>> {
+        let first = self.next_outbound - self.sent.len() as u64;
+        let end = from.checked_add(u64::from(count))?;
+        if count == 0 || from < first || end > self.next_outbound {
+            return None;
+        }
+        let start = (from - first) as usize;
+        Some(
+            self.sent
+                .range(start..start + count as usize)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Accounts for a received application message, returning whether it
+    /// should be delivered and the range skipped before it, if any.
+    pub(crate) fn receive(&mut self, seq: u64) -> (bool, Option<Range<u64>>) {
+        if seq >= self.next_inbound {
+            let gap = self.advance(seq);
+            self.next_inbound = seq + 1;
+            (true, gap)
+        } else {
+            (self.take_missing(seq), None)
+        }
+    }
+
+    /// Moves the next expected sequence number up to `next`, returning the
+    /// range skipped, if any.
+    pub(crate) fn advance(&mut self, next: u64) -> Option<Range<u64>> {
+        if next <= self.next_inbound {
+            return None;
+        }
+        let gap = self.next_inbound..next;
+        if self.peer_flow == FlowType::Recoverable {
+            self.missing.push(gap.clone());
+        }
+        self.next_inbound = next;
+        Some(gap)
+    }
+
+    /// Gives up on recovering `range`.
+    pub(crate) fn forget_missing(&mut self, range: Range<u64>) {
+        self.missing = self
+            .missing
+            .drain(..)
+            .flat_map(|r| {
+                [
+                    r.start..r.end.min(range.start),
+                    r.start.max(range.end)..r.end,
+                ]
+            })
+            .filter(|r| !r.is_empty())
+            .collect();
+    }
+
+    fn take_missing(&mut self, seq: u64) -> bool {
+        let Some(i) = self.missing.iter().position(|r| r.contains(&seq)) else {
+            return false;
+        };
+        let r = self.missing.remove(i);
+        for piece in [r.start..seq, seq + 1..r.end] {
+            if !piece.is_empty() {
+                self.missing.push(piece);
+            }
+        }
+        true
+    }
+}
+
+struct Entry {
+    flow: Arc<Mutex<Flow>>,
+    established: bool,
+}
+
+/// Logical sessions known to a server.
+///
+/// Shared by all connections of a server: a flow outlives the connection
+/// that negotiated it, so a client can reconnect, re-establish and recover
+/// what it missed.
+pub struct FlowRegistry {
+    config: SessionServerConfig,
+    flows: Mutex<HashMap<SessionId, Entry>>,
+}
+
+impl FlowRegistry {
+    /// Creates an empty registry.
+    #[must_use]
+    pub fn new(config: SessionServerConfig) -> Self {
+        Self {
+            config,
+            flows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the configuration.
+    #[must_use]
+    pub fn config(&self) -> &SessionServerConfig {
+        &self.config
+    }
+
+    /// Returns the number of logical sessions.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.flows.lock().len()
+    }
+
+    /// Returns true if there are no logical sessions.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.flows.lock().is_empty()
+    }
+
+    /// Returns true if `id` is a negotiated logical session.
+    #[must_use]
+    pub fn contains(&self, id: SessionId) -> bool {
+        self.flows.lock().contains_key(&id)
+    }
+
+    /// Returns true if `id` is established on a connection.
+    #[must_use]
+    pub fn is_established(&self, id: SessionId) -> bool {
+        self.flows.lock().get(&id).is_some_and(|e| e.established)
+    }
+
+    pub(crate) fn authenticate(&self, id: SessionId, credentials: &[u8]) -> bool {
+        self.config
+            .authenticator
+            .as_ref()
+            .is_none_or(|check| check(id, credentials))
+    }
+
+    pub(crate) fn negotiate(
+        &self,
+        id: SessionId,
+        peer_flow: FlowType,
+    ) -> Result<(), NegotiationRejectCode> {
+        let mut flows = self.flows.lock();
+        if flows.contains_key(&id) {
+            return Err(NegotiationRejectCode::DuplicateId);
+        }
+        let flow = Flow::new(peer_flow, self.config.retransmit_capacity);
+        flows.insert(
+            id,
+            Entry {
+                flow: Arc::new(Mutex::new(flow)),
+                established: false,
+            },
+        );
+        Ok(())
+    }
+
+    pub(crate) fn establish(
+        &self,
+        id: SessionId,
+    ) -> Result<Arc<Mutex<Flow>>, EstablishmentRejectCode> {
+        let mut flows = self.flows.lock();
+        let entry = flows
+            .get_mut(&id)
+            .ok_or(EstablishmentRejectCode::Unnegotiated)?;
+        if entry.established {
+            return Err(EstablishmentRejectCode::AlreadyEstablished);
+        }
+        entry.established = true;
+        Ok(Arc::clone(&entry.flow))
+    }
+
+    /// Unbinds `id` from its connection, keeping the flow.
+    pub(crate) fn release(&self, id: SessionId) {
+        if let Some(entry) = self.flows.lock().get_mut(&id) {
+            entry.established = false;
+        }
+    }
+
+    /// Ends the logical session `id`.
+    pub(crate) fn remove(&self, id: SessionId) {
+        self.flows.lock().remove(&id);
+    }
+}
+
+impl std::fmt::Debug for FlowRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlowRegistry")
+            .field("config", &self.config)
+            .field("sessions", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gaps_are_tracked_and_filled() {
+        let mut flow = Flow::new(FlowType::Recoverable, 8);
+        assert_eq!(flow.receive(1), (true, None));
+        assert_eq!(flow.receive(5), (true, Some(2..5)));
+        assert_eq!(flow.receive(1), (false, None));
+        assert_eq!(flow.receive(3), (true, None));
+        assert_eq!(flow.receive(3), (false, None));
+        assert_eq!(flow.missing, vec![2..3, 4..5]);
+        flow.forget_missing(2..5);
+        assert!(flow.missing.is_empty());
+    }
+
+    #[test]
+    fn test_idempotent_gaps_are_not_recovered() {
+        let mut flow = Flow::new(FlowType::Idempotent, 8);
+        assert_eq!(flow.advance(4), Some(1..4));
+        assert!(flow.missing.is_empty());
+        assert_eq!(flow.receive(2), (false, None));
+    }
+
+    #[test]
+    fn test_sent_range_is_bounded_by_capacity() {
+        let mut flow = Flow::new(FlowType::Recoverable, 2);
+        for i in 1..=3u8 {
+            assert_eq!(flow.record(&[i]), u64::from(i));
+        }
+        assert_eq!(flow.sent_range(2, 2), Some(vec![vec![2], vec![3]]));
+        assert_eq!(flow.sent_range(1, 1), None);
+        assert_eq!(flow.sent_range(3, 2), None);
+        assert_eq!(flow.sent_range(3, 0), None);
+    }
+
+    #[test]
+    fn test_registry_binds_one_connection_per_session() {
+        let registry = FlowRegistry::new(SessionServerConfig::default());
+        let id = SessionId(7);
+        assert_eq!(
+            registry.establish(id).err(),
+            Some(EstablishmentRejectCode::Unnegotiated)
+        );
+        registry.negotiate(id, FlowType::Recoverable).unwrap();
+        assert_eq!(
+            registry.negotiate(id, FlowType::Recoverable),
+            Err(NegotiationRejectCode::DuplicateId)
+        );
+        registry.establish(id).unwrap();
+        assert_eq!(
+            registry.establish(id).err(),
+            Some(EstablishmentRejectCode::AlreadyEstablished)
+        );
+        registry.release(id);
+        assert!(registry.establish(id).is_ok());
+        registry.remove(id);
+        assert!(registry.is_empty());
+    }
+}
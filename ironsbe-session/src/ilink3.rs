@@ -0,0 +1,951 @@
+//! CME iLink 3 binding.
+//!
+//! iLink 3 is the binary order entry protocol of CME Globex: a FIXP dialect
+//! encoded in SBE under schema 8. [`ILink3Codec`] maps the generic
+//! [`SessionMessage`]s onto the iLink 3 session templates and signs
+//! Negotiate and Establish with HMAC-SHA256, so a [`Session`](crate::Session)
+//! talks to CME order entry directly. Frames carry CME's compact 4-byte
+//! SOFH, `Framing::compact_sofh` in `ironsbe-transport`.
+//!
+//! CME runs the client flow idempotent: messages it skipped are reported
+//! with NotApplied instead of being retransmitted. Its own flow is
+//! recoverable, at most [`MAX_RETRANSMIT_COUNT`] messages per request.
+//! Finishing a session sends Terminate, as CME has no FinishedSending.
+//! What iLink 3 reports beyond FIXP, such as the fault tolerance indicator
+//! and error codes, is kept in [`ILink3Status`].
+
+use crate::codec::{Reader, SessionCodec, Writer};
+use crate::config::SessionConfig;
+use crate::error::SessionError;
+use crate::messages::{
+    Establish, EstablishmentAck, EstablishmentReject, EstablishmentRejectCode, FlowType, Negotiate,
+    NegotiationReject, NegotiationRejectCode, NegotiationResponse, NotApplied, Retransmission,
+    RetransmitReject, RetransmitRejectCode, RetransmitRequest, SessionId, SessionMessage,
+    Terminate, TerminationCode, timestamp_nanos,
+};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use ironsbe_core::header::MessageHeader;
+use parking_lot::Mutex;
+use sha2::Sha256;
+use std::fmt;
+use std::sync::Arc;
+
+/// Schema ID of iLink 3 messages.
+pub const ILINK3_SCHEMA_ID: u16 = 8;
+
+/// Schema version of the iLink 3 session messages sent.
+pub const ILINK3_SCHEMA_VERSION: u16 = 8;
+
+/// Most messages CME retransmits for one RetransmitRequest.
+pub const MAX_RETRANSMIT_COUNT: u32 = 2500;
+
+/// Template IDs of iLink 3 session messages.
+pub mod template {
+    /// `Negotiate500`.
+    pub const NEGOTIATE: u16 = 500;
+    /// `NegotiationResponse501`.
+    pub const NEGOTIATION_RESPONSE: u16 = 501;
+    /// `NegotiationReject502`.
+    pub const NEGOTIATION_REJECT: u16 = 502;
+    /// `Establish503`.
+    pub const ESTABLISH: u16 = 503;
+    /// `EstablishmentAck504`.
+    pub const ESTABLISHMENT_ACK: u16 = 504;
+    /// `EstablishmentReject505`.
+    pub const ESTABLISHMENT_REJECT: u16 = 505;
+    /// `Sequence506`.
+    pub const SEQUENCE: u16 = 506;
+    /// `Terminate507`.
+    pub const TERMINATE: u16 = 507;
+    /// `RetransmitRequest508`.
+    pub const RETRANSMIT_REQUEST: u16 = 508;
+    /// `Retransmission509`.
+    pub const RETRANSMISSION: u16 = 509;
+    /// `RetransmitReject510`.
+    pub const RETRANSMIT_REJECT: u16 = 510;
+    /// `NotApplied513`.
+    pub const NOT_APPLIED: u16 = 513;
+}
+
+/// iLink 3 `ErrorCodes` with a generic equivalent. Other codes decode as
+/// `Unspecified`; the raw value is kept in [`ILink3Status::last_reject`].
+pub mod error_code {
+    /// The HMAC signature did not match.
+    pub const HMAC_NOT_AUTHENTICATED: u16 = 0;
+    /// The HMAC signature could not be checked.
+    pub const HMAC_NOT_AVAILABLE: u16 = 1;
+    /// Negotiate: the UUID is not valid or was used before.
+    pub const INVALID_UUID: u16 = 2;
+    /// Establish: the UUID was never negotiated.
+    pub const UNNEGOTIATED: u16 = 2;
+    /// Establish: the UUID is established on another connection.
+    pub const ALREADY_ESTABLISHED: u16 = 3;
+    /// Establish: the session was blocked.
+    pub const SESSION_BLOCKED: u16 = 4;
+    /// Establish: the keepalive interval is out of range.
+    pub const INVALID_KEEPALIVE_INTERVAL: u16 = 5;
+    /// Terminate: normal logout.
+    pub const FINISHED: u16 = 0;
+}
+
+/// Null value of `u8` enum fields.
+const NULL_U8: u8 = u8::MAX;
+
+/// Null value of `u16` fields.
+const NULL_U16: u16 = u16::MAX;
+
+/// Length of the text `Reason` of rejects and Terminate.
+const REASON_LENGTH: usize = 48;
+
+/// Whether the gateway is the primary or the backup instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FaultToleranceIndicator {
+    /// Backup gateway; it does not process application messages.
+    Backup = 0,
+    /// Primary gateway.
+    Primary = 1,
+}
+
+impl FaultToleranceIndicator {
+    /// Decodes a wire value; null and unknown values are `None`.
+    #[must_use]
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Backup),
+            1 => Some(Self::Primary),
+            _ => None,
+        }
+    }
+}
+
+/// Credentials and identification of an iLink 3 session, as issued by CME.
+#[derive(Clone)]
+pub struct ILink3Credentials {
+    /// Access key ID, 20 characters.
+    pub access_key_id: String,
+    /// Secret key, base64url-encoded as issued.
+    pub secret_key: String,
+    /// Session ID, up to 3 characters.
+    pub session: String,
+    /// Firm ID, up to 5 characters.
+    pub firm: String,
+    /// Trading system name, up to 30 characters.
+    pub trading_system_name: String,
+    /// Trading system version, up to 10 characters.
+    pub trading_system_version: String,
+    /// Trading system vendor, up to 10 characters.
+    pub trading_system_vendor: String,
+}
+
+impl Default for ILink3Credentials {
+    fn default() -> Self {
+        Self {
+            access_key_id: String::new(),
+            secret_key: String::new(),
+            session: String::new(),
+            firm: String::new(),
+            trading_system_name: "IronSBE".to_string(),
+            trading_system_version: env!("CARGO_PKG_VERSION").to_string(),
+            trading_system_vendor: "IronSBE".to_string(),
+        }
+    }
+}
+
+impl fmt::Debug for ILink3Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ILink3Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("session", &self.session)
+            .field("firm", &self.firm)
+            .field("trading_system_name", &self.trading_system_name)
+            .field("trading_system_version", &self.trading_system_version)
+            .field("trading_system_vendor", &self.trading_system_vendor)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ILink3Credentials {
+    /// Creates credentials for `session` of `firm`.
+    #[must_use]
+    pub fn new(
+        access_key_id: impl Into<String>,
+        secret_key: impl Into<String>,
+        session: impl Into<String>,
+        firm: impl Into<String>,
+    ) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            secret_key: secret_key.into(),
+            session: session.into(),
+            firm: firm.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the trading system registered with CME.
+    #[must_use]
+    pub fn trading_system(
+        mut self,
+        name: impl Into<String>,
+        version: impl Into<String>,
+        vendor: impl Into<String>,
+    ) -> Self {
+        self.trading_system_name = name.into();
+        self.trading_system_version = version.into();
+        self.trading_system_vendor = vendor.into();
+        self
+    }
+}
+
+/// A reject or Terminate received from CME.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ILink3Reject {
+    /// Template ID of the message.
+    pub template_id: u16,
+    /// Raw `ErrorCodes`.
+    pub error_codes: u16,
+    /// Text `Reason`.
+    pub reason: String,
+}
+
+/// iLink 3 session state beyond FIXP, as last reported by CME.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ILink3Status {
+    /// UUID of the current session, 0 before Negotiate.
+    pub uuid: u64,
+    /// Whether the gateway is the primary or the backup.
+    pub fault_tolerance: Option<FaultToleranceIndicator>,
+    /// CME warned that no message arrived within the keepalive interval.
+    pub keepalive_lapsed: bool,
+    /// Days until the secret key expires.
+    pub secret_key_expiration: Option<u16>,
+    /// UUID of the previous session, 0 if none.
+    pub previous_uuid: u64,
+    /// Last sequence number CME sent on the previous session.
+    pub previous_seq_no: u32,
+    /// Most recent reject or abnormal Terminate.
+    pub last_reject: Option<ILink3Reject>,
+}
+
+struct Inner {
+    credentials: ILink3Credentials,
+    secret_key: Vec<u8>,
+    status: Mutex<ILink3Status>,
+}
+
+/// iLink 3 encoding of the session messages.
+///
+/// Session messages are told apart from application messages by template
+/// ID: both use schema 8. Clones share one [`ILink3Status`], so a codec
+/// serves one client session; the server-side messages are encoded too,
+/// for testing against a local peer.
+#[derive(Clone)]
+pub struct ILink3Codec {
+    inner: Arc<Inner>,
+}
+
+impl ILink3Codec {
+    /// Creates a codec signing with `credentials`.
+    ///
+    /// # Errors
+    /// Returns [`SessionError::Credentials`] if a field is too long or
+    /// empty, or the secret key is not base64url.
+    pub fn new(credentials: ILink3Credentials) -> Result<Self, SessionError> {
+        let c = &credentials;
+        if c.access_key_id.len() != 20 {
+            return Err(SessionError::Credentials(
+                "access key ID must be 20 characters",
+            ));
+        }
+        if c.session.is_empty() || c.session.len() > 3 {
+            return Err(SessionError::Credentials(
+                "session must be 1 to 3 characters",
+            ));
+        }
+        if c.firm.is_empty() || c.firm.len() > 5 {
+            return Err(SessionError::Credentials("firm must be 1 to 5 characters"));
+        }
+        if c.trading_system_name.len() > 30
+            || c.trading_system_version.len() > 10
+            || c.trading_system_vendor.len() > 10
+        {
+            return Err(SessionError::Credentials("trading system field too long"));
+        }
+        let secret_key = URL_SAFE_NO_PAD
+            .decode(c.secret_key.trim_end_matches('='))
+            .map_err(|_| SessionError::Credentials("secret key is not base64url"))?;
+        Ok(Self {
+            inner: Arc::new(Inner {
+                credentials,
+                secret_key,
+                status: Mutex::new(ILink3Status::default()),
+            }),
+        })
+    }
+
+    /// Returns the credentials.
+    #[must_use]
+    pub fn credentials(&self) -> &ILink3Credentials {
+        &self.inner.credentials
+    }
+
+    /// Returns the session state last reported by CME.
+    #[must_use]
+    pub fn status(&self) -> ILink3Status {
+        self.inner.status.lock().clone()
+    }
+
+    /// Returns a client session configuration using this codec.
+    ///
+    /// The UUID is the current time in microseconds, as CME recommends,
+    /// and the client flow is idempotent, so sent messages are not kept
+    /// for retransmission.
+    #[must_use]
+    pub fn session_config(&self) -> SessionConfig {
+        SessionConfig::default()
+            .session_id(SessionId(u128::from(timestamp_nanos() / 1_000)))
+            .flow_type(FlowType::Idempotent)
+            .retransmit_capacity(0)
+            .codec(self.clone())
+    }
+
+    /// HMAC-SHA256 of the fields of a Negotiate or Establish, one per line.
+    fn sign(&self, fields: &[&dyn fmt::Display]) -> [u8; 32] {
+        let canonical = fields
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.inner.secret_key)
+            .expect("HMAC accepts keys of any length");
+        mac.update(canonical.as_bytes());
+        mac.finalize().into_bytes().into()
+    }
+
+    fn set_uuid(&self, uuid: u64) {
+        self.inner.status.lock().uuid = uuid;
+    }
+
+    fn uuid(&self) -> u64 {
+        self.inner.status.lock().uuid
+    }
+
+    fn record_fault_tolerance(&self, value: u8) {
+        if let Some(indicator) = FaultToleranceIndicator::from_u8(value) {
+            self.inner.status.lock().fault_tolerance = Some(indicator);
+        }
+    }
+
+    fn record_reject(&self, template_id: u16, error_codes: u16, reason: &[u8]) {
+        let end = reason.iter().position(|&b| b == 0).unwrap_or(reason.len());
+        self.inner.status.lock().last_reject = Some(ILink3Reject {
+            template_id,
+            error_codes,
+            reason: String::from_utf8_lossy(&reason[..end]).into_owned(),
+        });
+    }
+
+    fn encode_terminate(w: &mut Writer, session_id: SessionId, error_codes: u16) -> u16 {
+        w.fixed(b"", REASON_LENGTH)
+            .u64(uuid(session_id))
+            .u64(timestamp_nanos())
+            .u16(error_codes)
+            .u8(NULL_U8);
+        template::TERMINATE
+    }
+}
+
+impl fmt::Debug for ILink3Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ILink3Codec")
+            .field("credentials", &self.inner.credentials)
+            .field("status", &*self.inner.status.lock())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The iLink 3 UUID, the low 64 bits of the session id.
+const fn uuid(session_id: SessionId) -> u64 {
+    session_id.0 as u64
+}
+
+impl SessionCodec for ILink3Codec {
+    fn encode(&self, message: &SessionMessage) -> Vec<u8> {
+        let c = &self.inner.credentials;
+        let mut w = Writer::default();
+        let mut credentials: Option<&[u8]> = None;
+        let template_id = match message {
+            SessionMessage::Negotiate(m) => {
+                let id = uuid(m.session_id);
+                self.set_uuid(id);
+                let signature = self.sign(&[&m.timestamp, &id, &c.session, &c.firm]);
+                w.fixed(&signature, 32)
+                    .fixed(c.access_key_id.as_bytes(), 20)
+                    .u64(id)
+                    .u64(m.timestamp)
+                    .fixed(c.session.as_bytes(), 3)
+                    .fixed(c.firm.as_bytes(), 5);
+                credentials = Some(&m.credentials);
+                template::NEGOTIATE
+            }
+            SessionMessage::NegotiationResponse(m) => {
+                w.u64(uuid(m.session_id))
+                    .u64(m.request_timestamp)
+                    .u16(NULL_U16)
+                    .u8(NULL_U8)
+                    .u8(NULL_U8)
+                    .u32(0)
+                    .u64(0);
+                credentials = Some(&[]);
+                template::NEGOTIATION_RESPONSE
+            }
+            SessionMessage::NegotiationReject(m) => {
+                let error_codes = match m.code {
+                    NegotiationRejectCode::Credentials => error_code::HMAC_NOT_AUTHENTICATED,
+                    NegotiationRejectCode::DuplicateId => error_code::INVALID_UUID,
+                    _ => NULL_U16,
+                };
+                w.fixed(b"", REASON_LENGTH)
+                    .u64(uuid(m.session_id))
+                    .u64(m.request_timestamp)
+                    .u16(error_codes)
+                    .u8(NULL_U8)
+                    .u8(NULL_U8);
+                template::NEGOTIATION_REJECT
+            }
+            SessionMessage::Establish(m) => {
+                let id = uuid(m.session_id);
+                self.set_uuid(id);
+                let next_seq_no = m.next_seq_no as u32;
+                let keepalive = m.keepalive_interval.min(u32::from(u16::MAX)) as u16;
+                let signature = self.sign(&[
+                    &m.timestamp,
+                    &id,
+                    &c.session,
+                    &c.firm,
+                    &c.trading_system_name,
+                    &c.trading_system_version,
+                    &c.trading_system_vendor,
+                    &next_seq_no,
+                    &keepalive,
+                ]);
+                w.fixed(&signature, 32)
+                    .fixed(c.access_key_id.as_bytes(), 20)
+                    .fixed(c.trading_system_name.as_bytes(), 30)
+                    .fixed(c.trading_system_vendor.as_bytes(), 10)
+                    .fixed(c.trading_system_version.as_bytes(), 10)
+                    .u64(id)
+                    .u64(m.timestamp)
+                    .u32(next_seq_no)
+                    .fixed(c.session.as_bytes(), 3)
+                    .fixed(c.firm.as_bytes(), 5)
+                    .u16(keepalive);
+                credentials = Some(&m.credentials);
+                template::ESTABLISH
+            }
+            SessionMessage::EstablishmentAck(m) => {
+                w.u64(uuid(m.session_id))
+                    .u64(m.request_timestamp)
+                    .u32(m.next_seq_no as u32)
+                    .u32(0)
+                    .u64(0)
+                    .u16(m.keepalive_interval.min(u32::from(u16::MAX)) as u16)
+                    .u16(NULL_U16)
+                    .u8(NULL_U8)
+                    .u8(NULL_U8);
+                template::ESTABLISHMENT_ACK
+            }
+            SessionMessage::EstablishmentReject(m) => {
+                let error_codes = match m.code {
+                    EstablishmentRejectCode::Credentials => error_code::HMAC_NOT_AUTHENTICATED,
+                    EstablishmentRejectCode::Unnegotiated => error_code::UNNEGOTIATED,
+                    EstablishmentRejectCode::AlreadyEstablished => error_code::ALREADY_ESTABLISHED,
+                    EstablishmentRejectCode::SessionBlocked => error_code::SESSION_BLOCKED,
+                    EstablishmentRejectCode::KeepaliveInterval => {
+                        error_code::INVALID_KEEPALIVE_INTERVAL
+                    }
+                    EstablishmentRejectCode::Unspecified => NULL_U16,
+                };
+                w.fixed(b"", REASON_LENGTH)
+                    .u64(uuid(m.session_id))
+                    .u64(m.request_timestamp)
+                    .u32(0)
+                    .u16(error_codes)
+                    .u8(NULL_U8)
+                    .u8(NULL_U8);
+                template::ESTABLISHMENT_REJECT
+            }
+            SessionMessage::Sequence { next_seq_no } => {
+                w.u64(self.uuid())
+                    .u32(*next_seq_no as u32)
+                    .u8(NULL_U8)
+                    .u8(0);
+                template::SEQUENCE
+            }
+            SessionMessage::RetransmitRequest(m) => {
+                w.u64(uuid(m.session_id))
+                    .u64(0)
+                    .u64(m.timestamp)
+                    .u32(m.from_seq_no as u32)
+                    .u16(m.count.min(MAX_RETRANSMIT_COUNT) as u16);
+                template::RETRANSMIT_REQUEST
+            }
+            SessionMessage::Retransmission(m) => {
+                w.u64(uuid(m.session_id))
+                    .u64(0)
+                    .u64(m.request_timestamp)
+                    .u32(m.next_seq_no as u32)
+                    .u16(m.count.min(MAX_RETRANSMIT_COUNT) as u16)
+                    .u8(NULL_U8);
+                template::RETRANSMISSION
+            }
+            SessionMessage::RetransmitReject(m) => {
+                w.fixed(b"", REASON_LENGTH)
+                    .u64(uuid(m.session_id))
+                    .u64(0)
+                    .u64(m.request_timestamp)
+                    .u16(NULL_U16);
+                template::RETRANSMIT_REJECT
+            }
+            SessionMessage::NotApplied(m) => {
+                w.u64(self.uuid())
+                    .u32(m.from_seq_no as u32)
+                    .u32(m.count)
+                    .u8(NULL_U8);
+                template::NOT_APPLIED
+            }
+            SessionMessage::Terminate(m) => {
+                let error_codes = match m.code {
+                    TerminationCode::Finished => error_code::FINISHED,
+                    code => code as u16,
+                };
+                Self::encode_terminate(&mut w, m.session_id, error_codes)
+            }
+            // iLink 3 has no Finished messages: finishing is a logout.
+            SessionMessage::FinishedSending(m) => {
+                Self::encode_terminate(&mut w, m.session_id, error_code::FINISHED)
+            }
+            SessionMessage::FinishedReceiving(m) => {
+                Self::encode_terminate(&mut w, m.session_id, error_code::FINISHED)
+            }
+        };
+        let block_length = w.0.len() as u16;
+        if let Some(credentials) = credentials {
+            w.var_data(credentials);
+        }
+
+        let mut frame = vec![0u8; MessageHeader::ENCODED_LENGTH];
+        MessageHeader::new(
+            block_length,
+            template_id,
+            ILINK3_SCHEMA_ID,
+            ILINK3_SCHEMA_VERSION,
+        )
+        .encode(&mut frame[..], 0);
+        frame.extend_from_slice(&w.0);
+        frame
+    }
+
+    fn decode(&self, frame: &[u8]) -> Result<Option<SessionMessage>, SessionError> {
+        let Some(header) = MessageHeader::try_wrap(frame, 0) else {
+            return Ok(None);
+        };
+        let template_id = header.template_id;
+        if header.schema_id != ILINK3_SCHEMA_ID
+            || !(template::NEGOTIATE..=template::NOT_APPLIED).contains(&template_id)
+        {
+            return Ok(None);
+        }
+        let body = &frame[MessageHeader::ENCODED_LENGTH..];
+        let block_length = usize::from(header.block_length);
+        if body.len() < block_length {
+            return Err(SessionError::decode(template_id, "truncated block"));
+        }
+        let mut r = Reader::new(&body[..block_length], template_id);
+        let mut var = Reader::new(&body[block_length..], template_id);
+        let id = |uuid: u64| SessionId(u128::from(uuid));
+        let message = match template_id {
+            template::NEGOTIATE => {
+                r.take(32 + 20)?;
+                let uuid = r.u64()?;
+                self.set_uuid(uuid);
+                let session_id = id(uuid);
+                let timestamp = r.u64()?;
+                SessionMessage::Negotiate(Negotiate {
+                    session_id,
+                    timestamp,
+                    client_flow: FlowType::Idempotent,
+                    credentials: var.var_data()?,
+                })
+            }
+            template::NEGOTIATION_RESPONSE => {
+                let uuid = r.u64()?;
+                let request_timestamp = r.u64()?;
+                let expiration = r.u16()?;
+                self.record_fault_tolerance(r.u8()?);
+                r.u8()?;
+                let previous_seq_no = r.u32()?;
+                let previous_uuid = r.u64()?;
+                {
+                    let mut status = self.inner.status.lock();
+                    status.uuid = uuid;
+                    status.secret_key_expiration = (expiration != NULL_U16).then_some(expiration);
+                    status.previous_seq_no = previous_seq_no;
+                    status.previous_uuid = previous_uuid;
+                }
+                SessionMessage::NegotiationResponse(NegotiationResponse {
+                    session_id: id(uuid),
+                    request_timestamp,
+                    server_flow: FlowType::Recoverable,
+                })
+            }
+            template::NEGOTIATION_REJECT => {
+                let reason = r.take(REASON_LENGTH)?;
+                let session_id = id(r.u64()?);
+                let request_timestamp = r.u64()?;
+                let error_codes = r.u16()?;
+                self.record_fault_tolerance(r.u8()?);
+                self.record_reject(template_id, error_codes, reason);
+                let code = match error_codes {
+                    error_code::HMAC_NOT_AUTHENTICATED | error_code::HMAC_NOT_AVAILABLE => {
+                        NegotiationRejectCode::Credentials
+                    }
+                    error_code::INVALID_UUID => NegotiationRejectCode::DuplicateId,
+                    _ => NegotiationRejectCode::Unspecified,
+                };
+                SessionMessage::NegotiationReject(NegotiationReject {
+                    session_id,
+                    request_timestamp,
+                    code,
+                })
+            }
+            template::ESTABLISH => {
+                r.take(32 + 20 + 30 + 10 + 10)?;
+                let uuid = r.u64()?;
+                self.set_uuid(uuid);
+                let session_id = id(uuid);
+                let timestamp = r.u64()?;
+                let next_seq_no = u64::from(r.u32()?);
+                r.take(3 + 5)?;
+                let keepalive_interval = u32::from(r.u16()?);
+                SessionMessage::Establish(Establish {
+                    session_id,
+                    timestamp,
+                    keepalive_interval,
+                    next_seq_no,
+                    credentials: var.var_data()?,
+                })
+            }
+            template::ESTABLISHMENT_ACK => {
+                let uuid = r.u64()?;
+                let request_timestamp = r.u64()?;
+                let next_seq_no = u64::from(r.u32()?);
+                let previous_seq_no = r.u32()?;
+                let previous_uuid = r.u64()?;
+                let keepalive_interval = u32::from(r.u16()?);
+                let expiration = r.u16()?;
+                self.record_fault_tolerance(r.u8()?);
+                {
+                    let mut status = self.inner.status.lock();
+                    status.uuid = uuid;
+                    status.secret_key_expiration = (expiration != NULL_U16).then_some(expiration);
+                    status.previous_seq_no = previous_seq_no;
+                    status.previous_uuid = previous_uuid;
+                    status.keepalive_lapsed = false;
+                }
+                SessionMessage::EstablishmentAck(EstablishmentAck {
+                    session_id: id(uuid),
+                    request_timestamp,
+                    keepalive_interval,
+                    next_seq_no,
+                })
+            }
+            template::ESTABLISHMENT_REJECT => {
+                let reason = r.take(REASON_LENGTH)?;
+                let session_id = id(r.u64()?);
+                let request_timestamp = r.u64()?;
+                r.u32()?;
+                let error_codes = r.u16()?;
+                self.record_fault_tolerance(r.u8()?);
+                self.record_reject(template_id, error_codes, reason);
+                let code = match error_codes {
+                    error_code::HMAC_NOT_AUTHENTICATED | error_code::HMAC_NOT_AVAILABLE => {
+                        EstablishmentRejectCode::Credentials
+                    }
+                    error_code::UNNEGOTIATED => EstablishmentRejectCode::Unnegotiated,
+                    error_code::ALREADY_ESTABLISHED => EstablishmentRejectCode::AlreadyEstablished,
+                    error_code::SESSION_BLOCKED => EstablishmentRejectCode::SessionBlocked,
+                    error_code::INVALID_KEEPALIVE_INTERVAL => {
+                        EstablishmentRejectCode::KeepaliveInterval
+                    }
+                    _ => EstablishmentRejectCode::Unspecified,
+                };
+                SessionMessage::EstablishmentReject(EstablishmentReject {
+                    session_id,
+                    request_timestamp,
+                    code,
+                })
+            }
+            template::SEQUENCE => {
+                r.u64()?;
+                let next_seq_no = u64::from(r.u32()?);
+                self.record_fault_tolerance(r.u8()?);
+                self.inner.status.lock().keepalive_lapsed = r.u8()? == 1;
+                SessionMessage::Sequence { next_seq_no }
+            }
+            template::TERMINATE => {
+                let reason = r.take(REASON_LENGTH)?;
+                let session_id = id(r.u64()?);
+                r.u64()?;
+                let error_codes = r.u16()?;
+                let code = if error_codes == error_code::FINISHED {
+                    TerminationCode::Finished
+                } else {
+                    self.record_reject(template_id, error_codes, reason);
+                    TerminationCode::Unspecified
+                };
+                SessionMessage::Terminate(Terminate { session_id, code })
+            }
+            template::RETRANSMIT_REQUEST => {
+                let session_id = id(r.u64()?);
+                r.u64()?;
+                SessionMessage::RetransmitRequest(RetransmitRequest {
+                    session_id,
+                    timestamp: r.u64()?,
+                    from_seq_no: u64::from(r.u32()?),
+                    count: u32::from(r.u16()?),
+                })
+            }
+            template::RETRANSMISSION => {
+                let session_id = id(r.u64()?);
+                r.u64()?;
+                SessionMessage::Retransmission(Retransmission {
+                    session_id,
+                    request_timestamp: r.u64()?,
+                    next_seq_no: u64::from(r.u32()?),
+                    count: u32::from(r.u16()?),
+                })
+            }
+            template::RETRANSMIT_REJECT => {
+                let reason = r.take(REASON_LENGTH)?;
+                let session_id = id(r.u64()?);
+                r.u64()?;
+                let request_timestamp = r.u64()?;
+                let error_codes = r.u16()?;
+                self.record_reject(template_id, error_codes, reason);
+                SessionMessage::RetransmitReject(RetransmitReject {
+                    session_id,
+                    request_timestamp,
+                    code: RetransmitRejectCode::OutOfRange,
+                })
+            }
+            template::NOT_APPLIED => {
+                r.u64()?;
+                SessionMessage::NotApplied(NotApplied {
+                    from_seq_no: u64::from(r.u32()?),
+                    count: r.u32()?,
+                })
+            }
+            _ => return Err(SessionError::decode(template_id, "unknown template")),
+        };
+        Ok(Some(message))
+    }
+
+    fn max_retransmit_count(&self) -> u32 {
+        MAX_RETRANSMIT_COUNT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 32 bytes 0x00..0x1F, base64url without padding.
+    const SECRET_KEY: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8";
+
+    fn codec() -> ILink3Codec {
+        ILink3Codec::new(ILink3Credentials::new(
+            "ACCESSKEYID123456789",
+            SECRET_KEY,
+            "ABC",
+            "FIRM1",
+        ))
+        .unwrap()
+    }
+
+    fn header(frame: &[u8]) -> MessageHeader {
+        MessageHeader::wrap(frame, 0)
+    }
+
+    #[test]
+    fn test_negotiate_is_signed() {
+        let codec = codec();
+        let frame = codec.encode(&SessionMessage::Negotiate(Negotiate {
+            session_id: SessionId(1_700_000_000_000_000),
+            timestamp: 1_700_000_000_000_000_000,
+            client_flow: FlowType::Idempotent,
+            credentials: Vec::new(),
+        }));
+        let h = header(&frame);
+        assert_eq!(
+            ({ h.block_length }, { h.template_id }, { h.schema_id }),
+            (76, template::NEGOTIATE, ILINK3_SCHEMA_ID)
+        );
+        let body = &frame[MessageHeader::ENCODED_LENGTH..];
+        // HMAC-SHA256 of "1700000000000000000\n1700000000000000\nABC\nFIRM1".
+        let expected = "f0f5fac783b6ed8bd5fc2c3e14debf6752257939993e2b34ed3abbbecbfe72c7";
+        let signature: String = body[..32].iter().map(|b| format!("{b:02x}")).collect();
+        assert_eq!(signature, expected);
+        assert_eq!(&body[32..52], b"ACCESSKEYID123456789");
+        assert_eq!(&body[68..76], b"ABCFIRM1");
+        // Empty Credentials follow the block.
+        assert_eq!(&body[76..], &[0, 0]);
+        assert_eq!(codec.status().uuid, 1_700_000_000_000_000);
+    }
+
+    #[test]
+    fn test_establish_signs_every_field() {
+        let codec = codec();
+        let establish = |next_seq_no| {
+            codec.encode(&SessionMessage::Establish(Establish {
+                session_id: SessionId(7),
+                timestamp: 9,
+                keepalive_interval: 5000,
+                next_seq_no,
+                credentials: Vec::new(),
+            }))
+        };
+        let frame = establish(1);
+        assert_eq!({ header(&frame).block_length }, 132);
+        let expected = codec.sign(&[
+            &9u64,
+            &7u64,
+            &"ABC",
+            &"FIRM1",
+            &"IronSBE",
+            &env!("CARGO_PKG_VERSION"),
+            &"IronSBE",
+            &1u32,
+            &5000u16,
+        ]);
+        assert_eq!(&frame[8..40], &expected);
+        assert_ne!(&establish(2)[8..40], &expected);
+
+        let decoded = codec.decode(&frame).unwrap();
+        assert!(matches!(
+            decoded,
+            Some(SessionMessage::Establish(Establish {
+                session_id: SessionId(7),
+                keepalive_interval: 5000,
+                next_seq_no: 1,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_responses_update_status() {
+        let codec = codec();
+        let mut w = Writer::default();
+        w.u64(42)
+            .u64(9)
+            .u32(100)
+            .u32(77)
+            .u64(41)
+            .u16(30_000)
+            .u16(12)
+            .u8(FaultToleranceIndicator::Primary as u8)
+            .u8(NULL_U8);
+        let mut frame = vec![0u8; MessageHeader::ENCODED_LENGTH];
+        MessageHeader::new(38, template::ESTABLISHMENT_ACK, ILINK3_SCHEMA_ID, 8)
+            .encode(&mut frame[..], 0);
+        frame.extend_from_slice(&w.0);
+
+        assert_eq!(
+            codec.decode(&frame).unwrap(),
+            Some(SessionMessage::EstablishmentAck(EstablishmentAck {
+                session_id: SessionId(42),
+                request_timestamp: 9,
+                keepalive_interval: 30_000,
+                next_seq_no: 100,
+            }))
+        );
+        let status = codec.status();
+        assert_eq!(
+            status.fault_tolerance,
+            Some(FaultToleranceIndicator::Primary)
+        );
+        assert_eq!(status.secret_key_expiration, Some(12));
+        assert_eq!((status.previous_uuid, status.previous_seq_no), (41, 77));
+
+        // Sequence carries the UUID from the handshake.
+        let frame = codec.encode(&SessionMessage::Sequence { next_seq_no: 5 });
+        assert_eq!(&frame[8..16], &42u64.to_le_bytes());
+        assert_eq!(
+            codec.decode(&frame).unwrap(),
+            Some(SessionMessage::Sequence { next_seq_no: 5 })
+        );
+    }
+
+    #[test]
+    fn test_rejects_keep_the_error_code() {
+        let codec = codec();
+        let frame = codec.encode(&SessionMessage::EstablishmentReject(EstablishmentReject {
+            session_id: SessionId(3),
+            request_timestamp: 1,
+            code: EstablishmentRejectCode::Unnegotiated,
+        }));
+        assert!(matches!(
+            codec.decode(&frame).unwrap(),
+            Some(SessionMessage::EstablishmentReject(EstablishmentReject {
+                code: EstablishmentRejectCode::Unnegotiated,
+                ..
+            }))
+        ));
+        let reject = codec.status().last_reject.unwrap();
+        assert_eq!(
+            (reject.template_id, reject.error_codes),
+            (template::ESTABLISHMENT_REJECT, error_code::UNNEGOTIATED)
+        );
+
+        // Finishing is a Terminate without an error.
+        let frame = codec.encode(&SessionMessage::FinishedSending(
+            crate::messages::FinishedSending {
+                session_id: SessionId(3),
+                last_seq_no: 4,
+            },
+        ));
+        assert_eq!(
+            codec.decode(&frame).unwrap(),
+            Some(SessionMessage::Terminate(Terminate {
+                session_id: SessionId(3),
+                code: TerminationCode::Finished,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_application_messages_pass_through() {
+        let codec = codec();
+        let mut frame = [0u8; 16];
+        MessageHeader::new(8, 514, ILINK3_SCHEMA_ID, 8).encode(&mut frame[..], 0);
+        assert_eq!(codec.decode(&frame).unwrap(), None);
+        MessageHeader::new(8, template::SEQUENCE, 1, 1).encode(&mut frame[..], 0);
+        assert_eq!(codec.decode(&frame).unwrap(), None);
+        assert_eq!(codec.max_retransmit_count(), MAX_RETRANSMIT_COUNT);
+    }
+
+    #[test]
+    fn test_invalid_credentials() {
+        let mut credentials = ILink3Credentials::new("SHORT", SECRET_KEY, "ABC", "FIRM1");
+        assert!(ILink3Codec::new(credentials.clone()).is_err());
+        credentials.access_key_id = "ACCESSKEYID123456789".to_string();
+        credentials.secret_key = "not base64!".to_string();
+        assert!(ILink3Codec::new(credentials.clone()).is_err());
+        credentials.secret_key = format!("{SECRET_KEY}=");
+        assert!(ILink3Codec::new(credentials).is_ok());
+    }
+}
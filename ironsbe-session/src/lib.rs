@@ -0,0 +1,39 @@
+//! # IronSBE Session
+//!
+//! FIX Performance Session Layer (FIXP) for IronSBE.
+//!
+//! FIXP adds logical sessions on top of a point-to-point transport: a
+//! client negotiates a session once and establishes it on every connection,
+//! so sequence numbers and unacknowledged messages survive reconnects. This
+//! crate provides:
+//! - The FIXP session messages and their SBE encoding
+//! - Negotiate/Establish handshakes with pluggable credential checks
+//! - Implicitly sequenced flows with gap detection and retransmit requests
+//! - Sequence heartbeats and keepalive lapse detection
+//! - FinishedSending/FinishedReceiving and Terminate
+//! - A CME iLink 3 binding with HMAC-signed credentials (`ilink3` feature)
+//!
+//! The [`Session`] state machine does no I/O, so it runs on any transport;
+//! `ironsbe-server` and `ironsbe-client` drive it in their opt-in session
+//! mode.
+
+pub mod codec;
+pub mod config;
+pub mod error;
+pub mod flow;
+#[cfg(feature = "ilink3")]
+pub mod ilink3;
+pub mod messages;
+pub mod session;
+
+pub use codec::{FIXP_SCHEMA_ID, FixpCodec, SessionCodec};
+pub use config::{Authenticator, SessionConfig, SessionServerConfig};
+pub use error::SessionError;
+pub use flow::FlowRegistry;
+#[cfg(feature = "ilink3")]
+pub use ilink3::{FaultToleranceIndicator, ILink3Codec, ILink3Credentials, ILink3Status};
+pub use messages::{
+    EstablishmentRejectCode, FlowType, NegotiationRejectCode, RetransmitRejectCode, SessionId,
+    SessionMessage, TerminationCode,
+};
+pub use session::{Session, SessionEvent};
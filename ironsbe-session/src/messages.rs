@@ -0,0 +1,444 @@
+//! FIXP session messages.
+//!
+//! These are the messages of the FIX Performance Session Layer, independent
+//! of how a venue encodes them; a [`SessionCodec`](crate::codec::SessionCodec)
+//! maps them to and from the wire. Timestamps are nanoseconds since the
+//! Unix epoch and keepalive intervals are milliseconds, as in FIXP.
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Identifier of a logical session, kept across reconnects.
+///
+/// FIXP uses a UUID; venues with shorter ids use the low bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SessionId(pub u128);
+
+impl SessionId {
+    /// Generates a random version 4 UUID.
+    #[must_use]
+    pub fn generate() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let word = || {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u64(count);
+            hasher.write_u64(timestamp_nanos());
+            hasher.finish()
+        };
+        let random = (u128::from(word()) << 64) | u128::from(word());
+        // Version 4, RFC 4122 variant.
+        Self((random & !(0xF << 76) & !(0b11 << 62)) | (0x4 << 76) | (0b10 << 62))
+    }
+
+    /// Returns the id as 16 big-endian bytes, the UUID byte order.
+    #[must_use]
+    pub const fn to_bytes(self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+
+    /// Creates an id from 16 big-endian bytes.
+    #[must_use]
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(u128::from_be_bytes(bytes))
+    }
+}
+
+impl fmt::Display for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let b = self.to_bytes();
+        write!(
+            f,
+            "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+            b[0],
+            b[1],
+            b[2],
+            b[3],
+            b[4],
+            b[5],
+            b[6],
+            b[7],
+            b[8],
+            b[9],
+            b[10],
+            b[11],
+            b[12],
+            b[13],
+            b[14],
+            b[15]
+        )
+    }
+}
+
+/// Returns the current time in nanoseconds since the Unix epoch.
+#[must_use]
+pub fn timestamp_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+/// Delivery guarantee of one direction of a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FlowType {
+    /// Sequenced, and gaps can be recovered by retransmission.
+    Recoverable = 0,
+    /// Sequenced, and gaps are detected but not recovered.
+    Idempotent = 1,
+    /// Not sequenced.
+    Unsequenced = 2,
+    /// No application messages flow this way.
+    None = 3,
+}
+
+impl FlowType {
+    /// Decodes a wire value.
+    #[must_use]
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Recoverable),
+            1 => Some(Self::Idempotent),
+            2 => Some(Self::Unsequenced),
+            3 => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
+/// Why a Negotiate was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum NegotiationRejectCode {
+    /// Any other reason.
+    Unspecified = 0,
+    /// The credentials were not accepted.
+    Credentials = 1,
+    /// The requested flow type is not supported.
+    FlowTypeNotSupported = 2,
+    /// The session id is already in use.
+    DuplicateId = 3,
+}
+
+/// Why an Establish was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum EstablishmentRejectCode {
+    /// Any other reason.
+    Unspecified = 0,
+    /// The session was never negotiated, or has finished.
+    Unnegotiated = 1,
+    /// The session is established on another connection.
+    AlreadyEstablished = 2,
+    /// The session was terminated for good.
+    SessionBlocked = 3,
+    /// The keepalive interval is not acceptable.
+    KeepaliveInterval = 4,
+    /// The credentials were not accepted.
+    Credentials = 5,
+}
+
+/// Why a RetransmitRequest was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RetransmitRejectCode {
+    /// The range is not (or no longer) available.
+    OutOfRange = 0,
+    /// Another retransmission is in progress.
+    RequestLimitExceeded = 1,
+    /// The session is not established.
+    InvalidSession = 2,
+}
+
+/// Why a session was terminated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TerminationCode {
+    /// Both sides finished sending.
+    Finished = 0,
+    /// Any other reason.
+    Unspecified = 1,
+    /// Retransmission requested beyond the available range.
+    ReRequestOutOfBounds = 2,
+    /// A retransmission request arrived while one was in progress.
+    ReRequestInProgress = 3,
+    /// No message arrived within the keepalive interval.
+    KeepaliveLapsed = 4,
+    /// A message was not valid in the session's state.
+    ProtocolError = 5,
+}
+
+impl NegotiationRejectCode {
+    /// Decodes a wire value; unknown values are `Unspecified`.
+    #[must_use]
+    pub const fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Credentials,
+            2 => Self::FlowTypeNotSupported,
+            3 => Self::DuplicateId,
+            _ => Self::Unspecified,
+        }
+    }
+}
+
+impl EstablishmentRejectCode {
+    /// Decodes a wire value; unknown values are `Unspecified`.
+    #[must_use]
+    pub const fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Unnegotiated,
+            2 => Self::AlreadyEstablished,
+            3 => Self::SessionBlocked,
+            4 => Self::KeepaliveInterval,
+            5 => Self::Credentials,
+            _ => Self::Unspecified,
+        }
+    }
+}
+
+impl RetransmitRejectCode {
+    /// Decodes a wire value; unknown values are `OutOfRange`.
+    #[must_use]
+    pub const fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::RequestLimitExceeded,
+            2 => Self::InvalidSession,
+            _ => Self::OutOfRange,
+        }
+    }
+}
+
+impl TerminationCode {
+    /// Decodes a wire value; unknown values are `Unspecified`.
+    #[must_use]
+    pub const fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Finished,
+            2 => Self::ReRequestOutOfBounds,
+            3 => Self::ReRequestInProgress,
+            4 => Self::KeepaliveLapsed,
+            5 => Self::ProtocolError,
+            _ => Self::Unspecified,
+        }
+    }
+}
+
+/// Client request to create a logical session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Negotiate {
+    /// Session being created.
+    pub session_id: SessionId,
+    /// Send time.
+    pub timestamp: u64,
+    /// Flow type of client-to-server messages.
+    pub client_flow: FlowType,
+    /// Opaque credentials checked by the server.
+    pub credentials: Vec<u8>,
+}
+
+/// Server acceptance of a [`Negotiate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegotiationResponse {
+    /// Negotiated session.
+    pub session_id: SessionId,
+    /// Timestamp of the Negotiate.
+    pub request_timestamp: u64,
+    /// Flow type of server-to-client messages.
+    pub server_flow: FlowType,
+}
+
+/// Server refusal of a [`Negotiate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegotiationReject {
+    /// Session that was not created.
+    pub session_id: SessionId,
+    /// Timestamp of the Negotiate.
+    pub request_timestamp: u64,
+    /// Reason.
+    pub code: NegotiationRejectCode,
+}
+
+/// Client request to bind a negotiated session to this connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Establish {
+    /// Negotiated session.
+    pub session_id: SessionId,
+    /// Send time.
+    pub timestamp: u64,
+    /// Longest time between messages from either side, in milliseconds.
+    pub keepalive_interval: u32,
+    /// Sequence number of the client's next application message.
+    pub next_seq_no: u64,
+    /// Opaque credentials checked by the server.
+    pub credentials: Vec<u8>,
+}
+
+/// Server acceptance of an [`Establish`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EstablishmentAck {
+    /// Established session.
+    pub session_id: SessionId,
+    /// Timestamp of the Establish.
+    pub request_timestamp: u64,
+    /// Keepalive interval in force, in milliseconds.
+    pub keepalive_interval: u32,
+    /// Sequence number of the server's next application message.
+    pub next_seq_no: u64,
+}
+
+/// Server refusal of an [`Establish`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EstablishmentReject {
+    /// Session that was not established.
+    pub session_id: SessionId,
+    /// Timestamp of the Establish.
+    pub request_timestamp: u64,
+    /// Reason.
+    pub code: EstablishmentRejectCode,
+}
+
+/// Request to resend a range of application messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetransmitRequest {
+    /// Established session.
+    pub session_id: SessionId,
+    /// Send time.
+    pub timestamp: u64,
+    /// First sequence number to resend.
+    pub from_seq_no: u64,
+    /// Number of messages to resend.
+    pub count: u32,
+}
+
+/// Announces `count` resent application messages starting at
+/// `next_seq_no`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Retransmission {
+    /// Established session.
+    pub session_id: SessionId,
+    /// Timestamp of the RetransmitRequest.
+    pub request_timestamp: u64,
+    /// Sequence number of the first resent message.
+    pub next_seq_no: u64,
+    /// Number of resent messages that follow.
+    pub count: u32,
+}
+
+/// Refusal of a [`RetransmitRequest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetransmitReject {
+    /// Established session.
+    pub session_id: SessionId,
+    /// Timestamp of the RetransmitRequest.
+    pub request_timestamp: u64,
+    /// Reason.
+    pub code: RetransmitRejectCode,
+}
+
+/// The sender will send no more application messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinishedSending {
+    /// Established session.
+    pub session_id: SessionId,
+    /// Sequence number of the last application message sent.
+    pub last_seq_no: u64,
+}
+
+/// Acknowledges [`FinishedSending`] once everything was received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinishedReceiving {
+    /// Established session.
+    pub session_id: SessionId,
+}
+
+/// Tells the sender of an idempotent flow that messages were skipped and
+/// will not be applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotApplied {
+    /// First skipped sequence number.
+    pub from_seq_no: u64,
+    /// Number of skipped messages.
+    pub count: u32,
+}
+
+/// Ends the connection, and with code `Finished` the logical session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Terminate {
+    /// Session being terminated.
+    pub session_id: SessionId,
+    /// Reason.
+    pub code: TerminationCode,
+}
+
+/// Any FIXP session message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionMessage {
+    /// See [`Negotiate`].
+    Negotiate(Negotiate),
+    /// See [`NegotiationResponse`].
+    NegotiationResponse(NegotiationResponse),
+    /// See [`NegotiationReject`].
+    NegotiationReject(NegotiationReject),
+    /// See [`Establish`].
+    Establish(Establish),
+    /// See [`EstablishmentAck`].
+    EstablishmentAck(EstablishmentAck),
+    /// See [`EstablishmentReject`].
+    EstablishmentReject(EstablishmentReject),
+    /// Heartbeat, and the sequence number of the sender's next
+    /// application message.
+    Sequence {
+        /// Sequence number of the next application message.
+        next_seq_no: u64,
+    },
+    /// See [`RetransmitRequest`].
+    RetransmitRequest(RetransmitRequest),
+    /// See [`Retransmission`].
+    Retransmission(Retransmission),
+    /// See [`RetransmitReject`].
+    RetransmitReject(RetransmitReject),
+    /// See [`NotApplied`].
+    NotApplied(NotApplied),
+    /// See [`FinishedSending`].
+    FinishedSending(FinishedSending),
+    /// See [`FinishedReceiving`].
+    FinishedReceiving(FinishedReceiving),
+    /// See [`Terminate`].
+    Terminate(Terminate),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_session_ids_are_v4_uuids() {
+        let a = SessionId::generate();
+        let b = SessionId::generate();
+        assert_ne!(a, b);
+        let bytes = a.to_bytes();
+        assert_eq!(bytes[6] >> 4, 4);
+        assert_eq!(bytes[8] >> 6, 0b10);
+        assert_eq!(SessionId::from_bytes(bytes), a);
+    }
+
+    #[test]
+    fn test_session_id_display() {
+        let id = SessionId(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+        assert_eq!(id.to_string(), "01234567-89ab-cdef-0123-456789abcdef");
+    }
+
+    #[test]
+    fn test_codes_from_wire() {
+        assert_eq!(FlowType::from_u8(0), Some(FlowType::Recoverable));
+        assert_eq!(FlowType::from_u8(9), None);
+        assert_eq!(
+            EstablishmentRejectCode::from_u8(1),
+            EstablishmentRejectCode::Unnegotiated
+        );
+        assert_eq!(TerminationCode::from_u8(200), TerminationCode::Unspecified);
+    }
+}
@@ -0,0 +1,1099 @@
+//! FIXP session state machine.
+//!
+//! A [`Session`] does no I/O. The connection feeds it received frames with
+//! [`Session::on_frame`] and timer expiries with [`Session::on_timer`], and
+//! drains frames to send with [`Session::poll_transmit`] and notifications
+//! with [`Session::poll_event`].
+
+use crate::codec::SessionCodec;
+use crate::config::SessionConfig;
+use crate::error::SessionError;
+use crate::flow::{Flow, FlowRegistry};
+use crate::messages::{
+    Establish, EstablishmentAck, EstablishmentReject, EstablishmentRejectCode, FinishedReceiving,
+    FinishedSending, FlowType, Negotiate, NegotiationReject, NegotiationRejectCode,
+    NegotiationResponse, NotApplied, Retransmission, RetransmitReject, RetransmitRejectCode,
+    RetransmitRequest, SessionId, SessionMessage, Terminate, TerminationCode, timestamp_nanos,
+};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Notification from a [`Session`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// The logical session was created.
+    Negotiated {
+        /// Session id.
+        session_id: SessionId,
+    },
+    /// The session was bound to this connection; application messages may
+    /// flow.
+    Established {
+        /// Session id.
+        session_id: SessionId,
+        /// Keepalive interval in force.
+        keepalive_interval: Duration,
+    },
+    /// Negotiation was rejected, by the server or by this side.
+    NegotiationRejected(NegotiationRejectCode),
+    /// Establishment was rejected, by the server or by this side.
+    EstablishmentRejected(EstablishmentRejectCode),
+    /// Inbound application messages were skipped. On a recoverable flow a
+    /// retransmission is requested and the messages are delivered when
+    /// they arrive.
+    Gap {
+        /// First skipped sequence number.
+        from_seq_no: u64,
+        /// Number of skipped messages.
+        count: u64,
+    },
+    /// The peer could not retransmit a range, which is given up.
+    RetransmitRejected {
+        /// First sequence number requested.
+        from_seq_no: u64,
+        /// Number of messages requested.
+        count: u32,
+        /// Reason.
+        code: RetransmitRejectCode,
+    },
+    /// The peer skipped sent messages on an idempotent flow and will not
+    /// apply them. The flow continues with the next message.
+    NotApplied {
+        /// First skipped sequence number.
+        from_seq_no: u64,
+        /// Number of skipped messages.
+        count: u32,
+    },
+    /// The peer will send no more application messages.
+    PeerFinished,
+    /// The connection-level session ended. With
+    /// [`TerminationCode::Finished`] the logical session ended too.
+    Terminated {
+        /// Reason.
+        code: TerminationCode,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Server waiting for Negotiate or Establish, or client not started.
+    Idle,
+    Negotiating,
+    Establishing,
+    Established,
+    Terminated,
+}
+
+enum Role {
+    Client {
+        config: SessionConfig,
+        negotiated: bool,
+    },
+    Server {
+        registry: Arc<FlowRegistry>,
+        /// Session bound to this connection, released on drop.
+        bound: Option<SessionId>,
+    },
+}
+
+/// One side of a FIXP session over one connection.
+///
+/// A client session is created once and [started](Self::start) on every
+/// connection, so the logical session, its sequence numbers and its
+/// retransmission buffer survive reconnects. A server session is created
+/// per connection and finds its flow in the shared [`FlowRegistry`].
+///
+/// Application messages are numbered implicitly from the `next_seq_no` of
+/// EstablishmentAck, Establish, Sequence and Retransmission messages.
+/// Duplicates are dropped; messages recovered after a gap are delivered
+/// when they arrive, after the ones that revealed the gap.
+pub struct Session {
+    role: Role,
+    codec: Arc<dyn SessionCodec>,
+    session_id: Option<SessionId>,
+    flow: Option<Arc<Mutex<Flow>>>,
+    state: State,
+    keepalive: Duration,
+    /// Sequence number of the next inbound application message.
+    implied_inbound: u64,
+    /// Messages left in the current retransmission, and where the live
+    /// flow resumes after it.
+    retransmitting: Option<(u32, u64)>,
+    request_in_flight: Option<Range<u64>>,
+    pending_requests: VecDeque<Range<u64>>,
+    finished_sending: bool,
+    peer_finished: bool,
+    sent_finished_receiving: bool,
+    last_sent: Option<Instant>,
+    last_received: Option<Instant>,
+    transmitted: bool,
+    outbox: VecDeque<Vec<u8>>,
+    events: VecDeque<SessionEvent>,
+}
+
+impl Session {
+    /// Creates the client side of a logical session.
+    #[must_use]
+    pub fn client(config: SessionConfig) -> Self {
+        let codec = Arc::clone(&config.codec);
+        let keepalive = config.keepalive_interval;
+        let session_id = Some(config.session_id);
+        let mut session = Self::new(
+            Role::Client {
+                config,
+                negotiated: false,
+            },
+            codec,
+            keepalive,
+        );
+        session.session_id = session_id;
+        session.reset_flow();
+        session
+    }
+
+    /// Creates the server side of a session for one connection.
+    #[must_use]
+    pub fn server(registry: Arc<FlowRegistry>) -> Self {
+        let codec = Arc::clone(&registry.config().codec);
+        let keepalive = registry.config().max_keepalive_interval;
+        Self::new(
+            Role::Server {
+                registry,
+                bound: None,
+            },
+            codec,
+            keepalive,
+        )
+    }
+
+    fn new(role: Role, codec: Arc<dyn SessionCodec>, keepalive: Duration) -> Self {
+        Self {
+            role,
+            codec,
+            session_id: None,
+            flow: None,
+            state: State::Idle,
+            keepalive,
+            implied_inbound: 1,
+            retransmitting: None,
+            request_in_flight: None,
+            pending_requests: VecDeque::new(),
+            finished_sending: false,
+            peer_finished: false,
+            sent_finished_receiving: false,
+            last_sent: None,
+            last_received: None,
+            transmitted: false,
+            outbox: VecDeque::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Returns the session id, once known.
+    #[must_use]
+    pub fn session_id(&self) -> Option<SessionId> {
+        self.session_id
+    }
+
+    /// Returns true if application messages may flow.
+    #[must_use]
+    pub fn is_established(&self) -> bool {
+        self.state == State::Established
+    }
+
+    /// Returns true once the session ended on this connection; the
+    /// connection should be closed after draining
+    /// [`poll_transmit`](Self::poll_transmit).
+    #[must_use]
+    pub fn is_terminated(&self) -> bool {
+        self.state == State::Terminated
+    }
+
+    /// Returns the sequence number the next sent application message gets.
+    #[must_use]
+    pub fn next_outbound_seq_no(&self) -> Option<u64> {
+        self.flow.as_ref().map(|flow| flow.lock().next_outbound)
+    }
+
+    /// Returns the sequence number expected for the next received
+    /// application message.
+    #[must_use]
+    pub fn next_inbound_seq_no(&self) -> Option<u64> {
+        self.flow.as_ref().map(|flow| flow.lock().next_inbound)
+    }
+
+    /// Starts the session on a new connection.
+    ///
+    /// A client sends Negotiate, or Establish if the logical session was
+    /// already negotiated; a server waits for the client.
+    pub fn start(&mut self, now: Instant) {
+        self.state = State::Idle;
+        self.retransmitting = None;
+        self.request_in_flight = None;
+        self.pending_requests.clear();
+        self.outbox.clear();
+        self.last_received = Some(now);
+        self.last_sent = Some(now);
+        if let Role::Client { negotiated, .. } = self.role {
+            if negotiated {
+                self.send_establish();
+            } else {
+                self.send_negotiate();
+            }
+        }
+        self.touch(now);
+    }
+
+    /// Handles a received frame.
+    ///
+    /// Returns the sequence number of an application message to deliver,
+    /// or `None` if the frame was a session message or a duplicate.
+    ///
+    /// # Errors
+    /// Returns an error if a session message cannot be decoded or an
+    /// application message arrives outside an established session; the
+    /// connection should be closed.
+    pub fn on_frame(&mut self, frame: &[u8], now: Instant) -> Result<Option<u64>, SessionError> {
+        if self.state == State::Terminated {
+            return Ok(None);
+        }
+        self.last_received = Some(now);
+        let result = match self.codec.decode(frame)? {
+            Some(message) => {
+                self.on_message(message);
+                Ok(None)
+            }
+            None => self.on_application(),
+        };
+        self.touch(now);
+        result
+    }
+
+    /// Assigns the next sequence number to an application message and
+    /// queues it for sending.
+    ///
+    /// # Errors
+    /// Returns [`SessionError::NotEstablished`] if the session is not
+    /// established or this side has finished sending.
+    pub fn send(&mut self, frame: &[u8], now: Instant) -> Result<u64, SessionError> {
+        if self.state != State::Established || self.finished_sending {
+            return Err(SessionError::NotEstablished);
+        }
+        let seq = self.flow().lock().record(frame);
+        self.outbox.push_back(frame.to_vec());
+        self.transmitted = true;
+        self.touch(now);
+        Ok(seq)
+    }
+
+    /// Returns the next frame to send.
+    pub fn poll_transmit(&mut self) -> Option<Vec<u8>> {
+        self.outbox.pop_front()
+    }
+
+    /// Returns the next notification.
+    pub fn poll_event(&mut self) -> Option<SessionEvent> {
+        self.events.pop_front()
+    }
+
+    /// Returns when [`on_timer`](Self::on_timer) must be called next.
+    #[must_use]
+    pub fn next_timeout(&self) -> Option<Instant> {
+        let lapse = self.last_received.map(|at| at + self.keepalive * 2);
+        match self.state {
+            State::Negotiating | State::Establishing => lapse,
+            State::Established => {
+                let heartbeat = self.last_sent.map(|at| at + self.keepalive);
+                lapse.min(heartbeat).or(lapse)
+            }
+            State::Idle | State::Terminated => None,
+        }
+    }
+
+    /// Sends a Sequence heartbeat after a keepalive interval without
+    /// sending, and terminates after two intervals without receiving.
+    pub fn on_timer(&mut self, now: Instant) {
+        if self.next_timeout().is_none_or(|at| now < at) {
+            return;
+        }
+        if self
+            .last_received
+            .is_some_and(|at| now >= at + self.keepalive * 2)
+        {
+            self.terminate(TerminationCode::KeepaliveLapsed);
+        } else if self.state == State::Established {
+            let next_seq_no = self.flow().lock().next_outbound;
+            self.push(&SessionMessage::Sequence { next_seq_no });
+        }
+        self.touch(now);
+    }
+
+    /// Announces that this side will send no more application messages.
+    ///
+    /// Once the peer acknowledges with FinishedReceiving, the logical
+    /// session is terminated with [`TerminationCode::Finished`].
+    ///
+    /// # Errors
+    /// Returns [`SessionError::NotEstablished`] if the session is not
+    /// established.
+    pub fn finish(&mut self, now: Instant) -> Result<(), SessionError> {
+        if self.state != State::Established {
+            return Err(SessionError::NotEstablished);
+        }
+        if !self.finished_sending {
+            self.finished_sending = true;
+            let last_seq_no = self.flow().lock().next_outbound - 1;
+            self.push(&SessionMessage::FinishedSending(FinishedSending {
+                session_id: self.id(),
+                last_seq_no,
+            }));
+        }
+        self.touch(now);
+        Ok(())
+    }
+
+    /// Terminates the session on this connection.
+    ///
+    /// With [`TerminationCode::Finished`] the logical session ends too.
+    pub fn terminate(&mut self, code: TerminationCode) {
+        if self.state == State::Terminated {
+            return;
+        }
+        if let Some(session_id) = self.session_id {
+            self.push(&SessionMessage::Terminate(Terminate { session_id, code }));
+        }
+        self.end(code);
+    }
+
+    fn end(&mut self, code: TerminationCode) {
+        self.state = State::Terminated;
+        self.events.push_back(SessionEvent::Terminated { code });
+        if code != TerminationCode::Finished {
+            return;
+        }
+        match &mut self.role {
+            Role::Client { config, negotiated } => {
+                // A finished session id cannot be reused.
+                *negotiated = false;
+                config.session_id = SessionId::generate();
+                self.session_id = Some(config.session_id);
+                self.finished_sending = false;
+                self.peer_finished = false;
+                self.sent_finished_receiving = false;
+                self.reset_flow();
+            }
+            Role::Server { registry, bound } => {
+                if let Some(id) = bound.take() {
+                    registry.remove(id);
+                }
+            }
+        }
+    }
+
+    fn on_message(&mut self, message: SessionMessage) {
+        let is_client = matches!(self.role, Role::Client { .. });
+        match message {
+            SessionMessage::Negotiate(m) if !is_client => self.on_negotiate(m),
+            SessionMessage::Establish(m) if !is_client => self.on_establish(m),
+            SessionMessage::NegotiationResponse(m)
+                if is_client && self.state == State::Negotiating =>
+            {
+                if let Role::Client { negotiated, .. } = &mut self.role {
+                    *negotiated = true;
+                }
+                self.flow().lock().peer_flow = m.server_flow;
+                self.events.push_back(SessionEvent::Negotiated {
+                    session_id: m.session_id,
+                });
+                self.send_establish();
+            }
+            SessionMessage::NegotiationReject(m)
+                if is_client && self.state == State::Negotiating =>
+            {
+                self.events
+                    .push_back(SessionEvent::NegotiationRejected(m.code));
+                self.state = State::Terminated;
+            }
+            SessionMessage::EstablishmentAck(m)
+                if is_client && self.state == State::Establishing =>
+            {
+                self.keepalive = Duration::from_millis(u64::from(m.keepalive_interval));
+                self.on_established(m.next_seq_no);
+            }
+            SessionMessage::EstablishmentReject(m)
+                if is_client && self.state == State::Establishing =>
+            {
+                if m.code == EstablishmentRejectCode::Unnegotiated {
+                    // The server lost the session: start a new one.
+                    if let Role::Client { negotiated, .. } = &mut self.role {
+                        *negotiated = false;
+                    }
+                    self.reset_flow();
+                    self.send_negotiate();
+                } else {
+                    self.events
+                        .push_back(SessionEvent::EstablishmentRejected(m.code));
+                    self.state = State::Terminated;
+                }
+            }
+            SessionMessage::Sequence { next_seq_no } if self.state == State::Established => {
+                self.on_next_seq(next_seq_no);
+            }
+            SessionMessage::RetransmitRequest(m) if self.state == State::Established => {
+                self.on_retransmit_request(&m);
+            }
+            SessionMessage::Retransmission(m) if self.state == State::Established => {
+                if m.count == 0 {
+                    self.request_in_flight = None;
+                    self.request_next();
+                } else {
+                    self.retransmitting = Some((m.count, self.implied_inbound));
+                    self.implied_inbound = m.next_seq_no;
+                }
+            }
+            SessionMessage::RetransmitReject(m) if self.state == State::Established => {
+                if let Some(range) = self.request_in_flight.take() {
+                    self.events.push_back(SessionEvent::RetransmitRejected {
+                        from_seq_no: range.start,
+                        count: (range.end - range.start) as u32,
+                        code: m.code,
+                    });
+                    self.flow().lock().forget_missing(range);
+                }
+                self.request_next();
+                self.maybe_finished_receiving();
+            }
+            SessionMessage::NotApplied(m) if self.state == State::Established => {
+                self.events.push_back(SessionEvent::NotApplied {
+                    from_seq_no: m.from_seq_no,
+                    count: m.count,
+                });
+                // Acknowledge, so the peer knows the flow goes on.
+                let next_seq_no = self.flow().lock().next_outbound;
+                self.push(&SessionMessage::Sequence { next_seq_no });
+            }
+            SessionMessage::FinishedSending(m) if self.state == State::Established => {
+                self.on_next_seq(m.last_seq_no + 1);
+                if !self.peer_finished {
+                    self.peer_finished = true;
+                    self.events.push_back(SessionEvent::PeerFinished);
+                }
+                self.maybe_finished_receiving();
+            }
+            SessionMessage::FinishedReceiving(_)
+                if self.state == State::Established && self.finished_sending =>
+            {
+                self.terminate(TerminationCode::Finished);
+            }
+            SessionMessage::Terminate(m)
+                if self.finished_sending && m.code == TerminationCode::Finished =>
+            {
+                // The answer to our own Terminate, for codecs that finish
+                // with one.
+                self.end(m.code);
+            }
+            SessionMessage::Terminate(m) => {
+                // Answer with Terminate, as FIXP requires.
+                self.terminate(m.code);
+            }
+            _ => self.terminate(TerminationCode::ProtocolError),
+        }
+    }
+
+    fn on_negotiate(&mut self, m: Negotiate) {
+        let Role::Server { registry, .. } = &self.role else {
+            return;
+        };
+        let result = if self.state != State::Idle || self.session_id.is_some() {
+            Err(NegotiationRejectCode::Unspecified)
+        } else if !registry.authenticate(m.session_id, &m.credentials) {
+            Err(NegotiationRejectCode::Credentials)
+        } else {
+            registry.negotiate(m.session_id, m.client_flow)
+        };
+        match result {
+            Ok(()) => {
+                self.session_id = Some(m.session_id);
+                self.push(&SessionMessage::NegotiationResponse(NegotiationResponse {
+                    session_id: m.session_id,
+                    request_timestamp: m.timestamp,
+                    server_flow: FlowType::Recoverable,
+                }));
+                self.events.push_back(SessionEvent::Negotiated {
+                    session_id: m.session_id,
+                });
+            }
+            Err(code) => {
+                self.push(&SessionMessage::NegotiationReject(NegotiationReject {
+                    session_id: m.session_id,
+                    request_timestamp: m.timestamp,
+                    code,
+                }));
+                self.events
+                    .push_back(SessionEvent::NegotiationRejected(code));
+                self.state = State::Terminated;
+            }
+        }
+    }
+
+    fn on_establish(&mut self, m: Establish) {
+        let Role::Server { registry, bound } = &mut self.role else {
+            return;
+        };
+        let keepalive = Duration::from_millis(u64::from(m.keepalive_interval));
+        let result =
+            if self.state != State::Idle || self.session_id.is_some_and(|id| id != m.session_id) {
+                Err(EstablishmentRejectCode::Unspecified)
+            } else if !registry.authenticate(m.session_id, &m.credentials) {
+                Err(EstablishmentRejectCode::Credentials)
+            } else if keepalive.is_zero() || keepalive > registry.config().max_keepalive_interval {
+                Err(EstablishmentRejectCode::KeepaliveInterval)
+            } else {
+                registry.establish(m.session_id)
+            };
+        match result {
+            Ok(flow) => {
+                *bound = Some(m.session_id);
+                let next_seq_no = flow.lock().next_outbound;
+                self.flow = Some(flow);
+                self.session_id = Some(m.session_id);
+                self.keepalive = keepalive;
+                self.push(&SessionMessage::EstablishmentAck(EstablishmentAck {
+                    session_id: m.session_id,
+                    request_timestamp: m.timestamp,
+                    keepalive_interval: m.keepalive_interval,
+                    next_seq_no,
+                }));
+                self.on_established(m.next_seq_no);
+            }
+            Err(code) => {
+                self.push(&SessionMessage::EstablishmentReject(EstablishmentReject {
+                    session_id: m.session_id,
+                    request_timestamp: m.timestamp,
+                    code,
+                }));
+                self.events
+                    .push_back(SessionEvent::EstablishmentRejected(code));
+                // A client whose session is unknown may negotiate a new one.
+                if code != EstablishmentRejectCode::Unnegotiated {
+                    self.state = State::Terminated;
+                }
+            }
+        }
+    }
+
+    fn on_established(&mut self, peer_next_seq_no: u64) {
+        self.state = State::Established;
+        self.events.push_back(SessionEvent::Established {
+            session_id: self.id(),
+            keepalive_interval: self.keepalive,
+        });
+        // Ranges still missing from an earlier connection come first.
+        let missing = self.flow().lock().missing.clone();
+        self.pending_requests.extend(missing);
+        self.on_next_seq(peer_next_seq_no);
+        self.request_next();
+    }
+
+    fn on_application(&mut self) -> Result<Option<u64>, SessionError> {
+        if self.state != State::Established {
+            return Err(SessionError::NotEstablished);
+        }
+        let seq = self.implied_inbound;
+        self.implied_inbound += 1;
+        if let Some((remaining, resume)) = &mut self.retransmitting {
+            *remaining -= 1;
+            if *remaining == 0 {
+                self.implied_inbound = *resume;
+                self.retransmitting = None;
+                self.request_in_flight = None;
+                self.request_next();
+            }
+        }
+        let (deliver, gap) = self.flow().lock().receive(seq);
+        if let Some(gap) = gap {
+            self.on_gap(gap);
+        }
+        self.maybe_finished_receiving();
+        Ok(deliver.then_some(seq))
+    }
+
+    fn on_next_seq(&mut self, next_seq_no: u64) {
+        // A Sequence during a retransmission describes the live flow.
+        match &mut self.retransmitting {
+            Some((_, resume)) => *resume = next_seq_no,
+            None => self.implied_inbound = next_seq_no,
+        }
+        let gap = self.flow().lock().advance(next_seq_no);
+        if let Some(gap) = gap {
+            self.on_gap(gap);
+        }
+    }
+
+    fn on_gap(&mut self, gap: Range<u64>) {
+        self.events.push_back(SessionEvent::Gap {
+            from_seq_no: gap.start,
+            count: gap.end - gap.start,
+        });
+        let peer_flow = self.flow().lock().peer_flow;
+        match peer_flow {
+            FlowType::Recoverable => {
+                self.pending_requests.push_back(gap);
+                self.request_next();
+            }
+            FlowType::Idempotent => {
+                self.push(&SessionMessage::NotApplied(NotApplied {
+                    from_seq_no: gap.start,
+                    count: (gap.end - gap.start).min(u64::from(u32::MAX)) as u32,
+                }));
+            }
+            FlowType::Unsequenced | FlowType::None => {}
+        }
+    }
+
+    /// Sends the next queued RetransmitRequest, one at a time.
+    fn request_next(&mut self) {
+        if self.request_in_flight.is_some() || self.state != State::Established {
+            return;
+        }
+        let Some(mut range) = self.pending_requests.pop_front() else {
+            return;
+        };
+        let count = (range.end - range.start).min(u64::from(self.codec.max_retransmit_count()));
+        if range.start + count < range.end {
+            self.pending_requests
+                .push_front(range.start + count..range.end);
+            range.end = range.start + count;
+        }
+        self.push(&SessionMessage::RetransmitRequest(RetransmitRequest {
+            session_id: self.id(),
+            timestamp: timestamp_nanos(),
+            from_seq_no: range.start,
+            count: count as u32,
+        }));
+        self.request_in_flight = Some(range);
+    }
+
+    fn on_retransmit_request(&mut self, m: &RetransmitRequest) {
+        let (frames, next_seq_no) = {
+            let flow = self.flow().lock();
+            (flow.sent_range(m.from_seq_no, m.count), flow.next_outbound)
+        };
+        let Some(frames) = frames else {
+            self.push(&SessionMessage::RetransmitReject(RetransmitReject {
+                session_id: m.session_id,
+                request_timestamp: m.timestamp,
+                code: RetransmitRejectCode::OutOfRange,
+            }));
+            return;
+        };
+        self.push(&SessionMessage::Retransmission(Retransmission {
+            session_id: m.session_id,
+            request_timestamp: m.timestamp,
+            next_seq_no: m.from_seq_no,
+            count: m.count,
+        }));
+        self.outbox.extend(frames);
+        self.push(&SessionMessage::Sequence { next_seq_no });
+    }
+
+    fn maybe_finished_receiving(&mut self) {
+        if !self.peer_finished || self.sent_finished_receiving {
+            return;
+        }
+        if self.flow().lock().missing.is_empty() {
+            self.sent_finished_receiving = true;
+            self.push(&SessionMessage::FinishedReceiving(FinishedReceiving {
+                session_id: self.id(),
+            }));
+        }
+    }
+
+    fn send_negotiate(&mut self) {
+        let Role::Client { config, .. } = &self.role else {
+            return;
+        };
+        let message = SessionMessage::Negotiate(Negotiate {
+            session_id: config.session_id,
+            timestamp: timestamp_nanos(),
+            client_flow: config.flow_type,
+            credentials: config.credentials.clone(),
+        });
+        self.push(&message);
+        self.state = State::Negotiating;
+    }
+
+    fn send_establish(&mut self) {
+        let Role::Client { config, .. } = &self.role else {
+            return;
+        };
+        let message = SessionMessage::Establish(Establish {
+            session_id: config.session_id,
+            timestamp: timestamp_nanos(),
+            keepalive_interval: u32::try_from(config.keepalive_interval.as_millis())
+                .unwrap_or(u32::MAX),
+            next_seq_no: self.flow().lock().next_outbound,
+            credentials: config.credentials.clone(),
+        });
+        self.push(&message);
+        self.state = State::Establishing;
+    }
+
+    fn reset_flow(&mut self) {
+        if let Role::Client { config, .. } = &self.role {
+            let flow = Flow::new(FlowType::Recoverable, config.retransmit_capacity);
+            self.flow = Some(Arc::new(Mutex::new(flow)));
+        }
+    }
+
+    fn push(&mut self, message: &SessionMessage) {
+        self.outbox.push_back(self.codec.encode(message));
+        self.transmitted = true;
+    }
+
+    /// Records that frames were queued at `now`, for heartbeats.
+    fn touch(&mut self, now: Instant) {
+        if std::mem::take(&mut self.transmitted) {
+            self.last_sent = Some(now);
+        }
+    }
+
+    fn flow(&self) -> &Mutex<Flow> {
+        self.flow.as_deref().expect("flow is set once negotiated")
+    }
+
+    fn id(&self) -> SessionId {
+        self.session_id.expect("session id is set once negotiated")
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if let Role::Server {
+            registry,
+            bound: Some(id),
+        } = &self.role
+        {
+            registry.release(*id);
+        }
+    }
+}
+
+impl std::fmt::Debug for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Session")
+            .field("session_id", &self.session_id)
+            .field("state", &self.state)
+            .field("keepalive", &self.keepalive)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SessionServerConfig;
+
+    fn app(byte: u8) -> Vec<u8> {
+        vec![byte; 12]
+    }
+
+    /// Moves every queued frame from `from` to `to`, returning the
+    /// application messages `to` delivered.
+    fn pump(from: &mut Session, to: &mut Session, now: Instant) -> Vec<(u64, Vec<u8>)> {
+        let mut delivered = Vec::new();
+        while let Some(frame) = from.poll_transmit() {
+            if let Some(seq) = to.on_frame(&frame, now).unwrap() {
+                delivered.push((seq, frame));
+            }
+        }
+        delivered
+    }
+
+    /// Pumps both ways until neither side has anything to send.
+    fn settle(client: &mut Session, server: &mut Session, now: Instant) -> Vec<(u64, Vec<u8>)> {
+        let mut delivered = Vec::new();
+        while !client.outbox.is_empty() || !server.outbox.is_empty() {
+            delivered.extend(pump(client, server, now));
+            pump(server, client, now);
+        }
+        delivered
+    }
+
+    fn events(session: &mut Session) -> Vec<SessionEvent> {
+        std::iter::from_fn(|| session.poll_event()).collect()
+    }
+
+    fn connect(registry: &Arc<FlowRegistry>, client: &mut Session, now: Instant) -> Session {
+        let mut server = Session::server(Arc::clone(registry));
+        server.start(now);
+        client.start(now);
+        settle(client, &mut server, now);
+        server
+    }
+
+    #[test]
+    fn test_handshake_and_sequenced_delivery() {
+        let now = Instant::now();
+        let registry = Arc::new(FlowRegistry::new(SessionServerConfig::default()));
+        let mut client = Session::client(SessionConfig::default());
+        let mut server = connect(&registry, &mut client, now);
+
+        assert!(client.is_established() && server.is_established());
+        let id = client.session_id().unwrap();
+        assert_eq!(server.session_id(), Some(id));
+        assert!(matches!(
+            events(&mut client)[..],
+            [
+                SessionEvent::Negotiated { .. },
+                SessionEvent::Established { .. }
+            ]
+        ));
+        assert!(registry.is_established(id));
+
+        assert_eq!(client.send(&app(1), now).unwrap(), 1);
+        assert_eq!(client.send(&app(2), now).unwrap(), 2);
+        let delivered = pump(&mut client, &mut server, now);
+        assert_eq!(delivered, vec![(1, app(1)), (2, app(2))]);
+
+        drop(server);
+        assert!(!registry.is_established(id));
+    }
+
+    #[test]
+    fn test_gap_is_recovered_by_retransmission() {
+        let now = Instant::now();
+        let registry = Arc::new(FlowRegistry::new(SessionServerConfig::default()));
+        let mut client = Session::client(SessionConfig::default());
+        let mut server = connect(&registry, &mut client, now);
+
+        for i in 1..=3 {
+            client.send(&app(i), now).unwrap();
+        }
+        let first = client.poll_transmit().unwrap();
+        assert_eq!(server.on_frame(&first, now).unwrap(), Some(1));
+        // Messages 2 and 3 are lost; the next heartbeat reveals the gap.
+        client.outbox.clear();
+        let later = now + SessionConfig::default().keepalive_interval;
+        client.on_timer(later);
+        assert!(pump(&mut client, &mut server, later).is_empty());
+        assert_eq!(
+            events(&mut server).last(),
+            Some(&SessionEvent::Gap {
+                from_seq_no: 2,
+                count: 2
+            })
+        );
+
+        // The request goes out and the client resends messages 2 and 3.
+        pump(&mut server, &mut client, later);
+        let resent = pump(&mut client, &mut server, later);
+        assert_eq!(resent, vec![(2, app(2)), (3, app(3))]);
+        // The live flow resumes after the retransmission.
+        client.send(&app(4), later).unwrap();
+        assert_eq!(pump(&mut client, &mut server, later), vec![(4, app(4))]);
+        assert!(server.flow().lock().missing.is_empty());
+    }
+
+    #[test]
+    fn test_idempotent_gap_is_not_applied() {
+        let now = Instant::now();
+        let registry = Arc::new(FlowRegistry::new(SessionServerConfig::default()));
+        let mut client = Session::client(SessionConfig::default().flow_type(FlowType::Idempotent));
+        let mut server = connect(&registry, &mut client, now);
+        events(&mut client);
+
+        client.send(&app(1), now).unwrap();
+        client.send(&app(2), now).unwrap();
+        // Message 2 is lost; the next heartbeat reveals the gap.
+        client.outbox.pop_back();
+        let later = now + SessionConfig::default().keepalive_interval;
+        client.on_timer(later);
+        assert_eq!(pump(&mut client, &mut server, later), vec![(1, app(1))]);
+
+        // The server reports the skipped message instead of requesting it.
+        pump(&mut server, &mut client, later);
+        assert_eq!(
+            events(&mut client),
+            vec![SessionEvent::NotApplied {
+                from_seq_no: 2,
+                count: 1
+            }]
+        );
+        assert!(server.flow().lock().missing.is_empty());
+        // The client acknowledges and carries on.
+        client.send(&app(3), later).unwrap();
+        assert_eq!(pump(&mut client, &mut server, later), vec![(3, app(3))]);
+        assert!(client.is_established() && server.is_established());
+    }
+
+    #[test]
+    fn test_reconnect_recovers_messages_sent_while_down() {
+        let now = Instant::now();
+        let registry = Arc::new(FlowRegistry::new(SessionServerConfig::default()));
+        let mut client = Session::client(SessionConfig::default());
+        let mut server = connect(&registry, &mut client, now);
+
+        server.send(&app(1), now).unwrap();
+        pump(&mut server, &mut client, now);
+        server.send(&app(2), now).unwrap();
+        server.send(&app(3), now).unwrap();
+        // The connection drops with messages 2 and 3 in flight.
+        drop(server);
+
+        let mut server = Session::server(Arc::clone(&registry));
+        server.start(now);
+        client.start(now);
+        pump(&mut client, &mut server, now);
+        let delivered = pump(&mut server, &mut client, now);
+        assert!(delivered.is_empty());
+        settle(&mut client, &mut server, now);
+        assert!(events(&mut client).contains(&SessionEvent::Gap {
+            from_seq_no: 2,
+            count: 2
+        }));
+        assert_eq!(client.next_inbound_seq_no(), Some(4));
+        assert!(client.flow().lock().missing.is_empty());
+        assert_eq!(server.send(&app(4), now).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_retransmission_outside_the_buffer_is_rejected() {
+        let now = Instant::now();
+        let config = SessionServerConfig::default().retransmit_capacity(1);
+        let registry = Arc::new(FlowRegistry::new(config));
+        let mut client = Session::client(SessionConfig::default());
+        let mut server = connect(&registry, &mut client, now);
+
+        for i in 1..=3 {
+            server.send(&app(i), now).unwrap();
+        }
+        server.outbox.clear();
+        server.push(&SessionMessage::Sequence { next_seq_no: 4 });
+        settle(&mut client, &mut server, now);
+
+        let events = events(&mut client);
+        assert!(events.contains(&SessionEvent::RetransmitRejected {
+            from_seq_no: 1,
+            count: 3,
+            code: RetransmitRejectCode::OutOfRange,
+        }));
+        assert!(client.flow().lock().missing.is_empty());
+    }
+
+    #[test]
+    fn test_heartbeat_and_keepalive_lapse() {
+        let now = Instant::now();
+        let registry = Arc::new(FlowRegistry::new(SessionServerConfig::default()));
+        let config = SessionConfig::default().keepalive_interval(Duration::from_millis(100));
+        let mut client = Session::client(config);
+        let mut server = connect(&registry, &mut client, now);
+
+        assert_eq!(
+            client.next_timeout(),
+            Some(now + Duration::from_millis(100))
+        );
+        let later = now + Duration::from_millis(100);
+        client.on_timer(later);
+        let heartbeat = client.poll_transmit().unwrap();
+        assert_eq!(
+            client.codec.decode(&heartbeat).unwrap(),
+            Some(SessionMessage::Sequence { next_seq_no: 1 })
+        );
+        server.on_frame(&heartbeat, later).unwrap();
+
+        client.on_timer(now + Duration::from_millis(200));
+        assert!(client.is_terminated());
+        assert_eq!(
+            events(&mut client).last(),
+            Some(&SessionEvent::Terminated {
+                code: TerminationCode::KeepaliveLapsed
+            })
+        );
+        pump(&mut client, &mut server, later);
+        assert!(server.is_terminated());
+        // The logical session survives a lapse.
+        drop(server);
+        assert!(registry.contains(client.session_id().unwrap()));
+    }
+
+    #[test]
+    fn test_finish_ends_the_logical_session() {
+        let now = Instant::now();
+        let registry = Arc::new(FlowRegistry::new(SessionServerConfig::default()));
+        let mut client = Session::client(SessionConfig::default());
+        let mut server = connect(&registry, &mut client, now);
+        let id = client.session_id().unwrap();
+
+        client.send(&app(1), now).unwrap();
+        client.finish(now).unwrap();
+        assert!(client.send(&app(2), now).is_err());
+        settle(&mut client, &mut server, now);
+
+        assert!(client.is_terminated() && server.is_terminated());
+        assert!(events(&mut server).contains(&SessionEvent::PeerFinished));
+        assert_eq!(
+            events(&mut client).last(),
+            Some(&SessionEvent::Terminated {
+                code: TerminationCode::Finished
+            })
+        );
+        assert!(registry.is_empty());
+        assert_ne!(client.session_id(), Some(id));
+
+        // The next connection negotiates a new session.
+        drop(server);
+        let server = connect(&registry, &mut client, now);
+        assert!(server.is_established());
+        assert_eq!(client.next_outbound_seq_no(), Some(1));
+    }
+
+    #[test]
+    fn test_bad_credentials_are_rejected() {
+        let now = Instant::now();
+        let config = SessionServerConfig::default().authenticator(|_, creds| creds == b"secret");
+        let registry = Arc::new(FlowRegistry::new(config));
+        let mut client = Session::client(SessionConfig::default().credentials("wrong"));
+        let server = connect(&registry, &mut client, now);
+
+        assert!(client.is_terminated() && server.is_terminated());
+        assert_eq!(
+            events(&mut client),
+            vec![SessionEvent::NegotiationRejected(
+                NegotiationRejectCode::Credentials
+            )]
+        );
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_session_is_renegotiated() {
+        let now = Instant::now();
+        let mut client = Session::client(SessionConfig::default());
+        let server = connect(
+            &Arc::new(FlowRegistry::new(SessionServerConfig::default())),
+            &mut client,
+            now,
+        );
+        drop(server);
+
+        // A server that lost its state answers Establish with Unnegotiated.
+        let registry = Arc::new(FlowRegistry::new(SessionServerConfig::default()));
+        let server = connect(&registry, &mut client, now);
+        assert!(client.is_established() && server.is_established());
+        assert!(registry.contains(client.session_id().unwrap()));
+    }
+
+    #[test]
+    fn test_application_message_before_establish_fails() {
+        let registry = Arc::new(FlowRegistry::new(SessionServerConfig::default()));
+        let mut server = Session::server(registry);
+        assert!(matches!(
+            server.on_frame(&app(1), Instant::now()),
+            Err(SessionError::NotEstablished)
+        ));
+    }
+}
@@ -0,0 +1,348 @@
+//! Packet capture to pcap files and paced replay of captures.
+//!
+//! A [`PacketCapture`] writes frames as they cross the wire to a pcap
+//! file that Wireshark, tcpdump and exchange tooling can open:
+//!
+//! - UDP feeds: set [`MulticastConfig::capture`](crate::udp::MulticastConfig::capture)
+//!   and every datagram the receiver reads, from either feed or the
+//!   retransmit server, is written with its receive timestamp before
+//!   arbitration.
+//! - TCP sessions: attach a [`TcpCapture`] to a
+//!   [`MessageTap`](ironsbe_core::MessageTap) and every tapped frame is
+//!   written as a TCP segment of one connection per stream.
+//!
+//! A [`PcapReader`] reads those files back, and captures taken by
+//! tcpdump, Wireshark or a capture appliance in pcap or pcapng format. A
+//! [`Replay`] hands the packets out with the gaps between them preserved
+//! or scaled, to feed a handler such as a market data book directly, and
+//! [`replay_udp`] (feature `tokio`) sends the UDP datagrams back onto the
+//! network, e.g. at a [`MulticastReceiver`](crate::udp::MulticastReceiver)
+//! under test.
+//!
+//! ```no_run
+//! use ironsbe_transport::capture::{PcapReader, Protocol, Replay};
+//!
+//! let reader = PcapReader::open("feed.pcap")?;
+//! for packet in Replay::new(reader, 1.0) {
+//!     let packet = packet?;
+//!     if let Some(datagram) = packet.datagram().filter(|d| d.protocol == Protocol::Udp) {
+//!         // Skip the 8-byte sequence header, decode the messages and pass
+//!         // them to the handler with `packet.timestamp` as receive time.
+//!         let _messages = &datagram.payload[8..];
+//!     }
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+mod packet;
+mod pcap;
+#[cfg(feature = "tokio")]
+mod replay;
+
+pub use packet::{Datagram, Protocol};
+pub use pcap::{CapturedPacket, PcapReader, PcapWriter};
+#[cfg(feature = "tokio")]
+pub use replay::{ReplayConfig, replay_udp};
+
+use ironsbe_core::tap::{TapDirection, TapRecord, TapSink};
+use parking_lot::Mutex;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Shared writer of captured packets to a pcap file.
+///
+/// Recording never fails the caller: write errors are logged and counted
+/// in [`errors`](Self::errors), and the packet is lost from the capture.
+pub struct PacketCapture {
+    writer: Mutex<PcapWriter<Box<dyn Write + Send>>>,
+    errors: AtomicU64,
+}
+
+impl PacketCapture {
+    /// Creates a capture writing to `out`.
+    ///
+    /// # Errors
+    /// Returns an error if the pcap header cannot be written.
+    pub fn new(out: impl Write + Send + 'static) -> io::Result<Self> {
+        Ok(Self {
+            writer: Mutex::new(PcapWriter::new(Box::new(out) as Box<dyn Write + Send>)?),
+            errors: AtomicU64::new(0),
+        })
+    }
+
+    /// Creates a capture writing to a new pcap file at `path`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be created or written.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+
+    /// Records a UDP datagram from `src` to `dst` received or sent at
+    /// `timestamp`, in nanoseconds since the Unix epoch.
+    pub fn udp(&self, timestamp: u64, src: SocketAddrV4, dst: SocketAddrV4, payload: &[u8]) {
+        let result = self.writer.lock().write_udp(timestamp, src, dst, payload);
+        self.check(result);
+    }
+
+    /// Records bytes sent over TCP from `src` to `dst` at `timestamp`, in
+    /// nanoseconds since the Unix epoch.
+    pub fn tcp(&self, timestamp: u64, src: SocketAddrV4, dst: SocketAddrV4, payload: &[u8]) {
+        let result = self.writer.lock().write_tcp(timestamp, src, dst, payload);
+        self.check(result);
+    }
+
+    /// Writes buffered packets to the file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn flush(&self) -> io::Result<()> {
+        self.writer.lock().flush()
+    }
+
+    /// Returns the number of packets lost to write errors.
+    #[must_use]
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    fn check(&self, result: io::Result<()>) {
+        if let Err(err) = result {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!("Failed to capture packet: {}", err);
+        }
+    }
+}
+
+impl fmt::Debug for PacketCapture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PacketCapture")
+            .field("errors", &self.errors())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for PacketCapture {
+    fn drop(&mut self) {
+        let result = self.writer.get_mut().flush();
+        self.check(result);
+    }
+}
+
+/// [`TapSink`] recording tapped frames as TCP segments.
+///
+/// A tap sees frames rather than sockets, so the addresses are made up:
+/// every stream is a connection between `local` and a port on `peer`
+/// derived from the stream id, with inbound frames sent by the peer and
+/// outbound frames by `local`.
+#[derive(Debug, Clone)]
+pub struct TcpCapture {
+    capture: Arc<PacketCapture>,
+    local: SocketAddrV4,
+    peer: Ipv4Addr,
+}
+
+impl TcpCapture {
+    /// Creates a sink recording to `capture`.
+    #[must_use]
+    pub fn new(capture: Arc<PacketCapture>, local: SocketAddrV4, peer: Ipv4Addr) -> Self {
+        Self {
+            capture,
+            local,
+            peer,
+        }
+    }
+}
+
+impl TapSink for TcpCapture {
+    fn record(&mut self, record: TapRecord) -> io::Result<()> {
+        // Ports 1024 and up, so no stream looks like a well-known service.
+        let port = 1024 + (record.stream % u64::from(u16::MAX - 1023)) as u16;
+        let peer = SocketAddrV4::new(self.peer, port);
+        let (src, dst) = match record.direction {
+            TapDirection::Inbound => (peer, self.local),
+            TapDirection::Outbound => (self.local, peer),
+        };
+        self.capture
+            .tcp(record.timestamp.as_nanos(), src, dst, &record.bytes);
+        Ok(())
+    }
+}
+
+/// Schedule of a paced replay.
+///
+/// The first packet is due at once, and every later one once the time
+/// between its timestamp and the first packet's, divided by the speed,
+/// has passed. Speeds that are not positive replay as fast as possible.
+#[derive(Debug)]
+struct Pacer {
+    speed: f64,
+    start: Option<(Instant, u64)>,
+}
+
+impl Pacer {
+    fn new(speed: f64) -> Self {
+        Self { speed, start: None }
+    }
+
+    /// Returns how long to wait before handing out a packet captured at
+    /// `timestamp`, if at all.
+    fn wait(&mut self, timestamp: u64) -> Option<Duration> {
+        let Some((started, first)) = self.start else {
+            self.start = Some((Instant::now(), timestamp));
+            return None;
+        };
+        if self.speed <= 0.0 || self.speed.is_nan() {
+            return None;
+        }
+        let delay =
+            Duration::from_nanos((timestamp.saturating_sub(first) as f64 / self.speed) as u64);
+        (started + delay).checked_duration_since(Instant::now())
+    }
+}
+
+/// Iterator over captured packets that waits before each one until it is
+/// due.
+///
+/// With a `speed` of `1.0` packets are spaced as they were captured,
+/// `10.0` replays ten times faster, and zero or less as fast as possible.
+/// A consumer slower than the capture falls behind rather than skipping
+/// packets.
+pub struct Replay<I> {
+    packets: I,
+    pacer: Pacer,
+}
+
+impl<I> Replay<I>
+where
+    I: Iterator<Item = io::Result<CapturedPacket>>,
+{
+    /// Creates a replay of `packets`, usually a [`PcapReader`].
+    pub fn new(packets: I, speed: f64) -> Self {
+        Self {
+            packets,
+            pacer: Pacer::new(speed),
+        }
+    }
+}
+
+impl<I> Iterator for Replay<I>
+where
+    I: Iterator<Item = io::Result<CapturedPacket>>,
+{
+    type Item = io::Result<CapturedPacket>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let packet = self.packets.next()?;
+        if let Ok(packet) = &packet
+            && let Some(wait) = self.pacer.wait(packet.timestamp)
+        {
+            std::thread::sleep(wait);
+        }
+        Some(packet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironsbe_core::types::Timestamp;
+    use std::sync::mpsc;
+
+    /// `Write` handing its bytes to the test once the capture is dropped.
+    struct Collect(Vec<u8>, mpsc::Sender<Vec<u8>>);
+
+    impl Write for Collect {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Drop for Collect {
+        fn drop(&mut self) {
+            let _ = self.1.send(std::mem::take(&mut self.0));
+        }
+    }
+
+    fn addr(s: &str) -> SocketAddrV4 {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_tcp_capture_records_tapped_frames() {
+        let (tx, rx) = mpsc::channel();
+        let capture = Arc::new(PacketCapture::new(Collect(Vec::new(), tx)).unwrap());
+        let local = addr("10.0.0.1:9000");
+        let mut sink = TcpCapture::new(capture.clone(), local, Ipv4Addr::new(10, 0, 0, 2));
+        for (direction, bytes) in [
+            (TapDirection::Inbound, b"new order".to_vec()),
+            (TapDirection::Outbound, b"execution".to_vec()),
+        ] {
+            sink.record(TapRecord {
+                timestamp: Timestamp::new(42),
+                direction,
+                stream: 7,
+                template_id: None,
+                bytes,
+            })
+            .unwrap();
+        }
+        drop(sink);
+        drop(capture);
+
+        let bytes = rx.recv().unwrap();
+        let packets: Vec<_> = PcapReader::new(&bytes[..])
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        let peer = addr("10.0.0.2:1031");
+        let first = packets[0].datagram().unwrap();
+        assert_eq!(
+            (first.protocol, first.src, first.dst),
+            (Protocol::Tcp, peer, local)
+        );
+        assert_eq!(first.payload, b"new order");
+        let second = packets[1].datagram().unwrap();
+        assert_eq!((second.src, second.dst), (local, peer));
+        assert_eq!(packets[1].timestamp, 42);
+    }
+
+    #[test]
+    fn test_replay_preserves_spacing() {
+        let packets = (0..4u64).map(|i| {
+            Ok(CapturedPacket {
+                timestamp: 1_000_000_000 + i * 40_000_000,
+                link_type: 1,
+                data: vec![i as u8],
+                big_endian: false,
+            })
+        });
+        let started = Instant::now();
+        let data: Vec<_> = Replay::new(packets.clone(), 1.0)
+            .map(|p| p.unwrap().data[0])
+            .collect();
+        assert_eq!(data, [0, 1, 2, 3]);
+        assert!(started.elapsed() >= Duration::from_millis(120));
+
+        let started = Instant::now();
+        assert_eq!(Replay::new(packets.clone(), 4.0).count(), 4);
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(30));
+        assert!(elapsed < Duration::from_millis(110));
+
+        let started = Instant::now();
+        assert_eq!(Replay::new(packets, 0.0).count(), 4);
+        assert!(started.elapsed() < Duration::from_millis(30));
+    }
+}
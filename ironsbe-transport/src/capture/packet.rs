@@ -0,0 +1,322 @@
+//! Building and parsing the link, IPv4, UDP and TCP headers around captured
+//! payloads.
+
+use std::net::{Ipv4Addr, SocketAddrV4};
+
+/// Ethernet.
+pub(crate) const LINKTYPE_ETHERNET: u16 = 1;
+/// BSD loopback, with a host-endian address family.
+const LINKTYPE_NULL: u16 = 0;
+/// Raw IP, as written by some capture tools.
+const LINKTYPE_RAW: u16 = 101;
+/// Linux cooked capture, as on the `any` interface.
+const LINKTYPE_LINUX_SLL: u16 = 113;
+/// Raw IPv4.
+const LINKTYPE_IPV4: u16 = 228;
+/// Linux cooked capture, version 2.
+const LINKTYPE_LINUX_SLL2: u16 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const ETHERTYPE_QINQ: u16 = 0x88A8;
+
+const PROTOCOL_TCP: u8 = 6;
+const PROTOCOL_UDP: u8 = 17;
+
+const ETHERNET_HEADER_LEN: usize = 14;
+const IPV4_HEADER_LEN: usize = 20;
+const UDP_HEADER_LEN: usize = 8;
+const TCP_HEADER_LEN: usize = 20;
+
+/// Largest UDP payload an IPv4 packet holds.
+pub(crate) const MAX_UDP_PAYLOAD: usize = 65_535 - IPV4_HEADER_LEN - UDP_HEADER_LEN;
+
+/// Largest TCP payload written in one segment.
+pub(crate) const MAX_TCP_PAYLOAD: usize = 65_535 - IPV4_HEADER_LEN - TCP_HEADER_LEN;
+
+/// Transport protocol of a captured datagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    /// UDP; the payload is one datagram.
+    Udp,
+    /// TCP; the payload is a segment of the byte stream, which need not
+    /// start or end on a frame boundary.
+    Tcp,
+}
+
+/// The addresses and payload of a captured IPv4 UDP or TCP packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Datagram<'a> {
+    /// Transport protocol.
+    pub protocol: Protocol,
+    /// Source address.
+    pub src: SocketAddrV4,
+    /// Destination address, such as the multicast group of a feed.
+    pub dst: SocketAddrV4,
+    /// Transport payload.
+    pub payload: &'a [u8],
+}
+
+/// Builds the Ethernet frame of a UDP datagram.
+pub(crate) fn udp_frame(src: SocketAddrV4, dst: SocketAddrV4, payload: &[u8]) -> Vec<u8> {
+    let mut frame = ethernet_ipv4(src, dst, PROTOCOL_UDP, UDP_HEADER_LEN + payload.len());
+    frame.extend_from_slice(&src.port().to_be_bytes());
+    frame.extend_from_slice(&dst.port().to_be_bytes());
+    frame.extend_from_slice(&((UDP_HEADER_LEN + payload.len()) as u16).to_be_bytes());
+    frame.extend_from_slice(&[0, 0]);
+    frame.extend_from_slice(payload);
+    let checksum = transport_checksum(
+        src,
+        dst,
+        PROTOCOL_UDP,
+        &frame[ETHERNET_HEADER_LEN + IPV4_HEADER_LEN..],
+    );
+    // Zero means no checksum in UDP, so a computed zero is sent as ones.
+    let checksum = if checksum == 0 { 0xFFFF } else { checksum };
+    let at = ETHERNET_HEADER_LEN + IPV4_HEADER_LEN + 6;
+    frame[at..at + 2].copy_from_slice(&checksum.to_be_bytes());
+    frame
+}
+
+/// Builds the Ethernet frame of a TCP segment with the PSH and ACK flags.
+pub(crate) fn tcp_frame(
+    src: SocketAddrV4,
+    dst: SocketAddrV4,
+    seq: u32,
+    ack: u32,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut frame = ethernet_ipv4(src, dst, PROTOCOL_TCP, TCP_HEADER_LEN + payload.len());
+    frame.extend_from_slice(&src.port().to_be_bytes());
+    frame.extend_from_slice(&dst.port().to_be_bytes());
+    frame.extend_from_slice(&seq.to_be_bytes());
+    frame.extend_from_slice(&ack.to_be_bytes());
+    frame.push((TCP_HEADER_LEN as u8 / 4) << 4);
+    frame.push(0x18); // PSH | ACK
+    frame.extend_from_slice(&u16::MAX.to_be_bytes()); // window
+    frame.extend_from_slice(&[0, 0, 0, 0]); // checksum, urgent pointer
+    frame.extend_from_slice(payload);
+    let checksum = transport_checksum(
+        src,
+        dst,
+        PROTOCOL_TCP,
+        &frame[ETHERNET_HEADER_LEN + IPV4_HEADER_LEN..],
+    );
+    let at = ETHERNET_HEADER_LEN + IPV4_HEADER_LEN + 16;
+    frame[at..at + 2].copy_from_slice(&checksum.to_be_bytes());
+    frame
+}
+
+/// Builds the Ethernet and IPv4 headers of a packet carrying `len` bytes
+/// of `protocol`.
+fn ethernet_ipv4(src: SocketAddrV4, dst: SocketAddrV4, protocol: u8, len: usize) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(ETHERNET_HEADER_LEN + IPV4_HEADER_LEN + len);
+    frame.extend_from_slice(&mac(*dst.ip()));
+    frame.extend_from_slice(&mac(*src.ip()));
+    frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+
+    let mut ip = [0u8; IPV4_HEADER_LEN];
+    ip[0] = 0x45;
+    ip[2..4].copy_from_slice(&((IPV4_HEADER_LEN + len) as u16).to_be_bytes());
+    ip[6] = 0x40; // don't fragment
+    ip[8] = 64; // TTL
+    ip[9] = protocol;
+    ip[12..16].copy_from_slice(&src.ip().octets());
+    ip[16..20].copy_from_slice(&dst.ip().octets());
+    let checksum = !fold(sum(0, &ip));
+    ip[10..12].copy_from_slice(&checksum.to_be_bytes());
+    frame.extend_from_slice(&ip);
+    frame
+}
+
+/// Returns the MAC address for `ip`: the mapped group address for
+/// multicast, and a locally administered one derived from the IP
+/// otherwise.
+fn mac(ip: Ipv4Addr) -> [u8; 6] {
+    let [a, b, c, d] = ip.octets();
+    if ip.is_multicast() {
+        [0x01, 0x00, 0x5E, b & 0x7F, c, d]
+    } else {
+        [0x02, 0x00, a, b, c, d]
+    }
+}
+
+/// Returns the UDP or TCP checksum of `segment`, including the IPv4
+/// pseudo-header.
+fn transport_checksum(src: SocketAddrV4, dst: SocketAddrV4, protocol: u8, segment: &[u8]) -> u16 {
+    let mut acc = sum(0, &src.ip().octets());
+    acc = sum(acc, &dst.ip().octets());
+    acc = sum(acc, &[0, protocol]);
+    acc = sum(acc, &(segment.len() as u16).to_be_bytes());
+    !fold(sum(acc, segment))
+}
+
+/// Adds `data` to a one's-complement sum of big-endian 16-bit words.
+fn sum(mut acc: u64, data: &[u8]) -> u64 {
+    let mut words = data.chunks_exact(2);
+    for word in &mut words {
+        acc += u64::from(u16::from_be_bytes([word[0], word[1]]));
+    }
+    if let [last] = words.remainder() {
+        acc += u64::from(*last) << 8;
+    }
+    acc
+}
+
+fn fold(mut acc: u64) -> u16 {
+    while acc > 0xFFFF {
+        acc = (acc & 0xFFFF) + (acc >> 16);
+    }
+    acc as u16
+}
+
+/// Parses the IPv4 UDP or TCP packet in a frame of `link_type`.
+///
+/// Returns `None` for other protocols, link types and IP fragments, and
+/// for truncated packets.
+pub(crate) fn parse(link_type: u16, frame: &[u8], big_endian: bool) -> Option<Datagram<'_>> {
+    let ip = match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut ethertype = u16::from_be_bytes(frame.get(12..14)?.try_into().ok()?);
+            let mut at = ETHERNET_HEADER_LEN;
+            while ethertype == ETHERTYPE_VLAN || ethertype == ETHERTYPE_QINQ {
+                ethertype = u16::from_be_bytes(frame.get(at + 2..at + 4)?.try_into().ok()?);
+                at += 4;
+            }
+            (ethertype == ETHERTYPE_IPV4).then_some(frame.get(at..)?)?
+        }
+        LINKTYPE_NULL => {
+            let family: [u8; 4] = frame.get(..4)?.try_into().ok()?;
+            let family = if big_endian {
+                u32::from_be_bytes(family)
+            } else {
+                u32::from_le_bytes(family)
+            };
+            (family == 2).then_some(frame.get(4..)?)?
+        }
+        LINKTYPE_RAW | LINKTYPE_IPV4 => frame,
+        LINKTYPE_LINUX_SLL => {
+            let protocol = u16::from_be_bytes(frame.get(14..16)?.try_into().ok()?);
+            (protocol == ETHERTYPE_IPV4).then_some(frame.get(16..)?)?
+        }
+        LINKTYPE_LINUX_SLL2 => {
+            let protocol = u16::from_be_bytes(frame.get(..2)?.try_into().ok()?);
+            (protocol == ETHERTYPE_IPV4).then_some(frame.get(20..)?)?
+        }
+        _ => return None,
+    };
+    parse_ipv4(ip)
+}
+
+fn parse_ipv4(ip: &[u8]) -> Option<Datagram<'_>> {
+    if ip.len() < IPV4_HEADER_LEN || ip[0] >> 4 != 4 {
+        return None;
+    }
+    let header_len = usize::from(ip[0] & 0x0F) * 4;
+    let total_len = usize::from(u16::from_be_bytes([ip[2], ip[3]]));
+    // More fragments, or a fragment offset.
+    if u16::from_be_bytes([ip[6], ip[7]]) & 0x3FFF != 0 {
+        return None;
+    }
+    // Ethernet pads short frames, so the IP length bounds the packet.
+    let segment = ip.get(header_len..total_len)?;
+    let src_ip = Ipv4Addr::new(ip[12], ip[13], ip[14], ip[15]);
+    let dst_ip = Ipv4Addr::new(ip[16], ip[17], ip[18], ip[19]);
+    let port = |at: usize| u16::from_be_bytes([segment[at], segment[at + 1]]);
+
+    let (protocol, payload) = match ip[9] {
+        PROTOCOL_UDP if segment.len() >= UDP_HEADER_LEN => {
+            let len = usize::from(port(4));
+            (Protocol::Udp, segment.get(UDP_HEADER_LEN..len)?)
+        }
+        PROTOCOL_TCP if segment.len() >= TCP_HEADER_LEN => {
+            let offset = usize::from(segment[12] >> 4) * 4;
+            (Protocol::Tcp, segment.get(offset..)?)
+        }
+        _ => return None,
+    };
+    Some(Datagram {
+        protocol,
+        src: SocketAddrV4::new(src_ip, port(0)),
+        dst: SocketAddrV4::new(dst_ip, port(2)),
+        payload,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(s: &str) -> SocketAddrV4 {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_udp_frame_round_trip() {
+        let frame = udp_frame(addr("10.0.0.1:5000"), addr("239.1.1.1:14310"), b"tick");
+        assert_eq!(&frame[..6], &[0x01, 0x00, 0x5E, 0x01, 0x01, 0x01]);
+        // Checksums over data that includes a correct one come out zero.
+        assert_eq!(!fold(sum(0, &frame[14..34])), 0);
+        let segment = &frame[ETHERNET_HEADER_LEN + IPV4_HEADER_LEN..];
+        let (src, dst) = (addr("10.0.0.1:5000"), addr("239.1.1.1:14310"));
+        assert_eq!(transport_checksum(src, dst, PROTOCOL_UDP, segment), 0);
+
+        let datagram = parse(LINKTYPE_ETHERNET, &frame, false).unwrap();
+        assert_eq!(datagram.protocol, Protocol::Udp);
+        assert_eq!(datagram.src, addr("10.0.0.1:5000"));
+        assert_eq!(datagram.dst, addr("239.1.1.1:14310"));
+        assert_eq!(datagram.payload, b"tick");
+    }
+
+    #[test]
+    fn test_tcp_frame_round_trip() {
+        let frame = tcp_frame(addr("10.0.0.1:9000"), addr("10.0.0.2:40000"), 7, 9, b"hi");
+        let datagram = parse(LINKTYPE_ETHERNET, &frame, false).unwrap();
+        assert_eq!(datagram.protocol, Protocol::Tcp);
+        assert_eq!(datagram.payload, b"hi");
+        let segment = &frame[ETHERNET_HEADER_LEN + IPV4_HEADER_LEN..];
+        let (src, dst) = (datagram.src, datagram.dst);
+        assert_eq!(transport_checksum(src, dst, PROTOCOL_TCP, segment), 0);
+    }
+
+    #[test]
+    fn test_parse_other_link_types() {
+        let frame = udp_frame(addr("10.0.0.1:1"), addr("10.0.0.2:2"), b"x");
+        let ip = &frame[ETHERNET_HEADER_LEN..];
+
+        let mut vlan = frame[..12].to_vec();
+        vlan.extend_from_slice(&[0x81, 0x00, 0x00, 0x05]);
+        vlan.extend_from_slice(&frame[12..]);
+        assert_eq!(
+            parse(LINKTYPE_ETHERNET, &vlan, false).unwrap().payload,
+            b"x"
+        );
+
+        assert_eq!(parse(LINKTYPE_RAW, ip, false).unwrap().payload, b"x");
+
+        let mut null = 2u32.to_le_bytes().to_vec();
+        null.extend_from_slice(ip);
+        assert_eq!(parse(LINKTYPE_NULL, &null, false).unwrap().payload, b"x");
+
+        let mut sll = vec![0u8; 14];
+        sll.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        sll.extend_from_slice(ip);
+        assert_eq!(
+            parse(LINKTYPE_LINUX_SLL, &sll, false).unwrap().payload,
+            b"x"
+        );
+
+        // Ethernet padding past the IP length is ignored.
+        let mut padded = frame.clone();
+        padded.extend_from_slice(&[0; 20]);
+        assert_eq!(
+            parse(LINKTYPE_ETHERNET, &padded, false).unwrap().payload,
+            b"x"
+        );
+
+        // Fragments are skipped.
+        let mut fragment = frame;
+        fragment[ETHERNET_HEADER_LEN + 6] = 0x20;
+        assert!(parse(LINKTYPE_ETHERNET, &fragment, false).is_none());
+    }
+}
@@ -0,0 +1,593 @@
+//! pcap and pcapng files.
+//!
+//! [`PcapWriter`] writes classic pcap with nanosecond timestamps and
+//! synthesized Ethernet, IPv4 and UDP or TCP headers, which Wireshark and
+//! tcpdump read like any other capture. [`PcapReader`] reads classic pcap
+//! in either byte order and either timestamp resolution, and pcapng, as
+//! written by tcpdump, Wireshark and most capture appliances.
+
+use super::packet::{self, Datagram, LINKTYPE_ETHERNET, MAX_TCP_PAYLOAD, MAX_UDP_PAYLOAD};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::SocketAddrV4;
+use std::path::Path;
+
+/// Magic number of classic pcap with microsecond timestamps.
+const PCAP_MAGIC_MICROS: u32 = 0xA1B2_C3D4;
+/// Magic number of classic pcap with nanosecond timestamps.
+const PCAP_MAGIC_NANOS: u32 = 0xA1B2_3C4D;
+
+/// pcapng section header block.
+const BLOCK_SECTION_HEADER: u32 = 0x0A0D_0D0A;
+/// pcapng interface description block.
+const BLOCK_INTERFACE: u32 = 1;
+/// pcapng simple packet block.
+const BLOCK_SIMPLE_PACKET: u32 = 3;
+/// pcapng enhanced packet block.
+const BLOCK_ENHANCED_PACKET: u32 = 6;
+/// Byte-order magic of a pcapng section.
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+/// pcapng `if_tsresol` option.
+const OPTION_TSRESOL: u16 = 9;
+
+/// Largest block or packet the reader accepts.
+const MAX_RECORD_LEN: usize = 16 * 1024 * 1024;
+
+/// Writer of classic pcap files.
+///
+/// Each payload becomes one Ethernet frame. TCP payloads are written as
+/// segments of one connection per address pair, with sequence and
+/// acknowledgement numbers continuing from segment to segment, so
+/// Wireshark can reassemble the stream.
+pub struct PcapWriter<W: Write> {
+    out: W,
+    /// Next sequence number of each TCP direction.
+    tcp: HashMap<(SocketAddrV4, SocketAddrV4), u32>,
+}
+
+impl PcapWriter<BufWriter<File>> {
+    /// Creates the pcap file at `path`, replacing any file there.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be created or written.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Creates a writer, writing the file header to `out`.
+    ///
+    /// # Errors
+    /// Returns an error if the header cannot be written.
+    pub fn new(mut out: W) -> io::Result<Self> {
+        let mut header = [0u8; 24];
+        header[..4].copy_from_slice(&PCAP_MAGIC_NANOS.to_le_bytes());
+        header[4..6].copy_from_slice(&2u16.to_le_bytes());
+        header[6..8].copy_from_slice(&4u16.to_le_bytes());
+        header[16..20].copy_from_slice(&(u32::from(u16::MAX)).to_le_bytes());
+        header[20..24].copy_from_slice(&u32::from(LINKTYPE_ETHERNET).to_le_bytes());
+        out.write_all(&header)?;
+        Ok(Self {
+            out,
+            tcp: HashMap::new(),
+        })
+    }
+
+    /// Writes a UDP datagram from `src` to `dst` received or sent at
+    /// `timestamp`, in nanoseconds since the Unix epoch.
+    ///
+    /// # Errors
+    /// Returns an `InvalidInput` error if the payload does not fit in a
+    /// datagram, or an error if the packet cannot be written.
+    pub fn write_udp(
+        &mut self,
+        timestamp: u64,
+        src: SocketAddrV4,
+        dst: SocketAddrV4,
+        payload: &[u8],
+    ) -> io::Result<()> {
+        if payload.len() > MAX_UDP_PAYLOAD {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "payload does not fit in a UDP datagram",
+            ));
+        }
+        self.write_packet(timestamp, &packet::udp_frame(src, dst, payload))
+    }
+
+    /// Writes bytes sent over TCP from `src` to `dst` at `timestamp`, in
+    /// nanoseconds since the Unix epoch, as one segment or as several if
+    /// they do not fit in one.
+    ///
+    /// # Errors
+    /// Returns an error if a packet cannot be written.
+    pub fn write_tcp(
+        &mut self,
+        timestamp: u64,
+        src: SocketAddrV4,
+        dst: SocketAddrV4,
+        payload: &[u8],
+    ) -> io::Result<()> {
+        for segment in payload.chunks(MAX_TCP_PAYLOAD) {
+            let ack = self.tcp.get(&(dst, src)).copied().unwrap_or(0);
+            let seq = self.tcp.entry((src, dst)).or_insert(0);
+            let frame = packet::tcp_frame(src, dst, *seq, ack, segment);
+            *seq = seq.wrapping_add(segment.len() as u32);
+            self.write_packet(timestamp, &frame)?;
+        }
+        Ok(())
+    }
+
+    fn write_packet(&mut self, timestamp: u64, frame: &[u8]) -> io::Result<()> {
+        let mut header = [0u8; 16];
+        header[..4].copy_from_slice(&((timestamp / 1_000_000_000) as u32).to_le_bytes());
+        header[4..8].copy_from_slice(&((timestamp % 1_000_000_000) as u32).to_le_bytes());
+        header[8..12].copy_from_slice(&(frame.len() as u32).to_le_bytes());
+        header[12..16].copy_from_slice(&(frame.len() as u32).to_le_bytes());
+        self.out.write_all(&header)?;
+        self.out.write_all(frame)
+    }
+
+    /// Flushes buffered packets to the underlying writer.
+    ///
+    /// # Errors
+    /// Returns an error if the writer cannot be flushed.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// A packet read from a capture file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedPacket {
+    /// Capture time in nanoseconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Link-layer header type of [`data`](Self::data), as a `LINKTYPE_*`
+    /// value.
+    pub link_type: u16,
+    /// The captured bytes, from the link-layer header on.
+    pub data: Vec<u8>,
+    pub(crate) big_endian: bool,
+}
+
+impl CapturedPacket {
+    /// Returns the IPv4 UDP or TCP packet in the frame.
+    ///
+    /// Returns `None` for other protocols and link types, for IP
+    /// fragments, and for packets cut short by the capture's snap length.
+    #[must_use]
+    pub fn datagram(&self) -> Option<Datagram<'_>> {
+        packet::parse(self.link_type, &self.data, self.big_endian)
+    }
+}
+
+/// Layout of the file being read.
+enum Format {
+    Pcap {
+        big_endian: bool,
+        /// Timestamp units per second.
+        resolution: u64,
+        link_type: u16,
+    },
+    PcapNg {
+        big_endian: bool,
+        /// Link type and timestamp units per second of each interface.
+        interfaces: Vec<(u16, u64)>,
+    },
+}
+
+/// Reader of pcap and pcapng files.
+///
+/// Iterating yields every packet in file order; use
+/// [`CapturedPacket::datagram`] to get at the UDP and TCP payloads.
+pub struct PcapReader<R: Read> {
+    input: R,
+    format: Format,
+}
+
+impl PcapReader<BufReader<File>> {
+    /// Opens the capture file at `path`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, or an `InvalidData`
+    /// error if it is not a pcap or pcapng file.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Creates a reader, reading the file header from `input`.
+    ///
+    /// # Errors
+    /// Returns an error if the header cannot be read, or an `InvalidData`
+    /// error if it is not a pcap or pcapng header.
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        input.read_exact(&mut magic)?;
+        let format = if u32::from_le_bytes(magic) == BLOCK_SECTION_HEADER {
+            let big_endian = read_section_header(&mut input)?;
+            Format::PcapNg {
+                big_endian,
+                interfaces: Vec::new(),
+            }
+        } else {
+            let (big_endian, resolution) =
+                match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)) {
+                    (PCAP_MAGIC_MICROS, _) => (false, 1_000_000),
+                    (PCAP_MAGIC_NANOS, _) => (false, 1_000_000_000),
+                    (_, PCAP_MAGIC_MICROS) => (true, 1_000_000),
+                    (_, PCAP_MAGIC_NANOS) => (true, 1_000_000_000),
+                    _ => return Err(invalid("not a pcap or pcapng file")),
+                };
+            let mut header = [0u8; 20];
+            input.read_exact(&mut header)?;
+            // The upper bits of the link type field hold FCS flags.
+            let link_type = u32_at(&header, 16, big_endian) as u16;
+            Format::Pcap {
+                big_endian,
+                resolution,
+                link_type,
+            }
+        };
+        Ok(Self { input, format })
+    }
+
+    /// Reads the next packet.
+    ///
+    /// # Returns
+    /// The packet, or `None` at the end of the file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, or an `InvalidData`
+    /// error if it is malformed or truncated.
+    pub fn next_packet(&mut self) -> io::Result<Option<CapturedPacket>> {
+        match &mut self.format {
+            Format::Pcap {
+                big_endian,
+                resolution,
+                link_type,
+            } => {
+                let mut header = [0u8; 16];
+                if !read_or_eof(&mut self.input, &mut header)? {
+                    return Ok(None);
+                }
+                let seconds = u64::from(u32_at(&header, 0, *big_endian));
+                let fraction = u64::from(u32_at(&header, 4, *big_endian));
+                let len = u32_at(&header, 8, *big_endian) as usize;
+                if len > MAX_RECORD_LEN {
+                    return Err(invalid("packet length out of range"));
+                }
+                let mut data = vec![0u8; len];
+                self.input.read_exact(&mut data).map_err(truncated)?;
+                Ok(Some(CapturedPacket {
+                    timestamp: seconds * 1_000_000_000 + fraction * (1_000_000_000 / *resolution),
+                    link_type: *link_type,
+                    data,
+                    big_endian: *big_endian,
+                }))
+            }
+            Format::PcapNg { .. } => self.next_block(),
+        }
+    }
+
+    /// Reads pcapng blocks up to and including the next packet.
+    fn next_block(&mut self) -> io::Result<Option<CapturedPacket>> {
+        loop {
+            let Format::PcapNg {
+                big_endian,
+                interfaces,
+            } = &mut self.format
+            else {
+                unreachable!("called on a pcapng file");
+            };
+            let mut header = [0u8; 8];
+            if !read_or_eof(&mut self.input, &mut header)? {
+                return Ok(None);
+            }
+            let block_type = u32::from_le_bytes(header[..4].try_into().unwrap());
+            if block_type == BLOCK_SECTION_HEADER {
+                // A new section may switch byte order and has its own
+                // interfaces.
+                *big_endian = read_section_header(&mut (&header[4..]).chain(&mut self.input))?;
+                interfaces.clear();
+                continue;
+            }
+            let block_type = u32_at(&header, 0, *big_endian);
+            let total = u32_at(&header, 4, *big_endian) as usize;
+            if !(12..=MAX_RECORD_LEN).contains(&total) || !total.is_multiple_of(4) {
+                return Err(invalid("block length out of range"));
+            }
+            let mut body = vec![0u8; total - 8];
+            self.input.read_exact(&mut body).map_err(truncated)?;
+            body.truncate(total - 12);
+            let be = *big_endian;
+
+            match block_type {
+                BLOCK_INTERFACE => {
+                    if body.len() < 8 {
+                        return Err(invalid("interface block too short"));
+                    }
+                    let link_type = u16_at(&body, 0, be);
+                    interfaces.push((link_type, interface_resolution(&body[8..], be)));
+                }
+                BLOCK_ENHANCED_PACKET => {
+                    if body.len() < 20 {
+                        return Err(invalid("packet block too short"));
+                    }
+                    let interface = u32_at(&body, 0, be) as usize;
+                    let &(link_type, resolution) = interfaces
+                        .get(interface)
+                        .ok_or_else(|| invalid("packet on an undeclared interface"))?;
+                    let units =
+                        u64::from(u32_at(&body, 4, be)) << 32 | u64::from(u32_at(&body, 8, be));
+                    let len = u32_at(&body, 12, be) as usize;
+                    let data = body
+                        .get(20..20 + len)
+                        .ok_or_else(|| invalid("packet longer than its block"))?;
+                    return Ok(Some(CapturedPacket {
+                        timestamp: to_nanos(units, resolution),
+                        link_type,
+                        data: data.to_vec(),
+                        big_endian: be,
+                    }));
+                }
+                BLOCK_SIMPLE_PACKET => {
+                    let &(link_type, _) = interfaces
+                        .first()
+                        .ok_or_else(|| invalid("packet on an undeclared interface"))?;
+                    if body.len() < 4 {
+                        return Err(invalid("packet block too short"));
+                    }
+                    let len = (u32_at(&body, 0, be) as usize).min(body.len() - 4);
+                    return Ok(Some(CapturedPacket {
+                        timestamp: 0,
+                        link_type,
+                        data: body[4..4 + len].to_vec(),
+                        big_endian: be,
+                    }));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = io::Result<CapturedPacket>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_packet().transpose()
+    }
+}
+
+/// Reads the rest of a section header block after its type, returning
+/// whether the section is big-endian.
+fn read_section_header(input: &mut impl Read) -> io::Result<bool> {
+    let mut fixed = [0u8; 8];
+    input.read_exact(&mut fixed).map_err(truncated)?;
+    let big_endian = match u32::from_le_bytes(fixed[4..8].try_into().unwrap()) {
+        BYTE_ORDER_MAGIC => false,
+        magic if magic.swap_bytes() == BYTE_ORDER_MAGIC => true,
+        _ => return Err(invalid("bad pcapng byte-order magic")),
+    };
+    let total = u32_at(&fixed, 0, big_endian) as usize;
+    if !(28..=MAX_RECORD_LEN).contains(&total) || !total.is_multiple_of(4) {
+        return Err(invalid("block length out of range"));
+    }
+    // Version, section length, options and the trailing length.
+    io::copy(&mut input.take((total - 12) as u64), &mut io::sink())?;
+    Ok(big_endian)
+}
+
+/// Returns the timestamp units per second from interface options.
+fn interface_resolution(mut options: &[u8], big_endian: bool) -> u64 {
+    while options.len() >= 4 {
+        let code = u16_at(options, 0, big_endian);
+        let len = usize::from(u16_at(options, 2, big_endian));
+        let Some(value) = options.get(4..4 + len) else {
+            break;
+        };
+        if code == OPTION_TSRESOL && len >= 1 {
+            let exponent = u32::from(value[0] & 0x7F);
+            let base: u64 = if value[0] & 0x80 == 0 { 10 } else { 2 };
+            return base.checked_pow(exponent).unwrap_or(1_000_000);
+        }
+        if code == 0 {
+            break;
+        }
+        options = &options[(4 + len).next_multiple_of(4).min(options.len())..];
+    }
+    1_000_000
+}
+
+fn to_nanos(units: u64, resolution: u64) -> u64 {
+    (u128::from(units) * 1_000_000_000 / u128::from(resolution.max(1))) as u64
+}
+
+/// Fills `buf`, returning `false` if the input ended before the first
+/// byte.
+fn read_or_eof(input: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match input.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(invalid("truncated capture file")),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+fn u16_at(data: &[u8], at: usize, big_endian: bool) -> u16 {
+    let bytes = [data[at], data[at + 1]];
+    if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    }
+}
+
+fn u32_at(data: &[u8], at: usize, big_endian: bool) -> u32 {
+    let bytes = data[at..at + 4].try_into().unwrap();
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+}
+
+fn invalid(what: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, what)
+}
+
+fn truncated(e: io::Error) -> io::Error {
+    if e.kind() == io::ErrorKind::UnexpectedEof {
+        invalid("truncated capture file")
+    } else {
+        e
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::Protocol;
+
+    fn addr(s: &str) -> SocketAddrV4 {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_pcap_round_trip() {
+        let mut writer = PcapWriter::new(Vec::new()).unwrap();
+        let (feed, group) = (addr("10.0.0.1:5000"), addr("239.1.1.1:14310"));
+        let (client, server) = (addr("10.0.0.2:40000"), addr("10.0.0.3:9000"));
+        writer
+            .write_udp(1_500_000_123, feed, group, b"tick")
+            .unwrap();
+        writer
+            .write_tcp(1_600_000_000, client, server, b"order")
+            .unwrap();
+        writer
+            .write_tcp(1_700_000_000, server, client, b"ack")
+            .unwrap();
+        writer
+            .write_tcp(1_800_000_000, client, server, b"cancel")
+            .unwrap();
+        let bytes = writer.into_inner();
+
+        let packets: Vec<_> = PcapReader::new(&bytes[..])
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(packets.len(), 4);
+        assert_eq!(packets[0].timestamp, 1_500_000_123);
+        let datagram = packets[0].datagram().unwrap();
+        assert_eq!(datagram.protocol, Protocol::Udp);
+        assert_eq!((datagram.src, datagram.dst), (feed, group));
+        assert_eq!(datagram.payload, b"tick");
+
+        // TCP sequence numbers continue per direction.
+        let seq = |p: &CapturedPacket| u32::from_be_bytes(p.data[38..42].try_into().unwrap());
+        let ack = |p: &CapturedPacket| u32::from_be_bytes(p.data[42..46].try_into().unwrap());
+        assert_eq!((seq(&packets[1]), ack(&packets[1])), (0, 0));
+        assert_eq!((seq(&packets[2]), ack(&packets[2])), (0, 5));
+        assert_eq!((seq(&packets[3]), ack(&packets[3])), (5, 3));
+        assert_eq!(packets[3].datagram().unwrap().payload, b"cancel");
+    }
+
+    #[test]
+    fn test_reads_big_endian_microsecond_pcap() {
+        let frame = packet::udp_frame(addr("10.0.0.1:1"), addr("10.0.0.2:2"), b"x");
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&PCAP_MAGIC_MICROS.to_be_bytes());
+        bytes.extend_from_slice(&[0, 2, 0, 4]);
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&65535u32.to_be_bytes());
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        bytes.extend_from_slice(&3u32.to_be_bytes());
+        bytes.extend_from_slice(&250u32.to_be_bytes());
+        bytes.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&frame);
+
+        let mut reader = PcapReader::new(&bytes[..]).unwrap();
+        let packet = reader.next_packet().unwrap().unwrap();
+        assert_eq!(packet.timestamp, 3_000_250_000);
+        assert_eq!(packet.datagram().unwrap().payload, b"x");
+        assert!(reader.next_packet().unwrap().is_none());
+    }
+
+    /// Builds a little-endian pcapng block.
+    fn block(block_type: u32, body: &[u8]) -> Vec<u8> {
+        let total = 12 + body.len().next_multiple_of(4);
+        let mut block = block_type.to_le_bytes().to_vec();
+        block.extend_from_slice(&(total as u32).to_le_bytes());
+        block.extend_from_slice(body);
+        block.resize(total - 4, 0);
+        block.extend_from_slice(&(total as u32).to_le_bytes());
+        block
+    }
+
+    #[test]
+    fn test_reads_pcapng() {
+        let frame = packet::udp_frame(addr("10.0.0.1:1"), addr("239.0.0.1:2"), b"ng");
+        let mut section = BYTE_ORDER_MAGIC.to_le_bytes().to_vec();
+        section.extend_from_slice(&[1, 0, 0, 0]);
+        section.extend_from_slice(&u64::MAX.to_le_bytes());
+        let mut interface = vec![1, 0, 0, 0, 0, 0, 0, 0];
+        // if_tsresol of 10^-9, then the end of options.
+        interface.extend_from_slice(&[9, 0, 1, 0, 9, 0, 0, 0, 0, 0, 0, 0]);
+        let mut packet = vec![0u8; 20];
+        let units = 1_000_000_000_123u64;
+        packet[4..8].copy_from_slice(&((units >> 32) as u32).to_le_bytes());
+        packet[8..12].copy_from_slice(&(units as u32).to_le_bytes());
+        packet[12..16].copy_from_slice(&(frame.len() as u32).to_le_bytes());
+        packet[16..20].copy_from_slice(&(frame.len() as u32).to_le_bytes());
+        packet.extend_from_slice(&frame);
+
+        let mut bytes = block(BLOCK_SECTION_HEADER, &section);
+        bytes.extend(block(BLOCK_INTERFACE, &interface));
+        bytes.extend(block(0x0000_0004, &[0; 8])); // name resolution, skipped
+        bytes.extend(block(BLOCK_ENHANCED_PACKET, &packet));
+
+        let packets: Vec<_> = PcapReader::new(&bytes[..])
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].timestamp, 1_000_000_000_123);
+        assert_eq!(packets[0].datagram().unwrap().payload, b"ng");
+    }
+
+    #[test]
+    fn test_rejects_other_files() {
+        assert_eq!(
+            PcapReader::new(&b"not a capture file"[..])
+                .err()
+                .unwrap()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+        let mut writer = PcapWriter::new(Vec::new()).unwrap();
+        writer
+            .write_udp(0, addr("10.0.0.1:1"), addr("10.0.0.2:2"), b"cut")
+            .unwrap();
+        let bytes = writer.into_inner();
+        let mut reader = PcapReader::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(
+            reader.next_packet().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! Sending captured UDP datagrams back onto the network.
+
+use super::{CapturedPacket, Pacer, Protocol};
+use std::collections::HashMap;
+use std::io;
+use std::net::{SocketAddr, SocketAddrV4};
+use tokio::net::UdpSocket;
+
+/// Configuration of [`replay_udp`].
+#[derive(Debug, Clone)]
+pub struct ReplayConfig {
+    /// Replay speed: `1.0` keeps the captured spacing, `10.0` replays ten
+    /// times faster, and zero or less as fast as possible.
+    pub speed: f64,
+    /// Where to send datagrams, by captured destination.
+    ///
+    /// Empty sends every datagram to its captured destination. Otherwise
+    /// only datagrams to a listed destination are sent, to the address it
+    /// maps to, e.g. a production multicast group to a test group or a
+    /// loopback port.
+    pub route: HashMap<SocketAddrV4, SocketAddr>,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            route: HashMap::new(),
+        }
+    }
+}
+
+/// Sends the UDP datagrams of `packets`, usually a
+/// [`PcapReader`](super::PcapReader), from `socket`, paced as configured.
+///
+/// TCP segments and packets that are not IPv4 are skipped.
+///
+/// # Returns
+/// The number of datagrams sent.
+///
+/// # Errors
+/// Returns an error if the capture cannot be read or a datagram cannot be
+/// sent.
+pub async fn replay_udp<I>(packets: I, socket: &UdpSocket, config: &ReplayConfig) -> io::Result<u64>
+where
+    I: IntoIterator<Item = io::Result<CapturedPacket>>,
+{
+    let mut pacer = Pacer::new(config.speed);
+    let mut sent = 0;
+    for packet in packets {
+        let packet = packet?;
+        let Some(datagram) = packet.datagram() else {
+            continue;
+        };
+        if datagram.protocol != Protocol::Udp {
+            continue;
+        }
+        let dst = if config.route.is_empty() {
+            SocketAddr::V4(datagram.dst)
+        } else {
+            match config.route.get(&datagram.dst) {
+                Some(dst) => *dst,
+                None => continue,
+            }
+        };
+        if let Some(wait) = pacer.wait(packet.timestamp) {
+            tokio::time::sleep(wait).await;
+        }
+        socket.send_to(datagram.payload, dst).await?;
+        sent += 1;
+    }
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::{PcapReader, PcapWriter};
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn test_replay_routes_and_paces_datagrams() {
+        let feed: SocketAddrV4 = "10.0.0.1:5000".parse().unwrap();
+        let group_a: SocketAddrV4 = "239.1.1.1:14310".parse().unwrap();
+        let group_b: SocketAddrV4 = "239.1.1.2:14310".parse().unwrap();
+        let mut writer = PcapWriter::new(Vec::new()).unwrap();
+        for i in 0..3u64 {
+            let timestamp = 1_000_000_000 + i * 30_000_000;
+            writer
+                .write_udp(timestamp, feed, group_a, &[i as u8])
+                .unwrap();
+            writer.write_udp(timestamp, feed, group_b, &[0xFF]).unwrap();
+        }
+        writer
+            .write_tcp(1_100_000_000, feed, group_a, b"not udp")
+            .unwrap();
+        let bytes = writer.into_inner();
+
+        let target = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = ReplayConfig {
+            route: HashMap::from([(group_a, target.local_addr().unwrap())]),
+            ..ReplayConfig::default()
+        };
+        let started = Instant::now();
+        let reader = PcapReader::new(&bytes[..]).unwrap();
+        assert_eq!(replay_udp(reader, &socket, &config).await.unwrap(), 3);
+        assert!(started.elapsed() >= Duration::from_millis(60));
+
+        let mut buf = [0u8; 16];
+        for i in 0..3u8 {
+            let len = target.recv(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], &[i]);
+        }
+    }
+}
@@ -26,6 +26,21 @@ pub enum TransportError {
         max: usize,
     },
 
+    /// Frame larger than the limit for its template.
+    #[error("frame too large: {size} bytes exceeds maximum {max} bytes for template {template_id}")]
+    TemplateFrameTooLarge {
+        /// Template id from the message header.
+        template_id: u16,
+        /// Actual frame size.
+        size: usize,
+        /// Maximum allowed size for the template.
+        max: usize,
+    },
+
+    /// Frame failed an integrity check, such as its checksum trailer.
+    #[error("decode error: {0}")]
+    Decode(#[from] ironsbe_core::DecodeError),
+
     /// Invalid frame.
     #[error("invalid frame: {message}")]
     InvalidFrame {
@@ -116,6 +131,32 @@ mod tests {
         assert!(msg.contains("frame too large"));
     }
 
+    #[test]
+    fn test_template_frame_too_large_error() {
+        let err = TransportError::TemplateFrameTooLarge {
+            template_id: 7,
+            size: 100,
+            max: 64,
+        };
+        assert_eq!(
+            err.to_string(),
+            "frame too large: 100 bytes exceeds maximum 64 bytes for template 7"
+        );
+    }
+
+    #[test]
+    fn test_decode_error_from() {
+        let err: TransportError = ironsbe_core::DecodeError::ChecksumMismatch {
+            expected: 1,
+            actual: 2,
+        }
+        .into();
+        assert_eq!(
+            err.to_string(),
+            "decode error: checksum mismatch: expected 0x00000001, actual 0x00000002"
+        );
+    }
+
     #[test]
     fn test_invalid_frame_error() {
         let err = TransportError::invalid_frame("bad header");
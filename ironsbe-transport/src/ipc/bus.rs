@@ -0,0 +1,565 @@
+//! Single-producer broadcast bus over shared memory.
+//!
+//! One [`BusProducer`] appends records to a ring that any number of
+//! [`BusConsumer`]s, up to the limit fixed at creation, read
+//! independently. Every consumer owns a slot in the file holding its own
+//! tail, so consumers in different processes progress at their own pace
+//! and each sees every record published after it subscribed. Records are
+//! never copied out of the mapping on the way in, and
+//! [`BusConsumer::read_with`] hands them to the reader in place.
+//!
+//! The producer never overwrites a record an active consumer has not read:
+//! when the slowest consumer is a full ring behind, [`BusProducer::write`]
+//! returns `false`. With no consumers subscribed, records are published
+//! and dropped.
+//!
+//! Records are 8-byte aligned and never wrap: a record that does not fit
+//! before the end of the ring is preceded by a padding record that skips
+//! to the start.
+
+use memmap2::{MmapMut, MmapOptions};
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Control block at the start of a bus file.
+#[repr(C)]
+pub struct SharedBus {
+    /// Write position (producer).
+    head: AtomicU64,
+    /// Padding to separate cache lines.
+    _pad1: [u8; 56],
+    /// Ring capacity in bytes.
+    capacity: u64,
+    /// Capacity mask for fast modulo (capacity - 1).
+    mask: u64,
+    /// Number of consumer slots.
+    slots: u64,
+    /// Padding to the end of the control block.
+    _pad2: [u8; 40],
+}
+
+/// A consumer's position, on a cache line of its own.
+#[repr(C)]
+pub struct ConsumerSlot {
+    /// Read position of the consumer.
+    tail: AtomicU64,
+    /// Non-zero while a consumer holds the slot.
+    active: AtomicU64,
+    /// Padding to the end of the cache line.
+    _pad: [u8; 48],
+}
+
+const _: () = {
+    assert!(std::mem::size_of::<SharedBus>() == SharedBus::HEADER_SIZE);
+    assert!(std::mem::size_of::<ConsumerSlot>() == SharedBus::SLOT_SIZE);
+};
+
+/// Length prefix of a padding record.
+const PADDING: u32 = u32::MAX;
+
+/// Size of a record's length prefix.
+const LEN_SIZE: usize = 4;
+
+/// Alignment of every record.
+const ALIGN: u64 = 8;
+
+impl SharedBus {
+    /// Size of the control block in bytes.
+    pub const HEADER_SIZE: usize = 128;
+
+    /// Size of a consumer slot in bytes.
+    pub const SLOT_SIZE: usize = 64;
+
+    /// Returns the offset of the ring in a bus file with `slots` consumer
+    /// slots.
+    #[must_use]
+    pub const fn data_offset(slots: usize) -> usize {
+        Self::HEADER_SIZE + slots * Self::SLOT_SIZE
+    }
+
+    /// Creates a bus backed by a file.
+    ///
+    /// # Arguments
+    /// * `path` - Path to the backing file
+    /// * `capacity` - Capacity of the ring in bytes (must be a power of 2
+    ///   and at least 8)
+    /// * `consumers` - Maximum number of consumers subscribed at once
+    ///
+    /// # Errors
+    /// Returns IO error if file operations fail.
+    ///
+    /// # Panics
+    /// Panics if capacity is not a power of 2 of at least 8, or
+    /// `consumers` is zero.
+    pub fn create(path: &Path, capacity: usize, consumers: usize) -> io::Result<MmapMut> {
+        assert!(
+            capacity.is_power_of_two() && capacity >= ALIGN as usize,
+            "capacity must be a power of 2 of at least 8"
+        );
+        assert!(consumers > 0, "at least one consumer slot is required");
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len((Self::data_offset(consumers) + capacity) as u64)?;
+
+        // SAFETY: the file was just truncated and sized by us; other
+        // processes only map it through this module.
+        let mut mmap = unsafe { MmapOptions::new().map_mut(&file)? };
+        // The fresh file is zeroed, so the head and every slot start at 0.
+        // SAFETY: the mapping is at least `HEADER_SIZE` bytes and
+        // page-aligned.
+        let header = unsafe { &mut *(mmap.as_mut_ptr() as *mut SharedBus) };
+        header.capacity = capacity as u64;
+        header.mask = (capacity - 1) as u64;
+        header.slots = consumers as u64;
+        Ok(mmap)
+    }
+
+    /// Opens an existing bus.
+    ///
+    /// # Arguments
+    /// * `path` - Path to the backing file
+    ///
+    /// # Errors
+    /// Returns IO error if file operations fail.
+    pub fn open(path: &Path) -> io::Result<MmapMut> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        // SAFETY: see `create`.
+        unsafe { MmapOptions::new().map_mut(&file) }
+    }
+
+    /// Returns the capacity of the ring in bytes.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity as usize
+    }
+
+    /// Returns the number of consumer slots.
+    #[must_use]
+    pub fn slots(&self) -> usize {
+        self.slots as usize
+    }
+}
+
+/// A mapped bus file whose header has been checked against its length.
+struct Mapping {
+    mmap: MmapMut,
+}
+
+impl Mapping {
+    fn new(mmap: MmapMut) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not a shared bus file");
+        if mmap.len() < SharedBus::HEADER_SIZE {
+            return Err(invalid());
+        }
+        let mapping = Self { mmap };
+        let header = mapping.header();
+        let capacity = header.capacity as usize;
+        let slots = header.slots as usize;
+        let len = slots
+            .checked_mul(SharedBus::SLOT_SIZE)
+            .and_then(|len| len.checked_add(SharedBus::HEADER_SIZE + capacity));
+        if !capacity.is_power_of_two()
+            || capacity < ALIGN as usize
+            || slots == 0
+            || len.is_none_or(|len| mapping.mmap.len() < len)
+        {
+            return Err(invalid());
+        }
+        Ok(mapping)
+    }
+
+    fn header(&self) -> &SharedBus {
+        // SAFETY: `new` checked the mapping holds a control block; the
+        // mapping is page-aligned.
+        unsafe { &*(self.mmap.as_ptr() as *const SharedBus) }
+    }
+
+    fn slot(&self, index: usize) -> &ConsumerSlot {
+        debug_assert!(index < self.header().slots as usize);
+        let offset = SharedBus::HEADER_SIZE + index * SharedBus::SLOT_SIZE;
+        // SAFETY: `new` checked the mapping holds every slot, and slots are
+        // 8-aligned.
+        unsafe { &*(self.mmap.as_ptr().add(offset) as *const ConsumerSlot) }
+    }
+
+    /// Returns the ring.
+    fn data(&self) -> &[u8] {
+        let start = SharedBus::data_offset(self.header().slots as usize);
+        &self.mmap[start..start + self.header().capacity as usize]
+    }
+
+    /// Returns the ring mutably.
+    fn data_mut(&mut self) -> &mut [u8] {
+        let start = SharedBus::data_offset(self.header().slots as usize);
+        let capacity = self.header().capacity as usize;
+        &mut self.mmap[start..start + capacity]
+    }
+}
+
+/// Producer side of a shared bus.
+pub struct BusProducer {
+    mapping: Mapping,
+}
+
+impl BusProducer {
+    /// Creates a producer from a memory map.
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if the map does not hold a bus.
+    pub fn new(mmap: MmapMut) -> io::Result<Self> {
+        Ok(Self {
+            mapping: Mapping::new(mmap)?,
+        })
+    }
+
+    /// Publishes a message to every subscribed consumer.
+    ///
+    /// # Arguments
+    /// * `data` - Data to write
+    ///
+    /// # Returns
+    /// `true` if written successfully, `false` if the slowest consumer has
+    /// not freed enough space or the message can never fit in the ring.
+    #[inline]
+    pub fn write(&mut self, data: &[u8]) -> bool {
+        let header = self.mapping.header();
+        let head = header.head.load(Ordering::Relaxed);
+        let capacity = header.capacity;
+        let mask = header.mask;
+
+        let Ok(len) = u32::try_from(data.len()) else {
+            return false;
+        };
+        if len == PADDING {
+            return false;
+        }
+        let record = (LEN_SIZE as u64 + u64::from(len)).next_multiple_of(ALIGN);
+        let offset = head & mask;
+        let padding = if offset + record > capacity {
+            capacity - offset
+        } else {
+            0
+        };
+        if padding + record > self.free(head) {
+            return false;
+        }
+
+        let mut offset = offset as usize;
+        if padding > 0 {
+            self.mapping.data_mut()[offset..offset + LEN_SIZE]
+                .copy_from_slice(&PADDING.to_le_bytes());
+            offset = 0;
+        }
+        let ring = self.mapping.data_mut();
+        ring[offset..offset + LEN_SIZE].copy_from_slice(&len.to_le_bytes());
+        ring[offset + LEN_SIZE..offset + LEN_SIZE + data.len()].copy_from_slice(data);
+
+        // Publish with release semantics
+        self.mapping
+            .header()
+            .head
+            .store(head + padding + record, Ordering::Release);
+        true
+    }
+
+    /// Returns the bytes free behind the slowest active consumer.
+    fn free(&self, head: u64) -> u64 {
+        let header = self.mapping.header();
+        let mut free = header.capacity;
+        for index in 0..header.slots as usize {
+            let slot = self.mapping.slot(index);
+            if slot.active.load(Ordering::SeqCst) == 0 {
+                continue;
+            }
+            // A consumer still setting up may show the tail of an earlier
+            // one, which is never ahead of the head.
+            let tail = slot.tail.load(Ordering::Acquire);
+            let used = head.saturating_sub(tail);
+            free = free.min(header.capacity.saturating_sub(used));
+        }
+        free
+    }
+
+    /// Returns the number of bytes available for writing, padding included.
+    #[must_use]
+    pub fn available(&self) -> usize {
+        let head = self.mapping.header().head.load(Ordering::Relaxed);
+        self.free(head) as usize
+    }
+
+    /// Returns the number of subscribed consumers.
+    #[must_use]
+    pub fn consumers(&self) -> usize {
+        let slots = self.mapping.header().slots as usize;
+        (0..slots)
+            .filter(|&index| self.mapping.slot(index).active.load(Ordering::Acquire) != 0)
+            .count()
+    }
+}
+
+/// Consumer side of a shared bus.
+///
+/// Dropping the consumer frees its slot, so the producer stops waiting for
+/// it.
+pub struct BusConsumer {
+    mapping: Mapping,
+    slot: usize,
+}
+
+impl BusConsumer {
+    /// Subscribes to the bus in a memory map, claiming a free consumer
+    /// slot. The consumer starts at the producer's current position, so it
+    /// sees the records published from now on.
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if the map does not hold a bus, or a
+    /// `WouldBlock` error if every slot is taken.
+    pub fn subscribe(mmap: MmapMut) -> io::Result<Self> {
+        let mapping = Mapping::new(mmap)?;
+        let slots = mapping.header().slots as usize;
+        let slot = (0..slots)
+            .find(|&index| {
+                mapping
+                    .slot(index)
+                    .active
+                    .compare_exchange(0, 1, Ordering::SeqCst, Ordering::Relaxed)
+                    .is_ok()
+            })
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "every bus consumer slot is taken",
+                )
+            })?;
+        let head = mapping.header().head.load(Ordering::SeqCst);
+        mapping.slot(slot).tail.store(head, Ordering::Release);
+        Ok(Self { mapping, slot })
+    }
+
+    /// Returns the index of the slot this consumer holds.
+    #[must_use]
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+
+    /// Reads the next message, copying it out of the ring.
+    ///
+    /// # Returns
+    /// The message data, or `None` if no message is pending.
+    #[inline]
+    pub fn read(&mut self) -> Option<Vec<u8>> {
+        self.read_with(<[u8]>::to_vec)
+    }
+
+    /// Passes the next message to `f` in place, then releases its space.
+    ///
+    /// # Returns
+    /// What `f` returned, or `None` if no message is pending.
+    #[inline]
+    pub fn read_with<R>(&mut self, f: impl FnOnce(&[u8]) -> R) -> Option<R> {
+        let header = self.mapping.header();
+        let slot = self.mapping.slot(self.slot);
+        let mut tail = slot.tail.load(Ordering::Relaxed);
+        let head = header.head.load(Ordering::Acquire);
+        let capacity = header.capacity;
+        let mask = header.mask;
+        let ring = self.mapping.data();
+
+        loop {
+            if tail >= head {
+                if tail != slot.tail.load(Ordering::Relaxed) {
+                    slot.tail.store(tail, Ordering::Release);
+                }
+                return None;
+            }
+            let offset = (tail & mask) as usize;
+            let len = u32::from_le_bytes(
+                ring[offset..offset + LEN_SIZE]
+                    .try_into()
+                    .expect("length prefix"),
+            );
+            if len == PADDING {
+                tail += capacity - offset as u64;
+                continue;
+            }
+            let start = offset + LEN_SIZE;
+            let result = f(&ring[start..start + len as usize]);
+            let record = (LEN_SIZE as u64 + u64::from(len)).next_multiple_of(ALIGN);
+            slot.tail.store(tail + record, Ordering::Release);
+            return Some(result);
+        }
+    }
+
+    /// Returns the number of bytes published but not yet read.
+    #[must_use]
+    pub fn available(&self) -> usize {
+        let tail = self.mapping.slot(self.slot).tail.load(Ordering::Relaxed);
+        let head = self.mapping.header().head.load(Ordering::Acquire);
+        (head - tail) as usize
+    }
+
+    /// Returns true if no message is pending.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.available() == 0
+    }
+}
+
+impl Drop for BusConsumer {
+    fn drop(&mut self) {
+        self.mapping
+            .slot(self.slot)
+            .active
+            .store(0, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn bus(capacity: usize, consumers: usize) -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bus");
+        drop(SharedBus::create(&path, capacity, consumers).unwrap());
+        (dir, path)
+    }
+
+    #[test]
+    fn test_every_consumer_sees_every_message() {
+        let (_dir, path) = bus(256, 4);
+        let mut producer = BusProducer::new(SharedBus::open(&path).unwrap()).unwrap();
+        let mut a = BusConsumer::subscribe(SharedBus::open(&path).unwrap()).unwrap();
+        let mut b = BusConsumer::subscribe(SharedBus::open(&path).unwrap()).unwrap();
+        assert_ne!(a.slot(), b.slot());
+        assert_eq!(producer.consumers(), 2);
+
+        assert!(producer.write(b"one"));
+        assert!(producer.write(b"two"));
+        assert_eq!(a.read().as_deref(), Some(&b"one"[..]));
+        assert_eq!(a.read().as_deref(), Some(&b"two"[..]));
+        assert!(a.read().is_none());
+        assert_eq!(b.read_with(<[u8]>::len), Some(3));
+        assert_eq!(b.read().as_deref(), Some(&b"two"[..]));
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn test_slowest_consumer_applies_back_pressure() {
+        let (_dir, path) = bus(64, 2);
+        let mut producer = BusProducer::new(SharedBus::open(&path).unwrap()).unwrap();
+        let mut fast = BusConsumer::subscribe(SharedBus::open(&path).unwrap()).unwrap();
+        let mut slow = BusConsumer::subscribe(SharedBus::open(&path).unwrap()).unwrap();
+
+        // 4-byte prefix + 12-byte payload = 16 bytes per record.
+        for i in 0..4u8 {
+            assert!(producer.write(&[i; 12]));
+            assert_eq!(fast.read(), Some(vec![i; 12]));
+        }
+        assert!(!producer.write(&[9; 12]));
+        assert_eq!(slow.read(), Some(vec![0; 12]));
+        assert!(producer.write(&[4; 12]));
+
+        // Dropping the slow consumer stops it holding the producer back.
+        drop(slow);
+        assert_eq!(producer.consumers(), 1);
+        assert_eq!(fast.read(), Some(vec![4; 12]));
+        assert_eq!(producer.available(), 64);
+    }
+
+    #[test]
+    fn test_records_wrap_with_padding() {
+        let (_dir, path) = bus(64, 1);
+        let mut producer = BusProducer::new(SharedBus::open(&path).unwrap()).unwrap();
+        let mut consumer = BusConsumer::subscribe(SharedBus::open(&path).unwrap()).unwrap();
+
+        for i in 0..50u8 {
+            let message = vec![i; usize::from(i % 20)];
+            assert!(producer.write(&message), "message {i}");
+            assert_eq!(consumer.read(), Some(message));
+        }
+        assert!(!producer.write(&[0; 61]));
+    }
+
+    #[test]
+    fn test_late_subscriber_starts_at_head() {
+        let (_dir, path) = bus(128, 1);
+        let mut producer = BusProducer::new(SharedBus::open(&path).unwrap()).unwrap();
+        // Without consumers, records are dropped rather than blocking.
+        for _ in 0..20 {
+            assert!(producer.write(b"unseen"));
+        }
+        let mut consumer = BusConsumer::subscribe(SharedBus::open(&path).unwrap()).unwrap();
+        assert!(consumer.read().is_none());
+        assert!(producer.write(b"seen"));
+        assert_eq!(consumer.read().as_deref(), Some(&b"seen"[..]));
+    }
+
+    #[test]
+    fn test_slots_are_limited_and_reused() {
+        let (_dir, path) = bus(64, 1);
+        let first = BusConsumer::subscribe(SharedBus::open(&path).unwrap()).unwrap();
+        let err = BusConsumer::subscribe(SharedBus::open(&path).unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        drop(first);
+        assert!(BusConsumer::subscribe(SharedBus::open(&path).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_foreign_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("foreign");
+        std::fs::write(&path, [0u8; 256]).unwrap();
+        let err = BusProducer::new(SharedBus::open(&path).unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_fan_out_across_threads() {
+        let (_dir, path) = bus(1024, 3);
+        let consumers: Vec<_> = (0..3)
+            .map(|_| BusConsumer::subscribe(SharedBus::open(&path).unwrap()).unwrap())
+            .collect();
+        let readers: Vec<_> = consumers
+            .into_iter()
+            .map(|mut consumer| {
+                std::thread::spawn(move || {
+                    let mut sum = 0u64;
+                    let mut seen = 0;
+                    while seen < 10_000 {
+                        if let Some(value) = consumer
+                            .read_with(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                        {
+                            assert_eq!(value, seen);
+                            sum += value;
+                            seen += 1;
+                        }
+                    }
+                    sum
+                })
+            })
+            .collect();
+
+        let mut producer = BusProducer::new(SharedBus::open(&path).unwrap()).unwrap();
+        for value in 0..10_000u64 {
+            while !producer.write(&value.to_le_bytes()) {
+                std::hint::spin_loop();
+            }
+        }
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), (0..10_000).sum::<u64>());
+        }
+    }
+}
@@ -1,9 +1,16 @@
 //! IPC (Inter-Process Communication) transport module.
 //!
-//! Provides shared memory based transport for ultra-low-latency local communication.
+//! Provides shared memory based transport for ultra-low-latency local communication:
+//! an SPSC ring buffer, and a broadcast bus that fans one producer out to
+//! several consumers.
 
+pub mod bus;
 pub mod ringbuffer;
 pub mod shm;
 
-pub use ringbuffer::{SharedConsumer, SharedProducer, SharedRingBuffer};
+pub use bus::{BusConsumer, BusProducer, SharedBus};
+pub use ironsbe_core::memory::{HugePages, MemoryConfig};
+pub use ringbuffer::{
+    PeerState, RecoveryReport, SharedConsumer, SharedProducer, SharedRingBuffer, SharedRingConfig,
+};
 pub use shm::{SharedMemory, SharedMemoryConfig};
@@ -1,34 +1,182 @@
 //! Lock-free SPSC ring buffer over shared memory.
+//!
+//! A consumer either polls [`SharedConsumer::read`] or blocks in
+//! [`SharedConsumer::read_wait`]. Blocking is cheap on rings created with a
+//! doorbell ([`SharedRingConfig::doorbell`]): the producer rings a futex
+//! word in the control block after each write that finds a consumer
+//! asleep, so the consumer wakes within microseconds without spinning. On
+//! rings without a doorbell a blocked consumer polls with short sleeps
+//! instead.
+//!
+//! # Platform support
+//!
+//! The doorbell and the check for a dead peer's process need Linux. On
+//! other platforms rings are created without a doorbell whatever the
+//! configuration says, so a blocked consumer always polls every 50 µs, and
+//! a peer is judged dead by its heartbeat alone.
+//!
+//! [`SharedConsumer::read_into`] and [`SharedConsumer::peek`] read without
+//! allocating: the first copies into a caller's buffer, the second borrows
+//! the message from the mapping until [`SharedConsumer::commit`].
+//!
+//! # Crash safety
+//!
+//! A producer or consumer that [attaches](SharedProducer::attach) records
+//! its process ID and a heartbeat in the control block, so a process taking
+//! over can tell a peer that died or hung from one that is still running.
+//! On rings created [crash-safe](SharedRingConfig::crash_safe), every
+//! record also carries a commit flag, set only once the record is complete,
+//! and consumers never deliver a record without it.
+//! [`SharedRingBuffer::recover`], which attaching runs for the endpoint
+//! being taken over, rolls back writes a dead producer left incomplete and
+//! finishes releases a dead consumer left half done.
 
+use ironsbe_core::DecodeError;
+use ironsbe_core::WriteBuffer;
+use ironsbe_core::canary;
+use ironsbe_core::checksum;
 use memmap2::{MmapMut, MmapOptions};
 use std::fs::OpenOptions;
+use std::io;
 use std::path::Path;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering, fence};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Polls of an empty ring before a waiting consumer goes to sleep.
+const SPIN_COUNT: usize = 1_024;
+
+/// Configuration of a new shared ring buffer.
+#[derive(Debug, Clone)]
+pub struct SharedRingConfig {
+    /// Capacity of the ring in bytes (must be a power of 2).
+    pub capacity: usize,
+    /// Whether the producer wakes a consumer blocked in
+    /// [`SharedConsumer::read_wait`]. Costs the producer a fence and a load
+    /// per write, and a system call when the consumer is asleep.
+    ///
+    /// Linux only: elsewhere it is ignored and consumers poll.
+    pub doorbell: bool,
+    /// Whether records carry a commit flag, so a record a producer did not
+    /// finish is never delivered and can be rolled back. Costs the producer
+    /// a second write of the length prefix. Older releases cannot read
+    /// crash-safe rings.
+    pub crash_safe: bool,
+}
+
+impl Default for SharedRingConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024 * 1024, // 1MB
+            doorbell: false,
+            crash_safe: false,
+        }
+    }
+}
+
+/// State of a ring endpoint, as seen from another process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerState {
+    /// No process is attached.
+    Detached,
+    /// The attached process is running and its heartbeat is fresh.
+    Alive,
+    /// The attached process has exited, or has not sent a heartbeat within
+    /// the timeout.
+    Dead,
+}
+
+/// What [`SharedRingBuffer::recover`] found and repaired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoveryReport {
+    /// State of the producer before recovery.
+    pub producer: PeerState,
+    /// State of the consumer before recovery.
+    pub consumer: PeerState,
+    /// Bytes of incomplete records removed from the end of the ring.
+    pub rolled_back: u64,
+    /// Bytes a dead consumer had read but not yet released.
+    pub released: u64,
+}
 
 /// Lock-free SPSC ring buffer over shared memory.
 ///
 /// The ring buffer uses a cache-line aligned control block to prevent
 /// false sharing between producer and consumer.
+///
+/// Rings created by debug builds keep their free space filled with a
+/// canary pattern: the consumer restores it over every record it reads,
+/// and the producer checks it before committing a new record, so a write
+/// that strays past its slot is reported at the next commit.
 #[repr(C)]
 pub struct SharedRingBuffer {
     /// Write position (producer).
     head: AtomicU64,
+    /// Process ID of the attached producer, or 0.
+    producer_pid: AtomicU32,
+    _pad0: u32,
+    /// Last heartbeat of the attached producer, in nanoseconds since the
+    /// Unix epoch.
+    producer_heartbeat: AtomicU64,
     /// Padding to separate cache lines.
-    _pad1: [u8; 56],
+    _pad1: [u8; 40],
     /// Read position (consumer).
     tail: AtomicU64,
-    /// Padding to separate cache lines.
-    _pad2: [u8; 56],
     /// Ring buffer capacity.
     capacity: u64,
     /// Capacity mask for fast modulo (capacity - 1).
     mask: u64,
+    /// Ring flags (`FLAG_*`).
+    flags: u64,
+    /// Futex word the producer bumps to wake a waiting consumer.
+    doorbell: AtomicU32,
+    /// Number of consumers asleep on the doorbell.
+    waiters: AtomicU32,
+    /// Process ID of the attached consumer, or 0.
+    consumer_pid: AtomicU32,
+    _pad2: u32,
+    /// Last heartbeat of the attached consumer.
+    consumer_heartbeat: AtomicU64,
+    /// Tail a consumer is releasing up to; ahead of the tail only while
+    /// it refills released space with the canary.
+    release_to: AtomicU64,
 }
 
+const _: () = assert!(std::mem::size_of::<SharedRingBuffer>() == SharedRingBuffer::HEADER_SIZE);
+
 impl SharedRingBuffer {
     /// Size of the control block header in bytes.
     pub const HEADER_SIZE: usize = 128;
 
+    /// Flag set when free space holds the canary pattern.
+    pub const FLAG_CANARY: u64 = 1;
+
+    /// Flag set when the producer rings the doorbell for waiting consumers.
+    pub const FLAG_DOORBELL: u64 = 2;
+
+    /// Flag set when records carry a commit flag.
+    pub const FLAG_COMMIT: u64 = 4;
+
+    /// Commit flag: the top bit of a record's length prefix.
+    pub const COMMIT_BIT: u32 = 1 << 31;
+
+    /// Returns true if the ring maintains canary-filled free space.
+    #[must_use]
+    pub fn has_canary(&self) -> bool {
+        self.flags & Self::FLAG_CANARY != 0
+    }
+
+    /// Returns true if the producer wakes waiting consumers.
+    #[must_use]
+    pub fn has_doorbell(&self) -> bool {
+        self.flags & Self::FLAG_DOORBELL != 0
+    }
+
+    /// Returns true if records carry a commit flag.
+    #[must_use]
+    pub fn is_crash_safe(&self) -> bool {
+        self.flags & Self::FLAG_COMMIT != 0
+    }
+
     /// Creates a new shared ring buffer backed by a file.
     ///
     /// # Arguments
@@ -41,6 +189,25 @@ impl SharedRingBuffer {
     /// # Panics
     /// Panics if capacity is not a power of 2.
     pub fn create(path: &Path, capacity: usize) -> std::io::Result<MmapMut> {
+        Self::create_with_config(
+            path,
+            &SharedRingConfig {
+                capacity,
+                ..SharedRingConfig::default()
+            },
+        )
+    }
+
+    /// Creates a new shared ring buffer backed by a file, with the options
+    /// in `config`.
+    ///
+    /// # Errors
+    /// Returns IO error if file operations fail.
+    ///
+    /// # Panics
+    /// Panics if the capacity is not a power of 2.
+    pub fn create_with_config(path: &Path, config: &SharedRingConfig) -> std::io::Result<MmapMut> {
+        let capacity = config.capacity;
         assert!(capacity.is_power_of_two(), "capacity must be power of 2");
 
         let file = OpenOptions::new()
@@ -61,6 +228,26 @@ impl SharedRingBuffer {
         header.tail = AtomicU64::new(0);
         header.capacity = capacity as u64;
         header.mask = (capacity - 1) as u64;
+        header.flags = 0;
+        header.doorbell = AtomicU32::new(0);
+        header.waiters = AtomicU32::new(0);
+        header.producer_pid = AtomicU32::new(0);
+        header.producer_heartbeat = AtomicU64::new(0);
+        header.consumer_pid = AtomicU32::new(0);
+        header.consumer_heartbeat = AtomicU64::new(0);
+        header.release_to = AtomicU64::new(0);
+        if config.doorbell && sys::HAS_FUTEX {
+            header.flags |= Self::FLAG_DOORBELL;
+        }
+        if config.crash_safe {
+            header.flags |= Self::FLAG_COMMIT;
+        }
+
+        if canary::ENABLED {
+            canary::fill(&mut mmap[Self::HEADER_SIZE..Self::HEADER_SIZE + capacity]);
+            let header = unsafe { &mut *(mmap.as_mut_ptr() as *mut SharedRingBuffer) };
+            header.flags |= Self::FLAG_CANARY;
+        }
 
         Ok(mmap)
     }
@@ -77,18 +264,247 @@ impl SharedRingBuffer {
 
         unsafe { MmapOptions::new().map_mut(&file) }
     }
+
+    /// Checks both endpoints of the ring in `mmap` and repairs what dead
+    /// ones left behind: a dead producer's incomplete records are rolled
+    /// back and its partial writes to free space are cleared, and a dead
+    /// consumer's half-done release is finished. Dead endpoints are
+    /// detached, so new processes can attach.
+    ///
+    /// An endpoint is dead if its process has exited or its heartbeat is
+    /// older than `timeout`. Live and detached endpoints are left alone.
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if the map does not hold a
+    /// consistent ring.
+    pub fn recover(mmap: &mut MmapMut, timeout: Duration) -> io::Result<RecoveryReport> {
+        check_ring(mmap)?;
+        let header = ring_header(mmap);
+        let producer_pid = header.producer_pid.load(Ordering::Acquire);
+        let consumer_pid = header.consumer_pid.load(Ordering::Acquire);
+        let producer = peer_state(&header.producer_pid, &header.producer_heartbeat, timeout);
+        let consumer = peer_state(&header.consumer_pid, &header.consumer_heartbeat, timeout);
+        let mut report = RecoveryReport {
+            producer,
+            consumer,
+            rolled_back: 0,
+            released: 0,
+        };
+        if consumer == PeerState::Dead {
+            report.released = recover_consumer(mmap);
+            detach(&ring_header(mmap).consumer_pid, consumer_pid);
+        }
+        if producer == PeerState::Dead {
+            report.rolled_back = recover_producer(mmap)?;
+            detach(&ring_header(mmap).producer_pid, producer_pid);
+        }
+        Ok(report)
+    }
+}
+
+/// Returns the control block at the start of `mmap`.
+fn ring_header(mmap: &[u8]) -> &SharedRingBuffer {
+    // SAFETY: callers check the map holds a control block; the map is
+    // page-aligned.
+    unsafe { &*(mmap.as_ptr() as *const SharedRingBuffer) }
+}
+
+/// Returns the `len` bytes of the data region starting at ring position
+/// `position`, split in two where they wrap around its end.
+fn region(mmap: &[u8], position: u64, len: usize) -> (&[u8], &[u8]) {
+    let header = ring_header(mmap);
+    let capacity = header.capacity as usize;
+    let start = (position & header.mask) as usize;
+    let data = &mmap[SharedRingBuffer::HEADER_SIZE..SharedRingBuffer::HEADER_SIZE + capacity];
+    if start + len <= capacity {
+        (&data[start..start + len], &[])
+    } else {
+        (&data[start..], &data[..len - (capacity - start)])
+    }
+}
+
+/// Checks that `mmap` holds a ring whose positions are consistent.
+fn check_ring(mmap: &[u8]) -> io::Result<()> {
+    let invalid = |what| io::Error::new(io::ErrorKind::InvalidData, what);
+    if mmap.len() < SharedRingBuffer::HEADER_SIZE {
+        return Err(invalid("not a shared ring file"));
+    }
+    let header = ring_header(mmap);
+    let capacity = header.capacity;
+    if !capacity.is_power_of_two()
+        || mmap.len() < SharedRingBuffer::HEADER_SIZE.saturating_add(capacity as usize)
+    {
+        return Err(invalid("not a shared ring file"));
+    }
+    let head = header.head.load(Ordering::Acquire);
+    let tail = header.tail.load(Ordering::Acquire);
+    if tail > head || head - tail > capacity {
+        return Err(invalid("shared ring positions are inconsistent"));
+    }
+    Ok(())
+}
+
+/// Returns the current time as a heartbeat.
+fn now_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| u64::try_from(now.as_nanos()).unwrap_or(u64::MAX))
+}
+
+/// Returns the state of the endpoint recorded in `pid` and `heartbeat`.
+fn peer_state(pid: &AtomicU32, heartbeat: &AtomicU64, timeout: Duration) -> PeerState {
+    let pid = pid.load(Ordering::Acquire);
+    if pid == 0 {
+        return PeerState::Detached;
+    }
+    let age = now_nanos().saturating_sub(heartbeat.load(Ordering::Acquire));
+    if !sys::process_exists(pid) || u128::from(age) > timeout.as_nanos() {
+        return PeerState::Dead;
+    }
+    PeerState::Alive
+}
+
+/// Records the calling process in `pid`, unless a live one is attached.
+fn attach(pid: &AtomicU32, heartbeat: &AtomicU64, timeout: Duration) -> io::Result<()> {
+    loop {
+        let current = pid.load(Ordering::Acquire);
+        if peer_state(pid, heartbeat, timeout) == PeerState::Alive {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("process {current} is attached to the ring"),
+            ));
+        }
+        heartbeat.store(now_nanos(), Ordering::Release);
+        if pid
+            .compare_exchange(
+                current,
+                std::process::id(),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok()
+        {
+            return Ok(());
+        }
+    }
+}
+
+/// Clears the process recorded in `pid`, if it is still `expected`.
+fn detach(pid: &AtomicU32, expected: u32) {
+    let _ = pid.compare_exchange(expected, 0, Ordering::AcqRel, Ordering::Relaxed);
+}
+
+/// Rolls the head back to the first record that is not complete, and
+/// refills the free space with the canary if the ring keeps one.
+///
+/// # Returns
+/// The number of bytes rolled back.
+fn recover_producer(mmap: &mut [u8]) -> io::Result<u64> {
+    check_ring(mmap)?;
+    let header = ring_header(mmap);
+    let head = header.head.load(Ordering::Acquire);
+    let tail = header.tail.load(Ordering::Acquire);
+    let capacity = header.capacity;
+    let mask = header.mask;
+    let crash_safe = header.is_crash_safe();
+    let guarded = header.has_canary();
+    let ring = &mut mmap[SharedRingBuffer::HEADER_SIZE..][..capacity as usize];
+
+    let mut end = tail;
+    if crash_safe {
+        while end < head {
+            let mut prefix = [0u8; 4];
+            for (i, byte) in prefix.iter_mut().enumerate() {
+                *byte = ring[((end + i as u64) & mask) as usize];
+            }
+            let prefix = u32::from_le_bytes(prefix);
+            let len = u64::from(prefix & !SharedRingBuffer::COMMIT_BIT);
+            if prefix & SharedRingBuffer::COMMIT_BIT == 0 || end + 4 + len > head {
+                break;
+            }
+            end += 4 + len;
+        }
+    }
+    if guarded {
+        let free = (tail + capacity - end) as usize;
+        let start = (end & mask) as usize;
+        let first = free.min(capacity as usize - start);
+        canary::fill(&mut ring[start..start + first]);
+        canary::fill(&mut ring[..free - first]);
+    }
+    if end < head {
+        ring_header(mmap).head.store(end, Ordering::Release);
+    }
+    Ok(head - end)
+}
+
+/// Finishes a release a consumer started but did not complete.
+///
+/// # Returns
+/// The number of bytes released.
+fn recover_consumer(mmap: &[u8]) -> u64 {
+    let header = ring_header(mmap);
+    let head = header.head.load(Ordering::Acquire);
+    let tail = header.tail.load(Ordering::Acquire);
+    let release_to = header.release_to.load(Ordering::Acquire);
+    if release_to <= tail || release_to > head {
+        return 0;
+    }
+    header.tail.store(release_to, Ordering::Release);
+    release_to - tail
 }
 
 /// Producer side of shared ring buffer.
 pub struct SharedProducer {
     mmap: MmapMut,
+    checksum: bool,
+    attached: bool,
 }
 
 impl SharedProducer {
     /// Creates a new producer from a memory map.
     #[must_use]
     pub fn new(mmap: MmapMut) -> Self {
-        Self { mmap }
+        Self {
+            mmap,
+            checksum: false,
+            attached: false,
+        }
+    }
+
+    /// Attaches a producer to the ring in `mmap`, recording this process
+    /// and a heartbeat in the control block. If the previous producer died,
+    /// what it left incomplete is rolled back first.
+    ///
+    /// Call [`heartbeat`](Self::heartbeat) more often than `timeout`, or
+    /// other processes will consider this producer dead.
+    ///
+    /// # Errors
+    /// Returns an `AddrInUse` error if a live producer is attached, or an
+    /// `InvalidData` error if the map does not hold a consistent ring.
+    pub fn attach(mut mmap: MmapMut, timeout: Duration) -> io::Result<Self> {
+        check_ring(&mmap)?;
+        let header = ring_header(&mmap);
+        attach(&header.producer_pid, &header.producer_heartbeat, timeout)?;
+        recover_producer(&mut mmap)?;
+        let mut producer = Self::new(mmap);
+        producer.attached = true;
+        Ok(producer)
+    }
+
+    /// Records that this producer is alive.
+    pub fn heartbeat(&self) {
+        self.header()
+            .producer_heartbeat
+            .store(now_nanos(), Ordering::Release);
+    }
+
+    /// Appends a CRC32C trailer to every record, inside its length. The
+    /// consumer must enable checksums too.
+    #[must_use]
+    pub fn with_checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
     }
 
     /// Writes a message to the ring buffer.
@@ -98,28 +514,41 @@ impl SharedProducer {
     ///
     /// # Returns
     /// `true` if written successfully, `false` if buffer is full.
+    ///
+    /// # Panics
+    /// If the ring has canary-filled free space, panics when the space about
+    /// to be committed was written since the consumer released it.
     #[inline]
     pub fn write(&mut self, data: &[u8]) -> bool {
-        let (head, tail, capacity, mask) = {
+        let (head, tail, capacity, mask, guarded) = {
             let header = self.header();
             (
                 header.head.load(Ordering::Relaxed),
                 header.tail.load(Ordering::Acquire),
                 header.capacity,
                 header.mask,
+                header.has_canary(),
             )
         };
 
+        let trailer = self.checksum.then(|| checksum::trailer(data));
+        let trailer = trailer.as_ref().map_or(&[][..], |trailer| &trailer[..]);
+        let record_len = data.len() + trailer.len();
+
         let available = capacity - (head - tail);
-        let needed = 4 + data.len() as u64; // length prefix + data
+        let needed = 4 + record_len as u64; // length prefix + record
 
         if available < needed {
             return false;
         }
 
-        // Write length prefix
         let offset = SharedRingBuffer::HEADER_SIZE + ((head & mask) as usize);
-        let len_bytes = (data.len() as u32).to_le_bytes();
+        if guarded {
+            self.check_canary(offset, needed as usize, capacity as usize);
+        }
+
+        // Write length prefix
+        let len_bytes = (record_len as u32).to_le_bytes();
 
         // Handle wrap-around for length prefix
         self.write_with_wrap(offset, &len_bytes, capacity as usize);
@@ -127,9 +556,20 @@ impl SharedProducer {
         // Write data
         let data_offset = offset + 4;
         self.write_with_wrap(data_offset, data, capacity as usize);
+        self.write_with_wrap(data_offset + data.len(), trailer, capacity as usize);
+
+        // Mark the record complete
+        if self.header().is_crash_safe() {
+            let committed = (record_len as u32 | SharedRingBuffer::COMMIT_BIT).to_le_bytes();
+            self.write_with_wrap(offset, &committed, capacity as usize);
+        }
 
         // Update head with release semantics
-        self.header().head.store(head + needed, Ordering::Release);
+        let header = self.header();
+        header.head.store(head + needed, Ordering::Release);
+        if header.has_doorbell() {
+            ring_doorbell(header);
+        }
 
         true
     }
@@ -151,6 +591,23 @@ impl SharedProducer {
         }
     }
 
+    /// Checks that free space about to be written still holds the canary.
+    fn check_canary(&self, offset: usize, len: usize, capacity: usize) {
+        let relative_offset = (offset - SharedRingBuffer::HEADER_SIZE) % capacity;
+        let first_part = len.min(capacity - relative_offset);
+        canary::check(
+            &self.mmap[offset..offset + first_part],
+            relative_offset,
+            "shared ring slot",
+        );
+        canary::check(
+            &self.mmap
+                [SharedRingBuffer::HEADER_SIZE..SharedRingBuffer::HEADER_SIZE + len - first_part],
+            0,
+            "shared ring slot",
+        );
+    }
+
     /// Returns a reference to the header.
     fn header(&self) -> &SharedRingBuffer {
         unsafe { &*(self.mmap.as_ptr() as *const SharedRingBuffer) }
@@ -166,24 +623,324 @@ impl SharedProducer {
     }
 }
 
+impl Drop for SharedProducer {
+    fn drop(&mut self) {
+        if self.attached {
+            detach(&self.header().producer_pid, std::process::id());
+        }
+    }
+}
+
 /// Consumer side of shared ring buffer.
 pub struct SharedConsumer {
     mmap: MmapMut,
+    checksum: bool,
+    attached: bool,
+    peeked: Option<Peeked>,
+    scratch: Vec<u8>,
+}
+
+/// A record returned by [`SharedConsumer::peek`] and not yet committed.
+#[derive(Clone, Copy)]
+struct Peeked {
+    tail: u64,
+    len: usize,
+    message: usize,
+    wrapped: bool,
 }
 
 impl SharedConsumer {
     /// Creates a new consumer from a memory map.
     #[must_use]
     pub fn new(mmap: MmapMut) -> Self {
-        Self { mmap }
+        Self {
+            mmap,
+            checksum: false,
+            attached: false,
+            peeked: None,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Attaches a consumer to the ring in `mmap`, recording this process
+    /// and a heartbeat in the control block. If the previous consumer died
+    /// while releasing a record, the release is finished first; a record it
+    /// had read but not started releasing is delivered again.
+    ///
+    /// Call [`heartbeat`](Self::heartbeat) more often than `timeout`, or
+    /// other processes will consider this consumer dead.
+    ///
+    /// # Errors
+    /// Returns an `AddrInUse` error if a live consumer is attached, or an
+    /// `InvalidData` error if the map does not hold a consistent ring.
+    pub fn attach(mmap: MmapMut, timeout: Duration) -> io::Result<Self> {
+        check_ring(&mmap)?;
+        let header = ring_header(&mmap);
+        attach(&header.consumer_pid, &header.consumer_heartbeat, timeout)?;
+        recover_consumer(&mmap);
+        let mut consumer = Self::new(mmap);
+        consumer.attached = true;
+        Ok(consumer)
+    }
+
+    /// Records that this consumer is alive.
+    pub fn heartbeat(&self) {
+        self.header()
+            .consumer_heartbeat
+            .store(now_nanos(), Ordering::Release);
+    }
+
+    /// Verifies and strips the CRC32C trailer of every record.
+    #[must_use]
+    pub fn with_checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
     }
 
     /// Reads the next message from the ring buffer.
     ///
+    /// With checksums enabled, records that fail verification are
+    /// skipped; use [`try_read`](Self::try_read) to see them.
+    ///
     /// # Returns
     /// A slice containing the message data, or `None` if buffer is empty.
     #[inline]
     pub fn read(&mut self) -> Option<Vec<u8>> {
+        loop {
+            if let Ok(message) = self.try_read() {
+                return message;
+            }
+        }
+    }
+
+    /// Reads the next message, blocking while the ring is empty.
+    ///
+    /// Sleeps on the doorbell if the ring has one. Without it, which is
+    /// always the case off Linux, polls every 50 µs, trading latency and
+    /// wake-ups for not spinning.
+    ///
+    /// With checksums enabled, records that fail verification are
+    /// skipped.
+    pub fn read_wait(&mut self) -> Vec<u8> {
+        loop {
+            if let Some(message) = self.read() {
+                return message;
+            }
+            self.wait(None);
+        }
+    }
+
+    /// Reads the next message, blocking while the ring is empty for at
+    /// most `timeout`. Waits like [`read_wait`](Self::read_wait).
+    ///
+    /// # Returns
+    /// The message data, or `None` if the timeout elapsed first.
+    pub fn read_wait_timeout(&mut self, timeout: Duration) -> Option<Vec<u8>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(message) = self.read() {
+                return Some(message);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || !self.wait(Some(remaining)) {
+                return self.read();
+            }
+        }
+    }
+
+    /// Waits until the ring is not empty, for at most `timeout`.
+    ///
+    /// Spins briefly, then sleeps on the doorbell if the ring has one, or
+    /// polls with short sleeps if not.
+    ///
+    /// # Returns
+    /// `true` if a record is pending.
+    fn wait(&self, timeout: Option<Duration>) -> bool {
+        for _ in 0..SPIN_COUNT {
+            if !self.is_empty() {
+                return true;
+            }
+            std::hint::spin_loop();
+        }
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let header = self.header();
+        loop {
+            let remaining = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) if !remaining.is_zero() => Some(remaining),
+                    _ => return !self.is_empty(),
+                },
+                None => None,
+            };
+            if header.has_doorbell() {
+                header.waiters.fetch_add(1, Ordering::Relaxed);
+                // Pairs with the fence in `ring_doorbell`: either the
+                // producer sees the waiter, or the consumer sees the record.
+                fence(Ordering::SeqCst);
+                let ring = header.doorbell.load(Ordering::Acquire);
+                if self.is_empty() {
+                    sys::futex_wait(&header.doorbell, ring, remaining);
+                }
+                header.waiters.fetch_sub(1, Ordering::Relaxed);
+            } else {
+                std::thread::sleep(remaining.map_or(sys::POLL_INTERVAL, |remaining| {
+                    remaining.min(sys::POLL_INTERVAL)
+                }));
+            }
+            if !self.is_empty() {
+                return true;
+            }
+        }
+    }
+
+    /// Reads the next message from the ring buffer, verifying its trailer
+    /// if checksums are enabled.
+    ///
+    /// # Returns
+    /// The message data, or `None` if buffer is empty.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::ChecksumMismatch`] if the record was
+    /// corrupted, or [`DecodeError::BufferTooShort`] if it is too short to
+    /// hold a trailer. The record is consumed either way.
+    #[inline]
+    pub fn try_read(&mut self) -> Result<Option<Vec<u8>>, DecodeError> {
+        let Some(mut record) = self.read_record() else {
+            return Ok(None);
+        };
+        if self.checksum {
+            let len = checksum::verify(&record)?.len();
+            record.truncate(len);
+        }
+        Ok(Some(record))
+    }
+
+    /// Copies the next message into the start of `buf`, verifying and
+    /// excluding its trailer if checksums are enabled.
+    ///
+    /// Unlike [`try_read`](Self::try_read), this does not allocate.
+    ///
+    /// # Returns
+    /// The length of the message, or `None` if buffer is empty.
+    ///
+    /// # Errors
+    /// Returns [`DecodeError::BufferTooShort`] with the record length as
+    /// `required` if `buf` cannot hold the record, which is then left in
+    /// the ring. Otherwise the record is consumed, and the errors are those
+    /// of [`try_read`](Self::try_read).
+    pub fn read_into(
+        &mut self,
+        buf: &mut (impl WriteBuffer + ?Sized),
+    ) -> Result<Option<usize>, DecodeError> {
+        let Some((tail, len)) = self.next_record() else {
+            return Ok(None);
+        };
+        let dst = buf.as_mut_slice();
+        if dst.len() < len {
+            return Err(DecodeError::BufferTooShort {
+                required: len,
+                available: dst.len(),
+            });
+        }
+        let (first, second) = region(&self.mmap, tail + 4, len);
+        dst[..first.len()].copy_from_slice(first);
+        dst[first.len()..len].copy_from_slice(second);
+        self.release(tail, len);
+        if self.checksum {
+            return Ok(Some(checksum::verify(&dst[..len])?.len()));
+        }
+        Ok(Some(len))
+    }
+
+    /// Returns the next message without consuming it.
+    ///
+    /// The message is borrowed straight from the shared mapping, or, if it
+    /// wraps around the end of the ring, from a buffer the consumer reuses.
+    /// Peeking again returns the same message until
+    /// [`commit`](Self::commit) releases it. With checksums enabled, the
+    /// trailer is verified and excluded, and records that fail
+    /// verification are skipped.
+    ///
+    /// # Returns
+    /// The message data, or `None` if buffer is empty.
+    pub fn peek(&mut self) -> Option<&[u8]> {
+        let peeked = match self.peeked {
+            Some(peeked) => peeked,
+            None => {
+                let peeked = self.locate()?;
+                self.peeked = Some(peeked);
+                peeked
+            }
+        };
+        if peeked.wrapped {
+            Some(&self.scratch[..peeked.message])
+        } else {
+            Some(region(&self.mmap, peeked.tail + 4, peeked.message).0)
+        }
+    }
+
+    /// Consumes the message returned by the last [`peek`](Self::peek).
+    ///
+    /// # Returns
+    /// `false` if no message was peeked since the last read or commit.
+    pub fn commit(&mut self) -> bool {
+        match self.peeked {
+            Some(peeked) => {
+                self.release(peeked.tail, peeked.len);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Finds the next record that passes verification, copying it to the
+    /// scratch buffer if it wraps.
+    fn locate(&mut self) -> Option<Peeked> {
+        loop {
+            let (tail, len) = self.next_record()?;
+            let (first, second) = region(&self.mmap, tail + 4, len);
+            let wrapped = !second.is_empty();
+            if wrapped {
+                self.scratch.clear();
+                self.scratch.extend_from_slice(first);
+                self.scratch.extend_from_slice(second);
+            }
+            let message = if self.checksum {
+                let record = if wrapped { &self.scratch[..] } else { first };
+                match checksum::verify(record) {
+                    Ok(message) => message.len(),
+                    Err(_) => {
+                        self.release(tail, len);
+                        continue;
+                    }
+                }
+            } else {
+                len
+            };
+            return Some(Peeked {
+                tail,
+                len,
+                message,
+                wrapped,
+            });
+        }
+    }
+
+    /// Consumes the next record, trailer included.
+    fn read_record(&mut self) -> Option<Vec<u8>> {
+        let (tail, len) = self.next_record()?;
+        let (first, second) = region(&self.mmap, tail + 4, len);
+        let data = [first, second].concat();
+        self.release(tail, len);
+        Some(data)
+    }
+
+    /// Locates the next complete record without consuming it.
+    ///
+    /// # Returns
+    /// The tail position and the length of the record, trailer included,
+    /// or `None` if buffer is empty.
+    fn next_record(&self) -> Option<(u64, usize)> {
         let header = self.header();
         let tail = header.tail.load(Ordering::Relaxed);
         let head = header.head.load(Ordering::Acquire);
@@ -193,41 +950,50 @@ impl SharedConsumer {
         }
 
         // Read length prefix
-        let offset = SharedRingBuffer::HEADER_SIZE + ((tail & header.mask) as usize);
-        let len_bytes = self.read_with_wrap(offset, 4, header.capacity as usize);
-        let len =
-            u32::from_le_bytes([len_bytes[0], len_bytes[1], len_bytes[2], len_bytes[3]]) as usize;
+        let (first, second) = region(&self.mmap, tail, 4);
+        let mut len_bytes = [0u8; 4];
+        len_bytes[..first.len()].copy_from_slice(first);
+        len_bytes[first.len()..].copy_from_slice(second);
+        let mut len = u32::from_le_bytes(len_bytes);
+        if header.is_crash_safe() {
+            // An incomplete record stays unread until recovery rolls it back.
+            if len & SharedRingBuffer::COMMIT_BIT == 0 {
+                return None;
+            }
+            len &= !SharedRingBuffer::COMMIT_BIT;
+        }
+        Some((tail, len as usize))
+    }
 
-        // Read data
-        let data_offset = offset + 4;
-        let data = self.read_with_wrap(data_offset, len, header.capacity as usize);
+    /// Releases the record of `len` bytes at `tail` to the producer.
+    fn release(&mut self, tail: u64, len: usize) {
+        self.peeked = None;
+        let header = self.header();
+        let consumed = 4 + len as u64;
+
+        // Hand the record's space back as canary before releasing it
+        if header.has_canary() {
+            if header.is_crash_safe() {
+                header.release_to.store(tail + consumed, Ordering::Release);
+            }
+            let offset = SharedRingBuffer::HEADER_SIZE + ((tail & header.mask) as usize);
+            let capacity = header.capacity as usize;
+            self.fill_canary(offset, consumed as usize, capacity);
+        }
 
         // Update tail
-        let consumed = 4 + len as u64;
+        let header = self.header();
         header.tail.store(tail + consumed, Ordering::Release);
-
-        Some(data)
     }
 
-    /// Reads data handling wrap-around.
-    fn read_with_wrap(&self, offset: usize, len: usize, capacity: usize) -> Vec<u8> {
+    /// Refills consumed space with the canary pattern, handling wrap-around.
+    fn fill_canary(&mut self, offset: usize, len: usize, capacity: usize) {
         let data_region =
-            &self.mmap[SharedRingBuffer::HEADER_SIZE..SharedRingBuffer::HEADER_SIZE + capacity];
+            &mut self.mmap[SharedRingBuffer::HEADER_SIZE..SharedRingBuffer::HEADER_SIZE + capacity];
         let relative_offset = (offset - SharedRingBuffer::HEADER_SIZE) % capacity;
-
-        let mut result = vec![0u8; len];
-
-        if relative_offset + len <= capacity {
-            // No wrap
-            result.copy_from_slice(&data_region[relative_offset..relative_offset + len]);
-        } else {
-            // Wrap around
-            let first_part = capacity - relative_offset;
-            result[..first_part].copy_from_slice(&data_region[relative_offset..]);
-            result[first_part..].copy_from_slice(&data_region[..len - first_part]);
-        }
-
-        result
+        let first_part = len.min(capacity - relative_offset);
+        canary::fill(&mut data_region[relative_offset..relative_offset + first_part]);
+        canary::fill(&mut data_region[..len - first_part]);
     }
 
     /// Returns a reference to the header.
@@ -251,6 +1017,105 @@ impl SharedConsumer {
     }
 }
 
+impl Drop for SharedConsumer {
+    fn drop(&mut self) {
+        if self.attached {
+            detach(&self.header().consumer_pid, std::process::id());
+        }
+    }
+}
+
+/// Wakes a consumer sleeping on the doorbell, if there is one.
+#[inline]
+fn ring_doorbell(header: &SharedRingBuffer) {
+    // Orders the head store before the waiter check; see `wait`.
+    fence(Ordering::SeqCst);
+    if header.waiters.load(Ordering::Relaxed) > 0 {
+        header.doorbell.fetch_add(1, Ordering::Release);
+        sys::futex_wake(&header.doorbell);
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::sync::atomic::AtomicU32;
+    use std::time::Duration;
+
+    /// Whether rings can have a doorbell.
+    pub(super) const HAS_FUTEX: bool = true;
+
+    /// Sleep between polls of a ring without a doorbell.
+    pub(super) const POLL_INTERVAL: Duration = Duration::from_micros(50);
+
+    /// Sleeps while `word` holds `expected`, for at most `timeout`. The
+    /// futex is not process-private, as the word lives in shared memory.
+    pub(super) fn futex_wait(word: &AtomicU32, expected: u32, timeout: Option<Duration>) {
+        let timespec = timeout.map(|timeout| libc::timespec {
+            tv_sec: libc::time_t::try_from(timeout.as_secs()).unwrap_or(libc::time_t::MAX),
+            tv_nsec: timeout.subsec_nanos() as libc::c_long,
+        });
+        let timespec = timespec
+            .as_ref()
+            .map_or(std::ptr::null(), |timespec| timespec as *const _);
+        // SAFETY: `word` is a valid, aligned futex word for the duration of
+        // the call, and `timespec` is null or points at a live timespec.
+        // Spurious wake-ups, timeouts and `EAGAIN` are handled by the
+        // caller re-checking the ring.
+        unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                word.as_ptr(),
+                libc::FUTEX_WAIT,
+                expected,
+                timespec,
+            );
+        }
+    }
+
+    /// Wakes every thread sleeping on `word`, in any process.
+    pub(super) fn futex_wake(word: &AtomicU32) {
+        // SAFETY: `word` is a valid, aligned futex word.
+        unsafe {
+            libc::syscall(libc::SYS_futex, word.as_ptr(), libc::FUTEX_WAKE, i32::MAX);
+        }
+    }
+
+    /// Returns false only if no process has ID `pid`. Processes in other
+    /// PID namespaces look absent.
+    pub(super) fn process_exists(pid: u32) -> bool {
+        let Ok(pid) = libc::pid_t::try_from(pid) else {
+            return false;
+        };
+        // SAFETY: signal 0 only checks that the process exists.
+        let rc = unsafe { libc::kill(pid, 0) };
+        rc == 0 || std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::sync::atomic::AtomicU32;
+    use std::time::Duration;
+
+    /// Rings are created without a doorbell, so the futex calls below are
+    /// never reached.
+    pub(super) const HAS_FUTEX: bool = false;
+
+    /// Sleep between polls of an empty ring.
+    pub(super) const POLL_INTERVAL: Duration = Duration::from_micros(50);
+
+    pub(super) fn futex_wait(_word: &AtomicU32, _expected: u32, timeout: Option<Duration>) {
+        std::thread::sleep(timeout.map_or(POLL_INTERVAL, |timeout| timeout.min(POLL_INTERVAL)));
+    }
+
+    pub(super) fn futex_wake(_word: &AtomicU32) {}
+
+    /// Liveness is judged by heartbeats alone on this platform.
+    pub(super) fn process_exists(_pid: u32) -> bool {
+        true
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -317,4 +1182,333 @@ mod tests {
 
         assert!(consumer.read().is_none());
     }
+
+    #[test]
+    fn test_shared_ring_buffer_round_trip_with_wrap() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_round_trip");
+
+        let mmap = SharedRingBuffer::create(&path, 64).unwrap();
+        let mut producer = SharedProducer::new(mmap);
+        let mut consumer = SharedConsumer::new(SharedRingBuffer::open(&path).unwrap());
+
+        for i in 0..20u8 {
+            let msg = [i; 13];
+            assert!(producer.write(&msg));
+            assert_eq!(consumer.read().unwrap(), msg);
+        }
+        assert!(consumer.is_empty());
+        assert_eq!(producer.available(), 64);
+    }
+
+    #[test]
+    fn test_shared_ring_buffer_checksum() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_checksum");
+
+        let mmap = SharedRingBuffer::create(&path, 64).unwrap();
+        let mut producer = SharedProducer::new(mmap).with_checksum(true);
+        let mut consumer =
+            SharedConsumer::new(SharedRingBuffer::open(&path).unwrap()).with_checksum(true);
+
+        for i in 0..10u8 {
+            let msg = [i; 11];
+            assert!(producer.write(&msg));
+            assert_eq!(consumer.try_read(), Ok(Some(msg.to_vec())));
+        }
+
+        // Flip a byte of the next record after it is committed.
+        assert!(producer.write(b"corrupted"));
+        assert!(producer.write(b"intact"));
+        let mut stray = SharedRingBuffer::open(&path).unwrap();
+        let offset = SharedRingBuffer::HEADER_SIZE + (10 * 19 + 4) % 64;
+        stray[offset] ^= 1;
+
+        assert!(matches!(
+            consumer.try_read(),
+            Err(DecodeError::ChecksumMismatch { .. })
+        ));
+        assert_eq!(consumer.read(), Some(b"intact".to_vec()));
+        assert_eq!(consumer.try_read(), Ok(None));
+    }
+
+    #[test]
+    fn test_read_into_copies_without_allocating() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_read_into");
+
+        let mmap = SharedRingBuffer::create(&path, 64).unwrap();
+        let mut producer = SharedProducer::new(mmap).with_checksum(true);
+        let mut consumer =
+            SharedConsumer::new(SharedRingBuffer::open(&path).unwrap()).with_checksum(true);
+
+        let mut buf = [0u8; 32];
+        for i in 0..20u8 {
+            let msg = [i; 13];
+            assert!(producer.write(&msg));
+            assert_eq!(consumer.read_into(&mut buf[..]), Ok(Some(13)));
+            assert_eq!(buf[..13], msg);
+        }
+        assert_eq!(consumer.read_into(&mut buf[..]), Ok(None));
+
+        // A buffer too short for the record leaves it in the ring.
+        assert!(producer.write(b"does not fit"));
+        let mut small = vec![0u8; 8];
+        assert_eq!(
+            consumer.read_into(&mut small),
+            Err(DecodeError::BufferTooShort {
+                required: 16,
+                available: 8,
+            })
+        );
+        assert_eq!(consumer.read(), Some(b"does not fit".to_vec()));
+    }
+
+    #[test]
+    fn test_peek_borrows_and_commit_releases() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_peek");
+
+        let mmap = SharedRingBuffer::create(&path, 64).unwrap();
+        let mut producer = SharedProducer::new(mmap).with_checksum(true);
+        let mut consumer =
+            SharedConsumer::new(SharedRingBuffer::open(&path).unwrap()).with_checksum(true);
+
+        assert_eq!(consumer.peek(), None);
+        assert!(!consumer.commit());
+
+        let mut wrapped = 0;
+        for i in 0..20u8 {
+            let msg = [i; 13];
+            assert!(producer.write(&msg));
+            let mapping = consumer.mmap.as_ptr_range();
+            let peeked = consumer.peek().unwrap();
+            assert_eq!(peeked, msg);
+            if !mapping.contains(&peeked.as_ptr()) {
+                wrapped += 1;
+            }
+            assert_eq!(consumer.peek().unwrap(), msg);
+            assert!(consumer.commit());
+            assert!(!consumer.commit());
+        }
+        // Only records that wrap are copied.
+        assert!(wrapped > 0 && wrapped < 20);
+        assert!(consumer.is_empty());
+        assert_eq!(producer.available(), 64);
+
+        // Corrupted records are skipped.
+        assert!(producer.write(b"corrupted"));
+        assert!(producer.write(b"intact"));
+        let mut stray = SharedRingBuffer::open(&path).unwrap();
+        let offset = SharedRingBuffer::HEADER_SIZE + (20 * 21 + 4) % 64;
+        stray[offset] ^= 1;
+        assert_eq!(consumer.peek(), Some(&b"intact"[..]));
+
+        // Reading the peeked record withdraws it from commit.
+        assert_eq!(consumer.read(), Some(b"intact".to_vec()));
+        assert!(!consumer.commit());
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn test_read_wait_wakes_on_doorbell() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_doorbell");
+
+        let config = SharedRingConfig {
+            capacity: 1024,
+            doorbell: true,
+            ..SharedRingConfig::default()
+        };
+        let mmap = SharedRingBuffer::create_with_config(&path, &config).unwrap();
+        let mut producer = SharedProducer::new(mmap);
+        assert_eq!(producer.header().has_doorbell(), cfg!(target_os = "linux"));
+        let mut consumer = SharedConsumer::new(SharedRingBuffer::open(&path).unwrap());
+
+        assert_eq!(consumer.read_wait_timeout(Duration::from_millis(20)), None);
+        let reader = std::thread::spawn(move || {
+            (0..100u8).map(|_| consumer.read_wait()).collect::<Vec<_>>()
+        });
+        for i in 0..100u8 {
+            if i % 10 == 0 {
+                // Let the consumer fall asleep on the doorbell.
+                std::thread::sleep(Duration::from_millis(2));
+            }
+            while !producer.write(&[i; 7]) {
+                std::hint::spin_loop();
+            }
+        }
+        let messages = reader.join().unwrap();
+        assert!(messages.iter().enumerate().all(|(i, m)| m == &[i as u8; 7]));
+    }
+
+    #[test]
+    fn test_read_wait_polls_without_doorbell() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_no_doorbell");
+
+        let mmap = SharedRingBuffer::create(&path, 256).unwrap();
+        let mut producer = SharedProducer::new(mmap);
+        assert!(!producer.header().has_doorbell());
+        let mut consumer = SharedConsumer::new(SharedRingBuffer::open(&path).unwrap());
+
+        let reader = std::thread::spawn(move || consumer.read_wait_timeout(Duration::from_secs(5)));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(producer.write(b"late"));
+        assert_eq!(reader.join().unwrap(), Some(b"late".to_vec()));
+    }
+
+    /// A process ID no live process has.
+    const DEAD_PID: u32 = 0x3fff_fffe;
+
+    fn crash_safe_ring(path: &Path, capacity: usize) -> MmapMut {
+        let config = SharedRingConfig {
+            capacity,
+            crash_safe: true,
+            ..SharedRingConfig::default()
+        };
+        SharedRingBuffer::create_with_config(path, &config).unwrap()
+    }
+
+    #[test]
+    fn test_attach_refuses_live_peer() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_attach");
+        drop(crash_safe_ring(&path, 256));
+        let timeout = Duration::from_secs(5);
+
+        let producer = SharedProducer::attach(SharedRingBuffer::open(&path).unwrap(), timeout);
+        let producer = producer.unwrap();
+        let err = SharedProducer::attach(SharedRingBuffer::open(&path).unwrap(), timeout)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        let consumer = SharedConsumer::attach(SharedRingBuffer::open(&path).unwrap(), timeout);
+        let consumer = consumer.unwrap();
+
+        let mut mmap = SharedRingBuffer::open(&path).unwrap();
+        let report = SharedRingBuffer::recover(&mut mmap, timeout).unwrap();
+        assert_eq!(report.producer, PeerState::Alive);
+        assert_eq!(report.consumer, PeerState::Alive);
+
+        // Detaching cleanly frees the endpoint.
+        drop(producer);
+        drop(consumer);
+        let report = SharedRingBuffer::recover(&mut mmap, timeout).unwrap();
+        assert_eq!(report.producer, PeerState::Detached);
+        assert_eq!(report.consumer, PeerState::Detached);
+        assert!(SharedProducer::attach(SharedRingBuffer::open(&path).unwrap(), timeout).is_ok());
+    }
+
+    #[test]
+    fn test_stale_heartbeat_is_dead() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_stale");
+        drop(crash_safe_ring(&path, 256));
+
+        let producer =
+            SharedProducer::attach(SharedRingBuffer::open(&path).unwrap(), Duration::MAX).unwrap();
+        producer
+            .header()
+            .producer_heartbeat
+            .store(1, Ordering::Release);
+        let err = SharedProducer::attach(SharedRingBuffer::open(&path).unwrap(), Duration::MAX)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+
+        // A hung producer is taken over once its heartbeat is too old.
+        let timeout = Duration::from_secs(1);
+        let successor = SharedProducer::attach(SharedRingBuffer::open(&path).unwrap(), timeout);
+        assert!(successor.is_ok());
+        producer.heartbeat();
+    }
+
+    #[test]
+    fn test_dead_producer_incomplete_write_is_rolled_back() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_rollback");
+        let mut producer = SharedProducer::new(crash_safe_ring(&path, 64));
+        let mut consumer = SharedConsumer::new(SharedRingBuffer::open(&path).unwrap());
+        assert!(producer.write(b"complete"));
+        assert!(producer.write(b"torn"));
+
+        // Simulate a producer that died after publishing the head of a
+        // record whose commit flag it never set.
+        let mut stray = SharedRingBuffer::open(&path).unwrap();
+        let header = ring_header(&stray);
+        header.producer_pid.store(DEAD_PID, Ordering::Release);
+        stray[SharedRingBuffer::HEADER_SIZE + 12 + 3] &= 0x7f;
+        drop(producer);
+
+        assert_eq!(consumer.read(), Some(b"complete".to_vec()));
+        assert_eq!(consumer.read(), None);
+
+        let report = SharedRingBuffer::recover(&mut stray, Duration::from_secs(5)).unwrap();
+        assert_eq!(report.producer, PeerState::Dead);
+        assert_eq!(report.rolled_back, 8);
+
+        let timeout = Duration::from_secs(5);
+        let mut successor =
+            SharedProducer::attach(SharedRingBuffer::open(&path).unwrap(), timeout).unwrap();
+        assert_eq!(successor.available(), 64);
+        assert!(successor.write(b"next"));
+        assert_eq!(consumer.read(), Some(b"next".to_vec()));
+    }
+
+    #[test]
+    fn test_dead_consumer_release_is_finished() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_release");
+        let mut producer = SharedProducer::new(crash_safe_ring(&path, 64));
+        assert!(producer.write(b"released"));
+        assert!(producer.write(b"pending"));
+
+        // Simulate a consumer that died while releasing the first record.
+        let mut stray = SharedRingBuffer::open(&path).unwrap();
+        let header = ring_header(&stray);
+        header.consumer_pid.store(DEAD_PID, Ordering::Release);
+        header.release_to.store(12, Ordering::Release);
+
+        let report = SharedRingBuffer::recover(&mut stray, Duration::from_secs(5)).unwrap();
+        assert_eq!(report.consumer, PeerState::Dead);
+        assert_eq!(report.released, 12);
+        assert_eq!(report.rolled_back, 0);
+
+        let mut successor = SharedConsumer::attach(
+            SharedRingBuffer::open(&path).unwrap(),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert_eq!(successor.read(), Some(b"pending".to_vec()));
+    }
+
+    #[test]
+    fn test_recover_rejects_inconsistent_ring() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_inconsistent");
+        let mut mmap = crash_safe_ring(&path, 64);
+        ring_header(&mmap).tail.store(8, Ordering::Release);
+        let err = SharedRingBuffer::recover(&mut mmap, Duration::from_secs(1)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "canary overwritten in shared ring slot at offset 12")]
+    fn test_shared_ring_buffer_detects_stray_write() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_stray_write");
+
+        let mmap = SharedRingBuffer::create(&path, 64).unwrap();
+        let mut producer = SharedProducer::new(mmap);
+        assert!(producer.header().has_canary());
+        assert!(producer.write(b"12345678"));
+
+        // Simulate an encoder overrunning the record it just committed
+        let mut stray = SharedRingBuffer::open(&path).unwrap();
+        stray[SharedRingBuffer::HEADER_SIZE + 12] = 0;
+
+        producer.write(b"next");
+    }
 }
@@ -1,5 +1,6 @@
 //! Shared memory utilities.
 
+use ironsbe_core::memory::{self, MemoryConfig};
 use memmap2::{MmapMut, MmapOptions};
 use std::fs::OpenOptions;
 use std::path::Path;
@@ -11,6 +12,12 @@ pub struct SharedMemoryConfig {
     pub size: usize,
     /// Whether to create the file if it doesn't exist.
     pub create: bool,
+    /// NUMA node, huge-page and prefault options for the mapping.
+    ///
+    /// File mappings cannot come from the huge-page pool, so
+    /// [`HugePages::Explicit`](memory::HugePages::Explicit) is treated as
+    /// transparent huge pages.
+    pub memory: MemoryConfig,
 }
 
 impl Default for SharedMemoryConfig {
@@ -18,6 +25,7 @@ impl Default for SharedMemoryConfig {
         Self {
             size: 1024 * 1024, // 1MB
             create: true,
+            memory: MemoryConfig::default(),
         }
     }
 }
@@ -36,7 +44,8 @@ impl SharedMemory {
     /// * `config` - Configuration options
     ///
     /// # Errors
-    /// Returns IO error if file operations fail.
+    /// Returns IO error if file operations fail, or, with a strict memory
+    /// configuration, if the placement cannot be applied.
     pub fn open(path: &Path, config: SharedMemoryConfig) -> std::io::Result<Self> {
         let file = OpenOptions::new()
             .read(true)
@@ -46,7 +55,8 @@ impl SharedMemory {
 
         file.set_len(config.size as u64)?;
 
-        let mmap = unsafe { MmapOptions::new().map_mut(&file)? };
+        let mut mmap = unsafe { MmapOptions::new().map_mut(&file)? };
+        memory::apply(&mut mmap, &config.memory)?;
 
         Ok(Self {
             mmap,
@@ -132,6 +142,7 @@ mod tests {
         let config = SharedMemoryConfig {
             size: 4096,
             create: false,
+            memory: MemoryConfig::new().numa_node(0),
         };
         let cloned = config.clone();
         assert_eq!(config.size, cloned.size);
@@ -181,6 +192,9 @@ mod tests {
         let config = SharedMemoryConfig {
             size: 1024,
             create: false,
+            memory: MemoryConfig::new()
+                .huge_pages(memory::HugePages::Transparent)
+                .prefault(true),
         };
         let shm = SharedMemory::open(&path, config).unwrap();
         assert_eq!(shm.size(), 1024);
@@ -6,9 +6,22 @@
 //! - [`traits`] - Backend-agnostic [`Transport`], [`Listener`], [`Connection`]
 //!   traits (always available)
 //! - [`tcp`] - Tokio-based TCP backend (feature `tcp-tokio`, enabled by
-//!   default)
-//! - [`udp`] - UDP unicast and multicast with A/B arbitration
-//! - [`ipc`] - Shared memory IPC transport
+//!   default), optionally over TLS (feature `tls`)
+//! - [`tcp_std`] - Blocking TCP backend on `std::net` (feature `tcp-std`,
+//!   enabled by default)
+//! - [`ws`] - SBE messages over WebSocket (feature `ws`)
+//! - [`udp`] - UDP unicast and multicast with A/B arbitration and NAK-based
+//!   retransmission (feature `tokio`)
+//! - [`ipc`] - Shared memory IPC transport: an SPSC ring with optional futex
+//!   wake-ups and crash recovery, and a broadcast bus with independent
+//!   consumers
+//! - [`capture`] - pcap capture of UDP and TCP traffic and paced replay
+//!   of pcap and pcapng files
+//! - [`limits`] - Per-template frame size limits for the stream backends
+//! - [`timestamp`] - Kernel and NIC receive timestamps for UDP and TCP
+//!   (Linux)
+//! - [`sockopt`] - Busy-poll, buffer size, TOS/DSCP and bind-to-device
+//!   options shared by the TCP and UDP configs (feature `tokio`)
 //!
 //! # Selecting a backend
 //!
@@ -22,15 +35,40 @@
 //! [`DefaultTransport`] is a type alias that resolves to the backend selected
 //! by the active feature.  Code that is generic over `T: Transport` can use
 //! `DefaultTransport` as the default type parameter.
+//!
+//! # Building without Tokio
+//!
+//! Tokio is pulled in only by the `tokio` feature, which every async
+//! backend enables. With `default-features = false` and at most
+//! `tcp-std`, the crate builds IPC and blocking TCP without an async
+//! runtime:
+//!
+//! ```toml
+//! ironsbe-transport = { version = "...", default-features = false, features = ["tcp-std"] }
+//! ```
 
+pub mod capture;
 pub mod error;
 pub mod ipc;
+pub mod limits;
+pub mod timestamp;
 pub mod traits;
+
+#[cfg(feature = "tokio")]
+pub mod sockopt;
+
+#[cfg(feature = "tokio")]
 pub mod udp;
 
 #[cfg(feature = "tcp-tokio")]
 pub mod tcp;
 
+#[cfg(feature = "tcp-std")]
+pub mod tcp_std;
+
+#[cfg(feature = "ws")]
+pub mod ws;
+
 /// Linux io_uring TCP backend (feature `tcp-uring`).
 ///
 /// This module is only compiled on Linux.  On other platforms enabling the
@@ -50,6 +88,8 @@ pub mod tcp_uring;
 pub mod xdp;
 
 pub use error::TransportError;
+pub use limits::FrameLimits;
+pub use timestamp::{RxTimestamp, TimestampMode, TimestampSource};
 pub use traits::{Connection, Listener, Transport};
 
 /// The transport backend selected by the active cargo feature.
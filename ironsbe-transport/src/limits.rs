@@ -0,0 +1,189 @@
+//! Per-template frame size limits.
+//!
+//! A single `max_frame_size` has to be large enough for the biggest message
+//! a peer may legitimately send, which lets any message grow that large.
+//! [`FrameLimits`] keeps that global cap as the default and adds tighter
+//! (or looser) caps for individual template ids, so heartbeats can be held
+//! to a few bytes while snapshots may still reach megabytes.
+//!
+//! Stream backends check the length prefix against the largest limit as
+//! soon as it arrives, then check the exact limit once the template id in
+//! the message header is readable, before buffering the rest of the frame.
+
+use crate::error::TransportError;
+use std::collections::HashMap;
+
+/// Offset of the template id within a message: it follows the `u16` block
+/// length in the standard header and in the extended headers venues use.
+const TEMPLATE_ID_OFFSET: usize = 2;
+
+/// Number of leading message bytes needed to read the template id.
+pub const TEMPLATE_ID_END: usize = TEMPLATE_ID_OFFSET + 2;
+
+/// Frame size limits: a default plus overrides per template id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameLimits {
+    max_frame_size: usize,
+    templates: HashMap<u16, usize>,
+}
+
+impl FrameLimits {
+    /// Creates limits that apply `max_frame_size` to every template.
+    #[must_use]
+    pub fn new(max_frame_size: usize) -> Self {
+        Self {
+            max_frame_size,
+            templates: HashMap::new(),
+        }
+    }
+
+    /// Sets the limit for one template id, overriding the default.
+    #[must_use]
+    pub fn template(mut self, template_id: u16, max: usize) -> Self {
+        self.templates.insert(template_id, max);
+        self
+    }
+
+    /// Replaces the default limit.
+    pub fn set_max_frame_size(&mut self, max_frame_size: usize) {
+        self.max_frame_size = max_frame_size;
+    }
+
+    /// Returns the default limit.
+    #[must_use]
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// Returns true if any template has its own limit.
+    #[must_use]
+    pub fn has_template_limits(&self) -> bool {
+        !self.templates.is_empty()
+    }
+
+    /// Returns the limit for `template_id`.
+    #[must_use]
+    pub fn limit_for(&self, template_id: u16) -> usize {
+        self.templates
+            .get(&template_id)
+            .copied()
+            .unwrap_or(self.max_frame_size)
+    }
+
+    /// Returns the largest limit of any template.
+    #[must_use]
+    pub fn ceiling(&self) -> usize {
+        self.templates
+            .values()
+            .copied()
+            .fold(self.max_frame_size, usize::max)
+    }
+
+    /// Checks a frame length before its header is known.
+    ///
+    /// # Errors
+    /// Returns [`TransportError::FrameTooLarge`] if `size` exceeds every
+    /// limit.
+    pub fn check_length(&self, size: usize) -> Result<(), TransportError> {
+        let max = self.ceiling();
+        if size > max {
+            return Err(TransportError::frame_too_large(size, max));
+        }
+        Ok(())
+    }
+
+    /// Checks a frame of `size` bytes whose message starts with `head`.
+    ///
+    /// `head` needs at least [`TEMPLATE_ID_END`] bytes for the template
+    /// limit to apply; shorter frames are held to the default.
+    ///
+    /// # Errors
+    /// Returns [`TransportError::TemplateFrameTooLarge`] if the frame
+    /// exceeds its template's own limit, or [`TransportError::FrameTooLarge`]
+    /// if it exceeds the default and its template has no limit of its own.
+    pub fn check(&self, size: usize, head: &[u8]) -> Result<(), TransportError> {
+        let limit = template_id(head)
+            .and_then(|template_id| Some((template_id, *self.templates.get(&template_id)?)));
+        match limit {
+            Some((template_id, max)) if size > max => Err(TransportError::TemplateFrameTooLarge {
+                template_id,
+                size,
+                max,
+            }),
+            Some(_) => Ok(()),
+            None if size > self.max_frame_size => {
+                Err(TransportError::frame_too_large(size, self.max_frame_size))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for FrameLimits {
+    fn default() -> Self {
+        Self::new(64 * 1024)
+    }
+}
+
+/// Reads the template id from the leading bytes of a message.
+#[must_use]
+pub fn template_id(head: &[u8]) -> Option<u16> {
+    let bytes = head.get(TEMPLATE_ID_OFFSET..TEMPLATE_ID_END)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Leading bytes of a message with `template_id`.
+    fn head(template_id: u16) -> [u8; 8] {
+        let mut bytes = [0u8; 8];
+        bytes[2..4].copy_from_slice(&template_id.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_template_overrides_default() {
+        let limits = FrameLimits::new(1024).template(0, 64).template(5, 1 << 20);
+        assert_eq!(limits.limit_for(0), 64);
+        assert_eq!(limits.limit_for(5), 1 << 20);
+        assert_eq!(limits.limit_for(9), 1024);
+        assert_eq!(limits.ceiling(), 1 << 20);
+
+        assert!(limits.check(64, &head(0)).is_ok());
+        assert!(matches!(
+            limits.check(65, &head(0)),
+            Err(TransportError::TemplateFrameTooLarge {
+                template_id: 0,
+                size: 65,
+                max: 64,
+            })
+        ));
+        assert!(limits.check(500_000, &head(5)).is_ok());
+        assert!(matches!(
+            limits.check(2048, &head(9)),
+            Err(TransportError::FrameTooLarge {
+                size: 2048,
+                max: 1024
+            })
+        ));
+    }
+
+    #[test]
+    fn test_length_and_short_frames_use_ceiling_and_default() {
+        let limits = FrameLimits::new(16).template(5, 256);
+        assert!(limits.check_length(256).is_ok());
+        assert!(matches!(
+            limits.check_length(257),
+            Err(TransportError::FrameTooLarge {
+                size: 257,
+                max: 256
+            })
+        ));
+        // Too short to name a template.
+        assert!(limits.check(3, &[0, 0, 5]).is_ok());
+        assert_eq!(template_id(&[0, 0, 5]), None);
+        assert_eq!(template_id(&head(5)), Some(5));
+    }
+}
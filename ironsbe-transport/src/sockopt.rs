@@ -0,0 +1,161 @@
+//! Latency-related socket options shared by the TCP and UDP backends.
+//!
+//! The configs expose the options as plain fields; this module applies
+//! them the same way whichever socket they end up on. Options the platform
+//! lacks fail with an `Unsupported` error instead of being ignored, so a
+//! tuned deployment never silently runs untuned.
+
+use socket2::SockRef;
+use std::io;
+use std::time::Duration;
+
+/// Converts a DSCP code point (0-63) into the `IP_TOS` byte it occupies,
+/// leaving the ECN bits clear.
+#[must_use]
+pub const fn dscp_to_tos(dscp: u8) -> u8 {
+    (dscp & 0x3f) << 2
+}
+
+/// Options applied to a socket before or after it connects.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SocketTuning<'a> {
+    /// `SO_RCVBUF`, in bytes.
+    pub(crate) recv_buffer_size: Option<usize>,
+    /// `SO_SNDBUF`, in bytes.
+    pub(crate) send_buffer_size: Option<usize>,
+    /// `SO_BUSY_POLL`.
+    pub(crate) busy_poll: Option<Duration>,
+    /// `IP_TOS`, or `IPV6_TCLASS` on IPv6 sockets.
+    pub(crate) tos: Option<u8>,
+    /// `SO_BINDTODEVICE`.
+    pub(crate) bind_device: Option<&'a str>,
+}
+
+impl SocketTuning<'_> {
+    /// Applies every option that is set.
+    ///
+    /// # Errors
+    /// Returns the first `setsockopt` error, or `Unsupported` for an option
+    /// the platform lacks.
+    pub(crate) fn apply(&self, sock: SockRef<'_>) -> io::Result<()> {
+        if let Some(size) = self.recv_buffer_size {
+            sock.set_recv_buffer_size(size)?;
+        }
+        if let Some(size) = self.send_buffer_size {
+            sock.set_send_buffer_size(size)?;
+        }
+        if let Some(busy_poll) = self.busy_poll {
+            set_busy_poll(&sock, busy_poll)?;
+        }
+        if let Some(tos) = self.tos {
+            set_tos(&sock, tos)?;
+        }
+        if let Some(device) = self.bind_device {
+            bind_device(&sock, device)?;
+        }
+        Ok(())
+    }
+}
+
+/// Sets `SO_BUSY_POLL`: how long a blocking read spins on the device
+/// queue before sleeping. Needs `CAP_NET_ADMIN` to raise it above the
+/// `net.core.busy_read` sysctl.
+#[cfg(target_os = "linux")]
+pub(crate) fn set_busy_poll(sock: &SockRef<'_>, busy_poll: Duration) -> io::Result<()> {
+    let micros = u32::try_from(busy_poll.as_micros()).unwrap_or(u32::MAX);
+    sock.set_busy_poll(micros)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn set_busy_poll(_sock: &SockRef<'_>, _busy_poll: Duration) -> io::Result<()> {
+    Err(unsupported("SO_BUSY_POLL"))
+}
+
+/// Sets `IP_TOS`, or `IPV6_TCLASS` for IPv6 sockets.
+pub(crate) fn set_tos(sock: &SockRef<'_>, tos: u8) -> io::Result<()> {
+    let ipv6 = sock
+        .local_addr()?
+        .as_socket()
+        .is_some_and(|addr| addr.is_ipv6());
+    if !ipv6 {
+        return sock.set_tos_v4(u32::from(tos));
+    }
+    #[cfg(target_os = "linux")]
+    return sock.set_tclass_v6(u32::from(tos));
+    #[cfg(not(target_os = "linux"))]
+    Err(unsupported("IPV6_TCLASS"))
+}
+
+/// Sets `SO_BINDTODEVICE`, restricting the socket to one interface.
+/// Needs `CAP_NET_RAW` on older kernels.
+#[cfg(target_os = "linux")]
+pub(crate) fn bind_device(sock: &SockRef<'_>, device: &str) -> io::Result<()> {
+    sock.bind_device(Some(device.as_bytes()))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn bind_device(_sock: &SockRef<'_>, _device: &str) -> io::Result<()> {
+    Err(unsupported("SO_BINDTODEVICE"))
+}
+
+/// Sets `TCP_QUICKACK`. Linux clears it again as the connection's ACK
+/// pattern settles, so it is best-effort.
+#[cfg(all(feature = "tcp-tokio", target_os = "linux"))]
+pub(crate) fn enable_quickack(sock: &SockRef<'_>) -> io::Result<()> {
+    sock.set_tcp_quickack(true)
+}
+
+#[cfg(all(feature = "tcp-tokio", not(target_os = "linux")))]
+pub(crate) fn enable_quickack(_sock: &SockRef<'_>) -> io::Result<()> {
+    Err(unsupported("TCP_QUICKACK"))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unsupported(option: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{option} is not supported on this platform"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dscp_to_tos() {
+        // Expedited Forwarding.
+        assert_eq!(dscp_to_tos(46), 0xb8);
+        assert_eq!(dscp_to_tos(0), 0);
+        // Out-of-range code points keep their low six bits.
+        assert_eq!(dscp_to_tos(0xff), 0xfc);
+    }
+
+    #[test]
+    fn test_tuning_is_applied() {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let tuning = SocketTuning {
+            recv_buffer_size: Some(64 * 1024),
+            send_buffer_size: Some(64 * 1024),
+            tos: Some(dscp_to_tos(46)),
+            ..SocketTuning::default()
+        };
+        let sock = SockRef::from(&socket);
+        tuning.apply(SockRef::from(&socket)).unwrap();
+        assert!(sock.recv_buffer_size().unwrap() >= 64 * 1024);
+        assert!(sock.send_buffer_size().unwrap() >= 64 * 1024);
+        assert_eq!(sock.tos_v4().unwrap(), 0xb8);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_bind_to_loopback_device() {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let sock = SockRef::from(&socket);
+        // Binding to a device needs CAP_NET_RAW on some kernels.
+        match bind_device(&sock, "lo") {
+            Ok(()) => assert_eq!(sock.device().unwrap().as_deref(), Some(&b"lo"[..])),
+            Err(err) => assert_eq!(err.kind(), io::ErrorKind::PermissionDenied),
+        }
+    }
+}
@@ -1,13 +1,23 @@
 //! TCP client implementation.
 
-use super::framing::SbeFrameCodec;
+use super::framing::{Framing, SbeFrameCodec, write_all_vectored};
+use super::stream::MaybeTlsStream;
+#[cfg(feature = "tls")]
+use super::tls::TlsClientConfig;
 use crate::error::TransportError;
+use crate::limits::FrameLimits;
+use crate::sockopt::{self, SocketTuning, dscp_to_tos};
+use crate::timestamp::{self, RxTimestamp, TimestampMode};
 use crate::traits;
 use bytes::BytesMut;
 use futures::{SinkExt, StreamExt};
+use ironsbe_core::vectored::IoSliceEncoder;
+use socket2::SockRef;
+use std::collections::HashMap;
+use std::io::IoSlice;
 use std::net::SocketAddr;
 use std::time::Duration;
-use tokio::net::TcpStream;
+use tokio::net::{TcpSocket, TcpStream};
 use tokio_util::codec::Framed;
 
 /// Configuration for TCP client.
@@ -19,12 +29,35 @@ pub struct TcpClientConfig {
     pub connect_timeout: Duration,
     /// Maximum frame size in bytes.
     pub max_frame_size: usize,
+    /// Maximum frame sizes for individual template ids, overriding
+    /// [`max_frame_size`](Self::max_frame_size).
+    pub template_max_frame_sizes: HashMap<u16, usize>,
+    /// Append and verify a CRC32C trailer on every frame.
+    pub checksum: bool,
+    /// Header written in front of each message; the peer must match it.
+    pub framing: Framing,
     /// Enable TCP_NODELAY.
     pub tcp_nodelay: bool,
     /// Receive buffer size.
     pub recv_buffer_size: Option<usize>,
     /// Send buffer size.
     pub send_buffer_size: Option<usize>,
+    /// `SO_BUSY_POLL` for the connecting socket: how long reads spin on the device
+    /// queue before sleeping. Linux only.
+    pub busy_poll: Option<Duration>,
+    /// `IP_TOS` (`IPV6_TCLASS` on IPv6) for the connecting socket, e.g. a DSCP marking
+    /// from [`dscp_to_tos`].
+    pub tos: Option<u8>,
+    /// Interface to bind the connecting socket to with `SO_BINDTODEVICE`. Linux only.
+    pub bind_device: Option<String>,
+    /// Enable TCP_QUICKACK on the connecting socket, so ACKs are not delayed. Linux only.
+    pub tcp_quickack: bool,
+    /// Kernel or NIC receive timestamps, read with
+    /// [`TcpClient::rx_timestamp`]; Linux only, and not over TLS.
+    pub timestamping: TimestampMode,
+    /// TLS settings; `None` connects in plain text.
+    #[cfg(feature = "tls")]
+    pub tls: Option<TlsClientConfig>,
 }
 
 impl Default for TcpClientConfig {
@@ -33,9 +66,19 @@ impl Default for TcpClientConfig {
             server_addr: "127.0.0.1:9000".parse().unwrap(),
             connect_timeout: Duration::from_secs(5),
             max_frame_size: 64 * 1024,
+            template_max_frame_sizes: HashMap::new(),
+            checksum: false,
+            framing: Framing::LengthPrefix,
             tcp_nodelay: true,
             recv_buffer_size: Some(256 * 1024),
             send_buffer_size: Some(256 * 1024),
+            busy_poll: None,
+            tos: None,
+            bind_device: None,
+            tcp_quickack: false,
+            timestamping: TimestampMode::Off,
+            #[cfg(feature = "tls")]
+            tls: None,
         }
     }
 }
@@ -71,6 +114,37 @@ impl TcpClientConfig {
         self
     }
 
+    /// Sets the maximum frame size for messages with `template_id`.
+    #[must_use]
+    pub fn template_max_frame_size(mut self, template_id: u16, size: usize) -> Self {
+        self.template_max_frame_sizes.insert(template_id, size);
+        self
+    }
+
+    /// Returns the frame limits enforced on connections.
+    #[must_use]
+    pub fn frame_limits(&self) -> FrameLimits {
+        self.template_max_frame_sizes.iter().fold(
+            FrameLimits::new(self.max_frame_size),
+            |limits, (&template_id, &size)| limits.template(template_id, size),
+        )
+    }
+
+    /// Enables CRC32C frame trailers; the peer must enable them too.
+    #[must_use]
+    pub fn checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
+    }
+
+    /// Sets the frame header, such as [`Framing::sofh`] for exchange
+    /// gateways; the peer must use the same framing.
+    #[must_use]
+    pub fn framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
     /// Sets TCP_NODELAY option.
     #[must_use]
     pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
@@ -91,11 +165,99 @@ impl TcpClientConfig {
         self.send_buffer_size = Some(size);
         self
     }
+
+    /// Sets `SO_BUSY_POLL` for the connecting socket.
+    #[must_use]
+    pub fn busy_poll(mut self, duration: Duration) -> Self {
+        self.busy_poll = Some(duration);
+        self
+    }
+
+    /// Sets `IP_TOS` for the connecting socket.
+    #[must_use]
+    pub fn tos(mut self, tos: u8) -> Self {
+        self.tos = Some(tos);
+        self
+    }
+
+    /// Marks the packets of the connecting socket with the DSCP code point `dscp`.
+    #[must_use]
+    pub fn dscp(self, dscp: u8) -> Self {
+        self.tos(dscp_to_tos(dscp))
+    }
+
+    /// Binds the connecting socket to the interface named `device`.
+    #[must_use]
+    pub fn bind_device(mut self, device: impl Into<String>) -> Self {
+        self.bind_device = Some(device.into());
+        self
+    }
+
+    /// Sets TCP_QUICKACK for the connecting socket.
+    #[must_use]
+    pub fn tcp_quickack(mut self, enabled: bool) -> Self {
+        self.tcp_quickack = enabled;
+        self
+    }
+
+    /// Asks the kernel to timestamp received segments; see
+    /// [`TcpClient::rx_timestamp`].
+    #[must_use]
+    pub fn timestamping(mut self, mode: TimestampMode) -> Self {
+        self.timestamping = mode;
+        self
+    }
+
+    /// Connects over TLS with the given settings.
+    #[cfg(feature = "tls")]
+    #[must_use]
+    pub fn tls(mut self, config: TlsClientConfig) -> Self {
+        self.tls = Some(config);
+        self
+    }
+}
+
+/// Opens a socket with the configured options and connects it.
+pub(crate) async fn open(config: &TcpClientConfig) -> std::io::Result<TcpStream> {
+    let socket = if config.server_addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    // Before connecting, so the handshake already goes through the bound
+    // device and advertises the buffer sizes.
+    SocketTuning {
+        recv_buffer_size: config.recv_buffer_size,
+        send_buffer_size: config.send_buffer_size,
+        busy_poll: config.busy_poll,
+        tos: config.tos,
+        bind_device: config.bind_device.as_deref(),
+    }
+    .apply(SockRef::from(&socket))?;
+    let stream = socket.connect(config.server_addr).await?;
+    stream.set_nodelay(config.tcp_nodelay)?;
+    if config.tcp_quickack {
+        sockopt::enable_quickack(&SockRef::from(&stream))?;
+    }
+    Ok(stream)
+}
+
+/// Runs the TLS handshake on a connected socket if `config` asks for it.
+#[cfg_attr(not(feature = "tls"), allow(unused_variables))]
+pub(crate) async fn secure(
+    stream: TcpStream,
+    config: &TcpClientConfig,
+) -> std::io::Result<MaybeTlsStream> {
+    #[cfg(feature = "tls")]
+    if let Some(tls) = &config.tls {
+        return tls.connect(stream).await;
+    }
+    Ok(MaybeTlsStream::Plain(stream))
 }
 
 /// TCP client for SBE messaging.
 pub struct TcpClient {
-    framed: Framed<TcpStream, SbeFrameCodec>,
+    framed: Framed<MaybeTlsStream, SbeFrameCodec>,
     peer_addr: SocketAddr,
 }
 
@@ -108,21 +270,23 @@ impl TcpClient {
     /// # Errors
     /// Returns `TransportError` if connection fails.
     pub async fn connect(config: TcpClientConfig) -> Result<Self, TransportError> {
-        let stream = tokio::time::timeout(
-            config.connect_timeout,
-            TcpStream::connect(config.server_addr),
-        )
-        .await
-        .map_err(|_| TransportError::ConnectTimeout)?
-        .map_err(TransportError::Io)?;
-
-        // Configure socket
-        stream.set_nodelay(config.tcp_nodelay)?;
-        super::apply_socket_buffer_sizes(&stream, config.recv_buffer_size, config.send_buffer_size)
+        let stream = tokio::time::timeout(config.connect_timeout, open(&config))
+            .await
+            .map_err(|_| TransportError::ConnectTimeout)?
             .map_err(TransportError::Io)?;
+        timestamp::enable(&stream, config.timestamping)?;
 
         let peer_addr = stream.peer_addr()?;
-        let framed = Framed::new(stream, SbeFrameCodec::new(config.max_frame_size));
+        let mut stream = secure(stream, &config).await?;
+        if config.timestamping != TimestampMode::Off {
+            stream = stream.timestamped();
+        }
+        let framed = Framed::new(
+            stream,
+            SbeFrameCodec::with_limits(config.frame_limits())
+                .with_checksum(config.checksum)
+                .with_framing(config.framing),
+        );
 
         Ok(Self { framed, peer_addr })
     }
@@ -138,6 +302,30 @@ impl TcpClient {
         self.framed.send(message).await.map_err(TransportError::Io)
     }
 
+    /// Sends a message assembled from slices without first copying it into
+    /// the frame buffer. The template limit is read from the first part.
+    ///
+    /// # Errors
+    /// Returns `TransportError` if the message exceeds the maximum frame size
+    /// or the write fails.
+    pub async fn send_vectored(
+        &mut self,
+        message: &IoSliceEncoder<'_>,
+    ) -> Result<(), TransportError> {
+        let slices = message.io_slices();
+        let head = slices.first().map_or(&[][..], |slice| &slice[..]);
+        let prefix = self.framed.codec().length_prefix(head, message.len())?;
+        let trailer = self.framed.codec().trailer(&slices);
+        SinkExt::<&[u8]>::flush(&mut self.framed).await?;
+        let mut frame = Vec::with_capacity(2 + slices.len());
+        frame.push(IoSlice::new(&prefix));
+        frame.extend(slices);
+        frame.extend(trailer.as_ref().map(|trailer| IoSlice::new(trailer)));
+        write_all_vectored(self.framed.get_mut(), &mut frame)
+            .await
+            .map_err(TransportError::Io)
+    }
+
     /// Receives a message from the server.
     ///
     /// # Returns
@@ -159,6 +347,23 @@ impl TcpClient {
         self.peer_addr
     }
 
+    /// Returns true if the connection is encrypted.
+    #[must_use]
+    pub fn is_tls(&self) -> bool {
+        self.framed.get_ref().is_tls()
+    }
+
+    /// Returns the kernel or NIC timestamp of the latest read from the
+    /// socket, if [`TcpClientConfig::timestamping`] is enabled.
+    ///
+    /// Read it right after [`recv`](Self::recv): it is the timestamp of
+    /// the read that delivered the end of the message, shared by any other
+    /// messages that arrived in the same read.
+    #[must_use]
+    pub fn rx_timestamp(&self) -> Option<RxTimestamp> {
+        self.framed.get_ref().rx_timestamp()
+    }
+
     /// Closes the connection.
     pub async fn close(mut self) -> Result<(), TransportError> {
         SinkExt::<&[u8]>::close(&mut self.framed)
@@ -178,6 +383,13 @@ impl traits::Connection for TcpClient {
         TcpClient::send(self, msg).await
     }
 
+    async fn send_vectored<'a>(
+        &'a mut self,
+        msg: &'a IoSliceEncoder<'a>,
+    ) -> Result<(), TransportError> {
+        TcpClient::send_vectored(self, msg).await
+    }
+
     fn peer_addr(&self) -> std::io::Result<SocketAddr> {
         Ok(self.peer_addr)
     }
@@ -227,6 +439,55 @@ mod tests {
         assert_eq!(config.max_frame_size, cloned.max_frame_size);
     }
 
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_tcp_client_rx_timestamps() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut framed = Framed::new(socket, SbeFrameCodec::new(1024));
+            // Until the client has a stamped frame and hangs up.
+            for _ in 0..50 {
+                if framed.send(&b"stamped"[..]).await.is_err() {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        });
+
+        let config = TcpClientConfig::new(addr).timestamping(TimestampMode::Software);
+        let mut client = TcpClient::connect(config).await.unwrap();
+        assert!(!client.is_tls());
+        assert_eq!(client.rx_timestamp(), None);
+        let stamp = crate::timestamp::until_stamped(async || {
+            let message = client.recv().await.unwrap().expect("a stamped frame");
+            assert_eq!(&message[..], b"stamped");
+            client.rx_timestamp()
+        })
+        .await
+        .expect("software timestamp");
+        assert_eq!(stamp.source, crate::timestamp::TimestampSource::Software);
+        drop(client);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tcp_client_socket_tuning() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = TcpClientConfig::new(listener.local_addr().unwrap())
+            .dscp(46)
+            .recv_buffer_size(64 * 1024)
+            .tcp_quickack(cfg!(target_os = "linux"));
+        assert_eq!(config.tos, Some(0xb8));
+
+        let stream = open(&config).await.unwrap();
+        let sock = SockRef::from(&stream);
+        assert_eq!(sock.tos_v4().unwrap(), 0xb8);
+        assert!(stream.nodelay().unwrap());
+        assert!(sock.recv_buffer_size().unwrap() >= 64 * 1024);
+    }
+
     #[test]
     fn test_tcp_client_config_debug() {
         let config = TcpClientConfig::default();
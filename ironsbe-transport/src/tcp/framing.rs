@@ -1,15 +1,119 @@
 //! SBE message framing codec for TCP.
 //!
-//! Provides length-prefixed framing for SBE messages over TCP streams.
+//! Provides length-prefixed framing for SBE messages over TCP streams,
+//! either IronSBE's 4-byte length prefix or the FIX Simple Open Framing
+//! Header (SOFH) that exchange gateways expect.
 
+use crate::error::TransportError;
+use crate::limits::{FrameLimits, TEMPLATE_ID_END};
 use bytes::{Buf, BufMut, BytesMut};
+use ironsbe_core::checksum::{self, TRAILER_LENGTH};
+use std::io::IoSlice;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio_util::codec::{Decoder, Encoder};
 
-/// Simple length-prefixed framing codec for SBE messages.
+/// Encoding type announcing little-endian SBE 1.0 in a SOFH.
+pub const SOFH_SBE_LE: u16 = 0xEB50;
+
+/// Encoding type announcing big-endian SBE 1.0 in a SOFH.
+pub const SOFH_SBE_BE: u16 = 0x5BE0;
+
+/// Encoding type CME announces for little-endian SBE in its compact SOFH.
+pub const SOFH_CME_SBE_LE: u16 = 0xCAFE;
+
+/// Length of the Simple Open Framing Header in bytes.
+pub const SOFH_LENGTH: usize = 6;
+
+/// Length of the compact 4-byte SOFH in bytes.
+pub const COMPACT_SOFH_LENGTH: usize = 4;
+
+/// Header written in front of each message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Framing {
+    /// IronSBE's 4-byte little-endian message length.
+    #[default]
+    LengthPrefix,
+    /// FIX Simple Open Framing Header: a 4-byte big-endian length of the
+    /// whole frame, header included, then a 2-byte big-endian encoding
+    /// type. Frames with a different encoding type are rejected.
+    Sofh {
+        /// Encoding type to send and expect, such as [`SOFH_SBE_LE`].
+        encoding_type: u16,
+    },
+    /// Compact 4-byte SOFH, as used by CME iLink 3: a 2-byte little-endian
+    /// length of the whole frame, header included, then a 2-byte
+    /// little-endian encoding type. Frames with a different encoding type
+    /// are rejected.
+    CompactSofh {
+        /// Encoding type to send and expect, such as [`SOFH_CME_SBE_LE`].
+        encoding_type: u16,
+    },
+}
+
+impl Framing {
+    /// SOFH announcing little-endian SBE 1.0.
+    #[must_use]
+    pub const fn sofh() -> Self {
+        Self::Sofh {
+            encoding_type: SOFH_SBE_LE,
+        }
+    }
+
+    /// Compact SOFH announcing CME's little-endian SBE encoding.
+    #[must_use]
+    pub const fn compact_sofh() -> Self {
+        Self::CompactSofh {
+            encoding_type: SOFH_CME_SBE_LE,
+        }
+    }
+
+    /// Returns the header length in bytes.
+    #[must_use]
+    pub const fn header_length(&self) -> usize {
+        match self {
+            Self::LengthPrefix => 4,
+            Self::Sofh { .. } => SOFH_LENGTH,
+            Self::CompactSofh { .. } => COMPACT_SOFH_LENGTH,
+        }
+    }
+}
+
+/// Header bytes for one frame, as returned by
+/// [`SbeFrameCodec::length_prefix`].
+#[derive(Debug, Clone, Copy)]
+pub struct FramePrefix {
+    bytes: [u8; SOFH_LENGTH],
+    len: usize,
+}
+
+impl std::ops::Deref for FramePrefix {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+/// Framing codec for SBE messages.
 ///
-/// Frame format: `[4-byte length (little-endian)][SBE message]`
+/// Frame format: `[header][SBE message]`, where the header is a 4-byte
+/// little-endian length by default, or a SOFH with
+/// [`with_framing`](Self::with_framing). Both peers must use the same
+/// framing.
+///
+/// With [`with_checksum`](Self::with_checksum), every frame also carries a
+/// 4-byte CRC32C trailer after the message, not counted in the length:
+/// `[header][SBE message][4-byte CRC32C]`. Both peers must agree on the
+/// setting. The trailer is an IronSBE extension that other SOFH peers do
+/// not expect.
+///
+/// Size and checksum violations surface as `InvalidData` errors wrapping a
+/// [`TransportError`], which callers can recover with
+/// `std::io::Error::get_ref` and `downcast_ref`.
 pub struct SbeFrameCodec {
-    max_frame_size: usize,
+    limits: FrameLimits,
+    checksum: bool,
+    framing: Framing,
 }
 
 impl SbeFrameCodec {
@@ -19,14 +123,160 @@ impl SbeFrameCodec {
     /// * `max_frame_size` - Maximum allowed frame size in bytes
     #[must_use]
     pub fn new(max_frame_size: usize) -> Self {
-        Self { max_frame_size }
+        Self::with_limits(FrameLimits::new(max_frame_size))
+    }
+
+    /// Creates a frame codec enforcing per-template limits.
+    #[must_use]
+    pub fn with_limits(limits: FrameLimits) -> Self {
+        Self {
+            limits,
+            checksum: false,
+            framing: Framing::LengthPrefix,
+        }
+    }
+
+    /// Sets the header written in front of each message.
+    #[must_use]
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Returns the header written in front of each message.
+    #[must_use]
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Enables or disables CRC32C frame trailers.
+    #[must_use]
+    pub fn with_checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
+    }
+
+    /// Returns true if frames carry a CRC32C trailer.
+    #[must_use]
+    pub fn checksum(&self) -> bool {
+        self.checksum
     }
 
     /// Returns the maximum frame size.
     #[must_use]
     pub fn max_frame_size(&self) -> usize {
-        self.max_frame_size
+        self.limits.max_frame_size()
+    }
+
+    /// Returns the frame size limits.
+    #[must_use]
+    pub fn limits(&self) -> &FrameLimits {
+        &self.limits
     }
+
+    /// Returns the header for a frame of `len` bytes whose message starts
+    /// with `head`.
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if `len` exceeds the limit for the
+    /// message's template, or does not fit a compact SOFH.
+    pub fn length_prefix(&self, head: &[u8], len: usize) -> std::io::Result<FramePrefix> {
+        self.limits.check(len, head).map_err(invalid_data)?;
+        let mut bytes = [0u8; SOFH_LENGTH];
+        match self.framing {
+            Framing::LengthPrefix => bytes[..4].copy_from_slice(&(len as u32).to_le_bytes()),
+            Framing::Sofh { encoding_type } => {
+                bytes[..4].copy_from_slice(&((len + SOFH_LENGTH) as u32).to_be_bytes());
+                bytes[4..].copy_from_slice(&encoding_type.to_be_bytes());
+            }
+            Framing::CompactSofh { encoding_type } => {
+                let frame_len = u16::try_from(len + COMPACT_SOFH_LENGTH).map_err(|_| {
+                    invalid_data(TransportError::invalid_frame(format!(
+                        "message of {len} bytes does not fit a compact SOFH"
+                    )))
+                })?;
+                bytes[..2].copy_from_slice(&frame_len.to_le_bytes());
+                bytes[2..4].copy_from_slice(&encoding_type.to_le_bytes());
+            }
+        }
+        Ok(FramePrefix {
+            bytes,
+            len: self.framing.header_length(),
+        })
+    }
+
+    /// Reads the message length from a complete frame header.
+    fn message_length(&self, header: &[u8]) -> std::io::Result<usize> {
+        let length = [header[0], header[1], header[2], header[3]];
+        match self.framing {
+            Framing::LengthPrefix => Ok(u32::from_le_bytes(length) as usize),
+            Framing::Sofh { encoding_type } => {
+                let received = u16::from_be_bytes([header[4], header[5]]);
+                if received != encoding_type {
+                    return Err(invalid_data(TransportError::invalid_frame(format!(
+                        "SOFH encoding type {received:#06x}, expected {encoding_type:#06x}"
+                    ))));
+                }
+                (u32::from_be_bytes(length) as usize)
+                    .checked_sub(SOFH_LENGTH)
+                    .ok_or_else(|| {
+                        invalid_data(TransportError::invalid_frame(
+                            "SOFH length shorter than the header",
+                        ))
+                    })
+            }
+            Framing::CompactSofh { encoding_type } => {
+                let received = u16::from_le_bytes([header[2], header[3]]);
+                if received != encoding_type {
+                    return Err(invalid_data(TransportError::invalid_frame(format!(
+                        "SOFH encoding type {received:#06x}, expected {encoding_type:#06x}"
+                    ))));
+                }
+                usize::from(u16::from_le_bytes([header[0], header[1]]))
+                    .checked_sub(COMPACT_SOFH_LENGTH)
+                    .ok_or_else(|| {
+                        invalid_data(TransportError::invalid_frame(
+                            "SOFH length shorter than the header",
+                        ))
+                    })
+            }
+        }
+    }
+
+    /// Returns the trailer for a message made of `parts`, or `None` if
+    /// checksums are disabled.
+    pub(crate) fn trailer(&self, parts: &[IoSlice<'_>]) -> Option<[u8; TRAILER_LENGTH]> {
+        self.checksum
+            .then(|| checksum::trailer_parts(parts.iter().map(|part| &part[..])))
+    }
+
+    /// Returns the number of bytes following the message in each frame.
+    fn trailer_length(&self) -> usize {
+        if self.checksum { TRAILER_LENGTH } else { 0 }
+    }
+}
+
+/// Wraps a size or checksum violation for the codec's `io::Error` channel.
+fn invalid_data(err: TransportError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+}
+
+/// Writes every byte of `slices` to `writer`, retrying short vectored
+/// writes.
+pub(crate) async fn write_all_vectored<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    mut slices: &mut [IoSlice<'_>],
+) -> std::io::Result<()> {
+    IoSlice::advance_slices(&mut slices, 0);
+    while !slices.is_empty() {
+        match writer.write_vectored(slices).await {
+            Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+            Ok(written) => IoSlice::advance_slices(&mut slices, written),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
 }
 
 impl Default for SbeFrameCodec {
@@ -40,37 +290,46 @@ impl Decoder for SbeFrameCodec {
     type Error = std::io::Error;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        // Need at least 4 bytes for length prefix
-        if src.len() < 4 {
+        // Need the whole header
+        let header_length = self.framing.header_length();
+        if src.len() < header_length {
             return Ok(None);
         }
 
-        // Read length (little-endian)
-        let length = u32::from_le_bytes([src[0], src[1], src[2], src[3]]) as usize;
+        let length = self.message_length(&src[..header_length])?;
 
         // Validate frame size
-        if length > self.max_frame_size {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!(
-                    "frame too large: {} bytes exceeds maximum {} bytes",
-                    length, self.max_frame_size
-                ),
-            ));
+        self.limits.check_length(length).map_err(invalid_data)?;
+        if self.limits.has_template_limits() {
+            // Wait for the template id before reserving the frame.
+            let head_end = header_length + length.min(TEMPLATE_ID_END);
+            if src.len() < head_end {
+                return Ok(None);
+            }
+            self.limits
+                .check(length, &src[header_length..head_end])
+                .map_err(invalid_data)?;
         }
 
         // Check if we have the complete frame
-        if src.len() < 4 + length {
+        let frame_length = header_length + length + self.trailer_length();
+        if src.len() < frame_length {
             // Reserve space for the rest of the frame
-            src.reserve(4 + length - src.len());
+            src.reserve(frame_length - src.len());
             return Ok(None);
         }
 
-        // Skip the length prefix
-        src.advance(4);
+        // Skip the header
+        src.advance(header_length);
 
         // Extract the frame
-        Ok(Some(src.split_to(length)))
+        let frame = src.split_to(length);
+        if self.checksum {
+            let trailer = [src[0], src[1], src[2], src[3]];
+            src.advance(TRAILER_LENGTH);
+            checksum::verify_trailer(&frame, trailer).map_err(|err| invalid_data(err.into()))?;
+        }
+        Ok(Some(frame))
     }
 }
 
@@ -79,26 +338,22 @@ impl Encoder<&[u8]> for SbeFrameCodec {
 
     fn encode(&mut self, item: &[u8], dst: &mut BytesMut) -> Result<(), Self::Error> {
         // Validate frame size
-        if item.len() > self.max_frame_size {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!(
-                    "frame too large: {} bytes exceeds maximum {} bytes",
-                    item.len(),
-                    self.max_frame_size
-                ),
-            ));
-        }
+        let prefix = self.length_prefix(item, item.len())?;
 
         // Reserve space
-        dst.reserve(4 + item.len());
+        dst.reserve(prefix.len() + item.len() + self.trailer_length());
 
-        // Write length prefix (little-endian)
-        dst.put_u32_le(item.len() as u32);
+        // Write header
+        dst.put_slice(&prefix);
 
         // Write frame data
         dst.put_slice(item);
 
+        // Write checksum trailer
+        if self.checksum {
+            dst.put_slice(&checksum::trailer(item));
+        }
+
         Ok(())
     }
 }
@@ -189,6 +444,140 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_template_limits() {
+        let mut codec = SbeFrameCodec::with_limits(FrameLimits::new(64).template(1, 16));
+        let mut buf = BytesMut::new();
+
+        // Header of a 32-byte template 1 message, before its body arrives.
+        buf.put_u32_le(32);
+        buf.put_slice(&[8, 0]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        buf.put_slice(&[1, 0]);
+        let err = codec.decode(&mut buf).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<TransportError>();
+        assert!(matches!(
+            err,
+            Some(TransportError::TemplateFrameTooLarge {
+                template_id: 1,
+                size: 32,
+                max: 16,
+            })
+        ));
+
+        // Other templates keep the default.
+        let message = [8u8, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let mut buf = BytesMut::new();
+        codec.encode(&message[..], &mut buf).unwrap();
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap().len(), 20);
+
+        let mut oversized = message;
+        oversized[2] = 1;
+        let mut buf = BytesMut::new();
+        let mut codec = SbeFrameCodec::with_limits(FrameLimits::new(64).template(1, 8));
+        assert!(codec.encode(&oversized[..], &mut buf).is_err());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_checksum_trailer() {
+        let mut codec = SbeFrameCodec::new(1024).with_checksum(true);
+        let mut buf = BytesMut::new();
+
+        codec.encode(b"frame1".as_slice(), &mut buf).unwrap();
+        assert_eq!(buf.len(), 4 + 6 + TRAILER_LENGTH);
+        assert_eq!(&buf[10..], checksum::trailer(b"frame1"));
+
+        // The trailer is awaited like the rest of the frame.
+        let mut partial = buf.split_to(12);
+        assert!(codec.decode(&mut partial).unwrap().is_none());
+        partial.unsplit(buf);
+        assert_eq!(&codec.decode(&mut partial).unwrap().unwrap()[..], b"frame1");
+        assert!(partial.is_empty());
+
+        codec.encode(b"frame2".as_slice(), &mut partial).unwrap();
+        partial[5] ^= 0x20;
+        let err = codec.decode(&mut partial).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<TransportError>();
+        assert!(matches!(
+            err,
+            Some(TransportError::Decode(
+                ironsbe_core::DecodeError::ChecksumMismatch { .. }
+            ))
+        ));
+        // The corrupted frame is consumed.
+        assert!(partial.is_empty());
+    }
+
+    #[test]
+    fn test_sofh_framing() {
+        let mut codec = SbeFrameCodec::new(1024).with_framing(Framing::sofh());
+        let mut buf = BytesMut::new();
+
+        codec.encode(b"frame1".as_slice(), &mut buf).unwrap();
+        assert_eq!(&buf[..SOFH_LENGTH], &[0, 0, 0, 12, 0xEB, 0x50]);
+        assert_eq!(&buf[SOFH_LENGTH..], b"frame1");
+
+        // The header is awaited in full.
+        let mut partial = buf.split_to(5);
+        assert!(codec.decode(&mut partial).unwrap().is_none());
+        partial.unsplit(buf);
+        assert_eq!(&codec.decode(&mut partial).unwrap().unwrap()[..], b"frame1");
+        assert!(partial.is_empty());
+
+        // A frame announcing another encoding is rejected.
+        let mut other = BytesMut::from(&[0u8, 0, 0, 7, 0x5B, 0xE0, 1][..]);
+        let err = codec.decode(&mut other).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<TransportError>();
+        assert!(matches!(err, Some(TransportError::InvalidFrame { .. })));
+
+        // So is a length that does not cover the header.
+        let mut short = BytesMut::from(&[0u8, 0, 0, 5, 0xEB, 0x50][..]);
+        assert!(codec.decode(&mut short).is_err());
+
+        // Limits apply to the message, not the header.
+        let mut codec = SbeFrameCodec::new(6).with_framing(Framing::Sofh {
+            encoding_type: SOFH_SBE_BE,
+        });
+        let mut buf = BytesMut::new();
+        codec.encode(b"frame2".as_slice(), &mut buf).unwrap();
+        assert_eq!(&buf[4..SOFH_LENGTH], &[0x5B, 0xE0]);
+        assert_eq!(&codec.decode(&mut buf).unwrap().unwrap()[..], b"frame2");
+        assert!(codec.encode(b"frame23".as_slice(), &mut buf).is_err());
+    }
+
+    #[test]
+    fn test_compact_sofh_framing() {
+        let mut codec = SbeFrameCodec::new(1024).with_framing(Framing::compact_sofh());
+        let mut buf = BytesMut::new();
+
+        codec.encode(b"frame1".as_slice(), &mut buf).unwrap();
+        assert_eq!(&buf[..COMPACT_SOFH_LENGTH], &[10, 0, 0xFE, 0xCA]);
+        assert_eq!(&buf[COMPACT_SOFH_LENGTH..], b"frame1");
+
+        let mut partial = buf.split_to(3);
+        assert!(codec.decode(&mut partial).unwrap().is_none());
+        partial.unsplit(buf);
+        assert_eq!(&codec.decode(&mut partial).unwrap().unwrap()[..], b"frame1");
+        assert!(partial.is_empty());
+
+        // A frame announcing another encoding is rejected.
+        let mut other = BytesMut::from(&[5u8, 0, 0x50, 0xEB, 1][..]);
+        let err = codec.decode(&mut other).unwrap_err();
+        let err = err.get_ref().unwrap().downcast_ref::<TransportError>();
+        assert!(matches!(err, Some(TransportError::InvalidFrame { .. })));
+
+        // So is a length that does not cover the header.
+        let mut short = BytesMut::from(&[3u8, 0, 0xFE, 0xCA][..]);
+        assert!(codec.decode(&mut short).is_err());
+
+        // Messages must fit the 2-byte length.
+        let mut codec =
+            SbeFrameCodec::new(usize::from(u16::MAX)).with_framing(Framing::compact_sofh());
+        let big = vec![0u8; usize::from(u16::MAX) - COMPACT_SOFH_LENGTH + 1];
+        assert!(codec.encode(big.as_slice(), &mut BytesMut::new()).is_err());
+    }
+
     #[test]
     fn test_multiple_frames() {
         let mut codec = SbeFrameCodec::new(1024);
@@ -3,49 +3,29 @@
 //! Provides Tokio-based TCP client and server implementations with SBE message
 //! framing.  The [`TokioTcpTransport`] type implements [`crate::Transport`]
 //! and is the default backend when the `tcp-tokio` feature is enabled.
+//! With the `tls` feature, connections can run over TLS; see [`tls`].
 
 use crate::traits;
-use socket2::SockRef;
-use tokio::net::TcpStream;
 
 pub mod client;
 pub mod framing;
 pub mod server;
+pub mod stream;
+#[cfg(feature = "tls")]
+pub mod tls;
 
 pub use client::{TcpClient, TcpClientConfig};
-pub use framing::SbeFrameCodec;
+pub use framing::{Framing, SbeFrameCodec};
 pub use server::{TcpConnection, TcpServer, TcpServerConfig};
-
-/// Applies optional `SO_RCVBUF` / `SO_SNDBUF` to a borrowed TCP stream.
-///
-/// `recv` / `send` are interpreted as **requested** sizes in bytes.  The
-/// kernel may clamp the value, and on Linux the value reported by
-/// `getsockopt` will typically be double what was set.  Both options are
-/// best-effort, but I/O errors are propagated so callers can detect a
-/// completely broken socket.
-///
-/// # Errors
-/// Returns the underlying I/O error if `setsockopt` fails.
-pub(crate) fn apply_socket_buffer_sizes(
-    stream: &TcpStream,
-    recv: Option<usize>,
-    send: Option<usize>,
-) -> std::io::Result<()> {
-    let sock = SockRef::from(stream);
-    if let Some(size) = recv {
-        sock.set_recv_buffer_size(size)?;
-    }
-    if let Some(size) = send {
-        sock.set_send_buffer_size(size)?;
-    }
-    Ok(())
-}
+pub use stream::MaybeTlsStream;
+#[cfg(feature = "tls")]
+pub use tls::{TlsClientConfig, TlsServerConfig};
 
 /// Tokio-based TCP transport backend.
 ///
 /// This is the default [`Transport`](crate::Transport) implementation.
-/// Connections are framed with a 4-byte little-endian length prefix using
-/// [`SbeFrameCodec`].
+/// Connections are framed with a 4-byte little-endian length prefix, or a
+/// SOFH if configured, using [`SbeFrameCodec`].
 ///
 /// # Server usage
 ///
@@ -73,17 +53,16 @@ impl traits::Transport for TokioTcpTransport {
     }
 
     async fn connect_with(config: TcpClientConfig) -> Result<TcpConnection, std::io::Error> {
-        let stream = tokio::time::timeout(
-            config.connect_timeout,
-            tokio::net::TcpStream::connect(config.server_addr),
-        )
-        .await
-        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "connect timeout"))??;
-        stream.set_nodelay(config.tcp_nodelay)?;
-        apply_socket_buffer_sizes(&stream, config.recv_buffer_size, config.send_buffer_size)?;
+        let stream = tokio::time::timeout(config.connect_timeout, client::open(&config))
+            .await
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "connect timeout"))??;
         let peer_addr = stream.peer_addr()?;
-        let framed =
-            tokio_util::codec::Framed::new(stream, SbeFrameCodec::new(config.max_frame_size));
-        Ok(TcpConnection::from_framed(framed, peer_addr))
+        let framed = tokio_util::codec::Framed::new(
+            client::secure(stream, &config).await?,
+            SbeFrameCodec::with_limits(config.frame_limits())
+                .with_checksum(config.checksum)
+                .with_framing(config.framing),
+        );
+        Ok(TcpConnection::new(framed, peer_addr))
     }
 }
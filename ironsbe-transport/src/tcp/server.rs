@@ -1,13 +1,25 @@
 //! TCP server implementation.
 
-use super::framing::SbeFrameCodec;
+use super::framing::{Framing, SbeFrameCodec, write_all_vectored};
+use super::stream::MaybeTlsStream;
+#[cfg(feature = "tls")]
+use super::tls::{Acceptor, TlsServerConfig};
+use crate::limits::FrameLimits;
+use crate::sockopt::{self, SocketTuning, dscp_to_tos};
 use crate::traits;
 use bytes::BytesMut;
+#[cfg(feature = "tls")]
+use futures::stream::FuturesUnordered;
 use futures::{SinkExt, StreamExt};
+use ironsbe_core::vectored::IoSliceEncoder;
+use socket2::SockRef;
+use std::collections::HashMap;
+use std::io::IoSlice;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::net::{TcpListener, TcpStream};
-use tokio_util::codec::Framed;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
+use tokio_util::codec::{Framed, FramedParts};
 
 /// Configuration for TCP server.
 #[derive(Debug, Clone)]
@@ -18,6 +30,13 @@ pub struct TcpServerConfig {
     pub max_connections: usize,
     /// Maximum frame size in bytes.
     pub max_frame_size: usize,
+    /// Maximum frame sizes for individual template ids, overriding
+    /// [`max_frame_size`](Self::max_frame_size).
+    pub template_max_frame_sizes: HashMap<u16, usize>,
+    /// Append and verify a CRC32C trailer on every frame.
+    pub checksum: bool,
+    /// Header written in front of each message; the peer must match it.
+    pub framing: Framing,
     /// Enable TCP_NODELAY.
     pub tcp_nodelay: bool,
     /// `SO_RCVBUF` to apply to accepted sockets, in bytes.
@@ -29,6 +48,20 @@ pub struct TcpServerConfig {
     ///
     /// Same caveats as [`recv_buffer_size`](Self::recv_buffer_size).
     pub send_buffer_size: Option<usize>,
+    /// `SO_BUSY_POLL` for accepted sockets: how long reads spin on the device
+    /// queue before sleeping. Linux only.
+    pub busy_poll: Option<Duration>,
+    /// `IP_TOS` (`IPV6_TCLASS` on IPv6) for accepted sockets, e.g. a DSCP marking
+    /// from [`dscp_to_tos`].
+    pub tos: Option<u8>,
+    /// Interface to bind the listening socket to with `SO_BINDTODEVICE`, so
+    /// only connections arriving on it are accepted. Linux only.
+    pub bind_device: Option<String>,
+    /// Enable TCP_QUICKACK on accepted sockets, so ACKs are not delayed. Linux only.
+    pub tcp_quickack: bool,
+    /// TLS settings; `None` accepts plain-text connections.
+    #[cfg(feature = "tls")]
+    pub tls: Option<TlsServerConfig>,
 }
 
 impl Default for TcpServerConfig {
@@ -37,9 +70,18 @@ impl Default for TcpServerConfig {
             bind_addr: "0.0.0.0:9000".parse().unwrap(),
             max_connections: 1000,
             max_frame_size: 64 * 1024,
+            template_max_frame_sizes: HashMap::new(),
+            checksum: false,
+            framing: Framing::LengthPrefix,
             tcp_nodelay: true,
             recv_buffer_size: Some(256 * 1024),
             send_buffer_size: Some(256 * 1024),
+            busy_poll: None,
+            tos: None,
+            bind_device: None,
+            tcp_quickack: false,
+            #[cfg(feature = "tls")]
+            tls: None,
         }
     }
 }
@@ -75,6 +117,37 @@ impl TcpServerConfig {
         self
     }
 
+    /// Sets the maximum frame size for messages with `template_id`.
+    #[must_use]
+    pub fn template_max_frame_size(mut self, template_id: u16, size: usize) -> Self {
+        self.template_max_frame_sizes.insert(template_id, size);
+        self
+    }
+
+    /// Returns the frame limits enforced on connections.
+    #[must_use]
+    pub fn frame_limits(&self) -> FrameLimits {
+        self.template_max_frame_sizes.iter().fold(
+            FrameLimits::new(self.max_frame_size),
+            |limits, (&template_id, &size)| limits.template(template_id, size),
+        )
+    }
+
+    /// Enables CRC32C frame trailers; the peer must enable them too.
+    #[must_use]
+    pub fn checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
+    }
+
+    /// Sets the frame header, such as [`Framing::sofh`] for exchange
+    /// gateways; the peer must use the same framing.
+    #[must_use]
+    pub fn framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
     /// Sets TCP_NODELAY.
     #[must_use]
     pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
@@ -95,12 +168,67 @@ impl TcpServerConfig {
         self.send_buffer_size = Some(size);
         self
     }
+
+    /// Sets `SO_BUSY_POLL` for accepted sockets.
+    #[must_use]
+    pub fn busy_poll(mut self, duration: Duration) -> Self {
+        self.busy_poll = Some(duration);
+        self
+    }
+
+    /// Sets `IP_TOS` for accepted sockets.
+    #[must_use]
+    pub fn tos(mut self, tos: u8) -> Self {
+        self.tos = Some(tos);
+        self
+    }
+
+    /// Marks the packets of accepted sockets with the DSCP code point `dscp`.
+    #[must_use]
+    pub fn dscp(self, dscp: u8) -> Self {
+        self.tos(dscp_to_tos(dscp))
+    }
+
+    /// Binds the listening socket to the interface named `device`.
+    #[must_use]
+    pub fn bind_device(mut self, device: impl Into<String>) -> Self {
+        self.bind_device = Some(device.into());
+        self
+    }
+
+    /// Sets TCP_QUICKACK for accepted sockets.
+    #[must_use]
+    pub fn tcp_quickack(mut self, enabled: bool) -> Self {
+        self.tcp_quickack = enabled;
+        self
+    }
+
+    /// Accepts only TLS connections, with the given settings.
+    #[cfg(feature = "tls")]
+    #[must_use]
+    pub fn tls(mut self, config: TlsServerConfig) -> Self {
+        self.tls = Some(config);
+        self
+    }
 }
 
+/// A TLS handshake in progress.
+#[cfg(feature = "tls")]
+type Handshake = std::pin::Pin<
+    Box<dyn Future<Output = std::io::Result<(MaybeTlsStream, BytesMut, SocketAddr)>> + Send>,
+>;
+
 /// TCP server for SBE messaging.
+///
+/// With TLS, handshakes run concurrently inside [`accept`](Self::accept),
+/// so a client that stalls its handshake does not hold up the others.
 pub struct TcpServer {
     listener: TcpListener,
     config: Arc<TcpServerConfig>,
+    #[cfg(feature = "tls")]
+    acceptor: Option<Acceptor>,
+    #[cfg(feature = "tls")]
+    handshakes: FuturesUnordered<Handshake>,
 }
 
 impl TcpServer {
@@ -110,12 +238,36 @@ impl TcpServer {
     /// * `config` - Server configuration
     ///
     /// # Errors
-    /// Returns IO error if binding fails.
+    /// Returns IO error if binding fails, or if the TLS certificate or key
+    /// is invalid.
     pub async fn bind(config: TcpServerConfig) -> std::io::Result<Self> {
-        let listener = TcpListener::bind(config.bind_addr).await?;
+        #[cfg(feature = "tls")]
+        let acceptor = config
+            .tls
+            .as_ref()
+            .map(TlsServerConfig::acceptor)
+            .transpose()?;
+        let listener = match &config.bind_device {
+            Some(device) => {
+                let socket = if config.bind_addr.is_ipv4() {
+                    TcpSocket::new_v4()?
+                } else {
+                    TcpSocket::new_v6()?
+                };
+                socket.set_reuseaddr(true)?;
+                sockopt::bind_device(&SockRef::from(&socket), device)?;
+                socket.bind(config.bind_addr)?;
+                socket.listen(1024)?
+            }
+            None => TcpListener::bind(config.bind_addr).await?,
+        };
         Ok(Self {
             listener,
             config: Arc::new(config),
+            #[cfg(feature = "tls")]
+            acceptor,
+            #[cfg(feature = "tls")]
+            handshakes: FuturesUnordered::new(),
         })
     }
 
@@ -125,20 +277,69 @@ impl TcpServer {
     /// A new `TcpConnection` for the accepted client.
     ///
     /// # Errors
-    /// Returns IO error if accept fails.
+    /// Returns IO error if accept fails, or if a TLS handshake fails or
+    /// times out.
     pub async fn accept(&mut self) -> std::io::Result<TcpConnection> {
+        #[cfg(feature = "tls")]
+        if let Some(acceptor) = &self.acceptor {
+            loop {
+                tokio::select! {
+                    Some(done) = self.handshakes.next() => {
+                        let (stream, early, addr) = done?;
+                        return Ok(self.connection(stream, early, addr));
+                    }
+                    accepted = self.listener.accept() => {
+                        let (stream, addr) = accepted?;
+                        self.configure(&stream)?;
+                        let acceptor = acceptor.clone();
+                        self.handshakes.push(Box::pin(async move {
+                            let (stream, early) = acceptor.accept(stream).await?;
+                            Ok((stream, early, addr))
+                        }));
+                    }
+                }
+            }
+        }
+
         let (stream, addr) = self.listener.accept().await?;
+        self.configure(&stream)?;
+        Ok(self.connection(MaybeTlsStream::Plain(stream), BytesMut::new(), addr))
+    }
+
+    /// Applies the socket options to an accepted stream.
+    fn configure(&self, stream: &TcpStream) -> std::io::Result<()> {
         stream.set_nodelay(self.config.tcp_nodelay)?;
-        super::apply_socket_buffer_sizes(
-            &stream,
-            self.config.recv_buffer_size,
-            self.config.send_buffer_size,
-        )?;
-
-        Ok(TcpConnection {
-            framed: Framed::new(stream, SbeFrameCodec::new(self.config.max_frame_size)),
-            peer_addr: addr,
-        })
+        // The device is inherited from the listening socket.
+        SocketTuning {
+            recv_buffer_size: self.config.recv_buffer_size,
+            send_buffer_size: self.config.send_buffer_size,
+            busy_poll: self.config.busy_poll,
+            tos: self.config.tos,
+            bind_device: None,
+        }
+        .apply(SockRef::from(stream))?;
+        if self.config.tcp_quickack {
+            sockopt::enable_quickack(&SockRef::from(stream))?;
+        }
+        Ok(())
+    }
+
+    /// Frames an accepted stream, decoding `early`, the TLS early data,
+    /// before anything read from it.
+    fn connection(
+        &self,
+        stream: MaybeTlsStream,
+        early: BytesMut,
+        peer_addr: SocketAddr,
+    ) -> TcpConnection {
+        let mut parts = FramedParts::new::<&[u8]>(
+            stream,
+            SbeFrameCodec::with_limits(self.config.frame_limits())
+                .with_checksum(self.config.checksum)
+                .with_framing(self.config.framing),
+        );
+        parts.read_buf = early;
+        TcpConnection::new(Framed::from_parts(parts), peer_addr)
     }
 
     /// Returns the local address the server is bound to.
@@ -149,15 +350,25 @@ impl TcpServer {
 
 /// A TCP connection to a client.
 pub struct TcpConnection {
-    framed: Framed<TcpStream, SbeFrameCodec>,
+    framed: Framed<MaybeTlsStream, SbeFrameCodec>,
     peer_addr: SocketAddr,
 }
 
 impl TcpConnection {
+    /// Creates a `TcpConnection` from a framed plain or TLS stream.
+    #[must_use]
+    pub fn new(framed: Framed<MaybeTlsStream, SbeFrameCodec>, peer_addr: SocketAddr) -> Self {
+        Self { framed, peer_addr }
+    }
+
     /// Creates a `TcpConnection` from an already-framed stream.
     #[must_use]
     pub fn from_framed(framed: Framed<TcpStream, SbeFrameCodec>, peer_addr: SocketAddr) -> Self {
-        Self { framed, peer_addr }
+        let parts = framed.into_parts();
+        let mut plain = FramedParts::new::<&[u8]>(MaybeTlsStream::Plain(parts.io), parts.codec);
+        plain.read_buf = parts.read_buf;
+        plain.write_buf = parts.write_buf;
+        Self::new(Framed::from_parts(plain), peer_addr)
     }
 
     /// Returns the peer address.
@@ -166,6 +377,12 @@ impl TcpConnection {
         self.peer_addr
     }
 
+    /// Returns true if the connection is encrypted.
+    #[must_use]
+    pub fn is_tls(&self) -> bool {
+        self.framed.get_ref().is_tls()
+    }
+
     /// Sends a message to the client.
     ///
     /// # Arguments
@@ -177,6 +394,27 @@ impl TcpConnection {
         self.framed.send(message).await
     }
 
+    /// Sends a message assembled from slices without first copying it into
+    /// the frame buffer; the length prefix and every part go to the socket
+    /// in vectored writes. The template limit is read from the first part.
+    ///
+    /// # Errors
+    /// Returns IO error if the message exceeds the maximum frame size or the
+    /// write fails.
+    pub async fn send_vectored(&mut self, message: &IoSliceEncoder<'_>) -> std::io::Result<()> {
+        let slices = message.io_slices();
+        let head = slices.first().map_or(&[][..], |slice| &slice[..]);
+        let prefix = self.framed.codec().length_prefix(head, message.len())?;
+        let trailer = self.framed.codec().trailer(&slices);
+        // Frames queued through `send` must reach the socket first.
+        SinkExt::<&[u8]>::flush(&mut self.framed).await?;
+        let mut frame = Vec::with_capacity(2 + slices.len());
+        frame.push(IoSlice::new(&prefix));
+        frame.extend(slices);
+        frame.extend(trailer.as_ref().map(|trailer| IoSlice::new(trailer)));
+        write_all_vectored(self.framed.get_mut(), &mut frame).await
+    }
+
     /// Receives a message from the client.
     ///
     /// # Returns
@@ -222,6 +460,13 @@ impl traits::Connection for TcpConnection {
         TcpConnection::send(self, msg).await
     }
 
+    async fn send_vectored<'a>(
+        &'a mut self,
+        msg: &'a IoSliceEncoder<'a>,
+    ) -> Result<(), std::io::Error> {
+        TcpConnection::send_vectored(self, msg).await
+    }
+
     fn peer_addr(&self) -> std::io::Result<SocketAddr> {
         Ok(self.peer_addr)
     }
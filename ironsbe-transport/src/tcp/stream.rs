@@ -0,0 +1,199 @@
+//! Byte stream under a framed TCP connection, plain or TLS.
+
+use crate::timestamp::RxTimestamp;
+use std::io::{self, IoSlice};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+
+/// A TCP stream, optionally wrapped in TLS.
+///
+/// [`TcpConnection`](super::TcpConnection) and [`TcpClient`](super::TcpClient)
+/// run the SBE frame codec over this, so framing, checksums and vectored
+/// sends work the same with and without TLS.
+#[derive(Debug)]
+pub enum MaybeTlsStream {
+    /// Unencrypted stream.
+    Plain(TcpStream),
+    /// Unencrypted stream read with `recvmsg`, keeping the kernel or NIC
+    /// receive timestamp of the latest read.
+    #[cfg(target_os = "linux")]
+    Timestamped {
+        /// The connected socket, with timestamping enabled.
+        stream: TcpStream,
+        /// Receive timestamp of the latest read that carried one.
+        rx_timestamp: Option<RxTimestamp>,
+    },
+    /// Stream after a completed TLS handshake, either side.
+    #[cfg(feature = "tls")]
+    Tls(Box<tokio_rustls::TlsStream<TcpStream>>),
+}
+
+impl MaybeTlsStream {
+    /// Returns the underlying TCP stream.
+    #[must_use]
+    pub fn tcp(&self) -> &TcpStream {
+        match self {
+            Self::Plain(stream) => stream,
+            #[cfg(target_os = "linux")]
+            Self::Timestamped { stream, .. } => stream,
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => stream.get_ref().0,
+        }
+    }
+
+    /// Returns true if the stream is encrypted.
+    #[must_use]
+    pub fn is_tls(&self) -> bool {
+        let plain = matches!(self, Self::Plain(_));
+        #[cfg(target_os = "linux")]
+        let plain = plain || matches!(self, Self::Timestamped { .. });
+        !plain
+    }
+
+    /// Returns the kernel or NIC timestamp of the latest read, if the
+    /// stream was made with receive timestamps.
+    ///
+    /// TCP coalesces segments, so one read, and every frame decoded from
+    /// it, may span several packets; the timestamp is that of the last.
+    #[must_use]
+    pub fn rx_timestamp(&self) -> Option<RxTimestamp> {
+        match self {
+            #[cfg(target_os = "linux")]
+            Self::Timestamped { rx_timestamp, .. } => *rx_timestamp,
+            _ => None,
+        }
+    }
+
+    /// Reads a plain stream with `recvmsg` from now on, so that
+    /// [`rx_timestamp`](Self::rx_timestamp) is kept up to date. The
+    /// socket must have timestamping enabled; TLS streams are returned
+    /// unchanged.
+    #[must_use]
+    pub(crate) fn timestamped(self) -> Self {
+        match self {
+            #[cfg(target_os = "linux")]
+            Self::Plain(stream) => Self::Timestamped {
+                stream,
+                rx_timestamp: None,
+            },
+            other => other,
+        }
+    }
+}
+
+impl From<TcpStream> for MaybeTlsStream {
+    fn from(stream: TcpStream) -> Self {
+        Self::Plain(stream)
+    }
+}
+
+impl AsyncRead for MaybeTlsStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(target_os = "linux")]
+            Self::Timestamped {
+                stream,
+                rx_timestamp,
+            } => poll_read_timestamped(stream, rx_timestamp, cx, buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+/// Reads from `stream` with `recvmsg`, recording the receive timestamp.
+#[cfg(target_os = "linux")]
+fn poll_read_timestamped(
+    stream: &TcpStream,
+    rx_timestamp: &mut Option<RxTimestamp>,
+    cx: &mut Context<'_>,
+    buf: &mut ReadBuf<'_>,
+) -> Poll<io::Result<()>> {
+    use std::os::fd::AsFd;
+    loop {
+        std::task::ready!(stream.poll_read_ready(cx))?;
+        let unfilled = buf.initialize_unfilled();
+        let result = stream.try_io(tokio::io::Interest::READABLE, || {
+            crate::timestamp::sys::recvmsg(stream.as_fd(), unfilled)
+        });
+        match result {
+            Ok((len, _, stamp)) => {
+                buf.advance(len);
+                if stamp.is_some() {
+                    *rx_timestamp = stamp;
+                }
+                return Poll::Ready(Ok(()));
+            }
+            // Readiness was stale; `try_io` cleared it, so poll again.
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+            Err(err) => return Poll::Ready(Err(err)),
+        }
+    }
+}
+
+impl AsyncWrite for MaybeTlsStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(target_os = "linux")]
+            Self::Timestamped { stream, .. } => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+            #[cfg(target_os = "linux")]
+            Self::Timestamped { stream, .. } => Pin::new(stream).poll_write_vectored(cx, bufs),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            Self::Plain(stream) => stream.is_write_vectored(),
+            #[cfg(target_os = "linux")]
+            Self::Timestamped { stream, .. } => stream.is_write_vectored(),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => stream.is_write_vectored(),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(target_os = "linux")]
+            Self::Timestamped { stream, .. } => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(target_os = "linux")]
+            Self::Timestamped { stream, .. } => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
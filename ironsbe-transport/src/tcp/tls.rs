@@ -0,0 +1,534 @@
+//! TLS for the Tokio TCP backend, on rustls.
+//!
+//! Set a [`TlsServerConfig`] on [`TcpServerConfig`](super::TcpServerConfig)
+//! and a [`TlsClientConfig`] on [`TcpClientConfig`](super::TcpClientConfig)
+//! and the frame codec runs over the TLS stream instead of the socket.
+//! Certificates and keys are loaded from PEM; a server can also require
+//! client certificates signed by a given CA (mutual TLS).
+//!
+//! Venues hand out their own CA rather than relying on public roots, so a
+//! client trusts only the CAs added with [`TlsClientConfig::ca_pem`] and
+//! friends. Clones of a client configuration share one rustls
+//! configuration, so reconnects resume the previous TLS session and skip
+//! the full handshake.
+//!
+//! A resumed TLS 1.3 session can also carry 0-RTT early data: with
+//! [`TlsClientConfig::early_data`] on the client and
+//! [`TlsServerConfig::max_early_data`] on the server, the first frames a
+//! reconnecting client sends leave with its `ClientHello` instead of a
+//! round trip later.
+//!
+//! Early data is not protected against replay. An attacker who captured
+//! it can send it again, and the server takes it as a fresh connection
+//! (rustls keeps tickets single use, but only within one server process
+//! and only until it restarts). Enable it only where the first frames of
+//! a connection are safe to process twice, such as a logon that the
+//! session layer rejects when its sequence number was already seen;
+//! never for orders.
+
+use super::stream::MaybeTlsStream;
+use bytes::BytesMut;
+use rustls_pki_types::pem::PemObject;
+use std::fmt;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::crypto::{CryptoProvider, ring};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{self, RootCertStore};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+pub use rustls_pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+
+/// Default time a TLS handshake may take.
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Server-side TLS settings.
+pub struct TlsServerConfig {
+    cert_chain: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+    client_ca: Vec<CertificateDer<'static>>,
+    handshake_timeout: Duration,
+    max_early_data: u32,
+}
+
+impl TlsServerConfig {
+    /// Creates settings presenting `cert_chain`, leaf first, signed with
+    /// `key`. Client certificates are not requested.
+    #[must_use]
+    pub fn new(cert_chain: Vec<CertificateDer<'static>>, key: PrivateKeyDer<'static>) -> Self {
+        Self {
+            cert_chain,
+            key,
+            client_ca: Vec::new(),
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+            max_early_data: 0,
+        }
+    }
+
+    /// Creates settings from a PEM certificate chain and PEM private key.
+    ///
+    /// # Errors
+    /// Returns [`io::ErrorKind::InvalidData`] if either is not valid PEM or
+    /// holds no certificate or key.
+    pub fn from_pem(cert_chain: &[u8], key: &[u8]) -> io::Result<Self> {
+        Ok(Self::new(certs_from_pem(cert_chain)?, key_from_pem(key)?))
+    }
+
+    /// Creates settings from a PEM certificate chain file and PEM private
+    /// key file.
+    ///
+    /// # Errors
+    /// Returns an error if a file cannot be read or is not valid PEM.
+    pub fn from_pem_files(cert_chain: impl AsRef<Path>, key: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_pem(&std::fs::read(cert_chain)?, &std::fs::read(key)?)
+    }
+
+    /// Requires clients to present a certificate signed by one of `roots`.
+    #[must_use]
+    pub fn client_ca(mut self, roots: Vec<CertificateDer<'static>>) -> Self {
+        self.client_ca.extend(roots);
+        self
+    }
+
+    /// Requires client certificates signed by a CA from PEM.
+    ///
+    /// # Errors
+    /// Returns [`io::ErrorKind::InvalidData`] if `pem` holds no certificate.
+    pub fn client_ca_pem(self, pem: &[u8]) -> io::Result<Self> {
+        Ok(self.client_ca(certs_from_pem(pem)?))
+    }
+
+    /// Requires client certificates signed by a CA from a PEM file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or holds no certificate.
+    pub fn client_ca_pem_file(self, path: impl AsRef<Path>) -> io::Result<Self> {
+        self.client_ca_pem(&std::fs::read(path)?)
+    }
+
+    /// Sets how long a client may take to complete the handshake.
+    #[must_use]
+    pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    /// Accepts up to `bytes` of 0-RTT early data from clients resuming a
+    /// session; 0, the default, refuses it. The data is read as the first
+    /// frames of the connection. Early data can be replayed; see the
+    /// [module documentation](self) before enabling it.
+    #[must_use]
+    pub fn max_early_data(mut self, bytes: u32) -> Self {
+        self.max_early_data = bytes;
+        self
+    }
+
+    /// Builds the acceptor for a listener.
+    pub(crate) fn acceptor(&self) -> io::Result<Acceptor> {
+        let provider = provider();
+        let builder = rustls::ServerConfig::builder_with_provider(Arc::clone(&provider))
+            .with_safe_default_protocol_versions()
+            .map_err(invalid_input)?;
+        let builder = if self.client_ca.is_empty() {
+            builder.with_no_client_auth()
+        } else {
+            let verifier = WebPkiClientVerifier::builder_with_provider(
+                Arc::new(roots(&self.client_ca)?),
+                provider,
+            )
+            .build()
+            .map_err(invalid_input)?;
+            builder.with_client_cert_verifier(verifier)
+        };
+        let mut config = builder
+            .with_single_cert(self.cert_chain.clone(), self.key.clone_key())
+            .map_err(invalid_input)?;
+        config.max_early_data_size = self.max_early_data;
+        Ok(Acceptor {
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+            timeout: self.handshake_timeout,
+        })
+    }
+}
+
+impl Clone for TlsServerConfig {
+    fn clone(&self) -> Self {
+        Self {
+            cert_chain: self.cert_chain.clone(),
+            key: self.key.clone_key(),
+            client_ca: self.client_ca.clone(),
+            handshake_timeout: self.handshake_timeout,
+            max_early_data: self.max_early_data,
+        }
+    }
+}
+
+impl fmt::Debug for TlsServerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsServerConfig")
+            .field("cert_chain", &self.cert_chain.len())
+            .field("client_ca", &self.client_ca.len())
+            .field("handshake_timeout", &self.handshake_timeout)
+            .field("max_early_data", &self.max_early_data)
+            .finish_non_exhaustive()
+    }
+}
+
+/// TLS acceptor of a listener, built once at bind time.
+#[derive(Clone)]
+pub(crate) struct Acceptor {
+    acceptor: TlsAcceptor,
+    timeout: Duration,
+}
+
+impl Acceptor {
+    /// Runs the server side of the handshake, returning the stream and
+    /// any early data the client sent with it.
+    ///
+    /// rustls keeps early data apart from the application data stream,
+    /// so it is drained here for the caller to read first. The client
+    /// sends all of it before finishing the handshake.
+    pub(crate) async fn accept(self, stream: TcpStream) -> io::Result<(MaybeTlsStream, BytesMut)> {
+        let mut stream = tokio::time::timeout(self.timeout, self.acceptor.accept(stream))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timeout"))??;
+        let mut early = Vec::new();
+        if let Some(mut data) = stream.get_mut().1.early_data() {
+            data.read_to_end(&mut early)?;
+        }
+        Ok((
+            MaybeTlsStream::Tls(Box::new(stream.into())),
+            BytesMut::from(&early[..]),
+        ))
+    }
+}
+
+/// Client-side TLS settings.
+#[derive(Clone)]
+pub struct TlsClientConfig {
+    server_name: ServerName<'static>,
+    roots: Vec<CertificateDer<'static>>,
+    identity: Option<(Vec<CertificateDer<'static>>, Arc<PrivateKeyDer<'static>>)>,
+    handshake_timeout: Duration,
+    early_data: bool,
+    /// Built on first connect and shared by clones, so that they share the
+    /// session cache.
+    config: Arc<OnceLock<Arc<rustls::ClientConfig>>>,
+}
+
+impl TlsClientConfig {
+    /// Creates settings verifying the server certificate against
+    /// `server_name`, a DNS name or IP address.
+    ///
+    /// # Errors
+    /// Returns [`io::ErrorKind::InvalidInput`] if `server_name` is neither.
+    pub fn new(server_name: &str) -> io::Result<Self> {
+        let server_name = ServerName::try_from(server_name)
+            .map_err(invalid_input)?
+            .to_owned();
+        Ok(Self {
+            server_name,
+            roots: Vec::new(),
+            identity: None,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+            early_data: false,
+            config: Arc::default(),
+        })
+    }
+
+    /// Trusts servers with certificates signed by one of `roots`.
+    #[must_use]
+    pub fn ca(mut self, roots: Vec<CertificateDer<'static>>) -> Self {
+        self.roots.extend(roots);
+        self.config = Arc::default();
+        self
+    }
+
+    /// Trusts the CA certificates in `pem`.
+    ///
+    /// # Errors
+    /// Returns [`io::ErrorKind::InvalidData`] if `pem` holds no certificate.
+    pub fn ca_pem(self, pem: &[u8]) -> io::Result<Self> {
+        Ok(self.ca(certs_from_pem(pem)?))
+    }
+
+    /// Trusts the CA certificates in a PEM file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or holds no certificate.
+    pub fn ca_pem_file(self, path: impl AsRef<Path>) -> io::Result<Self> {
+        self.ca_pem(&std::fs::read(path)?)
+    }
+
+    /// Presents `cert_chain`, signed with `key`, when the server asks for a
+    /// client certificate.
+    #[must_use]
+    pub fn client_cert(
+        mut self,
+        cert_chain: Vec<CertificateDer<'static>>,
+        key: PrivateKeyDer<'static>,
+    ) -> Self {
+        self.identity = Some((cert_chain, Arc::new(key)));
+        self.config = Arc::default();
+        self
+    }
+
+    /// Presents a client certificate from a PEM chain and PEM key.
+    ///
+    /// # Errors
+    /// Returns [`io::ErrorKind::InvalidData`] if either is not valid PEM.
+    pub fn client_cert_pem(self, cert_chain: &[u8], key: &[u8]) -> io::Result<Self> {
+        Ok(self.client_cert(certs_from_pem(cert_chain)?, key_from_pem(key)?))
+    }
+
+    /// Presents a client certificate from a PEM chain file and PEM key file.
+    ///
+    /// # Errors
+    /// Returns an error if a file cannot be read or is not valid PEM.
+    pub fn client_cert_pem_files(
+        self,
+        cert_chain: impl AsRef<Path>,
+        key: impl AsRef<Path>,
+    ) -> io::Result<Self> {
+        self.client_cert_pem(&std::fs::read(cert_chain)?, &std::fs::read(key)?)
+    }
+
+    /// Sets how long the handshake may take.
+    #[must_use]
+    pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    /// Sends the first frames as 0-RTT early data when resuming a session
+    /// that allows it, so a reconnect does not wait a round trip for the
+    /// handshake before they leave. Frames the server refuses as early
+    /// data are resent once the handshake completes.
+    ///
+    /// The handshake then finishes with the first send or flush, outside
+    /// [`handshake_timeout`](Self::handshake_timeout), and reads wait for
+    /// it: a client that expects the server to speak first must flush
+    /// before reading. Early data can be replayed; see the
+    /// [module documentation](self) before enabling it.
+    #[must_use]
+    pub fn early_data(mut self, enabled: bool) -> Self {
+        self.early_data = enabled;
+        self.config = Arc::default();
+        self
+    }
+
+    /// Returns the rustls configuration, building it on first use.
+    fn rustls_config(&self) -> io::Result<Arc<rustls::ClientConfig>> {
+        if let Some(config) = self.config.get() {
+            return Ok(Arc::clone(config));
+        }
+        let builder = rustls::ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .map_err(invalid_input)?
+            .with_root_certificates(roots(&self.roots)?);
+        let mut config = match &self.identity {
+            Some((chain, key)) => builder
+                .with_client_auth_cert(chain.clone(), key.clone_key())
+                .map_err(invalid_input)?,
+            None => builder.with_no_client_auth(),
+        };
+        config.enable_early_data = self.early_data;
+        Ok(Arc::clone(self.config.get_or_init(|| Arc::new(config))))
+    }
+
+    /// Runs the client side of the handshake.
+    pub(crate) async fn connect(&self, stream: TcpStream) -> io::Result<MaybeTlsStream> {
+        let connector = TlsConnector::from(self.rustls_config()?).early_data(self.early_data);
+        let stream = tokio::time::timeout(
+            self.handshake_timeout,
+            connector.connect(self.server_name.clone(), stream),
+        )
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timeout"))??;
+        Ok(MaybeTlsStream::Tls(Box::new(stream.into())))
+    }
+}
+
+impl fmt::Debug for TlsClientConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsClientConfig")
+            .field("server_name", &self.server_name)
+            .field("roots", &self.roots.len())
+            .field("client_cert", &self.identity.is_some())
+            .field("handshake_timeout", &self.handshake_timeout)
+            .field("early_data", &self.early_data)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the crypto provider; explicit so that another provider enabled
+/// elsewhere in the dependency graph does not make the default ambiguous.
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+fn roots(certs: &[CertificateDer<'static>]) -> io::Result<RootCertStore> {
+    let mut store = RootCertStore::empty();
+    for cert in certs {
+        store.add(cert.clone()).map_err(invalid_input)?;
+    }
+    Ok(store)
+}
+
+fn certs_from_pem(pem: &[u8]) -> io::Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_slice_iter(pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(invalid_data)?;
+    if certs.is_empty() {
+        return Err(invalid_data("no certificate in PEM"));
+    }
+    Ok(certs)
+}
+
+fn key_from_pem(pem: &[u8]) -> io::Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_slice(pem).map_err(invalid_data)
+}
+
+fn invalid_input(e: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, e.to_string())
+}
+
+fn invalid_data(e: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_bad_pem_and_names() {
+        let err = TlsServerConfig::from_pem(b"not pem", b"").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = TlsClientConfig::new("not a name!").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(TlsClientConfig::new("127.0.0.1").is_ok());
+    }
+
+    #[test]
+    fn test_rejects_mismatched_key() {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let other = rcgen::KeyPair::generate().unwrap();
+        let config =
+            TlsServerConfig::from_pem(cert.cert.pem().as_bytes(), other.serialize_pem().as_bytes())
+                .unwrap();
+        assert!(config.acceptor().is_err());
+        assert!(format!("{config:?}").contains("TlsServerConfig"));
+    }
+
+    #[tokio::test]
+    async fn test_clones_resume_the_session() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let acceptor = TlsServerConfig::from_pem(
+            cert.cert.pem().as_bytes(),
+            cert.signing_key.serialize_pem().as_bytes(),
+        )
+        .unwrap()
+        .acceptor()
+        .unwrap();
+        let client = TlsClientConfig::new("localhost")
+            .unwrap()
+            .ca_pem(cert.cert.pem().as_bytes())
+            .unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mut kinds = Vec::new();
+        for config in [client.clone(), client] {
+            let (mut server, mut client) = tokio::join!(
+                async {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    acceptor.clone().accept(stream).await.unwrap().0
+                },
+                async {
+                    let stream = TcpStream::connect(addr).await.unwrap();
+                    config.connect(stream).await.unwrap()
+                },
+            );
+            // TLS 1.3 session tickets follow the handshake; reading past
+            // them stores the session.
+            server.write_all(b"x").await.unwrap();
+            server.flush().await.unwrap();
+            client.read_exact(&mut [0; 1]).await.unwrap();
+            let MaybeTlsStream::Tls(tls) = &client else {
+                panic!("plain stream");
+            };
+            kinds.push(tls.get_ref().1.handshake_kind());
+        }
+        assert_eq!(
+            kinds,
+            [
+                Some(rustls::HandshakeKind::Full),
+                Some(rustls::HandshakeKind::Resumed)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resumed_session_sends_early_data() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let acceptor = TlsServerConfig::from_pem(
+            cert.cert.pem().as_bytes(),
+            cert.signing_key.serialize_pem().as_bytes(),
+        )
+        .unwrap()
+        .max_early_data(1024)
+        .acceptor()
+        .unwrap();
+        let client = TlsClientConfig::new("localhost")
+            .unwrap()
+            .ca_pem(cert.cert.pem().as_bytes())
+            .unwrap()
+            .early_data(true);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mut early = Vec::new();
+        for config in [client.clone(), client] {
+            let ((mut server, data), mut client) = tokio::join!(
+                async {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    acceptor.clone().accept(stream).await.unwrap()
+                },
+                async {
+                    let stream = TcpStream::connect(addr).await.unwrap();
+                    let mut client = config.connect(stream).await.unwrap();
+                    client.write_all(b"logon").await.unwrap();
+                    client.flush().await.unwrap();
+                    client
+                },
+            );
+            // Whatever did not go as early data arrives as usual.
+            let mut logon = data.to_vec();
+            logon.resize(5, 0);
+            server.read_exact(&mut logon[data.len()..]).await.unwrap();
+            assert_eq!(logon, b"logon");
+            server.write_all(b"x").await.unwrap();
+            server.flush().await.unwrap();
+            client.read_exact(&mut [0; 1]).await.unwrap();
+            let MaybeTlsStream::Tls(tls) = &client else {
+                panic!("plain stream");
+            };
+            let tokio_rustls::TlsStream::Client(tls) = &**tls else {
+                panic!("server stream");
+            };
+            assert_eq!(tls.get_ref().1.is_early_data_accepted(), !data.is_empty());
+            early.push(data);
+        }
+        assert!(early[0].is_empty());
+        assert_eq!(&early[1][..], b"logon");
+    }
+}
@@ -0,0 +1,519 @@
+//! Blocking TCP transport on `std::net` (feature `tcp-std`).
+//!
+//! For applications that run on plain threads and do not want an async
+//! runtime in their dependency tree. Connections use the same framing as
+//! the Tokio backend, a 4-byte little-endian length prefix followed by the
+//! SBE message, so blocking and async peers interoperate. Optional CRC32C
+//! trailers match the Tokio codec's as well.
+
+use crate::error::TransportError;
+use crate::limits::{FrameLimits, TEMPLATE_ID_END};
+use ironsbe_core::checksum::{self, TRAILER_LENGTH};
+use ironsbe_core::vectored::{IoSliceEncoder, write_all_vectored};
+use std::io::{self, IoSlice, Read};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Default maximum frame size in bytes.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 64 * 1024;
+
+/// Default connection timeout.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Size of the length prefix in front of every frame.
+const LENGTH_PREFIX: usize = 4;
+
+/// Blocking, length-prefixed TCP connection.
+///
+/// Partially received frames are kept across calls, so a read timeout
+/// never loses data. Use [`try_clone`](Self::try_clone) to read and write
+/// from different threads.
+pub struct BlockingTcpConnection {
+    stream: TcpStream,
+    peer_addr: SocketAddr,
+    limits: FrameLimits,
+    checksum: bool,
+    /// Received bytes not yet returned as a frame.
+    pending: Vec<u8>,
+    /// Read timeout currently set on the socket.
+    read_timeout: Option<Duration>,
+}
+
+impl BlockingTcpConnection {
+    /// Connects to `addr` with [`DEFAULT_CONNECT_TIMEOUT`].
+    ///
+    /// # Errors
+    /// Returns `TransportError` if the connection fails or times out.
+    pub fn connect(addr: SocketAddr) -> Result<Self, TransportError> {
+        Self::connect_timeout(addr, DEFAULT_CONNECT_TIMEOUT)
+    }
+
+    /// Connects to `addr`, giving up after `timeout`.
+    ///
+    /// # Errors
+    /// Returns `TransportError` if the connection fails or times out.
+    pub fn connect_timeout(addr: SocketAddr, timeout: Duration) -> Result<Self, TransportError> {
+        let stream = TcpStream::connect_timeout(&addr, timeout).map_err(|e| {
+            if e.kind() == io::ErrorKind::TimedOut {
+                TransportError::ConnectTimeout
+            } else {
+                TransportError::Io(e)
+            }
+        })?;
+        Ok(Self::from_stream(stream)?)
+    }
+
+    /// Wraps a connected stream, enabling `TCP_NODELAY`.
+    ///
+    /// # Errors
+    /// Returns the I/O error if the stream is not connected.
+    pub fn from_stream(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        let peer_addr = stream.peer_addr()?;
+        Ok(Self {
+            stream,
+            peer_addr,
+            limits: FrameLimits::new(DEFAULT_MAX_FRAME_SIZE),
+            checksum: false,
+            pending: Vec::new(),
+            read_timeout: None,
+        })
+    }
+
+    /// Sets the maximum frame size accepted and sent.
+    #[must_use]
+    pub fn max_frame_size(mut self, size: usize) -> Self {
+        self.limits.set_max_frame_size(size);
+        self
+    }
+
+    /// Sets the maximum frame size for messages with `template_id`.
+    #[must_use]
+    pub fn template_max_frame_size(mut self, template_id: u16, size: usize) -> Self {
+        self.limits = self.limits.template(template_id, size);
+        self
+    }
+
+    /// Replaces all frame size limits.
+    #[must_use]
+    pub fn frame_limits(mut self, limits: FrameLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Enables CRC32C frame trailers; the peer must enable them too.
+    #[must_use]
+    pub fn checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
+    }
+
+    /// Returns the peer address.
+    #[must_use]
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// Returns a second handle to the same connection.
+    ///
+    /// The clone starts with no buffered input, so only one handle should
+    /// receive.
+    ///
+    /// # Errors
+    /// Returns the I/O error if the socket cannot be duplicated.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self {
+            stream: self.stream.try_clone()?,
+            peer_addr: self.peer_addr,
+            limits: self.limits.clone(),
+            checksum: self.checksum,
+            pending: Vec::new(),
+            // The read timeout is a property of the shared socket.
+            read_timeout: self.read_timeout,
+        })
+    }
+
+    /// Sends one framed message.
+    ///
+    /// # Errors
+    /// Returns `TransportError` if the message exceeds the maximum frame
+    /// size or the write fails.
+    pub fn send(&mut self, message: &[u8]) -> Result<(), TransportError> {
+        let prefix = self.length_prefix(message, message.len())?;
+        let trailer = self.checksum.then(|| checksum::trailer(message));
+        write_all_vectored(
+            &mut self.stream,
+            &mut [
+                IoSlice::new(&prefix),
+                IoSlice::new(message),
+                IoSlice::new(trailer.as_ref().map_or(&[][..], |trailer| &trailer[..])),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Sends one framed message assembled from slices, handing the length
+    /// prefix and every part to a single vectored write. The template
+    /// limit is read from the first part.
+    ///
+    /// # Errors
+    /// Returns `TransportError` if the message exceeds the maximum frame
+    /// size or the write fails.
+    pub fn send_vectored(&mut self, message: &IoSliceEncoder<'_>) -> Result<(), TransportError> {
+        let slices = message.io_slices();
+        let head = slices.first().map_or(&[][..], |slice| &slice[..]);
+        let prefix = self.length_prefix(head, message.len())?;
+        let trailer = self
+            .checksum
+            .then(|| checksum::trailer_parts(slices.iter().map(|slice| &slice[..])));
+        let mut frame = Vec::with_capacity(2 + slices.len());
+        frame.push(IoSlice::new(&prefix));
+        frame.extend(slices);
+        frame.extend(trailer.as_ref().map(|trailer| IoSlice::new(trailer)));
+        write_all_vectored(&mut self.stream, &mut frame)?;
+        Ok(())
+    }
+
+    fn length_prefix(
+        &self,
+        head: &[u8],
+        len: usize,
+    ) -> Result<[u8; LENGTH_PREFIX], TransportError> {
+        self.limits.check(len, head)?;
+        Ok((len as u32).to_le_bytes())
+    }
+
+    /// Blocks until a whole message arrives.
+    ///
+    /// # Errors
+    /// Returns [`TransportError::ConnectionClosed`] if the peer closed the
+    /// connection, [`TransportError::Decode`] if a frame fails its checksum,
+    /// or another `TransportError` if the read fails or the frame is too
+    /// large.
+    pub fn recv(&mut self) -> Result<Vec<u8>, TransportError> {
+        self.set_read_timeout(None)?;
+        loop {
+            if let Some(frame) = self.take_frame()? {
+                return Ok(frame);
+            }
+            self.fill()?;
+        }
+    }
+
+    /// Waits up to `timeout` for a whole message.
+    ///
+    /// Returns `Ok(None)` if none arrived in time; bytes of a partially
+    /// received frame are kept for the next call.
+    ///
+    /// # Errors
+    /// Same as [`recv`](Self::recv).
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, TransportError> {
+        // A zero timeout is rejected by `set_read_timeout`.
+        self.set_read_timeout(Some(timeout.max(Duration::from_micros(1))))?;
+        loop {
+            if let Some(frame) = self.take_frame()? {
+                return Ok(Some(frame));
+            }
+            match self.fill() {
+                Ok(()) => {}
+                Err(TransportError::Io(e))
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Ok(None);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Shuts down both directions of the connection, waking any thread
+    /// blocked in [`recv`](Self::recv).
+    ///
+    /// # Errors
+    /// Returns the I/O error if the shutdown fails.
+    pub fn shutdown(&self) -> io::Result<()> {
+        self.stream.shutdown(Shutdown::Both)
+    }
+
+    /// Sets the socket read timeout, skipping the syscall if unchanged.
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        if self.read_timeout != timeout {
+            self.stream.set_read_timeout(timeout)?;
+            self.read_timeout = timeout;
+        }
+        Ok(())
+    }
+
+    /// Removes and returns the first complete frame in `pending`.
+    fn take_frame(&mut self) -> Result<Option<Vec<u8>>, TransportError> {
+        if self.pending.len() < LENGTH_PREFIX {
+            return Ok(None);
+        }
+        let length = u32::from_le_bytes([
+            self.pending[0],
+            self.pending[1],
+            self.pending[2],
+            self.pending[3],
+        ]) as usize;
+        self.limits.check_length(length)?;
+        if self.limits.has_template_limits() {
+            // Check the template's limit before buffering the frame.
+            let head_end = LENGTH_PREFIX + length.min(TEMPLATE_ID_END);
+            if self.pending.len() < head_end {
+                return Ok(None);
+            }
+            self.limits
+                .check(length, &self.pending[LENGTH_PREFIX..head_end])?;
+        }
+        let message_end = LENGTH_PREFIX + length;
+        let frame_end = if self.checksum {
+            message_end + TRAILER_LENGTH
+        } else {
+            message_end
+        };
+        if self.pending.len() < frame_end {
+            return Ok(None);
+        }
+        let frame = self.pending[LENGTH_PREFIX..message_end].to_vec();
+        let verified = if self.checksum {
+            checksum::verify(&self.pending[LENGTH_PREFIX..frame_end]).map(drop)
+        } else {
+            Ok(())
+        };
+        self.pending.drain(..frame_end);
+        verified?;
+        Ok(Some(frame))
+    }
+
+    /// Reads whatever is available into `pending`.
+    fn fill(&mut self) -> Result<(), TransportError> {
+        let mut chunk = [0u8; 8 * 1024];
+        match self.stream.read(&mut chunk) {
+            Ok(0) => Err(TransportError::ConnectionClosed),
+            Ok(n) => {
+                self.pending.extend_from_slice(&chunk[..n]);
+                Ok(())
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => Ok(()),
+            Err(e) => Err(TransportError::Io(e)),
+        }
+    }
+}
+
+/// Blocking TCP listener producing [`BlockingTcpConnection`]s.
+pub struct BlockingTcpListener {
+    listener: TcpListener,
+    limits: FrameLimits,
+    checksum: bool,
+}
+
+impl BlockingTcpListener {
+    /// Binds to `addr`.
+    ///
+    /// # Errors
+    /// Returns the I/O error if binding fails.
+    pub fn bind(addr: SocketAddr) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            limits: FrameLimits::new(DEFAULT_MAX_FRAME_SIZE),
+            checksum: false,
+        })
+    }
+
+    /// Sets the maximum frame size of accepted connections.
+    #[must_use]
+    pub fn max_frame_size(mut self, size: usize) -> Self {
+        self.limits.set_max_frame_size(size);
+        self
+    }
+
+    /// Sets the maximum frame size of accepted connections for messages
+    /// with `template_id`.
+    #[must_use]
+    pub fn template_max_frame_size(mut self, template_id: u16, size: usize) -> Self {
+        self.limits = self.limits.template(template_id, size);
+        self
+    }
+
+    /// Enables CRC32C frame trailers on accepted connections.
+    #[must_use]
+    pub fn checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
+    }
+
+    /// Returns the local address the listener is bound to.
+    ///
+    /// # Errors
+    /// Returns the I/O error if the address cannot be read.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Blocks until a client connects.
+    ///
+    /// # Errors
+    /// Returns the I/O error if accepting fails.
+    pub fn accept(&self) -> io::Result<BlockingTcpConnection> {
+        let (stream, _) = self.listener.accept()?;
+        Ok(BlockingTcpConnection::from_stream(stream)?
+            .frame_limits(self.limits.clone())
+            .checksum(self.checksum))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::thread;
+
+    fn pair() -> (BlockingTcpConnection, BlockingTcpConnection) {
+        let listener = BlockingTcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accept = thread::spawn(move || listener.accept().unwrap());
+        let client = BlockingTcpConnection::connect(addr).unwrap();
+        (client, accept.join().unwrap())
+    }
+
+    #[test]
+    fn test_blocking_round_trip() {
+        let (mut client, mut server) = pair();
+
+        client.send(b"hello").unwrap();
+        client.send(b"").unwrap();
+        client.send(&[7u8; 20_000]).unwrap();
+
+        assert_eq!(server.recv().unwrap(), b"hello");
+        assert_eq!(server.recv().unwrap(), b"");
+        assert_eq!(server.recv().unwrap(), vec![7u8; 20_000]);
+        assert_eq!(server.peer_addr(), client.stream.local_addr().unwrap());
+    }
+
+    #[test]
+    fn test_blocking_vectored_send() {
+        let (mut client, mut server) = pair();
+        let text = [9u8; 3_000];
+        let mut message = IoSliceEncoder::new();
+        message.append(b"head").append_var_data(&text);
+
+        client.send_vectored(&message).unwrap();
+        client.send(b"next").unwrap();
+        assert_eq!(server.recv().unwrap(), message.to_vec());
+        assert_eq!(server.recv().unwrap(), b"next");
+
+        let mut client = client.max_frame_size(4);
+        assert!(matches!(
+            client.send_vectored(&message),
+            Err(TransportError::FrameTooLarge {
+                size: 3_006,
+                max: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn test_blocking_template_limits() {
+        let (client, server) = pair();
+        let mut client = client.template_max_frame_size(0, 6);
+        let mut server = server.template_max_frame_size(0, 4);
+
+        // A template 0 heartbeat within each side's limit, then one that
+        // only the sender allows.
+        client.send(&[0, 0, 0, 0]).unwrap();
+        client.send(&[0, 0, 0, 0, 9, 9]).unwrap();
+        assert!(matches!(
+            client.send(&[0u8; 7]),
+            Err(TransportError::TemplateFrameTooLarge {
+                template_id: 0,
+                size: 7,
+                max: 6,
+            })
+        ));
+
+        assert_eq!(server.recv().unwrap(), [0, 0, 0, 0]);
+        assert!(matches!(
+            server.recv(),
+            Err(TransportError::TemplateFrameTooLarge {
+                template_id: 0,
+                size: 6,
+                max: 4,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_blocking_checksum_trailer() {
+        let (client, server) = pair();
+        let mut client = client.checksum(true);
+        let mut server = server.checksum(true);
+
+        let mut message = IoSliceEncoder::new();
+        message.append(b"head").append_var_data(b"text");
+        client.send(b"hello").unwrap();
+        client.send_vectored(&message).unwrap();
+        assert_eq!(server.recv().unwrap(), b"hello");
+        assert_eq!(server.recv().unwrap(), message.to_vec());
+
+        // A corrupted frame is reported and skipped.
+        let mut frame = 5u32.to_le_bytes().to_vec();
+        frame.extend_from_slice(b"hello");
+        frame.extend_from_slice(&checksum::trailer(b"jello"));
+        client.stream.write_all(&frame).unwrap();
+        client.send(b"after").unwrap();
+        assert!(matches!(
+            server.recv(),
+            Err(TransportError::Decode(
+                ironsbe_core::DecodeError::ChecksumMismatch { .. }
+            ))
+        ));
+        assert_eq!(server.recv().unwrap(), b"after");
+    }
+
+    #[test]
+    fn test_blocking_recv_timeout_keeps_partial_frame() {
+        let (mut client, mut server) = pair();
+
+        // Length prefix and half the payload, then the rest later.
+        client.stream.write_all(&4u32.to_le_bytes()).unwrap();
+        client.stream.write_all(b"ab").unwrap();
+        assert_eq!(
+            server.recv_timeout(Duration::from_millis(20)).unwrap(),
+            None
+        );
+
+        client.stream.write_all(b"cd").unwrap();
+        assert_eq!(
+            server.recv_timeout(Duration::from_secs(5)).unwrap(),
+            Some(b"abcd".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_blocking_frame_limits_and_close() {
+        let (client, mut server) = pair();
+        let mut client = client.max_frame_size(4);
+
+        assert!(matches!(
+            client.send(b"too long"),
+            Err(TransportError::FrameTooLarge { size: 8, max: 4 })
+        ));
+
+        client.stream.write_all(&100_000u32.to_le_bytes()).unwrap();
+        assert!(matches!(
+            server.recv(),
+            Err(TransportError::FrameTooLarge { size: 100_000, .. })
+        ));
+
+        drop(client);
+        let (client, mut server) = pair();
+        client.shutdown().unwrap();
+        assert!(matches!(
+            server.recv(),
+            Err(TransportError::ConnectionClosed)
+        ));
+    }
+}
@@ -17,8 +17,9 @@
 //! # Wire format
 //!
 //! Identical to the Tokio TCP backend (4-byte little-endian length prefix
-//! followed by the payload), so the two backends are wire-compatible — a
-//! `tcp-tokio` server can serve a `tcp-uring` client and vice versa.
+//! followed by the payload, plus the optional CRC32C trailer), so the two
+//! backends are wire-compatible — a `tcp-tokio` server can serve a
+//! `tcp-uring` client and vice versa.
 //!
 //! # Scope of this module
 //!
@@ -26,8 +27,10 @@
 //! pooling, registered buffers, registered fds, and `IORING_OP_SEND_ZC` are
 //! out of scope here and tracked in the follow-up issue.
 
+use crate::error::TransportError;
 use crate::traits;
 use bytes::{Bytes, BytesMut};
+use ironsbe_core::checksum::{self, TRAILER_LENGTH};
 use std::io;
 use std::net::SocketAddr;
 
@@ -45,6 +48,8 @@ pub struct UringServerConfig {
     pub bind_addr: SocketAddr,
     /// Maximum SBE frame size, in bytes.
     pub max_frame_size: usize,
+    /// Append and verify a CRC32C trailer on every frame.
+    pub checksum: bool,
 }
 
 impl Default for UringServerConfig {
@@ -54,6 +59,7 @@ impl Default for UringServerConfig {
                 .parse()
                 .expect("hardcoded default bind addr is valid"),
             max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            checksum: false,
         }
     }
 }
@@ -80,6 +86,13 @@ impl UringServerConfig {
         self.max_frame_size = size;
         self
     }
+
+    /// Enables CRC32C frame trailers; the peer must enable them too.
+    #[must_use]
+    pub fn checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
+    }
 }
 
 /// Configuration for [`UringTcpTransport::connect_with`].
@@ -89,6 +102,8 @@ pub struct UringClientConfig {
     pub server_addr: SocketAddr,
     /// Maximum SBE frame size, in bytes.
     pub max_frame_size: usize,
+    /// Append and verify a CRC32C trailer on every frame.
+    pub checksum: bool,
 }
 
 impl Default for UringClientConfig {
@@ -98,6 +113,7 @@ impl Default for UringClientConfig {
                 .parse()
                 .expect("hardcoded default server addr is valid"),
             max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            checksum: false,
         }
     }
 }
@@ -125,6 +141,13 @@ impl UringClientConfig {
         self.max_frame_size = size;
         self
     }
+
+    /// Enables CRC32C frame trailers; the peer must enable them too.
+    #[must_use]
+    pub fn checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
+    }
 }
 
 /// Linux io_uring TCP transport backend.
@@ -145,6 +168,7 @@ impl traits::LocalTransport for UringTcpTransport {
         Ok(UringListener {
             listener,
             max_frame_size: config.max_frame_size,
+            checksum: config.checksum,
         })
     }
 
@@ -155,6 +179,7 @@ impl traits::LocalTransport for UringTcpTransport {
             stream,
             peer_addr,
             config.max_frame_size,
+            config.checksum,
         ))
     }
 }
@@ -163,6 +188,7 @@ impl traits::LocalTransport for UringTcpTransport {
 pub struct UringListener {
     listener: tokio_uring::net::TcpListener,
     max_frame_size: usize,
+    checksum: bool,
 }
 
 impl traits::LocalListener for UringListener {
@@ -171,7 +197,12 @@ impl traits::LocalListener for UringListener {
 
     async fn accept(&mut self) -> io::Result<UringConnection> {
         let (stream, peer_addr) = self.listener.accept().await?;
-        Ok(UringConnection::new(stream, peer_addr, self.max_frame_size))
+        Ok(UringConnection::new(
+            stream,
+            peer_addr,
+            self.max_frame_size,
+            self.checksum,
+        ))
     }
 
     fn local_addr(&self) -> io::Result<SocketAddr> {
@@ -189,6 +220,7 @@ pub struct UringConnection {
     stream: tokio_uring::net::TcpStream,
     peer_addr: SocketAddr,
     max_frame_size: usize,
+    checksum: bool,
     /// Already-received bytes that have not yet been consumed by `recv`.
     pending: BytesMut,
 }
@@ -199,11 +231,13 @@ impl UringConnection {
         stream: tokio_uring::net::TcpStream,
         peer_addr: SocketAddr,
         max_frame_size: usize,
+        checksum: bool,
     ) -> Self {
         Self {
             stream,
             peer_addr,
             max_frame_size,
+            checksum,
             pending: BytesMut::with_capacity(max_frame_size + LENGTH_PREFIX_BYTES),
         }
     }
@@ -212,7 +246,7 @@ impl UringConnection {
     ///
     /// Returns `Ok(Some(frame))` if a full frame is available, `Ok(None)` if
     /// more bytes are needed, or an error if the length prefix is malformed
-    /// or exceeds `max_frame_size`.
+    /// or exceeds `max_frame_size`, or the frame fails its checksum.
     fn try_take_frame(&mut self) -> io::Result<Option<BytesMut>> {
         if self.pending.len() < LENGTH_PREFIX_BYTES {
             return Ok(None);
@@ -235,6 +269,7 @@ impl UringConnection {
         }
         let total = LENGTH_PREFIX_BYTES
             .checked_add(frame_len)
+            .and_then(|len| len.checked_add(self.trailer_len()))
             .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "frame length overflow"))?;
         if self.pending.len() < total {
             return Ok(None);
@@ -242,8 +277,20 @@ impl UringConnection {
         // Advance past the prefix and split off the payload.
         let _prefix = self.pending.split_to(LENGTH_PREFIX_BYTES);
         let payload = self.pending.split_to(frame_len);
+        if self.checksum {
+            let trailer = self.pending.split_to(TRAILER_LENGTH);
+            let trailer = [trailer[0], trailer[1], trailer[2], trailer[3]];
+            checksum::verify_trailer(&payload, trailer).map_err(|err| {
+                io::Error::new(io::ErrorKind::InvalidData, TransportError::from(err))
+            })?;
+        }
         Ok(Some(payload))
     }
+
+    /// Returns the number of bytes following the payload in each frame.
+    fn trailer_len(&self) -> usize {
+        if self.checksum { TRAILER_LENGTH } else { 0 }
+    }
 }
 
 impl traits::LocalConnection for UringConnection {
@@ -257,7 +304,7 @@ impl traits::LocalConnection for UringConnection {
             // Submit a fresh read into a fixed-size scratch buffer.  The
             // buffer is owned by the kernel for the duration of the op and
             // returned via BufResult.
-            let scratch = vec![0u8; self.max_frame_size + LENGTH_PREFIX_BYTES];
+            let scratch = vec![0u8; self.max_frame_size + LENGTH_PREFIX_BYTES + self.trailer_len()];
             let (res, buf) = self.stream.read(scratch).await;
             let n = res?;
             if n == 0 {
@@ -297,14 +344,18 @@ impl traits::LocalConnection for UringConnection {
         })?;
         let total = LENGTH_PREFIX_BYTES
             .checked_add(frame_len)
+            .and_then(|len| len.checked_add(self.trailer_len()))
             .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "frame length overflow"))?;
         // Build a single owned frame: 4-byte LE length prefix followed by
-        // the payload.  Allocating a fresh Vec here is the simplest correct
+        // the payload and the optional checksum trailer.  Allocating a fresh Vec here is the simplest correct
         // path; a future change can use a pooled BytesMut to remove the
         // allocation.
         let mut framed = Vec::with_capacity(total);
         framed.extend_from_slice(&frame_len_u32.to_le_bytes());
         framed.extend_from_slice(&msg);
+        if self.checksum {
+            framed.extend_from_slice(&checksum::trailer(&msg));
+        }
         // tokio-uring's write_all returns the buffer back regardless of
         // success so we can drop it cleanly.
         let (res, _buf) = self.stream.write_all(framed).await;
@@ -335,6 +386,8 @@ mod tests {
         let cfg = UringServerConfig::new(addr).max_frame_size(128 * 1024);
         assert_eq!(cfg.bind_addr, addr);
         assert_eq!(cfg.max_frame_size, 128 * 1024);
+        assert!(!cfg.checksum);
+        assert!(cfg.checksum(true).checksum);
     }
 
     #[test]
@@ -362,6 +415,8 @@ mod tests {
         let cfg = UringClientConfig::new(addr).max_frame_size(256 * 1024);
         assert_eq!(cfg.server_addr, addr);
         assert_eq!(cfg.max_frame_size, 256 * 1024);
+        assert!(!cfg.checksum);
+        assert!(cfg.checksum(true).checksum);
     }
 
     #[test]
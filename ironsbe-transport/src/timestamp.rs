@@ -0,0 +1,353 @@
+//! Kernel and NIC receive timestamps.
+//!
+//! With `SO_TIMESTAMPING` the kernel records, for every packet it receives,
+//! the time the packet came off the wire: stamped by the NIC when it
+//! supports hardware timestamping, otherwise by the driver when it hands
+//! the packet to the stack. Kernels without `SO_TIMESTAMPING` fall back to
+//! `SO_TIMESTAMPNS`, which is software only. The timestamps are read with
+//! `recvmsg` and surfaced as [`RxTimestamp`], measured on the realtime
+//! clock so they can be compared with times taken by the application.
+//!
+//! Receive timestamps are only available on Linux. Hardware timestamps
+//! additionally need the NIC configured for them (the `SIOCSHWTSTAMP`
+//! ioctl, e.g. `hwstamp_ctl -i eth0 -r 1`), which needs privileges and is
+//! left to the deployment; until then packets carry software timestamps.
+
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Which receive timestamps to ask the kernel for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TimestampMode {
+    /// No timestamps; packets are received with plain `recv`.
+    #[default]
+    Off,
+    /// Timestamps taken by the kernel when the packet reaches the stack.
+    Software,
+    /// Timestamps taken by the NIC, with software timestamps for packets
+    /// the NIC did not stamp.
+    Hardware,
+}
+
+/// Where a receive timestamp was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimestampSource {
+    /// Stamped by the NIC.
+    Hardware,
+    /// Stamped by the kernel.
+    Software,
+}
+
+/// Time a packet was received, as reported by the kernel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RxTimestamp {
+    /// Nanoseconds since the Unix epoch.
+    pub nanos: u64,
+    /// Where the timestamp was taken.
+    pub source: TimestampSource,
+}
+
+impl RxTimestamp {
+    /// Returns the timestamp as a [`SystemTime`].
+    #[must_use]
+    pub fn system_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_nanos(self.nanos)
+    }
+
+    /// Returns how long ago the packet was received, or zero if the clock
+    /// went backwards.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.system_time())
+            .unwrap_or_default()
+    }
+}
+
+/// Enables receive timestamps on `socket`.
+///
+/// Asks for `SO_TIMESTAMPING` first and falls back to `SO_TIMESTAMPNS`
+/// if the kernel rejects it; [`RxTimestamp::source`] tells which
+/// timestamps packets actually carry. [`TimestampMode::Off`] leaves the
+/// socket unchanged.
+///
+/// # Errors
+/// Returns IO error if neither option can be set.
+#[cfg(target_os = "linux")]
+pub fn enable(socket: &impl std::os::fd::AsFd, mode: TimestampMode) -> io::Result<()> {
+    sys::enable(socket.as_fd(), mode)
+}
+
+/// Enables receive timestamps on `socket`.
+///
+/// Receive timestamps are only available on Linux.
+///
+/// # Errors
+/// Returns an `Unsupported` error unless `mode` is [`TimestampMode::Off`].
+#[cfg(not(target_os = "linux"))]
+pub fn enable<S>(_socket: &S, mode: TimestampMode) -> io::Result<()> {
+    match mode {
+        TimestampMode::Off => Ok(()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "receive timestamps are only available on Linux",
+        )),
+    }
+}
+
+/// Receives a datagram and its timestamp, if timestamps are `enabled`.
+#[cfg(feature = "tokio")]
+#[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+pub(crate) async fn recv_from(
+    socket: &tokio::net::UdpSocket,
+    buf: &mut [u8],
+    enabled: bool,
+) -> io::Result<(usize, std::net::SocketAddr, Option<RxTimestamp>)> {
+    #[cfg(target_os = "linux")]
+    if enabled {
+        use std::os::fd::AsFd;
+        return socket
+            .async_io(tokio::io::Interest::READABLE, || {
+                let (len, addr, timestamp) = sys::recvmsg(socket.as_fd(), buf)?;
+                let addr = addr.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "unknown sender address")
+                })?;
+                Ok((len, addr, timestamp))
+            })
+            .await;
+    }
+    let (len, addr) = socket.recv_from(buf).await?;
+    Ok((len, addr, None))
+}
+
+#[cfg(target_os = "linux")]
+#[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+pub(crate) mod sys {
+    use super::{RxTimestamp, TimestampMode, TimestampSource};
+    use std::io;
+    use std::mem;
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+    use std::os::fd::{AsRawFd, BorrowedFd};
+
+    /// Room for one `SCM_TIMESTAMPING` message (three timespecs) and
+    /// then some, as `u64`s to keep the control headers aligned.
+    const CONTROL_WORDS: usize = 16;
+
+    pub(crate) fn enable(fd: BorrowedFd<'_>, mode: TimestampMode) -> io::Result<()> {
+        let software = libc::SOF_TIMESTAMPING_RX_SOFTWARE | libc::SOF_TIMESTAMPING_SOFTWARE;
+        let flags = match mode {
+            TimestampMode::Off => return Ok(()),
+            TimestampMode::Software => software,
+            TimestampMode::Hardware => {
+                software | libc::SOF_TIMESTAMPING_RX_HARDWARE | libc::SOF_TIMESTAMPING_RAW_HARDWARE
+            }
+        };
+        if set_option(fd, libc::SO_TIMESTAMPING, flags as libc::c_int).is_ok() {
+            return Ok(());
+        }
+        set_option(fd, libc::SO_TIMESTAMPNS, 1)
+    }
+
+    fn set_option(fd: BorrowedFd<'_>, name: libc::c_int, value: libc::c_int) -> io::Result<()> {
+        // SAFETY: `value` outlives the call and its size is passed along.
+        let rc = unsafe {
+            libc::setsockopt(
+                fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                name,
+                (&raw const value).cast(),
+                mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if rc == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    /// Reads from `fd` with `recvmsg`, returning the length read, the
+    /// sender for datagram sockets and the receive timestamp.
+    ///
+    /// Like the socket itself, returns `WouldBlock` when nothing is ready.
+    pub(crate) fn recvmsg(
+        fd: BorrowedFd<'_>,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, Option<SocketAddr>, Option<RxTimestamp>)> {
+        // SAFETY: all-zero is a valid `sockaddr_storage` and `msghdr`.
+        let mut addr: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let mut control = [0u64; CONTROL_WORDS];
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr().cast(),
+            iov_len: buf.len(),
+        };
+        // SAFETY: as above.
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_name = (&raw mut addr).cast();
+        msg.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        msg.msg_iov = &raw mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = mem::size_of_val(&control) as _;
+
+        // SAFETY: every pointer in `msg` refers to a live local or to
+        // `buf`, with the matching length.
+        let len = unsafe { libc::recvmsg(fd.as_raw_fd(), &raw mut msg, 0) };
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: the kernel filled `msg_control` with `msg_controllen`
+        // bytes of control messages.
+        let timestamp = unsafe { timestamp(&msg) };
+        let sender = (msg.msg_namelen > 0).then(|| socket_addr(&addr)).flatten();
+        Ok((len as usize, sender, timestamp))
+    }
+
+    /// Finds the receive timestamp in the control messages of `msg`,
+    /// preferring the NIC's.
+    ///
+    /// # Safety
+    /// `msg` must describe control messages written by the kernel.
+    unsafe fn timestamp(msg: &libc::msghdr) -> Option<RxTimestamp> {
+        let mut found = None;
+        // SAFETY: walking the control messages with the libc macros stays
+        // within `msg_control`.
+        unsafe {
+            let mut cmsg = libc::CMSG_FIRSTHDR(msg);
+            while !cmsg.is_null() {
+                let header = &*cmsg;
+                let data = libc::CMSG_DATA(cmsg);
+                if header.cmsg_level == libc::SOL_SOCKET {
+                    if header.cmsg_type == libc::SCM_TIMESTAMPING {
+                        // Software, deprecated, raw hardware.
+                        let stamps: [libc::timespec; 3] =
+                            data.cast::<[libc::timespec; 3]>().read_unaligned();
+                        if let Some(nanos) = nanos(&stamps[2]) {
+                            return Some(RxTimestamp {
+                                nanos,
+                                source: TimestampSource::Hardware,
+                            });
+                        }
+                        found = software(&stamps[0]).or(found);
+                    } else if header.cmsg_type == libc::SCM_TIMESTAMPNS {
+                        let stamp = data.cast::<libc::timespec>().read_unaligned();
+                        found = software(&stamp).or(found);
+                    }
+                }
+                cmsg = libc::CMSG_NXTHDR(msg, cmsg);
+            }
+        }
+        found
+    }
+
+    fn software(stamp: &libc::timespec) -> Option<RxTimestamp> {
+        nanos(stamp).map(|nanos| RxTimestamp {
+            nanos,
+            source: TimestampSource::Software,
+        })
+    }
+
+    /// Converts a timespec to nanoseconds; zero means not stamped.
+    fn nanos(stamp: &libc::timespec) -> Option<u64> {
+        let nanos = (stamp.tv_sec as u64)
+            .saturating_mul(1_000_000_000)
+            .saturating_add(stamp.tv_nsec as u64);
+        (nanos != 0).then_some(nanos)
+    }
+
+    fn socket_addr(addr: &libc::sockaddr_storage) -> Option<SocketAddr> {
+        match libc::c_int::from(addr.ss_family) {
+            libc::AF_INET => {
+                // SAFETY: the family says the storage holds a `sockaddr_in`.
+                let v4 = unsafe {
+                    &*(addr as *const libc::sockaddr_storage).cast::<libc::sockaddr_in>()
+                };
+                Some(SocketAddr::V4(SocketAddrV4::new(
+                    Ipv4Addr::from(u32::from_be(v4.sin_addr.s_addr)),
+                    u16::from_be(v4.sin_port),
+                )))
+            }
+            libc::AF_INET6 => {
+                // SAFETY: the family says the storage holds a `sockaddr_in6`.
+                let v6 = unsafe {
+                    &*(addr as *const libc::sockaddr_storage).cast::<libc::sockaddr_in6>()
+                };
+                Some(SocketAddr::V6(SocketAddrV6::new(
+                    Ipv6Addr::from(v6.sin6_addr.s6_addr),
+                    u16::from_be(v6.sin6_port),
+                    v6.sin6_flowinfo,
+                    v6.sin6_scope_id,
+                )))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Runs `attempt`, which sends and receives one packet, until the packet
+/// carries a timestamp, up to 50 times. Enabling the first timestamping
+/// socket is deferred in the kernel, so the first packets may arrive
+/// unstamped.
+#[cfg(all(test, feature = "tokio", target_os = "linux"))]
+pub(crate) async fn until_stamped(
+    mut attempt: impl AsyncFnMut() -> Option<RxTimestamp>,
+) -> Option<RxTimestamp> {
+    for _ in 0..50 {
+        if let Some(stamp) = attempt().await {
+            return Some(stamp);
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rx_timestamp_elapsed() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let stamp = RxTimestamp {
+            nanos: now.as_nanos() as u64 - 1_000_000,
+            source: TimestampSource::Software,
+        };
+        assert!(stamp.elapsed() >= Duration::from_millis(1));
+        assert_eq!(
+            stamp.system_time().duration_since(UNIX_EPOCH).unwrap(),
+            Duration::from_nanos(stamp.nanos)
+        );
+
+        // A timestamp in the future has no elapsed time.
+        let future = RxTimestamp {
+            nanos: stamp.nanos + 60_000_000_000,
+            ..stamp
+        };
+        assert_eq!(future.elapsed(), Duration::ZERO);
+    }
+
+    #[cfg(all(feature = "tokio", target_os = "linux"))]
+    #[tokio::test]
+    async fn test_udp_software_timestamps() {
+        let receiver = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        enable(&receiver, TimestampMode::Software).unwrap();
+        let sender = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let stamp = until_stamped(async || {
+            sender
+                .send_to(b"stamped", receiver.local_addr().unwrap())
+                .await
+                .unwrap();
+            let mut buf = [0u8; 16];
+            let (len, from, stamp) = recv_from(&receiver, &mut buf, true).await.unwrap();
+            assert_eq!(&buf[..len], b"stamped");
+            assert_eq!(from, sender.local_addr().unwrap());
+            stamp
+        })
+        .await;
+        let stamp = stamp.expect("loopback packets carry software timestamps");
+        assert_eq!(stamp.source, TimestampSource::Software);
+        assert!(u128::from(stamp.nanos) >= before.as_nanos());
+    }
+}
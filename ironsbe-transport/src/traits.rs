@@ -15,6 +15,7 @@
 //! 4. Add a conditional `DefaultTransport` alias in `lib.rs` if appropriate.
 
 use bytes::{Bytes, BytesMut};
+use ironsbe_core::vectored::IoSliceEncoder;
 use std::future::Future;
 use std::net::SocketAddr;
 
@@ -165,6 +166,21 @@ pub trait Connection: Send + 'static {
         async move { self.send(&msg).await }
     }
 
+    /// Sends one framed SBE message assembled from slices.
+    ///
+    /// Stream backends override this to pass the parts to a vectored write
+    /// instead of copying them into one buffer.  The default implementation
+    /// copies the message and forwards to [`send`](Self::send).
+    ///
+    /// # Errors
+    /// Returns an error on I/O failure.
+    fn send_vectored<'a>(
+        &'a mut self,
+        msg: &'a IoSliceEncoder<'a>,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'a {
+        async move { self.send(&msg.to_vec()).await }
+    }
+
     /// Returns the remote peer address.
     ///
     /// # Errors
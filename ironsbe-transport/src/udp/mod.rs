@@ -1,9 +1,14 @@
 //! UDP transport module.
 //!
-//! Provides UDP unicast and multicast implementations with A/B feed arbitration.
+//! Provides UDP unicast and multicast implementations with A/B feed arbitration
+//! and NAK-based retransmission.
 
 pub mod multicast;
+pub mod retransmit;
 pub mod unicast;
 
 pub use multicast::{FeedArbitrator, MulticastConfig, MulticastReceiver, SequencedPacket};
+pub use retransmit::{
+    RetransmitBuffer, RetransmitServer, RetransmitServerConfig, RetransmitSource,
+};
 pub use unicast::{UdpReceiver, UdpSender};
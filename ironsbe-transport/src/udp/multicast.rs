@@ -1,14 +1,38 @@
 //! UDP multicast with A/B feed arbitration.
+//!
+//! Gaps can be recovered from a
+//! [`RetransmitServer`](super::retransmit::RetransmitServer) by setting
+//! [`MulticastConfig::retransmit_server`].
+//!
+//! Datagrams are received straight into buffers from a [`BufferPool`] and
+//! handed downstream inside [`SequencedPacket::data`] without copying; a
+//! buffer goes back to the pool once the last clone of that `Bytes` is
+//! dropped.
+//!
+//! Setting [`MulticastConfig::capture`] writes every datagram received to
+//! a pcap file, which [`replay_udp`](crate::capture::replay_udp) can later
+//! send back to a receiver.
 
 use bytes::Bytes;
+use ironsbe_core::buffer::{BufferPool, PooledBuffer};
+use ironsbe_core::checksum::{self, TRAILER_LENGTH};
+use ironsbe_core::decoder::DecodeError;
+use ironsbe_core::types::Timestamp;
 use lru::LruCache;
 use parking_lot::RwLock;
-use std::net::{Ipv4Addr, SocketAddr};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::num::NonZeroUsize;
 use std::sync::Arc;
-use std::time::Instant;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use tokio::net::UdpSocket;
 
+use super::retransmit::encode_nak;
+use crate::capture::PacketCapture;
+use crate::sockopt::SocketTuning;
+use crate::timestamp::{self, RxTimestamp, TimestampMode};
+use socket2::SockRef;
+
 /// Configuration for multicast feed.
 #[derive(Debug, Clone)]
 pub struct MulticastConfig {
@@ -20,8 +44,42 @@ pub struct MulticastConfig {
     pub port: u16,
     /// Network interface to use.
     pub interface: Ipv4Addr,
-    /// Receive buffer size in bytes.
+    /// `SO_RCVBUF` for the feed sockets, in bytes. The kernel may clamp it
+    /// to `net.core.rmem_max`.
     pub recv_buffer_size: usize,
+    /// `SO_BUSY_POLL` for the feed sockets: how long reads spin on the
+    /// device queue before sleeping. Linux only.
+    pub busy_poll: Option<Duration>,
+    /// `IP_TOS` for the NAKs sent to the retransmit server, e.g. a DSCP
+    /// marking from [`dscp_to_tos`](crate::sockopt::dscp_to_tos).
+    pub tos: Option<u8>,
+    /// Interface to bind every socket to with `SO_BINDTODEVICE`. Linux only.
+    pub bind_device: Option<String>,
+    /// Number of pooled receive buffers created by
+    /// [`MulticastReceiver::new`].
+    ///
+    /// Bounds how many received packets may be held downstream at once
+    /// before the receiver falls back to heap allocation.
+    pub pool_capacity: usize,
+    /// Expect a CRC32C trailer, covering the sequence header and payload,
+    /// at the end of every datagram.
+    ///
+    /// Corrupt datagrams are dropped before arbitration, so the other
+    /// feed's copy of the packet is used instead.
+    pub checksum: bool,
+    /// Retransmit server to send NAKs to when a gap is detected.
+    ///
+    /// Replayed packets arrive on a unicast socket and go through the same
+    /// arbitration as the feeds, so each sequence number is still
+    /// delivered once. Recovered packets are delivered out of order.
+    pub retransmit_server: Option<SocketAddr>,
+    /// Kernel or NIC receive timestamps to attach to every packet as
+    /// [`SequencedPacket::rx_timestamp`]; Linux only.
+    pub timestamping: TimestampMode,
+    /// Capture to write every received datagram to, including duplicates
+    /// and corrupt ones, with its receive timestamp or else the time it
+    /// was read.
+    pub capture: Option<Arc<PacketCapture>>,
 }
 
 impl Default for MulticastConfig {
@@ -32,19 +90,39 @@ impl Default for MulticastConfig {
             port: 14310,
             interface: Ipv4Addr::UNSPECIFIED,
             recv_buffer_size: 8 * 1024 * 1024,
+            busy_poll: None,
+            tos: None,
+            bind_device: None,
+            pool_capacity: 64,
+            checksum: false,
+            retransmit_server: None,
+            timestamping: TimestampMode::Off,
+            capture: None,
         }
     }
 }
 
+/// Size of the sequence number header at the start of each datagram.
+const SEQUENCE_HEADER_LEN: usize = 8;
+
+/// Largest UDP payload, used for fallback buffers.
+const MAX_DATAGRAM_LEN: usize = 65536;
+
 /// Packet with sequence number for arbitration.
 #[derive(Debug, Clone)]
 pub struct SequencedPacket {
     /// Sequence number.
     pub sequence: u64,
     /// Packet data (excluding sequence header).
+    ///
+    /// Usually a view into a pooled receive buffer, which is released
+    /// when the last clone is dropped.
     pub data: Bytes,
     /// Time when packet was received.
     pub recv_time: Instant,
+    /// Time the kernel or NIC received the datagram, if
+    /// [`MulticastConfig::timestamping`] is enabled.
+    pub rx_timestamp: Option<RxTimestamp>,
 }
 
 /// A/B feed arbitrator for deduplication.
@@ -135,22 +213,124 @@ impl FeedArbitrator {
     }
 }
 
+/// Buffer a datagram is received into.
+enum RecvBuffer {
+    /// Buffer borrowed from the receiver's pool.
+    Pooled(PooledBuffer),
+    /// Heap buffer used while the pool is exhausted.
+    Heap(Vec<u8>),
+}
+
+impl RecvBuffer {
+    /// Returns the region a datagram may be received into.
+    fn writable(&mut self) -> &mut [u8] {
+        match self {
+            Self::Pooled(buf) => buf.writable(),
+            Self::Heap(buf) => buf,
+        }
+    }
+
+    /// Returns the sequence number of a received datagram of `len` bytes,
+    /// or `None` if it is too short to carry one.
+    fn sequence(&mut self, len: usize) -> Option<u64> {
+        if len < SEQUENCE_HEADER_LEN {
+            return None;
+        }
+        let header = &self.writable()[..SEQUENCE_HEADER_LEN];
+        Some(u64::from_le_bytes(header.try_into().unwrap()))
+    }
+
+    /// Checks the CRC32C trailer of a received datagram of `len` bytes.
+    fn verify(&mut self, len: usize) -> Result<(), DecodeError> {
+        checksum::verify(&self.writable()[..len]).map(|_| ())
+    }
+
+    /// Converts a datagram of `len` bytes into its payload, without
+    /// copying.
+    fn into_payload(self, len: usize) -> Bytes {
+        let bytes = match self {
+            Self::Pooled(mut buf) => {
+                buf.set_len(len);
+                Bytes::from_owner(buf)
+            }
+            Self::Heap(mut buf) => {
+                buf.truncate(len);
+                Bytes::from(buf)
+            }
+        };
+        bytes.slice(SEQUENCE_HEADER_LEN..)
+    }
+}
+
 /// Multicast receiver with A/B feed arbitration.
 pub struct MulticastReceiver {
     socket_a: Arc<UdpSocket>,
     socket_b: Arc<UdpSocket>,
+    /// Socket NAKs are sent from and replayed packets received on.
+    retransmit: Option<(Arc<UdpSocket>, SocketAddr)>,
+    naks_sent: AtomicU64,
     arbitrator: Arc<RwLock<FeedArbitrator>>,
+    pool: BufferPool,
+    pool_misses: AtomicU64,
+    checksum: bool,
+    checksum_failures: AtomicU64,
+    timestamping: bool,
+    capture: Option<FeedCapture>,
+}
+
+/// Capture of the datagrams a receiver reads, with the destination each
+/// socket's datagrams are recorded as sent to.
+struct FeedCapture {
+    capture: Arc<PacketCapture>,
+    feed_a: SocketAddrV4,
+    feed_b: SocketAddrV4,
+    retransmit: SocketAddrV4,
+}
+
+impl FeedCapture {
+    fn record(
+        &self,
+        src: SocketAddr,
+        dst: SocketAddrV4,
+        datagram: &[u8],
+        rx_timestamp: Option<RxTimestamp>,
+    ) {
+        let SocketAddr::V4(src) = src else {
+            return;
+        };
+        let timestamp = rx_timestamp.map_or_else(|| Timestamp::now().as_nanos(), |ts| ts.nanos);
+        self.capture.udp(timestamp, src, dst, datagram);
+    }
 }
 
 impl MulticastReceiver {
     /// Creates a new multicast receiver.
     ///
+    /// Allocates a pool of [`MulticastConfig::pool_capacity`] receive
+    /// buffers.
+    ///
     /// # Arguments
     /// * `config` - Multicast configuration
     ///
     /// # Errors
     /// Returns IO error if socket creation or multicast join fails.
     pub async fn new(config: MulticastConfig) -> std::io::Result<Self> {
+        let pool = BufferPool::new(config.pool_capacity);
+        Self::with_pool(config, pool).await
+    }
+
+    /// Creates a new multicast receiver that receives into `pool`.
+    ///
+    /// Lets several receivers, or a receiver and the rest of the
+    /// application, share one set of buffers.
+    ///
+    /// # Arguments
+    /// * `config` - Multicast configuration
+    /// * `pool` - Pool to receive packets into
+    ///
+    /// # Errors
+    /// Returns IO error if socket creation or multicast join fails.
+    pub async fn with_pool(config: MulticastConfig, pool: BufferPool) -> std::io::Result<Self> {
         // Create and bind sockets
         let bind_addr: SocketAddr = (Ipv4Addr::UNSPECIFIED, config.port).into();
 
@@ -161,10 +341,53 @@ impl MulticastReceiver {
         socket_a.join_multicast_v4(config.feed_a_group, config.interface)?;
         socket_b.join_multicast_v4(config.feed_b_group, config.interface)?;
 
+        let feed_tuning = SocketTuning {
+            recv_buffer_size: Some(config.recv_buffer_size),
+            busy_poll: config.busy_poll,
+            bind_device: config.bind_device.as_deref(),
+            ..SocketTuning::default()
+        };
+        for socket in [&socket_a, &socket_b] {
+            feed_tuning.apply(SockRef::from(socket))?;
+            timestamp::enable(socket, config.timestamping)?;
+        }
+
+        let retransmit = match config.retransmit_server {
+            Some(server) => {
+                let socket = UdpSocket::bind((config.interface, 0)).await?;
+                SocketTuning {
+                    tos: config.tos,
+                    ..feed_tuning
+                }
+                .apply(SockRef::from(&socket))?;
+                timestamp::enable(&socket, config.timestamping)?;
+                Some((Arc::new(socket), server))
+            }
+            None => None,
+        };
+
+        let capture = config.capture.map(|capture| FeedCapture {
+            capture,
+            feed_a: SocketAddrV4::new(config.feed_a_group, config.port),
+            feed_b: SocketAddrV4::new(config.feed_b_group, config.port),
+            retransmit: match retransmit.as_ref().map(|(socket, _)| socket.local_addr()) {
+                Some(Ok(SocketAddr::V4(addr))) => addr,
+                _ => SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0),
+            },
+        });
+
         Ok(Self {
             socket_a: Arc::new(socket_a),
             socket_b: Arc::new(socket_b),
+            retransmit,
+            naks_sent: AtomicU64::new(0),
             arbitrator: Arc::new(RwLock::new(FeedArbitrator::new(10000))),
+            pool,
+            pool_misses: AtomicU64::new(0),
+            checksum: config.checksum,
+            checksum_failures: AtomicU64::new(0),
+            timestamping: config.timestamping != TimestampMode::Off,
+            capture,
         })
     }
 
@@ -173,58 +396,177 @@ impl MulticastReceiver {
     /// # Errors
     /// Returns IO error if receive fails.
     pub async fn recv(&self) -> std::io::Result<SequencedPacket> {
-        let mut buf_a = vec![0u8; 65536];
-        let mut buf_b = vec![0u8; 65536];
-
+        let mut buf_a = self.take_buffer();
+        let mut buf_b = self.take_buffer();
+        let mut buf_r = match self.retransmit {
+            Some(_) => self.take_buffer(),
+            None => RecvBuffer::Heap(Vec::new()),
+        };
+
+        // Duplicates, short and corrupt datagrams leave their buffer in
+        // place to be received into again.
         loop {
             tokio::select! {
-                result = self.socket_a.recv(&mut buf_a) => {
-                    let len = result?;
-                    if let Some(packet) = self.process_packet(&buf_a[..len]) {
-                        return Ok(packet);
+                result = timestamp::recv_from(&self.socket_a, buf_a.writable(), self.timestamping) => {
+                    let (len, src, rx_timestamp) = result?;
+                    if let Some(capture) = &self.capture {
+                        capture.record(src, capture.feed_a, &buf_a.writable()[..len], rx_timestamp);
+                    }
+                    if let Some((sequence, len)) = self.accept(&mut buf_a, len) {
+                        let buf = std::mem::replace(&mut buf_a, RecvBuffer::Heap(Vec::new()));
+                        return Ok(Self::packet(sequence, buf, len, rx_timestamp));
                     }
                 }
-                result = self.socket_b.recv(&mut buf_b) => {
-                    let len = result?;
-                    if let Some(packet) = self.process_packet(&buf_b[..len]) {
-                        return Ok(packet);
+                result = timestamp::recv_from(&self.socket_b, buf_b.writable(), self.timestamping) => {
+                    let (len, src, rx_timestamp) = result?;
+                    if let Some(capture) = &self.capture {
+                        capture.record(src, capture.feed_b, &buf_b.writable()[..len], rx_timestamp);
+                    }
+                    if let Some((sequence, len)) = self.accept(&mut buf_b, len) {
+                        let buf = std::mem::replace(&mut buf_b, RecvBuffer::Heap(Vec::new()));
+                        return Ok(Self::packet(sequence, buf, len, rx_timestamp));
+                    }
+                }
+                result = Self::recv_retransmit(self.retransmit.as_ref(), buf_r.writable(), self.timestamping) => {
+                    let (len, src, rx_timestamp) = result?;
+                    if let Some(capture) = &self.capture {
+                        capture.record(src, capture.retransmit, &buf_r.writable()[..len], rx_timestamp);
+                    }
+                    if let Some((sequence, len)) = self.accept(&mut buf_r, len) {
+                        let buf = std::mem::replace(&mut buf_r, RecvBuffer::Heap(Vec::new()));
+                        return Ok(Self::packet(sequence, buf, len, rx_timestamp));
                     }
                 }
             }
         }
     }
 
-    /// Processes a received packet, returning it if it should be processed.
-    fn process_packet(&self, data: &[u8]) -> Option<SequencedPacket> {
-        // Extract sequence number from packet header (first 8 bytes)
-        if data.len() < 8 {
-            return None;
+    /// Receives a replayed packet, or never completes without a retransmit
+    /// server.
+    async fn recv_retransmit(
+        retransmit: Option<&(Arc<UdpSocket>, SocketAddr)>,
+        buf: &mut [u8],
+        timestamping: bool,
+    ) -> std::io::Result<(usize, SocketAddr, Option<RxTimestamp>)> {
+        match retransmit {
+            Some((socket, _)) => timestamp::recv_from(socket, buf, timestamping).await,
+            None => std::future::pending().await,
         }
+    }
 
-        let seq = u64::from_le_bytes(data[0..8].try_into().unwrap());
+    /// Sends a NAK for the inclusive range `start..=end`, if a retransmit
+    /// server is configured.
+    fn send_nak(&self, start: u64, end: u64) {
+        let Some((socket, server)) = &self.retransmit else {
+            return;
+        };
+        let count = (end - start + 1).min(u64::from(u32::MAX)) as u32;
+        match socket.try_send_to(&encode_nak(start, count), *server) {
+            Ok(_) => {
+                self.naks_sent.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(err) => tracing::warn!("Failed to send NAK for {} - {}: {}", start, end, err),
+        }
+    }
+
+    /// Takes a receive buffer from the pool, or allocates one if it is
+    /// empty.
+    fn take_buffer(&self) -> RecvBuffer {
+        match self.pool.acquire_owned() {
+            Some(buf) => RecvBuffer::Pooled(buf),
+            None => {
+                self.pool_misses.fetch_add(1, Ordering::Relaxed);
+                RecvBuffer::Heap(vec![0u8; MAX_DATAGRAM_LEN])
+            }
+        }
+    }
+
+    /// Returns the sequence number and length, without the trailer, of a
+    /// received datagram if it should be processed.
+    fn accept(&self, buf: &mut RecvBuffer, len: usize) -> Option<(u64, usize)> {
+        let len = if self.checksum {
+            if let Err(err) = buf.verify(len) {
+                self.checksum_failures.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!("Dropped corrupt datagram: {}", err);
+                return None;
+            }
+            len - TRAILER_LENGTH
+        } else {
+            len
+        };
+        let seq = buf.sequence(len)?;
 
         let mut arbitrator = self.arbitrator.write();
         if arbitrator.should_process(seq) {
             // Check for gaps
             if let Some((start, end)) = arbitrator.check_gap(seq) {
                 tracing::warn!("Detected gap: {} - {}", start, end);
+                self.send_nak(start, end);
             }
-
-            Some(SequencedPacket {
-                sequence: seq,
-                data: Bytes::copy_from_slice(&data[8..]),
-                recv_time: Instant::now(),
-            })
+            Some((seq, len))
         } else {
             None // Duplicate, already processed from other feed
         }
     }
 
+    /// Builds the packet for an accepted datagram, taking ownership of its
+    /// buffer.
+    fn packet(
+        sequence: u64,
+        buf: RecvBuffer,
+        len: usize,
+        rx_timestamp: Option<RxTimestamp>,
+    ) -> SequencedPacket {
+        SequencedPacket {
+            sequence,
+            data: buf.into_payload(len),
+            recv_time: Instant::now(),
+            rx_timestamp,
+        }
+    }
+
     /// Returns a reference to the arbitrator for statistics.
     #[must_use]
     pub fn arbitrator(&self) -> &Arc<RwLock<FeedArbitrator>> {
         &self.arbitrator
     }
+
+    /// Returns the pool packets are received into.
+    #[must_use]
+    pub fn pool(&self) -> &BufferPool {
+        &self.pool
+    }
+
+    /// Returns how many receive buffers had to be heap-allocated because
+    /// the pool was empty.
+    ///
+    /// A growing count means packets are held downstream for longer than
+    /// the pool allows for; raise [`MulticastConfig::pool_capacity`].
+    #[must_use]
+    pub fn pool_misses(&self) -> u64 {
+        self.pool_misses.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many NAKs were sent to the retransmit server.
+    #[must_use]
+    pub fn naks_sent(&self) -> u64 {
+        self.naks_sent.load(Ordering::Relaxed)
+    }
+
+    /// Returns the local address replayed packets are received on, if a
+    /// retransmit server is configured.
+    pub fn retransmit_addr(&self) -> Option<std::io::Result<SocketAddr>> {
+        self.retransmit
+            .as_ref()
+            .map(|(socket, _)| socket.local_addr())
+    }
+
+    /// Returns how many datagrams were dropped because their checksum did
+    /// not match.
+    #[must_use]
+    pub fn checksum_failures(&self) -> u64 {
+        self.checksum_failures.load(Ordering::Relaxed)
+    }
 }
 
 #[cfg(test)]
@@ -273,6 +615,218 @@ mod tests {
         assert_eq!(arb.highest_sequence(), 10);
     }
 
+    #[test]
+    fn test_pooled_payload_returns_buffer_on_drop() {
+        let pool = BufferPool::new(1);
+        let mut buf = RecvBuffer::Pooled(pool.acquire_owned().unwrap());
+        buf.writable()[..8].copy_from_slice(&7u64.to_le_bytes());
+        buf.writable()[8..11].copy_from_slice(b"abc");
+
+        assert_eq!(buf.sequence(11), Some(7));
+        assert_eq!(buf.sequence(7), None);
+
+        let payload = buf.into_payload(11);
+        assert_eq!(&payload[..], b"abc");
+        assert_eq!(pool.available(), 0);
+
+        let clone = payload.clone();
+        drop(payload);
+        assert_eq!(pool.available(), 0);
+        drop(clone);
+        assert_eq!(pool.available(), 1);
+    }
+
+    #[test]
+    fn test_heap_payload() {
+        let mut buf = RecvBuffer::Heap(vec![0u8; MAX_DATAGRAM_LEN]);
+        buf.writable()[..8].copy_from_slice(&3u64.to_le_bytes());
+        buf.writable()[8..10].copy_from_slice(b"hi");
+
+        assert_eq!(buf.sequence(10), Some(3));
+        assert_eq!(&buf.into_payload(10)[..], b"hi");
+    }
+
+    #[test]
+    fn test_checksummed_payload() {
+        let mut datagram = 9u64.to_le_bytes().to_vec();
+        datagram.extend_from_slice(b"data");
+        datagram.extend_from_slice(&checksum::trailer(&datagram));
+
+        let mut buf = RecvBuffer::Heap(vec![0u8; MAX_DATAGRAM_LEN]);
+        buf.writable()[..datagram.len()].copy_from_slice(&datagram);
+        assert_eq!(buf.verify(datagram.len()), Ok(()));
+        assert_eq!(buf.sequence(12), Some(9));
+        assert_eq!(&buf.into_payload(12)[..], b"data");
+
+        let mut corrupt = RecvBuffer::Heap(datagram.clone());
+        corrupt.writable()[8] ^= 1;
+        assert!(matches!(
+            corrupt.verify(datagram.len()),
+            Err(DecodeError::ChecksumMismatch { .. })
+        ));
+        assert!(RecvBuffer::Heap(vec![0u8; 3]).verify(3).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gap_is_recovered_from_retransmit_server() {
+        use super::super::retransmit::{
+            RetransmitBuffer, RetransmitServer, RetransmitServerConfig,
+        };
+
+        let buffer = Arc::new(RetransmitBuffer::new(8));
+        for seq in 1..=3u64 {
+            buffer.record(seq, Bytes::from(vec![seq as u8; 4]));
+        }
+        let config = RetransmitServerConfig {
+            bind_addr: "127.0.0.1:0".parse().unwrap(),
+            ..RetransmitServerConfig::default()
+        };
+        let server = RetransmitServer::bind(config, buffer).await.unwrap();
+        let server_addr = server.local_addr().unwrap();
+        tokio::spawn(async move { server.run().await });
+
+        // Feeds on loopback unicast sockets stand in for the multicast groups.
+        let socket_a = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let feed_addr = socket_a.local_addr().unwrap();
+        let receiver = MulticastReceiver {
+            socket_a: Arc::new(socket_a),
+            socket_b: Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap()),
+            retransmit: Some((
+                Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap()),
+                server_addr,
+            )),
+            naks_sent: AtomicU64::new(0),
+            arbitrator: Arc::new(RwLock::new(FeedArbitrator::new(100))),
+            pool: BufferPool::new(4),
+            pool_misses: AtomicU64::new(0),
+            checksum: false,
+            checksum_failures: AtomicU64::new(0),
+            timestamping: false,
+            capture: None,
+        };
+
+        let feed = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        for seq in [1u64, 3] {
+            let mut datagram = seq.to_le_bytes().to_vec();
+            datagram.extend_from_slice(&[seq as u8; 4]);
+            feed.send_to(&datagram, feed_addr).await.unwrap();
+        }
+        let mut sequences = Vec::new();
+        for _ in 0..3 {
+            let packet = receiver.recv().await.unwrap();
+            assert_eq!(&packet.data[..], &[packet.sequence as u8; 4]);
+            sequences.push(packet.sequence);
+        }
+        // Packet 2 is replayed after the packet that revealed the gap.
+        assert_eq!(sequences, vec![1, 3, 2]);
+        assert_eq!(receiver.naks_sent(), 1);
+    }
+
+    /// Builds a receiver whose feed A is a loopback unicast socket.
+    async fn loopback_receiver(
+        capture: Option<Arc<PacketCapture>>,
+    ) -> (MulticastReceiver, SocketAddr) {
+        let socket_a = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let feed_addr = socket_a.local_addr().unwrap();
+        let SocketAddr::V4(feed_a) = feed_addr else {
+            unreachable!("bound to IPv4");
+        };
+        let receiver = MulticastReceiver {
+            socket_a: Arc::new(socket_a),
+            socket_b: Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap()),
+            retransmit: None,
+            naks_sent: AtomicU64::new(0),
+            arbitrator: Arc::new(RwLock::new(FeedArbitrator::new(100))),
+            pool: BufferPool::new(4),
+            pool_misses: AtomicU64::new(0),
+            checksum: false,
+            checksum_failures: AtomicU64::new(0),
+            timestamping: false,
+            capture: capture.map(|capture| FeedCapture {
+                capture,
+                feed_a,
+                feed_b: SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0),
+                retransmit: SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0),
+            }),
+        };
+        (receiver, feed_addr)
+    }
+
+    #[tokio::test]
+    async fn test_captured_feed_replays_into_receiver() {
+        use crate::capture::{PcapReader, ReplayConfig, replay_udp};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("feed.pcap");
+        let capture = Arc::new(PacketCapture::create(&path).unwrap());
+        let (receiver, feed_addr) = loopback_receiver(Some(capture.clone())).await;
+
+        // Sequence 2 is sent twice; the capture keeps the duplicate.
+        let feed = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        for seq in [1u64, 2, 2, 3] {
+            let mut datagram = seq.to_le_bytes().to_vec();
+            datagram.extend_from_slice(&[seq as u8; 3]);
+            feed.send_to(&datagram, feed_addr).await.unwrap();
+        }
+        for seq in 1..=3 {
+            assert_eq!(receiver.recv().await.unwrap().sequence, seq);
+        }
+        drop(receiver);
+        drop(capture);
+
+        let (replayed, replay_addr) = loopback_receiver(None).await;
+        let SocketAddr::V4(captured) = feed_addr else {
+            unreachable!("bound to IPv4");
+        };
+        let config = ReplayConfig {
+            speed: 0.0,
+            route: std::collections::HashMap::from([(captured, replay_addr)]),
+        };
+        let reader = PcapReader::open(&path).unwrap();
+        assert_eq!(replay_udp(reader, &feed, &config).await.unwrap(), 4);
+        for seq in 1..=3 {
+            let packet = replayed.recv().await.unwrap();
+            assert_eq!(packet.sequence, seq);
+            assert_eq!(&packet.data[..], &[seq as u8; 3]);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_packets_carry_rx_timestamps() {
+        let socket_a = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        timestamp::enable(&socket_a, TimestampMode::Software).unwrap();
+        let feed_addr = socket_a.local_addr().unwrap();
+        let receiver = MulticastReceiver {
+            socket_a: Arc::new(socket_a),
+            socket_b: Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap()),
+            retransmit: None,
+            naks_sent: AtomicU64::new(0),
+            arbitrator: Arc::new(RwLock::new(FeedArbitrator::new(100))),
+            pool: BufferPool::new(2),
+            pool_misses: AtomicU64::new(0),
+            checksum: false,
+            checksum_failures: AtomicU64::new(0),
+            timestamping: true,
+            capture: None,
+        };
+
+        let feed = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut seq = 0u64;
+        let stamp = timestamp::until_stamped(async || {
+            seq += 1;
+            let mut datagram = seq.to_le_bytes().to_vec();
+            datagram.extend_from_slice(b"tick");
+            feed.send_to(&datagram, feed_addr).await.unwrap();
+            let packet = receiver.recv().await.unwrap();
+            assert_eq!(&packet.data[..], b"tick");
+            packet.rx_timestamp
+        })
+        .await
+        .expect("software timestamp");
+        assert_eq!(stamp.source, crate::timestamp::TimestampSource::Software);
+    }
+
     #[test]
     fn test_arbitrator_reset() {
         let mut arb = FeedArbitrator::new(100);
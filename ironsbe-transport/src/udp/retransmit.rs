@@ -0,0 +1,285 @@
+//! NAK-based retransmission for multicast feeds.
+//!
+//! A receiver that detects a gap sends a NAK, a unicast datagram naming the
+//! missing range, to a [`RetransmitServer`]. The server replays what its
+//! [`RetransmitSource`] still holds to the requester, in the feed's own
+//! datagram format: an 8-byte little-endian sequence number, the payload
+//! and, if enabled, a CRC32C trailer. [`MulticastReceiver`] sends NAKs
+//! itself when [`MulticastConfig::retransmit_server`] is set, and merges
+//! the replayed packets through its arbitrator.
+//!
+//! [`MulticastReceiver`]: super::MulticastReceiver
+//! [`MulticastConfig::retransmit_server`]: super::MulticastConfig::retransmit_server
+
+use bytes::Bytes;
+use ironsbe_core::checksum;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::net::UdpSocket;
+
+/// Length of a NAK datagram: the first missing sequence number (`u64`)
+/// and the number of missing packets (`u32`), little-endian.
+pub const NAK_LENGTH: usize = 12;
+
+/// Default most packets replayed for one NAK.
+pub const DEFAULT_MAX_NAK_COUNT: u32 = 1000;
+
+/// Encodes a NAK for `count` packets starting at `from`.
+#[must_use]
+pub fn encode_nak(from: u64, count: u32) -> [u8; NAK_LENGTH] {
+    let mut nak = [0u8; NAK_LENGTH];
+    nak[..8].copy_from_slice(&from.to_le_bytes());
+    nak[8..].copy_from_slice(&count.to_le_bytes());
+    nak
+}
+
+/// Decodes a NAK into the first missing sequence number and the count, or
+/// `None` if the datagram is not a NAK.
+#[must_use]
+pub fn decode_nak(datagram: &[u8]) -> Option<(u64, u32)> {
+    if datagram.len() != NAK_LENGTH {
+        return None;
+    }
+    let from = u64::from_le_bytes(datagram[..8].try_into().unwrap());
+    let count = u32::from_le_bytes(datagram[8..].try_into().unwrap());
+    Some((from, count))
+}
+
+/// Packets a [`RetransmitServer`] replays from.
+pub trait RetransmitSource: Send + Sync + 'static {
+    /// Returns the packets `from..from + count` still available, in
+    /// sequence order, as sequence number and payload.
+    fn fetch(&self, from: u64, count: u32) -> Vec<(u64, Bytes)>;
+}
+
+/// In-memory journal of the last published packets.
+///
+/// The publisher records every packet it sends on the feed; the oldest
+/// are dropped once `capacity` is reached.
+#[derive(Debug)]
+pub struct RetransmitBuffer {
+    packets: Mutex<VecDeque<(u64, Bytes)>>,
+    capacity: usize,
+}
+
+impl RetransmitBuffer {
+    /// Creates a buffer keeping the last `capacity` packets.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            packets: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Records a published packet. Sequence numbers must increase.
+    pub fn record(&self, sequence: u64, payload: Bytes) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut packets = self.packets.lock();
+        if packets.len() == self.capacity {
+            packets.pop_front();
+        }
+        packets.push_back((sequence, payload));
+    }
+
+    /// Returns the number of packets kept.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.packets.lock().len()
+    }
+
+    /// Returns true if no packet is kept.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.packets.lock().is_empty()
+    }
+}
+
+impl RetransmitSource for RetransmitBuffer {
+    fn fetch(&self, from: u64, count: u32) -> Vec<(u64, Bytes)> {
+        let end = from.saturating_add(u64::from(count));
+        let packets = self.packets.lock();
+        let start = packets.partition_point(|(seq, _)| *seq < from);
+        packets
+            .range(start..)
+            .take_while(|(seq, _)| *seq < end)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Configuration for a [`RetransmitServer`].
+#[derive(Debug, Clone)]
+pub struct RetransmitServerConfig {
+    /// Address NAKs are received on.
+    pub bind_addr: SocketAddr,
+    /// Most packets replayed for one NAK; longer ranges are truncated.
+    pub max_count: u32,
+    /// Append a CRC32C trailer to replayed datagrams, as on the feed.
+    pub checksum: bool,
+}
+
+impl Default for RetransmitServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: (Ipv4Addr::UNSPECIFIED, 14320).into(),
+            max_count: DEFAULT_MAX_NAK_COUNT,
+            checksum: false,
+        }
+    }
+}
+
+/// Answers NAKs by replaying packets to the requester.
+pub struct RetransmitServer<S: RetransmitSource = RetransmitBuffer> {
+    socket: UdpSocket,
+    source: Arc<S>,
+    max_count: u32,
+    checksum: bool,
+    requests: AtomicU64,
+    replayed: AtomicU64,
+}
+
+impl<S: RetransmitSource> RetransmitServer<S> {
+    /// Binds a server replaying from `source`.
+    ///
+    /// # Errors
+    /// Returns IO error if binding fails.
+    pub async fn bind(config: RetransmitServerConfig, source: Arc<S>) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(config.bind_addr).await?;
+        Ok(Self {
+            socket,
+            source,
+            max_count: config.max_count,
+            checksum: config.checksum,
+            requests: AtomicU64::new(0),
+            replayed: AtomicU64::new(0),
+        })
+    }
+
+    /// Returns the local address NAKs are received on.
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Returns the source packets are replayed from.
+    #[must_use]
+    pub fn source(&self) -> &Arc<S> {
+        &self.source
+    }
+
+    /// Answers NAKs until an IO error occurs.
+    ///
+    /// # Errors
+    /// Returns IO error if receiving or sending fails.
+    pub async fn run(&self) -> std::io::Result<()> {
+        loop {
+            self.serve_one().await?;
+        }
+    }
+
+    /// Waits for one NAK and replays the packets still available.
+    ///
+    /// Datagrams that are not NAKs are ignored.
+    ///
+    /// # Returns
+    /// Number of packets replayed.
+    ///
+    /// # Errors
+    /// Returns IO error if receiving or sending fails.
+    pub async fn serve_one(&self) -> std::io::Result<usize> {
+        let mut nak = [0u8; NAK_LENGTH + 1];
+        let (len, requester) = self.socket.recv_from(&mut nak).await?;
+        let Some((from, count)) = decode_nak(&nak[..len]) else {
+            tracing::debug!("Ignored malformed NAK from {}", requester);
+            return Ok(0);
+        };
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let packets = self.source.fetch(from, count.min(self.max_count));
+        for (sequence, payload) in &packets {
+            let mut datagram = Vec::with_capacity(8 + payload.len() + checksum::TRAILER_LENGTH);
+            datagram.extend_from_slice(&sequence.to_le_bytes());
+            datagram.extend_from_slice(payload);
+            if self.checksum {
+                let trailer = checksum::trailer(&datagram);
+                datagram.extend_from_slice(&trailer);
+            }
+            self.socket.send_to(&datagram, requester).await?;
+        }
+        self.replayed
+            .fetch_add(packets.len() as u64, Ordering::Relaxed);
+        Ok(packets.len())
+    }
+
+    /// Returns how many NAKs were answered.
+    #[must_use]
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many packets were replayed.
+    #[must_use]
+    pub fn replayed(&self) -> u64 {
+        self.replayed.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nak_round_trip() {
+        let nak = encode_nak(42, 7);
+        assert_eq!(decode_nak(&nak), Some((42, 7)));
+        assert_eq!(decode_nak(&nak[..NAK_LENGTH - 1]), None);
+    }
+
+    #[test]
+    fn test_buffer_keeps_the_last_packets() {
+        let buffer = RetransmitBuffer::new(3);
+        for seq in 1..=5 {
+            buffer.record(seq, Bytes::from(vec![seq as u8]));
+        }
+        assert_eq!(buffer.len(), 3);
+        let seqs =
+            |packets: Vec<(u64, Bytes)>| packets.into_iter().map(|(s, _)| s).collect::<Vec<_>>();
+        assert_eq!(seqs(buffer.fetch(1, 10)), vec![3, 4, 5]);
+        assert_eq!(seqs(buffer.fetch(4, 1)), vec![4]);
+        assert!(buffer.fetch(6, 2).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_server_replays_requested_range() {
+        let buffer = Arc::new(RetransmitBuffer::new(16));
+        for seq in 1..=10 {
+            buffer.record(seq, Bytes::from(format!("packet{seq}")));
+        }
+        let config = RetransmitServerConfig {
+            bind_addr: "127.0.0.1:0".parse().unwrap(),
+            max_count: 2,
+            checksum: true,
+        };
+        let server = RetransmitServer::bind(config, buffer).await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        client
+            .send_to(&encode_nak(4, 5), server.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        // The range is truncated to `max_count`.
+        assert_eq!(server.serve_one().await.unwrap(), 2);
+        let mut buf = [0u8; 64];
+        for seq in 4..=5u64 {
+            let len = client.recv(&mut buf).await.unwrap();
+            let datagram = checksum::verify(&buf[..len]).unwrap();
+            assert_eq!(&datagram[..8], &seq.to_le_bytes());
+            assert_eq!(&datagram[8..], format!("packet{seq}").as_bytes());
+        }
+        assert_eq!((server.requests(), server.replayed()), (1, 2));
+    }
+}
@@ -1,12 +1,19 @@
 //! UDP unicast sender and receiver.
 
+use crate::error::TransportError;
+use crate::sockopt;
+use crate::timestamp::{self, RxTimestamp, TimestampMode};
+use ironsbe_core::checksum;
+use socket2::SockRef;
 use std::net::SocketAddr;
+use std::time::Duration;
 use tokio::net::UdpSocket;
 
 /// UDP unicast sender.
 pub struct UdpSender {
     socket: UdpSocket,
     target: SocketAddr,
+    checksum: bool,
 }
 
 impl UdpSender {
@@ -20,7 +27,19 @@ impl UdpSender {
     /// Returns IO error if binding fails.
     pub async fn bind(local_addr: SocketAddr, target: SocketAddr) -> std::io::Result<Self> {
         let socket = UdpSocket::bind(local_addr).await?;
-        Ok(Self { socket, target })
+        Ok(Self {
+            socket,
+            target,
+            checksum: false,
+        })
+    }
+
+    /// Appends a CRC32C trailer to every datagram; the receiver must
+    /// enable checksums too.
+    #[must_use]
+    pub fn with_checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
     }
 
     /// Sends data to the target address.
@@ -34,6 +53,12 @@ impl UdpSender {
     /// # Errors
     /// Returns IO error if send fails.
     pub async fn send(&self, data: &[u8]) -> std::io::Result<usize> {
+        if self.checksum {
+            let mut datagram = Vec::with_capacity(data.len() + checksum::TRAILER_LENGTH);
+            datagram.extend_from_slice(data);
+            datagram.extend_from_slice(&checksum::trailer(data));
+            return self.socket.send_to(&datagram, self.target).await;
+        }
         self.socket.send_to(data, self.target).await
     }
 
@@ -47,12 +72,40 @@ impl UdpSender {
     pub fn target_addr(&self) -> SocketAddr {
         self.target
     }
+
+    /// Sets the send buffer size (`SO_SNDBUF`).
+    ///
+    /// # Errors
+    /// Returns IO error if setting fails.
+    pub fn set_send_buffer_size(&self, size: usize) -> std::io::Result<()> {
+        SockRef::from(&self.socket).set_send_buffer_size(size)
+    }
+
+    /// Sets `IP_TOS` (`IPV6_TCLASS` on IPv6) for every datagram sent; see
+    /// [`sockopt::dscp_to_tos`] for DSCP markings.
+    ///
+    /// # Errors
+    /// Returns IO error if setting fails.
+    pub fn set_tos(&self, tos: u8) -> std::io::Result<()> {
+        sockopt::set_tos(&SockRef::from(&self.socket), tos)
+    }
+
+    /// Sends only through the interface named `device`
+    /// (`SO_BINDTODEVICE`). Linux only.
+    ///
+    /// # Errors
+    /// Returns IO error if setting fails.
+    pub fn bind_device(&self, device: &str) -> std::io::Result<()> {
+        sockopt::bind_device(&SockRef::from(&self.socket), device)
+    }
 }
 
 /// UDP unicast receiver.
 pub struct UdpReceiver {
     socket: UdpSocket,
     buffer: Vec<u8>,
+    checksum: bool,
+    timestamping: bool,
 }
 
 impl UdpReceiver {
@@ -69,19 +122,65 @@ impl UdpReceiver {
         Ok(Self {
             socket,
             buffer: vec![0u8; buffer_size],
+            checksum: false,
+            timestamping: false,
         })
     }
 
+    /// Verifies and strips the CRC32C trailer of every datagram.
+    #[must_use]
+    pub fn with_checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
+    }
+
+    /// Asks the kernel to timestamp every received datagram, read back
+    /// with [`recv_with_timestamp`](Self::recv_with_timestamp).
+    ///
+    /// # Errors
+    /// Returns IO error if the socket rejects the timestamping options,
+    /// or on platforms other than Linux.
+    pub fn with_timestamping(mut self, mode: TimestampMode) -> std::io::Result<Self> {
+        timestamp::enable(&self.socket, mode)?;
+        self.timestamping = mode != TimestampMode::Off;
+        Ok(self)
+    }
+
     /// Receives data from any sender.
     ///
     /// # Returns
-    /// Tuple of (data slice, sender address).
+    /// Tuple of (data slice, sender address). With checksums enabled the
+    /// slice excludes the trailer.
     ///
     /// # Errors
-    /// Returns IO error if receive fails.
+    /// Returns IO error if receive fails, or an `InvalidData` error wrapping
+    /// [`TransportError::Decode`] if the datagram fails its checksum.
     pub async fn recv(&mut self) -> std::io::Result<(&[u8], SocketAddr)> {
-        let (len, addr) = self.socket.recv_from(&mut self.buffer).await?;
-        Ok((&self.buffer[..len], addr))
+        let (data, addr, _) = self.recv_with_timestamp().await?;
+        Ok((data, addr))
+    }
+
+    /// Receives data from any sender, with the time the kernel or NIC
+    /// received it.
+    ///
+    /// The timestamp is `None` unless timestamping was enabled with
+    /// [`with_timestamping`](Self::with_timestamping).
+    ///
+    /// # Errors
+    /// As for [`recv`](Self::recv).
+    pub async fn recv_with_timestamp(
+        &mut self,
+    ) -> std::io::Result<(&[u8], SocketAddr, Option<RxTimestamp>)> {
+        let (len, addr, rx_timestamp) =
+            timestamp::recv_from(&self.socket, &mut self.buffer, self.timestamping).await?;
+        let datagram = &self.buffer[..len];
+        if self.checksum {
+            let message = checksum::verify(datagram).map_err(|err| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, TransportError::from(err))
+            })?;
+            return Ok((message, addr, rx_timestamp));
+        }
+        Ok((datagram, addr, rx_timestamp))
     }
 
     /// Returns the local address.
@@ -89,14 +188,31 @@ impl UdpReceiver {
         self.socket.local_addr()
     }
 
-    /// Sets the receive buffer size.
+    /// Sets the receive buffer size (`SO_RCVBUF`). The kernel may clamp
+    /// it to `net.core.rmem_max`.
+    ///
+    /// # Errors
+    /// Returns IO error if setting fails.
+    pub fn set_recv_buffer_size(&self, size: usize) -> std::io::Result<()> {
+        SockRef::from(&self.socket).set_recv_buffer_size(size)
+    }
+
+    /// Sets `SO_BUSY_POLL`: how long reads spin on the device queue
+    /// before sleeping. Linux only.
     ///
     /// # Errors
     /// Returns IO error if setting fails.
-    pub fn set_recv_buffer_size(&self, _size: usize) -> std::io::Result<()> {
-        self.socket.set_broadcast(true)?;
-        // Note: actual buffer size setting requires platform-specific code
-        Ok(())
+    pub fn set_busy_poll(&self, duration: Duration) -> std::io::Result<()> {
+        sockopt::set_busy_poll(&SockRef::from(&self.socket), duration)
+    }
+
+    /// Receives only from the interface named `device`
+    /// (`SO_BINDTODEVICE`). Linux only.
+    ///
+    /// # Errors
+    /// Returns IO error if setting fails.
+    pub fn bind_device(&self, device: &str) -> std::io::Result<()> {
+        sockopt::bind_device(&SockRef::from(&self.socket), device)
     }
 }
 
@@ -148,6 +264,88 @@ mod tests {
         assert_eq!(from_addr, sender.local_addr().unwrap());
     }
 
+    #[tokio::test]
+    async fn test_udp_checksum() {
+        let receiver_addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let mut receiver = UdpReceiver::bind(receiver_addr, 1024)
+            .await
+            .unwrap()
+            .with_checksum(true);
+        let target = receiver.local_addr().unwrap();
+        let local: SocketAddr = "127.0.0.1:0".parse().unwrap();
+
+        let sender = UdpSender::bind(local, target)
+            .await
+            .unwrap()
+            .with_checksum(true);
+        assert_eq!(sender.send(b"checked").await.unwrap(), 11);
+        let (received, _) = receiver.recv().await.unwrap();
+        assert_eq!(received, b"checked");
+
+        // A datagram without a valid trailer surfaces as a decode error.
+        let plain = UdpSender::bind(local, target).await.unwrap();
+        plain.send(b"unchecked").await.unwrap();
+        let err = receiver.recv().await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(matches!(
+            err.get_ref()
+                .and_then(|inner| inner.downcast_ref::<TransportError>()),
+            Some(TransportError::Decode(_))
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_udp_receiver_timestamps() {
+        let receiver_addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let mut receiver = UdpReceiver::bind(receiver_addr, 1024)
+            .await
+            .unwrap()
+            .with_checksum(true)
+            .with_timestamping(TimestampMode::Software)
+            .unwrap();
+        let local: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let sender = UdpSender::bind(local, receiver.local_addr().unwrap())
+            .await
+            .unwrap()
+            .with_checksum(true);
+
+        let stamp = crate::timestamp::until_stamped(async || {
+            sender.send(b"timed").await.unwrap();
+            let (received, from, stamp) = receiver.recv_with_timestamp().await.unwrap();
+            assert_eq!(received, b"timed");
+            assert_eq!(from, sender.local_addr().unwrap());
+            stamp
+        })
+        .await;
+        assert!(stamp.is_some());
+
+        // Without timestamping the datagram arrives bare.
+        let mut plain = UdpReceiver::bind(receiver_addr, 1024).await.unwrap();
+        UdpSender::bind(local, plain.local_addr().unwrap())
+            .await
+            .unwrap()
+            .send(b"plain")
+            .await
+            .unwrap();
+        let (received, _, stamp) = plain.recv_with_timestamp().await.unwrap();
+        assert_eq!(received, b"plain");
+        assert_eq!(stamp, None);
+    }
+
+    #[tokio::test]
+    async fn test_udp_sender_socket_options() {
+        let local: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let sender = UdpSender::bind(local, "127.0.0.1:9999".parse().unwrap())
+            .await
+            .unwrap();
+        sender.set_tos(sockopt::dscp_to_tos(34)).unwrap();
+        sender.set_send_buffer_size(64 * 1024).unwrap();
+        let sock = SockRef::from(&sender.socket);
+        assert_eq!(sock.tos_v4().unwrap(), 34 << 2);
+        assert!(sock.send_buffer_size().unwrap() >= 64 * 1024);
+    }
+
     #[tokio::test]
     async fn test_udp_receiver_set_buffer_size() {
         let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
@@ -155,5 +353,7 @@ mod tests {
 
         let result = receiver.set_recv_buffer_size(4096);
         assert!(result.is_ok());
+        let size = SockRef::from(&receiver.socket).recv_buffer_size().unwrap();
+        assert!(size >= 4096);
     }
 }
@@ -0,0 +1,521 @@
+//! WebSocket transport.
+//!
+//! Carries SBE messages in binary WebSocket messages, one SBE message per
+//! WebSocket message, so browser dashboards and clients behind HTTP-only
+//! firewalls can consume the same streams as raw TCP peers.  WebSocket
+//! framing replaces the length prefix; the frame limits of the TCP backend
+//! apply unchanged.
+//!
+//! Text messages are a protocol violation.  Pings are answered and pongs
+//! ignored.
+
+use crate::limits::FrameLimits;
+use crate::traits;
+use bytes::{Bytes, BytesMut};
+use futures::stream::FuturesUnordered;
+use futures::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::error::ProtocolError;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+
+/// How long a WebSocket opening handshake may take by default.
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Configuration for a WebSocket listener.
+#[derive(Debug, Clone)]
+pub struct WsServerConfig {
+    /// Address to bind to.
+    pub bind_addr: SocketAddr,
+    /// Maximum frame size in bytes.
+    pub max_frame_size: usize,
+    /// Maximum frame sizes for individual template ids, overriding
+    /// [`max_frame_size`](Self::max_frame_size).
+    pub template_max_frame_sizes: HashMap<u16, usize>,
+    /// Enable TCP_NODELAY.
+    pub tcp_nodelay: bool,
+    /// How long a client may take to complete the opening handshake.
+    pub handshake_timeout: Duration,
+}
+
+impl Default for WsServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: "0.0.0.0:9080".parse().unwrap(),
+            max_frame_size: 64 * 1024,
+            template_max_frame_sizes: HashMap::new(),
+            tcp_nodelay: true,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+        }
+    }
+}
+
+impl From<SocketAddr> for WsServerConfig {
+    /// Creates a config with default tunables bound to `addr`.
+    fn from(addr: SocketAddr) -> Self {
+        Self::new(addr)
+    }
+}
+
+impl WsServerConfig {
+    /// Creates a new listener config with the specified bind address.
+    #[must_use]
+    pub fn new(bind_addr: SocketAddr) -> Self {
+        Self {
+            bind_addr,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the maximum frame size.
+    #[must_use]
+    pub fn max_frame_size(mut self, size: usize) -> Self {
+        self.max_frame_size = size;
+        self
+    }
+
+    /// Sets the maximum frame size for messages with `template_id`.
+    #[must_use]
+    pub fn template_max_frame_size(mut self, template_id: u16, size: usize) -> Self {
+        self.template_max_frame_sizes.insert(template_id, size);
+        self
+    }
+
+    /// Returns the frame limits enforced on connections.
+    #[must_use]
+    pub fn frame_limits(&self) -> FrameLimits {
+        limits(self.max_frame_size, &self.template_max_frame_sizes)
+    }
+
+    /// Sets TCP_NODELAY.
+    #[must_use]
+    pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.tcp_nodelay = enabled;
+        self
+    }
+
+    /// Sets how long a client may take to complete the opening handshake.
+    #[must_use]
+    pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+}
+
+/// Configuration for a WebSocket client connection.
+#[derive(Debug, Clone)]
+pub struct WsClientConfig {
+    /// Server address to connect to.
+    pub server_addr: SocketAddr,
+    /// Request path sent in the opening handshake.
+    pub path: String,
+    /// Connection timeout, covering the opening handshake.
+    pub connect_timeout: Duration,
+    /// Maximum frame size in bytes.
+    pub max_frame_size: usize,
+    /// Maximum frame sizes for individual template ids, overriding
+    /// [`max_frame_size`](Self::max_frame_size).
+    pub template_max_frame_sizes: HashMap<u16, usize>,
+    /// Enable TCP_NODELAY.
+    pub tcp_nodelay: bool,
+}
+
+impl Default for WsClientConfig {
+    fn default() -> Self {
+        Self {
+            server_addr: "127.0.0.1:9080".parse().unwrap(),
+            path: "/".to_string(),
+            connect_timeout: Duration::from_secs(5),
+            max_frame_size: 64 * 1024,
+            template_max_frame_sizes: HashMap::new(),
+            tcp_nodelay: true,
+        }
+    }
+}
+
+impl From<SocketAddr> for WsClientConfig {
+    /// Creates a config with default tunables targeting `addr`.
+    fn from(addr: SocketAddr) -> Self {
+        Self::new(addr)
+    }
+}
+
+impl WsClientConfig {
+    /// Creates a new client config for the specified server address.
+    #[must_use]
+    pub fn new(server_addr: SocketAddr) -> Self {
+        Self {
+            server_addr,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the request path, `/` by default.
+    #[must_use]
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Sets the connection timeout.
+    #[must_use]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Sets the maximum frame size.
+    #[must_use]
+    pub fn max_frame_size(mut self, size: usize) -> Self {
+        self.max_frame_size = size;
+        self
+    }
+
+    /// Sets the maximum frame size for messages with `template_id`.
+    #[must_use]
+    pub fn template_max_frame_size(mut self, template_id: u16, size: usize) -> Self {
+        self.template_max_frame_sizes.insert(template_id, size);
+        self
+    }
+
+    /// Returns the frame limits enforced on the connection.
+    #[must_use]
+    pub fn frame_limits(&self) -> FrameLimits {
+        limits(self.max_frame_size, &self.template_max_frame_sizes)
+    }
+
+    /// Sets TCP_NODELAY.
+    #[must_use]
+    pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.tcp_nodelay = enabled;
+        self
+    }
+}
+
+/// Builds frame limits from a default and per-template overrides.
+fn limits(max_frame_size: usize, templates: &HashMap<u16, usize>) -> FrameLimits {
+    templates.iter().fold(
+        FrameLimits::new(max_frame_size),
+        |limits, (&template_id, &size)| limits.template(template_id, size),
+    )
+}
+
+/// Protocol settings that reject oversized messages before buffering them.
+fn protocol_config(limits: &FrameLimits) -> WebSocketConfig {
+    let max = limits.ceiling();
+    WebSocketConfig::default()
+        .max_message_size(Some(max))
+        .max_frame_size(Some(max))
+}
+
+/// A WebSocket opening handshake in progress.
+type Handshake = Pin<Box<dyn Future<Output = io::Result<WsConnection>> + Send>>;
+
+/// WebSocket listener for SBE messaging.
+///
+/// Opening handshakes run concurrently inside [`accept`](Self::accept), so
+/// a client that stalls its handshake does not hold up the others.
+pub struct WsListener {
+    listener: TcpListener,
+    config: Arc<WsServerConfig>,
+    handshakes: FuturesUnordered<Handshake>,
+}
+
+impl WsListener {
+    /// Binds to the configured address.
+    ///
+    /// # Errors
+    /// Returns IO error if binding fails.
+    pub async fn bind(config: WsServerConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(config.bind_addr).await?;
+        Ok(Self {
+            listener,
+            config: Arc::new(config),
+            handshakes: FuturesUnordered::new(),
+        })
+    }
+
+    /// Accepts a new connection and completes its opening handshake.
+    ///
+    /// # Errors
+    /// Returns IO error if accept fails, or if a handshake fails or times
+    /// out.
+    pub async fn accept(&mut self) -> io::Result<WsConnection> {
+        loop {
+            tokio::select! {
+                Some(done) = self.handshakes.next() => return done,
+                accepted = self.listener.accept() => {
+                    let (stream, addr) = accepted?;
+                    stream.set_nodelay(self.config.tcp_nodelay)?;
+                    let config = Arc::clone(&self.config);
+                    self.handshakes.push(Box::pin(async move {
+                        let limits = config.frame_limits();
+                        let handshake = tokio_tungstenite::accept_async_with_config(
+                            stream,
+                            Some(protocol_config(&limits)),
+                        );
+                        let stream = tokio::time::timeout(config.handshake_timeout, handshake)
+                            .await
+                            .map_err(|_| {
+                                io::Error::new(io::ErrorKind::TimedOut, "WebSocket handshake timeout")
+                            })?
+                            .map_err(into_io)?;
+                        Ok(WsConnection::new(stream, addr, limits))
+                    }));
+                }
+            }
+        }
+    }
+
+    /// Returns the local address the listener is bound to.
+    ///
+    /// # Errors
+    /// Returns IO error if the address cannot be determined.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+}
+
+/// A WebSocket connection carrying one SBE message per binary message.
+pub struct WsConnection {
+    stream: WebSocketStream<TcpStream>,
+    peer_addr: SocketAddr,
+    limits: FrameLimits,
+}
+
+impl WsConnection {
+    fn new(stream: WebSocketStream<TcpStream>, peer_addr: SocketAddr, limits: FrameLimits) -> Self {
+        Self {
+            stream,
+            peer_addr,
+            limits,
+        }
+    }
+
+    /// Connects to a WebSocket server and completes the opening handshake.
+    ///
+    /// # Errors
+    /// Returns IO error if the connection or handshake fails or times out.
+    pub async fn connect(config: WsClientConfig) -> io::Result<Self> {
+        let limits = config.frame_limits();
+        let url = format!("ws://{}{}", config.server_addr, config.path);
+        let connect = async {
+            let stream = TcpStream::connect(config.server_addr).await?;
+            stream.set_nodelay(config.tcp_nodelay)?;
+            let (stream, _) = tokio_tungstenite::client_async_with_config(
+                url,
+                stream,
+                Some(protocol_config(&limits)),
+            )
+            .await
+            .map_err(into_io)?;
+            Ok::<_, io::Error>(stream)
+        };
+        let stream = tokio::time::timeout(config.connect_timeout, connect)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "connect timeout"))??;
+        Ok(Self::new(stream, config.server_addr, limits))
+    }
+
+    /// Returns the peer address.
+    #[must_use]
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// Sends a message in one binary WebSocket message.
+    ///
+    /// # Errors
+    /// Returns IO error if the message exceeds its frame limit or the send
+    /// fails.
+    pub async fn send(&mut self, message: &[u8]) -> io::Result<()> {
+        self.send_owned(Bytes::copy_from_slice(message)).await
+    }
+
+    /// Sends a message from an owned buffer without copying it.
+    ///
+    /// # Errors
+    /// Returns IO error if the message exceeds its frame limit or the send
+    /// fails.
+    pub async fn send_owned(&mut self, message: Bytes) -> io::Result<()> {
+        self.limits
+            .check(message.len(), &message)
+            .map_err(io::Error::other)?;
+        self.stream
+            .send(Message::Binary(message))
+            .await
+            .map_err(into_io)
+    }
+
+    /// Receives a message.
+    ///
+    /// # Returns
+    /// `Ok(Some(bytes))` if a message was received, `Ok(None)` if the
+    /// connection closed.
+    ///
+    /// # Errors
+    /// Returns IO error on a read failure, a text message or a message
+    /// that exceeds its frame limit.
+    pub async fn recv(&mut self) -> io::Result<Option<BytesMut>> {
+        loop {
+            let message = match self.stream.next().await {
+                Some(Ok(message)) => message,
+                None
+                | Some(Err(
+                    WsError::ConnectionClosed
+                    | WsError::Protocol(ProtocolError::ResetWithoutClosingHandshake),
+                )) => return Ok(None),
+                Some(Err(e)) => return Err(into_io(e)),
+            };
+            match message {
+                Message::Binary(data) => {
+                    self.limits
+                        .check(data.len(), &data)
+                        .map_err(io::Error::other)?;
+                    return Ok(Some(BytesMut::from(data)));
+                }
+                Message::Text(_) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "text message on a binary SBE stream",
+                    ));
+                }
+                Message::Close(_) => return Ok(None),
+                Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {}
+            }
+        }
+    }
+
+    /// Starts the closing handshake.
+    ///
+    /// # Errors
+    /// Returns IO error if the close frame cannot be sent.
+    pub async fn close(&mut self) -> io::Result<()> {
+        match self.stream.close(None).await {
+            Ok(()) | Err(WsError::ConnectionClosed | WsError::AlreadyClosed) => Ok(()),
+            Err(e) => Err(into_io(e)),
+        }
+    }
+}
+
+/// Converts a WebSocket error, keeping the kind of IO errors.
+fn into_io(error: WsError) -> io::Error {
+    match error {
+        WsError::Io(e) => e,
+        WsError::Capacity(e) => io::Error::new(io::ErrorKind::InvalidData, e),
+        e => io::Error::other(e),
+    }
+}
+
+/// WebSocket transport backend.
+///
+/// # Server usage
+///
+/// ```ignore
+/// let mut listener = WsTransport::bind("0.0.0.0:9080".parse()?).await?;
+/// let conn = listener.accept().await?;
+/// ```
+///
+/// # Client usage
+///
+/// ```ignore
+/// let conn = WsTransport::connect("127.0.0.1:9080".parse()?).await?;
+/// ```
+pub struct WsTransport;
+
+impl traits::Transport for WsTransport {
+    type Listener = WsListener;
+    type Connection = WsConnection;
+    type Error = io::Error;
+    type BindConfig = WsServerConfig;
+    type ConnectConfig = WsClientConfig;
+
+    async fn bind_with(config: WsServerConfig) -> io::Result<WsListener> {
+        WsListener::bind(config).await
+    }
+
+    async fn connect_with(config: WsClientConfig) -> io::Result<WsConnection> {
+        WsConnection::connect(config).await
+    }
+}
+
+impl traits::Listener for WsListener {
+    type Connection = WsConnection;
+    type Error = io::Error;
+
+    async fn accept(&mut self) -> io::Result<WsConnection> {
+        WsListener::accept(self).await
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        WsListener::local_addr(self)
+    }
+}
+
+impl traits::Connection for WsConnection {
+    type Error = io::Error;
+
+    async fn recv(&mut self) -> io::Result<Option<BytesMut>> {
+        WsConnection::recv(self).await
+    }
+
+    async fn send(&mut self, msg: &[u8]) -> io::Result<()> {
+        WsConnection::send(self, msg).await
+    }
+
+    async fn send_owned(&mut self, msg: Bytes) -> io::Result<()> {
+        WsConnection::send_owned(self, msg).await
+    }
+
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.peer_addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_config_builder() {
+        let addr: SocketAddr = "127.0.0.1:9081".parse().unwrap();
+        let config = WsServerConfig::new(addr)
+            .max_frame_size(1024)
+            .template_max_frame_size(7, 64)
+            .tcp_nodelay(false)
+            .handshake_timeout(Duration::from_secs(1));
+        assert_eq!(config.bind_addr, addr);
+        assert!(!config.tcp_nodelay);
+        assert_eq!(config.handshake_timeout, Duration::from_secs(1));
+        let limits = config.frame_limits();
+        assert_eq!(limits.max_frame_size(), 1024);
+        assert_eq!(limits.limit_for(7), 64);
+    }
+
+    #[test]
+    fn test_client_config_builder() {
+        let addr: SocketAddr = "127.0.0.1:9081".parse().unwrap();
+        let config = WsClientConfig::from(addr)
+            .path("/md")
+            .connect_timeout(Duration::from_secs(2))
+            .max_frame_size(512);
+        assert_eq!(config.server_addr, addr);
+        assert_eq!(config.path, "/md");
+        assert_eq!(config.connect_timeout, Duration::from_secs(2));
+        assert_eq!(config.frame_limits().max_frame_size(), 512);
+        assert!(config.tcp_nodelay);
+    }
+
+    #[test]
+    fn test_into_io_keeps_io_error_kind() {
+        let err = into_io(WsError::Io(io::ErrorKind::ConnectionReset.into()));
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+    }
+}
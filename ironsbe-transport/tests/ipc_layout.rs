@@ -0,0 +1,153 @@
+//! Binary compatibility snapshot of the shared-memory ring buffer and bus.
+//!
+//! Producer and consumer may be built from different releases, so the
+//! control blocks and record framing of ring and bus files must not change. The
+//! test writes through the public API and checks the raw file bytes at
+//! hand-written offsets.
+
+use ironsbe_core::canary;
+use ironsbe_transport::ipc::{
+    BusConsumer, BusProducer, SharedBus, SharedConsumer, SharedProducer, SharedRingBuffer,
+    SharedRingConfig,
+};
+use std::mem::{align_of, size_of};
+
+const _: () = {
+    assert!(size_of::<SharedRingBuffer>() == 128);
+    assert!(align_of::<SharedRingBuffer>() == 8);
+    assert!(size_of::<SharedBus>() == 128);
+    assert!(align_of::<SharedBus>() == 8);
+};
+
+fn u64_at(file: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(file[offset..offset + 8].try_into().unwrap())
+}
+
+#[test]
+fn test_ring_file_layout() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("ring");
+    let capacity = 256;
+
+    let mut producer = SharedProducer::new(SharedRingBuffer::create(&path, capacity).unwrap());
+    let mut consumer = SharedConsumer::new(SharedRingBuffer::open(&path).unwrap());
+    assert!(producer.write(b"abc"));
+    assert!(producer.write(b"wxyz"));
+    assert_eq!(consumer.read().as_deref(), Some(&b"abc"[..]));
+
+    let file = std::fs::read(&path).expect("ring file");
+    assert_eq!(file.len(), SharedRingBuffer::HEADER_SIZE + capacity);
+
+    // Control block: head and tail on separate cache lines, then the
+    // capacity, its mask and the flags.
+    assert_eq!(u64_at(&file, 0), 4 + 3 + 4 + 4, "head");
+    assert_eq!(u64_at(&file, 64), 4 + 3, "tail");
+    assert_eq!(u64_at(&file, 72), 256, "capacity");
+    assert_eq!(u64_at(&file, 80), 255, "mask");
+    let flags = u64_at(&file, 88);
+    assert_eq!(flags, u64::from(canary::ENABLED), "flags");
+
+    // Records: u32 little-endian length, then the payload, back to back.
+    let second = SharedRingBuffer::HEADER_SIZE + 4 + 3;
+    assert_eq!(&file[second..second + 8], b"\x04\x00\x00\x00wxyz");
+    // Debug builds refill records already read with the canary pattern.
+    if !canary::ENABLED {
+        let first = SharedRingBuffer::HEADER_SIZE;
+        assert_eq!(&file[first..first + 7], b"\x03\x00\x00\x00abc");
+    }
+}
+
+// Rings get a doorbell only on Linux.
+#[cfg(target_os = "linux")]
+#[test]
+fn test_ring_doorbell_layout() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("ring");
+    let config = SharedRingConfig {
+        capacity: 256,
+        doorbell: true,
+        ..SharedRingConfig::default()
+    };
+    drop(SharedRingBuffer::create_with_config(&path, &config).unwrap());
+
+    // The doorbell flag, then the futex word and the waiter count in what
+    // was padding before doorbells, so older rings read as quiet.
+    let file = std::fs::read(&path).expect("ring file");
+    let flags = u64_at(&file, 88);
+    assert_eq!(flags, 2 | u64::from(canary::ENABLED), "flags");
+    assert_eq!(&file[96..104], &[0; 8], "doorbell and waiters");
+}
+
+#[test]
+fn test_crash_safe_ring_layout() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("ring");
+    let config = SharedRingConfig {
+        capacity: 256,
+        crash_safe: true,
+        ..SharedRingConfig::default()
+    };
+    drop(SharedRingBuffer::create_with_config(&path, &config).unwrap());
+    let timeout = std::time::Duration::from_secs(60);
+    let mut producer = SharedProducer::attach(SharedRingBuffer::open(&path).unwrap(), timeout)
+        .expect("attach producer");
+    let consumer = SharedConsumer::attach(SharedRingBuffer::open(&path).unwrap(), timeout)
+        .expect("attach consumer");
+    assert!(producer.write(b"wxyz"));
+
+    let file = std::fs::read(&path).expect("ring file");
+    let pid = u64::from(std::process::id());
+    // Producer ID and heartbeat share the head's cache line; the
+    // consumer's follow the doorbell, then the release mark.
+    assert_eq!(u64_at(&file, 8), pid, "producer pid");
+    assert_ne!(u64_at(&file, 16), 0, "producer heartbeat");
+    assert_eq!(u64_at(&file, 88), 4 | u64::from(canary::ENABLED), "flags");
+    assert_eq!(u64_at(&file, 104), pid, "consumer pid");
+    assert_ne!(u64_at(&file, 112), 0, "consumer heartbeat");
+    assert_eq!(u64_at(&file, 120), 0, "release mark");
+
+    // The top bit of the length prefix marks a complete record.
+    let first = SharedRingBuffer::HEADER_SIZE;
+    assert_eq!(&file[first..first + 8], b"\x04\x00\x00\x80wxyz");
+    drop(consumer);
+}
+
+#[test]
+fn test_bus_file_layout() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let path = dir.path().join("bus");
+    let capacity = 64;
+
+    let mut producer = BusProducer::new(SharedBus::create(&path, capacity, 2).unwrap()).unwrap();
+    let mut consumer = BusConsumer::subscribe(SharedBus::open(&path).unwrap()).unwrap();
+    assert!(producer.write(b"abc"));
+    assert!(producer.write(&[7; 40]));
+    assert_eq!(consumer.read().as_deref(), Some(&b"abc"[..]));
+    assert_eq!(consumer.read(), Some(vec![7; 40]));
+    // 8 + 48 bytes used; the next 16-byte record does not fit before the
+    // end, so it goes to the start behind a padding record.
+    assert!(producer.write(b"wxyz-wxyz"));
+
+    let file = std::fs::read(&path).expect("bus file");
+    let data = SharedBus::data_offset(2);
+    assert_eq!(data, 128 + 2 * 64);
+    assert_eq!(file.len(), data + capacity);
+
+    // Control block: head on its own cache line, then the capacity, its
+    // mask and the number of consumer slots.
+    assert_eq!(u64_at(&file, 0), 64 + 16, "head");
+    assert_eq!(u64_at(&file, 64), 64, "capacity");
+    assert_eq!(u64_at(&file, 72), 63, "mask");
+    assert_eq!(u64_at(&file, 80), 2, "slots");
+
+    // One cache line per slot: the tail, then the active flag.
+    assert_eq!(u64_at(&file, 128), 56, "slot 0 tail");
+    assert_eq!(u64_at(&file, 136), 1, "slot 0 active");
+    assert_eq!(u64_at(&file, 192 + 8), 0, "slot 1 active");
+
+    // Records: u32 little-endian length, then the payload, padded to 8
+    // bytes. A length of u32::MAX pads to the end of the ring.
+    assert_eq!(&file[data + 16..data + 52], &[7; 36][..]);
+    assert_eq!(&file[data + 56..data + 60], b"\xff\xff\xff\xff");
+    assert_eq!(&file[data..data + 13], b"\x09\x00\x00\x00wxyz-wxyz");
+}
@@ -0,0 +1,225 @@
+//! Integration tests for TLS on the Tokio TCP backend.
+//!
+//! Certificates are generated per test: a CA signs the server certificate
+//! for `localhost` and, for mutual TLS, a client certificate.
+
+#![cfg(feature = "tls")]
+
+use ironsbe_core::vectored::IoSliceEncoder;
+use ironsbe_transport::tcp::{
+    TcpClient, TcpClientConfig, TcpServerConfig, TlsClientConfig, TlsServerConfig,
+    TokioTcpTransport,
+};
+use ironsbe_transport::traits::Transport;
+use rcgen::{BasicConstraints, CertificateParams, CertifiedIssuer, IsCa, KeyPair};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// PEM certificate and key.
+struct Identity {
+    cert: String,
+    key: String,
+}
+
+/// A test CA that issues leaf certificates.
+struct Ca {
+    issuer: CertifiedIssuer<'static, KeyPair>,
+}
+
+impl Ca {
+    fn new() -> Self {
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let issuer = CertifiedIssuer::self_signed(params, KeyPair::generate().unwrap()).unwrap();
+        Self { issuer }
+    }
+
+    fn pem(&self) -> String {
+        self.issuer.pem()
+    }
+
+    fn issue(&self, name: &str) -> Identity {
+        let key = KeyPair::generate().unwrap();
+        let cert = CertificateParams::new(vec![name.to_string()])
+            .unwrap()
+            .signed_by(&key, &self.issuer)
+            .unwrap();
+        Identity {
+            cert: cert.pem(),
+            key: key.serialize_pem(),
+        }
+    }
+}
+
+fn server_tls(ca: &Ca) -> TlsServerConfig {
+    let id = ca.issue("localhost");
+    TlsServerConfig::from_pem(id.cert.as_bytes(), id.key.as_bytes()).unwrap()
+}
+
+fn client_tls(ca: &Ca) -> TlsClientConfig {
+    TlsClientConfig::new("localhost")
+        .unwrap()
+        .ca_pem(ca.pem().as_bytes())
+        .unwrap()
+}
+
+async fn bind(tls: TlsServerConfig) -> (ironsbe_transport::tcp::TcpServer, SocketAddr) {
+    let bind_addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let listener = TokioTcpTransport::bind_with(TcpServerConfig::new(bind_addr).tls(tls))
+        .await
+        .expect("bind");
+    let addr = listener.local_addr().unwrap();
+    (listener, addr)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_tls_round_trip_with_vectored_and_checksummed_frames() {
+    let ca = Ca::new();
+    let bind_addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let mut listener = TokioTcpTransport::bind_with(
+        TcpServerConfig::new(bind_addr)
+            .checksum(true)
+            .tls(server_tls(&ca)),
+    )
+    .await
+    .expect("bind");
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let mut conn = listener.accept().await.expect("accept");
+        assert!(conn.is_tls());
+        while let Some(frame) = conn.recv().await.expect("recv") {
+            conn.send(&frame).await.expect("send");
+        }
+    });
+
+    let mut client = TcpClient::connect(
+        TcpClientConfig::new(addr)
+            .checksum(true)
+            .tls(client_tls(&ca)),
+    )
+    .await
+    .expect("connect");
+    assert!(client.is_tls());
+
+    client.send(b"hello").await.unwrap();
+    assert_eq!(&client.recv().await.unwrap().unwrap()[..], b"hello");
+
+    let text = vec![7u8; 50_000];
+    let mut message = IoSliceEncoder::new();
+    message.append(&[1, 2, 3, 4]).append_var_data(&text);
+    client.send_vectored(&message).await.unwrap();
+    assert_eq!(
+        client.recv().await.unwrap().unwrap()[..],
+        message.to_vec()[..]
+    );
+
+    client.close().await.unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_client_rejects_server_from_unknown_ca() {
+    let (mut listener, addr) = bind(server_tls(&Ca::new())).await;
+    let server = tokio::spawn(async move { listener.accept().await.map(|_| ()) });
+
+    let result =
+        TokioTcpTransport::connect_with(TcpClientConfig::new(addr).tls(client_tls(&Ca::new())))
+            .await;
+    assert!(result.is_err());
+    assert!(server.await.unwrap().is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_mutual_tls_requires_client_certificate() {
+    let ca = Ca::new();
+    let tls = server_tls(&ca).client_ca_pem(ca.pem().as_bytes()).unwrap();
+    let (mut listener, addr) = bind(tls).await;
+
+    let server = tokio::spawn(async move {
+        // The first client presents no certificate and is refused.
+        assert!(listener.accept().await.is_err());
+        let mut conn = listener.accept().await.expect("accept");
+        conn.send(b"welcome").await.unwrap();
+        conn
+    });
+
+    // Under TLS 1.3 the client finishes its side before the server checks
+    // the certificate, so the refusal shows up on the first read.
+    if let Ok(mut anonymous) =
+        TcpClient::connect(TcpClientConfig::new(addr).tls(client_tls(&ca))).await
+    {
+        assert!(!matches!(anonymous.recv().await, Ok(Some(_))));
+    }
+
+    let id = ca.issue("client");
+    let tls = client_tls(&ca)
+        .client_cert_pem(id.cert.as_bytes(), id.key.as_bytes())
+        .unwrap();
+    let mut client = TcpClient::connect(TcpClientConfig::new(addr).tls(tls))
+        .await
+        .expect("connect");
+    assert_eq!(&client.recv().await.unwrap().unwrap()[..], b"welcome");
+    drop(server.await.unwrap());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_stalled_handshake_does_not_block_accept() {
+    let ca = Ca::new();
+    let tls = server_tls(&ca).handshake_timeout(Duration::from_millis(500));
+    let (mut listener, addr) = bind(tls).await;
+
+    // Connects but never starts the handshake.
+    let _stalled = tokio::net::TcpStream::connect(addr).await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let mut results = Vec::new();
+        for _ in 0..2 {
+            results.push(listener.accept().await.map(|conn| conn.is_tls()));
+        }
+        results
+    });
+
+    let client = tokio::time::timeout(
+        Duration::from_millis(400),
+        TcpClient::connect(TcpClientConfig::new(addr).tls(client_tls(&ca))),
+    )
+    .await
+    .expect("handshake waited for the stalled client")
+    .expect("connect");
+
+    let results = server.await.unwrap();
+    assert!(matches!(results[0], Ok(true)));
+    assert_eq!(
+        results[1].as_ref().unwrap_err().kind(),
+        std::io::ErrorKind::TimedOut
+    );
+    drop(client);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_reconnect_sends_frames_as_early_data() {
+    let ca = Ca::new();
+    let (mut listener, addr) = bind(server_tls(&ca).max_early_data(16 * 1024)).await;
+
+    let server = tokio::spawn(async move {
+        for _ in 0..2 {
+            let mut conn = listener.accept().await.expect("accept");
+            while let Some(frame) = conn.recv().await.expect("recv") {
+                conn.send(&frame).await.expect("send");
+            }
+        }
+    });
+
+    // Clones share the session cache, as reconnects with one config do.
+    let config = TcpClientConfig::new(addr).tls(client_tls(&ca).early_data(true));
+    for logon in [&b"first"[..], b"again"] {
+        let mut client = TcpClient::connect(config.clone()).await.expect("connect");
+        client.send(logon).await.unwrap();
+        client.send(b"heartbeat").await.unwrap();
+        assert_eq!(&client.recv().await.unwrap().unwrap()[..], logon);
+        assert_eq!(&client.recv().await.unwrap().unwrap()[..], b"heartbeat");
+        client.close().await.unwrap();
+    }
+    server.await.unwrap();
+}
@@ -192,3 +192,168 @@ async fn test_round_trip_with_custom_buffer_sizes() {
 
     server_task.await.expect("server task");
 }
+
+/// Per-template limits are enforced on received frames: the server closes
+/// the stream with a structured error once a heartbeat exceeds its cap,
+/// while a larger message of another template still passes.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_template_frame_limits_reject_oversized_heartbeat() {
+    use ironsbe_transport::TransportError;
+
+    const HEARTBEAT: u16 = 0;
+    const SNAPSHOT: u16 = 7;
+
+    let bind_addr: SocketAddr = "127.0.0.1:0".parse().expect("valid addr");
+    let server_cfg = TcpServerConfig::new(bind_addr)
+        .max_frame_size(1024)
+        .template_max_frame_size(HEARTBEAT, 16)
+        .template_max_frame_size(SNAPSHOT, LARGE_FRAME);
+    let mut listener = TokioTcpTransport::bind_with(server_cfg)
+        .await
+        .expect("bind");
+    let listen_addr = listener.local_addr().expect("local_addr");
+
+    let server_task = tokio::spawn(async move {
+        let mut conn = listener.accept().await.expect("accept");
+        let snapshot = conn.recv().await.expect("recv").expect("frame");
+        assert_eq!(snapshot.len(), PAYLOAD_LEN);
+        let err = conn.recv().await.expect_err("oversized heartbeat");
+        let err = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<TransportError>());
+        assert!(matches!(
+            err,
+            Some(TransportError::TemplateFrameTooLarge {
+                template_id: HEARTBEAT,
+                size: 32,
+                max: 16,
+            })
+        ));
+    });
+
+    let client_cfg = TcpClientConfig::new(listen_addr).max_frame_size(LARGE_FRAME);
+    let mut client = TokioTcpTransport::connect_with(client_cfg)
+        .await
+        .expect("connect");
+    let mut snapshot = vec![0u8; PAYLOAD_LEN];
+    snapshot[2..4].copy_from_slice(&SNAPSHOT.to_le_bytes());
+    client.send(&snapshot).await.expect("send snapshot");
+    client.send(&[0u8; 32]).await.expect("send heartbeat");
+
+    server_task.await.expect("server task");
+}
+
+/// Peers that both enable checksums exchange plain and vectored frames with
+/// CRC32C trailers.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_checksum_trailers_round_trip() {
+    use ironsbe_core::vectored::IoSliceEncoder;
+
+    let bind_addr: SocketAddr = "127.0.0.1:0".parse().expect("valid addr");
+    let server_cfg = TcpServerConfig::new(bind_addr).checksum(true);
+    let mut listener = TokioTcpTransport::bind_with(server_cfg)
+        .await
+        .expect("bind");
+    let listen_addr = listener.local_addr().expect("local_addr");
+
+    let server_task = tokio::spawn(async move {
+        let mut conn = listener.accept().await.expect("accept");
+        let plain = conn.recv().await.expect("recv").expect("frame");
+        assert_eq!(&plain[..], b"plain");
+        let vectored = conn.recv().await.expect("recv").expect("frame");
+        assert_eq!(&vectored[..], b"head\x04\x00text");
+        conn.send(b"reply").await.expect("send reply");
+    });
+
+    let client_cfg = TcpClientConfig::new(listen_addr).checksum(true);
+    let mut client = TokioTcpTransport::connect_with(client_cfg)
+        .await
+        .expect("connect");
+    client.send(b"plain").await.expect("send plain");
+    let mut message = IoSliceEncoder::new();
+    message.append(b"head").append_var_data(b"text");
+    client.send_vectored(&message).await.expect("send vectored");
+    let reply = client.recv().await.expect("recv").expect("frame");
+    assert_eq!(&reply[..], b"reply");
+
+    server_task.await.expect("server task");
+}
+
+/// A SOFH server exchanges frames with a peer that writes the header by
+/// hand, as an exchange gateway would, and with a SOFH client's vectored
+/// sends.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_sofh_framing_round_trip() {
+    use ironsbe_core::vectored::IoSliceEncoder;
+    use ironsbe_transport::tcp::Framing;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let bind_addr: SocketAddr = "127.0.0.1:0".parse().expect("valid addr");
+    let server_cfg = TcpServerConfig::new(bind_addr).framing(Framing::sofh());
+    let mut listener = TokioTcpTransport::bind_with(server_cfg)
+        .await
+        .expect("bind");
+    let listen_addr = listener.local_addr().expect("local_addr");
+
+    let server_task = tokio::spawn(async move {
+        for _ in 0..2 {
+            let mut conn = listener.accept().await.expect("accept");
+            let frame = conn.recv().await.expect("recv").expect("frame");
+            conn.send(&frame).await.expect("send");
+        }
+    });
+
+    let mut gateway = tokio::net::TcpStream::connect(listen_addr)
+        .await
+        .expect("connect");
+    let frame = [0, 0, 0, 11, 0xEB, 0x50, b'h', b'e', b'l', b'l', b'o'];
+    gateway.write_all(&frame).await.expect("write");
+    let mut echoed = [0u8; 11];
+    gateway.read_exact(&mut echoed).await.expect("read");
+    assert_eq!(echoed, frame);
+
+    let client_cfg = TcpClientConfig::new(listen_addr).framing(Framing::sofh());
+    let mut client = TokioTcpTransport::connect_with(client_cfg)
+        .await
+        .expect("connect");
+    let mut message = IoSliceEncoder::new();
+    message.append(b"head").append_var_data(b"text");
+    client.send_vectored(&message).await.expect("send vectored");
+    let reply = client.recv().await.expect("recv").expect("frame");
+    assert_eq!(&reply[..], b"head\x04\x00text");
+
+    server_task.await.expect("server task");
+}
+
+/// Latency options on both sides leave the round trip intact and reach
+/// the accepted socket.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_round_trip_with_socket_tuning() {
+    let quickack = cfg!(target_os = "linux");
+    let bind_addr: SocketAddr = "127.0.0.1:0".parse().expect("valid addr");
+    let server_cfg = TcpServerConfig::new(bind_addr)
+        .dscp(46)
+        .tcp_quickack(quickack);
+    let mut listener = TokioTcpTransport::bind_with(server_cfg)
+        .await
+        .expect("bind");
+    let listen_addr = listener.local_addr().expect("local_addr");
+
+    let server_task = tokio::spawn(async move {
+        let mut conn = listener.accept().await.expect("accept");
+        let msg = conn.recv().await.expect("recv").expect("frame");
+        conn.send(&msg).await.expect("send");
+    });
+
+    let client_cfg = TcpClientConfig::new(listen_addr)
+        .tos(0x10)
+        .tcp_quickack(quickack);
+    let mut client = TokioTcpTransport::connect_with(client_cfg)
+        .await
+        .expect("connect");
+    client.send(b"tuned").await.expect("client send");
+    let reply = client.recv().await.expect("client recv").expect("frame");
+    assert_eq!(&reply[..], b"tuned");
+
+    server_task.await.expect("server task");
+}
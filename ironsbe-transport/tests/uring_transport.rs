@@ -160,3 +160,49 @@ fn test_uring_send_oversized_frame_returns_err() {
         server_task.await.expect("server task");
     });
 }
+
+#[test]
+fn test_uring_checksum_trailer() {
+    tokio_uring::start(async {
+        let bind_addr: SocketAddr = "127.0.0.1:0".parse().expect("valid addr");
+        let cfg = UringServerConfig::new(bind_addr).checksum(true);
+        let mut listener = UringTcpTransport::bind_with(cfg).await.expect("bind");
+        let listen_addr = listener.local_addr().expect("local_addr");
+
+        let server_task = tokio_uring::spawn(async move {
+            let mut conn = listener.accept().await.expect("accept");
+            let received = conn
+                .recv()
+                .await
+                .expect("server recv")
+                .expect("frame available");
+            assert_eq!(&received[..], PAYLOAD);
+            conn.send(PAYLOAD).await.expect("server send");
+
+            // A peer without trailers is caught by the first checksum.
+            let mut conn = listener.accept().await.expect("accept");
+            let err = conn.recv().await.expect_err("checksum mismatch");
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        });
+
+        let mut client =
+            UringTcpTransport::connect_with(UringClientConfig::new(listen_addr).checksum(true))
+                .await
+                .expect("connect");
+        client.send(PAYLOAD).await.expect("client send");
+        let echoed = client
+            .recv()
+            .await
+            .expect("client recv")
+            .expect("frame available");
+        assert_eq!(&echoed[..], PAYLOAD);
+
+        let mut plain = UringTcpTransport::connect_with(UringClientConfig::new(listen_addr))
+            .await
+            .expect("connect");
+        plain.send(PAYLOAD).await.expect("plain send");
+        plain.send(PAYLOAD).await.expect("plain send");
+
+        server_task.await.expect("server task");
+    });
+}
@@ -0,0 +1,70 @@
+//! Integration tests for vectored sends on the Tokio TCP backend.
+//!
+//! A message assembled with `IoSliceEncoder` must arrive as one frame,
+//! byte-identical to the contiguous encoding, interleaved in order with
+//! frames sent through the ordinary `send` path.
+
+#![cfg(feature = "tcp-tokio")]
+
+use ironsbe_core::vectored::IoSliceEncoder;
+use ironsbe_transport::tcp::{TcpServerConfig, TokioTcpTransport};
+use ironsbe_transport::traits::Transport;
+use std::net::SocketAddr;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_vectored_frames_interleave_with_plain_sends() {
+    let bind_addr: SocketAddr = "127.0.0.1:0".parse().expect("valid addr");
+    let mut listener = TokioTcpTransport::bind_with(TcpServerConfig::new(bind_addr))
+        .await
+        .expect("bind");
+    let listen_addr = listener.local_addr().expect("local_addr");
+
+    let head = [1u8, 2, 3, 4, 5, 6, 7, 8];
+    let text: Vec<u8> = (0..40_000).map(|i| (i % 251) as u8).collect();
+    let mut message = IoSliceEncoder::new();
+    message.append(&head).append_var_data(&text).append(b"tail");
+    let expected = message.to_vec();
+
+    let server_task = tokio::spawn(async move {
+        let mut conn = listener.accept().await.expect("accept");
+        let mut frames = Vec::new();
+        for _ in 0..3 {
+            frames.push(conn.recv().await.expect("recv").expect("frame"));
+        }
+        conn.send(b"done").await.expect("send");
+        frames
+    });
+
+    let mut client = TokioTcpTransport::connect(listen_addr)
+        .await
+        .expect("connect");
+    client.send(b"before").await.expect("send");
+    client.send_vectored(&message).await.expect("send_vectored");
+    client.send(b"after").await.expect("send");
+    let reply = client.recv().await.expect("recv").expect("frame");
+    assert_eq!(&reply[..], b"done");
+
+    let frames = server_task.await.expect("server task");
+    assert_eq!(&frames[0][..], b"before");
+    assert_eq!(&frames[1][..], &expected[..]);
+    assert_eq!(&frames[2][..], b"after");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_vectored_send_rejects_oversized_message() {
+    let bind_addr: SocketAddr = "127.0.0.1:0".parse().expect("valid addr");
+    let mut listener = TokioTcpTransport::bind_with(TcpServerConfig::new(bind_addr))
+        .await
+        .expect("bind");
+    let listen_addr = listener.local_addr().expect("local_addr");
+    let accept = tokio::spawn(async move { listener.accept().await.expect("accept") });
+
+    let mut client = TokioTcpTransport::connect(listen_addr)
+        .await
+        .expect("connect");
+    let big = vec![0u8; 40 * 1024];
+    let mut message = IoSliceEncoder::new();
+    message.append(&big).append(&big);
+    assert!(client.send_vectored(&message).await.is_err());
+    drop(accept.await.expect("server task"));
+}
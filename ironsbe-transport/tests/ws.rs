@@ -0,0 +1,136 @@
+//! Integration tests for the WebSocket transport.
+
+#![cfg(feature = "ws")]
+
+use futures::{SinkExt, StreamExt};
+use ironsbe_transport::traits::Transport;
+use ironsbe_transport::ws::{WsClientConfig, WsConnection, WsServerConfig, WsTransport};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+async fn bind(config: WsServerConfig) -> (ironsbe_transport::ws::WsListener, SocketAddr) {
+    let listener = WsTransport::bind_with(config).await.expect("bind");
+    let addr = listener.local_addr().unwrap();
+    (listener, addr)
+}
+
+fn any_port() -> SocketAddr {
+    "127.0.0.1:0".parse().unwrap()
+}
+
+/// A message whose header carries `template_id`, padded to `len` bytes.
+fn message(template_id: u16, len: usize) -> Vec<u8> {
+    let mut message = vec![0u8; len];
+    message[2..4].copy_from_slice(&template_id.to_le_bytes());
+    message
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_ws_round_trip_one_message_per_frame() {
+    let (mut listener, addr) = bind(WsServerConfig::new(any_port())).await;
+    let server = tokio::spawn(async move {
+        let mut conn = listener.accept().await.expect("accept");
+        while let Some(frame) = conn.recv().await.expect("recv") {
+            conn.send(&frame).await.expect("send");
+        }
+    });
+
+    let mut client = WsTransport::connect(addr).await.expect("connect");
+    for len in [4, 8, 40_000] {
+        let sent = message(1, len);
+        client.send(&sent).await.unwrap();
+        assert_eq!(client.recv().await.unwrap().unwrap()[..], sent[..]);
+    }
+    client
+        .send_owned(bytes::Bytes::from_static(b"owned"))
+        .await
+        .unwrap();
+    assert_eq!(&client.recv().await.unwrap().unwrap()[..], b"owned");
+
+    client.close().await.unwrap();
+    assert!(client.recv().await.unwrap().is_none());
+    server.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_ws_enforces_template_frame_limits() {
+    let config = WsServerConfig::new(any_port())
+        .max_frame_size(1024)
+        .template_max_frame_size(9, 16);
+    let (mut listener, addr) = bind(config).await;
+    let server = tokio::spawn(async move {
+        let mut conn = listener.accept().await.expect("accept");
+        let first = conn.recv().await.expect("recv").expect("frame");
+        assert_eq!(first.len(), 16);
+        conn.recv().await
+    });
+
+    let mut client = WsConnection::connect(WsClientConfig::new(addr))
+        .await
+        .expect("connect");
+    client.send(&message(9, 16)).await.unwrap();
+    client.send(&message(9, 17)).await.unwrap();
+    let err = server.await.unwrap().unwrap_err();
+    assert!(err.to_string().contains("template 9"), "{err}");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_ws_client_refuses_to_send_oversized_frame() {
+    let (mut listener, addr) = bind(WsServerConfig::new(any_port())).await;
+    let server = tokio::spawn(async move { listener.accept().await.map(|_| ()) });
+
+    let mut client = WsConnection::connect(WsClientConfig::new(addr).max_frame_size(32))
+        .await
+        .expect("connect");
+    assert!(client.send(&message(1, 33)).await.is_err());
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_ws_rejects_text_messages() {
+    let (mut listener, addr) = bind(WsServerConfig::new(any_port())).await;
+    let server = tokio::spawn(async move {
+        let mut conn = listener.accept().await.expect("accept");
+        conn.recv().await
+    });
+
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let (mut browser, _) = tokio_tungstenite::client_async(format!("ws://{addr}/"), stream)
+        .await
+        .expect("connect");
+    browser.send(Message::text("hello")).await.unwrap();
+    let err = server.await.unwrap().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    drop(browser.next().await);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_stalled_ws_handshake_does_not_block_accept() {
+    let config = WsServerConfig::new(any_port()).handshake_timeout(Duration::from_millis(500));
+    let (mut listener, addr) = bind(config).await;
+
+    // Connects but never sends the opening handshake.
+    let _stalled = tokio::net::TcpStream::connect(addr).await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let mut results = Vec::new();
+        for _ in 0..2 {
+            results.push(listener.accept().await.map(|_| ()));
+        }
+        results
+    });
+
+    let client = tokio::time::timeout(Duration::from_millis(400), WsTransport::connect(addr))
+        .await
+        .expect("handshake waited for the stalled client")
+        .expect("connect");
+
+    let results = server.await.unwrap();
+    assert!(results[0].is_ok());
+    assert_eq!(
+        results[1].as_ref().unwrap_err().kind(),
+        std::io::ErrorKind::TimedOut
+    );
+    drop(client);
+}
@@ -92,6 +92,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         }
                     }
                 }
+                ClientEvent::MessageExpired(data) => {
+                    eprintln!("[Client] Dropped expired message: {} bytes", data.len());
+                }
+                ClientEvent::Fixp(event) => {
+                    println!("[Client] Session event: {:?}", event);
+                }
                 ClientEvent::Error(e) => {
                     eprintln!("[Client] Error: {}", e);
                 }
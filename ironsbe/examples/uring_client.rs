@@ -74,6 +74,12 @@ mod imp {
                         ClientEvent::Disconnected => {
                             println!("[uring client] disconnected");
                         }
+                        ClientEvent::MessageExpired(bytes) => {
+                            eprintln!("[uring client] expired message ({} bytes)", bytes.len());
+                        }
+                        ClientEvent::Fixp(event) => {
+                            println!("[uring client] session event: {event:?}");
+                        }
                         ClientEvent::Error(msg) => {
                             eprintln!("[uring client] error event: {msg}");
                         }
@@ -37,9 +37,18 @@
 //! - [`transport`] - Network transports (TCP, UDP, IPC)
 //! - [`server`] - Server-side engine
 //! - [`client`] - Client-side engine
+//! - [`session`] - FIXP session layer for the server and client, with a
+//!   CME iLink 3 binding under the `ilink3` feature
 //! - [`marketdata`] - Market data handling patterns
+//! - [`journal`] - Persistent journal of sent and received frames, with
+//!   replay
+//! - [`runtime`] - CPU affinity for hot-path threads and isolation from
+//!   the tokio runtime
+//! - [`warmup`] - Start-up warm-up of the message hot path
 
 pub mod prelude;
+pub mod runtime;
+pub mod warmup;
 
 /// Core types and traits for SBE encoding/decoding.
 pub mod core {
@@ -76,17 +85,28 @@ pub mod client {
     pub use ironsbe_client::*;
 }
 
+/// FIX Performance Session Layer (FIXP).
+pub mod session {
+    pub use ironsbe_session::*;
+}
+
 /// Market data handling patterns.
 pub mod marketdata {
     pub use ironsbe_marketdata::*;
 }
 
+/// Persistent append-only message journal.
+pub mod journal {
+    pub use ironsbe_journal::*;
+}
+
 // Re-export commonly used items at the crate root
 pub use ironsbe_core::{
-    buffer::{AlignedBuffer, BufferPool, ReadBuffer, WriteBuffer},
+    buffer::{AlignedBuffer, AlignedVec, BufferPool, ReadBuffer, WriteBuffer},
     decoder::{DecodeError, SbeDecoder},
     encoder::SbeEncoder,
-    header::{GroupHeader, MessageHeader, VarDataHeader},
+    header::{GroupHeader, MessageHeader, SbeHeader, VarDataHeader},
+    pool::{TieredBufferPool, TieredPoolConfig},
 };
 
 pub use ironsbe_channel::{broadcast, mpsc, spsc};
@@ -7,14 +7,14 @@
 //! ```
 
 // Core types
-pub use ironsbe_core::buffer::{AlignedBuffer, BufferPool, ReadBuffer, WriteBuffer};
+pub use ironsbe_core::buffer::{AlignedBuffer, AlignedVec, BufferPool, ReadBuffer, WriteBuffer};
 pub use ironsbe_core::decoder::{DecodeError, SbeDecoder};
 pub use ironsbe_core::encoder::SbeEncoder;
 pub use ironsbe_core::error::{Error as CoreError, Result as CoreResult};
-pub use ironsbe_core::header::{GroupHeader, MessageHeader, VarDataHeader};
+pub use ironsbe_core::header::{GroupHeader, MessageHeader, SbeHeader, VarDataHeader};
 
 // Channel types
-pub use ironsbe_channel::{MpscReceiver, MpscSender, SpscReceiver, SpscSender};
+pub use ironsbe_channel::{MpscReceiver, MpscSender, SpscReceiver, SpscSender, WaitStrategy};
 pub use ironsbe_channel::{broadcast, mpsc, spsc};
 
 // Server types
@@ -31,5 +31,6 @@ pub use ironsbe_client::{
 // Market data types
 pub use ironsbe_marketdata::{
     BookSide, BookSnapshot, BookUpdate, InstrumentState, MarketDataEvent, MarketDataHandler,
-    OrderBook, PriceLevel, Side,
+    OrderBook, PriceLevel, ShardConfig, ShardedMarketDataHandler, Side, SnapshotScheduleConfig,
+    SnapshotScheduler,
 };
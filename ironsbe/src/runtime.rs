@@ -0,0 +1,168 @@
+//! Thread placement for servers, clients and consumers.
+//!
+//! [`CoreConfig`] names the cores of the hot-path threads. The thread-based
+//! components pin themselves from it:
+//! [`BlockingServer::cores`](ironsbe_server::BlockingServer::cores) pins its
+//! accept and session threads, and
+//! [`ShardConfig::core_config`](ironsbe_marketdata::ShardConfig::core_config)
+//! pins the market-data shard workers. Consumer threads of your own pin
+//! themselves with [`CoreConfig::pin_consumer`].
+//!
+//! The async server and client run as tasks, so they go where their
+//! runtime puts them. [`spawn_hot`] gives one a thread of its own, pinned
+//! to a hot-path core and driving a current-thread runtime, and
+//! [`build_runtime`] builds the runtime for everything else, kept off the
+//! hot-path cores when the configuration isolates them.
+//!
+//! # Example
+//! ```no_run
+//! use ironsbe::runtime::{self, CoreConfig};
+//!
+//! let cores = CoreConfig::new().accept_core(2).isolate(true);
+//! let runtime = runtime::build_runtime(&cores)?;
+//! let hot = runtime::spawn_hot(&cores, "ironsbe-server", cores.accept(), || async {
+//!     // Build and run the server here; its sessions are spawned onto
+//!     // this thread's runtime.
+//! })?;
+//! runtime.block_on(async {
+//!     // Everything else.
+//! });
+//! hot.join().unwrap()?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+pub use ironsbe_core::runtime::{CoreConfig, available_cores, pin_current_thread};
+
+use std::future::Future;
+use std::io;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use tokio::runtime::{Builder, Runtime};
+
+/// Builds a multi-thread tokio runtime for the work outside the hot path.
+///
+/// When `cores` isolates the hot path, the runtime gets one worker per
+/// [runtime core](CoreConfig::runtime_cores) and all of its threads are
+/// confined to those cores. Otherwise it is a default multi-thread
+/// runtime.
+///
+/// # Errors
+/// Returns an error if the runtime cannot be built or the runtime cores
+/// cannot be computed, or, in strict mode, if threads cannot be confined
+/// to them.
+pub fn build_runtime(cores: &CoreConfig) -> io::Result<Runtime> {
+    let mut builder = Builder::new_multi_thread();
+    builder.enable_all();
+    if let Some(runtime_cores) = cores.runtime_cores()? {
+        if cores.is_strict() {
+            // Worker threads cannot report an error, so try the mask on a
+            // throwaway thread first.
+            let probe = runtime_cores.clone();
+            thread::spawn(move || pin_current_thread(&probe))
+                .join()
+                .map_err(|_| io::Error::other("affinity probe panicked"))??;
+        }
+        builder.worker_threads(runtime_cores.len());
+        builder.on_thread_start(move || {
+            let _ = pin_current_thread(&runtime_cores);
+        });
+    }
+    builder.build()
+}
+
+/// Runs the future returned by `make` on a new thread named `name`,
+/// pinned to `core` and driving a current-thread runtime of its own.
+///
+/// Tasks the future spawns run on the same thread, so a server started
+/// here keeps its accept loop and sessions on `core`. The future does not
+/// need to be `Send`, as it is created on the new thread. The thread
+/// yields the future's output once it completes.
+///
+/// # Errors
+/// Returns an error if the thread or its runtime cannot be created, or,
+/// in strict mode, if the thread cannot be pinned. The thread then exits
+/// with the same error without calling `make`.
+pub fn spawn_hot<M, F>(
+    cores: &CoreConfig,
+    name: impl Into<String>,
+    core: Option<usize>,
+    make: M,
+) -> io::Result<JoinHandle<io::Result<F::Output>>>
+where
+    M: FnOnce() -> F + Send + 'static,
+    F: Future,
+    F::Output: Send + 'static,
+{
+    let cores = cores.clone();
+    let (ready_tx, ready) = mpsc::sync_channel(1);
+    let handle = thread::Builder::new().name(name.into()).spawn(move || {
+        let started = cores
+            .pin(core)
+            .and_then(|()| Builder::new_current_thread().enable_all().build());
+        let _ = ready_tx.send(started.as_ref().map(|_| ()).map_err(clone_error));
+        Ok(started?.block_on(make()))
+    })?;
+    match ready.recv() {
+        Ok(Ok(())) => Ok(handle),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(io::Error::other("hot-path thread exited before starting")),
+    }
+}
+
+fn clone_error(e: &io::Error) -> io::Error {
+    io::Error::new(e.kind(), e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_runtime_without_isolation() {
+        let runtime = build_runtime(&CoreConfig::new()).unwrap();
+        assert_eq!(runtime.block_on(async { 1 + 1 }), 2);
+    }
+
+    #[test]
+    fn test_spawn_hot_runs_future() {
+        let hot = spawn_hot(&CoreConfig::new(), "hot", None, || async {
+            let local = tokio::spawn(async { 7 });
+            local.await.unwrap() * 6
+        })
+        .unwrap();
+        assert_eq!(hot.join().unwrap().unwrap(), 42);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_isolated_runtime_avoids_hot_core() {
+        let available = available_cores().unwrap();
+        if available.len() < 2 {
+            return;
+        }
+        let hot = available[0];
+        let cores = CoreConfig::new()
+            .accept_core(hot)
+            .isolate(true)
+            .strict(true);
+
+        let pinned = spawn_hot(&cores, "hot", cores.accept(), || async {
+            available_cores().unwrap()
+        })
+        .unwrap();
+        assert_eq!(pinned.join().unwrap().unwrap(), vec![hot]);
+
+        let runtime = build_runtime(&cores).unwrap();
+        let worker = runtime
+            .block_on(tokio::spawn(async { available_cores().unwrap() }))
+            .unwrap();
+        assert!(!worker.contains(&hot));
+    }
+
+    #[test]
+    fn test_spawn_hot_reports_strict_pin_failure() {
+        let cores = CoreConfig::new().strict(true);
+        let err = spawn_hot(&cores, "hot", Some(usize::MAX), || async {}).unwrap_err();
+        assert_ne!(err.kind(), io::ErrorKind::Other);
+    }
+}
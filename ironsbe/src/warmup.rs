@@ -0,0 +1,262 @@
+//! Start-up warm-up of the message hot path.
+//!
+//! The first messages after start-up are slow: code and data pages fault
+//! in, branch predictors and caches are cold, and allocators and buffer
+//! pools hand out memory for the first time. [`Warmup`] runs dummy traffic
+//! through the same code before the first real message does. Each round
+//! encodes a representative message, decodes it, copies it through a
+//! buffer pool, passes it over an SPSC channel and, optionally, round-trips
+//! it over a loopback TCP connection.
+//!
+//! The traffic stays inside private channels and sockets, so nothing
+//! reaches a peer or an application consumer.
+
+use ironsbe_channel::spsc;
+use ironsbe_core::header::MessageHeader;
+use ironsbe_core::pool::TieredBufferPool;
+use ironsbe_transport::tcp_std::{BlockingTcpConnection, BlockingTcpListener};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Encodes a dummy message into the buffer and returns its length.
+type EncodeFn<'a> = Box<dyn FnMut(&mut [u8]) -> usize + 'a>;
+
+/// Decodes a dummy message.
+type DecodeFn<'a> = Box<dyn FnMut(&[u8]) + 'a>;
+
+/// Timings of a warm-up run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WarmupReport {
+    /// Rounds run.
+    pub iterations: usize,
+    /// Duration of the first, cold round.
+    pub first_round: Duration,
+    /// Duration of the last round.
+    pub last_round: Duration,
+    /// Duration of the whole run.
+    pub elapsed: Duration,
+}
+
+/// Builder and runner for a start-up warm-up.
+///
+/// # Example
+/// ```
+/// use ironsbe::warmup::Warmup;
+///
+/// let report = Warmup::new()
+///     .iterations(100)
+///     .codec(
+///         |buf| {
+///             buf[..8].copy_from_slice(&[8, 0, 1, 0, 1, 0, 0, 0]);
+///             16
+///         },
+///         |bytes| assert_eq!(bytes.len(), 16),
+///     )
+///     .loopback(true)
+///     .run()
+///     .unwrap();
+/// assert_eq!(report.iterations, 100);
+/// ```
+pub struct Warmup<'a> {
+    iterations: usize,
+    buffer_size: usize,
+    encode: Option<EncodeFn<'a>>,
+    decode: Option<DecodeFn<'a>>,
+    pool: Option<&'a TieredBufferPool>,
+    loopback: bool,
+}
+
+impl Default for Warmup<'_> {
+    fn default() -> Self {
+        Self {
+            iterations: 1_000,
+            buffer_size: 4 * 1024,
+            encode: None,
+            decode: None,
+            pool: None,
+            loopback: false,
+        }
+    }
+}
+
+impl<'a> Warmup<'a> {
+    /// Creates a warm-up of 1,000 rounds over channels only.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of rounds.
+    #[must_use]
+    pub fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Sets the size of the buffer handed to the encoder.
+    #[must_use]
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = size;
+        self
+    }
+
+    /// Sets the codec to exercise.
+    ///
+    /// `encode` writes a representative message, typically with a generated
+    /// encoder, and returns its length; `decode` reads it back with the
+    /// matching decoder and should touch the fields the hot path reads.
+    /// Without a codec, rounds carry a bare message header.
+    #[must_use]
+    pub fn codec(
+        mut self,
+        encode: impl FnMut(&mut [u8]) -> usize + 'a,
+        decode: impl FnMut(&[u8]) + 'a,
+    ) -> Self {
+        self.encode = Some(Box::new(encode));
+        self.decode = Some(Box::new(decode));
+        self
+    }
+
+    /// Copies every message through `pool`, faulting in its buffers.
+    ///
+    /// The acquisitions show up in the pool's counters.
+    #[must_use]
+    pub fn pool(mut self, pool: &'a TieredBufferPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Also round-trips every message over a loopback TCP connection.
+    #[must_use]
+    pub fn loopback(mut self, enabled: bool) -> Self {
+        self.loopback = enabled;
+        self
+    }
+
+    /// Runs the warm-up.
+    ///
+    /// # Errors
+    /// Returns the I/O error if the loopback connection fails.
+    ///
+    /// # Panics
+    /// Panics if the encoder returns a length beyond the buffer.
+    pub fn run(mut self) -> io::Result<WarmupReport> {
+        let mut buffer = vec![0u8; self.buffer_size];
+        let (mut tx, mut rx) = spsc::channel::<Vec<u8>>(16);
+        let mut sockets = if self.loopback {
+            Some(loopback_pair()?)
+        } else {
+            None
+        };
+
+        let start = Instant::now();
+        let mut first_round = Duration::ZERO;
+        let mut last_round = Duration::ZERO;
+        for round in 0..self.iterations {
+            let round_start = Instant::now();
+            let len = match self.encode.as_mut() {
+                Some(encode) => encode(&mut buffer),
+                None => {
+                    MessageHeader::default().encode(&mut buffer[..], 0);
+                    MessageHeader::ENCODED_LENGTH
+                }
+            };
+            let message = &buffer[..len];
+            if let Some(decode) = self.decode.as_mut() {
+                decode(message);
+            }
+            if let Some(mut pooled) = self.pool.and_then(|pool| pool.acquire(len)) {
+                pooled.writable()[..len].copy_from_slice(message);
+                pooled.set_len(len);
+            }
+            if tx.send(message.to_vec()).is_ok() {
+                std::hint::black_box(rx.recv());
+            }
+            if let Some((client, server)) = sockets.as_mut() {
+                client.send(message).map_err(into_io)?;
+                std::hint::black_box(server.recv().map_err(into_io)?);
+            }
+
+            last_round = round_start.elapsed();
+            if round == 0 {
+                first_round = last_round;
+            }
+        }
+
+        Ok(WarmupReport {
+            iterations: self.iterations,
+            first_round,
+            last_round,
+            elapsed: start.elapsed(),
+        })
+    }
+}
+
+/// Warms the hot path with `encode` and `decode` over channels and a
+/// loopback TCP connection, using the default number of rounds.
+///
+/// # Errors
+/// Returns the I/O error if the loopback connection fails.
+pub fn warmup(
+    encode: impl FnMut(&mut [u8]) -> usize,
+    decode: impl FnMut(&[u8]),
+) -> io::Result<WarmupReport> {
+    Warmup::new().codec(encode, decode).loopback(true).run()
+}
+
+/// Connects a client and server over `127.0.0.1`.
+fn loopback_pair() -> io::Result<(BlockingTcpConnection, BlockingTcpConnection)> {
+    let listener = BlockingTcpListener::bind("127.0.0.1:0".parse().expect("valid address"))?;
+    let client = BlockingTcpConnection::connect(listener.local_addr()?).map_err(into_io)?;
+    let server = listener.accept()?;
+    Ok((client, server))
+}
+
+fn into_io(err: ironsbe_transport::TransportError) -> io::Error {
+    match err {
+        ironsbe_transport::TransportError::Io(err) => err,
+        other => io::Error::other(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ironsbe_core::pool::TieredPoolConfig;
+
+    #[test]
+    fn test_warmup_runs_every_stage() {
+        let pool = TieredBufferPool::new(TieredPoolConfig::new().tier(1024, 2));
+        let mut decoded = 0;
+        let report = Warmup::new()
+            .iterations(50)
+            .codec(
+                |buf| {
+                    buf[..4].copy_from_slice(b"ping");
+                    4
+                },
+                |bytes| {
+                    assert_eq!(bytes, b"ping");
+                    decoded += 1;
+                },
+            )
+            .pool(&pool)
+            .loopback(true)
+            .run()
+            .unwrap();
+
+        assert_eq!(report.iterations, 50);
+        assert!(report.elapsed >= report.first_round);
+        assert_eq!(decoded, 50);
+        assert_eq!(pool.stats().acquires, 50);
+    }
+
+    #[test]
+    fn test_warmup_without_codec_uses_header() {
+        let report = Warmup::new().iterations(3).run().unwrap();
+        assert_eq!(report.iterations, 3);
+
+        let report = warmup(|_| 0, |bytes| assert!(bytes.is_empty())).unwrap();
+        assert_eq!(report.iterations, 1_000);
+    }
+}